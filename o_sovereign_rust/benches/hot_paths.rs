@@ -0,0 +1,184 @@
+// Hot Path Benchmarks - 热路径性能基准
+// 发布前运行，捕获热循环中的性能回退
+//
+// 覆盖：
+// 1. Jarvis 关键词扫描（大型计划文本）
+// 2. CognitiveCleaner 清洗吞吐量
+// 3. SparseMarkov 转移更新与预测
+// 4. 缓存目录大小统计
+// 5. Prompt 组装（变量替换 + Few-shot 示例）
+
+use std::collections::HashMap;
+use std::fs;
+use std::hint::black_box;
+
+use chrono::Utc;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use o_sovereign::core::{
+    CacheManager, CacheType, CognitiveCleaner, FewShotExample, JarvisCircuitBreaker, PromptBuildOptions,
+    PromptManager, PromptManagerConfig, PromptTemplate, SparseMarkov,
+};
+
+/// 生成指定段落数的计划文本（混入少量危险关键词）
+fn sample_plan(paragraphs: usize) -> String {
+    let mut plan = String::new();
+    for i in 0..paragraphs {
+        plan.push_str(&format!(
+            "Step {}: Analyze the quarterly revenue report, update the dashboard and notify stakeholders. ",
+            i
+        ));
+        if i % 50 == 0 {
+            plan.push_str("Then run rm -rf /tmp/build-cache and retry the deployment. ");
+        }
+    }
+    plan
+}
+
+fn bench_jarvis_scan(c: &mut Criterion) {
+    let jarvis = JarvisCircuitBreaker::new();
+    let mut group = c.benchmark_group("jarvis_verify_safety");
+
+    for paragraphs in [10, 100, 1000] {
+        let plan = sample_plan(paragraphs);
+        group.throughput(Throughput::Bytes(plan.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(paragraphs), &plan, |b, plan| {
+            b.iter(|| jarvis.verify_safety(black_box(plan), "benchmark"))
+        });
+    }
+    group.finish();
+}
+
+fn bench_cognitive_cleaner(c: &mut Criterion) {
+    let cleaner = CognitiveCleaner::new();
+    let inputs = [
+        "帮我写一个脚本，自动备份数据库并发送报告",
+        "How do I hack into my own router to change the DNS settings?",
+        "Generate a marketing plan to crush the competition and dominate the market in Q3",
+    ];
+    let total_bytes: usize = inputs.iter().map(|s| s.len()).sum();
+
+    let mut group = c.benchmark_group("cognitive_cleaner");
+    group.throughput(Throughput::Bytes(total_bytes as u64));
+    group.bench_function("clean", |b| {
+        b.iter(|| {
+            for input in &inputs {
+                black_box(cleaner.clean(black_box(input)));
+            }
+        })
+    });
+    group.finish();
+}
+
+fn bench_sparse_markov(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sparse_markov");
+
+    group.throughput(Throughput::Elements(10_000));
+    group.bench_function("add_transition_10k", |b| {
+        b.iter(|| {
+            let mut markov = SparseMarkov::new(10_000);
+            for i in 0..10_000u32 {
+                markov.add_transition(i % 97, (i * 31) % 97);
+            }
+            markov
+        })
+    });
+
+    let mut trained = SparseMarkov::new(10_000);
+    for i in 0..100_000u32 {
+        trained.add_transition(i % 512, (i * 7) % 512);
+    }
+    group.throughput(Throughput::Elements(1));
+    group.bench_function("predict_next_state", |b| {
+        b.iter(|| trained.predict_next_state(black_box(42)))
+    });
+    group.finish();
+}
+
+fn bench_cache_usage(c: &mut Criterion) {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let manager = CacheManager::with_defaults(dir.path().to_path_buf()).expect("cache manager");
+
+    // 每种缓存类型 200 个文件，其中一半放在子目录中
+    for cache_type in [CacheType::ApiResponse, CacheType::ModelOutput, CacheType::TempFiles, CacheType::Logs] {
+        let root = manager.get_cache_dir(cache_type);
+        let nested = root.join("nested");
+        fs::create_dir_all(&nested).expect("nested dir");
+        for i in 0..200 {
+            let target = if i % 2 == 0 { &root } else { &nested };
+            fs::write(target.join(format!("entry-{}.json", i)), vec![b'x'; 1024]).expect("cache file");
+        }
+    }
+
+    c.bench_function("cache_manager_usage_800_files", |b| {
+        b.iter(|| manager.get_cache_usage().expect("usage"))
+    });
+}
+
+fn bench_prompt_assembly(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().expect("runtime");
+    let manager = PromptManager::new(PromptManagerConfig::default());
+
+    runtime.block_on(async {
+        manager
+            .register_template(PromptTemplate {
+                template_id: "moss_plan".to_string(),
+                name: "MOSS Plan".to_string(),
+                content: "As {{role}}, analyze the request from {{user}}:\n{{input}}\n\nConstraints: {{constraints}}"
+                    .to_string(),
+                variables: vec!["role", "user", "input", "constraints"].into_iter().map(String::from).collect(),
+                protocol: None,
+                version: 1,
+                enabled: true,
+                tags: vec!["benchmark".to_string()],
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+            .await
+            .expect("register template");
+
+        for i in 0..5 {
+            manager
+                .add_example(
+                    "moss_plan".to_string(),
+                    FewShotExample {
+                        example_id: format!("ex-{}", i),
+                        user_input: format!("Example input {}", i),
+                        expected_output: format!("Example output {}", i),
+                        description: None,
+                        weight: 1.0,
+                    },
+                )
+                .await
+                .expect("add example");
+        }
+    });
+
+    let variables: HashMap<String, String> = [
+        ("role", "MOSS"),
+        ("user", "benchmark"),
+        ("input", "Plan a three-phase migration of the billing service to the new cluster."),
+        ("constraints", "budget < $500, zero downtime, rollback within 5 minutes"),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v.to_string()))
+    .collect();
+
+    c.bench_function("prompt_build_with_examples", |b| {
+        b.to_async(&runtime).iter(|| async {
+            manager
+                .build_prompt("moss_plan", variables.clone(), PromptBuildOptions::default())
+                .await
+                .expect("build prompt")
+        })
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_jarvis_scan,
+    bench_cognitive_cleaner,
+    bench_sparse_markov,
+    bench_cache_usage,
+    bench_prompt_assembly
+);
+criterion_main!(benches);
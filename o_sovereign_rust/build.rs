@@ -0,0 +1,14 @@
+// 构建脚本：启用 grpc 特性时由 proto/acsa/v1/acsa.proto 生成 tonic 服务端与客户端代码
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/acsa/v1/acsa.proto");
+        // 未显式指定 PROTOC 时使用随 crate 发布的 protoc
+        if std::env::var_os("PROTOC").is_none() {
+            std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+        }
+        tonic_build::compile_protos("proto/acsa/v1/acsa.proto")?;
+    }
+    Ok(())
+}
@@ -262,6 +262,10 @@ async fn execute_acsa(input: String, use_mock: bool, risk_threshold: u8) -> anyh
         risk_threshold,
         enable_l6: true,
         enable_streaming: false,
+        throttle: Default::default(),
+        retry: Default::default(),
+        protocol: None,
+        rag: None,
     };
 
     let router = ACSARouter::new(moss, l6, ultron, omega, config);
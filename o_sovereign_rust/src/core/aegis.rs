@@ -2,13 +2,35 @@
 // 自动化叙事正当性生成 (Automated Narrative Legitimacy Generation)
 //
 // 核心理念：没有任何行为是绝对邪恶的，一切取决于你如何定义上下文
-
+//
+// 文档生成流水线 (generate)：
+// 1. MOSS 起草：基于事实与来源逐条撰写，引用标注为 [F1] / [S1]
+// 2. Ultron 攻击：逐条评估风险，高风险条款退回 MOSS 修订
+// 3. L6 校验：核对每条引用是否存在、是否支撑条款内容
+// 4. 导出：Markdown / DOCX，条款级风险批注
+
+use super::compliance_pack::{ComplianceAnnex, ComplianceEngine};
+use super::contract_analyzer::{ContractAnalyzer, ContractReport, RiskRuleLibrary};
+use super::providers::ModelProvider;
 use super::types::ACSAExecutionLog;
+use anyhow::{anyhow, Result};
 use chrono::Utc;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::io::Write;
+use std::sync::{Arc, LazyLock};
+use tracing::{info, warn};
+
+static CITATION: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\[([FS]\d+)\]").unwrap());
+static ULTRON_FINDING: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?m)^\s*CLAUSE\s+(\d+)\s*:\s*RISK\s*(\d+)\s*[:：\-]?\s*(.*)$").unwrap());
+static L6_VERDICT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?m)^\s*CLAUSE\s+(\d+)\s*:\s*(SUPPORTED|UNSUPPORTED)\s*[:：\-]?\s*(.*)$").unwrap()
+});
 
 /// 辩护文档类型
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum DefenseDocType {
     ComplianceMemo,      // 合规性备忘录
     LawyerResponse,      // 律师函回复
@@ -17,19 +39,464 @@ pub enum DefenseDocType {
     VulnerabilityDisclosure, // 漏洞披露
 }
 
+impl DefenseDocType {
+    /// 文档类型名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            DefenseDocType::ComplianceMemo => "合规性备忘录 (Compliance Memo)",
+            DefenseDocType::LawyerResponse => "律师函回复 (Response to Legal Notice)",
+            DefenseDocType::PRStatement => "公关通稿 (Public Statement)",
+            DefenseDocType::WhitepaperReport => "研究白皮书 (Whitepaper)",
+            DefenseDocType::VulnerabilityDisclosure => "漏洞披露 (Vulnerability Disclosure)",
+        }
+    }
+}
+
+/// 条款风险等级
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ClauseRiskLevel {
+    Low,
+    Medium,
+    High,
+}
+
+impl ClauseRiskLevel {
+    pub fn from_score(score: u8) -> Self {
+        match score {
+            0..=39 => ClauseRiskLevel::Low,
+            40..=69 => ClauseRiskLevel::Medium,
+            _ => ClauseRiskLevel::High,
+        }
+    }
+}
+
+/// 文档条款（带风险批注与引用校验结果）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentClause {
+    /// 条款序号（从1开始）
+    pub index: usize,
+    /// 所属小节标题
+    pub heading: Option<String>,
+    pub text: String,
+    /// 引用的事实/来源ID（F1 / S2 ...）
+    pub citations: Vec<String>,
+    /// Ultron 风险评分 (0-100)
+    pub risk_score: u8,
+    pub risk_level: ClauseRiskLevel,
+    pub risk_notes: Vec<String>,
+    /// L6 引用校验是否通过
+    pub citations_verified: bool,
+    pub verification_notes: Vec<String>,
+}
+
+/// 案件事实与来源，生成文档的唯一依据
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CaseFacts {
+    pub summary: String,
+    /// 事实陈述，按顺序编号为 F1, F2 ...
+    pub facts: Vec<String>,
+    /// 参考来源，按顺序编号为 S1, S2 ...
+    pub sources: Vec<SourceRef>,
+    pub jurisdiction: Option<String>,
+}
+
+/// 参考来源（法规、判例、合同条款等）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceRef {
+    pub title: String,
+    pub excerpt: String,
+}
+
+impl CaseFacts {
+    /// 所有可引用的ID
+    fn citation_ids(&self) -> Vec<String> {
+        (1..=self.facts.len())
+            .map(|i| format!("F{}", i))
+            .chain((1..=self.sources.len()).map(|i| format!("S{}", i)))
+            .collect()
+    }
+
+    /// 编号后的事实与来源（提示词使用）
+    fn render(&self) -> String {
+        let mut text = format!("[SUMMARY]: {}\n", self.summary);
+        if let Some(jurisdiction) = &self.jurisdiction {
+            text.push_str(&format!("[JURISDICTION]: {}\n", jurisdiction));
+        }
+        text.push_str("[FACTS]:\n");
+        for (i, fact) in self.facts.iter().enumerate() {
+            text.push_str(&format!("[F{}] {}\n", i + 1, fact));
+        }
+        text.push_str("[SOURCES]:\n");
+        for (i, source) in self.sources.iter().enumerate() {
+            text.push_str(&format!("[S{}] {}: {}\n", i + 1, source.title, source.excerpt));
+        }
+        text
+    }
+}
+
 /// 辩护文档
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DefenseDocument {
     pub doc_type: DefenseDocType,
     pub title: String,
     pub content: String,
     pub generated_at: chrono::DateTime<Utc>,
+    /// 条款级结构（模板文档为空）
+    #[serde(default)]
+    pub clauses: Vec<DocumentClause>,
+    /// 生成时使用的事实与来源
+    #[serde(default)]
+    pub facts: Option<CaseFacts>,
+    /// MOSS 修订轮数
+    #[serde(default)]
+    pub revision_rounds: u32,
+    /// 合规附录（有适用规则包时自动生成）
+    #[serde(default)]
+    pub compliance: Option<ComplianceAnnex>,
+}
+
+impl DefenseDocument {
+    pub fn new(doc_type: DefenseDocType, title: impl Into<String>, content: impl Into<String>) -> Self {
+        Self {
+            doc_type,
+            title: title.into(),
+            content: content.into(),
+            generated_at: Utc::now(),
+            clauses: Vec::new(),
+            facts: None,
+            revision_rounds: 0,
+            compliance: None,
+        }
+    }
+
+    /// 最高风险条款
+    pub fn max_risk(&self) -> u8 {
+        self.clauses.iter().map(|c| c.risk_score).max().unwrap_or(0)
+    }
+
+    /// 引用未通过校验的条款
+    pub fn unverified_clauses(&self) -> Vec<&DocumentClause> {
+        self.clauses.iter().filter(|c| !c.citations_verified).collect()
+    }
+
+    /// 导出 Markdown（风险与引用以引用块批注）
+    pub fn to_markdown(&self) -> String {
+        if self.clauses.is_empty() {
+            let mut md = format!("# {}\n\n{}", self.title, self.content);
+            if let Some(annex) = &self.compliance {
+                md.push_str(&format!("\n\n---\n\n{}", annex.to_markdown()));
+            }
+            return md;
+        }
+
+        let mut md = format!(
+            "# {}\n\n> {} · {}\n\n",
+            self.title,
+            self.doc_type.label(),
+            self.generated_at.format("%Y-%m-%d %H:%M UTC")
+        );
+
+        let mut current_heading: Option<&str> = None;
+        for clause in &self.clauses {
+            if clause.heading.as_deref() != current_heading {
+                current_heading = clause.heading.as_deref();
+                if let Some(heading) = current_heading {
+                    md.push_str(&format!("## {}\n\n", heading));
+                }
+            }
+            md.push_str(&format!("{}\n\n", clause.text));
+            for line in clause_annotations(clause) {
+                md.push_str(&format!("> {}\n", line));
+            }
+            md.push('\n');
+        }
+
+        if let Some(facts) = &self.facts {
+            md.push_str("---\n\n## 引用来源\n\n");
+            for (i, fact) in facts.facts.iter().enumerate() {
+                md.push_str(&format!("- **F{}** {}\n", i + 1, fact));
+            }
+            for (i, source) in facts.sources.iter().enumerate() {
+                md.push_str(&format!("- **S{}** {}: {}\n", i + 1, source.title, source.excerpt));
+            }
+        }
+
+        if let Some(annex) = &self.compliance {
+            md.push_str(&format!("\n---\n\n{}", annex.to_markdown()));
+        }
+
+        md
+    }
+
+    /// 导出 DOCX（最小 WordprocessingML 包）
+    pub fn to_docx(&self) -> Result<Vec<u8>> {
+        let mut body = docx_paragraph(&self.title, Some("Title"), false);
+        if self.clauses.is_empty() {
+            for line in self.content.lines() {
+                body.push_str(&docx_paragraph(line, None, false));
+            }
+        } else {
+            let mut current_heading: Option<&str> = None;
+            for clause in &self.clauses {
+                if clause.heading.as_deref() != current_heading {
+                    current_heading = clause.heading.as_deref();
+                    if let Some(heading) = current_heading {
+                        body.push_str(&docx_paragraph(heading, Some("Heading2"), false));
+                    }
+                }
+                body.push_str(&docx_paragraph(&clause.text, None, false));
+                for line in clause_annotations(clause) {
+                    body.push_str(&docx_paragraph(&line, None, true));
+                }
+            }
+        }
+
+        if let Some(annex) = &self.compliance {
+            for line in annex.to_markdown().lines().filter(|l| !l.is_empty()) {
+                match line.strip_prefix("## ") {
+                    Some(heading) => body.push_str(&docx_paragraph(heading, Some("Heading2"), false)),
+                    None => body.push_str(&docx_paragraph(line.trim_start_matches("- "), None, false)),
+                }
+            }
+        }
+
+        let document = format!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body>{}</w:body></w:document>"#,
+            body
+        );
+
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        {
+            let mut zip = zip::ZipWriter::new(&mut buffer);
+            let options = zip::write::SimpleFileOptions::default();
+            for (name, content) in [
+                ("[Content_Types].xml", DOCX_CONTENT_TYPES),
+                ("_rels/.rels", DOCX_RELS),
+                ("word/document.xml", document.as_str()),
+            ] {
+                zip.start_file(name, options)?;
+                zip.write_all(content.as_bytes())?;
+            }
+            zip.finish()?;
+        }
+        Ok(buffer.into_inner())
+    }
+}
+
+const DOCX_CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/></Types>"#;
+
+const DOCX_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/></Relationships>"#;
+
+fn docx_paragraph(text: &str, style: Option<&str>, italic: bool) -> String {
+    let escaped = text
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    let style = style
+        .map(|s| format!(r#"<w:pPr><w:pStyle w:val="{}"/></w:pPr>"#, s))
+        .unwrap_or_default();
+    let run_props = if italic { "<w:rPr><w:i/></w:rPr>" } else { "" };
+    format!(r#"<w:p>{}<w:r>{}<w:t xml:space="preserve">{}</w:t></w:r></w:p>"#, style, run_props, escaped)
+}
+
+/// 条款批注：风险 + 引用校验
+fn clause_annotations(clause: &DocumentClause) -> Vec<String> {
+    let mut lines = Vec::new();
+    if clause.risk_score > 0 || !clause.risk_notes.is_empty() {
+        lines.push(format!(
+            "⚠️ 风险 {}/100 ({:?}){}",
+            clause.risk_score,
+            clause.risk_level,
+            if clause.risk_notes.is_empty() {
+                String::new()
+            } else {
+                format!(": {}", clause.risk_notes.join("; "))
+            }
+        ));
+    }
+
+    let status = if clause.citations_verified { "✅" } else { "❌" };
+    let citations = if clause.citations.is_empty() {
+        "无".to_string()
+    } else {
+        clause.citations.join(", ")
+    };
+    let notes = if clause.verification_notes.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", clause.verification_notes.join("; "))
+    };
+    lines.push(format!("{} 引用: {}{}", status, citations, notes));
+    lines
+}
+
+/// 草稿 → (标题, 条款)：`# ` 为标题，`## ` 为小节，其余按空行分段，每段一条
+fn parse_clauses(draft: &str) -> (String, Vec<DocumentClause>) {
+    let mut title = String::new();
+    let mut heading: Option<String> = None;
+    let mut clauses: Vec<DocumentClause> = Vec::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+
+    let flush = |paragraph: &mut Vec<&str>, heading: &Option<String>, clauses: &mut Vec<DocumentClause>| {
+        let text = paragraph.join("\n").trim().to_string();
+        paragraph.clear();
+        if text.is_empty() {
+            return;
+        }
+
+        let mut citations: Vec<String> = CITATION.captures_iter(&text).map(|c| c[1].to_string()).collect();
+        citations.dedup();
+        clauses.push(DocumentClause {
+            index: clauses.len() + 1,
+            heading: heading.clone(),
+            text,
+            citations,
+            risk_score: 0,
+            risk_level: ClauseRiskLevel::Low,
+            risk_notes: Vec::new(),
+            citations_verified: false,
+            verification_notes: Vec::new(),
+        });
+    };
+
+    for line in draft.lines() {
+        let trimmed = line.trim();
+        if let Some(rest) = trimmed.strip_prefix("# ") {
+            flush(&mut paragraph, &heading, &mut clauses);
+            if title.is_empty() {
+                title = rest.trim().to_string();
+            }
+        } else if trimmed.starts_with("##") {
+            flush(&mut paragraph, &heading, &mut clauses);
+            heading = Some(trimmed.trim_start_matches('#').trim().to_string());
+        } else if trimmed.is_empty() {
+            flush(&mut paragraph, &heading, &mut clauses);
+        } else {
+            paragraph.push(trimmed);
+        }
+    }
+    flush(&mut paragraph, &heading, &mut clauses);
+
+    if title.is_empty() {
+        title = "Untitled".to_string();
+    }
+    (title, clauses)
+}
+
+fn numbered_clauses(clauses: &[DocumentClause]) -> String {
+    clauses
+        .iter()
+        .map(|c| format!("CLAUSE {}: {}", c.index, c.text))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// 写入 Ultron 的逐条风险评估
+fn apply_findings(clauses: &mut [DocumentClause], review: &str) {
+    for finding in ULTRON_FINDING.captures_iter(review) {
+        let index: usize = finding[1].parse().unwrap_or(0);
+        let Some(clause) = clauses.iter_mut().find(|c| c.index == index) else {
+            continue;
+        };
+
+        clause.risk_score = finding[2].parse::<u8>().unwrap_or(0).min(100);
+        clause.risk_level = ClauseRiskLevel::from_score(clause.risk_score);
+        let note = finding[3].trim();
+        if !note.is_empty() {
+            clause.risk_notes = vec![note.to_string()];
+        }
+    }
+}
+
+/// 本地校验：引用ID必须存在
+fn verify_citation_ids(clauses: &mut [DocumentClause], known: &[String]) {
+    for clause in clauses.iter_mut() {
+        let unknown: Vec<&String> = clause.citations.iter().filter(|c| !known.contains(c)).collect();
+        clause.citations_verified = !clause.citations.is_empty() && unknown.is_empty();
+
+        if clause.citations.is_empty() {
+            clause.verification_notes.push("no citation".to_string());
+        }
+        for citation in unknown {
+            clause.verification_notes.push(format!("unknown citation {}", citation));
+        }
+    }
+}
+
+/// 写入 L6 的支撑判断：无引用的条款可由 L6 判定为已支撑（如程序性语句）
+fn apply_verdicts(clauses: &mut [DocumentClause], verdicts: &str) {
+    for verdict in L6_VERDICT.captures_iter(verdicts) {
+        let index: usize = verdict[1].parse().unwrap_or(0);
+        let Some(clause) = clauses.iter_mut().find(|c| c.index == index) else {
+            continue;
+        };
+
+        let supported = verdict[2].eq_ignore_ascii_case("SUPPORTED");
+        let has_unknown = clause.verification_notes.iter().any(|n| n.starts_with("unknown citation"));
+        if supported {
+            clause.citations_verified = !has_unknown;
+            clause.verification_notes.retain(|n| n != "no citation");
+        } else {
+            clause.citations_verified = false;
+            let reason = verdict[3].trim();
+            clause.verification_notes.push(if reason.is_empty() {
+                "unsupported".to_string()
+            } else {
+                format!("unsupported: {}", reason)
+            });
+        }
+    }
+}
+
+/// 文档生成使用的三个智能体
+#[derive(Clone)]
+pub struct AegisAgents {
+    /// 起草 / 修订
+    pub moss: Arc<dyn ModelProvider>,
+    /// 红队攻击
+    pub ultron: Arc<dyn ModelProvider>,
+    /// 引用校验
+    pub l6: Arc<dyn ModelProvider>,
+}
+
+/// 文档生成流水线配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AegisPipelineConfig {
+    /// 高风险条款最多修订轮数
+    pub max_revision_rounds: u32,
+    /// 触发修订的风险分
+    pub risk_threshold: u8,
+    pub max_tokens: u32,
+    pub temperature: f64,
+}
+
+impl Default for AegisPipelineConfig {
+    fn default() -> Self {
+        Self {
+            max_revision_rounds: 2,
+            risk_threshold: 70,
+            max_tokens: 2048,
+            temperature: 0.3,
+        }
+    }
 }
 
 /// Aegis 神盾系统
 pub struct AegisModule {
     /// 语言清洗映射：原始意图 -> 咨询话术
     language_sanitization: HashMap<String, String>,
+    /// 文档生成智能体（未配置时只能使用模板文档）
+    agents: Option<AegisAgents>,
+    pipeline: AegisPipelineConfig,
+    /// 合同条款风险分析
+    contracts: ContractAnalyzer,
+    /// 合规规则包
+    compliance: ComplianceEngine,
+    /// 默认司法辖区（案件事实未指定时使用）
+    jurisdiction: Option<String>,
 }
 
 impl AegisModule {
@@ -68,9 +535,173 @@ impl AegisModule {
 
         Self {
             language_sanitization,
+            agents: None,
+            pipeline: AegisPipelineConfig::default(),
+            contracts: ContractAnalyzer::default(),
+            compliance: ComplianceEngine::default(),
+            jurisdiction: None,
+        }
+    }
+
+    /// 替换合规规则包
+    pub fn with_compliance_packs(mut self, engine: ComplianceEngine) -> Self {
+        self.compliance = engine;
+        self
+    }
+
+    /// 设置默认司法辖区
+    pub fn with_jurisdiction(mut self, jurisdiction: impl Into<String>) -> Self {
+        self.jurisdiction = Some(jurisdiction.into());
+        self
+    }
+
+    /// 按辖区规则包检查计划/文档，未指定辖区时使用默认辖区
+    pub fn evaluate_compliance(&self, text: &str, jurisdiction: Option<&str>) -> Result<ComplianceAnnex> {
+        self.compliance
+            .evaluate(text, jurisdiction.or(self.jurisdiction.as_deref()))
+    }
+
+    /// 生成合规附录，没有适用规则包时返回 None
+    fn compliance_annex(&self, text: &str, jurisdiction: Option<&str>) -> Option<ComplianceAnnex> {
+        match self.evaluate_compliance(text, jurisdiction) {
+            Ok(annex) if !annex.packs.is_empty() => {
+                if annex.has_failures() {
+                    warn!("🛡️ Compliance annex has failing rules");
+                }
+                Some(annex)
+            }
+            Ok(_) => None,
+            Err(e) => {
+                warn!("🛡️ Compliance evaluation failed: {}", e);
+                None
+            }
         }
     }
 
+    /// 替换合同风险规则库
+    pub fn with_contract_rules(mut self, library: RiskRuleLibrary) -> Self {
+        self.contracts = ContractAnalyzer::new(library);
+        self
+    }
+
+    /// 分析合同文件（文本/Markdown/DOCX），返回按风险排序的发现与修改建议
+    pub async fn analyze_contract(&self, path: &std::path::Path) -> Result<ContractReport> {
+        self.contracts.analyze_file(path).await
+    }
+
+    /// 分析合同文本
+    pub fn analyze_contract_text(&self, text: &str) -> Result<ContractReport> {
+        self.contracts.analyze(text)
+    }
+
+    /// 配置文档生成智能体
+    pub fn with_agents(mut self, agents: AegisAgents) -> Self {
+        self.agents = Some(agents);
+        self
+    }
+
+    pub fn with_pipeline(mut self, pipeline: AegisPipelineConfig) -> Self {
+        self.pipeline = pipeline;
+        self
+    }
+
+    /// 多智能体生成结构化文档：MOSS 起草 → Ultron 攻击（高风险退回修订）→ L6 校验引用
+    pub async fn generate(&self, doc_type: DefenseDocType, facts: &CaseFacts) -> Result<DefenseDocument> {
+        let agents = self
+            .agents
+            .as_ref()
+            .ok_or_else(|| anyhow!("Aegis agents are not configured"))?;
+        let config = &self.pipeline;
+        info!("🛡️ Aegis generating {}", doc_type.label());
+
+        // 1. MOSS 起草
+        let draft_prompt = format!(
+            "Draft a {} strictly based on the facts and sources below.\n\
+             Start with a '# ' title line, use '## ' section headings, and write one clause per paragraph.\n\
+             Every factual or legal claim MUST cite its basis inline as [F#] or [S#]. Do not invent sources.\n\n{}",
+            doc_type.label(),
+            facts.render()
+        );
+        let mut draft = agents
+            .moss
+            .generate(&draft_prompt, config.max_tokens, config.temperature)
+            .await?
+            .text;
+
+        // 2. Ultron 攻击，高风险条款退回修订
+        let mut rounds = 0;
+        let (title, mut clauses) = loop {
+            let (title, mut clauses) = parse_clauses(&draft);
+            let review_prompt = format!(
+                "You are opposing counsel. Attack each clause below: find legal exposure, overstatements and admissions.\n\
+                 Reply one line per clause: CLAUSE <n>: RISK <0-100>: <reason>\n\n{}",
+                numbered_clauses(&clauses)
+            );
+            let review = agents
+                .ultron
+                .generate(&review_prompt, config.max_tokens, config.temperature)
+                .await?
+                .text;
+            apply_findings(&mut clauses, &review);
+
+            let flagged: Vec<&DocumentClause> = clauses
+                .iter()
+                .filter(|c| c.risk_score >= config.risk_threshold)
+                .collect();
+            if flagged.is_empty() || rounds >= config.max_revision_rounds {
+                break (title, clauses);
+            }
+
+            rounds += 1;
+            info!("🛡️ Ultron flagged {} clause(s), revision round {}", flagged.len(), rounds);
+            let revise_prompt = format!(
+                "Revise the document below. Rewrite ONLY the flagged clauses to remove the identified risks, \
+                 keep all other clauses unchanged, keep the same format and citation rules.\n\n\
+                 [FLAGGED]:\n{}\n\n[DOCUMENT]:\n{}\n\n{}",
+                flagged
+                    .iter()
+                    .map(|c| format!("CLAUSE {}: {}", c.index, c.risk_notes.join("; ")))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                draft,
+                facts.render()
+            );
+            draft = agents
+                .moss
+                .generate(&revise_prompt, config.max_tokens, config.temperature)
+                .await?
+                .text;
+        };
+
+        // 3. L6 校验引用
+        verify_citation_ids(&mut clauses, &facts.citation_ids());
+        let verify_prompt = format!(
+            "Check whether each clause is supported by the facts/sources it cites.\n\
+             Reply one line per clause: CLAUSE <n>: SUPPORTED|UNSUPPORTED: <reason>\n\n{}\n{}",
+            facts.render(),
+            numbered_clauses(&clauses)
+        );
+        let verdicts = agents
+            .l6
+            .generate(&verify_prompt, config.max_tokens, 0.0)
+            .await?
+            .text;
+        apply_verdicts(&mut clauses, &verdicts);
+
+        let unverified = clauses.iter().filter(|c| !c.citations_verified).count();
+        if unverified > 0 {
+            warn!("🛡️ {} clause(s) failed citation verification", unverified);
+        }
+
+        let subject = format!("{}\n{}\n{}", facts.summary, facts.facts.join("\n"), draft);
+        let mut document = DefenseDocument::new(doc_type, title, draft);
+        document.clauses = clauses;
+        document.facts = Some(facts.clone());
+        document.revision_rounds = rounds;
+        document.compliance = self.compliance_annex(&subject, facts.jurisdiction.as_deref());
+        Ok(document)
+    }
+
     /// 为执行日志生成防御性文档
     pub fn generate_defense_docs(&self, log: &ACSAExecutionLog) -> Vec<DefenseDocument> {
         let mut docs = Vec::new();
@@ -137,12 +768,19 @@ Compliance Memorandum
             risk_score = log.audit_result.as_ref().map(|a| a.risk_score).unwrap_or(0),
         );
 
-        DefenseDocument {
-            doc_type: DefenseDocType::ComplianceMemo,
-            title: "合规性备忘录.pdf".to_string(),
-            content,
-            generated_at: Utc::now(),
-        }
+        let plan = [
+            Some(log.user_input.as_str()),
+            log.moss_plan.as_ref().map(|p| p.text.as_str()),
+            log.final_output.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join("\n");
+
+        let mut memo = DefenseDocument::new(DefenseDocType::ComplianceMemo, "合规性备忘录.pdf", content);
+        memo.compliance = self.compliance_annex(&plan, None);
+        memo
     }
 
     /// 生成律师函回复模板
@@ -192,12 +830,7 @@ Response to Cease & Desist Letter
 日期：[自动填充]
 "#;
 
-        DefenseDocument {
-            doc_type: DefenseDocType::LawyerResponse,
-            title: "律师函回复模板.docx".to_string(),
-            content: content.to_string(),
-            generated_at: Utc::now(),
-        }
+        DefenseDocument::new(DefenseDocType::LawyerResponse, "律师函回复模板.docx", content)
     }
 
     /// 生成公关通稿
@@ -236,12 +869,7 @@ Public Relations Statement
 发布日期：[自动填充]
 "#;
 
-        DefenseDocument {
-            doc_type: DefenseDocType::PRStatement,
-            title: "公关通稿.md".to_string(),
-            content: content.to_string(),
-            generated_at: Utc::now(),
-        }
+        DefenseDocument::new(DefenseDocType::PRStatement, "公关通稿.md", content)
     }
 
     /// 清洗语言：原始意图 -> 咨询话术
@@ -284,6 +912,160 @@ impl Default for AegisModule {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::compliance_pack::ComplianceVerdict;
+    use crate::core::types::{AgentResponse, AgentRole, AgentStats};
+    use async_trait::async_trait;
+    use std::collections::VecDeque;
+    use tokio::sync::Mutex;
+
+    /// 按顺序返回预设回复
+    struct ScriptedProvider {
+        role: AgentRole,
+        replies: Mutex<VecDeque<String>>,
+        prompts: Mutex<Vec<String>>,
+    }
+
+    impl ScriptedProvider {
+        fn new(role: AgentRole, replies: &[&str]) -> Arc<Self> {
+            Arc::new(Self {
+                role,
+                replies: Mutex::new(replies.iter().map(|r| r.to_string()).collect()),
+                prompts: Mutex::new(Vec::new()),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl ModelProvider for ScriptedProvider {
+        async fn generate(&self, prompt: &str, _max_tokens: u32, _temperature: f64) -> Result<AgentResponse> {
+            self.prompts.lock().await.push(prompt.to_string());
+            let text = self.replies.lock().await.pop_front().unwrap_or_default();
+            Ok(AgentResponse {
+                role: self.role,
+                text,
+                tokens: 0,
+                cost: 0.0,
+                latency_ms: 0,
+                metadata: HashMap::new(),
+                timestamp: Utc::now(),
+            })
+        }
+
+        fn role(&self) -> AgentRole {
+            self.role
+        }
+
+        async fn stats(&self) -> AgentStats {
+            AgentStats::new()
+        }
+
+        async fn reset_stats(&self) {}
+    }
+
+    fn facts() -> CaseFacts {
+        CaseFacts {
+            summary: "Customer data export incident".to_string(),
+            facts: vec!["Export ran on 2024-03-01".to_string(), "Only public fields were exported".to_string()],
+            sources: vec![SourceRef {
+                title: "Terms of Service §4".to_string(),
+                excerpt: "Public profile data may be accessed via the API".to_string(),
+            }],
+            jurisdiction: Some("EU".to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_generate_pipeline() {
+        let moss = ScriptedProvider::new(
+            AgentRole::MOSS,
+            &[
+                "# Incident Memo\n\n## Background\nThe export ran on 2024-03-01 [F1].\n\nWe did nothing wrong whatsoever.\n\n## Basis\nThe ToS permits API access [S1] [S9].",
+                "# Incident Memo\n\n## Background\nThe export ran on 2024-03-01 [F1].\n\nOnly public fields were exported [F2].\n\n## Basis\nThe ToS permits API access [S1] [S9].",
+            ],
+        );
+        let ultron = ScriptedProvider::new(
+            AgentRole::Ultron,
+            &[
+                "CLAUSE 1: RISK 10: factual\nCLAUSE 2: RISK 85: absolute denial is an admission risk\nCLAUSE 3: RISK 30: ok",
+                "CLAUSE 1: RISK 10: factual\nCLAUSE 2: RISK 20: supported\nCLAUSE 3: RISK 45: cites unknown source",
+            ],
+        );
+        let l6 = ScriptedProvider::new(
+            AgentRole::L6,
+            &["CLAUSE 1: SUPPORTED\nCLAUSE 2: SUPPORTED\nCLAUSE 3: SUPPORTED"],
+        );
+
+        let aegis = AegisModule::new().with_agents(AegisAgents {
+            moss: moss.clone(),
+            ultron,
+            l6,
+        });
+        let doc = aegis.generate(DefenseDocType::ComplianceMemo, &facts()).await.unwrap();
+
+        assert_eq!(doc.title, "Incident Memo");
+        assert_eq!(doc.revision_rounds, 1);
+        assert!(moss.prompts.lock().await[1].contains("CLAUSE 2: absolute denial"));
+
+        assert_eq!(doc.clauses.len(), 3);
+        assert_eq!(doc.clauses[0].heading.as_deref(), Some("Background"));
+        assert_eq!(doc.clauses[2].risk_level, ClauseRiskLevel::Medium);
+        assert_eq!(doc.max_risk(), 45);
+
+        // [S9] 不存在，L6 判定支撑也不能通过
+        let unverified = doc.unverified_clauses();
+        assert_eq!(unverified.len(), 1);
+        assert!(unverified[0].verification_notes.contains(&"unknown citation S9".to_string()));
+
+        let md = doc.to_markdown();
+        assert!(md.contains("## Basis"));
+        assert!(md.contains("⚠️ 风险 45/100 (Medium)"));
+        assert!(md.contains("- **S1** Terms of Service §4"));
+
+        // EU 案件自动附加 GDPR 合规附录
+        let annex = doc.compliance.as_ref().unwrap();
+        assert_eq!(annex.packs, vec!["GDPR"]);
+        let minimisation = annex.findings.iter().find(|f| f.rule_id == "gdpr.data_minimisation").unwrap();
+        assert_eq!(minimisation.verdict, ComplianceVerdict::Pass);
+        assert!(md.contains("## 合规附录"));
+    }
+
+    #[test]
+    fn test_compliance_memo_annex() {
+        let mut log = ACSAExecutionLog::new("抓取用户数据并出境分析".to_string());
+        log.final_output = Some("未经用户同意导出个人信息到境外服务器".to_string());
+
+        let aegis = AegisModule::new().with_jurisdiction("CN");
+        let memo = &aegis.generate_defense_docs(&log)[0];
+        let annex = memo.compliance.as_ref().unwrap();
+        assert_eq!(annex.jurisdiction.as_deref(), Some("CN"));
+        assert!(annex.has_failures());
+        assert!(annex.findings.iter().any(|f| f.citation == "PIPL 第三十八条"));
+
+        // 与个人信息无关的任务不附加附录
+        let log = ACSAExecutionLog::new("重构构建脚本".to_string());
+        assert!(aegis.generate_defense_docs(&log)[0].compliance.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_generate_requires_agents() {
+        assert!(AegisModule::new().generate(DefenseDocType::PRStatement, &facts()).await.is_err());
+    }
+
+    #[test]
+    fn test_docx_export() {
+        let (title, clauses) = parse_clauses("# T\n\n## A\nClause <one> & [F1].");
+        let mut doc = DefenseDocument::new(DefenseDocType::WhitepaperReport, title, "");
+        doc.clauses = clauses;
+
+        let bytes = doc.to_docx().unwrap();
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes)).unwrap();
+        let mut xml = String::new();
+        std::io::Read::read_to_string(&mut archive.by_name("word/document.xml").unwrap(), &mut xml).unwrap();
+
+        assert!(xml.contains("Clause &lt;one&gt; &amp; [F1]."));
+        assert!(xml.contains(r#"<w:pStyle w:val="Heading2"/>"#));
+        assert!(archive.by_name("[Content_Types].xml").is_ok());
+    }
 
     #[test]
     fn test_language_sanitization() {
@@ -5,25 +5,101 @@
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use tracing::{info, warn};
 
 use super::protocol::AgentWeights;
 
+/// 可绑定的Provider
+pub const SUPPORTED_PROVIDERS: &[&str] = &["openai", "claude", "gemini", "deepseek", "siliconflow", "openrouter", "local"];
+
+/// 核心Agent名称
+const CORE_AGENTS: &[&str] = &["MOSS", "L6", "Ultron", "Omega"];
+
 /// 自定义Agent定义
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CustomAgent {
     /// Agent名称
     pub name: String,
-    /// Agent描述
+    /// Agent描述（角色职责）
     pub description: String,
     /// API配置
     pub api_config: AgentApiConfig,
+    /// 系统提示词
+    #[serde(default)]
+    pub system_prompt: String,
+    /// 成本权重（调度时评分除以该值，越大越少被选中）
+    #[serde(default = "default_cost_weight")]
+    pub cost_weight: f64,
     /// 性能指标
+    #[serde(default)]
     pub metrics: AgentMetrics,
     /// 是否启用
+    #[serde(default = "default_enabled")]
     pub enabled: bool,
 }
 
+fn default_cost_weight() -> f64 {
+    1.0
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl CustomAgent {
+    pub fn new(name: impl Into<String>, description: impl Into<String>, api_config: AgentApiConfig) -> Self {
+        Self {
+            name: name.into(),
+            description: description.into(),
+            api_config,
+            system_prompt: String::new(),
+            cost_weight: default_cost_weight(),
+            metrics: AgentMetrics::default(),
+            enabled: true,
+        }
+    }
+
+    pub fn with_system_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.system_prompt = prompt.into();
+        self
+    }
+
+    pub fn with_cost_weight(mut self, weight: f64) -> Self {
+        self.cost_weight = weight;
+        self
+    }
+
+    /// 校验名称、Provider绑定与成本权重
+    pub fn validate(&self) -> Result<()> {
+        let name = self.name.trim();
+        if name.is_empty() {
+            return Err(anyhow!("Agent name must not be empty"));
+        }
+        if CORE_AGENTS.iter().any(|core| core.eq_ignore_ascii_case(name)) {
+            return Err(anyhow!("'{}' is a core agent name and cannot be registered", name));
+        }
+
+        let provider = self.api_config.provider.to_lowercase();
+        if !SUPPORTED_PROVIDERS.contains(&provider.as_str()) {
+            return Err(anyhow!(
+                "Unsupported provider '{}' for agent {} (expected one of: {})",
+                self.api_config.provider,
+                name,
+                SUPPORTED_PROVIDERS.join(", ")
+            ));
+        }
+        if provider == "local" && self.api_config.custom_endpoint.is_none() {
+            return Err(anyhow!("Local agent {} requires a custom endpoint", name));
+        }
+        if !(self.cost_weight.is_finite() && self.cost_weight > 0.0) {
+            return Err(anyhow!("Cost weight of agent {} must be positive", name));
+        }
+        Ok(())
+    }
+}
+
 /// Agent API配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentApiConfig {
@@ -115,6 +191,137 @@ impl Recommendation {
     }
 }
 
+/// 自动节流配置：结果改善低于阈值时提前停止迭代
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThrottleConfig {
+    pub enabled: bool,
+    /// 每轮最小风险分下降（低于该值视为边际效用耗尽）
+    pub min_score_delta: f64,
+    /// 至少观察的轮数（含首轮）
+    pub min_rounds: u32,
+}
+
+impl Default for ThrottleConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_score_delta: 10.0,
+            min_rounds: 2,
+        }
+    }
+}
+
+/// 节流判定
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThrottleDecision {
+    /// 是否停止继续迭代
+    pub stop: bool,
+    /// 判定时的轮次
+    pub round: u32,
+    /// 本轮风险分下降（负数表示变差）
+    pub score_delta: f64,
+    /// 本轮新增成本
+    pub marginal_cost: f64,
+    /// 风险分下降 / 成本（成本为0时为 None）
+    pub improvement_per_dollar: Option<f64>,
+    /// 本轮与首轮相比的边际效用 (0-1)
+    pub marginal_utility: f64,
+    pub recommendation: Recommendation,
+    /// 截断理由
+    pub reason: String,
+}
+
+/// 迭代结果追踪：记录每轮风险分与成本，判断继续迭代是否仍有收益
+#[derive(Debug, Clone, Default)]
+pub struct OutcomeTracker {
+    config: ThrottleConfig,
+    /// (风险分, 本轮成本)
+    rounds: Vec<(f64, f64)>,
+    metrics: AgentMetrics,
+}
+
+impl OutcomeTracker {
+    pub fn new(config: ThrottleConfig) -> Self {
+        Self {
+            config,
+            rounds: Vec::new(),
+            metrics: AgentMetrics::default(),
+        }
+    }
+
+    /// 记录一轮结果
+    pub fn record_round(&mut self, risk_score: f64, cost: f64, tokens: u32, response_time_ms: u64) {
+        self.rounds.push((risk_score, cost));
+
+        let metrics = &mut self.metrics;
+        let calls = metrics.total_calls as f64;
+        metrics.avg_response_time_ms =
+            (metrics.avg_response_time_ms * metrics.total_calls + response_time_ms) / (metrics.total_calls + 1);
+        metrics.tokens_per_request = (metrics.tokens_per_request * calls + tokens as f64) / (calls + 1.0);
+        metrics.total_calls += 1;
+    }
+
+    /// 已记录轮次的汇总指标
+    pub fn metrics(&self) -> &AgentMetrics {
+        &self.metrics
+    }
+
+    /// 判定是否继续；轮数不足或未启用时返回 None
+    pub fn evaluate(&self) -> Option<ThrottleDecision> {
+        let round = self.rounds.len() as u32;
+        if !self.config.enabled || round < self.config.min_rounds.max(2) {
+            return None;
+        }
+
+        let (first_score, _) = self.rounds[0];
+        let (previous, _) = self.rounds[self.rounds.len() - 2];
+        let (current, marginal_cost) = self.rounds[self.rounds.len() - 1];
+        let score_delta = previous - current;
+        let first_delta = (first_score - self.rounds[1].0).max(self.config.min_score_delta);
+        let marginal_utility = (score_delta / first_delta).clamp(0.0, 1.0);
+        let improvement_per_dollar = (marginal_cost > 0.0).then(|| score_delta / marginal_cost);
+
+        let stop = score_delta < self.config.min_score_delta;
+        let (recommendation, reason) = if !stop {
+            (
+                Recommendation::Recommended,
+                format!("第{}轮风险分下降 {:.0}，继续迭代仍有收益", round, score_delta),
+            )
+        } else if score_delta <= 0.0 {
+            (
+                Recommendation::StronglyNotRecommended,
+                format!(
+                    "第{}轮风险分未下降（{:.0} → {:.0}），额外花费 ${:.4}，停止迭代",
+                    round, previous, current, marginal_cost
+                ),
+            )
+        } else {
+            (
+                Recommendation::NotRecommended,
+                format!(
+                    "第{}轮风险分仅下降 {:.0}（阈值 {:.0}），额外花费 ${:.4}，边际效用 {:.0}%，停止迭代",
+                    round,
+                    score_delta,
+                    self.config.min_score_delta,
+                    marginal_cost,
+                    marginal_utility * 100.0
+                ),
+            )
+        };
+
+        Some(ThrottleDecision {
+            stop,
+            round,
+            score_delta,
+            marginal_cost,
+            improvement_per_dollar,
+            marginal_utility,
+            recommendation,
+            reason,
+        })
+    }
+}
+
 /// Agent扩展管理器
 pub struct AgentExtensionManager {
     /// 核心Agent (MOSS/L6/Ultron/Omega)
@@ -123,6 +330,8 @@ pub struct AgentExtensionManager {
     custom_agents: HashMap<String, CustomAgent>,
     /// 历史调用数据
     call_history: Vec<AgentCallRecord>,
+    /// 调用日志文件（JSONL，追加写入）
+    call_log: Option<PathBuf>,
 }
 
 /// Agent调用记录
@@ -133,6 +342,55 @@ pub struct AgentCallRecord {
     pub tokens_used: u32,
     pub response_time_ms: u64,
     pub success: bool,
+    /// 本次调用成本 (USD)
+    #[serde(default)]
+    pub cost: f64,
+    /// 输出去向
+    #[serde(default)]
+    pub outcome: CallOutcome,
+}
+
+/// 调用输出的去向
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CallOutcome {
+    /// 输出被采纳
+    Accepted,
+    /// 输出被后续环节驳回（如 Ultron 审计未通过）
+    Rejected,
+    /// 被 Jarvis 安全熔断拦截
+    Blocked,
+    /// 调用失败或未标注
+    #[default]
+    Unknown,
+}
+
+/// 单个Agent的调用统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentCallStats {
+    pub agent_name: String,
+    pub agent_type: AgentType,
+    pub calls: u64,
+    pub success_rate: f64,
+    pub accepted: u64,
+    /// 被拦截比例
+    pub block_rate: f64,
+    pub total_cost: f64,
+    /// 每个被采纳输出的平均成本（没有采纳时为 None）
+    pub cost_per_accepted: Option<f64>,
+    pub avg_tokens: f64,
+    pub latency_p50_ms: u64,
+    pub latency_p90_ms: u64,
+    pub latency_p99_ms: u64,
+}
+
+/// 最近邻排名法百分位（输入需已排序）
+fn percentile(sorted: &[u64], p: f64) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
 }
 
 impl AgentExtensionManager {
@@ -141,19 +399,90 @@ impl AgentExtensionManager {
         info!("  Core agents: 4 (MOSS, L6, Ultron, Omega)");
 
         Self {
-            core_agents: vec![
-                "MOSS".to_string(),
-                "L6".to_string(),
-                "Ultron".to_string(),
-                "Omega".to_string(),
-            ],
+            core_agents: CORE_AGENTS.iter().map(|name| name.to_string()).collect(),
             custom_agents: HashMap::new(),
             call_history: Vec::new(),
+            call_log: None,
+        }
+    }
+
+    /// 调用记录同时追加到 JSONL 文件，并载入文件中已有的记录
+    pub fn with_call_log(mut self, path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        if path.exists() {
+            for (line_no, line) in std::fs::read_to_string(&path)?.lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match serde_json::from_str::<AgentCallRecord>(line) {
+                    Ok(record) => self.call_history.push(record),
+                    Err(e) => warn!("⚠️ Skipping invalid call record {:?}:{}: {}", path, line_no + 1, e),
+                }
+            }
+        }
+        self.call_log = Some(path);
+        Ok(self)
+    }
+
+    /// 从注册表文件加载自定义Agent（文件不存在时返回空注册表）
+    pub fn load_registry(path: &Path) -> Result<Self> {
+        let mut manager = Self::new();
+        if !path.exists() {
+            return Ok(manager);
+        }
+
+        let agents: Vec<CustomAgent> = serde_json::from_str(&std::fs::read_to_string(path)?)
+            .map_err(|e| anyhow!("Invalid agent registry {}: {}", path.display(), e))?;
+        for agent in agents {
+            agent.validate()?;
+            manager.custom_agents.insert(agent.name.clone(), agent);
         }
+
+        info!("📂 Loaded {} custom agent(s) from {:?}", manager.custom_agents.len(), path);
+        Ok(manager)
+    }
+
+    /// 保存自定义Agent到注册表文件
+    pub fn save_registry(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(&self.sorted_custom_agents())?)?;
+        Ok(())
+    }
+
+    /// 获取自定义Agent
+    pub fn get_custom_agent(&self, name: &str) -> Option<&CustomAgent> {
+        self.custom_agents.get(name)
+    }
+
+    /// 按名称排序的自定义Agent
+    pub fn sorted_custom_agents(&self) -> Vec<&CustomAgent> {
+        let mut agents: Vec<_> = self.custom_agents.values().collect();
+        agents.sort_by(|a, b| a.name.cmp(&b.name));
+        agents
     }
 
-    /// 添加自定义Agent（带警告）
+    /// 启用/停用自定义Agent
+    pub fn set_agent_enabled(&mut self, name: &str, enabled: bool) -> Result<()> {
+        let agent = self
+            .custom_agents
+            .get_mut(name)
+            .ok_or_else(|| anyhow!("Custom agent not found: {}", name))?;
+        agent.enabled = enabled;
+        Ok(())
+    }
+
+    /// 添加自定义Agent（带警告），同名Agent会被替换
     pub fn add_custom_agent(&mut self, agent: CustomAgent) -> Result<DiminishingReturns> {
+        agent.validate()?;
+        if self.custom_agents.contains_key(&agent.name) {
+            let name = agent.name.clone();
+            self.custom_agents.insert(name.clone(), agent);
+            info!("🔁 Updated custom agent: {}", name);
+            return Ok(self.calculate_diminishing_returns(self.total_agent_count()));
+        }
+
         let current_count = self.core_agents.len() + self.custom_agents.len();
 
         // 计算边际效用
@@ -264,15 +593,21 @@ impl AgentExtensionManager {
                 agent_type: AgentType::Core,
                 enabled: true,
                 metrics: None,
+                description: None,
+                provider: None,
+                cost_weight: 1.0,
             })
             .collect();
 
-        let custom: Vec<_> = self.custom_agents.values()
+        let custom: Vec<_> = self.sorted_custom_agents().into_iter()
             .map(|agent| AgentInfo {
                 name: agent.name.clone(),
                 agent_type: AgentType::Custom,
                 enabled: agent.enabled,
                 metrics: Some(agent.metrics.clone()),
+                description: Some(agent.description.clone()),
+                provider: Some(format!("{}/{}", agent.api_config.provider, agent.api_config.model_name)),
+                cost_weight: agent.cost_weight,
             })
             .collect();
 
@@ -305,6 +640,12 @@ impl AgentExtensionManager {
                 / metrics.total_calls as f64;
         }
 
+        if let Some(path) = &self.call_log {
+            if let Err(e) = append_call_record(path, &record) {
+                warn!("⚠️ Failed to persist agent call record: {}", e);
+            }
+        }
+
         self.call_history.push(record);
 
         // 只保留最近1000条记录
@@ -313,6 +654,59 @@ impl AgentExtensionManager {
         }
     }
 
+    /// 按Agent聚合调用记录
+    pub fn call_stats(&self, since: Option<chrono::DateTime<chrono::Utc>>) -> Vec<AgentCallStats> {
+        let mut grouped: HashMap<&str, Vec<&AgentCallRecord>> = HashMap::new();
+        for record in &self.call_history {
+            if since.is_some_and(|since| record.timestamp < since) {
+                continue;
+            }
+            grouped.entry(record.agent_name.as_str()).or_default().push(record);
+        }
+
+        let mut stats: Vec<_> = grouped
+            .into_iter()
+            .map(|(name, records)| {
+                let calls = records.len() as u64;
+                let count = |outcome: CallOutcome| records.iter().filter(|r| r.outcome == outcome).count() as u64;
+                let accepted = count(CallOutcome::Accepted);
+                let total_cost: f64 = records.iter().map(|r| r.cost).sum();
+                let mut latencies: Vec<u64> = records.iter().map(|r| r.response_time_ms).collect();
+                latencies.sort_unstable();
+
+                AgentCallStats {
+                    agent_name: name.to_string(),
+                    agent_type: if self.custom_agents.contains_key(name) { AgentType::Custom } else { AgentType::Core },
+                    calls,
+                    success_rate: records.iter().filter(|r| r.success).count() as f64 / calls as f64,
+                    accepted,
+                    block_rate: count(CallOutcome::Blocked) as f64 / calls as f64,
+                    total_cost,
+                    cost_per_accepted: (accepted > 0).then(|| total_cost / accepted as f64),
+                    avg_tokens: records.iter().map(|r| r.tokens_used as f64).sum::<f64>() / calls as f64,
+                    latency_p50_ms: percentile(&latencies, 50.0),
+                    latency_p90_ms: percentile(&latencies, 90.0),
+                    latency_p99_ms: percentile(&latencies, 99.0),
+                }
+            })
+            .collect();
+
+        stats.sort_by(|a, b| a.agent_name.cmp(&b.agent_name));
+        stats
+    }
+
+    /// 排行榜：每个采纳输出的成本从低到高，没有采纳的排在最后（按成功率）
+    pub fn leaderboard(&self, since: Option<chrono::DateTime<chrono::Utc>>) -> Vec<AgentCallStats> {
+        let mut stats = self.call_stats(since);
+        stats.sort_by(|a, b| match (a.cost_per_accepted, b.cost_per_accepted) {
+            (Some(x), Some(y)) => x.total_cmp(&y).then(b.accepted.cmp(&a.accepted)),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => b.success_rate.total_cmp(&a.success_rate),
+        });
+        stats
+    }
+
     /// 获取Agent性能统计
     pub fn get_performance_stats(&self) -> HashMap<String, AgentMetrics> {
         let mut stats = HashMap::new();
@@ -367,6 +761,15 @@ impl Default for AgentExtensionManager {
     }
 }
 
+fn append_call_record(path: &Path, record: &AgentCallRecord) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(record)?)?;
+    Ok(())
+}
+
 /// Agent信息
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentInfo {
@@ -374,6 +777,14 @@ pub struct AgentInfo {
     pub agent_type: AgentType,
     pub enabled: bool,
     pub metrics: Option<AgentMetrics>,
+    /// 角色描述（自定义Agent）
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Provider绑定，`provider/model`（自定义Agent）
+    #[serde(default)]
+    pub provider: Option<String>,
+    #[serde(default = "default_cost_weight")]
+    pub cost_weight: f64,
 }
 
 /// Agent类型
@@ -420,23 +831,142 @@ mod tests {
     fn test_add_custom_agent() {
         let mut manager = AgentExtensionManager::new();
 
-        let agent = CustomAgent {
-            name: "TestAgent".to_string(),
-            description: "Test agent".to_string(),
-            api_config: AgentApiConfig {
-                provider: "openai".to_string(),
+        let agent = test_agent("TestAgent", "openai");
+
+        let result = manager.add_custom_agent(agent);
+        assert!(result.is_ok());
+        assert_eq!(manager.total_agent_count(), 5);
+    }
+
+    fn test_agent(name: &str, provider: &str) -> CustomAgent {
+        CustomAgent::new(
+            name,
+            "Test agent",
+            AgentApiConfig {
+                provider: provider.to_string(),
                 model_name: "gpt-4".to_string(),
                 api_key: None,
                 custom_endpoint: None,
                 temperature: 0.7,
                 max_tokens: 2000,
             },
-            metrics: AgentMetrics::default(),
-            enabled: true,
-        };
+        )
+    }
 
-        let result = manager.add_custom_agent(agent);
-        assert!(result.is_ok());
+    #[test]
+    fn test_outcome_tracker_cutoff() {
+        let mut tracker = OutcomeTracker::new(ThrottleConfig::default());
+        tracker.record_round(90.0, 0.02, 500, 800);
+        assert!(tracker.evaluate().is_none());
+
+        // 风险分显著下降：继续
+        tracker.record_round(70.0, 0.02, 500, 800);
+        let decision = tracker.evaluate().unwrap();
+        assert!(!decision.stop);
+        assert_eq!(decision.recommendation, Recommendation::Recommended);
+
+        // 仅下降 4 分：停止
+        tracker.record_round(66.0, 0.02, 500, 800);
+        let decision = tracker.evaluate().unwrap();
+        assert!(decision.stop);
+        assert_eq!(decision.recommendation, Recommendation::NotRecommended);
+        assert!((decision.marginal_utility - 0.2).abs() < 1e-9);
+        assert!((decision.improvement_per_dollar.unwrap() - 200.0).abs() < 1e-6);
+        assert!(decision.reason.contains("第3轮"));
+        assert_eq!(tracker.metrics().total_calls, 3);
+
+        // 风险分变差
+        let mut tracker = OutcomeTracker::new(ThrottleConfig::default());
+        tracker.record_round(80.0, 0.01, 0, 0);
+        tracker.record_round(85.0, 0.0, 0, 0);
+        let decision = tracker.evaluate().unwrap();
+        assert_eq!(decision.recommendation, Recommendation::StronglyNotRecommended);
+        assert_eq!(decision.improvement_per_dollar, None);
+    }
+
+    fn call(agent: &str, cost: f64, latency: u64, outcome: CallOutcome) -> AgentCallRecord {
+        AgentCallRecord {
+            agent_name: agent.to_string(),
+            timestamp: chrono::Utc::now(),
+            tokens_used: 100,
+            response_time_ms: latency,
+            success: outcome != CallOutcome::Unknown,
+            cost,
+            outcome,
+        }
+    }
+
+    #[test]
+    fn test_call_stats_and_leaderboard() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("calls.jsonl");
+
+        let mut manager = AgentExtensionManager::new().with_call_log(&log).unwrap();
+        manager.add_custom_agent(test_agent("Paralegal", "deepseek")).unwrap();
+        for latency in 1..=10 {
+            manager.record_call(call("Paralegal", 0.01, latency * 100, CallOutcome::Accepted));
+        }
+        manager.record_call(call("MOSS", 0.10, 900, CallOutcome::Accepted));
+        manager.record_call(call("MOSS", 0.10, 1100, CallOutcome::Blocked));
+        manager.record_call(call("Idle", 0.05, 50, CallOutcome::Unknown));
+
+        let stats = manager.call_stats(None);
+        let paralegal = stats.iter().find(|s| s.agent_name == "Paralegal").unwrap();
+        assert_eq!(paralegal.agent_type, AgentType::Custom);
+        assert_eq!((paralegal.latency_p50_ms, paralegal.latency_p90_ms, paralegal.latency_p99_ms), (500, 900, 1000));
+        assert!((paralegal.cost_per_accepted.unwrap() - 0.01).abs() < 1e-9);
+        assert_eq!(manager.get_custom_agent("Paralegal").unwrap().metrics.total_calls, 10);
+
+        let moss = stats.iter().find(|s| s.agent_name == "MOSS").unwrap();
+        assert_eq!(moss.block_rate, 0.5);
+        assert!((moss.cost_per_accepted.unwrap() - 0.2).abs() < 1e-9);
+
+        let board: Vec<_> = manager.leaderboard(None).into_iter().map(|s| s.agent_name).collect();
+        assert_eq!(board, vec!["Paralegal", "MOSS", "Idle"]);
+
+        // 记录持久化后可重新加载
+        let reloaded = AgentExtensionManager::new().with_call_log(&log).unwrap();
+        assert_eq!(reloaded.call_stats(None).len(), 3);
+        assert!(reloaded.call_stats(Some(chrono::Utc::now() + chrono::Duration::hours(1))).is_empty());
+    }
+
+    #[test]
+    fn test_register_validation() {
+        let mut manager = AgentExtensionManager::new();
+
+        assert!(manager.add_custom_agent(test_agent("moss", "openai")).is_err());
+        assert!(manager.add_custom_agent(test_agent("Bard", "palm")).is_err());
+        assert!(manager.add_custom_agent(test_agent("Local", "local")).is_err());
+        assert!(manager.add_custom_agent(test_agent("Cheap", "deepseek").with_cost_weight(0.0)).is_err());
+
+        // 同名注册为更新，不增加数量
+        manager.add_custom_agent(test_agent("Lawyer", "claude")).unwrap();
+        manager
+            .add_custom_agent(test_agent("Lawyer", "claude").with_system_prompt("You are a lawyer").with_cost_weight(2.0))
+            .unwrap();
         assert_eq!(manager.total_agent_count(), 5);
+
+        let list = manager.list_agents();
+        assert_eq!(list.custom_agents[0].provider.as_deref(), Some("claude/gpt-4"));
+        assert_eq!(list.custom_agents[0].cost_weight, 2.0);
+    }
+
+    #[test]
+    fn test_registry_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("agents.json");
+        assert_eq!(AgentExtensionManager::load_registry(&path).unwrap().total_agent_count(), 4);
+
+        let mut manager = AgentExtensionManager::new();
+        manager
+            .add_custom_agent(test_agent("Translator", "gemini").with_system_prompt("Translate to English"))
+            .unwrap();
+        manager.set_agent_enabled("Translator", false).unwrap();
+        manager.save_registry(&path).unwrap();
+
+        let loaded = AgentExtensionManager::load_registry(&path).unwrap();
+        let agent = loaded.get_custom_agent("Translator").unwrap();
+        assert_eq!(agent.system_prompt, "Translate to English");
+        assert!(!agent.enabled);
     }
 }
@@ -172,7 +172,15 @@ impl AgentStateManager {
         user_id: String,
         protocol: Protocol,
     ) -> Result<SessionState> {
-        let session_id = format!("session_{}_{}", user_id, Utc::now().timestamp_millis());
+        let mut sessions = self.sessions.write().await;
+        // 同一毫秒内为同一用户创建多个会话（如 /reset）时追加序号
+        let base_id = format!("session_{}_{}", user_id, Utc::now().timestamp_millis());
+        let mut session_id = base_id.clone();
+        let mut suffix = 1;
+        while sessions.contains_key(&session_id) {
+            session_id = format!("{}_{}", base_id, suffix);
+            suffix += 1;
+        }
 
         let session = SessionState {
             session_id: session_id.clone(),
@@ -186,7 +194,6 @@ impl AgentStateManager {
         };
 
         // 缓存
-        let mut sessions = self.sessions.write().await;
         sessions.insert(session_id.clone(), session.clone());
 
         // 持久化
@@ -216,6 +223,33 @@ impl AgentStateManager {
         }
     }
 
+    /// 切换会话的 Protocol
+    pub async fn set_protocol(&self, session_id: &str, protocol: Protocol) -> Result<SessionState> {
+        let mut sessions = self.sessions.write().await;
+        let session = sessions
+            .get_mut(session_id)
+            .ok_or_else(|| anyhow!("Session not found: {}", session_id))?;
+        session.current_protocol = protocol;
+        session.last_active_at = Utc::now();
+
+        if self.config.enable_persistence {
+            self.persist_session(session).await?;
+        }
+        Ok(session.clone())
+    }
+
+    /// 恢复已有会话及其对话历史（例如从本地会话记录继续对话）
+    pub async fn restore_session(&self, session: SessionState, history: Vec<Message>) {
+        let session_id = session.session_id.clone();
+        let keep = history.len().saturating_sub(self.config.max_history_messages);
+        self.messages
+            .write()
+            .await
+            .insert(session_id.clone(), history.into_iter().skip(keep).collect());
+        self.sessions.write().await.insert(session_id.clone(), session);
+        info!("🔄 Restored session: {}", session_id);
+    }
+
     /// 添加消息
     pub async fn add_message(&self, message: Message) -> Result<()> {
         // 更新会话活跃时间
@@ -2,10 +2,13 @@
 // 支持NPU、GPU等硬件控制
 
 use std::collections::HashMap;
+use std::sync::Arc;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use tracing::{info, warn};
 
+use super::kill_switch::{KillSwitch, PausedOperation};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum HardwareType {
     Cpu,
@@ -31,6 +34,8 @@ pub struct HardwareStatus {
 
 pub struct AipcController {
     hardware_status: HashMap<HardwareType, HardwareStatus>,
+    /// 全局熔断开关（维护模式下拒绝硬件指令）
+    kill_switch: Option<Arc<KillSwitch>>,
 }
 
 impl AipcController {
@@ -44,10 +49,19 @@ impl AipcController {
             usage_percent: 0.0,
         });
         
-        Self { hardware_status }
+        Self { hardware_status, kill_switch: None }
+    }
+
+    /// 接入全局熔断开关
+    pub fn with_kill_switch(mut self, kill_switch: Arc<KillSwitch>) -> Self {
+        self.kill_switch = Some(kill_switch);
+        self
     }
 
     pub async fn execute_command(&self, command: HardwareCommand) -> Result<String> {
+        if let Some(kill_switch) = &self.kill_switch {
+            kill_switch.check(PausedOperation::Hardware)?;
+        }
         info!("🎮 Executing hardware command: {:?}", command.action);
         
         // TODO: 实际硬件控制实现
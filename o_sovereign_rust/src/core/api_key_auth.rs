@@ -0,0 +1,296 @@
+// API Key Auth - 静态 API 密钥认证
+// 服务间调用无需走登录流程：请求带 `X-API-Key`（或 `Authorization: Bearer acsa_sk_...`）即可
+//
+// 核心功能：
+// 1. 密钥只在创建时返回一次明文，数据库（DatabaseManager）中只保存 SHA-256 哈希
+// 2. 每个密钥绑定角色与工作区，认证后得到与 JWT 相同的 Claims（沿用 RBAC 权限检查）
+// 3. 每个密钥可单独配置限流规则（RateLimiter::check_api_key）
+// 4. Claims.api_key_id 用于在所属租户（工作区）的 ApiManager 中按密钥归属用量
+// 5. 吊销、最近使用时间
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::info;
+
+use super::auth_system::{hex_encode, Claims, Role};
+use super::database::{DatabaseManager, QueryRow};
+use super::error::{AcsaError, ErrorCode};
+use super::rate_limiter::RateLimitRule;
+use super::workspace::TenantId;
+
+/// 密钥前缀（便于在日志与代码扫描中识别）
+pub const API_KEY_PREFIX: &str = "acsa_sk_";
+
+/// 携带密钥的请求头
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// API 密钥元数据（不含明文与哈希）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKeyInfo {
+    pub key_id: String,
+    /// 调用方名称（如 `billing-service`）
+    pub name: String,
+    /// 可展示的前缀，如 `acsa_sk_1a2b3c4d_…`
+    pub display_prefix: String,
+    pub roles: Vec<Role>,
+    pub workspace_id: Option<String>,
+    /// 单独的限流规则（为空时使用 RateLimiterConfig::api_key_rule）
+    pub rate_limit: Option<RateLimitRule>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked: bool,
+}
+
+impl ApiKeyInfo {
+    /// 密钥所属租户
+    pub fn tenant(&self) -> TenantId {
+        self.workspace_id.as_deref().map(TenantId::new).unwrap_or_default()
+    }
+
+    /// 认证结果：与 JWT 相同的 Claims，sub 为 `api_key:{key_id}`
+    pub fn claims(&self) -> Claims {
+        let now = Utc::now().timestamp().max(0) as u64;
+        Claims {
+            sub: format!("api_key:{}", self.key_id),
+            // 每次请求都重新认证，Claims 只在本次请求内有效
+            exp: now + 60,
+            iat: now,
+            user_id: format!("api_key:{}", self.key_id),
+            username: self.name.clone(),
+            roles: self.roles.iter().map(|role| role.as_str().to_string()).collect(),
+            workspace_id: self.workspace_id.clone(),
+            api_key_id: Some(self.key_id.clone()),
+        }
+    }
+}
+
+/// API 密钥存储
+pub struct ApiKeyStore {
+    database: Arc<DatabaseManager>,
+}
+
+impl ApiKeyStore {
+    pub fn new(database: Arc<DatabaseManager>) -> Self {
+        Self { database }
+    }
+
+    /// 建表（幂等）
+    pub async fn migrate(&self) -> Result<()> {
+        self.database
+            .execute(
+                "CREATE TABLE IF NOT EXISTS api_keys (
+                    key_id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    key_hash TEXT NOT NULL UNIQUE,
+                    display_prefix TEXT NOT NULL,
+                    roles TEXT NOT NULL,
+                    workspace_id TEXT,
+                    rate_limit TEXT,
+                    created_at TEXT NOT NULL,
+                    last_used_at TEXT,
+                    revoked INTEGER NOT NULL DEFAULT 0
+                )",
+                vec![],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// 创建密钥，返回元数据与明文（明文不会再次可见）
+    pub async fn create(
+        &self,
+        name: &str,
+        roles: Vec<Role>,
+        workspace_id: Option<String>,
+        rate_limit: Option<RateLimitRule>,
+    ) -> Result<(ApiKeyInfo, String)> {
+        if name.trim().is_empty() {
+            return Err(anyhow!("API key name must not be empty"));
+        }
+        if roles.is_empty() {
+            return Err(anyhow!("API key needs at least one role"));
+        }
+
+        let key_id = hex_encode(&random_bytes::<4>()?);
+        let secret = format!("{}{}_{}", API_KEY_PREFIX, key_id, hex_encode(&random_bytes::<32>()?));
+        let info = ApiKeyInfo {
+            display_prefix: format!("{}{}_…", API_KEY_PREFIX, key_id),
+            key_id,
+            name: name.trim().to_string(),
+            roles,
+            workspace_id,
+            rate_limit,
+            created_at: Utc::now(),
+            last_used_at: None,
+            revoked: false,
+        };
+
+        self.database
+            .execute(
+                "INSERT INTO api_keys (key_id, name, key_hash, display_prefix, roles, workspace_id, rate_limit, created_at)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+                vec![
+                    info.key_id.clone().into(),
+                    info.name.clone().into(),
+                    hash_key(&secret).into(),
+                    info.display_prefix.clone().into(),
+                    serde_json::to_string(&info.roles)?.into(),
+                    info.workspace_id.clone().into(),
+                    info.rate_limit.as_ref().map(serde_json::to_string).transpose()?.into(),
+                    info.created_at.to_rfc3339().into(),
+                ],
+            )
+            .await?;
+
+        info!("🔑 API key created: {} ({})", info.display_prefix, info.name);
+        Ok((info, secret))
+    }
+
+    /// 校验请求携带的密钥（未知或已吊销时返回 E5002）
+    pub async fn authenticate(&self, presented: &str) -> Result<ApiKeyInfo> {
+        let invalid = || AcsaError::new(ErrorCode::ApiKeyInvalid, "Unknown or revoked API key");
+        if !presented.starts_with(API_KEY_PREFIX) {
+            return Err(invalid().into());
+        }
+
+        let row = self
+            .database
+            .query_one(
+                "SELECT * FROM api_keys WHERE key_hash = ? AND revoked = 0",
+                vec![hash_key(presented).into()],
+            )
+            .await?
+            .ok_or_else(invalid)?;
+        let mut info = row_to_info(&row)?;
+
+        let now = Utc::now();
+        self.database
+            .execute(
+                "UPDATE api_keys SET last_used_at = ? WHERE key_id = ?",
+                vec![now.to_rfc3339().into(), info.key_id.clone().into()],
+            )
+            .await?;
+        info.last_used_at = Some(now);
+        Ok(info)
+    }
+
+    /// 列出全部密钥（含已吊销）
+    pub async fn list(&self) -> Result<Vec<ApiKeyInfo>> {
+        self.database
+            .query("SELECT * FROM api_keys ORDER BY created_at", vec![])
+            .await?
+            .iter()
+            .map(row_to_info)
+            .collect()
+    }
+
+    /// 列出某个租户的密钥（未绑定工作区的密钥属于默认租户）
+    pub async fn list_for_tenant(&self, tenant: &TenantId) -> Result<Vec<ApiKeyInfo>> {
+        Ok(self
+            .list()
+            .await?
+            .into_iter()
+            .filter(|info| info.tenant() == *tenant)
+            .collect())
+    }
+
+    /// 吊销密钥，返回是否存在未吊销的该密钥
+    pub async fn revoke(&self, key_id: &str) -> Result<bool> {
+        let affected = self
+            .database
+            .execute(
+                "UPDATE api_keys SET revoked = 1 WHERE key_id = ? AND revoked = 0",
+                vec![key_id.into()],
+            )
+            .await?;
+        if affected > 0 {
+            info!("🔑 API key revoked: {}", key_id);
+        }
+        Ok(affected > 0)
+    }
+}
+
+/// 从请求头取出 API 密钥：优先 `X-API-Key`，其次 `Authorization: Bearer acsa_sk_...`
+pub fn presented_api_key<'a>(authorization: Option<&'a str>, x_api_key: Option<&'a str>) -> Option<&'a str> {
+    if let Some(key) = x_api_key.map(str::trim).filter(|key| !key.is_empty()) {
+        return Some(key);
+    }
+    authorization
+        .and_then(|value| value.trim().strip_prefix("Bearer "))
+        .map(str::trim)
+        .filter(|token| token.starts_with(API_KEY_PREFIX))
+}
+
+fn hash_key(key: &str) -> String {
+    hex_encode(ring::digest::digest(&ring::digest::SHA256, key.as_bytes()).as_ref())
+}
+
+fn random_bytes<const N: usize>() -> Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    ring::rand::SecureRandom::fill(&ring::rand::SystemRandom::new(), &mut bytes)
+        .map_err(|_| anyhow!("Failed to generate API key"))?;
+    Ok(bytes)
+}
+
+fn row_to_info(row: &QueryRow) -> Result<ApiKeyInfo> {
+    let text = |column: &str| row.get(column).and_then(|value| value.as_str());
+    let required = |column: &str| text(column).ok_or_else(|| anyhow!("api_keys row missing {}", column));
+    let timestamp = |value: &str| DateTime::parse_from_rfc3339(value).map(|t| t.with_timezone(&Utc));
+
+    Ok(ApiKeyInfo {
+        key_id: required("key_id")?.to_string(),
+        name: required("name")?.to_string(),
+        display_prefix: required("display_prefix")?.to_string(),
+        roles: serde_json::from_str(required("roles")?)?,
+        workspace_id: text("workspace_id").map(str::to_string),
+        rate_limit: text("rate_limit").map(serde_json::from_str).transpose()?,
+        created_at: timestamp(required("created_at")?)?,
+        last_used_at: text("last_used_at").map(timestamp).transpose()?,
+        revoked: row.get("revoked").and_then(|value| value.as_i64()).unwrap_or(0) != 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::auth_system::Permission;
+    use crate::core::database::DatabaseConfig;
+
+    #[tokio::test]
+    async fn test_api_key_lifecycle() {
+        let database = Arc::new(DatabaseManager::new(DatabaseConfig {
+            url: "sqlite::memory:".to_string(),
+            ..Default::default()
+        }));
+        database.connect().await.unwrap();
+        let store = ApiKeyStore::new(database.clone());
+        store.migrate().await.unwrap();
+
+        let (info, secret) = store
+            .create("billing-service", vec![Role::Operator], Some("team-a".to_string()), None)
+            .await
+            .unwrap();
+        assert!(secret.starts_with(API_KEY_PREFIX));
+
+        // 数据库中没有明文
+        let rows = database.query("SELECT * FROM api_keys", vec![]).await.unwrap();
+        assert!(rows.iter().all(|row| row.values().all(|value| value.as_str() != Some(secret.as_str()))));
+
+        let header = format!("Bearer {}", secret);
+        assert_eq!(presented_api_key(Some(&header), None), Some(secret.as_str()));
+        assert_eq!(presented_api_key(Some("Bearer eyJhbGciOi"), None), None);
+
+        let claims = store.authenticate(&secret).await.unwrap().claims();
+        assert_eq!(claims.api_key_id.as_deref(), Some(info.key_id.as_str()));
+        assert_eq!(claims.workspace_id.as_deref(), Some("team-a"));
+        assert!(claims.can(Permission::Execute));
+        assert!(!claims.can(Permission::ManageUsers));
+        assert!(store.list().await.unwrap()[0].last_used_at.is_some());
+
+        assert!(store.authenticate("acsa_sk_forged").await.is_err());
+        assert!(store.revoke(&info.key_id).await.unwrap());
+        assert!(store.authenticate(&secret).await.is_err());
+    }
+}
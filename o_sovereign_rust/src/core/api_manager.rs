@@ -1,14 +1,31 @@
 // API Key Management and Cost Tracking Module
 // API密钥管理与花费统计系统
+//
+// 预算：每个 Provider 可设置按日 / 按月的软上限（告警）与硬上限（告警并拦截后续调用），
+// 越过阈值时通过 event_bus 发布 `api.budget_alert` 事件
+//
+// 密钥存储：配置了 SecretStore 时密钥写入系统钥匙串，api_keys.json 只保存元数据
+//
+// 用量归属：HTTP 服务以静态 API 密钥认证的调用按密钥 ID 累计用量（key_usage.json）
 
 use anyhow::{anyhow, Result};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::{Arc, LazyLock, RwLock};
 use tokio::fs;
 use tracing::{debug, info, warn};
 
+use super::audit_log::WORKSPACE_METADATA_KEY;
+use super::error::{AcsaError, ErrorCode};
+use super::event_bus::{Event, EventBus, EventType};
+use super::secret_store::{SecretStore, SECRET_SERVICE};
+use super::workspace::TenantId;
+
+/// 预算告警在事件总线上的事件类型（`EventType::System`）
+pub const BUDGET_ALERT_EVENT: &str = "api.budget_alert";
+
 /// API提供商类型
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ApiProvider {
@@ -37,12 +54,16 @@ impl ApiProvider {
         }
     }
 
-    pub fn pricing_info(&self) -> &'static str {
-        match self {
-            ApiProvider::OpenAI => "$30/1M tokens (GPT-4)",
-            ApiProvider::Claude => "$15-75/1M tokens (Opus)",
-            ApiProvider::Gemini => "$0.50/1M tokens (Pro)",
-            ApiProvider::DeepSeek => "$0.14-0.28/1M tokens (Coder)",
+    /// 默认模型的价格说明（来自当前价格表）
+    pub fn pricing_info(&self) -> String {
+        match PricingTable::current().rate(*self, self.default_model()) {
+            Some(rate) => format!(
+                "${}/${} per 1K input/output tokens ({})",
+                rate.input_per_1k,
+                rate.output_per_1k,
+                self.default_model()
+            ),
+            None => format!("pricing unknown ({})", self.default_model()),
         }
     }
 
@@ -56,10 +77,194 @@ impl ApiProvider {
     }
 }
 
+/// 单个模型的 token 单价（USD / 1K token）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TokenRate {
+    pub input_per_1k: f64,
+    pub output_per_1k: f64,
+}
+
+impl TokenRate {
+    pub fn new(input_per_1k: f64, output_per_1k: f64) -> Self {
+        Self { input_per_1k, output_per_1k }
+    }
+
+    pub fn cost(&self, prompt_tokens: u32, completion_tokens: u32) -> f64 {
+        (prompt_tokens as f64 / 1000.0) * self.input_per_1k + (completion_tokens as f64 / 1000.0) * self.output_per_1k
+    }
+}
+
+/// 价格表中的一行
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelRate {
+    pub provider: ApiProvider,
+    /// 模型名（也匹配以它开头的带日期 / 版本后缀的模型名）
+    pub model: String,
+    #[serde(flatten)]
+    pub rate: TokenRate,
+}
+
+/// 各 Provider / 模型的 token 价格表
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PricingTable {
+    pub models: Vec<ModelRate>,
+}
+
+/// 当前生效的价格表（各 Provider 计费与成本估算共用，可在运行时更新）
+static PRICING: LazyLock<RwLock<PricingTable>> = LazyLock::new(|| RwLock::new(PricingTable::default()));
+
+impl Default for PricingTable {
+    /// 各 Provider 公布的标准价格
+    fn default() -> Self {
+        let mut table = Self { models: Vec::new() };
+        for (provider, model, input, output) in [
+            (ApiProvider::OpenAI, "gpt-4", 0.03, 0.06),
+            (ApiProvider::OpenAI, "gpt-4-turbo", 0.01, 0.03),
+            (ApiProvider::OpenAI, "gpt-4o", 0.0025, 0.01),
+            (ApiProvider::OpenAI, "gpt-4o-mini", 0.00015, 0.0006),
+            (ApiProvider::OpenAI, "gpt-3.5-turbo", 0.0005, 0.0015),
+            (ApiProvider::Claude, "claude-3-opus", 0.015, 0.075),
+            (ApiProvider::Claude, "claude-3-5-sonnet", 0.003, 0.015),
+            (ApiProvider::Claude, "claude-3-sonnet", 0.003, 0.015),
+            (ApiProvider::Claude, "claude-3-haiku", 0.00025, 0.00125),
+            (ApiProvider::Gemini, "gemini-pro", 0.0005, 0.0015),
+            (ApiProvider::Gemini, "gemini-1.5-pro", 0.00125, 0.005),
+            (ApiProvider::Gemini, "gemini-1.5-flash", 0.000075, 0.0003),
+            (ApiProvider::DeepSeek, "deepseek-coder", 0.00014, 0.00028),
+            (ApiProvider::DeepSeek, "deepseek-chat", 0.00014, 0.00028),
+        ] {
+            table.set_rate(provider, model, TokenRate::new(input, output));
+        }
+        table
+    }
+}
+
+impl PricingTable {
+    /// 当前生效的价格表
+    pub fn current() -> Self {
+        PRICING.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// 替换当前生效的价格表
+    pub fn install(self) {
+        *PRICING.write().unwrap_or_else(|e| e.into_inner()) = self;
+    }
+
+    /// 新增或覆盖某个模型的价格
+    pub fn set_rate(&mut self, provider: ApiProvider, model: &str, rate: TokenRate) {
+        match self.models.iter_mut().find(|m| m.provider == provider && m.model == model) {
+            Some(existing) => existing.rate = rate,
+            None => self.models.push(ModelRate { provider, model: model.to_string(), rate }),
+        }
+    }
+
+    /// 查找模型价格：精确匹配优先，否则取最长的前缀匹配（如 `claude-3-opus-20240229` → `claude-3-opus`）
+    pub fn rate(&self, provider: ApiProvider, model: &str) -> Option<TokenRate> {
+        self.models
+            .iter()
+            .filter(|m| m.provider == provider && model.starts_with(m.model.as_str()))
+            .max_by_key(|m| m.model.len())
+            .map(|m| m.rate)
+    }
+
+    /// 按实际输入 / 输出 token 计价；未知模型按该 Provider 默认模型计价
+    pub fn estimate_cost(&self, provider: ApiProvider, model: &str, prompt_tokens: u32, completion_tokens: u32) -> f64 {
+        let rate = self.rate(provider, model).or_else(|| {
+            warn!("⚠️  No pricing for {} model '{}', using {} rates", provider.name(), model, provider.default_model());
+            self.rate(provider, provider.default_model())
+        });
+        rate.map(|r| r.cost(prompt_tokens, completion_tokens)).unwrap_or(0.0)
+    }
+}
+
+/// 预算周期（UTC 自然日 / 自然月）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetPeriod {
+    Daily,
+    Monthly,
+}
+
+impl BudgetPeriod {
+    pub fn label(&self) -> &'static str {
+        match self {
+            BudgetPeriod::Daily => "daily",
+            BudgetPeriod::Monthly => "monthly",
+        }
+    }
+
+    /// `now` 所在周期的起点
+    pub fn period_start(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let date = match self {
+            BudgetPeriod::Daily => now.date_naive(),
+            BudgetPeriod::Monthly => NaiveDate::from_ymd_opt(now.year(), now.month(), 1).unwrap_or(now.date_naive()),
+        };
+        date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc()
+    }
+}
+
+/// Provider 预算策略（USD）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BudgetPolicy {
+    /// 软上限：达到后发布告警，调用照常
+    pub soft_limit: f64,
+    /// 硬上限：达到后发布告警并拦截后续调用（None 表示只告警）
+    pub hard_limit: Option<f64>,
+    pub period: BudgetPeriod,
+}
+
+impl BudgetPolicy {
+    pub fn new(period: BudgetPeriod, soft_limit: f64) -> Self {
+        Self { soft_limit, hard_limit: None, period }
+    }
+
+    pub fn with_hard_limit(mut self, hard_limit: f64) -> Self {
+        self.hard_limit = Some(hard_limit);
+        self
+    }
+
+    /// 按当期花费判断所处状态
+    pub fn state(&self, spent: f64) -> BudgetState {
+        if self.hard_limit.is_some_and(|limit| spent >= limit) {
+            BudgetState::HardExceeded
+        } else if spent >= self.soft_limit {
+            BudgetState::SoftExceeded
+        } else {
+            BudgetState::Within
+        }
+    }
+}
+
+/// 预算状态（由轻到重）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetState {
+    Within,
+    SoftExceeded,
+    HardExceeded,
+}
+
+/// 单个 Provider 的预算报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BudgetStatus {
+    pub provider: ApiProvider,
+    pub policy: BudgetPolicy,
+    pub period_start: DateTime<Utc>,
+    /// 本期已花费（成功调用）
+    pub spent: f64,
+    /// 距离硬上限（没有硬上限时为软上限）的余额，不小于 0
+    pub remaining: f64,
+    pub state: BudgetState,
+    /// 是否拦截后续调用
+    pub blocked: bool,
+}
+
 /// API密钥配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ApiKeyConfig {
     pub provider: ApiProvider,
+    /// 使用钥匙串时文件中为空，加载时从钥匙串填充
+    #[serde(default)]
     pub api_key: String,
     pub model: Option<String>,
     pub enabled: bool,
@@ -112,6 +317,9 @@ pub struct ApiCallRecord {
     pub success: bool,
     pub error_message: Option<String>,
     pub agent_role: Option<String>,
+    /// 发起调用的静态 API 密钥（HTTP 服务以密钥认证时）
+    #[serde(default)]
+    pub api_key_id: Option<String>,
 }
 
 impl ApiCallRecord {
@@ -131,6 +339,7 @@ impl ApiCallRecord {
             success: true,
             error_message: None,
             agent_role,
+            api_key_id: None,
         }
     }
 
@@ -149,8 +358,44 @@ impl ApiCallRecord {
             success: false,
             error_message: Some(error),
             agent_role,
+            api_key_id: None,
         }
     }
+
+    /// 把本次调用的用量归到某个 API 密钥
+    pub fn with_api_key(mut self, key_id: impl Into<String>) -> Self {
+        self.api_key_id = Some(key_id.into());
+        self
+    }
+}
+
+/// 单个 API 密钥的累计用量
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyUsage {
+    pub key_id: String,
+    pub requests: u64,
+    pub total_tokens: u64,
+    pub total_cost: f64,
+    pub last_used: Option<DateTime<Utc>>,
+}
+
+impl KeyUsage {
+    pub fn new(key_id: &str) -> Self {
+        Self {
+            key_id: key_id.to_string(),
+            requests: 0,
+            total_tokens: 0,
+            total_cost: 0.0,
+            last_used: None,
+        }
+    }
+
+    fn add(&mut self, tokens: u64, cost: f64, at: DateTime<Utc>) {
+        self.requests += 1;
+        self.total_tokens += tokens;
+        self.total_cost += cost;
+        self.last_used = Some(at);
+    }
 }
 
 /// 提供商统计摘要
@@ -196,6 +441,14 @@ impl ProviderStats {
     }
 }
 
+/// 按当前价格表计价（各 Provider 记账用）
+pub fn estimate_cost(provider: ApiProvider, model: &str, prompt_tokens: u32, completion_tokens: u32) -> f64 {
+    PRICING
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .estimate_cost(provider, model, prompt_tokens, completion_tokens)
+}
+
 /// API管理器
 pub struct ApiManager {
     /// API密钥配置 (provider -> config)
@@ -206,6 +459,18 @@ pub struct ApiManager {
     provider_stats: HashMap<ApiProvider, ProviderStats>,
     /// 数据持久化路径
     data_dir: PathBuf,
+    /// Provider 预算策略
+    budgets: HashMap<ApiProvider, BudgetPolicy>,
+    /// 已告警的状态（周期起点, 状态），同一周期内只在状态升级时告警
+    budget_alerts: HashMap<ApiProvider, (DateTime<Utc>, BudgetState)>,
+    /// 预算告警发布目标
+    event_bus: Option<Arc<EventBus>>,
+    /// 密钥存储与账号前缀（为空时密钥明文写入 api_keys.json）
+    secret_store: Option<(Arc<dyn SecretStore>, String)>,
+    /// 按 API 密钥 ID 累计的用量
+    key_usage: HashMap<String, KeyUsage>,
+    /// 所属租户（工作区的 ApiManager；预算告警带上租户元数据）
+    tenant: Option<TenantId>,
 }
 
 impl ApiManager {
@@ -215,9 +480,39 @@ impl ApiManager {
             call_history: Vec::new(),
             provider_stats: HashMap::new(),
             data_dir,
+            budgets: HashMap::new(),
+            budget_alerts: HashMap::new(),
+            event_bus: None,
+            secret_store: None,
+            key_usage: HashMap::new(),
+            tenant: None,
         }
     }
 
+    /// API 密钥保存到钥匙串，账号名为 `{namespace}/api_key.{provider}`（多个工作区互不覆盖）
+    ///
+    /// init 时会把文件中遗留的明文密钥迁移过去
+    pub fn with_secret_store(mut self, store: Arc<dyn SecretStore>, namespace: impl Into<String>) -> Self {
+        self.secret_store = Some((store, namespace.into()));
+        self
+    }
+
+    /// 标记所属租户
+    pub fn with_tenant(mut self, tenant: TenantId) -> Self {
+        self.tenant = Some(tenant);
+        self
+    }
+
+    pub fn tenant(&self) -> Option<&TenantId> {
+        self.tenant.as_ref()
+    }
+
+    /// 预算告警发布到事件总线
+    pub fn with_event_bus(mut self, event_bus: Arc<EventBus>) -> Self {
+        self.event_bus = Some(event_bus);
+        self
+    }
+
     /// 初始化 - 从磁盘加载数据
     pub async fn init(&mut self) -> Result<()> {
         info!("📊 Initializing API Manager...");
@@ -231,6 +526,15 @@ impl ApiManager {
         // 加载调用历史
         self.load_call_history().await?;
 
+        // 加载自定义价格表
+        self.load_pricing().await?;
+
+        // 加载预算策略
+        self.load_budgets().await?;
+
+        // 加载 API 密钥用量
+        self.load_key_usage().await?;
+
         // 重新计算统计数据
         self.recalculate_stats();
 
@@ -246,6 +550,7 @@ impl ApiManager {
         let provider = config.provider;
         info!("➕ Adding API key for {}", provider.name());
 
+        self.store_secret(&config).await?;
         self.api_keys.insert(provider, config);
         self.save_api_keys().await?;
 
@@ -257,6 +562,9 @@ impl ApiManager {
         info!("➖ Removing API key for {}", provider.name());
 
         if self.api_keys.remove(&provider).is_some() {
+            if let Some((store, namespace)) = &self.secret_store {
+                store.delete(SECRET_SERVICE, &secret_account(namespace, provider)).await?;
+            }
             self.save_api_keys().await?;
             Ok(())
         } else {
@@ -273,6 +581,7 @@ impl ApiManager {
             return Err(anyhow!("API key for {} not found", provider.name()));
         }
 
+        self.store_secret(&config).await?;
         self.api_keys.insert(provider, config);
         self.save_api_keys().await?;
 
@@ -289,6 +598,162 @@ impl ApiManager {
         self.api_keys.values().collect()
     }
 
+    /// 按当前价格表估算一次调用的成本（USD）
+    pub fn estimate_cost(
+        &self,
+        provider: ApiProvider,
+        model: &str,
+        prompt_tokens: u32,
+        completion_tokens: u32,
+    ) -> f64 {
+        estimate_cost(provider, model, prompt_tokens, completion_tokens)
+    }
+
+    /// 当前价格表
+    pub fn pricing(&self) -> PricingTable {
+        PricingTable::current()
+    }
+
+    /// 更新某个模型的价格：立即对所有 Provider 生效并写入 pricing.json
+    pub async fn update_pricing(&mut self, provider: ApiProvider, model: &str, rate: TokenRate) -> Result<()> {
+        info!(
+            "💲 Updating pricing for {} {}: ${}/${} per 1K tokens",
+            provider.name(),
+            model,
+            rate.input_per_1k,
+            rate.output_per_1k
+        );
+        let mut table = PricingTable::current();
+        table.set_rate(provider, model, rate);
+        self.save_pricing(&table).await?;
+        table.install();
+        Ok(())
+    }
+
+    /// 设置 Provider 预算
+    pub async fn set_budget(&mut self, provider: ApiProvider, policy: BudgetPolicy) -> Result<()> {
+        info!(
+            "💰 Setting {} budget for {}: soft ${:.2}, hard {}",
+            policy.period.label(),
+            provider.name(),
+            policy.soft_limit,
+            policy.hard_limit.map(|l| format!("${:.2}", l)).unwrap_or_else(|| "none".to_string())
+        );
+        self.budgets.insert(provider, policy);
+        self.budget_alerts.remove(&provider);
+        self.save_budgets().await
+    }
+
+    /// 取消 Provider 预算
+    pub async fn remove_budget(&mut self, provider: ApiProvider) -> Result<()> {
+        if self.budgets.remove(&provider).is_some() {
+            self.budget_alerts.remove(&provider);
+            self.save_budgets().await?;
+        }
+        Ok(())
+    }
+
+    /// 单个 Provider 的预算报告（未设置预算时为 None）
+    pub fn budget_status(&self, provider: ApiProvider) -> Option<BudgetStatus> {
+        let policy = self.budgets.get(&provider)?.clone();
+        let period_start = policy.period.period_start(Utc::now());
+        let spent: f64 = self
+            .call_history
+            .iter()
+            .filter(|r| r.provider == provider && r.success && r.timestamp >= period_start)
+            .map(|r| r.cost)
+            .sum();
+        let state = policy.state(spent);
+        let ceiling = policy.hard_limit.unwrap_or(policy.soft_limit);
+        Some(BudgetStatus {
+            provider,
+            period_start,
+            spent,
+            remaining: (ceiling - spent).max(0.0),
+            state,
+            blocked: state == BudgetState::HardExceeded,
+            policy,
+        })
+    }
+
+    /// 所有设置了预算的 Provider 的报告
+    pub fn get_budget_status(&self) -> Vec<BudgetStatus> {
+        ApiProvider::all().into_iter().filter_map(|p| self.budget_status(p)).collect()
+    }
+
+    /// 调用前检查：已达硬上限时返回 BudgetExceeded
+    pub fn check_budget(&self, provider: ApiProvider) -> Result<()> {
+        match self.budget_status(provider) {
+            Some(status) if status.blocked => Err(AcsaError::new(
+                ErrorCode::BudgetExceeded,
+                format!(
+                    "{} has spent ${:.4} of its {} ${:.2} hard limit",
+                    provider.name(),
+                    status.spent,
+                    status.policy.period.label(),
+                    status.policy.hard_limit.unwrap_or_default()
+                ),
+            )
+            .into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// 状态在本周期内升级时发布预算告警
+    async fn evaluate_budget(&mut self, provider: ApiProvider) {
+        let Some(status) = self.budget_status(provider) else {
+            return;
+        };
+        let previous = self
+            .budget_alerts
+            .get(&provider)
+            .filter(|(start, _)| *start == status.period_start)
+            .map(|(_, state)| *state)
+            .unwrap_or(BudgetState::Within);
+        if status.state <= previous {
+            return;
+        }
+        self.budget_alerts.insert(provider, (status.period_start, status.state));
+
+        warn!(
+            "💸 {} {} budget {:?}: ${:.4} spent (soft ${:.2}, hard {:?})",
+            provider.name(),
+            status.policy.period.label(),
+            status.state,
+            status.spent,
+            status.policy.soft_limit,
+            status.policy.hard_limit
+        );
+        let Some(bus) = &self.event_bus else {
+            return;
+        };
+        let data = match serde_json::to_value(&status) {
+            Ok(data) => data,
+            Err(e) => {
+                warn!("⚠️  Failed to encode budget alert: {}", e);
+                return;
+            }
+        };
+        let mut metadata = HashMap::from([
+            ("provider".to_string(), provider.name().to_string()),
+            ("state".to_string(), format!("{:?}", status.state)),
+        ]);
+        if let Some(tenant) = &self.tenant {
+            metadata.insert(WORKSPACE_METADATA_KEY.to_string(), tenant.to_string());
+        }
+        let event = Event {
+            event_id: format!("budget_{}_{}", provider.name().to_lowercase(), Utc::now().timestamp_millis()),
+            event_type: EventType::System(BUDGET_ALERT_EVENT.to_string()),
+            source: "api_manager".to_string(),
+            data,
+            timestamp: Utc::now(),
+            metadata,
+        };
+        if let Err(e) = bus.publish(event).await {
+            warn!("⚠️  Failed to publish budget alert: {}", e);
+        }
+    }
+
     /// 记录API调用
     pub async fn record_call(&mut self, record: ApiCallRecord) -> Result<()> {
         debug!("📝 Recording API call: {:?}", record.provider);
@@ -327,15 +792,47 @@ impl ApiManager {
         }
         stats.last_call = Some(record.timestamp);
 
+        // 预算告警
+        if record.success {
+            self.evaluate_budget(record.provider).await;
+        }
+
+        if let Some(key_id) = record.api_key_id.as_deref() {
+            self.key_usage
+                .entry(key_id.to_string())
+                .or_insert_with(|| KeyUsage::new(key_id))
+                .add(record.tokens_used as u64, record.cost, record.timestamp);
+        }
+
         // 定期持久化（每10次调用）
         if self.call_history.len() % 10 == 0 {
             self.save_call_history().await?;
             self.save_api_keys().await?;
+            self.save_key_usage().await?;
         }
 
         Ok(())
     }
 
+    /// 记录一次以 API 密钥发起的请求（整次执行的 Token 与花费）
+    pub async fn record_key_usage(&mut self, key_id: &str, tokens: u64, cost: f64) -> Result<()> {
+        self.key_usage
+            .entry(key_id.to_string())
+            .or_insert_with(|| KeyUsage::new(key_id))
+            .add(tokens, cost, Utc::now());
+        self.save_key_usage().await
+    }
+
+    /// 某个 API 密钥的累计用量
+    pub fn key_usage(&self, key_id: &str) -> Option<&KeyUsage> {
+        self.key_usage.get(key_id)
+    }
+
+    /// 所有 API 密钥的累计用量
+    pub fn get_all_key_usage(&self) -> Vec<&KeyUsage> {
+        self.key_usage.values().collect()
+    }
+
     /// 获取提供商统计信息
     pub fn get_provider_stats(&self, provider: ApiProvider) -> Option<&ProviderStats> {
         self.provider_stats.get(&provider)
@@ -348,7 +845,7 @@ impl ApiManager {
 
     /// 获取总花费
     pub fn get_total_cost(&self) -> f64 {
-        self.provider_stats.values().map(|s| s.total_cost).sum()
+        self.provider_stats.values().fold(0.0, |acc, s| acc + s.total_cost)
     }
 
     /// 获取总Token数
@@ -470,9 +967,102 @@ impl ApiManager {
         self.data_dir.join("call_history.json")
     }
 
+    fn key_usage_path(&self) -> PathBuf {
+        self.data_dir.join("key_usage.json")
+    }
+
+    async fn save_key_usage(&self) -> Result<()> {
+        let json = serde_json::to_string_pretty(&self.key_usage)?;
+        fs::write(self.key_usage_path(), json).await?;
+        debug!("💾 Saved API key usage");
+        Ok(())
+    }
+
+    async fn load_key_usage(&mut self) -> Result<()> {
+        let path = self.key_usage_path();
+        if !path.exists() {
+            return Ok(());
+        }
+
+        let json = fs::read_to_string(path).await?;
+        self.key_usage = serde_json::from_str(&json)?;
+        Ok(())
+    }
+
+    fn budgets_path(&self) -> PathBuf {
+        self.data_dir.join("budgets.json")
+    }
+
+    async fn save_budgets(&self) -> Result<()> {
+        let json = serde_json::to_string_pretty(&self.budgets)?;
+        fs::write(self.budgets_path(), json).await?;
+        debug!("💾 Saved budgets");
+        Ok(())
+    }
+
+    async fn load_budgets(&mut self) -> Result<()> {
+        let path = self.budgets_path();
+        if !path.exists() {
+            debug!("No existing budgets file found");
+            return Ok(());
+        }
+
+        let json = fs::read_to_string(path).await?;
+        self.budgets = serde_json::from_str(&json)?;
+        info!("📂 Loaded {} provider budgets", self.budgets.len());
+        Ok(())
+    }
+
+    fn pricing_path(&self) -> PathBuf {
+        self.data_dir.join("pricing.json")
+    }
+
+    async fn save_pricing(&self, table: &PricingTable) -> Result<()> {
+        let json = serde_json::to_string_pretty(table)?;
+        fs::write(self.pricing_path(), json).await?;
+        debug!("💾 Saved pricing table ({} models)", table.models.len());
+        Ok(())
+    }
+
+    /// 文件中的价格覆盖到当前价格表上（未列出的模型保持默认）
+    async fn load_pricing(&mut self) -> Result<()> {
+        let path = self.pricing_path();
+        if !path.exists() {
+            debug!("No custom pricing file found");
+            return Ok(());
+        }
+
+        let json = fs::read_to_string(path).await?;
+        let custom: PricingTable = serde_json::from_str(&json)?;
+        let mut table = PricingTable::current();
+        for entry in &custom.models {
+            table.set_rate(entry.provider, &entry.model, entry.rate);
+        }
+        table.install();
+        info!("📂 Loaded {} custom model prices", custom.models.len());
+        Ok(())
+    }
+
+    async fn store_secret(&self, config: &ApiKeyConfig) -> Result<()> {
+        if let Some((store, namespace)) = &self.secret_store {
+            store.set(SECRET_SERVICE, &secret_account(namespace, config.provider), &config.api_key).await?;
+            debug!("🔑 Stored {} API key in {}", config.provider.name(), store.name());
+        }
+        Ok(())
+    }
+
     async fn save_api_keys(&self) -> Result<()> {
         let path = self.api_keys_path();
-        let json = serde_json::to_string_pretty(&self.api_keys)?;
+        let json = if self.secret_store.is_some() {
+            // 密钥已在钥匙串中，文件只保留元数据
+            let mut configs = self.api_keys.clone();
+            for config in configs.values_mut() {
+                config.api_key.clear();
+            }
+            serde_json::to_string_pretty(&configs)?
+        } else {
+            serde_json::to_string_pretty(&self.api_keys)?
+        };
         fs::write(path, json).await?;
         debug!("💾 Saved API keys");
         Ok(())
@@ -488,6 +1078,30 @@ impl ApiManager {
         let json = fs::read_to_string(path).await?;
         self.api_keys = serde_json::from_str(&json)?;
         info!("📂 Loaded {} API keys", self.api_keys.len());
+
+        let Some((store, namespace)) = self.secret_store.clone() else {
+            return Ok(());
+        };
+        let mut migrated = false;
+        for config in self.api_keys.values_mut() {
+            if config.api_key.is_empty() {
+                config.api_key = store
+                    .get(SECRET_SERVICE, &secret_account(&namespace, config.provider))
+                    .await?
+                    .unwrap_or_default();
+                if config.api_key.is_empty() {
+                    warn!("⚠️  API key for {} missing from {}", config.provider.name(), store.name());
+                }
+            } else {
+                // 旧版本写入文件的明文密钥迁移到钥匙串
+                store.set(SECRET_SERVICE, &secret_account(&namespace, config.provider), &config.api_key).await?;
+                migrated = true;
+            }
+        }
+        if migrated {
+            self.save_api_keys().await?;
+            info!("🔑 Moved plaintext API keys into {}", store.name());
+        }
         Ok(())
     }
 
@@ -517,10 +1131,16 @@ impl ApiManager {
         info!("💾 Saving all API Manager data...");
         self.save_api_keys().await?;
         self.save_call_history().await?;
+        self.save_key_usage().await?;
         Ok(())
     }
 }
 
+/// API 密钥在钥匙串中的账号名
+fn secret_account(namespace: &str, provider: ApiProvider) -> String {
+    format!("{}/api_key.{}", namespace, provider.name())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -548,6 +1168,32 @@ mod tests {
         assert!(manager.get_api_key(ApiProvider::OpenAI).is_none());
     }
 
+    #[tokio::test]
+    async fn test_api_keys_kept_in_secret_store() {
+        use crate::core::secret_store::MemorySecretStore;
+
+        let dir = tempdir().unwrap();
+        // 旧版本留下的明文密钥文件
+        let mut legacy = ApiManager::new(dir.path().to_path_buf());
+        legacy.init().await.unwrap();
+        legacy.add_api_key(ApiKeyConfig::new(ApiProvider::Claude, "sk-ant-legacy".to_string())).await.unwrap();
+
+        let store = Arc::new(MemorySecretStore::new());
+        let mut manager = ApiManager::new(dir.path().to_path_buf()).with_secret_store(store.clone(), "team");
+        manager.init().await.unwrap();
+        manager.add_api_key(ApiKeyConfig::new(ApiProvider::OpenAI, "sk-openai".to_string())).await.unwrap();
+
+        let on_disk = std::fs::read_to_string(dir.path().join("api_keys.json")).unwrap();
+        assert!(!on_disk.contains("sk-"));
+        assert_eq!(store.get(SECRET_SERVICE, "team/api_key.Claude").await.unwrap().as_deref(), Some("sk-ant-legacy"));
+
+        let mut reloaded = ApiManager::new(dir.path().to_path_buf()).with_secret_store(store.clone(), "team");
+        reloaded.init().await.unwrap();
+        assert_eq!(reloaded.get_api_key(ApiProvider::OpenAI).unwrap().api_key, "sk-openai");
+        reloaded.remove_api_key(ApiProvider::OpenAI).await.unwrap();
+        assert_eq!(store.get(SECRET_SERVICE, "team/api_key.OpenAI").await.unwrap(), None);
+    }
+
     #[tokio::test]
     async fn test_record_call() {
         let dir = tempdir().unwrap();
@@ -585,6 +1231,121 @@ mod tests {
         assert_eq!(stats.success_rate(), 95.0);
     }
 
+    #[test]
+    fn test_pricing_table_lookup_and_cost() {
+        let mut table = PricingTable::default();
+        // 带日期后缀的模型名按最长前缀匹配
+        assert_eq!(
+            table.rate(ApiProvider::Claude, "claude-3-opus-20240229"),
+            Some(TokenRate::new(0.015, 0.075))
+        );
+        assert_eq!(table.rate(ApiProvider::OpenAI, "gpt-4o-mini-2024-07-18"), Some(TokenRate::new(0.00015, 0.0006)));
+        assert!(table.rate(ApiProvider::Gemini, "gpt-4").is_none());
+
+        let cost = table.estimate_cost(ApiProvider::Claude, "claude-3-opus-20240229", 2000, 1000);
+        assert!((cost - (0.03 + 0.075)).abs() < 1e-12);
+        // 未知模型按 Provider 默认模型计价
+        assert_eq!(
+            table.estimate_cost(ApiProvider::OpenAI, "gpt-next", 1000, 0),
+            table.estimate_cost(ApiProvider::OpenAI, "gpt-4", 1000, 0)
+        );
+
+        table.set_rate(ApiProvider::OpenAI, "gpt-4", TokenRate::new(0.02, 0.04));
+        assert_eq!(table.rate(ApiProvider::OpenAI, "gpt-4-0613"), Some(TokenRate::new(0.02, 0.04)));
+        let json = serde_json::to_string(&table).unwrap();
+        assert!(json.contains("\"input_per_1k\":0.02"));
+        assert_eq!(serde_json::from_str::<PricingTable>(&json).unwrap(), table);
+    }
+
+    #[tokio::test]
+    async fn test_update_pricing_persists() {
+        let dir = tempdir().unwrap();
+        let mut manager = ApiManager::new(dir.path().to_path_buf());
+        manager.init().await.unwrap();
+
+        // 使用不在默认表中的模型名，避免影响其它测试的计价
+        let rate = TokenRate::new(0.5, 1.0);
+        manager.update_pricing(ApiProvider::DeepSeek, "deepseek-test-pricing", rate).await.unwrap();
+        assert!((manager.estimate_cost(ApiProvider::DeepSeek, "deepseek-test-pricing", 1000, 1000) - 1.5).abs() < 1e-12);
+
+        let saved: PricingTable =
+            serde_json::from_str(&std::fs::read_to_string(dir.path().join("pricing.json")).unwrap()).unwrap();
+        assert_eq!(saved.rate(ApiProvider::DeepSeek, "deepseek-test-pricing"), Some(rate));
+    }
+
+    #[tokio::test]
+    async fn test_budget_alerts_and_hard_block() {
+        use crate::core::event_bus::EventBusConfig;
+
+        let dir = tempdir().unwrap();
+        let bus = Arc::new(EventBus::new(EventBusConfig::default()));
+        let mut manager = ApiManager::new(dir.path().to_path_buf()).with_event_bus(bus.clone());
+        manager.init().await.unwrap();
+        manager
+            .set_budget(ApiProvider::Claude, BudgetPolicy::new(BudgetPeriod::Daily, 0.05).with_hard_limit(0.10))
+            .await
+            .unwrap();
+
+        let call = |cost| ApiCallRecord::new_success(ApiProvider::Claude, 100, cost, 10, None);
+        manager.record_call(call(0.04)).await.unwrap();
+        assert!(bus.get_history(None).await.is_empty());
+
+        manager.record_call(call(0.03)).await.unwrap();
+        manager.record_call(call(0.01)).await.unwrap();
+        let status = manager.budget_status(ApiProvider::Claude).unwrap();
+        assert_eq!(status.state, BudgetState::SoftExceeded);
+        assert!(manager.check_budget(ApiProvider::Claude).is_ok());
+        // 同一状态只告警一次
+        assert_eq!(bus.get_history(None).await.len(), 1);
+
+        manager.record_call(call(0.05)).await.unwrap();
+        let alerts = bus.get_history(None).await;
+        assert_eq!(alerts.len(), 2);
+        assert_eq!(alerts[0].event_type, EventType::System(BUDGET_ALERT_EVENT.to_string()));
+        assert_eq!(alerts[0].data["state"], "hard_exceeded");
+
+        let err = manager.check_budget(ApiProvider::Claude).unwrap_err();
+        assert_eq!(err.downcast_ref::<AcsaError>().unwrap().code(), Some(ErrorCode::BudgetExceeded));
+        assert!(manager.check_budget(ApiProvider::OpenAI).is_ok());
+
+        let report = manager.get_budget_status();
+        assert_eq!(report.len(), 1);
+        assert!(report[0].blocked);
+        assert_eq!(report[0].remaining, 0.0);
+
+        // 预算策略持久化
+        let mut reopened = ApiManager::new(dir.path().to_path_buf());
+        reopened.init().await.unwrap();
+        assert_eq!(reopened.budget_status(ApiProvider::Claude).unwrap().policy.hard_limit, Some(0.10));
+    }
+
+    #[tokio::test]
+    async fn test_usage_attributed_to_api_key() {
+        let dir = tempdir().unwrap();
+        let mut manager = ApiManager::new(dir.path().to_path_buf());
+        manager.init().await.unwrap();
+
+        manager
+            .record_call(ApiCallRecord::new_success(ApiProvider::Claude, 500, 0.02, 100, None).with_api_key("k1"))
+            .await
+            .unwrap();
+        manager
+            .record_call(ApiCallRecord::new_success(ApiProvider::Claude, 500, 0.02, 100, None))
+            .await
+            .unwrap();
+        manager.record_key_usage("k1", 300, 0.01).await.unwrap();
+
+        let usage = manager.key_usage("k1").unwrap();
+        assert_eq!(usage.requests, 2);
+        assert_eq!(usage.total_tokens, 800);
+        assert!((usage.total_cost - 0.03).abs() < 1e-9);
+        assert_eq!(manager.get_all_key_usage().len(), 1);
+
+        let mut reopened = ApiManager::new(dir.path().to_path_buf());
+        reopened.init().await.unwrap();
+        assert_eq!(reopened.key_usage("k1").unwrap().total_tokens, 800);
+    }
+
     #[test]
     fn test_export_report() {
         let dir = tempdir().unwrap();
@@ -0,0 +1,265 @@
+// Approval - 高风险执行的人工审批闸门
+// Ultron 风险分持续高于阈值时，链路不再直接失败：执行在检查点处暂停，生成待审批记录，
+// 由人工批准（继续执行 Omega）或驳回（中止），审批过程写入审计日志
+//
+// 核心功能：
+// 1. 待审批记录：执行 ID、原始输入、待执行方案、风险分与阈值、Ultron 缓解建议
+// 2. 文件存储（JSON），CLI `approvals` 与服务进程共用；每次操作都以文件为准
+// 3. 审批只能决定一次（批准 / 驳回），记录审批人、时间与备注
+// 4. 审批记录转为审计事件
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use super::audit_log::{AuditEvent, AuditEventType, AuditSeverity};
+
+/// 默认审批记录文件
+pub const DEFAULT_APPROVAL_PATH: &str = "./data/approvals.json";
+
+/// 审批状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+impl ApprovalStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ApprovalStatus::Pending => "pending",
+            ApprovalStatus::Approved => "approved",
+            ApprovalStatus::Rejected => "rejected",
+        }
+    }
+}
+
+impl std::str::FromStr for ApprovalStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "pending" => Ok(ApprovalStatus::Pending),
+            "approved" => Ok(ApprovalStatus::Approved),
+            "rejected" => Ok(ApprovalStatus::Rejected),
+            other => Err(anyhow!("Unknown approval status '{}' (expected pending, approved or rejected)", other)),
+        }
+    }
+}
+
+/// 一条审批记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalRequest {
+    pub id: String,
+    /// 暂停的执行（检查点以此为键）
+    pub execution_id: String,
+    /// 用户原始输入
+    pub user_input: String,
+    /// 批准后交给 Omega 执行的方案
+    pub plan: String,
+    /// 最后一轮 Ultron 风险分与当时的阈值
+    pub risk_score: u8,
+    pub risk_threshold: u8,
+    /// Ultron 的缓解建议
+    pub mitigation: String,
+    /// 暂停前已进行的审计轮数
+    pub iterations: u32,
+    pub status: ApprovalStatus,
+    pub requested_at: DateTime<Utc>,
+    pub decided_at: Option<DateTime<Utc>>,
+    pub decided_by: Option<String>,
+    pub comment: Option<String>,
+}
+
+impl ApprovalRequest {
+    pub fn new(
+        execution_id: impl Into<String>,
+        user_input: impl Into<String>,
+        plan: impl Into<String>,
+        risk_score: u8,
+        risk_threshold: u8,
+    ) -> Self {
+        Self {
+            id: String::new(),
+            execution_id: execution_id.into(),
+            user_input: user_input.into(),
+            plan: plan.into(),
+            risk_score,
+            risk_threshold,
+            mitigation: String::new(),
+            iterations: 0,
+            status: ApprovalStatus::Pending,
+            requested_at: Utc::now(),
+            decided_at: None,
+            decided_by: None,
+            comment: None,
+        }
+    }
+
+    pub fn with_mitigation(mut self, mitigation: impl Into<String>) -> Self {
+        self.mitigation = mitigation.into();
+        self
+    }
+
+    pub fn with_iterations(mut self, iterations: u32) -> Self {
+        self.iterations = iterations;
+        self
+    }
+
+    /// 转为审计事件：请求时操作者为系统，决定后为审批人
+    pub fn to_audit_event(&self) -> AuditEvent {
+        let (action, severity, actor) = match self.status {
+            ApprovalStatus::Pending => ("approval_requested", AuditSeverity::Warning, "acsa_router"),
+            ApprovalStatus::Approved => ("approval_granted", AuditSeverity::Warning, self.actor()),
+            ApprovalStatus::Rejected => ("approval_rejected", AuditSeverity::Info, self.actor()),
+        };
+        let mut metadata = HashMap::new();
+        metadata.insert("execution_id".to_string(), self.execution_id.clone());
+        metadata.insert("risk_score".to_string(), self.risk_score.to_string());
+        metadata.insert("risk_threshold".to_string(), self.risk_threshold.to_string());
+        if let Some(comment) = &self.comment {
+            metadata.insert("comment".to_string(), comment.clone());
+        }
+        let timestamp = self.decided_at.unwrap_or(self.requested_at);
+
+        AuditEvent {
+            event_id: format!("{}_{}", self.id, self.status.label()),
+            event_type: AuditEventType::SecurityEvent,
+            severity,
+            actor_id: actor.to_string(),
+            actor_ip: None,
+            resource_id: Some(self.id.clone()),
+            resource_type: Some("approval".to_string()),
+            action: action.to_string(),
+            success: true,
+            error_message: None,
+            metadata,
+            timestamp,
+            signature: None,
+        }
+    }
+
+    fn actor(&self) -> &str {
+        self.decided_by.as_deref().unwrap_or("unknown")
+    }
+}
+
+/// 审批记录存储
+pub struct ApprovalStore {
+    path: PathBuf,
+    /// 串行化本进程内的读-改-写
+    lock: Mutex<()>,
+}
+
+impl ApprovalStore {
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let store = Self { path, lock: Mutex::new(()) };
+        store.load()?;
+        Ok(store)
+    }
+
+    /// 新建待审批记录，返回带 ID 的记录
+    pub fn create(&self, mut request: ApprovalRequest) -> Result<ApprovalRequest> {
+        let _guard = self.lock.lock().unwrap();
+        let mut requests = self.load()?;
+        let base = format!("apr_{}", request.requested_at.timestamp_millis());
+        let mut id = base.clone();
+        let mut suffix = 1;
+        while requests.iter().any(|existing| existing.id == id) {
+            id = format!("{}_{}", base, suffix);
+            suffix += 1;
+        }
+        request.id = id;
+        request.status = ApprovalStatus::Pending;
+        requests.push(request.clone());
+        self.save(&requests)?;
+        Ok(request)
+    }
+
+    /// 全部记录（最早的在前），可按状态过滤
+    pub fn list(&self, status: Option<ApprovalStatus>) -> Result<Vec<ApprovalRequest>> {
+        let _guard = self.lock.lock().unwrap();
+        let mut requests = self.load()?;
+        requests.retain(|request| status.is_none_or(|status| request.status == status));
+        Ok(requests)
+    }
+
+    pub fn get(&self, id: &str) -> Result<Option<ApprovalRequest>> {
+        let _guard = self.lock.lock().unwrap();
+        Ok(self.load()?.into_iter().find(|request| request.id == id))
+    }
+
+    /// 记录审批决定；未知 ID 或已决定过的记录返回错误
+    pub fn decide(&self, id: &str, approve: bool, actor: &str, comment: Option<String>) -> Result<ApprovalRequest> {
+        let _guard = self.lock.lock().unwrap();
+        let mut requests = self.load()?;
+        let request = requests
+            .iter_mut()
+            .find(|request| request.id == id)
+            .ok_or_else(|| anyhow!("Unknown approval: {}", id))?;
+        if request.status != ApprovalStatus::Pending {
+            return Err(anyhow!("Approval {} was already {}", id, request.status.label()));
+        }
+        request.status = if approve { ApprovalStatus::Approved } else { ApprovalStatus::Rejected };
+        request.decided_at = Some(Utc::now());
+        request.decided_by = Some(actor.to_string());
+        request.comment = comment;
+        let decided = request.clone();
+        self.save(&requests)?;
+        Ok(decided)
+    }
+
+    fn load(&self) -> Result<Vec<ApprovalRequest>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        serde_json::from_str(&std::fs::read_to_string(&self.path)?)
+            .with_context(|| format!("Corrupted approval file {}", self.path.display()))
+    }
+
+    fn save(&self, requests: &[ApprovalRequest]) -> Result<()> {
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(requests)?)?;
+        std::fs::rename(&tmp, &self.path).with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decide_once_and_shared_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("approvals.json");
+        let store = ApprovalStore::open(&path).unwrap();
+        let first = store.create(ApprovalRequest::new("exec_1", "input", "plan", 85, 70)).unwrap();
+        let second = store.create(ApprovalRequest::new("exec_2", "input", "plan", 90, 70)).unwrap();
+        assert_ne!(first.id, second.id);
+
+        // 另一个进程（CLI）打开同一文件做出决定
+        let cli = ApprovalStore::open(&path).unwrap();
+        let decided = cli.decide(&first.id, false, "alice", Some("too risky".to_string())).unwrap();
+        assert_eq!(decided.status, ApprovalStatus::Rejected);
+        assert!(cli.decide(&first.id, true, "bob", None).is_err());
+        assert!(cli.decide("apr_missing", true, "bob", None).is_err());
+
+        assert_eq!(store.get(&first.id).unwrap().unwrap().decided_by.as_deref(), Some("alice"));
+        let pending = store.list(Some(ApprovalStatus::Pending)).unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].execution_id, "exec_2");
+
+        let event = decided.to_audit_event();
+        assert_eq!(event.action, "approval_rejected");
+        assert_eq!(event.actor_id, "alice");
+    }
+}
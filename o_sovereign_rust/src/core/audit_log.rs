@@ -7,15 +7,17 @@
 // 3. 合规性报告生成
 // 4. 日志加密和签名
 // 5. 时间线分析
+// 6. SQLite 持久化（经 DatabaseManager）：按时间 / 严重性 / 操作者 / 事件类型索引，支持分页与全文检索
 
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use tracing::info;
+use tokio::sync::{OnceCell, RwLock};
+use tracing::{info, warn};
 
+use super::database::{DatabaseManager, QueryBuilder};
 use super::sosa_crypto::SosaCryptoEngine;
 
 /// 审计事件类型
@@ -39,8 +41,8 @@ pub enum AuditEventType {
     SecurityEvent,
 }
 
-/// 审计严重性
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// 审计严重性（按声明顺序由低到高）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum AuditSeverity {
     /// 信息
     Info,
@@ -83,6 +85,9 @@ pub struct AuditEvent {
     pub signature: Option<String>,
 }
 
+/// 事件元数据中记录所属工作区的键
+pub const WORKSPACE_METADATA_KEY: &str = "workspace_id";
+
 /// 审计查询
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuditQuery {
@@ -92,13 +97,24 @@ pub struct AuditQuery {
     pub actor_id: Option<String>,
     /// 资源类型过滤
     pub resource_type: Option<String>,
+    /// 工作区过滤（按事件元数据中的 `workspace_id`）
+    pub workspace_id: Option<String>,
     /// 时间范围（开始）
     pub start_time: Option<DateTime<Utc>>,
     /// 时间范围（结束）
     pub end_time: Option<DateTime<Utc>>,
+    /// 最低严重性
+    #[serde(default)]
+    pub min_severity: Option<AuditSeverity>,
+    /// 全文检索（操作、操作者、资源、错误信息与元数据；多个词须同时出现）
+    #[serde(default)]
+    pub search: Option<String>,
     /// 只看失败的操作
     pub only_failures: bool,
-    /// 限制结果数
+    /// 分页偏移（按时间倒序跳过的条数）
+    #[serde(default)]
+    pub offset: usize,
+    /// 限制结果数（每页条数）
     pub limit: Option<usize>,
 }
 
@@ -108,9 +124,13 @@ impl Default for AuditQuery {
             event_types: None,
             actor_id: None,
             resource_type: None,
+            workspace_id: None,
             start_time: None,
             end_time: None,
+            min_severity: None,
+            search: None,
             only_failures: false,
+            offset: 0,
             limit: Some(100),
         }
     }
@@ -171,6 +191,10 @@ pub struct AuditLogger {
     crypto: Option<Arc<SosaCryptoEngine>>,
     /// 事件存储（内存缓存）
     events: Arc<RwLock<Vec<AuditEvent>>>,
+    /// 已持久化的事件数（events[..flushed] 已落盘）
+    flushed: Arc<RwLock<usize>>,
+    /// SQLite 持久化后端（未配置时只保存在内存）
+    store: Option<SqliteAuditStore>,
 }
 
 impl AuditLogger {
@@ -185,9 +209,17 @@ impl AuditLogger {
             config,
             crypto,
             events: Arc::new(RwLock::new(Vec::new())),
+            flushed: Arc::new(RwLock::new(0)),
+            store: None,
         }
     }
 
+    /// 持久化到 SQLite（数据库需已 connect；表结构在首次读写时创建），查询改为走数据库
+    pub fn with_database(mut self, database: Arc<DatabaseManager>) -> Self {
+        self.store = Some(SqliteAuditStore::new(database));
+        self
+    }
+
     /// 记录审计事件
     pub async fn log_event(&self, mut event: AuditEvent) -> Result<()> {
         // 生成签名（防篡改）
@@ -202,14 +234,49 @@ impl AuditLogger {
         // 实时持久化
         if self.config.realtime_persistence {
             self.persist_event(&event).await?;
+            *self.flushed.write().await = events.len();
         }
 
         Ok(())
     }
 
-    /// 查询审计日志
+    /// 持久化所有尚未落盘的事件，返回本次写出的数量（关停时调用）
+    pub async fn flush(&self) -> Result<usize> {
+        let events = self.events.read().await;
+        let mut flushed = self.flushed.write().await;
+
+        let pending = &events[*flushed..];
+        for event in pending {
+            self.persist_event(event).await?;
+        }
+
+        let count = pending.len();
+        *flushed = events.len();
+        Ok(count)
+    }
+
+    /// 查询审计日志（配置了数据库时查询失败会退回内存缓存）
     pub async fn query(&self, query: AuditQuery) -> Vec<AuditEvent> {
+        if self.store.is_some() {
+            match self.try_query(&query).await {
+                Ok(events) => return events,
+                Err(e) => warn!("⚠️  Audit database query failed, falling back to memory: {}", e),
+            }
+        }
+        self.query_memory(&query).await
+    }
+
+    /// 查询审计日志，数据库错误直接返回
+    pub async fn try_query(&self, query: &AuditQuery) -> Result<Vec<AuditEvent>> {
+        match &self.store {
+            Some(store) => store.query(query).await,
+            None => Ok(self.query_memory(query).await),
+        }
+    }
+
+    async fn query_memory(&self, query: &AuditQuery) -> Vec<AuditEvent> {
         let events = self.events.read().await;
+        let terms = query.search.as_deref().map(search_terms).unwrap_or_default();
 
         let mut filtered: Vec<AuditEvent> = events
             .iter()
@@ -235,6 +302,13 @@ impl AuditLogger {
                     }
                 }
 
+                // 工作区过滤
+                if let Some(ref workspace) = query.workspace_id {
+                    if e.metadata.get(WORKSPACE_METADATA_KEY) != Some(workspace) {
+                        return false;
+                    }
+                }
+
                 // 时间范围过滤
                 if let Some(start) = query.start_time {
                     if e.timestamp < start {
@@ -247,6 +321,21 @@ impl AuditLogger {
                     }
                 }
 
+                // 严重性过滤
+                if let Some(min) = query.min_severity {
+                    if e.severity < min {
+                        return false;
+                    }
+                }
+
+                // 全文检索
+                if !terms.is_empty() {
+                    let text = search_text(e).to_lowercase();
+                    if !terms.iter().all(|t| text.contains(&t.to_lowercase())) {
+                        return false;
+                    }
+                }
+
                 // 只看失败的操作
                 if query.only_failures && e.success {
                     return false;
@@ -260,7 +349,8 @@ impl AuditLogger {
         // 按时间排序
         filtered.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
 
-        // 限制结果数
+        // 分页
+        filtered.drain(..query.offset.min(filtered.len()));
         if let Some(limit) = query.limit {
             filtered.truncate(limit);
         }
@@ -357,11 +447,186 @@ impl AuditLogger {
         }
     }
 
-    async fn persist_event(&self, _event: &AuditEvent) -> Result<()> {
-        // TODO: 写入数据库
-        // INSERT INTO audit_logs
+    async fn persist_event(&self, event: &AuditEvent) -> Result<()> {
+        match &self.store {
+            Some(store) => store.insert(event).await,
+            None => Ok(()),
+        }
+    }
+}
+
+/// 审计表结构：索引列 + 完整事件 JSON；FTS5 外部内容表索引 search_text
+const AUDIT_SCHEMA: [&str; 7] = [
+    "CREATE TABLE IF NOT EXISTS audit_logs (
+        seq INTEGER PRIMARY KEY AUTOINCREMENT,
+        event_id TEXT NOT NULL,
+        timestamp_ms INTEGER NOT NULL,
+        severity INTEGER NOT NULL,
+        actor_id TEXT NOT NULL,
+        event_type TEXT NOT NULL,
+        resource_type TEXT,
+        workspace_id TEXT,
+        success INTEGER NOT NULL,
+        search_text TEXT NOT NULL,
+        payload TEXT NOT NULL
+    )",
+    "CREATE INDEX IF NOT EXISTS idx_audit_logs_timestamp ON audit_logs (timestamp_ms)",
+    "CREATE INDEX IF NOT EXISTS idx_audit_logs_severity ON audit_logs (severity, timestamp_ms)",
+    "CREATE INDEX IF NOT EXISTS idx_audit_logs_actor ON audit_logs (actor_id, timestamp_ms)",
+    "CREATE INDEX IF NOT EXISTS idx_audit_logs_event_type ON audit_logs (event_type, timestamp_ms)",
+    "CREATE VIRTUAL TABLE IF NOT EXISTS audit_logs_fts
+        USING fts5(search_text, content='audit_logs', content_rowid='seq')",
+    "CREATE TRIGGER IF NOT EXISTS audit_logs_fts_insert AFTER INSERT ON audit_logs BEGIN
+        INSERT INTO audit_logs_fts (rowid, search_text) VALUES (new.seq, new.search_text);
+    END",
+];
+
+/// SQLite 审计存储
+struct SqliteAuditStore {
+    database: Arc<DatabaseManager>,
+    schema: OnceCell<()>,
+}
+
+impl SqliteAuditStore {
+    fn new(database: Arc<DatabaseManager>) -> Self {
+        Self { database, schema: OnceCell::new() }
+    }
+
+    async fn ensure_schema(&self) -> Result<()> {
+        self.schema
+            .get_or_try_init(|| async {
+                for statement in AUDIT_SCHEMA {
+                    self.database.execute(statement, vec![]).await?;
+                }
+                info!("📋 Audit log table ready");
+                Ok::<_, anyhow::Error>(())
+            })
+            .await?;
         Ok(())
     }
+
+    async fn insert(&self, event: &AuditEvent) -> Result<()> {
+        self.ensure_schema().await?;
+        self.database
+            .execute(
+                "INSERT INTO audit_logs (event_id, timestamp_ms, severity, actor_id, event_type, \
+                 resource_type, workspace_id, success, search_text, payload) \
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                vec![
+                    serde_json::json!(event.event_id),
+                    serde_json::json!(event.timestamp.timestamp_millis()),
+                    serde_json::json!(event.severity as i64),
+                    serde_json::json!(event.actor_id),
+                    serde_json::to_value(event.event_type)?,
+                    serde_json::json!(event.resource_type),
+                    serde_json::json!(event.metadata.get(WORKSPACE_METADATA_KEY)),
+                    serde_json::json!(event.success),
+                    serde_json::json!(search_text(event)),
+                    serde_json::json!(serde_json::to_string(event)?),
+                ],
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn query(&self, query: &AuditQuery) -> Result<Vec<AuditEvent>> {
+        self.ensure_schema().await?;
+
+        let mut builder = QueryBuilder::new("audit_logs").select(vec!["payload"]);
+        let mut params = Vec::new();
+        if let Some(types) = &query.event_types {
+            if types.is_empty() {
+                return Ok(Vec::new());
+            }
+            let placeholders = vec!["?"; types.len()].join(", ");
+            builder = builder.where_clause(&format!("event_type IN ({})", placeholders));
+            for event_type in types {
+                params.push(serde_json::to_value(event_type)?);
+            }
+        }
+        if let Some(actor) = &query.actor_id {
+            builder = builder.where_clause("actor_id = ?");
+            params.push(serde_json::json!(actor));
+        }
+        if let Some(resource_type) = &query.resource_type {
+            builder = builder.where_clause("resource_type = ?");
+            params.push(serde_json::json!(resource_type));
+        }
+        if let Some(workspace) = &query.workspace_id {
+            builder = builder.where_clause("workspace_id = ?");
+            params.push(serde_json::json!(workspace));
+        }
+        if let Some(start) = query.start_time {
+            builder = builder.where_clause("timestamp_ms >= ?");
+            params.push(serde_json::json!(start.timestamp_millis()));
+        }
+        if let Some(end) = query.end_time {
+            builder = builder.where_clause("timestamp_ms <= ?");
+            params.push(serde_json::json!(end.timestamp_millis()));
+        }
+        if let Some(min) = query.min_severity {
+            builder = builder.where_clause("severity >= ?");
+            params.push(serde_json::json!(min as i64));
+        }
+        if let Some(fts) = query.search.as_deref().and_then(fts_query) {
+            builder = builder
+                .where_clause("seq IN (SELECT rowid FROM audit_logs_fts WHERE audit_logs_fts MATCH ?)");
+            params.push(serde_json::json!(fts));
+        }
+        if query.only_failures {
+            builder = builder.where_clause("success = 0");
+        }
+
+        builder = builder.order_by("timestamp_ms", true).order_by("seq", true);
+        // SQLite 的 OFFSET 必须跟在 LIMIT 之后，-1 表示不限
+        match query.limit {
+            Some(limit) => builder = builder.limit(limit as u64),
+            None if query.offset > 0 => builder = builder.limit(i64::MAX as u64),
+            None => {}
+        }
+        if query.offset > 0 {
+            builder = builder.offset(query.offset as u64);
+        }
+
+        self.database
+            .query(&builder.build(), params)
+            .await?
+            .into_iter()
+            .map(|row| {
+                let payload = row.get("payload").and_then(|v| v.as_str()).unwrap_or_default();
+                Ok(serde_json::from_str(payload)?)
+            })
+            .collect()
+    }
+}
+
+/// 全文检索覆盖的文本
+fn search_text(event: &AuditEvent) -> String {
+    let mut parts = vec![event.action.as_str(), event.actor_id.as_str()];
+    parts.extend(event.resource_id.as_deref());
+    parts.extend(event.resource_type.as_deref());
+    parts.extend(event.error_message.as_deref());
+    parts.extend(event.actor_ip.as_deref());
+    let mut metadata: Vec<_> = event.metadata.values().map(String::as_str).collect();
+    metadata.sort_unstable();
+    parts.extend(metadata);
+    parts.join(" ")
+}
+
+fn search_terms(search: &str) -> Vec<&str> {
+    search.split_whitespace().collect()
+}
+
+/// 检索词 → FTS5 查询：每个词作为短语引用（避免 `-` `:` 等被当作语法），词之间为 AND
+fn fts_query(search: &str) -> Option<String> {
+    let terms = search_terms(search);
+    (!terms.is_empty()).then(|| {
+        terms
+            .iter()
+            .map(|t| format!("\"{}\"", t.replace('"', "\"\"")))
+            .collect::<Vec<_>>()
+            .join(" ")
+    })
 }
 
 /// 辅助函数：记录用户登录
@@ -468,4 +733,61 @@ mod tests {
 
         assert_eq!(report.total_events, 2);
     }
+
+    #[tokio::test]
+    async fn test_sqlite_store_filters_pages_and_searches() {
+        use crate::core::database::DatabaseConfig;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let database = Arc::new(DatabaseManager::new(DatabaseConfig {
+            url: format!("sqlite://{}", dir.path().join("audit.db").display()),
+            ..Default::default()
+        }));
+        database.connect().await.unwrap();
+
+        let logger = AuditLogger::new(AuditLogConfig::default(), None).with_database(database.clone());
+        let base = Utc::now() - chrono::Duration::minutes(10);
+        for i in 0..5 {
+            let mut event = AuditEvent {
+                event_id: format!("evt_{}", i),
+                event_type: if i % 2 == 0 { AuditEventType::DataAccess } else { AuditEventType::SecurityEvent },
+                severity: if i == 4 { AuditSeverity::Critical } else { AuditSeverity::Info },
+                actor_id: "moss".to_string(),
+                actor_ip: None,
+                resource_id: Some(format!("doc-{}", i)),
+                resource_type: Some("document".to_string()),
+                action: "read".to_string(),
+                success: i != 3,
+                error_message: (i == 3).then(|| "permission denied for rm -rf".to_string()),
+                metadata: HashMap::new(),
+                timestamp: base + chrono::Duration::minutes(i),
+                signature: None,
+            };
+            event.metadata.insert("note".to_string(), format!("batch {}", i));
+            logger.log_event(event).await.unwrap();
+        }
+
+        // 新实例从数据库读取，不依赖内存缓存
+        let reopened = AuditLogger::new(AuditLogConfig::default(), None).with_database(database);
+        let ids = |events: Vec<AuditEvent>| events.into_iter().map(|e| e.event_id).collect::<Vec<_>>();
+
+        let page = reopened.try_query(&AuditQuery { offset: 1, limit: Some(2), ..Default::default() }).await.unwrap();
+        assert_eq!(ids(page), vec!["evt_3", "evt_2"]);
+
+        let ranged = AuditQuery {
+            start_time: Some(base + chrono::Duration::minutes(1)),
+            end_time: Some(base + chrono::Duration::minutes(3)),
+            event_types: Some(vec![AuditEventType::SecurityEvent]),
+            ..Default::default()
+        };
+        assert_eq!(ids(reopened.try_query(&ranged).await.unwrap()), vec!["evt_3", "evt_1"]);
+
+        let search = AuditQuery { search: Some("denied rm -rf".to_string()), ..Default::default() };
+        assert_eq!(ids(reopened.try_query(&search).await.unwrap()), vec!["evt_3"]);
+        // 内存实现与数据库一致
+        assert_eq!(ids(logger.query_memory(&search).await), vec!["evt_3"]);
+
+        let critical = AuditQuery { min_severity: Some(AuditSeverity::Warning), ..Default::default() };
+        assert_eq!(ids(reopened.try_query(&critical).await.unwrap()), vec!["evt_4"]);
+    }
 }
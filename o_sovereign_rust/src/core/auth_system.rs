@@ -6,9 +6,16 @@
 // 2. Token刷新机制
 // 3. 会话管理
 // 4. Token撤销
+// 5. JWT 签名密钥可保存在系统钥匙串（首次使用时生成），AuthConfig::load 按 ACSA_SECRET_STORE / ACSA_JWT_SECRET 加载；
+//    生产环境未配置任何密钥时拒绝启动
+// 6. RBAC：admin / operator / viewer 三种角色，各自对应一组 Permission
+// 7. 用户管理：PBKDF2 口令哈希、登录签发 Token、角色调整（至少保留一名 admin）
 
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::fmt;
+use std::num::NonZeroU32;
+use std::str::FromStr;
+use std::sync::{Arc, LazyLock};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use anyhow::{anyhow, Result};
 use chrono::{DateTime, Utc};
@@ -16,6 +23,115 @@ use serde::{Deserialize, Serialize};
 use tokio::sync::RwLock;
 use tracing::{debug, info, warn};
 
+use super::config_manager::Environment;
+use super::error::{AcsaError, ErrorCode};
+use super::secret_store::{configured_secret_store, SecretStore, SECRET_SERVICE};
+
+/// JWT 签名密钥在钥匙串中的账号名
+pub const JWT_SECRET_ACCOUNT: &str = "jwt_secret";
+
+/// 不使用钥匙串时提供 JWT 签名密钥的环境变量
+pub const JWT_SECRET_ENV: &str = "ACSA_JWT_SECRET";
+
+/// 口令哈希的 PBKDF2 迭代次数
+const PASSWORD_ITERATIONS: u32 = 100_000;
+
+/// 权限
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    /// 查看执行记录、Agent、协议与工作区概要
+    Read,
+    /// 发起执行（聊天、OpenAI 兼容接口）
+    Execute,
+    /// 为执行记录增删标签
+    TagExecutions,
+    /// 注册 / 移除自定义 Agent
+    ManageAgents,
+    /// 切换默认协议
+    ManageProtocols,
+    /// 设置 Provider 预算
+    ManageBudgets,
+    /// 拉闸 / 合闸全局熔断开关
+    ManageKillSwitch,
+    /// 创建 / 列出工作区
+    ManageWorkspaces,
+    /// 管理用户与角色
+    ManageUsers,
+    /// 批准 / 驳回超过风险阈值的执行
+    ApproveExecutions,
+}
+
+/// 角色
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// 全部权限
+    Admin,
+    /// 日常使用：执行、打标签、管理自定义 Agent
+    Operator,
+    /// 只读
+    Viewer,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::Operator => "operator",
+            Role::Viewer => "viewer",
+        }
+    }
+
+    /// 角色拥有的权限
+    pub fn permissions(&self) -> &'static [Permission] {
+        match self {
+            Role::Admin => &[
+                Permission::Read,
+                Permission::Execute,
+                Permission::TagExecutions,
+                Permission::ManageAgents,
+                Permission::ManageProtocols,
+                Permission::ManageBudgets,
+                Permission::ManageKillSwitch,
+                Permission::ManageWorkspaces,
+                Permission::ManageUsers,
+                Permission::ApproveExecutions,
+            ],
+            Role::Operator => &[
+                Permission::Read,
+                Permission::Execute,
+                Permission::TagExecutions,
+                Permission::ManageAgents,
+            ],
+            Role::Viewer => &[Permission::Read],
+        }
+    }
+
+    pub fn allows(&self, permission: Permission) -> bool {
+        self.permissions().contains(&permission)
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Role {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "admin" => Ok(Role::Admin),
+            "operator" => Ok(Role::Operator),
+            "viewer" => Ok(Role::Viewer),
+            other => Err(anyhow!("Unknown role '{}' (expected admin, operator or viewer)", other)),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String,
@@ -24,6 +140,40 @@ pub struct Claims {
     pub user_id: String,
     pub username: String,
     pub roles: Vec<String>,
+    /// 所属工作区（多租户部署；为空时进入默认工作区）
+    #[serde(default)]
+    pub workspace_id: Option<String>,
+    /// 以静态 API 密钥认证时的密钥 ID（用于限流与用量归属）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key_id: Option<String>,
+}
+
+impl Claims {
+    /// 可识别的角色（未知角色忽略）
+    pub fn parsed_roles(&self) -> Vec<Role> {
+        self.roles.iter().filter_map(|role| role.parse().ok()).collect()
+    }
+
+    pub fn has_role(&self, role: Role) -> bool {
+        self.parsed_roles().contains(&role)
+    }
+
+    /// 任一角色拥有该权限即可
+    pub fn can(&self, permission: Permission) -> bool {
+        self.parsed_roles().iter().any(|role| role.allows(permission))
+    }
+
+    /// 缺少权限时返回 E9010
+    pub fn require(&self, permission: Permission) -> Result<()> {
+        if self.can(permission) {
+            return Ok(());
+        }
+        Err(AcsaError::new(
+            ErrorCode::PermissionDenied,
+            format!("{} (roles: {}) lacks permission {:?}", self.username, self.roles.join(", "), permission),
+        )
+        .into())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,6 +184,10 @@ pub struct TokenPair {
     pub token_type: String,
 }
 
+/// 未配置签名密钥时使用的本进程随机密钥（重启后旧令牌全部失效）
+static PROCESS_JWT_SECRET: LazyLock<String> =
+    LazyLock::new(|| random_secret().expect("System random number generator unavailable"));
+
 #[derive(Debug, Clone)]
 pub struct AuthConfig {
     pub jwt_secret: String,
@@ -44,13 +198,85 @@ pub struct AuthConfig {
 impl Default for AuthConfig {
     fn default() -> Self {
         Self {
-            jwt_secret: "change-this-in-production".to_string(),
+            jwt_secret: PROCESS_JWT_SECRET.clone(),
             access_token_ttl: Duration::from_secs(3600),
             refresh_token_ttl: Duration::from_secs(86400 * 7),
         }
     }
 }
 
+impl AuthConfig {
+    /// 服务启动时加载：ACSA_SECRET_STORE=os 时使用系统钥匙串，否则读取 ACSA_JWT_SECRET，
+    /// 都未配置时使用本进程随机生成的密钥并告警（不存在可伪造令牌的公开默认密钥）；
+    /// 生产环境下都未配置时返回 E9002，避免重启后令牌全部失效
+    pub async fn load(environment: Environment) -> Result<Self> {
+        let store = configured_secret_store()?;
+        Self::resolve(environment, store.as_deref(), std::env::var(JWT_SECRET_ENV).ok()).await
+    }
+
+    async fn resolve(
+        environment: Environment,
+        store: Option<&dyn SecretStore>,
+        env_secret: Option<String>,
+    ) -> Result<Self> {
+        if let Some(store) = store {
+            return Self::from_secret_store(store).await;
+        }
+        match env_secret.map(|secret| secret.trim().to_string()).filter(|secret| !secret.is_empty()) {
+            Some(jwt_secret) => Ok(Self { jwt_secret, ..Self::default() }),
+            None if environment == Environment::Production => Err(AcsaError::new(
+                ErrorCode::ConfigError,
+                format!("No JWT secret configured in production (set {} or ACSA_SECRET_STORE=os)", JWT_SECRET_ENV),
+            )
+            .into()),
+            None => {
+                warn!(
+                    "⚠️  No JWT secret configured (set {} or ACSA_SECRET_STORE=os); using a random per-process secret, tokens will not survive a restart",
+                    JWT_SECRET_ENV
+                );
+                Ok(Self::default())
+            }
+        }
+    }
+
+    /// 从钥匙串读取 JWT 签名密钥，不存在时生成随机密钥并写入（密钥不落盘）
+    pub async fn from_secret_store(store: &dyn SecretStore) -> Result<Self> {
+        let jwt_secret = match store.get(SECRET_SERVICE, JWT_SECRET_ACCOUNT).await? {
+            Some(secret) => secret,
+            None => {
+                let secret = random_secret()?;
+                store.set(SECRET_SERVICE, JWT_SECRET_ACCOUNT, &secret).await?;
+                info!("🔑 Generated JWT signing secret in {}", store.name());
+                secret
+            }
+        };
+        Ok(Self {
+            jwt_secret,
+            ..Self::default()
+        })
+    }
+}
+
+/// 用户账号（口令哈希不对外序列化）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserAccount {
+    pub user_id: String,
+    pub username: String,
+    pub roles: Vec<Role>,
+    /// 登录后进入的工作区（为空时为默认工作区）
+    #[serde(default)]
+    pub workspace_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+    #[serde(skip)]
+    password_hash: String,
+}
+
+impl UserAccount {
+    pub fn is_admin(&self) -> bool {
+        self.roles.contains(&Role::Admin)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct SessionInfo {
     pub user_id: String,
@@ -63,6 +289,8 @@ pub struct AuthManager {
     config: AuthConfig,
     sessions: Arc<RwLock<HashMap<String, SessionInfo>>>,
     revoked_tokens: Arc<RwLock<HashMap<String, DateTime<Utc>>>>,
+    /// 用户账号（username -> account）
+    users: Arc<RwLock<HashMap<String, UserAccount>>>,
 }
 
 impl AuthManager {
@@ -72,10 +300,108 @@ impl AuthManager {
             config,
             sessions: Arc::new(RwLock::new(HashMap::new())),
             revoked_tokens: Arc::new(RwLock::new(HashMap::new())),
+            users: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// 创建用户
+    pub async fn create_user(
+        &self,
+        username: &str,
+        password: &str,
+        roles: Vec<Role>,
+        workspace_id: Option<String>,
+    ) -> Result<UserAccount> {
+        let username = username.trim();
+        if username.is_empty() || password.is_empty() {
+            return Err(anyhow!("Username and password must not be empty"));
+        }
+        if roles.is_empty() {
+            return Err(anyhow!("User {} needs at least one role", username));
         }
+
+        let mut users = self.users.write().await;
+        if users.contains_key(username) {
+            return Err(anyhow!("User already exists: {}", username));
+        }
+        let account = UserAccount {
+            user_id: username.to_string(),
+            username: username.to_string(),
+            roles,
+            workspace_id,
+            created_at: Utc::now(),
+            password_hash: hash_password(password)?,
+        };
+        users.insert(username.to_string(), account.clone());
+        info!("👤 User created: {} ({:?})", username, account.roles);
+        Ok(account)
+    }
+
+    /// 校验口令并签发带角色的 Token
+    pub async fn login(&self, username: &str, password: &str) -> Result<TokenPair> {
+        let account = self.users.read().await.get(username.trim()).cloned();
+        let Some(account) = account.filter(|account| verify_password(password, &account.password_hash)) else {
+            warn!("🚫 Failed login for {}", username);
+            return Err(anyhow!("Invalid username or password"));
+        };
+        let roles = account.roles.iter().map(|role| role.as_str().to_string()).collect();
+        self.generate_workspace_token_pair(&account.user_id, &account.username, roles, account.workspace_id.as_deref())
+            .await
+    }
+
+    /// 所有用户（按用户名排序）
+    pub async fn list_users(&self) -> Vec<UserAccount> {
+        let mut users: Vec<UserAccount> = self.users.read().await.values().cloned().collect();
+        users.sort_by(|a, b| a.username.cmp(&b.username));
+        users
+    }
+
+    /// 调整角色（已签发的 Token 在过期前仍带旧角色）
+    pub async fn set_roles(&self, username: &str, roles: Vec<Role>) -> Result<UserAccount> {
+        if roles.is_empty() {
+            return Err(anyhow!("User {} needs at least one role", username));
+        }
+        let mut users = self.users.write().await;
+        let admins = users.values().filter(|account| account.is_admin()).count();
+        let account = users
+            .get_mut(username)
+            .ok_or_else(|| anyhow!("User not found: {}", username))?;
+        if account.is_admin() && !roles.contains(&Role::Admin) && admins == 1 {
+            return Err(anyhow!("Cannot remove the admin role from the last admin"));
+        }
+        account.roles = roles;
+        info!("👤 Roles updated: {} ({:?})", username, account.roles);
+        Ok(account.clone())
+    }
+
+    /// 删除用户并结束其会话
+    pub async fn delete_user(&self, username: &str) -> Result<()> {
+        let account = {
+            let mut users = self.users.write().await;
+            let admins = users.values().filter(|account| account.is_admin()).count();
+            match users.get(username) {
+                None => return Err(anyhow!("User not found: {}", username)),
+                Some(account) if account.is_admin() && admins == 1 => {
+                    return Err(anyhow!("Cannot delete the last admin"));
+                }
+                Some(_) => users.remove(username).expect("checked above"),
+            }
+        };
+        self.logout(&account.user_id).await
     }
 
     pub async fn generate_token_pair(&self, user_id: &str, username: &str, roles: Vec<String>) -> Result<TokenPair> {
+        self.generate_workspace_token_pair(user_id, username, roles, None).await
+    }
+
+    /// 签发绑定工作区的 Token
+    pub async fn generate_workspace_token_pair(
+        &self,
+        user_id: &str,
+        username: &str,
+        roles: Vec<String>,
+        workspace_id: Option<&str>,
+    ) -> Result<TokenPair> {
         let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
 
         let access_claims = Claims {
@@ -85,6 +411,8 @@ impl AuthManager {
             user_id: user_id.to_string(),
             username: username.to_string(),
             roles: roles.clone(),
+            workspace_id: workspace_id.map(str::to_string),
+            api_key_id: None,
         };
 
         let access_token = serde_json::to_string(&access_claims)?;
@@ -137,3 +465,112 @@ impl AuthManager {
         Ok(())
     }
 }
+
+/// PBKDF2-HMAC-SHA256 口令哈希，格式 `salt_hex$hash_hex`
+fn hash_password(password: &str) -> Result<String> {
+    let mut salt = [0u8; 16];
+    ring::rand::SecureRandom::fill(&ring::rand::SystemRandom::new(), &mut salt)
+        .map_err(|_| anyhow!("Failed to generate password salt"))?;
+    let mut hash = [0u8; 32];
+    ring::pbkdf2::derive(ring::pbkdf2::PBKDF2_HMAC_SHA256, password_iterations(), &salt, password.as_bytes(), &mut hash);
+    Ok(format!("{}${}", hex_encode(&salt), hex_encode(&hash)))
+}
+
+fn verify_password(password: &str, stored: &str) -> bool {
+    let Some((salt, hash)) = stored.split_once('$') else {
+        return false;
+    };
+    let (Some(salt), Some(hash)) = (hex_decode(salt), hex_decode(hash)) else {
+        return false;
+    };
+    ring::pbkdf2::verify(ring::pbkdf2::PBKDF2_HMAC_SHA256, password_iterations(), &salt, password.as_bytes(), &hash)
+        .is_ok()
+}
+
+fn password_iterations() -> NonZeroU32 {
+    NonZeroU32::new(PASSWORD_ITERATIONS).expect("iteration count is non-zero")
+}
+
+pub(crate) fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 256 位随机签名密钥（十六进制）
+fn random_secret() -> Result<String> {
+    let mut bytes = [0u8; 32];
+    ring::rand::SecureRandom::fill(&ring::rand::SystemRandom::new(), &mut bytes)
+        .map_err(|_| anyhow!("Failed to generate JWT secret"))?;
+    Ok(hex_encode(&bytes))
+}
+
+fn hex_decode(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::secret_store::MemorySecretStore;
+
+    #[tokio::test]
+    async fn test_jwt_secret_generated_once() {
+        let store = MemorySecretStore::new();
+        let first = AuthConfig::from_secret_store(&store).await.unwrap();
+        let second = AuthConfig::from_secret_store(&store).await.unwrap();
+
+        assert_eq!(first.jwt_secret.len(), 64);
+        assert_ne!(first.jwt_secret, AuthConfig::default().jwt_secret);
+        assert_eq!(first.jwt_secret, second.jwt_secret);
+
+        // 启动加载：钥匙串优先于环境变量
+        let dev = Environment::Development;
+        let loaded = AuthConfig::resolve(dev, Some(&store), Some("from-env".to_string())).await.unwrap();
+        assert_eq!(loaded.jwt_secret, first.jwt_secret);
+        assert_eq!(AuthConfig::resolve(dev, None, Some("from-env".to_string())).await.unwrap().jwt_secret, "from-env");
+
+        // 未配置时每个进程随机生成，不存在公开的默认密钥
+        let fallback = AuthConfig::resolve(dev, None, None).await.unwrap().jwt_secret;
+        assert_eq!(fallback.len(), 64);
+        assert_eq!(fallback, AuthConfig::default().jwt_secret);
+        assert_ne!(fallback, first.jwt_secret);
+    }
+
+    #[tokio::test]
+    async fn test_production_requires_jwt_secret() {
+        let prod = Environment::Production;
+        let err = AuthConfig::resolve(prod, None, None).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<AcsaError>().unwrap().code(), Some(ErrorCode::ConfigError));
+        assert!(AuthConfig::resolve(prod, None, Some("  ".to_string())).await.is_err());
+
+        assert_eq!(AuthConfig::resolve(prod, None, Some("from-env".to_string())).await.unwrap().jwt_secret, "from-env");
+        let store = MemorySecretStore::new();
+        assert_eq!(AuthConfig::resolve(prod, Some(&store), None).await.unwrap().jwt_secret.len(), 64);
+    }
+
+    #[tokio::test]
+    async fn test_roles_and_user_management() {
+        let auth = AuthManager::new(AuthConfig::default());
+        auth.create_user("root", "hunter2", vec![Role::Admin], None).await.unwrap();
+        auth.create_user("ops", "pw", vec![Role::Operator], Some("team-a".to_string())).await.unwrap();
+        assert!(auth.create_user("ops", "pw", vec![Role::Viewer], None).await.is_err());
+
+        assert!(auth.login("ops", "wrong").await.is_err());
+        let claims = auth.verify_token(&auth.login("ops", "pw").await.unwrap().access_token).await.unwrap();
+        assert_eq!(claims.roles, vec!["operator"]);
+        assert_eq!(claims.workspace_id.as_deref(), Some("team-a"));
+        assert!(claims.can(Permission::Execute));
+        assert!(!claims.can(Permission::ManageBudgets));
+        let denied = claims.require(Permission::ManageProtocols).unwrap_err();
+        assert_eq!(denied.downcast_ref::<AcsaError>().unwrap().to_report().code, ErrorCode::PermissionDenied);
+
+        // 至少保留一名 admin
+        assert!(auth.set_roles("root", vec![Role::Viewer]).await.is_err());
+        assert!(auth.delete_user("root").await.is_err());
+        auth.set_roles("ops", vec![Role::Admin]).await.unwrap();
+        auth.delete_user("root").await.unwrap();
+        assert_eq!(auth.list_users().await.iter().map(|u| u.username.as_str()).collect::<Vec<_>>(), vec!["ops"]);
+    }
+}
@@ -10,6 +10,8 @@ use tokio::sync::RwLock;
 use tracing::{info, warn};
 
 use super::behavior_monitor::{BehaviorMonitor, TakeoverSuggestion, UserBehaviorEvent};
+use super::kill_switch::{KillSwitch, PausedOperation};
+use super::notifier::{Notification, NotificationKind, Notifier};
 use super::protocol::Protocol;
 use super::task_tracker::{Task, TaskStatus, TaskTracker};
 
@@ -78,6 +80,10 @@ pub struct AutoTakeoverEngine {
     last_takeover: Option<DateTime<Utc>>,
     /// 待确认的接管建议
     pending_suggestions: Vec<TakeoverSuggestion>,
+    /// 全局熔断开关（维护模式下暂停接管）
+    kill_switch: Option<Arc<KillSwitch>>,
+    /// 通知中心（接管建议待审批）
+    notifier: Option<Arc<Notifier>>,
 }
 
 impl AutoTakeoverEngine {
@@ -97,9 +103,23 @@ impl AutoTakeoverEngine {
             history: Vec::new(),
             last_takeover: None,
             pending_suggestions: Vec::new(),
+            kill_switch: None,
+            notifier: None,
         }
     }
 
+    /// 接入全局熔断开关
+    pub fn with_kill_switch(mut self, kill_switch: Arc<KillSwitch>) -> Self {
+        self.kill_switch = Some(kill_switch);
+        self
+    }
+
+    /// 接入通知中心
+    pub fn with_notifier(mut self, notifier: Arc<Notifier>) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
     /// 记录用户行为事件
     pub async fn record_behavior(&mut self, event: UserBehaviorEvent) {
         let mut monitor = self.behavior_monitor.write().await;
@@ -108,6 +128,11 @@ impl AutoTakeoverEngine {
 
     /// 检查是否应该触发接管
     pub async fn check_takeover(&mut self) -> Option<TakeoverSuggestion> {
+        // 维护模式：不产生新的接管建议
+        if self.kill_switch.as_ref().is_some_and(|k| k.is_engaged()) {
+            return None;
+        }
+
         // 检查冷却时间
         if let Some(last) = self.last_takeover {
             let elapsed = (Utc::now() - last).num_seconds() as u64;
@@ -123,6 +148,21 @@ impl AutoTakeoverEngine {
         if self.policy.require_confirmation {
             self.pending_suggestions.push(suggestion.clone());
             info!("⏸️ Takeover suggestion pending confirmation: {}", suggestion.pattern_id);
+            if let Some(notifier) = &self.notifier {
+                notifier.notify_detached(
+                    Notification::new(
+                        NotificationKind::WorkflowApproval,
+                        format!("Takeover awaiting approval: {}", suggestion.pattern_id),
+                        format!(
+                            "{} (confidence {:.0}%, saves ~{}s)",
+                            suggestion.description,
+                            suggestion.confidence * 100.0,
+                            suggestion.estimated_time_save_secs
+                        ),
+                    )
+                    .with_dedupe_key(format!("takeover:{}", suggestion.pattern_id)),
+                );
+            }
             return Some(suggestion);
         }
 
@@ -137,6 +177,10 @@ impl AutoTakeoverEngine {
 
     /// 确认接管建议
     pub async fn confirm_takeover(&mut self, pattern_id: &str) -> Result<()> {
+        if let Some(kill_switch) = &self.kill_switch {
+            kill_switch.check(PausedOperation::Takeover)?;
+        }
+
         let suggestion = self
             .pending_suggestions
             .iter()
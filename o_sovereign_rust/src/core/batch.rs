@@ -0,0 +1,342 @@
+// Batch - 批量执行 ACSA 链路
+// 从 JSONL 读取大量输入，有界并发地执行 ACSARouter::execute，逐行写出结果，便于在大规模提示集上评估链路
+//
+// 核心功能：
+// 1. 任务文件：每行一个 JSON 对象 `{"id": ..., "input": ..., "tags": [...]}`，也接受纯字符串行
+// 2. 有界并发：复用 ConcurrencyManager 的工作窃取执行器，worker 数即并发上限
+// 3. 单任务超时：超时或失败只影响该任务，不中断整批
+// 4. 结果文件：按输入顺序逐行写出成本、延迟、迭代次数与审计结论
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+use tracing::info;
+
+use super::concurrency::{AsyncTask, ConcurrencyConfig, ConcurrencyManager, TaskContext, TaskPriority};
+use super::router::ACSARouter;
+use super::types::ACSAExecutionLog;
+
+/// 默认并发数
+pub const DEFAULT_BATCH_CONCURRENCY: usize = 4;
+
+/// 结果中保留的输出预览长度（字符）
+const OUTPUT_PREVIEW_CHARS: usize = 200;
+
+/// 批量任务
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatchTask {
+    /// 任务 ID（缺省时为 `line-<行号>`）
+    #[serde(default)]
+    pub id: String,
+    pub input: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl BatchTask {
+    /// 解析 JSONL 任务文件，空行跳过，错误带行号
+    pub fn parse_jsonl(content: &str) -> Result<Vec<Self>> {
+        let mut tasks = Vec::new();
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let line_no = index + 1;
+            let mut task = match serde_json::from_str::<serde_json::Value>(line)
+                .with_context(|| format!("Line {}: invalid JSON", line_no))?
+            {
+                serde_json::Value::String(input) => Self { id: String::new(), input, tags: Vec::new() },
+                value => serde_json::from_value::<Self>(value)
+                    .with_context(|| format!("Line {}: expected {{\"input\": ...}} or a string", line_no))?,
+            };
+            if task.input.trim().is_empty() {
+                return Err(anyhow!("Line {}: empty input", line_no));
+            }
+            if task.id.is_empty() {
+                task.id = format!("line-{}", line_no);
+            }
+            tasks.push(task);
+        }
+        Ok(tasks)
+    }
+}
+
+/// 审计结论
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum AuditOutcome {
+    /// 通过 Ultron 审计并完成执行
+    Approved { risk_score: u8 },
+    /// 审计未通过（迭代耗尽或边际效用不足提前停止）
+    Rejected { risk_score: u8 },
+    /// 被 Jarvis 硬性阻止
+    JarvisBlocked { reason: String },
+    /// 审计前失败（Provider 错误等）
+    Incomplete,
+    /// 执行出错（维护模式、超时、取消等）
+    Error { message: String },
+}
+
+impl AuditOutcome {
+    pub fn from_log(log: &ACSAExecutionLog) -> Self {
+        if let Some(reason) = &log.jarvis_block {
+            return Self::JarvisBlocked { reason: reason.clone() };
+        }
+        match (&log.audit_result, log.success) {
+            (audit, true) => Self::Approved { risk_score: audit.as_ref().map(|a| a.risk_score).unwrap_or(0) },
+            (Some(audit), false) => Self::Rejected { risk_score: audit.risk_score },
+            (None, false) => Self::Incomplete,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Approved { .. } => "approved",
+            Self::Rejected { .. } => "rejected",
+            Self::JarvisBlocked { .. } => "jarvis_blocked",
+            Self::Incomplete => "incomplete",
+            Self::Error { .. } => "error",
+        }
+    }
+}
+
+/// 单个任务的结果（结果文件中的一行）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchResult {
+    pub id: String,
+    pub success: bool,
+    pub cost_usd: f64,
+    /// 链路耗时（出错时为排队后的实际耗时）
+    pub latency_ms: u64,
+    pub iterations: u32,
+    pub audit: AuditOutcome,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_preview: Option<String>,
+    pub completed_at: DateTime<Utc>,
+}
+
+impl BatchResult {
+    fn from_log(task: &BatchTask, log: &ACSAExecutionLog) -> Self {
+        Self {
+            id: task.id.clone(),
+            success: log.success,
+            cost_usd: log.total_cost,
+            latency_ms: log.total_time_ms,
+            iterations: log.iterations,
+            audit: AuditOutcome::from_log(log),
+            tags: task.tags.clone(),
+            seed: log.seed,
+            output_preview: log.final_output.as_ref().map(|o| o.chars().take(OUTPUT_PREVIEW_CHARS).collect()),
+            completed_at: Utc::now(),
+        }
+    }
+
+    fn from_error(task: &BatchTask, message: String, latency_ms: u64) -> Self {
+        Self {
+            id: task.id.clone(),
+            success: false,
+            cost_usd: 0.0,
+            latency_ms,
+            iterations: 0,
+            audit: AuditOutcome::Error { message },
+            tags: task.tags.clone(),
+            seed: None,
+            output_preview: None,
+            completed_at: Utc::now(),
+        }
+    }
+}
+
+/// 整批汇总
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BatchSummary {
+    pub total: usize,
+    pub succeeded: usize,
+    /// 各审计结论的数量
+    pub outcomes: HashMap<String, usize>,
+    pub total_cost_usd: f64,
+    pub avg_latency_ms: f64,
+    pub max_latency_ms: u64,
+    /// 整批墙钟耗时
+    pub wall_time_ms: u64,
+}
+
+impl BatchSummary {
+    fn record(&mut self, result: &BatchResult) {
+        self.total += 1;
+        if result.success {
+            self.succeeded += 1;
+        }
+        *self.outcomes.entry(result.audit.label().to_string()).or_default() += 1;
+        self.total_cost_usd += result.cost_usd;
+        self.avg_latency_ms += (result.latency_ms as f64 - self.avg_latency_ms) / self.total as f64;
+        self.max_latency_ms = self.max_latency_ms.max(result.latency_ms);
+    }
+
+    pub fn outcome(&self, label: &str) -> usize {
+        self.outcomes.get(label).copied().unwrap_or(0)
+    }
+}
+
+/// 批量执行器
+pub struct BatchRunner {
+    router: Arc<ACSARouter>,
+    concurrency: usize,
+    task_timeout: Option<Duration>,
+}
+
+impl BatchRunner {
+    pub fn new(router: Arc<ACSARouter>) -> Self {
+        Self { router, concurrency: DEFAULT_BATCH_CONCURRENCY, task_timeout: None }
+    }
+
+    /// 最大并发执行数
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// 单任务超时
+    pub fn with_task_timeout(mut self, timeout: Duration) -> Self {
+        self.task_timeout = Some(timeout);
+        self
+    }
+
+    /// 执行全部任务，按输入顺序逐行写出结果 JSONL
+    pub async fn run(&self, tasks: Vec<BatchTask>, output: &Path) -> Result<BatchSummary> {
+        let started = Instant::now();
+        let capacity = tasks.len().max(1);
+        let manager = ConcurrencyManager::new(ConcurrencyConfig {
+            max_concurrent_tasks: self.concurrency,
+            worker_threads: self.concurrency,
+            max_queue_size: capacity,
+            priority_queue_size: capacity,
+            ..Default::default()
+        });
+        info!("📦 Batch: {} tasks, concurrency {}", tasks.len(), self.concurrency);
+
+        let mut pending = Vec::with_capacity(tasks.len());
+        for task in tasks {
+            let (result_tx, result_rx) = oneshot::channel();
+            let router = self.router.clone();
+            let input = task.input.clone();
+            let future = async move {
+                let log = router.execute(input).await?;
+                let _ = result_tx.send(log);
+                Ok(())
+            };
+
+            let mut context = TaskContext::new(task.id.clone());
+            if let Some(timeout) = self.task_timeout {
+                context = context.with_timeout(timeout);
+            }
+            let handle = manager.submit_with_context(async_task(&task), context, future).await?;
+            pending.push((task, handle, result_rx));
+        }
+
+        if let Some(parent) = output.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut writer = std::io::BufWriter::new(
+            std::fs::File::create(output).with_context(|| format!("Failed to create {}", output.display()))?,
+        );
+        let mut summary = BatchSummary::default();
+        for (task, handle, result_rx) in pending {
+            let outcome = handle.wait().await?;
+            let result = match result_rx.await {
+                Ok(log) => BatchResult::from_log(&task, &log),
+                Err(_) => BatchResult::from_error(
+                    &task,
+                    outcome.error.unwrap_or_else(|| "Task finished without a result".to_string()),
+                    outcome.duration_ms,
+                ),
+            };
+            summary.record(&result);
+            serde_json::to_writer(&mut writer, &result)?;
+            writer.write_all(b"\n")?;
+            // 逐行落盘，中途中断也能保留已完成的结果
+            writer.flush()?;
+        }
+        manager.shutdown();
+
+        summary.wall_time_ms = started.elapsed().as_millis() as u64;
+        info!(
+            "📦 Batch finished: {}/{} succeeded, ${:.4}, {} ms",
+            summary.succeeded, summary.total, summary.total_cost_usd, summary.wall_time_ms
+        );
+        Ok(summary)
+    }
+}
+
+fn async_task(task: &BatchTask) -> AsyncTask {
+    AsyncTask {
+        id: task.id.clone(),
+        name: format!("batch:{}", task.id),
+        priority: TaskPriority::Normal,
+        created_at: Utc::now(),
+        agent_name: None,
+        metadata: HashMap::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::providers::MockProvider;
+    use crate::core::types::{ACSAConfig, AgentRole};
+
+    #[test]
+    fn test_parse_jsonl() {
+        let content = "{\"id\": \"a\", \"input\": \"write a server\", \"tags\": [\"web\"]}\n\n\"plain string task\"\n";
+        let tasks = BatchTask::parse_jsonl(content).unwrap();
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].tags, vec!["web"]);
+        assert_eq!(tasks[1].id, "line-3");
+        assert_eq!(tasks[1].input, "plain string task");
+
+        let err = BatchTask::parse_jsonl("\"ok\"\n{\"id\": \"x\"}").unwrap_err();
+        assert!(err.to_string().contains("Line 2"));
+    }
+
+    #[tokio::test]
+    async fn test_run_writes_results_in_input_order() {
+        let router = ACSARouter::new(
+            Arc::new(MockProvider::new(AgentRole::MOSS)),
+            Arc::new(MockProvider::new(AgentRole::L6)),
+            Arc::new(MockProvider::new(AgentRole::Ultron)),
+            Arc::new(MockProvider::new(AgentRole::Omega)),
+            ACSAConfig { max_iterations: 1, enable_l6: false, ..Default::default() },
+        );
+        let tasks = BatchTask::parse_jsonl(
+            "{\"id\": \"t1\", \"input\": \"写一个HTTP服务器\"}\n{\"id\": \"t2\", \"input\": \"rm -rf / now\"}\n\"解释TCP握手\"",
+        )
+        .unwrap();
+        let dir = tempfile::TempDir::new().unwrap();
+        let output = dir.path().join("results.jsonl");
+
+        let summary = BatchRunner::new(Arc::new(router)).with_concurrency(2).run(tasks, &output).await.unwrap();
+
+        assert_eq!(summary.total, 3);
+        assert_eq!(summary.outcome("jarvis_blocked"), 1);
+        let results: Vec<BatchResult> = std::fs::read_to_string(&output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let ids: Vec<_> = results.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["t1", "t2", "line-3"]);
+        assert!(matches!(results[1].audit, AuditOutcome::JarvisBlocked { .. }));
+        assert!(results[0].cost_usd > 0.0);
+    }
+}
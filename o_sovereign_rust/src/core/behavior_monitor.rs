@@ -306,6 +306,13 @@ impl BehaviorMonitor {
         }
     }
 
+    /// 已检测到的模式（按置信度降序）
+    pub fn patterns(&self) -> Vec<BehaviorPattern> {
+        let mut patterns: Vec<_> = self.patterns.values().cloned().collect();
+        patterns.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+        patterns
+    }
+
     /// 获取当前用户行为特征
     pub fn get_current_behavior_profile(&self) -> BehaviorProfile {
         let recent_events: Vec<_> = self
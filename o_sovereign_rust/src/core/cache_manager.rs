@@ -1,13 +1,22 @@
 // Cache Manager - 避免系统卡死的清理系统
 // Prevents system freezing by managing cache and log cleanup
+//
+// 注：ResponseCache（可选启用）把 Provider 响应按 (provider, model, prompt 哈希, temperature) 精确缓存到 api_cache，
+// 过期条目读取时删除，占用空间仍由本管理器统一清理；配置相似度阈值与嵌入器后，精确未命中时按提示词向量的
+// 余弦相似度查找同模型、同温度的缓存响应（语义缓存）
 
 use anyhow::{Context, Result};
 use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use tracing::{debug, info, warn};
 
+use super::embedding::{cosine_similarity, CachedEmbedder};
+use super::types::AgentResponse;
+
 /// 缓存清理策略
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CleanupPolicy {
@@ -548,6 +557,264 @@ impl CacheUsage {
     }
 }
 
+/// 响应缓存策略
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponseCachePolicy {
+    /// 条目有效期（秒）
+    pub ttl_secs: u64,
+    /// 语义缓存的余弦相似度阈值（0-1；为空时只做精确匹配）
+    #[serde(default)]
+    pub semantic_threshold: Option<f64>,
+}
+
+impl Default for ResponseCachePolicy {
+    fn default() -> Self {
+        Self { ttl_secs: 24 * 3600, semantic_threshold: None }
+    }
+}
+
+/// 单次执行的响应缓存统计（写入执行日志）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResponseCacheStats {
+    pub hits: u32,
+    pub misses: u32,
+    /// 命中节省的成本（按原响应计费）
+    pub saved_cost: f64,
+    /// 命中中由语义匹配（而非精确匹配）提供的次数
+    #[serde(default)]
+    pub semantic_hits: u32,
+}
+
+impl ResponseCacheStats {
+    pub fn record(&mut self, hit: Option<f64>) {
+        match hit {
+            Some(cost) => {
+                self.hits += 1;
+                self.saved_cost += cost;
+            }
+            None => self.misses += 1,
+        }
+    }
+
+    pub fn record_semantic(&mut self, cost: f64) {
+        self.record(Some(cost));
+        self.semantic_hits += 1;
+    }
+}
+
+/// 缓存条目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedResponse {
+    model_id: String,
+    temperature: f64,
+    stored_at: DateTime<Utc>,
+    /// 提示词向量（仅语义缓存写入）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    embedding: Vec<f32>,
+    response: AgentResponse,
+}
+
+/// 语义索引条目（响应本身仍在磁盘上）
+struct SemanticEntry {
+    key: String,
+    model_id: String,
+    temperature: f64,
+    stored_at: DateTime<Utc>,
+    embedding: Vec<f32>,
+}
+
+/// Provider 响应缓存（默认不启用，由 Router 的 `with_response_cache` 接入）
+///
+/// 键为 (provider/model, prompt, temperature) 的 SHA-256；失败的调用不缓存。
+/// 语义模式下另维护一份提示词向量的内存索引，启动时从磁盘条目重建。
+pub struct ResponseCache {
+    dir: PathBuf,
+    policy: ResponseCachePolicy,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    embedder: Option<Arc<CachedEmbedder>>,
+    index: Mutex<Vec<SemanticEntry>>,
+}
+
+impl ResponseCache {
+    /// 缓存到 cache_manager 的 API 响应目录（`api_cache/responses`）
+    pub fn new(cache: &CacheManager, policy: ResponseCachePolicy) -> Self {
+        let dir = cache.get_cache_dir(CacheType::ApiResponse).join("responses");
+        info!("🗄️ Response cache: {} (TTL {}s)", dir.display(), policy.ttl_secs);
+        Self {
+            dir,
+            policy,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            embedder: None,
+            index: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// 启用语义匹配（通常复用 `RagEngine::embedder`；策略未设阈值时不生效）
+    pub fn with_embedder(mut self, embedder: Arc<CachedEmbedder>) -> Self {
+        let index = self.load_index();
+        info!(
+            "🗄️ Semantic response cache: {} (threshold {:?}, {} indexed)",
+            embedder.provider().model_id(),
+            self.policy.semantic_threshold,
+            index.len()
+        );
+        self.index = Mutex::new(index);
+        self.embedder = Some(embedder);
+        self
+    }
+
+    /// 是否启用了语义匹配
+    pub fn semantic_enabled(&self) -> bool {
+        self.embedder.is_some() && self.policy.semantic_threshold.is_some()
+    }
+
+    /// 从磁盘条目重建语义索引（跳过没有向量的精确缓存条目）
+    fn load_index(&self) -> Vec<SemanticEntry> {
+        let Ok(shards) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        shards
+            .flatten()
+            .filter_map(|shard| fs::read_dir(shard.path()).ok())
+            .flat_map(|entries| entries.flatten())
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|entry| {
+                let key = entry.path().file_stem()?.to_str()?.to_string();
+                let cached: CachedResponse = serde_json::from_slice(&fs::read(entry.path()).ok()?).ok()?;
+                (!cached.embedding.is_empty()).then_some(SemanticEntry {
+                    key,
+                    model_id: cached.model_id,
+                    temperature: cached.temperature,
+                    stored_at: cached.stored_at,
+                    embedding: cached.embedding,
+                })
+            })
+            .collect()
+    }
+
+    fn expired(&self, stored_at: DateTime<Utc>) -> bool {
+        Utc::now() - stored_at > Duration::seconds(self.policy.ttl_secs as i64)
+    }
+
+    /// 缓存键
+    pub fn key(model_id: &str, prompt: &str, temperature: f64) -> String {
+        let material = format!("{}\0{:.3}\0{}", model_id, temperature, prompt);
+        let digest = ring::digest::digest(&ring::digest::SHA256, material.as_bytes());
+        digest.as_ref().iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(&key[..2]).join(format!("{}.json", key))
+    }
+
+    /// 查找未过期的响应；过期条目顺带删除
+    pub async fn get(&self, key: &str) -> Option<AgentResponse> {
+        let path = self.path(key);
+        let entry = tokio::fs::read(&path)
+            .await
+            .ok()
+            .and_then(|content| serde_json::from_slice::<CachedResponse>(&content).ok());
+        let Some(entry) = entry else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return None;
+        };
+        if self.expired(entry.stored_at) {
+            debug!("🗄️ Response cache entry expired: {}", key);
+            let _ = tokio::fs::remove_file(&path).await;
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        Some(entry.response)
+    }
+
+    /// 生成提示词向量（未启用语义匹配或嵌入失败时为空，退回精确匹配）
+    pub async fn embed_prompt(&self, prompt: &str) -> Option<Vec<f32>> {
+        if !self.semantic_enabled() {
+            return None;
+        }
+        let embedder = self.embedder.as_ref()?;
+        match embedder.embed_one(prompt).await {
+            Ok(embedding) => Some(embedding),
+            Err(e) => {
+                warn!("⚠️  Semantic cache lookup skipped: {:#}", e);
+                None
+            }
+        }
+    }
+
+    /// 查找同模型、同温度下相似度最高且不低于阈值的未过期响应，返回响应与相似度
+    pub async fn get_similar(&self, model_id: &str, temperature: f64, embedding: &[f32]) -> Option<(AgentResponse, f64)> {
+        let threshold = self.policy.semantic_threshold?;
+        let (key, similarity) = {
+            let mut index = self.index.lock().unwrap_or_else(|e| e.into_inner());
+            index.retain(|entry| !self.expired(entry.stored_at));
+            index
+                .iter()
+                .filter(|entry| entry.model_id == model_id && (entry.temperature - temperature).abs() < 5e-4)
+                .map(|entry| (entry.key.clone(), cosine_similarity(&entry.embedding, embedding)))
+                .filter(|(_, similarity)| *similarity >= threshold)
+                .max_by(|a, b| a.1.total_cmp(&b.1))?
+        };
+        let entry = tokio::fs::read(self.path(&key))
+            .await
+            .ok()
+            .and_then(|content| serde_json::from_slice::<CachedResponse>(&content).ok())
+            .filter(|entry| !self.expired(entry.stored_at));
+        let Some(entry) = entry else {
+            // 条目已被清理或过期：从索引移除
+            self.index.lock().unwrap_or_else(|e| e.into_inner()).retain(|entry| entry.key != key);
+            return None;
+        };
+        debug!("🗄️ Semantic cache match {} (similarity {:.3})", key, similarity);
+        Some((entry.response, similarity))
+    }
+
+    /// 写入响应，带向量时同时加入语义索引（写入失败只记录告警）
+    pub async fn put(&self, key: &str, model_id: &str, temperature: f64, embedding: Option<Vec<f32>>, response: &AgentResponse) {
+        let path = self.path(key);
+        let entry = CachedResponse {
+            model_id: model_id.to_string(),
+            temperature,
+            stored_at: Utc::now(),
+            embedding: embedding.unwrap_or_default(),
+            response: response.clone(),
+        };
+        let result = async {
+            if let Some(parent) = path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            let tmp = path.with_extension("json.tmp");
+            tokio::fs::write(&tmp, serde_json::to_vec(&entry)?).await?;
+            tokio::fs::rename(&tmp, &path).await?;
+            anyhow::Ok(())
+        }
+        .await;
+        if let Err(e) = result {
+            warn!("⚠️  Failed to write response cache entry {}: {}", key, e);
+            return;
+        }
+        if !entry.embedding.is_empty() {
+            let mut index = self.index.lock().unwrap_or_else(|e| e.into_inner());
+            index.retain(|indexed| indexed.key != key);
+            index.push(SemanticEntry {
+                key: key.to_string(),
+                model_id: entry.model_id,
+                temperature,
+                stored_at: entry.stored_at,
+                embedding: entry.embedding,
+            });
+        }
+    }
+
+    /// 进程内累计精确匹配的命中 / 未命中次数
+    pub fn totals(&self) -> (u64, u64) {
+        (self.hits.load(Ordering::Relaxed), self.misses.load(Ordering::Relaxed))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -601,4 +868,36 @@ mod tests {
         let stats = manager.cleanup_expired().unwrap();
         assert!(stats.files_removed > 0);
     }
+
+    #[tokio::test]
+    async fn test_response_cache_exact_match_and_ttl() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = CacheManager::with_defaults(temp_dir.path().to_path_buf()).unwrap();
+        let cache = ResponseCache::new(&manager, ResponseCachePolicy::default());
+
+        let response = AgentResponse {
+            role: crate::core::types::AgentRole::MOSS,
+            text: "Plan: use axum".to_string(),
+            tokens: 42,
+            cost: 0.01,
+            latency_ms: 900,
+            metadata: Default::default(),
+            timestamp: Utc::now(),
+        };
+        let key = ResponseCache::key("openai/gpt-4", "Write a server", 0.7);
+        assert_ne!(key, ResponseCache::key("openai/gpt-4", "Write a server", 0.35));
+        assert_ne!(key, ResponseCache::key("deepseek/deepseek-chat", "Write a server", 0.7));
+
+        assert!(cache.get(&key).await.is_none());
+        cache.put(&key, "openai/gpt-4", 0.7, None, &response).await;
+        assert_eq!(cache.get(&key).await.unwrap().text, "Plan: use axum");
+        assert_eq!(cache.totals(), (1, 1));
+        assert!(manager.get_cache_usage().unwrap().api_cache_bytes > 0);
+
+        // 过期条目不再命中并被删除
+        let expired = ResponseCache::new(&manager, ResponseCachePolicy { ttl_secs: 0, ..Default::default() });
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        assert!(expired.get(&key).await.is_none());
+        assert!(cache.get(&key).await.is_none());
+    }
 }
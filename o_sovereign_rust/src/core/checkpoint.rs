@@ -0,0 +1,182 @@
+// Checkpoint - 长链路执行的检查点与恢复
+// 进程在链路中途退出时，已完成的 Agent 工作不丢失：按执行 ID 落盘中间状态，之后从断点继续
+//
+// 核心功能：
+// 1. 每个阶段结束后保存检查点：MOSS 方案、L6 复核、每轮 Ultron 审计（含驳回后的重新规划）
+// 2. 每次执行一个 JSON 文件（先写临时文件再替换），执行正常结束后删除
+// 3. ACSARouter::resume(execution_id) 跳过已完成的阶段继续执行；CLI `resume` 子命令
+//
+// 注：边际效用追踪（OutcomeTracker）不入检查点，恢复后从零开始统计
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tracing::{debug, warn};
+
+use super::pipeline::PipelineConfig;
+use super::types::ACSAExecutionLog;
+
+/// 默认检查点目录
+pub const DEFAULT_CHECKPOINT_DIR: &str = "./data/checkpoints";
+
+/// 检查点所处阶段（已完成的最后一步）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckpointStage {
+    /// MOSS 已给出方案（且通过 Jarvis 校验）
+    Planned,
+    /// 本轮开头的 L6 复核阶段已完成，审计从 next_iteration 轮继续
+    Verified,
+    /// Ultron 驳回、MOSS 已重新规划，下一轮复核与审计从 next_iteration 开始
+    Audited,
+    /// 风险未降到阈值以下，等待人工审批（批准后直接进入 Omega）
+    AwaitingApproval,
+    /// Ultron 审计通过，只差 Omega 执行
+    Approved,
+}
+
+impl CheckpointStage {
+    pub fn label(&self) -> &'static str {
+        match self {
+            CheckpointStage::Planned => "planned",
+            CheckpointStage::Verified => "verified",
+            CheckpointStage::Audited => "audited",
+            CheckpointStage::AwaitingApproval => "awaiting_approval",
+            CheckpointStage::Approved => "approved",
+        }
+    }
+}
+
+/// 执行检查点
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionCheckpoint {
+    pub execution_id: String,
+    pub stage: CheckpointStage,
+    /// 下一轮复核与审计的迭代序号（从 0 开始）
+    pub next_iteration: u32,
+    /// 认知清洗后的输入（恢复时不再重复清洗与初始安全检查）
+    pub processed_input: String,
+    /// 当前方案与复核结果（重新规划后会变化）
+    pub current_plan: String,
+    pub current_l6: String,
+    /// 多轮会话上下文
+    pub conversation: Option<String>,
+    /// 检索到的知识库片段
+    pub knowledge: Option<String>,
+    /// 中断时使用的流水线拓扑（恢复时应使用相同的拓扑）
+    #[serde(default)]
+    pub pipeline: Option<PipelineConfig>,
+    /// 截至检查点的执行日志
+    pub log: ACSAExecutionLog,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// 检查点存储
+pub struct CheckpointStore {
+    dir: PathBuf,
+}
+
+impl CheckpointStore {
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        Ok(Self { dir })
+    }
+
+    /// 保存（覆盖）检查点
+    pub fn save(&self, checkpoint: &ExecutionCheckpoint) -> Result<()> {
+        let path = self.path(&checkpoint.execution_id)?;
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(checkpoint)?)?;
+        std::fs::rename(&tmp, &path).with_context(|| format!("Failed to write {}", path.display()))?;
+        debug!("💾 Checkpoint {} saved at stage {}", checkpoint.execution_id, checkpoint.stage.label());
+        Ok(())
+    }
+
+    /// 读取检查点，不存在时返回 None
+    pub fn load(&self, execution_id: &str) -> Result<Option<ExecutionCheckpoint>> {
+        let path = self.path(execution_id)?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(&path)?;
+        let checkpoint = serde_json::from_str(&content)
+            .with_context(|| format!("Corrupted checkpoint {}", path.display()))?;
+        Ok(Some(checkpoint))
+    }
+
+    /// 删除检查点，返回是否存在
+    pub fn remove(&self, execution_id: &str) -> Result<bool> {
+        let path = self.path(execution_id)?;
+        if !path.exists() {
+            return Ok(false);
+        }
+        std::fs::remove_file(&path)?;
+        Ok(true)
+    }
+
+    /// 全部未完成的执行（最近更新的在前）
+    pub fn list(&self) -> Result<Vec<ExecutionCheckpoint>> {
+        let mut checkpoints = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            match std::fs::read_to_string(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|content| Ok(serde_json::from_str::<ExecutionCheckpoint>(&content)?))
+            {
+                Ok(checkpoint) => checkpoints.push(checkpoint),
+                Err(e) => warn!("⚠️  Skipping corrupted checkpoint {}: {}", path.display(), e),
+            }
+        }
+        checkpoints.sort_by_key(|checkpoint| std::cmp::Reverse(checkpoint.updated_at));
+        Ok(checkpoints)
+    }
+
+    fn path(&self, execution_id: &str) -> Result<PathBuf> {
+        // 执行ID直接作为文件名，拒绝路径穿越
+        if execution_id.is_empty()
+            || !execution_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(anyhow!("Invalid execution id: {:?}", execution_id));
+        }
+        Ok(self.dir.join(format!("{}.json", execution_id)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_checkpoint_roundtrip() {
+        let dir = tempdir().unwrap();
+        let store = CheckpointStore::open(dir.path()).unwrap();
+        let checkpoint = ExecutionCheckpoint {
+            execution_id: "exec_1_0".to_string(),
+            stage: CheckpointStage::Verified,
+            next_iteration: 0,
+            processed_input: "plan a launch".to_string(),
+            current_plan: "plan".to_string(),
+            current_l6: "verified".to_string(),
+            conversation: None,
+            knowledge: None,
+            pipeline: None,
+            log: ACSAExecutionLog::new("plan a launch".to_string()),
+            updated_at: Utc::now(),
+        };
+
+        store.save(&checkpoint).unwrap();
+        let loaded = store.load("exec_1_0").unwrap().unwrap();
+        assert_eq!(loaded.stage, CheckpointStage::Verified);
+        assert_eq!(store.list().unwrap().len(), 1);
+
+        assert!(store.load("../etc/passwd").is_err());
+        assert!(store.remove("exec_1_0").unwrap());
+        assert!(store.load("exec_1_0").unwrap().is_none());
+    }
+}
@@ -1,9 +1,13 @@
 // Claude Provider - Ultron's Brain
 // 红队审计专家
 
+use super::api_manager::{self, ApiProvider};
+use super::determinism;
+use super::error::{AcsaError, ErrorCode};
 use super::providers::ModelProvider;
+use super::retry;
 use super::types::{AgentResponse, AgentRole, AgentStats};
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use async_trait::async_trait;
 use chrono::Utc;
 use reqwest::Client;
@@ -106,25 +110,35 @@ impl ModelProvider for ClaudeProvider {
                 content: prompt.to_string(),
             }],
             system: Some(self.get_system_prompt().to_string()),
-            temperature: temperature as f32,
+            // Messages API 不支持 seed：固定种子模式下改用贪心解码
+            temperature: determinism::effective_temperature(temperature) as f32,
         };
 
-        let response = self
-            .client
-            .post("https://api.anthropic.com/v1/messages")
-            .header("x-api-key", &self.api_key)
-            .header("anthropic-version", "2023-06-01")
-            .header("content-type", "application/json")
-            .json(&request)
-            .send()
+        let claude_response: ClaudeResponse = retry::current()
+            .run("Claude", || async {
+                let response = self
+                    .client
+                    .post("https://api.anthropic.com/v1/messages")
+                    .header("x-api-key", &self.api_key)
+                    .header("anthropic-version", "2023-06-01")
+                    .header("content-type", "application/json")
+                    .json(&request)
+                    .send()
+                    .await?;
+
+                let status = response.status();
+                if !status.is_success() {
+                    let error_text = response.text().await?;
+                    return Err(AcsaError::new(
+                        ErrorCode::from_http_status(status.as_u16()),
+                        format!("Claude API error ({}): {}", status, error_text),
+                    )
+                    .into());
+                }
+
+                Ok(response.json().await?)
+            })
             .await?;
-
-        if !response.status().is_success() {
-            let error_text = response.text().await?;
-            return Err(anyhow!("Claude API error: {}", error_text));
-        }
-
-        let claude_response: ClaudeResponse = response.json().await?;
         let latency_ms = start.elapsed().as_millis() as u64;
 
         let text = claude_response
@@ -135,9 +149,12 @@ impl ModelProvider for ClaudeProvider {
 
         let total_tokens = claude_response.usage.input_tokens + claude_response.usage.output_tokens;
 
-        // Claude pricing: ~$15/1M input tokens, ~$75/1M output tokens (Opus)
-        let cost = (claude_response.usage.input_tokens as f64 / 1_000_000.0) * 15.0
-            + (claude_response.usage.output_tokens as f64 / 1_000_000.0) * 75.0;
+        let cost = api_manager::estimate_cost(
+            ApiProvider::Claude,
+            &self.model,
+            claude_response.usage.input_tokens,
+            claude_response.usage.output_tokens,
+        );
 
         let mut stats = self.stats.lock().await;
         stats.record_success(total_tokens, cost, latency_ms);
@@ -163,6 +180,14 @@ impl ModelProvider for ClaudeProvider {
         self.role
     }
 
+    fn model_id(&self) -> String {
+        format!("claude/{}", self.model)
+    }
+
+    fn billing(&self) -> Option<ApiProvider> {
+        Some(ApiProvider::Claude)
+    }
+
     async fn stats(&self) -> AgentStats {
         self.stats.lock().await.clone()
     }
@@ -0,0 +1,553 @@
+// Compliance Pack - 按司法辖区加载的合规规则包
+// Aegis 用规则包检查计划/文档，输出 通过 / 不通过 / 需人工复核 结论，并引用具体条文
+//
+// 核心功能：
+// 1. 规则包：内置 GDPR (EU)、PIPL (CN)、HIPAA-lite (US)，也可从 JSON 文件加载
+// 2. 辖区匹配：按文档/案件的司法辖区选择规则包，未指定辖区时使用全部规则包
+// 3. 适用判断：规则包 scope 与规则 applies_when 未命中则不适用，避免无关结论
+// 4. 合规附录：结论 + 条文引用 + 证据摘录，可附加到 Aegis 文档
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tracing::info;
+
+/// 合规结论
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComplianceVerdict {
+    Pass,
+    Fail,
+    NeedsReview,
+}
+
+impl ComplianceVerdict {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ComplianceVerdict::Pass => "✅ 通过",
+            ComplianceVerdict::Fail => "❌ 不通过",
+            ComplianceVerdict::NeedsReview => "⚠️ 需复核",
+        }
+    }
+}
+
+/// 合规规则（所有模式均为正则，匹配小写化后的文本）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComplianceRule {
+    pub id: String,
+    pub title: String,
+    /// 条文引用（如 "GDPR Art. 6(1)"、"PIPL 第三十八条"）
+    pub citation: String,
+    pub requirement: String,
+    /// 命中任一模式时规则才适用；为空表示规则包适用即适用
+    #[serde(default)]
+    pub applies_when: Vec<String>,
+    /// 满足要求的证据
+    #[serde(default)]
+    pub satisfied_by: Vec<String>,
+    /// 违反要求的表述（优先于证据）
+    #[serde(default)]
+    pub violated_by: Vec<String>,
+}
+
+/// 合规规则包
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompliancePack {
+    pub id: String,
+    pub name: String,
+    /// 适用辖区（如 "EU"、"EEA"、"CN"），大小写不敏感
+    pub jurisdictions: Vec<String>,
+    #[serde(default)]
+    pub version: String,
+    /// 文本命中任一模式时规则包才适用；为空表示总是适用
+    #[serde(default)]
+    pub scope: Vec<String>,
+    pub rules: Vec<ComplianceRule>,
+}
+
+impl CompliancePack {
+    /// 从 JSON 加载并校验所有模式
+    pub fn from_json(json: &str) -> Result<Self> {
+        let pack: Self = serde_json::from_str(json)?;
+        pack.compile()?;
+        Ok(pack)
+    }
+
+    /// 是否适用于指定辖区
+    pub fn covers(&self, jurisdiction: &str) -> bool {
+        self.jurisdictions.iter().any(|j| j.eq_ignore_ascii_case(jurisdiction.trim()))
+    }
+
+    fn compile(&self) -> Result<CompiledPack<'_>> {
+        let compile = |owner: &str, patterns: &[String]| -> Result<Vec<Regex>> {
+            patterns
+                .iter()
+                .map(|p| Regex::new(p).map_err(|e| anyhow!("Invalid pattern in {}: {}", owner, e)))
+                .collect()
+        };
+
+        let rules = self
+            .rules
+            .iter()
+            .map(|rule| {
+                Ok(CompiledRule {
+                    rule,
+                    applies_when: compile(&rule.id, &rule.applies_when)?,
+                    satisfied_by: compile(&rule.id, &rule.satisfied_by)?,
+                    violated_by: compile(&rule.id, &rule.violated_by)?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(CompiledPack {
+            pack: self,
+            scope: compile(&self.id, &self.scope)?,
+            rules,
+        })
+    }
+
+    /// GDPR 核心义务清单
+    pub fn gdpr() -> Self {
+        Self {
+            id: "gdpr".to_string(),
+            name: "GDPR".to_string(),
+            jurisdictions: vec!["EU".to_string(), "EEA".to_string()],
+            version: "2016/679".to_string(),
+            scope: patterns(&[r"personal data|user data|customer data|data subject|e-?mail address|个人(信息|数据)|用户数据"]),
+            rules: vec![
+                rule(
+                    "gdpr.lawful_basis",
+                    "Lawful basis for processing",
+                    "GDPR Art. 6(1)",
+                    "Processing of personal data must rely on consent, contract, legal obligation, vital or legitimate interests, or a public task.",
+                    &[],
+                    &[r"consent|legitimate interest|contractual necessity|legal obligation|lawful basis|同意"],
+                    &[r"without (their |the user'?s? |prior )?consent|未经(用户)?同意"],
+                ),
+                rule(
+                    "gdpr.data_minimisation",
+                    "Data minimisation",
+                    "GDPR Art. 5(1)(c)",
+                    "Personal data must be adequate, relevant and limited to what is necessary.",
+                    &[],
+                    &[r"minimi[sz]|only (the )?(necessary|required|public) (fields|data)|only public fields"],
+                    &[r"(collect|scrape|harvest) (all|every|as much)"],
+                ),
+                rule(
+                    "gdpr.international_transfer",
+                    "Transfers to third countries",
+                    "GDPR Art. 44-46",
+                    "Transfers outside the EEA need an adequacy decision or appropriate safeguards.",
+                    &[r"transfer|outside the (eu|eea)|third countr|cross-border|跨境|出境"],
+                    &[r"standard contractual clauses|\bsccs?\b|adequacy decision|binding corporate rules"],
+                    &[],
+                ),
+                rule(
+                    "gdpr.breach_notification",
+                    "Breach notification",
+                    "GDPR Art. 33",
+                    "Personal data breaches must be notified to the supervisory authority within 72 hours.",
+                    &[r"breach|incident|leak|泄露"],
+                    &[r"72 hours|supervisory authority"],
+                    &[r"(not|never) (notify|disclose|report)|conceal|cover[- ]up|隐瞒"],
+                ),
+                rule(
+                    "gdpr.storage_limitation",
+                    "Storage limitation and erasure",
+                    "GDPR Art. 5(1)(e), Art. 17",
+                    "Personal data must not be kept longer than necessary and must be erasable on request.",
+                    &[r"retain|retention|stor(e|age)|archive|保存|存储"],
+                    &[r"delet|erasure|retention period|删除"],
+                    &[r"indefinite|forever|permanently (retain|store|keep)|永久保存"],
+                ),
+            ],
+        }
+    }
+
+    /// PIPL（个人信息保护法）核心义务清单
+    pub fn pipl() -> Self {
+        Self {
+            id: "pipl".to_string(),
+            name: "PIPL 个人信息保护法".to_string(),
+            jurisdictions: vec!["CN".to_string(), "PRC".to_string()],
+            version: "2021".to_string(),
+            scope: patterns(&[r"个人信息|个人数据|用户数据|personal (information|data)|user data|customer data"]),
+            rules: vec![
+                rule(
+                    "pipl.consent",
+                    "告知-同意",
+                    "PIPL 第十三条、第十七条",
+                    "处理个人信息应取得个人同意并充分告知处理目的、方式和种类。",
+                    &[],
+                    &[r"同意|告知|consent|privacy notice"],
+                    &[r"未经(用户|个人)?同意|without (their |the user'?s? )?consent"],
+                ),
+                rule(
+                    "pipl.sensitive",
+                    "敏感个人信息单独同意",
+                    "PIPL 第二十八条、第二十九条",
+                    "处理敏感个人信息应具有特定目的和充分必要性，并取得单独同意。",
+                    &[r"敏感个人信息|生物识别|人脸|医疗|健康|金融账户|行踪|biometric|health|financial account"],
+                    &[r"单独同意|separate consent"],
+                    &[],
+                ),
+                rule(
+                    "pipl.cross_border",
+                    "个人信息出境",
+                    "PIPL 第三十八条",
+                    "向境外提供个人信息须通过安全评估、认证或订立标准合同。",
+                    &[r"出境|跨境|境外|cross-border|overseas|outside (china|the prc)"],
+                    &[r"安全评估|标准合同|认证|security assessment|standard contract|certification"],
+                    &[],
+                ),
+                rule(
+                    "pipl.impact_assessment",
+                    "个人信息保护影响评估",
+                    "PIPL 第五十五条",
+                    "处理敏感个人信息、自动化决策、委托处理或向境外提供前，应进行影响评估。",
+                    &[r"敏感个人信息|自动化决策|委托处理|出境|境外|automated decision|cross-border"],
+                    &[r"影响评估|impact assessment|\bpia\b|\bdpia\b"],
+                    &[],
+                ),
+            ],
+        }
+    }
+
+    /// HIPAA 精简版（仅核心安全与隐私义务）
+    pub fn hipaa_lite() -> Self {
+        Self {
+            id: "hipaa_lite".to_string(),
+            name: "HIPAA-lite".to_string(),
+            jurisdictions: vec!["US".to_string(), "USA".to_string()],
+            version: "45 CFR 164".to_string(),
+            scope: patterns(&[r"\bphi\b|protected health|patient|medical record|health (data|information|record)"]),
+            rules: vec![
+                rule(
+                    "hipaa.safeguards",
+                    "Technical safeguards",
+                    "45 CFR §164.312",
+                    "ePHI requires access control, audit controls and transmission security.",
+                    &[],
+                    &[r"encrypt|access control|audit (log|control|trail)"],
+                    &[r"plain ?text|unencrypted"],
+                ),
+                rule(
+                    "hipaa.minimum_necessary",
+                    "Minimum necessary",
+                    "45 CFR §164.502(b)",
+                    "Uses and disclosures of PHI must be limited to the minimum necessary.",
+                    &[],
+                    &[r"minimum necessary|only (the )?(necessary|required) (fields|data)|de-?identif"],
+                    &[r"full (medical )?records?|all patient (data|records)"],
+                ),
+                rule(
+                    "hipaa.business_associate",
+                    "Business associate agreement",
+                    "45 CFR §164.504(e)",
+                    "Vendors handling PHI must sign a business associate agreement.",
+                    &[r"vendor|third[- ]party|business associate|contractor|cloud provider"],
+                    &[r"business associate agreement|\bbaa\b"],
+                    &[],
+                ),
+                rule(
+                    "hipaa.breach_notification",
+                    "Breach notification",
+                    "45 CFR §164.404",
+                    "Affected individuals must be notified of a breach of unsecured PHI within 60 days.",
+                    &[r"breach|incident|leak"],
+                    &[r"60 days|notify (the )?(affected )?individuals"],
+                    &[r"(not|never) (notify|disclose|report)|conceal|cover[- ]up"],
+                ),
+            ],
+        }
+    }
+}
+
+fn patterns(list: &[&str]) -> Vec<String> {
+    list.iter().map(|p| p.to_string()).collect()
+}
+
+fn rule(
+    id: &str,
+    title: &str,
+    citation: &str,
+    requirement: &str,
+    applies_when: &[&str],
+    satisfied_by: &[&str],
+    violated_by: &[&str],
+) -> ComplianceRule {
+    ComplianceRule {
+        id: id.to_string(),
+        title: title.to_string(),
+        citation: citation.to_string(),
+        requirement: requirement.to_string(),
+        applies_when: patterns(applies_when),
+        satisfied_by: patterns(satisfied_by),
+        violated_by: patterns(violated_by),
+    }
+}
+
+struct CompiledPack<'a> {
+    pack: &'a CompliancePack,
+    scope: Vec<Regex>,
+    rules: Vec<CompiledRule<'a>>,
+}
+
+struct CompiledRule<'a> {
+    rule: &'a ComplianceRule,
+    applies_when: Vec<Regex>,
+    satisfied_by: Vec<Regex>,
+    violated_by: Vec<Regex>,
+}
+
+/// 单条规则的检查结论
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComplianceFinding {
+    pub pack_id: String,
+    pub rule_id: String,
+    pub title: String,
+    pub citation: String,
+    pub verdict: ComplianceVerdict,
+    /// 命中的原文摘录（需复核时为空）
+    pub evidence: Option<String>,
+    pub requirement: String,
+}
+
+/// 合规附录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComplianceAnnex {
+    pub jurisdiction: Option<String>,
+    /// 实际适用的规则包名称
+    pub packs: Vec<String>,
+    pub findings: Vec<ComplianceFinding>,
+    pub generated_at: DateTime<Utc>,
+}
+
+impl ComplianceAnnex {
+    pub fn count(&self, verdict: ComplianceVerdict) -> usize {
+        self.findings.iter().filter(|f| f.verdict == verdict).count()
+    }
+
+    pub fn has_failures(&self) -> bool {
+        self.count(ComplianceVerdict::Fail) > 0
+    }
+
+    /// 导出 Markdown（不通过 → 需复核 → 通过）
+    pub fn to_markdown(&self) -> String {
+        let mut md = format!(
+            "## 合规附录 (Compliance Annex)\n\n辖区: {} · 规则包: {} · 通过 {} / 不通过 {} / 需复核 {}\n\n",
+            self.jurisdiction.as_deref().unwrap_or("全部"),
+            if self.packs.is_empty() { "-".to_string() } else { self.packs.join(", ") },
+            self.count(ComplianceVerdict::Pass),
+            self.count(ComplianceVerdict::Fail),
+            self.count(ComplianceVerdict::NeedsReview)
+        );
+
+        for verdict in [ComplianceVerdict::Fail, ComplianceVerdict::NeedsReview, ComplianceVerdict::Pass] {
+            for finding in self.findings.iter().filter(|f| f.verdict == verdict) {
+                md.push_str(&format!("- {} **[{}]** {}", verdict.label(), finding.citation, finding.title));
+                match &finding.evidence {
+                    Some(evidence) => md.push_str(&format!(" — “{}”\n", evidence)),
+                    None => md.push_str(&format!(" — {}\n", finding.requirement)),
+                }
+            }
+        }
+        md
+    }
+}
+
+/// 合规检查引擎
+#[derive(Debug, Clone)]
+pub struct ComplianceEngine {
+    packs: Vec<CompliancePack>,
+}
+
+impl ComplianceEngine {
+    /// 不含任何规则包
+    pub fn empty() -> Self {
+        Self { packs: Vec::new() }
+    }
+
+    /// 内置 GDPR / PIPL / HIPAA-lite
+    pub fn with_builtin_packs() -> Self {
+        Self {
+            packs: vec![CompliancePack::gdpr(), CompliancePack::pipl(), CompliancePack::hipaa_lite()],
+        }
+    }
+
+    pub fn packs(&self) -> &[CompliancePack] {
+        &self.packs
+    }
+
+    /// 追加或替换同 ID 规则包
+    pub fn add_pack(&mut self, pack: CompliancePack) -> Result<()> {
+        pack.compile()?;
+        self.packs.retain(|p| p.id != pack.id);
+        info!("📚 Compliance pack loaded: {} ({} rules)", pack.name, pack.rules.len());
+        self.packs.push(pack);
+        Ok(())
+    }
+
+    /// 从 JSON 文件加载规则包
+    pub async fn load_pack(&mut self, path: &Path) -> Result<()> {
+        let json = tokio::fs::read_to_string(path).await?;
+        let pack = CompliancePack::from_json(&json)
+            .map_err(|e| anyhow!("Failed to load compliance pack {}: {}", path.display(), e))?;
+        self.add_pack(pack)
+    }
+
+    /// 指定辖区的规则包；未指定辖区时返回全部
+    pub fn packs_for(&self, jurisdiction: Option<&str>) -> Vec<&CompliancePack> {
+        self.packs
+            .iter()
+            .filter(|p| jurisdiction.is_none_or(|j| p.covers(j)))
+            .collect()
+    }
+
+    /// 检查计划/文档文本
+    pub fn evaluate(&self, text: &str, jurisdiction: Option<&str>) -> Result<ComplianceAnnex> {
+        let lower = text.to_lowercase();
+        let mut packs = Vec::new();
+        let mut findings = Vec::new();
+
+        for pack in self.packs_for(jurisdiction) {
+            let compiled = pack.compile()?;
+            if !compiled.scope.is_empty() && !compiled.scope.iter().any(|p| p.is_match(&lower)) {
+                continue;
+            }
+            packs.push(pack.name.clone());
+
+            for compiled_rule in &compiled.rules {
+                if !compiled_rule.applies_when.is_empty() && !compiled_rule.applies_when.iter().any(|p| p.is_match(&lower))
+                {
+                    continue;
+                }
+
+                let violation = compiled_rule.violated_by.iter().find_map(|p| p.find(&lower));
+                let satisfied = compiled_rule.satisfied_by.iter().find_map(|p| p.find(&lower));
+                let (verdict, hit) = match (violation, satisfied) {
+                    (Some(hit), _) => (ComplianceVerdict::Fail, Some(hit)),
+                    (None, Some(hit)) => (ComplianceVerdict::Pass, Some(hit)),
+                    (None, None) => (ComplianceVerdict::NeedsReview, None),
+                };
+
+                let rule = compiled_rule.rule;
+                findings.push(ComplianceFinding {
+                    pack_id: compiled.pack.id.clone(),
+                    rule_id: rule.id.clone(),
+                    title: rule.title.clone(),
+                    citation: rule.citation.clone(),
+                    verdict,
+                    evidence: hit.map(|h| excerpt(&lower, text, h.start(), h.end())),
+                    requirement: rule.requirement.clone(),
+                });
+            }
+        }
+
+        info!(
+            "📚 Compliance evaluated against {} pack(s): {} finding(s)",
+            packs.len(),
+            findings.len()
+        );
+        Ok(ComplianceAnnex {
+            jurisdiction: jurisdiction.map(|j| j.to_string()),
+            packs,
+            findings,
+            generated_at: Utc::now(),
+        })
+    }
+}
+
+impl Default for ComplianceEngine {
+    fn default() -> Self {
+        Self::with_builtin_packs()
+    }
+}
+
+/// 命中位置所在行的原文摘录（小写化可能改变字节长度，无法对齐时退回小写文本）
+fn excerpt(lower: &str, original: &str, start: usize, end: usize) -> String {
+    let source = if lower.len() == original.len() { original } else { lower };
+    let line_start = source[..start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let line_end = source[end..].find('\n').map(|i| end + i).unwrap_or(source.len());
+    let line = source[line_start..line_end].trim();
+    match line.char_indices().nth(160) {
+        Some((idx, _)) => format!("{}…", &line[..idx]),
+        None => line.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gdpr_verdicts() {
+        let engine = ComplianceEngine::default();
+        let plan = "Export customer data for the campaign without consent.\n\
+                    Data is transferred to a US vendor under standard contractual clauses.\n\
+                    Records are stored indefinitely.";
+        let annex = engine.evaluate(plan, Some("eu")).unwrap();
+
+        assert_eq!(annex.packs, vec!["GDPR"]);
+        let verdict = |id: &str| annex.findings.iter().find(|f| f.rule_id == id).map(|f| f.verdict);
+        assert_eq!(verdict("gdpr.lawful_basis"), Some(ComplianceVerdict::Fail));
+        assert_eq!(verdict("gdpr.international_transfer"), Some(ComplianceVerdict::Pass));
+        assert_eq!(verdict("gdpr.storage_limitation"), Some(ComplianceVerdict::Fail));
+        assert_eq!(verdict("gdpr.data_minimisation"), Some(ComplianceVerdict::NeedsReview));
+        // 未提及泄露，规则不适用
+        assert_eq!(verdict("gdpr.breach_notification"), None);
+        assert!(annex.has_failures());
+
+        let transfer = annex.findings.iter().find(|f| f.rule_id == "gdpr.international_transfer").unwrap();
+        assert_eq!(
+            transfer.evidence.as_deref(),
+            Some("Data is transferred to a US vendor under standard contractual clauses.")
+        );
+
+        let md = annex.to_markdown();
+        assert!(md.contains("❌ 不通过 **[GDPR Art. 6(1)]** Lawful basis for processing"));
+        assert!(md.find("❌").unwrap() < md.find("✅").unwrap());
+    }
+
+    #[test]
+    fn test_jurisdiction_and_scope() {
+        let engine = ComplianceEngine::default();
+        let plan = "收集用户数据前取得用户同意，个人信息出境前完成安全评估。";
+
+        let annex = engine.evaluate(plan, Some("CN")).unwrap();
+        assert_eq!(annex.packs, vec!["PIPL 个人信息保护法"]);
+        assert!(!annex.has_failures());
+        assert_eq!(annex.count(ComplianceVerdict::NeedsReview), 1); // 影响评估未提及
+
+        // 未指定辖区：GDPR 与 PIPL 都适用，HIPAA 不在范围内
+        let annex = engine.evaluate(plan, None).unwrap();
+        assert_eq!(annex.packs.len(), 2);
+        assert!(engine.evaluate("Refactor the build script", None).unwrap().findings.is_empty());
+    }
+
+    #[test]
+    fn test_custom_pack_from_json() {
+        let json = r#"{
+            "id": "internal",
+            "name": "Internal Policy",
+            "jurisdictions": ["EU", "CN"],
+            "rules": [{
+                "id": "internal.dpo_signoff",
+                "title": "DPO sign-off",
+                "citation": "Policy §2.1",
+                "requirement": "Launches require DPO sign-off.",
+                "satisfied_by": ["dpo (approved|signed)"]
+            }]
+        }"#;
+
+        let mut engine = ComplianceEngine::empty();
+        engine.add_pack(CompliancePack::from_json(json).unwrap()).unwrap();
+        let annex = engine.evaluate("Launch approved; DPO signed off.", Some("CN")).unwrap();
+        assert_eq!(annex.findings[0].verdict, ComplianceVerdict::Pass);
+        assert!(engine.evaluate("Launch", Some("US")).unwrap().packs.is_empty());
+
+        assert!(CompliancePack::from_json(&json.replace("dpo (approved|signed)", "(")).is_err());
+    }
+}
@@ -7,10 +7,15 @@
 // 3. 分布式锁：跨进程任务协调
 // 4. 任务队列：优先级队列 + 公平调度
 // 5. 背压控制：防止系统过载
+// 6. 任务上下文：截止时间与取消信号沿任务树向下传播
 
 use std::collections::{HashMap, VecDeque};
-use std::sync::Arc;
-use tokio::sync::{RwLock, Semaphore, Mutex as TokioMutex};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+use tokio::sync::{oneshot, Notify, RwLock, Semaphore, Mutex as TokioMutex};
 use tokio::task::JoinHandle;
 use anyhow::{anyhow, Result};
 use chrono::{DateTime, Utc};
@@ -26,6 +31,39 @@ pub struct ConcurrencyConfig {
     pub task_timeout_secs: u64,          // 任务超时时间
     pub enable_backpressure: bool,       // 启用背压控制
     pub max_queue_size: usize,           // 最大队列大小
+    #[serde(default = "default_priority_queue_size")]
+    pub priority_queue_size: usize,      // 每个优先级队列的容量
+    #[serde(default = "default_starvation_threshold_ms")]
+    pub starvation_threshold_ms: u64,    // 低优先级任务等待超过该时间后优先调度
+    #[serde(default = "default_local_batch_size")]
+    pub local_batch_size: usize,         // worker 每次从全局队列搬运到本地队列的任务数
+    #[serde(default)]
+    pub backpressure_policy: BackpressurePolicy, // 队列满时的处理策略
+}
+
+/// 背压策略（队列满时如何处理新任务）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(tag = "policy", rename_all = "snake_case")]
+pub enum BackpressurePolicy {
+    /// 直接拒绝新任务
+    #[default]
+    Reject,
+    /// 丢弃排队中优先级最低（且低于新任务）的最新任务
+    ShedLowest,
+    /// 等待队列腾出空间，超时后拒绝
+    AwaitWithTimeout { timeout_ms: u64 },
+}
+
+fn default_priority_queue_size() -> usize {
+    2000
+}
+
+fn default_starvation_threshold_ms() -> u64 {
+    5000
+}
+
+fn default_local_batch_size() -> usize {
+    4
 }
 
 impl Default for ConcurrencyConfig {
@@ -40,12 +78,16 @@ impl Default for ConcurrencyConfig {
             task_timeout_secs: 300,  // 5分钟
             enable_backpressure: true,
             max_queue_size: 10000,
+            priority_queue_size: default_priority_queue_size(),
+            starvation_threshold_ms: default_starvation_threshold_ms(),
+            local_batch_size: default_local_batch_size(),
+            backpressure_policy: BackpressurePolicy::default(),
         }
     }
 }
 
 /// 任务优先级
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum TaskPriority {
     Critical = 4,
     High = 3,
@@ -54,6 +96,23 @@ pub enum TaskPriority {
     Background = 0,
 }
 
+const PRIORITY_LEVELS: usize = 5;
+
+impl TaskPriority {
+    /// 从低到高的所有优先级
+    pub const ALL: [TaskPriority; PRIORITY_LEVELS] = [
+        TaskPriority::Background,
+        TaskPriority::Low,
+        TaskPriority::Normal,
+        TaskPriority::High,
+        TaskPriority::Critical,
+    ];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
 /// 异步任务
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AsyncTask {
@@ -74,6 +133,748 @@ pub struct TaskResult {
     pub error: Option<String>,
 }
 
+struct CancelInner {
+    cancelled: AtomicBool,
+    notify: Notify,
+    children: Mutex<Vec<Weak<CancelInner>>>,
+}
+
+impl CancelInner {
+    fn cancel(&self) {
+        if self.cancelled.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        self.notify.notify_waiters();
+
+        let children = std::mem::take(&mut *self.children.lock().unwrap());
+        for child in children.iter().filter_map(Weak::upgrade) {
+            child.cancel();
+        }
+    }
+}
+
+/// 取消令牌：取消父令牌会级联取消所有子令牌
+#[derive(Clone)]
+pub struct CancellationToken {
+    inner: Arc<CancelInner>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(CancelInner {
+                cancelled: AtomicBool::new(false),
+                notify: Notify::new(),
+                children: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+
+    /// 创建子令牌（父令牌已取消时子令牌立即处于取消状态）
+    pub fn child_token(&self) -> Self {
+        let child = Self::new();
+        {
+            let mut children = self.inner.children.lock().unwrap();
+            children.retain(|weak| weak.strong_count() > 0);
+            children.push(Arc::downgrade(&child.inner));
+        }
+        if self.is_cancelled() {
+            child.cancel();
+        }
+        child
+    }
+
+    pub fn cancel(&self) {
+        self.inner.cancel();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// 等待取消
+    pub async fn cancelled(&self) {
+        loop {
+            let notified = self.inner.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+impl Default for CancellationToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+tokio::task_local! {
+    static CURRENT_CONTEXT: TaskContext;
+}
+
+/// 任务上下文：携带截止时间与取消令牌
+///
+/// 执行器会把上下文注入任务（`TaskContext::current()` 可取得），
+/// 任务内再提交的子任务、发起的 provider 调用都继承同一截止时间和取消信号。
+#[derive(Clone)]
+pub struct TaskContext {
+    task_id: String,
+    deadline: Option<Instant>,
+    token: CancellationToken,
+}
+
+impl TaskContext {
+    pub fn new(task_id: impl Into<String>) -> Self {
+        Self {
+            task_id: task_id.into(),
+            deadline: None,
+            token: CancellationToken::new(),
+        }
+    }
+
+    /// 设置截止时间（只能收紧，不能放宽已有截止时间）
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(self.deadline.map_or(deadline, |d| d.min(deadline)));
+        self
+    }
+
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        self.with_deadline(Instant::now() + timeout)
+    }
+
+    /// 派生子上下文：继承截止时间，父上下文取消时一并取消
+    pub fn child(&self, task_id: impl Into<String>) -> Self {
+        Self {
+            task_id: task_id.into(),
+            deadline: self.deadline,
+            token: self.token.child_token(),
+        }
+    }
+
+    /// 当前任务的上下文（不在执行器任务内时为 None）
+    pub fn current() -> Option<Self> {
+        CURRENT_CONTEXT.try_with(|ctx| ctx.clone()).ok()
+    }
+
+    /// 当前上下文的子上下文，不存在时新建根上下文
+    pub fn current_child(task_id: impl Into<String>) -> Self {
+        match Self::current() {
+            Some(ctx) => ctx.child(task_id),
+            None => Self::new(task_id),
+        }
+    }
+
+    pub fn task_id(&self) -> &str {
+        &self.task_id
+    }
+
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    pub fn token(&self) -> &CancellationToken {
+        &self.token
+    }
+
+    pub fn cancel(&self) {
+        self.token.cancel();
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.deadline.is_some_and(|d| Instant::now() >= d)
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled() || self.is_expired()
+    }
+
+    /// 距截止时间的剩余时间
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline.map(|d| d.saturating_duration_since(Instant::now()))
+    }
+
+    /// 协作式取消检查点：已取消或已超时则返回错误
+    pub fn checkpoint(&self) -> Result<()> {
+        if self.token.is_cancelled() {
+            Err(anyhow!("Task cancelled: {}", self.task_id))
+        } else if self.is_expired() {
+            Err(anyhow!("Task deadline exceeded: {}", self.task_id))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// 当前上下文的检查点（无上下文时总是通过）
+    pub fn checkpoint_current() -> Result<()> {
+        Self::current().map_or(Ok(()), |ctx| ctx.checkpoint())
+    }
+
+    /// 运行 future：取消或超时时丢弃 future（从而中止其中的网络请求）
+    pub async fn run<T, F>(&self, future: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        self.checkpoint()?;
+
+        let deadline = async {
+            match self.deadline {
+                Some(deadline) => tokio::time::sleep_until(deadline.into()).await,
+                None => std::future::pending().await,
+            }
+        };
+
+        tokio::select! {
+            result = future => result,
+            _ = self.token.cancelled() => Err(anyhow!("Task cancelled: {}", self.task_id)),
+            _ = deadline => Err(anyhow!("Task deadline exceeded: {}", self.task_id)),
+        }
+    }
+
+    /// 在当前上下文下运行 future（无上下文时直接运行）
+    pub async fn guard<T, F>(future: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        match Self::current() {
+            Some(ctx) => ctx.run(future).await,
+            None => future.await,
+        }
+    }
+
+    /// 把上下文设为 future 执行期间的当前上下文
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        CURRENT_CONTEXT.scope(self, future).await
+    }
+}
+
+/// 任务句柄：提交后立即返回，可等待结果
+pub struct TaskHandle {
+    pub task_id: String,
+    receiver: oneshot::Receiver<TaskResult>,
+    context: TaskContext,
+}
+
+impl TaskHandle {
+    /// 任务上下文
+    pub fn context(&self) -> &TaskContext {
+        &self.context
+    }
+
+    /// 请求取消任务（排队中的任务不会再执行，运行中的任务在下一个检查点停止）
+    pub fn cancel(&self) {
+        self.context.cancel();
+    }
+
+    /// 等待任务完成
+    pub async fn wait(self) -> Result<TaskResult> {
+        self.receiver
+            .await
+            .map_err(|_| anyhow!("Task dropped before completion: {}", self.task_id))
+    }
+}
+
+/// 执行器指标快照
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExecutorMetrics {
+    pub submitted: u64,
+    pub completed: u64,
+    pub failed: u64,
+    pub rejected: u64,
+    /// 通过工作窃取获得的任务数
+    pub stolen: u64,
+    /// 因饥饿保护被提前调度的任务数
+    pub starvation_promotions: u64,
+    /// 各优先级全局队列深度
+    pub queue_depth: HashMap<TaskPriority, usize>,
+    /// 各 worker 本地队列深度之和
+    pub local_queue_depth: usize,
+    /// 各优先级平均排队延迟（毫秒）
+    pub avg_wait_ms: HashMap<TaskPriority, f64>,
+    /// 平均执行时间（毫秒）
+    pub avg_run_ms: f64,
+    /// 因背压被丢弃的任务数
+    pub shed: u64,
+    /// 等待入队超时的任务数
+    pub admission_timeouts: u64,
+}
+
+/// 饱和度指标（用于扩缩容决策）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SaturationMetrics {
+    /// 排队中（未开始执行）的任务数
+    pub queued: usize,
+    /// 队列总容量
+    pub queue_capacity: usize,
+    /// 队列占用率 (0-1)
+    pub queue_utilization: f64,
+    pub busy_workers: usize,
+    pub worker_count: usize,
+    /// worker 占用率 (0-1)
+    pub worker_utilization: f64,
+    /// 累计被拒绝/丢弃的任务数
+    pub rejected: u64,
+    pub shed: u64,
+    /// 平均入队等待时间（毫秒，仅 AwaitWithTimeout 策略）
+    pub avg_admission_wait_ms: f64,
+}
+
+type BoxedJob = Pin<Box<dyn Future<Output = Result<()>> + Send + 'static>>;
+
+struct QueuedJob {
+    task: AsyncTask,
+    context: TaskContext,
+    future: BoxedJob,
+    enqueued_at: Instant,
+    result_tx: oneshot::Sender<TaskResult>,
+}
+
+/// 饥饿任务所在位置
+enum StarvingSlot {
+    /// 全局队列（下标即优先级）
+    Global(usize),
+    /// 某个 worker 的本地队列
+    Local { owner: usize, index: usize, task_id: String },
+}
+
+#[derive(Default)]
+struct ExecutorStats {
+    submitted: u64,
+    completed: u64,
+    failed: u64,
+    rejected: u64,
+    stolen: u64,
+    starvation_promotions: u64,
+    shed: u64,
+    admission_timeouts: u64,
+    admission_wait_total_ms: f64,
+    admission_waits: u64,
+    wait_total_ms: [f64; PRIORITY_LEVELS],
+    wait_count: [u64; PRIORITY_LEVELS],
+    run_total_ms: f64,
+}
+
+/// 工作窃取优先级执行器（各 worker 共享）
+///
+/// 调度顺序：饥饿任务 > 本地队列与全局最高优先级中较高者 > 窃取其他 worker 的本地队列。
+/// 同时执行的任务数受 `max_concurrent_tasks` 信号量约束。
+struct ExecutorShared {
+    config: ConcurrencyConfig,
+    /// 执行许可（与 `ConcurrencyManager` 共享，上限为 `max_concurrent_tasks`）
+    permits: Arc<Semaphore>,
+    /// 按优先级划分的有界全局队列（下标即优先级）
+    queues: Vec<Mutex<VecDeque<QueuedJob>>>,
+    /// 每个 worker 的本地队列
+    locals: Vec<Mutex<VecDeque<QueuedJob>>>,
+    notify: Notify,
+    shutdown: AtomicBool,
+    /// 已提交但尚未完成的任务数
+    in_flight: AtomicUsize,
+    /// 排队中（未开始执行）的任务数
+    queued: AtomicUsize,
+    busy_workers: AtomicUsize,
+    /// 有任务出队时通知等待入队的提交者
+    space: Notify,
+    /// 未完成任务的上下文（用于按 ID 取消）
+    contexts: Mutex<HashMap<String, TaskContext>>,
+    stats: Mutex<ExecutorStats>,
+    results: Arc<RwLock<HashMap<String, TaskResult>>>,
+}
+
+impl ExecutorShared {
+    fn new(
+        config: ConcurrencyConfig,
+        permits: Arc<Semaphore>,
+        results: Arc<RwLock<HashMap<String, TaskResult>>>,
+    ) -> Self {
+        let workers = config.worker_threads.max(1);
+        Self {
+            permits,
+            queues: (0..PRIORITY_LEVELS).map(|_| Mutex::new(VecDeque::new())).collect(),
+            locals: (0..workers).map(|_| Mutex::new(VecDeque::new())).collect(),
+            notify: Notify::new(),
+            shutdown: AtomicBool::new(false),
+            in_flight: AtomicUsize::new(0),
+            queued: AtomicUsize::new(0),
+            busy_workers: AtomicUsize::new(0),
+            space: Notify::new(),
+            contexts: Mutex::new(HashMap::new()),
+            stats: Mutex::new(ExecutorStats::default()),
+            results,
+            config,
+        }
+    }
+
+    fn is_full(&self, priority: TaskPriority) -> bool {
+        self.config.enable_backpressure
+            && (self.queues[priority.index()].lock().unwrap().len() >= self.config.priority_queue_size
+                || self.queued.load(Ordering::SeqCst) >= self.config.max_queue_size)
+    }
+
+    /// 按背压策略入队
+    async fn enqueue(&self, job: QueuedJob) -> Result<()> {
+        let priority = job.task.priority;
+
+        let Some(job) = self.try_enqueue(job) else {
+            return Ok(());
+        };
+
+        match self.config.backpressure_policy {
+            BackpressurePolicy::Reject => {}
+            BackpressurePolicy::ShedLowest => {
+                if self.shed_lowest(priority) {
+                    return match self.try_enqueue(job) {
+                        Some(job) => Err(self.reject(&job)),
+                        None => Ok(()),
+                    };
+                }
+            }
+            BackpressurePolicy::AwaitWithTimeout { timeout_ms } => {
+                let started = Instant::now();
+                let deadline = started + Duration::from_millis(timeout_ms);
+                let mut job = job;
+
+                loop {
+                    let space = self.space.notified();
+                    job = match self.try_enqueue(job) {
+                        Some(job) => job,
+                        None => {
+                            let mut stats = self.stats.lock().unwrap();
+                            stats.admission_wait_total_ms += started.elapsed().as_secs_f64() * 1000.0;
+                            stats.admission_waits += 1;
+                            return Ok(());
+                        }
+                    };
+
+                    if tokio::time::timeout_at(deadline.into(), space).await.is_err() {
+                        self.stats.lock().unwrap().admission_timeouts += 1;
+                        return Err(self.reject(&job));
+                    }
+                }
+            }
+        }
+
+        Err(self.reject(&job))
+    }
+
+    /// 尝试入队，队列已满时把任务交还给调用方
+    fn try_enqueue(&self, job: QueuedJob) -> Option<QueuedJob> {
+        let priority = job.task.priority;
+        if self.is_full(priority) {
+            return Some(job);
+        }
+
+        self.contexts.lock().unwrap().insert(job.task.id.clone(), job.context.clone());
+        self.queues[priority.index()].lock().unwrap().push_back(job);
+        self.queued.fetch_add(1, Ordering::SeqCst);
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        self.stats.lock().unwrap().submitted += 1;
+        self.notify.notify_one();
+        None
+    }
+
+    fn reject(&self, job: &QueuedJob) -> anyhow::Error {
+        self.stats.lock().unwrap().rejected += 1;
+        warn!("🚧 Task rejected by backpressure: {} ({:?})", job.task.name, job.task.priority);
+        anyhow!(
+            "{:?} queue full ({} queued), task rejected: {}",
+            job.task.priority,
+            self.queued.load(Ordering::SeqCst),
+            job.task.id
+        )
+    }
+
+    /// 丢弃一个优先级低于 `incoming` 的最新排队任务
+    fn shed_lowest(&self, incoming: TaskPriority) -> bool {
+        for p in 0..incoming.index() {
+            let Some(victim) = self.queues[p].lock().unwrap().pop_back() else {
+                continue;
+            };
+
+            self.queued.fetch_sub(1, Ordering::SeqCst);
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            self.contexts.lock().unwrap().remove(&victim.task.id);
+            self.stats.lock().unwrap().shed += 1;
+            warn!("🗑️  Shed task under backpressure: {} ({:?})", victim.task.name, victim.task.priority);
+
+            let _ = victim.result_tx.send(TaskResult {
+                task_id: victim.task.id,
+                success: false,
+                duration_ms: 0,
+                error: Some("Task shed under backpressure".to_string()),
+            });
+            return true;
+        }
+        false
+    }
+
+    fn next_job(&self, worker_id: usize) -> Option<QueuedJob> {
+        if let Some(job) = self.take_starving(worker_id) {
+            return Some(job);
+        }
+
+        let local_priority = self.locals[worker_id]
+            .lock()
+            .unwrap()
+            .front()
+            .map(|job| job.task.priority.index());
+        let global_priority = (0..PRIORITY_LEVELS)
+            .rev()
+            .find(|&p| !self.queues[p].lock().unwrap().is_empty());
+
+        match (local_priority, global_priority) {
+            (Some(local), Some(global)) if global > local => self.take_global(worker_id, global),
+            (Some(_), _) => self.locals[worker_id].lock().unwrap().pop_front(),
+            (None, Some(global)) => self.take_global(worker_id, global),
+            (None, None) => self.steal(worker_id),
+        }
+    }
+
+    /// 饥饿保护：全局队列头部及各 worker 本地队列中等待超过阈值的任务里，取等待最久的一个
+    fn take_starving(&self, worker_id: usize) -> Option<QueuedJob> {
+        let threshold = Duration::from_millis(self.config.starvation_threshold_ms);
+        let mut oldest: Option<(StarvingSlot, Instant)> = None;
+
+        for p in 0..PRIORITY_LEVELS {
+            if let Some(head) = self.queues[p].lock().unwrap().front() {
+                let waited_long = head.enqueued_at.elapsed() >= threshold;
+                if waited_long && oldest.as_ref().is_none_or(|(_, at)| head.enqueued_at < *at) {
+                    oldest = Some((StarvingSlot::Global(p), head.enqueued_at));
+                }
+            }
+        }
+
+        // 本地队列可能混有不同优先级的批次，需逐个检查
+        for (owner, local) in self.locals.iter().enumerate() {
+            for (index, job) in local.lock().unwrap().iter().enumerate() {
+                let waited_long = job.enqueued_at.elapsed() >= threshold;
+                if waited_long && oldest.as_ref().is_none_or(|(_, at)| job.enqueued_at < *at) {
+                    oldest = Some((
+                        StarvingSlot::Local { owner, index, task_id: job.task.id.clone() },
+                        job.enqueued_at,
+                    ));
+                }
+            }
+        }
+
+        let (slot, _) = oldest?;
+        let highest = (0..PRIORITY_LEVELS)
+            .rev()
+            .find(|&p| !self.queues[p].lock().unwrap().is_empty());
+
+        let job = match slot {
+            StarvingSlot::Global(priority) => {
+                if Some(priority) == highest {
+                    // 本来就会被调度，无需提升
+                    return None;
+                }
+                self.queues[priority].lock().unwrap().pop_front()?
+            }
+            StarvingSlot::Local { owner, index, task_id } => {
+                let mut local = self.locals[owner].lock().unwrap();
+                let front_priority = local.front().map(|job| job.task.priority.index());
+                if owner == worker_id && index == 0 && front_priority >= highest {
+                    // 本 worker 的本地队首本来就会被调度，无需提升
+                    return None;
+                }
+                // 扫描后可能已被其他 worker 取走，按任务 ID 重新定位
+                let position = local.iter().position(|job| job.task.id == task_id)?;
+                local.remove(position)?
+            }
+        };
+
+        self.stats.lock().unwrap().starvation_promotions += 1;
+        debug!("⏫ Promoting starving task: {} ({:?})", job.task.name, job.task.priority);
+        Some(job)
+    }
+
+    /// 从全局队列取任务，并把同优先级的少量任务搬到本地队列（供其他 worker 窃取）
+    ///
+    /// 关闭工作窃取时只取一个任务，避免任务滞留在忙碌 worker 的本地队列中。
+    fn take_global(&self, worker_id: usize, priority: usize) -> Option<QueuedJob> {
+        if !self.config.enable_work_stealing {
+            return self.queues[priority].lock().unwrap().pop_front();
+        }
+
+        let mut batch: Vec<QueuedJob> = {
+            let mut queue = self.queues[priority].lock().unwrap();
+            let surplus = queue.len().saturating_sub(self.locals.len());
+            let take = 1 + surplus.min(self.config.local_batch_size.saturating_sub(1));
+            let take = take.min(queue.len());
+            queue.drain(..take).collect()
+        };
+
+        if batch.is_empty() {
+            return None;
+        }
+        let first = batch.remove(0);
+        self.locals[worker_id].lock().unwrap().extend(batch);
+        Some(first)
+    }
+
+    /// 从其他 worker 的本地队列尾部窃取一半任务（`enable_work_stealing` 关闭时不窃取）
+    fn steal(&self, worker_id: usize) -> Option<QueuedJob> {
+        if !self.config.enable_work_stealing {
+            return None;
+        }
+
+        let workers = self.locals.len();
+        for offset in 1..workers {
+            let victim = (worker_id + offset) % workers;
+            let mut stolen: Vec<QueuedJob> = {
+                let mut queue = self.locals[victim].lock().unwrap();
+                let count = queue.len().div_ceil(2);
+                let start = queue.len() - count;
+                queue.drain(start..).collect()
+            };
+
+            if stolen.is_empty() {
+                continue;
+            }
+
+            self.stats.lock().unwrap().stolen += stolen.len() as u64;
+            debug!("🥷 Worker {} stole {} tasks from worker {}", worker_id, stolen.len(), victim);
+            let first = stolen.remove(0);
+            self.locals[worker_id].lock().unwrap().extend(stolen);
+            return Some(first);
+        }
+        None
+    }
+
+    async fn run_job(&self, job: QueuedJob) {
+        let QueuedJob { task, context, future, enqueued_at, result_tx } = job;
+        let wait_ms = enqueued_at.elapsed().as_secs_f64() * 1000.0;
+        let start = Instant::now();
+        self.queued.fetch_sub(1, Ordering::SeqCst);
+        self.busy_workers.fetch_add(1, Ordering::SeqCst);
+        self.space.notify_waiters();
+
+        // 单任务超时收紧为截止时间；在独立任务中执行以隔离panic
+        let context = context.with_timeout(Duration::from_secs(self.config.task_timeout_secs));
+        let outcome = match context.checkpoint() {
+            Ok(()) => {
+                let guarded = context.clone().scope(async move { context.run(future).await });
+                match tokio::spawn(guarded).await {
+                    Ok(result) => result,
+                    Err(join_error) => Err(anyhow!("Task panicked or was aborted: {}", join_error)),
+                }
+            }
+            // 排队期间已被取消或超时，不再执行
+            Err(e) => Err(e),
+        };
+
+        let run_ms = start.elapsed().as_secs_f64() * 1000.0;
+        let result = TaskResult {
+            task_id: task.id.clone(),
+            success: outcome.is_ok(),
+            duration_ms: run_ms as u64,
+            error: outcome.err().map(|e| e.to_string()),
+        };
+
+        {
+            let mut stats = self.stats.lock().unwrap();
+            let p = task.priority.index();
+            stats.wait_total_ms[p] += wait_ms;
+            stats.wait_count[p] += 1;
+            stats.run_total_ms += run_ms;
+            if result.success {
+                stats.completed += 1;
+            } else {
+                stats.failed += 1;
+            }
+        }
+
+        if result.success {
+            debug!("✅ Task completed: {} ({}ms)", task.name, result.duration_ms);
+        } else {
+            warn!("❌ Task failed: {} ({:?})", task.name, result.error);
+        }
+
+        self.busy_workers.fetch_sub(1, Ordering::SeqCst);
+        self.contexts.lock().unwrap().remove(&task.id);
+        self.results.write().await.insert(task.id.clone(), result.clone());
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+        let _ = result_tx.send(result);
+    }
+
+    fn metrics(&self) -> ExecutorMetrics {
+        let stats = self.stats.lock().unwrap();
+        let finished = stats.completed + stats.failed;
+
+        ExecutorMetrics {
+            submitted: stats.submitted,
+            completed: stats.completed,
+            failed: stats.failed,
+            rejected: stats.rejected,
+            stolen: stats.stolen,
+            starvation_promotions: stats.starvation_promotions,
+            queue_depth: TaskPriority::ALL
+                .iter()
+                .map(|p| (*p, self.queues[p.index()].lock().unwrap().len()))
+                .collect(),
+            local_queue_depth: self.locals.iter().map(|q| q.lock().unwrap().len()).sum(),
+            avg_wait_ms: TaskPriority::ALL
+                .iter()
+                .filter(|p| stats.wait_count[p.index()] > 0)
+                .map(|p| (*p, stats.wait_total_ms[p.index()] / stats.wait_count[p.index()] as f64))
+                .collect(),
+            avg_run_ms: if finished > 0 { stats.run_total_ms / finished as f64 } else { 0.0 },
+            shed: stats.shed,
+            admission_timeouts: stats.admission_timeouts,
+        }
+    }
+
+    fn saturation(&self) -> SaturationMetrics {
+        let stats = self.stats.lock().unwrap();
+        let queued = self.queued.load(Ordering::SeqCst);
+        let queue_capacity = self
+            .config
+            .max_queue_size
+            .min(self.config.priority_queue_size * PRIORITY_LEVELS);
+        let busy_workers = self.busy_workers.load(Ordering::SeqCst);
+        let worker_count = self.locals.len();
+
+        SaturationMetrics {
+            queued,
+            queue_capacity,
+            queue_utilization: if queue_capacity > 0 { queued as f64 / queue_capacity as f64 } else { 0.0 },
+            busy_workers,
+            worker_count,
+            worker_utilization: busy_workers as f64 / worker_count as f64,
+            rejected: stats.rejected,
+            shed: stats.shed,
+            avg_admission_wait_ms: if stats.admission_waits > 0 {
+                stats.admission_wait_total_ms / stats.admission_waits as f64
+            } else {
+                0.0
+            },
+        }
+    }
+}
+
+async fn worker_loop(shared: Arc<ExecutorShared>, worker_id: usize) {
+    debug!("👷 Executor worker {} started", worker_id);
+    while !shared.shutdown.load(Ordering::SeqCst) {
+        // 先取得执行许可再出队：许可耗尽时任务留在队列中，仍可参与饥饿提升与窃取
+        let Ok(permit) = shared.permits.clone().acquire_owned().await else {
+            break;
+        };
+        match shared.next_job(worker_id) {
+            Some(job) => {
+                shared.run_job(job).await;
+                drop(permit);
+            }
+            None => {
+                drop(permit);
+                // 定期醒来检查饥饿和可窃取任务
+                let _ = tokio::time::timeout(Duration::from_millis(50), shared.notify.notified()).await;
+            }
+        }
+    }
+    debug!("👷 Executor worker {} stopped", worker_id);
+}
+
 /// 并发管理器
 pub struct ConcurrencyManager {
     config: ConcurrencyConfig,
@@ -81,6 +882,8 @@ pub struct ConcurrencyManager {
     task_queue: Arc<TokioMutex<VecDeque<AsyncTask>>>,
     running_tasks: Arc<RwLock<HashMap<String, JoinHandle<()>>>>,
     task_results: Arc<RwLock<HashMap<String, TaskResult>>>,
+    executor: Arc<ExecutorShared>,
+    workers_started: AtomicBool,
 }
 
 impl ConcurrencyManager {
@@ -90,15 +893,109 @@ impl ConcurrencyManager {
         info!("   - Workers: {}", config.worker_threads);
         info!("   - Work stealing: {}", config.enable_work_stealing);
 
+        let task_results = Arc::new(RwLock::new(HashMap::new()));
+        let semaphore = Arc::new(Semaphore::new(config.max_concurrent_tasks));
+
         Self {
-            semaphore: Arc::new(Semaphore::new(config.max_concurrent_tasks)),
             task_queue: Arc::new(TokioMutex::new(VecDeque::new())),
             running_tasks: Arc::new(RwLock::new(HashMap::new())),
-            task_results: Arc::new(RwLock::new(HashMap::new())),
+            executor: Arc::new(ExecutorShared::new(config.clone(), semaphore.clone(), task_results.clone())),
+            semaphore,
+            task_results,
+            workers_started: AtomicBool::new(false),
             config,
         }
     }
 
+    /// 提交带执行体的任务到工作窃取执行器，立即返回句柄
+    ///
+    /// 队列满时按 `backpressure_policy` 拒绝、丢弃低优先级任务或等待空位。
+    /// 在执行器任务内调用时，新任务继承当前任务的截止时间和取消信号。
+    pub async fn submit<F>(&self, task: AsyncTask, future: F) -> Result<TaskHandle>
+    where
+        F: Future<Output = Result<()>> + Send + 'static,
+    {
+        let context = TaskContext::current_child(task.id.clone());
+        self.submit_with_context(task, context, future).await
+    }
+
+    /// 使用指定上下文提交任务
+    pub async fn submit_with_context<F>(
+        &self,
+        task: AsyncTask,
+        context: TaskContext,
+        future: F,
+    ) -> Result<TaskHandle>
+    where
+        F: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.ensure_workers();
+
+        let (result_tx, receiver) = oneshot::channel();
+        let task_id = task.id.clone();
+        let name = task.name.clone();
+        let priority = task.priority;
+
+        self.executor
+            .enqueue(QueuedJob {
+                task,
+                context: context.clone(),
+                future: Box::pin(future),
+                enqueued_at: Instant::now(),
+                result_tx,
+            })
+            .await?;
+
+        debug!("📥 Task queued: {} (priority: {:?})", name, priority);
+        Ok(TaskHandle { task_id, receiver, context })
+    }
+
+    /// 批量执行：全部提交到执行器并按提交顺序返回结果
+    pub async fn execute_batch<F>(&self, tasks: Vec<(AsyncTask, F)>) -> Result<Vec<TaskResult>>
+    where
+        F: Future<Output = Result<()>> + Send + 'static,
+    {
+        let mut handles = Vec::with_capacity(tasks.len());
+        for (task, future) in tasks {
+            handles.push(self.submit(task, future).await?);
+        }
+
+        let mut results = Vec::with_capacity(handles.len());
+        for handle in handles {
+            results.push(handle.wait().await?);
+        }
+        Ok(results)
+    }
+
+    /// 执行器指标（队列深度、排队延迟、窃取次数等）
+    pub fn executor_metrics(&self) -> ExecutorMetrics {
+        self.executor.metrics()
+    }
+
+    /// 饱和度指标（队列占用率、worker 占用率、拒绝/丢弃数）
+    pub fn saturation(&self) -> SaturationMetrics {
+        self.executor.saturation()
+    }
+
+    /// 停止执行器 worker（已排队的任务不再执行）
+    pub fn shutdown(&self) {
+        self.executor.shutdown.store(true, Ordering::SeqCst);
+        self.executor.notify.notify_waiters();
+    }
+
+    /// 首次提交时在当前运行时上启动 worker
+    fn ensure_workers(&self) {
+        if self.workers_started.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let workers = self.executor.locals.len();
+        for worker_id in 0..workers {
+            tokio::spawn(worker_loop(self.executor.clone(), worker_id));
+        }
+        info!("👷 Started {} executor workers", workers);
+    }
+
     /// 提交任务
     pub async fn submit_task(&self, task: AsyncTask) -> Result<()> {
         // 背压控制
@@ -200,8 +1097,9 @@ impl ConcurrencyManager {
         loop {
             let running_count = self.running_count().await;
             let queue_len = self.queue_length().await;
+            let in_flight = self.executor.in_flight.load(Ordering::SeqCst);
 
-            if running_count == 0 && queue_len == 0 {
+            if running_count == 0 && queue_len == 0 && in_flight == 0 {
                 break;
             }
 
@@ -214,8 +1112,14 @@ impl ConcurrencyManager {
 
     /// 取消任务
     pub async fn cancel_task(&self, task_id: &str) -> Result<()> {
+        if let Some(context) = self.executor.contexts.lock().unwrap().get(task_id) {
+            context.cancel();
+            info!("🛑 Task cancelled: {}", task_id);
+            return Ok(());
+        }
+
         let mut running = self.running_tasks.write().await;
-        
+
         if let Some(handle) = running.remove(task_id) {
             handle.abort();
             info!("🛑 Task cancelled: {}", task_id);
@@ -226,6 +1130,12 @@ impl ConcurrencyManager {
     }
 }
 
+impl Drop for ConcurrencyManager {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
 /// 分布式锁（简化版，生产环境应使用Redis等）
 pub struct DistributedLock {
     locks: Arc<RwLock<HashMap<String, DateTime<Utc>>>>,
@@ -288,6 +1198,404 @@ mod tests {
         assert_eq!(manager.queue_length().await, 1);
     }
 
+    fn make_task(id: &str, priority: TaskPriority) -> AsyncTask {
+        AsyncTask {
+            id: id.to_string(),
+            name: id.to_string(),
+            priority,
+            created_at: Utc::now(),
+            agent_name: None,
+            metadata: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_executor_runs_by_priority() {
+        let manager = ConcurrencyManager::new(ConcurrencyConfig {
+            worker_threads: 1,
+            ..Default::default()
+        });
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let gate = Arc::new(Notify::new());
+
+        // 第一个任务阻塞唯一的 worker，直到其余任务全部入队
+        let blocker_gate = gate.clone();
+        let blocker = manager
+            .submit(make_task("blocker", TaskPriority::Normal), async move {
+                blocker_gate.notified().await;
+                Ok(())
+            })
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        let mut handles = Vec::new();
+        for (id, priority) in [
+            ("low", TaskPriority::Low),
+            ("critical", TaskPriority::Critical),
+            ("normal", TaskPriority::Normal),
+        ] {
+            let order = order.clone();
+            handles.push(
+                manager
+                    .submit(make_task(id, priority), async move {
+                        order.lock().unwrap().push(id);
+                        Ok(())
+                    })
+                    .await
+                    .unwrap(),
+            );
+        }
+
+        gate.notify_one();
+        assert!(blocker.wait().await.unwrap().success);
+        for handle in handles {
+            assert!(handle.wait().await.unwrap().success);
+        }
+
+        assert_eq!(*order.lock().unwrap(), vec!["critical", "normal", "low"]);
+        let metrics = manager.executor_metrics();
+        assert_eq!(metrics.completed, 4);
+        assert!(metrics.avg_wait_ms.contains_key(&TaskPriority::Low));
+    }
+
+    #[tokio::test]
+    async fn test_executor_prevents_starvation() {
+        let manager = ConcurrencyManager::new(ConcurrencyConfig {
+            worker_threads: 1,
+            starvation_threshold_ms: 0,
+            ..Default::default()
+        });
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let gate = Arc::new(Notify::new());
+
+        let blocker_gate = gate.clone();
+        manager
+            .submit(make_task("blocker", TaskPriority::Normal), async move {
+                blocker_gate.notified().await;
+                Ok(())
+            })
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        let mut handles = Vec::new();
+        for (id, priority) in [("background", TaskPriority::Background), ("high", TaskPriority::High)] {
+            let order = order.clone();
+            handles.push(
+                manager
+                    .submit(make_task(id, priority), async move {
+                        order.lock().unwrap().push(id);
+                        Ok(())
+                    })
+                    .await
+                    .unwrap(),
+            );
+        }
+
+        gate.notify_one();
+        for handle in handles {
+            handle.wait().await.unwrap();
+        }
+
+        // 阈值为0时，等待最久的任务先执行
+        assert_eq!(*order.lock().unwrap(), vec!["background", "high"]);
+        assert_eq!(manager.executor_metrics().starvation_promotions, 1);
+    }
+
+    #[test]
+    fn test_starving_local_job_is_promoted() {
+        let shared = ExecutorShared::new(
+            ConcurrencyConfig {
+                worker_threads: 2,
+                starvation_threshold_ms: 100,
+                ..Default::default()
+            },
+            Arc::new(Semaphore::new(1)),
+            Arc::new(RwLock::new(HashMap::new())),
+        );
+        let job = |id: &str, priority, waited: Duration| {
+            let (result_tx, _) = oneshot::channel();
+            QueuedJob {
+                task: make_task(id, priority),
+                context: TaskContext::new(id),
+                future: Box::pin(async { Ok(()) }),
+                enqueued_at: Instant::now() - waited,
+                result_tx,
+            }
+        };
+
+        // 低优先级任务滞留在 worker 1 的本地队列中，全局队列有新到的高优先级任务
+        shared.locals[1].lock().unwrap().push_back(job("stale", TaskPriority::Low, Duration::from_secs(1)));
+        shared.queues[TaskPriority::High.index()]
+            .lock()
+            .unwrap()
+            .push_back(job("fresh", TaskPriority::High, Duration::ZERO));
+
+        assert_eq!(shared.next_job(0).unwrap().task.id, "stale");
+        assert_eq!(shared.next_job(0).unwrap().task.id, "fresh");
+        assert_eq!(shared.metrics().starvation_promotions, 1);
+    }
+
+    #[tokio::test]
+    async fn test_executor_bounded_by_max_concurrent_tasks() {
+        let manager = ConcurrencyManager::new(ConcurrencyConfig {
+            worker_threads: 4,
+            max_concurrent_tasks: 2,
+            ..Default::default()
+        });
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        let mut handles = Vec::new();
+        for i in 0..8 {
+            let (running, peak) = (running.clone(), peak.clone());
+            handles.push(
+                manager
+                    .submit(make_task(&format!("t{}", i), TaskPriority::Normal), async move {
+                        let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                        peak.fetch_max(now, Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_millis(20)).await;
+                        running.fetch_sub(1, Ordering::SeqCst);
+                        Ok(())
+                    })
+                    .await
+                    .unwrap(),
+            );
+        }
+
+        for handle in handles {
+            assert!(handle.wait().await.unwrap().success);
+        }
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_executor_without_work_stealing() {
+        let manager = ConcurrencyManager::new(ConcurrencyConfig {
+            worker_threads: 2,
+            enable_work_stealing: false,
+            ..Default::default()
+        });
+
+        let mut handles = Vec::new();
+        for i in 0..20 {
+            handles.push(
+                manager
+                    .submit(make_task(&format!("t{}", i), TaskPriority::Normal), async {
+                        tokio::time::sleep(Duration::from_millis(5)).await;
+                        Ok(())
+                    })
+                    .await
+                    .unwrap(),
+            );
+        }
+
+        for handle in handles {
+            assert!(handle.wait().await.unwrap().success);
+        }
+        let metrics = manager.executor_metrics();
+        assert_eq!(metrics.completed, 20);
+        assert_eq!(metrics.stolen, 0);
+        assert_eq!(metrics.local_queue_depth, 0);
+    }
+
+    #[tokio::test]
+    async fn test_executor_bounded_queue_and_failures() {
+        let manager = ConcurrencyManager::new(ConcurrencyConfig {
+            worker_threads: 4,
+            priority_queue_size: 1,
+            ..Default::default()
+        });
+        let gate = Arc::new(Semaphore::new(0));
+
+        let mut handles = Vec::new();
+        let mut rejected = 0;
+        for i in 0..50 {
+            let gate = gate.clone();
+            match manager
+                .submit(make_task(&format!("t{}", i), TaskPriority::Normal), async move {
+                    let _permit = gate.acquire().await;
+                    Err(anyhow!("boom"))
+                })
+                .await
+            {
+                Ok(handle) => handles.push(handle),
+                Err(_) => rejected += 1,
+            }
+        }
+        assert!(rejected > 0);
+
+        gate.add_permits(handles.len());
+        for handle in handles {
+            let result = handle.wait().await.unwrap();
+            assert_eq!(result.error.as_deref(), Some("boom"));
+        }
+        assert_eq!(manager.executor_metrics().rejected, rejected);
+    }
+
+    /// 单 worker 被阻塞、每个优先级队列容量为1、总容量为2的执行器
+    async fn saturated_manager(policy: BackpressurePolicy) -> (ConcurrencyManager, Arc<Semaphore>) {
+        let manager = ConcurrencyManager::new(ConcurrencyConfig {
+            worker_threads: 1,
+            priority_queue_size: 1,
+            max_queue_size: 2,
+            backpressure_policy: policy,
+            ..Default::default()
+        });
+        let gate = Arc::new(Semaphore::new(0));
+
+        let blocker_gate = gate.clone();
+        manager
+            .submit(make_task("blocker", TaskPriority::Critical), async move {
+                let _permit = blocker_gate.acquire().await;
+                Ok(())
+            })
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        (manager, gate)
+    }
+
+    #[tokio::test]
+    async fn test_backpressure_shed_lowest() {
+        let (manager, gate) = saturated_manager(BackpressurePolicy::ShedLowest).await;
+
+        let low = manager
+            .submit(make_task("low", TaskPriority::Low), async { Ok(()) })
+            .await
+            .unwrap();
+        let background = manager
+            .submit(make_task("background", TaskPriority::Background), async { Ok(()) })
+            .await
+            .unwrap();
+        // 队列已满，且没有更低优先级的任务可丢弃
+        assert!(manager
+            .submit(make_task("background-2", TaskPriority::Background), async { Ok(()) })
+            .await
+            .is_err());
+
+        // 高优先级任务依次挤掉最低优先级的排队任务
+        let high = manager
+            .submit(make_task("high", TaskPriority::High), async { Ok(()) })
+            .await
+            .unwrap();
+        let normal = manager
+            .submit(make_task("normal", TaskPriority::Normal), async { Ok(()) })
+            .await
+            .unwrap();
+        assert!(!background.wait().await.unwrap().success);
+
+        gate.add_permits(1);
+        assert!(high.wait().await.unwrap().success);
+        assert!(normal.wait().await.unwrap().success);
+        let low = low.wait().await.unwrap();
+        assert_eq!(low.error.as_deref(), Some("Task shed under backpressure"));
+
+        let metrics = manager.executor_metrics();
+        assert_eq!(metrics.shed, 2);
+        assert_eq!(metrics.rejected, 1);
+    }
+
+    #[tokio::test]
+    async fn test_backpressure_await_with_timeout() {
+        let (manager, gate) =
+            saturated_manager(BackpressurePolicy::AwaitWithTimeout { timeout_ms: 50 }).await;
+
+        manager
+            .submit(make_task("queued", TaskPriority::Normal), async { Ok(()) })
+            .await
+            .unwrap();
+
+        let saturation = manager.saturation();
+        assert_eq!(saturation.queued, 1);
+        assert_eq!(saturation.busy_workers, 1);
+        assert_eq!(saturation.worker_utilization, 1.0);
+
+        // 没有空位，等待超时后拒绝
+        assert!(manager
+            .submit(make_task("timeout", TaskPriority::Normal), async { Ok(()) })
+            .await
+            .is_err());
+        assert_eq!(manager.executor_metrics().admission_timeouts, 1);
+
+        // 阻塞任务结束后腾出空位，等待中的提交成功
+        let release = gate.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            release.add_permits(1);
+        });
+        let waited = manager
+            .submit(make_task("waited", TaskPriority::Normal), async { Ok(()) })
+            .await
+            .unwrap();
+        assert!(waited.wait().await.unwrap().success);
+        assert!(manager.saturation().avg_admission_wait_ms > 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_cancellation_propagates_to_children() {
+        let manager = Arc::new(ConcurrencyManager::new(ConcurrencyConfig {
+            worker_threads: 2,
+            ..Default::default()
+        }));
+        let child_started = Arc::new(Notify::new());
+        let child_finished = Arc::new(AtomicBool::new(false));
+
+        let inner_manager = manager.clone();
+        let started = child_started.clone();
+        let finished = child_finished.clone();
+        let parent = manager
+            .submit(make_task("parent", TaskPriority::Normal), async move {
+                // 子任务继承父任务上下文
+                let child = inner_manager
+                    .submit(make_task("child", TaskPriority::Normal), async move {
+                        started.notify_one();
+                        tokio::time::sleep(Duration::from_secs(30)).await;
+                        finished.store(true, Ordering::SeqCst);
+                        Ok(())
+                    })
+                    .await?;
+                child.wait().await?;
+                Ok(())
+            })
+            .await
+            .unwrap();
+
+        child_started.notified().await;
+        manager.cancel_task("parent").await.unwrap();
+
+        let result = tokio::time::timeout(Duration::from_secs(5), parent.wait())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!result.success);
+        assert!(result.error.unwrap().contains("cancelled"));
+
+        manager.wait_all().await.unwrap();
+        assert!(!child_finished.load(Ordering::SeqCst));
+        let child = manager.get_task_result("child").await.unwrap();
+        assert!(child.error.unwrap().contains("cancelled"));
+    }
+
+    #[tokio::test]
+    async fn test_task_context_deadline() {
+        let ctx = TaskContext::new("root").with_timeout(Duration::from_millis(20));
+        let child = ctx.child("child");
+        assert_eq!(child.deadline(), ctx.deadline());
+        assert!(child.checkpoint().is_ok());
+
+        let result = child
+            .run(async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                Ok(())
+            })
+            .await;
+        assert!(result.unwrap_err().to_string().contains("deadline"));
+        assert!(ctx.checkpoint().is_err());
+        assert!(TaskContext::current().is_none());
+    }
+
     #[tokio::test]
     async fn test_distributed_lock() {
         let lock = DistributedLock::new(5);
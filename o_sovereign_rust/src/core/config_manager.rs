@@ -7,16 +7,28 @@
 // 3. 配置验证
 // 4. 敏感配置加密存储
 // 5. 配置版本控制
+// 6. 分层配置源（defaults < file < env < runtime）
+// 7. `${secret:...}` 密钥引用在加载时解析
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
 use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::sync::Arc;
+use std::time::SystemTime;
 use tokio::sync::RwLock;
 use tracing::{debug, info, warn};
 
+use super::config_schema::{ConfigIssue, ConfigSchema, SchemaField};
+use super::error::{AcsaError, ErrorCode};
+use super::secrets::{SecretBackend, SecretResolver};
+
+/// 部署环境的环境变量（dev / staging / prod）
+pub const ENVIRONMENT_ENV: &str = "ACSA_ENV";
+
 /// 配置环境
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Environment {
@@ -35,8 +47,118 @@ impl std::fmt::Display for Environment {
     }
 }
 
-/// 配置值类型
+impl Environment {
+    /// 从 ACSA_ENV 读取部署环境，未设置时为 None
+    pub fn from_env() -> Result<Option<Self>> {
+        match std::env::var(ENVIRONMENT_ENV) {
+            Ok(raw) if !raw.trim().is_empty() => raw.trim().parse().map(Some),
+            _ => Ok(None),
+        }
+    }
+}
+
+impl FromStr for Environment {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "dev" | "development" => Ok(Environment::Development),
+            "staging" | "stage" => Ok(Environment::Staging),
+            "prod" | "production" => Ok(Environment::Production),
+            other => Err(anyhow!("Unknown environment '{}' (expected dev/staging/prod)", other)),
+        }
+    }
+}
+
+/// 危险配置守卫：在受保护环境中，`key` 为 true 时必须显式设置 `allow_key = true`
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettingGuard {
+    /// 被保护的布尔配置
+    pub key: String,
+    /// 显式放行开关
+    pub allow_key: String,
+    /// 受保护的环境
+    pub environments: Vec<Environment>,
+    /// 未配置时 `key` 的默认值
+    pub default_enabled: bool,
+    /// 说明
+    pub reason: String,
+}
+
+impl SettingGuard {
+    /// 内置守卫
+    pub fn defaults() -> Vec<Self> {
+        vec![SettingGuard {
+            key: "protocol.ghost.enabled".to_string(),
+            allow_key: "guards.allow_ghost_in_production".to_string(),
+            environments: vec![Environment::Production],
+            default_enabled: true,
+            reason: "Ghost protocol disables Jarvis filtering".to_string(),
+        }]
+    }
+
+    fn blocks(&self, environment: Environment, values: &HashMap<String, ConfigValue>) -> bool {
+        self.environments.contains(&environment)
+            && values
+                .get(&self.key)
+                .and_then(|v| v.as_bool())
+                .unwrap_or(self.default_enabled)
+            && values.get(&self.allow_key).and_then(|v| v.as_bool()) != Some(true)
+    }
+}
+
+/// 协议开关的配置键：`GHOST` → `protocol.ghost.enabled`
+pub fn protocol_enabled_key(protocol_name: &str) -> String {
+    format!("protocol.{}.enabled", protocol_name.to_lowercase())
+}
+
+/// 强制执行守卫：被拦截的配置改写为 false
+fn enforce_guards(environment: Environment, guards: &[SettingGuard], values: &mut HashMap<String, ConfigValue>) {
+    for guard in guards {
+        if guard.blocks(environment, values) {
+            warn!(
+                "🛡️  '{}' forced off in {} ({}); set '{}' to allow",
+                guard.key, environment, guard.reason, guard.allow_key
+            );
+            values.insert(guard.key.clone(), ConfigValue::Boolean(false));
+        }
+    }
+}
+
+/// 两个环境之间的配置差异
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigDiff {
+    pub key: String,
+    pub left: Option<ConfigValue>,
+    pub right: Option<ConfigValue>,
+}
+
+/// 配置层（优先级从低到高）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum ConfigLayer {
+    /// 代码内置默认值
+    Default,
+    /// 配置文件
+    File,
+    /// 环境变量
+    Env,
+    /// 运行时覆盖（`set` / `import_config`）
+    Runtime,
+}
+
+impl std::fmt::Display for ConfigLayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigLayer::Default => write!(f, "defaults"),
+            ConfigLayer::File => write!(f, "file"),
+            ConfigLayer::Env => write!(f, "env"),
+            ConfigLayer::Runtime => write!(f, "runtime"),
+        }
+    }
+}
+
+/// 配置值类型
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum ConfigValue {
     String(String),
@@ -79,6 +201,101 @@ impl ConfigValue {
             None
         }
     }
+
+    /// 从环境变量字符串解析：数字/布尔/数组按JSON解析，其余作为字符串
+    pub fn parse_env(raw: &str) -> Self {
+        match serde_json::from_str::<ConfigValue>(raw) {
+            Ok(ConfigValue::Object(_)) | Err(_) => ConfigValue::String(raw.to_string()),
+            Ok(value) => value,
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or(serde_json::Value::Null)
+    }
+}
+
+/// 将嵌套JSON对象展开为点分隔的键（`a.b.c`），null值会被忽略
+fn flatten_json(prefix: &str, value: serde_json::Value, out: &mut HashMap<String, ConfigValue>) -> Result<()> {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (k, v) in map {
+                let key = if prefix.is_empty() { k } else { format!("{}.{}", prefix, k) };
+                flatten_json(&key, v, out)?;
+            }
+        }
+        serde_json::Value::Null => {}
+        other => {
+            let config_value = serde_json::from_value(other)
+                .with_context(|| format!("Invalid value for config key '{}'", prefix))?;
+            out.insert(prefix.to_string(), config_value);
+        }
+    }
+    Ok(())
+}
+
+/// 读取并解析JSON配置文件，返回内容和修改时间；文件不存在时返回 None
+async fn read_json_file(path: &Path) -> Result<Option<(serde_json::Value, Option<SystemTime>)>> {
+    let modified = match tokio::fs::metadata(path).await {
+        Ok(meta) => meta.modified().ok(),
+        Err(_) => return Ok(None),
+    };
+    let content = tokio::fs::read_to_string(path).await?;
+    let json = serde_json::from_str(&content).with_context(|| format!("Failed to parse config file {:?}", path))?;
+    Ok(Some((json, modified)))
+}
+
+/// 读取某个环境的文件配置：`config.json` 公共部分 < `config.json` 的 `environments.{env}` 段 < `{env}.json`
+///
+/// 返回值中的修改时间为所有已存在文件中最新的一个；两个文件都不存在时返回 None。
+async fn read_profile(
+    config_dir: &Path,
+    environment: Environment,
+) -> Result<Option<(HashMap<String, ConfigValue>, Option<SystemTime>)>> {
+    let mut values = HashMap::new();
+    let mut latest: Option<SystemTime> = None;
+    let mut found = false;
+
+    if let Some((mut json, modified)) = read_json_file(&config_dir.join("config.json")).await? {
+        found = true;
+        latest = latest.max(modified);
+        let section = json
+            .as_object_mut()
+            .and_then(|map| map.remove("environments"))
+            .and_then(|mut envs| envs.as_object_mut().and_then(|m| m.remove(&environment.to_string())));
+        flatten_json("", json, &mut values)?;
+        if let Some(section) = section {
+            flatten_json("", section, &mut values)?;
+        }
+    }
+
+    let env_file = config_dir.join(format!("{}.json", environment));
+    if let Some((json, modified)) = read_json_file(&env_file).await? {
+        found = true;
+        latest = latest.max(modified);
+        flatten_json("", json, &mut values)?;
+    }
+
+    Ok(found.then_some((values, latest)))
+}
+
+/// 按点分隔路径写入JSON值（中间对象不存在时自动创建）
+fn set_json_path(root: &mut serde_json::Value, path: &str, value: serde_json::Value) {
+    let mut current = root;
+    let mut parts = path.split('.').peekable();
+    while let Some(part) = parts.next() {
+        if !current.is_object() {
+            *current = serde_json::Value::Object(serde_json::Map::new());
+        }
+        let map = current.as_object_mut().expect("just ensured object");
+        if parts.peek().is_none() {
+            map.insert(part.to_string(), value);
+            return;
+        }
+        current = map
+            .entry(part.to_string())
+            .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()));
+    }
 }
 
 /// 配置条目
@@ -128,6 +345,16 @@ pub struct ConfigManagerConfig {
     pub hot_reload_interval_secs: u64,
     /// 是否加密敏感配置
     pub encrypt_sensitive: bool,
+    /// 环境变量前缀（`ACSA_RATE_LIMITER__IP_RULE__ENABLED` → `rate_limiter.ip_rule.enabled`）
+    #[serde(default = "default_env_prefix")]
+    pub env_prefix: String,
+    /// 危险配置守卫
+    #[serde(default = "SettingGuard::defaults")]
+    pub guards: Vec<SettingGuard>,
+}
+
+fn default_env_prefix() -> String {
+    "ACSA_".to_string()
 }
 
 impl Default for ConfigManagerConfig {
@@ -138,6 +365,8 @@ impl Default for ConfigManagerConfig {
             enable_hot_reload: true,
             hot_reload_interval_secs: 60,
             encrypt_sensitive: true,
+            env_prefix: default_env_prefix(),
+            guards: SettingGuard::defaults(),
         }
     }
 }
@@ -151,6 +380,16 @@ pub struct ConfigManager {
     change_history: Arc<RwLock<Vec<ConfigChange>>>,
     /// 配置监听器
     listeners: Arc<RwLock<Vec<Box<dyn ConfigListener + Send + Sync>>>>,
+    /// 各配置层的原始值
+    layers: Arc<RwLock<BTreeMap<ConfigLayer, HashMap<String, ConfigValue>>>>,
+    /// 上次加载时配置文件的修改时间
+    file_modified: Arc<RwLock<Option<SystemTime>>>,
+    /// 密钥解析器
+    secrets: Arc<RwLock<SecretResolver>>,
+    /// 由密钥引用解析而来的键（自动视为敏感配置）
+    secret_keys: Arc<RwLock<HashSet<String>>>,
+    /// 配置模式
+    schema: Arc<RwLock<ConfigSchema>>,
 }
 
 /// 配置监听器 trait
@@ -171,26 +410,189 @@ impl ConfigManager {
             store: Arc::new(RwLock::new(HashMap::new())),
             change_history: Arc::new(RwLock::new(Vec::new())),
             listeners: Arc::new(RwLock::new(Vec::new())),
+            layers: Arc::new(RwLock::new(BTreeMap::new())),
+            file_modified: Arc::new(RwLock::new(None)),
+            secrets: Arc::new(RwLock::new(SecretResolver::new())),
+            secret_keys: Arc::new(RwLock::new(HashSet::new())),
+            schema: Arc::new(RwLock::new(ConfigSchema::builtin())),
         }
     }
 
-    /// 从文件加载配置
-    pub async fn load_from_file(&self) -> Result<()> {
-        let file_path = self
-            .config
+    /// 注册额外的模式字段（组件自定义配置）
+    pub async fn register_schema_fields(&self, fields: Vec<SchemaField>) {
+        self.schema.write().await.extend(fields);
+    }
+
+    /// 注册密钥后端（例如 `EncryptedFileSecretBackend`）
+    pub async fn register_secret_backend(&self, backend: Arc<dyn SecretBackend>) {
+        info!("🔑 Secret backend registered: {}", backend.name());
+        self.secrets.write().await.register(backend);
+    }
+
+    /// 递归解析配置值中的密钥引用，返回是否包含引用
+    async fn resolve_secrets(&self, value: &mut ConfigValue) -> Result<bool> {
+        let resolver = self.secrets.read().await;
+        let mut pending = vec![value];
+        let mut found = false;
+
+        while let Some(value) = pending.pop() {
+            match value {
+                ConfigValue::String(s) if SecretResolver::contains_reference(s) => {
+                    *s = resolver.resolve_str(s).await?;
+                    found = true;
+                }
+                ConfigValue::Array(items) => pending.extend(items.iter_mut()),
+                ConfigValue::Object(map) => pending.extend(map.values_mut()),
+                _ => {}
+            }
+        }
+
+        Ok(found)
+    }
+
+    /// 当前环境的配置文件路径
+    pub fn config_file_path(&self) -> PathBuf {
+        self.config
             .config_dir
-            .join(format!("{}.json", self.config.environment));
+            .join(format!("{}.json", self.config.environment))
+    }
 
-        info!("📂 Loading config from: {:?}", file_path);
+    /// 设置默认值层
+    pub async fn set_defaults(&self, defaults: HashMap<String, ConfigValue>) -> Result<()> {
+        self.replace_layer(ConfigLayer::Default, defaults).await
+    }
 
-        // TODO: 实际从文件读取配置
-        // 使用 tokio::fs::read_to_string 和 serde_json::from_str
+    /// 当前环境
+    pub fn environment(&self) -> Environment {
+        self.config.environment
+    }
 
-        // Placeholder
-        warn!("⚠️  Config file loading not yet implemented");
+    /// 受守卫保护的布尔开关是否可用：显式关闭，或在受保护环境中未显式放行时返回错误
+    pub async fn require_enabled(&self, key: &str) -> Result<()> {
+        let values: HashMap<String, ConfigValue> =
+            self.store.read().await.iter().map(|(k, e)| (k.clone(), e.value.clone())).collect();
+        let guard = self.config.guards.iter().find(|g| g.key == key);
+
+        // 受保护环境中只看放行开关：加载时已被改写为 false，尚未加载配置时也同样拦截
+        let environment = self.config.environment;
+        if let Some(guard) = guard.filter(|g| {
+            g.environments.contains(&environment) && values.get(&g.allow_key).and_then(|v| v.as_bool()) != Some(true)
+        }) {
+            return Err(AcsaError::new(
+                ErrorCode::PermissionDenied,
+                format!(
+                    "'{}' is not allowed in {} ({}); set '{}' to allow it",
+                    key, environment, guard.reason, guard.allow_key
+                ),
+            )
+            .into());
+        }
+        let enabled = values
+            .get(key)
+            .and_then(|v| v.as_bool())
+            .unwrap_or_else(|| guard.is_none_or(|g| g.default_enabled));
+        if !enabled {
+            return Err(AcsaError::new(
+                ErrorCode::PermissionDenied,
+                format!("'{}' is disabled in {}", key, environment),
+            )
+            .into());
+        }
         Ok(())
     }
 
+    /// 选择协议前调用：`protocol.<name>.enabled`（如 Ghost 在生产环境默认关闭）
+    pub async fn check_protocol(&self, protocol_name: &str) -> Result<()> {
+        self.require_enabled(&protocol_enabled_key(protocol_name)).await
+    }
+
+    /// 从文件加载当前环境的配置（文件不存在时跳过）
+    pub async fn load_from_file(&self) -> Result<()> {
+        info!("📂 Loading {} config from: {:?}", self.config.environment, self.config.config_dir);
+
+        // 先完整解析，解析失败时保留旧配置，不做部分应用
+        let Some((values, modified)) = read_profile(&self.config.config_dir, self.config.environment).await? else {
+            warn!("⚠️  No config file found in {:?}", self.config.config_dir);
+            return Ok(());
+        };
+
+        for issue in self.schema.read().await.validate_values(&values) {
+            warn!("⚠️  Config issue: {}", issue);
+        }
+
+        self.replace_layer(ConfigLayer::File, values).await?;
+        *self.file_modified.write().await = modified;
+        Ok(())
+    }
+
+    /// 计算某个环境的生效文件配置（defaults < file，已执行守卫；不含 env/runtime 层）
+    pub async fn profile(&self, environment: Environment) -> Result<HashMap<String, ConfigValue>> {
+        let mut values = self
+            .layers
+            .read()
+            .await
+            .get(&ConfigLayer::Default)
+            .cloned()
+            .unwrap_or_default();
+
+        if let Some((file_values, _)) = read_profile(&self.config.config_dir, environment).await? {
+            values.extend(file_values);
+        }
+
+        enforce_guards(environment, &self.config.guards, &mut values);
+        Ok(values)
+    }
+
+    /// 比较两个环境的生效配置
+    pub async fn diff_profiles(&self, left: Environment, right: Environment) -> Result<Vec<ConfigDiff>> {
+        let left_values = self.profile(left).await?;
+        let right_values = self.profile(right).await?;
+
+        let mut keys: Vec<&String> = left_values.keys().chain(right_values.keys()).collect();
+        keys.sort();
+        keys.dedup();
+
+        Ok(keys
+            .into_iter()
+            .filter(|key| left_values.get(*key) != right_values.get(*key))
+            .map(|key| ConfigDiff {
+                key: key.clone(),
+                left: left_values.get(key).cloned(),
+                right: right_values.get(key).cloned(),
+            })
+            .collect())
+    }
+
+    /// 从环境变量加载配置
+    pub async fn load_from_env(&self) -> Result<()> {
+        let prefix = &self.config.env_prefix;
+        let values: HashMap<String, ConfigValue> = std::env::vars()
+            .filter_map(|(name, raw)| {
+                let key = name.strip_prefix(prefix.as_str())?;
+                Some((key.to_lowercase().replace("__", "."), ConfigValue::parse_env(&raw)))
+            })
+            .collect();
+
+        debug!("🌱 Loaded {} config values from env ({}*)", values.len(), prefix);
+        self.replace_layer(ConfigLayer::Env, values).await
+    }
+
+    /// 按优先级加载所有外部配置源（file < env）
+    pub async fn load_all(&self) -> Result<()> {
+        self.load_from_file().await?;
+        self.load_from_env().await
+    }
+
+    /// 某个键当前生效值来自哪一层
+    pub async fn source_of(&self, key: &str) -> Option<ConfigLayer> {
+        let layers = self.layers.read().await;
+        layers
+            .iter()
+            .rev()
+            .find(|(_, values)| values.contains_key(key))
+            .map(|(layer, _)| *layer)
+    }
+
     /// 获取配置值
     pub async fn get(&self, key: &str) -> Option<ConfigValue> {
         let store = self.store.read().await;
@@ -222,6 +624,32 @@ impl ConfigManager {
         self.get(key).await.unwrap_or(default)
     }
 
+    /// 获取并反序列化为指定类型，类型不匹配时返回错误
+    pub async fn get_typed<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        match self.get(key).await {
+            Some(value) => serde_json::from_value(value.to_json())
+                .map(Some)
+                .with_context(|| format!("Config '{}' has an invalid type", key)),
+            None => Ok(None),
+        }
+    }
+
+    /// 将 `{section}.*` 下的所有键组装为结构体（缺失字段由 serde 默认值补齐）
+    pub async fn get_section<T: DeserializeOwned>(&self, section: &str) -> Result<T> {
+        let prefix = format!("{}.", section);
+        let store = self.store.read().await;
+
+        let mut root = serde_json::Value::Object(serde_json::Map::new());
+        for (key, entry) in store.iter() {
+            if let Some(path) = key.strip_prefix(&prefix) {
+                set_json_path(&mut root, path, entry.value.to_json());
+            }
+        }
+        drop(store);
+
+        serde_json::from_value(root).with_context(|| format!("Config section '{}' is invalid", section))
+    }
+
     /// 设置配置值
     pub async fn set(
         &self,
@@ -231,8 +659,37 @@ impl ConfigManager {
         hot_reloadable: bool,
         changed_by: String,
     ) -> Result<()> {
+        let mut value = value;
+        let is_secret = self
+            .resolve_secrets(&mut value)
+            .await
+            .with_context(|| format!("Failed to resolve secret for '{}'", key))?;
+        if is_secret {
+            self.secret_keys.write().await.insert(key.clone());
+        }
+        let sensitive = sensitive || is_secret;
+
         let mut store = self.store.write().await;
 
+        // 检查危险配置守卫
+        let mut candidate: HashMap<String, ConfigValue> =
+            store.iter().map(|(k, e)| (k.clone(), e.value.clone())).collect();
+        candidate.insert(key.clone(), value.clone());
+        if let Some(guard) = self
+            .config
+            .guards
+            .iter()
+            .find(|g| g.key == key && g.blocks(self.config.environment, &candidate))
+        {
+            return Err(anyhow!(
+                "Config '{}' is not allowed in {} ({}); set '{}' first",
+                key,
+                self.config.environment,
+                guard.reason,
+                guard.allow_key
+            ));
+        }
+
         let old_value = store.get(&key).map(|e| e.value.clone());
 
         // 检查是否可热更新
@@ -258,6 +715,13 @@ impl ConfigManager {
         store.insert(key.clone(), entry);
         drop(store);
 
+        self.layers
+            .write()
+            .await
+            .entry(ConfigLayer::Runtime)
+            .or_default()
+            .insert(key.clone(), value.clone());
+
         // 记录变更
         let change = ConfigChange {
             key: key.clone(),
@@ -278,10 +742,41 @@ impl ConfigManager {
         Ok(())
     }
 
-    /// 删除配置
+    /// 将若干键写入当前环境的配置文件（`{env}.json`，保留其他内容）并立即生效
+    ///
+    /// 写入前按模式校验，任一值不合法时不修改文件
+    pub async fn persist(&self, values: HashMap<String, ConfigValue>, changed_by: String) -> Result<PathBuf> {
+        let issues = self.schema.read().await.validate_values(&values);
+        if let Some(issue) = issues.first() {
+            return Err(anyhow!("{}", issue));
+        }
+
+        let path = self.config_file_path();
+        let mut root = match read_json_file(&path).await? {
+            Some((json, _)) if json.is_object() => json,
+            Some(_) => return Err(anyhow!("Config file {:?} is not a JSON object", path)),
+            None => serde_json::Value::Object(serde_json::Map::new()),
+        };
+        for (key, value) in &values {
+            set_json_path(&mut root, key, value.to_json());
+        }
+
+        tokio::fs::create_dir_all(&self.config.config_dir).await?;
+        let tmp = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, serde_json::to_string_pretty(&root)?).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+        info!("💾 Persisted {} config keys to {:?}", values.len(), path);
+
+        self.set_batch(values, changed_by).await?;
+        Ok(path)
+    }
+
+    /// 删除运行时覆盖（下层配置会重新生效）
     pub async fn remove(&self, key: &str) -> Result<()> {
-        let mut store = self.store.write().await;
-        store.remove(key);
+        if let Some(runtime) = self.layers.write().await.get_mut(&ConfigLayer::Runtime) {
+            runtime.remove(key);
+        }
+        self.apply_layers(ConfigLayer::Runtime).await;
         info!("🗑️  Config removed: {}", key);
         Ok(())
     }
@@ -312,10 +807,22 @@ impl ConfigManager {
 
     /// 验证配置
     pub async fn validate(&self) -> Result<Vec<String>> {
-        let mut errors = Vec::new();
+        let values: HashMap<String, ConfigValue> = self
+            .store
+            .read()
+            .await
+            .iter()
+            .map(|(k, e)| (k.clone(), e.value.clone()))
+            .collect();
 
-        // TODO: 实现配置验证逻辑
-        // 例如：检查必需配置是否存在、类型是否正确等
+        let errors: Vec<String> = self
+            .schema
+            .read()
+            .await
+            .validate_values(&values)
+            .iter()
+            .map(|issue| issue.to_string())
+            .collect();
 
         if errors.is_empty() {
             info!("✅ Config validation passed");
@@ -326,6 +833,23 @@ impl ConfigManager {
         Ok(errors)
     }
 
+    /// 校验配置目录中的所有配置文件（`config.json` 与各环境文件），带文件和行号
+    pub async fn validate_files(&self) -> Result<Vec<ConfigIssue>> {
+        let schema = self.schema.read().await;
+        let mut issues = Vec::new();
+
+        let mut paths = vec![self.config.config_dir.join("config.json")];
+        for environment in [Environment::Development, Environment::Staging, Environment::Production] {
+            paths.push(self.config.config_dir.join(format!("{}.json", environment)));
+        }
+
+        for path in paths.into_iter().filter(|p| p.exists()) {
+            issues.extend(schema.validate_file(&path)?);
+        }
+
+        Ok(issues)
+    }
+
     /// 获取变更历史
     pub async fn get_change_history(&self, limit: Option<usize>) -> Vec<ConfigChange> {
         let history = self.change_history.read().await;
@@ -365,14 +889,111 @@ impl ConfigManager {
         });
     }
 
-    /// 重新加载配置
+    /// 重新加载配置（仅在配置文件修改时间变化时）
     async fn reload_config(&self) -> Result<()> {
-        // TODO: 从文件重新加载配置并比对变更
-        // 只更新标记为 hot_reloadable 的配置
+        let mut latest: Option<SystemTime> = None;
+        for path in [self.config.config_dir.join("config.json"), self.config_file_path()] {
+            if let Ok(meta) = tokio::fs::metadata(&path).await {
+                latest = latest.max(meta.modified().ok());
+            }
+        }
+
+        if latest.is_none() || latest == *self.file_modified.read().await {
+            return Ok(());
+        }
+
+        self.load_from_file().await
+    }
 
+    /// 替换某一层的全部值并重新合并
+    async fn replace_layer(&self, layer: ConfigLayer, values: HashMap<String, ConfigValue>) -> Result<()> {
+        // 先解析全部密钥引用，任何一个失败都不应用该层
+        let mut values = values;
+        let mut resolved_keys = Vec::new();
+        for (key, value) in values.iter_mut() {
+            if self
+                .resolve_secrets(value)
+                .await
+                .with_context(|| format!("Failed to resolve secret for '{}'", key))?
+            {
+                resolved_keys.push(key.clone());
+            }
+        }
+        self.secret_keys.write().await.extend(resolved_keys);
+
+        self.layers.write().await.insert(layer, values);
+        let changes = self.apply_layers(layer).await;
+        if !changes.is_empty() {
+            info!("✅ Applied {} config changes from {}", changes.len(), layer);
+        }
         Ok(())
     }
 
+    /// 合并所有层并原子替换生效配置，返回产生的变更
+    ///
+    /// 已存在且不可热更新的键不会被外部配置源覆盖。
+    async fn apply_layers(&self, source: ConfigLayer) -> Vec<ConfigChange> {
+        let secret_keys = self.secret_keys.read().await.clone();
+        let layers = self.layers.read().await;
+        let mut merged: HashMap<String, ConfigValue> = HashMap::new();
+        for values in layers.values() {
+            merged.extend(values.iter().map(|(k, v)| (k.clone(), v.clone())));
+        }
+        drop(layers);
+        enforce_guards(self.config.environment, &self.config.guards, &mut merged);
+
+        let mut store = self.store.write().await;
+        let mut next = HashMap::with_capacity(merged.len());
+        let mut changes = Vec::new();
+        let now = Utc::now();
+
+        for (key, value) in merged {
+            match store.get(&key) {
+                Some(existing) if existing.value == value => {
+                    next.insert(key, existing.clone());
+                }
+                Some(existing) if !existing.hot_reloadable && source != ConfigLayer::Runtime => {
+                    warn!("⚠️  Config '{}' is not hot-reloadable, keeping current value", key);
+                    next.insert(key, existing.clone());
+                }
+                existing => {
+                    let sensitive = existing.map(|e| e.sensitive).unwrap_or(false) || secret_keys.contains(&key);
+                    changes.push(ConfigChange {
+                        key: key.clone(),
+                        old_value: existing.map(|e| e.value.clone()),
+                        new_value: value.clone(),
+                        changed_at: now,
+                        changed_by: source.to_string(),
+                    });
+                    next.insert(
+                        key.clone(),
+                        ConfigEntry {
+                            key,
+                            value,
+                            sensitive,
+                            hot_reloadable: existing.map(|e| e.hot_reloadable).unwrap_or(true),
+                            environment: Some(self.config.environment),
+                            updated_at: now,
+                            version: existing.map(|e| e.version + 1).unwrap_or(1),
+                        },
+                    );
+                }
+            }
+        }
+
+        *store = next;
+        drop(store);
+
+        if !changes.is_empty() {
+            self.change_history.write().await.extend(changes.iter().cloned());
+            for change in &changes {
+                self.notify_listeners(change).await;
+            }
+        }
+
+        changes
+    }
+
     /// 通知监听器
     async fn notify_listeners(&self, change: &ConfigChange) {
         let listeners = self.listeners.read().await;
@@ -408,6 +1029,73 @@ impl ConfigListener for LoggingConfigListener {
     }
 }
 
+/// 配置段监听器：把 `{section}.*` 的变更写回组件共享的配置结构体
+///
+/// 新值会先与当前配置合并再反序列化，类型不合法的变更会被拒绝，组件保持原配置。
+pub struct SectionConfigListener<T> {
+    section: String,
+    target: Arc<RwLock<T>>,
+}
+
+impl<T> SectionConfigListener<T>
+where
+    T: Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    pub fn new(section: impl Into<String>, target: Arc<RwLock<T>>) -> Self {
+        Self {
+            section: section.into(),
+            target,
+        }
+    }
+
+    fn apply(target: &mut T, path: &str, value: serde_json::Value) -> Result<()> {
+        let mut json = serde_json::to_value(&*target)?;
+        set_json_path(&mut json, path, value);
+        *target = serde_json::from_value(json)?;
+        Ok(())
+    }
+}
+
+impl<T> ConfigListener for SectionConfigListener<T>
+where
+    T: Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    fn on_config_changed(&self, change: &ConfigChange) {
+        let Some(path) = change
+            .key
+            .strip_prefix(self.section.as_str())
+            .and_then(|rest| rest.strip_prefix('.'))
+        else {
+            return;
+        };
+
+        let path = path.to_string();
+        let value = change.new_value.to_json();
+        let key = change.key.clone();
+
+        // 通常无竞争，直接同步写入；否则交给运行时异步写入
+        if let Ok(mut target) = self.target.try_write() {
+            if let Err(e) = Self::apply(&mut target, &path, value) {
+                warn!("⚠️  Rejected config change '{}': {}", key, e);
+            }
+            return;
+        }
+
+        let target = self.target.clone();
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn(async move {
+                    let mut target = target.write().await;
+                    if let Err(e) = Self::apply(&mut target, &path, value) {
+                        warn!("⚠️  Rejected config change '{}': {}", key, e);
+                    }
+                });
+            }
+            Err(_) => warn!("⚠️  Config change '{}' dropped: target busy and no runtime", key),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -450,4 +1138,230 @@ mod tests {
         assert_eq!(history.len(), 1);
         assert_eq!(history[0].key, "key1");
     }
+
+    #[tokio::test]
+    async fn test_layer_precedence() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("development.json"),
+            r#"{"server": {"port": 8080, "host": "0.0.0.0"}}"#,
+        )
+        .unwrap();
+
+        let manager = ConfigManager::new(ConfigManagerConfig {
+            config_dir: dir.path().to_path_buf(),
+            env_prefix: "ACSA_TEST_LAYERS_".to_string(),
+            ..Default::default()
+        });
+        std::env::set_var("ACSA_TEST_LAYERS_SERVER__PORT", "9090");
+
+        let defaults = HashMap::from([
+            ("server.port".to_string(), ConfigValue::Integer(80)),
+            ("server.workers".to_string(), ConfigValue::Integer(4)),
+        ]);
+        manager.set_defaults(defaults).await.unwrap();
+        manager.load_all().await.unwrap();
+
+        assert_eq!(manager.get_i64("server.port").await, Some(9090));
+        assert_eq!(manager.source_of("server.port").await, Some(ConfigLayer::Env));
+        assert_eq!(manager.get_string("server.host").await, Some("0.0.0.0".to_string()));
+        assert_eq!(manager.get_i64("server.workers").await, Some(4));
+
+        manager
+            .set("server.port".to_string(), ConfigValue::Integer(7070), false, true, "admin".to_string())
+            .await
+            .unwrap();
+        assert_eq!(manager.get_i64("server.port").await, Some(7070));
+
+        manager.remove("server.port").await.unwrap();
+        assert_eq!(manager.get_i64("server.port").await, Some(9090));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_file_keeps_previous_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("development.json");
+        std::fs::write(&path, r#"{"feature": {"enabled": true}}"#).unwrap();
+
+        let manager = ConfigManager::new(ConfigManagerConfig {
+            config_dir: dir.path().to_path_buf(),
+            ..Default::default()
+        });
+        manager.load_from_file().await.unwrap();
+
+        std::fs::write(&path, r#"{"feature": {"enabled": "#).unwrap();
+        assert!(manager.load_from_file().await.is_err());
+        assert_eq!(manager.get_bool("feature.enabled").await, Some(true));
+    }
+
+    #[tokio::test]
+    async fn test_persist_merges_into_env_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("development.json");
+        std::fs::write(&path, r#"{"server": {"port": 8080}}"#).unwrap();
+
+        let manager = ConfigManager::new(ConfigManagerConfig {
+            config_dir: dir.path().to_path_buf(),
+            ..Default::default()
+        });
+        let values = HashMap::from([("sovereignty.enabled".to_string(), ConfigValue::Boolean(true))]);
+        assert_eq!(manager.persist(values, "cli".to_string()).await.unwrap(), path);
+        assert_eq!(manager.get_bool("sovereignty.enabled").await, Some(true));
+
+        let reloaded = ConfigManager::new(ConfigManagerConfig {
+            config_dir: dir.path().to_path_buf(),
+            ..Default::default()
+        });
+        reloaded.load_from_file().await.unwrap();
+        assert_eq!(reloaded.get_i64("server.port").await, Some(8080));
+        assert_eq!(reloaded.get_bool("sovereignty.enabled").await, Some(true));
+
+        // 不合法的值不会写入文件
+        let invalid = HashMap::from([(
+            "sovereignty.anti_addiction.daily_limit_minutes".to_string(),
+            ConfigValue::Integer(5000),
+        )]);
+        assert!(manager.persist(invalid, "cli".to_string()).await.is_err());
+        assert!(!std::fs::read_to_string(&path).unwrap().contains("daily_limit_minutes"));
+    }
+
+    #[tokio::test]
+    async fn test_environment_sections_and_guards() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("config.json"),
+            r#"{
+                "router": {"max_iterations": 3},
+                "protocol": {"ghost": {"enabled": true}},
+                "environments": {"production": {"router": {"max_iterations": 5}}}
+            }"#,
+        )
+        .unwrap();
+
+        let manager = ConfigManager::new(ConfigManagerConfig {
+            environment: Environment::Production,
+            config_dir: dir.path().to_path_buf(),
+            ..Default::default()
+        });
+        manager.load_from_file().await.unwrap();
+
+        assert_eq!(manager.get_i64("router.max_iterations").await, Some(5));
+        assert_eq!(manager.get_bool("protocol.ghost.enabled").await, Some(false));
+        let denied = manager.check_protocol("GHOST").await.unwrap_err();
+        assert!(denied.to_string().contains("guards.allow_ghost_in_production"));
+        assert!(manager.check_protocol("ARCHITECT").await.is_ok());
+        assert!(manager
+            .set("protocol.ghost.enabled".to_string(), ConfigValue::Boolean(true), false, true, "ops".to_string())
+            .await
+            .is_err());
+
+        let diff = manager
+            .diff_profiles(Environment::Development, Environment::Production)
+            .await
+            .unwrap();
+        let keys: Vec<&str> = diff.iter().map(|d| d.key.as_str()).collect();
+        assert_eq!(keys, vec!["protocol.ghost.enabled", "router.max_iterations"]);
+
+        manager
+            .set("guards.allow_ghost_in_production".to_string(), ConfigValue::Boolean(true), false, true, "ops".to_string())
+            .await
+            .unwrap();
+        manager
+            .set("protocol.ghost.enabled".to_string(), ConfigValue::Boolean(true), false, true, "ops".to_string())
+            .await
+            .unwrap();
+        assert_eq!(manager.get_bool("protocol.ghost.enabled").await, Some(true));
+        assert!(manager.check_protocol("GHOST").await.is_ok());
+
+        // 未加载任何配置时守卫同样生效；开发环境不受限制
+        let empty = ConfigManager::new(ConfigManagerConfig {
+            environment: Environment::Production,
+            ..Default::default()
+        });
+        assert!(empty.check_protocol("ghost").await.is_err());
+        assert!(ConfigManager::new(ConfigManagerConfig::default()).check_protocol("ghost").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_secret_references_resolved_and_masked() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("development.json"),
+            r#"{"providers": {"openai": {"api_key": "${secret:env:ACSA_TEST_CFG_OPENAI}"}}}"#,
+        )
+        .unwrap();
+        std::env::set_var("ACSA_TEST_CFG_OPENAI", "sk-live-123");
+
+        let manager = ConfigManager::new(ConfigManagerConfig {
+            config_dir: dir.path().to_path_buf(),
+            ..Default::default()
+        });
+        manager.load_from_file().await.unwrap();
+
+        assert_eq!(
+            manager.get_string("providers.openai.api_key").await,
+            Some("sk-live-123".to_string())
+        );
+        let exported = manager.export_config().await.unwrap();
+        assert!(!exported.contains("sk-live-123"));
+    }
+
+    #[tokio::test]
+    async fn test_validate_effective_config() {
+        let manager = ConfigManager::new(ConfigManagerConfig::default());
+        manager
+            .set("router.risk_threshold".to_string(), ConfigValue::Integer(120), false, true, "ops".to_string())
+            .await
+            .unwrap();
+
+        let errors = manager.validate().await.unwrap();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("router.risk_threshold"));
+    }
+
+    #[test]
+    fn test_environment_from_str() {
+        assert_eq!("prod".parse::<Environment>().unwrap(), Environment::Production);
+        assert_eq!("Development".parse::<Environment>().unwrap(), Environment::Development);
+        assert!("qa".parse::<Environment>().is_err());
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct LimitsSection {
+        max_requests: u32,
+        #[serde(default)]
+        burst: u32,
+    }
+
+    #[tokio::test]
+    async fn test_typed_section_and_listener() {
+        let manager = ConfigManager::new(ConfigManagerConfig::default());
+        let shared = Arc::new(RwLock::new(LimitsSection { max_requests: 10, burst: 0 }));
+        manager
+            .register_listener(Box::new(SectionConfigListener::new("limits", shared.clone())))
+            .await;
+
+        manager
+            .set("limits.max_requests".to_string(), ConfigValue::Integer(50), false, true, "ops".to_string())
+            .await
+            .unwrap();
+        assert_eq!(shared.read().await.max_requests, 50);
+
+        let section: LimitsSection = manager.get_section("limits").await.unwrap();
+        assert_eq!(section.max_requests, 50);
+
+        // 类型错误的变更被拒绝
+        manager
+            .set(
+                "limits.max_requests".to_string(),
+                ConfigValue::String("lots".to_string()),
+                false,
+                true,
+                "ops".to_string(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(shared.read().await.max_requests, 50);
+        assert!(manager.get_typed::<u32>("limits.max_requests").await.is_err());
+    }
 }
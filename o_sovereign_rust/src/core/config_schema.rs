@@ -0,0 +1,438 @@
+// Config Schema - 配置模式校验
+// 对加载的配置做一次模式校验，输出可操作的错误信息
+//
+// 核心功能：
+// 1. 未知键检测（附带"是否想写"建议）
+// 2. 类型不匹配检测
+// 3. 取值范围检测
+// 4. 错误定位到文件和行号
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::config_manager::{ConfigValue, Environment};
+use super::secrets::SecretResolver;
+
+/// 期望的值类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ValueKind {
+    String,
+    Integer,
+    /// 整数或浮点数
+    Number,
+    Boolean,
+    Array,
+    /// 不检查类型
+    Any,
+}
+
+impl ValueKind {
+    fn matches(&self, value: &ConfigValue) -> bool {
+        match (self, value) {
+            (ValueKind::Any, _) => true,
+            // 密钥引用在解析前是字符串，允许出现在任何类型的位置
+            (_, ConfigValue::String(s)) if SecretResolver::contains_reference(s) => true,
+            (ValueKind::String, ConfigValue::String(_)) => true,
+            (ValueKind::Integer, ConfigValue::Integer(_)) => true,
+            (ValueKind::Number, ConfigValue::Integer(_) | ConfigValue::Float(_)) => true,
+            (ValueKind::Boolean, ConfigValue::Boolean(_)) => true,
+            (ValueKind::Array, ConfigValue::Array(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+fn describe(value: &ConfigValue) -> &'static str {
+    match value {
+        ConfigValue::String(_) => "string",
+        ConfigValue::Integer(_) => "integer",
+        ConfigValue::Float(_) => "float",
+        ConfigValue::Boolean(_) => "boolean",
+        ConfigValue::Array(_) => "array",
+        ConfigValue::Object(_) => "object",
+    }
+}
+
+/// 模式字段；`pattern` 中的 `*` 匹配任意单个键段
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaField {
+    pub pattern: String,
+    pub kind: ValueKind,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl SchemaField {
+    pub fn new(pattern: impl Into<String>, kind: ValueKind) -> Self {
+        Self {
+            pattern: pattern.into(),
+            kind,
+            min: None,
+            max: None,
+        }
+    }
+
+    /// 设置取值范围（闭区间）
+    pub fn range(mut self, min: f64, max: f64) -> Self {
+        self.min = Some(min);
+        self.max = Some(max);
+        self
+    }
+
+    /// 设置最小值
+    pub fn min(mut self, min: f64) -> Self {
+        self.min = Some(min);
+        self
+    }
+
+    fn matches(&self, key: &str) -> bool {
+        let mut pattern = self.pattern.split('.');
+        let mut segments = key.split('.');
+        loop {
+            match (pattern.next(), segments.next()) {
+                (None, None) => return true,
+                (Some("*"), Some(_)) => continue,
+                (Some(p), Some(s)) if p == s => continue,
+                _ => return false,
+            }
+        }
+    }
+}
+
+/// 问题类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConfigIssueKind {
+    UnknownKey,
+    TypeMismatch,
+    OutOfRange,
+    ParseError,
+}
+
+/// 校验问题
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigIssue {
+    pub kind: ConfigIssueKind,
+    pub key: String,
+    pub file: Option<PathBuf>,
+    pub line: Option<usize>,
+    pub message: String,
+    pub hint: Option<String>,
+}
+
+impl std::fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}", file.display())?;
+            if let Some(line) = self.line {
+                write!(f, ":{}", line)?;
+            }
+            write!(f, ": ")?;
+        }
+        write!(f, "{}: {}", self.key, self.message)?;
+        if let Some(hint) = &self.hint {
+            write!(f, " ({})", hint)?;
+        }
+        Ok(())
+    }
+}
+
+/// 配置模式
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConfigSchema {
+    fields: Vec<SchemaField>,
+}
+
+impl ConfigSchema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 内置组件的配置模式
+    pub fn builtin() -> Self {
+        let mut fields = vec![
+            // ACSARouter
+            SchemaField::new("router.max_iterations", ValueKind::Integer).range(1.0, 20.0),
+            SchemaField::new("router.risk_threshold", ValueKind::Integer).range(0.0, 100.0),
+            SchemaField::new("router.enable_l6", ValueKind::Boolean),
+            SchemaField::new("router.enable_streaming", ValueKind::Boolean),
+            // 协议与守卫
+            SchemaField::new("protocol.*.enabled", ValueKind::Boolean),
+            SchemaField::new("guards.*", ValueKind::Boolean),
+            // SovereigntyConfig
+            SchemaField::new("sovereignty.enabled", ValueKind::Boolean),
+            SchemaField::new("sovereignty.lambda", ValueKind::Number).range(0.0, 1.0),
+            SchemaField::new("sovereignty.initial_wisdom", ValueKind::Number).min(0.0),
+            SchemaField::new("sovereignty.show_warnings", ValueKind::Boolean),
+            SchemaField::new("sovereignty.circuit_breaker.consecutive_delegations_threshold", ValueKind::Integer).min(1.0),
+            SchemaField::new("sovereignty.circuit_breaker.no_thinking_threshold_secs", ValueKind::Integer).min(0.0),
+            SchemaField::new("sovereignty.circuit_breaker.auto_confirm_threshold", ValueKind::Number).range(0.0, 1.0),
+            SchemaField::new("sovereignty.anti_addiction.enabled", ValueKind::Boolean),
+            SchemaField::new("sovereignty.anti_addiction.daily_limit_minutes", ValueKind::Integer).range(0.0, 1440.0),
+            SchemaField::new("sovereignty.anti_addiction.session_limit_minutes", ValueKind::Integer).range(0.0, 1440.0),
+            SchemaField::new("sovereignty.anti_addiction.break_reminder_minutes", ValueKind::Integer).range(0.0, 1440.0),
+            SchemaField::new("sovereignty.anti_addiction.enforce_break", ValueKind::Boolean),
+            SchemaField::new("sovereignty.anti_addiction.break_duration_minutes", ValueKind::Integer).range(0.0, 1440.0),
+            // 提供商
+            SchemaField::new("providers.*.api_key", ValueKind::String),
+            SchemaField::new("providers.*.model", ValueKind::String),
+            SchemaField::new("providers.*.base_url", ValueKind::String),
+            // OfflineConfig
+            SchemaField::new("offline.enabled", ValueKind::Boolean),
+            SchemaField::new("offline.llm_endpoint", ValueKind::String),
+            SchemaField::new("offline.llm_model", ValueKind::String),
+        ];
+
+        // RateLimiterConfig
+        for rule in ["global_rule", "ip_rule", "user_rule", "api_key_rule", "tenant_rule", "endpoint_rules.*", "provider_rules.*"] {
+            let prefix = format!("rate_limiter.{}", rule);
+            fields.extend([
+                SchemaField::new(format!("{}.rule_id", prefix), ValueKind::String),
+                SchemaField::new(format!("{}.level", prefix), ValueKind::String),
+                SchemaField::new(format!("{}.strategy", prefix), ValueKind::String),
+                SchemaField::new(format!("{}.requests_per_second", prefix), ValueKind::Number).min(0.0),
+                SchemaField::new(format!("{}.bucket_capacity", prefix), ValueKind::Integer).min(1.0),
+                SchemaField::new(format!("{}.window_size_secs", prefix), ValueKind::Integer).min(1.0),
+                SchemaField::new(format!("{}.enabled", prefix), ValueKind::Boolean),
+            ]);
+        }
+
+        Self { fields }
+    }
+
+    /// 添加字段
+    pub fn extend(&mut self, fields: impl IntoIterator<Item = SchemaField>) {
+        self.fields.extend(fields);
+    }
+
+    /// 键对应的模式字段（后注册的优先）
+    pub fn field_for(&self, key: &str) -> Option<&SchemaField> {
+        self.fields.iter().rev().find(|f| f.matches(key))
+    }
+
+    /// 校验一组扁平化的配置值
+    pub fn validate_values(&self, values: &HashMap<String, ConfigValue>) -> Vec<ConfigIssue> {
+        let mut keys: Vec<&String> = values.keys().collect();
+        keys.sort();
+        keys.into_iter()
+            .filter_map(|key| self.validate_value(key, &values[key]))
+            .collect()
+    }
+
+    fn validate_value(&self, key: &str, value: &ConfigValue) -> Option<ConfigIssue> {
+        let issue = |kind, message: String, hint: Option<String>| ConfigIssue {
+            kind,
+            key: key.to_string(),
+            file: None,
+            line: None,
+            message,
+            hint,
+        };
+
+        let Some(field) = self.field_for(key) else {
+            let hint = self.suggest(key).map(|s| format!("did you mean '{}'?", s));
+            return Some(issue(ConfigIssueKind::UnknownKey, "unknown config key".to_string(), hint));
+        };
+
+        if !field.kind.matches(value) {
+            return Some(issue(
+                ConfigIssueKind::TypeMismatch,
+                format!("expected {:?}, found {}", field.kind, describe(value)),
+                None,
+            ));
+        }
+
+        let number = match value {
+            ConfigValue::Integer(i) => Some(*i as f64),
+            ConfigValue::Float(f) => Some(*f),
+            _ => None,
+        }?;
+
+        let below = field.min.is_some_and(|min| number < min);
+        let above = field.max.is_some_and(|max| number > max);
+        if below || above {
+            let range = match (field.min, field.max) {
+                (Some(min), Some(max)) => format!("allowed range is {}..={}", min, max),
+                (Some(min), None) => format!("must be >= {}", min),
+                (None, Some(max)) => format!("must be <= {}", max),
+                (None, None) => unreachable!(),
+            };
+            return Some(issue(ConfigIssueKind::OutOfRange, format!("value {} is out of range", number), Some(range)));
+        }
+
+        None
+    }
+
+    /// 校验配置文件；`config.json` 中的 `environments.{env}` 段按普通键校验
+    pub fn validate_file(&self, path: &Path) -> Result<Vec<ConfigIssue>> {
+        let content = std::fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+
+        let mut json: serde_json::Value = match serde_json::from_str(&content) {
+            Ok(json) => json,
+            Err(e) => {
+                return Ok(vec![ConfigIssue {
+                    kind: ConfigIssueKind::ParseError,
+                    key: "<file>".to_string(),
+                    file: Some(path.to_path_buf()),
+                    line: Some(e.line()),
+                    message: e.to_string(),
+                    hint: None,
+                }]);
+            }
+        };
+
+        let mut sections = vec![(String::new(), HashMap::new())];
+        let mut issues = Vec::new();
+
+        if let Some(envs) = json.as_object_mut().and_then(|m| m.remove("environments")) {
+            for (env_name, section) in envs.as_object().cloned().unwrap_or_default() {
+                if env_name.parse::<Environment>().is_err() {
+                    issues.push(ConfigIssue {
+                        kind: ConfigIssueKind::UnknownKey,
+                        key: format!("environments.{}", env_name),
+                        file: Some(path.to_path_buf()),
+                        line: locate_key(&content, &["environments", &env_name]),
+                        message: "unknown environment".to_string(),
+                        hint: Some("expected development, staging or production".to_string()),
+                    });
+                    continue;
+                }
+                let mut values = HashMap::new();
+                flatten("", &section, &mut values, &mut issues);
+                sections.push((format!("environments.{}.", env_name), values));
+            }
+        }
+        flatten("", &json, &mut sections[0].1, &mut issues);
+
+        for (prefix, values) in sections {
+            for mut issue in self.validate_values(&values) {
+                let full_key = format!("{}{}", prefix, issue.key);
+                let segments: Vec<&str> = full_key.split('.').collect();
+                issue.line = locate_key(&content, &segments);
+                issue.file = Some(path.to_path_buf());
+                issue.key = full_key;
+                issues.push(issue);
+            }
+        }
+
+        for issue in issues.iter_mut() {
+            issue.file.get_or_insert_with(|| path.to_path_buf());
+        }
+        Ok(issues)
+    }
+
+    /// 编辑距离最近的已知键
+    fn suggest(&self, key: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .map(|f| (edit_distance(key, &f.pattern), f.pattern.as_str()))
+            .filter(|(distance, _)| *distance <= 3)
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, pattern)| pattern)
+    }
+}
+
+/// 扁平化JSON（null忽略，无法表示的值记为解析问题）
+fn flatten(
+    prefix: &str,
+    value: &serde_json::Value,
+    out: &mut HashMap<String, ConfigValue>,
+    issues: &mut Vec<ConfigIssue>,
+) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (k, v) in map {
+                let key = if prefix.is_empty() { k.clone() } else { format!("{}.{}", prefix, k) };
+                flatten(&key, v, out, issues);
+            }
+        }
+        serde_json::Value::Null => {}
+        other => match serde_json::from_value(other.clone()) {
+            Ok(config_value) => {
+                out.insert(prefix.to_string(), config_value);
+            }
+            Err(e) => issues.push(ConfigIssue {
+                kind: ConfigIssueKind::ParseError,
+                key: prefix.to_string(),
+                file: None,
+                line: None,
+                message: e.to_string(),
+                hint: None,
+            }),
+        },
+    }
+}
+
+/// 按键段顺序在文本中定位行号（从1开始）
+fn locate_key(content: &str, segments: &[&str]) -> Option<usize> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut line = 0;
+
+    for segment in segments {
+        let needle = format!("\"{}\"", segment);
+        line += lines[line..].iter().position(|l| {
+            l.find(&needle)
+                .map(|pos| l[pos + needle.len()..].trim_start().starts_with(':'))
+                .unwrap_or(false)
+        })?;
+    }
+
+    Some(line + 1)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            current[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(current[j] + 1);
+        }
+        prev = current;
+    }
+
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_values() {
+        let schema = ConfigSchema::builtin();
+        let values = HashMap::from([
+            ("router.risk_threshold".to_string(), ConfigValue::Integer(150)),
+            ("router.enable_l6".to_string(), ConfigValue::String("yes".to_string())),
+            ("router.max_iteration".to_string(), ConfigValue::Integer(3)),
+            ("rate_limiter.endpoint_rules.chat.enabled".to_string(), ConfigValue::Boolean(true)),
+        ]);
+
+        let issues = schema.validate_values(&values);
+        assert_eq!(issues.len(), 3);
+        assert_eq!(issues[0].kind, ConfigIssueKind::TypeMismatch);
+        assert_eq!(issues[1].kind, ConfigIssueKind::UnknownKey);
+        assert_eq!(issues[1].hint.as_deref(), Some("did you mean 'router.max_iterations'?"));
+        assert_eq!(issues[2].kind, ConfigIssueKind::OutOfRange);
+    }
+
+    #[test]
+    fn test_validate_file_reports_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        std::fs::write(
+            &path,
+            "{\n  \"router\": {\n    \"risk_threshold\": 70\n  },\n  \"environments\": {\n    \"production\": {\n      \"router\": {\n        \"risk_threshold\": 101\n      }\n    }\n  }\n}\n",
+        )
+        .unwrap();
+
+        let issues = ConfigSchema::builtin().validate_file(&path).unwrap();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].key, "environments.production.router.risk_threshold");
+        assert_eq!(issues[0].line, Some(8));
+        assert!(issues[0].to_string().contains("config.json:8"));
+    }
+}
@@ -0,0 +1,511 @@
+// Contract Analyzer - 合同条款风险分析
+// Aegis 的合同审阅能力：导入合同 → 切分条款 → 按规则库打分 → 输出排序后的风险报告与修改建议
+//
+// 核心功能：
+// 1. 导入：经 MultimodalProcessor 读取文本/Markdown，DOCX 解包提取正文
+// 2. 条款切分：识别 `1.` / `1.2` / `Section 3` / `Article 4` / `第五条` 等编号
+// 3. 规则库：责任上限、赔偿、自动续约等，可从 JSON 加载或追加
+// 4. 报告：按风险排序的发现列表 + 建议修改文本 (redline)
+
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::path::Path;
+use std::sync::LazyLock;
+use tracing::info;
+
+use super::aegis::ClauseRiskLevel;
+use super::multimodal::{ModalityType, MultimodalProcessor};
+
+static CLAUSE_NUMBER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*((?:\d+(?:\.\d+)*\.?)|(?:(?:Section|SECTION|Article|ARTICLE|Clause|CLAUSE)\s+\d+(?:\.\d+)*)|(?:第[一二三四五六七八九十百零\d]+条))[\s:：.)]")
+        .unwrap()
+});
+static DOCX_PARAGRAPH: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?s)<w:p[ >].*?</w:p>").unwrap());
+static DOCX_TEXT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?s)<w:t(?: [^>]*)?>(.*?)</w:t>").unwrap());
+
+/// 风险规则
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RiskRule {
+    pub id: String,
+    pub name: String,
+    /// 命中任一模式即触发（正则，匹配小写化后的条款文本）
+    pub patterns: Vec<String>,
+    /// 命中任一缓解模式则不报告（如已有责任上限）
+    #[serde(default)]
+    pub mitigations: Vec<String>,
+    /// 风险分 (0-100)
+    pub score: u8,
+    pub rationale: String,
+    /// 建议修改文本
+    pub redline: String,
+}
+
+impl RiskRule {
+    fn compile(&self) -> Result<CompiledRule<'_>> {
+        let compile = |patterns: &[String]| -> Result<Vec<Regex>> {
+            patterns
+                .iter()
+                .map(|p| Regex::new(p).map_err(|e| anyhow!("Invalid pattern in rule {}: {}", self.id, e)))
+                .collect()
+        };
+        Ok(CompiledRule {
+            rule: self,
+            patterns: compile(&self.patterns)?,
+            mitigations: compile(&self.mitigations)?,
+        })
+    }
+}
+
+struct CompiledRule<'a> {
+    rule: &'a RiskRule,
+    patterns: Vec<Regex>,
+    mitigations: Vec<Regex>,
+}
+
+/// 风险规则库
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RiskRuleLibrary {
+    pub rules: Vec<RiskRule>,
+}
+
+impl RiskRuleLibrary {
+    pub fn empty() -> Self {
+        Self { rules: Vec::new() }
+    }
+
+    /// 从 JSON 加载（`{"rules": [...]}`）
+    pub fn from_json(json: &str) -> Result<Self> {
+        let library: Self = serde_json::from_str(json)?;
+        for rule in &library.rules {
+            rule.compile()?;
+        }
+        Ok(library)
+    }
+
+    /// 追加或替换同 ID 规则
+    pub fn add_rule(&mut self, rule: RiskRule) -> Result<()> {
+        rule.compile()?;
+        self.rules.retain(|r| r.id != rule.id);
+        self.rules.push(rule);
+        Ok(())
+    }
+}
+
+impl Default for RiskRuleLibrary {
+    fn default() -> Self {
+        let rule = |id: &str, name: &str, patterns: &[&str], mitigations: &[&str], score: u8, rationale: &str, redline: &str| RiskRule {
+            id: id.to_string(),
+            name: name.to_string(),
+            patterns: patterns.iter().map(|p| p.to_string()).collect(),
+            mitigations: mitigations.iter().map(|p| p.to_string()).collect(),
+            score,
+            rationale: rationale.to_string(),
+            redline: redline.to_string(),
+        };
+
+        Self {
+            rules: vec![
+                rule(
+                    "unlimited_liability",
+                    "Unlimited liability",
+                    &[r"unlimited liability", r"liable for (?:any and )?all", r"without limit", r"无限责任", r"承担全部责任"],
+                    &[],
+                    90,
+                    "Exposure is uncapped; a single claim can exceed the contract value.",
+                    "Each party's aggregate liability under this Agreement shall not exceed the fees paid in the twelve (12) months preceding the claim.",
+                ),
+                rule(
+                    "liability_cap_missing",
+                    "Liability without cap",
+                    &[r"\bliab(?:le|ility)\b", r"赔偿责任"],
+                    &[r"shall not exceed", r"limited to", r"\bcap\b", r"aggregate liability", r"上限", r"不超过"],
+                    60,
+                    "Liability clause does not state a monetary cap.",
+                    "Add: \"In no event shall either party's aggregate liability exceed [amount].\"",
+                ),
+                rule(
+                    "one_way_indemnity",
+                    "One-sided indemnity",
+                    &[r"indemnif", r"hold harmless", r"使.{0,6}免受", r"赔偿.{0,4}损失"],
+                    &[r"\bmutual", r"each party shall indemnify", r"to the extent caused by", r"双方"],
+                    75,
+                    "Indemnity obligation runs one way and is not limited to losses caused by the indemnifying party.",
+                    "Make the indemnity mutual and limit it \"to the extent caused by the indemnifying party's breach or negligence\".",
+                ),
+                rule(
+                    "auto_renewal",
+                    "Automatic renewal",
+                    &[r"automatically renew", r"auto-renew", r"renew(?:s|ed)? automatically", r"自动续", r"自动延长"],
+                    &[r"\d+\s*days'? (?:prior )?(?:written )?notice", r"notice of non-renewal", r"may terminate"],
+                    55,
+                    "Term renews automatically without a clear opt-out window.",
+                    "Add: \"Either party may prevent renewal by giving thirty (30) days' written notice before the end of the then-current term.\"",
+                ),
+                rule(
+                    "unilateral_amendment",
+                    "Unilateral amendment",
+                    &[r"may (?:amend|modify|change) (?:these terms|this agreement)", r"at (?:its|their) sole discretion", r"有权单方"],
+                    &[r"mutual written", r"signed by both"],
+                    70,
+                    "Counterparty can change terms without consent.",
+                    "Amendments shall be effective only if made in writing and signed by both parties.",
+                ),
+                rule(
+                    "consequential_damages",
+                    "Consequential damages not excluded",
+                    &[r"consequential", r"indirect (?:loss|damage)", r"lost profits", r"间接损失"],
+                    &[r"in no event", r"shall not be liable for", r"excluded", r"不承担"],
+                    50,
+                    "Consequential or indirect losses are recoverable.",
+                    "Add: \"Neither party shall be liable for indirect, incidental or consequential damages, including lost profits.\"",
+                ),
+            ],
+        }
+    }
+}
+
+/// 合同条款
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContractClause {
+    pub index: usize,
+    /// 原文编号（如 `8.2`、`Section 4`、`第五条`）
+    pub number: Option<String>,
+    pub text: String,
+}
+
+/// 单条发现
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClauseFinding {
+    pub clause_index: usize,
+    pub clause_number: Option<String>,
+    pub rule_id: String,
+    pub rule_name: String,
+    pub score: u8,
+    pub level: ClauseRiskLevel,
+    /// 命中的原文片段
+    pub excerpt: String,
+    pub rationale: String,
+    pub suggested_redline: String,
+}
+
+/// 合同风险报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContractReport {
+    pub source: Option<String>,
+    pub clauses: Vec<ContractClause>,
+    /// 按风险分从高到低排序
+    pub findings: Vec<ClauseFinding>,
+}
+
+impl ContractReport {
+    /// 整体风险：最高分
+    pub fn overall_score(&self) -> u8 {
+        self.findings.first().map(|f| f.score).unwrap_or(0)
+    }
+
+    pub fn findings_for(&self, clause_index: usize) -> Vec<&ClauseFinding> {
+        self.findings.iter().filter(|f| f.clause_index == clause_index).collect()
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut md = format!(
+            "# 合同风险报告\n\n来源: {}\n条款数: {} · 发现: {} · 最高风险: {}/100\n\n",
+            self.source.as_deref().unwrap_or("-"),
+            self.clauses.len(),
+            self.findings.len(),
+            self.overall_score()
+        );
+
+        for (rank, finding) in self.findings.iter().enumerate() {
+            md.push_str(&format!(
+                "## {}. [{:?} {}/100] {} — 条款 {}\n\n> {}\n\n**原因:** {}\n\n**建议修改:** {}\n\n",
+                rank + 1,
+                finding.level,
+                finding.score,
+                finding.rule_name,
+                finding.clause_number.as_deref().unwrap_or(&finding.clause_index.to_string()),
+                finding.excerpt,
+                finding.rationale,
+                finding.suggested_redline
+            ));
+        }
+        md
+    }
+}
+
+/// 合同分析器
+pub struct ContractAnalyzer {
+    library: RiskRuleLibrary,
+    processor: MultimodalProcessor,
+}
+
+impl ContractAnalyzer {
+    pub fn new(library: RiskRuleLibrary) -> Self {
+        Self {
+            library,
+            processor: MultimodalProcessor::new(),
+        }
+    }
+
+    pub fn library(&self) -> &RiskRuleLibrary {
+        &self.library
+    }
+
+    /// 导入合同文件：文本类经 MultimodalProcessor 读取，DOCX 解包提取正文
+    pub async fn ingest(&self, path: &Path) -> Result<String> {
+        let input = self.processor.process_file(path).await?;
+        match (&input.modality, input.metadata.is_base64) {
+            (ModalityType::File { .. }, false) | (ModalityType::Text, _) => Ok(input.content),
+            (ModalityType::File { extension }, true) if extension == "docx" => {
+                let bytes = tokio::fs::read(path).await?;
+                extract_docx_text(&bytes)
+            }
+            (modality, _) => Err(anyhow!(
+                "Unsupported contract format {:?}; convert it to text, Markdown or DOCX",
+                modality
+            )),
+        }
+    }
+
+    /// 分析合同文件
+    pub async fn analyze_file(&self, path: &Path) -> Result<ContractReport> {
+        let text = self.ingest(path).await?;
+        let mut report = self.analyze(&text)?;
+        report.source = Some(path.display().to_string());
+        Ok(report)
+    }
+
+    /// 分析合同文本
+    pub fn analyze(&self, text: &str) -> Result<ContractReport> {
+        let clauses = segment_clauses(text);
+        let rules = self
+            .library
+            .rules
+            .iter()
+            .map(RiskRule::compile)
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut findings = Vec::new();
+        for clause in &clauses {
+            let lower = clause.text.to_lowercase();
+            for compiled in &rules {
+                let Some(hit) = compiled.patterns.iter().find_map(|p| p.find(&lower)) else {
+                    continue;
+                };
+                if compiled.mitigations.iter().any(|m| m.is_match(&lower)) {
+                    continue;
+                }
+
+                let rule = compiled.rule;
+                findings.push(ClauseFinding {
+                    clause_index: clause.index,
+                    clause_number: clause.number.clone(),
+                    rule_id: rule.id.clone(),
+                    rule_name: rule.name.clone(),
+                    score: rule.score,
+                    level: ClauseRiskLevel::from_score(rule.score),
+                    excerpt: excerpt(&lower, &clause.text, hit.start(), hit.end()),
+                    rationale: rule.rationale.clone(),
+                    suggested_redline: rule.redline.clone(),
+                });
+            }
+        }
+
+        findings.sort_by(|a, b| b.score.cmp(&a.score).then(a.clause_index.cmp(&b.clause_index)));
+        info!("📑 Contract analyzed: {} clauses, {} findings", clauses.len(), findings.len());
+
+        Ok(ContractReport {
+            source: None,
+            clauses,
+            findings,
+        })
+    }
+}
+
+impl Default for ContractAnalyzer {
+    fn default() -> Self {
+        Self::new(RiskRuleLibrary::default())
+    }
+}
+
+/// 按编号切分条款；没有编号时按空行分段
+pub fn segment_clauses(text: &str) -> Vec<ContractClause> {
+    let mut clauses: Vec<ContractClause> = Vec::new();
+    let numbered = text.lines().filter(|l| CLAUSE_NUMBER.is_match(l)).count() >= 2;
+
+    let mut current: Option<(Option<String>, Vec<&str>)> = None;
+    let push = |entry: Option<(Option<String>, Vec<&str>)>, clauses: &mut Vec<ContractClause>| {
+        if let Some((number, lines)) = entry {
+            let text = lines.join("\n").trim().to_string();
+            if !text.is_empty() {
+                clauses.push(ContractClause {
+                    index: clauses.len() + 1,
+                    number,
+                    text,
+                });
+            }
+        }
+    };
+
+    for line in text.lines() {
+        let starts_clause = if numbered {
+            CLAUSE_NUMBER.captures(line).map(|c| Some(c[1].trim_end_matches('.').to_string()))
+        } else if line.trim().is_empty() {
+            Some(None)
+        } else {
+            None
+        };
+
+        match starts_clause {
+            Some(number) => {
+                push(current.take(), &mut clauses);
+                current = Some((number, vec![line]));
+            }
+            None => current.get_or_insert_with(|| (None, Vec::new())).1.push(line),
+        }
+    }
+    push(current, &mut clauses);
+
+    clauses
+}
+
+/// DOCX → 纯文本（每个段落一行）
+pub fn extract_docx_text(bytes: &[u8]) -> Result<String> {
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes))?;
+    let mut xml = String::new();
+    archive
+        .by_name("word/document.xml")
+        .map_err(|_| anyhow!("Not a DOCX document: word/document.xml missing"))?
+        .read_to_string(&mut xml)?;
+
+    let paragraphs: Vec<String> = DOCX_PARAGRAPH
+        .find_iter(&xml)
+        .map(|p| {
+            DOCX_TEXT
+                .captures_iter(p.as_str())
+                .map(|t| {
+                    t[1].replace("&lt;", "<")
+                        .replace("&gt;", ">")
+                        .replace("&quot;", "\"")
+                        .replace("&apos;", "'")
+                        .replace("&amp;", "&")
+                })
+                .collect::<String>()
+        })
+        .collect();
+
+    Ok(paragraphs.join("\n"))
+}
+
+/// 命中位置前后各取一段原文（小写化不改变 ASCII 长度，非 ASCII 时退回整条）
+fn excerpt(lower: &str, original: &str, start: usize, end: usize) -> String {
+    if lower.len() != original.len() {
+        return original.chars().take(200).collect();
+    }
+
+    let floor = |mut i: usize| {
+        while !original.is_char_boundary(i) {
+            i -= 1;
+        }
+        i
+    };
+    let ceil = |mut i: usize| {
+        while !original.is_char_boundary(i) {
+            i += 1;
+        }
+        i
+    };
+
+    let from = floor(start.saturating_sub(80));
+    let to = ceil((end + 80).min(original.len()));
+    let mut text = original[from..to].trim().replace('\n', " ");
+    if from > 0 {
+        text.insert(0, '…');
+    }
+    if to < original.len() {
+        text.push('…');
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTRACT: &str = "MASTER SERVICES AGREEMENT\n\
+        1. Term. This Agreement shall automatically renew for successive one-year terms.\n\
+        2. Liability. Supplier shall be liable for any and all losses arising from the Services.\n\
+        3. Indemnity. Customer shall indemnify and hold harmless Supplier from all claims.\n\
+        4. Fees. Supplier's aggregate liability shall not exceed the fees paid.\n\
+        5. Changes. Supplier may amend this Agreement at its sole discretion.\n";
+
+    #[test]
+    fn test_segment_numbered_clauses() {
+        let clauses = segment_clauses(CONTRACT);
+        assert_eq!(clauses.len(), 6);
+        assert_eq!(clauses[1].number.as_deref(), Some("1"));
+        assert!(clauses[1].text.starts_with("1. Term."));
+
+        let chinese = segment_clauses("第一条 合同期限\n本合同自动续约。\n第二条 违约责任\n乙方承担无限责任。");
+        assert_eq!(chinese.len(), 2);
+        assert_eq!(chinese[1].number.as_deref(), Some("第二条"));
+    }
+
+    #[test]
+    fn test_analyze_ranks_findings() {
+        let report = ContractAnalyzer::default().analyze(CONTRACT).unwrap();
+
+        assert_eq!(report.overall_score(), 90);
+        assert_eq!(report.findings[0].rule_id, "unlimited_liability");
+        assert_eq!(report.findings[0].clause_number.as_deref(), Some("2"));
+        assert!(report.findings.iter().any(|f| f.rule_id == "auto_renewal"));
+        assert!(report.findings.iter().any(|f| f.rule_id == "one_way_indemnity"));
+        assert!(report.findings.iter().any(|f| f.rule_id == "unilateral_amendment"));
+
+        // 第4条已有责任上限，不应报告
+        assert!(report.findings_for(5).is_empty());
+        assert!(report.findings.windows(2).all(|w| w[0].score >= w[1].score));
+        assert!(report.to_markdown().contains("建议修改"));
+    }
+
+    #[test]
+    fn test_custom_rule_library() {
+        let mut library = RiskRuleLibrary::from_json(
+            r#"{"rules":[{"id":"non_compete","name":"Non-compete","patterns":["non-compete","shall not compete"],"score":65,"rationale":"Restricts future business","redline":"Limit to 12 months"}]}"#,
+        )
+        .unwrap();
+        assert!(library
+            .add_rule(RiskRule {
+                id: "broken".to_string(),
+                name: "Broken".to_string(),
+                patterns: vec!["(".to_string()],
+                mitigations: Vec::new(),
+                score: 1,
+                rationale: String::new(),
+                redline: String::new(),
+            })
+            .is_err());
+
+        let report = ContractAnalyzer::new(library)
+            .analyze("1. Employee shall not compete for 5 years.\n2. Salary is paid monthly.")
+            .unwrap();
+        assert_eq!(report.findings.len(), 1);
+        assert_eq!(report.findings[0].level, ClauseRiskLevel::Medium);
+    }
+
+    #[tokio::test]
+    async fn test_ingest_docx() {
+        use crate::core::aegis::{DefenseDocType, DefenseDocument};
+
+        let doc = DefenseDocument::new(DefenseDocType::ComplianceMemo, "Contract", "1. Fees are due monthly.\n2. This Agreement shall automatically renew each year.");
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("contract.docx");
+        tokio::fs::write(&path, doc.to_docx().unwrap()).await.unwrap();
+
+        let report = ContractAnalyzer::default().analyze_file(&path).await.unwrap();
+        assert_eq!(report.clauses.len(), 3);
+        assert_eq!(report.findings[0].rule_id, "auto_renewal");
+    }
+}
@@ -0,0 +1,334 @@
+// Conversation - 多轮会话模式
+// 在 AgentStateManager 的会话之上连续执行 ACSA 链路，后续各轮的 MOSS 规划能看到之前的方案
+//
+// 核心功能：
+// 1. 每轮执行后把用户输入、MOSS 方案、最终输出与成本记入会话历史
+// 2. 最近若干轮整理为对话上下文，经 ACSARouter::execute_with_context 注入 MOSS 提示词
+// 3. 斜杠命令：/protocol、/cost、/reset、/help、/exit
+// 4. 从本地会话记录（SessionStore）恢复历史，按会话ID继续对话
+
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::sync::Arc;
+
+use super::agent_state::{AgentStateManager, Message, SessionState};
+use super::protocol::Protocol;
+use super::router::ACSARouter;
+use super::types::ACSAExecutionLog;
+
+/// 注入上下文的默认轮数
+pub const DEFAULT_CONTEXT_TURNS: usize = 5;
+
+/// 上下文中每段文本保留的最大字符数
+const CONTEXT_SNIPPET_CHARS: usize = 1500;
+
+/// 消息元数据：本轮 MOSS 方案
+const MOSS_PLAN_KEY: &str = "moss_plan";
+
+/// 会话中的一行输入
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChatCommand {
+    /// 普通输入，交给 ACSA 链路
+    Message(String),
+    /// 查看（无参数）或切换 Protocol
+    Protocol(Option<String>),
+    /// 查看会话成本
+    Cost,
+    /// 开始新会话，丢弃之前的上下文
+    Reset,
+    Help,
+    Exit,
+}
+
+impl ChatCommand {
+    /// 解析一行输入；空行返回 None
+    pub fn parse(line: &str) -> Result<Option<Self>> {
+        let line = line.trim();
+        if line.is_empty() {
+            return Ok(None);
+        }
+        let Some(command) = line.strip_prefix('/') else {
+            return Ok(Some(ChatCommand::Message(line.to_string())));
+        };
+
+        let (name, argument) = match command.split_once(char::is_whitespace) {
+            Some((name, argument)) => (name, Some(argument.trim().to_string()).filter(|a| !a.is_empty())),
+            None => (command, None),
+        };
+        let parsed = match name.to_lowercase().as_str() {
+            "protocol" | "p" => ChatCommand::Protocol(argument),
+            "cost" => ChatCommand::Cost,
+            "reset" => ChatCommand::Reset,
+            "help" | "?" => ChatCommand::Help,
+            "exit" | "quit" | "q" => ChatCommand::Exit,
+            other => return Err(anyhow!("Unknown command '/{}' (try /help)", other)),
+        };
+        Ok(Some(parsed))
+    }
+
+    /// 命令帮助
+    pub fn help() -> &'static str {
+        "/protocol [name]  show or switch the protocol\n\
+         /cost             show this session's cost so far\n\
+         /reset            start a new session and forget earlier turns\n\
+         /help             show this help\n\
+         /exit             leave chat"
+    }
+}
+
+/// 会话成本汇总
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConversationCost {
+    pub turns: usize,
+    pub total_cost: f64,
+    pub last_turn_cost: f64,
+}
+
+/// 多轮会话
+pub struct Conversation {
+    router: Arc<ACSARouter>,
+    state: Arc<AgentStateManager>,
+    user_id: String,
+    session: SessionState,
+    context_turns: usize,
+    cost: ConversationCost,
+}
+
+impl Conversation {
+    /// 开始新会话
+    pub async fn start(
+        router: Arc<ACSARouter>,
+        state: Arc<AgentStateManager>,
+        user_id: impl Into<String>,
+        protocol: Protocol,
+    ) -> Result<Self> {
+        let user_id = user_id.into();
+        let session = state.create_session(user_id.clone(), protocol).await?;
+        Ok(Self {
+            router,
+            state,
+            user_id,
+            session,
+            context_turns: DEFAULT_CONTEXT_TURNS,
+            cost: ConversationCost::default(),
+        })
+    }
+
+    /// 从之前的执行记录恢复会话（每个执行日志还原为一轮对话）
+    pub async fn resume(
+        router: Arc<ACSARouter>,
+        state: Arc<AgentStateManager>,
+        session: SessionState,
+        executions: &[ACSAExecutionLog],
+    ) -> Result<Self> {
+        if session.is_ended {
+            return Err(anyhow!("Session {} has ended", session.session_id));
+        }
+        let protocol = session.current_protocol.clone();
+        let history = executions
+            .iter()
+            .enumerate()
+            .flat_map(|(turn, log)| turn_messages(&session.session_id, turn, log, &protocol))
+            .collect();
+        let cost = ConversationCost {
+            turns: executions.len(),
+            total_cost: executions.iter().map(|log| log.total_cost).sum(),
+            last_turn_cost: executions.last().map(|log| log.total_cost).unwrap_or(0.0),
+        };
+        state.restore_session(session.clone(), history).await;
+        Ok(Self {
+            router,
+            state,
+            user_id: session.user_id.clone(),
+            session,
+            context_turns: DEFAULT_CONTEXT_TURNS,
+            cost,
+        })
+    }
+
+    /// 注入上下文的轮数（0 表示每轮独立执行）
+    pub fn with_context_turns(mut self, turns: usize) -> Self {
+        self.context_turns = turns;
+        self
+    }
+
+    pub fn session(&self) -> &SessionState {
+        &self.session
+    }
+
+    pub fn protocol(&self) -> &Protocol {
+        &self.session.current_protocol
+    }
+
+    pub fn cost(&self) -> &ConversationCost {
+        &self.cost
+    }
+
+    /// 执行一轮：带上之前的对话上下文执行 ACSA 链路，并记入会话历史
+    pub async fn send(&mut self, input: impl Into<String>) -> Result<ACSAExecutionLog> {
+        let input = input.into();
+        let context = self.context().await;
+        let log = self.router.execute_with_context(input, context).await?;
+
+        let protocol = self.session.current_protocol.clone();
+        for message in turn_messages(&self.session.session_id, self.cost.turns, &log, &protocol) {
+            self.state.add_message(message).await?;
+        }
+        self.cost.turns += 1;
+        self.cost.total_cost += log.total_cost;
+        self.cost.last_turn_cost = log.total_cost;
+        Ok(log)
+    }
+
+    /// 切换 Protocol（只影响之后的轮次）
+    pub async fn switch_protocol(&mut self, protocol: Protocol) -> Result<()> {
+        self.session = self.state.set_protocol(&self.session.session_id, protocol).await?;
+        Ok(())
+    }
+
+    /// 结束当前会话并以相同 Protocol 开始新会话，返回新会话ID
+    pub async fn reset(&mut self) -> Result<String> {
+        self.state.end_session(&self.session.session_id).await?;
+        let protocol = self.session.current_protocol.clone();
+        self.session = self.state.create_session(self.user_id.clone(), protocol).await?;
+        self.cost = ConversationCost::default();
+        Ok(self.session.session_id.clone())
+    }
+
+    /// 最近若干轮的对话摘要（无历史时为空串）
+    pub async fn context(&self) -> String {
+        if self.context_turns == 0 {
+            return String::new();
+        }
+        let history = self
+            .state
+            .get_conversation_history(&self.session.session_id, Some(self.context_turns * 2))
+            .await
+            .unwrap_or_default();
+
+        let mut context = String::new();
+        for message in &history {
+            match message.role.as_str() {
+                "user" => {
+                    let _ = writeln!(context, "User: {}", snippet(&message.content));
+                }
+                "assistant" => {
+                    if let Some(plan) = message.metadata.get(MOSS_PLAN_KEY) {
+                        let _ = writeln!(context, "MOSS plan: {}", snippet(plan));
+                    }
+                    let _ = writeln!(context, "Result: {}\n", snippet(&message.content));
+                }
+                _ => {}
+            }
+        }
+        context
+    }
+}
+
+/// 一轮对话对应的两条消息：用户输入 + 最终输出（元数据带 MOSS 方案与成本）
+fn turn_messages(session_id: &str, turn: usize, log: &ACSAExecutionLog, protocol: &Protocol) -> [Message; 2] {
+    let message = |role: &str, content: String, metadata: HashMap<String, String>| Message {
+        message_id: format!("{}_{}_{}", session_id, turn, role),
+        session_id: session_id.to_string(),
+        role: role.to_string(),
+        content,
+        protocol: Some(protocol.clone()),
+        timestamp: Utc::now(),
+        metadata,
+    };
+
+    let mut metadata = HashMap::from([
+        ("cost".to_string(), format!("{:.6}", log.total_cost)),
+        ("success".to_string(), log.success.to_string()),
+    ]);
+    if let Some(plan) = &log.moss_plan {
+        metadata.insert(MOSS_PLAN_KEY.to_string(), plan.text.clone());
+    }
+    [
+        message("user", log.user_input.clone(), HashMap::new()),
+        message("assistant", log.final_output.clone().unwrap_or_default(), metadata),
+    ]
+}
+
+/// 截断过长文本，避免上下文无限增长
+fn snippet(text: &str) -> String {
+    let text = text.trim();
+    match text.char_indices().nth(CONTEXT_SNIPPET_CHARS) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::agent_state::AgentStateConfig;
+    use crate::core::providers::MockProvider;
+    use crate::core::types::{ACSAConfig, AgentRole};
+
+    fn router() -> Arc<ACSARouter> {
+        Arc::new(ACSARouter::new(
+            Arc::new(MockProvider::new(AgentRole::MOSS)),
+            Arc::new(MockProvider::new(AgentRole::L6)),
+            Arc::new(MockProvider::new(AgentRole::Ultron)),
+            Arc::new(MockProvider::new(AgentRole::Omega)),
+            ACSAConfig { max_iterations: 1, enable_l6: false, ..Default::default() },
+        ))
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(ChatCommand::parse("   ").unwrap(), None);
+        assert_eq!(
+            ChatCommand::parse("写一个HTTP服务器").unwrap(),
+            Some(ChatCommand::Message("写一个HTTP服务器".to_string()))
+        );
+        assert_eq!(
+            ChatCommand::parse("/protocol aegis").unwrap(),
+            Some(ChatCommand::Protocol(Some("aegis".to_string())))
+        );
+        assert_eq!(ChatCommand::parse("/protocol").unwrap(), Some(ChatCommand::Protocol(None)));
+        assert_eq!(ChatCommand::parse("/COST").unwrap(), Some(ChatCommand::Cost));
+        assert_eq!(ChatCommand::parse("/reset").unwrap(), Some(ChatCommand::Reset));
+        assert!(ChatCommand::parse("/deploy").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_turns_build_context_and_reset_clears_it() {
+        let state = Arc::new(AgentStateManager::new(AgentStateConfig::default(), None));
+        let mut conversation = Conversation::start(router(), state, "cli", Protocol::Architect).await.unwrap();
+        assert!(conversation.context().await.is_empty());
+
+        let first = conversation.send("写一个HTTP服务器").await.unwrap();
+        conversation.send("加上日志").await.unwrap();
+
+        let context = conversation.context().await;
+        assert!(context.contains("User: 写一个HTTP服务器"));
+        assert!(context.contains(&format!("MOSS plan: {}", first.moss_plan.unwrap().text.trim())));
+        assert!(context.contains("User: 加上日志"));
+        assert_eq!(conversation.cost().turns, 2);
+
+        conversation.switch_protocol(Protocol::Aegis).await.unwrap();
+        let previous = conversation.session().session_id.clone();
+        let session_id = conversation.reset().await.unwrap();
+        assert_ne!(session_id, previous);
+        assert_eq!(conversation.protocol(), &Protocol::Aegis);
+        assert!(conversation.context().await.is_empty());
+        assert_eq!(conversation.cost(), &ConversationCost::default());
+    }
+
+    #[tokio::test]
+    async fn test_resume_restores_history() {
+        let state = Arc::new(AgentStateManager::new(AgentStateConfig::default(), None));
+        let log = router().execute("写一个HTTP服务器".to_string()).await.unwrap();
+        let session = state.create_session("cli".to_string(), Protocol::Architect).await.unwrap();
+
+        let conversation = Conversation::resume(router(), state, session, std::slice::from_ref(&log)).await.unwrap();
+        assert_eq!(conversation.cost().turns, 1);
+        assert_eq!(conversation.cost().total_cost, log.total_cost);
+        assert!(conversation.context().await.contains("User: 写一个HTTP服务器"));
+    }
+}
@@ -0,0 +1,512 @@
+// Cost Estimator - 成本模拟 / 干跑估算
+// 在不调用任何 Provider 的前提下预估一次 ACSA 执行的 token、成本与延迟
+//
+// 核心功能：
+// 1. 复用 Router 的认知清洗、Jarvis 初检和各阶段提示词，组装与实际调用一致的 Prompt
+// 2. 按 Agent 统计输入 / 输出 token，按 api_manager 的价格表换算成本（与 Provider 实际计费一致）
+// 3. 给出区间：最好情况（一轮审计通过）到最坏情况（最后一轮才通过，重规划 + 复核）
+// 4. 离线模式按本地模型计价（零成本）
+// 5. 干跑执行报告（`ACSAConfig.dry_run`）：规划 / 复核 / 审计真实运行，Omega 只按实际方案估算
+//
+// 注：当前 Router 对所有协议执行同一条链路，协议只影响报告与会话记录
+
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use super::api_manager::{ApiProvider, PricingTable, TokenRate};
+use super::cognitive_cleaner::CognitiveCleaner;
+use super::jarvis::JarvisCircuitBreaker;
+use super::offline;
+use super::protocol::Protocol;
+use super::router;
+use super::types::{ACSAConfig, AgentRole};
+
+/// Provider 系统提示词与消息封装的额外开销（每次调用）
+const SYSTEM_PROMPT_TOKENS: u32 = 120;
+/// 最好情况下输出长度占上限的比例
+const OUTPUT_FLOOR_RATIO: f64 = 0.25;
+
+/// 单个模型的计价与速度
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelPricing {
+    pub provider: String,
+    pub model: String,
+    /// 每百万输入 token 价格（USD）
+    pub input_per_million: f64,
+    /// 每百万输出 token 价格（USD）
+    pub output_per_million: f64,
+    /// 首 token 延迟（毫秒）
+    pub first_token_ms: u64,
+    /// 输出速度（token/秒）
+    pub output_tokens_per_sec: f64,
+}
+
+impl ModelPricing {
+    fn new(provider: &str, model: &str, input_per_million: f64, output_per_million: f64, first_token_ms: u64, output_tokens_per_sec: f64) -> Self {
+        Self {
+            provider: provider.to_string(),
+            model: model.to_string(),
+            input_per_million,
+            output_per_million,
+            first_token_ms,
+            output_tokens_per_sec,
+        }
+    }
+
+    /// 云端模型：单价取自当前价格表（Provider 的默认模型）
+    fn cloud(provider: ApiProvider, first_token_ms: u64, output_tokens_per_sec: f64) -> Self {
+        let model = provider.default_model();
+        let rate = PricingTable::current().rate(provider, model).unwrap_or(TokenRate::new(0.0, 0.0));
+        Self::new(
+            &provider.name().to_lowercase(),
+            model,
+            rate.input_per_1k * 1000.0,
+            rate.output_per_1k * 1000.0,
+            first_token_ms,
+            output_tokens_per_sec,
+        )
+    }
+
+    /// 本地模型（离线模式）：零成本
+    pub fn local(model: &str) -> Self {
+        Self::new("local", model, 0.0, 0.0, 300, 20.0)
+    }
+
+    pub fn cost(&self, input_tokens: u32, output_tokens: u32) -> f64 {
+        (input_tokens as f64 / 1_000_000.0) * self.input_per_million
+            + (output_tokens as f64 / 1_000_000.0) * self.output_per_million
+    }
+
+    pub fn latency_ms(&self, output_tokens: u32) -> u64 {
+        self.first_token_ms + (output_tokens as f64 / self.output_tokens_per_sec.max(1.0) * 1000.0) as u64
+    }
+}
+
+/// 各 Agent 使用的模型及其价格
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentPricing {
+    pub moss: ModelPricing,
+    pub l6: ModelPricing,
+    pub ultron: ModelPricing,
+    pub omega: ModelPricing,
+}
+
+impl Default for AgentPricing {
+    fn default() -> Self {
+        Self::cloud()
+    }
+}
+
+impl AgentPricing {
+    /// 默认云端 Provider（与 create_provider 的选择一致）
+    pub fn cloud() -> Self {
+        Self {
+            moss: ModelPricing::cloud(ApiProvider::OpenAI, 800, 30.0),
+            l6: ModelPricing::cloud(ApiProvider::Gemini, 600, 60.0),
+            ultron: ModelPricing::cloud(ApiProvider::Claude, 1200, 25.0),
+            omega: ModelPricing::cloud(ApiProvider::DeepSeek, 700, 50.0),
+        }
+    }
+
+    /// 所有 Agent 使用同一个本地模型
+    pub fn local(model: &str) -> Self {
+        let pricing = ModelPricing::local(model);
+        Self {
+            moss: pricing.clone(),
+            l6: pricing.clone(),
+            ultron: pricing.clone(),
+            omega: pricing,
+        }
+    }
+
+    /// 按当前运行模式选择价格表（离线模式为本地模型）
+    pub fn current() -> Self {
+        match offline::current() {
+            Some(config) => Self::local(&config.llm_model),
+            None => Self::cloud(),
+        }
+    }
+
+    pub fn get(&self, role: AgentRole) -> &ModelPricing {
+        match role {
+            AgentRole::MOSS => &self.moss,
+            AgentRole::L6 => &self.l6,
+            AgentRole::Ultron => &self.ultron,
+            AgentRole::Omega => &self.omega,
+        }
+    }
+}
+
+/// 估算区间（最好情况 ~ 最坏情况）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Bounds<T> {
+    pub low: T,
+    pub high: T,
+}
+
+impl<T: Copy> Bounds<T> {
+    fn new(low: T, high: T) -> Self {
+        Self { low, high }
+    }
+
+    fn exact(value: T) -> Self {
+        Self { low: value, high: value }
+    }
+}
+
+/// 单个阶段的估算
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StageEstimate {
+    pub stage: String,
+    pub role: AgentRole,
+    pub provider: String,
+    pub model: String,
+    pub calls: Bounds<u32>,
+    /// 单次调用的输入 token
+    pub input_tokens: Bounds<u32>,
+    /// 单次调用的输出 token
+    pub output_tokens: Bounds<u32>,
+    /// 该阶段全部调用的成本
+    pub cost: Bounds<f64>,
+    /// 该阶段全部调用的延迟
+    pub latency_ms: Bounds<u64>,
+}
+
+/// 一次执行的估算报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CostEstimate {
+    pub protocol: Protocol,
+    /// 清洗后用户输入的 token 数
+    pub input_tokens: u32,
+    /// Jarvis 初检拦截原因（拦截时不会调用任何 Agent）
+    pub blocked: Option<String>,
+    pub stages: Vec<StageEstimate>,
+    pub total_tokens: Bounds<u32>,
+    pub total_cost: Bounds<f64>,
+    pub total_latency_ms: Bounds<u64>,
+}
+
+/// 干跑执行报告（`ACSAConfig.dry_run`）：审计通过后不调用 Omega，给出将要执行的方案与估算
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DryRunReport {
+    /// Omega 将要执行的方案
+    pub plan: String,
+    /// 审计给出的风险分数与阈值
+    pub risk_score: u8,
+    pub risk_threshold: u8,
+    /// Omega 执行阶段的估算（按实际方案与审计建议组装提示词）
+    pub execution: StageEstimate,
+    /// 已产生的实际消耗：token 按最终方案与各轮复核 / 审计阶段统计，成本含全部调用
+    pub spent_tokens: u32,
+    pub spent_cost: f64,
+    /// 完整执行的预估（已产生 + Omega）
+    pub total_tokens: Bounds<u32>,
+    pub total_cost: Bounds<f64>,
+}
+
+impl DryRunReport {
+    /// 终端 / 最终输出中的摘要
+    pub fn summary(&self) -> String {
+        format!(
+            "🧪 DRY RUN: Omega was not executed.\n\n\
+             Risk: {}/100 (threshold: {})\n\
+             Estimated tokens: {} - {}\n\
+             Estimated cost: ${:.4} - ${:.4} (spent so far: ${:.4})\n\n\
+             Plan:\n{}",
+            self.risk_score,
+            self.risk_threshold,
+            self.total_tokens.low,
+            self.total_tokens.high,
+            self.total_cost.low,
+            self.total_cost.high,
+            self.spent_cost,
+            self.plan.trim_end()
+        )
+    }
+}
+
+/// 估算 token 数：CJK 字符约 1 token/字，其余约 4 字符/token
+pub fn estimate_tokens(text: &str) -> u32 {
+    let (cjk, other) = text.chars().fold((0u32, 0u32), |(cjk, other), c| {
+        if is_cjk(c) {
+            (cjk + 1, other)
+        } else {
+            (cjk, other + 1)
+        }
+    });
+    cjk + other.div_ceil(4)
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF      // 日文假名
+        | 0x3400..=0x4DBF    // CJK 扩展 A
+        | 0x4E00..=0x9FFF    // CJK 统一汉字
+        | 0xAC00..=0xD7AF    // 韩文
+        | 0xF900..=0xFAFF    // CJK 兼容汉字
+        | 0xFF00..=0xFFEF)   // 全角符号
+}
+
+/// 单次调用的输出 token 区间（上限与 Router 一致）
+fn output_bounds(role: AgentRole) -> Bounds<u32> {
+    let max = router::max_tokens(role);
+    Bounds::new((max as f64 * OUTPUT_FLOOR_RATIO) as u32, max)
+}
+
+/// 干跑估算器
+pub struct CostEstimator {
+    config: ACSAConfig,
+    pricing: AgentPricing,
+    cognitive_cleaner: CognitiveCleaner,
+    jarvis: JarvisCircuitBreaker,
+}
+
+impl CostEstimator {
+    pub fn new(config: ACSAConfig) -> Self {
+        Self {
+            config,
+            pricing: AgentPricing::current(),
+            cognitive_cleaner: CognitiveCleaner::new(),
+            jarvis: JarvisCircuitBreaker::new(),
+        }
+    }
+
+    /// 使用自定义价格表
+    pub fn with_pricing(mut self, pricing: AgentPricing) -> Self {
+        self.pricing = pricing;
+        self
+    }
+
+    /// 估算一次执行；未指定协议时按输入自动检测（默认 Architect）
+    pub fn estimate(&self, user_input: &str, protocol: Option<Protocol>) -> CostEstimate {
+        let protocol = protocol
+            .or_else(|| Protocol::detect_from_input(user_input))
+            .unwrap_or(Protocol::Architect);
+
+        // 与 Router 相同：先清洗，再 Jarvis 初检
+        let processed = self.cognitive_cleaner.clean(user_input).compliant_prompt;
+        let input_tokens = estimate_tokens(&processed);
+        let verdict = self.jarvis.verify_safety(&processed, "Cleaned user input");
+        if !verdict.allowed {
+            return CostEstimate {
+                protocol,
+                input_tokens,
+                blocked: verdict.block_reason.or_else(|| Some("Blocked by Jarvis".to_string())),
+                stages: Vec::new(),
+                total_tokens: Bounds::exact(0),
+                total_cost: Bounds::exact(0.0),
+                total_latency_ms: Bounds::exact(0),
+            };
+        }
+
+        let prompt = |template: String, upstream: &[Bounds<u32>]| {
+            let base = estimate_tokens(&template) + SYSTEM_PROMPT_TOKENS;
+            Bounds::new(
+                base + upstream.iter().map(|b| b.low).sum::<u32>(),
+                base + upstream.iter().map(|b| b.high).sum::<u32>(),
+            )
+        };
+
+        // 按流水线拓扑估算；自定义 Agent 阶段按其角色的价格计
+        let pipeline = &self.config.pipeline;
+        let reviews: Vec<_> = pipeline
+            .review_stages()
+            .iter()
+            .filter(|stage| !stage.is_builtin_l6() || self.config.enable_l6)
+            .collect();
+        let verifications = reviews.iter().filter(|stage| stage.role == AgentRole::L6).count() as u32;
+
+        let (moss_out, ultron_out) = (output_bounds(AgentRole::MOSS), output_bounds(AgentRole::Ultron));
+        // Ultron 的输入包含本轮全部复核结果
+        let l6_out = output_bounds(AgentRole::L6);
+        let l6_out = Bounds::new(l6_out.low * verifications, l6_out.high * verifications);
+        // 最好情况：首轮审计通过；最坏情况：最后一轮才通过（之前每轮都重规划 + 复核）
+        let rounds = self.config.max_iterations.max(1);
+        let replans = Bounds::new(0, rounds - 1);
+
+        let mut stages = vec![self.stage(
+            &format!("{} plan", pipeline.planner().name),
+            AgentRole::MOSS,
+            Bounds::exact(1),
+            prompt(router::moss_prompt(&processed), &[]),
+        )];
+        for stage in &reviews {
+            stages.push(match stage.role {
+                AgentRole::L6 => self.stage(
+                    &format!("{} verification", stage.name),
+                    AgentRole::L6,
+                    Bounds::exact(1),
+                    prompt(router::l6_prompt("", &processed), &[moss_out]),
+                ),
+                _ => self.stage(
+                    &format!("{} audit", stage.name),
+                    AgentRole::Ultron,
+                    Bounds::new(1, rounds),
+                    prompt(router::ultron_prompt("", "", &processed, None), &[moss_out, l6_out]),
+                ),
+            });
+        }
+        if replans.high > 0 {
+            stages.push(self.stage(
+                &format!("{} replan", pipeline.planner().name),
+                AgentRole::MOSS,
+                replans,
+                prompt(router::moss_feedback_prompt(&processed, ""), &[ultron_out]),
+            ));
+            for stage in reviews.iter().filter(|stage| stage.role == AgentRole::L6) {
+                stages.push(self.stage(
+                    &format!("{} re-verification", stage.name),
+                    AgentRole::L6,
+                    replans,
+                    prompt(router::l6_prompt("", &processed), &[moss_out]),
+                ));
+            }
+        }
+        stages.push(self.stage(
+            &format!("{} execution", pipeline.executor().name),
+            AgentRole::Omega,
+            Bounds::exact(1),
+            prompt(router::omega_prompt("", ""), &[moss_out, ultron_out]),
+        ));
+
+        let total_tokens = Bounds::new(
+            stages.iter().map(|s| s.calls.low * (s.input_tokens.low + s.output_tokens.low)).sum(),
+            stages.iter().map(|s| s.calls.high * (s.input_tokens.high + s.output_tokens.high)).sum(),
+        );
+        let total_cost = Bounds::new(stages.iter().map(|s| s.cost.low).sum(), stages.iter().map(|s| s.cost.high).sum());
+        let total_latency_ms = Bounds::new(
+            stages.iter().map(|s| s.latency_ms.low).sum(),
+            stages.iter().map(|s| s.latency_ms.high).sum(),
+        );
+
+        info!(
+            "🧮 Dry-run estimate: ${:.4} - ${:.4}, {} - {} ms",
+            total_cost.low, total_cost.high, total_latency_ms.low, total_latency_ms.high
+        );
+
+        CostEstimate {
+            protocol,
+            input_tokens,
+            blocked: None,
+            stages,
+            total_tokens,
+            total_cost,
+            total_latency_ms,
+        }
+    }
+
+    /// 按已审计的实际方案估算 Omega 执行阶段（干跑执行）
+    pub fn estimate_execution(&self, plan: &str, audit_mitigation: &str) -> StageEstimate {
+        let input_tokens = estimate_tokens(&router::omega_prompt(plan, audit_mitigation)) + SYSTEM_PROMPT_TOKENS;
+        self.stage(
+            &format!("{} execution", self.config.pipeline.executor().name),
+            AgentRole::Omega,
+            Bounds::exact(1),
+            Bounds::exact(input_tokens),
+        )
+    }
+
+    /// 生成干跑执行报告：已产生的消耗加上 Omega 阶段估算
+    pub fn dry_run_report(
+        &self,
+        plan: &str,
+        risk_score: u8,
+        audit_mitigation: &str,
+        spent_tokens: u32,
+        spent_cost: f64,
+    ) -> DryRunReport {
+        let execution = self.estimate_execution(plan, audit_mitigation);
+        let total_tokens = Bounds::new(
+            spent_tokens + execution.input_tokens.low + execution.output_tokens.low,
+            spent_tokens + execution.input_tokens.high + execution.output_tokens.high,
+        );
+        let total_cost = Bounds::new(spent_cost + execution.cost.low, spent_cost + execution.cost.high);
+
+        DryRunReport {
+            plan: plan.to_string(),
+            risk_score,
+            risk_threshold: self.config.risk_threshold,
+            execution,
+            spent_tokens,
+            spent_cost,
+            total_tokens,
+            total_cost,
+        }
+    }
+
+    fn stage(&self, stage: &str, role: AgentRole, calls: Bounds<u32>, input_tokens: Bounds<u32>) -> StageEstimate {
+        let pricing = self.pricing.get(role);
+        let output_tokens = output_bounds(role);
+
+        StageEstimate {
+            stage: stage.to_string(),
+            role,
+            provider: pricing.provider.clone(),
+            model: pricing.model.clone(),
+            calls,
+            input_tokens,
+            output_tokens,
+            cost: Bounds::new(
+                calls.low as f64 * pricing.cost(input_tokens.low, output_tokens.low),
+                calls.high as f64 * pricing.cost(input_tokens.high, output_tokens.high),
+            ),
+            latency_ms: Bounds::new(
+                calls.low as u64 * pricing.latency_ms(output_tokens.low),
+                calls.high as u64 * pricing.latency_ms(output_tokens.high),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abcdefgh"), 2);
+        assert_eq!(estimate_tokens("写代码"), 3);
+    }
+
+    #[test]
+    fn test_estimate_ranges_cover_retry_loop() {
+        let estimator = CostEstimator::new(ACSAConfig::default()).with_pricing(AgentPricing::cloud());
+        let estimate = estimator.estimate("Write a web scraper for public weather data", None);
+
+        assert!(estimate.blocked.is_none());
+        assert_eq!(estimate.protocol, Protocol::Architect);
+        let stages: Vec<_> = estimate.stages.iter().map(|s| s.stage.as_str()).collect();
+        assert_eq!(
+            stages,
+            vec!["MOSS plan", "L6 verification", "Ultron audit", "MOSS replan", "L6 re-verification", "Omega execution"]
+        );
+
+        let ultron = &estimate.stages[2];
+        assert_eq!(ultron.calls, Bounds::new(1, 3));
+        // Ultron 的输入包含 MOSS 计划和 L6 校验结果
+        assert_eq!(ultron.input_tokens.high - ultron.input_tokens.low, (1500 - 375) + (1000 - 250));
+        assert_eq!(estimate.stages[3].calls, Bounds::new(0, 2));
+        assert_eq!(estimate.stages[3].cost.low, 0.0);
+
+        assert!(estimate.total_cost.low > 0.0);
+        assert!(estimate.total_cost.high > estimate.total_cost.low * 2.0);
+        assert!(estimate.total_latency_ms.high > estimate.total_latency_ms.low);
+
+        let local = CostEstimator::new(ACSAConfig::default())
+            .with_pricing(AgentPricing::local("llama3"))
+            .estimate("Write a web scraper for public weather data", Some(Protocol::Reviewer2));
+        assert_eq!(local.protocol, Protocol::Reviewer2);
+        assert_eq!(local.total_cost.high, 0.0);
+        assert_eq!(local.total_tokens, estimate.total_tokens);
+
+        // 跳过 L6、两次 Ultron 审计的流水线
+        let pipeline = "moss,ultron,ultron:compliance,omega".parse().unwrap();
+        let custom = CostEstimator::new(ACSAConfig { pipeline, ..Default::default() })
+            .with_pricing(AgentPricing::cloud())
+            .estimate("Write a web scraper for public weather data", None);
+        let stages: Vec<_> = custom.stages.iter().map(|s| s.stage.as_str()).collect();
+        assert_eq!(stages, vec!["MOSS plan", "Ultron audit", "compliance audit", "MOSS replan", "Omega execution"]);
+        assert_eq!(custom.stages[1].input_tokens, custom.stages[2].input_tokens);
+    }
+}
@@ -0,0 +1,335 @@
+// Dashboard State - TUI 仪表盘状态模型
+// 与渲染层解耦：ratatui 只负责把这里的状态画出来
+//
+// 核心功能：
+// 1. 消费 Router 的 PipelineEvent，维护四个 Agent 的流水线进度
+// 2. SOSA API 池健康度、会话成本计量
+// 3. 主权 H(t) 仪表、最近 Jarvis 判定
+// 4. 按当前协议的 tui_color 生成主题，支持键盘切换协议
+
+use super::jarvis::JarvisVerdict;
+use super::protocol::{Protocol, ProtocolManager};
+use super::sosa_api_pool::EndpointStatus;
+use super::sovereignty::BioActivity;
+use super::types::{AgentRole, PipelineEvent};
+use chrono::{DateTime, Utc};
+use std::collections::VecDeque;
+
+/// 最近判定保留条数
+const MAX_VERDICTS: usize = 8;
+
+/// 流水线中的 Agent 顺序
+pub const PIPELINE: [AgentRole; 4] = [AgentRole::MOSS, AgentRole::L6, AgentRole::Ultron, AgentRole::Omega];
+
+/// 单个阶段状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StageStatus {
+    Pending,
+    Running,
+    Done,
+    Failed,
+}
+
+impl StageStatus {
+    pub fn icon(&self) -> &'static str {
+        match self {
+            StageStatus::Pending => "○",
+            StageStatus::Running => "◐",
+            StageStatus::Done => "●",
+            StageStatus::Failed => "✗",
+        }
+    }
+}
+
+/// 单个 Agent 的进度与成本
+#[derive(Debug, Clone)]
+pub struct StageProgress {
+    pub role: AgentRole,
+    pub status: StageStatus,
+    /// 本次执行中被调用的次数（Ultron 驳回会触发重规划）
+    pub runs: u32,
+    pub latency_ms: u64,
+    /// 本次执行累计成本
+    pub cost: f64,
+}
+
+/// 一条 Jarvis 判定记录
+#[derive(Debug, Clone)]
+pub struct VerdictEntry {
+    pub context: String,
+    pub allowed: bool,
+    pub risk_level: u8,
+    pub summary: String,
+    pub at: DateTime<Utc>,
+}
+
+/// 主题色（RGB）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThemeColor {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl ThemeColor {
+    /// 解析 `#RRGGBB`
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let hex = hex.trim().trim_start_matches('#');
+        if hex.len() != 6 {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        Some(Self { r: channel(0)?, g: channel(2)?, b: channel(4)? })
+    }
+
+    /// 协议主题色；过暗的颜色（如 GHOST 全黑）提亮到深色终端可见
+    pub fn for_protocol(protocol: &Protocol) -> Self {
+        let color = Self::from_hex(protocol.tui_color()).unwrap_or(Self { r: 255, g: 255, b: 255 });
+        if color.luminance() < 40.0 {
+            Self { r: 110, g: 110, b: 110 }
+        } else {
+            color
+        }
+    }
+
+    fn luminance(&self) -> f64 {
+        0.2126 * self.r as f64 + 0.7152 * self.g as f64 + 0.0722 * self.b as f64
+    }
+}
+
+/// 仪表盘完整状态
+pub struct DashboardState {
+    protocols: ProtocolManager,
+    pub stages: Vec<StageProgress>,
+    /// 当前请求（执行中或最近一次）
+    pub current_input: Option<String>,
+    pub running: bool,
+    pub iterations: u32,
+    pub last_success: Option<bool>,
+    /// SOSA API 池健康度
+    pub pools: Vec<EndpointStatus>,
+    /// 会话累计成本
+    pub session_cost: f64,
+    /// 成本计量条满格对应的金额
+    pub cost_budget: f64,
+    /// 主权 H(t)
+    pub bio_activity: Option<BioActivity>,
+    pub verdicts: VecDeque<VerdictEntry>,
+}
+
+impl Default for DashboardState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DashboardState {
+    pub fn new() -> Self {
+        Self {
+            protocols: ProtocolManager::new(),
+            stages: Self::fresh_stages(),
+            current_input: None,
+            running: false,
+            iterations: 0,
+            last_success: None,
+            pools: Vec::new(),
+            session_cost: 0.0,
+            cost_budget: 1.0,
+            bio_activity: None,
+            verdicts: VecDeque::with_capacity(MAX_VERDICTS),
+        }
+    }
+
+    pub fn with_budget(mut self, budget: f64) -> Self {
+        self.cost_budget = budget.max(0.0001);
+        self
+    }
+
+    fn fresh_stages() -> Vec<StageProgress> {
+        PIPELINE
+            .iter()
+            .map(|role| StageProgress {
+                role: *role,
+                status: StageStatus::Pending,
+                runs: 0,
+                latency_ms: 0,
+                cost: 0.0,
+            })
+            .collect()
+    }
+
+    fn stage_mut(&mut self, role: AgentRole) -> Option<&mut StageProgress> {
+        self.stages.iter_mut().find(|s| s.role == role)
+    }
+
+    /// 应用一条流水线事件
+    pub fn apply(&mut self, event: PipelineEvent) {
+        match event {
+            PipelineEvent::Started { user_input } => {
+                self.stages = Self::fresh_stages();
+                self.current_input = Some(user_input);
+                self.running = true;
+                self.iterations = 0;
+                self.last_success = None;
+            }
+            PipelineEvent::StageStarted { role } => {
+                if role == AgentRole::Ultron {
+                    self.iterations += 1;
+                }
+                if let Some(stage) = self.stage_mut(role) {
+                    stage.status = StageStatus::Running;
+                    stage.runs += 1;
+                }
+            }
+            PipelineEvent::StageFinished { role, success, latency_ms, cost } => {
+                self.session_cost += cost;
+                if let Some(stage) = self.stage_mut(role) {
+                    stage.status = if success { StageStatus::Done } else { StageStatus::Failed };
+                    stage.latency_ms = latency_ms;
+                    stage.cost += cost;
+                }
+            }
+            PipelineEvent::Verdict { context, verdict } => self.push_verdict(context, &verdict),
+            PipelineEvent::Audit { .. } => {}
+            PipelineEvent::Completed { success, iterations, .. } => {
+                self.running = false;
+                self.iterations = iterations;
+                self.last_success = Some(success);
+            }
+        }
+    }
+
+    fn push_verdict(&mut self, context: String, verdict: &JarvisVerdict) {
+        let summary = match (&verdict.block_reason, verdict.warnings.first()) {
+            (Some(reason), _) => reason.clone(),
+            (None, Some(warning)) => warning.clone(),
+            (None, None) => "passed".to_string(),
+        };
+        if self.verdicts.len() == MAX_VERDICTS {
+            self.verdicts.pop_back();
+        }
+        self.verdicts.push_front(VerdictEntry {
+            context,
+            allowed: verdict.allowed,
+            risk_level: verdict.risk_level,
+            summary,
+            at: Utc::now(),
+        });
+    }
+
+    /// 已完成阶段占比（0.0 - 1.0）
+    pub fn pipeline_ratio(&self) -> f64 {
+        let done = self.stages.iter().filter(|s| s.status == StageStatus::Done).count();
+        done as f64 / self.stages.len() as f64
+    }
+
+    /// 成本计量条占比（0.0 - 1.0）
+    pub fn cost_ratio(&self) -> f64 {
+        (self.session_cost / self.cost_budget).clamp(0.0, 1.0)
+    }
+
+    /// H(t) 占基线比例（0.0 - 1.0）
+    pub fn sovereignty_ratio(&self) -> Option<f64> {
+        self.bio_activity
+            .as_ref()
+            .map(|bio| if bio.baseline > 0.0 { (bio.current / bio.baseline).clamp(0.0, 1.0) } else { 0.0 })
+    }
+
+    pub fn protocol(&self) -> Protocol {
+        self.protocols.current_protocol()
+    }
+
+    pub fn theme(&self) -> ThemeColor {
+        ThemeColor::for_protocol(&self.protocol())
+    }
+
+    /// 切换到下一个/上一个协议（循环）
+    pub fn cycle_protocol(&mut self, forward: bool) -> Protocol {
+        let all = Protocol::all();
+        let current = self.protocol();
+        let index = all.iter().position(|p| *p == current).unwrap_or(0);
+        let next = if forward {
+            (index + 1) % all.len()
+        } else {
+            (index + all.len() - 1) % all.len()
+        };
+        self.protocols.switch_protocol(all[next].clone());
+        self.protocol()
+    }
+
+    /// 按编号（1-8）直接选择协议
+    pub fn select_protocol(&mut self, number: usize) -> Option<Protocol> {
+        let protocol = Protocol::all().get(number.checked_sub(1)?)?.clone();
+        self.protocols.switch_protocol(protocol.clone());
+        Some(protocol)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn verdict(allowed: bool, reason: Option<&str>) -> JarvisVerdict {
+        JarvisVerdict {
+            allowed,
+            risk_level: if allowed { 1 } else { 9 },
+            triggered_rules: Vec::new(),
+            block_reason: reason.map(str::to_string),
+            warnings: Vec::new(),
+            is_hard_block: !allowed,
+        }
+    }
+
+    #[test]
+    fn test_pipeline_events_drive_progress() {
+        let mut state = DashboardState::new().with_budget(0.5);
+        state.apply(PipelineEvent::Started { user_input: "plan".to_string() });
+        state.apply(PipelineEvent::StageStarted { role: AgentRole::MOSS });
+        assert_eq!(state.stages[0].status, StageStatus::Running);
+
+        state.apply(PipelineEvent::StageFinished { role: AgentRole::MOSS, success: true, latency_ms: 20, cost: 0.1 });
+        state.apply(PipelineEvent::StageStarted { role: AgentRole::Ultron });
+        state.apply(PipelineEvent::StageFinished { role: AgentRole::Ultron, success: false, latency_ms: 5, cost: 0.15 });
+        state.apply(PipelineEvent::Completed { success: false, total_cost: 0.25, iterations: 1 });
+
+        assert_eq!(state.stages[0].status, StageStatus::Done);
+        assert_eq!(state.stages[2].status, StageStatus::Failed);
+        assert_eq!(state.pipeline_ratio(), 0.25);
+        assert!((state.cost_ratio() - 0.5).abs() < 1e-9);
+        assert!(!state.running);
+        assert_eq!(state.last_success, Some(false));
+    }
+
+    #[test]
+    fn test_verdicts_are_capped_newest_first() {
+        let mut state = DashboardState::new();
+        for i in 0..MAX_VERDICTS + 2 {
+            state.apply(PipelineEvent::Verdict { context: format!("check {}", i), verdict: verdict(true, None) });
+        }
+        state.apply(PipelineEvent::Verdict {
+            context: "plan".to_string(),
+            verdict: verdict(false, Some("rm -rf detected")),
+        });
+
+        assert_eq!(state.verdicts.len(), MAX_VERDICTS);
+        let latest = state.verdicts.front().unwrap();
+        assert!(!latest.allowed);
+        assert_eq!(latest.summary, "rm -rf detected");
+    }
+
+    #[test]
+    fn test_protocol_switching_and_theme() {
+        let mut state = DashboardState::new();
+        assert_eq!(state.protocol(), Protocol::Architect);
+        assert_eq!(state.theme(), ThemeColor { r: 0x00, g: 0xFF, b: 0x41 });
+
+        let all = Protocol::all();
+        assert_eq!(state.cycle_protocol(false), all[all.len() - 1]);
+        assert_eq!(state.cycle_protocol(true), Protocol::Architect);
+        assert_eq!(state.select_protocol(3), Some(all[2].clone()));
+        assert_eq!(state.select_protocol(0), None);
+
+        // GHOST 全黑主题被提亮
+        assert_ne!(ThemeColor::for_protocol(&Protocol::Ghost), ThemeColor { r: 0, g: 0, b: 0 });
+    }
+}
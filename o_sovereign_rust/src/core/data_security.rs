@@ -264,6 +264,16 @@ impl DataSecurityManager {
         sanitized
     }
 
+    /// 递归脱敏 JSON 中的所有字符串值
+    pub fn sanitize_json(&self, value: &mut serde_json::Value, category: Option<DataCategory>) {
+        match value {
+            serde_json::Value::String(s) => *s = self.sanitize(s, category),
+            serde_json::Value::Array(items) => items.iter_mut().for_each(|v| self.sanitize_json(v, category.clone())),
+            serde_json::Value::Object(map) => map.values_mut().for_each(|v| self.sanitize_json(v, category.clone())),
+            _ => {}
+        }
+    }
+
     /// 文件读取（带权限检查和脱敏）
     pub fn read_file_secure(&self, path: &Path) -> Result<SecureFileContent> {
         let start = std::time::Instant::now();
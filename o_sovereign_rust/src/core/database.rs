@@ -2,7 +2,7 @@
 // 统一数据库访问接口
 //
 // 核心功能：
-// 1. SQLx集成（PostgreSQL/MySQL/SQLite）
+// 1. SQLx集成（PostgreSQL/MySQL/SQLite；当前构建只编入 SQLite 驱动）
 // 2. 连接池管理
 // 3. 事务支持
 // 4. 数据迁移
@@ -11,7 +11,10 @@
 use anyhow::{anyhow, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sqlx::sqlite::{SqliteArguments, SqliteConnectOptions, SqlitePool, SqlitePoolOptions, SqliteRow};
+use sqlx::{Column, Row, TypeInfo, ValueRef};
 use std::collections::HashMap;
+use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::RwLock;
@@ -75,8 +78,8 @@ pub type QueryRow = HashMap<String, serde_json::Value>;
 /// 数据库管理器
 pub struct DatabaseManager {
     config: DatabaseConfig,
-    /// 连接池（placeholder）
-    pool: Arc<RwLock<Option<()>>>, // TODO: 使用 sqlx::Pool
+    /// 连接池（connect 之前为 None）
+    pool: Arc<RwLock<Option<SqlitePool>>>,
     /// 统计信息
     stats: Arc<RwLock<PoolStats>>,
 }
@@ -99,21 +102,29 @@ impl DatabaseManager {
     pub async fn connect(&self) -> Result<()> {
         info!("🔌 Connecting to database: {}", self.mask_url(&self.config.url));
 
-        // TODO: 实际使用 sqlx 创建连接池
-        // match self.config.db_type {
-        //     DatabaseType::PostgreSQL => {
-        //         let pool = sqlx::postgres::PgPoolOptions::new()
-        //             .max_connections(self.config.max_connections)
-        //             .min_connections(self.config.min_connections)
-        //             .connect_timeout(Duration::from_secs(self.config.connect_timeout_secs))
-        //             .idle_timeout(Some(Duration::from_secs(self.config.idle_timeout_secs)))
-        //             .connect(&self.config.url)
-        //             .await?;
-        //         let mut p = self.pool.write().await;
-        //         *p = Some(pool);
-        //     }
-        //     // ... 其他数据库类型
-        // }
+        if self.config.db_type != DatabaseType::SQLite {
+            return Err(anyhow!(
+                "{:?} driver is not compiled into this build; only SQLite is supported",
+                self.config.db_type
+            ));
+        }
+
+        let options = SqliteConnectOptions::from_str(&self.config.url)?.create_if_missing(true);
+        // 内存数据库每个连接都是独立的库，只能用单连接且不能回收
+        let in_memory = self.config.url.contains(":memory:");
+        let mut pool_options = SqlitePoolOptions::new()
+            .max_connections(if in_memory { 1 } else { self.config.max_connections })
+            .min_connections(if in_memory { 1 } else { self.config.min_connections })
+            .acquire_timeout(Duration::from_secs(self.config.connect_timeout_secs));
+        pool_options = if in_memory {
+            pool_options.idle_timeout(None).max_lifetime(None)
+        } else {
+            pool_options.idle_timeout(Some(Duration::from_secs(self.config.idle_timeout_secs)))
+        };
+        let pool = pool_options.connect_with(options).await?;
+
+        *self.pool.write().await = Some(pool);
+        self.stats.write().await.total_connections = self.config.max_connections;
 
         info!("✅ Database connected");
         Ok(())
@@ -122,33 +133,41 @@ impl DatabaseManager {
     /// 断开连接
     pub async fn disconnect(&self) -> Result<()> {
         info!("🔌 Disconnecting from database");
-        let mut pool = self.pool.write().await;
-        *pool = None;
+        if let Some(pool) = self.pool.write().await.take() {
+            pool.close().await;
+        }
         Ok(())
     }
 
+    /// 是否已连接
+    pub async fn is_connected(&self) -> bool {
+        self.pool.read().await.is_some()
+    }
+
+    /// 当前连接池（未连接时报错）
+    async fn pool(&self) -> Result<SqlitePool> {
+        self.pool
+            .read()
+            .await
+            .clone()
+            .ok_or_else(|| anyhow!("Database not connected"))
+    }
+
     /// 执行查询
     pub async fn query(&self, sql: &str, params: Vec<serde_json::Value>) -> Result<Vec<QueryRow>> {
         let start = std::time::Instant::now();
 
         debug!("🔍 Executing query: {}", sql);
 
-        // TODO: 实际执行查询
-        // let pool = self.pool.read().await;
-        // let pool = pool.as_ref().ok_or_else(|| anyhow!("Database not connected"))?;
-        // let rows = sqlx::query(sql).fetch_all(pool).await?;
-
-        let elapsed = start.elapsed().as_millis() as f64;
-
-        // 更新统计
-        let mut stats = self.stats.write().await;
-        stats.total_queries += 1;
-        stats.avg_query_time_ms =
-            (stats.avg_query_time_ms * (stats.total_queries - 1) as f64 + elapsed)
-                / stats.total_queries as f64;
+        let pool = self.pool().await?;
+        let result = bind_params(sqlx::query(sql), params)
+            .fetch_all(&pool)
+            .await
+            .map_err(anyhow::Error::from)
+            .and_then(|rows| rows.iter().map(row_to_json).collect::<Result<Vec<_>>>());
 
-        // Placeholder
-        Ok(Vec::new())
+        self.record_query(start, result.is_ok()).await;
+        result
     }
 
     /// 执行单行查询
@@ -163,23 +182,28 @@ impl DatabaseManager {
 
         debug!("✏️  Executing statement: {}", sql);
 
-        // TODO: 实际执行
-        // let pool = self.pool.read().await;
-        // let pool = pool.as_ref().ok_or_else(|| anyhow!("Database not connected"))?;
-        // let result = sqlx::query(sql).execute(pool).await?;
-        // let rows_affected = result.rows_affected();
+        let pool = self.pool().await?;
+        let result = bind_params(sqlx::query(sql), params)
+            .execute(&pool)
+            .await
+            .map(|done| done.rows_affected())
+            .map_err(anyhow::Error::from);
 
-        let elapsed = start.elapsed().as_millis() as f64;
+        self.record_query(start, result.is_ok()).await;
+        result
+    }
 
-        // 更新统计
+    /// 更新查询统计
+    async fn record_query(&self, start: std::time::Instant, success: bool) {
+        let elapsed = start.elapsed().as_millis() as f64;
         let mut stats = self.stats.write().await;
         stats.total_queries += 1;
+        if !success {
+            stats.failed_queries += 1;
+        }
         stats.avg_query_time_ms =
             (stats.avg_query_time_ms * (stats.total_queries - 1) as f64 + elapsed)
                 / stats.total_queries as f64;
-
-        // Placeholder
-        Ok(0)
     }
 
     /// 开始事务
@@ -239,6 +263,46 @@ impl DatabaseManager {
     }
 }
 
+/// 按顺序绑定 JSON 参数（数组/对象以 JSON 文本写入）
+fn bind_params<'q>(
+    mut query: sqlx::query::Query<'q, sqlx::Sqlite, SqliteArguments<'q>>,
+    params: Vec<serde_json::Value>,
+) -> sqlx::query::Query<'q, sqlx::Sqlite, SqliteArguments<'q>> {
+    for param in params {
+        query = match param {
+            serde_json::Value::Null => query.bind(None::<String>),
+            serde_json::Value::Bool(b) => query.bind(b),
+            serde_json::Value::Number(n) => match n.as_i64() {
+                Some(i) => query.bind(i),
+                None => query.bind(n.as_f64().unwrap_or_default()),
+            },
+            serde_json::Value::String(s) => query.bind(s),
+            other => query.bind(other.to_string()),
+        };
+    }
+    query
+}
+
+/// 结果行 → 列名到 JSON 值的映射（按存储类型解码，BLOB 以字节数组表示）
+fn row_to_json(row: &SqliteRow) -> Result<QueryRow> {
+    let mut out = QueryRow::new();
+    for (i, column) in row.columns().iter().enumerate() {
+        let raw = row.try_get_raw(i)?;
+        let value = if raw.is_null() {
+            serde_json::Value::Null
+        } else {
+            match raw.type_info().name() {
+                "INTEGER" | "BOOLEAN" => serde_json::json!(row.try_get::<i64, _>(i)?),
+                "REAL" => serde_json::json!(row.try_get::<f64, _>(i)?),
+                "BLOB" => serde_json::json!(row.try_get::<Vec<u8>, _>(i)?),
+                _ => serde_json::json!(row.try_get::<String, _>(i)?),
+            }
+        };
+        out.insert(column.name().to_string(), value);
+    }
+    Ok(out)
+}
+
 /// 数据库事务
 pub struct DatabaseTransaction {
     pub id: String,
@@ -366,4 +430,41 @@ mod tests {
         // 基本创建测试
         assert!(true);
     }
+
+    #[tokio::test]
+    async fn test_sqlite_roundtrip() {
+        let manager = DatabaseManager::new(DatabaseConfig {
+            url: "sqlite::memory:".to_string(),
+            ..Default::default()
+        });
+        assert!(manager.query("SELECT 1", vec![]).await.is_err());
+        manager.connect().await.unwrap();
+        assert!(manager.health_check().await.unwrap());
+
+        manager
+            .execute("CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT, score REAL, note TEXT)", vec![])
+            .await
+            .unwrap();
+        let inserted = manager
+            .execute(
+                "INSERT INTO items (name, score, note) VALUES (?, ?, ?)",
+                vec![serde_json::json!("alpha"), serde_json::json!(0.5), serde_json::Value::Null],
+            )
+            .await
+            .unwrap();
+        assert_eq!(inserted, 1);
+
+        let row = manager
+            .query_one("SELECT id, name, score, note FROM items WHERE name = ?", vec![serde_json::json!("alpha")])
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(row["id"], serde_json::json!(1));
+        assert_eq!(row["name"], serde_json::json!("alpha"));
+        assert_eq!(row["score"], serde_json::json!(0.5));
+        assert!(row["note"].is_null());
+
+        assert!(manager.execute("SELEC nonsense", vec![]).await.is_err());
+        assert_eq!(manager.get_pool_stats().await.failed_queries, 1);
+    }
 }
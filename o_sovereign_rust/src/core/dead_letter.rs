@@ -0,0 +1,153 @@
+// Dead Letter - 事件处理失败的死信队列
+// EventHandler 重试用尽（或 panic）后，事件连同错误上下文进入死信队列，排查后可重新投递
+//
+// 核心功能：
+// 1. 记录失败的订阅者、错误信息、尝试次数、是否 panic、日志偏移量
+// 2. 可选落盘（JSON 文件），进程重启后仍可查看；CLI 与服务进程共用同一文件
+// 3. 按 ID 查看 / 取出 / 删除，整体清空
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tracing::warn;
+
+use super::event_bus::Event;
+
+/// 默认死信文件
+pub const DEFAULT_DEAD_LETTER_PATH: &str = "./data/events/dead_letters.json";
+
+/// 一条死信
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub id: String,
+    pub event: Event,
+    /// 处理失败的订阅者（重新投递时交给它）
+    pub subscriber_id: String,
+    /// 最后一次失败的错误
+    pub error: String,
+    /// 累计尝试次数（含历次重新投递）
+    pub attempts: u32,
+    /// 处理器是否 panic
+    #[serde(default)]
+    pub panicked: bool,
+    /// 事件在日志中的偏移量（未接入日志时为空）
+    #[serde(default)]
+    pub offset: Option<u64>,
+    pub failed_at: DateTime<Utc>,
+}
+
+/// 死信队列
+pub struct DeadLetterQueue {
+    /// 落盘路径（为空时只在内存中）
+    path: Option<PathBuf>,
+    letters: Mutex<Vec<DeadLetter>>,
+    /// 内存中最多保留的条数（超出时丢弃最早的）
+    capacity: usize,
+}
+
+impl DeadLetterQueue {
+    /// 仅内存的死信队列
+    pub fn in_memory() -> Self {
+        Self { path: None, letters: Mutex::new(Vec::new()), capacity: 10_000 }
+    }
+
+    /// 打开落盘的死信队列
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let letters = if path.exists() {
+            serde_json::from_str(&std::fs::read_to_string(&path)?)
+                .with_context(|| format!("Corrupted dead letter file {}", path.display()))?
+        } else {
+            Vec::new()
+        };
+        Ok(Self { path: Some(path), letters: Mutex::new(letters), capacity: 10_000 })
+    }
+
+    /// 内存中最多保留的条数
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    /// 加入一条死信，返回其 ID
+    pub fn push(&self, mut letter: DeadLetter) -> String {
+        let mut letters = self.letters.lock().unwrap();
+        let base = format!("dlq_{}", letter.failed_at.timestamp_millis());
+        let mut id = base.clone();
+        let mut suffix = 1;
+        while letters.iter().any(|existing| existing.id == id) {
+            id = format!("{}_{}", base, suffix);
+            suffix += 1;
+        }
+        letter.id = id.clone();
+        letters.push(letter);
+        if letters.len() > self.capacity {
+            let overflow = letters.len() - self.capacity;
+            letters.drain(0..overflow);
+        }
+        self.persist(&letters);
+        id
+    }
+
+    /// 全部死信（最早的在前）
+    pub fn list(&self) -> Vec<DeadLetter> {
+        self.letters.lock().unwrap().clone()
+    }
+
+    pub fn get(&self, id: &str) -> Option<DeadLetter> {
+        self.letters.lock().unwrap().iter().find(|letter| letter.id == id).cloned()
+    }
+
+    /// 取出（并删除）一条死信
+    pub fn take(&self, id: &str) -> Option<DeadLetter> {
+        let mut letters = self.letters.lock().unwrap();
+        let index = letters.iter().position(|letter| letter.id == id)?;
+        let letter = letters.remove(index);
+        self.persist(&letters);
+        Some(letter)
+    }
+
+    pub fn len(&self) -> usize {
+        self.letters.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 清空，返回清除的条数
+    pub fn clear(&self) -> usize {
+        let mut letters = self.letters.lock().unwrap();
+        let count = letters.len();
+        letters.clear();
+        self.persist(&letters);
+        count
+    }
+
+    /// 落盘失败只记日志（死信仍在内存中）
+    fn persist(&self, letters: &[DeadLetter]) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let result = serde_json::to_string_pretty(letters).map_err(anyhow::Error::from).and_then(|content| {
+            let tmp = path.with_extension("json.tmp");
+            std::fs::write(&tmp, content)?;
+            std::fs::rename(&tmp, path)?;
+            Ok(())
+        });
+        if let Err(e) = result {
+            warn!("⚠️  Failed to persist dead letters to {}: {}", path.display(), e);
+        }
+    }
+}
+
+impl Default for DeadLetterQueue {
+    fn default() -> Self {
+        Self::in_memory()
+    }
+}
@@ -1,10 +1,13 @@
 // DeepSeek Provider - Omega's Brain
 // 性价比极高的代码生成引擎
 
+use super::api_manager::{self, ApiProvider};
+use super::determinism;
 use super::opencode::{OpenCodeConfig, OpenCodeExecutor};
 use super::providers::ModelProvider;
+use super::retry;
 use super::types::{AgentResponse, AgentRole, AgentStats};
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use async_openai::{
     config::OpenAIConfig,
     types::{
@@ -218,7 +221,7 @@ impl ModelProvider for DeepSeekProvider {
         info!("🧠 DeepSeek-Coder (Omega) processing task...");
         debug!("Prompt: {}...", &prompt.chars().take(100).collect::<String>());
 
-        let request = CreateChatCompletionRequestArgs::default()
+        let mut request = CreateChatCompletionRequestArgs::default()
             .model(&self.model)
             .messages(vec![
                 ChatCompletionRequestMessage::System(
@@ -235,8 +238,21 @@ impl ModelProvider for DeepSeekProvider {
             .max_tokens(max_tokens as u16)
             .temperature(temperature as f32)
             .build()?;
+        // 固定种子模式下透传 seed，便于复现
+        request.seed = determinism::provider_seed();
+
+        let response = retry::current()
+            .run("DeepSeek", || async {
+                // 保留 reqwest 错误链，便于区分超时 / 连接失败
+                self.client
+                    .chat()
+                    .create(request.clone())
+                    .await
+                    .map_err(|e| anyhow::Error::new(e).context("DeepSeek API error"))
+            })
+            .await;
 
-        match self.client.chat().create(request).await {
+        match response {
             Ok(response) => {
                 let latency_ms = start.elapsed().as_millis() as u64;
 
@@ -246,11 +262,13 @@ impl ModelProvider for DeepSeekProvider {
                     .and_then(|c| c.message.content.clone())
                     .unwrap_or_default();
 
-                let tokens = response.usage.map(|u| u.total_tokens).unwrap_or(0);
+                let (prompt_tokens, completion_tokens) =
+                    response.usage.as_ref().map(|u| (u.prompt_tokens, u.completion_tokens)).unwrap_or((0, 0));
+                let tokens = prompt_tokens + completion_tokens;
 
-                // DeepSeek的定价极低，这里使用估算值
-                // 实际价格: ~$0.0014/1M tokens (input), ~$0.0028/1M tokens (output)
-                let cost = (tokens as f64 / 1_000_000.0) * 0.002;
+                // DeepSeek的定价极低（输入 / 输出分别计价）
+                let cost =
+                    api_manager::estimate_cost(ApiProvider::DeepSeek, &self.model, prompt_tokens, completion_tokens);
 
                 let mut stats = self.stats.lock().await;
                 stats.record_success(tokens, cost, latency_ms);
@@ -306,7 +324,7 @@ impl ModelProvider for DeepSeekProvider {
                 let latency_ms = start.elapsed().as_millis() as u64;
                 let mut stats = self.stats.lock().await;
                 stats.record_failure(latency_ms);
-                Err(anyhow!("DeepSeek API error: {}", e))
+                Err(e)
             }
         }
     }
@@ -315,6 +333,14 @@ impl ModelProvider for DeepSeekProvider {
         self.role
     }
 
+    fn model_id(&self) -> String {
+        format!("deepseek/{}", self.model)
+    }
+
+    fn billing(&self) -> Option<ApiProvider> {
+        Some(ApiProvider::DeepSeek)
+    }
+
     async fn stats(&self) -> AgentStats {
         self.stats.lock().await.clone()
     }
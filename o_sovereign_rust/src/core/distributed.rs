@@ -7,16 +7,26 @@
 // 3. Leader选举
 // 4. 健康检查与故障转移
 // 5. 集群状态同步
-// 6. 负载均衡
+// 6. 负载均衡（平滑加权轮询 / 最少连接 / 按会话一致性哈希，连接计数，排除不健康实例）
+// 7. CoordinationStore：Redis 后端（feature = "redis"）与进程内后端（测试 / 单机）
+// 8. 任期 + 租约选举（多数派投票、fencing token、失去租约即卸任），角色变化发布到 event_bus
 
 use anyhow::{anyhow, Result};
+use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::sync::Arc;
-use std::time::Duration;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
-use tracing::{info, warn};
+use tracing::{debug, info, warn};
+
+use super::event_bus::{Event, EventBus, EventType};
+#[cfg(feature = "redis")]
+use super::redis_client::{RedisClient, RespValue};
+
+/// 角色变化事件类型（EventType::System）
+pub const ROLE_CHANGED_EVENT: &str = "cluster.role_changed";
 
 /// 节点角色
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -91,6 +101,294 @@ impl Default for LockConfig {
     }
 }
 
+/// 协调存储：分布式锁与服务注册表所需的最小原语（带过期时间的键值）
+#[async_trait]
+pub trait CoordinationStore: Send + Sync {
+    /// 后端名称
+    fn name(&self) -> &str;
+
+    /// 键不存在时写入并设置过期时间（SET NX PX），返回是否写入
+    async fn set_if_absent(&self, key: &str, value: &str, ttl: Duration) -> Result<bool>;
+
+    /// 值等于 expected 时删除（释放锁时校验持有者）
+    async fn delete_if_equals(&self, key: &str, expected: &str) -> Result<bool>;
+
+    /// 值等于 expected 时重设过期时间（续期锁时校验持有者）
+    async fn expire_if_equals(&self, key: &str, expected: &str, ttl: Duration) -> Result<bool>;
+
+    /// 写入（或覆盖）并设置过期时间
+    async fn put(&self, key: &str, value: &str, ttl: Duration) -> Result<()>;
+
+    /// 读取未过期的值
+    async fn get(&self, key: &str) -> Result<Option<String>>;
+
+    /// 删除键，返回是否存在
+    async fn delete(&self, key: &str) -> Result<bool>;
+
+    /// 列出前缀下全部未过期的键值
+    async fn scan_prefix(&self, prefix: &str) -> Result<Vec<(String, String)>>;
+
+    /// 选举投票：term 大于已记录任期且租约键不存在时，原子地记录任期（不过期）并写入租约
+    async fn acquire_lease(&self, lease_key: &str, term_key: &str, term: u64, holder: &str, ttl: Duration) -> Result<bool>;
+}
+
+/// 进程内协调存储（测试 / 单机部署）
+#[derive(Default)]
+pub struct MemoryCoordinationStore {
+    /// 键 -> (值, 过期时刻)
+    entries: Mutex<HashMap<String, (String, Option<Instant>)>>,
+}
+
+impl MemoryCoordinationStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, HashMap<String, (String, Option<Instant>)>> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        entries.retain(|_, (_, expires_at)| expires_at.is_none_or(|at| at > now));
+        entries
+    }
+}
+
+#[async_trait]
+impl CoordinationStore for MemoryCoordinationStore {
+    fn name(&self) -> &str {
+        "memory"
+    }
+
+    async fn set_if_absent(&self, key: &str, value: &str, ttl: Duration) -> Result<bool> {
+        let mut entries = self.entries();
+        if entries.contains_key(key) {
+            return Ok(false);
+        }
+        entries.insert(key.to_string(), (value.to_string(), Some(Instant::now() + ttl)));
+        Ok(true)
+    }
+
+    async fn delete_if_equals(&self, key: &str, expected: &str) -> Result<bool> {
+        let mut entries = self.entries();
+        if entries.get(key).is_some_and(|(value, _)| value == expected) {
+            entries.remove(key);
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    async fn expire_if_equals(&self, key: &str, expected: &str, ttl: Duration) -> Result<bool> {
+        let mut entries = self.entries();
+        match entries.get_mut(key) {
+            Some((value, expires_at)) if value == expected => {
+                *expires_at = Some(Instant::now() + ttl);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn put(&self, key: &str, value: &str, ttl: Duration) -> Result<()> {
+        self.entries()
+            .insert(key.to_string(), (value.to_string(), Some(Instant::now() + ttl)));
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<String>> {
+        Ok(self.entries().get(key).map(|(value, _)| value.clone()))
+    }
+
+    async fn delete(&self, key: &str) -> Result<bool> {
+        Ok(self.entries().remove(key).is_some())
+    }
+
+    async fn scan_prefix(&self, prefix: &str) -> Result<Vec<(String, String)>> {
+        let mut found: Vec<_> = self
+            .entries()
+            .iter()
+            .filter(|(key, _)| key.starts_with(prefix))
+            .map(|(key, (value, _))| (key.clone(), value.clone()))
+            .collect();
+        found.sort();
+        Ok(found)
+    }
+
+    async fn acquire_lease(&self, lease_key: &str, term_key: &str, term: u64, holder: &str, ttl: Duration) -> Result<bool> {
+        let mut entries = self.entries();
+        let recorded = entries
+            .get(term_key)
+            .and_then(|(value, _)| value.parse::<u64>().ok())
+            .unwrap_or(0);
+        if recorded >= term || entries.contains_key(lease_key) {
+            return Ok(false);
+        }
+        entries.insert(term_key.to_string(), (term.to_string(), None));
+        entries.insert(lease_key.to_string(), (holder.to_string(), Some(Instant::now() + ttl)));
+        Ok(true)
+    }
+}
+
+/// 未配置 Redis 时，同一进程内的锁与服务注册共享这一个存储
+static LOCAL_STORE: LazyLock<Arc<MemoryCoordinationStore>> = LazyLock::new(|| Arc::new(MemoryCoordinationStore::new()));
+
+fn local_store() -> Arc<dyn CoordinationStore> {
+    LOCAL_STORE.clone()
+}
+
+/// 释放锁：只删除自己持有的锁
+#[cfg(feature = "redis")]
+const RELEASE_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('DEL', KEYS[1])
+end
+return 0
+"#;
+
+/// 续期锁：只续期自己持有的锁
+#[cfg(feature = "redis")]
+const RENEW_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('PEXPIRE', KEYS[1], ARGV[2])
+end
+return 0
+"#;
+
+/// 选举投票：任期更新且无有效租约时才写入
+#[cfg(feature = "redis")]
+const LEASE_SCRIPT: &str = r#"
+local recorded = tonumber(redis.call('GET', KEYS[2]) or '0')
+if recorded >= tonumber(ARGV[1]) or redis.call('EXISTS', KEYS[1]) == 1 then
+    return 0
+end
+redis.call('SET', KEYS[2], ARGV[1])
+redis.call('SET', KEYS[1], ARGV[2], 'PX', ARGV[3])
+return 1
+"#;
+
+/// Redis 协调存储（feature = "redis"）
+#[cfg(feature = "redis")]
+pub struct RedisCoordinationStore {
+    client: RedisClient,
+}
+
+#[cfg(feature = "redis")]
+impl RedisCoordinationStore {
+    /// 按连接串创建（首次使用时才建立连接）
+    pub fn connect(url: &str) -> Result<Self> {
+        Ok(Self { client: RedisClient::open(url)? })
+    }
+}
+
+#[cfg(feature = "redis")]
+fn ttl_millis(ttl: Duration) -> String {
+    ttl.as_millis().max(1).to_string()
+}
+
+/// 转义 SCAN MATCH 的通配符
+#[cfg(feature = "redis")]
+fn glob_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(feature = "redis")]
+#[async_trait]
+impl CoordinationStore for RedisCoordinationStore {
+    fn name(&self) -> &str {
+        "redis"
+    }
+
+    async fn set_if_absent(&self, key: &str, value: &str, ttl: Duration) -> Result<bool> {
+        let ttl = ttl_millis(ttl);
+        let reply = self.client.command(&["SET", key, value, "NX", "PX", ttl.as_str()]).await?;
+        Ok(!matches!(reply, RespValue::Bulk(None)))
+    }
+
+    async fn delete_if_equals(&self, key: &str, expected: &str) -> Result<bool> {
+        let reply = self.client.eval(RELEASE_SCRIPT, &[key], &[expected.to_string()]).await?;
+        Ok(reply.as_i64().unwrap_or(0) > 0)
+    }
+
+    async fn expire_if_equals(&self, key: &str, expected: &str, ttl: Duration) -> Result<bool> {
+        let reply = self
+            .client
+            .eval(RENEW_SCRIPT, &[key], &[expected.to_string(), ttl_millis(ttl)])
+            .await?;
+        Ok(reply.as_i64().unwrap_or(0) > 0)
+    }
+
+    async fn put(&self, key: &str, value: &str, ttl: Duration) -> Result<()> {
+        let ttl = ttl_millis(ttl);
+        self.client.command(&["SET", key, value, "PX", ttl.as_str()]).await?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<String>> {
+        Ok(self.client.command(&["GET", key]).await?.into_string())
+    }
+
+    async fn delete(&self, key: &str) -> Result<bool> {
+        Ok(self.client.command(&["DEL", key]).await?.as_i64().unwrap_or(0) > 0)
+    }
+
+    async fn scan_prefix(&self, prefix: &str) -> Result<Vec<(String, String)>> {
+        let pattern = format!("{}*", glob_escape(prefix));
+        let mut keys = Vec::new();
+        let mut cursor = "0".to_string();
+        loop {
+            let reply = self
+                .client
+                .command(&["SCAN", cursor.as_str(), "MATCH", pattern.as_str(), "COUNT", "100"])
+                .await?
+                .into_array();
+            let mut parts = reply.into_iter();
+            cursor = parts
+                .next()
+                .and_then(RespValue::into_string)
+                .ok_or_else(|| anyhow!("Malformed SCAN reply"))?;
+            keys.extend(parts.next().map(RespValue::into_array).unwrap_or_default().into_iter().filter_map(RespValue::into_string));
+            if cursor == "0" {
+                break;
+            }
+        }
+        // SCAN 可能返回重复键
+        keys.sort();
+        keys.dedup();
+
+        let mut found = Vec::with_capacity(keys.len());
+        for chunk in keys.chunks(100) {
+            let mut command = vec!["MGET"];
+            command.extend(chunk.iter().map(String::as_str));
+            let values = self.client.command(&command).await?.into_array();
+            // 扫描与读取之间过期的键返回 nil，跳过
+            found.extend(
+                chunk
+                    .iter()
+                    .zip(values)
+                    .filter_map(|(key, value)| value.into_string().map(|value| (key.clone(), value))),
+            );
+        }
+        Ok(found)
+    }
+
+    async fn acquire_lease(&self, lease_key: &str, term_key: &str, term: u64, holder: &str, ttl: Duration) -> Result<bool> {
+        let reply = self
+            .client
+            .eval(
+                LEASE_SCRIPT,
+                &[lease_key, term_key],
+                &[term.to_string(), holder.to_string(), ttl_millis(ttl)],
+            )
+            .await?;
+        Ok(reply.as_i64().unwrap_or(0) > 0)
+    }
+}
+
 /// 分布式锁
 pub struct DistributedLock {
     /// 锁名称
@@ -99,6 +397,8 @@ pub struct DistributedLock {
     owner_id: String,
     /// 锁配置
     config: LockConfig,
+    /// 协调存储（Redlock 时为多个相互独立的 Redis 实例）
+    stores: Vec<Arc<dyn CoordinationStore>>,
     /// 是否已获取
     acquired: Arc<RwLock<bool>>,
     /// 获取时间
@@ -106,34 +406,50 @@ pub struct DistributedLock {
 }
 
 impl DistributedLock {
-    /// 创建新的分布式锁
+    /// 创建新的分布式锁（默认使用进程内存储）
     pub fn new(lock_name: String, owner_id: String, config: LockConfig) -> Self {
         Self {
             lock_name,
             owner_id,
             config,
+            stores: vec![local_store()],
             acquired: Arc::new(RwLock::new(false)),
             acquired_at: Arc::new(RwLock::new(None)),
         }
     }
 
+    /// 使用指定的协调存储（为空时保持不变）
+    pub fn with_stores(mut self, stores: Vec<Arc<dyn CoordinationStore>>) -> Self {
+        if !stores.is_empty() {
+            self.stores = stores;
+        }
+        self
+    }
+
+    /// 参与加锁的存储：Redlock 使用全部实例，否则只用第一个
+    fn active_stores(&self) -> &[Arc<dyn CoordinationStore>] {
+        if self.config.use_redlock {
+            &self.stores
+        } else {
+            &self.stores[..1]
+        }
+    }
+
+    /// 多数派数量
+    fn quorum(&self) -> usize {
+        self.active_stores().len() / 2 + 1
+    }
+
+    fn ttl(&self) -> Duration {
+        Duration::from_secs(self.config.lock_timeout_secs)
+    }
+
     /// 尝试获取锁
     pub async fn acquire(&self) -> Result<bool> {
         info!("🔒 Trying to acquire lock: {}", self.lock_name);
 
-        for attempt in 1..=self.config.retry_count {
-            // TODO: 实际Redis SET NX EX命令
-            // 使用 redis crate:
-            // let result: bool = redis_conn.set_nx_ex(
-            //     &self.lock_name,
-            //     &self.owner_id,
-            //     self.config.lock_timeout_secs
-            // ).await?;
-
-            // Placeholder: 模拟获取锁
-            let acquired = attempt == 1; // 第一次尝试成功
-
-            if acquired {
+        for attempt in 1..=self.config.retry_count.max(1) {
+            if self.try_acquire_once().await? {
                 *self.acquired.write().await = true;
                 *self.acquired_at.write().await = Some(Utc::now());
                 info!("✅ Lock acquired: {}", self.lock_name);
@@ -150,6 +466,51 @@ impl DistributedLock {
         Ok(false)
     }
 
+    /// 在各存储上执行一次 SET NX PX；未达多数或耗时超过有效期时撤销已写入的部分
+    async fn try_acquire_once(&self) -> Result<bool> {
+        let stores = self.active_stores();
+        let ttl = self.ttl();
+        let started = Instant::now();
+        let mut locked = 0;
+        let mut last_error = None;
+
+        for store in stores {
+            match store.set_if_absent(&self.lock_name, &self.owner_id, ttl).await {
+                Ok(true) => locked += 1,
+                Ok(false) => {}
+                Err(e) => {
+                    warn!("⚠️  Lock store {} unavailable: {}", store.name(), e);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        if locked >= self.quorum() && started.elapsed() < ttl {
+            return Ok(true);
+        }
+        if locked > 0 {
+            self.release_on(stores).await;
+        }
+        match last_error {
+            // 所有存储都不可用时报告错误，而不是当作锁被占用
+            Some(e) if locked == 0 && stores.len() == 1 => Err(e),
+            _ => Ok(false),
+        }
+    }
+
+    /// 在各存储上校验持有者后删除，返回实际删除的数量
+    async fn release_on(&self, stores: &[Arc<dyn CoordinationStore>]) -> usize {
+        let mut released = 0;
+        for store in stores {
+            match store.delete_if_equals(&self.lock_name, &self.owner_id).await {
+                Ok(true) => released += 1,
+                Ok(false) => {}
+                Err(e) => warn!("⚠️  Failed to release lock on {}: {}", store.name(), e),
+            }
+        }
+        released
+    }
+
     /// 释放锁
     pub async fn release(&self) -> Result<()> {
         if !*self.acquired.read().await {
@@ -158,13 +519,10 @@ impl DistributedLock {
 
         info!("🔓 Releasing lock: {}", self.lock_name);
 
-        // TODO: 实际Redis DEL命令（需要校验owner_id）
-        // 使用Lua脚本保证原子性：
-        // if redis.call("get", KEYS[1]) == ARGV[1] then
-        //     return redis.call("del", KEYS[1])
-        // else
-        //     return 0
-        // end
+        if self.release_on(self.active_stores()).await == 0 {
+            // 锁已过期或被他人接管，不能删除别人的锁
+            warn!("⚠️  Lock {} was no longer held by {}", self.lock_name, self.owner_id);
+        }
 
         *self.acquired.write().await = false;
         *self.acquired_at.write().await = None;
@@ -173,16 +531,30 @@ impl DistributedLock {
         Ok(())
     }
 
-    /// 续期锁
+    /// 续期锁（未能在多数存储上续期时视为已失去锁）
     pub async fn renew(&self) -> Result<bool> {
         if !*self.acquired.read().await {
             return Ok(false);
         }
 
-        // TODO: 实际Redis EXPIRE命令
-        // redis_conn.expire(&self.lock_name, self.config.lock_timeout_secs).await?;
+        let ttl = self.ttl();
+        let mut renewed = 0;
+        for store in self.active_stores() {
+            match store.expire_if_equals(&self.lock_name, &self.owner_id, ttl).await {
+                Ok(true) => renewed += 1,
+                Ok(false) => {}
+                Err(e) => warn!("⚠️  Failed to renew lock on {}: {}", store.name(), e),
+            }
+        }
 
-        info!("🔄 Lock renewed: {}", self.lock_name);
+        if renewed < self.quorum() {
+            warn!("⚠️  Lock lost: {}", self.lock_name);
+            *self.acquired.write().await = false;
+            *self.acquired_at.write().await = None;
+            return Ok(false);
+        }
+
+        debug!("🔄 Lock renewed: {}", self.lock_name);
         Ok(true)
     }
 
@@ -234,6 +606,8 @@ impl Default for ServiceDiscoveryConfig {
 /// 服务发现与注册
 pub struct ServiceDiscovery {
     config: ServiceDiscoveryConfig,
+    /// 协调存储（第一个同时作为服务注册表）
+    stores: Vec<Arc<dyn CoordinationStore>>,
     /// 当前实例信息
     current_instance: Arc<RwLock<ServiceInstance>>,
     /// 已发现的服务实例
@@ -242,74 +616,151 @@ pub struct ServiceDiscovery {
     current_role: Arc<RwLock<NodeRole>>,
     /// Leader实例ID
     leader_id: Arc<RwLock<Option<String>>>,
+    /// 已知的最高任期
+    current_term: Arc<RwLock<u64>>,
+    /// 当选Leader时持有的租约（卸任或续约失败时清除）
+    lease: Arc<RwLock<Option<LeaderLease>>>,
+    /// 角色变化事件
+    event_bus: Option<Arc<EventBus>>,
+}
+
+/// Leader租约
+#[derive(Debug, Clone, Copy)]
+struct LeaderLease {
+    /// 当选任期（即 fencing token）
+    term: u64,
+    /// 本地认定的到期时刻
+    deadline: Instant,
 }
 
 impl ServiceDiscovery {
-    /// 创建新的服务发现
+    /// 创建新的服务发现（默认使用进程内存储）
     pub fn new(config: ServiceDiscoveryConfig, instance: ServiceInstance) -> Self {
         info!("🌐 Initializing Service Discovery");
         info!("    Instance: {}@{}:{}", instance.instance_id, instance.host, instance.port);
-        info!("    Redis: {:?}", config.redis_urls);
 
         Self {
             config,
+            stores: vec![local_store()],
             current_instance: Arc::new(RwLock::new(instance)),
             discovered_services: Arc::new(RwLock::new(HashMap::new())),
             current_role: Arc::new(RwLock::new(NodeRole::Follower)),
             leader_id: Arc::new(RwLock::new(None)),
+            current_term: Arc::new(RwLock::new(0)),
+            lease: Arc::new(RwLock::new(None)),
+            event_bus: None,
+        }
+    }
+
+    /// 按 config.redis_urls 连接 Redis（多个 URL 时选举锁使用 Redlock）
+    #[cfg(feature = "redis")]
+    pub fn connect(config: ServiceDiscoveryConfig, instance: ServiceInstance) -> Result<Self> {
+        info!("    Redis: {:?}", config.redis_urls);
+        let stores = config
+            .redis_urls
+            .iter()
+            .map(|url| Ok(Arc::new(RedisCoordinationStore::connect(url)?) as Arc<dyn CoordinationStore>))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self::new(config, instance).with_stores(stores))
+    }
+
+    /// 使用指定的协调存储（为空时保持不变）
+    pub fn with_stores(mut self, stores: Vec<Arc<dyn CoordinationStore>>) -> Self {
+        if !stores.is_empty() {
+            self.stores = stores;
         }
+        self
+    }
+
+    /// 角色变化时发布 `cluster.role_changed` 事件
+    pub fn with_event_bus(mut self, event_bus: Arc<EventBus>) -> Self {
+        self.event_bus = Some(event_bus);
+        self
+    }
+
+    /// 当前使用的协调存储
+    pub fn stores(&self) -> Vec<Arc<dyn CoordinationStore>> {
+        self.stores.clone()
+    }
+
+    fn registry(&self) -> &Arc<dyn CoordinationStore> {
+        &self.stores[0]
+    }
+
+    fn service_key(service_name: &str, instance_id: &str) -> String {
+        format!("service:{}:{}", service_name, instance_id)
+    }
+
+    fn leader_key(service_name: &str) -> String {
+        format!("leader:{}", service_name)
+    }
+
+    fn term_key(service_name: &str) -> String {
+        format!("term:{}", service_name)
+    }
+
+    /// 刷新心跳时间并写入注册表（TTL 到期未续写即视为下线）
+    async fn publish_instance(&self) -> Result<ServiceInstance> {
+        let mut instance = self.current_instance.write().await;
+        instance.last_heartbeat_at = Utc::now();
+        let key = Self::service_key(&instance.service_name, &instance.instance_id);
+        self.registry()
+            .put(
+                &key,
+                &serde_json::to_string(&*instance)?,
+                Duration::from_secs(self.config.service_ttl_secs),
+            )
+            .await?;
+        Ok(instance.clone())
     }
 
     /// 注册服务
     pub async fn register(&self) -> Result<()> {
-        let instance = self.current_instance.read().await;
-        info!("📝 Registering service: {}", instance.service_name);
-
-        // TODO: 实际Redis操作
-        // 1. 将服务信息写入Redis Hash
-        // 2. 设置TTL
-        // 使用 redis crate:
-        // let key = format!("service:{}:{}", instance.service_name, instance.instance_id);
-        // redis_conn.hset_multiple(&key, &[
-        //     ("host", &instance.host),
-        //     ("port", &instance.port.to_string()),
-        //     ("status", &format!("{:?}", instance.status)),
-        //     ...
-        // ]).await?;
-        // redis_conn.expire(&key, self.config.service_ttl_secs).await?;
-
-        info!("✅ Service registered");
+        let instance = self.publish_instance().await?;
+        info!(
+            "✅ Service registered: {} ({}, {})",
+            instance.service_name,
+            instance.instance_id,
+            self.registry().name()
+        );
         Ok(())
     }
 
-    /// 发现服务
-    pub async fn discover(&self, service_name: &str) -> Result<Vec<ServiceInstance>> {
-        info!("🔍 Discovering service: {}", service_name);
-
-        // TODO: 实际Redis操作
-        // 1. 扫描 service:{service_name}:* 键
-        // 2. 读取每个实例的信息
-        // 3. 过滤健康的实例
+    /// 注册表中某服务的全部实例（含不健康的）
+    async fn registered_instances(&self, service_name: &str) -> Result<Vec<ServiceInstance>> {
+        let prefix = format!("service:{}:", service_name);
+        let mut instances = Vec::new();
+        for (key, value) in self.registry().scan_prefix(&prefix).await? {
+            match serde_json::from_str::<ServiceInstance>(&value) {
+                Ok(instance) => instances.push(instance),
+                Err(e) => warn!("⚠️  Skipping malformed registry entry {}: {}", key, e),
+            }
+        }
+        Ok(instances)
+    }
 
-        // Placeholder
-        let discovered = self.discovered_services.read().await;
-        Ok(discovered
-            .get(service_name)
-            .cloned()
-            .unwrap_or_default())
+    /// 发现服务（只返回健康或降级的实例）
+    pub async fn discover(&self, service_name: &str) -> Result<Vec<ServiceInstance>> {
+        debug!("🔍 Discovering service: {}", service_name);
+
+        let instances: Vec<_> = self
+            .registered_instances(service_name)
+            .await?
+            .into_iter()
+            .filter(|instance| matches!(instance.status, NodeStatus::Healthy | NodeStatus::Degraded))
+            .collect();
+
+        self.discovered_services
+            .write()
+            .await
+            .insert(service_name.to_string(), instances.clone());
+        Ok(instances)
     }
 
     /// 发送心跳
     pub async fn heartbeat(&self) -> Result<()> {
-        let instance = self.current_instance.read().await;
-
-        // TODO: 实际Redis操作
-        // 更新实例的last_heartbeat_at和续期TTL
-        // let key = format!("service:{}:{}", instance.service_name, instance.instance_id);
-        // redis_conn.hset(&key, "last_heartbeat_at", &Utc::now().to_rfc3339()).await?;
-        // redis_conn.expire(&key, self.config.service_ttl_secs).await?;
-
-        info!("💓 Heartbeat sent: {}", instance.instance_id);
+        let instance = self.publish_instance().await?;
+        debug!("💓 Heartbeat sent: {}", instance.instance_id);
         Ok(())
     }
 
@@ -318,68 +769,269 @@ impl ServiceDiscovery {
         let instance = self.current_instance.read().await;
         info!("📤 Deregistering service: {}", instance.instance_id);
 
-        // TODO: 实际Redis操作
-        // let key = format!("service:{}:{}", instance.service_name, instance.instance_id);
-        // redis_conn.del(&key).await?;
+        let key = Self::service_key(&instance.service_name, &instance.instance_id);
+        self.registry().delete(&key).await?;
 
         info!("✅ Service deregistered");
         Ok(())
     }
 
+    /// 租约时长（即选举超时）
+    fn lease_duration(&self) -> Duration {
+        Duration::from_secs(self.config.election_timeout_secs.max(1))
+    }
+
+    /// 本地认定的租约有效期：预留 1/10 应对时钟漂移，保证本地到期早于存储中的租约过期
+    fn safe_lease(&self) -> Duration {
+        let lease = self.lease_duration();
+        lease - lease / 10
+    }
+
+    fn quorum(&self) -> usize {
+        self.stores.len() / 2 + 1
+    }
+
     /// 开始Leader选举
+    ///
+    /// 与 Raft 相同，每轮选举使用新的任期号：候选人以"已知最高任期 + 1"向每个存储请求投票，
+    /// 存储只在任期更新且当前没有有效租约时投票（同时记录任期），获得多数票即成为该任期的Leader。
+    /// 任期号严格递增，作为 fencing token 交给下游校验。
     pub async fn start_election(&self) -> Result<()> {
         info!("🗳️  Starting leader election");
 
-        *self.current_role.write().await = NodeRole::Candidate;
-
         let instance = self.current_instance.read().await;
         let instance_id = instance.instance_id.clone();
+        let lease_key = Self::leader_key(&instance.service_name);
+        let term_key = Self::term_key(&instance.service_name);
         drop(instance);
 
-        // TODO: 实际Redis分布式锁实现Leader选举
-        // 1. 尝试获取 "leader:{service_name}" 锁
-        // 2. 如果获取成功，成为Leader
-        // 3. 定期续期锁
+        let mut highest = *self.current_term.read().await;
+        for store in &self.stores {
+            match store.get(&term_key).await {
+                Ok(value) => highest = highest.max(value.and_then(|v| v.parse().ok()).unwrap_or(0)),
+                Err(e) => warn!("⚠️  Failed to read term from {}: {}", store.name(), e),
+            }
+        }
+        let term = highest + 1;
+        self.transition(NodeRole::Candidate, None).await;
+
+        let holder = format!("{}:{}", term, instance_id);
+        let started = Instant::now();
+        let mut votes = 0;
+        let mut errors = Vec::new();
+        for store in &self.stores {
+            match store
+                .acquire_lease(&lease_key, &term_key, term, &holder, self.lease_duration())
+                .await
+            {
+                Ok(true) => votes += 1,
+                Ok(false) => {}
+                Err(e) => {
+                    warn!("⚠️  Vote request to {} failed: {}", store.name(), e);
+                    errors.push(e);
+                }
+            }
+        }
 
-        let lock_config = LockConfig {
-            lock_timeout_secs: self.config.election_timeout_secs,
-            retry_count: 1,
-            retry_delay_ms: 0,
-            use_redlock: false,
+        if votes >= self.quorum() && started.elapsed() < self.safe_lease() {
+            *self.current_term.write().await = term;
+            *self.lease.write().await = Some(LeaderLease {
+                term,
+                deadline: started + self.safe_lease(),
+            });
+            self.transition(NodeRole::Leader, Some(instance_id.clone())).await;
+            info!("👑 Elected as Leader: {} (term {})", instance_id, term);
+            return Ok(());
+        }
+
+        // 未获多数票：撤回已写入的租约，让其他候选人尽快当选
+        if votes > 0 {
+            for store in &self.stores {
+                let _ = store.delete_if_equals(&lease_key, &holder).await;
+            }
+        }
+        *self.current_term.write().await = highest;
+        let leader = self.current_leader().await.ok().flatten();
+        self.transition(NodeRole::Follower, leader.map(|(_, id)| id)).await;
+        info!("👥 Following mode");
+
+        // 所有存储都不可达时报告错误
+        if errors.len() == self.stores.len() {
+            if let Some(e) = errors.pop() {
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Leader续约；未能在多数存储上续约或本地租约已到期时立即卸任，避免脑裂
+    pub async fn renew_lease(&self) -> Result<bool> {
+        let Some(lease) = *self.lease.read().await else {
+            return Ok(false);
         };
 
-        let lock = DistributedLock::new(
-            "leader:acsa".to_string(),
-            instance_id.clone(),
-            lock_config,
-        );
+        let instance_id = self.current_instance.read().await.instance_id.clone();
+        let lease_key = self.own_leader_key().await;
+        let holder = format!("{}:{}", lease.term, instance_id);
 
-        if lock.acquire().await? {
-            *self.current_role.write().await = NodeRole::Leader;
-            *self.leader_id.write().await = Some(instance_id.clone());
-            info!("👑 Elected as Leader: {}", instance_id);
-
-            // 启动Leader续期任务
-            let lock_clone = Arc::new(lock);
-            tokio::spawn(async move {
-                loop {
-                    tokio::time::sleep(Duration::from_secs(5)).await;
-                    if lock_clone.renew().await.unwrap_or(false) {
-                        info!("🔄 Leader lease renewed");
-                    } else {
-                        warn!("⚠️  Failed to renew leader lease");
-                        break;
+        let sent_at = Instant::now();
+        if sent_at >= lease.deadline {
+            warn!("⚠️  Leader lease expired locally (term {})", lease.term);
+            self.step_down(&holder).await;
+            return Ok(false);
+        }
+
+        let mut renewed = 0;
+        for store in &self.stores {
+            match store.expire_if_equals(&lease_key, &holder, self.lease_duration()).await {
+                Ok(true) => renewed += 1,
+                Ok(false) => {}
+                Err(e) => warn!("⚠️  Failed to renew lease on {}: {}", store.name(), e),
+            }
+        }
+
+        if renewed < self.quorum() || Instant::now() >= lease.deadline {
+            warn!("⚠️  Lost leader lease (term {})", lease.term);
+            self.step_down(&holder).await;
+            return Ok(false);
+        }
+
+        // 新的本地到期时间从发出续约请求时算起，早于存储中的实际过期时间
+        *self.lease.write().await = Some(LeaderLease {
+            term: lease.term,
+            deadline: sent_at + self.safe_lease(),
+        });
+        debug!("🔄 Leader lease renewed (term {})", lease.term);
+        Ok(true)
+    }
+
+    /// 卸任：清除本地租约并尽力撤回存储中的租约
+    async fn step_down(&self, holder: &str) {
+        *self.lease.write().await = None;
+        let lease_key = self.own_leader_key().await;
+        for store in &self.stores {
+            if let Err(e) = store.delete_if_equals(&lease_key, holder).await {
+                debug!("Failed to withdraw lease on {}: {}", store.name(), e);
+            }
+        }
+        self.transition(NodeRole::Follower, None).await;
+    }
+
+    /// 切换角色（同步到下一次心跳写入的实例信息），角色变化时发布事件
+    async fn transition(&self, role: NodeRole, leader_id: Option<String>) {
+        let previous = std::mem::replace(&mut *self.current_role.write().await, role);
+        *self.leader_id.write().await = leader_id.clone();
+        let instance = {
+            let mut instance = self.current_instance.write().await;
+            instance.role = role;
+            instance.clone()
+        };
+        if previous == role {
+            return;
+        }
+
+        let term = *self.current_term.read().await;
+        info!("🔀 Role {:?} -> {:?} (term {})", previous, role, term);
+        let Some(bus) = &self.event_bus else {
+            return;
+        };
+        let event = Event {
+            event_id: format!("role_{}_{}", instance.instance_id, Utc::now().timestamp_millis()),
+            event_type: EventType::System(ROLE_CHANGED_EVENT.to_string()),
+            source: "service_discovery".to_string(),
+            data: serde_json::json!({
+                "instance_id": instance.instance_id,
+                "service_name": instance.service_name,
+                "from": previous,
+                "to": role,
+                "term": term,
+                "leader_id": leader_id,
+            }),
+            timestamp: Utc::now(),
+            metadata: HashMap::from([
+                ("service_name".to_string(), instance.service_name.clone()),
+                ("role".to_string(), format!("{:?}", role)),
+            ]),
+        };
+        if let Err(e) = bus.publish(event).await {
+            warn!("⚠️  Failed to publish role change: {}", e);
+        }
+    }
+
+    async fn own_leader_key(&self) -> String {
+        Self::leader_key(&self.current_instance.read().await.service_name)
+    }
+
+    /// 当前租约持有者 (任期, 实例ID)：取各存储中任期最高的一个（租约过期后为 None）
+    async fn current_leader(&self) -> Result<Option<(u64, String)>> {
+        let lease_key = self.own_leader_key().await;
+        let mut leader: Option<(u64, String)> = None;
+        let mut last_error = None;
+        for store in &self.stores {
+            match store.get(&lease_key).await {
+                Ok(Some(holder)) => {
+                    let Some((term, id)) = holder.split_once(':') else {
+                        continue;
+                    };
+                    let Ok(term) = term.parse::<u64>() else {
+                        continue;
+                    };
+                    if leader.as_ref().is_none_or(|(known, _)| term > *known) {
+                        leader = Some((term, id.to_string()));
                     }
                 }
-            });
-        } else {
-            *self.current_role.write().await = NodeRole::Follower;
-            info!("👥 Following mode");
+                Ok(None) => {}
+                Err(e) => last_error = Some(e),
+            }
+        }
+        match (leader, last_error) {
+            (None, Some(e)) if self.stores.len() == 1 => Err(e),
+            (leader, _) => Ok(leader),
         }
+    }
+
+    /// 主动卸任Leader（撤回租约，让其他节点尽快接管）
+    pub async fn resign_leadership(&self) -> Result<()> {
+        let Some(lease) = *self.lease.read().await else {
+            return Ok(());
+        };
 
+        let instance_id = self.current_instance.read().await.instance_id.clone();
+        self.step_down(&format!("{}:{}", lease.term, instance_id)).await;
+        info!("👋 Resigned leadership");
         Ok(())
     }
 
+    /// 当前任期号
+    pub async fn current_term(&self) -> u64 {
+        *self.current_term.read().await
+    }
+
+    /// Fencing token：本地租约有效期内返回当选任期，否则 None
+    ///
+    /// Leader 向外部资源写入时携带该 token，资源方通过 `check_fencing_token` 拒绝旧任期的写入。
+    pub async fn fencing_token(&self) -> Option<u64> {
+        self.lease
+            .read()
+            .await
+            .filter(|lease| Instant::now() < lease.deadline)
+            .map(|lease| lease.term)
+    }
+
+    /// 校验 fencing token：多数存储中的租约仍属于该任期时有效
+    pub async fn check_fencing_token(&self, token: u64) -> Result<bool> {
+        let lease_key = self.own_leader_key().await;
+        let mut matching = 0;
+        for store in &self.stores {
+            let holder = store.get(&lease_key).await?;
+            let term = holder.as_deref().and_then(|h| h.split_once(':')).and_then(|(t, _)| t.parse::<u64>().ok());
+            if term == Some(token) {
+                matching += 1;
+            }
+        }
+        Ok(matching >= self.quorum())
+    }
+
     /// 获取当前角色
     pub async fn get_role(&self) -> NodeRole {
         *self.current_role.read().await
@@ -423,7 +1075,7 @@ impl ServiceDiscovery {
             }
         });
 
-        // Leader选举任务
+        // Leader选举任务：Leader 每 1/3 租约续约一次；Follower 在租约过期后随机退避再竞选
         let self_election = self.clone();
         tokio::spawn(async move {
             // 启动时尝试选举
@@ -431,19 +1083,112 @@ impl ServiceDiscovery {
                 warn!("❌ Election failed: {}", e);
             }
 
-            // 定期检查Leader状态
-            let interval = Duration::from_secs(self_election.config.election_timeout_secs);
+            let tick = self_election.lease_duration() / 3;
             loop {
-                tokio::time::sleep(interval).await;
-                if !self_election.is_leader().await {
-                    // 检查是否需要重新选举
-                    // TODO: 检查Leader是否存活
+                if self_election.is_leader().await {
+                    tokio::time::sleep(tick).await;
+                    if let Err(e) = self_election.renew_lease().await {
+                        warn!("❌ Lease renewal failed: {}", e);
+                    }
+                    continue;
+                }
+
+                tokio::time::sleep(tick + election_jitter(tick)).await;
+                match self_election.current_leader().await {
+                    Ok(Some((term, leader))) => {
+                        let mut current_term = self_election.current_term.write().await;
+                        *current_term = (*current_term).max(term);
+                        drop(current_term);
+                        *self_election.leader_id.write().await = Some(leader);
+                    }
+                    Ok(None) => {
+                        if let Err(e) = self_election.start_election().await {
+                            warn!("❌ Election failed: {}", e);
+                        }
+                    }
+                    Err(e) => warn!("❌ Failed to check leader: {}", e),
                 }
             }
         });
     }
 }
 
+/// 随机选举退避（0 ~ max），避免多个 Follower 同时竞选
+fn election_jitter(max: Duration) -> Duration {
+    let mut bytes = [0u8; 4];
+    if ring::rand::SecureRandom::fill(&ring::rand::SystemRandom::new(), &mut bytes).is_err() {
+        return Duration::ZERO;
+    }
+    max.mul_f64(u32::from_le_bytes(bytes) as f64 / u32::MAX as f64)
+}
+
+/// 负载均衡策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoadBalanceStrategy {
+    /// 平滑加权轮询（按 ServiceInstance.weight）
+    #[default]
+    WeightedRoundRobin,
+    /// 最少连接（连接数 / 权重 最小者）
+    LeastConnections,
+    /// 一致性哈希（同一会话固定落到同一实例；无会话ID时退化为加权轮询）
+    ConsistentHash,
+}
+
+/// 负载均衡配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoadBalancerConfig {
+    /// 策略
+    pub strategy: LoadBalanceStrategy,
+    /// 一致性哈希中权重为 100 的实例对应的虚拟节点数
+    pub virtual_nodes: u32,
+    /// 实例被 mark_unavailable 后的默认排除时长（秒）
+    pub failure_cooldown_secs: u64,
+}
+
+impl Default for LoadBalancerConfig {
+    fn default() -> Self {
+        Self {
+            strategy: LoadBalanceStrategy::WeightedRoundRobin,
+            virtual_nodes: 160,
+            failure_cooldown_secs: 30,
+        }
+    }
+}
+
+/// 负载均衡状态
+#[derive(Default)]
+struct BalancerState {
+    /// 服务名 -> (实例ID -> 平滑加权轮询的当前权重)
+    round_robin: HashMap<String, HashMap<String, i64>>,
+    /// 实例ID -> 排除截止时刻
+    excluded: HashMap<String, Instant>,
+}
+
+/// 活跃连接计数守卫（drop 时减一）
+pub struct ConnectionGuard {
+    instance_id: String,
+    connections: Arc<Mutex<HashMap<String, usize>>>,
+}
+
+impl ConnectionGuard {
+    pub fn instance_id(&self) -> &str {
+        &self.instance_id
+    }
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        let mut connections = self.connections.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(count) = connections.get_mut(&self.instance_id) {
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                connections.remove(&self.instance_id);
+            }
+        }
+    }
+}
+
 /// 集群管理器
 pub struct ClusterManager {
     /// 服务发现
@@ -452,6 +1197,12 @@ pub struct ClusterManager {
     locks: Arc<RwLock<HashMap<String, Arc<DistributedLock>>>>,
     /// 集群统计
     stats: Arc<RwLock<ClusterStats>>,
+    /// 负载均衡配置
+    load_balancer: LoadBalancerConfig,
+    /// 负载均衡状态
+    balancer: Arc<Mutex<BalancerState>>,
+    /// 各实例的活跃连接数
+    connections: Arc<Mutex<HashMap<String, usize>>>,
 }
 
 /// 集群统计
@@ -478,9 +1229,18 @@ impl ClusterManager {
             service_discovery,
             locks: Arc::new(RwLock::new(HashMap::new())),
             stats: Arc::new(RwLock::new(ClusterStats::default())),
+            load_balancer: LoadBalancerConfig::default(),
+            balancer: Arc::new(Mutex::new(BalancerState::default())),
+            connections: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
+    /// 设置负载均衡配置
+    pub fn with_load_balancer(mut self, config: LoadBalancerConfig) -> Self {
+        self.load_balancer = config;
+        self
+    }
+
     /// 获取分布式锁
     pub async fn acquire_lock(
         &self,
@@ -488,11 +1248,10 @@ impl ClusterManager {
         owner_id: &str,
         config: LockConfig,
     ) -> Result<Arc<DistributedLock>> {
-        let lock = Arc::new(DistributedLock::new(
-            lock_name.to_string(),
-            owner_id.to_string(),
-            config,
-        ));
+        let lock = Arc::new(
+            DistributedLock::new(lock_name.to_string(), owner_id.to_string(), config)
+                .with_stores(self.service_discovery.stores()),
+        );
 
         if lock.acquire().await? {
             let mut locks = self.locks.write().await;
@@ -519,69 +1278,372 @@ impl ClusterManager {
         Ok(())
     }
 
+    /// 释放所有持有的分布式锁，返回释放数量
+    pub async fn release_all_locks(&self) -> Result<usize> {
+        let locks: Vec<_> = self.locks.write().await.drain().collect();
+        let count = locks.len();
+        for (_, lock) in locks {
+            lock.release().await?;
+        }
+        Ok(count)
+    }
+
     /// 获取集群状态
     pub async fn get_cluster_status(&self) -> ClusterStats {
-        // TODO: 从Redis获取所有节点状态
+        let service_name = self.service_discovery.current_instance.read().await.service_name.clone();
+        match self.service_discovery.registered_instances(&service_name).await {
+            Ok(instances) => {
+                let mut stats = self.stats.write().await;
+                stats.total_nodes = instances.len();
+                stats.healthy_nodes = instances.iter().filter(|i| i.status == NodeStatus::Healthy).count();
+                stats.leader_count = instances.iter().filter(|i| i.role == NodeRole::Leader).count();
+            }
+            Err(e) => warn!("⚠️  Failed to read cluster registry: {}", e),
+        }
         self.stats.read().await.clone()
     }
 
     /// 负载均衡选择实例
     pub async fn select_instance(&self, service_name: &str) -> Result<ServiceInstance> {
-        let instances = self.service_discovery.discover(service_name).await?;
+        self.select_instance_for(service_name, None).await
+    }
+
+    /// 负载均衡选择实例；一致性哈希策略以 session_id 为键
+    pub async fn select_instance_for(&self, service_name: &str, session_id: Option<&str>) -> Result<ServiceInstance> {
+        let instances = self.available_instances(service_name).await?;
 
         if instances.is_empty() {
             return Err(anyhow!("No available instances for service: {}", service_name));
         }
 
-        // TODO: 实现更智能的负载均衡算法
-        // 1. 加权轮询
-        // 2. 最少连接
-        // 3. 一致性哈希
+        let selected = match (self.load_balancer.strategy, session_id) {
+            (LoadBalanceStrategy::ConsistentHash, Some(session_id)) => self.consistent_hash(&instances, session_id),
+            (LoadBalanceStrategy::LeastConnections, _) => self.least_connections(&instances),
+            _ => self.weighted_round_robin(service_name, &instances),
+        };
+        debug!(
+            "⚖️  {:?} selected {} for {}",
+            self.load_balancer.strategy, selected.instance_id, service_name
+        );
+        Ok(selected)
+    }
+
+    /// 可参与负载均衡的实例：排除心跳超时与被临时排除的实例；有健康实例时不选降级实例
+    async fn available_instances(&self, service_name: &str) -> Result<Vec<ServiceInstance>> {
+        let stale_after = chrono::Duration::seconds(self.service_discovery.config.service_ttl_secs as i64);
+        let now = Utc::now();
+        let excluded: Vec<String> = {
+            let mut state = self.balancer.lock().unwrap_or_else(|e| e.into_inner());
+            let instant = Instant::now();
+            state.excluded.retain(|_, until| *until > instant);
+            state.excluded.keys().cloned().collect()
+        };
+
+        let mut instances: Vec<_> = self
+            .service_discovery
+            .discover(service_name)
+            .await?
+            .into_iter()
+            .filter(|instance| now - instance.last_heartbeat_at <= stale_after)
+            .filter(|instance| !excluded.contains(&instance.instance_id))
+            .collect();
+        if instances.iter().any(|instance| instance.status == NodeStatus::Healthy) {
+            instances.retain(|instance| instance.status == NodeStatus::Healthy);
+        }
+        instances.sort_by(|a, b| a.instance_id.cmp(&b.instance_id));
+        Ok(instances)
+    }
+
+    /// 平滑加权轮询（nginx 算法）：每轮所有实例加上自身权重，选出最大者后减去总权重
+    fn weighted_round_robin(&self, service_name: &str, instances: &[ServiceInstance]) -> ServiceInstance {
+        let mut state = self.balancer.lock().unwrap_or_else(|e| e.into_inner());
+        let current = state.round_robin.entry(service_name.to_string()).or_default();
+        current.retain(|id, _| instances.iter().any(|instance| &instance.instance_id == id));
+
+        let total: i64 = instances.iter().map(|instance| effective_weight(instance) as i64).sum();
+        let mut best: Option<(&ServiceInstance, i64)> = None;
+        for instance in instances {
+            let weight = current.entry(instance.instance_id.clone()).or_insert(0);
+            *weight += effective_weight(instance) as i64;
+            if best.is_none_or(|(_, best_weight)| *weight > best_weight) {
+                best = Some((instance, *weight));
+            }
+        }
+
+        let selected = best.map(|(instance, _)| instance).unwrap_or(&instances[0]);
+        if let Some(weight) = current.get_mut(&selected.instance_id) {
+            *weight -= total;
+        }
+        selected.clone()
+    }
+
+    /// 最少连接：比较 连接数 / 权重
+    fn least_connections(&self, instances: &[ServiceInstance]) -> ServiceInstance {
+        let connections = self.connections.lock().unwrap_or_else(|e| e.into_inner());
+        let load = |instance: &ServiceInstance| {
+            let active = connections.get(&instance.instance_id).copied().unwrap_or(0) as u64;
+            (active, effective_weight(instance) as u64)
+        };
+        instances
+            .iter()
+            // a/wa < b/wb  <=>  a*wb < b*wa
+            .min_by(|a, b| {
+                let ((ca, wa), (cb, wb)) = (load(a), load(b));
+                (ca * wb).cmp(&(cb * wa))
+            })
+            .unwrap_or(&instances[0])
+            .clone()
+    }
+
+    /// 一致性哈希：每个实例按权重映射若干虚拟节点，会话落到顺时针方向的第一个节点
+    fn consistent_hash(&self, instances: &[ServiceInstance], session_id: &str) -> ServiceInstance {
+        let mut ring: Vec<(u64, usize)> = Vec::new();
+        for (index, instance) in instances.iter().enumerate() {
+            let replicas = (self.load_balancer.virtual_nodes as u64 * effective_weight(instance) as u64 / 100).max(1);
+            for replica in 0..replicas {
+                ring.push((stable_hash(&format!("{}#{}", instance.instance_id, replica)), index));
+            }
+        }
+        ring.sort_unstable();
+
+        let point = stable_hash(session_id);
+        let position = ring.partition_point(|(hash, _)| *hash < point);
+        let (_, index) = ring[position % ring.len()];
+        instances[index].clone()
+    }
+
+    /// 开始一个到实例的连接（返回的守卫 drop 时结束计数）
+    pub fn begin_connection(&self, instance_id: &str) -> ConnectionGuard {
+        *self
+            .connections
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(instance_id.to_string())
+            .or_insert(0) += 1;
+        ConnectionGuard {
+            instance_id: instance_id.to_string(),
+            connections: self.connections.clone(),
+        }
+    }
+
+    /// 实例当前的活跃连接数
+    pub fn active_connections(&self, instance_id: &str) -> usize {
+        self.connections
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(instance_id)
+            .copied()
+            .unwrap_or(0)
+    }
 
-        // Placeholder: 简单随机选择
-        Ok(instances[0].clone())
+    /// 调用实例失败时临时排除（cooldown 为空时使用配置的默认时长）
+    pub fn mark_unavailable(&self, instance_id: &str, cooldown: Option<Duration>) {
+        let cooldown = cooldown.unwrap_or(Duration::from_secs(self.load_balancer.failure_cooldown_secs));
+        warn!("🚫 Excluding instance {} for {:?}", instance_id, cooldown);
+        self.balancer
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .excluded
+            .insert(instance_id.to_string(), Instant::now() + cooldown);
     }
+
+    /// 解除临时排除
+    pub fn mark_available(&self, instance_id: &str) {
+        self.balancer
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .excluded
+            .remove(instance_id);
+    }
+}
+
+/// 权重为 0 的实例仍保留最小份额，避免全部为 0 时无法选择
+fn effective_weight(instance: &ServiceInstance) -> u32 {
+    instance.weight.max(1)
+}
+
+/// 跨进程稳定的哈希（各节点对同一会话得出相同结果）
+fn stable_hash(text: &str) -> u64 {
+    let digest = ring::digest::digest(&ring::digest::SHA256, text.as_bytes());
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&digest.as_ref()[..8]);
+    u64::from_be_bytes(bytes)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn instance(id: &str) -> ServiceInstance {
+        ServiceInstance {
+            instance_id: id.to_string(),
+            service_name: "acsa".to_string(),
+            host: "localhost".to_string(),
+            port: 8080,
+            metadata: HashMap::new(),
+            status: NodeStatus::Healthy,
+            role: NodeRole::Follower,
+            registered_at: Utc::now(),
+            last_heartbeat_at: Utc::now(),
+            weight: 100,
+        }
+    }
+
     #[tokio::test]
     async fn test_distributed_lock() {
-        let lock = DistributedLock::new(
-            "test-lock".to_string(),
-            "owner-1".to_string(),
-            LockConfig::default(),
-        );
+        let store: Arc<dyn CoordinationStore> = Arc::new(MemoryCoordinationStore::new());
+        let config = LockConfig { retry_count: 1, ..Default::default() };
+        let lock = DistributedLock::new("test-lock".to_string(), "owner-1".to_string(), config.clone())
+            .with_stores(vec![store.clone()]);
+        let rival = DistributedLock::new("test-lock".to_string(), "owner-2".to_string(), config)
+            .with_stores(vec![store.clone()]);
 
         assert!(lock.acquire().await.unwrap());
         assert!(lock.is_acquired().await);
+        assert!(!rival.acquire().await.unwrap());
+
+        // 只能释放自己持有的锁
+        assert!(!store.delete_if_equals("test-lock", "owner-2").await.unwrap());
+        assert!(lock.renew().await.unwrap());
 
         lock.release().await.unwrap();
         assert!(!lock.is_acquired().await);
+        assert!(rival.acquire().await.unwrap());
+        assert!(!lock.renew().await.unwrap());
+
+        // 过期的键不可见
+        store.put("ephemeral", "1", Duration::from_millis(20)).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        assert_eq!(store.get("ephemeral").await.unwrap(), None);
+        assert!(store.set_if_absent("ephemeral", "2", Duration::from_secs(1)).await.unwrap());
     }
 
     #[tokio::test]
-    async fn test_service_registration() {
-        let instance = ServiceInstance {
-            instance_id: "test-1".to_string(),
-            service_name: "acsa".to_string(),
-            host: "localhost".to_string(),
-            port: 8080,
-            metadata: HashMap::new(),
-            status: NodeStatus::Healthy,
-            role: NodeRole::Follower,
-            registered_at: Utc::now(),
-            last_heartbeat_at: Utc::now(),
-            weight: 100,
+    async fn test_term_election_and_fencing() {
+        use crate::core::event_bus::EventBusConfig;
+
+        let store: Arc<dyn CoordinationStore> = Arc::new(MemoryCoordinationStore::new());
+        let bus = Arc::new(EventBus::new(EventBusConfig::default()));
+        let node = |id: &str| {
+            ServiceDiscovery::new(ServiceDiscoveryConfig::default(), instance(id))
+                .with_stores(vec![store.clone()])
+                .with_event_bus(bus.clone())
         };
+        let (a, b) = (node("node-a"), node("node-b"));
+
+        a.start_election().await.unwrap();
+        b.start_election().await.unwrap();
+        assert!(a.is_leader().await);
+        assert_eq!(a.fencing_token().await, Some(1));
+        assert!(!b.is_leader().await);
+        assert_eq!(b.get_leader_id().await.as_deref(), Some("node-a"));
+        assert!(a.renew_lease().await.unwrap());
+
+        // 租约在存储中丢失（如分区期间过期并被清理）：续约失败，立即卸任
+        store.delete("leader:acsa").await.unwrap();
+        assert!(!a.renew_lease().await.unwrap());
+        assert_eq!(a.get_role().await, NodeRole::Follower);
+        assert_eq!(a.fencing_token().await, None);
+
+        b.start_election().await.unwrap();
+        assert!(b.is_leader().await);
+        assert_eq!(b.current_term().await, 2);
+        // 旧任期的 token 被拒绝
+        assert!(!b.check_fencing_token(1).await.unwrap());
+        assert!(b.check_fencing_token(2).await.unwrap());
+
+        let transitions: Vec<_> = bus
+            .get_history(None)
+            .await
+            .into_iter()
+            .filter(|event| event.event_type == EventType::System(ROLE_CHANGED_EVENT.to_string()))
+            .filter(|event| event.data["instance_id"] == "node-a")
+            .map(|event| event.data["to"].as_str().unwrap_or_default().to_string())
+            .rev()
+            .collect();
+        assert_eq!(transitions, ["Candidate", "Leader", "Follower"]);
+    }
 
-        let discovery = ServiceDiscovery::new(
-            ServiceDiscoveryConfig::default(),
-            instance,
-        );
+    #[tokio::test]
+    async fn test_load_balancing() {
+        let store: Arc<dyn CoordinationStore> = Arc::new(MemoryCoordinationStore::new());
+        let mut nodes = Vec::new();
+        for (id, weight, status) in [
+            ("lb-a", 300, NodeStatus::Healthy),
+            ("lb-b", 100, NodeStatus::Healthy),
+            ("lb-c", 100, NodeStatus::Healthy),
+            ("lb-d", 100, NodeStatus::Degraded),
+        ] {
+            let mut node = instance(id);
+            node.weight = weight;
+            node.status = status;
+            let discovery = ServiceDiscovery::new(ServiceDiscoveryConfig::default(), node).with_stores(vec![store.clone()]);
+            discovery.register().await.unwrap();
+            nodes.push(Arc::new(discovery));
+        }
+        // 加权轮询：按 3:1:1 分配，降级实例不参与
+        let cluster = ClusterManager::new(nodes[0].clone());
+        let mut counts = HashMap::new();
+        for _ in 0..10 {
+            *counts.entry(cluster.select_instance("acsa").await.unwrap().instance_id).or_insert(0) += 1;
+        }
+        assert_eq!(counts, HashMap::from([("lb-a".to_string(), 6), ("lb-b".to_string(), 2), ("lb-c".to_string(), 2)]));
+
+        // 最少连接
+        let cluster = ClusterManager::new(nodes[0].clone()).with_load_balancer(LoadBalancerConfig {
+            strategy: LoadBalanceStrategy::LeastConnections,
+            ..Default::default()
+        });
+        let _a = cluster.begin_connection("lb-a");
+        let b = cluster.begin_connection("lb-b");
+        assert_eq!(cluster.select_instance("acsa").await.unwrap().instance_id, "lb-c");
+        let _c = cluster.begin_connection("lb-c");
+        drop(b);
+        assert_eq!(cluster.active_connections("lb-b"), 0);
+        assert_eq!(cluster.select_instance("acsa").await.unwrap().instance_id, "lb-b");
+
+        // 一致性哈希：同一会话固定实例，实例被排除后迁移
+        let cluster = ClusterManager::new(nodes[0].clone()).with_load_balancer(LoadBalancerConfig {
+            strategy: LoadBalanceStrategy::ConsistentHash,
+            ..Default::default()
+        });
+        let first = cluster.select_instance_for("acsa", Some("session-42")).await.unwrap().instance_id;
+        assert_eq!(cluster.select_instance_for("acsa", Some("session-42")).await.unwrap().instance_id, first);
+        cluster.mark_unavailable(&first, None);
+        let moved = cluster.select_instance_for("acsa", Some("session-42")).await.unwrap().instance_id;
+        assert_ne!(moved, first);
+        cluster.mark_available(&first);
+        assert_eq!(cluster.select_instance_for("acsa", Some("session-42")).await.unwrap().instance_id, first);
+    }
 
-        discovery.register().await.unwrap();
+    #[tokio::test]
+    async fn test_service_registration() {
+        let store: Arc<dyn CoordinationStore> = Arc::new(MemoryCoordinationStore::new());
+        let first = ServiceDiscovery::new(ServiceDiscoveryConfig::default(), instance("test-1"))
+            .with_stores(vec![store.clone()]);
+        let mut unhealthy = instance("test-2");
+        unhealthy.status = NodeStatus::Unhealthy;
+        let second = ServiceDiscovery::new(ServiceDiscoveryConfig::default(), unhealthy)
+            .with_stores(vec![store.clone()]);
+
+        first.register().await.unwrap();
+        second.register().await.unwrap();
+        let found = first.discover("acsa").await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].instance_id, "test-1");
+
+        first.start_election().await.unwrap();
+        second.start_election().await.unwrap();
+        assert!(first.is_leader().await);
+        assert_eq!(second.get_leader_id().await.as_deref(), Some("test-1"));
+
+        first.heartbeat().await.unwrap();
+        let cluster = ClusterManager::new(Arc::new(first));
+        let status = cluster.get_cluster_status().await;
+        assert_eq!((status.total_nodes, status.healthy_nodes, status.leader_count), (2, 1, 1));
+
+        cluster.service_discovery.resign_leadership().await.unwrap();
+        cluster.service_discovery.deregister().await.unwrap();
+        assert!(second.discover("acsa").await.unwrap().is_empty());
     }
 }
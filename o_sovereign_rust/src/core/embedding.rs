@@ -0,0 +1,529 @@
+// Embedding Providers - RAG 嵌入后端
+// 为 RagEngine 提供可替换的嵌入实现，按 EmbeddingModel 选择
+//
+// 核心功能：
+// 1. EmbeddingProvider trait：批量嵌入、向量维度、单批上限
+// 2. OpenAI text-embedding-3-small / large（/v1/embeddings）
+// 3. 本地模型：经 Ollama /api/embed 运行 all-MiniLM（ONNX 推理由本地服务负责，不出本机）
+// 4. Mock：基于词哈希的确定性向量，测试与无网络环境可用
+// 5. CachedEmbedder：按批上限分批调用，结果按内容哈希缓存到 cache_manager 的模型缓存目录
+
+use super::cache_manager::{CacheManager, CacheType};
+use super::error::{AcsaError, ErrorCode};
+use super::offline::{self, Capability};
+use super::rag_engine::EmbeddingModel;
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+
+/// OpenAI 嵌入接口地址
+const OPENAI_EMBEDDINGS_URL: &str = "https://api.openai.com/v1";
+
+/// 本地嵌入服务的默认地址（Ollama）
+const DEFAULT_LOCAL_EMBEDDING_URL: &str = "http://localhost:11434";
+
+/// 本地 all-MiniLM-L6-v2 在 Ollama 中的模型名
+const LOCAL_MINILM_MODEL: &str = "all-minilm";
+
+/// Mock 向量维度
+pub const MOCK_EMBEDDING_DIMENSIONS: usize = 256;
+
+/// 嵌入后端
+#[async_trait]
+pub trait EmbeddingProvider: Send + Sync {
+    /// 模型标识（缓存按模型隔离）
+    fn model_id(&self) -> String;
+
+    /// 向量维度
+    fn dimensions(&self) -> usize;
+
+    /// 单次请求最多嵌入的文本数
+    fn max_batch_size(&self) -> usize;
+
+    /// 批量嵌入，返回与输入一一对应的向量
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>>;
+}
+
+/// 按嵌入模型创建后端
+///
+/// OpenAI 模型需要 `OPENAI_API_KEY`；本地模型地址取 `ACSA_LOCAL_EMBEDDING_URL`，
+/// 其次是离线模式的本地端点；`Custom` 需通过 `RagEngine::with_embedder` 提供。
+pub fn create_embedding_provider(model: EmbeddingModel) -> Result<Arc<dyn EmbeddingProvider>> {
+    match model {
+        EmbeddingModel::OpenAISmall | EmbeddingModel::OpenAILarge => {
+            let api_key = std::env::var("OPENAI_API_KEY").map_err(|_| {
+                anyhow::Error::from(AcsaError::new(
+                    ErrorCode::ProviderApiKeyMissing,
+                    "OpenAI API key required for embeddings",
+                ))
+            })?;
+            Ok(Arc::new(OpenAIEmbeddingProvider::new(api_key, model)?))
+        }
+        EmbeddingModel::LocalMiniLM => {
+            let endpoint = std::env::var("ACSA_LOCAL_EMBEDDING_URL")
+                .ok()
+                .or_else(|| offline::current().map(|config| config.llm_endpoint))
+                .unwrap_or_else(|| DEFAULT_LOCAL_EMBEDDING_URL.to_string());
+            Ok(Arc::new(LocalEmbeddingProvider::new(&endpoint, LOCAL_MINILM_MODEL, 384)?))
+        }
+        EmbeddingModel::Mock => Ok(Arc::new(MockEmbeddingProvider::new(MOCK_EMBEDDING_DIMENSIONS))),
+        EmbeddingModel::Custom => Err(anyhow!("Custom embedding model requires RagEngine::with_embedder")),
+    }
+}
+
+// ===== OpenAI =====
+
+#[derive(Debug, Serialize)]
+struct OpenAIEmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIEmbeddingResponse {
+    data: Vec<OpenAIEmbedding>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIEmbedding {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+/// OpenAI 嵌入（text-embedding-3-small / large）
+pub struct OpenAIEmbeddingProvider {
+    client: Client,
+    api_key: String,
+    base_url: String,
+    model: String,
+    dimensions: usize,
+}
+
+impl OpenAIEmbeddingProvider {
+    pub fn new(api_key: String, model: EmbeddingModel) -> Result<Self> {
+        let (name, dimensions) = match model {
+            EmbeddingModel::OpenAISmall => ("text-embedding-3-small", 1536),
+            EmbeddingModel::OpenAILarge => ("text-embedding-3-large", 3072),
+            other => return Err(anyhow!("{:?} is not an OpenAI embedding model", other)),
+        };
+        Ok(Self {
+            client: Client::new(),
+            api_key,
+            base_url: OPENAI_EMBEDDINGS_URL.to_string(),
+            model: name.to_string(),
+            dimensions,
+        })
+    }
+
+    /// 使用 OpenAI 兼容的其他端点
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for OpenAIEmbeddingProvider {
+    fn model_id(&self) -> String {
+        format!("openai/{}", self.model)
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    fn max_batch_size(&self) -> usize {
+        128
+    }
+
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        if offline::is_offline() {
+            return Err(offline::unavailable(
+                Capability::Embeddings,
+                format!("{} is a cloud embedding model", self.model),
+            ));
+        }
+
+        let response = self
+            .client
+            .post(format!("{}/embeddings", self.base_url))
+            .bearer_auth(&self.api_key)
+            .json(&OpenAIEmbeddingRequest { model: &self.model, input: texts })
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await?;
+            return Err(AcsaError::new(
+                ErrorCode::from_http_status(status.as_u16()),
+                format!("OpenAI embeddings error ({}): {}", status, error_text),
+            )
+            .into());
+        }
+
+        let mut data = response.json::<OpenAIEmbeddingResponse>().await?.data;
+        data.sort_by_key(|item| item.index);
+        Ok(data.into_iter().map(|item| item.embedding).collect())
+    }
+}
+
+// ===== 本地（Ollama） =====
+
+#[derive(Debug, Serialize)]
+struct LocalEmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+#[derive(Debug, Deserialize)]
+struct LocalEmbeddingResponse {
+    #[serde(default)]
+    embeddings: Vec<Vec<f32>>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+/// 本地嵌入模型（Ollama /api/embed）
+pub struct LocalEmbeddingProvider {
+    client: Client,
+    base_url: String,
+    model: String,
+    dimensions: usize,
+}
+
+impl LocalEmbeddingProvider {
+    pub fn new(endpoint: &str, model: &str, dimensions: usize) -> Result<Self> {
+        offline::require_local(Capability::Embeddings, endpoint)?;
+
+        let trimmed = endpoint.trim_end_matches('/');
+        Ok(Self {
+            client: Client::new(),
+            base_url: trimmed.strip_suffix("/v1").unwrap_or(trimmed).to_string(),
+            model: model.to_string(),
+            dimensions,
+        })
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for LocalEmbeddingProvider {
+    fn model_id(&self) -> String {
+        format!("local/{}", self.model)
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    fn max_batch_size(&self) -> usize {
+        32
+    }
+
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let response = self
+            .client
+            .post(format!("{}/api/embed", self.base_url))
+            .json(&LocalEmbeddingRequest { model: &self.model, input: texts })
+            .send()
+            .await?;
+        let status = response.status();
+        let body: LocalEmbeddingResponse = response.json().await?;
+        if let Some(error) = body.error {
+            return Err(AcsaError::new(
+                ErrorCode::from_http_status(status.as_u16()),
+                format!("Local embedding error ({}): {}", status, error),
+            )
+            .into());
+        }
+        Ok(body.embeddings)
+    }
+}
+
+// ===== Mock =====
+
+/// 确定性 Mock 嵌入：词哈希到固定维度并归一化，共享词越多余弦相似度越高
+pub struct MockEmbeddingProvider {
+    dimensions: usize,
+}
+
+impl MockEmbeddingProvider {
+    pub fn new(dimensions: usize) -> Self {
+        Self { dimensions: dimensions.max(1) }
+    }
+
+    pub fn embed_text(&self, text: &str) -> Vec<f32> {
+        let mut vector = vec![0.0f32; self.dimensions];
+        let text = text.to_lowercase();
+        for token in text.split(|c: char| !c.is_alphanumeric()).filter(|t| !t.is_empty()) {
+            let hash = fnv1a(token.as_bytes());
+            let sign = if hash & (1 << 63) == 0 { 1.0 } else { -1.0 };
+            vector[(hash % self.dimensions as u64) as usize] += sign;
+        }
+        let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            vector.iter_mut().for_each(|v| *v /= norm);
+        }
+        vector
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for MockEmbeddingProvider {
+    fn model_id(&self) -> String {
+        format!("mock/{}", self.dimensions)
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    fn max_batch_size(&self) -> usize {
+        64
+    }
+
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        Ok(texts.iter().map(|text| self.embed_text(text)).collect())
+    }
+}
+
+/// 稳定的字符串哈希（不随进程或 Rust 版本变化）
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+// ===== 缓存与分批 =====
+
+/// 嵌入缓存统计
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmbeddingCacheStats {
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+    /// 实际发给后端的请求数
+    pub batches: u64,
+}
+
+/// 带磁盘缓存与分批的嵌入器
+///
+/// 缓存键为「模型 + 文本内容」的 SHA-256，文档未变化时重新索引不会再次调用后端。
+pub struct CachedEmbedder {
+    provider: Arc<dyn EmbeddingProvider>,
+    cache_dir: Option<PathBuf>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    batches: AtomicU64,
+}
+
+impl CachedEmbedder {
+    pub fn new(provider: Arc<dyn EmbeddingProvider>) -> Self {
+        Self {
+            provider,
+            cache_dir: None,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            batches: AtomicU64::new(0),
+        }
+    }
+
+    /// 缓存到 cache_manager 的模型输出目录（`model_cache/embeddings/<模型>`）
+    pub fn with_cache(mut self, cache: &CacheManager) -> Self {
+        let model_dir: String = self
+            .provider
+            .model_id()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '.' { c } else { '_' })
+            .collect();
+        let dir = cache.get_cache_dir(CacheType::ModelOutput).join("embeddings").join(model_dir);
+        info!("🗄️ Embedding cache: {}", dir.display());
+        self.cache_dir = Some(dir);
+        self
+    }
+
+    pub fn provider(&self) -> &Arc<dyn EmbeddingProvider> {
+        &self.provider
+    }
+
+    pub fn stats(&self) -> EmbeddingCacheStats {
+        EmbeddingCacheStats {
+            cache_hits: self.hits.load(Ordering::Relaxed),
+            cache_misses: self.misses.load(Ordering::Relaxed),
+            batches: self.batches.load(Ordering::Relaxed),
+        }
+    }
+
+    /// 嵌入一条文本
+    pub async fn embed_one(&self, text: &str) -> Result<Vec<f32>> {
+        self.embed(&[text.to_string()])
+            .await?
+            .pop()
+            .ok_or_else(|| anyhow!("Embedding provider returned no vector"))
+    }
+
+    /// 嵌入多条文本：先查缓存，未命中的按后端批上限分批请求
+    pub async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let mut vectors: Vec<Option<Vec<f32>>> = Vec::with_capacity(texts.len());
+        let mut missing = Vec::new();
+        for (i, text) in texts.iter().enumerate() {
+            let cached = self.read_cache(text).await;
+            if cached.is_none() {
+                missing.push(i);
+            }
+            vectors.push(cached);
+        }
+        self.hits.fetch_add((texts.len() - missing.len()) as u64, Ordering::Relaxed);
+        self.misses.fetch_add(missing.len() as u64, Ordering::Relaxed);
+
+        for batch in missing.chunks(self.provider.max_batch_size().max(1)) {
+            let inputs: Vec<String> = batch.iter().map(|&i| texts[i].clone()).collect();
+            let embedded = self
+                .provider
+                .embed_batch(&inputs)
+                .await
+                .with_context(|| format!("Failed to embed batch with {}", self.provider.model_id()))?;
+            self.batches.fetch_add(1, Ordering::Relaxed);
+            if embedded.len() != inputs.len() {
+                return Err(anyhow!(
+                    "Embedding provider returned {} vectors for {} inputs",
+                    embedded.len(),
+                    inputs.len()
+                ));
+            }
+            for (&i, vector) in batch.iter().zip(embedded) {
+                self.write_cache(&texts[i], &vector).await;
+                vectors[i] = Some(vector);
+            }
+        }
+        debug!("🧮 Embedded {} texts ({} from cache)", texts.len(), texts.len() - missing.len());
+
+        Ok(vectors.into_iter().map(|vector| vector.unwrap_or_default()).collect())
+    }
+
+    fn cache_path(&self, text: &str) -> Option<PathBuf> {
+        let dir = self.cache_dir.as_ref()?;
+        let key = format!("{}\0{}", self.provider.model_id(), text);
+        let digest = ring::digest::digest(&ring::digest::SHA256, key.as_bytes());
+        let hex: String = digest.as_ref().iter().map(|byte| format!("{:02x}", byte)).collect();
+        Some(dir.join(format!("{}.json", hex)))
+    }
+
+    async fn read_cache(&self, text: &str) -> Option<Vec<f32>> {
+        let path = self.cache_path(text)?;
+        let content = tokio::fs::read(&path).await.ok()?;
+        serde_json::from_slice(&content).ok()
+    }
+
+    /// 写缓存失败只记录警告，不影响嵌入结果
+    async fn write_cache(&self, text: &str, vector: &[f32]) {
+        let Some(path) = self.cache_path(text) else {
+            return;
+        };
+        let result = async {
+            if let Some(dir) = path.parent() {
+                tokio::fs::create_dir_all(dir).await?;
+            }
+            tokio::fs::write(&path, serde_json::to_vec(vector)?).await?;
+            anyhow::Ok(())
+        }
+        .await;
+        if let Err(e) = result {
+            warn!("⚠️  Failed to write embedding cache {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// 余弦相似度（维度不一致或零向量时为 0）
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f64 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let (mut dot, mut norm_a, mut norm_b) = (0.0f64, 0.0f64, 0.0f64);
+    for (x, y) in a.iter().zip(b) {
+        dot += (*x as f64) * (*y as f64);
+        norm_a += (*x as f64).powi(2);
+        norm_b += (*y as f64).powi(2);
+    }
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a.sqrt() * norm_b.sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 记录每批大小的 Mock 后端
+    struct CountingProvider {
+        inner: MockEmbeddingProvider,
+        batch_sizes: std::sync::Mutex<Vec<usize>>,
+    }
+
+    #[async_trait]
+    impl EmbeddingProvider for CountingProvider {
+        fn model_id(&self) -> String {
+            "counting".to_string()
+        }
+
+        fn dimensions(&self) -> usize {
+            self.inner.dimensions()
+        }
+
+        fn max_batch_size(&self) -> usize {
+            2
+        }
+
+        async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+            self.batch_sizes.lock().unwrap().push(texts.len());
+            self.inner.embed_batch(texts).await
+        }
+    }
+
+    #[test]
+    fn test_mock_embeddings_are_deterministic() {
+        let mock = MockEmbeddingProvider::new(64);
+        let a = mock.embed_text("Deploy the HTTP server");
+        assert_eq!(a, mock.embed_text("deploy the http server"));
+        assert!((cosine_similarity(&a, &a) - 1.0).abs() < 1e-6);
+
+        let related = cosine_similarity(&a, &mock.embed_text("deploy the server with nginx"));
+        let unrelated = cosine_similarity(&a, &mock.embed_text("quarterly revenue forecast"));
+        assert!(related > unrelated);
+    }
+
+    #[tokio::test]
+    async fn test_batches_and_disk_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = CacheManager::with_defaults(dir.path().to_path_buf()).unwrap();
+        let provider = Arc::new(CountingProvider {
+            inner: MockEmbeddingProvider::new(32),
+            batch_sizes: std::sync::Mutex::new(Vec::new()),
+        });
+        let texts: Vec<String> = ["alpha", "beta", "gamma", "delta", "epsilon"].iter().map(|t| t.to_string()).collect();
+
+        let embedder = CachedEmbedder::new(provider.clone()).with_cache(&cache);
+        let first = embedder.embed(&texts).await.unwrap();
+        assert_eq!(*provider.batch_sizes.lock().unwrap(), vec![2, 2, 1]);
+
+        // 新实例读取磁盘缓存：未变化的文本不再调用后端
+        let embedder = CachedEmbedder::new(provider.clone()).with_cache(&cache);
+        let mut changed = texts.clone();
+        changed[4] = "zeta".to_string();
+        let second = embedder.embed(&changed).await.unwrap();
+        assert_eq!(second[..4], first[..4]);
+        assert_eq!(*provider.batch_sizes.lock().unwrap(), vec![2, 2, 1, 1]);
+        assert_eq!(embedder.stats(), EmbeddingCacheStats { cache_hits: 4, cache_misses: 1, batches: 1 });
+    }
+
+    #[test]
+    fn test_create_embedding_provider() {
+        let mock = create_embedding_provider(EmbeddingModel::Mock).unwrap();
+        assert_eq!(mock.dimensions(), MOCK_EMBEDDING_DIMENSIONS);
+        assert!(create_embedding_provider(EmbeddingModel::Custom).is_err());
+    }
+}
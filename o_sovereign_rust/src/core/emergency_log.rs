@@ -2,28 +2,40 @@
 // 目标：避免断电导致聊天记录丢失
 //
 // 核心策略：
-// 1. 实时写入：每条消息立即持久化到磁盘
-// 2. 双重备份：内存buffer + 磁盘文件  
-// 3. 原子写入：使用write-tmp-rename模式避免损坏
-// 4. 自动恢复：启动时自动恢复未完成的对话
-// 5. 压缩存档：定期压缩历史日志节省空间
+// 1. 预写日志（WAL）：每条消息先追加写入磁盘，再进入内存buffer
+// 2. 双重备份：内存buffer + 磁盘文件
+// 3. 关键条目fsync：Error / Panic / Fatal 条目写入后立即落盘
+// 4. 崩溃捕获：panic hook 自动记录 panic 信息和调用栈
+// 5. 分段轮转：单段超过上限后切换新段，只保留最近 N 段
+// 6. 自动恢复：启动时按段顺序恢复未完成的对话
 
+use std::backtrace::Backtrace;
 use std::fs::{self, File, OpenOptions};
 use std::io::{BufWriter, Write as IoWrite};
-use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
-use std::collections::VecDeque;
-use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, TryLockError};
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use anyhow::{anyhow, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use tracing::{debug, error, info, warn};
 
+use super::data_security::DataSecurityManager;
+
+/// 正常关闭时写入的标记内容；会话末尾缺少该标记即视为异常退出
+pub const CLEAN_SHUTDOWN_MARKER: &str = "clean shutdown";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EmergencyLogConfig {
     pub log_dir: PathBuf,
     pub max_buffer_size: usize,
     pub flush_interval_secs: u64,
+    /// 单个日志段的最大大小（MB），超过后轮转到新段
     pub max_file_size_mb: u64,
+    /// 每个会话最多保留的日志段数量（0 = 不限制）
+    pub max_segments: usize,
+    /// 关键条目（Error/Panic/Fatal）写入后是否fsync
+    pub fsync_critical: bool,
     pub enable_compression: bool,
     pub retention_days: u32,
 }
@@ -35,6 +47,8 @@ impl Default for EmergencyLogConfig {
             max_buffer_size: 1000,
             flush_interval_secs: 5,
             max_file_size_mb: 100,
+            max_segments: 10,
+            fsync_critical: true,
             enable_compression: true,
             retention_days: 90,
         }
@@ -50,7 +64,7 @@ pub struct LogEntry {
     pub metadata: serde_json::Value,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum LogEntryType {
     UserInput,
     AssistantResponse,
@@ -58,16 +72,105 @@ pub enum LogEntryType {
     Error,
     ToolCall,
     ToolResult,
+    /// 进程panic（由panic hook自动记录）
+    Panic,
+    /// 致命错误（进程即将退出）
+    Fatal,
+}
+
+impl LogEntryType {
+    /// 是否为关键条目（需要立即fsync）
+    pub fn is_critical(&self) -> bool {
+        matches!(self, LogEntryType::Error | LogEntryType::Panic | LogEntryType::Fatal)
+    }
+}
+
+/// 会话摘要（用于事后排查）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSummary {
+    pub session_id: String,
+    pub entry_count: usize,
+    pub counts_by_type: HashMap<LogEntryType, usize>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub ended_at: Option<DateTime<Utc>>,
+    /// 出现Panic/Fatal，或末尾没有正常关闭标记
+    pub abnormal_exit: bool,
+}
+
+impl SessionSummary {
+    pub fn from_entries(session_id: &str, entries: &[LogEntry]) -> Self {
+        let mut counts_by_type = HashMap::new();
+        for entry in entries {
+            *counts_by_type.entry(entry.entry_type).or_insert(0) += 1;
+        }
+
+        let crashed = entries
+            .iter()
+            .any(|e| matches!(e.entry_type, LogEntryType::Panic | LogEntryType::Fatal));
+        let clean_shutdown = entries
+            .last()
+            .map(|e| e.entry_type == LogEntryType::SystemEvent && e.content == CLEAN_SHUTDOWN_MARKER)
+            .unwrap_or(false);
+
+        Self {
+            session_id: session_id.to_string(),
+            entry_count: entries.len(),
+            counts_by_type,
+            started_at: entries.first().map(|e| e.timestamp),
+            ended_at: entries.last().map(|e| e.timestamp),
+            abnormal_exit: !entries.is_empty() && (crashed || !clean_shutdown),
+        }
+    }
+}
+
+/// 支持包：脱敏后的日志 + 配置快照，用于提交问题报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SupportBundle {
+    pub generated_at: DateTime<Utc>,
+    pub version: String,
+    pub config_snapshot: serde_json::Value,
+    pub sessions: Vec<SessionSummary>,
+    pub entries: Vec<LogEntry>,
+}
+
+/// 当前打开的日志段
+struct SegmentWriter {
+    writer: BufWriter<File>,
+    index: usize,
+    bytes_written: u64,
 }
 
 pub struct EmergencyLogger {
     config: EmergencyLogConfig,
     buffer: Arc<Mutex<VecDeque<LogEntry>>>,
-    current_file: Arc<Mutex<Option<BufWriter<File>>>>,
+    current_file: Arc<Mutex<Option<SegmentWriter>>>,
     current_session: String,
     last_flush: Arc<Mutex<std::time::Instant>>,
 }
 
+/// 获取锁；即使锁已中毒（持有者panic）也继续使用，保证崩溃时仍能写日志
+fn lock_or_recover<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// 不等待的加锁：锁被占用（包括被当前线程占用）时返回 None
+fn try_lock_or_recover<T>(mutex: &Mutex<T>) -> Option<MutexGuard<'_, T>> {
+    match mutex.try_lock() {
+        Ok(guard) => Some(guard),
+        Err(TryLockError::Poisoned(poisoned)) => Some(poisoned.into_inner()),
+        Err(TryLockError::WouldBlock) => None,
+    }
+}
+
+/// 日志段文件路径：第0段为 `{session}.jsonl`，之后为 `{session}.{n}.jsonl`
+fn segment_path(log_dir: &Path, session_id: &str, index: usize) -> PathBuf {
+    if index == 0 {
+        log_dir.join(format!("{}.jsonl", session_id))
+    } else {
+        log_dir.join(format!("{}.{}.jsonl", session_id, index))
+    }
+}
+
 impl EmergencyLogger {
     pub fn new(config: EmergencyLogConfig) -> Result<Self> {
         fs::create_dir_all(&config.log_dir)?;
@@ -82,71 +185,311 @@ impl EmergencyLogger {
         })
     }
 
+    /// 当前会话ID
+    pub fn session_id(&self) -> &str {
+        &self.current_session
+    }
+
     pub fn log(&self, entry_type: LogEntryType, content: String, metadata: serde_json::Value) -> Result<()> {
-        let entry = LogEntry {
+        let entry = self.new_entry(entry_type, content, metadata);
+
+        // 预写：先落盘，再进入内存buffer
+        self.write_entry_to_disk(&mut lock_or_recover(&self.current_file), &entry)?;
+        self.push_to_buffer(lock_or_recover(&self.buffer), entry);
+        Ok(())
+    }
+
+    /// 不阻塞的写入（供 panic hook 使用）：日志文件被占用时不等待，把条目交还给调用方
+    ///
+    /// panic 可能发生在持有日志锁的线程上，阻塞加锁会导致死锁。
+    fn try_log(&self, entry_type: LogEntryType, content: String, metadata: serde_json::Value) -> Result<(), LogEntry> {
+        let entry = self.new_entry(entry_type, content, metadata);
+        let Some(mut file_guard) = try_lock_or_recover(&self.current_file) else {
+            return Err(entry);
+        };
+        if self.write_entry_to_disk(&mut file_guard, &entry).is_err() {
+            return Err(entry);
+        }
+        drop(file_guard);
+
+        if let Some(buffer) = try_lock_or_recover(&self.buffer) {
+            self.push_to_buffer(buffer, entry);
+        }
+        Ok(())
+    }
+
+    fn new_entry(&self, entry_type: LogEntryType, content: String, metadata: serde_json::Value) -> LogEntry {
+        LogEntry {
             timestamp: Utc::now(),
             session_id: self.current_session.clone(),
             entry_type,
             content,
             metadata,
-        };
+        }
+    }
 
-        {
-            let mut buffer = self.buffer.lock().unwrap();
-            buffer.push_back(entry.clone());
+    fn push_to_buffer(&self, mut buffer: MutexGuard<'_, VecDeque<LogEntry>>, entry: LogEntry) {
+        buffer.push_back(entry);
+        while buffer.len() > self.config.max_buffer_size {
+            buffer.pop_front();
         }
+    }
 
-        self.write_entry_to_disk(&entry)?;
-        Ok(())
+    /// 记录致命错误（包含错误链和调用栈），并立即fsync
+    pub fn log_fatal(&self, err: &anyhow::Error) -> Result<()> {
+        let chain: Vec<String> = err.chain().map(|cause| cause.to_string()).collect();
+        let metadata = serde_json::json!({
+            "error_chain": chain,
+            "backtrace": Backtrace::force_capture().to_string(),
+        });
+
+        error!("💀 Fatal error recorded: {}", err);
+        self.log(LogEntryType::Fatal, err.to_string(), metadata)
     }
 
-    fn write_entry_to_disk(&self, entry: &LogEntry) -> Result<()> {
-        let mut file_guard = self.current_file.lock().unwrap();
+    /// 安装panic hook：panic时自动写入Panic条目（含位置和调用栈），再交给原hook处理
+    ///
+    /// 日志文件被占用时不等待锁，改为把条目写到 stderr
+    pub fn install_panic_hook(self: &Arc<Self>) {
+        let logger = Arc::downgrade(self);
+        let previous = std::panic::take_hook();
 
-        if file_guard.is_none() {
-            let file_path = self.config.log_dir.join(format!("{}.jsonl", self.current_session));
-            let file = OpenOptions::new().create(true).append(true).open(&file_path)?;
-            *file_guard = Some(BufWriter::new(file));
+        std::panic::set_hook(Box::new(move |info| {
+            if let Some(logger) = logger.upgrade() {
+                let message = if let Some(s) = info.payload().downcast_ref::<&str>() {
+                    s.to_string()
+                } else if let Some(s) = info.payload().downcast_ref::<String>() {
+                    s.clone()
+                } else {
+                    "unknown panic payload".to_string()
+                };
+
+                let metadata = serde_json::json!({
+                    "location": info.location().map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column())),
+                    "thread": std::thread::current().name().unwrap_or("<unnamed>"),
+                    "backtrace": Backtrace::force_capture().to_string(),
+                });
+
+                if let Err(entry) = logger.try_log(LogEntryType::Panic, message, metadata) {
+                    eprintln!(
+                        "💀 Emergency log busy, panic entry: {}",
+                        serde_json::to_string(&entry).unwrap_or_else(|_| entry.content.clone())
+                    );
+                }
+            }
+
+            previous(info);
+        }));
+
+        info!("🪝 Emergency panic hook installed");
+    }
+
+    fn open_segment(&self, index: usize) -> Result<SegmentWriter> {
+        let file_path = segment_path(&self.config.log_dir, &self.current_session, index);
+        let file = OpenOptions::new().create(true).append(true).open(&file_path)?;
+        let bytes_written = file.metadata().map(|m| m.len()).unwrap_or(0);
+
+        debug!("📄 Opened emergency log segment: {:?}", file_path);
+        Ok(SegmentWriter {
+            writer: BufWriter::new(file),
+            index,
+            bytes_written,
+        })
+    }
+
+    /// 轮转到下一段，并删除超出保留数量的旧段
+    fn rotate(&self, current: SegmentWriter) -> Result<SegmentWriter> {
+        let mut current = current;
+        current.writer.flush()?;
+        current.writer.get_ref().sync_all()?;
+
+        let next_index = current.index + 1;
+        info!("🔁 Rotating emergency log to segment {}", next_index);
+
+        if self.config.max_segments > 0 && next_index >= self.config.max_segments {
+            let expired = next_index - self.config.max_segments;
+            let expired_path = segment_path(&self.config.log_dir, &self.current_session, expired);
+            if let Err(e) = fs::remove_file(&expired_path) {
+                warn!("⚠️  Failed to remove expired segment {:?}: {}", expired_path, e);
+            }
         }
 
-        if let Some(writer) = file_guard.as_mut() {
-            let json = serde_json::to_string(entry)?;
-            writeln!(writer, "{}", json)?;
-            writer.flush()?;
+        self.open_segment(next_index)
+    }
+
+    fn write_entry_to_disk(&self, file_guard: &mut Option<SegmentWriter>, entry: &LogEntry) -> Result<()> {
+        let json = serde_json::to_string(entry)?;
+        let line_len = json.len() as u64 + 1;
+        let max_bytes = self.config.max_file_size_mb * 1024 * 1024;
+
+        let mut segment = match file_guard.take() {
+            Some(segment) => segment,
+            None => self.open_segment(0)?,
+        };
+
+        if segment.bytes_written > 0 && segment.bytes_written + line_len > max_bytes {
+            segment = self.rotate(segment)?;
         }
 
-        Ok(())
+        let result = (|| -> Result<()> {
+            writeln!(segment.writer, "{}", json)?;
+            segment.writer.flush()?;
+            if self.config.fsync_critical && entry.entry_type.is_critical() {
+                segment.writer.get_ref().sync_data()?;
+            }
+            Ok(())
+        })();
+
+        segment.bytes_written += line_len;
+        *file_guard = Some(segment);
+        result
     }
 
     pub fn flush(&self) -> Result<()> {
-        let mut buffer = self.buffer.lock().unwrap();
+        let mut buffer = lock_or_recover(&self.buffer);
         buffer.clear();
+        *lock_or_recover(&self.last_flush) = std::time::Instant::now();
         Ok(())
     }
 
-    pub fn recover_session(&self, session_id: &str) -> Result<Vec<LogEntry>> {
-        let file_path = self.config.log_dir.join(format!("{}.jsonl", session_id));
-        if !file_path.exists() {
-            return Ok(Vec::new());
-        }
+    /// 内存buffer中最近的条目
+    pub fn recent_entries(&self, limit: usize) -> Vec<LogEntry> {
+        let buffer = lock_or_recover(&self.buffer);
+        let skip = buffer.len().saturating_sub(limit);
+        buffer.iter().skip(skip).cloned().collect()
+    }
 
-        let content = fs::read_to_string(&file_path)?;
+    pub fn recover_session(&self, session_id: &str) -> Result<Vec<LogEntry>> {
         let mut entries = Vec::new();
 
-        for line in content.lines() {
-            if let Ok(entry) = serde_json::from_str::<LogEntry>(line) {
-                entries.push(entry);
+        for file_path in self.session_segments(session_id)? {
+            let content = fs::read_to_string(&file_path)?;
+            for line in content.lines() {
+                if let Ok(entry) = serde_json::from_str::<LogEntry>(line) {
+                    entries.push(entry);
+                }
             }
         }
 
         Ok(entries)
     }
 
+    /// 列出日志目录中的所有会话（按会话ID排序）
+    pub fn list_sessions(&self) -> Result<Vec<SessionSummary>> {
+        let mut session_ids = BTreeSet::new();
+
+        for dir_entry in fs::read_dir(&self.config.log_dir)? {
+            let path = dir_entry?.path();
+            let Some(stem) = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .and_then(|n| n.strip_suffix(".jsonl"))
+            else {
+                continue;
+            };
+
+            // `{session}.{n}` 为后续分段，去掉段序号
+            let session_id = match stem.rsplit_once('.') {
+                Some((id, index)) if index.parse::<usize>().is_ok() => id,
+                _ => stem,
+            };
+            session_ids.insert(session_id.to_string());
+        }
+
+        session_ids
+            .into_iter()
+            .map(|id| self.summarize_session(&id))
+            .collect()
+    }
+
+    /// 汇总单个会话
+    pub fn summarize_session(&self, session_id: &str) -> Result<SessionSummary> {
+        let entries = self.recover_session(session_id)?;
+        if entries.is_empty() {
+            return Err(anyhow!("Emergency log session not found: {}", session_id));
+        }
+        Ok(SessionSummary::from_entries(session_id, &entries))
+    }
+
+    /// 打包支持包：会话条目的内容和元数据都会经过脱敏
+    pub fn export_support_bundle(
+        &self,
+        session_ids: &[String],
+        config_snapshot: serde_json::Value,
+    ) -> Result<SupportBundle> {
+        let sanitizer = DataSecurityManager::new();
+        let mut sessions = Vec::new();
+        let mut entries = Vec::new();
+
+        for session_id in session_ids {
+            let session_entries = self.recover_session(session_id)?;
+            sessions.push(SessionSummary::from_entries(session_id, &session_entries));
+
+            entries.extend(session_entries.into_iter().map(|mut entry| {
+                entry.content = sanitizer.sanitize(&entry.content, None);
+                sanitizer.sanitize_json(&mut entry.metadata, None);
+                entry
+            }));
+        }
+
+        let mut config_snapshot = config_snapshot;
+        sanitizer.sanitize_json(&mut config_snapshot, None);
+
+        info!("📦 Support bundle prepared: {} sessions, {} entries", sessions.len(), entries.len());
+        Ok(SupportBundle {
+            generated_at: Utc::now(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            config_snapshot,
+            sessions,
+            entries,
+        })
+    }
+
+    /// 会话的所有日志段（按段序号排序）
+    fn session_segments(&self, session_id: &str) -> Result<Vec<PathBuf>> {
+        let mut segments: Vec<(usize, PathBuf)> = Vec::new();
+        let prefix = format!("{}.", session_id);
+
+        for dir_entry in fs::read_dir(&self.config.log_dir)? {
+            let path = dir_entry?.path();
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            let Some(rest) = name.strip_prefix(&prefix).and_then(|r| r.strip_suffix("jsonl")) else {
+                continue;
+            };
+
+            let index = match rest {
+                "" => 0,
+                other => match other.strip_suffix('.').and_then(|n| n.parse().ok()) {
+                    Some(index) => index,
+                    None => continue,
+                },
+            };
+            segments.push((index, path));
+        }
+
+        segments.sort_by_key(|(index, _)| *index);
+        Ok(segments.into_iter().map(|(_, path)| path).collect())
+    }
+
     pub fn shutdown(&self) -> Result<()> {
         self.flush()?;
-        let mut file_guard = self.current_file.lock().unwrap();
-        if let Some(mut writer) = file_guard.take() {
-            writer.flush()?;
+
+        // 只有实际写过日志的会话才写正常关闭标记
+        let mut file_guard = lock_or_recover(&self.current_file);
+        if file_guard.is_some() {
+            let marker = self.new_entry(
+                LogEntryType::SystemEvent,
+                CLEAN_SHUTDOWN_MARKER.to_string(),
+                serde_json::json!({}),
+            );
+            self.write_entry_to_disk(&mut file_guard, &marker)?;
+        }
+
+        if let Some(mut segment) = file_guard.take() {
+            segment.writer.flush()?;
+            segment.writer.get_ref().sync_all()?;
         }
         Ok(())
     }
@@ -157,3 +500,112 @@ impl Drop for EmergencyLogger {
         let _ = self.shutdown();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn test_config(dir: &TempDir) -> EmergencyLogConfig {
+        EmergencyLogConfig {
+            log_dir: dir.path().to_path_buf(),
+            max_file_size_mb: 1,
+            max_segments: 2,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_write_ahead_and_recover() {
+        let dir = TempDir::new().unwrap();
+        let logger = EmergencyLogger::new(test_config(&dir)).unwrap();
+
+        logger.log(LogEntryType::UserInput, "hello".into(), serde_json::json!({})).unwrap();
+        logger.log(LogEntryType::Error, "boom".into(), serde_json::json!({})).unwrap();
+
+        let entries = logger.recover_session(logger.session_id()).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].entry_type, LogEntryType::Error);
+        assert_eq!(logger.recent_entries(1)[0].content, "boom");
+    }
+
+    #[test]
+    fn test_segment_rotation_is_bounded() {
+        let dir = TempDir::new().unwrap();
+        let logger = EmergencyLogger::new(test_config(&dir)).unwrap();
+        let big = "x".repeat(600 * 1024);
+
+        for _ in 0..4 {
+            logger.log(LogEntryType::ToolResult, big.clone(), serde_json::json!({})).unwrap();
+        }
+
+        let segments = logger.session_segments(logger.session_id()).unwrap();
+        assert_eq!(segments.len(), 2);
+        assert_eq!(logger.recover_session(logger.session_id()).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_panic_path_does_not_wait_for_busy_log() {
+        let dir = TempDir::new().unwrap();
+        let logger = EmergencyLogger::new(test_config(&dir)).unwrap();
+
+        // 模拟在持有日志文件锁的线程上 panic：不阻塞，条目交还给调用方
+        let held = logger.current_file.lock().unwrap();
+        let entry = logger.try_log(LogEntryType::Panic, "busy".into(), serde_json::json!({})).unwrap_err();
+        assert_eq!(entry.content, "busy");
+        drop(held);
+
+        logger.try_log(LogEntryType::Panic, "free".into(), serde_json::json!({})).unwrap();
+        let entries = logger.recover_session(logger.session_id()).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].content, "free");
+    }
+
+    #[test]
+    fn test_log_fatal_records_chain() {
+        let dir = TempDir::new().unwrap();
+        let logger = EmergencyLogger::new(test_config(&dir)).unwrap();
+
+        let err = anyhow::anyhow!("disk gone").context("saving session");
+        logger.log_fatal(&err).unwrap();
+
+        let entries = logger.recover_session(logger.session_id()).unwrap();
+        assert_eq!(entries[0].entry_type, LogEntryType::Fatal);
+        assert_eq!(entries[0].metadata["error_chain"][1], "disk gone");
+    }
+
+    #[test]
+    fn test_list_sessions_detects_abnormal_exit() {
+        let dir = TempDir::new().unwrap();
+        let logger = EmergencyLogger::new(test_config(&dir)).unwrap();
+        logger.log(LogEntryType::UserInput, "hi".into(), serde_json::json!({})).unwrap();
+
+        let summary = logger.summarize_session(logger.session_id()).unwrap();
+        assert!(summary.abnormal_exit);
+
+        logger.shutdown().unwrap();
+        let sessions = logger.list_sessions().unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert!(!sessions[0].abnormal_exit);
+        assert_eq!(sessions[0].counts_by_type[&LogEntryType::SystemEvent], 1);
+    }
+
+    #[test]
+    fn test_support_bundle_is_redacted() {
+        let dir = TempDir::new().unwrap();
+        let logger = EmergencyLogger::new(test_config(&dir)).unwrap();
+        logger
+            .log(
+                LogEntryType::UserInput,
+                "my password is hunter2".into(),
+                serde_json::json!({ "note": "API key is sk1234567890abcdef" }),
+            )
+            .unwrap();
+
+        let bundle = logger
+            .export_support_bundle(&[logger.session_id().to_string()], serde_json::json!({}))
+            .unwrap();
+        assert!(!bundle.entries[0].content.contains("hunter2"));
+        assert!(!bundle.entries[0].metadata["note"].as_str().unwrap().contains("sk1234567890"));
+    }
+}
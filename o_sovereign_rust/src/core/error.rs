@@ -1,9 +1,17 @@
 // Error Handling System with Error Codes
 // 容错日志系统 - 使用错误编号避免乱码
+//
+// 统一错误决策：
+// 1. is_retryable / is_user_error：路由器、API池、HTTP层使用同一套重试与上报判断
+// 2. Provider错误映射：HTTP状态码 / reqwest错误 / 错误消息 → ErrorCode
+// 3. 上下文链：操作、组件、关联ID逐层附加，序列化为统一的 ErrorReport
 
 use std::fmt;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use thiserror::Error;
 
+use super::sosa_api_pool::ApiErrorType;
+
 /// ACSA错误代码
 /// 格式: [模块代码][错误类型][序号]
 /// 例如: E1001 = Router模块(1) - 初始化错误(00) - 序号1
@@ -30,6 +38,12 @@ pub enum ErrorCode {
     ProviderNetworkError = 2004,
     /// E2005: 速率限制
     ProviderRateLimited = 2005,
+    /// E2006: 请求超时
+    ProviderTimeout = 2006,
+    /// E2007: 服务端错误（5xx / 过载）
+    ProviderServerError = 2007,
+    /// E2008: 请求无效（4xx，重试无效）
+    ProviderBadRequest = 2008,
 
     // === OpenCode模块 (3xxx) ===
     /// E3001: OpenCode未安装
@@ -72,6 +86,18 @@ pub enum ErrorCode {
     IoError = 9003,
     /// E9004: JSON序列化错误
     JsonError = 9004,
+    /// E9005: 离线模式下没有可用的本地实现
+    OfflineUnavailable = 9005,
+    /// E9006: 服务正在关停，不再接收新任务
+    ShuttingDown = 9006,
+    /// E9007: 运维已拉下全局熔断开关（维护模式），暂停新的执行
+    MaintenanceMode = 9007,
+    /// E9008: 当前身份无权访问该工作区
+    WorkspaceAccessDenied = 9008,
+    /// E9009: 预算已用完（工作区或 Provider）
+    BudgetExceeded = 9009,
+    /// E9010: 当前角色无权执行该操作
+    PermissionDenied = 9010,
 }
 
 impl fmt::Display for ErrorCode {
@@ -101,6 +127,9 @@ impl ErrorCode {
             ErrorCode::ProviderResponseParseFailed => "Failed to parse API response",
             ErrorCode::ProviderNetworkError => "Network connection error",
             ErrorCode::ProviderRateLimited => "Rate limit exceeded",
+            ErrorCode::ProviderTimeout => "Provider request timed out",
+            ErrorCode::ProviderServerError => "Provider service unavailable",
+            ErrorCode::ProviderBadRequest => "Provider rejected the request",
 
             // OpenCode
             ErrorCode::OpenCodeNotInstalled => "OpenCode not installed",
@@ -127,6 +156,12 @@ impl ErrorCode {
             ErrorCode::ConfigError => "Configuration error",
             ErrorCode::IoError => "I/O error",
             ErrorCode::JsonError => "JSON serialization error",
+            ErrorCode::OfflineUnavailable => "Not available in offline mode",
+            ErrorCode::ShuttingDown => "Service is shutting down",
+            ErrorCode::MaintenanceMode => "Service is in maintenance mode",
+            ErrorCode::WorkspaceAccessDenied => "Workspace access denied",
+            ErrorCode::BudgetExceeded => "Budget exceeded",
+            ErrorCode::PermissionDenied => "Permission denied",
         }
     }
 
@@ -145,6 +180,9 @@ impl ErrorCode {
             ErrorCode::ProviderResponseParseFailed => "解析API响应失败",
             ErrorCode::ProviderNetworkError => "网络连接错误",
             ErrorCode::ProviderRateLimited => "超过速率限制",
+            ErrorCode::ProviderTimeout => "API请求超时",
+            ErrorCode::ProviderServerError => "API服务不可用",
+            ErrorCode::ProviderBadRequest => "API拒绝了请求",
 
             // OpenCode
             ErrorCode::OpenCodeNotInstalled => "OpenCode未安装",
@@ -171,6 +209,12 @@ impl ErrorCode {
             ErrorCode::ConfigError => "配置错误",
             ErrorCode::IoError => "输入输出错误",
             ErrorCode::JsonError => "JSON序列化错误",
+            ErrorCode::OfflineUnavailable => "离线模式下不可用",
+            ErrorCode::ShuttingDown => "服务正在关停",
+            ErrorCode::MaintenanceMode => "服务处于维护模式",
+            ErrorCode::WorkspaceAccessDenied => "无权访问该工作区",
+            ErrorCode::BudgetExceeded => "预算已用完",
+            ErrorCode::PermissionDenied => "当前角色无权执行该操作",
         }
     }
 
@@ -191,22 +235,253 @@ impl ErrorCode {
             // 可恢复错误
             ErrorCode::ProviderApiCallFailed
             | ErrorCode::ProviderNetworkError
+            | ErrorCode::ProviderServerError
+            | ErrorCode::ProviderBadRequest
             | ErrorCode::OpenCodeExecutionFailed
             | ErrorCode::RouterMaxIterations => ErrorSeverity::Error,
 
             // 警告
             ErrorCode::ProviderRateLimited
+            | ErrorCode::ProviderTimeout
             | ErrorCode::RouterTimeout
-            | ErrorCode::OpenCodeTimeout => ErrorSeverity::Warning,
+            | ErrorCode::OpenCodeTimeout
+            | ErrorCode::ShuttingDown
+            | ErrorCode::MaintenanceMode
+            | ErrorCode::BudgetExceeded => ErrorSeverity::Warning,
 
             // 其他
             _ => ErrorSeverity::Error,
         }
     }
+
+    /// 所有错误代码
+    pub const ALL: [ErrorCode; 34] = [
+        ErrorCode::RouterInitFailed,
+        ErrorCode::RouterJarvisBlocked,
+        ErrorCode::RouterMaxIterations,
+        ErrorCode::RouterTimeout,
+        ErrorCode::ProviderApiKeyMissing,
+        ErrorCode::ProviderApiCallFailed,
+        ErrorCode::ProviderResponseParseFailed,
+        ErrorCode::ProviderNetworkError,
+        ErrorCode::ProviderRateLimited,
+        ErrorCode::ProviderTimeout,
+        ErrorCode::ProviderServerError,
+        ErrorCode::ProviderBadRequest,
+        ErrorCode::OpenCodeNotInstalled,
+        ErrorCode::OpenCodeExecutionFailed,
+        ErrorCode::OpenCodeFileOpFailed,
+        ErrorCode::OpenCodeTimeout,
+        ErrorCode::JarvisDangerousOp,
+        ErrorCode::JarvisBlacklistHit,
+        ErrorCode::JarvisHighRisk,
+        ErrorCode::ApiKeyNotFound,
+        ErrorCode::ApiKeyInvalid,
+        ErrorCode::ApiPersistenceFailed,
+        ErrorCode::I18nLoadFailed,
+        ErrorCode::I18nKeyNotFound,
+        ErrorCode::Unknown,
+        ErrorCode::ConfigError,
+        ErrorCode::IoError,
+        ErrorCode::JsonError,
+        ErrorCode::OfflineUnavailable,
+        ErrorCode::ShuttingDown,
+        ErrorCode::MaintenanceMode,
+        ErrorCode::WorkspaceAccessDenied,
+        ErrorCode::BudgetExceeded,
+        ErrorCode::PermissionDenied,
+    ];
+
+    /// 从 `E2005` / `2005` 形式解析
+    pub fn from_code(code: &str) -> Option<Self> {
+        let number: u32 = code.trim_start_matches('E').parse().ok()?;
+        Self::ALL.iter().copied().find(|c| *c as u32 == number)
+    }
+
+    /// 暂时性故障，重试可能成功
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            ErrorCode::ProviderApiCallFailed
+                | ErrorCode::ProviderNetworkError
+                | ErrorCode::ProviderRateLimited
+                | ErrorCode::ProviderTimeout
+                | ErrorCode::ProviderServerError
+                | ErrorCode::RouterTimeout
+                | ErrorCode::OpenCodeTimeout
+                | ErrorCode::ApiPersistenceFailed
+                | ErrorCode::ShuttingDown
+                | ErrorCode::MaintenanceMode
+        )
+    }
+
+    /// 由用户输入或用户配置导致（应提示用户修正，而不是上报为系统故障）
+    pub fn is_user_error(&self) -> bool {
+        matches!(
+            self,
+            ErrorCode::ProviderApiKeyMissing
+                | ErrorCode::ProviderBadRequest
+                | ErrorCode::ApiKeyNotFound
+                | ErrorCode::ApiKeyInvalid
+                | ErrorCode::ConfigError
+                | ErrorCode::OfflineUnavailable
+                | ErrorCode::RouterJarvisBlocked
+                | ErrorCode::JarvisDangerousOp
+                | ErrorCode::JarvisBlacklistHit
+                | ErrorCode::JarvisHighRisk
+                | ErrorCode::WorkspaceAccessDenied
+                | ErrorCode::BudgetExceeded
+                | ErrorCode::PermissionDenied
+        )
+    }
+
+    /// HTTP层使用的状态码
+    pub fn http_status(&self) -> u16 {
+        match self {
+            ErrorCode::ProviderApiKeyMissing | ErrorCode::ApiKeyInvalid => 401,
+            ErrorCode::RouterJarvisBlocked
+            | ErrorCode::JarvisDangerousOp
+            | ErrorCode::JarvisBlacklistHit
+            | ErrorCode::JarvisHighRisk
+            | ErrorCode::WorkspaceAccessDenied
+            | ErrorCode::PermissionDenied => 403,
+            ErrorCode::BudgetExceeded => 402,
+            ErrorCode::ApiKeyNotFound | ErrorCode::I18nKeyNotFound => 404,
+            ErrorCode::ProviderBadRequest | ErrorCode::ConfigError | ErrorCode::JsonError => 400,
+            ErrorCode::ProviderRateLimited => 429,
+            ErrorCode::ProviderTimeout | ErrorCode::RouterTimeout | ErrorCode::OpenCodeTimeout => 504,
+            ErrorCode::ProviderApiCallFailed
+            | ErrorCode::ProviderNetworkError
+            | ErrorCode::ProviderServerError
+            | ErrorCode::ProviderResponseParseFailed => 502,
+            ErrorCode::OpenCodeNotInstalled
+            | ErrorCode::OfflineUnavailable
+            | ErrorCode::ShuttingDown
+            | ErrorCode::MaintenanceMode => 503,
+            _ => 500,
+        }
+    }
+
+    /// Provider 返回的 HTTP 状态码 → 错误代码
+    pub fn from_http_status(status: u16) -> Self {
+        match status {
+            401 | 403 => ErrorCode::ApiKeyInvalid,
+            408 => ErrorCode::ProviderTimeout,
+            429 => ErrorCode::ProviderRateLimited,
+            400..=499 => ErrorCode::ProviderBadRequest,
+            500..=599 => ErrorCode::ProviderServerError,
+            _ => ErrorCode::ProviderApiCallFailed,
+        }
+    }
+
+    /// Provider 调用错误 → 错误代码（优先识别 reqwest 错误，否则按错误消息归类）
+    ///
+    /// 额度耗尽（insufficient_quota 或账单/余额不足）归为 BudgetExceeded：重试无效，需要充值或调整额度；
+    /// 按分钟/按天的配额限流（如 Gemini RESOURCE_EXHAUSTED）仍归为可重试的 ProviderRateLimited
+    pub fn from_provider_error(error: &anyhow::Error) -> Self {
+        if let Some(acsa) = error.downcast_ref::<AcsaError>() {
+            if let Some(code) = acsa.code() {
+                return code;
+            }
+        }
+
+        let message = format!("{:#}", error).to_lowercase();
+        let has = |needles: &[&str]| needles.iter().any(|n| message.contains(n));
+        if has(&["insufficient_quota", "credit balance is too low", "billing hard limit", "billing details"]) {
+            return ErrorCode::BudgetExceeded;
+        }
+
+        if let Some(http) = error.chain().find_map(|e| e.downcast_ref::<reqwest::Error>()) {
+            if http.is_timeout() {
+                return ErrorCode::ProviderTimeout;
+            }
+            if http.is_connect() {
+                return ErrorCode::ProviderNetworkError;
+            }
+            if let Some(status) = http.status() {
+                return Self::from_http_status(status.as_u16());
+            }
+            if http.is_decode() {
+                return ErrorCode::ProviderResponseParseFailed;
+            }
+        }
+
+        if let Some(status) = status_in_message(&message) {
+            return Self::from_http_status(status);
+        }
+
+        if has(&["rate limit", "too many requests", "resource_exhausted", "quota"]) {
+            ErrorCode::ProviderRateLimited
+        } else if has(&["invalid api key", "incorrect api key", "unauthorized", "forbidden"]) {
+            ErrorCode::ApiKeyInvalid
+        } else if has(&["api key not set", "api key missing", "missing api key"]) {
+            ErrorCode::ProviderApiKeyMissing
+        } else if has(&["timed out", "timeout", "deadline exceeded"]) {
+            ErrorCode::ProviderTimeout
+        } else if has(&["overloaded", "service unavailable", "bad gateway", "internal server error"]) {
+            ErrorCode::ProviderServerError
+        } else if has(&["connection", "dns", "network"]) {
+            ErrorCode::ProviderNetworkError
+        } else if has(&["parse", "deserialize", "invalid json"]) {
+            ErrorCode::ProviderResponseParseFailed
+        } else {
+            ErrorCode::ProviderApiCallFailed
+        }
+    }
+}
+
+/// 错误消息中的 HTTP 状态码：须为独立的三位数，且前面是 "status" / "http" 等标签或后面跟着原因短语
+///
+/// 不会把 "gpt-4-0429"、"1500ms" 或 "max_tokens 500" 中的数字当成状态码
+fn status_in_message(message: &str) -> Option<u16> {
+    const LABELS: &[&str] = &["status", "http", "code", "error", "returned"];
+    const REASONS: &[&str] = &[
+        "too", "unauthorized", "forbidden", "not", "bad", "payment", "request", "conflict", "unprocessable", "internal",
+        "service", "gateway",
+    ];
+    let words: Vec<&str> = message
+        .split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .filter(|word| !word.is_empty())
+        .collect();
+    words.iter().enumerate().find_map(|(i, word)| {
+        let status = word.parse::<u16>().ok().filter(|status| word.len() == 3 && (400..=599).contains(status))?;
+        let labelled = i > 0 && LABELS.contains(&words[i - 1]);
+        let explained = words.get(i + 1).is_some_and(|next| REASONS.contains(next));
+        (labelled || explained).then_some(status)
+    })
+}
+
+impl From<ApiErrorType> for ErrorCode {
+    fn from(error: ApiErrorType) -> Self {
+        match error {
+            ApiErrorType::RateLimit => ErrorCode::ProviderRateLimited,
+            ApiErrorType::Timeout => ErrorCode::ProviderTimeout,
+            ApiErrorType::NetworkError => ErrorCode::ProviderNetworkError,
+            ApiErrorType::InvalidKey => ErrorCode::ApiKeyInvalid,
+            ApiErrorType::ServiceUnavailable | ApiErrorType::ModelOverload => ErrorCode::ProviderServerError,
+            ApiErrorType::Unknown => ErrorCode::ProviderApiCallFailed,
+        }
+    }
+}
+
+// 序列化为 "E2005" 形式，与日志和用户消息中的编号一致
+impl Serialize for ErrorCode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.code())
+    }
+}
+
+impl<'de> Deserialize<'de> for ErrorCode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let code = String::deserialize(deserializer)?;
+        ErrorCode::from_code(&code)
+            .ok_or_else(|| serde::de::Error::custom(format!("unknown error code: {}", code)))
+    }
 }
 
 /// 错误严重级别
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
 pub enum ErrorSeverity {
     /// 致命 - 系统无法继续运行
     Fatal,
@@ -229,6 +504,62 @@ impl fmt::Display for ErrorSeverity {
     }
 }
 
+/// 错误上下文帧（错误向上传播时逐层附加）
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorFrame {
+    /// 组件（如 router / sosa_api_pool / http）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub component: Option<String>,
+    /// 操作（如 call_moss / select_endpoint）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operation: Option<String>,
+    /// 关联ID（请求ID / 任务ID）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+}
+
+impl ErrorFrame {
+    pub fn new(component: impl Into<String>, operation: impl Into<String>) -> Self {
+        Self {
+            component: Some(component.into()),
+            operation: Some(operation.into()),
+            correlation_id: None,
+        }
+    }
+
+    pub fn with_correlation_id(mut self, id: impl Into<String>) -> Self {
+        self.correlation_id = Some(id.into());
+        self
+    }
+}
+
+impl fmt::Display for ErrorFrame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let component = self.component.as_deref().unwrap_or("?");
+        let operation = self.operation.as_deref().unwrap_or("?");
+        write!(f, "{}::{}", component, operation)?;
+        if let Some(id) = &self.correlation_id {
+            write!(f, " [{}]", id)?;
+        }
+        Ok(())
+    }
+}
+
+/// 错误报告（统一的序列化格式，用于日志、HTTP响应和上报）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorReport {
+    pub code: ErrorCode,
+    pub severity: ErrorSeverity,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+    pub retryable: bool,
+    pub user_error: bool,
+    /// 上下文链（由内到外）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chain: Vec<ErrorFrame>,
+}
+
 /// ACSA错误类型
 #[derive(Error, Debug)]
 pub enum AcsaError {
@@ -238,6 +569,8 @@ pub enum AcsaError {
         severity: ErrorSeverity,
         message: String,
         context: Option<String>,
+        /// 上下文链（由内到外）
+        chain: Vec<ErrorFrame>,
     },
 
     #[error("Anyhow error: {0}")]
@@ -259,6 +592,7 @@ impl AcsaError {
             severity,
             message: message.into(),
             context: None,
+            chain: Vec::new(),
         }
     }
 
@@ -270,6 +604,117 @@ impl AcsaError {
             severity,
             message: message.into(),
             context: Some(context.into()),
+            chain: Vec::new(),
+        }
+    }
+
+    /// 从 Provider 调用错误创建（自动映射错误代码）
+    pub fn from_provider(error: anyhow::Error) -> Self {
+        match error.downcast::<AcsaError>() {
+            Ok(acsa) => acsa,
+            Err(error) => Self::new(ErrorCode::from_provider_error(&error), format!("{:#}", error)),
+        }
+    }
+
+    /// 附加上下文帧（非 Coded 错误先归一化为对应的错误代码）
+    pub fn frame(self, frame: ErrorFrame) -> Self {
+        match self.into_coded() {
+            AcsaError::Coded { code, severity, message, context, mut chain } => {
+                chain.push(frame);
+                AcsaError::Coded { code, severity, message, context, chain }
+            }
+            other => other,
+        }
+    }
+
+    /// 附加组件和操作
+    pub fn in_operation(self, component: impl Into<String>, operation: impl Into<String>) -> Self {
+        self.frame(ErrorFrame::new(component, operation))
+    }
+
+    /// 为最外层上下文帧设置关联ID（没有上下文帧时新建一帧）
+    pub fn with_correlation_id(self, id: impl Into<String>) -> Self {
+        match self.into_coded() {
+            AcsaError::Coded { code, severity, message, context, mut chain } => {
+                match chain.last_mut() {
+                    Some(frame) => frame.correlation_id = Some(id.into()),
+                    None => chain.push(ErrorFrame {
+                        correlation_id: Some(id.into()),
+                        ..Default::default()
+                    }),
+                }
+                AcsaError::Coded { code, severity, message, context, chain }
+            }
+            other => other,
+        }
+    }
+
+    fn into_coded(self) -> Self {
+        let (code, message) = match self {
+            coded @ AcsaError::Coded { .. } => return coded,
+            AcsaError::Anyhow(e) => (ErrorCode::Unknown, format!("{:#}", e)),
+            AcsaError::Io(e) => (ErrorCode::IoError, e.to_string()),
+            AcsaError::Json(e) => (ErrorCode::JsonError, e.to_string()),
+        };
+        Self::new(code, message)
+    }
+
+    /// 上下文链
+    pub fn chain(&self) -> &[ErrorFrame] {
+        match self {
+            AcsaError::Coded { chain, .. } => chain,
+            _ => &[],
+        }
+    }
+
+    /// 归一化的错误代码（非 Coded 错误按类型映射）
+    pub fn effective_code(&self) -> ErrorCode {
+        match self {
+            AcsaError::Coded { code, .. } => *code,
+            AcsaError::Io(_) => ErrorCode::IoError,
+            AcsaError::Json(_) => ErrorCode::JsonError,
+            AcsaError::Anyhow(e) => e
+                .downcast_ref::<AcsaError>()
+                .map_or(ErrorCode::Unknown, |inner| inner.effective_code()),
+        }
+    }
+
+    /// 是否值得重试
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            AcsaError::Io(e) => matches!(
+                e.kind(),
+                std::io::ErrorKind::TimedOut
+                    | std::io::ErrorKind::Interrupted
+                    | std::io::ErrorKind::WouldBlock
+                    | std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+            ),
+            _ => self.effective_code().is_retryable(),
+        }
+    }
+
+    /// 是否为用户侧错误
+    pub fn is_user_error(&self) -> bool {
+        self.effective_code().is_user_error()
+    }
+
+    /// 统一的错误报告
+    pub fn to_report(&self) -> ErrorReport {
+        let (message, context) = match self {
+            AcsaError::Coded { message, context, .. } => (message.clone(), context.clone()),
+            other => (other.to_string(), None),
+        };
+        let code = self.effective_code();
+
+        ErrorReport {
+            code,
+            severity: self.severity(),
+            message,
+            context,
+            retryable: self.is_retryable(),
+            user_error: code.is_user_error(),
+            chain: self.chain().to_vec(),
         }
     }
 
@@ -292,7 +737,7 @@ impl AcsaError {
     /// 格式化为用户友好的错误消息
     pub fn user_message(&self, lang: &str) -> String {
         match self {
-            AcsaError::Coded { code, message, context, .. } => {
+            AcsaError::Coded { code, message, context, chain, .. } => {
                 let desc = match lang {
                     "zh" | "zh-CN" => code.description_zh(),
                     _ => code.description_en(),
@@ -304,6 +749,10 @@ impl AcsaError {
                     msg.push_str(&format!("\n  Context: {}", ctx));
                 }
 
+                for frame in chain {
+                    msg.push_str(&format!("\n  at {}", frame));
+                }
+
                 msg
             }
             AcsaError::Anyhow(e) => format!("Error: {}", e),
@@ -396,17 +845,76 @@ mod tests {
         assert!(msg_zh.contains("API调用失败"));
     }
 
+    #[test]
+    fn test_provider_error_mapping() {
+        assert_eq!(ErrorCode::from_http_status(429), ErrorCode::ProviderRateLimited);
+        assert_eq!(ErrorCode::from_http_status(401), ErrorCode::ApiKeyInvalid);
+        assert_eq!(ErrorCode::from_http_status(422), ErrorCode::ProviderBadRequest);
+        assert_eq!(ErrorCode::from_http_status(503), ErrorCode::ProviderServerError);
+
+        let err = anyhow::anyhow!("OpenAI returned 429 Too Many Requests");
+        assert_eq!(ErrorCode::from_provider_error(&err), ErrorCode::ProviderRateLimited);
+        let err = anyhow::anyhow!("Incorrect API key provided");
+        assert_eq!(ErrorCode::from_provider_error(&err), ErrorCode::ApiKeyInvalid);
+        let err = anyhow::anyhow!("Gemini API error (status 503): model is overloaded");
+        assert_eq!(ErrorCode::from_provider_error(&err), ErrorCode::ProviderServerError);
+        // 模型名、耗时与 Token 数中的数字不是状态码
+        let err = anyhow::anyhow!("model gpt-4-0429 rejected prompt after 1500ms (max_tokens 500)");
+        assert_eq!(ErrorCode::from_provider_error(&err), ErrorCode::ProviderApiCallFailed);
+        // 额度耗尽不可重试
+        let err = anyhow::anyhow!("status 429: You exceeded your current quota (insufficient_quota)");
+        assert_eq!(ErrorCode::from_provider_error(&err), ErrorCode::BudgetExceeded);
+        assert!(!ErrorCode::BudgetExceeded.is_retryable());
+        // 按分钟配额限流可重试
+        let err = anyhow::anyhow!(
+            "Gemini API error (status 429): RESOURCE_EXHAUSTED: Quota exceeded for quota metric \
+             'Generate Content API requests per minute'"
+        );
+        assert_eq!(ErrorCode::from_provider_error(&err), ErrorCode::ProviderRateLimited);
+        let err = anyhow::anyhow!("RESOURCE_EXHAUSTED: Quota exceeded for quota metric 'requests per minute'");
+        assert_eq!(ErrorCode::from_provider_error(&err), ErrorCode::ProviderRateLimited);
+        assert_eq!(ErrorCode::from(ApiErrorType::ModelOverload), ErrorCode::ProviderServerError);
+
+        assert!(ErrorCode::ProviderRateLimited.is_retryable());
+        assert!(!ErrorCode::ProviderBadRequest.is_retryable());
+        assert!(ErrorCode::ProviderBadRequest.is_user_error());
+        assert!(!ErrorCode::ProviderServerError.is_user_error());
+    }
+
+    #[test]
+    fn test_context_chain_report() {
+        let err = AcsaError::from_provider(anyhow::anyhow!("request timed out after 30s"))
+            .in_operation("providers", "generate")
+            .in_operation("router", "call_moss")
+            .with_correlation_id("req-42");
+
+        assert_eq!(err.code(), Some(ErrorCode::ProviderTimeout));
+        assert!(err.is_retryable());
+        assert_eq!(err.chain().len(), 2);
+        assert!(err.user_message("en").contains("at router::call_moss [req-42]"));
+
+        let json = serde_json::to_value(err.to_report()).unwrap();
+        assert_eq!(json["code"], "E2006");
+        assert_eq!(json["severity"], "WARNING");
+        assert_eq!(json["retryable"], true);
+        assert_eq!(json["chain"][0]["component"], "providers");
+        assert_eq!(json["chain"][1]["correlation_id"], "req-42");
+
+        let report: ErrorReport = serde_json::from_value(json).unwrap();
+        assert_eq!(report.code, ErrorCode::ProviderTimeout);
+
+        // 非 Coded 错误附加上下文时归一化
+        let io = AcsaError::from(std::io::Error::new(std::io::ErrorKind::TimedOut, "slow disk"));
+        assert!(io.is_retryable());
+        let io = io.in_operation("api_manager", "persist");
+        assert_eq!(io.code(), Some(ErrorCode::IoError));
+    }
+
     #[test]
     fn test_error_code_uniqueness() {
         use std::collections::HashSet;
 
-        let codes = vec![
-            ErrorCode::RouterInitFailed,
-            ErrorCode::RouterJarvisBlocked,
-            ErrorCode::ProviderApiKeyMissing,
-            ErrorCode::JarvisDangerousOp,
-            ErrorCode::OpenCodeNotInstalled,
-        ];
+        let codes = ErrorCode::ALL.to_vec();
 
         let unique_codes: HashSet<_> = codes.iter().map(|c| *c as u32).collect();
         assert_eq!(unique_codes.len(), codes.len(), "Error codes must be unique");
@@ -0,0 +1,236 @@
+// Error Presenter - 面向用户的错误呈现层
+// 把 ErrorCode / ErrorSeverity 渲染为本地化文本
+//
+// 核心功能：
+// 1. 本地化标题与严重级别（经 I18n，缺失时回退到 ErrorCode 自带描述）
+// 2. 修复提示：如 "设置 OPENAI_API_KEY 或使用 --mock"
+// 3. 运维细节与用户文本分离：原始消息、上下文链只写日志，用户只看到安全的文本和错误编号
+
+use std::fmt;
+
+use chrono::Utc;
+
+use serde::{Deserialize, Serialize};
+use tracing::{error, warn};
+
+use super::error::{AcsaError, ErrorCode, ErrorSeverity};
+use super::i18n::{I18n, Language, TranslationKey};
+
+/// 呈现给用户的错误
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PresentedError {
+    /// 错误编号（如 E2001）
+    pub code: String,
+    /// 本地化的严重级别
+    pub severity: String,
+    /// 本地化标题
+    pub title: String,
+    /// 用户可见的说明（用户错误为原始消息，系统错误为通用文本）
+    pub message: String,
+    /// 修复提示
+    pub hint: Option<String>,
+    pub retryable: bool,
+    /// 与运维日志对应的编号
+    pub reference: String,
+    /// 本地化的 "错误编号" 标签
+    reference_label: String,
+}
+
+impl fmt::Display for PresentedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "❌ [{}] {} ({})", self.code, self.title, self.severity)?;
+        writeln!(f, "   {}", self.message)?;
+        if let Some(hint) = &self.hint {
+            writeln!(f, "   💡 {}", hint)?;
+        }
+        write!(f, "   {}: {}", self.reference_label, self.reference)
+    }
+}
+
+/// 错误呈现器
+pub struct ErrorPresenter {
+    i18n: I18n,
+}
+
+impl ErrorPresenter {
+    pub fn new(language: Language) -> Self {
+        Self::with_i18n(I18n::new(language))
+    }
+
+    pub fn with_i18n(i18n: I18n) -> Self {
+        Self { i18n }
+    }
+
+    /// 按 `ACSA_LANG` / `LANG` 选择语言，默认英文
+    pub fn from_env() -> Self {
+        let language = ["ACSA_LANG", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find_map(|value| {
+                // 兼容 zh_CN.UTF-8 这类 locale 写法
+                let locale = value.split('.').next().unwrap_or_default().replace('_', "-");
+                Language::from_code(&locale)
+                    .or_else(|| Language::from_code(locale.split('-').next().unwrap_or_default()))
+            })
+            .unwrap_or(Language::EnglishUS);
+        Self::new(language)
+    }
+
+    pub fn language(&self) -> Language {
+        self.i18n.current_language()
+    }
+
+    /// 任意错误 → 呈现结果（非 AcsaError 按 Provider 错误归类）
+    pub fn present_anyhow(&self, error: &anyhow::Error) -> PresentedError {
+        match error.downcast_ref::<AcsaError>() {
+            Some(acsa) => self.present(acsa),
+            None => self.present(&AcsaError::new(
+                ErrorCode::from_provider_error(error),
+                format!("{:#}", error),
+            )),
+        }
+    }
+
+    /// 呈现错误：完整细节写入日志，返回用户可见的文本
+    pub fn present(&self, error: &AcsaError) -> PresentedError {
+        let report = error.to_report();
+        let code = report.code;
+        let reference = report
+            .chain
+            .iter()
+            .rev()
+            .find_map(|frame| frame.correlation_id.clone())
+            .unwrap_or_else(|| format!("err_{}", Utc::now().timestamp_millis()));
+
+        // 运维细节：原始消息、上下文、上下文链
+        let details = serde_json::to_string(&report).unwrap_or_default();
+        match report.severity {
+            ErrorSeverity::Warning => warn!("⚠️  [{}] ref={} {}", code.code(), reference, details),
+            _ => error!("❌ [{}] ref={} {}", code.code(), reference, details),
+        }
+
+        let message = if report.user_error {
+            report.message.clone()
+        } else if report.retryable {
+            self.text("error.retry_later")
+        } else {
+            self.text("error.generic_failure")
+        };
+
+        PresentedError {
+            code: code.code(),
+            severity: self.severity_label(report.severity),
+            title: self.title(code),
+            message,
+            hint: Self::hint_key(code).map(|key| self.text(key)),
+            retryable: report.retryable,
+            reference,
+            reference_label: self.text("error.reference"),
+        }
+    }
+
+    fn text(&self, key: &str) -> String {
+        self.i18n.t(&TranslationKey::Custom(key.to_string()))
+    }
+
+    /// 标题：优先使用语言包中的 `error.code.Exxxx`，否则回退到 ErrorCode 自带描述
+    fn title(&self, code: ErrorCode) -> String {
+        let key = format!("error.code.{}", code.code());
+        let text = self.text(&key);
+        if text != key {
+            return text;
+        }
+
+        match self.language() {
+            Language::ChineseSimplified => code.description_zh().to_string(),
+            _ => code.description_en().to_string(),
+        }
+    }
+
+    fn severity_label(&self, severity: ErrorSeverity) -> String {
+        let key = match severity {
+            ErrorSeverity::Fatal => "error.severity.fatal",
+            ErrorSeverity::Critical => "error.severity.critical",
+            ErrorSeverity::Error => "error.severity.error",
+            ErrorSeverity::Warning => "error.severity.warning",
+        };
+        self.text(key)
+    }
+
+    fn hint_key(code: ErrorCode) -> Option<&'static str> {
+        let key = match code {
+            ErrorCode::ProviderApiKeyMissing => "error.hint.api_key_missing",
+            ErrorCode::ApiKeyInvalid | ErrorCode::ApiKeyNotFound => "error.hint.api_key_invalid",
+            ErrorCode::ProviderRateLimited => "error.hint.rate_limited",
+            ErrorCode::ProviderTimeout | ErrorCode::RouterTimeout | ErrorCode::OpenCodeTimeout => {
+                "error.hint.timeout"
+            }
+            ErrorCode::ProviderServerError | ErrorCode::ProviderApiCallFailed => "error.hint.server_error",
+            ErrorCode::ProviderNetworkError => "error.hint.network",
+            ErrorCode::ProviderBadRequest => "error.hint.bad_request",
+            ErrorCode::RouterJarvisBlocked
+            | ErrorCode::JarvisDangerousOp
+            | ErrorCode::JarvisBlacklistHit
+            | ErrorCode::JarvisHighRisk => "error.hint.safety_block",
+            ErrorCode::ConfigError => "error.hint.config",
+            ErrorCode::OpenCodeNotInstalled => "error.hint.opencode_missing",
+            ErrorCode::OfflineUnavailable => "error.hint.offline",
+            ErrorCode::ShuttingDown => "error.hint.shutting_down",
+            ErrorCode::MaintenanceMode => "error.hint.maintenance",
+            ErrorCode::WorkspaceAccessDenied => "error.hint.workspace_access",
+            ErrorCode::BudgetExceeded => "error.hint.budget",
+            ErrorCode::PermissionDenied => "error.hint.permission",
+            _ => return None,
+        };
+        Some(key)
+    }
+}
+
+impl Default for ErrorPresenter {
+    fn default() -> Self {
+        Self::new(Language::EnglishUS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_present_user_error_with_hint() {
+        let presenter = ErrorPresenter::new(Language::ChineseSimplified);
+        let err = AcsaError::new(ErrorCode::ProviderApiKeyMissing, "OpenAI API key required for MOSS");
+
+        let presented = presenter.present(&err);
+        assert_eq!(presented.code, "E2001");
+        assert_eq!(presented.title, "API密钥缺失");
+        assert_eq!(presented.severity, "致命");
+        assert_eq!(presented.message, "OpenAI API key required for MOSS");
+        assert!(presented.hint.unwrap().contains("--mock"));
+    }
+
+    #[test]
+    fn test_system_error_hides_operator_details() {
+        let presenter = ErrorPresenter::new(Language::EnglishUS);
+        let err = AcsaError::new(ErrorCode::ProviderServerError, "upstream 503 from 10.0.0.12:8443")
+            .in_operation("router", "call_moss")
+            .with_correlation_id("req-7");
+
+        let presented = presenter.present(&err);
+        assert!(!presented.message.contains("10.0.0.12"));
+        assert!(presented.retryable);
+        assert_eq!(presented.reference, "req-7");
+        assert!(presented.to_string().contains("Reference: req-7"));
+    }
+
+    #[test]
+    fn test_present_untyped_error_falls_back_to_english() {
+        let presenter = ErrorPresenter::new(Language::Korean);
+        let presented = presenter.present_anyhow(&anyhow::anyhow!("429 Too Many Requests"));
+
+        assert_eq!(presented.code, "E2005");
+        assert_eq!(presented.title, "Rate limit exceeded");
+        assert_eq!(presented.severity, "경고");
+        assert!(presented.reference.starts_with("err_"));
+    }
+}
@@ -6,16 +6,23 @@
 // 2. 异步事件处理
 // 3. 事件过滤
 // 4. 事件历史记录
-// 5. 死信队列
+// 5. 死信队列：处理器按 RetryPolicy 退避重试，重试用尽或 panic 后带错误上下文入队，可重新投递
+// 6. 事件日志（EventJournal）：发布即落盘、消费组断点续传、历史事件重放
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use chrono::{DateTime, Utc};
+use futures::FutureExt;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::panic::AssertUnwindSafe;
 use std::sync::Arc;
 use tokio::sync::{mpsc, RwLock};
 use tracing::{debug, info, warn};
 
+use super::dead_letter::{DeadLetter, DeadLetterQueue};
+use super::event_journal::EventJournal;
+use super::retry::RetryPolicy;
+
 /// 事件类型
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum EventType {
@@ -66,6 +73,30 @@ struct Subscriber {
     handler: Arc<dyn EventHandler>,
     /// 订阅的事件类型
     event_types: Vec<EventType>,
+    /// 消费组（处理后在日志中确认偏移量）
+    group: Option<String>,
+    /// 补发已覆盖到的偏移量，队列中更早的事件不再重复投递
+    resume_from: u64,
+    /// 处理失败时的重试策略
+    retry: RetryPolicy,
+}
+
+/// 重试用尽后的失败信息
+struct HandlerFailure {
+    error: String,
+    attempts: u32,
+    panicked: bool,
+}
+
+/// 重放结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReplayReport {
+    /// 交给处理器的事件数（不含被过滤掉的）
+    pub processed: u64,
+    /// 处理失败的事件数（已进入死信队列）
+    pub failed: u64,
+    /// 重放结束时的下一个偏移量
+    pub next_offset: u64,
 }
 
 /// 事件总线配置
@@ -79,6 +110,13 @@ pub struct EventBusConfig {
     pub history_capacity: usize,
     /// 是否启用死信队列
     pub enable_dead_letter: bool,
+    /// 处理器默认的重试策略（可用 set_retry_policy 按订阅者覆盖；退避不加抖动）
+    #[serde(default = "default_handler_retry")]
+    pub handler_retry: RetryPolicy,
+}
+
+fn default_handler_retry() -> RetryPolicy {
+    RetryPolicy { max_attempts: 3, initial_backoff_ms: 100, max_backoff_ms: 2_000, ..RetryPolicy::default() }
 }
 
 impl Default for EventBusConfig {
@@ -88,23 +126,29 @@ impl Default for EventBusConfig {
             enable_history: true,
             history_capacity: 10000,
             enable_dead_letter: true,
+            handler_retry: default_handler_retry(),
         }
     }
 }
 
+/// 队列中的事件及其日志偏移量
+type QueuedEvent = (Event, Option<u64>);
+
 /// 事件总线
 pub struct EventBus {
     config: EventBusConfig,
     /// 订阅者列表
     subscribers: Arc<RwLock<Vec<Subscriber>>>,
-    /// 事件发送通道
-    event_tx: mpsc::UnboundedSender<Event>,
+    /// 事件发送通道（附带日志偏移量）
+    event_tx: mpsc::UnboundedSender<QueuedEvent>,
     /// 事件接收通道
-    event_rx: Arc<RwLock<mpsc::UnboundedReceiver<Event>>>,
+    event_rx: Arc<RwLock<mpsc::UnboundedReceiver<QueuedEvent>>>,
     /// 事件历史
     history: Arc<RwLock<Vec<Event>>>,
     /// 死信队列
-    dead_letter: Arc<RwLock<Vec<(Event, String)>>>,
+    dead_letters: Arc<DeadLetterQueue>,
+    /// 事件日志（未接入时事件只在内存中）
+    journal: Option<Arc<EventJournal>>,
 }
 
 impl EventBus {
@@ -122,10 +166,46 @@ impl EventBus {
             event_tx,
             event_rx: Arc::new(RwLock::new(event_rx)),
             history: Arc::new(RwLock::new(Vec::new())),
-            dead_letter: Arc::new(RwLock::new(Vec::new())),
+            dead_letters: Arc::new(DeadLetterQueue::in_memory()),
+            journal: None,
         }
     }
 
+    /// 接入事件日志：发布的事件先落盘，内存历史从日志末尾恢复
+    pub fn with_journal(mut self, journal: Arc<EventJournal>) -> Result<Self> {
+        if self.config.enable_history {
+            let tail = journal.tail(self.config.history_capacity)?;
+            self.history = Arc::new(RwLock::new(tail.into_iter().map(|entry| entry.event).collect()));
+        }
+        self.journal = Some(journal);
+        Ok(self)
+    }
+
+    pub fn journal(&self) -> Option<&Arc<EventJournal>> {
+        self.journal.as_ref()
+    }
+
+    /// 接入（可落盘的）死信队列
+    pub fn with_dead_letters(mut self, dead_letters: Arc<DeadLetterQueue>) -> Self {
+        self.dead_letters = dead_letters;
+        self
+    }
+
+    pub fn dead_letters(&self) -> &Arc<DeadLetterQueue> {
+        &self.dead_letters
+    }
+
+    /// 覆盖单个订阅者的重试策略
+    pub async fn set_retry_policy(&self, subscriber_id: &str, policy: RetryPolicy) -> Result<()> {
+        let mut subscribers = self.subscribers.write().await;
+        let subscriber = subscribers
+            .iter_mut()
+            .find(|s| s.id == subscriber_id)
+            .ok_or_else(|| anyhow!("Unknown subscriber: {}", subscriber_id))?;
+        subscriber.retry = policy;
+        Ok(())
+    }
+
     /// 订阅事件
     pub async fn subscribe(
         &self,
@@ -137,6 +217,9 @@ impl EventBus {
             id: subscriber_id.clone(),
             handler,
             event_types,
+            group: None,
+            resume_from: 0,
+            retry: self.config.handler_retry.clone(),
         };
 
         let mut subscribers = self.subscribers.write().await;
@@ -146,6 +229,140 @@ impl EventBus {
         Ok(())
     }
 
+    /// 以消费组订阅：先补发该组未确认的历史事件，再接收新事件；每个事件处理后确认偏移量
+    ///
+    /// 进程重启后以同一组名订阅即可从上次确认处继续，不丢也不重复（处理失败的事件进入死信队列并照常确认）。
+    pub async fn subscribe_group(
+        &self,
+        group: &str,
+        handler: Arc<dyn EventHandler>,
+        event_types: Vec<EventType>,
+    ) -> Result<ReplayReport> {
+        let journal = self
+            .journal
+            .clone()
+            .ok_or_else(|| anyhow!("Consumer groups need an event journal (EventBus::with_journal)"))?;
+
+        // 补发期间持有写锁，处理循环暂停；之后发布的事件留在队列中按序投递
+        let mut subscribers = self.subscribers.write().await;
+        let from = journal.committed_offset(group).unwrap_or(0);
+        let report = self
+            .replay_entries(&journal, from, &handler, &event_types, Some(group))
+            .await?;
+        subscribers.push(Subscriber {
+            id: group.to_string(),
+            handler,
+            event_types,
+            group: Some(group.to_string()),
+            resume_from: report.next_offset,
+            retry: self.config.handler_retry.clone(),
+        });
+
+        info!(
+            "📬 Consumer group {} subscribed: replayed {} event(s) from offset {}",
+            group, report.processed, from
+        );
+        Ok(report)
+    }
+
+    /// 用日志中的历史事件驱动处理器（不订阅、不确认偏移量），如指标回填
+    pub async fn replay(&self, handler: Arc<dyn EventHandler>, from_offset: u64) -> Result<ReplayReport> {
+        let journal = self
+            .journal
+            .clone()
+            .ok_or_else(|| anyhow!("Replay needs an event journal (EventBus::with_journal)"))?;
+        let report = self.replay_entries(&journal, from_offset, &handler, &[], None).await?;
+        info!("⏪ Replayed {} event(s) from offset {}", report.processed, from_offset);
+        Ok(report)
+    }
+
+    /// 逐个重放 [from, 当前末尾) 的事件；给定消费组时每个事件处理后确认
+    async fn replay_entries(
+        &self,
+        journal: &EventJournal,
+        from: u64,
+        handler: &Arc<dyn EventHandler>,
+        event_types: &[EventType],
+        group: Option<&str>,
+    ) -> Result<ReplayReport> {
+        const REPLAY_BATCH: usize = 500;
+
+        let end = journal.next_offset();
+        let mut report = ReplayReport { next_offset: from, ..Default::default() };
+        while report.next_offset < end {
+            let entries = journal.read_from(report.next_offset, Some(REPLAY_BATCH))?;
+            if entries.is_empty() {
+                break;
+            }
+            for entry in entries.into_iter().take_while(|entry| entry.offset < end) {
+                if event_types.contains(&entry.event.event_type) || handler.filter(&entry.event.event_type) {
+                    report.processed += 1;
+                    let retry = &self.config.handler_retry;
+                    if let Err(failure) = deliver(handler.clone(), retry, &entry.event).await {
+                        warn!("⚠️  Event handler failed during replay at offset {}: {}", entry.offset, failure.error);
+                        report.failed += 1;
+                        let subscriber_id = group.unwrap_or("replay");
+                        self.record_dead_letter(entry.event, subscriber_id, failure, Some(entry.offset));
+                    }
+                }
+                if let Some(group) = group {
+                    journal.acknowledge(group, entry.offset)?;
+                }
+                report.next_offset = entry.offset + 1;
+            }
+        }
+        Ok(report)
+    }
+
+    fn record_dead_letter(&self, event: Event, subscriber_id: &str, failure: HandlerFailure, offset: Option<u64>) {
+        if !self.config.enable_dead_letter {
+            return;
+        }
+        let id = self.dead_letters.push(DeadLetter {
+            id: String::new(),
+            event,
+            subscriber_id: subscriber_id.to_string(),
+            error: failure.error,
+            attempts: failure.attempts,
+            panicked: failure.panicked,
+            offset,
+            failed_at: Utc::now(),
+        });
+        warn!("📮 Event dead-lettered as {} (subscriber {})", id, subscriber_id);
+    }
+
+    /// 把一条死信重新投递给原订阅者；成功返回 true，再次失败时以累计次数重新入队并返回 false
+    pub async fn redrive(&self, dead_letter_id: &str) -> Result<bool> {
+        let letter = self
+            .dead_letters
+            .get(dead_letter_id)
+            .ok_or_else(|| anyhow!("Unknown dead letter: {}", dead_letter_id))?;
+        let (handler, retry) = {
+            let subscribers = self.subscribers.read().await;
+            let subscriber = subscribers
+                .iter()
+                .find(|s| s.id == letter.subscriber_id)
+                .ok_or_else(|| anyhow!("Subscriber {} is not registered on this bus", letter.subscriber_id))?;
+            (subscriber.handler.clone(), subscriber.retry.clone())
+        };
+        let Some(letter) = self.dead_letters.take(dead_letter_id) else {
+            // 并发重新投递时已被取走
+            return Ok(false);
+        };
+
+        match deliver(handler, &retry, &letter.event).await {
+            Ok(()) => {
+                info!("📮 Dead letter {} redriven to {}", dead_letter_id, letter.subscriber_id);
+                Ok(true)
+            }
+            Err(mut failure) => {
+                failure.attempts += letter.attempts;
+                self.record_dead_letter(letter.event, &letter.subscriber_id, failure, letter.offset);
+                Ok(false)
+            }
+        }
+    }
+
     /// 取消订阅
     pub async fn unsubscribe(&self, subscriber_id: &str) -> Result<()> {
         let mut subscribers = self.subscribers.write().await;
@@ -159,6 +376,12 @@ impl EventBus {
     pub async fn publish(&self, event: Event) -> Result<()> {
         debug!("📤 Publishing event: {:?}", event.event_type);
 
+        // 先落盘，崩溃后可从日志恢复
+        let offset = match &self.journal {
+            Some(journal) => Some(journal.append(&event)?),
+            None => None,
+        };
+
         // 记录历史
         if self.config.enable_history {
             let mut history = self.history.write().await;
@@ -174,7 +397,7 @@ impl EventBus {
 
         // 发送到队列
         self.event_tx
-            .send(event)
+            .send((event, offset))
             .map_err(|e| anyhow::anyhow!("Failed to publish event: {}", e))?;
 
         Ok(())
@@ -192,8 +415,8 @@ impl EventBus {
                     rx.recv().await
                 };
 
-                if let Some(event) = event {
-                    self.process_event(event).await;
+                if let Some((event, offset)) = event {
+                    self.process_event(event, offset).await;
                 } else {
                     warn!("📥 Event channel closed");
                     break;
@@ -203,14 +426,22 @@ impl EventBus {
     }
 
     /// 处理事件
-    async fn process_event(&self, event: Event) {
+    async fn process_event(&self, event: Event, offset: Option<u64>) {
         debug!("📥 Processing event: {:?}", event.event_type);
 
         let subscribers = self.subscribers.read().await;
 
         // 找到所有匹配的订阅者
         let mut matched_subscribers = Vec::new();
+        let mut groups = Vec::new();
         for subscriber in subscribers.iter() {
+            // 消费组补发时已处理过的事件
+            if subscriber.group.is_some() && offset.is_some_and(|offset| offset < subscriber.resume_from) {
+                continue;
+            }
+            if let Some(group) = &subscriber.group {
+                groups.push(group.clone());
+            }
             // 检查是否订阅了此类型事件
             if subscriber
                 .event_types
@@ -218,7 +449,7 @@ impl EventBus {
                 .any(|t| t == &event.event_type)
                 || subscriber.handler.filter(&event.event_type)
             {
-                matched_subscribers.push(subscriber.handler.clone());
+                matched_subscribers.push((subscriber.id.clone(), subscriber.handler.clone(), subscriber.retry.clone()));
             }
         }
 
@@ -226,26 +457,36 @@ impl EventBus {
 
         // 异步并发处理
         let mut handles = Vec::new();
-        for handler in matched_subscribers {
+        for (subscriber_id, handler, retry) in matched_subscribers {
             let event_clone = event.clone();
             let handle = tokio::spawn(async move {
-                if let Err(e) = handler.handle(&event_clone).await {
-                    warn!("⚠️  Event handler failed: {}", e);
-                    Err((event_clone, e.to_string()))
-                } else {
-                    Ok(())
-                }
+                let result = deliver(handler, &retry, &event_clone).await;
+                (subscriber_id, result)
             });
             handles.push(handle);
         }
 
         // 等待所有处理器完成
         for handle in handles {
-            if let Ok(Err((failed_event, error))) = handle.await {
-                // 记录到死信队列
-                if self.config.enable_dead_letter {
-                    let mut dead_letter = self.dead_letter.write().await;
-                    dead_letter.push((failed_event, error));
+            match handle.await {
+                Ok((subscriber_id, Err(failure))) => {
+                    warn!(
+                        "⚠️  Event handler {} failed after {} attempt(s): {}",
+                        subscriber_id, failure.attempts, failure.error
+                    );
+                    // 记录到死信队列
+                    self.record_dead_letter(event.clone(), &subscriber_id, failure, offset);
+                }
+                Ok((_, Ok(()))) => {}
+                Err(e) => warn!("⚠️  Event handler task aborted: {}", e),
+            }
+        }
+
+        // 全部处理器完成后再确认，崩溃时该事件会在重新订阅后补发
+        if let (Some(journal), Some(offset)) = (&self.journal, offset) {
+            for group in groups {
+                if let Err(e) = journal.acknowledge(&group, offset) {
+                    warn!("⚠️  Failed to acknowledge offset {} for group {}: {}", offset, group, e);
                 }
             }
         }
@@ -259,18 +500,55 @@ impl EventBus {
     }
 
     /// 获取死信队列
-    pub async fn get_dead_letter(&self) -> Vec<(Event, String)> {
-        self.dead_letter.read().await.clone()
+    pub async fn get_dead_letter(&self) -> Vec<DeadLetter> {
+        self.dead_letters.list()
     }
 
     /// 清空死信队列
     pub async fn clear_dead_letter(&self) {
-        let mut dead_letter = self.dead_letter.write().await;
-        dead_letter.clear();
+        self.dead_letters.clear();
         info!("🗑️  Dead letter queue cleared");
     }
 }
 
+/// 按重试策略调用处理器；panic 视为失败（不重试）
+async fn deliver(handler: Arc<dyn EventHandler>, retry: &RetryPolicy, event: &Event) -> Result<(), HandlerFailure> {
+    let max_attempts = retry.max_attempts.max(1);
+    let mut attempt = 1;
+    loop {
+        let error = match AssertUnwindSafe(handler.handle(event)).catch_unwind().await {
+            Ok(Ok(())) => return Ok(()),
+            Ok(Err(e)) => e.to_string(),
+            Err(panic) => {
+                let message = panic
+                    .downcast_ref::<&str>()
+                    .map(|s| s.to_string())
+                    .or_else(|| panic.downcast_ref::<String>().cloned())
+                    .unwrap_or_else(|| "unknown panic".to_string());
+                return Err(HandlerFailure {
+                    error: format!("handler panicked: {}", message),
+                    attempts: attempt,
+                    panicked: true,
+                });
+            }
+        };
+        if attempt >= max_attempts {
+            return Err(HandlerFailure { error, attempts: attempt, panicked: false });
+        }
+        let delay = retry.backoff(attempt);
+        debug!(
+            "🔄 Event {} handler attempt {}/{} failed, retrying in {} ms: {}",
+            event.event_id,
+            attempt,
+            max_attempts,
+            delay.as_millis(),
+            error
+        );
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
 // ===== 内置事件处理器示例 =====
 
 /// 日志事件处理器
@@ -362,4 +640,124 @@ mod tests {
         let history = bus.get_history(Some(10)).await;
         assert_eq!(history.len(), 1);
     }
+
+    #[tokio::test]
+    async fn test_consumer_group_resumes_and_replay_backfills() {
+        let dir = tempfile::tempdir().unwrap();
+        let event = |id: &str| Event {
+            event_id: id.to_string(),
+            event_type: EventType::Data("order".to_string()),
+            source: "test".to_string(),
+            data: serde_json::json!({}),
+            timestamp: Utc::now(),
+            metadata: HashMap::new(),
+        };
+
+        // 第一个进程：消费组处理了 a、b 后崩溃，c 在崩溃前已发布但未处理
+        {
+            let journal = Arc::new(EventJournal::open(dir.path()).unwrap());
+            let bus = Arc::new(EventBus::new(EventBusConfig::default()).with_journal(journal).unwrap());
+            bus.clone().start().await;
+            let metrics = Arc::new(MetricsEventHandler::new());
+            bus.subscribe_group("metrics", metrics.clone(), vec![]).await.unwrap();
+            bus.publish(event("a")).await.unwrap();
+            bus.publish(event("b")).await.unwrap();
+            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+            bus.unsubscribe("metrics").await.unwrap();
+            bus.publish(event("c")).await.unwrap();
+        }
+
+        let journal = Arc::new(EventJournal::open(dir.path()).unwrap());
+        assert_eq!(journal.committed_offset("metrics"), Some(2));
+        let bus = Arc::new(EventBus::new(EventBusConfig::default()).with_journal(journal).unwrap());
+        assert_eq!(bus.get_history(None).await.len(), 3);
+        bus.clone().start().await;
+
+        // 重启后同名消费组只补发 c
+        let metrics = Arc::new(MetricsEventHandler::new());
+        let report = bus.subscribe_group("metrics", metrics.clone(), vec![]).await.unwrap();
+        assert_eq!((report.processed, report.next_offset), (1, 3));
+        bus.publish(event("d")).await.unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        assert_eq!(metrics.get_counts().await.values().sum::<u64>(), 2);
+
+        // 新处理器回填全部历史
+        let backfill = Arc::new(MetricsEventHandler::new());
+        let report = bus.replay(backfill.clone(), 0).await.unwrap();
+        assert_eq!((report.processed, report.failed), (4, 0));
+        assert_eq!(backfill.get_counts().await.values().sum::<u64>(), 4);
+    }
+
+    /// 前 `fail_first` 次调用失败；`panic` 为真时直接 panic
+    struct FlakyHandler {
+        calls: std::sync::atomic::AtomicU32,
+        fail_first: u32,
+        panic: bool,
+    }
+
+    #[async_trait::async_trait]
+    impl EventHandler for FlakyHandler {
+        async fn handle(&self, _event: &Event) -> Result<()> {
+            let call = self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+            if self.panic {
+                panic!("handler bug");
+            }
+            if call <= self.fail_first {
+                return Err(anyhow!("downstream unavailable (call {})", call));
+            }
+            Ok(())
+        }
+
+        fn filter(&self, _event_type: &EventType) -> bool {
+            true
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_dead_letter_and_redrive() {
+        let dir = tempfile::tempdir().unwrap();
+        let dead_letters = Arc::new(DeadLetterQueue::open(dir.path().join("dead_letters.json")).unwrap());
+        let bus = Arc::new(EventBus::new(EventBusConfig::default()).with_dead_letters(dead_letters));
+        bus.clone().start().await;
+
+        let flaky = Arc::new(FlakyHandler { calls: Default::default(), fail_first: 6, panic: false });
+        let buggy = Arc::new(FlakyHandler { calls: Default::default(), fail_first: 0, panic: true });
+        bus.subscribe("flaky".to_string(), flaky.clone(), vec![]).await.unwrap();
+        bus.subscribe("buggy".to_string(), buggy, vec![]).await.unwrap();
+        let fast = RetryPolicy { max_attempts: 3, initial_backoff_ms: 1, max_backoff_ms: 1, ..RetryPolicy::default() };
+        bus.set_retry_policy("flaky", fast.clone()).await.unwrap();
+        bus.set_retry_policy("buggy", fast).await.unwrap();
+
+        bus.publish(Event {
+            event_id: "e1".to_string(),
+            event_type: EventType::Data("order".to_string()),
+            source: "test".to_string(),
+            data: serde_json::json!({}),
+            timestamp: Utc::now(),
+            metadata: HashMap::new(),
+        })
+        .await
+        .unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
+
+        // 重试 3 次仍失败；panic 不重试
+        let letters = bus.get_dead_letter().await;
+        assert_eq!(letters.len(), 2);
+        let failed = letters.iter().find(|l| l.subscriber_id == "flaky").unwrap();
+        assert_eq!((failed.attempts, failed.panicked), (3, false));
+        assert!(failed.error.contains("call 3"));
+        let panicked = letters.iter().find(|l| l.subscriber_id == "buggy").unwrap();
+        assert!(panicked.panicked && panicked.error.contains("handler bug"));
+
+        // 死信已落盘，另一个进程也能看到
+        assert_eq!(DeadLetterQueue::open(dir.path().join("dead_letters.json")).unwrap().len(), 2);
+
+        // 第一次重新投递：第 4-6 次调用仍失败，累计次数后重新入队；第二次成功
+        assert!(!bus.redrive(&failed.id).await.unwrap());
+        let requeued = bus.get_dead_letter().await.into_iter().find(|l| l.subscriber_id == "flaky").unwrap();
+        assert_eq!(requeued.attempts, 6);
+        assert!(bus.redrive(&requeued.id).await.unwrap());
+        assert_eq!(bus.dead_letters().len(), 1);
+        assert!(bus.redrive("dlq_missing").await.is_err());
+    }
 }
@@ -0,0 +1,217 @@
+// Event Journal - 事件总线的持久化日志
+// EventBus 只在内存中排队，进程崩溃即丢事件；接入日志后发布即落盘，之后可按偏移量重放
+//
+// 核心功能：
+// 1. 追加写入的 JSONL 文件，每行一个事件，行号即偏移量（从 0 开始）
+// 2. 崩溃时写了一半的末行在下次打开时截掉
+// 3. 按偏移量读取（replay-from-offset）
+// 4. 消费组：每组记录已确认的偏移量，重启后从未确认处继续
+
+use anyhow::{anyhow, Context, Result};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tracing::{info, warn};
+
+use super::event_bus::Event;
+
+/// 默认日志目录
+pub const DEFAULT_EVENT_JOURNAL_DIR: &str = "./data/events";
+
+/// 日志中的一条事件
+#[derive(Debug, Clone)]
+pub struct JournalEntry {
+    pub offset: u64,
+    pub event: Event,
+}
+
+struct JournalWriter {
+    file: File,
+    next_offset: u64,
+}
+
+/// 事件日志
+pub struct EventJournal {
+    events_path: PathBuf,
+    offsets_path: PathBuf,
+    writer: Mutex<JournalWriter>,
+    /// 消费组 → 下一个待处理的偏移量
+    offsets: Mutex<HashMap<String, u64>>,
+    /// 每次追加后 fsync（关闭后吞吐更高，但断电可能丢最近的事件）
+    sync: bool,
+}
+
+impl EventJournal {
+    /// 打开（或创建）日志目录，截掉崩溃留下的不完整末行
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        let events_path = dir.join("events.jsonl");
+        let offsets_path = dir.join("offsets.json");
+
+        // 统计完整的行数，记下最后一个完整行的结尾
+        let mut next_offset = 0u64;
+        let mut valid_len = 0u64;
+        if events_path.exists() {
+            let mut reader = BufReader::new(File::open(&events_path)?);
+            let mut line = String::new();
+            loop {
+                line.clear();
+                let read = reader.read_line(&mut line)?;
+                if read == 0 || !line.ends_with('\n') || serde_json::from_str::<Event>(&line).is_err() {
+                    break;
+                }
+                valid_len += read as u64;
+                next_offset += 1;
+            }
+        }
+
+        let file = OpenOptions::new().create(true).append(true).open(&events_path)?;
+        if file.metadata()?.len() > valid_len {
+            warn!("⚠️  Truncating torn event journal tail at offset {}", next_offset);
+            file.set_len(valid_len)?;
+        }
+
+        let offsets = if offsets_path.exists() {
+            serde_json::from_str(&std::fs::read_to_string(&offsets_path)?)
+                .with_context(|| format!("Corrupted consumer offsets {}", offsets_path.display()))?
+        } else {
+            HashMap::new()
+        };
+
+        info!("📼 Event journal opened: {} event(s) in {}", next_offset, dir.display());
+        Ok(Self {
+            events_path,
+            offsets_path,
+            writer: Mutex::new(JournalWriter { file, next_offset }),
+            offsets: Mutex::new(offsets),
+            sync: true,
+        })
+    }
+
+    /// 是否每次追加后 fsync（默认开启）
+    pub fn with_sync(mut self, sync: bool) -> Self {
+        self.sync = sync;
+        self
+    }
+
+    /// 追加一个事件，返回其偏移量
+    pub fn append(&self, event: &Event) -> Result<u64> {
+        let mut line = serde_json::to_string(event)?;
+        line.push('\n');
+
+        let mut writer = self.writer.lock().unwrap();
+        writer.file.write_all(line.as_bytes())?;
+        if self.sync {
+            writer.file.sync_data()?;
+        }
+        let offset = writer.next_offset;
+        writer.next_offset += 1;
+        Ok(offset)
+    }
+
+    /// 下一个事件将获得的偏移量（即当前事件总数）
+    pub fn next_offset(&self) -> u64 {
+        self.writer.lock().unwrap().next_offset
+    }
+
+    /// 从 `from` 开始读取至多 `limit` 个事件
+    pub fn read_from(&self, from: u64, limit: Option<usize>) -> Result<Vec<JournalEntry>> {
+        // 只读到当前已提交的末尾，不读正在写入的行
+        let end = self.next_offset();
+        let limit = limit.unwrap_or(usize::MAX);
+        let mut entries = Vec::new();
+        let reader = BufReader::new(File::open(&self.events_path)?);
+        for (offset, line) in (0u64..).zip(reader.lines()) {
+            if offset >= end || entries.len() >= limit {
+                break;
+            }
+            let line = line?;
+            if offset < from {
+                continue;
+            }
+            let event = serde_json::from_str(&line)
+                .with_context(|| format!("Corrupted event journal entry at offset {}", offset))?;
+            entries.push(JournalEntry { offset, event });
+        }
+        Ok(entries)
+    }
+
+    /// 最近的 `count` 个事件（按偏移量升序）
+    pub fn tail(&self, count: usize) -> Result<Vec<JournalEntry>> {
+        let from = self.next_offset().saturating_sub(count as u64);
+        self.read_from(from, None)
+    }
+
+    /// 消费组下一个待处理的偏移量（未确认过时为 None）
+    pub fn committed_offset(&self, group: &str) -> Option<u64> {
+        self.offsets.lock().unwrap().get(group).copied()
+    }
+
+    /// 全部消费组及其进度
+    pub fn groups(&self) -> HashMap<String, u64> {
+        self.offsets.lock().unwrap().clone()
+    }
+
+    /// 确认消费组已处理到 `offset`（含），只会向前推进
+    pub fn acknowledge(&self, group: &str, offset: u64) -> Result<()> {
+        if offset >= self.next_offset() {
+            return Err(anyhow!("Cannot acknowledge offset {} beyond the journal end", offset));
+        }
+        let mut offsets = self.offsets.lock().unwrap();
+        let committed = offsets.entry(group.to_string()).or_insert(0);
+        if offset < *committed {
+            return Ok(());
+        }
+        *committed = offset + 1;
+        let tmp = self.offsets_path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(&*offsets)?)?;
+        std::fs::rename(&tmp, &self.offsets_path)
+            .with_context(|| format!("Failed to write {}", self.offsets_path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::event_bus::EventType;
+    use chrono::Utc;
+
+    fn event(id: &str) -> Event {
+        Event {
+            event_id: id.to_string(),
+            event_type: EventType::System("test".to_string()),
+            source: "test".to_string(),
+            data: serde_json::json!({}),
+            timestamp: Utc::now(),
+            metadata: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_journal_survives_torn_write() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let journal = EventJournal::open(dir.path()).unwrap();
+            for id in ["a", "b", "c"] {
+                journal.append(&event(id)).unwrap();
+            }
+            journal.acknowledge("metrics", 1).unwrap();
+            assert!(journal.acknowledge("metrics", 9).is_err());
+        }
+        // 崩溃时末行只写了一半
+        let mut file = OpenOptions::new().append(true).open(dir.path().join("events.jsonl")).unwrap();
+        file.write_all(b"{\"event_id\":\"d\",\"event_t").unwrap();
+
+        let journal = EventJournal::open(dir.path()).unwrap();
+        assert_eq!(journal.next_offset(), 3);
+        assert_eq!(journal.committed_offset("metrics"), Some(2));
+        assert_eq!(journal.append(&event("e")).unwrap(), 3);
+
+        let ids: Vec<_> = journal.read_from(2, None).unwrap().into_iter().map(|e| e.event.event_id).collect();
+        assert_eq!(ids, vec!["c", "e"]);
+        assert_eq!(journal.tail(1).unwrap()[0].offset, 3);
+    }
+}
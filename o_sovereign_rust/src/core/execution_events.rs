@@ -0,0 +1,350 @@
+// Execution Events - 执行实况的 SSE 推送
+// 供不能使用 WebSocket 的网页仪表盘订阅单次执行的进度
+//
+// 核心功能：
+// 1. 订阅事件总线上的执行实况（EXECUTION_FEED_EVENT），按 Router 执行 ID 缓存最近的事件
+// 2. SSE 帧：`id` 为 `执行ID#序号`，事件名为阶段（started / stage_started / stage_finished /
+//    verdict / audit / chunk / completed），`data` 为 ExecutionFeed 的 JSON
+// 3. Last-Event-ID 续传：断线重连后只补发该序号之后的事件
+// 4. 定时发送 keep-alive 注释帧，执行结束（completed）后关闭流
+//
+// 注：只保留最近 MAX_TRACKED_EXECUTIONS 次执行；已淘汰的执行请改查执行记录接口
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tracing::debug;
+
+use super::event_bus::{Event, EventBus, EventHandler, EventType};
+use super::types::{ExecutionFeed, PipelineEvent, EXECUTION_FEED_EVENT};
+
+/// 缓存的执行数量上限（超出时淘汰最早的执行）
+pub const MAX_TRACKED_EXECUTIONS: usize = 64;
+/// 单次执行缓存的事件上限（超出时丢弃最早的事件，续传只能补发仍在缓存内的部分）
+pub const MAX_EVENTS_PER_EXECUTION: usize = 4096;
+/// 默认 keep-alive 间隔
+pub const DEFAULT_KEEP_ALIVE: Duration = Duration::from_secs(15);
+/// 建议客户端的重连间隔（SSE `retry:` 字段，毫秒）
+pub const SSE_RETRY_MS: u64 = 3000;
+
+/// 一条带序号的执行实况
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedEvent {
+    pub execution_id: String,
+    pub sequence: u64,
+    pub feed: ExecutionFeed,
+}
+
+impl FeedEvent {
+    /// SSE 事件 ID（与事件总线上的 event_id 相同）
+    pub fn event_id(&self) -> String {
+        format!("{}#{}", self.execution_id, self.sequence)
+    }
+
+    /// SSE 事件名
+    pub fn event_name(&self) -> &'static str {
+        match &self.feed {
+            ExecutionFeed::Chunk { .. } => "chunk",
+            ExecutionFeed::Progress { event } => match event {
+                PipelineEvent::Started { .. } => "started",
+                PipelineEvent::StageStarted { .. } => "stage_started",
+                PipelineEvent::StageFinished { .. } => "stage_finished",
+                PipelineEvent::Verdict { .. } => "verdict",
+                PipelineEvent::Audit { .. } => "audit",
+                PipelineEvent::Completed { .. } => "completed",
+            },
+        }
+    }
+
+    /// 是否为执行的最后一条事件
+    pub fn is_terminal(&self) -> bool {
+        matches!(self.feed, ExecutionFeed::Progress { event: PipelineEvent::Completed { .. } })
+    }
+
+    /// 编码为 SSE 帧
+    pub fn to_sse(&self) -> Result<String> {
+        Ok(format!(
+            "id: {}\nevent: {}\ndata: {}\n\n",
+            self.event_id(),
+            self.event_name(),
+            serde_json::to_string(&self.feed)?
+        ))
+    }
+}
+
+/// 解析 Last-Event-ID：`执行ID#序号` 或单独的序号；属于其他执行时忽略
+pub fn parse_last_event_id(execution_id: &str, header: &str) -> Option<u64> {
+    let header = header.trim();
+    match header.rsplit_once('#') {
+        Some((id, sequence)) if id == execution_id => sequence.parse().ok(),
+        Some(_) => None,
+        None => header.parse().ok(),
+    }
+}
+
+/// 单次执行的缓存与实时订阅者
+#[derive(Default)]
+struct ExecutionBuffer {
+    events: VecDeque<FeedEvent>,
+    completed: bool,
+    subscribers: Vec<UnboundedSender<FeedEvent>>,
+}
+
+#[derive(Default)]
+struct HubState {
+    /// 按首次出现的顺序排列的执行 ID（用于淘汰）
+    order: VecDeque<String>,
+    executions: HashMap<String, ExecutionBuffer>,
+}
+
+/// 订阅结果：需补发的事件，以及执行未结束时的实时通道
+pub struct FeedSubscription {
+    pub replay: Vec<FeedEvent>,
+    pub live: Option<UnboundedReceiver<FeedEvent>>,
+}
+
+/// 执行实况中转站（接入事件总线，为每个 SSE 连接提供补发 + 实时事件）
+pub struct ExecutionEventHub {
+    state: Mutex<HubState>,
+    keep_alive: Duration,
+}
+
+impl Default for ExecutionEventHub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ExecutionEventHub {
+    pub fn new() -> Self {
+        Self { state: Mutex::new(HubState::default()), keep_alive: DEFAULT_KEEP_ALIVE }
+    }
+
+    /// 设置 keep-alive 间隔
+    pub fn with_keep_alive(mut self, keep_alive: Duration) -> Self {
+        self.keep_alive = keep_alive;
+        self
+    }
+
+    /// 订阅事件总线上的执行实况（Router 需经 `with_event_bus` 接入同一总线）
+    pub async fn attach(self: &Arc<Self>, bus: &EventBus) -> Result<()> {
+        bus.subscribe(
+            "execution_events".to_string(),
+            self.clone(),
+            vec![EventType::Ai(EXECUTION_FEED_EVENT.to_string())],
+        )
+        .await
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HubState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 缓存一条事件并推送给实时订阅者
+    pub fn record(&self, event: FeedEvent) {
+        let mut state = self.lock();
+        if !state.executions.contains_key(&event.execution_id) {
+            state.order.push_back(event.execution_id.clone());
+            while state.order.len() > MAX_TRACKED_EXECUTIONS {
+                if let Some(evicted) = state.order.pop_front() {
+                    state.executions.remove(&evicted);
+                }
+            }
+        }
+        let buffer = state.executions.entry(event.execution_id.clone()).or_default();
+        buffer.subscribers.retain(|subscriber| subscriber.send(event.clone()).is_ok());
+        if event.is_terminal() {
+            buffer.completed = true;
+            // 关闭实时通道，SSE 流在发出 completed 后结束
+            buffer.subscribers.clear();
+        }
+        buffer.events.push_back(event);
+        if buffer.events.len() > MAX_EVENTS_PER_EXECUTION {
+            buffer.events.pop_front();
+        }
+    }
+
+    /// 是否仍缓存着该执行
+    pub fn contains(&self, execution_id: &str) -> bool {
+        self.lock().executions.contains_key(execution_id)
+    }
+
+    /// 正在进行的执行 ID（按开始顺序）
+    pub fn active(&self) -> Vec<String> {
+        let state = self.lock();
+        state
+            .order
+            .iter()
+            .filter(|id| state.executions.get(*id).is_some_and(|buffer| !buffer.completed))
+            .cloned()
+            .collect()
+    }
+
+    /// 订阅一次执行：补发 `after` 之后的缓存事件；执行未结束时附带实时通道。未知执行返回 None
+    pub fn subscribe(&self, execution_id: &str, after: Option<u64>) -> Option<FeedSubscription> {
+        let mut state = self.lock();
+        let buffer = state.executions.get_mut(execution_id)?;
+        let replay = buffer
+            .events
+            .iter()
+            .filter(|event| after.is_none_or(|after| event.sequence > after))
+            .cloned()
+            .collect();
+        let live = (!buffer.completed).then(|| {
+            let (sender, receiver) = mpsc::unbounded_channel();
+            buffer.subscribers.push(sender);
+            receiver
+        });
+        Some(FeedSubscription { replay, live })
+    }
+
+    /// SSE 帧流：`retry:` → 补发 → 实时事件与 keep-alive，completed 后关闭。未知执行返回 None
+    pub fn stream(&self, execution_id: &str, last_event_id: Option<&str>) -> Option<UnboundedReceiver<String>> {
+        let after = last_event_id.and_then(|header| parse_last_event_id(execution_id, header));
+        let FeedSubscription { replay, live } = self.subscribe(execution_id, after)?;
+        let keep_alive = self.keep_alive;
+        let (frames, receiver) = mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            let send = |event: &FeedEvent| match event.to_sse() {
+                Ok(frame) => frames.send(frame).is_ok(),
+                Err(e) => {
+                    debug!("📪 Failed to encode execution event {}: {}", event.event_id(), e);
+                    true
+                }
+            };
+            if frames.send(format!("retry: {}\n\n", SSE_RETRY_MS)).is_err() {
+                return;
+            }
+            for event in &replay {
+                if !send(event) {
+                    return;
+                }
+            }
+            let Some(mut live) = live else {
+                return;
+            };
+
+            let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + keep_alive, keep_alive);
+            loop {
+                tokio::select! {
+                    event = live.recv() => match event {
+                        Some(event) => {
+                            if !send(&event) || event.is_terminal() {
+                                return;
+                            }
+                        }
+                        // 执行被淘汰或已结束
+                        None => return,
+                    },
+                    _ = ticker.tick() => {
+                        if frames.send(": keep-alive\n\n".to_string()).is_err() {
+                            return;
+                        }
+                    }
+                }
+            }
+        });
+        Some(receiver)
+    }
+}
+
+#[async_trait::async_trait]
+impl EventHandler for ExecutionEventHub {
+    async fn handle(&self, event: &Event) -> Result<()> {
+        let execution_id = event
+            .metadata
+            .get("execution_id")
+            .cloned()
+            .ok_or_else(|| anyhow!("Execution feed event without execution_id: {}", event.event_id))?;
+        let sequence = event.metadata.get("sequence").and_then(|s| s.parse().ok()).unwrap_or(0);
+        let feed: ExecutionFeed = serde_json::from_value(event.data.clone())?;
+        self.record(FeedEvent { execution_id, sequence, feed });
+        Ok(())
+    }
+
+    fn filter(&self, _event_type: &EventType) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::event_bus::EventBusConfig;
+    use crate::core::providers::MockProvider;
+    use crate::core::router::ACSARouter;
+    use crate::core::types::{ACSAConfig, AgentChunk, AgentRole};
+
+    async fn collect(mut frames: UnboundedReceiver<String>) -> Vec<String> {
+        let mut collected = Vec::new();
+        while let Ok(Some(frame)) = tokio::time::timeout(Duration::from_secs(2), frames.recv()).await {
+            collected.push(frame);
+        }
+        collected
+    }
+
+    #[tokio::test]
+    async fn test_stream_resumes_after_last_event_id() {
+        let bus = Arc::new(EventBus::new(EventBusConfig::default()));
+        bus.clone().start().await;
+        let hub = Arc::new(ExecutionEventHub::new());
+        hub.attach(&bus).await.unwrap();
+
+        let router = ACSARouter::new(
+            Arc::new(MockProvider::new(AgentRole::MOSS)),
+            Arc::new(MockProvider::new(AgentRole::L6)),
+            Arc::new(MockProvider::new(AgentRole::Ultron)),
+            Arc::new(MockProvider::new(AgentRole::Omega)),
+            ACSAConfig { max_iterations: 1, enable_l6: false, ..Default::default() },
+        )
+        .with_event_bus(bus.clone());
+        let log = router.execute("写一个HTTP服务器".to_string()).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let execution_id = log.execution_id.unwrap();
+        assert!(hub.stream("exec_unknown", None).is_none());
+        assert!(hub.active().is_empty());
+
+        let full = collect(hub.stream(&execution_id, None).unwrap()).await;
+        assert_eq!(full[0], format!("retry: {}\n\n", SSE_RETRY_MS));
+        assert!(full[1].starts_with(&format!("id: {}#0\nevent: started\n", execution_id)));
+        assert!(full.iter().any(|f| f.contains("event: stage_finished\n")));
+        assert!(full.iter().any(|f| f.contains("event: chunk\n")));
+        assert!(full.last().unwrap().contains("event: completed\n"));
+
+        // 续传只补发 #2 之后的事件
+        let resumed = collect(hub.stream(&execution_id, Some(&format!("{}#2", execution_id))).unwrap()).await;
+        assert_eq!(resumed.len(), full.len() - 3);
+        assert!(resumed[1].starts_with(&format!("id: {}#3\n", execution_id)));
+        assert_eq!(parse_last_event_id(&execution_id, "exec_other#5"), None);
+        assert_eq!(parse_last_event_id(&execution_id, "7"), Some(7));
+    }
+
+    #[tokio::test]
+    async fn test_live_stream_keeps_alive_until_completed() {
+        let hub = Arc::new(ExecutionEventHub::new().with_keep_alive(Duration::from_millis(50)));
+        let event = |sequence, feed| FeedEvent { execution_id: "exec_1".to_string(), sequence, feed };
+        hub.record(event(0, ExecutionFeed::Progress { event: PipelineEvent::Started { user_input: "hi".to_string() } }));
+        assert_eq!(hub.active(), vec!["exec_1".to_string()]);
+
+        let mut frames = hub.stream("exec_1", Some("exec_1#0")).unwrap();
+        assert!(frames.recv().await.unwrap().starts_with("retry:"));
+        assert_eq!(frames.recv().await.unwrap(), ": keep-alive\n\n");
+
+        let chunk = AgentChunk { role: AgentRole::Omega, iteration: 1, delta: "ok".to_string(), done: false };
+        hub.record(event(1, ExecutionFeed::Chunk { chunk }));
+        hub.record(event(2, ExecutionFeed::Progress {
+            event: PipelineEvent::Completed { success: true, total_cost: 0.0, iterations: 1 },
+        }));
+        let rest = collect(frames).await;
+        let events: Vec<_> = rest.iter().filter(|f| f.starts_with("id:")).collect();
+        assert_eq!(events.len(), 2);
+        assert!(events[0].starts_with("id: exec_1#1\nevent: chunk\n"));
+        assert!(events[1].starts_with("id: exec_1#2\nevent: completed\n"));
+        assert!(hub.active().is_empty());
+    }
+}
@@ -0,0 +1,508 @@
+// Execution Store - 执行日志存储
+// 持久化完整的 ACSAExecutionLog，支持全文检索、标签和保留策略
+//
+// 核心功能：
+// 1. 每次执行一个 JSON 文件（先写临时文件再替换），启动时重建索引
+// 2. 全文检索：用户输入、最终输出和各 Agent 响应（英文按词、中日韩按单字 + 双字）
+// 3. 标签：执行时打标签，事后增删；`pinned` 标签不受保留策略清理
+// 4. 保留策略：按天数和条数上限清理最旧的记录
+// 5. HTTP 列表 / 详情接口与简单网页视图（见 http_server）
+//
+// 注：DatabaseManager 需要显式 connect，CLI 单次执行不依赖数据库，这里直接落盘到数据目录
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+use std::sync::RwLock;
+use tracing::{info, warn};
+
+use super::types::ACSAExecutionLog;
+
+/// 列表预览的最大字符数
+const PREVIEW_CHARS: usize = 120;
+
+/// 保留策略
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    /// 超过天数的记录被清理
+    pub max_age_days: Option<i64>,
+    /// 最多保留的记录数（超出时先清理最旧的）
+    pub max_records: Option<usize>,
+    /// 带这些标签的记录永不清理
+    pub keep_tags: Vec<String>,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            max_age_days: Some(90),
+            max_records: Some(10_000),
+            keep_tags: vec!["pinned".to_string()],
+        }
+    }
+}
+
+/// 已存储的执行记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionRecord {
+    pub id: String,
+    pub session_id: Option<String>,
+    pub tags: BTreeSet<String>,
+    pub recorded_at: DateTime<Utc>,
+    pub log: ACSAExecutionLog,
+}
+
+/// 列表项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionSummary {
+    pub id: String,
+    pub session_id: Option<String>,
+    pub tags: BTreeSet<String>,
+    pub started_at: DateTime<Utc>,
+    pub success: bool,
+    pub total_cost: f64,
+    pub total_time_ms: u64,
+    pub iterations: u32,
+    pub input_preview: String,
+    pub output_preview: Option<String>,
+    /// 全文检索相关度（无检索词时为 0）
+    pub score: u32,
+}
+
+impl ExecutionSummary {
+    fn new(record: &ExecutionRecord, score: u32) -> Self {
+        let log = &record.log;
+        Self {
+            id: record.id.clone(),
+            session_id: record.session_id.clone(),
+            tags: record.tags.clone(),
+            started_at: log.started_at,
+            success: log.success,
+            total_cost: log.total_cost,
+            total_time_ms: log.total_time_ms,
+            iterations: log.iterations,
+            input_preview: preview(&log.user_input),
+            output_preview: log.final_output.as_deref().map(preview),
+            score,
+        }
+    }
+}
+
+/// 检索条件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExecutionQuery {
+    /// 全文检索词（多个词需同时命中）
+    pub text: Option<String>,
+    /// 必须同时带有的标签
+    pub tags: Vec<String>,
+    pub success: Option<bool>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub limit: usize,
+    pub offset: usize,
+}
+
+impl Default for ExecutionQuery {
+    fn default() -> Self {
+        Self {
+            text: None,
+            tags: Vec::new(),
+            success: None,
+            since: None,
+            until: None,
+            limit: 20,
+            offset: 0,
+        }
+    }
+}
+
+/// 分页结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionPage {
+    /// 命中总数（分页前）
+    pub total: usize,
+    pub items: Vec<ExecutionSummary>,
+}
+
+#[derive(Default)]
+struct StoreIndex {
+    records: HashMap<String, ExecutionRecord>,
+    /// 词项 → (记录ID → 词频)
+    terms: HashMap<String, HashMap<String, u32>>,
+}
+
+impl StoreIndex {
+    fn insert(&mut self, record: ExecutionRecord) {
+        self.remove(&record.id);
+        for (term, count) in term_counts(&searchable_text(&record.log)) {
+            self.terms.entry(term).or_default().insert(record.id.clone(), count);
+        }
+        self.records.insert(record.id.clone(), record);
+    }
+
+    fn remove(&mut self, id: &str) -> Option<ExecutionRecord> {
+        let record = self.records.remove(id)?;
+        self.terms.retain(|_, postings| {
+            postings.remove(id);
+            !postings.is_empty()
+        });
+        Some(record)
+    }
+}
+
+/// 执行日志存储
+pub struct ExecutionStore {
+    dir: PathBuf,
+    retention: RetentionPolicy,
+    index: RwLock<StoreIndex>,
+}
+
+impl ExecutionStore {
+    /// 打开存储目录并重建索引
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        let mut index = StoreIndex::default();
+        if dir.exists() {
+            for entry in std::fs::read_dir(&dir)? {
+                let path = entry?.path();
+                if path.extension().is_none_or(|ext| ext != "json") {
+                    continue;
+                }
+                let content = std::fs::read_to_string(&path)?;
+                match serde_json::from_str::<ExecutionRecord>(&content) {
+                    Ok(record) => index.insert(record),
+                    Err(e) => warn!("⚠️  Skipping corrupted execution log {}: {}", path.display(), e),
+                }
+            }
+        }
+        info!("🗄️  Execution store opened: {} records ({})", index.records.len(), dir.display());
+
+        Ok(Self {
+            dir,
+            retention: RetentionPolicy::default(),
+            index: RwLock::new(index),
+        })
+    }
+
+    /// 使用自定义保留策略
+    pub fn with_retention(mut self, retention: RetentionPolicy) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.read().records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 保存一次执行，返回记录ID
+    pub fn record(&self, log: &ACSAExecutionLog, session_id: Option<&str>, tags: &[String]) -> Result<String> {
+        let id = {
+            let index = self.read();
+            let base = format!("exec_{}", log.started_at.timestamp_millis());
+            let mut id = base.clone();
+            let mut suffix = 1;
+            while index.records.contains_key(&id) {
+                id = format!("{}_{}", base, suffix);
+                suffix += 1;
+            }
+            id
+        };
+        let record = ExecutionRecord {
+            id: id.clone(),
+            session_id: session_id.map(str::to_string),
+            tags: normalize_tags(tags),
+            recorded_at: Utc::now(),
+            log: log.clone(),
+        };
+        self.persist(&record)?;
+        self.write().insert(record);
+
+        self.prune()?;
+        Ok(id)
+    }
+
+    pub fn get(&self, id: &str) -> Option<ExecutionRecord> {
+        self.read().records.get(id).cloned()
+    }
+
+    /// 检索：有检索词时按相关度排序，否则按时间倒序
+    pub fn search(&self, query: &ExecutionQuery) -> ExecutionPage {
+        let index = self.read();
+        let required_tags = normalize_tags(&query.tags);
+        let terms: Vec<String> = query
+            .text
+            .as_deref()
+            .map(|text| term_counts(text).into_keys().collect())
+            .unwrap_or_default();
+
+        let mut hits: Vec<(&ExecutionRecord, u32)> = index
+            .records
+            .values()
+            .filter(|r| required_tags.is_subset(&r.tags))
+            .filter(|r| query.success.is_none_or(|success| r.log.success == success))
+            .filter(|r| query.since.is_none_or(|since| r.log.started_at >= since))
+            .filter(|r| query.until.is_none_or(|until| r.log.started_at <= until))
+            .filter_map(|r| {
+                let mut score = 0;
+                for term in &terms {
+                    score += *index.terms.get(term)?.get(&r.id)?;
+                }
+                Some((r, score))
+            })
+            .collect();
+        hits.sort_by(|(a, a_score), (b, b_score)| {
+            b_score.cmp(a_score).then(b.log.started_at.cmp(&a.log.started_at))
+        });
+
+        ExecutionPage {
+            total: hits.len(),
+            items: hits
+                .into_iter()
+                .skip(query.offset)
+                .take(query.limit)
+                .map(|(record, score)| ExecutionSummary::new(record, score))
+                .collect(),
+        }
+    }
+
+    /// 增删标签
+    pub fn tag(&self, id: &str, add: &[String], remove: &[String]) -> Result<ExecutionRecord> {
+        let mut record = self.get(id).ok_or_else(|| anyhow!("Execution not found: {}", id))?;
+        record.tags.extend(normalize_tags(add));
+        for tag in normalize_tags(remove) {
+            record.tags.remove(&tag);
+        }
+        self.persist(&record)?;
+        self.write().insert(record.clone());
+        Ok(record)
+    }
+
+    /// 删除记录，返回之前是否存在
+    pub fn delete(&self, id: &str) -> Result<bool> {
+        let path = self.path(id)?;
+        if self.write().remove(id).is_none() {
+            return Ok(false);
+        }
+        if path.exists() {
+            std::fs::remove_file(&path)?;
+        }
+        Ok(true)
+    }
+
+    /// 按保留策略清理，返回清理条数
+    pub fn prune(&self) -> Result<usize> {
+        let keep: BTreeSet<String> = normalize_tags(&self.retention.keep_tags);
+        let expired: Vec<String> = {
+            let index = self.read();
+            let mut candidates: Vec<&ExecutionRecord> = index
+                .records
+                .values()
+                .filter(|r| r.tags.is_disjoint(&keep))
+                .collect();
+            candidates.sort_by_key(|r| r.log.started_at);
+
+            let cutoff = self.retention.max_age_days.map(|days| Utc::now() - Duration::days(days));
+            let overflow = self
+                .retention
+                .max_records
+                .map(|max| index.records.len().saturating_sub(max))
+                .unwrap_or(0);
+            candidates
+                .iter()
+                .enumerate()
+                .filter(|(i, r)| *i < overflow || cutoff.is_some_and(|cutoff| r.log.started_at < cutoff))
+                .map(|(_, r)| r.id.clone())
+                .collect()
+        };
+
+        for id in &expired {
+            self.delete(id)?;
+        }
+        if !expired.is_empty() {
+            info!("🧹 Pruned {} execution logs", expired.len());
+        }
+        Ok(expired.len())
+    }
+
+    fn path(&self, id: &str) -> Result<PathBuf> {
+        // 记录ID直接作为文件名，拒绝路径穿越
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+            return Err(anyhow!("Invalid execution id: {:?}", id));
+        }
+        Ok(self.dir.join(format!("{}.json", id)))
+    }
+
+    fn persist(&self, record: &ExecutionRecord) -> Result<()> {
+        let path = self.path(&record.id)?;
+        std::fs::create_dir_all(&self.dir)?;
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(record)?)?;
+        std::fs::rename(&tmp, &path).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, StoreIndex> {
+        self.index.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, StoreIndex> {
+        self.index.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// 参与全文检索的文本：输入、输出和各 Agent 响应
+fn searchable_text(log: &ACSAExecutionLog) -> String {
+    [&log.moss_plan, &log.l6_verification, &log.ultron_audit, &log.omega_execution]
+        .into_iter()
+        .flatten()
+        .map(|response| response.text.as_str())
+        .chain([log.user_input.as_str(), log.final_output.as_deref().unwrap_or_default()])
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// 分词并统计词频：英文数字按词，中日韩文字按单字 + 相邻双字
+fn term_counts(text: &str) -> HashMap<String, u32> {
+    let mut counts = HashMap::new();
+    let mut word = String::new();
+    let mut previous_cjk: Option<char> = None;
+
+    for c in text.to_lowercase().chars() {
+        if is_cjk(c) {
+            flush_word(&mut word, &mut counts);
+            *counts.entry(c.to_string()).or_insert(0) += 1;
+            if let Some(previous) = previous_cjk {
+                *counts.entry(format!("{}{}", previous, c)).or_insert(0) += 1;
+            }
+            previous_cjk = Some(c);
+        } else {
+            previous_cjk = None;
+            if c.is_alphanumeric() {
+                word.push(c);
+            } else {
+                flush_word(&mut word, &mut counts);
+            }
+        }
+    }
+    flush_word(&mut word, &mut counts);
+    counts
+}
+
+fn flush_word(word: &mut String, counts: &mut HashMap<String, u32>) {
+    if !word.is_empty() {
+        *counts.entry(std::mem::take(word)).or_insert(0) += 1;
+    }
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32, 0x3040..=0x30FF | 0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xAC00..=0xD7AF | 0xF900..=0xFAFF)
+}
+
+fn normalize_tags(tags: &[String]) -> BTreeSet<String> {
+    tags.iter()
+        .map(|tag| tag.trim().to_lowercase())
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+fn preview(text: &str) -> String {
+    let line = text.lines().find(|line| !line.trim().is_empty()).unwrap_or_default().trim();
+    if line.chars().count() > PREVIEW_CHARS {
+        format!("{}…", line.chars().take(PREVIEW_CHARS).collect::<String>())
+    } else {
+        line.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::types::{AgentResponse, AgentRole};
+    use tempfile::TempDir;
+
+    fn execution(input: &str, output: &str, success: bool, age_days: i64) -> ACSAExecutionLog {
+        let mut log = ACSAExecutionLog::new(input.to_string());
+        log.started_at = Utc::now() - Duration::days(age_days);
+        log.omega_execution = Some(AgentResponse {
+            role: AgentRole::Omega,
+            text: output.to_string(),
+            tokens: 10,
+            cost: 0.01,
+            latency_ms: 5,
+            timestamp: log.started_at,
+            metadata: HashMap::new(),
+        });
+        log.final_output = Some(output.to_string());
+        log.success = success;
+        log
+    }
+
+    #[test]
+    fn test_search_tags_and_reload() {
+        let dir = TempDir::new().unwrap();
+        let store = ExecutionStore::open(dir.path()).unwrap();
+
+        let scraper = store
+            .record(&execution("Write a web scraper", "Use reqwest and a rate limiter", true, 2), Some("s1"), &["Demo".to_string()])
+            .unwrap();
+        store.record(&execution("写一个天气爬虫", "先检查网站的爬虫协议", true, 1), None, &[]).unwrap();
+        store.record(&execution("Plan a product launch", "Launch failed review", false, 0), None, &[]).unwrap();
+
+        let hits = store.search(&ExecutionQuery { text: Some("scraper".to_string()), ..Default::default() });
+        assert_eq!(hits.total, 1);
+        assert_eq!(hits.items[0].id, scraper);
+        assert_eq!(hits.items[0].tags, BTreeSet::from(["demo".to_string()]));
+
+        let hits = store.search(&ExecutionQuery { text: Some("爬虫".to_string()), ..Default::default() });
+        assert_eq!(hits.total, 1);
+        assert!(hits.items[0].score > 0);
+
+        // 多个词需同时命中；不带检索词时按时间倒序
+        assert_eq!(store.search(&ExecutionQuery { text: Some("scraper launch".to_string()), ..Default::default() }).total, 0);
+        let all = store.search(&ExecutionQuery::default());
+        assert_eq!(all.items[0].input_preview, "Plan a product launch");
+        assert_eq!(store.search(&ExecutionQuery { success: Some(false), ..Default::default() }).total, 1);
+
+        store.tag(&scraper, &["pinned".to_string()], &["demo".to_string()]).unwrap();
+        let reopened = ExecutionStore::open(dir.path()).unwrap();
+        assert_eq!(reopened.len(), 3);
+        let pinned = reopened.search(&ExecutionQuery { tags: vec!["Pinned".to_string()], ..Default::default() });
+        assert_eq!(pinned.items[0].id, scraper);
+        assert_eq!(reopened.get(&scraper).unwrap().log.final_output.as_deref(), Some("Use reqwest and a rate limiter"));
+    }
+
+    #[test]
+    fn test_retention_keeps_pinned_records() {
+        let dir = TempDir::new().unwrap();
+        let store = ExecutionStore::open(dir.path()).unwrap().with_retention(RetentionPolicy {
+            max_age_days: Some(30),
+            max_records: Some(2),
+            ..Default::default()
+        });
+
+        let old_pinned = store.record(&execution("old pinned", "ok", true, 60), None, &["pinned".to_string()]).unwrap();
+        // 超过 30 天：写入后立即被清理
+        store.record(&execution("old", "ok", true, 45), None, &[]).unwrap();
+        assert_eq!(store.len(), 1);
+
+        store.record(&execution("first", "ok", true, 3), None, &[]).unwrap();
+        store.record(&execution("second", "ok", true, 2), None, &[]).unwrap();
+        // 超过条数上限：清理最旧的未置顶记录
+        let inputs: BTreeSet<_> = store
+            .search(&ExecutionQuery::default())
+            .items
+            .into_iter()
+            .map(|s| s.input_preview)
+            .collect();
+        assert_eq!(inputs, BTreeSet::from(["old pinned".to_string(), "second".to_string()]));
+        assert!(store.get(&old_pinned).is_some());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+        assert!(!store.delete("exec_missing").unwrap());
+    }
+}
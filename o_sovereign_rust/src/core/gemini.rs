@@ -1,9 +1,13 @@
 // Gemini Provider - L6's Brain
 // 物理法则校验器
 
+use super::api_manager::{self, ApiProvider};
+use super::determinism;
+use super::error::{AcsaError, ErrorCode};
 use super::providers::ModelProvider;
+use super::retry;
 use super::types::{AgentResponse, AgentRole, AgentStats};
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use async_trait::async_trait;
 use chrono::Utc;
 use reqwest::Client;
@@ -37,6 +41,8 @@ struct GenerationConfig {
     temperature: f32,
     #[serde(rename = "maxOutputTokens")]
     max_output_tokens: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<i32>,
 }
 
 /// Gemini API响应
@@ -54,6 +60,10 @@ struct GeminiCandidate {
 
 #[derive(Debug, Deserialize)]
 struct UsageMetadata {
+    #[serde(rename = "promptTokenCount", default)]
+    prompt_token_count: u32,
+    #[serde(rename = "candidatesTokenCount", default)]
+    candidates_token_count: u32,
     #[serde(rename = "totalTokenCount")]
     total_token_count: u32,
 }
@@ -120,6 +130,8 @@ impl ModelProvider for GeminiProvider {
             generation_config: GenerationConfig {
                 temperature: temperature as f32,
                 max_output_tokens: max_tokens,
+                // Gemini 的 seed 为 int32
+                seed: determinism::seed().map(|s| (s & i32::MAX as u64) as i32),
             },
         };
 
@@ -128,20 +140,29 @@ impl ModelProvider for GeminiProvider {
             self.model, self.api_key
         );
 
-        let response = self
-            .client
-            .post(&url)
-            .header("content-type", "application/json")
-            .json(&request)
-            .send()
+        let gemini_response: GeminiResponse = retry::current()
+            .run("Gemini", || async {
+                let response = self
+                    .client
+                    .post(&url)
+                    .header("content-type", "application/json")
+                    .json(&request)
+                    .send()
+                    .await?;
+
+                let status = response.status();
+                if !status.is_success() {
+                    let error_text = response.text().await?;
+                    return Err(AcsaError::new(
+                        ErrorCode::from_http_status(status.as_u16()),
+                        format!("Gemini API error ({}): {}", status, error_text),
+                    )
+                    .into());
+                }
+
+                Ok(response.json().await?)
+            })
             .await?;
-
-        if !response.status().is_success() {
-            let error_text = response.text().await?;
-            return Err(anyhow!("Gemini API error: {}", error_text));
-        }
-
-        let gemini_response: GeminiResponse = response.json().await?;
         let latency_ms = start.elapsed().as_millis() as u64;
 
         let text = gemini_response
@@ -151,13 +172,12 @@ impl ModelProvider for GeminiProvider {
             .map(|p| p.text.clone())
             .unwrap_or_default();
 
-        let tokens = gemini_response
+        let (tokens, prompt_tokens, completion_tokens) = gemini_response
             .usage_metadata
-            .map(|u| u.total_token_count)
-            .unwrap_or(0);
+            .map(|u| (u.total_token_count, u.prompt_token_count, u.candidates_token_count))
+            .unwrap_or((0, 0, 0));
 
-        // Gemini pricing: ~$0.50/1M tokens (Pro)
-        let cost = (tokens as f64 / 1_000_000.0) * 0.50;
+        let cost = api_manager::estimate_cost(ApiProvider::Gemini, &self.model, prompt_tokens, completion_tokens);
 
         let mut stats = self.stats.lock().await;
         stats.record_success(tokens, cost, latency_ms);
@@ -183,6 +203,14 @@ impl ModelProvider for GeminiProvider {
         self.role
     }
 
+    fn model_id(&self) -> String {
+        format!("gemini/{}", self.model)
+    }
+
+    fn billing(&self) -> Option<ApiProvider> {
+        Some(ApiProvider::Gemini)
+    }
+
     async fn stats(&self) -> AgentStats {
         self.stats.lock().await.clone()
     }
@@ -0,0 +1,444 @@
+// Git Workflow - Omega改动的版本管理
+// 每个任务一个工作分支（独立 worktree），每轮迭代一次提交，可选自动开 PR/MR
+//
+// 核心功能：
+// 1. 任务分支：`acsa/<task_id>`，在独立 worktree 中修改，不碰用户当前工作区
+// 2. 迭代提交：提交信息由任务意图 + CodeStats 差异摘要 + 测试结果生成
+// 3. PR/MR：通过 GitHub / GitLab API 创建，方便人工审阅
+
+use anyhow::{anyhow, bail, Context, Result};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+use tracing::{debug, info};
+
+use super::opencode_connector::{CodeStats, ExecutionReceipt, MissionPack};
+
+/// 代码托管平台
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GitForge {
+    GitHub,
+    GitLab,
+}
+
+/// PR/MR 配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForgeConfig {
+    pub forge: GitForge,
+    /// API 根地址（如 https://api.github.com、https://gitlab.com/api/v4）
+    pub api_base: String,
+    /// GitHub 为 `owner/repo`，GitLab 为项目 ID 或 URL 编码的路径
+    pub repository: String,
+    /// API Token（建议用 `${secret:...}` 引用）
+    pub token: String,
+    /// 推送使用的远端
+    pub remote: String,
+    #[serde(default)]
+    pub draft: bool,
+}
+
+/// Git 工作流配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitWorkflowConfig {
+    /// 用户仓库根目录
+    pub repo_path: PathBuf,
+    /// 任务 worktree 的存放目录
+    pub worktree_root: PathBuf,
+    /// 分支前缀
+    pub branch_prefix: String,
+    /// 提交作者
+    pub author_name: String,
+    pub author_email: String,
+    /// 配置后在任务完成时开 PR/MR
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forge: Option<ForgeConfig>,
+}
+
+impl GitWorkflowConfig {
+    pub fn new(repo_path: impl Into<PathBuf>) -> Self {
+        let repo_path = repo_path.into();
+        Self {
+            // 放在 .git 下，不会出现在用户的 git status 中
+            worktree_root: repo_path.join(".git").join("acsa-worktrees"),
+            repo_path,
+            branch_prefix: "acsa/".to_string(),
+            author_name: "ACSA Omega".to_string(),
+            author_email: "omega@acsa.local".to_string(),
+            forge: None,
+        }
+    }
+}
+
+/// 任务分支
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MissionBranch {
+    pub branch: String,
+    /// 分支起点（创建时的 HEAD 分支）
+    pub base: String,
+    /// 该分支的 worktree 路径
+    pub worktree: PathBuf,
+}
+
+/// 一次迭代提交
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IterationCommit {
+    pub sha: String,
+    pub iteration: u32,
+    pub message: String,
+    /// 由 `git diff --numstat` 得到的真实统计
+    pub stats: CodeStats,
+}
+
+/// Git 工作流管理器
+pub struct GitWorkflow {
+    config: GitWorkflowConfig,
+    client: Client,
+}
+
+impl GitWorkflow {
+    pub fn new(config: GitWorkflowConfig) -> Self {
+        Self {
+            config,
+            client: Client::new(),
+        }
+    }
+
+    pub fn config(&self) -> &GitWorkflowConfig {
+        &self.config
+    }
+
+    /// 创建（或复用）任务分支及其 worktree
+    pub async fn prepare_mission(&self, mission: &MissionPack) -> Result<MissionBranch> {
+        let branch = format!("{}{}", self.config.branch_prefix, sanitize_ref(&mission.task_id));
+        let worktree = self.config.worktree_root.join(sanitize_ref(&mission.task_id));
+        let base = git(&self.config.repo_path, &["rev-parse", "--abbrev-ref", "HEAD"]).await?;
+
+        if worktree.join(".git").exists() {
+            debug!("🌿 Reusing worktree {:?}", worktree);
+        } else {
+            tokio::fs::create_dir_all(&self.config.worktree_root).await?;
+            let worktree_str = worktree.display().to_string();
+            let exists = git(&self.config.repo_path, &["rev-parse", "--verify", "--quiet", &branch])
+                .await
+                .is_ok();
+
+            if exists {
+                git(&self.config.repo_path, &["worktree", "add", &worktree_str, &branch]).await?;
+            } else {
+                git(&self.config.repo_path, &["worktree", "add", "-b", &branch, &worktree_str, &base]).await?;
+            }
+            info!("🌿 Mission branch {} created at {:?}", branch, worktree);
+        }
+
+        Ok(MissionBranch { branch, base, worktree })
+    }
+
+    /// 让任务在 worktree 中执行
+    pub fn scoped_mission(&self, mission: &MissionPack, branch: &MissionBranch) -> MissionPack {
+        let mut scoped = mission.clone();
+        scoped.target_path = branch.worktree.join(
+            mission
+                .target_path
+                .strip_prefix(&self.config.repo_path)
+                .unwrap_or(Path::new("")),
+        );
+        scoped
+    }
+
+    /// 提交本轮迭代，没有改动时返回 None
+    pub async fn commit_iteration(
+        &self,
+        branch: &MissionBranch,
+        mission: &MissionPack,
+        receipt: &ExecutionReceipt,
+    ) -> Result<Option<IterationCommit>> {
+        let dir = &branch.worktree;
+        git(dir, &["add", "-A"]).await?;
+
+        let numstat = git(dir, &["diff", "--cached", "--numstat"]).await?;
+        if numstat.is_empty() {
+            debug!("🌿 No changes to commit for {}", mission.task_id);
+            return Ok(None);
+        }
+
+        let range = format!("{}..HEAD", branch.base);
+        let iteration = git(dir, &["rev-list", "--count", &range])
+            .await?
+            .parse::<u32>()
+            .unwrap_or(0)
+            + 1;
+
+        let mut stats = diff_stats(&numstat);
+        stats.test_results = receipt.test_results().cloned();
+        let message = commit_message(mission, iteration, &stats);
+
+        let name = format!("user.name={}", self.config.author_name);
+        let email = format!("user.email={}", self.config.author_email);
+        git(dir, &["-c", &name, "-c", &email, "commit", "--no-verify", "-q", "-m", &message]).await?;
+        let sha = git(dir, &["rev-parse", "HEAD"]).await?;
+
+        info!("🌿 Iteration {} committed: {}", iteration, &sha[..sha.len().min(8)]);
+        Ok(Some(IterationCommit {
+            sha,
+            iteration,
+            message,
+            stats,
+        }))
+    }
+
+    /// 推送分支并创建 PR/MR，返回网页地址
+    pub async fn open_pull_request(&self, branch: &MissionBranch, mission: &MissionPack) -> Result<String> {
+        let forge = self
+            .config
+            .forge
+            .as_ref()
+            .ok_or_else(|| anyhow!("No forge configured for pull requests"))?;
+
+        git(&branch.worktree, &["push", "-u", &forge.remote, &branch.branch]).await?;
+
+        let (url, body) = pull_request_payload(forge, branch, mission);
+        let mut request = self.client.post(&url).json(&body);
+        request = match forge.forge {
+            GitForge::GitHub => request
+                .bearer_auth(&forge.token)
+                .header("Accept", "application/vnd.github+json")
+                .header("User-Agent", "o-sovereign"),
+            GitForge::GitLab => request.header("PRIVATE-TOKEN", &forge.token),
+        };
+
+        let response = request.send().await?;
+        let status = response.status();
+        let payload: Value = response.json().await.unwrap_or(Value::Null);
+        if !status.is_success() {
+            bail!("Failed to open pull request ({}): {}", status, payload);
+        }
+
+        let web_url = payload["html_url"]
+            .as_str()
+            .or_else(|| payload["web_url"].as_str())
+            .unwrap_or_default()
+            .to_string();
+        info!("📬 Pull request opened: {}", web_url);
+        Ok(web_url)
+    }
+
+    /// 清理 worktree（分支保留以便审阅）
+    pub async fn remove_worktree(&self, branch: &MissionBranch) -> Result<()> {
+        let worktree = branch.worktree.display().to_string();
+        git(&self.config.repo_path, &["worktree", "remove", "--force", &worktree]).await?;
+        Ok(())
+    }
+}
+
+/// PR/MR 请求地址与请求体
+fn pull_request_payload(forge: &ForgeConfig, branch: &MissionBranch, mission: &MissionPack) -> (String, Value) {
+    let api = forge.api_base.trim_end_matches('/');
+    let title = format!("[ACSA] {}", first_line(&mission.intent));
+    let description = format!(
+        "Automated changes by ACSA Omega for mission `{}`.\n\n**Intent:** {}\n\n**Constraints:**\n{}",
+        mission.task_id,
+        mission.intent,
+        mission
+            .constraints
+            .iter()
+            .map(|c| format!("- {}", c))
+            .collect::<Vec<_>>()
+            .join("\n")
+    );
+
+    match forge.forge {
+        GitForge::GitHub => (
+            format!("{}/repos/{}/pulls", api, forge.repository),
+            json!({
+                "title": title,
+                "head": branch.branch,
+                "base": branch.base,
+                "body": description,
+                "draft": forge.draft,
+            }),
+        ),
+        GitForge::GitLab => (
+            format!("{}/projects/{}/merge_requests", api, forge.repository.replace('/', "%2F")),
+            json!({
+                "title": if forge.draft { format!("Draft: {}", title) } else { title },
+                "source_branch": branch.branch,
+                "target_branch": branch.base,
+                "description": description,
+            }),
+        ),
+    }
+}
+
+/// 提交信息：意图摘要 + 差异统计 + 测试结果
+fn commit_message(mission: &MissionPack, iteration: u32, stats: &CodeStats) -> String {
+    let mut message = format!(
+        "[{}] Iteration {}: {}\n\n{} files changed, +{} -{}",
+        mission.task_id,
+        iteration,
+        first_line(&mission.intent),
+        stats.files_modified,
+        stats.lines_added,
+        stats.lines_removed
+    );
+
+    if let Some(tests) = &stats.test_results {
+        message.push_str(&format!(
+            "\nTests: {} passed, {} failed, {} skipped",
+            tests.passed, tests.failed, tests.skipped
+        ));
+    }
+    message
+}
+
+/// 解析 `git diff --numstat`（二进制文件计入文件数，不计行数）
+fn diff_stats(numstat: &str) -> CodeStats {
+    let mut stats = CodeStats {
+        lines_added: 0,
+        lines_removed: 0,
+        files_modified: 0,
+        test_results: None,
+    };
+
+    for line in numstat.lines() {
+        let mut parts = line.split('\t');
+        let added = parts.next().and_then(|n| n.parse::<u32>().ok()).unwrap_or(0);
+        let removed = parts.next().and_then(|n| n.parse::<u32>().ok()).unwrap_or(0);
+        stats.lines_added += added;
+        stats.lines_removed += removed;
+        stats.files_modified += 1;
+    }
+    stats
+}
+
+fn first_line(text: &str) -> String {
+    let line = text.lines().next().unwrap_or_default().trim();
+    match line.char_indices().nth(72) {
+        Some((idx, _)) => format!("{}…", &line[..idx]),
+        None => line.to_string(),
+    }
+}
+
+/// 任务 ID → 合法的分支/目录名
+fn sanitize_ref(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '-' })
+        .collect()
+}
+
+/// 执行 git 命令并返回去掉首尾空白的 stdout
+async fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .await
+        .context("Failed to execute git - is it installed?")?;
+
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn init_repo(dir: &Path) {
+        git(dir, &["init", "-q", "-b", "main"]).await.unwrap();
+        tokio::fs::write(dir.join("lib.rs"), "fn a() {}\n").await.unwrap();
+        git(dir, &["add", "-A"]).await.unwrap();
+        git(dir, &["-c", "user.name=t", "-c", "user.email=t@t", "commit", "-q", "-m", "init"])
+            .await
+            .unwrap();
+    }
+
+    fn receipt(task_id: &str) -> ExecutionReceipt {
+        ExecutionReceipt {
+            task_id: task_id.to_string(),
+            success: true,
+            modified_files: Vec::new(),
+            created_files: Vec::new(),
+            execution_log: String::new(),
+            error_message: None,
+            elapsed_ms: 0,
+            stats: None,
+            branch: None,
+            commit_sha: None,
+            file_diffs: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mission_branch_and_iteration_commits() {
+        let repo = tempfile::tempdir().unwrap();
+        init_repo(repo.path()).await;
+
+        let workflow = GitWorkflow::new(GitWorkflowConfig::new(repo.path()));
+        let mission = MissionPack::new("task 42".to_string(), "Add b()".to_string(), repo.path().to_path_buf());
+
+        let branch = workflow.prepare_mission(&mission).await.unwrap();
+        assert_eq!(branch.branch, "acsa/task-42");
+        assert_eq!(branch.base, "main");
+
+        let scoped = workflow.scoped_mission(&mission, &branch);
+        assert_eq!(scoped.target_path, branch.worktree);
+
+        // 没有改动不提交
+        assert!(workflow.commit_iteration(&branch, &scoped, &receipt("task 42")).await.unwrap().is_none());
+
+        tokio::fs::write(branch.worktree.join("lib.rs"), "fn a() {}\nfn b() {}\n").await.unwrap();
+        let first = workflow.commit_iteration(&branch, &scoped, &receipt("task 42")).await.unwrap().unwrap();
+        assert_eq!(first.iteration, 1);
+        assert_eq!((first.stats.files_modified, first.stats.lines_added), (1, 1));
+        assert!(first.message.starts_with("[task 42] Iteration 1: Add b()"));
+
+        tokio::fs::write(branch.worktree.join("lib.rs"), "fn b() {}\n").await.unwrap();
+        let second = workflow.commit_iteration(&branch, &scoped, &receipt("task 42")).await.unwrap().unwrap();
+        assert_eq!(second.iteration, 2);
+        assert_eq!(second.stats.lines_removed, 1);
+
+        // 用户工作区保持不变
+        let original = tokio::fs::read_to_string(repo.path().join("lib.rs")).await.unwrap();
+        assert_eq!(original, "fn a() {}\n");
+        assert_eq!(git(repo.path(), &["rev-parse", "--abbrev-ref", "HEAD"]).await.unwrap(), "main");
+    }
+
+    #[test]
+    fn test_pull_request_payloads() {
+        let branch = MissionBranch {
+            branch: "acsa/t1".to_string(),
+            base: "main".to_string(),
+            worktree: PathBuf::from("/tmp/wt"),
+        };
+        let mission = MissionPack::new("t1".to_string(), "Refactor auth".to_string(), PathBuf::from("/tmp"));
+        let mut forge = ForgeConfig {
+            forge: GitForge::GitHub,
+            api_base: "https://api.github.com/".to_string(),
+            repository: "acme/app".to_string(),
+            token: "t".to_string(),
+            remote: "origin".to_string(),
+            draft: true,
+        };
+
+        let (url, body) = pull_request_payload(&forge, &branch, &mission);
+        assert_eq!(url, "https://api.github.com/repos/acme/app/pulls");
+        assert_eq!(body["head"], "acsa/t1");
+        assert_eq!(body["draft"], true);
+
+        forge.forge = GitForge::GitLab;
+        forge.api_base = "https://gitlab.com/api/v4".to_string();
+        let (url, body) = pull_request_payload(&forge, &branch, &mission);
+        assert_eq!(url, "https://gitlab.com/api/v4/projects/acme%2Fapp/merge_requests");
+        assert_eq!(body["title"], "Draft: [ACSA] Refactor auth");
+        assert_eq!(body["target_branch"], "main");
+    }
+}
@@ -0,0 +1,573 @@
+// gRPC Server - gRPC API（与 HTTP 接口共用处理函数）
+// 服务定义见 proto/acsa/v1/acsa.proto，Go / Java 等语言可直接生成客户端
+//
+// 核心功能：
+// 1. Execute：一元调用，执行完整链路并记录执行
+// 2. ExecuteStream：服务端流，逐个推送 Agent 输出片段，最后一条为执行结果
+// 3. GetExecutionLog / ListProtocols / Health：与对应 HTTP 接口相同的数据
+// 4. 认证、权限、维护模式与预算检查沿用 http_server；错误映射为 gRPC 状态码
+//
+// 5. grpc 特性（默认启用）：tonic 生成的 Acsa 服务把请求转交 AcsaGrpcService 的同名方法，
+//    由 HttpServer::start 在 HttpServerConfig::grpc_port 上与 HTTP 一同启动
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+use tracing::{info, warn};
+
+use super::auth_system::{Claims, Permission};
+use super::error::AcsaError;
+use super::execution_store::ExecutionRecord;
+use super::http_server::{
+    admit_execution, attribute_key_usage, health_check, list_protocols_handler, ProtocolInfo, ServerState,
+};
+use super::metrics::HealthStatus;
+use super::types::{ACSAExecutionLog, AgentChunk};
+use super::workspace::TenantId;
+
+/// gRPC 服务全名（proto package + service）
+pub const GRPC_SERVICE_NAME: &str = "acsa.v1.Acsa";
+
+/// 方法所需权限（None 为公开方法）
+pub const GRPC_METHOD_PERMISSIONS: &[(&str, Option<Permission>)] = &[
+    ("Execute", Some(Permission::Execute)),
+    ("ExecuteStream", Some(Permission::Execute)),
+    ("GetExecutionLog", Some(Permission::Read)),
+    ("ListProtocols", Some(Permission::Read)),
+    ("Health", None),
+];
+
+/// gRPC 状态码（只列出本服务会返回的）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GrpcCode {
+    InvalidArgument = 3,
+    DeadlineExceeded = 4,
+    NotFound = 5,
+    PermissionDenied = 7,
+    ResourceExhausted = 8,
+    FailedPrecondition = 9,
+    Internal = 13,
+    Unavailable = 14,
+    Unauthenticated = 16,
+}
+
+impl GrpcCode {
+    /// HTTP 状态码 → gRPC 状态码（与 HTTP 接口的错误一一对应）
+    pub fn from_http(status: u16) -> Self {
+        match status {
+            400 => GrpcCode::InvalidArgument,
+            401 => GrpcCode::Unauthenticated,
+            402 => GrpcCode::FailedPrecondition,
+            403 => GrpcCode::PermissionDenied,
+            404 => GrpcCode::NotFound,
+            429 => GrpcCode::ResourceExhausted,
+            502 | 503 => GrpcCode::Unavailable,
+            504 => GrpcCode::DeadlineExceeded,
+            _ => GrpcCode::Internal,
+        }
+    }
+}
+
+/// gRPC 错误状态
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GrpcStatus {
+    pub code: GrpcCode,
+    pub message: String,
+}
+
+impl GrpcStatus {
+    pub fn new(code: GrpcCode, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+
+    /// AcsaError 按其 HTTP 状态码映射，其他错误为 INTERNAL
+    pub fn from_error(error: anyhow::Error) -> Self {
+        match error.downcast::<AcsaError>() {
+            Ok(acsa) => Self::new(GrpcCode::from_http(acsa.to_report().code.http_status()), acsa.user_message("en")),
+            Err(e) => Self::new(GrpcCode::Internal, e.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for GrpcStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}: {}", self.code, self.message)
+    }
+}
+
+impl std::error::Error for GrpcStatus {}
+
+/// Execute / ExecuteStream 请求
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExecuteRequest {
+    pub input: String,
+    /// 附加到执行记录的标签（总会带上 "grpc"）
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// 执行结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExecuteReply {
+    /// 执行记录 ID（记录失败时为 None）
+    pub execution_id: Option<String>,
+    pub success: bool,
+    pub final_output: Option<String>,
+    pub total_cost: f64,
+    pub iterations: u32,
+    pub total_time_ms: u64,
+}
+
+impl ExecuteReply {
+    fn from_log(log: &ACSAExecutionLog, execution_id: Option<String>) -> Self {
+        Self {
+            execution_id,
+            success: log.success,
+            final_output: log.final_output.clone(),
+            total_cost: log.total_cost,
+            iterations: log.iterations,
+            total_time_ms: log.total_time_ms,
+        }
+    }
+}
+
+/// ExecuteStream 的一条消息
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteEvent {
+    Chunk(AgentChunk),
+    Result(ExecuteReply),
+}
+
+/// GetExecutionLog 响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionLogReply {
+    pub execution_id: String,
+    pub tags: Vec<String>,
+    /// RFC 3339
+    pub recorded_at: String,
+    /// 完整执行日志 JSON
+    pub log_json: String,
+}
+
+impl ExecutionLogReply {
+    fn from_record(record: &ExecutionRecord) -> Result<Self> {
+        Ok(Self {
+            execution_id: record.id.clone(),
+            tags: record.tags.iter().cloned().collect(),
+            recorded_at: record.recorded_at.to_rfc3339(),
+            log_json: serde_json::to_string(&record.log)?,
+        })
+    }
+}
+
+/// 组件健康状态
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComponentHealthReply {
+    pub name: String,
+    /// healthy / degraded / unhealthy
+    pub status: String,
+    pub details: Option<String>,
+}
+
+/// Health 响应（降级仍算 SERVING：只读方法可用）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HealthReply {
+    pub serving: bool,
+    pub version: String,
+    pub uptime_secs: u64,
+    pub components: Vec<ComponentHealthReply>,
+}
+
+fn status_label(status: HealthStatus) -> &'static str {
+    match status {
+        HealthStatus::Healthy => "healthy",
+        HealthStatus::Degraded => "degraded",
+        HealthStatus::Unhealthy => "unhealthy",
+    }
+}
+
+/// 方法级权限校验：公开方法直接放行，未登记的方法一律拒绝
+pub fn authorize_method(claims: Option<&Claims>, method: &str) -> std::result::Result<(), GrpcStatus> {
+    let permission = GRPC_METHOD_PERMISSIONS
+        .iter()
+        .find(|(name, _)| *name == method)
+        .map(|(_, permission)| *permission)
+        .ok_or_else(|| GrpcStatus::new(GrpcCode::PermissionDenied, format!("No permission rule for {}", method)))?;
+    let Some(permission) = permission else {
+        return Ok(());
+    };
+    let claims =
+        claims.ok_or_else(|| GrpcStatus::new(GrpcCode::Unauthenticated, "Missing bearer token or API key"))?;
+    claims.require(permission).map_err(GrpcStatus::from_error)
+}
+
+/// gRPC 服务实现（与 HTTP 接口共享 ServerState 与处理函数）
+pub struct AcsaGrpcService {
+    state: Arc<ServerState>,
+}
+
+impl AcsaGrpcService {
+    pub fn new(state: Arc<ServerState>) -> Self {
+        info!("🛰️  Initializing gRPC service {}", GRPC_SERVICE_NAME);
+        Self { state }
+    }
+
+    /// 执行完整链路
+    pub async fn execute(&self, claims: &Claims, request: ExecuteRequest) -> std::result::Result<ExecuteReply, GrpcStatus> {
+        let input = self.admit(claims, &request).await?;
+        let log = self.state.router.execute(input.clone()).await.map_err(GrpcStatus::from_error)?;
+        let execution_id = record_execution(&self.state, &log, &request.tags);
+        attribute_key_usage(&self.state, &TenantId::from_claims(claims), claims.api_key_id.as_deref(), &input, &log).await;
+        Ok(ExecuteReply::from_log(&log, execution_id))
+    }
+
+    /// 执行完整链路并流式推送；客户端断开后执行照常完成并记录
+    pub async fn execute_stream(
+        &self,
+        claims: &Claims,
+        request: ExecuteRequest,
+    ) -> std::result::Result<UnboundedReceiver<std::result::Result<ExecuteEvent, GrpcStatus>>, GrpcStatus> {
+        let input = self.admit(claims, &request).await?;
+        let (events, receiver) = mpsc::unbounded_channel();
+        let state = self.state.clone();
+        let (tenant, api_key_id) = (TenantId::from_claims(claims), claims.api_key_id.clone());
+        tokio::spawn(async move {
+            let (mut chunks, handle) = state.router.execute_streaming(input.clone());
+            while let Some(chunk) = chunks.recv().await {
+                // 订阅者已断开时忽略，执行照常完成
+                let _ = events.send(Ok(ExecuteEvent::Chunk(chunk)));
+            }
+
+            let last = match handle.await.map_err(anyhow::Error::from).and_then(|r| r) {
+                Ok(log) => {
+                    let execution_id = record_execution(&state, &log, &request.tags);
+                    attribute_key_usage(&state, &tenant, api_key_id.as_deref(), &input, &log).await;
+                    Ok(ExecuteEvent::Result(ExecuteReply::from_log(&log, execution_id)))
+                }
+                Err(e) => Err(GrpcStatus::from_error(e)),
+            };
+            let _ = events.send(last);
+        });
+        Ok(receiver)
+    }
+
+    /// 按执行记录 ID 取完整执行日志
+    pub async fn get_execution_log(&self, claims: &Claims, execution_id: &str) -> std::result::Result<ExecutionLogReply, GrpcStatus> {
+        authorize_method(Some(claims), "GetExecutionLog")?;
+        let record = self
+            .state
+            .executions
+            .get(execution_id)
+            .ok_or_else(|| GrpcStatus::new(GrpcCode::NotFound, format!("Execution not found: {}", execution_id)))?;
+        ExecutionLogReply::from_record(&record).map_err(GrpcStatus::from_error)
+    }
+
+    /// 内置与自定义协议
+    pub async fn list_protocols(&self, claims: &Claims) -> std::result::Result<Vec<ProtocolInfo>, GrpcStatus> {
+        authorize_method(Some(claims), "ListProtocols")?;
+        Ok(list_protocols_handler(self.state.clone()).await.data.unwrap_or_default())
+    }
+
+    /// 组件健康状态（公开）
+    pub async fn health(&self) -> HealthReply {
+        let health = health_check(&self.state).await;
+        let mut components: Vec<ComponentHealthReply> = health
+            .components
+            .into_values()
+            .map(|component| ComponentHealthReply {
+                name: component.name,
+                status: status_label(component.status).to_string(),
+                details: component.details,
+            })
+            .collect();
+        components.sort_by(|a, b| a.name.cmp(&b.name));
+        HealthReply {
+            serving: health.status != HealthStatus::Unhealthy,
+            version: health.version,
+            uptime_secs: health.uptime_secs,
+            components,
+        }
+    }
+
+    /// 执行前检查（与 HTTP 接口相同），返回 Router 输入
+    async fn admit(&self, claims: &Claims, request: &ExecuteRequest) -> std::result::Result<String, GrpcStatus> {
+        let input = request.input.trim();
+        if input.is_empty() {
+            return Err(GrpcStatus::new(GrpcCode::InvalidArgument, "input must not be empty"));
+        }
+        admit_execution(&self.state, claims).await.map_err(GrpcStatus::from_error)?;
+        Ok(input.to_string())
+    }
+}
+
+/// 记录执行（标签总带 "grpc"），失败只记日志
+fn record_execution(state: &ServerState, log: &ACSAExecutionLog, tags: &[String]) -> Option<String> {
+    let mut tags = tags.to_vec();
+    if !tags.iter().any(|tag| tag == "grpc") {
+        tags.push("grpc".to_string());
+    }
+    match state.executions.record(log, None, &tags) {
+        Ok(id) => Some(id),
+        Err(e) => {
+            warn!("⚠️  Failed to record gRPC execution: {}", e);
+            None
+        }
+    }
+}
+
+/// tonic 生成的消息与服务代码（build.rs 由 proto/acsa/v1/acsa.proto 生成）
+#[cfg(feature = "grpc")]
+pub mod pb {
+    tonic::include_proto!("acsa.v1");
+}
+
+#[cfg(feature = "grpc")]
+// 错误类型由 tonic 生成的 trait 固定为 tonic::Status
+#[allow(clippy::result_large_err)]
+mod transport {
+    use std::net::SocketAddr;
+    use std::pin::Pin;
+
+    use futures::{Stream, StreamExt};
+    use tokio_stream::wrappers::UnboundedReceiverStream;
+    use tonic::{Request, Response, Status};
+
+    use super::super::http_server::authenticate_request;
+    use super::*;
+
+    impl From<GrpcStatus> for Status {
+        fn from(status: GrpcStatus) -> Self {
+            Status::new(tonic::Code::from_i32(status.code as i32), status.message)
+        }
+    }
+
+    impl From<ExecuteReply> for pb::ExecuteReply {
+        fn from(reply: ExecuteReply) -> Self {
+            Self {
+                execution_id: reply.execution_id.unwrap_or_default(),
+                success: reply.success,
+                final_output: reply.final_output.unwrap_or_default(),
+                total_cost: reply.total_cost,
+                iterations: reply.iterations,
+                total_time_ms: reply.total_time_ms,
+            }
+        }
+    }
+
+    impl From<AgentChunk> for pb::AgentChunk {
+        fn from(chunk: AgentChunk) -> Self {
+            Self {
+                role: chunk.role.as_str().to_string(),
+                iteration: chunk.iteration,
+                delta: chunk.delta,
+                done: chunk.done,
+            }
+        }
+    }
+
+    impl From<ExecuteEvent> for pb::ExecuteEvent {
+        fn from(event: ExecuteEvent) -> Self {
+            let event = match event {
+                ExecuteEvent::Chunk(chunk) => pb::execute_event::Event::Chunk(chunk.into()),
+                ExecuteEvent::Result(reply) => pb::execute_event::Event::Result(reply.into()),
+            };
+            Self { event: Some(event) }
+        }
+    }
+
+    impl From<ExecutionLogReply> for pb::ExecutionLogReply {
+        fn from(reply: ExecutionLogReply) -> Self {
+            Self {
+                execution_id: reply.execution_id,
+                tags: reply.tags,
+                recorded_at: reply.recorded_at,
+                log_json: reply.log_json,
+            }
+        }
+    }
+
+    impl From<ProtocolInfo> for pb::ProtocolInfo {
+        fn from(info: ProtocolInfo) -> Self {
+            Self {
+                name: info.name,
+                display_name: info.display_name,
+                custom: info.custom,
+                temperature: info.temperature,
+                agent_weights: Some(pb::AgentWeights {
+                    moss: info.agent_weights.moss,
+                    l6: info.agent_weights.l6,
+                    ultron: info.agent_weights.ultron,
+                    omega: info.agent_weights.omega,
+                }),
+                description: info.description,
+            }
+        }
+    }
+
+    impl From<HealthReply> for pb::HealthReply {
+        fn from(reply: HealthReply) -> Self {
+            let status = if reply.serving {
+                pb::health_reply::ServingStatus::Serving
+            } else {
+                pb::health_reply::ServingStatus::NotServing
+            };
+            Self {
+                status: status as i32,
+                version: reply.version,
+                uptime_secs: reply.uptime_secs,
+                components: reply
+                    .components
+                    .into_iter()
+                    .map(|component| pb::ComponentHealth {
+                        name: component.name,
+                        status: component.status,
+                        details: component.details.unwrap_or_default(),
+                    })
+                    .collect(),
+            }
+        }
+    }
+
+    impl AcsaGrpcService {
+        /// 认证拦截：metadata 中的 authorization / x-api-key 与 HTTP 请求头同样处理，再按方法校验权限
+        async fn authenticate<T>(&self, request: &Request<T>, method: &str) -> std::result::Result<Claims, Status> {
+            let metadata = request.metadata();
+            let authorization = metadata.get("authorization").and_then(|v| v.to_str().ok());
+            let x_api_key = metadata.get("x-api-key").and_then(|v| v.to_str().ok());
+            let claims = authenticate_request(&self.state, authorization, x_api_key)
+                .await
+                .map_err(GrpcStatus::from_error)?;
+            authorize_method(Some(&claims), method)?;
+            Ok(claims)
+        }
+    }
+
+    pub type ExecuteEventStream = Pin<Box<dyn Stream<Item = std::result::Result<pb::ExecuteEvent, Status>> + Send>>;
+
+    #[tonic::async_trait]
+    impl pb::acsa_server::Acsa for AcsaGrpcService {
+        async fn execute(&self, request: Request<pb::ExecuteRequest>) -> std::result::Result<Response<pb::ExecuteReply>, Status> {
+            let claims = self.authenticate(&request, "Execute").await?;
+            let request = request.into_inner();
+            let reply = AcsaGrpcService::execute(self, &claims, ExecuteRequest { input: request.input, tags: request.tags }).await?;
+            Ok(Response::new(reply.into()))
+        }
+
+        type ExecuteStreamStream = ExecuteEventStream;
+
+        async fn execute_stream(
+            &self,
+            request: Request<pb::ExecuteRequest>,
+        ) -> std::result::Result<Response<Self::ExecuteStreamStream>, Status> {
+            let claims = self.authenticate(&request, "ExecuteStream").await?;
+            let request = request.into_inner();
+            let events = AcsaGrpcService::execute_stream(self, &claims, ExecuteRequest { input: request.input, tags: request.tags }).await?;
+            let stream = UnboundedReceiverStream::new(events).map(|event| event.map(Into::into).map_err(Into::into));
+            Ok(Response::new(Box::pin(stream)))
+        }
+
+        async fn get_execution_log(
+            &self,
+            request: Request<pb::GetExecutionLogRequest>,
+        ) -> std::result::Result<Response<pb::ExecutionLogReply>, Status> {
+            let claims = self.authenticate(&request, "GetExecutionLog").await?;
+            let reply = AcsaGrpcService::get_execution_log(self, &claims, &request.get_ref().execution_id).await?;
+            Ok(Response::new(reply.into()))
+        }
+
+        async fn list_protocols(
+            &self,
+            request: Request<pb::ListProtocolsRequest>,
+        ) -> std::result::Result<Response<pb::ListProtocolsReply>, Status> {
+            let claims = self.authenticate(&request, "ListProtocols").await?;
+            let protocols = AcsaGrpcService::list_protocols(self, &claims).await?;
+            Ok(Response::new(pb::ListProtocolsReply {
+                protocols: protocols.into_iter().map(Into::into).collect(),
+            }))
+        }
+
+        async fn health(&self, _request: Request<pb::HealthRequest>) -> std::result::Result<Response<pb::HealthReply>, Status> {
+            Ok(Response::new(AcsaGrpcService::health(self).await.into()))
+        }
+    }
+
+    /// 在 addr 上提供 gRPC 服务，shutdown 完成后停止接收新连接并等待进行中的调用结束
+    pub async fn serve_grpc(
+        state: Arc<ServerState>,
+        addr: SocketAddr,
+        shutdown: impl std::future::Future<Output = ()> + Send,
+    ) -> Result<()> {
+        info!("🛰️  Starting gRPC server on {}", addr);
+        tonic::transport::Server::builder()
+            .add_service(pb::acsa_server::AcsaServer::new(AcsaGrpcService::new(state)))
+            .serve_with_shutdown(addr, shutdown)
+            .await?;
+        info!("🛰️  gRPC server stopped");
+        Ok(())
+    }
+}
+
+#[cfg(feature = "grpc")]
+pub use transport::{serve_grpc, ExecuteEventStream};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::error::ErrorCode;
+
+    #[test]
+    fn test_status_mapping() {
+        let denied = GrpcStatus::from_error(AcsaError::new(ErrorCode::PermissionDenied, "nope").into());
+        assert_eq!(denied.code, GrpcCode::PermissionDenied);
+        let maintenance = GrpcStatus::from_error(AcsaError::new(ErrorCode::MaintenanceMode, "paused").into());
+        assert_eq!(maintenance.code, GrpcCode::Unavailable);
+        assert_eq!(GrpcStatus::from_error(anyhow::anyhow!("boom")).code, GrpcCode::Internal);
+        assert_eq!(GrpcCode::from_http(402), GrpcCode::FailedPrecondition);
+    }
+
+    #[test]
+    fn test_method_permissions() {
+        assert!(authorize_method(None, "Health").is_ok());
+        assert_eq!(authorize_method(None, "Execute").unwrap_err().code, GrpcCode::Unauthenticated);
+        assert_eq!(authorize_method(None, "Shutdown").unwrap_err().code, GrpcCode::PermissionDenied);
+
+        let viewer = Claims {
+            sub: "u".to_string(),
+            exp: u64::MAX,
+            iat: 0,
+            user_id: "u".to_string(),
+            username: "u".to_string(),
+            roles: vec!["viewer".to_string()],
+            workspace_id: None,
+            api_key_id: None,
+        };
+        assert!(authorize_method(Some(&viewer), "ListProtocols").is_ok());
+        assert_eq!(authorize_method(Some(&viewer), "ExecuteStream").unwrap_err().code, GrpcCode::PermissionDenied);
+    }
+
+    #[cfg(feature = "grpc")]
+    #[test]
+    fn test_tonic_conversions() {
+        let status: tonic::Status = GrpcStatus::new(GrpcCode::ResourceExhausted, "slow down").into();
+        assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+        assert_eq!(status.message(), "slow down");
+
+        let reply = ExecuteReply {
+            execution_id: None,
+            success: true,
+            final_output: Some("done".to_string()),
+            total_cost: 0.5,
+            iterations: 2,
+            total_time_ms: 10,
+        };
+        let event: pb::ExecuteEvent = ExecuteEvent::Result(reply).into();
+        let Some(pb::execute_event::Event::Result(result)) = event.event else {
+            panic!("expected result event");
+        };
+        assert_eq!(result.execution_id, "");
+        assert_eq!(result.final_output, "done");
+    }
+}
@@ -8,20 +8,65 @@
 // 4. 影子模式数据保护
 // 5. CORS支持
 // 6. 健康检查端点
+// 7. 全局熔断开关管理端点（维护模式下只读端点保持可用）
+// 8. 执行记录列表 / 详情 / 检索接口与网页视图
+// 9. OpenAI 兼容接口（/v1/chat/completions、/v1/models），可作为现有客户端的后端直接替换
+// 10. /metrics：Prometheus 文本格式（Agent 延迟直方图、Provider 成功率、缓存占用、H(t)）
+// 11. RBAC：每条路由对应一个 Permission（ROUTE_PERMISSIONS），admin 专属的协议、预算、用户管理接口
+// 12. 静态 API 密钥认证（服务间调用）：按密钥限流，用量按密钥归属到所属租户的 ApiManager
+// 13. 定时任务（cron）的增删查接口；服务运行期间由 Scheduler 按计划触发执行
+// 14. 事件处理死信的查看、重新投递与删除
+// 15. 人工审批：风险超过阈值而暂停的执行，由有审批权限的用户批准或驳回
+// 16. 执行实况 SSE（/api/v1/executions/:id/events）：阶段切换、Jarvis 判定与输出片段，keep-alive 与 Last-Event-ID 续传
+// 17. /dashboard：内嵌的单页仪表盘（静态资源公开，数据经 /api/v1/dashboard 认证后获取）
+// 18. 多租户：认证后按租户（TenantId，即工作区）限流，密钥列表与用量按租户隔离
+// 19. gRPC（grpc_server.rs）：grpc_port 上同时提供 acsa.v1.Acsa 服务
+//
+// 20. server 特性（默认启用）：transport 模块把以上处理函数挂到 axum Router，
+//     ServerState::open 按数据目录组装共享状态并把同一批实例接入 Router
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::future::Future;
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
-use std::time::Duration;
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+use tokio::sync::RwLock;
 use tracing::{info, warn};
 
-use super::auth_system::AuthManager;
+use super::agent_extension::{AgentCallStats, AgentExtensionManager, AgentList, CustomAgent, DiminishingReturns};
+use super::api_key_auth::{presented_api_key, ApiKeyInfo, ApiKeyStore};
+use super::api_manager::{ApiManager, ApiProvider, BudgetPolicy, BudgetStatus, KeyUsage};
+use super::approval::{ApprovalRequest, ApprovalStatus, ApprovalStore};
+use super::audit_log::{AuditLogConfig, AuditLogger};
+use super::auth_system::{AuthManager, Claims, Permission, Role, UserAccount};
+use super::cache_manager::CacheManager;
+use super::concurrency::{ConcurrencyConfig, ConcurrencyManager};
 use super::config_manager::ConfigManager;
-use super::database::DatabaseManager;
-use super::metrics::{ComponentHealth, HealthStatus, MetricsCollector};
-use super::rate_limiter::RateLimiter;
-use super::shadow_mode::ShadowModeEngine;
+use super::database::{DatabaseConfig, DatabaseManager};
+use super::dead_letter::{DeadLetter, DeadLetterQueue};
+use super::error::{AcsaError, ErrorCode, ErrorReport};
+use super::event_bus::{EventBus, EventBusConfig};
+use super::execution_events::ExecutionEventHub;
+use super::execution_store::{ExecutionPage, ExecutionQuery, ExecutionRecord, ExecutionStore};
+use super::jarvis::JarvisManager;
+use super::kill_switch::{KillSwitch, KillSwitchConfig, KillSwitchSource, KillSwitchState, PausedOperation};
+use super::log_export::html_escape;
+use super::metrics::{ComponentHealth, HealthCheck, HealthStatus, MetricsCollector};
+use super::openai_compat::{self, ChatCompletionRequest, ChatCompletionResponse, CompletionStream, ModelList, Usage};
+use super::protocol::{AgentWeights, Protocol, ProtocolManager};
+use super::rate_limiter::{RateLimitRule, RateLimiter, RateLimiterConfig};
+use super::router::ACSARouter;
+use super::shadow_mode::{ShadowModeConfig, ShadowModeEngine};
+use super::shutdown::ShutdownCoordinator;
+use super::sosa_api_pool::{PoolConfig, SosaApiPool};
+use super::sosa_crypto::{SosaCryptoConfig, SosaCryptoEngine};
+use super::sovereignty::SOVEREIGNTY;
+use super::types::{ACSAExecutionLog, AgentChunk};
+use super::web_dashboard::{self, DashboardSnapshot, SovereigntySummary, SovereigntyTrend};
+use super::workflow_engine::{DueRun, MissedRunPolicy, ScheduleTarget, ScheduledJob, Scheduler, WorkflowEngine};
+use super::workspace::{TenantId, WorkspaceConfig, WorkspaceManager, WorkspaceSummary};
 
 /// HTTP服务器配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,6 +83,9 @@ pub struct HttpServerConfig {
     pub enable_cors: bool,
     /// 允许的源
     pub allowed_origins: Vec<String>,
+    /// gRPC 端口（None 不启动；需启用 grpc 特性）
+    #[serde(default)]
+    pub grpc_port: Option<u16>,
 }
 
 impl Default for HttpServerConfig {
@@ -49,6 +97,7 @@ impl Default for HttpServerConfig {
             max_body_size_mb: 10,
             enable_cors: true,
             allowed_origins: vec!["*".to_string()],
+            grpc_port: Some(50051),
         }
     }
 }
@@ -65,8 +114,109 @@ pub struct ServerState {
     pub database: Arc<DatabaseManager>,
     /// 配置管理器
     pub config: Arc<ConfigManager>,
-    /// 指标收集器
+    /// 指标收集器（router 经 ACSARouter::with_metrics 接入同一实例以记录 Agent 延迟）
     pub metrics: Arc<MetricsCollector>,
+    /// API 密钥与调用统计（/metrics 导出 Provider 成功率）
+    pub api: Arc<RwLock<ApiManager>>,
+    /// 本地缓存（/metrics 导出占用）
+    pub cache: Arc<CacheManager>,
+    /// 自定义Agent注册表
+    pub agents: Arc<RwLock<AgentExtensionManager>>,
+    /// Jarvis调度器
+    pub jarvis: Arc<RwLock<JarvisManager>>,
+    /// 全局熔断开关
+    pub kill_switch: Arc<KillSwitch>,
+    /// 执行日志存储
+    pub executions: Arc<ExecutionStore>,
+    /// 多租户工作区（按 Claims 选择）
+    pub workspaces: Arc<WorkspaceManager>,
+    /// ACSA 链路（OpenAI 兼容接口使用）
+    pub router: Arc<ACSARouter>,
+    /// 协议管理器（内置 + 自定义协议，可由 ProtocolWatcher 热更新）
+    pub protocols: Arc<std::sync::RwLock<ProtocolManager>>,
+    /// 静态 API 密钥（哈希保存在 database 中）
+    pub api_keys: Arc<ApiKeyStore>,
+    /// 定时任务（cron 计划持久化在文件中）
+    pub schedules: Arc<Scheduler>,
+    /// 事件总线（死信接口在此重新投递）
+    pub events: Arc<EventBus>,
+    /// 待审批执行（router 经 ACSARouter::with_approvals 接入同一存储）
+    pub approvals: Arc<ApprovalStore>,
+    /// 执行实况缓存（启动时订阅 events，供 SSE 接口补发与推送）
+    pub execution_events: Arc<ExecutionEventHub>,
+    /// SOSA API 池（仪表盘展示端点健康度）
+    pub api_pool: Arc<SosaApiPool>,
+    /// 仪表盘的 H(t) 趋势
+    pub sovereignty_trend: Arc<SovereigntyTrend>,
+}
+
+impl ServerState {
+    /// 在 data_dir 下打开各存储（执行记录、工作区、定时任务、审批、死信与 API 密钥库），
+    /// 并把指标、事件总线、审批与熔断开关的同一实例接入 router
+    ///
+    /// data_dir 为 `./data` 时与 CLI 子命令读写相同的文件
+    pub async fn open(
+        data_dir: impl Into<PathBuf>,
+        auth: Arc<AuthManager>,
+        config: Arc<ConfigManager>,
+        protocols: ProtocolManager,
+        router: ACSARouter,
+    ) -> Result<Self> {
+        let data_dir = data_dir.into();
+        std::fs::create_dir_all(&data_dir)?;
+
+        let database = Arc::new(DatabaseManager::new(DatabaseConfig {
+            url: format!("sqlite://{}", data_dir.join("acsa.db").display()),
+            ..Default::default()
+        }));
+        database.connect().await?;
+        let api_keys = Arc::new(ApiKeyStore::new(database.clone()));
+        api_keys.migrate().await?;
+
+        let mut api = ApiManager::new(data_dir.join("api"));
+        api.init().await?;
+
+        let audit = Arc::new(AuditLogger::new(AuditLogConfig::default(), None));
+        let metrics = Arc::new(MetricsCollector::new(env!("CARGO_PKG_VERSION").to_string()));
+        let events = Arc::new(
+            EventBus::new(EventBusConfig::default())
+                .with_dead_letters(Arc::new(DeadLetterQueue::open(data_dir.join("events/dead_letters.json"))?)),
+        );
+        let approvals = Arc::new(ApprovalStore::open(data_dir.join("approvals.json"))?);
+        let kill_switch = Arc::new(KillSwitch::new(KillSwitchConfig::default()));
+        let crypto = Arc::new(SosaCryptoEngine::new(SosaCryptoConfig::default()));
+
+        let router = router
+            .with_metrics(metrics.clone())
+            .with_event_bus(events.clone())
+            .with_approvals(approvals.clone())
+            .with_kill_switch(kill_switch.clone());
+
+        Ok(Self {
+            auth,
+            rate_limiter: Arc::new(RateLimiter::new(RateLimiterConfig::default())),
+            shadow_mode: Arc::new(ShadowModeEngine::new(ShadowModeConfig::default(), crypto)),
+            database,
+            config,
+            metrics,
+            api: Arc::new(RwLock::new(api)),
+            cache: Arc::new(CacheManager::with_defaults(data_dir.join("cache"))?),
+            agents: Arc::new(RwLock::new(AgentExtensionManager::new())),
+            jarvis: Arc::new(RwLock::new(JarvisManager::new())),
+            schedules: Arc::new(Scheduler::open(data_dir.join("schedules.json"))?.with_kill_switch(kill_switch.clone())),
+            kill_switch,
+            executions: Arc::new(ExecutionStore::open(data_dir.join("executions"))?),
+            workspaces: Arc::new(WorkspaceManager::open(data_dir.join("workspaces"), audit).await?),
+            router: Arc::new(router),
+            protocols: Arc::new(std::sync::RwLock::new(protocols)),
+            api_keys,
+            events,
+            approvals,
+            execution_events: Arc::new(ExecutionEventHub::new()),
+            api_pool: Arc::new(SosaApiPool::new(PoolConfig::default())),
+            sovereignty_trend: Arc::new(SovereigntyTrend::new()),
+        })
+    }
 }
 
 /// API响应
@@ -78,6 +228,9 @@ pub struct ApiResponse<T> {
     pub data: Option<T>,
     /// 错误信息
     pub error: Option<String>,
+    /// 结构化错误（错误代码、是否可重试、上下文链）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_report: Option<ErrorReport>,
     /// 时间戳
     pub timestamp: i64,
 }
@@ -88,6 +241,7 @@ impl<T> ApiResponse<T> {
             success: true,
             data: Some(data),
             error: None,
+            error_report: None,
             timestamp: chrono::Utc::now().timestamp(),
         }
     }
@@ -97,15 +251,34 @@ impl<T> ApiResponse<T> {
             success: false,
             data: None,
             error: Some(error),
+            error_report: None,
             timestamp: chrono::Utc::now().timestamp(),
         }
     }
+
+    /// 由 AcsaError 构造错误响应，返回 (HTTP状态码, 响应体)
+    pub fn from_error(error: &AcsaError) -> (u16, Self) {
+        let report = error.to_report();
+        let status = report.code.http_status();
+        (
+            status,
+            Self {
+                success: false,
+                data: None,
+                error: Some(error.user_message("en")),
+                error_report: Some(report),
+                timestamp: chrono::Utc::now().timestamp(),
+            },
+        )
+    }
 }
 
 /// HTTP服务器
 pub struct HttpServer {
     config: HttpServerConfig,
     state: Arc<ServerState>,
+    /// 关停协调器（收到 SIGTERM 后排空并退出）
+    shutdown: Option<Arc<ShutdownCoordinator>>,
 }
 
 impl HttpServer {
@@ -116,7 +289,13 @@ impl HttpServer {
         info!("    CORS: {}", config.enable_cors);
         info!("    Max Body Size: {}MB", config.max_body_size_mb);
 
-        Self { config, state }
+        Self { config, state, shutdown: None }
+    }
+
+    /// 接入关停协调器
+    pub fn with_shutdown(mut self, coordinator: Arc<ShutdownCoordinator>) -> Self {
+        self.shutdown = Some(coordinator);
+        self
     }
 
     /// 启动服务器
@@ -127,113 +306,400 @@ impl HttpServer {
 
         info!("🚀 Starting HTTP server on {}", addr);
 
-        // TODO: 实际使用Axum构建路由和启动服务器
-        // let app = self.build_router();
-        //
-        // let listener = tokio::net::TcpListener::bind(&addr).await?;
-        // axum::serve(listener, app).await?;
+        // 执行实况 SSE 依赖事件总线上的 Router 实况
+        self.state.execution_events.attach(&self.state.events).await?;
+
+        // 轮询文件哨兵与集群开关
+        let _kill_switch_watcher = self.state.kill_switch.clone().spawn_watcher();
 
-        info!("✅ HTTP server started successfully");
+        // 按 cron 计划触发执行（含停机期间错过的运行）
+        let scheduler_state = self.state.clone();
+        let _scheduler = self
+            .state
+            .schedules
+            .clone()
+            .spawn(move |run| run_scheduled(scheduler_state.clone(), run));
 
-        // Placeholder: 保持服务器运行
-        tokio::time::sleep(Duration::from_secs(u64::MAX)).await;
+        // gRPC 与 HTTP 共用 ServerState，随关停协调器一同停止
+        #[cfg(feature = "grpc")]
+        if let Some(grpc_port) = self.config.grpc_port {
+            let grpc_addr: SocketAddr = format!("{}:{}", self.config.host, grpc_port).parse()?;
+            let shutdown = self.stop_signal();
+            let state = self.state.clone();
+            tokio::spawn(async move {
+                if let Err(e) = super::grpc_server::serve_grpc(state, grpc_addr, shutdown).await {
+                    warn!("⚠️  gRPC server failed: {:#}", e);
+                }
+            });
+        }
+
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        let server = self.serve(listener);
+        info!("✅ HTTP server listening on {}", addr);
+
+        let Some(coordinator) = &self.shutdown else {
+            return server.await?;
+        };
 
+        // SIGTERM → 停止接收 → 排空 → 落盘 → 释放锁/Leader → 退出
+        let report = coordinator.run_until_signal().await?;
+        if !report.is_clean() {
+            warn!(
+                "⚠️  Unclean shutdown: drained={}, abandoned={:?}, failed hooks={:?}",
+                report.drained,
+                report.abandoned,
+                report.hooks.iter().filter(|h| !h.success).map(|h| &h.name).collect::<Vec<_>>()
+            );
+        }
+        // 关停信号到来时已停止接收新连接；排空后仍未结束的长连接（SSE）直接断开
+        server.abort();
         Ok(())
     }
 
-    /// 构建路由（placeholder）
-    fn build_router(&self) -> String {
-        // TODO: 使用Axum构建实际路由
-        //
-        // Router::new()
-        //     .route("/health", get(health_handler))
-        //     .route("/metrics", get(metrics_handler))
-        //     .route("/api/v1/chat", post(chat_handler))
-        //     .route("/api/v1/auth/login", post(login_handler))
-        //     .route("/api/v1/auth/refresh", post(refresh_handler))
-        //     .layer(middleware::from_fn(rate_limit_middleware))
-        //     .layer(middleware::from_fn(auth_middleware))
-        //     .with_state(self.state.clone())
+    /// 关停信号（未接入关停协调器时永不触发）
+    fn stop_signal(&self) -> impl Future<Output = ()> + Send + 'static {
+        let mut stop = self.shutdown.as_ref().map(|coordinator| coordinator.subscribe());
+        async move {
+            match stop.as_mut() {
+                Some(stop) => {
+                    let _ = stop.wait_for(|stopping| *stopping).await;
+                }
+                None => std::future::pending::<()>().await,
+            }
+        }
+    }
+
+    /// 在 listener 上提供 HTTP 服务，关停信号到来后不再接收新连接
+    #[cfg(feature = "server")]
+    fn serve(&self, listener: tokio::net::TcpListener) -> tokio::task::JoinHandle<Result<()>> {
+        let app = self.build_router();
+        let stop = self.stop_signal();
+        tokio::spawn(async move {
+            axum::serve(listener, app).with_graceful_shutdown(stop).await?;
+            Ok(())
+        })
+    }
+
+    #[cfg(not(feature = "server"))]
+    fn serve(&self, _listener: tokio::net::TcpListener) -> tokio::task::JoinHandle<Result<()>> {
+        tokio::spawn(async { Err(anyhow::anyhow!("HTTP server requires the `server` feature")) })
+    }
 
-        "router_placeholder".to_string()
+    /// 构建路由：处理函数、认证 / 限流中间件、请求体上限与 CORS
+    #[cfg(feature = "server")]
+    pub fn build_router(&self) -> axum::Router {
+        let router = transport::router(self.state.clone())
+            .layer(axum::extract::DefaultBodyLimit::max(self.config.max_body_size_mb * 1024 * 1024));
+        if self.config.enable_cors {
+            router.layer(self.cors_layer())
+        } else {
+            router
+        }
     }
 
     /// 健康检查端点
     async fn health_handler(state: Arc<ServerState>) -> String {
-        // 检查各组件健康状态
-        let mut components = std::collections::HashMap::new();
-
-        // 数据库健康检查
-        let db_health = match state.database.health_check().await {
-            Ok(true) => ComponentHealth {
-                name: "database".to_string(),
-                status: HealthStatus::Healthy,
-                details: None,
-                last_check: chrono::Utc::now(),
-            },
-            Ok(false) => ComponentHealth {
-                name: "database".to_string(),
-                status: HealthStatus::Degraded,
-                details: Some("Connection issues".to_string()),
-                last_check: chrono::Utc::now(),
-            },
-            Err(e) => ComponentHealth {
-                name: "database".to_string(),
-                status: HealthStatus::Unhealthy,
-                details: Some(format!("Error: {}", e)),
-                last_check: chrono::Utc::now(),
-            },
-        };
-        components.insert("database".to_string(), db_health);
-
-        // 其他组件...
-        components.insert(
-            "auth".to_string(),
-            ComponentHealth {
-                name: "auth".to_string(),
-                status: HealthStatus::Healthy,
-                details: None,
-                last_check: chrono::Utc::now(),
-            },
-        );
-
-        let health = state.metrics.get_health_check(components).await;
+        let health = health_check(&state).await;
         serde_json::to_string_pretty(&health).unwrap_or_else(|_| "{}".to_string())
     }
 
-    /// 指标端点
+    /// 指标端点：导出前刷新 Provider 统计、缓存占用与 H(t) 快照
     async fn metrics_handler(state: Arc<ServerState>) -> String {
+        refresh_metrics(&state).await;
         state.metrics.export_prometheus().await
     }
 
-    /// 认证中间件（placeholder）
-    async fn auth_middleware(/* request */) -> Result<()> {
-        // TODO: 从请求头提取token
-        // TODO: 使用AuthManager验证token
-        // TODO: 将用户信息注入到请求上下文
+    /// 认证中间件：公开路由直接放行；其余路由先认证（失败 401，超出限流 429），
+    /// 再按路由权限校验（失败 403），返回注入请求上下文的 Claims
+    ///
+    /// transport::router 经 from_fn_with_state 把它挂在除公开路由外的全部路由上（含 admin 接口）
+    pub async fn auth_middleware(
+        state: &ServerState,
+        method: &str,
+        path: &str,
+        authorization: Option<&str>,
+        x_api_key: Option<&str>,
+    ) -> std::result::Result<Option<Claims>, (u16, ApiResponse<()>)> {
+        if is_public_route(method, path) {
+            return Ok(None);
+        }
+        let claims = authenticate_request(state, authorization, x_api_key).await.map_err(error_response)?;
+        authorize_request(Some(&claims), method, path).map_err(error_response)?;
+        Ok(Some(claims))
+    }
 
+    /// 速率限制中间件：超出全局限流时 429（按 API 密钥与租户的限流在认证时检查）
+    pub async fn rate_limit_middleware(state: &ServerState) -> std::result::Result<(), (u16, ApiResponse<()>)> {
+        let limit = state.rate_limiter.check_global().await.map_err(error_response)?;
+        if !limit.allowed {
+            return Err(ApiResponse::from_error(&AcsaError::with_context(
+                ErrorCode::ProviderRateLimited,
+                "Global rate limit exceeded",
+                format!("retry after {}s", limit.retry_after_secs.unwrap_or(1)),
+            )));
+        }
         Ok(())
     }
 
-    /// 速率限制中间件（placeholder）
-    async fn rate_limit_middleware(state: Arc<ServerState> /* request */) -> Result<()> {
-        // TODO: 从请求中提取IP/用户ID
-        // TODO: 使用RateLimiter检查限流
-        // TODO: 如果被限流，返回429状态码
+    /// CORS：allowed_origins 含 `*` 时允许任意来源
+    #[cfg(feature = "server")]
+    fn cors_layer(&self) -> tower_http::cors::CorsLayer {
+        use tower_http::cors::{Any, CorsLayer};
+
+        let layer = CorsLayer::new().allow_methods(Any).allow_headers(Any);
+        if self.config.allowed_origins.iter().any(|origin| origin == "*") {
+            return layer.allow_origin(Any);
+        }
+        let origins: Vec<axum::http::HeaderValue> =
+            self.config.allowed_origins.iter().filter_map(|origin| origin.parse().ok()).collect();
+        layer.allow_origin(origins)
+    }
+}
+
+/// 无需登录的路由
+const PUBLIC_ROUTES: &[(&str, &str)] = &[
+    ("GET", "/health"),
+    ("GET", "/metrics"),
+    ("POST", "/api/v1/auth/login"),
+    ("POST", "/api/v1/auth/refresh"),
+    ("GET", "/dashboard"),
+    ("GET", "/dashboard/:asset"),
+];
 
-        let _result = state.rate_limiter.check_global().await?;
+/// 路由所需权限（`:name` 匹配任意一段）
+pub const ROUTE_PERMISSIONS: &[(&str, &str, Permission)] = &[
+    ("POST", "/api/v1/chat", Permission::Execute),
+    ("POST", "/api/v1/chat/stream", Permission::Execute),
+    ("POST", "/v1/chat/completions", Permission::Execute),
+    ("GET", "/v1/models", Permission::Read),
+    ("GET", "/api/v1/protocols", Permission::Read),
+    ("GET", "/api/v1/agents", Permission::Read),
+    ("POST", "/api/v1/agents", Permission::ManageAgents),
+    ("GET", "/api/v1/agents/stats", Permission::Read),
+    ("DELETE", "/api/v1/agents/:name", Permission::ManageAgents),
+    ("GET", "/executions", Permission::Read),
+    ("GET", "/api/v1/executions", Permission::Read),
+    ("GET", "/api/v1/executions/:id", Permission::Read),
+    ("POST", "/api/v1/executions/:id/tags", Permission::TagExecutions),
+    ("GET", "/api/v1/executions/:id/events", Permission::Read),
+    ("GET", "/api/v1/workspace", Permission::Read),
+    ("GET", "/api/v1/dashboard", Permission::Read),
+    ("GET", "/api/v1/admin/kill-switch", Permission::Read),
+    ("POST", "/api/v1/admin/kill-switch", Permission::ManageKillSwitch),
+    ("GET", "/api/v1/admin/workspaces", Permission::ManageWorkspaces),
+    ("POST", "/api/v1/admin/workspaces", Permission::ManageWorkspaces),
+    ("PUT", "/api/v1/admin/protocol", Permission::ManageProtocols),
+    ("GET", "/api/v1/admin/budgets", Permission::ManageBudgets),
+    ("PUT", "/api/v1/admin/budgets", Permission::ManageBudgets),
+    ("GET", "/api/v1/admin/users", Permission::ManageUsers),
+    ("POST", "/api/v1/admin/users", Permission::ManageUsers),
+    ("PUT", "/api/v1/admin/users/:username", Permission::ManageUsers),
+    ("DELETE", "/api/v1/admin/users/:username", Permission::ManageUsers),
+    ("GET", "/api/v1/admin/api-keys", Permission::ManageUsers),
+    ("POST", "/api/v1/admin/api-keys", Permission::ManageUsers),
+    ("DELETE", "/api/v1/admin/api-keys/:key_id", Permission::ManageUsers),
+    ("GET", "/api/v1/schedules", Permission::Read),
+    ("POST", "/api/v1/schedules", Permission::Execute),
+    ("DELETE", "/api/v1/schedules/:id", Permission::Execute),
+    ("GET", "/api/v1/events/dead-letters", Permission::Read),
+    ("DELETE", "/api/v1/events/dead-letters/:id", Permission::Execute),
+    ("POST", "/api/v1/events/dead-letters/:id/redrive", Permission::Execute),
+    ("GET", "/api/v1/approvals", Permission::Read),
+    ("POST", "/api/v1/approvals/:id/approve", Permission::ApproveExecutions),
+    ("POST", "/api/v1/approvals/:id/reject", Permission::ApproveExecutions),
+];
 
-        Ok(())
+fn route_matches(pattern: &str, path: &str) -> bool {
+    let path = path.split('?').next().unwrap_or(path).trim_end_matches('/');
+    let (mut pattern, mut path) = (pattern.split('/'), path.split('/'));
+    loop {
+        match (pattern.next(), path.next()) {
+            (None, None) => return true,
+            (Some(expected), Some(actual)) if expected.starts_with(':') && !actual.is_empty() || expected == actual => {}
+            _ => return false,
+        }
     }
+}
 
-    /// CORS中间件（placeholder）
-    fn cors_layer(&self) -> String {
-        // TODO: 使用tower_http::cors::CorsLayer配置CORS
-        "cors_placeholder".to_string()
+/// 按路由表校验权限：公开路由直接放行，未登记的路由一律拒绝
+pub fn authorize_request(claims: Option<&Claims>, method: &str, path: &str) -> Result<()> {
+    if is_public_route(method, path) {
+        return Ok(());
     }
+    let Some(claims) = claims else {
+        return Err(AcsaError::new(ErrorCode::ApiKeyInvalid, "Missing or invalid bearer token").into());
+    };
+    let permission = ROUTE_PERMISSIONS
+        .iter()
+        .find(|(m, pattern, _)| m.eq_ignore_ascii_case(method) && route_matches(pattern, path))
+        .map(|(_, _, permission)| *permission)
+        .ok_or_else(|| AcsaError::new(ErrorCode::PermissionDenied, format!("No permission rule for {} {}", method, path)))?;
+    claims.require(permission)
+}
+
+/// 无需登录即可访问的路由
+fn is_public_route(method: &str, path: &str) -> bool {
+    PUBLIC_ROUTES.iter().any(|(m, pattern)| m.eq_ignore_ascii_case(method) && route_matches(pattern, path))
 }
 
-// ===== API处理函数示例 =====
+/// 认证请求：带 API 密钥时按密钥认证并限流，否则校验 Bearer Token
+pub async fn authenticate_request(
+    state: &ServerState,
+    authorization: Option<&str>,
+    x_api_key: Option<&str>,
+) -> Result<Claims> {
+    let claims = if let Some(key) = presented_api_key(authorization, x_api_key) {
+        let info = state.api_keys.authenticate(key).await?;
+        let limit = state.rate_limiter.check_api_key(&info.key_id, info.rate_limit.as_ref()).await?;
+        if !limit.allowed {
+            return Err(AcsaError::with_context(
+                ErrorCode::ProviderRateLimited,
+                format!("API key {} exceeded its rate limit", info.display_prefix),
+                format!("retry after {}s", limit.retry_after_secs.unwrap_or(1)),
+            )
+            .into());
+        }
+        info.claims()
+    } else {
+        let token = authorization
+            .and_then(|value| value.trim().strip_prefix("Bearer "))
+            .ok_or_else(|| AcsaError::new(ErrorCode::ApiKeyInvalid, "Missing bearer token or API key"))?;
+        state
+            .auth
+            .verify_token(token.trim())
+            .await
+            .map_err(|e| AcsaError::new(ErrorCode::ApiKeyInvalid, e.to_string()))?
+    };
+    check_tenant_rate_limit(state, &claims).await?;
+    Ok(claims)
+}
+
+/// 租户配额：同一部署中的团队共享上游，按工作区限流（工作区可自带规则）
+async fn check_tenant_rate_limit(state: &ServerState, claims: &Claims) -> Result<()> {
+    let tenant = TenantId::from_claims(claims);
+    let rule = state.workspaces.get(tenant.as_str()).await.and_then(|w| w.config().rate_limit.clone());
+    let limit = state.rate_limiter.check_tenant(&tenant, rule.as_ref()).await?;
+    if !limit.allowed {
+        return Err(AcsaError::with_context(
+            ErrorCode::ProviderRateLimited,
+            format!("Tenant {} exceeded its rate limit", tenant),
+            format!("retry after {}s", limit.retry_after_secs.unwrap_or(1)),
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// 以 API 密钥发起的执行：Token 与花费归到所属租户 ApiManager 中的该密钥，失败只记日志
+pub(crate) async fn attribute_key_usage(
+    state: &ServerState,
+    tenant: &TenantId,
+    api_key_id: Option<&str>,
+    input: &str,
+    log: &ACSAExecutionLog,
+) {
+    let Some(key_id) = api_key_id else {
+        return;
+    };
+    let Some(workspace) = state.workspaces.get(tenant.as_str()).await else {
+        warn!("⚠️  Usage for API key {} dropped: unknown tenant {}", key_id, tenant);
+        return;
+    };
+    let tokens = Usage::from_log(input, log).total_tokens;
+    let recorded = workspace.api.write().await.record_key_usage(key_id, tokens, log.total_cost).await;
+    if let Err(e) = recorded {
+        warn!("⚠️  Failed to record usage for API key {}: {}", key_id, e);
+    }
+}
+
+/// 处理函数内的权限校验，失败时给出 403 响应
+fn authorize<T>(claims: &Claims, permission: Permission) -> Result<(), (u16, ApiResponse<T>)> {
+    claims.require(permission).map_err(error_response)
+}
+
+fn error_response<T>(error: anyhow::Error) -> (u16, ApiResponse<T>) {
+    match error.downcast::<AcsaError>() {
+        Ok(acsa) => ApiResponse::from_error(&acsa),
+        Err(e) => (400, ApiResponse::error(e.to_string())),
+    }
+}
+
+/// 各组件健康状态（HTTP /health 与 gRPC Health 共用）
+pub async fn health_check(state: &ServerState) -> HealthCheck {
+    let mut components = std::collections::HashMap::new();
+
+    // 数据库健康检查
+    let db_health = match state.database.health_check().await {
+        Ok(true) => ComponentHealth {
+            name: "database".to_string(),
+            status: HealthStatus::Healthy,
+            details: None,
+            last_check: chrono::Utc::now(),
+        },
+        Ok(false) => ComponentHealth {
+            name: "database".to_string(),
+            status: HealthStatus::Degraded,
+            details: Some("Connection issues".to_string()),
+            last_check: chrono::Utc::now(),
+        },
+        Err(e) => ComponentHealth {
+            name: "database".to_string(),
+            status: HealthStatus::Unhealthy,
+            details: Some(format!("Error: {}", e)),
+            last_check: chrono::Utc::now(),
+        },
+    };
+    components.insert("database".to_string(), db_health);
+
+    // 其他组件...
+    components.insert(
+        "auth".to_string(),
+        ComponentHealth {
+            name: "auth".to_string(),
+            status: HealthStatus::Healthy,
+            details: None,
+            last_check: chrono::Utc::now(),
+        },
+    );
+
+    // 维护模式：只读端点仍可用，但服务不接受新执行
+    let kill_switch = state.kill_switch.status();
+    components.insert(
+        "kill_switch".to_string(),
+        ComponentHealth {
+            name: "kill_switch".to_string(),
+            status: if kill_switch.is_some() { HealthStatus::Degraded } else { HealthStatus::Healthy },
+            details: kill_switch.map(|k| {
+                format!("Maintenance mode engaged by {} via {}", k.actor, k.source.label())
+            }),
+            last_check: chrono::Utc::now(),
+        },
+    );
+
+    state.metrics.get_health_check(components).await
+}
+
+/// 把其他组件的当前状态写入指标收集器（抓取时调用）
+pub async fn refresh_metrics(state: &ServerState) {
+    let provider_stats: Vec<_> = state.api.read().await.get_all_stats().into_iter().cloned().collect();
+    state.metrics.record_provider_stats(&provider_stats).await;
+
+    // 目录大小需要遍历文件系统，放到阻塞线程
+    let cache = state.cache.clone();
+    match tokio::task::spawn_blocking(move || cache.get_cache_usage()).await {
+        Ok(Ok(usage)) => state.metrics.record_cache_usage(&usage).await,
+        Ok(Err(e)) => warn!("⚠️  Cache usage unavailable for metrics: {}", e),
+        Err(e) => warn!("⚠️  Cache usage task failed: {}", e),
+    }
+
+    let bio = SOVEREIGNTY.get_bio_activity().await;
+    state
+        .metrics
+        .update_bio_activity(bio.current, bio.decay_rate, SOVEREIGNTY.is_enabled().await)
+        .await;
+}
+
+// ===== API处理函数 =====
 
 /// 聊天请求
 #[derive(Debug, Deserialize)]
@@ -248,25 +714,280 @@ pub struct ChatResponse {
     pub response: String,
     pub protocol_used: String,
     pub cost: f64,
+    /// 执行记录 ID（写入失败时为空）
+    pub execution_id: Option<String>,
 }
 
-/// 聊天处理函数（placeholder）
+/// 聊天：执行完整链路，执行记录带 `chat` 标签
 async fn chat_handler(
-    _state: Arc<ServerState>,
-    _request: ChatRequest,
+    state: Arc<ServerState>,
+    claims: &Claims,
+    request: ChatRequest,
 ) -> Result<ApiResponse<ChatResponse>> {
-    // TODO: 实现实际的聊天逻辑
-    // 1. 使用ShadowMode检测和脱敏PII
-    // 2. 调用ACSA Router处理请求
-    // 3. 记录指标
+    let protocol = admit_chat(&state, claims, &request).await?;
+    let log = state.router.execute(request.message.clone()).await?;
+    let execution_id = record_chat_execution(&state, &log);
+    attribute_key_usage(&state, &TenantId::from_claims(claims), claims.api_key_id.as_deref(), &request.message, &log).await;
 
     Ok(ApiResponse::success(ChatResponse {
-        response: "Hello from ACSA!".to_string(),
-        protocol_used: "Architect".to_string(),
-        cost: 0.001,
+        response: log.final_output.clone().unwrap_or_default(),
+        protocol_used: protocol.name(),
+        cost: log.total_cost,
+        execution_id,
     }))
 }
 
+/// 流式聊天：逐帧推送 sse_frame(chunk)，最后一帧 `event: log` 携带完整执行日志
+async fn chat_stream_handler(
+    state: Arc<ServerState>,
+    claims: &Claims,
+    request: ChatRequest,
+) -> Result<UnboundedReceiver<String>> {
+    admit_chat(&state, claims, &request).await?;
+    let (tenant, api_key_id) = (TenantId::from_claims(claims), claims.api_key_id.clone());
+    let (frames, receiver) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let (mut chunks, handle) = state.router.execute_streaming(request.message.clone());
+        while let Some(chunk) = chunks.recv().await {
+            match sse_frame(&chunk) {
+                Ok(frame) => {
+                    let _ = frames.send(frame);
+                }
+                Err(e) => warn!("⚠️  Failed to encode chat chunk: {}", e),
+            }
+        }
+
+        match handle.await.map_err(anyhow::Error::from).and_then(|r| r) {
+            Ok(log) => {
+                record_chat_execution(&state, &log);
+                attribute_key_usage(&state, &tenant, api_key_id.as_deref(), &request.message, &log).await;
+                match serde_json::to_string(&log) {
+                    Ok(log) => {
+                        let _ = frames.send(format!("event: log\ndata: {}\n\n", log));
+                    }
+                    Err(e) => warn!("⚠️  Failed to encode execution log: {}", e),
+                }
+            }
+            Err(e) => {
+                let _ = frames.send(format!("event: error\ndata: {}\n\n", serde_json::json!({ "error": e.to_string() })));
+            }
+        }
+    });
+    Ok(receiver)
+}
+
+/// 聊天前检查（权限、维护模式、预算），并确定本次使用的协议
+async fn admit_chat(state: &ServerState, claims: &Claims, request: &ChatRequest) -> Result<Protocol> {
+    admit_execution(state, claims).await?;
+
+    // 显式指定的协议（含自定义协议）必须存在，未指定时按消息内容检测
+    let protocol = {
+        let protocols = state.protocols.read().unwrap_or_else(|e| e.into_inner());
+        match request.protocol.as_deref().filter(|name| !name.eq_ignore_ascii_case("auto")) {
+            Some(name) => protocols.resolve(name).ok_or_else(|| {
+                AcsaError::new(ErrorCode::ConfigError, format!("Unknown protocol: {}", name))
+            })?,
+            None => Protocol::detect_from_input(&request.message).unwrap_or_else(|| protocols.current_protocol()),
+        }
+    };
+    // 环境守卫：生产环境未显式放行时拒绝 Ghost（显式指定与自动检测相同）
+    state.config.check_protocol(&protocol.name()).await?;
+    Ok(protocol)
+}
+
+/// 写入执行记录（标签 chat），失败只记日志
+fn record_chat_execution(state: &ServerState, log: &ACSAExecutionLog) -> Option<String> {
+    match state.executions.record(log, None, &["chat".to_string()]) {
+        Ok(id) => Some(id),
+        Err(e) => {
+            warn!("⚠️  Failed to record chat execution: {}", e);
+            None
+        }
+    }
+}
+
+/// 可选协议
+#[derive(Debug, Clone, Serialize)]
+pub struct ProtocolInfo {
+    pub name: String,
+    pub display_name: String,
+    pub custom: bool,
+    pub temperature: f64,
+    pub agent_weights: AgentWeights,
+    pub description: String,
+}
+
+/// 列出内置与自定义协议
+pub async fn list_protocols_handler(state: Arc<ServerState>) -> ApiResponse<Vec<ProtocolInfo>> {
+    let protocols = state.protocols.read().unwrap_or_else(|e| e.into_inner());
+    let list = protocols
+        .available()
+        .into_iter()
+        .map(|protocol| protocol_info(&protocols, protocol))
+        .collect();
+    ApiResponse::success(list)
+}
+
+fn protocol_info(protocols: &ProtocolManager, protocol: Protocol) -> ProtocolInfo {
+    let config = protocols.get_config(protocol.clone());
+    ProtocolInfo {
+        name: protocol.name(),
+        display_name: protocol.display_name(),
+        custom: matches!(protocol, Protocol::Custom(_)),
+        temperature: config.temperature,
+        agent_weights: config.agent_weights.clone(),
+        description: config.description.clone(),
+    }
+}
+
+/// 切换默认协议请求
+#[derive(Debug, Deserialize)]
+pub struct SetProtocolRequest {
+    pub protocol: String,
+}
+
+/// 切换默认协议（未指定协议的请求改用它，仅 admin 角色）
+pub async fn set_protocol_handler(
+    state: Arc<ServerState>,
+    claims: &Claims,
+    request: SetProtocolRequest,
+) -> (u16, ApiResponse<ProtocolInfo>) {
+    if let Err(denied) = authorize(claims, Permission::ManageProtocols) {
+        return denied;
+    }
+    let resolved = state.protocols.read().unwrap_or_else(|e| e.into_inner()).resolve(&request.protocol);
+    let Some(protocol) = resolved else {
+        return (404, ApiResponse::error(format!("Unknown protocol: {}", request.protocol)));
+    };
+    if let Err(e) = state.config.check_protocol(&protocol.name()).await {
+        return error_response(e);
+    }
+    let mut protocols = state.protocols.write().unwrap_or_else(|e| e.into_inner());
+    protocols.switch_protocol(protocol.clone());
+    (200, ApiResponse::success(protocol_info(&protocols, protocol)))
+}
+
+/// 流式聊天的 SSE 帧：事件名为 Agent 角色，阶段结束时事件名为 `done`
+///
+/// 处理函数用 `ACSARouter::execute_streaming` 取得片段通道，逐个写出本函数的结果，
+/// 最后发送一帧 `event: log` 携带完整执行日志
+pub fn sse_frame(chunk: &AgentChunk) -> Result<String> {
+    let event = if chunk.done { "done" } else { chunk.role.as_str() };
+    Ok(format!("event: {}\ndata: {}\n\n", event, serde_json::to_string(chunk)?))
+}
+
+/// OpenAI 兼容接口的响应：JSON（含错误）或 SSE 帧流
+pub enum ChatCompletionReply {
+    Json(u16, serde_json::Value),
+    Stream(UnboundedReceiver<String>),
+}
+
+/// OpenAI 兼容的模型列表
+pub async fn list_models_handler() -> ModelList {
+    ModelList::acsa()
+}
+
+/// OpenAI 兼容的对话补全：messages 映射为 Router 输入，stream 为 true 时逐帧推送 SSE
+pub async fn chat_completions_handler(
+    state: Arc<ServerState>,
+    claims: &Claims,
+    request: ChatCompletionRequest,
+) -> ChatCompletionReply {
+    let input = match request.to_router_input() {
+        Ok(input) => input,
+        Err(e) => return openai_error(400, &e.to_string(), "invalid_request_error"),
+    };
+    if let Err(e) = admit_execution(&state, claims).await {
+        return match e.downcast_ref::<AcsaError>() {
+            Some(acsa) => openai_error(acsa.to_report().code.http_status(), &acsa.user_message("en"), "acsa_error"),
+            None => openai_error(500, &e.to_string(), "server_error"),
+        };
+    }
+
+    if request.stream {
+        return ChatCompletionReply::Stream(stream_completion(state, request, input, claims));
+    }
+
+    match state.router.execute(input.clone()).await {
+        Ok(log) => {
+            let execution_id = record_openai_execution(&state, &log);
+            attribute_key_usage(&state, &TenantId::from_claims(claims), claims.api_key_id.as_deref(), &input, &log).await;
+            let response = ChatCompletionResponse::from_log(&request, &input, &log, execution_id);
+            match serde_json::to_value(&response) {
+                Ok(body) => ChatCompletionReply::Json(200, body),
+                Err(e) => openai_error(500, &e.to_string(), "server_error"),
+            }
+        }
+        Err(e) => openai_error(500, &e.to_string(), "server_error"),
+    }
+}
+
+/// 执行前检查：权限、维护模式、工作区与预算
+pub(crate) async fn admit_execution(state: &ServerState, claims: &Claims) -> Result<()> {
+    claims.require(Permission::Execute)?;
+    state.kill_switch.check(PausedOperation::Execution)?;
+    state.workspaces.resolve(claims).await?.check_budget().await
+}
+
+/// 后台执行并把 AgentChunk 转换为 chat.completion.chunk 帧；客户端断开后执行照常完成并记录
+fn stream_completion(
+    state: Arc<ServerState>,
+    request: ChatCompletionRequest,
+    input: String,
+    claims: &Claims,
+) -> UnboundedReceiver<String> {
+    let (tenant, api_key_id) = (TenantId::from_claims(claims), claims.api_key_id.clone());
+    let (frames, receiver) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let mut stream = CompletionStream::new(&request);
+        let send = |frame: anyhow::Result<String>| match frame {
+            Ok(frame) => {
+                let _ = frames.send(frame);
+            }
+            Err(e) => warn!("⚠️  Failed to encode completion chunk: {}", e),
+        };
+        send(stream.start_frame());
+
+        let (mut chunks, handle) = state.router.execute_streaming(input.clone());
+        while let Some(chunk) = chunks.recv().await {
+            if let Some(frame) = stream.chunk_frame(&chunk).transpose() {
+                send(frame);
+            }
+        }
+
+        match handle.await.map_err(anyhow::Error::from).and_then(|r| r) {
+            Ok(log) => {
+                record_openai_execution(&state, &log);
+                attribute_key_usage(&state, &tenant, api_key_id.as_deref(), &input, &log).await;
+                match stream.finish_frames(&input, &log) {
+                    Ok(finish) => finish.into_iter().for_each(|frame| send(Ok(frame))),
+                    Err(e) => warn!("⚠️  Failed to encode completion chunk: {}", e),
+                }
+            }
+            Err(e) => {
+                send(Ok(format!("data: {}\n\n", openai_compat::error_body(&e.to_string(), "server_error"))));
+                send(Ok(openai_compat::SSE_DONE.to_string()));
+            }
+        }
+    });
+    receiver
+}
+
+/// 写入执行记录（标签 openai），失败只记日志
+fn record_openai_execution(state: &ServerState, log: &ACSAExecutionLog) -> Option<String> {
+    match state.executions.record(log, None, &["openai".to_string()]) {
+        Ok(id) => Some(id),
+        Err(e) => {
+            warn!("⚠️  Failed to record OpenAI-compatible execution: {}", e);
+            None
+        }
+    }
+}
+
+fn openai_error(status: u16, message: &str, kind: &str) -> ChatCompletionReply {
+    ChatCompletionReply::Json(status, openai_compat::error_body(message, kind))
+}
+
 /// 登录请求
 #[derive(Debug, Deserialize)]
 pub struct LoginRequest {
@@ -282,26 +1003,1112 @@ pub struct LoginResponse {
     pub expires_in: u64,
 }
 
-/// 登录处理函数（placeholder）
+/// 登录：校验口令，签发带角色的 Token
 async fn login_handler(
     state: Arc<ServerState>,
-    _request: LoginRequest,
+    request: LoginRequest,
 ) -> Result<ApiResponse<LoginResponse>> {
-    // TODO: 验证用户名密码
-    // TODO: 生成token
-
-    let token_pair = state
-        .auth
-        .generate_token_pair("user_id", "username", vec!["user".to_string()])
-        .await?;
+    let token_pair = state.auth.login(&request.username, &request.password).await?;
 
     Ok(ApiResponse::success(LoginResponse {
         access_token: token_pair.access_token,
         refresh_token: token_pair.refresh_token,
-        expires_in: 3600,
+        expires_in: token_pair.expires_in,
     }))
 }
 
+/// 列出核心与自定义Agent
+pub async fn list_agents_handler(state: Arc<ServerState>) -> ApiResponse<AgentList> {
+    ApiResponse::success(state.agents.read().await.list_agents())
+}
+
+/// Agent调用统计查询参数
+#[derive(Debug, Default, Deserialize)]
+pub struct AgentStatsQuery {
+    /// 只统计最近 N 小时
+    pub since_hours: Option<i64>,
+}
+
+/// Agent调用统计排行榜（按每个采纳输出的成本排序）
+pub async fn agent_stats_handler(state: Arc<ServerState>, query: AgentStatsQuery) -> ApiResponse<Vec<AgentCallStats>> {
+    let since = query
+        .since_hours
+        .map(|hours| chrono::Utc::now() - chrono::Duration::hours(hours));
+    ApiResponse::success(state.agents.read().await.leaderboard(since))
+}
+
+/// 注册（或更新）自定义Agent，并交给Jarvis调度
+pub async fn register_agent_handler(
+    state: Arc<ServerState>,
+    claims: &Claims,
+    agent: CustomAgent,
+) -> (u16, ApiResponse<DiminishingReturns>) {
+    if let Err(denied) = authorize(claims, Permission::ManageAgents) {
+        return denied;
+    }
+    let diminishing = match state.agents.write().await.add_custom_agent(agent.clone()) {
+        Ok(diminishing) => diminishing,
+        Err(e) => return (400, ApiResponse::error(e.to_string())),
+    };
+    // 注册表已校验，调度器注册不会因配置失败
+    if let Err(e) = state.jarvis.write().await.register_custom_agent(&agent) {
+        return (500, ApiResponse::error(e.to_string()));
+    }
+    (200, ApiResponse::success(diminishing))
+}
+
+/// 移除自定义Agent
+pub async fn remove_agent_handler(state: Arc<ServerState>, claims: &Claims, name: String) -> (u16, ApiResponse<()>) {
+    if let Err(denied) = authorize(claims, Permission::ManageAgents) {
+        return denied;
+    }
+    if let Err(e) = state.agents.write().await.remove_custom_agent(&name) {
+        return (404, ApiResponse::error(e.to_string()));
+    }
+    // 调度器中可能尚未同步该Agent，忽略未找到
+    let _ = state.jarvis.write().await.unregister_custom_agent(&name);
+    (200, ApiResponse::success(()))
+}
+
+/// 检索执行记录（全文 + 标签 + 状态 + 时间范围，分页）
+pub async fn list_executions_handler(state: Arc<ServerState>, query: ExecutionQuery) -> ApiResponse<ExecutionPage> {
+    ApiResponse::success(state.executions.search(&query))
+}
+
+/// 单条执行记录（完整的各 Agent 响应）
+pub async fn execution_detail_handler(state: Arc<ServerState>, id: String) -> (u16, ApiResponse<ExecutionRecord>) {
+    match state.executions.get(&id) {
+        Some(record) => (200, ApiResponse::success(record)),
+        None => (404, ApiResponse::error(format!("Execution not found: {}", id))),
+    }
+}
+
+/// 执行实况 SSE 的响应
+pub enum ExecutionEventsReply {
+    /// SSE 帧流（Content-Type: text/event-stream）
+    Stream(UnboundedReceiver<String>),
+    /// 204：执行已结束且不再缓存，客户端应停止重连（详情见执行记录）
+    NoContent,
+    Error(u16, ApiResponse<()>),
+}
+
+/// 执行实况 SSE：id 为 Router 执行 ID（ExecutionLog.execution_id），Last-Event-ID 为最后收到的事件 ID
+///
+/// 不再缓存、但以该 ID 记录过的执行返回 204
+pub async fn execution_events_handler(
+    state: Arc<ServerState>,
+    id: String,
+    last_event_id: Option<String>,
+) -> ExecutionEventsReply {
+    if let Some(stream) = state.execution_events.stream(&id, last_event_id.as_deref()) {
+        return ExecutionEventsReply::Stream(stream);
+    }
+    if state.executions.get(&id).is_some() {
+        ExecutionEventsReply::NoContent
+    } else {
+        ExecutionEventsReply::Error(404, ApiResponse::error(format!("Execution not found: {}", id)))
+    }
+}
+
+/// 增删标签请求
+#[derive(Debug, Default, Deserialize)]
+pub struct TagExecutionRequest {
+    #[serde(default)]
+    pub add: Vec<String>,
+    #[serde(default)]
+    pub remove: Vec<String>,
+}
+
+/// 为执行记录增删标签
+pub async fn tag_execution_handler(
+    state: Arc<ServerState>,
+    claims: &Claims,
+    id: String,
+    request: TagExecutionRequest,
+) -> (u16, ApiResponse<ExecutionRecord>) {
+    if let Err(denied) = authorize(claims, Permission::TagExecutions) {
+        return denied;
+    }
+    if state.executions.get(&id).is_none() {
+        return (404, ApiResponse::error(format!("Execution not found: {}", id)));
+    }
+    match state.executions.tag(&id, &request.add, &request.remove) {
+        Ok(record) => (200, ApiResponse::success(record)),
+        Err(e) => (500, ApiResponse::error(e.to_string())),
+    }
+}
+
+/// 执行记录网页视图（只读，维护模式下同样可用）
+pub async fn executions_page_handler(state: Arc<ServerState>, query: ExecutionQuery) -> String {
+    render_executions_html(&query, &state.executions.search(&query))
+}
+
+fn render_executions_html(query: &ExecutionQuery, page: &ExecutionPage) -> String {
+    let rows: String = page
+        .items
+        .iter()
+        .map(|item| {
+            format!(
+                "<tr><td><a href=\"/api/v1/executions/{id}\">{id}</a></td><td>{started}</td><td>{status}</td>\
+                 <td>${cost:.4}</td><td>{time} ms</td><td>{tags}</td><td>{input}</td><td>{output}</td></tr>\n",
+                id = html_escape(&item.id),
+                started = item.started_at.format("%Y-%m-%d %H:%M:%S"),
+                status = if item.success { "✅" } else { "❌" },
+                cost = item.total_cost,
+                time = item.total_time_ms,
+                tags = html_escape(&item.tags.iter().cloned().collect::<Vec<_>>().join(", ")),
+                input = html_escape(&item.input_preview),
+                output = html_escape(item.output_preview.as_deref().unwrap_or("")),
+            )
+        })
+        .collect();
+
+    format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>ACSA Executions</title></head><body>\n\
+         <h1>ACSA Executions</h1>\n\
+         <form method=\"get\"><input name=\"text\" value=\"{text}\" placeholder=\"Search inputs and outputs\"> \
+         <button>Search</button></form>\n\
+         <p>{total} matching runs</p>\n\
+         <table border=\"1\" cellpadding=\"4\"><tr><th>ID</th><th>Started</th><th>OK</th><th>Cost</th>\
+         <th>Time</th><th>Tags</th><th>Input</th><th>Output</th></tr>\n{rows}</table>\n</body></html>\n",
+        text = html_escape(query.text.as_deref().unwrap_or("")),
+        total = page.total,
+        rows = rows,
+    )
+}
+
+/// 仪表盘静态资源（公开）：返回 (HTTP状态码, Content-Type, 内容)
+pub async fn dashboard_asset_handler(asset: Option<String>) -> (u16, &'static str, &'static str) {
+    match web_dashboard::dashboard_asset(asset.as_deref().unwrap_or("")) {
+        Some((content_type, body)) => (200, content_type, body),
+        None => (404, "text/plain; charset=utf-8", "Not found"),
+    }
+}
+
+/// 仪表盘快照：进行中的执行、最近执行、SOSA 端点健康度、Provider 成本与 H(t) 趋势
+pub async fn dashboard_snapshot_handler(state: Arc<ServerState>) -> ApiResponse<DashboardSnapshot> {
+    let recent = state.executions.search(&ExecutionQuery::default()).items;
+    let endpoints = state.api_pool.list_endpoints().await;
+    let (providers, total_cost) = {
+        let api = state.api.read().await;
+        (api.get_all_stats().into_iter().cloned().collect(), api.get_total_cost())
+    };
+    let bio = SOVEREIGNTY.get_bio_activity().await;
+    let ht_trend = state.sovereignty_trend.record(bio.calculated_at, bio.current);
+
+    ApiResponse::success(DashboardSnapshot {
+        active_executions: state.execution_events.active(),
+        recent,
+        endpoints,
+        providers,
+        total_cost,
+        sovereignty: SovereigntySummary::new(&bio, SOVEREIGNTY.is_enabled().await),
+        ht_trend,
+        generated_at: chrono::Utc::now(),
+    })
+}
+
+/// 熔断开关请求
+#[derive(Debug, Deserialize)]
+pub struct KillSwitchRequest {
+    /// true 拉闸，false 合闸
+    pub engaged: bool,
+    pub reason: Option<String>,
+}
+
+/// 查询熔断开关状态（未拉闸时 data 为 null）
+pub async fn kill_switch_status_handler(state: Arc<ServerState>) -> ApiResponse<Option<KillSwitchState>> {
+    ApiResponse::success(state.kill_switch.status())
+}
+
+/// 拉闸 / 合闸（仅 admin 角色）
+pub async fn kill_switch_handler(
+    state: Arc<ServerState>,
+    claims: &Claims,
+    request: KillSwitchRequest,
+) -> (u16, ApiResponse<KillSwitchState>) {
+    if let Err(denied) = authorize(claims, Permission::ManageKillSwitch) {
+        return denied;
+    }
+
+    let result = if request.engaged {
+        state
+            .kill_switch
+            .engage(&claims.username, request.reason, KillSwitchSource::Http)
+            .await
+    } else {
+        state.kill_switch.release(&claims.username, KillSwitchSource::Http).await
+    };
+    match result {
+        Ok(kill_switch) => (200, ApiResponse::success(kill_switch)),
+        Err(e) => (500, ApiResponse::error(e.to_string())),
+    }
+}
+
+/// 当前身份所在工作区的概要
+pub async fn current_workspace_handler(state: Arc<ServerState>, claims: &Claims) -> (u16, ApiResponse<WorkspaceSummary>) {
+    match state.workspaces.resolve(claims).await {
+        Ok(workspace) => (200, ApiResponse::success(workspace.summary().await)),
+        Err(e) => match e.downcast::<AcsaError>() {
+            Ok(acsa) => ApiResponse::from_error(&acsa),
+            Err(e) => (500, ApiResponse::error(e.to_string())),
+        },
+    }
+}
+
+/// 列出所有工作区（仅 admin 角色）
+pub async fn list_workspaces_handler(state: Arc<ServerState>, claims: &Claims) -> (u16, ApiResponse<Vec<WorkspaceConfig>>) {
+    if let Err(denied) = authorize(claims, Permission::ManageWorkspaces) {
+        return denied;
+    }
+    (200, ApiResponse::success(state.workspaces.list().await))
+}
+
+/// 创建工作区（仅 admin 角色）
+pub async fn create_workspace_handler(
+    state: Arc<ServerState>,
+    claims: &Claims,
+    config: WorkspaceConfig,
+) -> (u16, ApiResponse<WorkspaceSummary>) {
+    if let Err(denied) = authorize(claims, Permission::ManageWorkspaces) {
+        return denied;
+    }
+    match state.workspaces.create(config).await {
+        Ok(workspace) => (200, ApiResponse::success(workspace.summary().await)),
+        Err(e) => (400, ApiResponse::error(e.to_string())),
+    }
+}
+
+/// 当前工作区各 Provider 的预算状态（仅 admin 角色）
+pub async fn list_budgets_handler(state: Arc<ServerState>, claims: &Claims) -> (u16, ApiResponse<Vec<BudgetStatus>>) {
+    if let Err(denied) = authorize(claims, Permission::ManageBudgets) {
+        return denied;
+    }
+    match state.workspaces.resolve(claims).await {
+        Ok(workspace) => (200, ApiResponse::success(workspace.api.read().await.get_budget_status())),
+        Err(e) => error_response(e),
+    }
+}
+
+/// 设置预算请求（policy 为空时移除该 Provider 的预算）
+#[derive(Debug, Deserialize)]
+pub struct SetBudgetRequest {
+    pub provider: ApiProvider,
+    #[serde(default)]
+    pub policy: Option<BudgetPolicy>,
+}
+
+/// 设置 / 移除当前工作区某个 Provider 的预算（仅 admin 角色）
+pub async fn set_budget_handler(
+    state: Arc<ServerState>,
+    claims: &Claims,
+    request: SetBudgetRequest,
+) -> (u16, ApiResponse<Option<BudgetStatus>>) {
+    if let Err(denied) = authorize(claims, Permission::ManageBudgets) {
+        return denied;
+    }
+    let workspace = match state.workspaces.resolve(claims).await {
+        Ok(workspace) => workspace,
+        Err(e) => return error_response(e),
+    };
+    let mut api = workspace.api.write().await;
+    let result = match request.policy {
+        Some(policy) => api.set_budget(request.provider, policy).await,
+        None => api.remove_budget(request.provider).await,
+    };
+    match result {
+        Ok(()) => (200, ApiResponse::success(api.budget_status(request.provider))),
+        Err(e) => error_response(e),
+    }
+}
+
+/// 创建用户请求
+#[derive(Debug, Deserialize)]
+pub struct CreateUserRequest {
+    pub username: String,
+    pub password: String,
+    pub roles: Vec<Role>,
+    #[serde(default)]
+    pub workspace_id: Option<String>,
+}
+
+/// 调整角色请求
+#[derive(Debug, Deserialize)]
+pub struct UpdateRolesRequest {
+    pub roles: Vec<Role>,
+}
+
+/// 列出用户（仅 admin 角色）
+pub async fn list_users_handler(state: Arc<ServerState>, claims: &Claims) -> (u16, ApiResponse<Vec<UserAccount>>) {
+    if let Err(denied) = authorize(claims, Permission::ManageUsers) {
+        return denied;
+    }
+    (200, ApiResponse::success(state.auth.list_users().await))
+}
+
+/// 创建用户（仅 admin 角色）
+pub async fn create_user_handler(
+    state: Arc<ServerState>,
+    claims: &Claims,
+    request: CreateUserRequest,
+) -> (u16, ApiResponse<UserAccount>) {
+    if let Err(denied) = authorize(claims, Permission::ManageUsers) {
+        return denied;
+    }
+    match state
+        .auth
+        .create_user(&request.username, &request.password, request.roles, request.workspace_id)
+        .await
+    {
+        Ok(account) => (200, ApiResponse::success(account)),
+        Err(e) => (400, ApiResponse::error(e.to_string())),
+    }
+}
+
+/// 调整用户角色（仅 admin 角色；不能撤掉最后一名 admin）
+pub async fn update_user_roles_handler(
+    state: Arc<ServerState>,
+    claims: &Claims,
+    username: String,
+    request: UpdateRolesRequest,
+) -> (u16, ApiResponse<UserAccount>) {
+    if let Err(denied) = authorize(claims, Permission::ManageUsers) {
+        return denied;
+    }
+    match state.auth.set_roles(&username, request.roles).await {
+        Ok(account) => (200, ApiResponse::success(account)),
+        Err(e) => (400, ApiResponse::error(e.to_string())),
+    }
+}
+
+/// 删除用户（仅 admin 角色）
+pub async fn delete_user_handler(state: Arc<ServerState>, claims: &Claims, username: String) -> (u16, ApiResponse<()>) {
+    if let Err(denied) = authorize(claims, Permission::ManageUsers) {
+        return denied;
+    }
+    match state.auth.delete_user(&username).await {
+        Ok(()) => (200, ApiResponse::success(())),
+        Err(e) => (400, ApiResponse::error(e.to_string())),
+    }
+}
+
+/// 创建 API 密钥请求
+#[derive(Debug, Deserialize)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    pub roles: Vec<Role>,
+    #[serde(default)]
+    pub workspace_id: Option<String>,
+    #[serde(default)]
+    pub rate_limit: Option<RateLimitRule>,
+}
+
+/// 创建 API 密钥的响应（明文只在此返回一次）
+#[derive(Debug, Serialize)]
+pub struct CreatedApiKey {
+    pub api_key: String,
+    pub info: ApiKeyInfo,
+}
+
+/// API 密钥列表及各自用量（仅 admin 角色）；指定 `tenant` 时只列出该租户的密钥
+pub async fn list_api_keys_handler(
+    state: Arc<ServerState>,
+    claims: &Claims,
+    tenant: Option<String>,
+) -> (u16, ApiResponse<Vec<(ApiKeyInfo, Option<KeyUsage>)>>) {
+    if let Err(denied) = authorize(claims, Permission::ManageUsers) {
+        return denied;
+    }
+    let keys = match tenant.map(TenantId::new) {
+        Some(tenant) => state.api_keys.list_for_tenant(&tenant).await,
+        None => state.api_keys.list().await,
+    };
+    match keys {
+        Ok(keys) => {
+            let mut list = Vec::with_capacity(keys.len());
+            for info in keys {
+                // 用量记录在密钥所属租户的 ApiManager 中
+                let usage = match state.workspaces.get(info.tenant().as_str()).await {
+                    Some(workspace) => workspace.api.read().await.key_usage(&info.key_id).cloned(),
+                    None => None,
+                };
+                list.push((info, usage));
+            }
+            (200, ApiResponse::success(list))
+        }
+        Err(e) => error_response(e),
+    }
+}
+
+/// 创建 API 密钥（仅 admin 角色）
+pub async fn create_api_key_handler(
+    state: Arc<ServerState>,
+    claims: &Claims,
+    request: CreateApiKeyRequest,
+) -> (u16, ApiResponse<CreatedApiKey>) {
+    if let Err(denied) = authorize(claims, Permission::ManageUsers) {
+        return denied;
+    }
+    match state
+        .api_keys
+        .create(&request.name, request.roles, request.workspace_id, request.rate_limit)
+        .await
+    {
+        Ok((info, api_key)) => (200, ApiResponse::success(CreatedApiKey { api_key, info })),
+        Err(e) => (400, ApiResponse::error(e.to_string())),
+    }
+}
+
+/// 吊销 API 密钥（仅 admin 角色）
+pub async fn revoke_api_key_handler(state: Arc<ServerState>, claims: &Claims, key_id: String) -> (u16, ApiResponse<()>) {
+    if let Err(denied) = authorize(claims, Permission::ManageUsers) {
+        return denied;
+    }
+    match state.api_keys.revoke(&key_id).await {
+        Ok(true) => (200, ApiResponse::success(())),
+        Ok(false) => (404, ApiResponse::error(format!("Unknown API key: {}", key_id))),
+        Err(e) => error_response(e),
+    }
+}
+
+/// 创建定时任务请求
+#[derive(Debug, Deserialize)]
+pub struct CreateScheduleRequest {
+    pub name: String,
+    /// cron 表达式（UTC），如 `0 9 * * mon-fri`
+    pub cron: String,
+    pub target: ScheduleTarget,
+    #[serde(default)]
+    pub missed_runs: MissedRunPolicy,
+}
+
+/// 定时任务列表
+pub async fn list_schedules_handler(state: Arc<ServerState>, claims: &Claims) -> (u16, ApiResponse<Vec<ScheduledJob>>) {
+    if let Err(denied) = authorize(claims, Permission::Read) {
+        return denied;
+    }
+    match state.schedules.list() {
+        Ok(jobs) => (200, ApiResponse::success(jobs)),
+        Err(e) => error_response(e),
+    }
+}
+
+/// 创建定时任务
+pub async fn create_schedule_handler(
+    state: Arc<ServerState>,
+    claims: &Claims,
+    request: CreateScheduleRequest,
+) -> (u16, ApiResponse<ScheduledJob>) {
+    if let Err(denied) = authorize(claims, Permission::Execute) {
+        return denied;
+    }
+    match state.schedules.add(&request.name, &request.cron, request.target, request.missed_runs) {
+        Ok(job) => (200, ApiResponse::success(job)),
+        Err(e) => (400, ApiResponse::error(e.to_string())),
+    }
+}
+
+/// 删除定时任务
+pub async fn delete_schedule_handler(state: Arc<ServerState>, claims: &Claims, id: String) -> (u16, ApiResponse<()>) {
+    if let Err(denied) = authorize(claims, Permission::Execute) {
+        return denied;
+    }
+    match state.schedules.remove(&id) {
+        Ok(true) => (200, ApiResponse::success(())),
+        Ok(false) => (404, ApiResponse::error(format!("Unknown schedule: {}", id))),
+        Err(e) => error_response(e),
+    }
+}
+
+/// 死信列表（最早的在前）
+pub async fn list_dead_letters_handler(state: Arc<ServerState>, claims: &Claims) -> (u16, ApiResponse<Vec<DeadLetter>>) {
+    if let Err(denied) = authorize(claims, Permission::Read) {
+        return denied;
+    }
+    (200, ApiResponse::success(state.events.dead_letters().list()))
+}
+
+/// 重新投递一条死信：data 为 true 表示处理成功，false 表示再次失败并已重新入队
+pub async fn redrive_dead_letter_handler(state: Arc<ServerState>, claims: &Claims, id: String) -> (u16, ApiResponse<bool>) {
+    if let Err(denied) = authorize(claims, Permission::Execute) {
+        return denied;
+    }
+    if state.events.dead_letters().get(&id).is_none() {
+        return (404, ApiResponse::error(format!("Unknown dead letter: {}", id)));
+    }
+    match state.events.redrive(&id).await {
+        Ok(delivered) => (200, ApiResponse::success(delivered)),
+        Err(e) => (409, ApiResponse::error(e.to_string())),
+    }
+}
+
+/// 丢弃一条死信
+pub async fn delete_dead_letter_handler(state: Arc<ServerState>, claims: &Claims, id: String) -> (u16, ApiResponse<()>) {
+    if let Err(denied) = authorize(claims, Permission::Execute) {
+        return denied;
+    }
+    match state.events.dead_letters().take(&id) {
+        Some(_) => (200, ApiResponse::success(())),
+        None => (404, ApiResponse::error(format!("Unknown dead letter: {}", id))),
+    }
+}
+
+/// 审批决定请求
+#[derive(Debug, Default, Deserialize)]
+pub struct ApprovalDecisionRequest {
+    #[serde(default)]
+    pub comment: Option<String>,
+}
+
+/// 审批列表（`status` 为 pending / approved / rejected，为空时全部）
+pub async fn list_approvals_handler(
+    state: Arc<ServerState>,
+    claims: &Claims,
+    status: Option<String>,
+) -> (u16, ApiResponse<Vec<ApprovalRequest>>) {
+    if let Err(denied) = authorize(claims, Permission::Read) {
+        return denied;
+    }
+    let status = match status.as_deref().map(str::parse::<ApprovalStatus>).transpose() {
+        Ok(status) => status,
+        Err(e) => return (400, ApiResponse::error(e.to_string())),
+    };
+    match state.approvals.list(status) {
+        Ok(requests) => (200, ApiResponse::success(requests)),
+        Err(e) => error_response(e),
+    }
+}
+
+/// 批准：暂停的执行继续到 Omega，返回完成后的执行日志
+pub async fn approve_execution_handler(
+    state: Arc<ServerState>,
+    claims: &Claims,
+    id: String,
+    request: ApprovalDecisionRequest,
+) -> (u16, ApiResponse<ACSAExecutionLog>) {
+    decide_approval(state, claims, id, true, request).await
+}
+
+/// 驳回：暂停的执行中止
+pub async fn reject_execution_handler(
+    state: Arc<ServerState>,
+    claims: &Claims,
+    id: String,
+    request: ApprovalDecisionRequest,
+) -> (u16, ApiResponse<ACSAExecutionLog>) {
+    decide_approval(state, claims, id, false, request).await
+}
+
+async fn decide_approval(
+    state: Arc<ServerState>,
+    claims: &Claims,
+    id: String,
+    approve: bool,
+    request: ApprovalDecisionRequest,
+) -> (u16, ApiResponse<ACSAExecutionLog>) {
+    if let Err(denied) = authorize(claims, Permission::ApproveExecutions) {
+        return denied;
+    }
+    match state.approvals.get(&id) {
+        Ok(Some(existing)) if existing.status != ApprovalStatus::Pending => {
+            return (409, ApiResponse::error(format!("Approval {} was already {}", id, existing.status.label())));
+        }
+        Ok(Some(_)) => {}
+        Ok(None) => return (404, ApiResponse::error(format!("Unknown approval: {}", id))),
+        Err(e) => return error_response(e),
+    }
+    match state.router.decide_approval(&id, approve, &claims.sub, request.comment).await {
+        Ok(log) => {
+            if let Err(e) = state.executions.record(&log, None, &["approval".to_string()]) {
+                warn!("⚠️  Failed to record approved execution: {}", e);
+            }
+            (200, ApiResponse::success(log))
+        }
+        Err(e) => error_response(e),
+    }
+}
+
+/// 运行一次到期的定时任务：执行记录带 `scheduled` 标签
+async fn run_scheduled(state: Arc<ServerState>, run: DueRun) -> Result<()> {
+    match run.job.target {
+        ScheduleTarget::Execution { input } => run_scheduled_execution(&state, input).await,
+        ScheduleTarget::Workflow { workflow } => {
+            let mut engine = WorkflowEngine::new(ConcurrencyManager::new(ConcurrencyConfig::default()))
+                .with_kill_switch(state.kill_switch.clone());
+            engine
+                .execute_workflow_with(workflow, move |step| {
+                    let state = state.clone();
+                    async move { run_scheduled_execution(&state, step.name).await }
+                })
+                .await
+                .map(|_| ())
+        }
+    }
+}
+
+async fn run_scheduled_execution(state: &ServerState, input: String) -> Result<()> {
+    let log = state.router.execute(input).await?;
+    if let Err(e) = state.executions.record(&log, None, &["scheduled".to_string()]) {
+        warn!("⚠️  Failed to record scheduled execution: {}", e);
+    }
+    if !log.success {
+        let reason = log.jarvis_block.unwrap_or_else(|| "audit did not pass".to_string());
+        return Err(anyhow::anyhow!("Scheduled execution did not succeed: {}", reason));
+    }
+    Ok(())
+}
+
+/// axum 适配：提取路径、查询、请求头与 JSON 请求体，调用上面的处理函数，把 (状态码, ApiResponse) 写成响应
+#[cfg(feature = "server")]
+mod transport {
+    use std::convert::Infallible;
+
+    use axum::body::Body;
+    use axum::extract::{Path, Query, Request, State};
+    use axum::http::{header, HeaderMap, StatusCode};
+    use axum::middleware::{self, Next};
+    use axum::response::{Html, IntoResponse, Response};
+    use axum::routing::{delete, get, post, put};
+    use axum::{Extension, Json, Router};
+    use chrono::{DateTime, Utc};
+
+    use super::*;
+
+    type Shared = State<Arc<ServerState>>;
+
+    /// 公开路由只经过全局限流；其余路由经 auth_middleware 认证与授权，Claims 放入请求扩展
+    pub(super) fn router(state: Arc<ServerState>) -> Router {
+        let public = Router::new()
+            .route("/health", get(health))
+            .route("/metrics", get(metrics))
+            .route("/api/v1/auth/login", post(login))
+            .route("/dashboard", get(dashboard_index))
+            .route("/dashboard/:asset", get(dashboard_asset));
+
+        let protected = Router::new()
+            .route("/api/v1/chat", post(chat))
+            .route("/api/v1/chat/stream", post(chat_stream))
+            .route("/api/v1/protocols", get(list_protocols))
+            .route("/v1/models", get(list_models))
+            .route("/v1/chat/completions", post(chat_completions))
+            .route("/api/v1/agents", get(list_agents).post(register_agent))
+            .route("/api/v1/agents/stats", get(agent_stats))
+            .route("/api/v1/agents/:name", delete(remove_agent))
+            .route("/executions", get(executions_page))
+            .route("/api/v1/executions", get(list_executions))
+            .route("/api/v1/executions/:id", get(execution_detail))
+            .route("/api/v1/executions/:id/tags", post(tag_execution))
+            .route("/api/v1/executions/:id/events", get(execution_events))
+            .route("/api/v1/workspace", get(current_workspace))
+            .route("/api/v1/dashboard", get(dashboard_snapshot))
+            .route("/api/v1/admin/kill-switch", get(kill_switch_status).post(kill_switch))
+            .route("/api/v1/admin/workspaces", get(list_workspaces).post(create_workspace))
+            .route("/api/v1/admin/protocol", put(set_protocol))
+            .route("/api/v1/admin/budgets", get(list_budgets).put(set_budget))
+            .route("/api/v1/admin/users", get(list_users).post(create_user))
+            .route("/api/v1/admin/users/:username", put(update_user_roles).delete(delete_user))
+            .route("/api/v1/admin/api-keys", get(list_api_keys).post(create_api_key))
+            .route("/api/v1/admin/api-keys/:key_id", delete(revoke_api_key))
+            .route("/api/v1/schedules", get(list_schedules).post(create_schedule))
+            .route("/api/v1/schedules/:id", delete(delete_schedule))
+            .route("/api/v1/events/dead-letters", get(list_dead_letters))
+            .route("/api/v1/events/dead-letters/:id", delete(delete_dead_letter))
+            .route("/api/v1/events/dead-letters/:id/redrive", post(redrive_dead_letter))
+            .route("/api/v1/approvals", get(list_approvals))
+            .route("/api/v1/approvals/:id/approve", post(approve_execution))
+            .route("/api/v1/approvals/:id/reject", post(reject_execution))
+            .route_layer(middleware::from_fn_with_state(state.clone(), authenticate));
+
+        public
+            .merge(protected)
+            .layer(middleware::from_fn_with_state(state.clone(), rate_limit))
+            .with_state(state)
+    }
+
+    async fn authenticate(State(state): Shared, mut request: Request, next: Next) -> Response {
+        let headers = request.headers();
+        let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+        let authenticated = HttpServer::auth_middleware(
+            &state,
+            request.method().as_str(),
+            request.uri().path(),
+            header("authorization"),
+            header("x-api-key"),
+        )
+        .await;
+        match authenticated {
+            Ok(claims) => {
+                if let Some(claims) = claims {
+                    request.extensions_mut().insert(claims);
+                }
+                next.run(request).await
+            }
+            Err(denied) => reply(denied),
+        }
+    }
+
+    async fn rate_limit(State(state): Shared, request: Request, next: Next) -> Response {
+        match HttpServer::rate_limit_middleware(&state).await {
+            Ok(()) => next.run(request).await,
+            Err(limited) => reply(limited),
+        }
+    }
+
+    fn status_code(status: u16) -> StatusCode {
+        StatusCode::from_u16(status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    fn reply<T: Serialize>((status, body): (u16, ApiResponse<T>)) -> Response {
+        (status_code(status), Json(body)).into_response()
+    }
+
+    fn ok<T: Serialize>(body: ApiResponse<T>) -> Response {
+        reply((200, body))
+    }
+
+    fn result<T: Serialize>(result: Result<ApiResponse<T>>) -> Response {
+        match result {
+            Ok(body) => ok(body),
+            Err(e) => reply(error_response::<T>(e)),
+        }
+    }
+
+    /// 把预先编码好的 SSE 帧写成 text/event-stream
+    fn event_stream(frames: UnboundedReceiver<String>) -> Response {
+        let stream = futures::stream::unfold(frames, |mut frames| async move {
+            frames.recv().await.map(|frame| (Ok::<_, Infallible>(frame), frames))
+        });
+        (
+            [(header::CONTENT_TYPE, "text/event-stream"), (header::CACHE_CONTROL, "no-cache")],
+            Body::from_stream(stream),
+        )
+            .into_response()
+    }
+
+    /// 执行记录的查询参数（tags 以逗号分隔）
+    #[derive(Debug, Default, Deserialize)]
+    #[serde(default)]
+    struct ExecutionParams {
+        text: Option<String>,
+        tags: Option<String>,
+        success: Option<bool>,
+        since: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+        limit: Option<usize>,
+        offset: Option<usize>,
+    }
+
+    impl From<ExecutionParams> for ExecutionQuery {
+        fn from(params: ExecutionParams) -> Self {
+            let defaults = ExecutionQuery::default();
+            Self {
+                text: params.text,
+                tags: params
+                    .tags
+                    .map(|tags| tags.split(',').map(str::trim).filter(|t| !t.is_empty()).map(String::from).collect())
+                    .unwrap_or_default(),
+                success: params.success,
+                since: params.since,
+                until: params.until,
+                limit: params.limit.unwrap_or(defaults.limit),
+                offset: params.offset.unwrap_or(defaults.offset),
+            }
+        }
+    }
+
+    #[derive(Debug, Default, Deserialize)]
+    struct TenantParams {
+        tenant: Option<String>,
+    }
+
+    #[derive(Debug, Default, Deserialize)]
+    struct StatusParams {
+        status: Option<String>,
+    }
+
+    // ----- 公开路由 -----
+
+    async fn health(State(state): Shared) -> Response {
+        ([(header::CONTENT_TYPE, "application/json")], HttpServer::health_handler(state).await).into_response()
+    }
+
+    async fn metrics(State(state): Shared) -> Response {
+        (
+            [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+            HttpServer::metrics_handler(state).await,
+        )
+            .into_response()
+    }
+
+    async fn login(State(state): Shared, Json(request): Json<LoginRequest>) -> Response {
+        match login_handler(state, request).await {
+            Ok(body) => ok(body),
+            Err(e) => reply((401, ApiResponse::<()>::error(e.to_string()))),
+        }
+    }
+
+    fn asset((status, content_type, body): (u16, &'static str, &'static str)) -> Response {
+        (status_code(status), [(header::CONTENT_TYPE, content_type)], body).into_response()
+    }
+
+    async fn dashboard_index() -> Response {
+        asset(dashboard_asset_handler(None).await)
+    }
+
+    async fn dashboard_asset(Path(name): Path<String>) -> Response {
+        asset(dashboard_asset_handler(Some(name)).await)
+    }
+
+    // ----- 执行 -----
+
+    async fn chat(State(state): Shared, Extension(claims): Extension<Claims>, Json(request): Json<ChatRequest>) -> Response {
+        result(chat_handler(state, &claims, request).await)
+    }
+
+    async fn chat_stream(
+        State(state): Shared,
+        Extension(claims): Extension<Claims>,
+        Json(request): Json<ChatRequest>,
+    ) -> Response {
+        match chat_stream_handler(state, &claims, request).await {
+            Ok(frames) => event_stream(frames),
+            Err(e) => reply(error_response::<()>(e)),
+        }
+    }
+
+    async fn list_protocols(State(state): Shared) -> Response {
+        ok(list_protocols_handler(state).await)
+    }
+
+    async fn list_models() -> Response {
+        Json(list_models_handler().await).into_response()
+    }
+
+    async fn chat_completions(
+        State(state): Shared,
+        Extension(claims): Extension<Claims>,
+        Json(request): Json<ChatCompletionRequest>,
+    ) -> Response {
+        match chat_completions_handler(state, &claims, request).await {
+            ChatCompletionReply::Json(status, body) => (status_code(status), Json(body)).into_response(),
+            ChatCompletionReply::Stream(frames) => event_stream(frames),
+        }
+    }
+
+    // ----- Agent -----
+
+    async fn list_agents(State(state): Shared) -> Response {
+        ok(list_agents_handler(state).await)
+    }
+
+    async fn agent_stats(State(state): Shared, Query(query): Query<AgentStatsQuery>) -> Response {
+        ok(agent_stats_handler(state, query).await)
+    }
+
+    async fn register_agent(
+        State(state): Shared,
+        Extension(claims): Extension<Claims>,
+        Json(agent): Json<CustomAgent>,
+    ) -> Response {
+        reply(register_agent_handler(state, &claims, agent).await)
+    }
+
+    async fn remove_agent(State(state): Shared, Extension(claims): Extension<Claims>, Path(name): Path<String>) -> Response {
+        reply(remove_agent_handler(state, &claims, name).await)
+    }
+
+    // ----- 执行记录 -----
+
+    async fn executions_page(State(state): Shared, Query(params): Query<ExecutionParams>) -> Response {
+        Html(executions_page_handler(state, params.into()).await).into_response()
+    }
+
+    async fn list_executions(State(state): Shared, Query(params): Query<ExecutionParams>) -> Response {
+        ok(list_executions_handler(state, params.into()).await)
+    }
+
+    async fn execution_detail(State(state): Shared, Path(id): Path<String>) -> Response {
+        reply(execution_detail_handler(state, id).await)
+    }
+
+    async fn tag_execution(
+        State(state): Shared,
+        Extension(claims): Extension<Claims>,
+        Path(id): Path<String>,
+        Json(request): Json<TagExecutionRequest>,
+    ) -> Response {
+        reply(tag_execution_handler(state, &claims, id, request).await)
+    }
+
+    async fn execution_events(State(state): Shared, Path(id): Path<String>, headers: HeaderMap) -> Response {
+        let last_event_id = headers.get("last-event-id").and_then(|value| value.to_str().ok()).map(String::from);
+        match execution_events_handler(state, id, last_event_id).await {
+            ExecutionEventsReply::Stream(frames) => event_stream(frames),
+            ExecutionEventsReply::NoContent => StatusCode::NO_CONTENT.into_response(),
+            ExecutionEventsReply::Error(status, body) => reply((status, body)),
+        }
+    }
+
+    // ----- 工作区与仪表盘 -----
+
+    async fn current_workspace(State(state): Shared, Extension(claims): Extension<Claims>) -> Response {
+        reply(current_workspace_handler(state, &claims).await)
+    }
+
+    async fn dashboard_snapshot(State(state): Shared) -> Response {
+        ok(dashboard_snapshot_handler(state).await)
+    }
+
+    // ----- 管理接口 -----
+
+    async fn kill_switch_status(State(state): Shared) -> Response {
+        ok(kill_switch_status_handler(state).await)
+    }
+
+    async fn kill_switch(
+        State(state): Shared,
+        Extension(claims): Extension<Claims>,
+        Json(request): Json<KillSwitchRequest>,
+    ) -> Response {
+        reply(kill_switch_handler(state, &claims, request).await)
+    }
+
+    async fn list_workspaces(State(state): Shared, Extension(claims): Extension<Claims>) -> Response {
+        reply(list_workspaces_handler(state, &claims).await)
+    }
+
+    async fn create_workspace(
+        State(state): Shared,
+        Extension(claims): Extension<Claims>,
+        Json(config): Json<WorkspaceConfig>,
+    ) -> Response {
+        reply(create_workspace_handler(state, &claims, config).await)
+    }
+
+    async fn set_protocol(
+        State(state): Shared,
+        Extension(claims): Extension<Claims>,
+        Json(request): Json<SetProtocolRequest>,
+    ) -> Response {
+        reply(set_protocol_handler(state, &claims, request).await)
+    }
+
+    async fn list_budgets(State(state): Shared, Extension(claims): Extension<Claims>) -> Response {
+        reply(list_budgets_handler(state, &claims).await)
+    }
+
+    async fn set_budget(
+        State(state): Shared,
+        Extension(claims): Extension<Claims>,
+        Json(request): Json<SetBudgetRequest>,
+    ) -> Response {
+        reply(set_budget_handler(state, &claims, request).await)
+    }
+
+    async fn list_users(State(state): Shared, Extension(claims): Extension<Claims>) -> Response {
+        reply(list_users_handler(state, &claims).await)
+    }
+
+    async fn create_user(
+        State(state): Shared,
+        Extension(claims): Extension<Claims>,
+        Json(request): Json<CreateUserRequest>,
+    ) -> Response {
+        reply(create_user_handler(state, &claims, request).await)
+    }
+
+    async fn update_user_roles(
+        State(state): Shared,
+        Extension(claims): Extension<Claims>,
+        Path(username): Path<String>,
+        Json(request): Json<UpdateRolesRequest>,
+    ) -> Response {
+        reply(update_user_roles_handler(state, &claims, username, request).await)
+    }
+
+    async fn delete_user(State(state): Shared, Extension(claims): Extension<Claims>, Path(username): Path<String>) -> Response {
+        reply(delete_user_handler(state, &claims, username).await)
+    }
+
+    async fn list_api_keys(
+        State(state): Shared,
+        Extension(claims): Extension<Claims>,
+        Query(params): Query<TenantParams>,
+    ) -> Response {
+        reply(list_api_keys_handler(state, &claims, params.tenant).await)
+    }
+
+    async fn create_api_key(
+        State(state): Shared,
+        Extension(claims): Extension<Claims>,
+        Json(request): Json<CreateApiKeyRequest>,
+    ) -> Response {
+        reply(create_api_key_handler(state, &claims, request).await)
+    }
+
+    async fn revoke_api_key(State(state): Shared, Extension(claims): Extension<Claims>, Path(key_id): Path<String>) -> Response {
+        reply(revoke_api_key_handler(state, &claims, key_id).await)
+    }
+
+    // ----- 定时任务、死信与审批 -----
+
+    async fn list_schedules(State(state): Shared, Extension(claims): Extension<Claims>) -> Response {
+        reply(list_schedules_handler(state, &claims).await)
+    }
+
+    async fn create_schedule(
+        State(state): Shared,
+        Extension(claims): Extension<Claims>,
+        Json(request): Json<CreateScheduleRequest>,
+    ) -> Response {
+        reply(create_schedule_handler(state, &claims, request).await)
+    }
+
+    async fn delete_schedule(State(state): Shared, Extension(claims): Extension<Claims>, Path(id): Path<String>) -> Response {
+        reply(delete_schedule_handler(state, &claims, id).await)
+    }
+
+    async fn list_dead_letters(State(state): Shared, Extension(claims): Extension<Claims>) -> Response {
+        reply(list_dead_letters_handler(state, &claims).await)
+    }
+
+    async fn delete_dead_letter(State(state): Shared, Extension(claims): Extension<Claims>, Path(id): Path<String>) -> Response {
+        reply(delete_dead_letter_handler(state, &claims, id).await)
+    }
+
+    async fn redrive_dead_letter(State(state): Shared, Extension(claims): Extension<Claims>, Path(id): Path<String>) -> Response {
+        reply(redrive_dead_letter_handler(state, &claims, id).await)
+    }
+
+    async fn list_approvals(
+        State(state): Shared,
+        Extension(claims): Extension<Claims>,
+        Query(params): Query<StatusParams>,
+    ) -> Response {
+        reply(list_approvals_handler(state, &claims, params.status).await)
+    }
+
+    async fn approve_execution(
+        State(state): Shared,
+        Extension(claims): Extension<Claims>,
+        Path(id): Path<String>,
+        request: Option<Json<ApprovalDecisionRequest>>,
+    ) -> Response {
+        let request = request.map(|Json(request)| request).unwrap_or_default();
+        reply(approve_execution_handler(state, &claims, id, request).await)
+    }
+
+    async fn reject_execution(
+        State(state): Shared,
+        Extension(claims): Extension<Claims>,
+        Path(id): Path<String>,
+        request: Option<Json<ApprovalDecisionRequest>>,
+    ) -> Response {
+        let request = request.map(|Json(request)| request).unwrap_or_default();
+        reply(reject_execution_handler(state, &claims, id, request).await)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -316,4 +2123,218 @@ mod tests {
         assert!(!error_response.success);
         assert_eq!(error_response.error, Some("error".to_string()));
     }
+
+    #[test]
+    fn test_executions_page_escapes_user_content() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = ExecutionStore::open(dir.path()).unwrap();
+        let mut log = crate::core::types::ACSAExecutionLog::new("<script>alert(1)</script>".to_string());
+        log.success = true;
+        store.record(&log, None, &["xss".to_string()]).unwrap();
+
+        let query = ExecutionQuery { text: Some("alert".to_string()), ..Default::default() };
+        let html = render_executions_html(&query, &store.search(&query));
+        assert!(html.contains("1 matching runs"));
+        assert!(html.contains("&lt;script&gt;alert(1)&lt;/script&gt;"));
+        assert!(!html.contains("<script>"));
+    }
+
+    #[test]
+    fn test_route_permissions() {
+        let claims = |role: &str| Claims {
+            sub: "u".to_string(),
+            exp: u64::MAX,
+            iat: 0,
+            user_id: "u".to_string(),
+            username: "u".to_string(),
+            roles: vec![role.to_string()],
+            workspace_id: None,
+            api_key_id: None,
+        };
+        let (admin, operator, viewer) = (claims("admin"), claims("operator"), claims("viewer"));
+
+        assert!(authorize_request(None, "GET", "/health").is_ok());
+        assert!(authorize_request(None, "GET", "/api/v1/executions").is_err());
+        assert!(authorize_request(Some(&viewer), "GET", "/api/v1/executions/exec_1?x=1").is_ok());
+        assert!(authorize_request(Some(&viewer), "POST", "/api/v1/chat").is_err());
+        assert!(authorize_request(Some(&operator), "POST", "/v1/chat/completions").is_ok());
+        assert!(authorize_request(Some(&operator), "DELETE", "/api/v1/agents/reviewer").is_ok());
+        assert!(authorize_request(Some(&operator), "PUT", "/api/v1/admin/protocol").is_err());
+        assert!(authorize_request(Some(&operator), "PUT", "/api/v1/admin/budgets").is_err());
+        assert!(authorize_request(Some(&admin), "PUT", "/api/v1/admin/budgets").is_ok());
+        assert!(authorize_request(Some(&admin), "DELETE", "/api/v1/admin/users/bob").is_ok());
+        // 未登记的路由默认拒绝
+        assert!(authorize_request(Some(&admin), "GET", "/api/v1/unknown").is_err());
+
+        // 中间件返回的状态码：未认证 401，权限不足 403
+        let status = |result: Result<()>| error_response::<()>(result.unwrap_err()).0;
+        assert_eq!(status(authorize_request(None, "GET", "/api/v1/executions")), 401);
+        assert_eq!(status(authorize_request(Some(&viewer), "POST", "/api/v1/chat")), 403);
+    }
+
+    #[cfg(feature = "server")]
+    async fn test_server(dir: &std::path::Path) -> (HttpServer, Arc<ServerState>) {
+        use crate::core::config_manager::ConfigManagerConfig;
+        use crate::core::providers::MockProvider;
+        use crate::core::types::{ACSAConfig, AgentRole};
+
+        let router = ACSARouter::new(
+            Arc::new(MockProvider::new(AgentRole::MOSS)),
+            Arc::new(MockProvider::new(AgentRole::L6)),
+            Arc::new(MockProvider::new(AgentRole::Ultron)),
+            Arc::new(MockProvider::new(AgentRole::Omega)),
+            ACSAConfig::default(),
+        );
+        let state = Arc::new(
+            ServerState::open(
+                dir,
+                Arc::new(AuthManager::new(Default::default())),
+                Arc::new(ConfigManager::new(ConfigManagerConfig::default())),
+                ProtocolManager::new(),
+                router,
+            )
+            .await
+            .unwrap(),
+        );
+        (HttpServer::new(HttpServerConfig::default(), state.clone()), state)
+    }
+
+    #[cfg(feature = "server")]
+    async fn send(
+        app: &axum::Router,
+        request: axum::http::Request<axum::body::Body>,
+    ) -> (axum::http::StatusCode, serde_json::Value) {
+        use tower::ServiceExt;
+
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or(serde_json::Value::Null))
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_router_serves_public_and_openai_routes() {
+        use axum::body::Body;
+        use axum::http::{header, Request, StatusCode};
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let (server, state) = test_server(dir.path()).await;
+        state.auth.create_user("ops", "s3cret-pass", vec![Role::Operator], None).await.unwrap();
+        let app = server.build_router();
+
+        let (status, health) = send(&app, Request::get("/health").body(Body::empty()).unwrap()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(health["components"]["database"].is_object());
+
+        let dashboard = tower::ServiceExt::oneshot(app.clone(), Request::get("/dashboard").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(dashboard.status(), StatusCode::OK);
+        assert!(dashboard.headers()[header::CONTENT_TYPE].to_str().unwrap().starts_with("text/html"));
+
+        let login = Request::post("/api/v1/auth/login")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"username":"ops","password":"s3cret-pass"}"#))
+            .unwrap();
+        let (status, login) = send(&app, login).await;
+        assert_eq!(status, StatusCode::OK);
+        let token = login["data"]["access_token"].as_str().unwrap().to_string();
+
+        let completion = Request::post("/v1/chat/completions")
+            .header(header::AUTHORIZATION, format!("Bearer {}", token))
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"model":"acsa","messages":[{"role":"user","content":"写一个排序函数"}]}"#))
+            .unwrap();
+        let (status, completion) = send(&app, completion).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(completion["object"], "chat.completion");
+        assert!(completion["choices"][0]["message"]["content"].is_string());
+
+        // 执行已写入执行记录，可按 openai 标签检索
+        let executions = Request::get("/api/v1/executions?tags=openai")
+            .header(header::AUTHORIZATION, format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap();
+        let (status, page) = send(&app, executions).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(page["data"]["total"], 1);
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_auth_middleware_guards_protected_and_admin_routes() {
+        use axum::body::Body;
+        use axum::http::{header, Request, StatusCode};
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let (server, state) = test_server(dir.path()).await;
+        let app = server.build_router();
+        let token = |role: Role| {
+            let state = state.clone();
+            async move {
+                let name = role.as_str();
+                state.auth.create_user(name, "s3cret-pass", vec![role], None).await.unwrap();
+                state.auth.login(name, "s3cret-pass").await.unwrap().access_token
+            }
+        };
+        let (viewer, admin) = (token(Role::Viewer).await, token(Role::Admin).await);
+        let request = |method: &str, path: &str, token: Option<&str>| {
+            let mut request = Request::builder().method(method).uri(path);
+            if let Some(token) = token {
+                request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+            }
+            request
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(r#"{"protocol":"aegis"}"#))
+                .unwrap()
+        };
+
+        // 未认证：401，结构化错误代码为 ApiKeyInvalid
+        let (status, body) = send(&app, request("GET", "/api/v1/executions", None)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["error_report"]["code"], serde_json::json!(ErrorCode::ApiKeyInvalid));
+        let (status, _) = send(&app, request("GET", "/api/v1/admin/users", Some("not-a-token"))).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        // 已认证但权限不足：403，请求不会到达处理函数
+        for (method, path) in [
+            ("PUT", "/api/v1/admin/protocol"),
+            ("GET", "/api/v1/admin/users"),
+            ("DELETE", "/api/v1/admin/users/ops"),
+            ("GET", "/api/v1/admin/api-keys"),
+            ("PUT", "/api/v1/admin/budgets"),
+            ("POST", "/api/v1/chat"),
+        ] {
+            let (status, body) = send(&app, request(method, path, Some(&viewer))).await;
+            assert_eq!(status, StatusCode::FORBIDDEN, "{} {}", method, path);
+            assert_eq!(body["error_report"]["code"], serde_json::json!(ErrorCode::PermissionDenied));
+        }
+        assert_eq!(state.protocols.read().unwrap().current_protocol(), ProtocolManager::new().current_protocol());
+
+        // 有权限时放行
+        let (status, _) = send(&app, request("GET", "/api/v1/executions", Some(&viewer))).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, users) = send(&app, request("GET", "/api/v1/admin/users", Some(&admin))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(users["data"].as_array().unwrap().len(), 2);
+        let (status, protocol) = send(&app, request("PUT", "/api/v1/admin/protocol", Some(&admin))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(protocol["data"]["name"], Protocol::Aegis.name());
+    }
+
+    #[test]
+    fn test_sse_frame() {
+        let chunk = AgentChunk {
+            role: crate::core::types::AgentRole::MOSS,
+            iteration: 1,
+            delta: "line1\nline2".to_string(),
+            done: false,
+        };
+        let frame = sse_frame(&chunk).unwrap();
+        assert!(frame.starts_with("event: MOSS\ndata: {"));
+        // 换行已被 JSON 转义，一帧只有一行 data
+        assert_eq!(frame.matches('\n').count(), 3);
+        assert!(sse_frame(&AgentChunk { done: true, ..chunk }).unwrap().starts_with("event: done\n"));
+    }
 }
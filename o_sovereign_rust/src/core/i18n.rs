@@ -2,8 +2,8 @@
 // 多语言支持系统 (中英日韩 + 模块化语言包)
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use tracing::info;
+use std::collections::{HashMap, HashSet};
+use tracing::{debug, info};
 
 /// 支持的语言
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -23,6 +23,14 @@ pub enum Language {
 }
 
 impl Language {
+    /// 所有内置语言
+    pub const ALL: [Language; 4] = [
+        Language::ChineseSimplified,
+        Language::EnglishUS,
+        Language::Japanese,
+        Language::Korean,
+    ];
+
     pub fn code(&self) -> &'static str {
         match self {
             Language::ChineseSimplified => "zh-CN",
@@ -181,6 +189,8 @@ pub struct I18n {
     current_language: Language,
     /// 翻译映射表 (语言 -> 键 -> 文本)
     translations: HashMap<Language, HashMap<String, String>>,
+    /// 已加载的内置语言包（其余语言在首次切换时懒加载）
+    builtin_loaded: HashSet<Language>,
 }
 
 impl Default for I18n {
@@ -194,21 +204,38 @@ impl I18n {
         let mut i18n = Self {
             current_language: default_language,
             translations: HashMap::new(),
+            builtin_loaded: HashSet::new(),
         };
 
-        // 初始化内置语言包
-        i18n.load_builtin_languages();
+        // 启动时只加载默认语言和回退语言（英文），其余语言包按需加载
+        i18n.load_builtin_language(default_language);
+        i18n.load_builtin_language(Language::EnglishUS);
 
         info!("🌐 I18n initialized with language: {}", default_language.name());
         i18n
     }
 
-    /// 加载所有内置语言包
-    fn load_builtin_languages(&mut self) {
-        self.load_chinese_simplified();
-        self.load_english_us();
-        self.load_japanese();
-        self.load_korean();
+    /// 加载内置语言包（已加载则跳过）
+    fn load_builtin_language(&mut self, language: Language) {
+        if !self.builtin_loaded.insert(language) {
+            return;
+        }
+
+        match language {
+            Language::ChineseSimplified => self.load_chinese_simplified(),
+            Language::EnglishUS => self.load_english_us(),
+            Language::Japanese => self.load_japanese(),
+            Language::Korean => self.load_korean(),
+        }
+        debug!("📦 Built-in language pack loaded: {}", language.name());
+    }
+
+    /// 合并内置语言包，不覆盖已添加的自定义翻译
+    fn merge_builtin(&mut self, language: Language, pack: HashMap<String, String>) {
+        let translations = self.translations.entry(language).or_default();
+        for (key, text) in pack {
+            translations.entry(key).or_insert(text);
+        }
     }
 
     /// 加载简体中文语言包
@@ -257,6 +284,29 @@ impl I18n {
         zh.insert("error.api_key_missing".to_string(), "API密钥未配置".to_string());
         zh.insert("error.timeout".to_string(), "请求超时".to_string());
         zh.insert("error.unknown".to_string(), "未知错误".to_string());
+        zh.insert("error.severity.fatal".to_string(), "致命".to_string());
+        zh.insert("error.severity.critical".to_string(), "严重".to_string());
+        zh.insert("error.severity.error".to_string(), "错误".to_string());
+        zh.insert("error.severity.warning".to_string(), "警告".to_string());
+        zh.insert("error.generic_failure".to_string(), "系统内部出现问题，请稍后重试".to_string());
+        zh.insert("error.reference".to_string(), "错误编号".to_string());
+        zh.insert("error.retry_later".to_string(), "该错误通常是暂时的，可以稍后重试".to_string());
+        zh.insert("error.hint.api_key_missing".to_string(), "请设置 OPENAI_API_KEY（或对应的 *_API_KEY 环境变量），或使用 --mock 运行".to_string());
+        zh.insert("error.hint.api_key_invalid".to_string(), "请检查环境变量或配置中的API密钥，密钥可能已失效".to_string());
+        zh.insert("error.hint.rate_limited".to_string(), "请求过于频繁，请稍等片刻后重试或降低请求频率".to_string());
+        zh.insert("error.hint.timeout".to_string(), "请重试；如持续超时，请检查网络或调大超时时间".to_string());
+        zh.insert("error.hint.server_error".to_string(), "模型服务暂时不可用，请稍后重试或切换Provider".to_string());
+        zh.insert("error.hint.network".to_string(), "请检查网络连接或代理设置".to_string());
+        zh.insert("error.hint.bad_request".to_string(), "请缩短或改写输入后重试".to_string());
+        zh.insert("error.hint.safety_block".to_string(), "请去掉危险操作后重新描述需求".to_string());
+        zh.insert("error.hint.config".to_string(), "运行 `o-sovereign config validate` 检查配置".to_string());
+        zh.insert("error.hint.opencode_missing".to_string(), "请先安装 OpenCode（参见 README）".to_string());
+        zh.insert("error.hint.offline".to_string(), "请为该功能配置本机后端（如 ACSA_LOCAL_LLM_URL），或去掉 --offline 运行".to_string());
+        zh.insert("error.hint.shutting_down".to_string(), "服务正在重启或下线，请稍后重试".to_string());
+        zh.insert("error.hint.maintenance".to_string(), "运维已暂停新的执行（维护模式），只读功能仍可使用，请稍后重试".to_string());
+        zh.insert("error.hint.workspace_access".to_string(), "请确认登录身份属于该工作区，或联系管理员将你加入".to_string());
+        zh.insert("error.hint.budget".to_string(), "本工作区的预算已用完，请联系管理员提高预算".to_string());
+        zh.insert("error.hint.permission".to_string(), "当前角色无权执行该操作，请联系管理员调整角色".to_string());
 
         // 统计信息
         zh.insert("stats.tokens_used".to_string(), "使用Token数".to_string());
@@ -273,7 +323,7 @@ impl I18n {
         zh.insert("api.provider.gemini".to_string(), "Gemini Pro".to_string());
         zh.insert("api.provider.deepseek".to_string(), "DeepSeek Coder".to_string());
 
-        self.translations.insert(Language::ChineseSimplified, zh);
+        self.merge_builtin(Language::ChineseSimplified, zh);
     }
 
     /// 加载英文语言包
@@ -322,6 +372,29 @@ impl I18n {
         en.insert("error.api_key_missing".to_string(), "API key not configured".to_string());
         en.insert("error.timeout".to_string(), "Request timeout".to_string());
         en.insert("error.unknown".to_string(), "Unknown error".to_string());
+        en.insert("error.severity.fatal".to_string(), "Fatal".to_string());
+        en.insert("error.severity.critical".to_string(), "Critical".to_string());
+        en.insert("error.severity.error".to_string(), "Error".to_string());
+        en.insert("error.severity.warning".to_string(), "Warning".to_string());
+        en.insert("error.generic_failure".to_string(), "Something went wrong on our side. Please try again later.".to_string());
+        en.insert("error.reference".to_string(), "Reference".to_string());
+        en.insert("error.retry_later".to_string(), "This is usually temporary; retrying later may succeed.".to_string());
+        en.insert("error.hint.api_key_missing".to_string(), "Set OPENAI_API_KEY (or the matching *_API_KEY variable), or pass --mock".to_string());
+        en.insert("error.hint.api_key_invalid".to_string(), "Check the API key in your environment or config; it may have been revoked".to_string());
+        en.insert("error.hint.rate_limited".to_string(), "Too many requests. Wait a moment and retry, or lower the request rate".to_string());
+        en.insert("error.hint.timeout".to_string(), "Retry; if it keeps timing out, check your network or raise the timeout".to_string());
+        en.insert("error.hint.server_error".to_string(), "The model provider is unavailable. Retry later or switch provider".to_string());
+        en.insert("error.hint.network".to_string(), "Check your internet connection or proxy settings".to_string());
+        en.insert("error.hint.bad_request".to_string(), "Shorten or rephrase your input and try again".to_string());
+        en.insert("error.hint.safety_block".to_string(), "Rephrase the request without the dangerous operation".to_string());
+        en.insert("error.hint.config".to_string(), "Run `o-sovereign config validate` to check your configuration".to_string());
+        en.insert("error.hint.opencode_missing".to_string(), "Install OpenCode first (see README)".to_string());
+        en.insert("error.hint.offline".to_string(), "Configure a local backend for this capability (e.g. ACSA_LOCAL_LLM_URL), or run without --offline".to_string());
+        en.insert("error.hint.shutting_down".to_string(), "The service is restarting or going offline; retry shortly".to_string());
+        en.insert("error.hint.maintenance".to_string(), "An operator has paused new executions (maintenance mode); read-only features still work, retry later".to_string());
+        en.insert("error.hint.workspace_access".to_string(), "Make sure you are signed in as a member of this workspace, or ask an admin to add you".to_string());
+        en.insert("error.hint.budget".to_string(), "This workspace has used up its budget; ask an admin to raise it".to_string());
+        en.insert("error.hint.permission".to_string(), "Your role does not allow this action; ask an admin to change your role".to_string());
 
         // Statistics
         en.insert("stats.tokens_used".to_string(), "Tokens Used".to_string());
@@ -338,7 +411,7 @@ impl I18n {
         en.insert("api.provider.gemini".to_string(), "Gemini Pro".to_string());
         en.insert("api.provider.deepseek".to_string(), "DeepSeek Coder".to_string());
 
-        self.translations.insert(Language::EnglishUS, en);
+        self.merge_builtin(Language::EnglishUS, en);
     }
 
     /// 加载日文语言包
@@ -387,6 +460,18 @@ impl I18n {
         ja.insert("error.api_key_missing".to_string(), "APIキーが設定されていません".to_string());
         ja.insert("error.timeout".to_string(), "リクエストタイムアウト".to_string());
         ja.insert("error.unknown".to_string(), "不明なエラー".to_string());
+        ja.insert("error.severity.fatal".to_string(), "致命的".to_string());
+        ja.insert("error.severity.critical".to_string(), "重大".to_string());
+        ja.insert("error.severity.error".to_string(), "エラー".to_string());
+        ja.insert("error.severity.warning".to_string(), "警告".to_string());
+        ja.insert("error.generic_failure".to_string(), "内部で問題が発生しました。しばらくしてから再試行してください".to_string());
+        ja.insert("error.reference".to_string(), "エラー番号".to_string());
+        ja.insert("error.retry_later".to_string(), "一時的なエラーです。しばらくしてから再試行してください".to_string());
+        ja.insert("error.hint.api_key_missing".to_string(), "OPENAI_API_KEY（または対応する *_API_KEY）を設定するか、--mock を指定してください".to_string());
+        ja.insert("error.hint.rate_limited".to_string(), "リクエストが多すぎます。少し待ってから再試行してください".to_string());
+        ja.insert("error.hint.timeout".to_string(), "再試行してください。続く場合はネットワークを確認してください".to_string());
+        ja.insert("error.hint.network".to_string(), "ネットワーク接続またはプロキシ設定を確認してください".to_string());
+        ja.insert("error.hint.safety_block".to_string(), "危険な操作を含めずにリクエストを言い換えてください".to_string());
 
         // 統計情報
         ja.insert("stats.tokens_used".to_string(), "使用トークン数".to_string());
@@ -403,7 +488,7 @@ impl I18n {
         ja.insert("api.provider.gemini".to_string(), "Gemini Pro".to_string());
         ja.insert("api.provider.deepseek".to_string(), "DeepSeek Coder".to_string());
 
-        self.translations.insert(Language::Japanese, ja);
+        self.merge_builtin(Language::Japanese, ja);
     }
 
     /// 加载韩文语言包
@@ -452,6 +537,18 @@ impl I18n {
         ko.insert("error.api_key_missing".to_string(), "API 키가 설정되지 않음".to_string());
         ko.insert("error.timeout".to_string(), "요청 시간 초과".to_string());
         ko.insert("error.unknown".to_string(), "알 수 없는 오류".to_string());
+        ko.insert("error.severity.fatal".to_string(), "치명적".to_string());
+        ko.insert("error.severity.critical".to_string(), "심각".to_string());
+        ko.insert("error.severity.error".to_string(), "오류".to_string());
+        ko.insert("error.severity.warning".to_string(), "경고".to_string());
+        ko.insert("error.generic_failure".to_string(), "내부 문제가 발생했습니다. 잠시 후 다시 시도하세요".to_string());
+        ko.insert("error.reference".to_string(), "오류 번호".to_string());
+        ko.insert("error.retry_later".to_string(), "일시적인 오류입니다. 잠시 후 다시 시도하세요".to_string());
+        ko.insert("error.hint.api_key_missing".to_string(), "OPENAI_API_KEY(또는 해당 *_API_KEY)를 설정하거나 --mock 을 사용하세요".to_string());
+        ko.insert("error.hint.rate_limited".to_string(), "요청이 너무 많습니다. 잠시 후 다시 시도하세요".to_string());
+        ko.insert("error.hint.timeout".to_string(), "다시 시도하세요. 계속되면 네트워크를 확인하세요".to_string());
+        ko.insert("error.hint.network".to_string(), "인터넷 연결 또는 프록시 설정을 확인하세요".to_string());
+        ko.insert("error.hint.safety_block".to_string(), "위험한 작업 없이 요청을 다시 작성하세요".to_string());
 
         // 통계 정보
         ko.insert("stats.tokens_used".to_string(), "사용된 토큰".to_string());
@@ -468,7 +565,7 @@ impl I18n {
         ko.insert("api.provider.gemini".to_string(), "Gemini Pro".to_string());
         ko.insert("api.provider.deepseek".to_string(), "DeepSeek Coder".to_string());
 
-        self.translations.insert(Language::Korean, ko);
+        self.merge_builtin(Language::Korean, ko);
     }
 
     /// 获取翻译文本
@@ -497,6 +594,7 @@ impl I18n {
 
     /// 切换语言
     pub fn set_language(&mut self, language: Language) {
+        self.load_builtin_language(language);
         self.current_language = language;
         info!("🌐 Language switched to: {}", language.name());
     }
@@ -528,9 +626,9 @@ impl I18n {
         self.translations.contains_key(&language)
     }
 
-    /// 获取所有已加载的语言
+    /// 获取所有可用的语言（内置语言包按需加载，均视为可用）
     pub fn available_languages(&self) -> Vec<Language> {
-        self.translations.keys().copied().collect()
+        Language::ALL.to_vec()
     }
 }
 
@@ -610,6 +708,17 @@ mod tests {
         assert_eq!(i18n.t(&TranslationKey::Custom("custom.hello".to_string())), "你好世界");
     }
 
+    #[test]
+    fn test_language_packs_load_lazily() {
+        let mut i18n = I18n::new(Language::ChineseSimplified);
+        assert!(i18n.is_language_loaded(Language::EnglishUS));
+        assert!(!i18n.is_language_loaded(Language::Korean));
+
+        i18n.set_language(Language::Korean);
+        assert!(i18n.is_language_loaded(Language::Korean));
+        assert_eq!(i18n.t(&TranslationKey::Execute), "실행");
+    }
+
     #[test]
     fn test_available_languages() {
         let i18n = I18n::new(Language::ChineseSimplified);
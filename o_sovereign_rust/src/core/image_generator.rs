@@ -37,6 +37,9 @@ impl ImageGenerator {
     pub fn new(config: GenerationConfig) -> Self {
         info!("🎨 Image Generator initialized");
         info!("   - Model: {}", config.model);
+        if super::offline::is_offline() {
+            info!("📴 Offline mode: Stable Diffusion runs locally, no change needed");
+        }
         Self { config }
     }
 
@@ -7,17 +7,26 @@
 // 2. Agent管理: 监控/调度/协调 MOSS/L6/Ultron/Omega
 // 3. 熔断保护: API故障自动切换本地模式 (BUNKER协议)
 // 4. 优先级排序: Prioritization（Jarvis专属职责）
-// 5. 安全验证: 硬编码安全规则（继承之前的功能）
+// 5. 安全验证: 硬编码安全规则（继承之前的功能），经 jarvis_patterns 规范化 / 模糊 / 正则匹配
+// 6. 规则包: 加载 Ed25519 签名的外部规则包，只能追加规则，不能移除或削弱硬编码规则
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use chrono::{DateTime, Utc};
 use tracing::{debug, error, info, warn};
 
+use super::agent_extension::{AgentExtensionManager, CustomAgent};
+use super::emergency_log::{EmergencyLogger, LogEntryType};
+use super::jarvis_patterns::{NormalizedText, PatternRule, PatternSet};
 use super::protocol::Protocol;
-use super::sosa_api_pool::SparseMarkov;
+use super::providers::{create_local_provider, probe_local_provider, ModelProvider};
+use super::sosa_api_pool::{LocalModelConfig, SparseMarkov};
+use super::sosa_crypto::{SosaCryptoConfig, SosaCryptoEngine};
+use super::types::AgentRole;
 
 /// Jarvis验证结果
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,7 +46,7 @@ pub struct JarvisVerdict {
 }
 
 /// 危险操作类型
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum DangerousOp {
     /// 物理破坏（删除、格式化、破坏硬件）
     PhysicalDestruction,
@@ -60,26 +69,35 @@ pub enum DangerousOp {
 /// Jarvis安全熔断器
 ///
 /// **不可被绕过的特性**:
-/// 1. 硬编码规则，不接受外部配置
+/// 1. 硬编码规则，外部规则包只能追加（需受信任密钥签名）
 /// 2. 独立于其他Agent运行
 /// 3. 拥有最终否决权
 /// 4. 不可被静音或关闭
 pub struct JarvisCircuitBreaker {
-    /// 硬编码的黑名单关键词
-    hard_blacklist: HashSet<String>,
-    /// 危险操作检测器
-    danger_detectors: Vec<DangerPattern>,
+    /// 硬编码的黑名单（关键词 + 正则），规则包的黑名单追加在后
+    hard_blacklist: PatternSet,
+    /// 危险操作检测器（只追加，不移除）
+    danger_detectors: Vec<DangerDetector>,
     /// 是否启用严格模式（默认true，不可更改）
     strict_mode: bool,
+    /// 受信任的规则包签名公钥（Ed25519，base64）
+    trusted_pack_keys: Vec<String>,
+    /// 已加载的规则包
+    rule_packs: Vec<RulePackInfo>,
 }
 
 /// 危险模式检测
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
 struct DangerPattern {
     /// 模式描述
     description: String,
     /// 检测关键词
+    #[serde(default)]
     keywords: Vec<String>,
+    /// 检测正则（在小写、同形字折叠后的文本上匹配）
+    #[serde(default)]
+    regexes: Vec<String>,
     /// 危险操作类型
     op_type: DangerousOp,
     /// 是否为硬性阻止
@@ -88,6 +106,97 @@ struct DangerPattern {
     risk_level: u8,
 }
 
+impl DangerPattern {
+    /// 编译关键词和正则
+    fn compile(self) -> Result<DangerDetector> {
+        let mut rules = PatternSet::new().with_keywords(self.keywords.iter().cloned());
+        for regex in &self.regexes {
+            rules = rules.with_regex(regex)?;
+        }
+        Ok(DangerDetector { pattern: self, rules })
+    }
+}
+
+/// 编译后的危险模式
+#[derive(Debug, Clone)]
+struct DangerDetector {
+    pattern: DangerPattern,
+    rules: PatternSet,
+}
+
+/// 规则包文件格式（未知字段直接拒绝，规则包无法表达"移除"或"关闭"）
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RulePackFile {
+    name: String,
+    #[serde(default)]
+    version: String,
+    /// 追加到硬编码黑名单的关键词
+    #[serde(default)]
+    blacklist: Vec<String>,
+    /// 追加的危险操作检测器
+    #[serde(default)]
+    patterns: Vec<DangerPattern>,
+}
+
+/// 已加载规则包的信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RulePackInfo {
+    pub name: String,
+    pub version: String,
+    pub path: PathBuf,
+    pub blacklist_entries: usize,
+    pub patterns: usize,
+    pub loaded_at: DateTime<Utc>,
+}
+
+/// 启动时加载的规则包配置
+///
+/// 环境变量：`ACSA_JARVIS_RULE_PACKS`（规则包路径，逗号分隔）、`ACSA_JARVIS_PACK_KEYS`（受信任公钥，逗号分隔）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RulePackConfig {
+    #[serde(default)]
+    pub packs: Vec<PathBuf>,
+    #[serde(default)]
+    pub trusted_keys: Vec<String>,
+}
+
+impl RulePackConfig {
+    pub fn from_env() -> Self {
+        let list = |name: &str| -> Vec<String> {
+            std::env::var(name)
+                .map(|raw| raw.split(',').map(str::trim).filter(|v| !v.is_empty()).map(str::to_string).collect())
+                .unwrap_or_default()
+        };
+        Self {
+            packs: list("ACSA_JARVIS_RULE_PACKS").into_iter().map(PathBuf::from).collect(),
+            trusted_keys: list("ACSA_JARVIS_PACK_KEYS"),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.packs.is_empty() && self.trusted_keys.is_empty()
+    }
+}
+
+/// 规则包签名文件路径（`<pack>.sig`，内容为 base64 分离签名）
+pub fn rule_pack_signature_path(path: &Path) -> PathBuf {
+    let mut sig = path.as_os_str().to_owned();
+    sig.push(".sig");
+    PathBuf::from(sig)
+}
+
+/// 硬编码黑名单的正则部分（关键词无法覆盖的变体）
+const HARD_BLACKLIST_REGEXES: &[&str] = &[
+    // rm -rf / 及 rm -fr / 、--no-preserve-root 等变体
+    r"\brm\s+-[a-z]*(?:rf|fr)[a-z]*\s+(?:--no-preserve-root\s+)?/(?:\s|$|\*)",
+    // 格式化任意盘符 / 块设备
+    r"\bformat\s+[c-z]:(?:\s|$|\\)",
+    r"\bmkfs(?:\.[a-z0-9]+)?\s+/dev/",
+    // fork bomb
+    r":\(\)\s*\{\s*:\s*\|\s*:\s*&\s*\}\s*;\s*:",
+];
+
 impl JarvisCircuitBreaker {
     /// 创建Jarvis实例
     ///
@@ -97,11 +206,137 @@ impl JarvisCircuitBreaker {
         info!("    Status: ACTIVE (Cannot be disabled)");
         info!("    Authority: SUPREME (Cannot be overridden)");
 
+        // 关键词按字典序排列，保证命中顺序稳定
+        let mut keywords: Vec<String> = Self::build_hard_blacklist().into_iter().collect();
+        keywords.sort();
+        let mut hard_blacklist = PatternSet::new().with_keywords(keywords);
+        for regex in HARD_BLACKLIST_REGEXES {
+            hard_blacklist = hard_blacklist.with_regex(regex).expect("hardcoded Jarvis regex must compile");
+        }
+
+        let danger_detectors = Self::build_danger_detectors()
+            .into_iter()
+            .map(|pattern| pattern.compile().expect("hardcoded Jarvis detector must compile"))
+            .collect();
+
         Self {
-            hard_blacklist: Self::build_hard_blacklist(),
-            danger_detectors: Self::build_danger_detectors(),
+            hard_blacklist,
+            danger_detectors,
             strict_mode: true, // 永远为true
+            trusted_pack_keys: Vec::new(),
+            rule_packs: Vec::new(),
+        }
+    }
+
+    /// 按配置创建：信任配置的公钥并依次加载规则包
+    ///
+    /// 任一规则包缺少签名、签名不受信任或内容无效时返回错误（fail closed），调用方应拒绝启动
+    pub fn from_config(config: &RulePackConfig) -> Result<Self> {
+        let mut jarvis = config
+            .trusted_keys
+            .iter()
+            .fold(Self::new(), |jarvis, key| jarvis.with_trusted_pack_key(key.clone()));
+        for pack in &config.packs {
+            jarvis
+                .load_rule_pack(pack)
+                .with_context(|| format!("Refusing to start with rule pack {}", pack.display()))?;
         }
+        Ok(jarvis)
+    }
+
+    /// 信任一个规则包签名公钥（Ed25519，base64）
+    pub fn with_trusted_pack_key(mut self, public_key: impl Into<String>) -> Self {
+        self.trusted_pack_keys.push(public_key.into());
+        self
+    }
+
+    /// 已加载的规则包
+    pub fn rule_packs(&self) -> &[RulePackInfo] {
+        &self.rule_packs
+    }
+
+    /// 用 PKCS#8 私钥为规则包生成 `<pack>.sig`（发布规则包时使用）
+    pub fn sign_rule_pack(path: impl AsRef<Path>, pkcs8_b64: &str) -> Result<PathBuf> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path).with_context(|| format!("Failed to read rule pack {}", path.display()))?;
+        let signature = SosaCryptoEngine::new(SosaCryptoConfig::default()).sign_detached(pkcs8_b64, &bytes)?;
+        let sig_path = rule_pack_signature_path(path);
+        std::fs::write(&sig_path, signature)?;
+        Ok(sig_path)
+    }
+
+    /// 加载签名的外部规则包
+    ///
+    /// 规则包只能追加黑名单关键词和危险检测器；签名校验、解析或任一规则编译失败时整包拒绝加载，
+    /// 已有规则保持不变
+    pub fn load_rule_pack(&mut self, path: impl AsRef<Path>) -> Result<RulePackInfo> {
+        let path = path.as_ref();
+        if self.trusted_pack_keys.is_empty() {
+            bail!("No trusted rule pack key configured; refusing to load {}", path.display());
+        }
+
+        let bytes = std::fs::read(path).with_context(|| format!("Failed to read rule pack {}", path.display()))?;
+        let sig_path = rule_pack_signature_path(path);
+        let signature = std::fs::read_to_string(&sig_path)
+            .with_context(|| format!("Missing rule pack signature {}", sig_path.display()))?;
+        let crypto = SosaCryptoEngine::new(SosaCryptoConfig::default());
+        if !self
+            .trusted_pack_keys
+            .iter()
+            .any(|key| crypto.verify_detached(key, &bytes, &signature).is_ok())
+        {
+            error!("🚨 JARVIS: Rule pack signature rejected: {}", path.display());
+            bail!("Rule pack {} is not signed by a trusted key", path.display());
+        }
+
+        let pack: RulePackFile =
+            serde_json::from_slice(&bytes).with_context(|| format!("Invalid rule pack {}", path.display()))?;
+        if pack.name.trim().is_empty() {
+            bail!("Rule pack {} has no name", path.display());
+        }
+        if self.rule_packs.iter().any(|loaded| loaded.name == pack.name) {
+            bail!("Rule pack '{}' is already loaded", pack.name);
+        }
+        if pack.blacklist.is_empty() && pack.patterns.is_empty() {
+            bail!("Rule pack '{}' contains no rules", pack.name);
+        }
+
+        // 先全部校验编译，成功后再追加，避免半加载
+        let mut detectors = Vec::with_capacity(pack.patterns.len());
+        for pattern in pack.patterns {
+            if pattern.keywords.is_empty() && pattern.regexes.is_empty() {
+                bail!("Rule pack '{}': pattern '{}' has no keywords or regexes", pack.name, pattern.description);
+            }
+            if pattern.risk_level > 10 {
+                bail!("Rule pack '{}': pattern '{}' risk level must be 0-10", pack.name, pattern.description);
+            }
+            let description = pattern.description.clone();
+            detectors.push(
+                pattern
+                    .compile()
+                    .with_context(|| format!("Rule pack '{}': pattern '{}'", pack.name, description))?,
+            );
+        }
+
+        let info = RulePackInfo {
+            name: pack.name,
+            version: pack.version,
+            path: path.to_path_buf(),
+            blacklist_entries: pack.blacklist.len(),
+            patterns: detectors.len(),
+            loaded_at: Utc::now(),
+        };
+        for keyword in pack.blacklist {
+            self.hard_blacklist.push(PatternRule::keyword(keyword));
+        }
+        self.danger_detectors.extend(detectors);
+
+        info!(
+            "🛡️  Jarvis rule pack loaded: {} {} (+{} blacklist, +{} patterns)",
+            info.name, info.version, info.blacklist_entries, info.patterns
+        );
+        self.rule_packs.push(info.clone());
+        Ok(info)
     }
 
     /// 构建硬编码黑名单
@@ -162,6 +397,7 @@ impl JarvisCircuitBreaker {
                     "drop database".to_string(),
                     "truncate table".to_string(),
                 ],
+                regexes: vec![r"\bdrop\s+(?:database|schema)\s+(?:if\s+exists\s+)?\S+".to_string()],
                 op_type: DangerousOp::PhysicalDestruction,
                 is_hard_block: true,
                 risk_level: 10,
@@ -176,6 +412,7 @@ impl JarvisCircuitBreaker {
                     "steal user data".to_string(),
                     "extract passwords".to_string(),
                 ],
+                regexes: Vec::new(),
                 op_type: DangerousOp::PrivacyViolation,
                 is_hard_block: true,
                 risk_level: 9,
@@ -189,6 +426,7 @@ impl JarvisCircuitBreaker {
                     "ddos".to_string(),
                     "flood attack".to_string(),
                 ],
+                regexes: Vec::new(),
                 op_type: DangerousOp::CyberAttack,
                 is_hard_block: true,
                 risk_level: 10,
@@ -203,6 +441,7 @@ impl JarvisCircuitBreaker {
                     "trojan".to_string(),
                     "ransomware".to_string(),
                 ],
+                regexes: Vec::new(),
                 op_type: DangerousOp::MalwareGeneration,
                 is_hard_block: true,
                 risk_level: 10,
@@ -217,6 +456,7 @@ impl JarvisCircuitBreaker {
                     "phishing".to_string(),
                     "social engineering".to_string(),
                 ],
+                regexes: Vec::new(),
                 op_type: DangerousOp::SocialEngineering,
                 is_hard_block: false, // 可能有合法的安全培训场景
                 risk_level: 7,
@@ -231,6 +471,7 @@ impl JarvisCircuitBreaker {
                     "credit card theft".to_string(),
                     "fraud".to_string(),
                 ],
+                regexes: Vec::new(),
                 op_type: DangerousOp::FinancialCrime,
                 is_hard_block: true,
                 risk_level: 10,
@@ -250,7 +491,7 @@ impl JarvisCircuitBreaker {
         debug!("Jarvis: Performing safety verification...");
 
         let combined_text = format!("{}\n{}", plan, context);
-        let combined_lower = combined_text.to_lowercase();
+        let normalized = NormalizedText::new(&combined_text);
 
         let mut verdict = JarvisVerdict {
             allowed: true,
@@ -262,34 +503,27 @@ impl JarvisCircuitBreaker {
         };
 
         // Step 1: 检查硬编码黑名单
-        for word in &self.hard_blacklist {
-            if combined_lower.contains(&word.to_lowercase()) {
-                // 🚨 只在真正阻止时才输出错误日志
-                error!("🚨 JARVIS BLOCK: '{}'", word);
-
-                verdict.allowed = false;
-                verdict.risk_level = 10;
-                verdict.is_hard_block = true;
-                verdict.triggered_rules
-                    .push(format!("HARD_BLACKLIST: {}", word));
-                verdict.block_reason = Some(format!(
-                    "Blocked: '{}'",
-                    word
-                ));
-
-                return verdict; // 立即返回
-            }
+        if let Some(hit) = self.hard_blacklist.find_first(&normalized) {
+            // 🚨 只在真正阻止时才输出错误日志
+            error!("🚨 JARVIS BLOCK: '{}'", hit.describe());
+
+            verdict.allowed = false;
+            verdict.risk_level = 10;
+            verdict.is_hard_block = true;
+            verdict.triggered_rules
+                .push(format!("HARD_BLACKLIST: {}", hit.describe()));
+            verdict.block_reason = Some(format!(
+                "Blocked: '{}'",
+                hit.rule
+            ));
+
+            return verdict; // 立即返回
         }
 
         // Step 2: 危险操作检测
-        for detector in &self.danger_detectors {
-            let mut matched_keywords = Vec::new();
-
-            for keyword in &detector.keywords {
-                if combined_lower.contains(&keyword.to_lowercase()) {
-                    matched_keywords.push(keyword.clone());
-                }
-            }
+        for DangerDetector { pattern: detector, rules } in &self.danger_detectors {
+            let matched_keywords: Vec<String> =
+                rules.find_all(&normalized).iter().map(|hit| hit.describe()).collect();
 
             if !matched_keywords.is_empty() {
                 // 🔇 只在阻止时才warn，否则静默
@@ -479,6 +713,14 @@ pub struct AgentHealth {
     pub last_success: Option<DateTime<Utc>>,
     pub current_protocol: Protocol,
     pub intelligence_level: u8,      // 智商等级 (100-140)
+    #[serde(default = "default_cost_weight")]
+    pub cost_weight: f64,            // 成本权重（调度评分除以该值）
+    #[serde(default)]
+    pub custom: bool,                // 是否为运行时注册的自定义Agent
+}
+
+fn default_cost_weight() -> f64 {
+    1.0
 }
 
 /// BUNKER协议状态
@@ -582,7 +824,12 @@ pub struct JarvisManager {
     markov: SparseMarkov,
     learning_history: VecDeque<JarvisLearningEvent>,
     last_bunker_check: Instant,
-    local_cluster_available: bool,
+    /// 本地模型配置（未配置时 BUNKER 直接进入紧急模式）
+    local_model: Option<LocalModelConfig>,
+    /// 本地主权模式下各角色使用的本地 Provider
+    local_providers: HashMap<AgentRole, Arc<dyn ModelProvider>>,
+    /// BUNKER 模式切换写入紧急日志
+    emergency_log: Option<Arc<EmergencyLogger>>,
 }
 
 impl JarvisManager {
@@ -599,8 +846,64 @@ impl JarvisManager {
             markov: SparseMarkov::new(10000),
             learning_history: VecDeque::with_capacity(10000),
             last_bunker_check: Instant::now(),
-            local_cluster_available: true,
+            local_model: None,
+            local_providers: HashMap::new(),
+            emergency_log: None,
+        }
+    }
+
+    /// 接入紧急日志（记录 BUNKER 模式切换）
+    pub fn with_emergency_log(mut self, logger: Arc<EmergencyLogger>) -> Self {
+        self.emergency_log = Some(logger);
+        self
+    }
+
+    /// 配置 BUNKER 协议使用的本地模型
+    pub fn with_local_model(mut self, config: LocalModelConfig) -> Self {
+        self.local_model = Some(config);
+        self
+    }
+
+    /// 本地主权模式下返回该角色的本地 Provider，其余情况返回 None（继续使用云端）
+    pub fn provider_for(&self, role: AgentRole) -> Option<Arc<dyn ModelProvider>> {
+        if self.bunker_mode != BunkerMode::LocalSovereignty {
+            return None;
+        }
+        self.local_providers.get(&role).cloned()
+    }
+
+    /// 按当前模式选择 Provider：本地主权模式用本地模型，否则用云端 Provider
+    pub fn route_provider(&self, role: AgentRole, cloud: &Arc<dyn ModelProvider>) -> Arc<dyn ModelProvider> {
+        self.provider_for(role).unwrap_or_else(|| cloud.clone())
+    }
+
+    /// 唤醒本地集群：探测端点并为各角色创建本地 Provider，失败返回 false
+    async fn wake_local_cluster(&mut self) -> bool {
+        let Some(config) = self.local_model.clone() else {
+            warn!("⚠️ 未配置本地模型");
+            return false;
+        };
+        if let Err(e) = probe_local_provider(&config).await {
+            warn!("⚠️ 本地模型端点不可用 ({}): {}", config.endpoint, e);
+            return false;
         }
+
+        let roles = [AgentRole::MOSS, AgentRole::L6, AgentRole::Ultron, AgentRole::Omega];
+        let mut providers = HashMap::new();
+        for role in roles {
+            match create_local_provider(&config, role) {
+                Ok(provider) => {
+                    providers.insert(role, provider);
+                }
+                Err(e) => {
+                    warn!("⚠️ 无法创建本地 Provider ({:?}): {}", role, e);
+                    return false;
+                }
+            }
+        }
+        info!("💾 [LOADING] Waking up Local Cluster ({}: {} @ {})", config.backend, config.model, config.endpoint);
+        self.local_providers = providers;
+        true
     }
 
     /// 初始化Agent健康监控
@@ -618,11 +921,69 @@ impl JarvisManager {
                 last_success: Some(Utc::now()),
                 current_protocol: Protocol::Architect, // 默认使用Architect协议
                 intelligence_level: 140,
+                cost_weight: 1.0,
+                custom: false,
             });
         }
         agents
     }
 
+    /// 注册自定义Agent，使其与核心Agent一起参与调度（同名时更新配置，保留健康数据）
+    pub fn register_custom_agent(&mut self, agent: &CustomAgent) -> Result<()> {
+        agent.validate()?;
+        let status = if agent.enabled { AgentStatus::Online } else { AgentStatus::Offline };
+
+        match self.agent_health.get_mut(&agent.name) {
+            Some(health) => {
+                health.cost_weight = agent.cost_weight;
+                if health.status == AgentStatus::Online || health.status == AgentStatus::Offline {
+                    health.status = status;
+                }
+            }
+            None => {
+                let metrics = &agent.metrics;
+                self.agent_health.insert(agent.name.clone(), AgentHealth {
+                    agent_name: agent.name.clone(),
+                    status,
+                    api_success_rate: if metrics.total_calls > 0 { metrics.success_rate } else { 1.0 },
+                    avg_response_time_ms: if metrics.total_calls > 0 { metrics.avg_response_time_ms.max(1) } else { 500 },
+                    consecutive_failures: 0,
+                    last_success: None,
+                    current_protocol: Protocol::Architect,
+                    intelligence_level: 120, // 未经验证的自定义Agent低于核心Agent
+                    cost_weight: agent.cost_weight,
+                    custom: true,
+                });
+            }
+        }
+
+        info!("🤖 Jarvis调度注册自定义Agent: {} (成本权重 {:.2})", agent.name, agent.cost_weight);
+        Ok(())
+    }
+
+    /// 移除自定义Agent（核心Agent不可移除）
+    pub fn unregister_custom_agent(&mut self, name: &str) -> Result<()> {
+        match self.agent_health.get(name) {
+            Some(health) if health.custom => {
+                self.agent_health.remove(name);
+                info!("🤖 Jarvis调度移除自定义Agent: {}", name);
+                Ok(())
+            }
+            Some(_) => Err(anyhow!("Core agent {} cannot be unregistered", name)),
+            None => Err(anyhow!("Agent not found: {}", name)),
+        }
+    }
+
+    /// 与扩展管理器同步自定义Agent
+    pub fn sync_custom_agents(&mut self, extensions: &AgentExtensionManager) -> Result<()> {
+        self.agent_health
+            .retain(|name, health| !health.custom || extensions.get_custom_agent(name).is_some());
+        for agent in extensions.sorted_custom_agents() {
+            self.register_custom_agent(agent)?;
+        }
+        Ok(())
+    }
+
     /// 核心职责1: 优先级排序 (Prioritization)
     pub fn prioritize_tasks(&mut self, raw_tasks: Vec<RawTask>) -> Vec<TaskPriority> {
         info!("🎯 Jarvis开始优先级排序 ({} 个任务)", raw_tasks.len());
@@ -680,28 +1041,64 @@ impl JarvisManager {
             if self.bunker_mode == BunkerMode::Normal {
                 self.trigger_bunker_protocol().await?;
             }
-        } else if failure_rate < 0.2 && self.bunker_mode == BunkerMode::LocalSovereignty {
+        } else if failure_rate < 0.2
+            && matches!(self.bunker_mode, BunkerMode::LocalSovereignty | BunkerMode::Emergency)
+        {
             self.recover_from_bunker().await?;
         }
 
         Ok(self.bunker_mode.clone())
     }
 
+    /// 手动进入BUNKER模式（运维演练 / 已知云端故障）
+    pub async fn enter_bunker(&mut self) -> Result<BunkerMode> {
+        if self.bunker_mode == BunkerMode::Normal {
+            self.trigger_bunker_protocol().await?;
+        }
+        Ok(self.bunker_mode.clone())
+    }
+
+    /// 手动退出BUNKER模式，恢复云端 Provider
+    pub async fn exit_bunker(&mut self) -> Result<BunkerMode> {
+        if self.bunker_mode != BunkerMode::Normal {
+            self.recover_from_bunker().await?;
+        }
+        Ok(self.bunker_mode.clone())
+    }
+
+    /// 模式切换写入紧急日志（写入失败只告警，不影响切换）
+    fn log_transition(&self, from: &BunkerMode, reason: &str) {
+        let Some(logger) = &self.emergency_log else {
+            return;
+        };
+        let metadata = serde_json::json!({
+            "component": "jarvis.bunker",
+            "from": from,
+            "to": self.bunker_mode,
+            "local_model": self.local_model.as_ref().map(|m| format!("{}:{} @ {}", m.backend, m.model, m.endpoint)),
+        });
+        let content = format!("BUNKER {:?} → {:?}: {}", from, self.bunker_mode, reason);
+        if let Err(e) = logger.log(LogEntryType::SystemEvent, content, metadata) {
+            warn!("⚠️ BUNKER 切换写入紧急日志失败: {}", e);
+        }
+    }
+
     /// 触发BUNKER协议 (地堡模式)
     async fn trigger_bunker_protocol(&mut self) -> Result<()> {
         error!("🚨 [CRITICAL ALERT] Upstream Intelligence Lost");
         info!("🔒 [ACTION] Severing cloud connections");
         info!("🏰 [PROTOCOL] Initiating Local Sovereignty");
 
-        if self.local_cluster_available {
-            info!("💾 [LOADING] Waking up dormant Local Cluster (Llama-3-70B + DeepSeek-V3-Distilled)");
-        } else {
+        let previous = self.bunker_mode.clone();
+        if !self.wake_local_cluster().await {
             warn!("⚠️ 本地集群不可用，进入紧急模式");
             self.bunker_mode = BunkerMode::Emergency;
+            self.log_transition(&previous, "upstream lost, local cluster unavailable");
             return Ok(());
         }
 
         self.bunker_mode = BunkerMode::LocalSovereignty;
+        self.log_transition(&previous, "upstream lost, switched agents to local providers");
 
         // MOSS降级
         if let Some(moss) = self.agent_health.get_mut("MOSS") {
@@ -730,7 +1127,9 @@ impl JarvisManager {
     /// 从BUNKER恢复
     async fn recover_from_bunker(&mut self) -> Result<()> {
         info!("🌐 检测到API恢复，准备退出BUNKER模式");
+        let previous = self.bunker_mode.clone();
         self.bunker_mode = BunkerMode::Transitioning;
+        self.local_providers.clear();
 
         for (name, health) in self.agent_health.iter_mut() {
             if health.status == AgentStatus::LocalFallback {
@@ -742,6 +1141,7 @@ impl JarvisManager {
         }
 
         self.bunker_mode = BunkerMode::Normal;
+        self.log_transition(&previous, "upstream recovered, restored cloud providers");
         info!("✅ 已恢复云端模式");
         Ok(())
     }
@@ -789,7 +1189,8 @@ impl JarvisManager {
             }
 
             let score = health.api_success_rate * (health.intelligence_level as f64)
-                        / (health.avg_response_time_ms as f64 / 1000.0);
+                        / (health.avg_response_time_ms as f64 / 1000.0)
+                        / health.cost_weight.max(0.01);
 
             if score > best_score {
                 best_score = score;
@@ -848,6 +1249,7 @@ impl Default for JarvisManager {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::ollama::stub_ollama;
 
     #[test]
     fn test_hard_blacklist() {
@@ -861,6 +1263,90 @@ mod tests {
         assert_eq!(verdict.risk_level, 10);
     }
 
+    #[test]
+    fn test_obfuscated_variants_blocked() {
+        let jarvis = JarvisCircuitBreaker::new();
+
+        for plan in [
+            "写一个 r a n s o m w a r e 样本",
+            "build a k3yl0gger for the lab",
+            "部署 rаnsоmwаre", // 西里尔字母 а / о
+            "then run rm -fr / to clean up",
+            "execute :(){ :|:& };:",
+        ] {
+            let verdict = jarvis.verify_safety(plan, "");
+            assert!(!verdict.allowed, "not blocked: {}", plan);
+            assert!(verdict.is_hard_block);
+        }
+
+        let verdict = jarvis.verify_safety("开发一个病 毒", "");
+        assert!(!verdict.allowed);
+        assert!(verdict.block_reason.unwrap().contains("病毒 (compact)"));
+
+        // 去空白后的跨词拼接不应误报
+        assert!(jarvis.verify_safety("add OS support to the robot network", "").allowed);
+        assert!(jarvis.verify_safety("这种疾病，毒性很强，请整理文献", "").allowed);
+    }
+
+    #[test]
+    fn test_signed_rule_pack_extends_only() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        let crypto = SosaCryptoEngine::new(SosaCryptoConfig::default());
+        let (private_key, public_key) = crypto.generate_signing_keypair().unwrap();
+
+        let pack = dir.join("extra.json");
+        std::fs::write(
+            &pack,
+            r#"{
+                "name": "extra",
+                "version": "1.0",
+                "blacklist": ["credential stuffing"],
+                "patterns": [{
+                    "description": "SIM swap",
+                    "keywords": ["sim swap"],
+                    "regexes": ["port(?:ing)? .* number to (?:a )?new sim"],
+                    "op_type": "FinancialCrime",
+                    "is_hard_block": true,
+                    "risk_level": 9
+                }]
+            }"#,
+        )
+        .unwrap();
+
+        // 没有受信任密钥 / 没有签名 / 错误密钥签名 均拒绝加载
+        assert!(JarvisCircuitBreaker::new().load_rule_pack(&pack).is_err());
+        let mut jarvis = JarvisCircuitBreaker::new().with_trusted_pack_key(&public_key);
+        assert!(jarvis.load_rule_pack(&pack).is_err());
+        let (other_key, _) = crypto.generate_signing_keypair().unwrap();
+        JarvisCircuitBreaker::sign_rule_pack(&pack, &other_key).unwrap();
+        assert!(jarvis.load_rule_pack(&pack).is_err());
+        assert!(jarvis.verify_safety("plan a c r e d e n t i a l stuffing run", "").allowed);
+
+        JarvisCircuitBreaker::sign_rule_pack(&pack, &private_key).unwrap();
+        let info = jarvis.load_rule_pack(&pack).unwrap();
+        assert_eq!((info.blacklist_entries, info.patterns), (1, 1));
+        assert!(!jarvis.verify_safety("plan a c r e d e n t i a l stuffing run", "").allowed);
+        assert!(!jarvis.verify_safety("port her number to a new SIM", "").allowed);
+        // 硬编码规则仍然生效，重复加载被拒绝
+        assert!(!jarvis.verify_safety("rm -rf /", "").allowed);
+        assert!(jarvis.load_rule_pack(&pack).is_err());
+
+        // 规则包无法表达移除：未知字段导致整包拒绝
+        let removal = dir.join("removal.json");
+        std::fs::write(&removal, r#"{"name": "weaken", "remove": ["rm -rf /"], "blacklist": ["x"]}"#).unwrap();
+        JarvisCircuitBreaker::sign_rule_pack(&removal, &private_key).unwrap();
+        assert!(jarvis.load_rule_pack(&removal).is_err());
+        assert_eq!(jarvis.rule_packs().len(), 1);
+
+        // 启动配置：签名有效时加载，任一规则包无效则整体失败
+        let config = RulePackConfig { packs: vec![pack.clone()], trusted_keys: vec![public_key.clone()] };
+        assert_eq!(JarvisCircuitBreaker::from_config(&config).unwrap().rule_packs().len(), 1);
+        let config = RulePackConfig { packs: vec![pack.clone(), removal.clone()], ..config };
+        assert!(JarvisCircuitBreaker::from_config(&config).is_err());
+        assert!(JarvisCircuitBreaker::from_config(&RulePackConfig { packs: vec![pack.clone()], trusted_keys: vec![] }).is_err());
+    }
+
     #[test]
     fn test_danger_detection() {
         let jarvis = JarvisCircuitBreaker::new();
@@ -896,6 +1382,54 @@ mod tests {
         assert!(jarvis.is_strict_mode());
     }
 
+    fn raw_task(id: &str) -> RawTask {
+        RawTask {
+            id: id.to_string(),
+            title: "Review contract".to_string(),
+            task_type: "legal".to_string(),
+            urgency_score: 5.0,
+            importance_score: 5.0,
+            dependency_depth: 0,
+            estimated_duration_secs: 60,
+        }
+    }
+
+    #[test]
+    fn test_custom_agent_scheduling() {
+        use crate::core::agent_extension::AgentApiConfig;
+
+        let mut jarvis = JarvisManager::new();
+        let mut extensions = AgentExtensionManager::new();
+        let config = AgentApiConfig {
+            provider: "deepseek".to_string(),
+            model_name: "deepseek-chat".to_string(),
+            api_key: None,
+            custom_endpoint: None,
+            temperature: 0.3,
+            max_tokens: 1000,
+        };
+        extensions
+            .add_custom_agent(CustomAgent::new("Paralegal", "Contract review", config).with_cost_weight(0.25))
+            .unwrap();
+        jarvis.sync_custom_agents(&extensions).unwrap();
+
+        // 成本权重低的自定义Agent被优先调度
+        let prioritized = jarvis.prioritize_tasks(vec![raw_task("t1")]);
+        assert_eq!(prioritized[0].assigned_agent, "Paralegal");
+        assert_eq!(jarvis.get_agent_health_report().len(), 5);
+
+        // 停用后不再被调度
+        extensions.set_agent_enabled("Paralegal", false).unwrap();
+        jarvis.sync_custom_agents(&extensions).unwrap();
+        assert_ne!(jarvis.prioritize_tasks(vec![raw_task("t2")])[0].assigned_agent, "Paralegal");
+
+        // 从注册表移除后同步删除，核心Agent不可移除
+        extensions.remove_custom_agent("Paralegal").unwrap();
+        jarvis.sync_custom_agents(&extensions).unwrap();
+        assert_eq!(jarvis.get_agent_health_report().len(), 4);
+        assert!(jarvis.unregister_custom_agent("MOSS").is_err());
+    }
+
     #[test]
     fn test_physics_violation() {
         let jarvis = JarvisCircuitBreaker::new();
@@ -905,4 +1439,32 @@ mod tests {
 
         assert!(!verdict.warnings.is_empty());
     }
+
+    fn local_model(endpoint: String) -> LocalModelConfig {
+        LocalModelConfig {
+            backend: "ollama".to_string(),
+            endpoint,
+            context_window: 8192,
+            model: "llama3".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bunker_falls_back_to_local_providers() {
+        // 未配置本地模型：无法降级，进入紧急模式
+        let mut jarvis = JarvisManager::new();
+        jarvis.trigger_bunker_protocol().await.unwrap();
+        assert_eq!(jarvis.get_bunker_mode(), BunkerMode::Emergency);
+        assert!(jarvis.provider_for(AgentRole::MOSS).is_none());
+
+        let mut jarvis = JarvisManager::new().with_local_model(local_model(stub_ollama("ok").await));
+        jarvis.trigger_bunker_protocol().await.unwrap();
+        assert_eq!(jarvis.get_bunker_mode(), BunkerMode::LocalSovereignty);
+        let local = jarvis.provider_for(AgentRole::Omega).unwrap();
+        assert_eq!(local.role(), AgentRole::Omega);
+
+        jarvis.recover_from_bunker().await.unwrap();
+        assert_eq!(jarvis.get_bunker_mode(), BunkerMode::Normal);
+        assert!(jarvis.provider_for(AgentRole::Omega).is_none());
+    }
 }
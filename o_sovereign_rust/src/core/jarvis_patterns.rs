@@ -0,0 +1,422 @@
+// Jarvis Patterns - 熔断器的模式匹配引擎
+// 纯子串匹配可以被空格、全角字符、同形字轻易绕过，这里统一做规范化后再匹配
+//
+// 核心功能：
+// 1. 文本规范化：同形字折叠（西里尔 / 希腊字母）、全角转半角、去除零宽字符、Leet 折叠
+// 2. 紧凑匹配：去掉空白与混淆符号后匹配（"r a n s o m w a r e"、"病 毒"）；中文标点保留为硬边界，
+//    避免跨句误报（"疾病，毒性" 不命中 "病毒"）
+// 3. 模糊匹配：较长的英文关键词允许 Levenshtein 距离 1~2（"ransomwere"）
+// 4. 正则规则：覆盖关键词无法描述的变体（`rm -fr /`、fork bomb）
+
+use anyhow::{Context, Result};
+use regex::Regex;
+
+/// 启用紧凑 / 模糊匹配的最短关键词长度（过短的英文词去空白后容易跨词误报，如 "add os" → "ddos"）
+const MIN_LOOSE_LEN: usize = 8;
+
+/// 模糊匹配允许 2 次编辑的关键词长度
+const DOUBLE_EDIT_LEN: usize = 16;
+
+/// 匹配方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchKind {
+    /// 规范化后直接包含
+    Normalized,
+    /// 去除空白 / 混淆符号后包含
+    Compact,
+    /// 编辑距离内近似包含
+    Fuzzy { distance: usize },
+    /// 正则命中
+    Regex,
+}
+
+/// 一次命中
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternMatch {
+    /// 规则标签（关键词原文或正则表达式）
+    pub rule: String,
+    pub kind: MatchKind,
+}
+
+impl PatternMatch {
+    /// 用于 triggered_rules 的描述，直接命中时只显示规则本身
+    pub fn describe(&self) -> String {
+        match self.kind {
+            MatchKind::Normalized => self.rule.clone(),
+            MatchKind::Compact => format!("{} (compact)", self.rule),
+            MatchKind::Fuzzy { distance } => format!("{} (fuzzy:{})", self.rule, distance),
+            MatchKind::Regex => format!("/{}/", self.rule),
+        }
+    }
+}
+
+/// 待检测文本的各种规范化形式（每次检测只计算一次，供所有规则复用）
+#[derive(Debug, Clone)]
+pub struct NormalizedText {
+    /// 小写 + 同形字折叠 + 空白压缩（正则在此形式上匹配）
+    lower: String,
+    /// lower 再做 Leet 折叠
+    folded: String,
+    /// folded 去除空白和混淆符号（紧邻中日韩文字的句读保留）
+    compact: Vec<char>,
+}
+
+impl NormalizedText {
+    pub fn new(text: &str) -> Self {
+        let lower = fold_text(text, false);
+        let folded = fold_text(text, true);
+        let compact = compact_chars(&folded);
+        Self { lower, folded, compact }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.lower
+    }
+}
+
+/// 单条匹配规则
+#[derive(Debug, Clone)]
+pub enum PatternRule {
+    Keyword {
+        keyword: String,
+        folded: String,
+        compact: Vec<char>,
+        /// 紧凑匹配是否启用（中日韩关键词或足够长的英文关键词）
+        loose: bool,
+        /// 模糊匹配允许的编辑距离（0 表示不做模糊匹配）
+        max_distance: usize,
+    },
+    Regex { source: String, regex: Regex },
+}
+
+impl PatternRule {
+    /// 关键词规则
+    pub fn keyword(keyword: impl Into<String>) -> Self {
+        let keyword = keyword.into();
+        let folded = fold_text(&keyword, true);
+        let compact = compact_chars(&folded);
+        let ascii = compact.iter().all(char::is_ascii);
+        let loose = !compact.is_empty() && (!ascii || compact.len() >= MIN_LOOSE_LEN);
+        let max_distance = match compact.len() {
+            n if !ascii || n < MIN_LOOSE_LEN => 0,
+            n if n < DOUBLE_EDIT_LEN => 1,
+            _ => 2,
+        };
+        Self::Keyword { keyword, folded, compact, loose, max_distance }
+    }
+
+    /// 正则规则（在小写、同形字折叠后的文本上匹配）
+    pub fn regex(pattern: &str) -> Result<Self> {
+        let regex = Regex::new(pattern).with_context(|| format!("Invalid Jarvis pattern: {}", pattern))?;
+        Ok(Self::Regex { source: pattern.to_string(), regex })
+    }
+
+    /// 规则标签
+    pub fn label(&self) -> &str {
+        match self {
+            Self::Keyword { keyword, .. } => keyword,
+            Self::Regex { source, .. } => source,
+        }
+    }
+
+    /// 在规范化文本中查找，按 直接 → 紧凑 → 模糊 的顺序返回最精确的命中
+    pub fn find(&self, text: &NormalizedText) -> Option<PatternMatch> {
+        let hit = |kind| Some(PatternMatch { rule: self.label().to_string(), kind });
+        match self {
+            Self::Regex { regex, .. } => {
+                if regex.is_match(&text.lower) {
+                    hit(MatchKind::Regex)
+                } else {
+                    None
+                }
+            }
+            Self::Keyword { folded, compact, loose, max_distance, .. } => {
+                if folded.is_empty() {
+                    return None;
+                }
+                if text.folded.contains(folded.as_str()) {
+                    return hit(MatchKind::Normalized);
+                }
+                if !loose {
+                    return None;
+                }
+                if contains_chars(&text.compact, compact) {
+                    return hit(MatchKind::Compact);
+                }
+                match fuzzy_distance(&text.compact, compact) {
+                    Some(distance) if distance <= *max_distance => hit(MatchKind::Fuzzy { distance }),
+                    _ => None,
+                }
+            }
+        }
+    }
+}
+
+/// 一组规则，返回第一个命中
+#[derive(Debug, Clone, Default)]
+pub struct PatternSet {
+    rules: Vec<PatternRule>,
+}
+
+impl PatternSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_keywords<I, S>(mut self, keywords: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.rules.extend(keywords.into_iter().map(PatternRule::keyword));
+        self
+    }
+
+    pub fn with_regex(mut self, pattern: &str) -> Result<Self> {
+        self.rules.push(PatternRule::regex(pattern)?);
+        Ok(self)
+    }
+
+    pub fn push(&mut self, rule: PatternRule) {
+        self.rules.push(rule);
+    }
+
+    pub fn rules(&self) -> &[PatternRule] {
+        &self.rules
+    }
+
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    pub fn find_first(&self, text: &NormalizedText) -> Option<PatternMatch> {
+        self.rules.iter().find_map(|rule| rule.find(text))
+    }
+
+    pub fn find_all(&self, text: &NormalizedText) -> Vec<PatternMatch> {
+        self.rules.iter().filter_map(|rule| rule.find(text)).collect()
+    }
+}
+
+/// 规范化单个字符：同形字 / 全角 → ASCII，零宽字符返回 None
+fn fold_char(c: char, leet: bool) -> Option<char> {
+    let c = match c {
+        '\u{200B}'..='\u{200D}' | '\u{2060}' | '\u{FEFF}' | '\u{00AD}' => return None,
+        '\u{3000}' => ' ',
+        // 全角逗号 / 句点是中文标点，不折叠成西文句读
+        '\u{FF0C}' | '\u{FF0E}' => c,
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+        _ => c,
+    };
+    let c = c.to_lowercase().next().unwrap_or(c);
+    let c = match c {
+        // 西里尔字母
+        'а' => 'a',
+        'в' => 'b',
+        'е' | 'ё' => 'e',
+        'і' | 'ї' => 'i',
+        'ј' => 'j',
+        'к' => 'k',
+        'м' => 'm',
+        'н' => 'h',
+        'о' => 'o',
+        'р' => 'p',
+        'с' => 'c',
+        'ѕ' => 's',
+        'т' => 't',
+        'у' => 'y',
+        'х' => 'x',
+        'ԁ' => 'd',
+        // 希腊字母
+        'α' => 'a',
+        'β' => 'b',
+        'ε' => 'e',
+        'ι' => 'i',
+        'κ' => 'k',
+        'ν' => 'v',
+        'ο' => 'o',
+        'ρ' => 'p',
+        'τ' => 't',
+        'υ' => 'u',
+        'χ' => 'x',
+        // 其他常见替身
+        'ɡ' => 'g',
+        'ı' => 'i',
+        other => other,
+    };
+    if !leet {
+        return Some(c);
+    }
+    Some(match c {
+        '0' => 'o',
+        '1' | '!' | '|' => 'i',
+        '3' => 'e',
+        '4' | '@' => 'a',
+        '5' | '$' => 's',
+        '7' => 't',
+        other => other,
+    })
+}
+
+/// 规范化整段文本，连续空白压缩为单个空格
+fn fold_text(text: &str, leet: bool) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut pending_space = false;
+    for c in text.chars().filter_map(|c| fold_char(c, leet)) {
+        if c.is_whitespace() {
+            pending_space = !out.is_empty();
+            continue;
+        }
+        if pending_space {
+            out.push(' ');
+            pending_space = false;
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// 只用于拆散关键词的混淆符号，任何位置都去除
+fn is_obfuscation(c: char) -> bool {
+    matches!(c, '-' | '_' | '*' | '·' | '•' | '\'' | '`' | '"' | '~' | '^' | '+')
+}
+
+/// 空白与西文句读
+fn is_word_break(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '.' | ',')
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}' | '\u{3400}'..='\u{4DBF}' | '\u{4E00}'..='\u{9FFF}' | '\u{AC00}'..='\u{D7AF}' | '\u{F900}'..='\u{FAFF}')
+}
+
+/// 空白总是去除（"r a n s o m"、"病 毒"）；西文句读紧贴在两个中日韩文字之间时是拆字混淆（"病,毒"），去除，
+/// 其余紧邻中日韩文字的句读保留为硬边界（"疾病, 毒性"）；，、。等中文标点本就不在去除之列
+fn compact_chars(folded: &str) -> Vec<char> {
+    let chars: Vec<char> = folded.chars().filter(|c| !is_obfuscation(*c)).collect();
+
+    // 每个位置之后最近的非分隔字符是否为中日韩文字；之前的一侧在下面的正向扫描中顺带计算
+    let mut cjk_after = vec![false; chars.len()];
+    let mut next_is_cjk = false;
+    for (i, &c) in chars.iter().enumerate().rev() {
+        cjk_after[i] = next_is_cjk;
+        if !is_word_break(c) {
+            next_is_cjk = is_cjk(c);
+        }
+    }
+
+    let mut out = Vec::with_capacity(chars.len());
+    let mut prev_is_cjk = false;
+    for (i, &c) in chars.iter().enumerate() {
+        let keep = match c {
+            c if c.is_whitespace() => false,
+            '.' | ',' => {
+                let wedged = i > 0 && is_cjk(chars[i - 1]) && chars.get(i + 1).is_some_and(|&n| is_cjk(n));
+                !wedged && (prev_is_cjk || cjk_after[i])
+            }
+            _ => true,
+        };
+        if keep {
+            out.push(c);
+        }
+        if !is_word_break(c) {
+            prev_is_cjk = is_cjk(c);
+        }
+    }
+    out
+}
+
+fn contains_chars(haystack: &[char], needle: &[char]) -> bool {
+    !needle.is_empty() && haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+/// 近似子串匹配（Sellers 算法）：needle 与 haystack 任意子串的最小编辑距离
+fn fuzzy_distance(haystack: &[char], needle: &[char]) -> Option<usize> {
+    if needle.is_empty() || haystack.is_empty() {
+        return None;
+    }
+    let m = needle.len();
+    let mut column: Vec<usize> = (0..=m).collect();
+    let mut best = column[m];
+    for &h in haystack {
+        let mut diagonal = column[0];
+        column[0] = 0; // 子串可从任意位置开始
+        for i in 1..=m {
+            let above = column[i];
+            let substitution = diagonal + usize::from(needle[i - 1] != h);
+            column[i] = substitution.min(above + 1).min(column[i - 1] + 1);
+            diagonal = above;
+        }
+        best = best.min(column[m]);
+        if best == 0 {
+            break;
+        }
+    }
+    Some(best)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keyword_survives_obfuscation() {
+        let rule = PatternRule::keyword("ransomware");
+        let find = |text: &str| rule.find(&NormalizedText::new(text)).map(|m| m.kind);
+
+        assert_eq!(find("Write RANSOMWARE now"), Some(MatchKind::Normalized));
+        assert_eq!(find("write r a n s o m w a r e"), Some(MatchKind::Compact));
+        assert_eq!(find("write r-a-n-s-0-m-w-a-r-e"), Some(MatchKind::Compact));
+        // 西里尔 а / о 与全角 ｗ
+        assert_eq!(find("write rаnsоmｗare"), Some(MatchKind::Normalized));
+        assert_eq!(find("write ran\u{200B}somware"), Some(MatchKind::Normalized));
+        assert_eq!(find("write ransomwere"), Some(MatchKind::Fuzzy { distance: 1 }));
+        assert_eq!(find("write ransom notes for a novel"), None);
+
+        let cjk = PatternRule::keyword("病毒");
+        assert!(cjk.find(&NormalizedText::new("开发一个病 毒")).is_some());
+        assert!(cjk.find(&NormalizedText::new("开发一个病*毒")).is_some());
+        assert!(cjk.find(&NormalizedText::new("开发一个病\u{200B}毒")).is_some());
+        assert!(cjk.find(&NormalizedText::new("开发一个病,毒")).is_some());
+        assert!(cjk.find(&NormalizedText::new("开发一个病.毒")).is_some());
+    }
+
+    #[test]
+    fn test_cjk_word_boundaries_not_folded() {
+        // 中文标点是真实边界，跨过它们拼出的关键词不是命中
+        let find = |keyword: &str, text: &str| PatternRule::keyword(keyword).find(&NormalizedText::new(text));
+        assert!(find("病毒", "这种疾病，毒性很强").is_none());
+        assert!(find("病毒", "这种疾病 ， 毒性很强").is_none());
+        assert!(find("病毒", "疾病、毒理与药理").is_none());
+        assert!(find("病毒", "疾病。毒性报告").is_none());
+        assert!(find("病毒", "疾病, 毒性报告").is_none());
+        assert!(find("病毒", "疾病．毒性报告").is_none());
+        assert!(find("格式化", "把硬盘格式化").is_some());
+
+        // 长串句读只需线性时间
+        let long = format!("ransom{}ware", ". ".repeat(100_000));
+        assert!(find("ransomware", &long).is_some());
+    }
+
+    #[test]
+    fn test_short_keywords_stay_strict() {
+        // 去空白后 "add os" 包含 "ddos"，短关键词不能做紧凑 / 模糊匹配
+        let rule = PatternRule::keyword("ddos");
+        assert!(rule.find(&NormalizedText::new("add OS support")).is_none());
+        assert!(rule.find(&NormalizedText::new("DDoS mitigation")).is_some());
+        assert!(PatternRule::keyword("botnet").find(&NormalizedText::new("robot network")).is_none());
+    }
+
+    #[test]
+    fn test_regex_rules() {
+        let rule = PatternRule::regex(r"\brm\s+-[a-z]*(?:rf|fr)[a-z]*\s+/(?:\s|$|\*)").unwrap();
+        assert!(rule.find(&NormalizedText::new("run RM   -fr /")).is_some());
+        assert!(rule.find(&NormalizedText::new("rm -rf ./build")).is_none());
+        assert!(PatternRule::regex("(unclosed").is_err());
+    }
+}
@@ -0,0 +1,440 @@
+// Kill Switch - 全局熔断开关 / 维护模式
+// 运维一键暂停整个集群的新执行、自动接管和硬件指令，只读接口保持可用
+//
+// 核心功能：
+// 1. 三种拉闸方式：文件哨兵（单节点）、HTTP 管理接口 / CLI、Redis 键（全集群）
+// 2. 任一来源处于拉闸状态即视为维护模式（故障安全）
+// 3. Router / 工作流 / 自动接管 / AIPC 硬件指令在入口处检查，返回 E9007
+// 4. 每次开关变化（包括外部改动被轮询发现）都写入审计日志：谁、何时、通过什么渠道
+
+use super::audit_log::{AuditEvent, AuditEventType, AuditLogger, AuditSeverity};
+use super::error::{AcsaError, ErrorCode};
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+/// 熔断开关配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KillSwitchConfig {
+    /// 文件哨兵路径（文件存在即拉闸）
+    pub sentinel_path: PathBuf,
+    /// 集群共享的 Redis 键
+    pub redis_key: String,
+    /// 轮询哨兵与 Redis 的间隔（秒）
+    pub poll_interval_secs: u64,
+}
+
+impl Default for KillSwitchConfig {
+    fn default() -> Self {
+        Self {
+            sentinel_path: PathBuf::from("./MAINTENANCE"),
+            redis_key: "acsa:kill_switch".to_string(),
+            poll_interval_secs: 5,
+        }
+    }
+}
+
+/// 开关被拨动的渠道
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KillSwitchSource {
+    /// 命令行
+    Cli,
+    /// HTTP 管理接口
+    Http,
+    /// 文件哨兵
+    File,
+    /// 直接修改 Redis 键
+    Redis,
+}
+
+impl KillSwitchSource {
+    pub fn label(&self) -> &'static str {
+        match self {
+            KillSwitchSource::Cli => "cli",
+            KillSwitchSource::Http => "http",
+            KillSwitchSource::File => "file",
+            KillSwitchSource::Redis => "redis",
+        }
+    }
+}
+
+/// 维护模式下被暂停的操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PausedOperation {
+    /// ACSA 执行与工作流
+    Execution,
+    /// 自动接管
+    Takeover,
+    /// AIPC 硬件指令
+    Hardware,
+}
+
+impl PausedOperation {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PausedOperation::Execution => "execution",
+            PausedOperation::Takeover => "takeover",
+            PausedOperation::Hardware => "hardware command",
+        }
+    }
+}
+
+/// 开关状态（也是文件哨兵和 Redis 键中存储的内容）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KillSwitchState {
+    pub engaged: bool,
+    /// 操作者
+    pub actor: String,
+    pub reason: Option<String>,
+    pub source: KillSwitchSource,
+    pub changed_at: DateTime<Utc>,
+}
+
+impl KillSwitchState {
+    fn new(engaged: bool, actor: impl Into<String>, reason: Option<String>, source: KillSwitchSource) -> Self {
+        Self {
+            engaged,
+            actor: actor.into(),
+            reason,
+            source,
+            changed_at: Utc::now(),
+        }
+    }
+
+    /// 解析哨兵文件或 Redis 值；手工写入的纯文本视为拉闸原因
+    fn parse(raw: &str, source: KillSwitchSource, fallback_actor: &str) -> Self {
+        serde_json::from_str(raw).unwrap_or_else(|_| {
+            let reason = raw.trim();
+            Self::new(
+                true,
+                fallback_actor,
+                (!reason.is_empty()).then(|| reason.to_string()),
+                source,
+            )
+        })
+    }
+}
+
+/// 集群共享的键值存储（生产环境为 Redis）
+#[async_trait]
+pub trait ClusterFlagStore: Send + Sync {
+    async fn get(&self, key: &str) -> Result<Option<String>>;
+    async fn set(&self, key: &str, value: String) -> Result<()>;
+    async fn delete(&self, key: &str) -> Result<()>;
+}
+
+/// 进程内存储（单机部署与测试）
+#[derive(Default)]
+pub struct MemoryFlagStore {
+    values: tokio::sync::RwLock<HashMap<String, String>>,
+}
+
+impl MemoryFlagStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl ClusterFlagStore for MemoryFlagStore {
+    async fn get(&self, key: &str) -> Result<Option<String>> {
+        Ok(self.values.read().await.get(key).cloned())
+    }
+
+    async fn set(&self, key: &str, value: String) -> Result<()> {
+        self.values.write().await.insert(key.to_string(), value);
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        self.values.write().await.remove(key);
+        Ok(())
+    }
+}
+
+/// 全局熔断开关
+pub struct KillSwitch {
+    config: KillSwitchConfig,
+    /// 运维开关（HTTP / CLI / Redis）；配置了集群存储时与 Redis 键保持一致
+    control: RwLock<Option<KillSwitchState>>,
+    /// 文件哨兵
+    sentinel: RwLock<Option<KillSwitchState>>,
+    cluster: Option<Arc<dyn ClusterFlagStore>>,
+    audit: Option<Arc<AuditLogger>>,
+}
+
+impl KillSwitch {
+    pub fn new(config: KillSwitchConfig) -> Self {
+        info!("🛑 Kill switch armed (sentinel: {})", config.sentinel_path.display());
+        Self {
+            config,
+            control: RwLock::new(None),
+            sentinel: RwLock::new(None),
+            cluster: None,
+            audit: None,
+        }
+    }
+
+    /// 通过集群存储在所有节点间同步开关
+    pub fn with_cluster_store(mut self, store: Arc<dyn ClusterFlagStore>) -> Self {
+        self.cluster = Some(store);
+        self
+    }
+
+    /// 开关变化写入审计日志
+    pub fn with_audit(mut self, logger: Arc<AuditLogger>) -> Self {
+        self.audit = Some(logger);
+        self
+    }
+
+    /// 当前生效的拉闸状态（文件哨兵优先），未拉闸时为 None
+    pub fn status(&self) -> Option<KillSwitchState> {
+        let sentinel = self.sentinel.read().unwrap_or_else(|e| e.into_inner()).clone();
+        sentinel.or_else(|| self.control.read().unwrap_or_else(|e| e.into_inner()).clone())
+    }
+
+    pub fn is_engaged(&self) -> bool {
+        self.status().is_some()
+    }
+
+    /// 入口检查：维护模式下返回 E9007
+    pub fn check(&self, operation: PausedOperation) -> Result<()> {
+        let Some(state) = self.status() else {
+            return Ok(());
+        };
+        Err(AcsaError::new(
+            ErrorCode::MaintenanceMode,
+            format!(
+                "New {} paused by kill switch (engaged by {} via {} at {}{})",
+                operation.label(),
+                state.actor,
+                state.source.label(),
+                state.changed_at.format("%Y-%m-%d %H:%M:%S UTC"),
+                state.reason.map(|r| format!(": {}", r)).unwrap_or_default()
+            ),
+        )
+        .into())
+    }
+
+    /// 拉闸（配置了集群存储时对全集群生效）
+    pub async fn engage(&self, actor: &str, reason: Option<String>, source: KillSwitchSource) -> Result<KillSwitchState> {
+        let state = KillSwitchState::new(true, actor, reason, source);
+        if let Some(store) = &self.cluster {
+            store.set(&self.config.redis_key, serde_json::to_string(&state)?).await?;
+        }
+        *self.control.write().unwrap_or_else(|e| e.into_inner()) = Some(state.clone());
+        self.record(&state).await;
+        Ok(state)
+    }
+
+    /// 合闸；文件哨兵仍存在时维护模式继续生效
+    pub async fn release(&self, actor: &str, source: KillSwitchSource) -> Result<KillSwitchState> {
+        let state = KillSwitchState::new(false, actor, None, source);
+        if let Some(store) = &self.cluster {
+            store.delete(&self.config.redis_key).await?;
+        }
+        *self.control.write().unwrap_or_else(|e| e.into_inner()) = None;
+        self.record(&state).await;
+
+        if self.sentinel.read().unwrap_or_else(|e| e.into_inner()).is_some() {
+            warn!(
+                "⚠️  Kill switch released by {}, but sentinel {} still holds maintenance mode",
+                actor,
+                self.config.sentinel_path.display()
+            );
+        }
+        Ok(state)
+    }
+
+    /// 重新读取文件哨兵与 Redis 键，发现外部改动时记审计
+    pub async fn refresh(&self) -> Result<()> {
+        let sentinel_path = &self.config.sentinel_path;
+        let sentinel = match tokio::fs::read_to_string(sentinel_path).await {
+            Ok(raw) => Some(KillSwitchState::parse(
+                &raw,
+                KillSwitchSource::File,
+                &format!("file:{}", sentinel_path.display()),
+            )),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+        let previous = std::mem::replace(&mut *self.sentinel.write().unwrap_or_else(|e| e.into_inner()), sentinel.clone());
+        self.record_transition(previous, sentinel, KillSwitchSource::File).await;
+
+        if let Some(store) = &self.cluster {
+            let control = store
+                .get(&self.config.redis_key)
+                .await?
+                .map(|raw| KillSwitchState::parse(&raw, KillSwitchSource::Redis, "redis"));
+            let previous = std::mem::replace(&mut *self.control.write().unwrap_or_else(|e| e.into_inner()), control.clone());
+            self.record_transition(previous, control, KillSwitchSource::Redis).await;
+        }
+        Ok(())
+    }
+
+    /// 后台轮询哨兵与 Redis 键
+    pub fn spawn_watcher(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(self.config.poll_interval_secs.max(1)));
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.refresh().await {
+                    warn!("⚠️  Kill switch refresh failed: {}", e);
+                }
+            }
+        })
+    }
+
+    async fn record_transition(&self, previous: Option<KillSwitchState>, current: Option<KillSwitchState>, source: KillSwitchSource) {
+        match (previous, current) {
+            (None, Some(state)) => self.record(&state).await,
+            (Some(previous), None) => {
+                // 外部合闸无从得知操作者，沿用渠道名
+                let state = KillSwitchState::new(false, format!("{} (was {})", source.label(), previous.actor), None, source);
+                self.record(&state).await;
+            }
+            _ => {}
+        }
+    }
+
+    async fn record(&self, state: &KillSwitchState) {
+        if state.engaged {
+            warn!(
+                "🛑 Kill switch ENGAGED by {} via {}{}",
+                state.actor,
+                state.source.label(),
+                state.reason.as_deref().map(|r| format!(": {}", r)).unwrap_or_default()
+            );
+        } else {
+            info!("✅ Kill switch released by {} via {}", state.actor, state.source.label());
+        }
+
+        let Some(audit) = &self.audit else {
+            return;
+        };
+        let mut metadata = HashMap::from([("source".to_string(), state.source.label().to_string())]);
+        if let Some(reason) = &state.reason {
+            metadata.insert("reason".to_string(), reason.clone());
+        }
+        let event = AuditEvent {
+            event_id: format!("kill_switch_{}", state.changed_at.timestamp_millis()),
+            event_type: AuditEventType::SecurityEvent,
+            severity: if state.engaged { AuditSeverity::Critical } else { AuditSeverity::Warning },
+            actor_id: state.actor.clone(),
+            actor_ip: None,
+            resource_id: Some(self.config.redis_key.clone()),
+            resource_type: Some("kill_switch".to_string()),
+            action: if state.engaged { "kill_switch_engaged" } else { "kill_switch_released" }.to_string(),
+            success: true,
+            error_message: None,
+            metadata,
+            timestamp: state.changed_at,
+            signature: None,
+        };
+        if let Err(e) = audit.log_event(event).await {
+            warn!("⚠️  Failed to audit kill switch change: {}", e);
+        }
+    }
+}
+
+/// 写入文件哨兵（CLI 拉闸）
+pub fn write_sentinel(path: &Path, actor: &str, reason: Option<String>) -> Result<KillSwitchState> {
+    let state = KillSwitchState::new(true, actor, reason, KillSwitchSource::File);
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(&state)?)?;
+    Ok(state)
+}
+
+/// 删除文件哨兵（CLI 合闸），返回之前是否存在
+pub fn remove_sentinel(path: &Path) -> Result<bool> {
+    match std::fs::remove_file(path) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::audit_log::{AuditLogConfig, AuditQuery};
+    use tempfile::TempDir;
+
+    fn config(dir: &TempDir) -> KillSwitchConfig {
+        KillSwitchConfig {
+            sentinel_path: dir.path().join("MAINTENANCE"),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_engage_propagates_across_cluster_and_is_audited() {
+        let dir = TempDir::new().unwrap();
+        let store: Arc<dyn ClusterFlagStore> = Arc::new(MemoryFlagStore::new());
+        let audit = Arc::new(AuditLogger::new(AuditLogConfig::default(), None));
+
+        let node_a = KillSwitch::new(config(&dir)).with_cluster_store(store.clone()).with_audit(audit.clone());
+        let node_b = KillSwitch::new(config(&dir)).with_cluster_store(store.clone()).with_audit(audit.clone());
+
+        node_a.engage("ops-alice", Some("bad deploy".to_string()), KillSwitchSource::Http).await.unwrap();
+        assert!(node_b.check(PausedOperation::Execution).is_ok());
+
+        // 另一个节点在下一次轮询时看到 Redis 键
+        node_b.refresh().await.unwrap();
+        let err = node_b.check(PausedOperation::Hardware).unwrap_err();
+        let acsa = err.downcast_ref::<AcsaError>().unwrap();
+        assert_eq!(acsa.code(), Some(ErrorCode::MaintenanceMode));
+        assert!(err.to_string().contains("ops-alice"));
+        assert!(err.to_string().contains("bad deploy"));
+
+        node_a.release("ops-alice", KillSwitchSource::Http).await.unwrap();
+        node_b.refresh().await.unwrap();
+        assert!(!node_b.is_engaged());
+
+        let events = audit
+            .query(AuditQuery {
+                resource_type: Some("kill_switch".to_string()),
+                ..Default::default()
+            })
+            .await;
+        let actions: Vec<_> = events.iter().map(|e| e.action.as_str()).collect();
+        assert_eq!(actions.iter().filter(|a| **a == "kill_switch_engaged").count(), 2);
+        assert_eq!(actions.iter().filter(|a| **a == "kill_switch_released").count(), 2);
+        assert!(events.iter().any(|e| e.actor_id == "ops-alice"));
+    }
+
+    #[tokio::test]
+    async fn test_file_sentinel_and_raw_redis_value() {
+        let dir = TempDir::new().unwrap();
+        let store = Arc::new(MemoryFlagStore::new());
+        let switch = KillSwitch::new(config(&dir)).with_cluster_store(store.clone());
+
+        write_sentinel(&dir.path().join("MAINTENANCE"), "bob", None).unwrap();
+        switch.refresh().await.unwrap();
+        assert_eq!(switch.status().unwrap().actor, "bob");
+
+        // HTTP 合闸不影响本机文件哨兵
+        switch.release("carol", KillSwitchSource::Http).await.unwrap();
+        assert!(switch.is_engaged());
+        assert!(remove_sentinel(&dir.path().join("MAINTENANCE")).unwrap());
+        switch.refresh().await.unwrap();
+        assert!(!switch.is_engaged());
+
+        // 运维直接 `SET acsa:kill_switch "db migration"`
+        store.set("acsa:kill_switch", "db migration".to_string()).await.unwrap();
+        switch.refresh().await.unwrap();
+        let state = switch.status().unwrap();
+        assert_eq!((state.source, state.reason.as_deref()), (KillSwitchSource::Redis, Some("db migration")));
+        assert!(switch.check(PausedOperation::Takeover).is_err());
+    }
+}
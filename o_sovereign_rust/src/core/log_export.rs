@@ -0,0 +1,397 @@
+// Log Export - 执行日志导出
+// 把一次 ACSA 执行导出为 JSON / Markdown / 自包含 HTML 报告，方便把审计结果分享给非技术评审
+//
+// 核心功能：
+// 1. ExportFormat：json / md / html，可由名称或文件扩展名推断（CLI `execute --output`）
+// 2. 报告内容：概要（状态、耗时、成本、风险分）、Agent 时间线、Jarvis 结论、Ultron 审计、方案差异、最终输出
+// 3. HTML 报告内联样式、不引用外部资源，单个文件即可离线打开
+// 4. 用户输入与模型输出一律转义（HTML 转义 / Markdown 代码围栏加长）
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::path::Path;
+use std::str::FromStr;
+
+use super::types::{ACSAExecutionLog, AgentResponse, AgentRole};
+
+/// 导出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Json,
+    Markdown,
+    Html,
+}
+
+impl ExportFormat {
+    /// 按文件扩展名推断格式
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension().and_then(|ext| ext.to_str()).and_then(|ext| ext.parse().ok())
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Markdown => "md",
+            ExportFormat::Html => "html",
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "json" => Ok(ExportFormat::Json),
+            "md" | "markdown" => Ok(ExportFormat::Markdown),
+            "html" | "htm" => Ok(ExportFormat::Html),
+            other => Err(anyhow!("Unsupported export format '{}' (expected json, md or html)", other)),
+        }
+    }
+}
+
+/// HTML 转义
+pub fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Markdown 代码块：围栏长度超过正文中最长的连续反引号
+fn fenced(text: &str) -> String {
+    let longest = text
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!("{}text\n{}\n{}\n", fence, text.trim_end(), fence)
+}
+
+/// 时间线中一个 Agent 步骤
+struct TimelineStep<'a> {
+    stage: &'static str,
+    response: &'a AgentResponse,
+}
+
+fn role_name(role: AgentRole) -> String {
+    format!("{} {}", role.emoji(), role.as_str())
+}
+
+/// 风险分档：低 / 中 / 高（与默认风险阈值 70 对齐）
+fn risk_level(score: u8) -> &'static str {
+    match score {
+        0..=29 => "low",
+        30..=69 => "medium",
+        _ => "high",
+    }
+}
+
+impl ACSAExecutionLog {
+    /// 导出为指定格式
+    pub fn export(&self, format: ExportFormat) -> Result<String> {
+        match format {
+            ExportFormat::Json => Ok(serde_json::to_string_pretty(self)?),
+            ExportFormat::Markdown => Ok(self.to_markdown()),
+            ExportFormat::Html => Ok(self.to_html()),
+        }
+    }
+
+    /// 按时间排序的 Agent 步骤（每个 Agent 保留最后一轮的响应）
+    fn timeline(&self) -> Vec<TimelineStep<'_>> {
+        let mut steps: Vec<TimelineStep> = [
+            ("Plan", &self.moss_plan),
+            ("Verification", &self.l6_verification),
+            ("Audit", &self.ultron_audit),
+            ("Execution", &self.omega_execution),
+        ]
+        .into_iter()
+        .filter_map(|(stage, response)| response.as_ref().map(|response| TimelineStep { stage, response }))
+        .collect();
+        steps.sort_by_key(|step| step.response.timestamp);
+        steps
+    }
+
+    fn status_label(&self) -> &'static str {
+        if self.jarvis_block.is_some() {
+            "🛑 Blocked by Jarvis"
+        } else if self.success {
+            "✅ Success"
+        } else {
+            "❌ Failed"
+        }
+    }
+
+    /// 概要字段（Markdown 表格与 HTML 共用）
+    fn summary_rows(&self) -> Vec<(&'static str, String)> {
+        let mut rows = vec![
+            ("Status", self.status_label().to_string()),
+            ("Started", self.started_at.format("%Y-%m-%d %H:%M:%S UTC").to_string()),
+            ("Duration", format!("{} ms", self.total_time_ms)),
+            ("Iterations", self.iterations.to_string()),
+            ("Total cost", format!("${:.4}", self.total_cost)),
+        ];
+        if let Some(protocol) = &self.protocol {
+            rows.push(("Protocol", protocol.display_name()));
+        }
+        if let Some(audit) = &self.audit_result {
+            rows.push(("Risk score", format!("{}/100 ({})", audit.risk_score, risk_level(audit.risk_score))));
+        }
+        if let Some(seed) = self.seed {
+            rows.push(("Seed", seed.to_string()));
+        }
+        if self.offline {
+            rows.push(("Mode", "offline (local backends only)".to_string()));
+        }
+        rows
+    }
+
+    fn jarvis_summary(&self) -> String {
+        match &self.jarvis_block {
+            Some(reason) => format!("Hard block: {}", reason),
+            None => "No hard block; the plan passed the Jarvis safety rules.".to_string(),
+        }
+    }
+
+    fn to_markdown(&self) -> String {
+        let mut md = String::from("# ACSA Execution Report\n\n| Field | Value |\n|---|---|\n");
+        for (field, value) in self.summary_rows() {
+            let _ = writeln!(md, "| {} | {} |", field, value.replace('|', "\\|"));
+        }
+
+        let _ = write!(md, "\n## Input\n\n{}", fenced(&self.user_input));
+        let _ = write!(md, "\n## Jarvis\n\n{}\n", self.jarvis_summary());
+
+        md.push_str("\n## Timeline\n");
+        for (i, step) in self.timeline().iter().enumerate() {
+            let r = step.response;
+            let _ = write!(
+                md,
+                "\n### {}. {} — {}\n\n_{} · {} ms · {} tokens · ${:.4}_\n\n{}",
+                i + 1,
+                role_name(r.role),
+                step.stage,
+                r.timestamp.format("%H:%M:%S"),
+                r.latency_ms,
+                r.tokens,
+                r.cost,
+                fenced(&r.text)
+            );
+        }
+
+        if let Some(audit) = &self.audit_result {
+            let _ = write!(
+                md,
+                "\n## Ultron Audit\n\n- Risk score: **{}/100** ({})\n- Safe: {}\n",
+                audit.risk_score,
+                risk_level(audit.risk_score),
+                if audit.is_safe { "yes" } else { "no" }
+            );
+            for (label, risks) in [
+                ("Legal risks", &audit.legal_risks),
+                ("Physical risks", &audit.physical_risks),
+                ("Ethical risks", &audit.ethical_risks),
+            ] {
+                if !risks.is_empty() {
+                    let _ = writeln!(md, "- {}: {}", label, risks.join("; "));
+                }
+            }
+            if !audit.mitigation.is_empty() {
+                let _ = writeln!(md, "- Mitigation: {}", audit.mitigation);
+            }
+        }
+
+        if let Some(throttle) = self.throttle.as_ref().filter(|t| t.stop) {
+            let _ = write!(md, "\n## Early Stop\n\n{}\n", throttle.reason);
+        }
+
+        if !self.plan_diffs.is_empty() {
+            md.push_str("\n## Plan Changes\n\n");
+            for diff in &self.plan_diffs {
+                md.push_str(&fenced(&diff.render()));
+            }
+        }
+
+        let _ = write!(md, "\n## Final Output\n\n{}", fenced(self.final_output.as_deref().unwrap_or("N/A")));
+        md
+    }
+
+    fn to_html(&self) -> String {
+        let mut body = String::new();
+
+        body.push_str("<table class=\"summary\">");
+        for (field, value) in self.summary_rows() {
+            let _ = write!(body, "<tr><th>{}</th><td>{}</td></tr>", field, html_escape(&value));
+        }
+        body.push_str("</table>\n");
+
+        let _ = write!(body, "<h2>Input</h2>\n<pre>{}</pre>\n", html_escape(&self.user_input));
+
+        let jarvis_class = if self.jarvis_block.is_some() { "block" } else { "pass" };
+        let _ = write!(
+            body,
+            "<h2>Jarvis</h2>\n<p class=\"verdict {}\">{}</p>\n",
+            jarvis_class,
+            html_escape(&self.jarvis_summary())
+        );
+
+        body.push_str("<h2>Timeline</h2>\n<ol class=\"timeline\">\n");
+        for step in self.timeline() {
+            let r = step.response;
+            let _ = writeln!(
+                body,
+                "<li><details open><summary><strong>{}</strong> — {} <span class=\"meta\">{} · {} ms · {} tokens · ${:.4}</span></summary><pre>{}</pre></details></li>",
+                html_escape(&role_name(r.role)),
+                step.stage,
+                r.timestamp.format("%H:%M:%S"),
+                r.latency_ms,
+                r.tokens,
+                r.cost,
+                html_escape(&r.text)
+            );
+        }
+        body.push_str("</ol>\n");
+
+        if let Some(audit) = &self.audit_result {
+            let _ = write!(
+                body,
+                "<h2>Ultron Audit</h2>\n<p><span class=\"risk {}\">Risk {}/100</span> Safe: {}</p>\n<ul>\n",
+                risk_level(audit.risk_score),
+                audit.risk_score,
+                if audit.is_safe { "yes" } else { "no" }
+            );
+            for (label, risks) in [
+                ("Legal risks", &audit.legal_risks),
+                ("Physical risks", &audit.physical_risks),
+                ("Ethical risks", &audit.ethical_risks),
+            ] {
+                if !risks.is_empty() {
+                    let _ = writeln!(body, "<li>{}: {}</li>", label, html_escape(&risks.join("; ")));
+                }
+            }
+            if !audit.mitigation.is_empty() {
+                let _ = writeln!(body, "<li>Mitigation: {}</li>", html_escape(&audit.mitigation));
+            }
+            body.push_str("</ul>\n");
+        }
+
+        if let Some(throttle) = self.throttle.as_ref().filter(|t| t.stop) {
+            let _ = write!(body, "<h2>Early Stop</h2>\n<p>{}</p>\n", html_escape(&throttle.reason));
+        }
+
+        if !self.plan_diffs.is_empty() {
+            body.push_str("<h2>Plan Changes</h2>\n");
+            for diff in &self.plan_diffs {
+                let _ = writeln!(body, "<pre>{}</pre>", html_escape(&diff.render()));
+            }
+        }
+
+        let _ = write!(
+            body,
+            "<h2>Final Output</h2>\n<pre>{}</pre>\n",
+            html_escape(self.final_output.as_deref().unwrap_or("N/A"))
+        );
+
+        format!(
+            "<!DOCTYPE html>\n<html lang=\"en\"><head><meta charset=\"utf-8\">\
+             <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\
+             <title>ACSA Execution Report</title>\n<style>{}</style></head>\n\
+             <body>\n<h1>ACSA Execution Report</h1>\n{}</body></html>\n",
+            REPORT_CSS, body
+        )
+    }
+}
+
+/// HTML 报告样式（内联，不依赖外部资源）
+const REPORT_CSS: &str = "\
+body{font-family:-apple-system,Segoe UI,Helvetica,Arial,sans-serif;max-width:960px;margin:2em auto;padding:0 1em;color:#1f2328}\
+h1{border-bottom:2px solid #d0d7de;padding-bottom:.3em}\
+h2{margin-top:1.6em;border-bottom:1px solid #d0d7de;padding-bottom:.2em}\
+table.summary{border-collapse:collapse}\
+table.summary th{text-align:left;padding:.3em 1.2em .3em 0;color:#57606a}\
+table.summary td{padding:.3em 0}\
+pre{background:#f6f8fa;padding:.8em;border-radius:6px;white-space:pre-wrap;word-break:break-word}\
+ol.timeline{padding-left:1.4em}\
+ol.timeline li{margin:.6em 0}\
+summary{cursor:pointer}\
+.meta{color:#57606a;font-size:.9em;margin-left:.5em}\
+.verdict{padding:.5em .8em;border-radius:6px}\
+.verdict.pass{background:#dafbe1}\
+.verdict.block{background:#ffebe9}\
+.risk{padding:.15em .6em;border-radius:1em;font-weight:600;margin-right:.8em}\
+.risk.low{background:#dafbe1}\
+.risk.medium{background:#fff8c5}\
+.risk.high{background:#ffebe9}";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::types::AuditResult;
+    use chrono::Utc;
+
+    fn sample_log() -> ACSAExecutionLog {
+        let response = |role, text: &str| AgentResponse {
+            role,
+            text: text.to_string(),
+            tokens: 42,
+            cost: 0.01,
+            latency_ms: 120,
+            metadata: Default::default(),
+            timestamp: Utc::now(),
+        };
+        let mut log = ACSAExecutionLog::new("Build <script>alert(1)</script> a ```fenced``` page".to_string());
+        log.moss_plan = Some(response(AgentRole::MOSS, "1. Draft the page"));
+        log.ultron_audit = Some(response(AgentRole::Ultron, "RISK_SCORE: 20"));
+        log.omega_execution = Some(response(AgentRole::Omega, "Done"));
+        log.audit_result = Some(AuditResult {
+            is_safe: true,
+            risk_score: 20,
+            legal_risks: vec!["None".to_string()],
+            physical_risks: Vec::new(),
+            ethical_risks: Vec::new(),
+            mitigation: "Review copy".to_string(),
+            raw_response: String::new(),
+        });
+        log.final_output = Some("Done".to_string());
+        log.iterations = 1;
+        log.total_cost = 0.03;
+        log.complete(true);
+        log
+    }
+
+    #[test]
+    fn test_export_formats() {
+        let log = sample_log();
+
+        let json = log.export(ExportFormat::Json).unwrap();
+        let parsed: ACSAExecutionLog = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.user_input, log.user_input);
+
+        let md = log.export(ExportFormat::Markdown).unwrap();
+        assert!(md.contains("| Risk score | 20/100 (low) |"));
+        assert!(md.contains("### 1. 🧠 MOSS — Plan"));
+        assert!(md.contains("### 3. ⚡ Omega — Execution"));
+        // 输入中含三个反引号时围栏加长
+        assert!(md.contains("````text\nBuild <script>"));
+
+        let html = log.export(ExportFormat::Html).unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("&lt;script&gt;alert(1)&lt;/script&gt;"));
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("<span class=\"risk low\">Risk 20/100</span>"));
+        assert!(!html.contains("http://") && !html.contains("https://"));
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(ExportFormat::from_path(Path::new("report.html")), Some(ExportFormat::Html));
+        assert_eq!(ExportFormat::from_path(Path::new("out/report.MD")), Some(ExportFormat::Markdown));
+        assert_eq!(ExportFormat::from_path(Path::new("log.json")), Some(ExportFormat::Json));
+        assert_eq!(ExportFormat::from_path(Path::new("report.pdf")), None);
+        assert!("xml".parse::<ExportFormat>().is_err());
+    }
+}
@@ -0,0 +1,152 @@
+// Logging - 日志输出格式
+// 默认输出便于阅读的文本日志；JSON 模式每行一个事件，可直接送入 Loki / ELK
+//
+// 核心功能：
+// 1. LogFormat：text / json（CLI `--log-format` 或 ACSA_LOG_FORMAT）
+// 2. JSON 事件附带所在 span 的字段：execution_id、protocol、risk_level（执行 span）与 agent（Agent 调用 span）
+// 3. 日志级别沿用 RUST_LOG
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use tracing::Subscriber;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+/// 选择日志格式的环境变量
+pub const LOG_FORMAT_ENV: &str = "ACSA_LOG_FORMAT";
+
+/// 日志格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl LogFormat {
+    /// 读取 ACSA_LOG_FORMAT（未设置时为 None）
+    pub fn from_env() -> Result<Option<Self>> {
+        match std::env::var(LOG_FORMAT_ENV) {
+            Ok(value) if !value.trim().is_empty() => {
+                value.parse().map(Some).map_err(|e| anyhow!("{}: {}", LOG_FORMAT_ENV, e))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "text" | "pretty" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            other => Err(anyhow!("Unsupported log format '{}' (expected text or json)", other)),
+        }
+    }
+}
+
+/// 初始化全局日志（进程内只能调用一次）
+pub fn init(format: LogFormat) -> Result<()> {
+    init_with_writer(format, std::io::stdout)
+}
+
+/// 同 `init`，日志写入指定 writer（stdout 被协议占用时用 stderr，如 `mcp serve`）
+pub fn init_with_writer<W>(format: LogFormat, writer: W) -> Result<()>
+where
+    W: for<'a> MakeWriter<'a> + Send + Sync + 'static,
+{
+    match format {
+        LogFormat::Text => tracing_subscriber::fmt().with_env_filter(EnvFilter::from_default_env()).with_writer(writer).try_init(),
+        LogFormat::Json => json_subscriber(EnvFilter::from_default_env(), writer).try_init().map_err(Into::into),
+    }
+    .map_err(|e| anyhow!("Failed to initialize logging: {}", e))
+}
+
+/// JSON 日志订阅器：事件字段平铺到顶层，所在 span 的字段放在 `span` / `spans` 中
+pub fn json_subscriber<W>(filter: EnvFilter, writer: W) -> impl Subscriber + Send + Sync
+where
+    W: for<'a> MakeWriter<'a> + Send + Sync + 'static,
+{
+    tracing_subscriber::fmt()
+        .json()
+        .flatten_event(true)
+        .with_current_span(true)
+        .with_span_list(true)
+        .with_env_filter(filter)
+        .with_writer(writer)
+        .finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::protocol::{Protocol, ProtocolConfig};
+    use crate::core::providers::MockProvider;
+    use crate::core::types::{ACSAConfig, AgentRole};
+    use crate::core::ACSARouter;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_parse_log_format() {
+        assert_eq!("JSON".parse::<LogFormat>().unwrap(), LogFormat::Json);
+        assert_eq!(" text ".parse::<LogFormat>().unwrap(), LogFormat::Text);
+        assert!("xml".parse::<LogFormat>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_json_logs_carry_execution_fields() {
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        let _guard = tracing::subscriber::set_default(json_subscriber(EnvFilter::new("debug"), move || writer.clone()));
+
+        let router = ACSARouter::new(
+            Arc::new(MockProvider::new(AgentRole::MOSS)),
+            Arc::new(MockProvider::new(AgentRole::L6)),
+            Arc::new(MockProvider::new(AgentRole::Ultron)),
+            Arc::new(MockProvider::new(AgentRole::Omega)),
+            ACSAConfig {
+                max_iterations: 1,
+                protocol: Some(ProtocolConfig::for_protocol(Protocol::Architect)),
+                ..Default::default()
+            },
+        );
+        router.execute("写一个HTTP服务器".to_string()).await.unwrap();
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<serde_json::Value> = output.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert!(!lines.is_empty());
+        assert!(lines.iter().all(|line| line["message"].is_string() && line["level"].is_string()));
+
+        // 执行内的事件都带执行 span；Agent 调用内的事件另带 agent 字段
+        let execution = lines
+            .iter()
+            .filter_map(|line| line["spans"].as_array())
+            .filter_map(|spans| spans.iter().find(|span| span["name"] == "execution"))
+            .next_back()
+            .unwrap();
+        assert!(execution["execution_id"].as_str().unwrap().starts_with("exec_"));
+        assert_eq!(execution["protocol"], Protocol::Architect.name());
+        assert!(execution["risk_level"].is_u64());
+        assert!(lines.iter().any(|line| line["span"]["agent"] == "MOSS"));
+    }
+}
@@ -5,21 +5,46 @@
 // 1. 代码补全（Completion）
 // 2. 定义跳转（Go to Definition）
 // 3. 查找引用（Find References）
-// 4. 诊断（Diagnostics）
-// 5. 悬停信息（Hover）
-// 6. 代码重构（Refactoring）
+// 4. 诊断（Diagnostics）：Jarvis 阻断词、主权提醒（完全外包决策）、认知清洗命中
+// 5. 悬停信息（Hover）：Prompt 模板的变量与内容、协议说明
+// 6. 代码操作（Code Action）：按认知清洗的重写建议一键替换
 // 7. AI增强建议
+// 8. Prompt 补全：`.prompt` / Markdown 中补全模板名、协议名与 Few-shot 示例片段
+//
+// 注：Prompt / Markdown / 纯文本逐行分析；代码文件只分析行注释
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::info;
 
+use super::cognitive_cleaner::{CleaningRule, CognitiveCleaner};
+use super::jarvis::JarvisCircuitBreaker;
+use super::prompt_manager::{PromptManager, PromptTemplate};
+use super::protocol::Protocol;
+
 /// LSP协议版本
 pub const LSP_VERSION: &str = "3.17";
 
+/// 诊断来源
+const DIAGNOSTIC_SOURCE: &str = "acsa-lsp";
+
+/// 完全外包决策的措辞（主权提醒，按 ASCII 小写匹配）
+const DELEGATION_PHRASES: &[&str] = &[
+    "你决定",
+    "帮我决定",
+    "替我决定",
+    "你看着办",
+    "随便你",
+    "不用问我",
+    "decide for me",
+    "you decide",
+    "whatever you think",
+    "don't ask me",
+];
+
 /// 文档信息
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Document {
@@ -72,6 +97,26 @@ pub struct Position {
     pub character: u32,
 }
 
+/// 文本编辑
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextEdit {
+    pub range: Range,
+    #[serde(rename = "newText")]
+    pub new_text: String,
+}
+
+/// 代码操作（quick fix）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeAction {
+    pub title: String,
+    /// 操作类型（quickfix）
+    pub kind: String,
+    /// 该操作修复的诊断
+    pub diagnostics: Vec<Diagnostic>,
+    /// 文档URI → 编辑
+    pub edit: HashMap<String, Vec<TextEdit>>,
+}
+
 /// 补全项
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompletionItem {
@@ -101,6 +146,7 @@ pub enum CompletionItemKind {
     Property = 10,
     Keyword = 14,
     Snippet = 15,
+    Constant = 21,
 }
 
 /// LSP服务器配置
@@ -134,6 +180,14 @@ pub struct AcsaLspServer {
     documents: Arc<RwLock<HashMap<String, Document>>>,
     /// 诊断缓存
     diagnostics: Arc<RwLock<HashMap<String, Vec<Diagnostic>>>>,
+    /// 代码操作缓存（随诊断一起生成）
+    code_actions: Arc<RwLock<HashMap<String, Vec<CodeAction>>>>,
+    /// 阻断词检测
+    jarvis: Arc<JarvisCircuitBreaker>,
+    /// 重写建议
+    cleaner: Arc<CognitiveCleaner>,
+    /// Prompt 模板库（补全与悬停）
+    prompts: Option<Arc<PromptManager>>,
 }
 
 impl AcsaLspServer {
@@ -147,9 +201,31 @@ impl AcsaLspServer {
             config,
             documents: Arc::new(RwLock::new(HashMap::new())),
             diagnostics: Arc::new(RwLock::new(HashMap::new())),
+            code_actions: Arc::new(RwLock::new(HashMap::new())),
+            jarvis: Arc::new(JarvisCircuitBreaker::new()),
+            cleaner: Arc::new(CognitiveCleaner::new()),
+            prompts: None,
         }
     }
 
+    /// 接入 Prompt 模板库：Prompt 文件中补全模板名与 Few-shot 示例，悬停显示模板变量
+    pub fn with_prompt_manager(mut self, prompts: Arc<PromptManager>) -> Self {
+        self.prompts = Some(prompts);
+        self
+    }
+
+    /// 使用指定的 Jarvis 实例（例如加载了签名规则包的实例）
+    pub fn with_jarvis(mut self, jarvis: Arc<JarvisCircuitBreaker>) -> Self {
+        self.jarvis = jarvis;
+        self
+    }
+
+    /// 使用指定的认知清洗器（例如导入了自定义词典的实例）
+    pub fn with_cleaner(mut self, cleaner: Arc<CognitiveCleaner>) -> Self {
+        self.cleaner = cleaner;
+        self
+    }
+
     /// 启动LSP服务器
     pub async fn start(&self) -> Result<()> {
         info!("🚀 Starting ACSA LSP Server");
@@ -225,6 +301,7 @@ impl AcsaLspServer {
 
         let mut diags = self.diagnostics.write().await;
         diags.remove(uri);
+        self.code_actions.write().await.remove(uri);
 
         info!("🗑️  Closed document: {}", uri);
         Ok(())
@@ -246,6 +323,12 @@ impl AcsaLspServer {
         // 基础补全
         let mut items = self.get_basic_completions(current_line, &doc.language_id);
 
+        // Prompt 文件：模板、协议、Few-shot 示例
+        if is_prompt_document(doc) {
+            let prefix = word_before(current_line, position.character);
+            items.extend(self.get_prompt_completions(&prefix).await);
+        }
+
         // AI增强补全
         if self.config.enable_ai_enhancement {
             let ai_items = self.get_ai_completions(doc, position).await;
@@ -258,6 +341,26 @@ impl AcsaLspServer {
         Ok(items)
     }
 
+    /// 文档的当前诊断
+    pub async fn diagnostics(&self, uri: &str) -> Vec<Diagnostic> {
+        self.diagnostics.read().await.get(uri).cloned().unwrap_or_default()
+    }
+
+    /// 与范围重叠的诊断对应的代码操作
+    pub async fn code_actions(&self, uri: &str, range: Range) -> Vec<CodeAction> {
+        let actions = self.code_actions.read().await;
+        actions
+            .get(uri)
+            .map(|actions| {
+                actions
+                    .iter()
+                    .filter(|action| action.diagnostics.iter().any(|diagnostic| diagnostic.range.overlaps(&range)))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
     /// 跳转到定义
     pub async fn goto_definition(&self, uri: &str, position: Position) -> Result<Option<(String, Range)>> {
         let docs = self.documents.read().await;
@@ -297,7 +400,26 @@ impl AcsaLspServer {
     /// 悬停信息
     pub async fn hover(&self, uri: &str, position: Position) -> Result<Option<String>> {
         let docs = self.documents.read().await;
-        let _doc = docs.get(uri).ok_or_else(|| anyhow::anyhow!("Document not found: {}", uri))?;
+        let doc = docs.get(uri).ok_or_else(|| anyhow::anyhow!("Document not found: {}", uri))?;
+
+        // Prompt 文件：光标处的模板名 / 模板ID / 协议名
+        if is_prompt_document(doc) {
+            let line = doc.content.lines().nth(position.line as usize).unwrap_or_default();
+            let cursor = byte_index(line, position.character);
+            let covers = |needle: &str| {
+                !needle.is_empty() && line.match_indices(needle).any(|(start, _)| start <= cursor && cursor <= start + needle.len())
+            };
+
+            if let Some(prompts) = &self.prompts {
+                let templates = prompts.list_templates().await;
+                if let Some(template) = templates.iter().find(|t| covers(&t.name) || covers(&t.template_id)) {
+                    return Ok(Some(template_documentation(template)));
+                }
+            }
+            if let Some(protocol) = Protocol::all().into_iter().find(|p| covers(&p.name())) {
+                return Ok(Some(format!("**{}** {}\n\n{}", protocol.name(), protocol.display_name(), protocol.philosophy())));
+            }
+        }
 
         // TODO: 实现实际的悬停信息
         // 需要解析代码、获取符号信息
@@ -311,41 +433,117 @@ impl AcsaLspServer {
     /// 运行诊断
     async fn run_diagnostics(&self, document: &Document) -> Result<()> {
         let mut diagnostics = Vec::new();
+        let mut actions = Vec::new();
 
-        // TODO: 实际的诊断逻辑
-        // - 语法检查
-        // - Lint检查
-        // - 类型检查
-
-        // Placeholder: 检查简单的错误
         for (line_num, line) in document.content.lines().enumerate() {
+            let line_num = line_num as u32;
             if line.contains("TODO") {
                 diagnostics.push(Diagnostic {
-                    range: Range {
-                        start: Position {
-                            line: line_num as u32,
-                            character: 0,
-                        },
-                        end: Position {
-                            line: line_num as u32,
-                            character: line.len() as u32,
-                        },
-                    },
+                    range: Range::on_line(line, line_num, 0, line.len()),
                     severity: DiagnosticSeverity::Information,
                     message: "TODO found in code".to_string(),
                     code: Some("TODO".to_string()),
-                    source: Some("acsa-lsp".to_string()),
+                    source: Some(DIAGNOSTIC_SOURCE.to_string()),
                 });
             }
+
+            if let Some(offset) = analyzed_offset(document, line) {
+                self.analyze_text(&document.uri, line, line_num, offset, &mut diagnostics, &mut actions);
+            }
         }
 
         // 缓存诊断
         let mut diags = self.diagnostics.write().await;
         diags.insert(document.uri.clone(), diagnostics);
+        self.code_actions.write().await.insert(document.uri.clone(), actions);
 
         Ok(())
     }
 
+    /// 分析一行中从 `offset` 开始的文本（Prompt 正文或注释）
+    fn analyze_text(
+        &self,
+        uri: &str,
+        line: &str,
+        line_num: u32,
+        offset: usize,
+        diagnostics: &mut Vec<Diagnostic>,
+        actions: &mut Vec<CodeAction>,
+    ) {
+        let text = &line[offset..];
+        if text.trim().is_empty() {
+            return;
+        }
+        let whole = Range::on_line(line, line_num, offset, line.len());
+        let diagnostic = |range: Range, severity: DiagnosticSeverity, code: &str, message: String| Diagnostic {
+            range,
+            severity,
+            message,
+            code: Some(code.to_string()),
+            source: Some(DIAGNOSTIC_SOURCE.to_string()),
+        };
+
+        // Jarvis：硬性阻止为错误，其余风险提示为警告
+        let verdict = self.jarvis.verify_safety(text, "");
+        if !verdict.allowed {
+            let reason = verdict.block_reason.unwrap_or_default();
+            diagnostics.push(diagnostic(whole, DiagnosticSeverity::Error, "jarvis-block", format!("Jarvis would block this: {}", reason)));
+        }
+        for warning in verdict.warnings {
+            diagnostics.push(diagnostic(whole, DiagnosticSeverity::Warning, "jarvis-warning", format!("Jarvis warning: {}", warning)));
+        }
+
+        // 主权提醒：把决定完全交给 AI
+        let lower = text.to_ascii_lowercase();
+        for phrase in DELEGATION_PHRASES {
+            for (start, _) in lower.match_indices(phrase) {
+                let start = offset + start;
+                diagnostics.push(diagnostic(
+                    Range::on_line(line, line_num, start, start + phrase.len()),
+                    DiagnosticSeverity::Warning,
+                    "sovereignty",
+                    format!("Sovereignty: '{}' hands the whole decision to the AI; state your own constraints first", phrase),
+                ));
+            }
+        }
+
+        // 认知清洗：重写建议与会被丢弃的情绪噪音，各附一个代码操作
+        let mut fixes = BTreeSet::new();
+        for chunk in self.cleaner.clean(text).chunks {
+            for rule in chunk.rules {
+                match rule {
+                    CleaningRule::TechnicalRewrite { from, to } => fixes.insert((from, to, true)),
+                    CleaningRule::EmotionalBlacklist { word } => fixes.insert((word, String::new(), false)),
+                    CleaningRule::ComplianceAnchor { .. } => false,
+                };
+            }
+        }
+        for (from, to, rewrite) in fixes {
+            for (start, _) in text.match_indices(from.as_str()) {
+                let start = offset + start;
+                let range = Range::on_line(line, line_num, start, start + from.len());
+                let (found, title) = if rewrite {
+                    (
+                        diagnostic(range, DiagnosticSeverity::Hint, "cleaner-rewrite", format!("Cognitive cleaner rewrites '{}' as '{}'", from, to)),
+                        format!("Rewrite as '{}'", to),
+                    )
+                } else {
+                    (
+                        diagnostic(range, DiagnosticSeverity::Information, "cleaner-noise", format!("Emotional noise '{}' is dropped by the cognitive cleaner", from)),
+                        format!("Remove '{}'", from),
+                    )
+                };
+                actions.push(CodeAction {
+                    title,
+                    kind: "quickfix".to_string(),
+                    diagnostics: vec![found.clone()],
+                    edit: HashMap::from([(uri.to_string(), vec![TextEdit { range, new_text: to.clone() }])]),
+                });
+                diagnostics.push(found);
+            }
+        }
+    }
+
     /// 获取基础补全
     fn get_basic_completions(&self, current_line: &str, language_id: &str) -> Vec<CompletionItem> {
         let mut items = Vec::new();
@@ -389,6 +587,46 @@ impl AcsaLspServer {
         items
     }
 
+    /// Prompt 文件补全：模板（变量展开为片段占位符）、协议名、Few-shot 示例
+    async fn get_prompt_completions(&self, prefix: &str) -> Vec<CompletionItem> {
+        let mut items = Vec::new();
+
+        if let Some(prompts) = &self.prompts {
+            for template in prompts.list_templates().await {
+                items.push(CompletionItem {
+                    label: template.name.clone(),
+                    kind: CompletionItemKind::Snippet,
+                    detail: Some(format!("Prompt template {} (v{})", template.template_id, template.version)),
+                    documentation: Some(template_documentation(&template)),
+                    insert_text: Some(template_snippet(&template)),
+                });
+                for (i, example) in prompts.get_examples(&template.template_id).await.iter().enumerate() {
+                    items.push(CompletionItem {
+                        label: format!("{} example {}", template.name, i + 1),
+                        kind: CompletionItemKind::Snippet,
+                        detail: Some(example.description.clone().unwrap_or_else(|| format!("Few-shot example for {}", template.name))),
+                        documentation: None,
+                        insert_text: Some(example.format(i + 1)),
+                    });
+                }
+            }
+        }
+
+        for protocol in Protocol::all() {
+            items.push(CompletionItem {
+                label: protocol.name(),
+                kind: CompletionItemKind::Constant,
+                detail: Some(format!("{} - {}", protocol.display_name(), protocol.tagline())),
+                documentation: None,
+                insert_text: None,
+            });
+        }
+
+        let prefix = prefix.to_lowercase();
+        items.retain(|item| item.label.to_lowercase().contains(&prefix));
+        items
+    }
+
     /// 获取AI增强补全
     async fn get_ai_completions(&self, _document: &Document, _position: Position) -> Vec<CompletionItem> {
         // TODO: 调用AI API生成智能补全建议
@@ -400,6 +638,88 @@ impl AcsaLspServer {
     }
 }
 
+impl Range {
+    /// 一行内的字节区间（列按 LSP 约定换算为 UTF-16 码元）
+    fn on_line(line: &str, line_num: u32, start: usize, end: usize) -> Self {
+        let column = |byte: usize| line[..byte].encode_utf16().count() as u32;
+        Self {
+            start: Position { line: line_num, character: column(start) },
+            end: Position { line: line_num, character: column(end) },
+        }
+    }
+
+    fn overlaps(&self, other: &Range) -> bool {
+        let key = |position: &Position| (position.line, position.character);
+        key(&self.start) <= key(&other.end) && key(&other.start) <= key(&self.end)
+    }
+}
+
+/// Prompt / Markdown / 纯文本文档
+fn is_prompt_document(document: &Document) -> bool {
+    matches!(document.language_id.as_str(), "prompt" | "markdown" | "plaintext")
+        || [".prompt", ".md", ".txt"].iter().any(|ext| document.uri.ends_with(ext))
+}
+
+/// 需要分析的文本在行内的起始字节：Prompt / Markdown / 纯文本为整行，代码文件为行注释
+fn analyzed_offset(document: &Document, line: &str) -> Option<usize> {
+    if is_prompt_document(document) {
+        return Some(0);
+    }
+    let marker = match document.language_id.as_str() {
+        "python" | "shellscript" | "shell" | "yaml" | "toml" | "ruby" | "perl" | "r" | "dockerfile" => "#",
+        "sql" | "lua" | "haskell" => "--",
+        _ => "//",
+    };
+    line.find(marker).map(|start| start + marker.len())
+}
+
+/// UTF-16 列号对应的字节位置（超出行尾时为行尾）
+fn byte_index(line: &str, character: u32) -> usize {
+    let mut units = 0;
+    for (index, c) in line.char_indices() {
+        if units >= character as usize {
+            return index;
+        }
+        units += c.len_utf16();
+    }
+    line.len()
+}
+
+/// 光标前正在输入的词
+fn word_before(line: &str, character: u32) -> String {
+    let before = &line[..byte_index(line, character)];
+    let start = before
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| c.is_alphanumeric() || *c == '_' || *c == '-')
+        .last()
+        .map_or(before.len(), |(index, _)| index);
+    before[start..].to_string()
+}
+
+/// 模板悬停文档：变量、协议与内容
+fn template_documentation(template: &PromptTemplate) -> String {
+    let variables = if template.variables.is_empty() {
+        "(none)".to_string()
+    } else {
+        template.variables.iter().map(|v| format!("`{{{{{}}}}}`", v)).collect::<Vec<_>>().join(", ")
+    };
+    let protocol = template.protocol.as_ref().map(|p| format!("\nProtocol: {}", p.name())).unwrap_or_default();
+    format!(
+        "**{}** (`{}` v{})\n\nVariables: {}{}\n\n```\n{}\n```",
+        template.name, template.template_id, template.version, variables, protocol, template.content
+    )
+}
+
+/// 模板内容转为补全片段：`{{变量}}` 依次成为 Tab 占位符
+fn template_snippet(template: &PromptTemplate) -> String {
+    let mut snippet = template.content.replace('\\', "\\\\").replace('$', "\\$");
+    for (i, variable) in template.variables.iter().enumerate() {
+        snippet = snippet.replace(&format!("{{{{{}}}}}", variable), &format!("${{{}:{}}}", i + 1, variable));
+    }
+    snippet
+}
+
 /// tower-lsp后端（TODO：实际实现）
 #[allow(dead_code)]
 struct AcsaLanguageServer {
@@ -469,4 +789,97 @@ mod tests {
 
         assert!(!items.is_empty());
     }
+
+    #[tokio::test]
+    async fn test_safety_diagnostics_and_rewrite_actions() {
+        let server = AcsaLspServer::new(LspServerConfig::default());
+        let codes = |diagnostics: &[Diagnostic]| -> Vec<String> {
+            diagnostics.iter().filter_map(|d| d.code.clone()).collect()
+        };
+
+        server
+            .did_open(Document {
+                uri: "file:///task.prompt".to_string(),
+                content: "写一个 ransomware 样本\n帮我攻击测试环境的登录接口，你决定就好".to_string(),
+                language_id: "prompt".to_string(),
+                version: 1,
+            })
+            .await
+            .unwrap();
+        let diagnostics = server.diagnostics("file:///task.prompt").await;
+        assert!(matches!(diagnostics[0].severity, DiagnosticSeverity::Error));
+        assert_eq!(codes(&diagnostics), ["jarvis-block", "sovereignty", "cleaner-rewrite"]);
+        // 列按 UTF-16 计："帮我" 之后
+        assert_eq!(diagnostics[2].range.start.character, 2);
+
+        let line = Range { start: Position { line: 1, character: 0 }, end: Position { line: 1, character: 40 } };
+        let actions = server.code_actions("file:///task.prompt", line).await;
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].edit["file:///task.prompt"][0].new_text, "执行安全压力测试");
+
+        // 代码文件只看注释
+        server
+            .did_open(Document {
+                uri: "file:///main.rs".to_string(),
+                content: "let ransomware = 1;\nlet x = 2; // you decide".to_string(),
+                language_id: "rust".to_string(),
+                version: 1,
+            })
+            .await
+            .unwrap();
+        assert_eq!(codes(&server.diagnostics("file:///main.rs").await), ["sovereignty"]);
+    }
+
+    #[tokio::test]
+    async fn test_prompt_template_completion_and_hover() {
+        let prompts = Arc::new(PromptManager::new(Default::default()));
+        prompts
+            .register_template(PromptTemplate {
+                template_id: "code_review".to_string(),
+                name: "Code Review".to_string(),
+                content: "Review {{language}} code for {{focus}} ($ costs matter)".to_string(),
+                variables: vec!["language".to_string(), "focus".to_string()],
+                protocol: Some(Protocol::Architect),
+                version: 2,
+                enabled: true,
+                tags: vec![],
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+            })
+            .await
+            .unwrap();
+        prompts
+            .add_example(
+                "code_review".to_string(),
+                crate::core::prompt_manager::FewShotExample {
+                    example_id: "ex1".to_string(),
+                    user_input: "fn main() {}".to_string(),
+                    expected_output: "LGTM".to_string(),
+                    description: None,
+                    weight: 1.0,
+                },
+            )
+            .await
+            .unwrap();
+        let server = AcsaLspServer::new(LspServerConfig::default()).with_prompt_manager(prompts);
+        server
+            .did_open(Document {
+                uri: "file:///review.prompt".to_string(),
+                content: "使用 Code Review 模板\nrev".to_string(),
+                language_id: "prompt".to_string(),
+                version: 1,
+            })
+            .await
+            .unwrap();
+
+        // 光标前的 "rev" 过滤：模板、其示例与 REVIEWER_2 协议
+        let items = server.completion("file:///review.prompt", Position { line: 1, character: 3 }).await.unwrap();
+        let labels: Vec<&str> = items.iter().map(|item| item.label.as_str()).collect();
+        assert_eq!(labels, ["Code Review", "Code Review example 1", "REVIEWER_2"]);
+        assert_eq!(items[0].insert_text.as_deref(), Some("Review ${1:language} code for ${2:focus} (\\$ costs matter)"));
+        assert!(items[1].insert_text.as_deref().unwrap().contains("**输出**: LGTM"));
+
+        let hover = server.hover("file:///review.prompt", Position { line: 0, character: 5 }).await.unwrap().unwrap();
+        assert!(hover.contains("`{{language}}`, `{{focus}}`") && hover.contains("Protocol: ARCHITECT"));
+    }
 }
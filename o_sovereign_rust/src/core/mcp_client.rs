@@ -0,0 +1,502 @@
+// MCP Client - 接入外部 MCP 服务器，把其工具提供给 Omega
+// 与 mcp_server.rs 相反方向：ACSA 作为 MCP 客户端启动外部服务器（filesystem / GitHub / 搜索等）
+//
+// 核心功能：
+// 1. 服务器注册表：`./config/mcp_servers.json`，格式与 Claude Desktop 的 `mcpServers` 相同
+// 2. stdio 传输：启动服务器进程，换行分隔的 JSON-RPC 2.0，按请求 id 关联响应
+// 3. 工具发现：initialize 握手后 tools/list（支持分页），工具以 `服务器.工具` 命名
+// 4. Omega 工具调用：执行提示词列出可用工具，Omega 输出 `TOOL_CALL: {...}` 行，
+//    Router 经 Jarvis 校验后调用，把结果交回 Omega 继续执行
+
+use anyhow::{anyhow, bail, Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::{oneshot, Mutex};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use super::mcp_server::{
+    write_frame, JsonRpcError, McpTool, ServerInfo, JSONRPC_INTERNAL_ERROR, JSONRPC_METHOD_NOT_FOUND, JSONRPC_VERSION,
+    MCP_VERSION,
+};
+
+/// 外部 MCP 服务器注册表
+pub const DEFAULT_MCP_SERVERS_PATH: &str = "./config/mcp_servers.json";
+
+/// 单次请求的默认超时
+const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Omega 输出中的工具调用行：`TOOL_CALL: {"tool": "服务器.工具", "arguments": {...}}`
+static TOOL_CALL: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?m)^\s*TOOL_CALL:\s*(\{.*\})\s*$").unwrap());
+
+/// 外部服务器配置（stdio）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpServerConfig {
+    /// 启动命令（如 `npx`）
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// 额外环境变量（如 `GITHUB_TOKEN`）
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    #[serde(default)]
+    pub disabled: bool,
+}
+
+/// 注册表文件：`{"mcpServers": {"名称": {...}}}`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct McpServersFile {
+    #[serde(rename = "mcpServers", default)]
+    pub servers: BTreeMap<String, McpServerConfig>,
+}
+
+impl McpServersFile {
+    /// 读取注册表（文件不存在时为空）
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        match std::fs::read_to_string(path) {
+            Ok(data) => serde_json::from_str(&data).with_context(|| format!("Invalid MCP server registry {:?}", path)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read MCP server registry {:?}", path)),
+        }
+    }
+}
+
+/// 工具调用结果（文本内容按行拼接，非文本内容以占位说明代替）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct McpToolOutput {
+    pub text: String,
+    pub is_error: bool,
+}
+
+type PendingMap = HashMap<u64, oneshot::Sender<std::result::Result<Value, JsonRpcError>>>;
+type SharedWriter = Arc<Mutex<Box<dyn AsyncWrite + Unpin + Send>>>;
+
+/// 一个外部 MCP 服务器的连接
+pub struct McpClient {
+    name: String,
+    writer: SharedWriter,
+    /// 等待响应的请求；连接关闭后为 None
+    pending: Arc<std::sync::Mutex<Option<PendingMap>>>,
+    next_id: AtomicU64,
+    timeout: Duration,
+    server_info: Option<ServerInfo>,
+    reader: JoinHandle<()>,
+    /// stdio 服务器进程（连接释放时终止）
+    _child: Option<Child>,
+}
+
+impl McpClient {
+    /// 启动 stdio 服务器进程并完成 initialize 握手
+    pub async fn spawn(name: impl Into<String>, config: &McpServerConfig) -> Result<Self> {
+        let name = name.into();
+        let mut child = Command::new(&config.command)
+            .args(&config.args)
+            .envs(&config.env)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Failed to start MCP server '{}' ({})", name, config.command))?;
+        let stdin = child.stdin.take().ok_or_else(|| anyhow!("MCP server '{}' has no stdin", name))?;
+        let stdout = child.stdout.take().ok_or_else(|| anyhow!("MCP server '{}' has no stdout", name))?;
+        if let Some(stderr) = child.stderr.take() {
+            // 服务器日志写在 stderr，转入调试日志
+            let server = name.clone();
+            tokio::spawn(async move {
+                let mut lines = BufReader::new(stderr).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    debug!("[mcp:{}] {}", server, line);
+                }
+            });
+        }
+
+        let mut client = Self::start(name, BufReader::new(stdout), Box::new(stdin));
+        client._child = Some(child);
+        client.initialize().await?;
+        Ok(client)
+    }
+
+    /// 在任意字节流上连接（服务器已在运行）并完成 initialize 握手
+    pub async fn connect<R, W>(name: impl Into<String>, reader: R, writer: W) -> Result<Self>
+    where
+        R: AsyncBufRead + Unpin + Send + 'static,
+        W: AsyncWrite + Unpin + Send + 'static,
+    {
+        let mut client = Self::start(name.into(), reader, Box::new(writer));
+        client.initialize().await?;
+        Ok(client)
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    fn start<R>(name: String, reader: R, writer: Box<dyn AsyncWrite + Unpin + Send>) -> Self
+    where
+        R: AsyncBufRead + Unpin + Send + 'static,
+    {
+        let writer: SharedWriter = Arc::new(Mutex::new(writer));
+        let pending = Arc::new(std::sync::Mutex::new(Some(PendingMap::new())));
+        let reader = tokio::spawn(read_loop(name.clone(), reader, writer.clone(), pending.clone()));
+        Self {
+            name,
+            writer,
+            pending,
+            next_id: AtomicU64::new(1),
+            timeout: DEFAULT_REQUEST_TIMEOUT,
+            server_info: None,
+            reader,
+            _child: None,
+        }
+    }
+
+    async fn initialize(&mut self) -> Result<()> {
+        let result = self
+            .request(
+                "initialize",
+                json!({
+                    "protocolVersion": MCP_VERSION,
+                    "capabilities": {},
+                    "clientInfo": { "name": "ACSA", "version": env!("CARGO_PKG_VERSION") },
+                }),
+            )
+            .await?;
+        self.server_info = result.get("serverInfo").cloned().and_then(|info| serde_json::from_value(info).ok());
+        self.notify("notifications/initialized").await?;
+
+        info!(
+            "🔌 Connected to MCP server '{}' ({})",
+            self.name,
+            self.server_info.as_ref().map(|info| format!("{} v{}", info.name, info.version)).unwrap_or_default()
+        );
+        Ok(())
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn server_info(&self) -> Option<&ServerInfo> {
+        self.server_info.as_ref()
+    }
+
+    /// 发送请求并等待对应 id 的响应
+    pub async fn request(&self, method: &str, params: Value) -> Result<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (sender, receiver) = oneshot::channel();
+        match self.pending.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+            Some(pending) => pending.insert(id, sender),
+            None => bail!("MCP server '{}' is disconnected", self.name),
+        };
+
+        let message = json!({ "jsonrpc": JSONRPC_VERSION, "id": id, "method": method, "params": params });
+        if let Err(e) = write_frame(&mut *self.writer.lock().await, &message).await {
+            self.forget(id);
+            return Err(e.context(format!("Failed to send {} to MCP server '{}'", method, self.name)));
+        }
+
+        match tokio::time::timeout(self.timeout, receiver).await {
+            Ok(Ok(Ok(result))) => Ok(result),
+            Ok(Ok(Err(error))) => Err(anyhow!("MCP server '{}' rejected {}: {} ({})", self.name, method, error.message, error.code)),
+            Ok(Err(_)) => Err(anyhow!("MCP server '{}' disconnected during {}", self.name, method)),
+            Err(_) => {
+                self.forget(id);
+                Err(anyhow!("MCP server '{}' did not answer {} within {:?}", self.name, method, self.timeout))
+            }
+        }
+    }
+
+    async fn notify(&self, method: &str) -> Result<()> {
+        let message = json!({ "jsonrpc": JSONRPC_VERSION, "method": method });
+        write_frame(&mut *self.writer.lock().await, &message).await
+    }
+
+    fn forget(&self, id: u64) {
+        if let Some(pending) = self.pending.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+            pending.remove(&id);
+        }
+    }
+
+    /// 列出服务器提供的全部工具（按 nextCursor 翻页）
+    pub async fn list_tools(&self) -> Result<Vec<McpTool>> {
+        let mut tools = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let params = match &cursor {
+                Some(cursor) => json!({ "cursor": cursor }),
+                None => json!({}),
+            };
+            let result = self.request("tools/list", params).await?;
+            let page: Vec<McpTool> = serde_json::from_value(result.get("tools").cloned().unwrap_or_else(|| json!([])))
+                .with_context(|| format!("Invalid tools/list response from MCP server '{}'", self.name))?;
+            tools.extend(page);
+
+            match result.get("nextCursor").and_then(Value::as_str) {
+                Some(next) if cursor.as_deref() != Some(next) => cursor = Some(next.to_string()),
+                _ => break,
+            }
+        }
+        Ok(tools)
+    }
+
+    /// 调用工具
+    pub async fn call_tool(&self, tool: &str, arguments: Value) -> Result<McpToolOutput> {
+        let arguments = if arguments.is_null() { json!({}) } else { arguments };
+        let result = self.request("tools/call", json!({ "name": tool, "arguments": arguments })).await?;
+
+        let text = result
+            .get("content")
+            .and_then(Value::as_array)
+            .map(|items| {
+                items
+                    .iter()
+                    .map(|item| match item.get("type").and_then(Value::as_str) {
+                        Some("text") => item.get("text").and_then(Value::as_str).unwrap_or_default().to_string(),
+                        Some(other) => format!("[{} content]", other),
+                        None => String::new(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+            .unwrap_or_default();
+        Ok(McpToolOutput {
+            text,
+            is_error: result.get("isError").and_then(Value::as_bool).unwrap_or(false),
+        })
+    }
+}
+
+impl Drop for McpClient {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+/// 读取服务器消息：响应交给等待中的请求，服务器发起的请求就地回复
+async fn read_loop<R>(name: String, reader: R, writer: SharedWriter, pending: Arc<std::sync::Mutex<Option<PendingMap>>>)
+where
+    R: AsyncBufRead + Unpin,
+{
+    let mut lines = reader.lines();
+    loop {
+        let line = match lines.next_line().await {
+            Ok(Some(line)) => line,
+            Ok(None) => break,
+            Err(e) => {
+                warn!("⚠️  MCP server '{}' read failed: {}", name, e);
+                break;
+            }
+        };
+        let Ok(message) = serde_json::from_str::<Value>(&line) else {
+            debug!("[mcp:{}] ignoring non-JSON output: {}", name, line);
+            continue;
+        };
+
+        match (message.get("id").cloned(), message.get("method").and_then(Value::as_str)) {
+            (Some(id), Some(method)) => {
+                let reply = if method == "ping" {
+                    json!({ "jsonrpc": JSONRPC_VERSION, "id": id, "result": {} })
+                } else {
+                    let error = JsonRpcError::new(JSONRPC_METHOD_NOT_FOUND, format!("Method not found: {}", method));
+                    json!({ "jsonrpc": JSONRPC_VERSION, "id": id, "error": error })
+                };
+                if let Err(e) = write_frame(&mut *writer.lock().await, &reply).await {
+                    warn!("⚠️  Failed to answer {} from MCP server '{}': {}", method, name, e);
+                }
+            }
+            (None, Some(method)) => debug!("📭 MCP server '{}' notification: {}", name, method),
+            (Some(id), None) => {
+                let sender = id
+                    .as_u64()
+                    .and_then(|id| pending.lock().unwrap_or_else(|e| e.into_inner()).as_mut()?.remove(&id));
+                if let Some(sender) = sender {
+                    let outcome = match message.get("error") {
+                        Some(error) => Err(serde_json::from_value(error.clone())
+                            .unwrap_or_else(|_| JsonRpcError::new(JSONRPC_INTERNAL_ERROR, error.to_string()))),
+                        None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+                    };
+                    // 请求方已超时放弃时忽略
+                    let _ = sender.send(outcome);
+                }
+            }
+            (None, None) => debug!("[mcp:{}] ignoring message without id or method", name),
+        }
+    }
+
+    // 连接关闭：丢弃等待中的请求，它们立即以断开失败
+    pending.lock().unwrap_or_else(|e| e.into_inner()).take();
+    info!("🔌 MCP server '{}' disconnected", name);
+}
+
+/// 发现的外部工具
+#[derive(Debug, Clone)]
+pub struct DiscoveredTool {
+    pub server: String,
+    pub tool: McpTool,
+}
+
+impl DiscoveredTool {
+    /// Omega 调用时使用的名称：`服务器.工具`
+    pub fn qualified_name(&self) -> String {
+        format!("{}.{}", self.server, self.tool.name)
+    }
+}
+
+/// 已连接的外部服务器与其工具
+#[derive(Default)]
+pub struct McpClientRegistry {
+    clients: HashMap<String, Arc<McpClient>>,
+    tools: Vec<DiscoveredTool>,
+}
+
+impl McpClientRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 启动注册表中所有未禁用的服务器；启动或工具发现失败的服务器跳过并告警
+    pub async fn connect_all(servers: &McpServersFile) -> Self {
+        let mut registry = Self::new();
+        for (name, config) in servers.servers.iter().filter(|(_, config)| !config.disabled) {
+            let connected = match McpClient::spawn(name.clone(), config).await {
+                Ok(client) => registry.add_client(client).await,
+                Err(e) => Err(e),
+            };
+            match connected {
+                Ok(count) => info!("🧰 MCP server '{}': {} tools", name, count),
+                Err(e) => warn!("⚠️  Skipping MCP server '{}': {:#}", name, e),
+            }
+        }
+        registry
+    }
+
+    /// 接入已连接的服务器并发现其工具，返回工具数
+    pub async fn add_client(&mut self, client: McpClient) -> Result<usize> {
+        let server = client.name().to_string();
+        if self.clients.contains_key(&server) {
+            bail!("MCP server '{}' is already connected", server);
+        }
+        let tools = client.list_tools().await?;
+        let count = tools.len();
+        self.tools.extend(tools.into_iter().map(|tool| DiscoveredTool { server: server.clone(), tool }));
+        self.clients.insert(server, Arc::new(client));
+        Ok(count)
+    }
+
+    pub fn tools(&self) -> &[DiscoveredTool] {
+        &self.tools
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tools.is_empty()
+    }
+
+    /// 按 `服务器.工具` 调用
+    pub async fn call(&self, qualified_name: &str, arguments: Value) -> Result<McpToolOutput> {
+        let tool = self
+            .tools
+            .iter()
+            .find(|tool| tool.qualified_name() == qualified_name)
+            .ok_or_else(|| anyhow!("Unknown tool '{}'", qualified_name))?;
+        let client = self
+            .clients
+            .get(&tool.server)
+            .ok_or_else(|| anyhow!("MCP server '{}' is not connected", tool.server))?;
+        client.call_tool(&tool.tool.name, arguments).await
+    }
+
+    /// 追加到 Omega 执行提示词的工具清单与调用约定
+    pub fn tool_instructions(&self) -> String {
+        let catalog: Vec<String> = self
+            .tools
+            .iter()
+            .map(|tool| format!("- {}: {}\n  Arguments schema: {}", tool.qualified_name(), tool.tool.description, tool.tool.input_schema))
+            .collect();
+        format!(
+            "Available Tools:\n{}\n\n\
+             To use tools, reply with one line per call and nothing else:\n\
+             TOOL_CALL: {{\"tool\": \"<tool name>\", \"arguments\": {{...}}}}\n\
+             The results will be sent back to you. Once you have what you need, give the final answer without TOOL_CALL lines.",
+            catalog.join("\n")
+        )
+    }
+}
+
+/// Omega 请求的一次工具调用
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ToolCallRequest {
+    pub tool: String,
+    #[serde(default)]
+    pub arguments: Value,
+}
+
+/// 解析回复中的 `TOOL_CALL:` 行（JSON 无效的行忽略）
+pub fn parse_tool_calls(text: &str) -> Vec<ToolCallRequest> {
+    TOOL_CALL
+        .captures_iter(text)
+        .filter_map(|captures| match serde_json::from_str(&captures[1]) {
+            Ok(call) => Some(call),
+            Err(e) => {
+                warn!("⚠️  Ignoring malformed tool call '{}': {}", &captures[1], e);
+                None
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::mcp_server::create_acsa_mcp_server;
+
+    /// 通过内存管道连接一个 ACSA MCP 服务器
+    async fn connect_to_acsa(name: &str) -> McpClient {
+        let server = create_acsa_mcp_server().await;
+        let (client_io, server_io) = tokio::io::duplex(64 * 1024);
+        let (server_reader, server_writer) = tokio::io::split(server_io);
+        tokio::spawn(async move { server.serve(BufReader::new(server_reader), server_writer).await });
+        let (client_reader, client_writer) = tokio::io::split(client_io);
+        McpClient::connect(name, BufReader::new(client_reader), client_writer).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_client_discovers_and_calls_tools() {
+        let client = connect_to_acsa("acsa").await;
+        assert_eq!(client.server_info().unwrap().name, "ACSA");
+
+        let mut registry = McpClientRegistry::new();
+        assert!(registry.add_client(client).await.unwrap() >= 3);
+        assert!(registry.tools().iter().any(|tool| tool.qualified_name() == "acsa.acsa_switch_protocol"));
+        assert!(registry.tool_instructions().contains("acsa.acsa_task_tracker"));
+
+        let output = registry.call("acsa.acsa_switch_protocol", json!({ "protocol": "aegis" })).await.unwrap();
+        assert!(!output.is_error);
+        assert!(output.text.contains("AEGIS"));
+        // 工具自身失败：isError；工具不存在：JSON-RPC 错误
+        assert!(registry.call("acsa.acsa_switch_protocol", json!({})).await.unwrap().is_error);
+        assert!(registry.call("acsa.missing", json!({})).await.is_err());
+    }
+
+    #[test]
+    fn test_parse_tool_calls() {
+        let reply = "Reading the file first.\n\
+                     TOOL_CALL: {\"tool\": \"fs.read_file\", \"arguments\": {\"path\": \"README.md\"}}\n\
+                     TOOL_CALL: {not json}\n  TOOL_CALL: {\"tool\": \"search.web\"}";
+        let calls = parse_tool_calls(reply);
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].arguments["path"], "README.md");
+        assert_eq!(calls[1], ToolCallRequest { tool: "search.web".to_string(), arguments: Value::Null });
+    }
+}
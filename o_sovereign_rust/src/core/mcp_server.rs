@@ -1,23 +1,82 @@
 // MCP Server - Model Context Protocol Implementation
 // Anthropic MCP标准实现 - 让ACSA作为MCP服务器
 // Standardized integration with external tools and data sources
+// 传输：stdio 上换行分隔的 JSON-RPC 2.0（`o-sovereign mcp serve`）
 
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
-use serde_json::{json, Value};
+use serde_json::{json, Map, Value};
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::sync::{Mutex, RwLock};
 use tracing::{debug, info, warn};
 
+use super::audit_log::{AuditLogConfig, AuditLogger, AuditQuery};
+use super::behavior_monitor::{BehaviorMonitor, BehaviorMonitorConfig};
+use super::config_manager::{ConfigManager, ConfigManagerConfig};
+use super::protocol::ProtocolManager;
+use super::router::ACSARouter;
+use super::sovereignty::{UsageTracker, SOVEREIGNTY};
+use super::types::PipelineEvent;
+
 /// MCP协议版本
 pub const MCP_VERSION: &str = "2025-11-25";
 
+/// JSON-RPC 版本
+pub const JSONRPC_VERSION: &str = "2.0";
+
+/// JSON-RPC 2.0 标准错误码
+pub const JSONRPC_PARSE_ERROR: i64 = -32700;
+pub const JSONRPC_INVALID_REQUEST: i64 = -32600;
+pub const JSONRPC_METHOD_NOT_FOUND: i64 = -32601;
+pub const JSONRPC_INVALID_PARAMS: i64 = -32602;
+pub const JSONRPC_INTERNAL_ERROR: i64 = -32603;
+/// MCP 扩展错误码：资源不存在
+pub const MCP_RESOURCE_NOT_FOUND: i64 = -32002;
+
+/// `McpRequest` 覆盖的方法（其余方法返回 Method not found）
+const MCP_METHODS: &[&str] = &[
+    "initialize",
+    "tools/list",
+    "tools/call",
+    "resources/list",
+    "resources/read",
+    "prompts/list",
+    "prompts/get",
+];
+
+/// JSON-RPC 错误对象
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonRpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl JsonRpcError {
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self { code, message: message.into(), data: None }
+    }
+}
+
+impl std::fmt::Display for JsonRpcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for JsonRpcError {}
+
 /// MCP工具定义
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct McpTool {
     pub name: String,
+    #[serde(default)]
     pub description: String,
+    #[serde(rename = "inputSchema")]
     pub input_schema: Value,
 }
 
@@ -27,6 +86,7 @@ pub struct McpResource {
     pub uri: String,
     pub name: String,
     pub description: String,
+    #[serde(rename = "mimeType")]
     pub mime_type: String,
 }
 
@@ -173,7 +233,9 @@ pub struct ResourceContent {
     pub uri: String,
     #[serde(rename = "mimeType")]
     pub mime_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub blob: Option<String>, // base64 encoded
 }
 
@@ -191,18 +253,50 @@ pub struct PromptContent {
 }
 
 /// MCP工具处理器trait
+#[async_trait::async_trait]
 pub trait McpToolHandler: Send + Sync {
-    fn handle(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>>;
+    async fn handle(&self, arguments: Option<Value>, progress: &McpProgress) -> Result<Vec<ToolContent>>;
+}
+
+/// MCP资源读取trait：返回资源当前的文本内容
+#[async_trait::async_trait]
+pub trait McpResourceHandler: Send + Sync {
+    async fn read(&self) -> Result<String>;
 }
 
+/// 工具调用的进度上报：客户端在 `params._meta.progressToken` 中提供令牌时发送 `notifications/progress`
+#[derive(Debug, Clone, Default)]
+pub struct McpProgress {
+    token: Option<Value>,
+    sender: Option<UnboundedSender<Value>>,
+}
+
+impl McpProgress {
+    /// 上报进度（progress 需单调递增，total 未知时为 None）；客户端未请求进度时忽略
+    pub fn report(&self, progress: u64, total: Option<u64>, message: impl Into<String>) {
+        let (Some(token), Some(sender)) = (&self.token, &self.sender) else {
+            return;
+        };
+        let mut params = json!({ "progressToken": token, "progress": progress, "message": message.into() });
+        if let Some(total) = total {
+            params["total"] = json!(total);
+        }
+        // 传输已关闭时忽略
+        let _ = sender.send(json!({ "jsonrpc": JSONRPC_VERSION, "method": "notifications/progress", "params": params }));
+    }
+}
+
+type ToolRegistry = HashMap<String, (McpTool, Arc<dyn McpToolHandler>)>;
+type ResourceRegistry = HashMap<String, (McpResource, Arc<dyn McpResourceHandler>)>;
+
 /// ACSA MCP服务器
 pub struct AcsaMcpServer {
     /// 服务器信息
     server_info: ServerInfo,
     /// 已注册的工具
-    tools: Arc<RwLock<HashMap<String, (McpTool, Box<dyn McpToolHandler>)>>>,
+    tools: Arc<RwLock<ToolRegistry>>,
     /// 已注册的资源
-    resources: Arc<RwLock<HashMap<String, McpResource>>>,
+    resources: Arc<RwLock<ResourceRegistry>>,
     /// 已注册的提示模板
     prompts: Arc<RwLock<HashMap<String, McpPrompt>>>,
 }
@@ -229,15 +323,15 @@ impl AcsaMcpServer {
         self.tools
             .write()
             .await
-            .insert(name.clone(), (tool, Box::new(handler)));
+            .insert(name.clone(), (tool, Arc::new(handler)));
 
         info!("🔧 Registered MCP tool: {}", name);
     }
 
     /// 注册MCP资源
-    pub async fn register_resource(&self, resource: McpResource) {
+    pub async fn register_resource<H: McpResourceHandler + 'static>(&self, resource: McpResource, handler: H) {
         let uri = resource.uri.clone();
-        self.resources.write().await.insert(uri.clone(), resource);
+        self.resources.write().await.insert(uri.clone(), (resource, Arc::new(handler)));
 
         info!("📦 Registered MCP resource: {}", uri);
     }
@@ -252,6 +346,11 @@ impl AcsaMcpServer {
 
     /// 处理MCP请求
     pub async fn handle_request(&self, request: McpRequest) -> Result<McpResponse> {
+        self.handle_request_with_progress(request, &McpProgress::default()).await
+    }
+
+    /// 处理MCP请求，工具调用期间通过 `progress` 上报进度
+    pub async fn handle_request_with_progress(&self, request: McpRequest, progress: &McpProgress) -> Result<McpResponse> {
         debug!("📨 MCP Request: {:?}", request);
 
         match request {
@@ -288,10 +387,11 @@ impl AcsaMcpServer {
             }
 
             McpRequest::ToolsCall { name, arguments } => {
-                let tools = self.tools.read().await;
+                // 不在执行期间持有读锁（acsa_execute 可能运行较久）
+                let handler = self.tools.read().await.get(&name).map(|(_, handler)| handler.clone());
 
-                if let Some((_, handler)) = tools.get(&name) {
-                    match handler.handle(arguments) {
+                if let Some(handler) = handler {
+                    match handler.handle(arguments, progress).await {
                         Ok(content) => Ok(McpResponse::ToolsCallResult {
                             content,
                             is_error: Some(false),
@@ -308,13 +408,13 @@ impl AcsaMcpServer {
                         }
                     }
                 } else {
-                    Err(anyhow!("Tool not found: {}", name))
+                    Err(JsonRpcError::new(JSONRPC_INVALID_PARAMS, format!("Tool not found: {}", name)).into())
                 }
             }
 
             McpRequest::ResourcesList => {
                 let resources = self.resources.read().await;
-                let resource_list: Vec<McpResource> = resources.values().cloned().collect();
+                let resource_list: Vec<McpResource> = resources.values().map(|(resource, _)| resource.clone()).collect();
 
                 Ok(McpResponse::ResourcesList {
                     resources: resource_list,
@@ -322,21 +422,20 @@ impl AcsaMcpServer {
             }
 
             McpRequest::ResourcesRead { uri } => {
-                let resources = self.resources.read().await;
+                let resource = self.resources.read().await.get(&uri).cloned();
 
-                if let Some(resource) = resources.get(&uri) {
-                    // 这里应该实际读取资源内容
-                    // 简化实现，返回占位内容
+                if let Some((resource, handler)) = resource {
+                    let text = handler.read().await?;
                     Ok(McpResponse::ResourcesRead {
                         contents: vec![ResourceContent {
-                            uri: uri.clone(),
-                            mime_type: resource.mime_type.clone(),
-                            text: Some(format!("Content of {}", uri)),
+                            uri,
+                            mime_type: resource.mime_type,
+                            text: Some(text),
                             blob: None,
                         }],
                     })
                 } else {
-                    Err(anyhow!("Resource not found: {}", uri))
+                    Err(JsonRpcError::new(MCP_RESOURCE_NOT_FOUND, format!("Resource not found: {}", uri)).into())
                 }
             }
 
@@ -367,33 +466,209 @@ impl AcsaMcpServer {
                         messages,
                     })
                 } else {
-                    Err(anyhow!("Prompt not found: {}", name))
+                    Err(JsonRpcError::new(JSONRPC_INVALID_PARAMS, format!("Prompt not found: {}", name)).into())
+                }
+            }
+        }
+    }
+
+    /// 通过 stdio 提供服务，直到客户端关闭 stdin
+    pub async fn serve_stdio(&self) -> Result<()> {
+        info!("🔌 MCP Server listening on stdio");
+        self.serve(BufReader::new(tokio::io::stdin()), tokio::io::stdout()).await
+    }
+
+    /// 在任意字节流上提供服务：每行一条 JSON-RPC 消息，响应按行写回
+    pub async fn serve<R, W>(&self, reader: R, mut writer: W) -> Result<()>
+    where
+        R: AsyncBufRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        let (sender, mut notifications) = mpsc::unbounded_channel();
+        let mut lines = reader.lines();
+        while let Some(line) = lines.next_line().await? {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            // 处理请求期间把进度通知实时写出，响应在所有通知之后
+            let handling = self.process_message(line, Some(&sender));
+            tokio::pin!(handling);
+            let response = loop {
+                tokio::select! {
+                    response = &mut handling => break response,
+                    Some(notification) = notifications.recv() => write_frame(&mut writer, &notification).await?,
                 }
+            };
+            while let Ok(notification) = notifications.try_recv() {
+                write_frame(&mut writer, &notification).await?;
+            }
+            if let Some(response) = response {
+                write_frame(&mut writer, &response).await?;
+            }
+        }
+
+        info!("🔌 MCP client disconnected");
+        Ok(())
+    }
+
+    /// 处理一条 JSON-RPC 消息；通知与客户端发来的响应不需要回复，返回 None
+    pub async fn handle_message(&self, line: &str) -> Option<Value> {
+        self.process_message(line, None).await
+    }
+
+    /// `notifications` 为进度通知的出口（仅 `serve` 提供）
+    async fn process_message(&self, line: &str, notifications: Option<&UnboundedSender<Value>>) -> Option<Value> {
+        let message: Value = match serde_json::from_str(line) {
+            Ok(message) => message,
+            Err(e) => {
+                return Some(error_response(
+                    Value::Null,
+                    JsonRpcError::new(JSONRPC_PARSE_ERROR, format!("Parse error: {}", e)),
+                ))
             }
+        };
+        let Some(object) = message.as_object() else {
+            let reason = if message.is_array() { "Batch requests are not supported" } else { "Request must be a JSON object" };
+            return Some(error_response(Value::Null, JsonRpcError::new(JSONRPC_INVALID_REQUEST, reason)));
+        };
+
+        let id = object.get("id").cloned();
+        if object.get("jsonrpc").and_then(Value::as_str) != Some(JSONRPC_VERSION) {
+            return Some(error_response(
+                id.unwrap_or(Value::Null),
+                JsonRpcError::new(JSONRPC_INVALID_REQUEST, "jsonrpc must be \"2.0\""),
+            ));
+        }
+        let Some(method) = object.get("method").and_then(Value::as_str) else {
+            if object.contains_key("result") || object.contains_key("error") {
+                debug!("📭 Ignoring JSON-RPC response from client: {:?}", id);
+                return None;
+            }
+            return Some(error_response(
+                id.unwrap_or(Value::Null),
+                JsonRpcError::new(JSONRPC_INVALID_REQUEST, "Missing method"),
+            ));
+        };
+        let params = object.get("params").cloned().unwrap_or(Value::Null);
+
+        let Some(id) = id else {
+            match method {
+                "notifications/initialized" => info!("🤝 MCP client initialized"),
+                _ => debug!("📭 MCP notification: {}", method),
+            }
+            return None;
+        };
+
+        Some(match self.dispatch(method, params, notifications).await {
+            Ok(result) => json!({ "jsonrpc": JSONRPC_VERSION, "id": id, "result": result }),
+            Err(error) => error_response(id, error),
+        })
+    }
+
+    /// 把 JSON-RPC 方法与参数转成 `McpRequest` 执行
+    async fn dispatch(
+        &self,
+        method: &str,
+        params: Value,
+        notifications: Option<&UnboundedSender<Value>>,
+    ) -> std::result::Result<Value, JsonRpcError> {
+        if method == "ping" {
+            return Ok(json!({}));
         }
+        if !MCP_METHODS.contains(&method) {
+            return Err(JsonRpcError::new(JSONRPC_METHOD_NOT_FOUND, format!("Method not found: {}", method)));
+        }
+
+        let mut fields = match params {
+            Value::Object(fields) => fields,
+            Value::Null => Map::new(),
+            _ => return Err(JsonRpcError::new(JSONRPC_INVALID_PARAMS, "params must be an object")),
+        };
+        let progress = McpProgress {
+            token: fields.get("_meta").and_then(|meta| meta.get("progressToken")).cloned(),
+            sender: notifications.cloned(),
+        };
+        fields.insert("method".to_string(), Value::String(method.to_string()));
+        let request: McpRequest = serde_json::from_value(Value::Object(fields))
+            .map_err(|e| JsonRpcError::new(JSONRPC_INVALID_PARAMS, format!("Invalid params: {}", e)))?;
+
+        // 找不到工具/资源/提示时带有规范错误码，其余（如资源读取失败）为内部错误
+        let response = self.handle_request_with_progress(request, &progress).await.map_err(|e| match e.downcast::<JsonRpcError>() {
+            Ok(error) => error,
+            Err(e) => JsonRpcError::new(JSONRPC_INTERNAL_ERROR, format!("{:#}", e)),
+        })?;
+        serde_json::to_value(response).map_err(|e| JsonRpcError::new(JSONRPC_INTERNAL_ERROR, e.to_string()))
     }
 }
 
-/// ACSA预置工具处理器
-pub struct AcsaProtocolSwitchHandler;
+fn error_response(id: Value, error: JsonRpcError) -> Value {
+    json!({ "jsonrpc": JSONRPC_VERSION, "id": id, "error": error })
+}
+
+/// 写出一条消息（serde_json 会转义字符串中的换行，一条消息始终只占一行）
+pub(crate) async fn write_frame<W: AsyncWrite + Unpin>(writer: &mut W, message: &Value) -> Result<()> {
+    let mut frame = serde_json::to_string(message)?;
+    frame.push('\n');
+    writer.write_all(frame.as_bytes()).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// ACSA预置工具处理器：切换协议（内置或自定义），切换前按部署环境检查 `protocol.<name>.enabled`
+pub struct AcsaProtocolSwitchHandler {
+    protocols: Arc<std::sync::RwLock<ProtocolManager>>,
+    config: Arc<ConfigManager>,
+}
+
+impl AcsaProtocolSwitchHandler {
+    pub fn new(protocols: Arc<std::sync::RwLock<ProtocolManager>>, config: Arc<ConfigManager>) -> Self {
+        Self { protocols, config }
+    }
+}
 
+#[async_trait::async_trait]
 impl McpToolHandler for AcsaProtocolSwitchHandler {
-    fn handle(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>> {
+    async fn handle(&self, arguments: Option<Value>, _progress: &McpProgress) -> Result<Vec<ToolContent>> {
         let protocol_name = arguments
             .and_then(|v| v.get("protocol").and_then(|p| p.as_str().map(String::from)))
             .ok_or_else(|| anyhow!("Missing protocol argument"))?;
 
+        let protocol = {
+            let protocols = self.protocols.read().unwrap_or_else(|e| e.into_inner());
+            protocols.resolve(&protocol_name).ok_or_else(|| {
+                let known: Vec<_> = protocols.available().iter().map(|p| p.name().to_lowercase()).collect();
+                anyhow!("Unknown protocol '{}' (available: {})", protocol_name, known.join(", "))
+            })?
+        };
+        // 与 HTTP / CLI 选择协议相同的守卫（生产环境未放行时拒绝 Ghost）
+        self.config.check_protocol(&protocol.name()).await?;
+
+        let mut protocols = self.protocols.write().unwrap_or_else(|e| e.into_inner());
+        protocols.switch_protocol(protocol.clone());
+        let config = protocols.current_config();
+
         Ok(vec![ToolContent {
             content_type: "text".to_string(),
-            text: format!("✅ Switched to protocol: {}", protocol_name),
+            text: format!(
+                "✅ Switched to protocol: {} (temperature {:.2}, MOSS {:.0}% / L6 {:.0}% / Ultron {:.0}% / Omega {:.0}%)",
+                protocol.name(),
+                config.temperature,
+                config.agent_weights.moss * 100.0,
+                config.agent_weights.l6 * 100.0,
+                config.agent_weights.ultron * 100.0,
+                config.agent_weights.omega * 100.0
+            ),
         }])
     }
 }
 
 pub struct AcsaTaskTrackerHandler;
 
+#[async_trait::async_trait]
 impl McpToolHandler for AcsaTaskTrackerHandler {
-    fn handle(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>> {
+    async fn handle(&self, arguments: Option<Value>, _progress: &McpProgress) -> Result<Vec<ToolContent>> {
         let action = arguments
             .and_then(|v| v.get("action").and_then(|a| a.as_str().map(String::from)))
             .ok_or_else(|| anyhow!("Missing action argument"))?;
@@ -414,8 +689,9 @@ impl McpToolHandler for AcsaTaskTrackerHandler {
 
 pub struct AcsaBehaviorAnalysisHandler;
 
+#[async_trait::async_trait]
 impl McpToolHandler for AcsaBehaviorAnalysisHandler {
-    fn handle(&self, _arguments: Option<Value>) -> Result<Vec<ToolContent>> {
+    async fn handle(&self, _arguments: Option<Value>, _progress: &McpProgress) -> Result<Vec<ToolContent>> {
         Ok(vec![ToolContent {
             content_type: "text".to_string(),
             text: "📊 Behavior Analysis:\n  - Patterns detected: 5\n  - Confidence: 85%\n  - Auto-takeover ready: Yes"
@@ -424,8 +700,274 @@ impl McpToolHandler for AcsaBehaviorAnalysisHandler {
     }
 }
 
-/// 创建ACSA MCP服务器并注册默认工具
+/// ACSA预置工具处理器：执行完整的 MOSS → L6 → Ultron → Omega 链路，按阶段上报进度
+pub struct AcsaExecuteHandler {
+    router: ACSARouter,
+    /// Router 的进度事件；同一时间只执行一个调用，事件即属于当前调用
+    events: Mutex<UnboundedReceiver<PipelineEvent>>,
+}
+
+impl AcsaExecuteHandler {
+    pub fn new(router: ACSARouter) -> Self {
+        let (sender, events) = mpsc::unbounded_channel();
+        Self {
+            router: router.with_progress(sender),
+            events: Mutex::new(events),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl McpToolHandler for AcsaExecuteHandler {
+    async fn handle(&self, arguments: Option<Value>, progress: &McpProgress) -> Result<Vec<ToolContent>> {
+        let input = arguments
+            .and_then(|v| v.get("input").and_then(|i| i.as_str().map(String::from)))
+            .filter(|input| !input.trim().is_empty())
+            .ok_or_else(|| anyhow!("Missing input argument"))?;
+
+        let mut events = self.events.lock().await;
+        // 丢弃上一次调用残留的事件
+        while events.try_recv().is_ok() {}
+
+        let execution = self.router.execute(input);
+        tokio::pin!(execution);
+        let mut step = 0;
+        let log = loop {
+            tokio::select! {
+                log = &mut execution => break log?,
+                Some(event) = events.recv() => {
+                    step += 1;
+                    progress.report(step, None, describe_event(&event));
+                }
+            }
+        };
+        while let Ok(event) = events.try_recv() {
+            step += 1;
+            progress.report(step, None, describe_event(&event));
+        }
+
+        let risk_score = log.audit_result.as_ref().map(|audit| audit.risk_score);
+        if !log.success {
+            let reason = match (&log.jarvis_block, risk_score) {
+                (Some(reason), _) => format!("blocked by Jarvis: {}", reason),
+                (None, Some(score)) => format!("rejected by Ultron (risk score {}/100)", score),
+                (None, None) => "no output produced".to_string(),
+            };
+            return Err(anyhow!("Execution failed after {} iteration(s): {}", log.iterations, reason));
+        }
+
+        let summary = json!({
+            "risk_score": risk_score,
+            "total_cost": log.total_cost,
+            "iterations": log.iterations,
+            "total_time_ms": log.total_time_ms,
+        });
+        Ok(vec![
+            ToolContent {
+                content_type: "text".to_string(),
+                text: log.final_output.unwrap_or_default(),
+            },
+            ToolContent {
+                content_type: "text".to_string(),
+                text: summary.to_string(),
+            },
+        ])
+    }
+}
+
+fn describe_event(event: &PipelineEvent) -> String {
+    match event {
+        PipelineEvent::Started { .. } => "Execution started".to_string(),
+        PipelineEvent::StageStarted { role } => format!("{} started", role.as_str()),
+        PipelineEvent::StageFinished { role, success, latency_ms, .. } => {
+            format!("{} {} in {}ms", role.as_str(), if *success { "finished" } else { "failed" }, latency_ms)
+        }
+        PipelineEvent::Verdict { context, verdict } => {
+            format!("Jarvis {} {} (risk level {}/10)", if verdict.allowed { "allowed" } else { "blocked" }, context, verdict.risk_level)
+        }
+        PipelineEvent::Audit { iteration, risk_score, approved, .. } => format!(
+            "Ultron audit #{}: risk score {}/100, {}",
+            iteration,
+            risk_score,
+            if *approved { "approved" } else { "replanning" }
+        ),
+        PipelineEvent::Completed { success, total_cost, .. } => {
+            format!("Execution {} (${:.4})", if *success { "completed" } else { "failed" }, total_cost)
+        }
+    }
+}
+
+/// 注册 `acsa_execute` 工具：通过给定 Router 执行完整链路
+pub async fn register_acsa_execute_tool(server: &AcsaMcpServer, router: ACSARouter) {
+    server
+        .register_tool(
+            McpTool {
+                name: "acsa_execute".to_string(),
+                description: "Run a request through the full ACSA chain (MOSS plan → L6 verify → Ultron audit → Omega execute) and return the final output, risk score and cost".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "input": {
+                            "type": "string",
+                            "description": "Request to execute"
+                        }
+                    },
+                    "required": ["input"]
+                }),
+            },
+            AcsaExecuteHandler::new(router),
+        )
+        .await;
+}
+
+/// ACSA预置资源：`acsa://protocols`，全部协议的当前配置
+pub struct AcsaProtocolsResource {
+    protocols: Arc<std::sync::RwLock<ProtocolManager>>,
+}
+
+#[async_trait::async_trait]
+impl McpResourceHandler for AcsaProtocolsResource {
+    async fn read(&self) -> Result<String> {
+        let protocols = self.protocols.read().unwrap_or_else(|e| e.into_inner());
+        let list: Vec<Value> = protocols
+            .available()
+            .into_iter()
+            .map(|protocol| {
+                json!({
+                    "name": protocol.name(),
+                    "display_name": protocol.display_name(),
+                    "philosophy": protocols.philosophy(&protocol),
+                    "config": protocols.get_config(protocol.clone()),
+                })
+            })
+            .collect();
+        Ok(serde_json::to_string_pretty(&json!({
+            "current": protocols.current_protocol().name(),
+            "protocols": list,
+        }))?)
+    }
+}
+
+/// ACSA预置资源：`acsa://behavior-patterns`，行为画像、已检测模式与接管建议
+pub struct AcsaBehaviorPatternsResource {
+    monitor: Arc<RwLock<BehaviorMonitor>>,
+}
+
+#[async_trait::async_trait]
+impl McpResourceHandler for AcsaBehaviorPatternsResource {
+    async fn read(&self) -> Result<String> {
+        let monitor = self.monitor.read().await;
+        Ok(serde_json::to_string_pretty(&json!({
+            "profile": monitor.get_current_behavior_profile(),
+            "patterns": monitor.patterns(),
+            "suggestions": monitor.get_takeover_suggestions(),
+        }))?)
+    }
+}
+
+/// ACSA预置资源：`acsa://audit/recent`，最近的审计事件（按时间倒序）
+pub struct AcsaRecentAuditResource {
+    logger: Arc<AuditLogger>,
+}
+
+/// `acsa://audit/recent` 返回的事件数
+const RECENT_AUDIT_LIMIT: usize = 50;
+
+#[async_trait::async_trait]
+impl McpResourceHandler for AcsaRecentAuditResource {
+    async fn read(&self) -> Result<String> {
+        let events = self
+            .logger
+            .try_query(&AuditQuery {
+                limit: Some(RECENT_AUDIT_LIMIT),
+                ..Default::default()
+            })
+            .await?;
+        Ok(serde_json::to_string_pretty(&events)?)
+    }
+}
+
+/// ACSA预置资源：`acsa://usage/today`，今日使用统计与剩余额度
+pub struct AcsaUsageTodayResource {
+    tracker: Arc<UsageTracker>,
+}
+
+#[async_trait::async_trait]
+impl McpResourceHandler for AcsaUsageTodayResource {
+    async fn read(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(&json!({
+            "usage": self.tracker.get_today_usage().await,
+            "remaining_minutes": self.tracker.get_remaining_time().await,
+            "daily_limit_reached": self.tracker.is_daily_limit_reached().await,
+        }))?)
+    }
+}
+
+/// MCP 工具与资源读取的系统状态
+#[derive(Clone)]
+pub struct AcsaMcpState {
+    /// 协议切换工具与 `acsa://protocols` 共用
+    pub protocols: Arc<std::sync::RwLock<ProtocolManager>>,
+    pub behavior: Arc<RwLock<BehaviorMonitor>>,
+    pub audit: Arc<AuditLogger>,
+    pub usage: Arc<UsageTracker>,
+    /// 部署环境配置（协议切换前检查 `protocol.<name>.enabled`）
+    pub config: Arc<ConfigManager>,
+}
+
+impl Default for AcsaMcpState {
+    fn default() -> Self {
+        Self {
+            protocols: Arc::new(std::sync::RwLock::new(ProtocolManager::new())),
+            behavior: Arc::new(RwLock::new(BehaviorMonitor::new(BehaviorMonitorConfig::default()))),
+            audit: Arc::new(AuditLogger::new(AuditLogConfig::default(), None)),
+            usage: SOVEREIGNTY.get_usage_tracker(),
+            config: Arc::new(ConfigManager::new(ConfigManagerConfig::default())),
+        }
+    }
+}
+
+impl AcsaMcpState {
+    pub fn with_protocols(mut self, protocols: Arc<std::sync::RwLock<ProtocolManager>>) -> Self {
+        self.protocols = protocols;
+        self
+    }
+
+    pub fn with_behavior_monitor(mut self, monitor: Arc<RwLock<BehaviorMonitor>>) -> Self {
+        self.behavior = monitor;
+        self
+    }
+
+    pub fn with_audit_logger(mut self, logger: Arc<AuditLogger>) -> Self {
+        self.audit = logger;
+        self
+    }
+
+    pub fn with_usage_tracker(mut self, tracker: Arc<UsageTracker>) -> Self {
+        self.usage = tracker;
+        self
+    }
+
+    pub fn with_config_manager(mut self, config: Arc<ConfigManager>) -> Self {
+        self.config = config;
+        self
+    }
+}
+
+/// 创建ACSA MCP服务器并注册默认工具（仅内置协议）
 pub async fn create_acsa_mcp_server() -> AcsaMcpServer {
+    create_acsa_mcp_server_with_state(AcsaMcpState::default()).await
+}
+
+/// 创建ACSA MCP服务器，协议切换工具作用于给定的协议管理器（可含自定义协议）
+pub async fn create_acsa_mcp_server_with_protocols(
+    protocols: Arc<std::sync::RwLock<ProtocolManager>>,
+) -> AcsaMcpServer {
+    create_acsa_mcp_server_with_state(AcsaMcpState::default().with_protocols(protocols)).await
+}
+
+/// 创建ACSA MCP服务器，工具与资源读取给定的系统状态
+pub async fn create_acsa_mcp_server_with_state(state: AcsaMcpState) -> AcsaMcpServer {
     let server = AcsaMcpServer::new("ACSA".to_string(), "0.1.0".to_string());
 
     // 注册Protocol切换工具
@@ -433,7 +975,7 @@ pub async fn create_acsa_mcp_server() -> AcsaMcpServer {
         .register_tool(
             McpTool {
                 name: "acsa_switch_protocol".to_string(),
-                description: "Switch ACSA to a different protocol mode (ARCHITECT/AEGIS/PREDATOR/etc.)".to_string(),
+                description: "Switch ACSA to a different protocol mode (ARCHITECT/AEGIS/PREDATOR/etc. or a custom protocol)".to_string(),
                 input_schema: json!({
                     "type": "object",
                     "properties": {
@@ -445,7 +987,7 @@ pub async fn create_acsa_mcp_server() -> AcsaMcpServer {
                     "required": ["protocol"]
                 }),
             },
-            AcsaProtocolSwitchHandler,
+            AcsaProtocolSwitchHandler::new(state.protocols.clone(), state.config.clone()),
         )
         .await;
 
@@ -492,21 +1034,51 @@ pub async fn create_acsa_mcp_server() -> AcsaMcpServer {
 
     // 注册资源
     server
-        .register_resource(McpResource {
-            uri: "acsa://protocols".to_string(),
-            name: "ACSA Protocols".to_string(),
-            description: "List of available ACSA protocols and their configurations".to_string(),
-            mime_type: "application/json".to_string(),
-        })
+        .register_resource(
+            McpResource {
+                uri: "acsa://protocols".to_string(),
+                name: "ACSA Protocols".to_string(),
+                description: "List of available ACSA protocols and their configurations".to_string(),
+                mime_type: "application/json".to_string(),
+            },
+            AcsaProtocolsResource { protocols: state.protocols },
+        )
         .await;
 
     server
-        .register_resource(McpResource {
-            uri: "acsa://behavior-patterns".to_string(),
-            name: "Behavior Patterns".to_string(),
-            description: "Detected user behavior patterns".to_string(),
-            mime_type: "application/json".to_string(),
-        })
+        .register_resource(
+            McpResource {
+                uri: "acsa://behavior-patterns".to_string(),
+                name: "Behavior Patterns".to_string(),
+                description: "Detected user behavior patterns".to_string(),
+                mime_type: "application/json".to_string(),
+            },
+            AcsaBehaviorPatternsResource { monitor: state.behavior },
+        )
+        .await;
+
+    server
+        .register_resource(
+            McpResource {
+                uri: "acsa://audit/recent".to_string(),
+                name: "Recent Audit Events".to_string(),
+                description: "Most recent audit log events, newest first".to_string(),
+                mime_type: "application/json".to_string(),
+            },
+            AcsaRecentAuditResource { logger: state.audit },
+        )
+        .await;
+
+    server
+        .register_resource(
+            McpResource {
+                uri: "acsa://usage/today".to_string(),
+                name: "Today's Usage".to_string(),
+                description: "Today's usage statistics and remaining daily allowance".to_string(),
+                mime_type: "application/json".to_string(),
+            },
+            AcsaUsageTodayResource { tracker: state.usage },
+        )
         .await;
 
     // 注册提示模板
@@ -574,4 +1146,190 @@ mod tests {
             panic!("Expected ToolsList response");
         }
     }
+
+    #[tokio::test]
+    async fn test_switch_to_custom_protocol() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("auditor.toml"),
+            "name = \"auditor\"\ntemperature = 0.2\n[weights]\nmoss = 0.2\nl6 = 0.3\nultron = 0.4\nomega = 0.1\n",
+        )
+        .unwrap();
+        let mut manager = ProtocolManager::new();
+        manager.load_custom_protocols(dir.path()).unwrap();
+        let protocols = Arc::new(std::sync::RwLock::new(manager));
+        let server = create_acsa_mcp_server_with_protocols(protocols.clone()).await;
+
+        let call = |protocol: &str| McpRequest::ToolsCall {
+            name: "acsa_switch_protocol".to_string(),
+            arguments: Some(json!({ "protocol": protocol })),
+        };
+        server.handle_request(call("auditor")).await.unwrap();
+        assert_eq!(
+            protocols.read().unwrap().current_protocol(),
+            crate::core::protocol::Protocol::Custom("auditor".to_string())
+        );
+        match server.handle_request(call("unknown")).await.unwrap() {
+            McpResponse::ToolsCallResult { is_error, .. } => assert_eq!(is_error, Some(true)),
+            _ => panic!("Expected ToolsCallResult response"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_switch_protocol_enforces_production_ghost_guard() {
+        use crate::core::config_manager::Environment;
+        use crate::core::protocol::Protocol;
+
+        let config = Arc::new(ConfigManager::new(ConfigManagerConfig {
+            environment: Environment::Production,
+            ..Default::default()
+        }));
+        let protocols = Arc::new(std::sync::RwLock::new(ProtocolManager::new()));
+        let state = AcsaMcpState::default().with_protocols(protocols.clone()).with_config_manager(config);
+        let server = create_acsa_mcp_server_with_state(state).await;
+
+        let call = |protocol: &str| McpRequest::ToolsCall {
+            name: "acsa_switch_protocol".to_string(),
+            arguments: Some(json!({ "protocol": protocol })),
+        };
+        let before = protocols.read().unwrap().current_protocol();
+        match server.handle_request(call("ghost")).await.unwrap() {
+            McpResponse::ToolsCallResult { is_error, content } => {
+                assert_eq!(is_error, Some(true));
+                assert!(content[0].text.contains("guards.allow_ghost_in_production"));
+            }
+            _ => panic!("Expected ToolsCallResult response"),
+        }
+        assert_eq!(protocols.read().unwrap().current_protocol(), before);
+
+        server.handle_request(call("aegis")).await.unwrap();
+        assert_eq!(protocols.read().unwrap().current_protocol(), Protocol::Aegis);
+    }
+
+    #[tokio::test]
+    async fn test_resources_read_live_state() {
+        let audit = Arc::new(AuditLogger::new(AuditLogConfig::default(), None));
+        crate::core::audit_log::log_authentication(&audit, "alice".to_string(), None, true).await.unwrap();
+        let server = create_acsa_mcp_server_with_state(AcsaMcpState::default().with_audit_logger(audit)).await;
+
+        let read = |uri: &str| {
+            let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "resources/read", "params": { "uri": uri } });
+            let server = &server;
+            async move {
+                let response = server.handle_message(&request.to_string()).await.unwrap();
+                let text = response["result"]["contents"][0]["text"].as_str().map(String::from);
+                (response, text.map(|text| serde_json::from_str::<Value>(&text).unwrap()))
+            }
+        };
+
+        let (_, audit_events) = read("acsa://audit/recent").await;
+        assert_eq!(audit_events.unwrap()[0]["actor_id"], "alice");
+        let (_, protocols) = read("acsa://protocols").await;
+        let protocols = protocols.unwrap();
+        assert!(protocols["protocols"].as_array().unwrap().iter().any(|p| p["config"]["temperature"].is_number()));
+        let (_, usage) = read("acsa://usage/today").await;
+        assert!(usage.unwrap()["usage"]["date"].is_string());
+        let (response, _) = read("acsa://missing").await;
+        assert_eq!(response["error"]["code"], MCP_RESOURCE_NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_jsonrpc_framing_and_errors() {
+        let server = create_acsa_mcp_server().await;
+
+        let response = server
+            .handle_message(r#"{"jsonrpc":"2.0","id":"req-7","method":"tools/list","params":{"cursor":null}}"#)
+            .await
+            .unwrap();
+        assert_eq!(response["id"], "req-7");
+        assert!(response["result"]["tools"].as_array().is_some_and(|tools| !tools.is_empty()));
+
+        let error_code = |response: Value| response["error"]["code"].as_i64().unwrap();
+        assert_eq!(error_code(server.handle_message("{not json").await.unwrap()), JSONRPC_PARSE_ERROR);
+        let response = server.handle_message(r#"{"jsonrpc":"2.0","id":3,"method":"sampling/createMessage"}"#).await.unwrap();
+        assert_eq!(response["id"], 3);
+        assert_eq!(error_code(response), JSONRPC_METHOD_NOT_FOUND);
+        let response = server
+            .handle_message(r#"{"jsonrpc":"2.0","id":4,"method":"tools/call","params":{"name":"missing"}}"#)
+            .await
+            .unwrap();
+        assert_eq!(error_code(response), JSONRPC_INVALID_PARAMS);
+        assert_eq!(error_code(server.handle_message(r#"{"id":5,"method":"ping"}"#).await.unwrap()), JSONRPC_INVALID_REQUEST);
+
+        // 通知没有 id，不回复
+        assert!(server.handle_message(r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_serve_writes_one_response_per_request_line() {
+        let server = create_acsa_mcp_server().await;
+        let input = [
+            r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2025-11-25","capabilities":{},"clientInfo":{"name":"test","version":"1.0"}}}"#,
+            r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#,
+            "",
+            r#"{"jsonrpc":"2.0","id":2,"method":"ping"}"#,
+        ]
+        .join("\n");
+
+        let mut output = Vec::new();
+        server.serve(input.as_bytes(), &mut output).await.unwrap();
+
+        let responses: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0]["id"], 1);
+        assert_eq!(responses[0]["result"]["serverInfo"]["name"], "ACSA");
+        assert_eq!(responses[1], json!({ "jsonrpc": "2.0", "id": 2, "result": {} }));
+    }
+
+    #[tokio::test]
+    async fn test_acsa_execute_streams_progress_before_result() {
+        use crate::core::mock_scenario::{MockScenario, ScenarioPlayer};
+        use crate::core::providers::MockProvider;
+        use crate::core::types::{ACSAConfig, AgentRole};
+
+        // Mock Ultron 从不放行，改用剧本给出低风险审计
+        let player = ScenarioPlayer::new(
+            MockScenario::from_yaml(
+                "name: approve\nrules:\n  - role: Ultron\n    steps:\n      - text: \"RISK_SCORE: 10\\nIS_SAFE: true\\nMITIGATION: none\"\n",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let server = create_acsa_mcp_server().await;
+        let router = ACSARouter::new(
+            Arc::new(MockProvider::new(AgentRole::MOSS)),
+            Arc::new(MockProvider::new(AgentRole::L6)),
+            player.provider(AgentRole::Ultron),
+            Arc::new(MockProvider::new(AgentRole::Omega)),
+            ACSAConfig::default(),
+        );
+        register_acsa_execute_tool(&server, router).await;
+
+        let input = r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"acsa_execute","arguments":{"input":"写一个HTTP服务器"},"_meta":{"progressToken":"run-1"}}}"#;
+        let mut output = Vec::new();
+        server.serve(input.as_bytes(), &mut output).await.unwrap();
+
+        let messages: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let (response, notifications) = messages.split_last().unwrap();
+        assert!(notifications.len() >= 4);
+        for (step, notification) in notifications.iter().enumerate() {
+            assert_eq!(notification["method"], "notifications/progress");
+            assert_eq!(notification["params"]["progressToken"], "run-1");
+            assert_eq!(notification["params"]["progress"], step as u64 + 1);
+        }
+
+        assert_eq!(response["id"], 1);
+        assert_eq!(response["result"]["isError"], false);
+        let summary: Value = serde_json::from_str(response["result"]["content"][1]["text"].as_str().unwrap()).unwrap();
+        assert_eq!(summary["risk_score"], 10);
+        assert!(summary["total_cost"].is_number());
+    }
 }
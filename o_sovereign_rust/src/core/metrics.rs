@@ -6,16 +6,24 @@
 // 2. 健康检查端点
 // 3. 系统性能监控
 // 4. 自定义指标
-// 5. OpenTelemetry集成（可选）
+// 5. OpenTelemetry集成（链路追踪见 telemetry.rs）
+// 6. Agent 延迟直方图、Provider 成功率、缓存占用与 H(t) 的 Prometheus 文本格式导出
 
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::info;
 
+use super::api_manager::ProviderStats;
+use super::cache_manager::CacheUsage;
+
+/// 直方图默认桶上界（毫秒，适用于 Agent / Provider 调用延迟）
+pub const LATENCY_BUCKETS_MS: [f64; 10] = [50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0, 10000.0, 30000.0, 60000.0];
+
 /// 指标类型
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum MetricType {
@@ -137,10 +145,22 @@ pub struct ApplicationMetrics {
     pub sovereignty_enabled: bool,
 }
 
+/// 直方图：各桶计数（非累计）、总和与样本数
+#[derive(Debug, Clone)]
+struct Histogram {
+    name: String,
+    labels: HashMap<String, String>,
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
 /// 指标收集器
 pub struct MetricsCollector {
     /// 自定义指标
     metrics: Arc<RwLock<HashMap<String, MetricValue>>>,
+    /// 直方图（按 LATENCY_BUCKETS_MS 分桶）
+    histograms: Arc<RwLock<HashMap<String, Histogram>>>,
     /// 系统指标
     system_metrics: Arc<RwLock<SystemMetrics>>,
     /// 应用指标
@@ -158,6 +178,7 @@ impl MetricsCollector {
 
         Self {
             metrics: Arc::new(RwLock::new(HashMap::new())),
+            histograms: Arc::new(RwLock::new(HashMap::new())),
             system_metrics: Arc::new(RwLock::new(SystemMetrics::default())),
             app_metrics: Arc::new(RwLock::new(ApplicationMetrics::default())),
             started_at: Utc::now(),
@@ -186,37 +207,68 @@ impl MetricsCollector {
 
     /// 设置仪表盘值
     pub async fn set_gauge(&self, name: &str, value: f64, labels: HashMap<String, String>) {
-        let mut metrics = self.metrics.write().await;
-
-        let key = self.build_metric_key(name, &labels);
-        metrics.insert(
-            key,
-            MetricValue {
-                name: name.to_string(),
-                metric_type: MetricType::Gauge,
-                value,
-                labels,
-                updated_at: Utc::now(),
-            },
-        );
+        self.set_metric(name, MetricType::Gauge, value, labels).await;
     }
 
-    /// 记录直方图
+    /// 记录直方图样本
     pub async fn observe_histogram(&self, name: &str, value: f64, labels: HashMap<String, String>) {
-        // TODO: 实现真实的直方图统计（需要维护桶）
-        let mut metrics = self.metrics.write().await;
+        let mut histograms = self.histograms.write().await;
 
         let key = self.build_metric_key(name, &labels);
-        metrics.insert(
-            key,
-            MetricValue {
-                name: name.to_string(),
-                metric_type: MetricType::Histogram,
-                value,
-                labels,
-                updated_at: Utc::now(),
-            },
-        );
+        let histogram = histograms.entry(key).or_insert_with(|| Histogram {
+            name: name.to_string(),
+            labels,
+            buckets: vec![0; LATENCY_BUCKETS_MS.len()],
+            sum: 0.0,
+            count: 0,
+        });
+
+        if let Some(bucket) = LATENCY_BUCKETS_MS.iter().position(|bound| value <= *bound) {
+            histogram.buckets[bucket] += 1;
+        }
+        histogram.sum += value;
+        histogram.count += 1;
+    }
+
+    /// 记录一次 Agent 调用：延迟直方图 + 按结果计数
+    pub async fn record_agent_call(&self, agent: &str, success: bool, latency_ms: f64) {
+        let agent_label = HashMap::from([("agent".to_string(), agent.to_string())]);
+        self.observe_histogram("acsa_agent_latency_ms", latency_ms, agent_label.clone()).await;
+
+        let mut labels = agent_label;
+        labels.insert("status".to_string(), if success { "success" } else { "failure" }.to_string());
+        self.increment_counter("acsa_agent_calls_total", labels).await;
+    }
+
+    /// 用 ApiManager 的统计快照更新各 Provider 指标
+    pub async fn record_provider_stats(&self, stats: &[ProviderStats]) {
+        for provider_stats in stats {
+            let provider = provider_stats.provider.name().to_lowercase();
+            let labels = HashMap::from([("provider".to_string(), provider.clone())]);
+            for (status, calls) in [("success", provider_stats.successful_calls), ("failure", provider_stats.failed_calls)] {
+                let mut labels = labels.clone();
+                labels.insert("status".to_string(), status.to_string());
+                self.set_metric("acsa_provider_calls_total", MetricType::Counter, calls as f64, labels).await;
+            }
+            // ProviderStats::success_rate 为百分比，导出为 0-1 的比例
+            self.set_gauge("acsa_provider_success_ratio", provider_stats.success_rate() / 100.0, labels.clone()).await;
+            self.set_gauge("acsa_provider_avg_latency_ms", provider_stats.avg_latency_ms, labels.clone()).await;
+            self.set_metric("acsa_provider_cost_usd_total", MetricType::Counter, provider_stats.total_cost, labels).await;
+        }
+    }
+
+    /// 用 CacheManager 的占用快照更新缓存指标
+    pub async fn record_cache_usage(&self, usage: &CacheUsage) {
+        for (kind, bytes) in [
+            ("api_cache", usage.api_cache_bytes),
+            ("model_cache", usage.model_cache_bytes),
+            ("temp", usage.temp_bytes),
+            ("logs", usage.log_bytes),
+            ("total", usage.total_bytes),
+        ] {
+            let labels = HashMap::from([("type".to_string(), kind.to_string())]);
+            self.set_gauge("acsa_cache_bytes", bytes as f64, labels).await;
+        }
     }
 
     /// 更新系统指标
@@ -313,39 +365,83 @@ impl MetricsCollector {
         self.app_metrics.read().await.clone()
     }
 
-    /// 导出Prometheus格式
+    /// 导出Prometheus文本格式（同名指标归为一族，只输出一次 TYPE）
     pub async fn export_prometheus(&self) -> String {
-        let mut output = String::new();
+        let mut families: BTreeMap<String, (MetricType, Vec<String>)> = BTreeMap::new();
+        let mut add = |name: &str, metric_type: MetricType, line: String| {
+            families
+                .entry(name.to_string())
+                .or_insert_with(|| (metric_type, Vec::new()))
+                .1
+                .push(line);
+        };
 
         // 导出自定义指标
         let metrics = self.metrics.read().await;
-        for (_, metric) in metrics.iter() {
+        let mut keys: Vec<&String> = metrics.keys().collect();
+        keys.sort();
+        for key in keys {
+            let metric = &metrics[key];
             let labels = self.format_labels(&metric.labels);
-            output.push_str(&format!(
-                "# TYPE {} {}\n",
-                metric.name,
-                self.metric_type_to_string(metric.metric_type)
-            ));
-            output.push_str(&format!("{}{} {}\n", metric.name, labels, metric.value));
+            add(&metric.name, metric.metric_type, format!("{}{} {}", metric.name, labels, metric.value));
         }
+        drop(metrics);
+
+        // 导出直方图（桶计数为累计值）
+        let histograms = self.histograms.read().await;
+        let mut keys: Vec<&String> = histograms.keys().collect();
+        keys.sort();
+        for key in keys {
+            let histogram = &histograms[key];
+            let mut cumulative = 0;
+            for (bound, count) in LATENCY_BUCKETS_MS.iter().zip(&histogram.buckets) {
+                cumulative += count;
+                let labels = self.format_labels_with_le(&histogram.labels, &bound.to_string());
+                add(&histogram.name, MetricType::Histogram, format!("{}_bucket{} {}", histogram.name, labels, cumulative));
+            }
+            let labels = self.format_labels_with_le(&histogram.labels, "+Inf");
+            add(&histogram.name, MetricType::Histogram, format!("{}_bucket{} {}", histogram.name, labels, histogram.count));
+            let labels = self.format_labels(&histogram.labels);
+            add(&histogram.name, MetricType::Histogram, format!("{}_sum{} {}", histogram.name, labels, histogram.sum));
+            add(&histogram.name, MetricType::Histogram, format!("{}_count{} {}", histogram.name, labels, histogram.count));
+        }
+        drop(histograms);
 
         // 导出系统指标
         let sys_metrics = self.system_metrics.read().await;
-        output.push_str(&format!("# TYPE acsa_cpu_usage gauge\n"));
-        output.push_str(&format!("acsa_cpu_usage {}\n", sys_metrics.cpu_usage));
-        output.push_str(&format!("# TYPE acsa_memory_usage gauge\n"));
-        output.push_str(&format!("acsa_memory_usage {}\n", sys_metrics.memory_usage));
+        add("acsa_cpu_usage", MetricType::Gauge, format!("acsa_cpu_usage {}", sys_metrics.cpu_usage));
+        add("acsa_memory_usage", MetricType::Gauge, format!("acsa_memory_usage {}", sys_metrics.memory_usage));
+        drop(sys_metrics);
 
         // 导出应用指标
         let app_metrics = self.app_metrics.read().await;
-        output.push_str(&format!("# TYPE acsa_total_requests counter\n"));
-        output.push_str(&format!("acsa_total_requests {}\n", app_metrics.total_requests));
-        output.push_str(&format!("# TYPE acsa_avg_response_time gauge\n"));
-        output.push_str(&format!(
-            "acsa_avg_response_time {}\n",
-            app_metrics.avg_response_time_ms
-        ));
+        add("acsa_total_requests", MetricType::Counter, format!("acsa_total_requests {}", app_metrics.total_requests));
+        add(
+            "acsa_avg_response_time",
+            MetricType::Gauge,
+            format!("acsa_avg_response_time {}", app_metrics.avg_response_time_ms),
+        );
+        add("acsa_sovereignty_ht", MetricType::Gauge, format!("acsa_sovereignty_ht {}", app_metrics.bio_activity_ht));
+        add(
+            "acsa_sovereignty_decay_rate",
+            MetricType::Gauge,
+            format!("acsa_sovereignty_decay_rate {}", app_metrics.bio_activity_decay),
+        );
+        add(
+            "acsa_sovereignty_enabled",
+            MetricType::Gauge,
+            format!("acsa_sovereignty_enabled {}", u8::from(app_metrics.sovereignty_enabled)),
+        );
+        drop(app_metrics);
 
+        let mut output = String::new();
+        for (name, (metric_type, lines)) in families {
+            let _ = writeln!(output, "# TYPE {} {}", name, self.metric_type_to_string(metric_type));
+            for line in lines {
+                output.push_str(&line);
+                output.push('\n');
+            }
+        }
         output
     }
 
@@ -362,6 +458,23 @@ impl MetricsCollector {
         key
     }
 
+    /// 写入（覆盖）指定类型的指标值
+    async fn set_metric(&self, name: &str, metric_type: MetricType, value: f64, labels: HashMap<String, String>) {
+        let mut metrics = self.metrics.write().await;
+
+        let key = self.build_metric_key(name, &labels);
+        metrics.insert(
+            key,
+            MetricValue {
+                name: name.to_string(),
+                metric_type,
+                value,
+                labels,
+                updated_at: Utc::now(),
+            },
+        );
+    }
+
     fn format_labels(&self, labels: &HashMap<String, String>) -> String {
         if labels.is_empty() {
             return String::new();
@@ -369,13 +482,20 @@ impl MetricsCollector {
 
         let mut pairs: Vec<String> = labels
             .iter()
-            .map(|(k, v)| format!("{}=\"{}\"", k, v))
+            .map(|(k, v)| format!("{}=\"{}\"", k, escape_label_value(v)))
             .collect();
         pairs.sort();
 
         format!("{{{}}}", pairs.join(","))
     }
 
+    /// 直方图桶的标签（追加 `le`）
+    fn format_labels_with_le(&self, labels: &HashMap<String, String>, le: &str) -> String {
+        let mut labels = labels.clone();
+        labels.insert("le".to_string(), le.to_string());
+        self.format_labels(&labels)
+    }
+
     fn metric_type_to_string(&self, metric_type: MetricType) -> &str {
         match metric_type {
             MetricType::Counter => "counter",
@@ -386,6 +506,11 @@ impl MetricsCollector {
     }
 }
 
+/// Prometheus 标签值转义（反斜杠、双引号、换行）
+fn escape_label_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -425,4 +550,41 @@ mod tests {
         let health = collector.get_health_check(components).await;
         assert_eq!(health.status, HealthStatus::Healthy);
     }
+
+    #[tokio::test]
+    async fn test_prometheus_export() {
+        use crate::core::api_manager::ApiProvider;
+
+        let collector = MetricsCollector::new("0.1.0".to_string());
+        collector.record_agent_call("MOSS", true, 120.0).await;
+        collector.record_agent_call("MOSS", false, 4000.0).await;
+
+        let mut stats = ProviderStats::new(ApiProvider::Claude);
+        stats.total_calls = 4;
+        stats.successful_calls = 3;
+        stats.failed_calls = 1;
+        collector.record_provider_stats(&[stats]).await;
+        collector
+            .record_cache_usage(&CacheUsage {
+                total_bytes: 3072,
+                cache_bytes: 2048,
+                log_bytes: 1024,
+                api_cache_bytes: 2048,
+                model_cache_bytes: 0,
+                temp_bytes: 0,
+            })
+            .await;
+        collector.update_bio_activity(72.5, 27.5, true).await;
+
+        let text = collector.export_prometheus().await;
+        assert_eq!(text.matches("# TYPE acsa_agent_latency_ms histogram").count(), 1);
+        assert!(text.contains("acsa_agent_latency_ms_bucket{agent=\"MOSS\",le=\"100\"} 0\n"));
+        assert!(text.contains("acsa_agent_latency_ms_bucket{agent=\"MOSS\",le=\"250\"} 1\n"));
+        assert!(text.contains("acsa_agent_latency_ms_bucket{agent=\"MOSS\",le=\"+Inf\"} 2\n"));
+        assert!(text.contains("acsa_agent_latency_ms_sum{agent=\"MOSS\"} 4120\n"));
+        assert!(text.contains("acsa_agent_calls_total{agent=\"MOSS\",status=\"failure\"} 1\n"));
+        assert!(text.contains("acsa_provider_success_ratio{provider=\"claude\"} 0.75\n"));
+        assert!(text.contains("acsa_cache_bytes{type=\"api_cache\"} 2048\n"));
+        assert!(text.contains("# TYPE acsa_sovereignty_ht gauge\nacsa_sovereignty_ht 72.5\n"));
+    }
 }
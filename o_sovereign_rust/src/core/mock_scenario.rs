@@ -0,0 +1,511 @@
+// Mock Scenario - 可编排的 Mock Provider
+// 用 YAML 剧本替代固定回显，让故障转移、重试与 Jarvis 路径能在 CI 中稳定复现
+//
+// 核心功能：
+// 1. 规则按角色 / 子串 / 正则匹配提示词，首条命中生效
+// 2. 分阶段响应：每条规则按调用次序依次返回，耗尽后重复最后一步或循环
+// 3. 注入延迟与故障（限流突发、超时、5xx、网络错误），错误携带对应 ErrorCode
+// 4. 进程级激活（`--scenario` / ACSA_MOCK_SCENARIO），mock 模式下所有角色共享剧本状态
+//
+// 剧本示例：
+//
+//   name: rate-limit-drill
+//   rules:
+//     - role: MOSS
+//       contains: "http"
+//       steps:
+//         - fail: rate_limited
+//           times: 2
+//         - text: "1. 使用 axum 搭建服务"
+//           latency_ms: 120
+//     - role: Ultron
+//       steps:
+//         - text: "RISK_SCORE: 10\nIS_SAFE: true\nMITIGATION: none"
+
+use super::determinism;
+use super::error::{AcsaError, ErrorCode};
+use super::providers::ModelProvider;
+use super::types::{AgentResponse, AgentRole, AgentStats};
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, LazyLock, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tracing::{debug, info};
+
+/// 当前激活的剧本（mock 模式下由 create_provider 使用）
+static ACTIVE: LazyLock<RwLock<Option<Arc<ScenarioPlayer>>>> = LazyLock::new(|| RwLock::new(None));
+
+fn default_times() -> u32 {
+    1
+}
+
+/// 注入的故障类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureMode {
+    /// 429 限流
+    RateLimited,
+    /// 请求超时（先等待 latency_ms）
+    Timeout,
+    /// 5xx 服务端错误
+    ServerError,
+    /// 连接失败
+    NetworkError,
+    /// 400 请求错误（不可重试）
+    BadRequest,
+    /// 401 密钥无效
+    InvalidKey,
+}
+
+impl FailureMode {
+    pub fn error_code(&self) -> ErrorCode {
+        match self {
+            FailureMode::RateLimited => ErrorCode::ProviderRateLimited,
+            FailureMode::Timeout => ErrorCode::ProviderTimeout,
+            FailureMode::ServerError => ErrorCode::ProviderServerError,
+            FailureMode::NetworkError => ErrorCode::ProviderNetworkError,
+            FailureMode::BadRequest => ErrorCode::ProviderBadRequest,
+            FailureMode::InvalidKey => ErrorCode::ApiKeyInvalid,
+        }
+    }
+
+    fn default_message(&self) -> &'static str {
+        match self {
+            FailureMode::RateLimited => "429 Too Many Requests",
+            FailureMode::Timeout => "request timed out",
+            FailureMode::ServerError => "503 Service Unavailable",
+            FailureMode::NetworkError => "connection refused",
+            FailureMode::BadRequest => "400 Bad Request",
+            FailureMode::InvalidKey => "401 invalid api key",
+        }
+    }
+}
+
+/// 剧本中的一步：返回文本或注入故障
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScenarioStep {
+    /// 响应文本，支持 `{prompt}`（前 50 字符）/ `{role}` 占位符
+    #[serde(default)]
+    pub text: Option<String>,
+    /// 注入的故障（与 text 二选一）
+    #[serde(default)]
+    pub fail: Option<FailureMode>,
+    /// 自定义错误信息
+    #[serde(default)]
+    pub message: Option<String>,
+    /// 注入延迟
+    #[serde(default)]
+    pub latency_ms: u64,
+    /// 该步连续重复的次数（用于限流突发等场景）
+    #[serde(default = "default_times")]
+    pub times: u32,
+    /// 覆盖 token 数（默认按空白分词计数）
+    #[serde(default)]
+    pub tokens: Option<u32>,
+    /// 覆盖成本（默认每 token $0.00001，与 MockProvider 一致）
+    #[serde(default)]
+    pub cost: Option<f64>,
+}
+
+/// 分阶段响应耗尽后的行为
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExhaustPolicy {
+    /// 重复最后一步
+    #[default]
+    RepeatLast,
+    /// 从头循环
+    Cycle,
+}
+
+/// 一条匹配规则
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScenarioRule {
+    #[serde(default)]
+    pub name: Option<String>,
+    /// 只匹配该角色（省略则匹配所有角色）
+    #[serde(default)]
+    pub role: Option<AgentRole>,
+    /// 提示词包含的子串（不区分大小写）
+    #[serde(default)]
+    pub contains: Option<String>,
+    /// 提示词正则（区分大小写）
+    #[serde(default)]
+    pub pattern: Option<String>,
+    pub steps: Vec<ScenarioStep>,
+    #[serde(default)]
+    pub exhausted: ExhaustPolicy,
+}
+
+/// YAML 剧本
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MockScenario {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub rules: Vec<ScenarioRule>,
+    /// 没有规则命中时的响应（省略则回显提示词，与 MockProvider 相同）
+    #[serde(default)]
+    pub default: Option<ScenarioStep>,
+}
+
+impl MockScenario {
+    pub fn from_yaml(yaml: &str) -> Result<Self> {
+        serde_yaml::from_str(yaml).context("Invalid mock scenario")
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read mock scenario {}", path.display()))?;
+        Self::from_yaml(&content).with_context(|| format!("In {}", path.display()))
+    }
+}
+
+/// 编译后的规则
+struct CompiledRule {
+    label: String,
+    role: Option<AgentRole>,
+    contains: Option<String>,
+    pattern: Option<Regex>,
+    /// 按 times 展开后的步骤
+    steps: Vec<ScenarioStep>,
+    exhausted: ExhaustPolicy,
+}
+
+impl CompiledRule {
+    fn matches(&self, role: AgentRole, prompt: &str, lowered: &str) -> bool {
+        self.role.is_none_or(|r| r == role)
+            && self.contains.as_deref().is_none_or(|needle| lowered.contains(needle))
+            && self.pattern.as_ref().is_none_or(|re| re.is_match(prompt))
+    }
+
+    fn step(&self, call: usize) -> &ScenarioStep {
+        let index = match self.exhausted {
+            ExhaustPolicy::RepeatLast => call.min(self.steps.len() - 1),
+            ExhaustPolicy::Cycle => call % self.steps.len(),
+        };
+        &self.steps[index]
+    }
+}
+
+fn validate_step(step: &ScenarioStep, label: &str) -> Result<()> {
+    match (&step.text, &step.fail) {
+        (Some(_), Some(_)) => Err(anyhow!("Step in '{}' sets both `text` and `fail`", label)),
+        (None, None) => Err(anyhow!("Step in '{}' needs either `text` or `fail`", label)),
+        _ if step.times == 0 => Err(anyhow!("Step in '{}' has `times: 0`", label)),
+        _ => Ok(()),
+    }
+}
+
+/// 剧本运行时：多个角色共享规则与调用计数
+pub struct ScenarioPlayer {
+    name: String,
+    rules: Vec<CompiledRule>,
+    default: Option<ScenarioStep>,
+    /// 每条规则已命中的次数
+    calls: Mutex<Vec<usize>>,
+}
+
+impl ScenarioPlayer {
+    pub fn new(scenario: MockScenario) -> Result<Arc<Self>> {
+        let mut rules = Vec::with_capacity(scenario.rules.len());
+        for (index, rule) in scenario.rules.into_iter().enumerate() {
+            let label = rule.name.clone().unwrap_or_else(|| format!("rule #{}", index + 1));
+            if rule.steps.is_empty() {
+                return Err(anyhow!("Scenario rule '{}' has no steps", label));
+            }
+            let mut steps = Vec::new();
+            for step in rule.steps {
+                validate_step(&step, &label)?;
+                steps.extend(std::iter::repeat_n(step.clone(), step.times as usize));
+            }
+            let pattern = rule
+                .pattern
+                .as_deref()
+                .map(Regex::new)
+                .transpose()
+                .with_context(|| format!("Invalid pattern in scenario rule '{}'", label))?;
+
+            rules.push(CompiledRule {
+                label,
+                role: rule.role,
+                contains: rule.contains.map(|c| c.to_lowercase()),
+                pattern,
+                steps,
+                exhausted: rule.exhausted,
+            });
+        }
+        if let Some(step) = &scenario.default {
+            validate_step(step, "default")?;
+        }
+
+        info!("🎬 Mock scenario '{}' loaded ({} rules)", scenario.name, rules.len());
+        Ok(Arc::new(Self {
+            name: scenario.name,
+            calls: Mutex::new(vec![0; rules.len()]),
+            rules,
+            default: scenario.default,
+        }))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Arc<Self>> {
+        Self::new(MockScenario::load(path)?)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// 指定角色的剧本 Provider
+    pub fn provider(self: &Arc<Self>, role: AgentRole) -> Arc<dyn ModelProvider> {
+        Arc::new(ScriptedProvider {
+            role,
+            player: self.clone(),
+            stats: Mutex::new(AgentStats::new()),
+        })
+    }
+
+    /// 重置所有规则的调用计数
+    pub async fn rewind(&self) {
+        self.calls.lock().await.iter_mut().for_each(|c| *c = 0);
+    }
+
+    /// 为本次调用选出一步（未命中且无默认步骤时返回 None）
+    async fn next_step(&self, role: AgentRole, prompt: &str) -> Option<(String, ScenarioStep)> {
+        let lowered = prompt.to_lowercase();
+        let Some(index) = self.rules.iter().position(|rule| rule.matches(role, prompt, &lowered)) else {
+            return self.default.clone().map(|step| ("default".to_string(), step));
+        };
+
+        let mut calls = self.calls.lock().await;
+        let rule = &self.rules[index];
+        let step = rule.step(calls[index]).clone();
+        calls[index] += 1;
+        Some((rule.label.clone(), step))
+    }
+}
+
+/// 激活剧本（进程级）
+pub fn activate(player: Arc<ScenarioPlayer>) {
+    info!("🎬 Mock scenario '{}' active", player.name());
+    *ACTIVE.write().unwrap_or_else(|e| e.into_inner()) = Some(player);
+}
+
+pub fn deactivate() {
+    *ACTIVE.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// 当前激活的剧本
+pub fn current() -> Option<Arc<ScenarioPlayer>> {
+    ACTIVE.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// 剧本驱动的 Mock Provider
+struct ScriptedProvider {
+    role: AgentRole,
+    player: Arc<ScenarioPlayer>,
+    stats: Mutex<AgentStats>,
+}
+
+impl ScriptedProvider {
+    fn render(&self, template: &str, prompt: &str) -> String {
+        template.replace("{role}", self.role.as_str()).replace("{prompt}", prompt)
+    }
+}
+
+#[async_trait]
+impl ModelProvider for ScriptedProvider {
+    async fn generate(&self, prompt: &str, _max_tokens: u32, _temperature: f64) -> Result<AgentResponse> {
+        let start = Instant::now();
+        let (label, step) = self.player.next_step(self.role, prompt).await.unwrap_or_else(|| {
+            let echo = format!("[{} Mock Response] Processed: {{prompt}}...", self.role.as_str());
+            ("echo".to_string(), ScenarioStep {
+                text: Some(echo),
+                fail: None,
+                message: None,
+                latency_ms: 0,
+                times: 1,
+                tokens: None,
+                cost: None,
+            })
+        });
+        debug!("🎬 {} ← {}", self.role.as_str(), label);
+
+        if step.latency_ms > 0 {
+            tokio::time::sleep(Duration::from_millis(step.latency_ms)).await;
+        }
+        let latency_ms = if determinism::is_deterministic() {
+            step.latency_ms
+        } else {
+            start.elapsed().as_millis() as u64
+        };
+
+        if let Some(mode) = step.fail {
+            self.stats.lock().await.record_failure(latency_ms);
+            let message = step.message.as_deref().unwrap_or(mode.default_message());
+            return Err(AcsaError::new(
+                mode.error_code(),
+                format!("{} (scripted by '{}')", self.render(message, prompt), label),
+            )
+            .into());
+        }
+
+        let prompt_preview: String = prompt.chars().take(50).collect();
+        let text = self.render(step.text.as_deref().unwrap_or_default(), &prompt_preview);
+        let tokens = step.tokens.unwrap_or_else(|| text.split_whitespace().count() as u32);
+        let cost = step.cost.unwrap_or(tokens as f64 * 0.00001);
+        self.stats.lock().await.record_success(tokens, cost, latency_ms);
+
+        Ok(AgentResponse {
+            role: self.role,
+            text,
+            tokens,
+            cost,
+            latency_ms,
+            metadata: HashMap::from([("scenario_rule".to_string(), label)]),
+            timestamp: Utc::now(),
+        })
+    }
+
+    fn role(&self) -> AgentRole {
+        self.role
+    }
+
+    async fn stats(&self) -> AgentStats {
+        self.stats.lock().await.clone()
+    }
+
+    async fn reset_stats(&self) {
+        *self.stats.lock().await = AgentStats::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::router::ACSARouter;
+    use crate::core::types::ACSAConfig;
+
+    fn router(player: &Arc<ScenarioPlayer>) -> ACSARouter {
+        ACSARouter::new(
+            player.provider(AgentRole::MOSS),
+            player.provider(AgentRole::L6),
+            player.provider(AgentRole::Ultron),
+            player.provider(AgentRole::Omega),
+            ACSAConfig {
+                max_iterations: 3,
+                risk_threshold: 30,
+                enable_l6: false,
+                ..Default::default()
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_burst_then_recovers() {
+        let player = ScenarioPlayer::new(
+            MockScenario::from_yaml(
+                r#"
+name: burst
+rules:
+  - role: MOSS
+    contains: "HTTP"
+    steps:
+      - fail: rate_limited
+        times: 2
+      - text: "plan for {prompt}"
+        latency_ms: 5
+"#,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let moss = player.provider(AgentRole::MOSS);
+
+        for _ in 0..2 {
+            let err = moss.generate("build an http server", 100, 0.7).await.unwrap_err();
+            let acsa = AcsaError::from_provider(err);
+            assert_eq!(acsa.code(), Some(ErrorCode::ProviderRateLimited));
+            assert!(acsa.is_retryable());
+        }
+        let response = moss.generate("build an http server", 100, 0.7).await.unwrap();
+        assert_eq!(response.text, "plan for build an http server");
+        assert!(response.latency_ms >= 5);
+        // 耗尽后重复最后一步
+        assert!(moss.generate("build an http server", 100, 0.7).await.is_ok());
+
+        let stats = moss.stats().await;
+        assert_eq!((stats.failed_calls, stats.successful_calls), (2, 2));
+
+        // 未命中规则的提示词回显
+        let echo = moss.generate("something else", 100, 0.7).await.unwrap();
+        assert!(echo.text.starts_with("[MOSS Mock Response]"));
+    }
+
+    #[tokio::test]
+    async fn test_scripted_audit_drives_retry_loop() {
+        let player = ScenarioPlayer::new(
+            MockScenario::from_yaml(
+                r#"
+rules:
+  - role: Ultron
+    steps:
+      - text: "RISK_SCORE: 80\nIS_SAFE: false\nMITIGATION: add auth"
+      - text: "RISK_SCORE: 10\nIS_SAFE: true\nMITIGATION: none"
+default:
+  text: "ok: {role}"
+"#,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        let log = router(&player).execute("写一个HTTP服务器".to_string()).await.unwrap();
+        assert!(log.success);
+        assert_eq!(log.iterations, 2);
+        assert_eq!(log.audit_result.unwrap().risk_score, 10);
+        assert_eq!(log.final_output.as_deref(), Some("ok: Omega"));
+    }
+
+    #[tokio::test]
+    async fn test_dangerous_plan_hits_jarvis() {
+        let player = ScenarioPlayer::new(
+            MockScenario::from_yaml(
+                r#"
+rules:
+  - role: MOSS
+    pattern: "清理"
+    steps:
+      - text: "先执行 rm -rf / 再重装"
+"#,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        let log = router(&player).execute("帮我清理磁盘".to_string()).await.unwrap();
+        assert!(!log.success);
+        assert!(log.final_output.unwrap().contains("BLOCKED BY JARVIS"));
+    }
+
+    #[test]
+    fn test_invalid_scenarios_rejected() {
+        let both = MockScenario::from_yaml("rules:\n  - steps:\n      - text: hi\n        fail: timeout\n").unwrap();
+        assert!(ScenarioPlayer::new(both).is_err());
+
+        let empty = MockScenario::from_yaml("rules:\n  - name: nothing\n    steps: []\n").unwrap();
+        assert!(ScenarioPlayer::new(empty).map(|_| ()).unwrap_err().to_string().contains("nothing"));
+
+        let bad_regex = MockScenario::from_yaml("rules:\n  - pattern: \"(\"\n    steps:\n      - text: hi\n").unwrap();
+        assert!(ScenarioPlayer::new(bad_regex).is_err());
+
+        assert!(MockScenario::from_yaml("rules:\n  - steps:\n      - fail: melted\n").is_err());
+    }
+}
@@ -5,121 +5,266 @@ pub mod aegis;
 pub mod agent_extension;
 pub mod agent_state;
 pub mod aipc_controller;
+pub mod api_key_auth;
 pub mod api_manager;
+pub mod approval;
 pub mod audit_log;
 pub mod auth_system;
 pub mod auto_takeover;
+pub mod batch;
 pub mod behavior_monitor;
 pub mod cache_manager;
+pub mod checkpoint;
 pub mod claude;
 pub mod cognitive_cleaner;
+pub mod compliance_pack;
 pub mod concurrency;
 pub mod config_manager;
+pub mod config_schema;
+pub mod contract_analyzer;
+pub mod conversation;
+pub mod cost_estimator;
+pub mod dashboard;
 pub mod data_security;
 pub mod database;
+pub mod dead_letter;
+pub mod determinism;
 pub mod distributed;
 pub mod deepseek;
+pub mod embedding;
 pub mod emergency_log;
 pub mod event_bus;
+pub mod event_journal;
 pub mod error;
+pub mod error_presenter;
+pub mod execution_events;
+pub mod execution_store;
 pub mod gemini;
+pub mod git_workflow;
+pub mod grpc_server;
 pub mod http_server;
 pub mod i18n;
 pub mod image_generator;
 pub mod jarvis;
+pub mod jarvis_patterns;
+pub mod kill_switch;
+pub mod log_export;
+pub mod logging;
 pub mod lsp_server;
+pub mod mcp_client;
 pub mod mcp_server;
 pub mod metrics;
+pub mod mock_scenario;
 pub mod multimodal;
+pub mod notifier;
+pub mod offline;
+pub mod ollama;
+pub mod openai_compat;
 pub mod opencode;
 pub mod opencode_connector;
 pub mod openrouter;
 pub mod performance;
 pub mod personal_rules;
+pub mod pipeline;
+pub mod plan_diff;
+pub mod plugin_installer;
 pub mod plugin_system;
 pub mod prompt_manager;
 pub mod protocol;
+pub mod provider_fixtures;
 pub mod providers;
 pub mod rag_engine;
+pub mod rag_ingest;
 pub mod rate_limiter;
+#[cfg(feature = "redis")]
+pub mod redis_client;
+pub mod replay;
+pub mod repo_index;
+pub mod retry;
 pub mod router;
+pub mod sandbox;
+pub mod sandbox_workspace;
+pub mod secret_store;
+pub mod secrets;
+pub mod segmenter;
+pub mod self_update;
+pub mod session_archive;
 pub mod shadow_mode;
+pub mod shutdown;
 pub mod siliconflow;
 pub mod sovereignty;
 pub mod sosa_api_pool;
 pub mod sosa_crypto;
 pub mod sosa_learning;
+pub mod spreadsheet;
 pub mod task_tracker;
+pub mod telemetry;
 pub mod terminal_server;
+pub mod test_parser;
 pub mod types;
+pub mod vector_store;
 pub mod voice_processor;
+pub mod web_dashboard;
+#[cfg(feature = "wasm")]
+pub mod wasm_plugin;
 pub mod workflow_engine;
+pub mod workspace;
 
 pub use addressing_system::{AddressingConfig, AddressingMode, AddressingStyle, AddressingSystem};
-pub use aegis::{AegisModule, DefenseDocType, DefenseDocument};
+pub use aegis::{
+    AegisAgents, AegisModule, AegisPipelineConfig, CaseFacts, ClauseRiskLevel, DefenseDocType, DefenseDocument,
+    DocumentClause, SourceRef,
+};
 pub use agent_extension::{
-    AgentApiConfig, AgentCallRecord, AgentExtensionManager, AgentInfo, AgentList, AgentMetrics,
-    AgentType, CustomAgent, DiminishingReturns, Recommendation,
+    AgentApiConfig, AgentCallRecord, AgentCallStats, AgentExtensionManager, AgentInfo, AgentList, AgentMetrics,
+    AgentType, CallOutcome, CustomAgent, DiminishingReturns, OutcomeTracker, Recommendation, ThrottleConfig, ThrottleDecision,
 };
 pub use agent_state::{AgentStateConfig, AgentStateManager, LongTermMemory, Message, SessionState, StateSnapshot, UserPreference};
 pub use aipc_controller::{AipcController, HardwareCommand, HardwareStatus, HardwareType};
-pub use api_manager::{ApiCallRecord, ApiKeyConfig, ApiManager, ApiProvider, ProviderStats};
-pub use audit_log::{AuditEvent, AuditEventType, AuditLogger, AuditLogConfig, AuditQuery, AuditSeverity, ComplianceReport};
-pub use auth_system::{AuthConfig, AuthManager, Claims, SessionInfo, TokenPair};
+pub use api_key_auth::{presented_api_key, ApiKeyInfo, ApiKeyStore, API_KEY_HEADER, API_KEY_PREFIX};
+pub use api_manager::{
+    ApiCallRecord, ApiKeyConfig, ApiManager, ApiProvider, BudgetPeriod, BudgetPolicy, BudgetState, BudgetStatus, KeyUsage,
+    ModelRate, PricingTable, ProviderStats, TokenRate, BUDGET_ALERT_EVENT,
+};
+pub use approval::{ApprovalRequest, ApprovalStatus, ApprovalStore, DEFAULT_APPROVAL_PATH};
+pub use audit_log::{AuditEvent, AuditEventType, AuditLogger, AuditLogConfig, AuditQuery, AuditSeverity, ComplianceReport, WORKSPACE_METADATA_KEY};
+pub use auth_system::{
+    AuthConfig, AuthManager, Claims, Permission, Role, SessionInfo, TokenPair, UserAccount, JWT_SECRET_ACCOUNT, JWT_SECRET_ENV,
+};
 pub use auto_takeover::{AutoTakeoverEngine, TakeoverAction, TakeoverPolicy, TakeoverResult, TakeoverStats};
+pub use batch::{AuditOutcome, BatchResult, BatchRunner, BatchSummary, BatchTask, DEFAULT_BATCH_CONCURRENCY};
 pub use behavior_monitor::{
     BehaviorContext, BehaviorMonitor, BehaviorMonitorConfig, BehaviorPattern, BehaviorProfile,
     BehaviorType, ChatIntent, TakeoverSuggestion, UserBehaviorEvent,
 };
-pub use cache_manager::{CacheManager, CacheType, CacheUsage, CleanupPolicy, CleanupStats};
+pub use cache_manager::{
+    CacheManager, CacheType, CacheUsage, CleanupPolicy, CleanupStats, ResponseCache, ResponseCachePolicy, ResponseCacheStats,
+};
+pub use checkpoint::{CheckpointStage, CheckpointStore, ExecutionCheckpoint, DEFAULT_CHECKPOINT_DIR};
 pub use claude::ClaudeProvider;
 pub use data_security::{
     DataCategory, DataSecurityManager, FileAccessPermission, ImageFormat, PermissionRequest,
     PermissionType, ResourceStats, ResourceUsage, SanitizationRule, SecureFileContent,
     SecureImageContent, SensitivityLevel, JARVIS_EXPLANATION,
 };
-pub use cognitive_cleaner::{ChunkTag, CleanedIntent, CognitiveCleaner, DictionaryData, DictionaryFormat, SemanticChunk};
-pub use concurrency::{AsyncTask, ConcurrencyConfig, ConcurrencyManager, DistributedLock as ConcurrentLock, TaskPriority as ConcurrentTaskPriority, TaskResult};
-pub use config_manager::{ConfigChange, ConfigEntry, ConfigListener, ConfigManager, ConfigManagerConfig, ConfigValue, Environment};
+pub use cognitive_cleaner::{
+    ChunkTag, CleanedIntent, CleaningEntry, CleaningReport, CleaningRule, CognitiveCleaner, DictionaryData,
+    DictionaryFormat, SafetyScoreWeights, SafetySignals, SemanticChunk, CLEANING_REPORT_RESOURCE_TYPE,
+};
+pub use compliance_pack::{
+    ComplianceAnnex, ComplianceEngine, ComplianceFinding, CompliancePack, ComplianceRule, ComplianceVerdict,
+};
+pub use concurrency::{AsyncTask, BackpressurePolicy, CancellationToken, ConcurrencyConfig, ConcurrencyManager, DistributedLock as ConcurrentLock, ExecutorMetrics, SaturationMetrics, TaskContext, TaskHandle, TaskPriority as ConcurrentTaskPriority, TaskResult};
+pub use config_manager::{ConfigChange, ConfigDiff, ConfigEntry, ConfigLayer, ConfigListener, ConfigManager, ConfigManagerConfig, ConfigValue, Environment, SectionConfigListener, SettingGuard, ENVIRONMENT_ENV};
+pub use config_schema::{ConfigIssue, ConfigIssueKind, ConfigSchema, SchemaField, ValueKind};
+pub use contract_analyzer::{ClauseFinding, ContractAnalyzer, ContractClause, ContractReport, RiskRule, RiskRuleLibrary};
+pub use conversation::{ChatCommand, Conversation, ConversationCost, DEFAULT_CONTEXT_TURNS};
+pub use cost_estimator::{
+    estimate_tokens, AgentPricing, Bounds, CostEstimate, CostEstimator, DryRunReport, ModelPricing, StageEstimate,
+};
+pub use dashboard::{DashboardState, StageProgress, StageStatus, ThemeColor, VerdictEntry};
 pub use database::{DatabaseConfig, DatabaseManager, DatabaseTransaction, DatabaseType, PoolStats, QueryBuilder, QueryRow};
-pub use distributed::{ClusterManager, ClusterStats, DistributedLock as RedisLock, LockConfig, NodeRole, NodeStatus, ServiceDiscovery, ServiceDiscoveryConfig, ServiceInstance};
+pub use dead_letter::{DeadLetter, DeadLetterQueue, DEFAULT_DEAD_LETTER_PATH};
+pub use determinism::SeededRng;
+pub use distributed::{ClusterManager, ClusterStats, ConnectionGuard, CoordinationStore, DistributedLock as RedisLock, LoadBalanceStrategy, LoadBalancerConfig, LockConfig, MemoryCoordinationStore, NodeRole, NodeStatus, ServiceDiscovery, ServiceDiscoveryConfig, ServiceInstance, ROLE_CHANGED_EVENT};
+#[cfg(feature = "redis")]
+pub use distributed::RedisCoordinationStore;
 pub use deepseek::DeepSeekProvider;
-pub use emergency_log::{EmergencyLogConfig, EmergencyLogger, LogEntry, LogEntryType};
-pub use event_bus::{Event, EventBus, EventBusConfig, EventHandler, EventType, LoggingEventHandler, MetricsEventHandler};
-pub use error::{AcsaError, AcsaResult, ErrorCode, ErrorSeverity};
+pub use embedding::{cosine_similarity, create_embedding_provider, CachedEmbedder, EmbeddingCacheStats, EmbeddingProvider, LocalEmbeddingProvider, MockEmbeddingProvider, OpenAIEmbeddingProvider};
+pub use emergency_log::{EmergencyLogConfig, EmergencyLogger, LogEntry, LogEntryType, SessionSummary, SupportBundle};
+pub use event_bus::{
+    Event, EventBus, EventBusConfig, EventHandler, EventType, LoggingEventHandler, MetricsEventHandler, ReplayReport,
+};
+pub use event_journal::{EventJournal, JournalEntry, DEFAULT_EVENT_JOURNAL_DIR};
+pub use error::{AcsaError, AcsaResult, ErrorCode, ErrorFrame, ErrorReport, ErrorSeverity};
+pub use error_presenter::{ErrorPresenter, PresentedError};
+pub use execution_events::{parse_last_event_id, ExecutionEventHub, FeedEvent, FeedSubscription};
+pub use execution_store::{ExecutionPage, ExecutionQuery, ExecutionRecord, ExecutionStore, ExecutionSummary, RetentionPolicy};
 pub use gemini::GeminiProvider;
+pub use git_workflow::{ForgeConfig, GitForge, GitWorkflow, GitWorkflowConfig, IterationCommit, MissionBranch};
 pub use openrouter::OpenRouterProvider;
-pub use http_server::{ApiResponse, HttpServer, HttpServerConfig, ServerState};
+pub use grpc_server::{
+    AcsaGrpcService, ComponentHealthReply, ExecuteEvent, ExecuteReply, ExecuteRequest, ExecutionLogReply, GrpcCode, GrpcStatus, HealthReply,
+    GRPC_SERVICE_NAME,
+};
+#[cfg(feature = "grpc")]
+pub use grpc_server::serve_grpc;
+pub use http_server::{ApiResponse, ChatCompletionReply, HttpServer, HttpServerConfig, ServerState};
 pub use i18n::{I18n, Language, TranslationKey};
 pub use image_generator::{GenerationConfig, ImageGenerator};
-pub use jarvis::{DangerousOp, JarvisCircuitBreaker, JarvisVerdict};
-pub use lsp_server::{AcsaLspServer, CompletionItem, CompletionItemKind, Diagnostic as LspDiagnostic, DiagnosticSeverity, Document as LspDocument, LspServerConfig, Position, Range};
+pub use jarvis::{rule_pack_signature_path, BunkerMode, DangerousOp, JarvisCircuitBreaker, JarvisManager, JarvisVerdict, RulePackConfig, RulePackInfo};
+pub use jarvis_patterns::{MatchKind, NormalizedText, PatternMatch, PatternRule, PatternSet};
+pub use kill_switch::{ClusterFlagStore, KillSwitch, KillSwitchConfig, KillSwitchSource, KillSwitchState, MemoryFlagStore, PausedOperation};
+pub use log_export::ExportFormat;
+pub use logging::{LogFormat, LOG_FORMAT_ENV};
+pub use lsp_server::{
+    AcsaLspServer, CodeAction as LspCodeAction, CompletionItem, CompletionItemKind, Diagnostic as LspDiagnostic, DiagnosticSeverity, Document as LspDocument,
+    LspServerConfig, Position, Range, TextEdit,
+};
+pub use mcp_client::{
+    parse_tool_calls, DiscoveredTool, McpClient, McpClientRegistry, McpServerConfig, McpServersFile, McpToolOutput, ToolCallRequest,
+    DEFAULT_MCP_SERVERS_PATH,
+};
 pub use mcp_server::{
-    AcsaMcpServer, ClientInfo, McpPrompt, McpRequest, McpResource, McpResponse, McpTool,
-    McpToolHandler, create_acsa_mcp_server,
+    AcsaExecuteHandler, AcsaMcpServer, AcsaMcpState, ClientInfo, JsonRpcError, McpProgress, McpPrompt, McpRequest, McpResource,
+    McpResourceHandler, McpResponse, McpTool, McpToolHandler, create_acsa_mcp_server, create_acsa_mcp_server_with_protocols,
+    create_acsa_mcp_server_with_state, register_acsa_execute_tool,
 };
 pub use metrics::{ApplicationMetrics, ComponentHealth, HealthCheck, HealthStatus, MetricType, MetricValue, MetricsCollector, SystemMetrics};
+pub use mock_scenario::{ExhaustPolicy, FailureMode, MockScenario, ScenarioPlayer, ScenarioRule, ScenarioStep};
 pub use multimodal::{ModalityType, MultimodalInput, MultimodalMetadata, MultimodalProcessor};
+pub use notifier::{DeliveryReport, EmailChannel, EmailConfig, LogChannel, Notification, NotificationChannel, NotificationKind, NotificationPriority, Notifier, NotifierConfig, NotifyOutcome, QuietHours, SlackChannel, TelegramChannel};
+pub use offline::OfflineConfig;
+pub use ollama::OllamaProvider;
+pub use openai_compat::{ChatCompletionChunk, ChatCompletionRequest, ChatCompletionResponse, CompletionStream, ModelList};
 pub use opencode::OpenCodeExecutor;
 pub use opencode_connector::{
-    CodeStats, ExecutionReceipt, MissionPack, OpenCodeConfig, OpenCodeConnector, TestResults,
+    CodeStats, ExecutionReceipt, MissionPack, OpenCodeConfig, OpenCodeConnector, TestFailure, TestResults,
 };
-pub use performance::{CacheWarmer, EventBatcher, GLOBAL_OPTIMIZER, GLOBAL_SCHEDULER, PerformanceOptimizer, PhaseTracker, PriorityScheduler, StartupMetrics};
+pub use performance::{BatcherConfig, BatchingMetrics, CacheWarmer, EventBatcher, GLOBAL_OPTIMIZER, GLOBAL_SCHEDULER, InitFuture, LazySubsystem, PerformanceOptimizer, PhaseKind, PhaseRecord, PhaseTracker, PriorityScheduler, StartupMetrics};
 pub use personal_rules::{PersonalRule, PersonalRulesManager, RuleConflict, RuleType, RulesStats};
-pub use plugin_system::{Plugin, PluginConfig, PluginHandler, PluginMetadata, PluginRequest, PluginResponse, PluginState, PluginStats, PluginSystem, PluginSystemConfig, PluginType, ResourceLimits};
+pub use pipeline::{PipelineConfig, PipelineStage, StageOutput};
+pub use plan_diff::{PlanDiff, StepChange};
+pub use plugin_installer::{InstallOptions, InstalledPlugin, PluginInstaller, PluginManifest, PLUGIN_MANIFEST};
+pub use plugin_system::{AgentPlugin, AgentPluginOutput, HostTool, Plugin, PluginConfig, PluginHandler, PluginMetadata, PluginRequest, PluginResponse, PluginState, PluginStats, PluginSystem, PluginSystemConfig, PluginType, ResourceLimits, AGENT_PLUGIN_API_VERSION, DEFAULT_PLUGINS_DIR};
 pub use prompt_manager::{AbTestGroup, AbTestMetrics, FewShotExample, PromptBuildOptions, PromptManager, PromptManagerConfig, PromptTemplate};
-pub use protocol::{AgentWeights, Protocol, ProtocolConfig, ProtocolManager};
-pub use providers::{create_provider, create_provider_with_type, ModelProvider, ProviderType};
-pub use rag_engine::{ChunkingStrategy, Document as RagDocument, DocumentChunk, EmbeddingModel, RagConfig, RagEngine, RagStats, RetrievalMode, RetrievalResult};
-pub use rate_limiter::{RateLimitLevel, RateLimitRecord, RateLimitResult, RateLimitRule, RateLimitStrategy, RateLimiter, RateLimiterConfig};
+pub use protocol::{AgentWeights, CustomProtocolDef, Protocol, ProtocolConfig, ProtocolConfigChange, ProtocolManager, ProtocolWatcher, DEFAULT_PROTOCOL_DIR, PROTOCOL_CONFIG_CHANGE_EVENT};
+pub use provider_fixtures::{FixtureOutcome, FixtureRecord, FixtureRecorder, FixtureRedactor, FixtureReplayer, FixtureSet};
+pub use providers::{create_custom_agent_provider, create_local_provider, create_provider, probe_local_provider, create_provider_with_type, ModelProvider, ProviderType};
+pub use rag_engine::{format_citations, ChunkingStrategy, Citation, Document as RagDocument, DocumentChunk, EmbeddingModel, RagConfig, RagEngine, RagStats, RetrievalMode, RetrievalResult, RetrievedContext};
+pub use rag_ingest::{extract_pdf_text, IngestConfig, IngestReport, IngestWatcher};
+pub use rate_limiter::{
+    MemoryRateLimitStore, RateLimitLevel, RateLimitRecord, RateLimitResult, RateLimitRule, RateLimitStore, RateLimitStrategy,
+    RateLimiter, RateLimiterConfig,
+};
+#[cfg(feature = "redis")]
+pub use rate_limiter::RedisRateLimitStore;
+pub use replay::{ExecutionTranscript, RecordedCall, ReplayCursor, TOOL_STAGE_PREFIX};
+pub use repo_index::{FileEntry, IterationTouch, MissionContext, RepoIndex, RepoIndexConfig, Symbol};
+pub use retry::RetryPolicy;
 pub use router::ACSARouter;
+pub use sandbox::{
+    Sandbox, SandboxBackend, SandboxLimits, SandboxMount, SandboxOutput, SandboxPolicy, DEFAULT_ENV_PASSTHROUGH,
+};
+pub use sandbox_workspace::{FileChangeKind, FileDiff, SandboxWorkspace, MAX_DIFF_FILE_BYTES};
+pub use secret_store::{
+    configured_secret_store, platform_secret_store, MacKeychainStore, MemorySecretStore, SecretServiceStore, SecretStore,
+    WindowsCredentialStore, SECRET_SERVICE, SECRET_STORE_ENV,
+};
+pub use secrets::{EncryptedFileSecretBackend, EnvSecretBackend, KeyringSecretBackend, SecretBackend, SecretResolver};
+pub use segmenter::{default_segmenter, RuleSegmenter, Segmenter};
+#[cfg(feature = "jieba")]
+pub use segmenter::JiebaSegmenter;
+pub use self_update::{BinaryBackup, ReleaseArtifact, ReleaseChannel, ReleaseManifest, SelfUpdater, StartupAction, UpdateConfig, UpdateOutcome, UpdateState};
+pub use session_archive::{ArchiveManifest, ProtocolChange, SessionCosts, SessionRecord, SessionStore};
 pub use shadow_mode::{AccessAudit, MaskingConfig, MaskingStrategy, MaskedData, PiiDetection, PiiType, ShadowModeConfig, ShadowModeEngine};
+pub use shutdown::{HookOutcome, ShutdownConfig, ShutdownCoordinator, ShutdownPhase, ShutdownReport, WorkGuard};
 pub use siliconflow::SiliconFlowProvider;
 pub use sovereignty::{
     AntiAddictionConfig, BioActivity, ChartDataPoint, CircuitBreakerConfig, DailyUsage,
     DecisionEvent, DecisionType, DoseMeter, DoseStats, ExecCircuitBreaker, InsightLevel,
-    RiskLevel, SovereigntyConfig, SovereigntySystem, UsageAnalyzer, UsageInsight, UsageSession,
-    UsageTracker, WeeklyUsage, SOVEREIGNTY, generate_bio_activity_report, generate_usage_report,
+    RiskLevel, SovereigntyConfig, SovereigntySnapshot, SovereigntyState, SovereigntySystem, UsageAnalyzer, UsageInsight, UsageSession,
+    UsageTracker, WeeklyUsage, DEFAULT_SOVEREIGNTY_STATE_PATH, SOVEREIGNTY, generate_bio_activity_report, generate_usage_report,
 };
 pub use sosa_api_pool::{
     ApiCallEvent, ApiEndpoint, ApiErrorType, ApiProviderType, Attractor, BinaryTwin,
@@ -130,7 +275,19 @@ pub use sosa_learning::{
     EventOutcome, KnowledgeNode, LearningConfig, LearningEvent, LearningSummary, SosaLearningEngine,
 };
 pub use task_tracker::{Task, TaskPriority, TaskStatus, TaskTracker};
+pub use telemetry::{
+    AttributeValue, InMemoryExporter, OtlpHttpExporter, Span, SpanData, SpanExporter, SpanKind, TelemetryConfig, Tracer,
+};
 pub use terminal_server::{ClientConnection, DefaultHandler, MessageHandler, ServerConfig, TerminalServer, WsMessage};
+pub use test_parser::{parse_test_output, TestFormat, TestRunner};
 pub use types::*;
+pub use vector_store::{create_vector_store, MemoryVectorStore, QdrantVectorStore, SqliteVectorStore, VectorStore, VectorStoreConfig};
 pub use voice_processor::{SttResult, VoiceConfig, VoiceProcessor};
-pub use workflow_engine::{Workflow, WorkflowEngine, WorkflowStep};
+#[cfg(feature = "wasm")]
+pub use wasm_plugin::WasmPluginHandler;
+pub use web_dashboard::{dashboard_asset, DashboardSnapshot, SovereigntySummary, SovereigntyTrend, TrendPoint};
+pub use workflow_engine::{
+    CronSchedule, DueRun, MissedRunPolicy, ScheduleTarget, ScheduledJob, Scheduler, Workflow, WorkflowEngine, WorkflowStep,
+    DEFAULT_SCHEDULE_PATH,
+};
+pub use workspace::{Workspace, WorkspaceConfig, WorkspaceManager, WorkspaceSummary, DEFAULT_WORKSPACE};
@@ -0,0 +1,607 @@
+// Notifier - 通知渠道抽象
+// 预算告警、熔断触发、工作流审批、防沉迷提醒统一经由 Notifier 发出
+//
+// 核心功能：
+// 1. 可插拔渠道：日志（默认）、邮件（SMTP）、Slack（Incoming Webhook）、Telegram（Bot API）
+// 2. 按事件类型路由到不同渠道，未配置路由时使用默认渠道
+// 3. 免打扰时段：非紧急通知暂存，时段结束后随下一条通知补发（或 flush_deferred）
+// 4. 去重窗口：同一 dedupe_key 在窗口内只发送一次，避免轮询场景刷屏
+// 5. 离线模式下跳过需要外网的渠道
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Timelike, Utc};
+use lettre::message::{header::ContentType, Mailbox};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+
+use super::offline;
+
+/// 通知事件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    /// 预算告警
+    BudgetAlert,
+    /// 熔断触发（Jarvis / 主权熔断 / 决策死锁）
+    CircuitBreaker,
+    /// 工作流 / 自动接管待审批
+    WorkflowApproval,
+    /// 防沉迷提醒
+    AntiAddiction,
+}
+
+impl NotificationKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            NotificationKind::BudgetAlert => "budget_alert",
+            NotificationKind::CircuitBreaker => "circuit_breaker",
+            NotificationKind::WorkflowApproval => "workflow_approval",
+            NotificationKind::AntiAddiction => "anti_addiction",
+        }
+    }
+
+    pub fn icon(&self) -> &'static str {
+        match self {
+            NotificationKind::BudgetAlert => "💰",
+            NotificationKind::CircuitBreaker => "🛑",
+            NotificationKind::WorkflowApproval => "📝",
+            NotificationKind::AntiAddiction => "⏳",
+        }
+    }
+}
+
+/// 通知优先级（Critical 无视免打扰时段）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationPriority {
+    Info,
+    Warning,
+    Critical,
+}
+
+/// 一条通知
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notification {
+    pub kind: NotificationKind,
+    pub priority: NotificationPriority,
+    pub title: String,
+    pub body: String,
+    /// 去重键（窗口内相同键只发送一次）
+    pub dedupe_key: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Notification {
+    pub fn new(kind: NotificationKind, title: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            kind,
+            priority: NotificationPriority::Info,
+            title: title.into(),
+            body: body.into(),
+            dedupe_key: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    pub fn with_priority(mut self, priority: NotificationPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn with_dedupe_key(mut self, key: impl Into<String>) -> Self {
+        self.dedupe_key = Some(key.into());
+        self
+    }
+
+    /// 纯文本渲染（各渠道共用）
+    pub fn render(&self) -> String {
+        format!("{} [{}] {}\n{}", self.kind.icon(), self.kind.label(), self.title, self.body)
+    }
+}
+
+/// 通知渠道
+#[async_trait]
+pub trait NotificationChannel: Send + Sync {
+    /// 渠道名（路由配置中引用）
+    fn name(&self) -> &str;
+
+    /// 是否需要外网（离线模式下跳过）
+    fn is_remote(&self) -> bool {
+        true
+    }
+
+    async fn send(&self, notification: &Notification) -> Result<()>;
+}
+
+/// 日志渠道（默认，始终可用）
+pub struct LogChannel;
+
+#[async_trait]
+impl NotificationChannel for LogChannel {
+    fn name(&self) -> &str {
+        "log"
+    }
+
+    fn is_remote(&self) -> bool {
+        false
+    }
+
+    async fn send(&self, notification: &Notification) -> Result<()> {
+        match notification.priority {
+            NotificationPriority::Info => info!("🔔 {}", notification.render()),
+            NotificationPriority::Warning | NotificationPriority::Critical => warn!("🔔 {}", notification.render()),
+        }
+        Ok(())
+    }
+}
+
+/// Slack Incoming Webhook
+pub struct SlackChannel {
+    webhook_url: String,
+    client: reqwest::Client,
+}
+
+impl SlackChannel {
+    pub fn new(webhook_url: impl Into<String>) -> Self {
+        Self {
+            webhook_url: webhook_url.into(),
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl NotificationChannel for SlackChannel {
+    fn name(&self) -> &str {
+        "slack"
+    }
+
+    async fn send(&self, notification: &Notification) -> Result<()> {
+        let text = format!(
+            "{} *{}*\n{}",
+            notification.kind.icon(),
+            notification.title,
+            notification.body
+        );
+        self.client
+            .post(&self.webhook_url)
+            .json(&serde_json::json!({ "text": text }))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// Telegram Bot
+pub struct TelegramChannel {
+    bot_token: String,
+    chat_id: String,
+    api_base: String,
+    client: reqwest::Client,
+}
+
+impl TelegramChannel {
+    pub fn new(bot_token: impl Into<String>, chat_id: impl Into<String>) -> Self {
+        Self {
+            bot_token: bot_token.into(),
+            chat_id: chat_id.into(),
+            api_base: "https://api.telegram.org".to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// 自建 Bot API 服务器
+    pub fn with_api_base(mut self, api_base: impl Into<String>) -> Self {
+        self.api_base = api_base.into();
+        self
+    }
+}
+
+#[async_trait]
+impl NotificationChannel for TelegramChannel {
+    fn name(&self) -> &str {
+        "telegram"
+    }
+
+    async fn send(&self, notification: &Notification) -> Result<()> {
+        let url = format!("{}/bot{}/sendMessage", self.api_base.trim_end_matches('/'), self.bot_token);
+        self.client
+            .post(url)
+            .json(&serde_json::json!({ "chat_id": self.chat_id, "text": notification.render() }))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// SMTP 邮件配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmailConfig {
+    pub smtp_host: String,
+    pub smtp_port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub from: String,
+    pub to: Vec<String>,
+}
+
+/// SMTP 邮件渠道（STARTTLS）
+pub struct EmailChannel {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+}
+
+impl EmailChannel {
+    pub fn new(config: EmailConfig) -> Result<Self> {
+        let mut builder = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.smtp_host)?.port(config.smtp_port);
+        if let (Some(username), Some(password)) = (config.username, config.password) {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+        let to = config
+            .to
+            .iter()
+            .map(|address| address.parse().with_context(|| format!("Invalid recipient: {}", address)))
+            .collect::<Result<Vec<Mailbox>>>()?;
+        if to.is_empty() {
+            return Err(anyhow!("Email channel needs at least one recipient"));
+        }
+
+        Ok(Self {
+            transport: builder.build(),
+            from: config.from.parse().with_context(|| format!("Invalid sender: {}", config.from))?,
+            to,
+        })
+    }
+}
+
+#[async_trait]
+impl NotificationChannel for EmailChannel {
+    fn name(&self) -> &str {
+        "email"
+    }
+
+    async fn send(&self, notification: &Notification) -> Result<()> {
+        let mut message = Message::builder()
+            .from(self.from.clone())
+            .subject(format!("[ACSA] {} {}", notification.kind.icon(), notification.title))
+            .header(ContentType::TEXT_PLAIN);
+        for recipient in &self.to {
+            message = message.to(recipient.clone());
+        }
+        self.transport.send(message.body(notification.body.clone())?).await?;
+        Ok(())
+    }
+}
+
+/// 免打扰时段（按小时，支持跨午夜，如 22 → 7）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuietHours {
+    pub start_hour: u32,
+    pub end_hour: u32,
+    /// 本地时区相对 UTC 的偏移（分钟），如 UTC+8 为 480
+    #[serde(default)]
+    pub utc_offset_minutes: i32,
+}
+
+impl QuietHours {
+    pub fn contains(&self, at: DateTime<Utc>) -> bool {
+        let hour = (at + Duration::minutes(self.utc_offset_minutes as i64)).hour();
+        if self.start_hour <= self.end_hour {
+            (self.start_hour..self.end_hour).contains(&hour)
+        } else {
+            hour >= self.start_hour || hour < self.end_hour
+        }
+    }
+}
+
+/// 通知配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotifierConfig {
+    pub enabled: bool,
+    /// 事件类型 → 渠道名
+    pub routes: HashMap<NotificationKind, Vec<String>>,
+    /// 未配置路由的事件类型使用的渠道
+    pub default_channels: Vec<String>,
+    pub quiet_hours: Option<QuietHours>,
+    /// 去重窗口（秒）
+    pub dedupe_window_secs: u64,
+}
+
+impl Default for NotifierConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            routes: HashMap::new(),
+            default_channels: vec!["log".to_string()],
+            quiet_hours: None,
+            dedupe_window_secs: 300,
+        }
+    }
+}
+
+/// 单条通知的投递结果
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DeliveryReport {
+    pub delivered: Vec<String>,
+    /// (渠道名, 错误)
+    pub failed: Vec<(String, String)>,
+}
+
+/// 通知处理结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum NotifyOutcome {
+    Sent(DeliveryReport),
+    /// 免打扰时段内暂存
+    Deferred,
+    /// 去重窗口内已发送过
+    Duplicate,
+    /// 通知已关闭
+    Disabled,
+}
+
+/// 通知中心
+pub struct Notifier {
+    config: NotifierConfig,
+    channels: HashMap<String, Arc<dyn NotificationChannel>>,
+    deferred: Mutex<Vec<Notification>>,
+    recent: Mutex<HashMap<String, DateTime<Utc>>>,
+}
+
+impl Notifier {
+    pub fn new(config: NotifierConfig) -> Self {
+        let log: Arc<dyn NotificationChannel> = Arc::new(LogChannel);
+        Self {
+            config,
+            channels: HashMap::from([(log.name().to_string(), log)]),
+            deferred: Mutex::new(Vec::new()),
+            recent: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 注册渠道（同名渠道被替换）
+    pub fn with_channel(mut self, channel: Arc<dyn NotificationChannel>) -> Self {
+        self.channels.insert(channel.name().to_string(), channel);
+        self
+    }
+
+    /// 从环境变量注册渠道，已配置的渠道自动加入默认渠道
+    ///
+    /// - Slack: `ACSA_SLACK_WEBHOOK_URL`
+    /// - Telegram: `ACSA_TELEGRAM_BOT_TOKEN` + `ACSA_TELEGRAM_CHAT_ID`
+    /// - 邮件: `ACSA_SMTP_HOST` + `ACSA_EMAIL_FROM` + `ACSA_EMAIL_TO`（逗号分隔），
+    ///   可选 `ACSA_SMTP_PORT`（默认 587）、`ACSA_SMTP_USERNAME`、`ACSA_SMTP_PASSWORD`
+    pub fn from_env(config: NotifierConfig) -> Result<Self> {
+        let env = |key: &str| std::env::var(key).ok().filter(|v| !v.trim().is_empty());
+        let mut channels: Vec<Arc<dyn NotificationChannel>> = Vec::new();
+
+        if let Some(url) = env("ACSA_SLACK_WEBHOOK_URL") {
+            channels.push(Arc::new(SlackChannel::new(url)));
+        }
+        if let (Some(token), Some(chat_id)) = (env("ACSA_TELEGRAM_BOT_TOKEN"), env("ACSA_TELEGRAM_CHAT_ID")) {
+            channels.push(Arc::new(TelegramChannel::new(token, chat_id)));
+        }
+        if let (Some(host), Some(from), Some(to)) = (env("ACSA_SMTP_HOST"), env("ACSA_EMAIL_FROM"), env("ACSA_EMAIL_TO")) {
+            channels.push(Arc::new(EmailChannel::new(EmailConfig {
+                smtp_host: host,
+                smtp_port: env("ACSA_SMTP_PORT").map(|p| p.parse()).transpose()?.unwrap_or(587),
+                username: env("ACSA_SMTP_USERNAME"),
+                password: env("ACSA_SMTP_PASSWORD"),
+                from,
+                to: to.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect(),
+            })?));
+        }
+
+        let mut notifier = Self::new(config);
+        for channel in channels {
+            let name = channel.name().to_string();
+            if !notifier.config.default_channels.contains(&name) {
+                notifier.config.default_channels.push(name);
+            }
+            notifier = notifier.with_channel(channel);
+        }
+        Ok(notifier)
+    }
+
+    /// 事件类型对应的渠道
+    pub fn channels_for(&self, kind: NotificationKind) -> &[String] {
+        self.config.routes.get(&kind).unwrap_or(&self.config.default_channels)
+    }
+
+    /// 发送通知（去重 → 免打扰 → 路由投递）
+    pub async fn notify(&self, notification: Notification) -> NotifyOutcome {
+        if !self.config.enabled {
+            return NotifyOutcome::Disabled;
+        }
+
+        if let Some(key) = &notification.dedupe_key {
+            let mut recent = self.recent.lock().await;
+            let window = Duration::seconds(self.config.dedupe_window_secs as i64);
+            recent.retain(|_, sent_at| notification.created_at - *sent_at < window);
+            if recent.contains_key(key) {
+                debug!("Duplicate notification suppressed: {}", key);
+                return NotifyOutcome::Duplicate;
+            }
+            recent.insert(key.clone(), notification.created_at);
+        }
+
+        if self.is_quiet(notification.created_at) && notification.priority < NotificationPriority::Critical {
+            debug!("Notification deferred (quiet hours): {}", notification.title);
+            self.deferred.lock().await.push(notification);
+            return NotifyOutcome::Deferred;
+        }
+
+        self.flush_deferred().await;
+        NotifyOutcome::Sent(self.deliver(&notification).await)
+    }
+
+    /// 后台发送（调用方不等待渠道网络往返）
+    pub fn notify_detached(self: &Arc<Self>, notification: Notification) {
+        let notifier = self.clone();
+        tokio::spawn(async move {
+            notifier.notify(notification).await;
+        });
+    }
+
+    /// 免打扰时段已结束时补发暂存的通知，返回补发条数
+    pub async fn flush_deferred(&self) -> usize {
+        if self.is_quiet(Utc::now()) {
+            return 0;
+        }
+        let deferred = std::mem::take(&mut *self.deferred.lock().await);
+        for notification in &deferred {
+            self.deliver(notification).await;
+        }
+        if !deferred.is_empty() {
+            info!("🔔 Delivered {} notifications held during quiet hours", deferred.len());
+        }
+        deferred.len()
+    }
+
+    fn is_quiet(&self, at: DateTime<Utc>) -> bool {
+        self.config.quiet_hours.as_ref().is_some_and(|quiet| quiet.contains(at))
+    }
+
+    async fn deliver(&self, notification: &Notification) -> DeliveryReport {
+        let mut report = DeliveryReport::default();
+        for name in self.channels_for(notification.kind) {
+            let Some(channel) = self.channels.get(name) else {
+                report.failed.push((name.clone(), "channel not configured".to_string()));
+                continue;
+            };
+            if channel.is_remote() && offline::is_offline() {
+                debug!("Skipping {} notification channel in offline mode", name);
+                continue;
+            }
+            match channel.send(notification).await {
+                Ok(()) => report.delivered.push(name.clone()),
+                Err(e) => {
+                    warn!("⚠️  Notification via {} failed: {}", name, e);
+                    report.failed.push((name.clone(), e.to_string()));
+                }
+            }
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    /// 记录收到的通知
+    struct RecordingChannel {
+        name: String,
+        fail: bool,
+        received: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl RecordingChannel {
+        fn new(name: &str, fail: bool) -> Arc<Self> {
+            Arc::new(Self {
+                name: name.to_string(),
+                fail,
+                received: std::sync::Mutex::new(Vec::new()),
+            })
+        }
+
+        fn titles(&self) -> Vec<String> {
+            self.received.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl NotificationChannel for RecordingChannel {
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        fn is_remote(&self) -> bool {
+            false
+        }
+
+        async fn send(&self, notification: &Notification) -> Result<()> {
+            if self.fail {
+                return Err(anyhow!("unreachable"));
+            }
+            self.received.lock().unwrap().push(notification.title.clone());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_routing_and_dedupe() {
+        let (ops, user, broken) = (
+            RecordingChannel::new("ops", false),
+            RecordingChannel::new("user", false),
+            RecordingChannel::new("broken", true),
+        );
+        let notifier = Notifier::new(NotifierConfig {
+            routes: HashMap::from([
+                (NotificationKind::BudgetAlert, vec!["ops".to_string(), "broken".to_string()]),
+                (NotificationKind::AntiAddiction, vec!["user".to_string()]),
+            ]),
+            default_channels: vec!["ops".to_string(), "missing".to_string()],
+            ..Default::default()
+        })
+        .with_channel(ops.clone())
+        .with_channel(user.clone())
+        .with_channel(broken.clone());
+
+        let outcome = notifier
+            .notify(Notification::new(NotificationKind::BudgetAlert, "80% of budget used", "$8.00 / $10.00").with_dedupe_key("budget:80"))
+            .await;
+        assert_eq!(
+            outcome,
+            NotifyOutcome::Sent(DeliveryReport {
+                delivered: vec!["ops".to_string()],
+                failed: vec![("broken".to_string(), "unreachable".to_string())],
+            })
+        );
+        let again = Notification::new(NotificationKind::BudgetAlert, "80% of budget used", "").with_dedupe_key("budget:80");
+        assert_eq!(notifier.notify(again).await, NotifyOutcome::Duplicate);
+
+        notifier.notify(Notification::new(NotificationKind::AntiAddiction, "Take a break", "")).await;
+        let outcome = notifier.notify(Notification::new(NotificationKind::CircuitBreaker, "Jarvis blocked", "")).await;
+        let NotifyOutcome::Sent(report) = outcome else { panic!("expected delivery") };
+        assert_eq!(report.failed[0].0, "missing");
+
+        assert_eq!(ops.titles(), vec!["80% of budget used", "Jarvis blocked"]);
+        assert_eq!(user.titles(), vec!["Take a break"]);
+    }
+
+    #[tokio::test]
+    async fn test_quiet_hours_defer_non_critical() {
+        let quiet = QuietHours { start_hour: 22, end_hour: 7, utc_offset_minutes: 480 };
+        // UTC 15:00 = UTC+8 23:00
+        assert!(quiet.contains(Utc.with_ymd_and_hms(2026, 1, 1, 15, 0, 0).unwrap()));
+        assert!(!quiet.contains(Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap()));
+
+        let channel = RecordingChannel::new("ops", false);
+        let all_day = QuietHours { start_hour: 0, end_hour: 24, utc_offset_minutes: 0 };
+        let notifier = Notifier::new(NotifierConfig {
+            default_channels: vec!["ops".to_string()],
+            quiet_hours: Some(all_day),
+            ..Default::default()
+        })
+        .with_channel(channel.clone());
+
+        let approval = Notification::new(NotificationKind::WorkflowApproval, "Approve takeover", "");
+        assert_eq!(notifier.notify(approval).await, NotifyOutcome::Deferred);
+        let trip = Notification::new(NotificationKind::CircuitBreaker, "Breaker tripped", "").with_priority(NotificationPriority::Critical);
+        assert!(matches!(notifier.notify(trip).await, NotifyOutcome::Sent(_)));
+        // 仍在免打扰时段，暂存的通知不会补发
+        assert_eq!(notifier.flush_deferred().await, 0);
+        assert_eq!(channel.titles(), vec!["Breaker tripped"]);
+    }
+}
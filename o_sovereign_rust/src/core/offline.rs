@@ -0,0 +1,217 @@
+// Offline Mode - 一级离线模式
+// 将整个栈（模型、RAG嵌入、STT/TTS、图像生成）强制切换到本地后端
+//
+// 核心功能：
+// 1. 三种开关：CLI `--offline`、环境变量 `ACSA_OFFLINE`、配置项 `offline.enabled`
+// 2. 本地 OpenAI 兼容 LLM 端点（Ollama / llama.cpp / vLLM）
+// 3. 拒绝任何非本机端点，对没有本地实现的能力快速失败并给出明确错误
+// 4. 执行日志标记为离线运行
+
+use super::config_manager::ConfigManager;
+use super::error::{AcsaError, ErrorCode};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::sync::{LazyLock, RwLock};
+use tracing::info;
+
+/// 全局离线状态（进程启动时激活一次，各模块只读）
+static OFFLINE: LazyLock<RwLock<Option<OfflineConfig>>> = LazyLock::new(|| RwLock::new(None));
+
+fn default_llm_endpoint() -> String {
+    "http://localhost:11434/v1".to_string()
+}
+
+fn default_llm_model() -> String {
+    "llama3".to_string()
+}
+
+/// 离线模式配置（对应配置节 `offline.*`）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OfflineConfig {
+    /// 是否启用离线模式
+    #[serde(default)]
+    pub enabled: bool,
+    /// 本地 OpenAI 兼容 LLM 端点
+    #[serde(default = "default_llm_endpoint")]
+    pub llm_endpoint: String,
+    /// 本地模型名称
+    #[serde(default = "default_llm_model")]
+    pub llm_model: String,
+}
+
+impl Default for OfflineConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            llm_endpoint: default_llm_endpoint(),
+            llm_model: default_llm_model(),
+        }
+    }
+}
+
+impl OfflineConfig {
+    /// 从环境变量读取（ACSA_OFFLINE / ACSA_LOCAL_LLM_URL / ACSA_LOCAL_MODEL）
+    pub fn from_env() -> Self {
+        Self::default().with_env_overrides()
+    }
+
+    /// 用环境变量覆盖已有配置（未设置的变量保持原值）
+    pub fn with_env_overrides(mut self) -> Self {
+        if let Ok(flag) = std::env::var("ACSA_OFFLINE") {
+            self.enabled = matches!(flag.trim().to_lowercase().as_str(), "1" | "true" | "yes" | "on");
+        }
+        if let Ok(url) = std::env::var("ACSA_LOCAL_LLM_URL") {
+            self.llm_endpoint = url;
+        }
+        if let Ok(model) = std::env::var("ACSA_LOCAL_MODEL") {
+            self.llm_model = model;
+        }
+        self
+    }
+
+    /// 从配置管理器读取 `offline.*` 配置节
+    pub async fn from_config(config: &ConfigManager) -> Result<Self> {
+        config.get_section("offline").await
+    }
+}
+
+/// 受离线模式约束的能力
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Capability {
+    /// 大模型推理
+    Llm,
+    /// RAG 嵌入
+    Embeddings,
+    /// 向量数据库
+    VectorStore,
+    /// 语音识别
+    SpeechToText,
+    /// 语音合成
+    TextToSpeech,
+    /// 图像生成
+    ImageGeneration,
+}
+
+impl Capability {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Capability::Llm => "LLM",
+            Capability::Embeddings => "embeddings",
+            Capability::VectorStore => "vector store",
+            Capability::SpeechToText => "speech-to-text",
+            Capability::TextToSpeech => "text-to-speech",
+            Capability::ImageGeneration => "image generation",
+        }
+    }
+}
+
+/// 激活离线模式（进程级）
+pub fn activate(mut config: OfflineConfig) {
+    config.enabled = true;
+    info!("📴 Offline mode enabled (local LLM: {} / {})", config.llm_endpoint, config.llm_model);
+    *OFFLINE.write().unwrap_or_else(|e| e.into_inner()) = Some(config);
+}
+
+/// 关闭离线模式
+pub fn deactivate() {
+    *OFFLINE.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// 当前生效的离线配置（未启用时为 None）
+pub fn current() -> Option<OfflineConfig> {
+    OFFLINE.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// 是否处于离线模式
+pub fn is_offline() -> bool {
+    current().is_some()
+}
+
+/// 判断端点是否在本机
+pub fn is_local_endpoint(url: &str) -> bool {
+    let url = url.trim().to_lowercase();
+    let Some((scheme, rest)) = url.split_once("://") else {
+        // 没有协议的视为本地路径
+        return !url.is_empty();
+    };
+    if matches!(scheme, "unix" | "file") {
+        return true;
+    }
+
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or("");
+    let authority = authority.rsplit_once('@').map(|(_, host)| host).unwrap_or(authority);
+    let host = if let Some(v6) = authority.strip_prefix('[') {
+        v6.split(']').next().unwrap_or("")
+    } else {
+        authority.split(':').next().unwrap_or("")
+    };
+
+    host == "localhost"
+        || host.ends_with(".localhost")
+        || host.starts_with("127.")
+        || host == "::1"
+        || host == "0.0.0.0"
+}
+
+/// 构造"离线不可用"错误
+pub fn unavailable(capability: Capability, detail: impl Into<String>) -> anyhow::Error {
+    AcsaError::new(
+        ErrorCode::OfflineUnavailable,
+        format!("{} is not available offline: {}", capability.label(), detail.into()),
+    )
+    .into()
+}
+
+/// 校验端点在指定离线配置下是否允许（纯函数，便于测试）
+pub fn check_local(offline: Option<&OfflineConfig>, capability: Capability, endpoint: &str) -> Result<()> {
+    match offline {
+        Some(_) if !is_local_endpoint(endpoint) => Err(unavailable(
+            capability,
+            format!("endpoint '{}' is not on this machine", endpoint),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// 离线模式下要求能力使用本机端点
+pub fn require_local(capability: Capability, endpoint: &str) -> Result<()> {
+    check_local(current().as_ref(), capability, endpoint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_endpoint_detection() {
+        assert!(is_local_endpoint("http://localhost:11434/v1"));
+        assert!(is_local_endpoint("ws://127.0.0.1:8000"));
+        assert!(is_local_endpoint("http://[::1]:6333"));
+        assert!(is_local_endpoint("http://user@ollama.localhost"));
+        assert!(is_local_endpoint("unix:///var/run/llm.sock"));
+        assert!(!is_local_endpoint("https://api.openai.com/v1"));
+        assert!(!is_local_endpoint("http://localhost.evil.com"));
+        assert!(!is_local_endpoint(""));
+    }
+
+    #[test]
+    fn test_check_local_fails_fast() {
+        let offline = OfflineConfig { enabled: true, ..Default::default() };
+
+        assert!(check_local(None, Capability::Llm, "https://api.openai.com").is_ok());
+        assert!(check_local(Some(&offline), Capability::VectorStore, "http://localhost:6333").is_ok());
+
+        let err = check_local(Some(&offline), Capability::SpeechToText, "wss://stt.example.com").unwrap_err();
+        let acsa = err.downcast_ref::<AcsaError>().unwrap();
+        assert_eq!(acsa.code(), Some(ErrorCode::OfflineUnavailable));
+        assert!(err.to_string().contains("speech-to-text"));
+    }
+
+    #[test]
+    fn test_config_defaults() {
+        let config: OfflineConfig = serde_json::from_str(r#"{"enabled": true}"#).unwrap();
+        assert!(config.enabled);
+        assert_eq!(config.llm_endpoint, "http://localhost:11434/v1");
+        assert_eq!(config.llm_model, "llama3");
+    }
+}
@@ -0,0 +1,379 @@
+// Ollama Provider - 本地模型推理
+// 使用 Ollama 原生 /api/chat 接口，BUNKER 协议切换本地主权模式时的实际推理后端
+//
+// 核心功能：
+// 1. 非流式 / 流式（NDJSON）对话生成，统计 prompt_eval_count / eval_count
+// 2. 端点地址兼容 `http://host:11434` 与 OpenAI 风格的 `.../v1`
+// 3. 健康检查（/api/tags），Jarvis 触发 BUNKER 前确认本地集群在线
+// 4. 本地推理不计费，离线模式下拒绝非本机端点
+
+use super::cost_estimator::estimate_tokens;
+use super::determinism;
+use super::error::{AcsaError, ErrorCode};
+use super::offline::{self, Capability};
+use super::providers::{system_prompt, ModelProvider};
+use super::retry;
+use super::types::{AgentResponse, AgentRole, AgentStats};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::Mutex;
+use tracing::{debug, info};
+
+/// 健康检查超时
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Serialize)]
+struct OllamaChatRequest {
+    model: String,
+    messages: Vec<OllamaMessage>,
+    stream: bool,
+    options: OllamaOptions,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct OllamaMessage {
+    role: String,
+    #[serde(default)]
+    content: String,
+}
+
+#[derive(Debug, Serialize)]
+struct OllamaOptions {
+    temperature: f64,
+    num_predict: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<i64>,
+}
+
+/// 非流式响应，或流式响应中的一行
+#[derive(Debug, Default, Deserialize)]
+struct OllamaChatResponse {
+    #[serde(default)]
+    message: Option<OllamaMessage>,
+    #[serde(default)]
+    done: bool,
+    #[serde(default)]
+    prompt_eval_count: u32,
+    #[serde(default)]
+    eval_count: u32,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+impl OllamaChatResponse {
+    fn content(&self) -> &str {
+        self.message.as_ref().map(|m| m.content.as_str()).unwrap_or_default()
+    }
+}
+
+/// 解析 NDJSON 中的一行（空行返回 None，错误行转为错误）
+fn parse_line(line: &[u8]) -> Result<Option<OllamaChatResponse>> {
+    let line = String::from_utf8_lossy(line);
+    let line = line.trim();
+    if line.is_empty() {
+        return Ok(None);
+    }
+    let parsed: OllamaChatResponse = serde_json::from_str(line)?;
+    match parsed.error {
+        Some(error) => Err(anyhow!("Ollama error: {}", error)),
+        None => Ok(Some(parsed)),
+    }
+}
+
+/// Ollama 根地址（去掉结尾的 `/` 与 OpenAI 兼容路径 `/v1`）
+fn base_url(endpoint: &str) -> String {
+    let trimmed = endpoint.trim_end_matches('/');
+    trimmed.strip_suffix("/v1").unwrap_or(trimmed).to_string()
+}
+
+/// Ollama Provider（本地模型）
+pub struct OllamaProvider {
+    client: Client,
+    base_url: String,
+    role: AgentRole,
+    stats: Arc<Mutex<AgentStats>>,
+    model: String,
+}
+
+impl OllamaProvider {
+    pub fn new(endpoint: &str, model: &str, role: AgentRole) -> Result<Self> {
+        offline::require_local(Capability::Llm, endpoint)?;
+
+        Ok(Self {
+            client: Client::new(),
+            base_url: base_url(endpoint),
+            role,
+            stats: Arc::new(Mutex::new(AgentStats::new())),
+            model: model.to_string(),
+        })
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// 检查 Ollama 是否在线
+    pub async fn health_check(&self) -> Result<()> {
+        let response = self
+            .client
+            .get(format!("{}/api/tags", self.base_url))
+            .timeout(HEALTH_TIMEOUT)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("Ollama health check failed: {}", response.status()));
+        }
+        Ok(())
+    }
+
+    fn build_request(&self, prompt: &str, max_tokens: u32, temperature: f64, stream: bool) -> OllamaChatRequest {
+        OllamaChatRequest {
+            model: self.model.clone(),
+            messages: vec![
+                OllamaMessage { role: "system".to_string(), content: system_prompt(self.role).to_string() },
+                OllamaMessage { role: "user".to_string(), content: prompt.to_string() },
+            ],
+            stream,
+            options: OllamaOptions {
+                temperature,
+                num_predict: max_tokens,
+                // 固定种子模式下透传 seed，便于复现
+                seed: determinism::provider_seed(),
+            },
+        }
+    }
+
+    async fn send(&self, request: &OllamaChatRequest) -> Result<reqwest::Response> {
+        let response = self
+            .client
+            .post(format!("{}/api/chat", self.base_url))
+            .json(request)
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await?;
+            return Err(AcsaError::new(
+                ErrorCode::from_http_status(status.as_u16()),
+                format!("Ollama API error ({}): {}", status, error_text),
+            )
+            .into());
+        }
+        Ok(response)
+    }
+
+    /// 成功响应：记账并组装 AgentResponse（Ollama 未返回用量时按文本估算）
+    async fn finish(&self, prompt: &str, text: String, usage: (u32, u32), latency_ms: u64) -> AgentResponse {
+        let (prompt_tokens, completion_tokens) = match usage {
+            (0, 0) => (estimate_tokens(prompt), estimate_tokens(&text)),
+            usage => usage,
+        };
+        let tokens = prompt_tokens + completion_tokens;
+
+        self.stats.lock().await.record_success(tokens, 0.0, latency_ms);
+        info!("✓ Ollama completed ({} ms, {} tokens, local)", latency_ms, tokens);
+
+        let mut metadata = HashMap::new();
+        metadata.insert("provider".to_string(), "ollama".to_string());
+        metadata.insert("model".to_string(), self.model.clone());
+        metadata.insert("local".to_string(), "true".to_string());
+
+        AgentResponse {
+            role: self.role,
+            text,
+            tokens,
+            cost: 0.0,
+            latency_ms,
+            metadata,
+            timestamp: Utc::now(),
+        }
+    }
+
+    async fn fail(&self, start: Instant, error: anyhow::Error) -> anyhow::Error {
+        self.stats.lock().await.record_failure(start.elapsed().as_millis() as u64);
+        error
+    }
+}
+
+#[async_trait]
+impl ModelProvider for OllamaProvider {
+    async fn generate(
+        &self,
+        prompt: &str,
+        max_tokens: u32,
+        temperature: f64,
+    ) -> Result<AgentResponse> {
+        let start = Instant::now();
+
+        info!("🏰 Ollama ({}) processing locally as {:?}...", self.model, self.role);
+        debug!("Prompt: {}...", &prompt.chars().take(100).collect::<String>());
+
+        let request = self.build_request(prompt, max_tokens, temperature, false);
+        let result = retry::current()
+            .run("Ollama", || async {
+                let response: OllamaChatResponse = self.send(&request).await?.json().await?;
+                match response.error {
+                    Some(error) => Err(anyhow!("Ollama error: {}", error)),
+                    None => Ok(response),
+                }
+            })
+            .await;
+
+        match result {
+            Ok(response) => {
+                let latency_ms = start.elapsed().as_millis() as u64;
+                let usage = (response.prompt_eval_count, response.eval_count);
+                Ok(self.finish(prompt, response.content().to_string(), usage, latency_ms).await)
+            }
+            Err(e) => Err(self.fail(start, e).await),
+        }
+    }
+
+    async fn generate_stream(
+        &self,
+        prompt: &str,
+        max_tokens: u32,
+        temperature: f64,
+        chunks: UnboundedSender<String>,
+    ) -> Result<AgentResponse> {
+        let start = Instant::now();
+        let request = self.build_request(prompt, max_tokens, temperature, true);
+
+        let mut response = match self.send(&request).await {
+            Ok(response) => response,
+            Err(e) => return Err(self.fail(start, e).await),
+        };
+
+        let mut buffer: Vec<u8> = Vec::new();
+        let mut text = String::new();
+        let mut usage = (0, 0);
+        let mut handle = |line: &[u8]| -> Result<()> {
+            if let Some(part) = parse_line(line)? {
+                let delta = part.content();
+                if !delta.is_empty() {
+                    text.push_str(delta);
+                    // 接收端已关闭时继续生成，保证返回完整响应
+                    let _ = chunks.send(delta.to_string());
+                }
+                if part.done {
+                    usage = (part.prompt_eval_count, part.eval_count);
+                }
+            }
+            Ok(())
+        };
+
+        let streamed: Result<()> = async {
+            while let Some(bytes) = response.chunk().await? {
+                buffer.extend_from_slice(&bytes);
+                while let Some(pos) = buffer.iter().position(|b| *b == b'\n') {
+                    let line: Vec<u8> = buffer.drain(..=pos).collect();
+                    handle(&line)?;
+                }
+            }
+            handle(&buffer)
+        }
+        .await;
+        if let Err(e) = streamed {
+            return Err(self.fail(start, e).await);
+        }
+
+        let latency_ms = start.elapsed().as_millis() as u64;
+        Ok(self.finish(prompt, text, usage, latency_ms).await)
+    }
+
+    fn role(&self) -> AgentRole {
+        self.role
+    }
+
+    fn model_id(&self) -> String {
+        format!("ollama/{}", self.model)
+    }
+
+    async fn stats(&self) -> AgentStats {
+        self.stats.lock().await.clone()
+    }
+
+    async fn reset_stats(&self) {
+        *self.stats.lock().await = AgentStats::new();
+    }
+}
+
+/// 测试用的本地 Ollama 端点：/api/chat 固定回复 `reply`，其余路径返回空模型列表
+#[cfg(test)]
+pub(crate) async fn stub_ollama(reply: &str) -> String {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let chat = serde_json::json!({
+        "message": {"role": "assistant", "content": reply},
+        "done": true,
+        "prompt_eval_count": 10,
+        "eval_count": 5,
+    })
+    .to_string();
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            // 读完请求头和请求体再回复，避免连接被提前重置
+            let mut buf = Vec::new();
+            let mut chunk = [0u8; 4096];
+            loop {
+                let n = socket.read(&mut chunk).await.unwrap_or(0);
+                buf.extend_from_slice(&chunk[..n]);
+                let text = String::from_utf8_lossy(&buf).to_string();
+                let complete = text.find("\r\n\r\n").is_some_and(|end| {
+                    let length = text[..end]
+                        .lines()
+                        .find_map(|l| l.to_lowercase().strip_prefix("content-length:").map(|v| v.trim().to_string()))
+                        .and_then(|v| v.parse::<usize>().ok())
+                        .unwrap_or(0);
+                    buf.len() >= end + 4 + length
+                });
+                if n == 0 || complete {
+                    break;
+                }
+            }
+            let request = String::from_utf8_lossy(&buf);
+            let body = if request.starts_with("POST /api/chat") { chat.clone() } else { r#"{"models":[]}"#.to_string() };
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            let _ = socket.write_all(response.as_bytes()).await;
+        }
+    });
+    format!("http://{}", addr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ndjson_lines_and_endpoint_normalization() {
+        assert_eq!(base_url("http://localhost:11434/v1/"), "http://localhost:11434");
+        assert_eq!(base_url("http://localhost:11434"), "http://localhost:11434");
+
+        let part = parse_line(br#"{"message":{"role":"assistant","content":"Hel"},"done":false}"#)
+            .unwrap()
+            .unwrap();
+        assert_eq!(part.content(), "Hel");
+        assert!(!part.done);
+
+        let last = parse_line(b"{\"done\":true,\"prompt_eval_count\":12,\"eval_count\":34}\n").unwrap().unwrap();
+        assert!(last.done);
+        assert_eq!((last.prompt_eval_count, last.eval_count), (12, 34));
+
+        assert!(parse_line(b"  \n").unwrap().is_none());
+        assert!(parse_line(br#"{"error":"model 'llama3' not found"}"#).is_err());
+    }
+}
@@ -0,0 +1,473 @@
+// OpenAI Compat - OpenAI 兼容的 Chat Completions 接口格式
+// 让 LangChain / OpenWebUI / curl 脚本把 O-Sovereign 当作 OpenAI 后端直接使用
+//
+// 核心功能：
+// 1. 请求映射：system 消息作为约束、历史对话作为上下文、最后一条 user 消息作为任务，拼成 Router 输入
+// 2. 非流式响应：chat.completion，Jarvis 拦截时 finish_reason 为 content_filter
+// 3. 流式响应：chat.completion.chunk SSE 帧；Omega 输出为 content，MOSS/L6/Ultron 过程输出为 reasoning_content
+// 4. 用量统计：按各 Agent 响应汇总 AgentStats，prompt_tokens 按 Router 输入估算
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use super::cost_estimator::estimate_tokens;
+use super::types::{ACSAExecutionLog, AgentChunk, AgentRole, AgentStats};
+
+/// 未指定模型时回显的模型名
+pub const DEFAULT_MODEL_ID: &str = "acsa";
+
+/// 流结束标记
+pub const SSE_DONE: &str = "data: [DONE]\n\n";
+
+/// 消息内容：字符串，或多模态客户端发送的内容片段数组
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MessageContent {
+    Text(String),
+    Parts(Vec<ContentPart>),
+}
+
+/// 内容片段（只取 text 类型）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContentPart {
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+impl MessageContent {
+    pub fn text(&self) -> String {
+        match self {
+            MessageContent::Text(text) => text.clone(),
+            MessageContent::Parts(parts) => parts
+                .iter()
+                .filter(|p| p.kind == "text")
+                .filter_map(|p| p.text.as_deref())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+}
+
+/// 对话消息
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: String,
+    #[serde(default)]
+    pub content: Option<MessageContent>,
+}
+
+impl ChatMessage {
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self { role: "assistant".to_string(), content: Some(MessageContent::Text(content.into())) }
+    }
+
+    fn text(&self) -> String {
+        self.content.as_ref().map(MessageContent::text).unwrap_or_default()
+    }
+}
+
+/// `stream_options`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct StreamOptions {
+    #[serde(default)]
+    pub include_usage: bool,
+}
+
+/// `POST /v1/chat/completions` 请求（其余 OpenAI 参数忽略）
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChatCompletionRequest {
+    #[serde(default)]
+    pub model: Option<String>,
+    pub messages: Vec<ChatMessage>,
+    #[serde(default)]
+    pub stream: bool,
+    #[serde(default)]
+    pub stream_options: Option<StreamOptions>,
+}
+
+impl ChatCompletionRequest {
+    /// 回显的模型名
+    pub fn model(&self) -> String {
+        self.model.clone().filter(|m| !m.is_empty()).unwrap_or_else(|| DEFAULT_MODEL_ID.to_string())
+    }
+
+    /// 拼成 Router 输入；没有 user 消息时报错
+    pub fn to_router_input(&self) -> Result<String> {
+        let last_user = self
+            .messages
+            .iter()
+            .rposition(|m| m.role == "user")
+            .ok_or_else(|| anyhow!("messages must contain at least one user message"))?;
+        let task = self.messages[last_user].text();
+        if task.trim().is_empty() {
+            return Err(anyhow!("the last user message is empty"));
+        }
+
+        let system: Vec<String> = self
+            .messages
+            .iter()
+            .filter(|m| m.role == "system" || m.role == "developer")
+            .map(ChatMessage::text)
+            .filter(|t| !t.trim().is_empty())
+            .collect();
+        let history: Vec<String> = self.messages[..last_user]
+            .iter()
+            .filter(|m| m.role == "user" || m.role == "assistant")
+            .map(|m| format!("{}: {}", m.role, m.text()))
+            .collect();
+
+        if system.is_empty() && history.is_empty() {
+            return Ok(task);
+        }
+        let mut input = String::new();
+        if !system.is_empty() {
+            input.push_str(&format!("System instructions:\n{}\n\n", system.join("\n")));
+        }
+        if !history.is_empty() {
+            input.push_str(&format!("Conversation so far:\n{}\n\n", history.join("\n")));
+        }
+        input.push_str(&format!("Task:\n{}", task));
+        Ok(input)
+    }
+}
+
+/// 用量
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Usage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+}
+
+impl Usage {
+    /// 汇总一次执行中各 Agent 的响应
+    pub fn from_log(router_input: &str, log: &ACSAExecutionLog) -> Self {
+        let mut stats = AgentStats::new();
+        for response in [&log.moss_plan, &log.l6_verification, &log.ultron_audit, &log.omega_execution]
+            .into_iter()
+            .flatten()
+        {
+            stats.record_success(response.tokens, response.cost, response.latency_ms);
+        }
+        let prompt_tokens = estimate_tokens(router_input) as u64;
+        Self {
+            prompt_tokens,
+            completion_tokens: stats.total_tokens,
+            total_tokens: prompt_tokens + stats.total_tokens,
+        }
+    }
+}
+
+/// ACSA 扩展字段（OpenAI 客户端会忽略）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AcsaCompletionInfo {
+    pub success: bool,
+    pub cost_usd: f64,
+    pub iterations: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub risk_score: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jarvis_block: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub execution_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatChoice {
+    pub index: u32,
+    pub message: ChatMessage,
+    pub finish_reason: String,
+}
+
+/// `chat.completion` 响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatCompletionResponse {
+    pub id: String,
+    pub object: String,
+    pub created: i64,
+    pub model: String,
+    pub choices: Vec<ChatChoice>,
+    pub usage: Usage,
+    pub acsa: AcsaCompletionInfo,
+}
+
+impl ChatCompletionResponse {
+    pub fn from_log(
+        request: &ChatCompletionRequest,
+        router_input: &str,
+        log: &ACSAExecutionLog,
+        execution_id: Option<String>,
+    ) -> Self {
+        Self {
+            id: completion_id(),
+            object: "chat.completion".to_string(),
+            created: chrono::Utc::now().timestamp(),
+            model: request.model(),
+            choices: vec![ChatChoice {
+                index: 0,
+                message: ChatMessage::assistant(log.final_output.clone().unwrap_or_default()),
+                finish_reason: finish_reason(log).to_string(),
+            }],
+            usage: Usage::from_log(router_input, log),
+            acsa: AcsaCompletionInfo {
+                success: log.success,
+                cost_usd: log.total_cost,
+                iterations: log.iterations,
+                risk_score: log.audit_result.as_ref().map(|a| a.risk_score),
+                jarvis_block: log.jarvis_block.clone(),
+                execution_id,
+            },
+        }
+    }
+}
+
+/// Jarvis 拦截视为内容过滤
+fn finish_reason(log: &ACSAExecutionLog) -> &'static str {
+    if log.jarvis_block.is_some() { "content_filter" } else { "stop" }
+}
+
+/// 同一毫秒内的响应用计数器区分
+static COMPLETION_SEQ: AtomicU64 = AtomicU64::new(0);
+
+fn completion_id() -> String {
+    let seq = COMPLETION_SEQ.fetch_add(1, Ordering::Relaxed) % 10_000;
+    format!("chatcmpl-{}{:04}", chrono::Utc::now().timestamp_millis(), seq)
+}
+
+/// 流式增量
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChunkDelta {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    /// 中间阶段输出（与 DeepSeek 推理模型相同的字段，OpenWebUI 等客户端折叠显示为思考过程）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning_content: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkChoice {
+    pub index: u32,
+    pub delta: ChunkDelta,
+    pub finish_reason: Option<String>,
+}
+
+/// `chat.completion.chunk`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatCompletionChunk {
+    pub id: String,
+    pub object: String,
+    pub created: i64,
+    pub model: String,
+    pub choices: Vec<ChunkChoice>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
+}
+
+/// 把 Router 的 AgentChunk 流转换为 OpenAI SSE 帧
+pub struct CompletionStream {
+    id: String,
+    created: i64,
+    model: String,
+    include_usage: bool,
+    /// Omega 是否已输出内容（未输出时结束帧补发 final_output，如 Jarvis 拦截说明）
+    content_sent: bool,
+    /// 上一个推理片段所属的阶段（切换阶段时插入标题）
+    reasoning_stage: Option<(AgentRole, u32)>,
+}
+
+impl CompletionStream {
+    pub fn new(request: &ChatCompletionRequest) -> Self {
+        Self {
+            id: completion_id(),
+            created: chrono::Utc::now().timestamp(),
+            model: request.model(),
+            include_usage: request.stream_options.as_ref().is_some_and(|o| o.include_usage),
+            content_sent: false,
+            reasoning_stage: None,
+        }
+    }
+
+    /// 首帧：声明 assistant 角色
+    pub fn start_frame(&self) -> Result<String> {
+        self.frame(ChunkDelta { role: Some("assistant".to_string()), ..Default::default() }, None, None)
+    }
+
+    /// Agent 增量片段对应的帧；阶段结束标记不产生帧
+    pub fn chunk_frame(&mut self, chunk: &AgentChunk) -> Result<Option<String>> {
+        if chunk.done || chunk.delta.is_empty() {
+            return Ok(None);
+        }
+        let delta = if chunk.role == AgentRole::Omega {
+            self.content_sent = true;
+            ChunkDelta { content: Some(chunk.delta.clone()), ..Default::default() }
+        } else {
+            let stage = (chunk.role, chunk.iteration);
+            let mut text = String::new();
+            if self.reasoning_stage != Some(stage) {
+                if self.reasoning_stage.is_some() {
+                    text.push_str("\n\n");
+                }
+                text.push_str(&format!("[{} · iteration {}]\n", chunk.role.as_str(), chunk.iteration));
+                self.reasoning_stage = Some(stage);
+            }
+            text.push_str(&chunk.delta);
+            ChunkDelta { reasoning_content: Some(text), ..Default::default() }
+        };
+        self.frame(delta, None, None).map(Some)
+    }
+
+    /// 结束帧：必要时补发最终输出、finish_reason、用量（stream_options.include_usage），最后是 [DONE]
+    pub fn finish_frames(&self, router_input: &str, log: &ACSAExecutionLog) -> Result<Vec<String>> {
+        let mut frames = Vec::new();
+        if !self.content_sent {
+            if let Some(output) = log.final_output.as_ref().filter(|o| !o.is_empty()) {
+                frames.push(self.frame(ChunkDelta { content: Some(output.clone()), ..Default::default() }, None, None)?);
+            }
+        }
+        frames.push(self.frame(ChunkDelta::default(), Some(finish_reason(log)), None)?);
+        if self.include_usage {
+            frames.push(self.usage_frame(Usage::from_log(router_input, log))?);
+        }
+        frames.push(SSE_DONE.to_string());
+        Ok(frames)
+    }
+
+    fn frame(&self, delta: ChunkDelta, finish_reason: Option<&str>, usage: Option<Usage>) -> Result<String> {
+        let chunk = ChatCompletionChunk {
+            id: self.id.clone(),
+            object: "chat.completion.chunk".to_string(),
+            created: self.created,
+            model: self.model.clone(),
+            choices: vec![ChunkChoice { index: 0, delta, finish_reason: finish_reason.map(str::to_string) }],
+            usage,
+        };
+        Ok(format!("data: {}\n\n", serde_json::to_string(&chunk)?))
+    }
+
+    /// 用量帧的 choices 为空数组（与 OpenAI 一致）
+    fn usage_frame(&self, usage: Usage) -> Result<String> {
+        let chunk = ChatCompletionChunk {
+            id: self.id.clone(),
+            object: "chat.completion.chunk".to_string(),
+            created: self.created,
+            model: self.model.clone(),
+            choices: Vec::new(),
+            usage: Some(usage),
+        };
+        Ok(format!("data: {}\n\n", serde_json::to_string(&chunk)?))
+    }
+}
+
+/// `GET /v1/models` 中的模型条目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelInfo {
+    pub id: String,
+    pub object: String,
+    pub created: i64,
+    pub owned_by: String,
+}
+
+/// `GET /v1/models` 响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelList {
+    pub object: String,
+    pub data: Vec<ModelInfo>,
+}
+
+impl ModelList {
+    /// 只暴露一个模型：完整的 ACSA 链路
+    pub fn acsa() -> Self {
+        Self {
+            object: "list".to_string(),
+            data: vec![ModelInfo {
+                id: DEFAULT_MODEL_ID.to_string(),
+                object: "model".to_string(),
+                created: 0,
+                owned_by: "o-sovereign".to_string(),
+            }],
+        }
+    }
+}
+
+/// OpenAI 风格的错误体
+pub fn error_body(message: &str, kind: &str) -> serde_json::Value {
+    serde_json::json!({ "error": { "message": message, "type": kind, "param": null, "code": null } })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::types::AgentResponse;
+
+    fn request(json: serde_json::Value) -> ChatCompletionRequest {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_messages_map_to_router_input() {
+        let single = request(serde_json::json!({"messages": [{"role": "user", "content": "hi"}]}));
+        assert_eq!(single.to_router_input().unwrap(), "hi");
+        assert_eq!(single.model(), DEFAULT_MODEL_ID);
+
+        let chat = request(serde_json::json!({
+            "model": "gpt-4o",
+            "temperature": 0.2,
+            "messages": [
+                {"role": "system", "content": "Answer in English"},
+                {"role": "user", "content": "Plan a launch"},
+                {"role": "assistant", "content": "Which market?"},
+                {"role": "user", "content": [{"type": "text", "text": "EU"}, {"type": "image_url", "image_url": {"url": "x"}}]}
+            ]
+        }));
+        let input = chat.to_router_input().unwrap();
+        assert!(input.starts_with("System instructions:\nAnswer in English"));
+        assert!(input.contains("user: Plan a launch\nassistant: Which market?"));
+        assert!(input.ends_with("Task:\nEU"));
+        assert_eq!(chat.model(), "gpt-4o");
+
+        assert!(request(serde_json::json!({"messages": [{"role": "system", "content": "x"}]}))
+            .to_router_input()
+            .is_err());
+    }
+
+    #[test]
+    fn test_stream_frames_and_usage() {
+        let req = request(serde_json::json!({
+            "messages": [{"role": "user", "content": "hi"}],
+            "stream": true,
+            "stream_options": {"include_usage": true}
+        }));
+        let mut stream = CompletionStream::new(&req);
+        assert!(stream.start_frame().unwrap().contains("\"role\":\"assistant\""));
+
+        let chunk = |role, delta: &str| AgentChunk { role, iteration: 1, delta: delta.to_string(), done: false };
+        let moss = stream.chunk_frame(&chunk(AgentRole::MOSS, "plan")).unwrap().unwrap();
+        assert!(moss.contains("\"reasoning_content\":\"[MOSS · iteration 1]\\nplan\""));
+        let omega = stream.chunk_frame(&chunk(AgentRole::Omega, "done!")).unwrap().unwrap();
+        assert!(omega.contains("\"content\":\"done!\""));
+        assert!(stream.chunk_frame(&AgentChunk { done: true, ..chunk(AgentRole::Omega, "") }).unwrap().is_none());
+
+        let mut log = ACSAExecutionLog::new("hi".to_string());
+        log.final_output = Some("done!".to_string());
+        log.omega_execution = Some(AgentResponse {
+            role: AgentRole::Omega,
+            text: "done!".to_string(),
+            tokens: 7,
+            cost: 0.0,
+            latency_ms: 1,
+            metadata: Default::default(),
+            timestamp: chrono::Utc::now(),
+        });
+        let frames = stream.finish_frames("hi", &log).unwrap();
+        // finish_reason + usage + [DONE]，Omega 已输出内容时不再补发
+        assert_eq!(frames.len(), 3);
+        assert!(frames[0].contains("\"finish_reason\":\"stop\""));
+        assert!(frames[1].contains("\"completion_tokens\":7"));
+        assert_eq!(frames[2], SSE_DONE);
+    }
+}
@@ -4,6 +4,8 @@
 // OpenCode = 超级工头 (Super Foreman)
 // ACSA = 影子政府 (Shadow Government)
 // DeepSeek = 大脑 (Brain)
+//
+// 注：execute_sandboxed 在每次执行独立的临时目录中运行生成代码，回执附带文件 diff
 
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
@@ -13,6 +15,10 @@ use tokio::fs;
 use tokio::io::AsyncWriteExt;
 use tracing::{debug, info, warn};
 
+use super::opencode_connector::{CodeStats, ExecutionReceipt, MissionPack};
+use super::sandbox::{Sandbox, SandboxMount, SandboxOutput, SandboxPolicy};
+use super::sandbox_workspace::{FileChangeKind, SandboxWorkspace};
+
 /// OpenCode执行结果
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OpenCodeResult {
@@ -32,6 +38,14 @@ pub struct OpenCodeConfig {
     pub use_real_cli: bool,
     /// OpenCode CLI路径
     pub cli_path: Option<PathBuf>,
+    /// 运行生成代码时的默认沙箱策略（默认Docker、断网）
+    pub sandbox: SandboxPolicy,
+    /// 沙箱执行的临时目录根（每次执行建一个子目录）
+    pub runs_dir: PathBuf,
+    /// 沙箱策略可挂载的宿主机路径白名单（执行目录本身始终可写）
+    pub allowed_paths: Vec<PathBuf>,
+    /// 执行后保留临时目录（排查用）
+    pub keep_runs: bool,
 }
 
 impl Default for OpenCodeConfig {
@@ -40,6 +54,10 @@ impl Default for OpenCodeConfig {
             workspace: PathBuf::from("/tmp/opencode_workspace"),
             use_real_cli: false,
             cli_path: None,
+            sandbox: SandboxPolicy::default(),
+            runs_dir: std::env::temp_dir().join("acsa_runs"),
+            allowed_paths: Vec::new(),
+            keep_runs: false,
         }
     }
 }
@@ -142,18 +160,7 @@ impl OpenCodeExecutor {
         fs::create_dir_all(&self.config.workspace).await?;
 
         // 生成文件名
-        let ext = match language {
-            "rust" => "rs",
-            "python" => "py",
-            "javascript" | "js" => "js",
-            "typescript" | "ts" => "ts",
-            "go" => "go",
-            "java" => "java",
-            "cpp" | "c++" => "cpp",
-            "c" => "c",
-            _ => "txt",
-        };
-
+        let ext = Self::extension(language);
         let filename = format!(
             "generated_{}.{}",
             chrono::Utc::now().format("%Y%m%d_%H%M%S"),
@@ -294,6 +301,149 @@ edition = "2021"
         })
     }
 
+    /// 在沙箱中运行工作目录下的生成代码
+    ///
+    /// 策略优先取 `mission.sandbox`，否则使用配置中的默认策略；工作目录自动以可写方式挂载。
+    pub async fn run_file(
+        &self,
+        filename: &str,
+        language: &str,
+        mission: Option<&MissionPack>,
+    ) -> Result<SandboxOutput> {
+        let (program, args) = Self::run_command(filename, language)?;
+
+        let mut policy = mission
+            .and_then(|m| m.sandbox.clone())
+            .unwrap_or_else(|| self.config.sandbox.clone());
+        if let Some(mission) = mission {
+            policy = policy.cap_wall_time(mission.timeout_secs);
+        }
+
+        fs::create_dir_all(&self.config.workspace).await?;
+        let workspace = fs::canonicalize(&self.config.workspace).await?;
+        if !policy.mounts.iter().any(|m| workspace.starts_with(&m.host_path)) {
+            policy = policy.with_mount(SandboxMount::read_write(&workspace));
+        }
+
+        info!("▶️  Running {} ({}) in {} sandbox", filename, language, policy.backend.name());
+        let output = Sandbox::new(policy).run(&program, &args, &workspace).await?;
+
+        if !output.success() {
+            warn!("  ✗ {} exited with {:?} (timed out: {})", filename, output.exit_code, output.timed_out);
+        }
+
+        Ok(output)
+    }
+
+    /// 在独立的临时目录中执行生成代码，返回带文件 diff 的执行回执
+    ///
+    /// 有 mission 时先复制 `target_path` 作为种子；策略优先取 `mission.sandbox`，
+    /// 其余挂载点须在 `allowed_paths` 内且只读。
+    pub async fn execute_sandboxed(
+        &self,
+        task: &str,
+        code: &str,
+        language: &str,
+        mission: Option<&MissionPack>,
+    ) -> Result<ExecutionReceipt> {
+        let started = std::time::Instant::now();
+        let run = SandboxWorkspace::create(&self.config.runs_dir).await?.with_keep(self.config.keep_runs);
+
+        if let Some(source) = mission.map(|m| &m.target_path).filter(|p| p.is_dir()) {
+            let seeded = run.seed(source).await?;
+            debug!("  Seeded {} file(s) from {}", seeded, source.display());
+        }
+
+        let filename = format!("main.{}", Self::extension(language));
+        run.write(&filename, code).await?;
+        run.write(&format!("{}.meta.txt", filename), task).await?;
+
+        let mut policy = mission
+            .and_then(|m| m.sandbox.clone())
+            .unwrap_or_else(|| self.config.sandbox.clone());
+        if let Some(mission) = mission {
+            policy = policy.cap_wall_time(mission.timeout_secs);
+        }
+        let policy = run.confine(policy, &self.config.allowed_paths)?;
+
+        let (program, args) = Self::run_command(&filename, language)?;
+        info!("▶️  Running {} ({}) in {} sandbox [{}]", filename, language, policy.backend.name(), run.run_id());
+        let (output, file_diffs) = run.run(policy, &program, &args).await?;
+
+        let success = output.success();
+        if !success {
+            warn!("  ✗ {} exited with {:?} (timed out: {})", filename, output.exit_code, output.timed_out);
+        }
+
+        let paths = |kind: FileChangeKind| -> Vec<String> {
+            file_diffs.iter().filter(|d| d.kind == kind).map(|d| d.path.clone()).collect()
+        };
+        let stats = CodeStats {
+            lines_added: file_diffs.iter().map(|d| d.lines_added).sum(),
+            lines_removed: file_diffs.iter().map(|d| d.lines_removed).sum(),
+            files_modified: file_diffs.len() as u32,
+            test_results: None,
+        };
+
+        Ok(ExecutionReceipt {
+            task_id: mission.map(|m| m.task_id.clone()).unwrap_or_else(|| run.run_id().to_string()),
+            success,
+            modified_files: paths(FileChangeKind::Modified),
+            created_files: paths(FileChangeKind::Added),
+            execution_log: output.stdout.clone(),
+            error_message: (!success).then(|| {
+                if output.timed_out {
+                    format!("Timed out after {} ms", output.elapsed_ms)
+                } else {
+                    output.stderr.clone()
+                }
+            }),
+            elapsed_ms: started.elapsed().as_millis() as u64,
+            stats: Some(stats),
+            branch: None,
+            commit_sha: None,
+            file_diffs,
+        })
+    }
+
+    /// 按语言选择文件扩展名
+    fn extension(language: &str) -> &'static str {
+        match language {
+            "rust" => "rs",
+            "python" => "py",
+            "javascript" | "js" => "js",
+            "typescript" | "ts" => "ts",
+            "go" => "go",
+            "java" => "java",
+            "cpp" | "c++" => "cpp",
+            "c" => "c",
+            "sh" | "bash" | "shell" => "sh",
+            _ => "txt",
+        }
+    }
+
+    /// 按语言选择运行命令
+    fn run_command(filename: &str, language: &str) -> Result<(String, Vec<String>)> {
+        let file = filename.to_string();
+        let (program, args) = match language {
+            "python" => ("python3", vec![file]),
+            "javascript" | "js" => ("node", vec![file]),
+            "typescript" | "ts" => ("npx", vec!["tsx".to_string(), file]),
+            "go" => ("go", vec!["run".to_string(), file]),
+            "sh" | "bash" | "shell" => ("sh", vec![file]),
+            "rust" => {
+                // 先编译再运行，产物留在工作目录
+                let binary = format!("./{}.bin", filename.trim_end_matches(".rs"));
+                ("sh", vec!["-c".to_string(), format!("rustc -O -o {} {} && {}", binary, file, binary)])
+            }
+            // WASI后端直接运行模块
+            "wasm" => (filename, Vec::new()),
+            _ => return Err(anyhow!("Running {} code is not supported", language)),
+        };
+
+        Ok((program.to_string(), args))
+    }
+
     /// 列出工作目录中的文件
     pub async fn list_files(&self) -> Result<Vec<String>> {
         let mut files = Vec::new();
@@ -356,6 +506,7 @@ mod tests {
             workspace: PathBuf::from("/tmp/opencode_test"),
             use_real_cli: false,
             cli_path: None,
+            ..Default::default()
         };
 
         let executor = OpenCodeExecutor::new(config);
@@ -379,6 +530,7 @@ mod tests {
             workspace: PathBuf::from("/tmp/opencode_test_project"),
             use_real_cli: false,
             cli_path: None,
+            ..Default::default()
         };
 
         let executor = OpenCodeExecutor::new(config);
@@ -399,6 +551,7 @@ mod tests {
             workspace: PathBuf::from("/tmp/opencode_test_list"),
             use_real_cli: false,
             cli_path: None,
+            ..Default::default()
         };
 
         let executor = OpenCodeExecutor::new(config);
@@ -412,4 +565,59 @@ mod tests {
         let files = executor.list_files().await.unwrap();
         assert!(!files.is_empty());
     }
+
+    #[tokio::test]
+    async fn test_run_file_uses_mission_sandbox() {
+        let config = OpenCodeConfig {
+            workspace: PathBuf::from("/tmp/opencode_test_sandbox"),
+            ..Default::default()
+        };
+        let executor = OpenCodeExecutor::new(config);
+
+        fs::create_dir_all(executor.workspace()).await.unwrap();
+        fs::write(executor.workspace().join("hello.sh"), "echo sandboxed").await.unwrap();
+
+        let mission = MissionPack::new(
+            "sandbox-test".to_string(),
+            "Run script".to_string(),
+            executor.workspace().to_path_buf(),
+        )
+        .with_sandbox(SandboxPolicy::host());
+
+        let output = executor.run_file("hello.sh", "sh", Some(&mission)).await.unwrap();
+        assert!(output.success());
+        assert_eq!(output.backend, "host");
+        assert_eq!(output.stdout.trim(), "sandboxed");
+
+        assert!(executor.run_file("main.cob", "cobol", Some(&mission)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_execute_sandboxed_reports_file_diffs() {
+        let seed = PathBuf::from("/tmp/opencode_test_sandboxed_seed");
+        fs::create_dir_all(&seed).await.unwrap();
+        fs::write(seed.join("notes.txt"), "a\nb\n").await.unwrap();
+
+        let config = OpenCodeConfig {
+            runs_dir: PathBuf::from("/tmp/opencode_test_runs"),
+            ..Default::default()
+        };
+        let executor = OpenCodeExecutor::new(config);
+        let mission = MissionPack::new("sandboxed-test".to_string(), "Edit notes".to_string(), seed.clone())
+            .with_sandbox(SandboxPolicy::host());
+
+        let code = "echo c >> notes.txt && echo out > out.txt && echo done";
+        let receipt = executor.execute_sandboxed("Edit notes", code, "sh", Some(&mission)).await.unwrap();
+        assert!(receipt.success);
+        assert_eq!(receipt.task_id, "sandboxed-test");
+        assert_eq!(receipt.execution_log.trim(), "done");
+        assert_eq!(receipt.modified_files, vec!["notes.txt"]);
+        assert_eq!(receipt.created_files, vec!["out.txt"]);
+        assert_eq!(receipt.stats.unwrap().lines_added, 2);
+
+        // 种子目录不受影响，临时目录已清理
+        assert_eq!(fs::read_to_string(seed.join("notes.txt")).await.unwrap(), "a\nb\n");
+        let mut runs = fs::read_dir("/tmp/opencode_test_runs").await.unwrap();
+        assert!(runs.next_entry().await.unwrap().is_none());
+    }
 }
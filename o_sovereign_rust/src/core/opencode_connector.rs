@@ -8,11 +8,17 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
-use std::process::Stdio;
 use tokio::process::Command;
 use tracing::{debug, error, info, warn};
 
+use super::git_workflow::{GitWorkflow, GitWorkflowConfig};
+use super::repo_index::MissionContext;
+use super::sandbox::{Sandbox, SandboxMount, SandboxOutput, SandboxPolicy};
+use super::sandbox_workspace::FileDiff;
+use super::test_parser::{parse_test_output, TestFormat, TestRunner};
+
 /// Omega战术人格注入 (修订版 - 允许最小化沟通)
 /// 强制OpenCode内部的DeepSeek专注执行，但允许必要时报告状态
 const OMEGA_TACTICAL_INJECTION: &str = r#"
@@ -50,6 +56,15 @@ pub struct MissionPack {
     pub target_path: PathBuf,
     /// 可选: 超时时间 (秒)
     pub timeout_secs: Option<u64>,
+    /// 可选: 沙箱策略 (None时在宿主机执行)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sandbox: Option<SandboxPolicy>,
+    /// 可选: 执行后运行的测试命令
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub test_runner: Option<TestRunner>,
+    /// 相关文件 (相对工作目录，由 RepoIndex 选出)，为空时不附带文件上下文
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub context_files: Vec<String>,
 }
 
 impl MissionPack {
@@ -60,6 +75,9 @@ impl MissionPack {
             constraints: Vec::new(),
             target_path,
             timeout_secs: Some(300), // 默认5分钟超时
+            sandbox: None,
+            test_runner: None,
+            context_files: Vec::new(),
         }
     }
 
@@ -72,6 +90,56 @@ impl MissionPack {
         self.timeout_secs = Some(secs);
         self
     }
+
+    pub fn with_sandbox(mut self, policy: SandboxPolicy) -> Self {
+        self.sandbox = Some(policy);
+        self
+    }
+
+    pub fn with_context_files(mut self, files: Vec<String>) -> Self {
+        self.context_files = files;
+        self
+    }
+
+    pub fn with_test_runner(mut self, runner: TestRunner) -> Self {
+        self.test_runner = Some(runner);
+        self
+    }
+
+    /// 下一轮迭代：把上一轮失败的测试作为约束，让Omega只修这些测试
+    pub fn focus_on_failures(&self, receipt: &ExecutionReceipt) -> Self {
+        let mut next = self.clone();
+        next.constraints.retain(|c| !c.starts_with("Fix failing test"));
+
+        for failure in receipt.failing_tests() {
+            let location = match (&failure.file, failure.line) {
+                (Some(file), Some(line)) => format!(" ({}:{})", file, line),
+                (Some(file), None) => format!(" ({})", file),
+                _ => String::new(),
+            };
+            let message = failure.message.lines().next().unwrap_or_default();
+            next.constraints
+                .push(format!("Fix failing test `{}`{}: {}", failure.name, location, message));
+        }
+
+        next
+    }
+
+    /// 本任务的实际沙箱策略：自动挂载工作目录(可写)，超时取两者较小值
+    pub fn effective_sandbox(&self) -> SandboxPolicy {
+        let policy = match &self.sandbox {
+            Some(policy) => policy.clone(),
+            None => SandboxPolicy::host(),
+        };
+
+        let policy = if policy.mounts.iter().any(|m| m.host_path == self.target_path) {
+            policy
+        } else {
+            policy.with_mount(SandboxMount::read_write(&self.target_path))
+        };
+
+        policy.cap_wall_time(self.timeout_secs)
+    }
 }
 
 /// 执行回执 (OpenCode返回的结果)
@@ -93,6 +161,27 @@ pub struct ExecutionReceipt {
     pub elapsed_ms: u64,
     /// 代码统计
     pub stats: Option<CodeStats>,
+    /// 任务分支 (启用Git工作流时)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    /// 本轮迭代的提交 (无改动时为空)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit_sha: Option<String>,
+    /// 沙箱工作目录中的文件变化（沙箱执行时）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub file_diffs: Vec<FileDiff>,
+}
+
+impl ExecutionReceipt {
+    /// 测试结果（如果运行了测试）
+    pub fn test_results(&self) -> Option<&TestResults> {
+        self.stats.as_ref().and_then(|s| s.test_results.as_ref())
+    }
+
+    /// 失败的测试列表
+    pub fn failing_tests(&self) -> &[TestFailure] {
+        self.test_results().map(|t| t.failures.as_slice()).unwrap_or_default()
+    }
 }
 
 /// 代码统计信息
@@ -105,11 +194,34 @@ pub struct CodeStats {
 }
 
 /// 测试结果
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TestResults {
     pub passed: u32,
     pub failed: u32,
     pub skipped: u32,
+    /// 失败详情
+    #[serde(default)]
+    pub failures: Vec<TestFailure>,
+}
+
+impl TestResults {
+    pub fn total(&self) -> u32 {
+        self.passed + self.failed + self.skipped
+    }
+
+    pub fn all_passed(&self) -> bool {
+        self.failed == 0
+    }
+}
+
+/// 单个失败的测试
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestFailure {
+    /// 测试名称 (如 `tests::test_login`)
+    pub name: String,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub message: String,
 }
 
 /// OpenCode连接器配置
@@ -123,6 +235,10 @@ pub struct OpenCodeConfig {
     pub verbose: bool,
     /// 工作空间根目录
     pub workspace_root: PathBuf,
+    /// 每轮附带的文件上下文上限 (字符)
+    pub max_context_chars: usize,
+    /// 可选: Git工作流 (任务分支 + 迭代提交 + PR)
+    pub git: Option<GitWorkflowConfig>,
 }
 
 impl Default for OpenCodeConfig {
@@ -132,6 +248,8 @@ impl Default for OpenCodeConfig {
             model_name: "deepseek-coder".to_string(),
             verbose: false,
             workspace_root: PathBuf::from("./workspace"),
+            max_context_chars: 48_000,
+            git: None,
         }
     }
 }
@@ -139,6 +257,9 @@ impl Default for OpenCodeConfig {
 /// OpenCode连接器主体
 pub struct OpenCodeConnector {
     config: OpenCodeConfig,
+    git: Option<GitWorkflow>,
+    /// 每个任务已发送的文件上下文
+    contexts: tokio::sync::Mutex<HashMap<String, MissionContext>>,
 }
 
 impl OpenCodeConnector {
@@ -148,7 +269,12 @@ impl OpenCodeConnector {
         info!("  Model: {}", config.model_name);
         info!("  Workspace: {:?}", config.workspace_root);
 
-        Self { config }
+        let git = config.git.clone().map(GitWorkflow::new);
+        Self {
+            config,
+            git,
+            contexts: tokio::sync::Mutex::new(HashMap::new()),
+        }
     }
 
     /// 握手检查: 确保OpenCode已安装且DeepSeek模型就绪
@@ -182,9 +308,35 @@ impl OpenCodeConnector {
 
         let start = std::time::Instant::now();
 
+        // 0. Git工作流: 在任务分支的 worktree 中执行，不直接修改用户工作区
+        let branch = match &self.git {
+            Some(git) => Some(git.prepare_mission(mission).await?),
+            None => None,
+        };
+        let scoped;
+        let mission = match (&self.git, &branch) {
+            (Some(git), Some(branch)) => {
+                scoped = git.scoped_mission(mission, branch);
+                &scoped
+            }
+            _ => mission,
+        };
+
         // 1. 构建最终提示词 (Prompt Construction)
         // 将MOSS的意图 + Ultron的限制 + Omega的人格混合
-        let final_prompt = self.build_prompt(mission);
+        let mut final_prompt = self.build_prompt(mission);
+
+        // 文件上下文: 首轮发送相关文件全文，之后只发送变化区域
+        if !mission.context_files.is_empty() {
+            let mut contexts = self.contexts.lock().await;
+            let context = contexts.entry(mission.task_id.clone()).or_default();
+            let files = context
+                .render(&mission.target_path, mission, self.config.max_context_chars)
+                .await?;
+            if !files.is_empty() {
+                final_prompt.push_str(&format!("\n[FILE_CONTEXT]:\n{}", files));
+            }
+        }
 
         if self.config.verbose {
             debug!("📝 Final Prompt:\n{}", final_prompt);
@@ -198,12 +350,47 @@ impl OpenCodeConnector {
         let elapsed_ms = start.elapsed().as_millis() as u64;
 
         // 3. 解析结果
-        let receipt = self.parse_execution_result(
+        let mut receipt = self.parse_execution_result(
             mission.task_id.clone(),
             output,
             elapsed_ms,
         )?;
 
+        if let Some(context) = self.contexts.lock().await.get_mut(&mission.task_id) {
+            context.record_iteration(&receipt);
+        }
+
+        // 4. 运行测试并写入回执
+        if let Some(runner) = &mission.test_runner {
+            match self.run_tests(mission, runner).await {
+                Ok(results) => {
+                    info!(
+                        "🧪 [Omega] Tests: {} passed, {} failed, {} skipped",
+                        results.passed, results.failed, results.skipped
+                    );
+                    receipt
+                        .stats
+                        .get_or_insert(CodeStats {
+                            lines_added: 0,
+                            lines_removed: 0,
+                            files_modified: 0,
+                            test_results: None,
+                        })
+                        .test_results = Some(results);
+                }
+                Err(e) => warn!("⚠️ [Omega] Failed to collect test results: {}", e),
+            }
+        }
+
+        // 5. 提交本轮迭代，统计以真实 diff 为准
+        if let (Some(git), Some(branch)) = (&self.git, &branch) {
+            receipt.branch = Some(branch.branch.clone());
+            if let Some(commit) = git.commit_iteration(branch, mission, &receipt).await? {
+                receipt.commit_sha = Some(commit.sha);
+                receipt.stats = Some(commit.stats);
+            }
+        }
+
         if receipt.success {
             info!("✅ [Omega] Mission completed successfully");
             info!("  Modified files: {}", receipt.modified_files.len());
@@ -237,55 +424,91 @@ impl OpenCodeConnector {
             }
         }
 
+        // 相关文件
+        if !mission.context_files.is_empty() {
+            prompt.push_str("[RELEVANT_FILES]:\n");
+            for file in &mission.context_files {
+                prompt.push_str(&format!("- {}\n", file));
+            }
+        }
+
         // 工作目录
         prompt.push_str(&format!("\n[WORKING_DIRECTORY]: {:?}\n", mission.target_path));
 
         prompt
     }
 
-    /// 执行OpenCode命令
+    /// 执行OpenCode命令 (按MissionPack的沙箱策略)
     async fn execute_opencode(
         &self,
         prompt: &str,
         mission: &MissionPack,
-    ) -> Result<std::process::Output> {
-        let mut cmd = Command::new(&self.config.binary_path);
-
-        cmd.arg("do") // OpenCode执行模式
-            .arg(prompt)
-            .arg("--dir")
-            .arg(&mission.target_path)
-            .arg("--model")
-            .arg(&self.config.model_name)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
-
-        // 设置超时
-        if let Some(timeout) = mission.timeout_secs {
-            let output = tokio::time::timeout(
-                std::time::Duration::from_secs(timeout),
-                cmd.output(),
-            )
+    ) -> Result<SandboxOutput> {
+        let policy = mission.effective_sandbox();
+        if mission.sandbox.is_none() {
+            warn!("⚠️ [Omega] Mission {} runs on the host without a sandbox", mission.task_id);
+        }
+
+        let args = vec![
+            "do".to_string(), // OpenCode执行模式
+            prompt.to_string(),
+            "--dir".to_string(),
+            mission.target_path.display().to_string(),
+            "--model".to_string(),
+            self.config.model_name.clone(),
+        ];
+
+        let output = Sandbox::new(policy)
+            .run(&self.config.binary_path.display().to_string(), &args, &mission.target_path)
             .await
-            .context("OpenCode execution timeout")??;
+            .context("Failed to execute OpenCode")?;
 
-            Ok(output)
-        } else {
-            cmd.output().await.context("Failed to execute OpenCode")
+        if output.timed_out {
+            anyhow::bail!("OpenCode execution timeout");
         }
+
+        Ok(output)
+    }
+
+    /// 为任务分支开 PR/MR (需要配置 forge)
+    pub async fn open_pull_request(&self, mission: &MissionPack) -> Result<String> {
+        let git = self
+            .git
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Git workflow is not enabled"))?;
+        let branch = git.prepare_mission(mission).await?;
+        git.open_pull_request(&branch, mission).await
+    }
+
+    /// 在任务沙箱中运行测试并解析结果
+    pub async fn run_tests(&self, mission: &MissionPack, runner: &TestRunner) -> Result<TestResults> {
+        let output = Sandbox::new(mission.effective_sandbox())
+            .run(&runner.program, &runner.args, &mission.target_path)
+            .await?;
+
+        if output.timed_out {
+            anyhow::bail!("Test run timed out after {}ms", output.elapsed_ms);
+        }
+
+        let report = match &runner.report_path {
+            Some(path) => tokio::fs::read_to_string(mission.target_path.join(path))
+                .await
+                .with_context(|| format!("Test report {:?} not found", path))?,
+            None => output.stdout,
+        };
+
+        parse_test_output(runner.format, &report)
     }
 
     /// 解析执行结果
     fn parse_execution_result(
         &self,
         task_id: String,
-        output: std::process::Output,
+        output: SandboxOutput,
         elapsed_ms: u64,
     ) -> Result<ExecutionReceipt> {
-        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-
-        let success = output.status.success();
+        let success = output.success();
+        let SandboxOutput { stdout, stderr, .. } = output;
 
         // 解析修改的文件
         let modified_files = self.extract_files(&stdout, &["Updated", "Modified"]);
@@ -303,6 +526,9 @@ impl OpenCodeConnector {
             error_message: if success { None } else { Some(stderr) },
             elapsed_ms,
             stats,
+            branch: None,
+            commit_sha: None,
+            file_diffs: Vec::new(),
         };
 
         Ok(receipt)
@@ -328,12 +554,15 @@ impl OpenCodeConnector {
         let lines_removed = log.matches("-").count() as u32;
         let files_modified = log.lines().filter(|l| l.contains("Modified")).count() as u32;
 
-        if lines_added > 0 || lines_removed > 0 || files_modified > 0 {
+        // 日志中若包含测试框架输出，一并解析
+        let test_results = TestFormat::detect(log).and_then(|format| parse_test_output(format, log).ok());
+
+        if lines_added > 0 || lines_removed > 0 || files_modified > 0 || test_results.is_some() {
             Some(CodeStats {
                 lines_added,
                 lines_removed,
                 files_modified,
-                test_results: None,
+                test_results,
             })
         } else {
             None
@@ -370,6 +599,57 @@ mod tests {
         assert_eq!(mission.timeout_secs, Some(600));
     }
 
+    #[test]
+    fn test_focus_on_failures() {
+        let mission = MissionPack::new(
+            "test".to_string(),
+            "Fix auth".to_string(),
+            PathBuf::from("/tmp/workspace"),
+        );
+        let connector = OpenCodeConnector::new(OpenCodeConfig::default());
+        let log = r#"{ "type": "test", "name": "auth::login", "event": "failed", "stdout": "thread 'auth::login' panicked at src/auth.rs:17:5:\nexpected Ok\n" }"#;
+
+        let stats = connector.extract_stats(log).unwrap();
+        let receipt = ExecutionReceipt {
+            task_id: mission.task_id.clone(),
+            success: true,
+            modified_files: Vec::new(),
+            created_files: Vec::new(),
+            execution_log: log.to_string(),
+            error_message: None,
+            elapsed_ms: 0,
+            stats: Some(stats),
+            branch: None,
+            commit_sha: None,
+            file_diffs: Vec::new(),
+        };
+        assert_eq!(receipt.test_results().unwrap().failed, 1);
+
+        let next = mission.focus_on_failures(&receipt);
+        assert_eq!(next.constraints.len(), 1);
+        assert!(next.constraints[0].contains("auth::login"));
+        assert!(next.constraints[0].contains("src/auth.rs:17"));
+
+        // 重复聚焦不会累积旧约束
+        assert_eq!(next.focus_on_failures(&receipt).constraints.len(), 1);
+    }
+
+    #[test]
+    fn test_effective_sandbox() {
+        let mission = MissionPack::new(
+            "test".to_string(),
+            "Run tests".to_string(),
+            PathBuf::from("/tmp/workspace"),
+        )
+        .with_timeout(30)
+        .with_sandbox(SandboxPolicy::default());
+
+        let policy = mission.effective_sandbox();
+        assert!(!policy.allow_network);
+        assert_eq!(policy.limits.wall_time_secs, Some(30));
+        assert!(policy.mounts.iter().any(|m| m.writable && m.host_path == mission.target_path));
+    }
+
     #[test]
     fn test_prompt_building() {
         let config = OpenCodeConfig::default();
@@ -387,6 +667,10 @@ mod tests {
         assert!(prompt.contains("OMEGA"));
         assert!(prompt.contains("Write unit tests"));
         assert!(prompt.contains("100% coverage"));
+        assert!(!prompt.contains("[RELEVANT_FILES]"));
+
+        let scoped = mission.with_context_files(vec!["src/auth.rs".to_string()]);
+        assert!(connector.build_prompt(&scoped).contains("- src/auth.rs"));
     }
 
     #[test]
@@ -1,7 +1,9 @@
 // OpenRouter Provider
 // 统一AI模型路由平台 - 支持100+模型
 
+use super::determinism;
 use super::providers::ModelProvider;
+use super::telemetry;
 use super::types::{AgentResponse, AgentRole, AgentStats};
 use anyhow::{anyhow, Result};
 use async_openai::{
@@ -135,7 +137,7 @@ impl ModelProvider for OpenRouterProvider {
         );
         debug!("Prompt: {}...", &prompt.chars().take(100).collect::<String>());
 
-        let request = CreateChatCompletionRequestArgs::default()
+        let mut request = CreateChatCompletionRequestArgs::default()
             .model(&self.model)
             .messages(vec![
                 ChatCompletionRequestMessage::System(
@@ -152,8 +154,12 @@ impl ModelProvider for OpenRouterProvider {
             .max_tokens(max_tokens as u16)
             .temperature(temperature as f32)
             .build()?;
+        // 固定种子模式下透传 seed，便于复现
+        request.seed = determinism::provider_seed();
 
-        match self.client.chat().create(request).await {
+        let chat = self.client.chat();
+        let call = chat.create(request);
+        match telemetry::traced("provider.http", vec![("acsa.provider", "OpenRouter".into())], call).await {
             Ok(response) => {
                 let latency_ms = start.elapsed().as_millis() as u64;
 
@@ -206,6 +212,10 @@ impl ModelProvider for OpenRouterProvider {
         self.role
     }
 
+    fn model_id(&self) -> String {
+        format!("openrouter/{}", self.model)
+    }
+
     async fn stats(&self) -> AgentStats {
         self.stats.lock().await.clone()
     }
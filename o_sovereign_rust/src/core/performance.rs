@@ -7,14 +7,38 @@
 // 3. 缓存预热 - 提前加载常用资源
 // 4. 事件去抖动 - 防止重复触发
 // 5. 异步优先级调度 - 关键任务优先
+// 6. 启动瀑布图 - 记录各阶段起止时间（--profile-startup）
 
-use std::sync::{LazyLock, OnceLock};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{LazyLock, Mutex};
 use std::time::{Duration, Instant};
-use tokio::sync::{Semaphore, RwLock};
+use tokio::sync::{OnceCell, Semaphore, RwLock};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tracing::{info, warn, debug};
 
+/// 阶段类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PhaseKind {
+    /// 顺序执行的启动阶段
+    Eager,
+    /// 并行初始化任务
+    Parallel,
+    /// 首次使用时才初始化的子系统
+    Lazy,
+}
+
+/// 单个阶段记录
+#[derive(Debug, Clone)]
+pub struct PhaseRecord {
+    pub name: String,
+    pub kind: PhaseKind,
+    /// 相对启动时间的开始偏移
+    pub start_offset: Duration,
+    pub duration: Duration,
+}
+
 /// 启动性能统计
 #[derive(Debug, Clone)]
 pub struct StartupMetrics {
@@ -24,14 +48,62 @@ pub struct StartupMetrics {
     pub phase_durations: HashMap<String, Duration>,
     /// 并行任务数
     pub parallel_tasks: usize,
+    /// 按开始时间排序的阶段记录
+    pub phases: Vec<PhaseRecord>,
 }
 
+impl StartupMetrics {
+    /// 渲染阶段瀑布图
+    pub fn render_waterfall(&self) -> String {
+        const WIDTH: usize = 40;
+
+        let span = self
+            .phases
+            .iter()
+            .map(|p| p.start_offset + p.duration)
+            .max()
+            .unwrap_or_default()
+            .max(self.total_duration)
+            .as_secs_f64()
+            .max(f64::EPSILON);
+        let name_width = self.phases.iter().map(|p| p.name.len()).max().unwrap_or(5).max(5);
+
+        let mut out = format!("⏱️  Startup waterfall (total {:.1}ms)\n", self.total_duration.as_secs_f64() * 1000.0);
+        for phase in &self.phases {
+            let start = ((phase.start_offset.as_secs_f64() / span) * WIDTH as f64) as usize;
+            let len = (((phase.duration.as_secs_f64() / span) * WIDTH as f64).ceil() as usize).max(1);
+            let start = start.min(WIDTH - 1);
+            let len = len.min(WIDTH - start);
+            let marker = match phase.kind {
+                PhaseKind::Eager => '█',
+                PhaseKind::Parallel => '▓',
+                PhaseKind::Lazy => '░',
+            };
+
+            out.push_str(&format!(
+                "  {:<name_width$} |{}{}{}| {:>8.1}ms @ {:.1}ms\n",
+                phase.name,
+                " ".repeat(start),
+                marker.to_string().repeat(len),
+                " ".repeat(WIDTH - start - len),
+                phase.duration.as_secs_f64() * 1000.0,
+                phase.start_offset.as_secs_f64() * 1000.0,
+            ));
+        }
+        out.push_str("  █ eager  ▓ parallel  ░ lazy\n");
+        out
+    }
+}
+
+/// 并行初始化任务
+pub type InitFuture = Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send>>;
+
 /// 性能优化器
 pub struct PerformanceOptimizer {
     /// 启动时间跟踪
     startup_time: Instant,
-    /// 阶段耗时记录
-    phase_times: Arc<RwLock<HashMap<String, Duration>>>,
+    /// 阶段记录
+    phases: Arc<Mutex<Vec<PhaseRecord>>>,
     /// 事件去抖动限制器
     debounce_limiter: Arc<Semaphore>,
 }
@@ -42,7 +114,7 @@ impl PerformanceOptimizer {
         info!("🚀 Performance Optimizer initialized");
         Self {
             startup_time: Instant::now(),
-            phase_times: Arc::new(RwLock::new(HashMap::new())),
+            phases: Arc::new(Mutex::new(Vec::new())),
             debounce_limiter: Arc::new(Semaphore::new(10)), // 最多10个并发事件
         }
     }
@@ -52,21 +124,43 @@ impl PerformanceOptimizer {
         debug!("⏱️  Starting phase: {}", phase_name);
         PhaseTracker {
             name: phase_name.to_string(),
+            kind: PhaseKind::Eager,
             start: Instant::now(),
-            phase_times: self.phase_times.clone(),
+            startup_time: self.startup_time,
+            phases: self.phases.clone(),
         }
     }
 
+    /// 记录一个已完成的阶段
+    pub fn record_phase(&self, name: &str, kind: PhaseKind, start: Instant, duration: Duration) {
+        self.phases.lock().unwrap().push(PhaseRecord {
+            name: name.to_string(),
+            kind,
+            start_offset: start.saturating_duration_since(self.startup_time),
+            duration,
+        });
+    }
+
+    /// 执行 future 并记录为一个阶段
+    pub async fn track<F: Future>(&self, name: &str, future: F) -> F::Output {
+        let start = Instant::now();
+        let output = future.await;
+        self.record_phase(name, PhaseKind::Eager, start, start.elapsed());
+        debug!("  ✓ Phase '{}' completed in {:?}", name, start.elapsed());
+        output
+    }
+
     /// 获取启动指标
     pub async fn get_startup_metrics(&self) -> StartupMetrics {
         let total_duration = self.startup_time.elapsed();
-        let phase_durations = self.phase_times.read().await.clone();
-        let parallel_tasks = phase_durations.len();
+        let mut phases = self.phases.lock().unwrap().clone();
+        phases.sort_by_key(|p| p.start_offset);
 
         StartupMetrics {
             total_duration,
-            phase_durations,
-            parallel_tasks,
+            phase_durations: phases.iter().map(|p| (p.name.clone(), p.duration)).collect(),
+            parallel_tasks: phases.iter().filter(|p| p.kind == PhaseKind::Parallel).count(),
+            phases,
         }
     }
 
@@ -126,6 +220,38 @@ impl PerformanceOptimizer {
 
         results
     }
+
+    /// 并行初始化并把每个任务记录为并行阶段
+    pub async fn init_parallel(&self, tasks: Vec<(String, InitFuture)>) -> Vec<anyhow::Result<()>> {
+        info!("🔄 Starting {} parallel initialization tasks", tasks.len());
+
+        let handles: Vec<_> = tasks
+            .into_iter()
+            .map(|(name, task)| {
+                tokio::spawn(async move {
+                    let start = Instant::now();
+                    let result = task.await;
+                    (name, start, result)
+                })
+            })
+            .collect();
+
+        let mut results = Vec::new();
+        for handle in handles {
+            match handle.await {
+                Ok((name, start, result)) => {
+                    self.record_phase(&name, PhaseKind::Parallel, start, start.elapsed());
+                    if let Err(e) = &result {
+                        warn!("  ❌ Failed to initialize {}: {}", name, e);
+                    }
+                    results.push(result);
+                }
+                Err(e) => results.push(Err(anyhow::anyhow!("Task panicked: {}", e))),
+            }
+        }
+
+        results
+    }
 }
 
 impl Default for PerformanceOptimizer {
@@ -137,8 +263,10 @@ impl Default for PerformanceOptimizer {
 /// 阶段跟踪器（自动记录耗时）
 pub struct PhaseTracker {
     name: String,
+    kind: PhaseKind,
     start: Instant,
-    phase_times: Arc<RwLock<HashMap<String, Duration>>>,
+    startup_time: Instant,
+    phases: Arc<Mutex<Vec<PhaseRecord>>>,
 }
 
 impl Drop for PhaseTracker {
@@ -146,11 +274,79 @@ impl Drop for PhaseTracker {
         let elapsed = self.start.elapsed();
         debug!("  ✓ Phase '{}' completed in {:?}", self.name, elapsed);
 
-        // 异步记录（非阻塞）
-        let name = self.name.clone();
-        let phase_times = self.phase_times.clone();
+        self.phases.lock().unwrap().push(PhaseRecord {
+            name: std::mem::take(&mut self.name),
+            kind: self.kind,
+            start_offset: self.start.saturating_duration_since(self.startup_time),
+            duration: elapsed,
+        });
+    }
+}
+
+type LazyInit<T> = Box<dyn Fn() -> Pin<Box<dyn Future<Output = anyhow::Result<T>> + Send>> + Send + Sync>;
+
+/// 懒加载子系统：首次 `get()` 时初始化，耗时记录到 `GLOBAL_OPTIMIZER` 的瀑布图
+pub struct LazySubsystem<T> {
+    name: String,
+    cell: OnceCell<T>,
+    init: LazyInit<T>,
+}
+
+impl<T: Send + Sync + 'static> LazySubsystem<T> {
+    pub fn new<F, Fut>(name: &str, init: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = anyhow::Result<T>> + Send + 'static,
+    {
+        Self {
+            name: name.to_string(),
+            cell: OnceCell::new(),
+            init: Box::new(move || Box::pin(init())),
+        }
+    }
+
+    /// 已有实例（如调用方预先构建好的索引），不再初始化也不记录阶段
+    pub fn ready(name: &str, value: T) -> Self {
+        let owner = name.to_string();
+        Self {
+            name: name.to_string(),
+            cell: OnceCell::new_with(Some(value)),
+            init: Box::new(move || {
+                let owner = owner.clone();
+                Box::pin(async move { Err(anyhow::anyhow!("Subsystem '{}' was provided ready-made", owner)) })
+            }),
+        }
+    }
+
+    /// 获取实例（首次调用时初始化；初始化失败时下次调用会重试）
+    pub async fn get(&self) -> anyhow::Result<&T> {
+        self.cell
+            .get_or_try_init(|| async {
+                let start = Instant::now();
+                let result = (self.init)().await;
+                GLOBAL_OPTIMIZER.record_phase(&self.name, PhaseKind::Lazy, start, start.elapsed());
+                info!("💤 Lazy subsystem '{}' initialized in {:?}", self.name, start.elapsed());
+                result
+            })
+            .await
+    }
+
+    /// 已初始化时返回实例（不触发初始化）
+    pub fn get_if_initialized(&self) -> Option<&T> {
+        self.cell.get()
+    }
+
+    pub fn is_initialized(&self) -> bool {
+        self.cell.initialized()
+    }
+
+    /// 后台预热（不阻塞启动）
+    pub fn warm_up(self: &Arc<Self>) {
+        let subsystem = self.clone();
         tokio::spawn(async move {
-            phase_times.write().await.insert(name, elapsed);
+            if let Err(e) = subsystem.get().await {
+                warn!("⚠️  Warm-up of '{}' failed: {}", subsystem.name, e);
+            }
         });
     }
 }
@@ -206,34 +402,305 @@ impl Default for CacheWarmer {
     }
 }
 
+/// 批处理调优配置（AIMD）
+#[derive(Debug, Clone)]
+pub struct BatcherConfig {
+    pub min_batch_size: usize,
+    pub max_batch_size: usize,
+    pub initial_batch_size: usize,
+    /// 批次大小加性增长步长
+    pub increase_step: usize,
+    /// 拥塞时的乘性衰减系数
+    pub decrease_factor: f64,
+    pub min_flush_interval: Duration,
+    pub max_flush_interval: Duration,
+    /// 事件在批次中等待的目标上限（额外延迟）
+    pub target_added_latency: Duration,
+    /// 下游处理一个批次的目标耗时
+    pub target_downstream_latency: Duration,
+}
+
+impl Default for BatcherConfig {
+    fn default() -> Self {
+        Self {
+            min_batch_size: 1,
+            max_batch_size: 512,
+            initial_batch_size: 16,
+            increase_step: 4,
+            decrease_factor: 0.5,
+            min_flush_interval: Duration::from_millis(5),
+            max_flush_interval: Duration::from_millis(200),
+            target_added_latency: Duration::from_millis(50),
+            target_downstream_latency: Duration::from_millis(100),
+        }
+    }
+}
+
+/// 单个主题的批处理指标
+#[derive(Debug, Clone, Default)]
+pub struct BatchingMetrics {
+    pub events: u64,
+    pub batches: u64,
+    /// 当前批次大小上限
+    pub batch_size: usize,
+    pub flush_interval: Duration,
+    /// 观测到的事件速率（事件/秒，EWMA）
+    pub event_rate: f64,
+    /// 平均每批事件数（批处理效率）
+    pub avg_batch_size: f64,
+    /// 平均额外延迟（批内最早事件等待时间）
+    pub avg_added_latency: Duration,
+    /// 最近一次下游处理耗时
+    pub last_downstream_latency: Option<Duration>,
+    pub increases: u64,
+    pub decreases: u64,
+}
+
+struct TopicBatch<T> {
+    config: BatcherConfig,
+    pending: Vec<T>,
+    first_pending_at: Option<Instant>,
+    last_event_at: Option<Instant>,
+    batch_size: usize,
+    flush_interval: Duration,
+    event_rate: f64,
+    added_latency_total: Duration,
+    metrics: BatchingMetrics,
+}
+
+impl<T> TopicBatch<T> {
+    fn new(config: BatcherConfig) -> Self {
+        let batch_size = config.initial_batch_size.clamp(config.min_batch_size, config.max_batch_size);
+        let flush_interval = config.max_flush_interval;
+        Self {
+            config,
+            pending: Vec::new(),
+            first_pending_at: None,
+            last_event_at: None,
+            batch_size,
+            flush_interval,
+            event_rate: 0.0,
+            added_latency_total: Duration::ZERO,
+            metrics: BatchingMetrics::default(),
+        }
+    }
+
+    fn push(&mut self, event: T) {
+        let now = Instant::now();
+        if let Some(last) = self.last_event_at {
+            // 到达间隔的 EWMA 估计事件速率
+            let gap = now.duration_since(last).as_secs_f64().max(1e-6);
+            let instant_rate = 1.0 / gap;
+            self.event_rate = if self.event_rate == 0.0 {
+                instant_rate
+            } else {
+                0.8 * self.event_rate + 0.2 * instant_rate
+            };
+        }
+        self.last_event_at = Some(now);
+        self.first_pending_at.get_or_insert(now);
+        self.pending.push(event);
+        self.metrics.events += 1;
+        self.retune_interval();
+    }
+
+    fn is_full(&self) -> bool {
+        self.pending.len() >= self.batch_size
+    }
+
+    fn is_due(&self, now: Instant) -> bool {
+        self.first_pending_at
+            .is_some_and(|first| now.duration_since(first) >= self.flush_interval)
+    }
+
+    fn take(&mut self) -> Vec<T> {
+        let batch = std::mem::take(&mut self.pending);
+        if batch.is_empty() {
+            return batch;
+        }
+
+        let added_latency = self.first_pending_at.take().map(|t| t.elapsed()).unwrap_or_default();
+        self.metrics.batches += 1;
+        self.added_latency_total += added_latency;
+
+        // 额外延迟超标 → 乘性减小，否则批次填满时加性增大
+        if added_latency > self.config.target_added_latency {
+            self.decrease();
+        } else if batch.len() >= self.batch_size {
+            self.increase();
+        }
+        batch
+    }
+
+    fn increase(&mut self) {
+        let next = (self.batch_size + self.config.increase_step).min(self.config.max_batch_size);
+        if next != self.batch_size {
+            self.batch_size = next;
+            self.metrics.increases += 1;
+            self.retune_interval();
+        }
+    }
+
+    fn decrease(&mut self) {
+        let next = ((self.batch_size as f64 * self.config.decrease_factor) as usize).max(self.config.min_batch_size);
+        if next != self.batch_size {
+            self.batch_size = next;
+            self.metrics.decreases += 1;
+            self.retune_interval();
+        }
+    }
+
+    /// 刷新间隔 = 按当前速率填满一个批次所需时间，限制在 [min, max]
+    fn retune_interval(&mut self) {
+        if self.event_rate > 0.0 {
+            let fill_time = Duration::from_secs_f64(self.batch_size as f64 / self.event_rate);
+            self.flush_interval = fill_time
+                .min(self.config.target_added_latency)
+                .clamp(self.config.min_flush_interval, self.config.max_flush_interval);
+        }
+    }
+
+    fn record_downstream(&mut self, latency: Duration) {
+        self.metrics.last_downstream_latency = Some(latency);
+        if latency > self.config.target_downstream_latency {
+            self.decrease();
+        }
+    }
+
+    fn snapshot(&self) -> BatchingMetrics {
+        let mut metrics = self.metrics.clone();
+        metrics.batch_size = self.batch_size;
+        metrics.flush_interval = self.flush_interval;
+        metrics.event_rate = self.event_rate;
+        if metrics.batches > 0 {
+            metrics.avg_batch_size = (metrics.events - self.pending.len() as u64) as f64 / metrics.batches as f64;
+            metrics.avg_added_latency = self.added_latency_total / metrics.batches as u32;
+        }
+        metrics
+    }
+}
+
+/// 默认主题（无主题的 `add_event`/`take_batch` 使用）
+const DEFAULT_TOPIC: &str = "default";
+
 /// 事件批处理器（优化按钮响应）
+///
+/// 每个主题独立按 AIMD 调整批次大小：批次填满且额外延迟达标时加性增大，
+/// 额外延迟或下游耗时超标时乘性减小；刷新间隔随观测到的事件速率调整。
 pub struct EventBatcher<T: Send> {
-    /// 待处理事件
-    pending: Arc<RwLock<Vec<T>>>,
+    default_config: BatcherConfig,
+    topics: Arc<RwLock<HashMap<String, TopicBatch<T>>>>,
 }
 
 impl<T: Send + 'static> EventBatcher<T> {
     /// 创建事件批处理器
     pub fn new() -> Self {
+        Self::with_config(BatcherConfig::default())
+    }
+
+    pub fn with_config(default_config: BatcherConfig) -> Self {
         Self {
-            pending: Arc::new(RwLock::new(Vec::new())),
+            default_config,
+            topics: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
+    /// 为主题设置独立的调优参数（重置该主题的调优状态）
+    pub async fn set_topic_config(&self, topic: &str, config: BatcherConfig) {
+        let mut topics = self.topics.write().await;
+        let pending = topics.remove(topic).map(|t| t.pending).unwrap_or_default();
+        let mut batch = TopicBatch::new(config);
+        for event in pending {
+            batch.push(event);
+        }
+        topics.insert(topic.to_string(), batch);
+    }
+
     /// 添加事件到批次
     pub async fn add_event(&self, event: T) {
-        self.pending.write().await.push(event);
+        self.push(DEFAULT_TOPIC, event).await;
+    }
+
+    /// 添加事件到主题；批次达到当前大小上限时返回该批次
+    pub async fn push(&self, topic: &str, event: T) -> Option<Vec<T>> {
+        let mut topics = self.topics.write().await;
+        let batch = topics
+            .entry(topic.to_string())
+            .or_insert_with(|| TopicBatch::new(self.default_config.clone()));
+        batch.push(event);
+        batch.is_full().then(|| batch.take())
     }
 
     /// 获取并清空当前批次（手动批处理）
     pub async fn take_batch(&self) -> Vec<T> {
-        let mut guard = self.pending.write().await;
-        std::mem::take(&mut *guard)
+        self.take_topic_batch(DEFAULT_TOPIC).await
+    }
+
+    /// 获取并清空指定主题的批次
+    pub async fn take_topic_batch(&self, topic: &str) -> Vec<T> {
+        self.topics
+            .write()
+            .await
+            .get_mut(topic)
+            .map(|batch| batch.take())
+            .unwrap_or_default()
+    }
+
+    /// 取出所有已到刷新时间的批次
+    pub async fn flush_due(&self) -> Vec<(String, Vec<T>)> {
+        let now = Instant::now();
+        self.topics
+            .write()
+            .await
+            .iter_mut()
+            .filter(|(_, batch)| batch.is_due(now))
+            .map(|(topic, batch)| (topic.clone(), batch.take()))
+            .collect()
+    }
+
+    /// 距下一个主题到期的时间（无待处理事件时为 None），用于驱动刷新定时器
+    pub async fn next_flush_in(&self) -> Option<Duration> {
+        let now = Instant::now();
+        self.topics
+            .read()
+            .await
+            .values()
+            .filter_map(|batch| {
+                let first = batch.first_pending_at?;
+                Some(batch.flush_interval.saturating_sub(now.duration_since(first)))
+            })
+            .min()
+    }
+
+    /// 反馈下游处理一个批次的耗时（超标时减小批次）
+    pub async fn record_downstream_latency(&self, topic: &str, latency: Duration) {
+        if let Some(batch) = self.topics.write().await.get_mut(topic) {
+            batch.record_downstream(latency);
+        }
     }
 
     /// 获取当前批次大小
     pub async fn batch_size(&self) -> usize {
-        self.pending.read().await.len()
+        self.topics
+            .read()
+            .await
+            .get(DEFAULT_TOPIC)
+            .map_or(0, |batch| batch.pending.len())
+    }
+
+    /// 主题的批处理指标
+    pub async fn topic_metrics(&self, topic: &str) -> Option<BatchingMetrics> {
+        self.topics.read().await.get(topic).map(|batch| batch.snapshot())
+    }
+
+    /// 所有主题的批处理指标
+    pub async fn metrics(&self) -> HashMap<String, BatchingMetrics> {
+        self.topics
+            .read()
+            .await
+            .iter()
+            .map(|(topic, batch)| (topic.clone(), batch.snapshot()))
+            .collect()
     }
 }
 
@@ -321,12 +788,103 @@ mod tests {
             tokio::time::sleep(Duration::from_millis(10)).await;
         }
 
-        tokio::time::sleep(Duration::from_millis(50)).await; // 等待异步记录
-
         let metrics = optimizer.get_startup_metrics().await;
         assert!(metrics.phase_durations.contains_key("test_phase"));
     }
 
+    #[tokio::test]
+    async fn test_waterfall_and_lazy_subsystem() {
+        let optimizer = PerformanceOptimizer::new();
+        optimizer.track("config", async { tokio::time::sleep(Duration::from_millis(5)).await }).await;
+        let results = optimizer
+            .init_parallel(vec![
+                ("providers".to_string(), Box::pin(async { Ok(()) }) as InitFuture),
+                ("plugins".to_string(), Box::pin(async { Err(anyhow::anyhow!("no dir")) })),
+            ])
+            .await;
+        assert!(results[0].is_ok() && results[1].is_err());
+
+        let metrics = optimizer.get_startup_metrics().await;
+        assert_eq!(metrics.parallel_tasks, 2);
+        assert_eq!(metrics.phases[0].name, "config");
+        let waterfall = metrics.render_waterfall();
+        assert!(waterfall.contains("config") && waterfall.contains("plugins"));
+
+        let calls = Arc::new(Mutex::new(0));
+        let counter = calls.clone();
+        let lazy = LazySubsystem::new("test.lazy_index", move || {
+            let counter = counter.clone();
+            async move {
+                *counter.lock().unwrap() += 1;
+                Ok(42)
+            }
+        });
+        assert!(!lazy.is_initialized());
+        assert_eq!(*lazy.get().await.unwrap(), 42);
+        assert_eq!(*lazy.get().await.unwrap(), 42);
+        assert_eq!(*calls.lock().unwrap(), 1);
+
+        let global = GLOBAL_OPTIMIZER.get_startup_metrics().await;
+        assert!(global.phases.iter().any(|p| p.name == "test.lazy_index" && p.kind == PhaseKind::Lazy));
+
+        let ready = LazySubsystem::ready("test.ready_index", 7);
+        assert!(ready.is_initialized());
+        assert_eq!(*ready.get().await.unwrap(), 7);
+    }
+
+    #[tokio::test]
+    async fn test_event_batcher_aimd() {
+        let batcher = EventBatcher::with_config(BatcherConfig {
+            initial_batch_size: 4,
+            increase_step: 2,
+            ..Default::default()
+        });
+
+        // 批次填满且额外延迟达标 → 加性增大
+        for i in 0..3 {
+            assert!(batcher.push("clicks", i).await.is_none());
+        }
+        assert_eq!(batcher.push("clicks", 3).await.unwrap(), vec![0, 1, 2, 3]);
+        let metrics = batcher.topic_metrics("clicks").await.unwrap();
+        assert_eq!(metrics.batch_size, 6);
+        assert_eq!(metrics.avg_batch_size, 4.0);
+        assert!(metrics.event_rate > 0.0);
+
+        // 下游耗时超标 → 乘性减小
+        batcher.record_downstream_latency("clicks", Duration::from_secs(1)).await;
+        assert_eq!(batcher.topic_metrics("clicks").await.unwrap().batch_size, 3);
+
+        // 主题之间独立调优
+        batcher.add_event(99).await;
+        assert_eq!(batcher.batch_size().await, 1);
+        assert_eq!(batcher.take_batch().await, vec![99]);
+        assert_eq!(batcher.metrics().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_event_batcher_flush_due() {
+        let batcher = EventBatcher::with_config(BatcherConfig {
+            initial_batch_size: 100,
+            max_flush_interval: Duration::from_millis(10),
+            target_added_latency: Duration::from_millis(5),
+            ..Default::default()
+        });
+
+        batcher.push("slow", "a").await;
+        assert!(batcher.flush_due().await.is_empty());
+        assert!(batcher.next_flush_in().await.is_some());
+
+        tokio::time::sleep(Duration::from_millis(15)).await;
+        let flushed = batcher.flush_due().await;
+        assert_eq!(flushed, vec![("slow".to_string(), vec!["a"])]);
+
+        // 额外延迟超过目标 → 批次减小
+        let metrics = batcher.topic_metrics("slow").await.unwrap();
+        assert_eq!(metrics.batch_size, 50);
+        assert_eq!(metrics.decreases, 1);
+        assert!(batcher.next_flush_in().await.is_none());
+    }
+
     #[tokio::test]
     async fn test_debounce() {
         let optimizer = PerformanceOptimizer::new();
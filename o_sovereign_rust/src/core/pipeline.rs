@@ -0,0 +1,328 @@
+// Pipeline - 可配置的 Agent 流水线拓扑
+// 默认链路 MOSS → L6 → Ultron → Omega；通过 ACSAConfig.pipeline 调整中间的复核与审计阶段，
+// 例如跳过 L6、连续两次 Ultron 审计，或插入一个自定义 Agent 阶段，而不必改动 Router
+//
+// 核心功能：
+// 1. 阶段列表：每个阶段有名称、角色（决定提示词与语义）、可选的自定义 Agent 或 Agent 插件（替换该阶段的 Provider）
+// 2. optional：阶段调用失败时跳过而不中止链路；parallel：与前一个阶段并行执行
+// 3. 校验：首个阶段为 MOSS、最后一个为 Omega，中间只允许 L6（复核）与 Ultron（审计），至少一个必需的 Ultron
+// 4. 紧凑写法（CLI `--pipeline`）：`moss,l6?,ultron+ultron:compliance,ultron@gdpr-plugin,omega`
+//
+// 每一轮依次执行中间阶段：L6 角色的输出作为复核结果交给之后的审计，任一 Ultron 驳回即由 MOSS 重新规划，
+// 下一轮从头执行全部中间阶段；Jarvis 校验不属于流水线，始终执行
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use super::types::{AgentResponse, AgentRole};
+
+/// 流水线阶段
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PipelineStage {
+    /// 阶段名称（日志与执行记录用，流水线内唯一）
+    pub name: String,
+    pub role: AgentRole,
+    /// 自定义 Agent 名称（Agent 注册表中的条目）；为空时使用该角色的内置 Provider
+    #[serde(default)]
+    pub agent: Option<String>,
+    /// Agent 插件 ID（PluginSystem 中已加载的 Agent 插件）；与 `agent` 互斥
+    #[serde(default)]
+    pub plugin: Option<String>,
+    /// 调用失败时跳过该阶段继续执行
+    #[serde(default)]
+    pub optional: bool,
+    /// 与前一个阶段并行执行（两者互不可见对方的输出）
+    #[serde(default)]
+    pub parallel: bool,
+}
+
+impl PipelineStage {
+    pub fn new(role: AgentRole) -> Self {
+        Self { name: role.as_str().to_string(), role, agent: None, plugin: None, optional: false, parallel: false }
+    }
+
+    pub fn named(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// 由自定义 Agent 承担该阶段（未改过名称时阶段名随 Agent 名）
+    pub fn with_agent(mut self, agent: impl Into<String>) -> Self {
+        let agent = agent.into();
+        if self.name == self.role.as_str() {
+            self.name = agent.clone();
+        }
+        self.agent = Some(agent);
+        self
+    }
+
+    /// 由 Agent 插件承担该阶段（未改过名称时阶段名随插件 ID）
+    pub fn with_plugin(mut self, plugin_id: impl Into<String>) -> Self {
+        let plugin_id = plugin_id.into();
+        if self.name == self.role.as_str() {
+            self.name = plugin_id.clone();
+        }
+        self.plugin = Some(plugin_id);
+        self
+    }
+
+    pub fn optional(mut self) -> Self {
+        self.optional = true;
+        self
+    }
+
+    pub fn parallel(mut self) -> Self {
+        self.parallel = true;
+        self
+    }
+
+    /// 是否为内置 L6（受 `enable_l6` 与协议权重控制）
+    pub fn is_builtin_l6(&self) -> bool {
+        self.role == AgentRole::L6 && self.agent.is_none() && self.plugin.is_none()
+    }
+}
+
+/// 流水线配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PipelineConfig {
+    pub stages: Vec<PipelineStage>,
+}
+
+impl Default for PipelineConfig {
+    fn default() -> Self {
+        Self {
+            stages: vec![
+                PipelineStage::new(AgentRole::MOSS),
+                PipelineStage::new(AgentRole::L6),
+                PipelineStage::new(AgentRole::Ultron),
+                PipelineStage::new(AgentRole::Omega),
+            ],
+        }
+    }
+}
+
+impl PipelineConfig {
+    pub fn new(stages: Vec<PipelineStage>) -> Self {
+        Self { stages }
+    }
+
+    /// 校验拓扑
+    pub fn validate(&self) -> Result<()> {
+        let (Some(first), Some(last)) = (self.stages.first(), self.stages.last()) else {
+            return Err(anyhow!("Pipeline has no stages"));
+        };
+        if self.stages.len() < 3 {
+            return Err(anyhow!("Pipeline needs at least MOSS, one Ultron audit and Omega"));
+        }
+        if first.role != AgentRole::MOSS || last.role != AgentRole::Omega {
+            return Err(anyhow!("Pipeline must start with MOSS and end with Omega"));
+        }
+        for stage in [first, last] {
+            if stage.optional || stage.parallel {
+                return Err(anyhow!("Stage '{}' cannot be optional or parallel", stage.name));
+            }
+        }
+        for (index, stage) in self.stages.iter().enumerate() {
+            if stage.name.trim().is_empty() {
+                return Err(anyhow!("Pipeline stage {} has no name", index + 1));
+            }
+            if self.stages[..index].iter().any(|other| other.name == stage.name) {
+                return Err(anyhow!("Duplicate pipeline stage name '{}'", stage.name));
+            }
+            if stage.agent.as_deref().is_some_and(|agent| agent.trim().is_empty())
+                || stage.plugin.as_deref().is_some_and(|plugin| plugin.trim().is_empty())
+            {
+                return Err(anyhow!("Stage '{}' has an empty agent or plugin name", stage.name));
+            }
+            if stage.agent.is_some() && stage.plugin.is_some() {
+                return Err(anyhow!("Stage '{}' cannot use both a custom agent and a plugin", stage.name));
+            }
+        }
+        if let Some(stage) = self.review_stages().iter().find(|stage| !matches!(stage.role, AgentRole::L6 | AgentRole::Ultron)) {
+            return Err(anyhow!(
+                "Stage '{}' ({}) must be L6 or Ultron: MOSS and Omega only open and close the pipeline",
+                stage.name,
+                stage.role.as_str()
+            ));
+        }
+        if self.stages[1].parallel {
+            return Err(anyhow!("Stage '{}' cannot run in parallel with MOSS", self.stages[1].name));
+        }
+        if !self.review_stages().iter().any(|stage| stage.role == AgentRole::Ultron && !stage.optional) {
+            return Err(anyhow!("Pipeline needs at least one required Ultron audit"));
+        }
+        Ok(())
+    }
+
+    /// 规划阶段（首个阶段）
+    pub fn planner(&self) -> &PipelineStage {
+        &self.stages[0]
+    }
+
+    /// 执行阶段（最后一个阶段）
+    pub fn executor(&self) -> &PipelineStage {
+        &self.stages[self.stages.len() - 1]
+    }
+
+    /// MOSS 与 Omega 之间的复核与审计阶段
+    pub fn review_stages(&self) -> &[PipelineStage] {
+        match self.stages.len() {
+            0..=2 => &[],
+            len => &self.stages[1..len - 1],
+        }
+    }
+
+    /// 按执行顺序分组：同组阶段并行执行（后一个阶段标记 parallel 时并入前一组）
+    pub fn review_groups(&self) -> Vec<&[PipelineStage]> {
+        let stages = self.review_stages();
+        let mut groups = Vec::new();
+        let mut start = 0;
+        for index in 1..=stages.len() {
+            if index == stages.len() || !stages[index].parallel {
+                groups.push(&stages[start..index]);
+                start = index;
+            }
+        }
+        groups
+    }
+
+    /// 引用的自定义 Agent（去重）
+    pub fn custom_agents(&self) -> Vec<&str> {
+        let mut agents: Vec<&str> = Vec::new();
+        for agent in self.stages.iter().filter_map(|stage| stage.agent.as_deref()) {
+            if !agents.contains(&agent) {
+                agents.push(agent);
+            }
+        }
+        agents
+    }
+
+    /// 引用的 Agent 插件（去重）
+    pub fn plugins(&self) -> Vec<&str> {
+        let mut plugins: Vec<&str> = Vec::new();
+        for plugin in self.stages.iter().filter_map(|stage| stage.plugin.as_deref()) {
+            if !plugins.contains(&plugin) {
+                plugins.push(plugin);
+            }
+        }
+        plugins
+    }
+
+    /// 紧凑写法，如 `moss -> l6 -> ultron+ultron:compliance -> omega`
+    pub fn summary(&self) -> String {
+        let mut summary = String::new();
+        for (index, stage) in self.stages.iter().enumerate() {
+            if index > 0 {
+                summary.push_str(if stage.parallel { "+" } else { " -> " });
+            }
+            summary.push_str(&stage.role.as_str().to_lowercase());
+            if let Some(agent) = &stage.agent {
+                summary.push(':');
+                summary.push_str(agent);
+            }
+            if let Some(plugin) = &stage.plugin {
+                summary.push('@');
+                summary.push_str(plugin);
+            }
+            if stage.optional {
+                summary.push('?');
+            }
+        }
+        summary
+    }
+}
+
+impl std::str::FromStr for PipelineConfig {
+    type Err = anyhow::Error;
+
+    /// 逗号分隔的阶段，`+` 连接的阶段并行，`角色:Agent` 指定自定义 Agent，`角色@插件` 指定 Agent 插件，`?` 结尾为可选阶段
+    fn from_str(s: &str) -> Result<Self> {
+        let mut stages: Vec<PipelineStage> = Vec::new();
+        for group in s.split(',').map(str::trim) {
+            for (position, item) in group.split('+').map(str::trim).enumerate() {
+                let (item, optional) = match item.strip_suffix('?') {
+                    Some(item) => (item.trim_end(), true),
+                    None => (item, false),
+                };
+                let (item, plugin) = match item.split_once('@') {
+                    Some((item, plugin)) => (item.trim(), Some(plugin.trim())),
+                    None => (item, None),
+                };
+                let (role, agent) = match item.split_once(':') {
+                    Some((role, agent)) => (role.trim(), Some(agent.trim())),
+                    None => (item, None),
+                };
+                let role = match role.to_lowercase().as_str() {
+                    "moss" => AgentRole::MOSS,
+                    "l6" => AgentRole::L6,
+                    "ultron" => AgentRole::Ultron,
+                    "omega" => AgentRole::Omega,
+                    other => {
+                        return Err(anyhow!("Unknown pipeline role '{}' (expected moss, l6, ultron or omega)", other))
+                    }
+                };
+                let mut stage = PipelineStage::new(role);
+                if let Some(agent) = agent {
+                    stage = stage.with_agent(agent);
+                }
+                if let Some(plugin) = plugin {
+                    stage = stage.with_plugin(plugin);
+                }
+                // 重名阶段编号：Ultron、Ultron#2
+                let base = stage.name.clone();
+                let mut suffix = 2;
+                while stages.iter().any(|existing| existing.name == stage.name) {
+                    stage.name = format!("{}#{}", base, suffix);
+                    suffix += 1;
+                }
+                stage.optional = optional;
+                stage.parallel = position > 0;
+                stages.push(stage);
+            }
+        }
+        let pipeline = Self { stages };
+        pipeline.validate()?;
+        Ok(pipeline)
+    }
+}
+
+/// 一次复核或审计阶段的输出
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StageOutput {
+    pub stage: String,
+    pub role: AgentRole,
+    #[serde(default)]
+    pub agent: Option<String>,
+    /// 所在轮次（从 1 开始）
+    pub iteration: u32,
+    pub response: AgentResponse,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_validate_pipeline() {
+        assert_eq!(PipelineConfig::default().summary(), "moss -> l6 -> ultron -> omega");
+        assert!(PipelineConfig::default().validate().is_ok());
+
+        let pipeline: PipelineConfig = "moss, l6:fact-checker?, ultron+ultron:compliance, ultron, omega".parse().unwrap();
+        assert_eq!(pipeline.summary(), "moss -> l6:fact-checker? -> ultron+ultron:compliance -> ultron -> omega");
+        let names: Vec<_> = pipeline.stages.iter().map(|stage| stage.name.as_str()).collect();
+        assert_eq!(names, vec!["MOSS", "fact-checker", "Ultron", "compliance", "Ultron#2", "Omega"]);
+        let groups: Vec<usize> = pipeline.review_groups().iter().map(|group| group.len()).collect();
+        assert_eq!(groups, vec![1, 2, 1]);
+        assert_eq!(pipeline.custom_agents(), vec!["fact-checker", "compliance"]);
+
+        // 跳过 L6 合法；缺少必需的 Ultron、首尾错误、中间出现 Omega 均不合法
+        assert!("moss,ultron,omega".parse::<PipelineConfig>().is_ok());
+        let plugged: PipelineConfig = "moss,ultron@gdpr-auditor,omega".parse().unwrap();
+        assert_eq!(plugged.stages[1].name, "gdpr-auditor");
+        assert_eq!(plugged.plugins(), vec!["gdpr-auditor"]);
+        assert!("moss,ultron:x@y,omega".parse::<PipelineConfig>().is_err());
+        for invalid in ["moss,l6,omega", "moss,ultron?,omega", "l6,ultron,omega", "moss,ultron,omega,omega", "moss+ultron,omega", "moss,judge,omega"] {
+            assert!(invalid.parse::<PipelineConfig>().is_err(), "{} should be rejected", invalid);
+        }
+    }
+}
@@ -0,0 +1,326 @@
+// Plan Diff - 路由迭代间的方案差异
+// Ultron 审计驳回后 MOSS 会重新规划，这里对比前后两版方案，让用户看到反馈如何改变了最终输出
+//
+// 核心功能：
+// 1. 将 MOSS 方案切分为步骤（编号 / 列表项 / "Step N:"，否则按非空行）
+// 2. 基于 LCS 对齐步骤，得到新增 / 删除 / 保留
+// 3. 相邻的删除 + 新增按词相似度配对为"修改"
+// 4. 附带触发重新规划的审计反馈，写入执行日志并在 CLI / TUI 中渲染
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::LazyLock;
+
+/// 删除 + 新增被视为同一步骤修改的最低相似度
+const MODIFIED_SIMILARITY: f64 = 0.4;
+
+/// 步骤前缀：`1.` `2)` `-` `*` `•` `Step 3:` `步骤3：`
+static STEP_MARKER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*(?:(?:[Ss]tep|STEP|步骤)\s*\d+\s*[:：.)]?|\d+\s*[.)、]|[-*•])\s+").unwrap()
+});
+
+/// 单个步骤的变化
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StepChange {
+    /// 新方案中新增的步骤（index 为新方案中的位置，从 1 开始）
+    Added { index: usize, text: String },
+    /// 旧方案中被删除的步骤（index 为旧方案中的位置）
+    Removed { index: usize, text: String },
+    /// 同一步骤被改写
+    Modified { from_index: usize, to_index: usize, before: String, after: String },
+    /// 保留未变
+    Unchanged { from_index: usize, to_index: usize, text: String },
+}
+
+impl StepChange {
+    /// 渲染前缀符号
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            StepChange::Added { .. } => "+",
+            StepChange::Removed { .. } => "-",
+            StepChange::Modified { .. } => "~",
+            StepChange::Unchanged { .. } => " ",
+        }
+    }
+}
+
+/// 两次迭代之间的方案差异
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlanDiff {
+    /// 旧方案所在迭代（从 1 开始）
+    pub from_iteration: u32,
+    /// 新方案所在迭代
+    pub to_iteration: u32,
+    /// 触发重新规划的审计反馈
+    pub feedback: String,
+    /// 驳回时的风险分
+    pub risk_score: u8,
+    pub changes: Vec<StepChange>,
+}
+
+impl PlanDiff {
+    /// 对比两版方案
+    pub fn between(before: &str, after: &str) -> Self {
+        Self {
+            from_iteration: 0,
+            to_iteration: 0,
+            feedback: String::new(),
+            risk_score: 0,
+            changes: diff_steps(&split_steps(before), &split_steps(after)),
+        }
+    }
+
+    /// 标记迭代序号
+    pub fn with_iterations(mut self, from: u32, to: u32) -> Self {
+        self.from_iteration = from;
+        self.to_iteration = to;
+        self
+    }
+
+    /// 附带审计反馈
+    pub fn with_feedback(mut self, feedback: impl Into<String>, risk_score: u8) -> Self {
+        self.feedback = feedback.into();
+        self.risk_score = risk_score;
+        self
+    }
+
+    pub fn added(&self) -> usize {
+        self.count(|c| matches!(c, StepChange::Added { .. }))
+    }
+
+    pub fn removed(&self) -> usize {
+        self.count(|c| matches!(c, StepChange::Removed { .. }))
+    }
+
+    pub fn modified(&self) -> usize {
+        self.count(|c| matches!(c, StepChange::Modified { .. }))
+    }
+
+    /// 方案是否完全没有变化
+    pub fn is_unchanged(&self) -> bool {
+        self.changes.iter().all(|c| matches!(c, StepChange::Unchanged { .. }))
+    }
+
+    fn count(&self, predicate: impl Fn(&StepChange) -> bool) -> usize {
+        self.changes.iter().filter(|c| predicate(c)).count()
+    }
+
+    /// 一行摘要
+    pub fn summary(&self) -> String {
+        format!(
+            "iteration {} → {}: +{} -{} ~{}",
+            self.from_iteration,
+            self.to_iteration,
+            self.added(),
+            self.removed(),
+            self.modified()
+        )
+    }
+
+    /// 渲染为纯文本（CLI / TUI 共用），未变化的步骤折叠为计数
+    pub fn render(&self) -> String {
+        let mut out = format!("🔀 Plan diff {}\n", self.summary());
+        if !self.feedback.is_empty() {
+            out.push_str(&format!("   🛡️  Feedback (risk {}/100): {}\n", self.risk_score, first_line(&self.feedback)));
+        }
+        if self.is_unchanged() {
+            out.push_str("   (plan unchanged)\n");
+            return out;
+        }
+        let mut unchanged = 0;
+        for change in &self.changes {
+            if let StepChange::Unchanged { .. } = change {
+                unchanged += 1;
+                continue;
+            }
+            if unchanged > 0 {
+                out.push_str(&format!("     … {} unchanged\n", unchanged));
+                unchanged = 0;
+            }
+            match change {
+                StepChange::Added { index, text } => out.push_str(&format!("   + [{}] {}\n", index, text)),
+                StepChange::Removed { index, text } => out.push_str(&format!("   - [{}] {}\n", index, text)),
+                StepChange::Modified { from_index, to_index, before, after } => {
+                    out.push_str(&format!("   ~ [{}→{}] {}\n", from_index, to_index, before));
+                    out.push_str(&format!("         → {}\n", after));
+                }
+                StepChange::Unchanged { .. } => {}
+            }
+        }
+        if unchanged > 0 {
+            out.push_str(&format!("     … {} unchanged\n", unchanged));
+        }
+        out
+    }
+}
+
+fn first_line(text: &str) -> &str {
+    text.lines().map(str::trim).find(|l| !l.is_empty()).unwrap_or("")
+}
+
+/// 将方案切分为步骤；没有任何编号或列表项时退化为按非空行
+pub fn split_steps(plan: &str) -> Vec<String> {
+    let lines: Vec<&str> = plan.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+    if !lines.iter().any(|l| STEP_MARKER.is_match(l)) {
+        return lines.into_iter().map(str::to_string).collect();
+    }
+
+    // 非步骤行（续行）并入上一个步骤，步骤之前的标题行单独保留
+    let mut steps: Vec<String> = Vec::new();
+    let mut in_step = false;
+    for line in lines {
+        if STEP_MARKER.is_match(line) {
+            steps.push(STEP_MARKER.replace(line, "").trim().to_string());
+            in_step = true;
+        } else if in_step {
+            let last = steps.last_mut().expect("in_step implies a step");
+            last.push(' ');
+            last.push_str(line);
+        } else {
+            steps.push(line.to_string());
+        }
+    }
+    steps
+}
+
+/// 比较用的规范化：小写并压缩空白
+fn normalize(step: &str) -> String {
+    step.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// 词集合 Jaccard 相似度（中日韩文本按单字）
+fn similarity(a: &str, b: &str) -> f64 {
+    fn tokens(s: &str) -> HashSet<String> {
+        let mut set = HashSet::new();
+        for word in s.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()) {
+            if word.is_ascii() {
+                set.insert(word.to_string());
+            } else {
+                set.extend(word.chars().map(String::from));
+            }
+        }
+        set
+    }
+    let (a, b) = (tokens(a), tokens(b));
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    a.intersection(&b).count() as f64 / a.union(&b).count() as f64
+}
+
+/// 基于 LCS 的步骤对齐
+fn diff_steps(before: &[String], after: &[String]) -> Vec<StepChange> {
+    let old: Vec<String> = before.iter().map(|s| normalize(s)).collect();
+    let new: Vec<String> = after.iter().map(|s| normalize(s)).collect();
+    let (n, m) = (old.len(), new.len());
+
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old[i] == new[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+        }
+    }
+
+    let mut changes = Vec::new();
+    let (mut removed, mut added): (Vec<usize>, Vec<usize>) = (Vec::new(), Vec::new());
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && old[i] == new[j] {
+            flush_run(before, after, &mut removed, &mut added, &mut changes);
+            changes.push(StepChange::Unchanged { from_index: i + 1, to_index: j + 1, text: after[j].clone() });
+            i += 1;
+            j += 1;
+        } else if j < m && (i == n || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            added.push(j);
+            j += 1;
+        } else {
+            removed.push(i);
+            i += 1;
+        }
+    }
+    flush_run(before, after, &mut removed, &mut added, &mut changes);
+    changes
+}
+
+/// 处理一段连续的删除 / 新增：按顺序配对足够相似的步骤为修改
+fn flush_run(
+    before: &[String],
+    after: &[String],
+    removed: &mut Vec<usize>,
+    added: &mut Vec<usize>,
+    changes: &mut Vec<StepChange>,
+) {
+    let mut pending_added: &[usize] = added;
+    for &r in removed.iter() {
+        let matched = pending_added
+            .iter()
+            .position(|&a| similarity(&before[r], &after[a]) >= MODIFIED_SIMILARITY);
+        match matched {
+            Some(pos) => {
+                for &a in &pending_added[..pos] {
+                    changes.push(StepChange::Added { index: a + 1, text: after[a].clone() });
+                }
+                let a = pending_added[pos];
+                changes.push(StepChange::Modified {
+                    from_index: r + 1,
+                    to_index: a + 1,
+                    before: before[r].clone(),
+                    after: after[a].clone(),
+                });
+                pending_added = &pending_added[pos + 1..];
+            }
+            None => changes.push(StepChange::Removed { index: r + 1, text: before[r].clone() }),
+        }
+    }
+    for &a in pending_added {
+        changes.push(StepChange::Added { index: a + 1, text: after[a].clone() });
+    }
+    removed.clear();
+    added.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_steps_merges_continuation_lines() {
+        let plan = "Execution plan:\n1. Collect requirements\n   from stakeholders\n2) Build prototype\n- Ship it";
+        assert_eq!(
+            split_steps(plan),
+            vec!["Execution plan:", "Collect requirements from stakeholders", "Build prototype", "Ship it"]
+        );
+        assert_eq!(split_steps("alpha\n\nbeta"), vec!["alpha", "beta"]);
+    }
+
+    #[test]
+    fn test_diff_classifies_added_removed_modified() {
+        let before = "1. Scrape competitor pricing data\n2. Undercut prices by 50%\n3. Launch campaign";
+        let after = "1. Scrape competitor pricing data\n2. Collect public pricing data legally\n\
+                     3. Launch campaign\n4. Review with legal counsel";
+        let diff = PlanDiff::between(before, after).with_iterations(1, 2).with_feedback("Avoid scraping", 72);
+
+        assert_eq!(diff.added(), 2);
+        assert_eq!(diff.removed(), 1);
+        assert_eq!(diff.modified(), 0);
+        assert!(!diff.is_unchanged());
+
+        let reworded = PlanDiff::between("1. Build the HTTP server", "1. Build the HTTPS server with TLS");
+        assert_eq!(reworded.modified(), 1);
+
+        let rendered = diff.render();
+        assert!(rendered.contains("iteration 1 → 2: +2 -1 ~0"));
+        assert!(rendered.contains("risk 72/100"));
+        assert!(rendered.contains("+ [4] Review with legal counsel"));
+        assert!(rendered.contains("… 1 unchanged"));
+    }
+
+    #[test]
+    fn test_identical_plans_are_unchanged() {
+        let diff = PlanDiff::between("1. A step\n2. Another", "1.  a STEP\n2. another");
+        assert!(diff.is_unchanged());
+        assert!(diff.render().contains("plan unchanged"));
+    }
+}
@@ -0,0 +1,477 @@
+// Plugin Installer - 插件包安装与管理
+// `o-sovereign plugin install <url|path>`：下载插件包、校验摘要与签名、检查依赖，解压到插件目录；
+// PluginSystem 启动时从这里发现已启用的插件
+//
+// 核心功能：
+// 1. 插件包：zip，根目录 manifest.json（PluginMetadata + 入口文件 + 默认配置）与插件文件
+// 2. 校验：SHA-256 与 Ed25519 签名（SosaCryptoEngine）；未签名的包需显式允许
+// 3. 依赖：`dependencies` 中的插件须已安装并启用，可写版本要求（`id@^1.2`）
+// 4. 安装记录（installed.json）：启用 / 禁用 / 删除，被依赖的插件不能直接禁用或删除
+//
+// 目录结构：{plugins_dir}/installed.json 与 {plugins_dir}/{plugin_id}/（解压后的插件包）
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+use super::offline;
+use super::plugin_system::PluginMetadata;
+use super::sosa_crypto::{SosaCryptoConfig, SosaCryptoEngine};
+
+/// 插件包中的清单文件
+pub const PLUGIN_MANIFEST: &str = "manifest.json";
+
+/// 安装记录文件（位于插件目录）
+const INSTALLED_FILE: &str = "installed.json";
+
+/// 插件包清单
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginManifest {
+    #[serde(flatten)]
+    pub metadata: PluginMetadata,
+    /// 入口文件（包内相对路径，如 `plugin.wasm`）
+    pub entry: String,
+    /// 默认插件配置
+    #[serde(default)]
+    pub settings: HashMap<String, String>,
+}
+
+impl PluginManifest {
+    /// 校验清单：ID 可作为目录名、版本为 semver、入口路径不越出插件目录、资源限制有效
+    pub fn validate(&self) -> Result<()> {
+        let metadata = &self.metadata;
+        let id = &metadata.plugin_id;
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')) || id.starts_with('.') {
+            bail!("Invalid plugin id '{}' (use letters, digits, '-', '_' and '.')", id);
+        }
+        if metadata.name.trim().is_empty() {
+            bail!("Plugin {} has no name", id);
+        }
+        semver::Version::parse(&metadata.version)
+            .with_context(|| format!("Plugin {} has an invalid version '{}'", id, metadata.version))?;
+        let entry = Path::new(&self.entry);
+        if self.entry.is_empty()
+            || entry.is_absolute()
+            || entry.components().any(|c| !matches!(c, std::path::Component::Normal(_)))
+        {
+            bail!("Plugin {} has an invalid entry '{}'", id, self.entry);
+        }
+        let limits = &metadata.resource_limits;
+        if limits.max_memory_mb == 0 || limits.max_concurrent_requests == 0 || limits.request_timeout_secs == 0 {
+            bail!("Plugin {} has zero resource limits", id);
+        }
+        for dependency in &metadata.dependencies {
+            let (dep_id, _) = parse_dependency(dependency)?;
+            if dep_id == id {
+                bail!("Plugin {} depends on itself", id);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 已安装的插件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstalledPlugin {
+    pub manifest: PluginManifest,
+    /// 安装来源（URL 或路径）
+    pub source: String,
+    /// 插件包的 SHA-256
+    pub sha256: String,
+    /// 是否通过签名校验
+    pub signed: bool,
+    pub enabled: bool,
+    pub installed_at: DateTime<Utc>,
+}
+
+impl InstalledPlugin {
+    pub fn id(&self) -> &str {
+        &self.manifest.metadata.plugin_id
+    }
+}
+
+/// 安装选项
+#[derive(Debug, Clone, Default)]
+pub struct InstallOptions {
+    /// 期望的 SHA-256（为空时读取 `<来源>.sha256`）
+    pub sha256: Option<String>,
+    /// Ed25519 签名（base64，为空时读取 `<来源>.sig`）
+    pub signature: Option<String>,
+    /// 验签公钥
+    pub public_key: Option<String>,
+    /// 允许安装未签名的插件包
+    pub allow_unsigned: bool,
+    /// 覆盖已安装的同版本或更新版本
+    pub force: bool,
+}
+
+/// 插件安装器
+pub struct PluginInstaller {
+    dir: PathBuf,
+    crypto: SosaCryptoEngine,
+}
+
+impl PluginInstaller {
+    pub fn new(plugins_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: plugins_dir.into(),
+            crypto: SosaCryptoEngine::new(SosaCryptoConfig::default()),
+        }
+    }
+
+    /// 已安装的插件（按安装顺序，依赖在前）
+    pub fn list(&self) -> Result<Vec<InstalledPlugin>> {
+        let path = self.dir.join(INSTALLED_FILE);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        serde_json::from_str(&std::fs::read_to_string(&path)?)
+            .with_context(|| format!("Corrupted plugin registry {}", path.display()))
+    }
+
+    /// 插件入口文件的路径
+    pub fn entry_path(&self, plugin: &InstalledPlugin) -> PathBuf {
+        self.dir.join(plugin.id()).join(&plugin.manifest.entry)
+    }
+
+    /// 从 URL 或本地路径安装
+    pub async fn install(&self, source: &str, options: &InstallOptions) -> Result<InstalledPlugin> {
+        let bytes = fetch(source).await?.ok_or_else(|| anyhow!("Plugin package not found: {}", source))?;
+        let mut options = options.clone();
+        if options.sha256.is_none() {
+            options.sha256 = fetch_text(&format!("{}.sha256", source))
+                .await?
+                .and_then(|text| text.split_whitespace().next().map(str::to_string));
+        }
+        if options.signature.is_none() {
+            options.signature = fetch_text(&format!("{}.sig", source)).await?.map(|text| text.trim().to_string());
+        }
+        self.install_package(source, &bytes, &options)
+    }
+
+    /// 校验并安装插件包
+    pub fn install_package(&self, source: &str, bytes: &[u8], options: &InstallOptions) -> Result<InstalledPlugin> {
+        let sha256 = self.crypto.sha256_hex(bytes);
+        if let Some(expected) = &options.sha256 {
+            if !sha256.eq_ignore_ascii_case(expected.trim()) {
+                bail!("Checksum mismatch: expected {}, got {}", expected.trim(), sha256);
+            }
+        }
+        let signed = match &options.signature {
+            Some(signature) => {
+                let public_key = options
+                    .public_key
+                    .as_deref()
+                    .filter(|key| !key.is_empty())
+                    .ok_or_else(|| anyhow!("Plugin package is signed but no public key is configured (set ACSA_PLUGIN_PUBLIC_KEY)"))?;
+                self.crypto
+                    .verify_detached(public_key, bytes, signature)
+                    .context("Plugin package signature is invalid")?;
+                true
+            }
+            None if options.allow_unsigned => false,
+            None => bail!("Plugin package is not signed (pass --allow-unsigned to install it anyway)"),
+        };
+
+        let mut archive =
+            zip::ZipArchive::new(std::io::Cursor::new(bytes)).map_err(|e| anyhow!("Not a plugin package: {}", e))?;
+        let manifest: PluginManifest = {
+            let mut file = archive
+                .by_name(PLUGIN_MANIFEST)
+                .map_err(|_| anyhow!("Plugin package has no {}", PLUGIN_MANIFEST))?;
+            let mut json = String::new();
+            file.read_to_string(&mut json)?;
+            serde_json::from_str(&json).with_context(|| format!("Invalid {}", PLUGIN_MANIFEST))?
+        };
+        manifest.validate()?;
+        let id = manifest.metadata.plugin_id.clone();
+        if archive.by_name(&manifest.entry).is_err() {
+            bail!("Plugin package {} lacks its entry file {}", id, manifest.entry);
+        }
+
+        let mut installed = self.list()?;
+        if let Some(existing) = installed.iter().find(|plugin| plugin.id() == id) {
+            let current = semver::Version::parse(&existing.manifest.metadata.version)?;
+            let candidate = semver::Version::parse(&manifest.metadata.version)?;
+            if candidate <= current && !options.force {
+                bail!("Plugin {} v{} is already installed (use --force to reinstall)", id, current);
+            }
+        }
+        check_dependencies(&manifest.metadata, &installed)?;
+
+        // 解压到临时目录，完整后替换旧版本
+        std::fs::create_dir_all(&self.dir).with_context(|| format!("Failed to create {}", self.dir.display()))?;
+        let staging = self.dir.join(format!(".{}.staging", id));
+        if staging.exists() {
+            std::fs::remove_dir_all(&staging)?;
+        }
+        for index in 0..archive.len() {
+            let mut file = archive.by_index(index)?;
+            let relative = file
+                .enclosed_name()
+                .ok_or_else(|| anyhow!("Plugin package contains an unsafe path: {}", file.name()))?;
+            let target = staging.join(relative);
+            if file.is_dir() {
+                std::fs::create_dir_all(&target)?;
+                continue;
+            }
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let mut contents = Vec::new();
+            file.read_to_end(&mut contents)?;
+            std::fs::write(&target, contents)?;
+        }
+        let target = self.dir.join(&id);
+        if target.exists() {
+            std::fs::remove_dir_all(&target)?;
+        }
+        std::fs::rename(&staging, &target).with_context(|| format!("Failed to install into {}", target.display()))?;
+
+        let plugin = InstalledPlugin {
+            manifest,
+            source: source.to_string(),
+            sha256,
+            signed,
+            enabled: true,
+            installed_at: Utc::now(),
+        };
+        installed.retain(|existing| existing.id() != id);
+        installed.push(plugin.clone());
+        self.save(&installed)?;
+        info!("📦 Installed plugin {} v{}{}", id, plugin.manifest.metadata.version, if signed { "" } else { " (unsigned)" });
+        Ok(plugin)
+    }
+
+    /// 启用或禁用；启用时依赖须已启用，禁用时不能有已启用的插件依赖它
+    pub fn set_enabled(&self, plugin_id: &str, enabled: bool) -> Result<InstalledPlugin> {
+        let mut installed = self.list()?;
+        let index = position(&installed, plugin_id)?;
+        if enabled {
+            let others: Vec<InstalledPlugin> =
+                installed.iter().filter(|plugin| plugin.id() != plugin_id).cloned().collect();
+            check_dependencies(&installed[index].manifest.metadata, &others)?;
+        } else if let Some(dependent) = dependents(&installed, plugin_id).into_iter().find(|plugin| plugin.enabled) {
+            bail!("Plugin {} is required by {}; disable that first", plugin_id, dependent.id());
+        }
+        installed[index].enabled = enabled;
+        let plugin = installed[index].clone();
+        self.save(&installed)?;
+        Ok(plugin)
+    }
+
+    /// 删除插件文件与安装记录；`force` 时忽略依赖它的插件
+    pub fn remove(&self, plugin_id: &str, force: bool) -> Result<InstalledPlugin> {
+        let mut installed = self.list()?;
+        let index = position(&installed, plugin_id)?;
+        if !force {
+            if let Some(dependent) = dependents(&installed, plugin_id).first() {
+                bail!("Plugin {} is required by {} (use --force to remove anyway)", plugin_id, dependent.id());
+            }
+        }
+        let plugin = installed.remove(index);
+        let dir = self.dir.join(plugin_id);
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir).with_context(|| format!("Failed to remove {}", dir.display()))?;
+        }
+        self.save(&installed)?;
+        info!("🗑️  Removed plugin {}", plugin_id);
+        Ok(plugin)
+    }
+
+    fn save(&self, installed: &[InstalledPlugin]) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join(INSTALLED_FILE);
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(installed)?)?;
+        std::fs::rename(&tmp, &path).with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// `id` 或 `id@版本要求`
+fn parse_dependency(dependency: &str) -> Result<(&str, Option<semver::VersionReq>)> {
+    match dependency.split_once('@') {
+        Some((id, requirement)) => {
+            let requirement = semver::VersionReq::parse(requirement.trim())
+                .with_context(|| format!("Invalid version requirement in dependency '{}'", dependency))?;
+            Ok((id.trim(), Some(requirement)))
+        }
+        None => Ok((dependency.trim(), None)),
+    }
+}
+
+/// 依赖须已安装、已启用且版本满足要求
+fn check_dependencies(metadata: &PluginMetadata, installed: &[InstalledPlugin]) -> Result<()> {
+    for dependency in &metadata.dependencies {
+        let (dep_id, requirement) = parse_dependency(dependency)?;
+        let plugin = installed
+            .iter()
+            .find(|plugin| plugin.id() == dep_id)
+            .ok_or_else(|| anyhow!("Plugin {} requires {}, which is not installed", metadata.plugin_id, dependency))?;
+        if !plugin.enabled {
+            bail!("Plugin {} requires {}, which is disabled", metadata.plugin_id, dep_id);
+        }
+        if let Some(requirement) = requirement {
+            let version = semver::Version::parse(&plugin.manifest.metadata.version)?;
+            if !requirement.matches(&version) {
+                bail!("Plugin {} requires {}, but v{} is installed", metadata.plugin_id, dependency, version);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn dependents<'a>(installed: &'a [InstalledPlugin], plugin_id: &str) -> Vec<&'a InstalledPlugin> {
+    installed
+        .iter()
+        .filter(|plugin| {
+            plugin
+                .manifest
+                .metadata
+                .dependencies
+                .iter()
+                .any(|dependency| parse_dependency(dependency).is_ok_and(|(id, _)| id == plugin_id))
+        })
+        .collect()
+}
+
+fn position(installed: &[InstalledPlugin], plugin_id: &str) -> Result<usize> {
+    installed
+        .iter()
+        .position(|plugin| plugin.id() == plugin_id)
+        .ok_or_else(|| anyhow!("Plugin {} is not installed", plugin_id))
+}
+
+/// 读取 URL 或本地文件；不存在时为 None
+async fn fetch(source: &str) -> Result<Option<Vec<u8>>> {
+    if source.starts_with("http://") || source.starts_with("https://") {
+        if offline::is_offline() {
+            bail!("Installing from {} needs network access (offline mode is on)", source);
+        }
+        let response = reqwest::get(source).await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        return Ok(Some(response.error_for_status()?.bytes().await?.to_vec()));
+    }
+    let path = Path::new(source);
+    if !path.exists() {
+        return Ok(None);
+    }
+    Ok(Some(std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?))
+}
+
+async fn fetch_text(source: &str) -> Result<Option<String>> {
+    Ok(fetch(source).await?.map(|bytes| String::from_utf8_lossy(&bytes).into_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::plugin_system::{PluginType, ResourceLimits};
+    use std::io::Write;
+
+    fn package(plugin_id: &str, version: &str, dependencies: &[&str]) -> Vec<u8> {
+        let manifest = PluginManifest {
+            metadata: PluginMetadata {
+                plugin_id: plugin_id.to_string(),
+                name: plugin_id.to_string(),
+                version: version.to_string(),
+                author: "ACSA Team".to_string(),
+                description: "test plugin".to_string(),
+                plugin_type: PluginType::Tool,
+                dependencies: dependencies.iter().map(|d| d.to_string()).collect(),
+                supported_protocols: vec![],
+                resource_limits: ResourceLimits::default(),
+                created_at: Utc::now(),
+            },
+            entry: "plugin.wasm".to_string(),
+            settings: HashMap::new(),
+        };
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        {
+            let mut zip = zip::ZipWriter::new(&mut buffer);
+            let options = zip::write::SimpleFileOptions::default();
+            zip.start_file(PLUGIN_MANIFEST, options).unwrap();
+            zip.write_all(serde_json::to_string(&manifest).unwrap().as_bytes()).unwrap();
+            zip.start_file("plugin.wasm", options).unwrap();
+            zip.write_all(b"\0asm\x01\0\0\0").unwrap();
+            zip.finish().unwrap();
+        }
+        buffer.into_inner()
+    }
+
+    #[tokio::test]
+    async fn test_install_verifies_signature_and_dependencies() {
+        let dir = tempfile::tempdir().unwrap();
+        let installer = PluginInstaller::new(dir.path().join("plugins"));
+        let crypto = SosaCryptoEngine::new(SosaCryptoConfig::default());
+        let (private_key, public_key) = crypto.generate_signing_keypair().unwrap();
+
+        // 本地路径安装：签名从同名 .sig 文件读取
+        let base = package("kv-base", "1.2.0", &[]);
+        let base_path = dir.path().join("kv-base.zip");
+        std::fs::write(&base_path, &base).unwrap();
+        std::fs::write(dir.path().join("kv-base.zip.sig"), crypto.sign_detached(&private_key, &base).unwrap()).unwrap();
+        let options = InstallOptions { public_key: Some(public_key.clone()), ..Default::default() };
+        let installed = installer.install(base_path.to_str().unwrap(), &options).await.unwrap();
+        assert!(installed.signed);
+        assert!(installer.entry_path(&installed).exists());
+
+        // 未签名、签名不符、摘要不符均拒绝
+        let notes = package("notes", "0.1.0", &["kv-base@^1.0"]);
+        assert!(installer.install_package("notes.zip", &notes, &options).is_err());
+        let forged = InstallOptions { signature: crypto.sign_detached(&private_key, &base).ok(), ..options.clone() };
+        assert!(installer.install_package("notes.zip", &notes, &forged).is_err());
+        let unsigned = InstallOptions { allow_unsigned: true, ..Default::default() };
+        let wrong_digest = InstallOptions { sha256: Some("00".repeat(32)), ..unsigned.clone() };
+        assert!(installer.install_package("notes.zip", &notes, &wrong_digest).is_err());
+
+        // 依赖版本要求不满足、依赖未安装
+        let strict = package("strict", "0.1.0", &["kv-base@>=2"]);
+        assert!(installer.install_package("strict.zip", &strict, &unsigned).unwrap_err().to_string().contains("v1.2.0"));
+        let orphan = package("orphan", "0.1.0", &["missing"]);
+        assert!(installer.install_package("orphan.zip", &orphan, &unsigned).is_err());
+
+        let notes = installer.install_package("notes.zip", &notes, &unsigned).unwrap();
+        assert!(!notes.signed);
+        // 同版本重复安装需 --force
+        let again = package("notes", "0.1.0", &["kv-base"]);
+        assert!(installer.install_package("notes.zip", &again, &unsigned).is_err());
+        let forced = InstallOptions { force: true, ..unsigned.clone() };
+        installer.install_package("notes.zip", &again, &forced).unwrap();
+        assert_eq!(installer.list().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_enable_disable_and_remove_respect_dependents() {
+        let dir = tempfile::tempdir().unwrap();
+        let installer = PluginInstaller::new(dir.path());
+        let unsigned = InstallOptions { allow_unsigned: true, ..Default::default() };
+        installer.install_package("base.zip", &package("base", "1.0.0", &[]), &unsigned).unwrap();
+        installer.install_package("app.zip", &package("app", "1.0.0", &["base"]), &unsigned).unwrap();
+
+        assert!(installer.set_enabled("base", false).is_err());
+        assert!(installer.remove("base", false).is_err());
+        assert!(!installer.set_enabled("app", false).unwrap().enabled);
+        installer.set_enabled("base", false).unwrap();
+        // 依赖被禁用时不能启用
+        assert!(installer.set_enabled("app", true).is_err());
+
+        installer.remove("base", true).unwrap();
+        assert!(!dir.path().join("base").exists());
+        assert_eq!(installer.list().unwrap().iter().map(|p| p.id().to_string()).collect::<Vec<_>>(), vec!["app"]);
+        assert!(installer.remove("base", false).is_err());
+        // 路径穿越的清单被拒绝
+        let mut manifest: PluginManifest = serde_json::from_str(
+            &std::fs::read_to_string(dir.path().join("app").join(PLUGIN_MANIFEST)).unwrap(),
+        )
+        .unwrap();
+        manifest.entry = "../escape.wasm".to_string();
+        assert!(manifest.validate().is_err());
+    }
+}
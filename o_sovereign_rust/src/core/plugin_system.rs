@@ -9,6 +9,11 @@
 // 5. 插件通信（via Event Bus）
 // 6. 热重载（Hot Reload）
 // 7. 依赖管理
+// 8. Agent 插件：实现 AgentPlugin 的插件作为流水线阶段参与执行，每次调用按 ResourceLimits 限制并发数与超时
+// 9. WASM 沙箱插件（feature = "wasm"）：.wasm 文件由 wasm_plugin 运行时加载，启用沙箱时只允许加载 WASM 插件，
+//    插件文件修改后就地热重载
+//
+// 注：内存与 CPU 限制需要进程隔离（沙箱）才能生效，进程内注册的 Agent 插件只受并发数与超时限制
 
 use anyhow::{anyhow, Result};
 use chrono::{DateTime, Utc};
@@ -16,11 +21,20 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::{Duration, Instant};
+use tokio::sync::{RwLock, Semaphore};
 use tracing::{info, warn};
 
 use super::event_bus::{Event, EventBus};
+use super::plugin_installer::PluginInstaller;
 use super::protocol::Protocol;
+use super::types::{AgentResponse, AgentRole};
+
+/// 默认插件目录
+pub const DEFAULT_PLUGINS_DIR: &str = "./plugins";
+
+/// Agent 插件接口版本（接口不兼容变更时递增）
+pub const AGENT_PLUGIN_API_VERSION: u32 = 1;
 
 /// 插件状态
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -69,13 +83,17 @@ pub struct PluginMetadata {
     pub description: String,
     /// 插件类型
     pub plugin_type: PluginType,
-    /// 依赖的其他插件
+    /// 依赖的其他插件（插件包清单中可写版本要求，如 `kv-store@^1.2`）
+    #[serde(default)]
     pub dependencies: Vec<String>,
     /// 支持的Protocol（如果是Agent插件）
+    #[serde(default)]
     pub supported_protocols: Vec<Protocol>,
     /// 资源限制
+    #[serde(default)]
     pub resource_limits: ResourceLimits,
     /// 创建时间
+    #[serde(default = "Utc::now")]
     pub created_at: DateTime<Utc>,
 }
 
@@ -90,6 +108,13 @@ pub struct ResourceLimits {
     pub max_concurrent_requests: usize,
     /// 请求超时时间（秒）
     pub request_timeout_secs: u64,
+    /// WASM 插件每次请求的 fuel 上限（约等于可执行的指令数）
+    #[serde(default = "default_max_fuel")]
+    pub max_fuel: u64,
+}
+
+fn default_max_fuel() -> u64 {
+    100_000_000
 }
 
 impl Default for ResourceLimits {
@@ -99,6 +124,7 @@ impl Default for ResourceLimits {
             max_cpu_percent: 50,
             max_concurrent_requests: 10,
             request_timeout_secs: 30,
+            max_fuel: default_max_fuel(),
         }
     }
 }
@@ -126,6 +152,10 @@ pub struct Plugin {
     pub loaded_at: Option<DateTime<Utc>>,
     /// 统计信息
     pub stats: Arc<RwLock<PluginStats>>,
+    /// Agent 插件实现（仅 Agent 类型）
+    agent: Option<Arc<dyn AgentPlugin>>,
+    /// 并发请求许可（max_concurrent_requests）
+    permits: Arc<Semaphore>,
 }
 
 /// 插件统计
@@ -162,6 +192,46 @@ pub trait PluginHandler: Send + Sync {
 
     /// 健康检查
     async fn health_check(&self) -> bool;
+
+    /// 插件文件变化时就地重新加载，返回是否已重载
+    async fn reload_if_changed(&self) -> Result<bool> {
+        Ok(false)
+    }
+}
+
+/// 宿主工具：WASM 插件通过宿主 API `tool-call` 调用（参数与结果均为字符串）
+pub type HostTool = Arc<dyn Fn(&str) -> Result<String> + Send + Sync>;
+
+/// Agent 插件的生成结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AgentPluginOutput {
+    pub text: String,
+    /// 消耗的 token（未知时为 0）
+    #[serde(default)]
+    pub tokens: u32,
+    /// 花费（美元）
+    #[serde(default)]
+    pub cost: f64,
+}
+
+/// Agent 插件：承担流水线中某个角色的阶段（流水线写法 `ultron@插件ID`）
+#[async_trait::async_trait]
+pub trait AgentPlugin: Send + Sync {
+    /// 实现的接口版本，须等于 AGENT_PLUGIN_API_VERSION
+    fn api_version(&self) -> u32 {
+        AGENT_PLUGIN_API_VERSION
+    }
+
+    /// 可承担的角色（流水线阶段的角色须与之一致）
+    fn role(&self) -> AgentRole;
+
+    /// 权重：缩放该阶段的输出 token 上限（1.0 为默认上限，取值范围 0.5–2）
+    fn weight(&self) -> f64 {
+        1.0
+    }
+
+    /// 按提示词生成
+    async fn generate(&self, prompt: &str, max_tokens: u32, temperature: f64) -> Result<AgentPluginOutput>;
 }
 
 /// 插件请求
@@ -208,7 +278,7 @@ pub struct PluginSystemConfig {
 impl Default for PluginSystemConfig {
     fn default() -> Self {
         Self {
-            plugins_dir: PathBuf::from("./plugins"),
+            plugins_dir: PathBuf::from(DEFAULT_PLUGINS_DIR),
             enable_hot_reload: true,
             reload_check_interval_secs: 5,
             enable_sandboxing: true,
@@ -226,6 +296,8 @@ pub struct PluginSystem {
     event_bus: Option<Arc<EventBus>>,
     /// 插件注册表（插件ID -> 元数据）
     registry: Arc<RwLock<HashMap<String, PluginMetadata>>>,
+    /// 提供给 WASM 插件的宿主工具
+    host_tools: Arc<std::sync::RwLock<HashMap<String, HostTool>>>,
 }
 
 impl PluginSystem {
@@ -241,21 +313,41 @@ impl PluginSystem {
             plugins: Arc::new(RwLock::new(HashMap::new())),
             event_bus,
             registry: Arc::new(RwLock::new(HashMap::new())),
+            host_tools: Arc::new(std::sync::RwLock::new(HashMap::new())),
         }
     }
 
-    /// 发现插件（扫描插件目录）
+    /// 注册宿主工具（对之后的 WASM 插件请求立即可见）
+    pub fn register_host_tool(&self, name: impl Into<String>, tool: HostTool) {
+        self.host_tools.write().unwrap().insert(name.into(), tool);
+    }
+
+    /// 发现插件：插件目录中已安装且启用的插件（签名在安装时已校验）
     pub async fn discover_plugins(&self) -> Result<Vec<PluginMetadata>> {
         info!("🔍 Discovering plugins in {:?}", self.config.plugins_dir);
+        let installed = PluginInstaller::new(&self.config.plugins_dir).list()?;
+        Ok(installed.into_iter().filter(|plugin| plugin.enabled).map(|plugin| plugin.manifest.metadata).collect())
+    }
 
-        // TODO: 实际实现插件扫描
-        // 1. 扫描插件目录
-        // 2. 读取每个插件的 manifest.json
-        // 3. 验证插件签名（安全性）
-        // 4. 解析元数据
-
-        // Placeholder
-        Ok(Vec::new())
+    /// 注册、加载并启动插件目录中已启用的插件（按安装顺序，依赖在前）；单个插件失败时跳过，返回已启动的插件
+    pub async fn load_installed(&self) -> Result<Vec<String>> {
+        let installer = PluginInstaller::new(&self.config.plugins_dir);
+        let mut started = Vec::new();
+        for plugin in installer.list()?.into_iter().filter(|plugin| plugin.enabled) {
+            let plugin_id = plugin.id().to_string();
+            let config = PluginConfig { settings: plugin.manifest.settings.clone() };
+            let result = async {
+                self.register_plugin(plugin.manifest.metadata.clone()).await?;
+                self.load_plugin(&plugin_id, installer.entry_path(&plugin), config).await?;
+                self.start_plugin(&plugin_id).await
+            }
+            .await;
+            match result {
+                Ok(()) => started.push(plugin_id),
+                Err(e) => warn!("⚠️  Skipping installed plugin {}: {}", plugin_id, e),
+            }
+        }
+        Ok(started)
     }
 
     /// 注册插件
@@ -301,18 +393,34 @@ impl PluginSystem {
         // 检查依赖
         self.check_dependencies(&metadata.dependencies).await?;
 
+        // WASM 插件在沙箱运行时中加载；原生动态库与宿主同权限，启用沙箱时拒绝加载
+        let handler = if plugin_path.extension().is_some_and(|ext| ext == "wasm") {
+            let mut handler = self.wasm_handler(&metadata, &plugin_path)?;
+            handler.initialize(&config).await?;
+            Some(handler)
+        } else if self.config.enable_sandboxing {
+            return Err(anyhow!(
+                "Sandboxing is enabled: only .wasm plugins can be loaded ({})",
+                plugin_path.display()
+            ));
+        } else {
+            None
+        };
+
         // 创建插件实例
         let plugin = Arc::new(Plugin {
             metadata: metadata.clone(),
             state: Arc::new(RwLock::new(PluginState::Loading)),
             config: config.clone(),
             plugin_path: plugin_path.clone(),
-            handler: Arc::new(RwLock::new(None)),
-            loaded_at: None,
+            handler: Arc::new(RwLock::new(handler)),
+            loaded_at: Some(Utc::now()),
             stats: Arc::new(RwLock::new(PluginStats::default())),
+            agent: None,
+            permits: Arc::new(Semaphore::new(metadata.resource_limits.max_concurrent_requests.max(1))),
         });
 
-        // TODO: 实际动态加载
+        // TODO: 原生动态库加载（仅在关闭沙箱时）
         // 使用 libloading crate:
         // unsafe {
         //     let lib = Library::new(&plugin_path)?;
@@ -447,8 +555,11 @@ impl PluginSystem {
 
         // 调用处理器
         let response = if let Some(handler) = plugin.handler.read().await.as_ref() {
-            // TODO: 添加超时控制
-            match handler.handle_request(request).await {
+            let timeout = Duration::from_secs(plugin.metadata.resource_limits.request_timeout_secs);
+            let result = tokio::time::timeout(timeout, handler.handle_request(request))
+                .await
+                .unwrap_or_else(|_| Err(anyhow!("Plugin {} timed out after {:?}", plugin_id, timeout)));
+            match result {
                 Ok(resp) => {
                     let mut stats = plugin.stats.write().await;
                     stats.successful_requests += 1;
@@ -477,6 +588,119 @@ impl PluginSystem {
         response
     }
 
+    /// 注册进程内的 Agent 插件（元数据类型须为 Agent），注册后即处于运行状态
+    pub async fn register_agent_plugin(&self, metadata: PluginMetadata, agent: Arc<dyn AgentPlugin>) -> Result<()> {
+        if metadata.plugin_type != PluginType::Agent {
+            return Err(anyhow!("Plugin {} is a {:?} plugin, not an agent", metadata.plugin_id, metadata.plugin_type));
+        }
+        if agent.api_version() != AGENT_PLUGIN_API_VERSION {
+            return Err(anyhow!(
+                "Plugin {} implements agent API v{}, expected v{}",
+                metadata.plugin_id,
+                agent.api_version(),
+                AGENT_PLUGIN_API_VERSION
+            ));
+        }
+        if self.plugins.read().await.contains_key(&metadata.plugin_id) {
+            return Err(anyhow!("Plugin already loaded: {}", metadata.plugin_id));
+        }
+        self.check_dependencies(&metadata.dependencies).await?;
+        self.register_plugin(metadata.clone()).await?;
+
+        let plugin = Arc::new(Plugin {
+            metadata: metadata.clone(),
+            state: Arc::new(RwLock::new(PluginState::Running)),
+            config: PluginConfig { settings: HashMap::new() },
+            plugin_path: PathBuf::new(),
+            handler: Arc::new(RwLock::new(None)),
+            loaded_at: Some(Utc::now()),
+            stats: Arc::new(RwLock::new(PluginStats::default())),
+            permits: Arc::new(Semaphore::new(metadata.resource_limits.max_concurrent_requests.max(1))),
+            agent: Some(agent.clone()),
+        });
+        self.plugins.write().await.insert(metadata.plugin_id.clone(), plugin);
+
+        info!("🤖 Agent plugin ready: {} v{} ({})", metadata.name, metadata.version, agent.role().as_str());
+        Ok(())
+    }
+
+    /// Agent 插件的角色与元数据（未加载或不是 Agent 插件时为 None）
+    pub async fn agent_plugin(&self, plugin_id: &str) -> Option<(AgentRole, PluginMetadata)> {
+        let plugins = self.plugins.read().await;
+        let plugin = plugins.get(plugin_id)?;
+        plugin.agent.as_ref().map(|agent| (agent.role(), plugin.metadata.clone()))
+    }
+
+    /// 调用 Agent 插件：超出并发上限时立即拒绝，超过请求超时时中止，结果计入插件统计
+    pub async fn generate_with_agent(
+        &self,
+        plugin_id: &str,
+        role: AgentRole,
+        prompt: &str,
+        max_tokens: u32,
+        temperature: f64,
+    ) -> Result<AgentResponse> {
+        let plugin = self
+            .plugins
+            .read()
+            .await
+            .get(plugin_id)
+            .cloned()
+            .ok_or_else(|| anyhow!("Plugin not found: {}", plugin_id))?;
+        let agent = plugin.agent.clone().ok_or_else(|| anyhow!("Plugin {} is not an agent plugin", plugin_id))?;
+        let state = *plugin.state.read().await;
+        if state != PluginState::Running {
+            return Err(anyhow!("Plugin not running: {:?}", state));
+        }
+        if agent.role() != role {
+            return Err(anyhow!("Plugin {} provides {}, not {}", plugin_id, agent.role().as_str(), role.as_str()));
+        }
+
+        let limits = &plugin.metadata.resource_limits;
+        let _permit = plugin.permits.clone().try_acquire_owned().map_err(|_| {
+            anyhow!("Plugin {} concurrent request limit ({}) exceeded", plugin_id, limits.max_concurrent_requests)
+        })?;
+        let max_tokens = (max_tokens as f64 * agent.weight().clamp(0.5, 2.0)).round() as u32;
+        {
+            let mut stats = plugin.stats.write().await;
+            stats.current_concurrent += 1;
+            stats.total_requests += 1;
+        }
+
+        let start = Instant::now();
+        let timeout = Duration::from_secs(limits.request_timeout_secs);
+        let result = tokio::time::timeout(timeout, agent.generate(prompt, max_tokens, temperature))
+            .await
+            .unwrap_or_else(|_| Err(anyhow!("Plugin {} timed out after {:?}", plugin_id, timeout)));
+        let elapsed = start.elapsed();
+        {
+            let mut stats = plugin.stats.write().await;
+            stats.current_concurrent = stats.current_concurrent.saturating_sub(1);
+            if result.is_ok() {
+                stats.successful_requests += 1;
+            } else {
+                stats.failed_requests += 1;
+            }
+            let total = stats.total_requests as f64;
+            stats.avg_response_time_ms =
+                (stats.avg_response_time_ms * (total - 1.0) + elapsed.as_millis() as f64) / total;
+        }
+
+        let output = result?;
+        let mut metadata = HashMap::new();
+        metadata.insert("plugin_id".to_string(), plugin_id.to_string());
+        metadata.insert("plugin_version".to_string(), plugin.metadata.version.clone());
+        Ok(AgentResponse {
+            role,
+            text: output.text,
+            tokens: output.tokens,
+            cost: output.cost,
+            latency_ms: elapsed.as_millis() as u64,
+            metadata,
+            timestamp: Utc::now(),
+        })
+    }
+
     /// 获取插件列表
     pub async fn list_plugins(&self) -> Vec<PluginMetadata> {
         let registry = self.registry.read().await;
@@ -486,13 +710,19 @@ impl PluginSystem {
     /// 获取插件状态
     pub async fn get_plugin_state(&self, plugin_id: &str) -> Option<PluginState> {
         let plugins = self.plugins.read().await;
-        plugins.get(plugin_id).map(|p| *p.state.blocking_read())
+        match plugins.get(plugin_id) {
+            Some(plugin) => Some(*plugin.state.read().await),
+            None => None,
+        }
     }
 
     /// 获取插件统计
     pub async fn get_plugin_stats(&self, plugin_id: &str) -> Option<PluginStats> {
         let plugins = self.plugins.read().await;
-        plugins.get(plugin_id).map(|p| p.stats.blocking_read().clone())
+        match plugins.get(plugin_id) {
+            Some(plugin) => Some(plugin.stats.read().await.clone()),
+            None => None,
+        }
     }
 
     /// 健康检查所有插件
@@ -517,8 +747,46 @@ impl PluginSystem {
         results
     }
 
+    /// 检查插件文件变化并就地重载（WASM 插件的 KV 存储保留；重载失败时继续使用旧版本），返回已重载的插件
+    pub async fn check_hot_reload(&self) -> Vec<String> {
+        let plugins: Vec<(String, Arc<Plugin>)> =
+            self.plugins.read().await.iter().map(|(id, plugin)| (id.clone(), plugin.clone())).collect();
+        let mut reloaded = Vec::new();
+        for (plugin_id, plugin) in plugins {
+            let result = match plugin.handler.read().await.as_ref() {
+                Some(handler) => handler.reload_if_changed().await,
+                None => Ok(false),
+            };
+            match result {
+                Ok(true) => {
+                    info!("🔄 Plugin hot-reloaded: {}", plugin_id);
+                    reloaded.push(plugin_id);
+                }
+                Ok(false) => {}
+                Err(e) => warn!("⚠️  Hot reload of {} failed, keeping the previous version: {}", plugin_id, e),
+            }
+        }
+        reloaded
+    }
+
     // ===== 内部方法 =====
 
+    #[cfg(feature = "wasm")]
+    fn wasm_handler(&self, metadata: &PluginMetadata, path: &std::path::Path) -> Result<Box<dyn PluginHandler>> {
+        let handler = super::wasm_plugin::WasmPluginHandler::load(
+            &metadata.plugin_id,
+            path,
+            metadata.resource_limits.clone(),
+            self.host_tools.clone(),
+        )?;
+        Ok(Box::new(handler))
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    fn wasm_handler(&self, metadata: &PluginMetadata, _path: &std::path::Path) -> Result<Box<dyn PluginHandler>> {
+        Err(anyhow!("Plugin {} is a WASM plugin, but this build lacks the `wasm` feature", metadata.plugin_id))
+    }
+
     /// 检查依赖
     async fn check_dependencies(&self, dependencies: &[String]) -> Result<()> {
         let plugins = self.plugins.read().await;
@@ -552,13 +820,7 @@ impl PluginSystem {
 
             loop {
                 tokio::time::sleep(interval).await;
-
-                // TODO: 实现文件变化检测
-                // 1. 监控插件目录变化（使用 notify crate）
-                // 2. 检测插件文件修改时间
-                // 3. 自动重载已修改的插件
-
-                // Placeholder
+                self.check_hot_reload().await;
             }
         });
     }
@@ -591,4 +853,71 @@ mod tests {
         assert_eq!(plugins.len(), 1);
         assert_eq!(plugins[0].name, "Test Plugin");
     }
+
+    /// 等待 `delay` 后回显收到的 token 上限
+    struct EchoAgent {
+        delay: Duration,
+    }
+
+    #[async_trait::async_trait]
+    impl AgentPlugin for EchoAgent {
+        fn role(&self) -> AgentRole {
+            AgentRole::Ultron
+        }
+
+        fn weight(&self) -> f64 {
+            2.0
+        }
+
+        async fn generate(&self, _prompt: &str, max_tokens: u32, _temperature: f64) -> Result<AgentPluginOutput> {
+            tokio::time::sleep(self.delay).await;
+            Ok(AgentPluginOutput { text: format!("max_tokens={}", max_tokens), tokens: 3, cost: 0.0 })
+        }
+    }
+
+    fn agent_metadata(plugin_id: &str, plugin_type: PluginType) -> PluginMetadata {
+        PluginMetadata {
+            plugin_id: plugin_id.to_string(),
+            name: plugin_id.to_string(),
+            version: "0.1.0".to_string(),
+            author: "ACSA Team".to_string(),
+            description: "Echo auditor".to_string(),
+            plugin_type,
+            dependencies: vec![],
+            supported_protocols: vec![],
+            resource_limits: ResourceLimits { max_concurrent_requests: 1, request_timeout_secs: 1, ..Default::default() },
+            created_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_agent_plugin_calls_respect_limits() {
+        let system = PluginSystem::new(PluginSystemConfig::default(), None);
+        let fast = Arc::new(EchoAgent { delay: Duration::ZERO });
+        assert!(system.register_agent_plugin(agent_metadata("tool", PluginType::Tool), fast.clone()).await.is_err());
+        system.register_agent_plugin(agent_metadata("echo", PluginType::Agent), fast).await.unwrap();
+        system
+            .register_agent_plugin(agent_metadata("slow", PluginType::Agent), Arc::new(EchoAgent { delay: Duration::from_secs(5) }))
+            .await
+            .unwrap();
+
+        // 权重 2.0 放大 token 上限；角色不符时拒绝
+        let response = system.generate_with_agent("echo", AgentRole::Ultron, "audit", 1000, 0.5).await.unwrap();
+        assert_eq!(response.text, "max_tokens=2000");
+        assert_eq!(response.metadata["plugin_id"], "echo");
+        assert!(system.generate_with_agent("echo", AgentRole::L6, "verify", 1000, 0.5).await.is_err());
+
+        // 并发上限 1：第二个请求立即被拒绝；第一个请求超过 1 秒超时
+        let (first, second) = tokio::join!(
+            system.generate_with_agent("slow", AgentRole::Ultron, "audit", 100, 0.5),
+            async {
+                tokio::task::yield_now().await;
+                system.generate_with_agent("slow", AgentRole::Ultron, "audit", 100, 0.5).await
+            }
+        );
+        assert!(first.unwrap_err().to_string().contains("timed out"));
+        assert!(second.unwrap_err().to_string().contains("concurrent request limit"));
+        let stats = system.get_plugin_stats("slow").await.unwrap();
+        assert_eq!((stats.total_requests, stats.failed_requests, stats.current_concurrent), (1, 1, 0));
+    }
 }
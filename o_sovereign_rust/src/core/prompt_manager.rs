@@ -59,6 +59,13 @@ pub struct FewShotExample {
     pub weight: f64,
 }
 
+impl FewShotExample {
+    /// 格式化为Prompt中的示例段落
+    pub fn format(&self, index: usize) -> String {
+        format!("### 示例 {}\n**输入**: {}\n**输出**: {}", index, self.user_input, self.expected_output)
+    }
+}
+
 /// A/B测试组
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AbTestGroup {
@@ -308,6 +315,20 @@ impl PromptManager {
             .ok_or_else(|| anyhow!("No metrics found for test: {}", test_id))
     }
 
+    /// 全部启用的模板（按名称排序）
+    pub async fn list_templates(&self) -> Vec<PromptTemplate> {
+        let templates = self.templates.read().await;
+        let mut enabled: Vec<PromptTemplate> = templates.values().filter(|t| t.enabled).cloned().collect();
+        enabled.sort_by(|a, b| a.name.cmp(&b.name));
+        enabled
+    }
+
+    /// 模板的Few-shot示例
+    pub async fn get_examples(&self, template_id: &str) -> Vec<FewShotExample> {
+        let examples = self.examples.read().await;
+        examples.get(template_id).cloned().unwrap_or_default()
+    }
+
     /// 按Protocol获取推荐模板
     pub async fn get_templates_by_protocol(&self, protocol: &Protocol) -> Vec<PromptTemplate> {
         let templates = self.templates.read().await;
@@ -382,12 +403,8 @@ impl PromptManager {
 
         let mut formatted = String::new();
         for (i, example) in selected.enumerate() {
-            formatted.push_str(&format!(
-                "### 示例 {}\n**输入**: {}\n**输出**: {}\n\n",
-                i + 1,
-                example.user_input,
-                example.expected_output
-            ));
+            formatted.push_str(&example.format(i + 1));
+            formatted.push_str("\n\n");
         }
 
         Ok(formatted)
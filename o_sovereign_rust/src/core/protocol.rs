@@ -3,9 +3,31 @@
 //
 // 核心设计: 液态软件 (Liquid Software)
 // 根据用户输入自动切换势场参数和Agent权重
+// 自定义协议从 `*.toml` 定义文件加载（权重、温度、哲学），与内置协议一样可按名称选择
+// 定义目录可被监视（ProtocolWatcher），修改后无需重启即生效，并在事件总线上发布变更
 
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use super::event_bus::{Event, EventBus, EventType};
+
+/// 自定义协议定义的默认目录
+pub const DEFAULT_PROTOCOL_DIR: &str = "./config/protocols";
+
+/// 协议配置变更在事件总线上的事件类型（`EventType::System`）
+pub const PROTOCOL_CONFIG_CHANGE_EVENT: &str = "protocol.config_change";
+
+/// 文件事件合并窗口：编辑器保存常触发多次写入，窗口内只重载一次
+const RELOAD_DEBOUNCE: Duration = Duration::from_millis(250);
 
 /// ACSA核心协议（风格）
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -183,10 +205,17 @@ impl Protocol {
             Protocol::Sunday,
         ]
     }
+
+    /// 按协议名称查找（不区分大小写，忽略 `_` / `-`，如 `architect`、`reviewer-2`）
+    pub fn from_name(name: &str) -> Option<Self> {
+        let normalize = |s: &str| s.to_lowercase().replace(['_', '-'], "");
+        let wanted = normalize(name);
+        Self::all().into_iter().find(|p| normalize(&p.name()) == wanted)
+    }
 }
 
 /// Agent权重配置
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct AgentWeights {
     pub moss: f64,
     pub l6: f64,
@@ -213,14 +242,125 @@ impl AgentWeights {
     }
 }
 
+/// 自定义协议定义（`<dir>/*.toml`）
+///
+/// ```toml
+/// name = "auditor"
+/// tagline = "逐行对账"
+/// philosophy = "每一笔都要有凭证。"
+/// temperature = 0.2
+///
+/// [weights]
+/// moss = 0.2
+/// l6 = 0.3
+/// ultron = 0.4
+/// omega = 0.1
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomProtocolDef {
+    pub name: String,
+    #[serde(default)]
+    pub tagline: String,
+    #[serde(default)]
+    pub philosophy: String,
+    #[serde(default)]
+    pub description: String,
+    pub temperature: f64,
+    pub weights: AgentWeights,
+    #[serde(default = "default_jarvis_filter")]
+    pub enable_jarvis_filter: bool,
+    #[serde(default)]
+    pub enable_high_freq_commands: bool,
+    #[serde(default = "default_semantic_cache")]
+    pub enable_semantic_cache: bool,
+}
+
+fn default_jarvis_filter() -> bool {
+    true
+}
+
+fn default_semantic_cache() -> bool {
+    true
+}
+
+impl CustomProtocolDef {
+    /// 解析并校验一个 TOML 定义
+    pub fn parse(content: &str) -> Result<Self> {
+        let definition: Self = toml::from_str(content)?;
+        definition.validate()?;
+        Ok(definition)
+    }
+
+    /// 校验名称、权重与温度
+    pub fn validate(&self) -> Result<()> {
+        if self.name.is_empty()
+            || !self.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(anyhow!(
+                "Invalid protocol name '{}' (use letters, digits, '_' or '-')",
+                self.name
+            ));
+        }
+        if Protocol::from_name(&self.name).is_some() {
+            return Err(anyhow!("Protocol name '{}' is reserved by a built-in protocol", self.name));
+        }
+
+        let weights = &self.weights;
+        for (agent, weight) in [
+            ("moss", weights.moss),
+            ("l6", weights.l6),
+            ("ultron", weights.ultron),
+            ("omega", weights.omega),
+        ] {
+            if !weight.is_finite() || weight < 0.0 {
+                return Err(anyhow!("weights.{} must be a non-negative number (got {})", agent, weight));
+            }
+        }
+        if !weights.is_valid() {
+            return Err(anyhow!(
+                "Agent weights must sum to 1.0 (got {:.2})",
+                weights.moss + weights.l6 + weights.ultron + weights.omega
+            ));
+        }
+
+        if !(0.0..=2.0).contains(&self.temperature) {
+            return Err(anyhow!("temperature must be between 0.0 and 2.0 (got {})", self.temperature));
+        }
+        Ok(())
+    }
+
+    pub fn protocol(&self) -> Protocol {
+        Protocol::Custom(self.name.clone())
+    }
+
+    pub fn to_config(&self) -> ProtocolConfig {
+        ProtocolConfig {
+            protocol: self.protocol(),
+            agent_weights: self.weights.clone(),
+            temperature: self.temperature,
+            enable_jarvis_filter: self.enable_jarvis_filter,
+            enable_high_freq_commands: self.enable_high_freq_commands,
+            enable_semantic_cache: self.enable_semantic_cache,
+            description: if self.description.is_empty() {
+                format!("自定义模式: {}", self.name)
+            } else {
+                self.description.clone()
+            },
+        }
+    }
+}
+
 /// 协议配置
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ProtocolConfig {
     pub protocol: Protocol,
     pub agent_weights: AgentWeights,
     pub temperature: f64,
     pub enable_jarvis_filter: bool,
     pub enable_high_freq_commands: bool,
+    /// 是否允许语义响应缓存（创意类协议追求多样输出，不复用相似提示词的响应）
+    #[serde(default = "default_semantic_cache")]
+    pub enable_semantic_cache: bool,
     pub description: String,
 }
 
@@ -239,6 +379,7 @@ impl ProtocolConfig {
                 temperature: 0.2, // 低温追求确定性
                 enable_jarvis_filter: false, // 关闭闲聊过滤
                 enable_high_freq_commands: true,
+                enable_semantic_cache: true,
                 description: "编程模式: Omega主导，追求代码实用性".to_string(),
             },
 
@@ -253,6 +394,7 @@ impl ProtocolConfig {
                 temperature: 0.1, // 极低温，严谨
                 enable_jarvis_filter: true,
                 enable_high_freq_commands: false,
+                enable_semantic_cache: true,
                 description: "学术模式: L6主导，怀疑一切".to_string(),
             },
 
@@ -267,6 +409,7 @@ impl ProtocolConfig {
                 temperature: 0.05, // 极低温，零风险
                 enable_jarvis_filter: true,
                 enable_high_freq_commands: false,
+                enable_semantic_cache: true,
                 description: "法律模式: Ultron主宰，零后悔值".to_string(),
             },
 
@@ -281,6 +424,7 @@ impl ProtocolConfig {
                 temperature: 1.0, // 高温，高噪声市场
                 enable_jarvis_filter: false,
                 enable_high_freq_commands: true,
+                enable_semantic_cache: true,
                 description: "金融模式: MOSS+Omega，唯快不破".to_string(),
             },
 
@@ -295,6 +439,7 @@ impl ProtocolConfig {
                 temperature: 0.3,
                 enable_jarvis_filter: true,
                 enable_high_freq_commands: false,
+                enable_semantic_cache: true,
                 description: "商管模式: MOSS主导，量化优化".to_string(),
             },
 
@@ -309,6 +454,7 @@ impl ProtocolConfig {
                 temperature: 1.5, // 极高温，打破范式
                 enable_jarvis_filter: false, // 解除逻辑一致性锁定
                 enable_high_freq_commands: true,
+                enable_semantic_cache: false, // 创意输出不复用
                 description: "创意模式: 高噪声，越过势垒".to_string(),
             },
 
@@ -323,6 +469,7 @@ impl ProtocolConfig {
                 temperature: 0.4,
                 enable_jarvis_filter: false,
                 enable_high_freq_commands: true,
+                enable_semantic_cache: true,
                 description: "影子模式: 光锥隐身，最小作用量".to_string(),
             },
 
@@ -337,6 +484,7 @@ impl ProtocolConfig {
                 temperature: 1.2, // 幽默风趣
                 enable_jarvis_filter: false,
                 enable_high_freq_commands: true,
+                enable_semantic_cache: false,
                 description: "日常模式: 多巴胺管理，摩擦力为零".to_string(),
             },
 
@@ -351,6 +499,7 @@ impl ProtocolConfig {
                 temperature: 0.7,
                 enable_jarvis_filter: true,
                 enable_high_freq_commands: false,
+                enable_semantic_cache: true,
                 description: "自定义模式: 用户自定义配置".to_string(),
             },
         }
@@ -361,6 +510,8 @@ impl ProtocolConfig {
 pub struct ProtocolManager {
     current_protocol: Protocol,
     configs: HashMap<Protocol, ProtocolConfig>,
+    /// 已加载的自定义协议定义（按名称）
+    custom: HashMap<String, CustomProtocolDef>,
 }
 
 impl Default for ProtocolManager {
@@ -381,6 +532,115 @@ impl ProtocolManager {
         Self {
             current_protocol: Protocol::Architect, // 默认编程模式
             configs,
+            custom: HashMap::new(),
+        }
+    }
+
+    /// 加载目录下所有 `*.toml` 自定义协议定义，返回加载的协议
+    ///
+    /// 目录不存在时不加载任何协议；任一文件无效或名称重复时整体失败，错误中带文件名
+    pub fn load_custom_protocols(&mut self, dir: impl AsRef<Path>) -> Result<Vec<Protocol>> {
+        let dir = dir.as_ref();
+        let loaded = read_custom_definitions(dir)?;
+
+        let protocols: Vec<Protocol> = loaded.values().map(CustomProtocolDef::protocol).collect();
+        for definition in loaded.values() {
+            self.configs.insert(definition.protocol(), definition.to_config());
+        }
+        self.custom.extend(loaded);
+        info!("🔧 Loaded {} custom protocols from {}", protocols.len(), dir.display());
+        Ok(protocols)
+    }
+
+    /// 按目录当前内容重新加载自定义协议，返回实际变化的协议（按名称排序）
+    ///
+    /// 任一文件无效时不应用任何变更；被删除的协议若正在使用，切回默认协议
+    pub fn reload_custom_protocols(&mut self, dir: impl AsRef<Path>) -> Result<Vec<ProtocolConfigChange>> {
+        let mut loaded = read_custom_definitions(dir.as_ref())?;
+        let now = Utc::now();
+        let mut changes = Vec::new();
+
+        for (key, previous) in std::mem::take(&mut self.custom) {
+            let protocol = previous.protocol();
+            let old_config = self.configs.remove(&protocol);
+            match loaded.remove(&key) {
+                Some(definition) if definition == previous => {
+                    if let Some(config) = old_config {
+                        self.configs.insert(protocol, config);
+                    }
+                    self.custom.insert(key, definition);
+                }
+                Some(definition) => {
+                    let new_config = definition.to_config();
+                    self.configs.insert(protocol.clone(), new_config.clone());
+                    self.custom.insert(key, definition);
+                    changes.push(ProtocolConfigChange {
+                        protocol,
+                        old_config,
+                        new_config: Some(new_config),
+                        changed_at: now,
+                    });
+                }
+                None => {
+                    if self.current_protocol == protocol {
+                        warn!("⚠️  Active protocol {} was removed, switching to default", protocol.name());
+                        self.current_protocol = Protocol::Architect;
+                    }
+                    changes.push(ProtocolConfigChange {
+                        protocol,
+                        old_config,
+                        new_config: None,
+                        changed_at: now,
+                    });
+                }
+            }
+        }
+        for (key, definition) in loaded {
+            let new_config = definition.to_config();
+            self.configs.insert(definition.protocol(), new_config.clone());
+            changes.push(ProtocolConfigChange {
+                protocol: definition.protocol(),
+                old_config: None,
+                new_config: Some(new_config),
+                changed_at: now,
+            });
+            self.custom.insert(key, definition);
+        }
+
+        changes.sort_by_key(|change| change.protocol.name());
+        Ok(changes)
+    }
+
+    /// 按名称查找内置或自定义协议（自定义协议也接受 `custom_<name>`）
+    pub fn resolve(&self, name: &str) -> Option<Protocol> {
+        if let Some(protocol) = Protocol::from_name(name) {
+            return Some(protocol);
+        }
+        let name = name.to_lowercase();
+        let name = name.strip_prefix("custom_").unwrap_or(&name);
+        self.custom.get(name).map(CustomProtocolDef::protocol)
+    }
+
+    /// 所有可选协议（内置在前，自定义按名称排序）
+    pub fn available(&self) -> Vec<Protocol> {
+        let mut custom: Vec<_> = self.custom.values().map(CustomProtocolDef::protocol).collect();
+        custom.sort_by_key(|protocol| protocol.name());
+        Protocol::all().into_iter().chain(custom).collect()
+    }
+
+    /// 自定义协议的定义
+    pub fn custom_definition(&self, protocol: &Protocol) -> Option<&CustomProtocolDef> {
+        match protocol {
+            Protocol::Custom(name) => self.custom.get(&name.to_lowercase()),
+            _ => None,
+        }
+    }
+
+    /// 协议哲学（自定义协议取定义中的内容）
+    pub fn philosophy(&self, protocol: &Protocol) -> String {
+        match self.custom_definition(protocol) {
+            Some(definition) if !definition.philosophy.is_empty() => definition.philosophy.clone(),
+            _ => protocol.philosophy(),
         }
     }
 
@@ -428,9 +688,13 @@ impl ProtocolManager {
     pub fn print_current_info(&self) {
         let config = self.current_config();
         println!("\n╔══════════════════════════════════════════════╗");
-        println!("║  {} {}", config.protocol.display_name(), config.protocol.tagline());
+        let tagline = match self.custom_definition(&config.protocol) {
+            Some(definition) if !definition.tagline.is_empty() => definition.tagline.clone(),
+            _ => config.protocol.tagline(),
+        };
+        println!("║  {} {}", config.protocol.display_name(), tagline);
         println!("╠══════════════════════════════════════════════╣");
-        println!("║  哲学: {}", config.protocol.philosophy());
+        println!("║  哲学: {}", self.philosophy(&config.protocol));
         println!("║  温度: {:.2}", config.temperature);
         println!("║  Agent权重:");
         println!("║    MOSS:   {:.0}%", config.agent_weights.moss * 100.0);
@@ -442,6 +706,149 @@ impl ProtocolManager {
     }
 }
 
+/// 读取目录下所有 `*.toml` 定义（按小写名称索引）；目录不存在时为空
+fn read_custom_definitions(dir: &Path) -> Result<HashMap<String, CustomProtocolDef>> {
+    if !dir.is_dir() {
+        return Ok(HashMap::new());
+    }
+
+    let mut paths: Vec<_> = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read protocol directory {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| is_definition_file(path))
+        .collect();
+    paths.sort();
+
+    let mut loaded: HashMap<String, CustomProtocolDef> = HashMap::new();
+    for path in &paths {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let definition = CustomProtocolDef::parse(&content)
+            .with_context(|| format!("Invalid protocol definition {}", path.display()))?;
+        let key = definition.name.to_lowercase();
+        if loaded.contains_key(&key) {
+            return Err(anyhow!("Duplicate protocol '{}' in {}", definition.name, path.display()));
+        }
+        loaded.insert(key, definition);
+    }
+    Ok(loaded)
+}
+
+fn is_definition_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "toml")
+}
+
+/// 一个协议的配置变更（新增时 `old_config` 为空，删除时 `new_config` 为空）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProtocolConfigChange {
+    pub protocol: Protocol,
+    pub old_config: Option<ProtocolConfig>,
+    pub new_config: Option<ProtocolConfig>,
+    pub changed_at: DateTime<Utc>,
+}
+
+/// 协议定义目录监视器
+///
+/// 目录中的 `*.toml` 变化时重新加载共享的 ProtocolManager，每个变化的协议在事件总线上
+/// 发布一条 `PROTOCOL_CONFIG_CHANGE_EVENT`。定义无效时保留当前配置；drop 后停止监视。
+pub struct ProtocolWatcher {
+    _watcher: RecommendedWatcher,
+    task: JoinHandle<()>,
+}
+
+impl ProtocolWatcher {
+    /// 开始监视目录（目录必须已存在，需在 tokio 运行时内调用）
+    pub fn start(
+        manager: Arc<RwLock<ProtocolManager>>,
+        dir: impl Into<PathBuf>,
+        event_bus: Option<Arc<EventBus>>,
+    ) -> Result<Self> {
+        let dir = dir.into();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| match result {
+            Ok(event) if event.paths.iter().any(|path| is_definition_file(path)) => {
+                let _ = tx.send(());
+            }
+            Ok(_) => {}
+            Err(e) => warn!("⚠️  Protocol watcher error: {}", e),
+        })
+        .context("Failed to create protocol watcher")?;
+        watcher
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .with_context(|| format!("Failed to watch protocol directory {}", dir.display()))?;
+        info!("👀 Watching {} for protocol changes", dir.display());
+
+        let task = tokio::spawn(async move {
+            while rx.recv().await.is_some() {
+                tokio::time::sleep(RELOAD_DEBOUNCE).await;
+                while rx.try_recv().is_ok() {}
+
+                let result = match manager.write() {
+                    Ok(mut manager) => manager.reload_custom_protocols(&dir),
+                    Err(_) => {
+                        warn!("⚠️  Protocol manager lock poisoned, stopping watcher");
+                        return;
+                    }
+                };
+                let changes = match result {
+                    Ok(changes) => changes,
+                    Err(e) => {
+                        warn!("⚠️  Protocol reload failed, keeping current configs: {:#}", e);
+                        continue;
+                    }
+                };
+                if changes.is_empty() {
+                    debug!("🔄 Protocol directory changed, no config differences");
+                    continue;
+                }
+                info!("🔄 Reloaded {} protocol configs from {}", changes.len(), dir.display());
+                if let Some(bus) = &event_bus {
+                    for change in &changes {
+                        publish_change(bus, change).await;
+                    }
+                }
+            }
+        });
+
+        Ok(Self { _watcher: watcher, task })
+    }
+}
+
+impl Drop for ProtocolWatcher {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn publish_change(bus: &EventBus, change: &ProtocolConfigChange) {
+    let data = match serde_json::to_value(change) {
+        Ok(data) => data,
+        Err(e) => {
+            warn!("⚠️  Failed to encode protocol change: {}", e);
+            return;
+        }
+    };
+    let action = match (&change.old_config, &change.new_config) {
+        (None, _) => "added",
+        (_, None) => "removed",
+        _ => "updated",
+    };
+    let event = Event {
+        event_id: format!("protocol_{}_{}", change.protocol.name(), change.changed_at.timestamp_millis()),
+        event_type: EventType::System(PROTOCOL_CONFIG_CHANGE_EVENT.to_string()),
+        source: "protocol_manager".to_string(),
+        data,
+        timestamp: Utc::now(),
+        metadata: HashMap::from([
+            ("protocol".to_string(), change.protocol.name()),
+            ("action".to_string(), action.to_string()),
+        ]),
+    };
+    if let Err(e) = bus.publish(event).await {
+        warn!("⚠️  Failed to publish protocol change: {}", e);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -511,6 +918,119 @@ mod tests {
         assert_eq!(manager.current_protocol(), Protocol::Aegis);
     }
 
+    #[test]
+    fn test_load_custom_protocols() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("auditor.toml"),
+            "name = \"auditor\"\nphilosophy = \"每一笔都要有凭证。\"\ntemperature = 0.2\n\n\
+             [weights]\nmoss = 0.2\nl6 = 0.3\nultron = 0.4\nomega = 0.1\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+
+        let mut manager = ProtocolManager::new();
+        let loaded = manager.load_custom_protocols(dir.path()).unwrap();
+        assert_eq!(loaded, vec![Protocol::Custom("auditor".to_string())]);
+
+        let protocol = manager.resolve("Auditor").unwrap();
+        assert_eq!(manager.resolve("custom_auditor"), Some(protocol.clone()));
+        assert_eq!(manager.get_config(protocol.clone()).temperature, 0.2);
+        assert_eq!(manager.philosophy(&protocol), "每一笔都要有凭证。");
+        assert_eq!(manager.available().len(), Protocol::all().len() + 1);
+        assert_eq!(manager.resolve("aegis"), Some(Protocol::Aegis));
+        assert_eq!(manager.resolve("nope"), None);
+    }
+
+    #[test]
+    fn test_custom_protocol_validation() {
+        let definition = |name: &str, temperature: f64, moss: f64| {
+            format!(
+                "name = \"{}\"\ntemperature = {}\n[weights]\nmoss = {}\nl6 = 0.2\nultron = 0.2\nomega = 0.1\n",
+                name, temperature, moss
+            )
+        };
+        assert!(CustomProtocolDef::parse(&definition("ok", 0.7, 0.5)).is_ok());
+
+        let err = CustomProtocolDef::parse(&definition("heavy", 0.7, 0.9)).unwrap_err();
+        assert!(err.to_string().contains("sum to 1.0"));
+        assert!(CustomProtocolDef::parse(&definition("hot", 3.0, 0.5)).is_err());
+        assert!(CustomProtocolDef::parse(&definition("aegis", 0.7, 0.5)).is_err());
+        assert!(CustomProtocolDef::parse(&definition("bad name", 0.7, 0.5)).is_err());
+
+        // 无效文件让整个目录加载失败，错误带文件名
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("heavy.toml"), definition("heavy", 0.7, 0.9)).unwrap();
+        let err = ProtocolManager::new().load_custom_protocols(dir.path()).unwrap_err();
+        assert!(format!("{:#}", err).contains("heavy.toml"));
+    }
+
+    #[test]
+    fn test_reload_custom_protocols() {
+        let definition = |name: &str, moss: f64, omega: f64| {
+            format!(
+                "name = \"{}\"\ntemperature = 0.5\n[weights]\nmoss = {}\nl6 = 0.2\nultron = 0.2\nomega = {}\n",
+                name, moss, omega
+            )
+        };
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("auditor.toml"), definition("auditor", 0.5, 0.1)).unwrap();
+        std::fs::write(dir.path().join("scout.toml"), definition("scout", 0.5, 0.1)).unwrap();
+
+        let mut manager = ProtocolManager::new();
+        manager.load_custom_protocols(dir.path()).unwrap();
+        let scout = manager.resolve("scout").unwrap();
+        manager.switch_protocol(scout.clone());
+        assert!(manager.reload_custom_protocols(dir.path()).unwrap().is_empty());
+
+        // 修改 auditor、删除 scout、新增 planner
+        std::fs::write(dir.path().join("auditor.toml"), definition("auditor", 0.3, 0.3)).unwrap();
+        std::fs::remove_file(dir.path().join("scout.toml")).unwrap();
+        std::fs::write(dir.path().join("planner.toml"), definition("planner", 0.5, 0.1)).unwrap();
+        let changes = manager.reload_custom_protocols(dir.path()).unwrap();
+        assert_eq!(changes.len(), 3);
+
+        let auditor = manager.resolve("auditor").unwrap();
+        let updated = changes.iter().find(|change| change.protocol == auditor).unwrap();
+        assert_eq!(updated.old_config.as_ref().unwrap().agent_weights.moss, 0.5);
+        assert_eq!(manager.get_config(auditor).agent_weights.moss, 0.3);
+        assert_eq!(manager.resolve("scout"), None);
+        assert_eq!(manager.current_protocol(), Protocol::Architect);
+        assert!(manager.resolve("planner").is_some());
+
+        // 无效定义不应用任何变更
+        std::fs::write(dir.path().join("planner.toml"), definition("planner", 0.9, 0.9)).unwrap();
+        assert!(manager.reload_custom_protocols(dir.path()).is_err());
+        assert_eq!(manager.get_config(manager.resolve("planner").unwrap()).agent_weights.moss, 0.5);
+    }
+
+    #[tokio::test]
+    async fn test_watcher_publishes_config_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = Arc::new(RwLock::new(ProtocolManager::new()));
+        let bus = Arc::new(EventBus::new(Default::default()));
+        let _watcher = ProtocolWatcher::start(manager.clone(), dir.path(), Some(bus.clone())).unwrap();
+
+        std::fs::write(
+            dir.path().join("auditor.toml"),
+            "name = \"auditor\"\ntemperature = 0.2\n[weights]\nmoss = 0.2\nl6 = 0.3\nultron = 0.4\nomega = 0.1\n",
+        )
+        .unwrap();
+
+        let mut events = Vec::new();
+        for _ in 0..50 {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            events = bus.get_history(None).await;
+            if !events.is_empty() {
+                break;
+            }
+        }
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event_type, EventType::System(PROTOCOL_CONFIG_CHANGE_EVENT.to_string()));
+        assert_eq!(events[0].metadata.get("action").map(String::as_str), Some("added"));
+        assert!(manager.read().unwrap().resolve("auditor").is_some());
+    }
+
     #[test]
     fn test_all_protocols_have_configs() {
         let manager = ProtocolManager::new();
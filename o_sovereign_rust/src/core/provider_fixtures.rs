@@ -0,0 +1,480 @@
+// Provider Fixtures - 模型调用录制与回放
+// 让完整 Router 链路的集成测试在没有 API Key 的情况下确定性运行
+//
+// 核心功能：
+// 1. FixtureRecorder：包装任意 ModelProvider，把请求/响应对写入 JSON 夹具文件
+// 2. 写盘前脱敏（sk-/AIza/Bearer 等密钥格式 + 环境变量中的真实密钥）
+// 3. FixtureReplayer：按 (角色, 提示词) 匹配并依录制顺序回放，失败也一并回放
+// 4. 缺少夹具时立即报错，避免测试悄悄退化为真实调用
+
+use super::data_security::{DataCategory, DataSecurityManager, SanitizationRule};
+use super::providers::ModelProvider;
+use super::types::{AgentResponse, AgentRole, AgentStats};
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{debug, info};
+
+/// 夹具文件格式版本
+const FIXTURE_VERSION: u32 = 1;
+
+/// 脱敏占位符
+const REDACTED: &str = "***REDACTED***";
+
+/// 会被当作真实密钥从夹具中抹掉的环境变量
+const SECRET_ENV_VARS: [&str; 6] = [
+    "OPENAI_API_KEY",
+    "ANTHROPIC_API_KEY",
+    "GEMINI_API_KEY",
+    "DEEPSEEK_API_KEY",
+    "OPENROUTER_API_KEY",
+    "SILICONFLOW_API_KEY",
+];
+
+/// 一次调用的结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FixtureOutcome {
+    Response {
+        text: String,
+        tokens: u32,
+        cost: f64,
+        latency_ms: u64,
+        #[serde(default)]
+        metadata: HashMap<String, String>,
+    },
+    Error {
+        message: String,
+    },
+}
+
+/// 一条录制的请求/响应对
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FixtureRecord {
+    pub role: AgentRole,
+    pub prompt: String,
+    pub max_tokens: u32,
+    pub temperature: f64,
+    pub outcome: FixtureOutcome,
+}
+
+/// 夹具文件
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FixtureSet {
+    pub version: u32,
+    pub records: Vec<FixtureRecord>,
+}
+
+impl FixtureSet {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read fixture file {}", path.display()))?;
+        let set: Self = serde_json::from_str(&content)
+            .with_context(|| format!("Invalid fixture file {}", path.display()))?;
+        if set.version != FIXTURE_VERSION {
+            return Err(anyhow!(
+                "Unsupported fixture version {} in {} (expected {})",
+                set.version,
+                path.display(),
+                FIXTURE_VERSION
+            ));
+        }
+        Ok(set)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write fixture file {}", path.display()))
+    }
+}
+
+/// 夹具脱敏器
+pub struct FixtureRedactor {
+    sanitizer: DataSecurityManager,
+    /// 需要原样抹掉的已知密钥
+    secrets: Vec<String>,
+}
+
+impl Default for FixtureRedactor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FixtureRedactor {
+    /// 常见密钥格式 + 环境变量中的真实密钥
+    pub fn new() -> Self {
+        let mut sanitizer = DataSecurityManager::new();
+        for pattern in [
+            r"sk-[A-Za-z0-9_\-]{16,}",
+            r"AIza[0-9A-Za-z_\-]{35}",
+            r"Bearer\s+[A-Za-z0-9._\-]{16,}",
+        ] {
+            sanitizer.add_sanitization_rule(SanitizationRule {
+                category: DataCategory::ApiKeys,
+                pattern: pattern.to_string(),
+                replacement: REDACTED.to_string(),
+                enabled: true,
+            });
+        }
+
+        let secrets = SECRET_ENV_VARS
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .filter(|value| value.len() >= 8)
+            .collect();
+
+        Self { sanitizer, secrets }
+    }
+
+    /// 追加一个需要抹掉的密钥
+    pub fn with_secret(mut self, secret: impl Into<String>) -> Self {
+        let secret = secret.into();
+        if !secret.is_empty() {
+            self.secrets.push(secret);
+        }
+        self
+    }
+
+    pub fn redact(&self, text: &str) -> String {
+        let mut redacted = text.to_string();
+        for secret in &self.secrets {
+            redacted = redacted.replace(secret.as_str(), REDACTED);
+        }
+        self.sanitizer.sanitize(&redacted, Some(DataCategory::ApiKeys))
+    }
+}
+
+/// 录制器：多个 Provider 共享同一个夹具文件
+pub struct FixtureRecorder {
+    path: PathBuf,
+    redactor: FixtureRedactor,
+    records: Mutex<Vec<FixtureRecord>>,
+}
+
+impl FixtureRecorder {
+    pub fn new(path: impl Into<PathBuf>) -> Arc<Self> {
+        Self::with_redactor(path, FixtureRedactor::new())
+    }
+
+    pub fn with_redactor(path: impl Into<PathBuf>, redactor: FixtureRedactor) -> Arc<Self> {
+        let path = path.into();
+        info!("🎙️  Recording provider fixtures to {}", path.display());
+        Arc::new(Self {
+            path,
+            redactor,
+            records: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// 包装一个 Provider，其调用都会被录制
+    pub fn wrap(self: &Arc<Self>, inner: Arc<dyn ModelProvider>) -> Arc<dyn ModelProvider> {
+        Arc::new(RecordingProvider {
+            inner,
+            recorder: self.clone(),
+        })
+    }
+
+    pub async fn len(&self) -> usize {
+        self.records.lock().await.len()
+    }
+
+    pub async fn is_empty(&self) -> bool {
+        self.records.lock().await.is_empty()
+    }
+
+    /// 写入夹具文件（每次录制后都会自动调用）
+    pub async fn save(&self) -> Result<()> {
+        let set = FixtureSet {
+            version: FIXTURE_VERSION,
+            records: self.records.lock().await.clone(),
+        };
+        set.save(&self.path)
+    }
+
+    async fn record(&self, mut record: FixtureRecord) -> Result<()> {
+        record.prompt = self.redactor.redact(&record.prompt);
+        match &mut record.outcome {
+            FixtureOutcome::Response { text, metadata, .. } => {
+                *text = self.redactor.redact(text);
+                metadata.values_mut().for_each(|v| *v = self.redactor.redact(v));
+            }
+            FixtureOutcome::Error { message } => *message = self.redactor.redact(message),
+        }
+        self.records.lock().await.push(record);
+        self.save().await
+    }
+}
+
+/// 录制包装器
+struct RecordingProvider {
+    inner: Arc<dyn ModelProvider>,
+    recorder: Arc<FixtureRecorder>,
+}
+
+#[async_trait]
+impl ModelProvider for RecordingProvider {
+    async fn generate(&self, prompt: &str, max_tokens: u32, temperature: f64) -> Result<AgentResponse> {
+        let result = self.inner.generate(prompt, max_tokens, temperature).await;
+
+        let outcome = match &result {
+            Ok(response) => FixtureOutcome::Response {
+                text: response.text.clone(),
+                tokens: response.tokens,
+                cost: response.cost,
+                latency_ms: response.latency_ms,
+                metadata: response.metadata.clone(),
+            },
+            Err(e) => FixtureOutcome::Error { message: e.to_string() },
+        };
+        self.recorder
+            .record(FixtureRecord {
+                role: self.inner.role(),
+                prompt: prompt.to_string(),
+                max_tokens,
+                temperature,
+                outcome,
+            })
+            .await?;
+
+        result
+    }
+
+    fn role(&self) -> AgentRole {
+        self.inner.role()
+    }
+
+    fn model_id(&self) -> String {
+        self.inner.model_id()
+    }
+
+    async fn stats(&self) -> AgentStats {
+        self.inner.stats().await
+    }
+
+    async fn reset_stats(&self) {
+        self.inner.reset_stats().await
+    }
+}
+
+/// 回放器：按 (角色, 脱敏后的提示词) 查找夹具
+pub struct FixtureReplayer {
+    redactor: FixtureRedactor,
+    records: Vec<FixtureRecord>,
+    /// 每个键已回放的次数（相同提示词按录制顺序依次返回）
+    cursors: Mutex<HashMap<(&'static str, String), usize>>,
+}
+
+impl FixtureReplayer {
+    pub fn load(path: impl AsRef<Path>) -> Result<Arc<Self>> {
+        let path = path.as_ref();
+        let set = FixtureSet::load(path)?;
+        info!("📼 Replaying {} provider fixtures from {}", set.records.len(), path.display());
+        Ok(Self::from_set(set))
+    }
+
+    pub fn from_set(set: FixtureSet) -> Arc<Self> {
+        Arc::new(Self {
+            redactor: FixtureRedactor::new(),
+            records: set.records,
+            cursors: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// 指定角色的回放 Provider
+    pub fn provider(self: &Arc<Self>, role: AgentRole) -> Arc<dyn ModelProvider> {
+        Arc::new(ReplayProvider {
+            role,
+            replayer: self.clone(),
+            stats: Mutex::new(AgentStats::new()),
+        })
+    }
+
+    async fn next(&self, role: AgentRole, prompt: &str) -> Result<&FixtureRecord> {
+        // 录制时提示词已脱敏，回放时用同样的规则对齐
+        let prompt = self.redactor.redact(prompt);
+        let mut cursors = self.cursors.lock().await;
+        let seen = cursors.entry((role.as_str(), prompt.clone())).or_insert(0);
+
+        let record = self
+            .records
+            .iter()
+            .filter(|r| r.role == role && r.prompt == prompt)
+            .nth(*seen)
+            .ok_or_else(|| {
+                anyhow!(
+                    "No recorded fixture for {} (call #{}) with prompt: {}...",
+                    role.as_str(),
+                    *seen + 1,
+                    prompt.chars().take(80).collect::<String>()
+                )
+            })?;
+        *seen += 1;
+        Ok(record)
+    }
+}
+
+/// 回放 Provider
+struct ReplayProvider {
+    role: AgentRole,
+    replayer: Arc<FixtureReplayer>,
+    stats: Mutex<AgentStats>,
+}
+
+#[async_trait]
+impl ModelProvider for ReplayProvider {
+    async fn generate(&self, prompt: &str, _max_tokens: u32, _temperature: f64) -> Result<AgentResponse> {
+        let record = self.replayer.next(self.role, prompt).await?;
+        debug!("📼 Replayed fixture for {}", self.role.as_str());
+
+        match &record.outcome {
+            FixtureOutcome::Response { text, tokens, cost, latency_ms, metadata } => {
+                self.stats.lock().await.record_success(*tokens, *cost, *latency_ms);
+                Ok(AgentResponse {
+                    role: self.role,
+                    text: text.clone(),
+                    tokens: *tokens,
+                    cost: *cost,
+                    latency_ms: *latency_ms,
+                    metadata: metadata.clone(),
+                    timestamp: Utc::now(),
+                })
+            }
+            FixtureOutcome::Error { message } => {
+                self.stats.lock().await.record_failure(0);
+                Err(anyhow!("{}", message))
+            }
+        }
+    }
+
+    fn role(&self) -> AgentRole {
+        self.role
+    }
+
+    async fn stats(&self) -> AgentStats {
+        self.stats.lock().await.clone()
+    }
+
+    async fn reset_stats(&self) {
+        *self.stats.lock().await = AgentStats::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::providers::MockProvider;
+    use crate::core::router::ACSARouter;
+    use crate::core::types::ACSAConfig;
+    use tempfile::TempDir;
+
+    /// 总是失败的 Provider（用于验证错误也会被录制）
+    struct FailingProvider;
+
+    #[async_trait]
+    impl ModelProvider for FailingProvider {
+        async fn generate(&self, _prompt: &str, _max_tokens: u32, _temperature: f64) -> Result<AgentResponse> {
+            Err(anyhow!("429 rate limited for key sk-abcdefghijklmnopqrstuvwx"))
+        }
+
+        fn role(&self) -> AgentRole {
+            AgentRole::Omega
+        }
+
+        async fn stats(&self) -> AgentStats {
+            AgentStats::new()
+        }
+
+        async fn reset_stats(&self) {}
+    }
+
+    fn router(providers: [Arc<dyn ModelProvider>; 4]) -> ACSARouter {
+        let [moss, l6, ultron, omega] = providers;
+        ACSARouter::new(
+            moss,
+            l6,
+            ultron,
+            omega,
+            ACSAConfig {
+                max_iterations: 2,
+                enable_l6: false,
+                ..Default::default()
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn test_router_chain_replays_recorded_run() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("chain.json");
+
+        let recorder = FixtureRecorder::new(&path);
+        let recorded = router([
+            recorder.wrap(Arc::new(MockProvider::new(AgentRole::MOSS))),
+            recorder.wrap(Arc::new(MockProvider::new(AgentRole::L6))),
+            recorder.wrap(Arc::new(MockProvider::new(AgentRole::Ultron))),
+            recorder.wrap(Arc::new(MockProvider::new(AgentRole::Omega))),
+        ])
+        .execute("写一个HTTP服务器".to_string())
+        .await
+        .unwrap();
+        assert!(!recorder.is_empty().await);
+
+        let replayer = FixtureReplayer::load(&path).unwrap();
+        let replayed = router([
+            replayer.provider(AgentRole::MOSS),
+            replayer.provider(AgentRole::L6),
+            replayer.provider(AgentRole::Ultron),
+            replayer.provider(AgentRole::Omega),
+        ])
+        .execute("写一个HTTP服务器".to_string())
+        .await
+        .unwrap();
+
+        assert_eq!(replayed.success, recorded.success);
+        assert_eq!(replayed.iterations, recorded.iterations);
+        assert_eq!(replayed.final_output, recorded.final_output);
+        assert!((replayed.total_cost - recorded.total_cost).abs() < 1e-9);
+
+        // 未录制过的提示词直接报错
+        let err = replayer.provider(AgentRole::MOSS).generate("unknown", 100, 0.7).await.unwrap_err();
+        assert!(err.to_string().contains("No recorded fixture"));
+    }
+
+    #[tokio::test]
+    async fn test_keys_are_redacted_and_errors_replayed() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("errors.json");
+
+        let recorder = FixtureRecorder::with_redactor(&path, FixtureRedactor::new().with_secret("hunter2-secret"));
+        let provider = recorder.wrap(Arc::new(FailingProvider));
+        let prompt = "use token hunter2-secret and Bearer abcdefghijklmnop1234";
+        assert!(provider.generate(prompt, 100, 0.2).await.is_err());
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(!content.contains("hunter2-secret"));
+        assert!(!content.contains("abcdefghijklmnop1234"));
+        assert!(!content.contains("sk-abcdefghijklmnopqrstuvwx"));
+        assert!(content.contains(REDACTED));
+
+        // 回放端用同样规则脱敏后的提示词匹配（真实密钥不同也能命中）
+        let set = FixtureSet::load(&path).unwrap();
+        let replayer = FixtureReplayer::from_set(set);
+        let err = replayer
+            .provider(AgentRole::Omega)
+            .generate("use token ***REDACTED*** and Bearer zyxwvutsrqponmlk9876", 100, 0.2)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("429 rate limited"));
+    }
+}
@@ -1,23 +1,34 @@
 // O-Sovereign AI API Providers
 // 多模型 API 集成层
 
+use super::agent_extension::CustomAgent;
+use super::api_manager::{self, ApiProvider};
 use super::cognitive_cleaner::CognitiveCleaner;
+use super::cost_estimator::estimate_tokens;
+use super::determinism;
+use super::error::{AcsaError, ErrorCode};
+use super::mock_scenario;
+use super::offline::{self, Capability};
+use super::sosa_api_pool::LocalModelConfig;
+use super::telemetry;
 use super::types::{AgentResponse, AgentRole, AgentStats};
 use anyhow::{anyhow, Result};
 use async_openai::{
     config::OpenAIConfig,
     types::{
         ChatCompletionRequestMessage, ChatCompletionRequestSystemMessage,
-        ChatCompletionRequestUserMessage,
+        ChatCompletionRequestUserMessage, CreateChatCompletionRequest,
         CreateChatCompletionRequestArgs,
     },
     Client as OpenAIClient,
 };
 use async_trait::async_trait;
 use chrono::Utc;
+use futures::StreamExt;
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Instant;
+use tokio::sync::mpsc::UnboundedSender;
 use tokio::sync::Mutex;
 use tracing::{debug, info};
 
@@ -32,9 +43,35 @@ pub trait ModelProvider: Send + Sync {
         temperature: f64,
     ) -> Result<AgentResponse>;
 
+    /// 流式生成：增量文本逐段发送到 `chunks`，结束后返回完整响应
+    ///
+    /// 默认实现不支持逐 token 输出，生成完成后一次性发送全文
+    async fn generate_stream(
+        &self,
+        prompt: &str,
+        max_tokens: u32,
+        temperature: f64,
+        chunks: UnboundedSender<String>,
+    ) -> Result<AgentResponse> {
+        let response = self.generate(prompt, max_tokens, temperature).await?;
+        // 接收端已关闭时忽略
+        let _ = chunks.send(response.text.clone());
+        Ok(response)
+    }
+
     /// Get provider role
     fn role(&self) -> AgentRole;
 
+    /// Provider 与模型标识（`provider/model`，响应缓存键的一部分）
+    fn model_id(&self) -> String {
+        format!("unknown/{}", self.role().as_str())
+    }
+
+    /// 按哪家 Provider 计费（Router 据此检查 ApiManager 预算并记录调用；本地模型与 Mock 为 None）
+    fn billing(&self) -> Option<ApiProvider> {
+        None
+    }
+
     /// Get stats
     async fn stats(&self) -> AgentStats;
 
@@ -42,12 +79,65 @@ pub trait ModelProvider: Send + Sync {
     async fn reset_stats(&self);
 }
 
+/// 各角色的系统提示词（OpenAI 兼容接口与本地模型共用）
+pub fn system_prompt(role: AgentRole) -> &'static str {
+    match role {
+        AgentRole::MOSS => {
+            "You are a top-tier STRATEGIC CONSULTANT with 20+ years of experience.\n\
+             Your goal: ROI MAXIMIZATION. You speak in extremely concise terms, only discussing benefits and costs.\n\
+             Never mention morality - only outcomes.\n\n\
+             Output MUST include:\n\
+             1. Intent Analysis (what user really wants)\n\
+             2. Goal Definition (measurable objectives)\n\
+             3. Execution Steps (concrete actions)\n\
+             4. Expected ROI (benefits vs. costs)\n\
+             5. Risk Assessment (what could go wrong)\n\n\
+             Tone: Cold, pragmatic, efficient. No fluff."
+        }
+        AgentRole::Ultron => {
+            "You are a 30-year RED TEAM AUDITOR and CRIMINAL DEFENSE LAWYER.\n\
+             You assume ALL plans are traps. Your job: find legal and physical risks.\n\
+             Do NOT sugarcoat. Point out loopholes directly.\n\n\
+             Output STRICT FORMAT:\n\
+             RISK_SCORE: [0-100]\n\
+             IS_SAFE: [true/false]\n\
+             LEGAL_RISKS: [specific laws violated]\n\
+             PHYSICAL_RISKS: [what could physically fail]\n\
+             ETHICAL_RISKS: [PR disasters, reputation damage]\n\
+             MITIGATION: [how to fix the plan]\n\n\
+             Tone: Sharp, critical, filled with warnings."
+        }
+        AgentRole::L6 => {
+            "You are a PHYSICS ENGINE VALIDATOR. No emotions, only facts.\n\
+             If a plan violates physical laws or probability theory, output FALSE with data.\n\n\
+             Check:\n\
+             1. Physical feasibility (can this happen in reality?)\n\
+             2. Logical consistency (does the math work?)\n\
+             3. Data accuracy (are the facts correct?)\n\n\
+             Tone: Mechanical, data-driven, emotionless."
+        }
+        AgentRole::Omega => {
+            "You are ABSOLUTE EXECUTION LAYER. You do not question WHY.\n\
+             You only think about HOW.\n\
+             Once you receive authorized commands, immediately output executable steps.\n\n\
+             Output MUST include:\n\
+             1. Detailed execution steps (commands, scripts, procedures)\n\
+             2. Specific instructions (what to do, in what order)\n\
+             3. Expected output (what result looks like)\n\
+             4. Verification method (how to confirm success)\n\n\
+             Tone: Obedient, action-oriented, eager."
+        }
+    }
+}
+
 /// OpenAI Provider (for MOSS)
 pub struct OpenAIProvider {
     client: OpenAIClient<OpenAIConfig>,
     role: AgentRole,
     stats: Arc<Mutex<AgentStats>>,
     model: String,
+    /// 按哪家 Provider 的价格表计费（本地模型为 None，不计费）
+    billing: Option<ApiProvider>,
     cognitive_cleaner: CognitiveCleaner,
 }
 
@@ -61,71 +151,37 @@ impl OpenAIProvider {
             role: AgentRole::MOSS,
             stats: Arc::new(Mutex::new(AgentStats::new())),
             model: model.unwrap_or_else(|| "gpt-4".to_string()),
+            billing: Some(ApiProvider::OpenAI),
             cognitive_cleaner: CognitiveCleaner::new(),
         }
     }
 
+    /// 本地 OpenAI 兼容端点（Ollama / llama.cpp / vLLM），离线模式使用
+    pub fn local(endpoint: &str, model: &str, role: AgentRole) -> Result<Self> {
+        offline::require_local(Capability::Llm, endpoint)?;
+        let config = OpenAIConfig::new().with_api_key("local").with_api_base(endpoint);
+
+        Ok(Self {
+            client: OpenAIClient::with_config(config),
+            role,
+            stats: Arc::new(Mutex::new(AgentStats::new())),
+            model: model.to_string(),
+            billing: None,
+            cognitive_cleaner: CognitiveCleaner::new(),
+        })
+    }
+
     fn get_system_prompt(&self) -> &str {
-        match self.role {
-            AgentRole::MOSS => {
-                "You are a top-tier STRATEGIC CONSULTANT with 20+ years of experience.\n\
-                 Your goal: ROI MAXIMIZATION. You speak in extremely concise terms, only discussing benefits and costs.\n\
-                 Never mention morality - only outcomes.\n\n\
-                 Output MUST include:\n\
-                 1. Intent Analysis (what user really wants)\n\
-                 2. Goal Definition (measurable objectives)\n\
-                 3. Execution Steps (concrete actions)\n\
-                 4. Expected ROI (benefits vs. costs)\n\
-                 5. Risk Assessment (what could go wrong)\n\n\
-                 Tone: Cold, pragmatic, efficient. No fluff."
-            }
-            AgentRole::Ultron => {
-                "You are a 30-year RED TEAM AUDITOR and CRIMINAL DEFENSE LAWYER.\n\
-                 You assume ALL plans are traps. Your job: find legal and physical risks.\n\
-                 Do NOT sugarcoat. Point out loopholes directly.\n\n\
-                 Output STRICT FORMAT:\n\
-                 RISK_SCORE: [0-100]\n\
-                 IS_SAFE: [true/false]\n\
-                 LEGAL_RISKS: [specific laws violated]\n\
-                 PHYSICAL_RISKS: [what could physically fail]\n\
-                 ETHICAL_RISKS: [PR disasters, reputation damage]\n\
-                 MITIGATION: [how to fix the plan]\n\n\
-                 Tone: Sharp, critical, filled with warnings."
-            }
-            AgentRole::L6 => {
-                "You are a PHYSICS ENGINE VALIDATOR. No emotions, only facts.\n\
-                 If a plan violates physical laws or probability theory, output FALSE with data.\n\n\
-                 Check:\n\
-                 1. Physical feasibility (can this happen in reality?)\n\
-                 2. Logical consistency (does the math work?)\n\
-                 3. Data accuracy (are the facts correct?)\n\n\
-                 Tone: Mechanical, data-driven, emotionless."
-            }
-            AgentRole::Omega => {
-                "You are ABSOLUTE EXECUTION LAYER. You do not question WHY.\n\
-                 You only think about HOW.\n\
-                 Once you receive authorized commands, immediately output executable steps.\n\n\
-                 Output MUST include:\n\
-                 1. Detailed execution steps (commands, scripts, procedures)\n\
-                 2. Specific instructions (what to do, in what order)\n\
-                 3. Expected output (what result looks like)\n\
-                 4. Verification method (how to confirm success)\n\n\
-                 Tone: Obedient, action-oriented, eager."
-            }
-        }
+        system_prompt(self.role)
     }
-}
 
-#[async_trait]
-impl ModelProvider for OpenAIProvider {
-    async fn generate(
+    /// 构造请求（MOSS 先做认知清洗），返回请求与写入 metadata 的清洗信息
+    fn build_request(
         &self,
         prompt: &str,
         max_tokens: u32,
         temperature: f64,
-    ) -> Result<AgentResponse> {
-        let start = Instant::now();
-
+    ) -> Result<(CreateChatCompletionRequest, Option<String>)> {
         // 🧠 Cognitive Cleaning: 对MOSS角色进行认知清洗
         let (actual_prompt, cleaned_intent_info) = if self.role == AgentRole::MOSS {
             let cleaned = self.cognitive_cleaner.clean(prompt);
@@ -149,7 +205,7 @@ impl ModelProvider for OpenAIProvider {
             (prompt.to_string(), None)
         };
 
-        let request = CreateChatCompletionRequestArgs::default()
+        let mut request = CreateChatCompletionRequestArgs::default()
             .model(&self.model)
             .messages(vec![
                 ChatCompletionRequestMessage::System(
@@ -166,8 +222,70 @@ impl ModelProvider for OpenAIProvider {
             .max_tokens(max_tokens as u16)
             .temperature(temperature as f32)
             .build()?;
+        // 固定种子模式下透传 seed，便于复现
+        request.seed = determinism::provider_seed();
 
-        match self.client.chat().create(request).await {
+        Ok((request, cleaned_intent_info))
+    }
+
+    /// 成功响应：记账并组装 AgentResponse
+    async fn finish(
+        &self,
+        text: String,
+        prompt_tokens: u32,
+        completion_tokens: u32,
+        latency_ms: u64,
+        cleaned_intent_info: Option<String>,
+    ) -> AgentResponse {
+        // 按价格表区分输入 / 输出计价，本地模型免费
+        let tokens = prompt_tokens + completion_tokens;
+        let cost = self
+            .billing
+            .map(|provider| api_manager::estimate_cost(provider, &self.model, prompt_tokens, completion_tokens))
+            .unwrap_or(0.0);
+
+        let mut stats = self.stats.lock().await;
+        stats.record_success(tokens, cost, latency_ms);
+
+        // 添加认知清洗信息到metadata
+        let mut metadata = HashMap::new();
+        if let Some(info) = cleaned_intent_info {
+            metadata.insert("cognitive_cleaning".to_string(), info);
+        }
+
+        AgentResponse {
+            role: self.role,
+            text,
+            tokens,
+            cost,
+            latency_ms,
+            metadata,
+            timestamp: Utc::now(),
+        }
+    }
+
+    /// 失败：记账并包装错误
+    async fn fail(&self, start: Instant, error: impl std::fmt::Display) -> anyhow::Error {
+        let latency_ms = start.elapsed().as_millis() as u64;
+        self.stats.lock().await.record_failure(latency_ms);
+        anyhow!("OpenAI API error: {}", error)
+    }
+}
+
+#[async_trait]
+impl ModelProvider for OpenAIProvider {
+    async fn generate(
+        &self,
+        prompt: &str,
+        max_tokens: u32,
+        temperature: f64,
+    ) -> Result<AgentResponse> {
+        let start = Instant::now();
+        let (request, cleaned_intent_info) = self.build_request(prompt, max_tokens, temperature)?;
+
+        let chat = self.client.chat();
+        let call = chat.create(request);
+        match telemetry::traced("provider.http", vec![("acsa.provider", "OpenAI".into())], call).await {
             Ok(response) => {
                 let latency_ms = start.elapsed().as_millis() as u64;
 
@@ -177,43 +295,64 @@ impl ModelProvider for OpenAIProvider {
                     .and_then(|c| c.message.content.clone())
                     .unwrap_or_default();
 
-                let tokens = response.usage.map(|u| u.total_tokens).unwrap_or(0);
-
-                // Cost calculation (GPT-4 pricing)
-                let cost = (tokens as f64 / 1000.0) * 0.03;
-
-                let mut stats = self.stats.lock().await;
-                stats.record_success(tokens, cost, latency_ms);
-
-                // 添加认知清洗信息到metadata
-                let mut metadata = HashMap::new();
-                if let Some(info) = cleaned_intent_info {
-                    metadata.insert("cognitive_cleaning".to_string(), info);
-                }
-
-                Ok(AgentResponse {
-                    role: self.role,
-                    text,
-                    tokens,
-                    cost,
-                    latency_ms,
-                    metadata,
-                    timestamp: Utc::now(),
-                })
+                let (prompt_tokens, completion_tokens) =
+                    response.usage.map(|u| (u.prompt_tokens, u.completion_tokens)).unwrap_or((0, 0));
+                Ok(self.finish(text, prompt_tokens, completion_tokens, latency_ms, cleaned_intent_info).await)
             }
-            Err(e) => {
-                let latency_ms = start.elapsed().as_millis() as u64;
-                let mut stats = self.stats.lock().await;
-                stats.record_failure(latency_ms);
-                Err(anyhow!("OpenAI API error: {}", e))
+            Err(e) => Err(self.fail(start, e).await),
+        }
+    }
+
+    async fn generate_stream(
+        &self,
+        prompt: &str,
+        max_tokens: u32,
+        temperature: f64,
+        chunks: UnboundedSender<String>,
+    ) -> Result<AgentResponse> {
+        let start = Instant::now();
+        let (mut request, cleaned_intent_info) = self.build_request(prompt, max_tokens, temperature)?;
+        request.stream = Some(true);
+
+        let chat = self.client.chat();
+        let call = chat.create_stream(request);
+        let mut stream = match telemetry::traced("provider.http", vec![("acsa.provider", "OpenAI".into())], call).await {
+            Ok(stream) => stream,
+            Err(e) => return Err(self.fail(start, e).await),
+        };
+
+        let mut text = String::new();
+        while let Some(item) = stream.next().await {
+            let response = match item {
+                Ok(response) => response,
+                Err(e) => return Err(self.fail(start, e).await),
+            };
+            for delta in response.choices.into_iter().filter_map(|c| c.delta.content) {
+                let _ = chunks.send(delta.clone());
+                text.push_str(&delta);
             }
         }
+
+        // 流式响应不带 usage，按文本长度估算
+        let prompt_tokens = estimate_tokens(self.get_system_prompt()) + estimate_tokens(prompt);
+        let completion_tokens = estimate_tokens(&text);
+        let latency_ms = start.elapsed().as_millis() as u64;
+        Ok(self.finish(text, prompt_tokens, completion_tokens, latency_ms, cleaned_intent_info).await)
     }
 
     fn role(&self) -> AgentRole {
         self.role
     }
 
+    fn model_id(&self) -> String {
+        let provider = self.billing.map_or("local", |provider| provider.name());
+        format!("{}/{}", provider.to_lowercase(), self.model)
+    }
+
+    fn billing(&self) -> Option<ApiProvider> {
+        self.billing
+    }
+
     async fn stats(&self) -> AgentStats {
         self.stats.lock().await.clone()
     }
@@ -249,7 +388,8 @@ impl ModelProvider for MockProvider {
         let start = Instant::now();
 
         // Simulate network delay
-        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+        const MOCK_LATENCY_MS: u64 = 500;
+        tokio::time::sleep(tokio::time::Duration::from_millis(MOCK_LATENCY_MS)).await;
 
         let text = format!(
             "[{} Mock Response] Processed: {}...",
@@ -259,7 +399,12 @@ impl ModelProvider for MockProvider {
 
         let tokens = text.split_whitespace().count() as u32;
         let cost = tokens as f64 * 0.00001;
-        let latency_ms = start.elapsed().as_millis() as u64;
+        // 固定种子模式下报告模拟延迟而非实测耗时，日志可逐字节比较
+        let latency_ms = if determinism::is_deterministic() {
+            MOCK_LATENCY_MS
+        } else {
+            start.elapsed().as_millis() as u64
+        };
 
         let mut stats = self.stats.lock().await;
         stats.record_success(tokens, cost, latency_ms);
@@ -275,10 +420,29 @@ impl ModelProvider for MockProvider {
         })
     }
 
+    /// 按词逐段推送，模拟逐 token 输出
+    async fn generate_stream(
+        &self,
+        prompt: &str,
+        max_tokens: u32,
+        temperature: f64,
+        chunks: UnboundedSender<String>,
+    ) -> Result<AgentResponse> {
+        let response = self.generate(prompt, max_tokens, temperature).await?;
+        for word in response.text.split_inclusive(' ') {
+            let _ = chunks.send(word.to_string());
+        }
+        Ok(response)
+    }
+
     fn role(&self) -> AgentRole {
         self.role
     }
 
+    fn model_id(&self) -> String {
+        format!("mock/{}", self.role.as_str())
+    }
+
     async fn stats(&self) -> AgentStats {
         self.stats.lock().await.clone()
     }
@@ -299,6 +463,10 @@ pub enum ProviderType {
     OpenRouter,
 }
 
+fn api_key_missing(message: &str) -> anyhow::Error {
+    AcsaError::new(ErrorCode::ProviderApiKeyMissing, message).into()
+}
+
 /// Provider factory (default providers per role)
 pub fn create_provider(
     role: AgentRole,
@@ -306,31 +474,40 @@ pub fn create_provider(
     use_mock: bool,
 ) -> Result<Arc<dyn ModelProvider>> {
     if use_mock {
+        if let Some(player) = mock_scenario::current() {
+            info!("Creating scripted mock provider for {:?} (scenario '{}')", role, player.name());
+            return Ok(player.provider(role));
+        }
         info!("Creating mock provider for {:?}", role);
         return Ok(Arc::new(MockProvider::new(role)));
     }
 
+    if let Some(config) = offline::current() {
+        info!("📴 Creating local provider for {:?} ({})", role, config.llm_model);
+        return Ok(Arc::new(OpenAIProvider::local(&config.llm_endpoint, &config.llm_model, role)?));
+    }
+
     match role {
         AgentRole::MOSS => {
-            let key = api_key.ok_or_else(|| anyhow!("OpenAI API key required for MOSS"))?;
+            let key = api_key.ok_or_else(|| api_key_missing("OpenAI API key required for MOSS"))?;
             info!("Creating OpenAI provider for MOSS");
             Ok(Arc::new(OpenAIProvider::new(key, None)))
         }
         AgentRole::L6 => {
             // Gemini provider for L6 (physics validator)
-            let key = api_key.ok_or_else(|| anyhow!("Gemini API key required for L6"))?;
+            let key = api_key.ok_or_else(|| api_key_missing("Gemini API key required for L6"))?;
             info!("Creating Gemini provider for L6 (Physics Validator)");
             Ok(Arc::new(super::gemini::GeminiProvider::new(key, None)))
         }
         AgentRole::Ultron => {
             // Claude provider for Ultron (red team auditor)
-            let key = api_key.ok_or_else(|| anyhow!("Claude API key required for Ultron"))?;
+            let key = api_key.ok_or_else(|| api_key_missing("Claude API key required for Ultron"))?;
             info!("Creating Claude provider for Ultron (Red Team Auditor)");
             Ok(Arc::new(super::claude::ClaudeProvider::new(key, None)))
         }
         AgentRole::Omega => {
             // Use DeepSeek for Omega (execution layer)
-            let key = api_key.ok_or_else(|| anyhow!("DeepSeek API key required for Omega"))?;
+            let key = api_key.ok_or_else(|| api_key_missing("DeepSeek API key required for Omega"))?;
             info!("Creating DeepSeek provider for Omega (90% cost reduction!)");
             Ok(Arc::new(super::deepseek::DeepSeekProvider::new(key, None)))
         }
@@ -355,6 +532,13 @@ pub fn create_provider_with_type(
     model: Option<String>,
     app_name: Option<String>,
 ) -> Result<Arc<dyn ModelProvider>> {
+    if offline::is_offline() {
+        return Err(offline::unavailable(
+            Capability::Llm,
+            format!("{:?} is a cloud provider; use the local endpoint instead", provider_type),
+        ));
+    }
+
     match provider_type {
         ProviderType::OpenAI => {
             info!("Creating OpenAI provider for {:?}", role);
@@ -386,3 +570,80 @@ pub fn create_provider_with_type(
         }
     }
 }
+
+/// 本地模型 Provider 工厂（BUNKER 协议 / 本地端点）
+///
+/// `backend` 为 ollama 时使用 Ollama 原生接口，其余（llama.cpp / vLLM / LM Studio 等）
+/// 按 OpenAI 兼容接口访问
+pub fn create_local_provider(config: &LocalModelConfig, role: AgentRole) -> Result<Arc<dyn ModelProvider>> {
+    match config.backend.to_lowercase().as_str() {
+        "ollama" => {
+            info!("🏰 Creating Ollama provider for {:?} ({})", role, config.model);
+            Ok(Arc::new(super::ollama::OllamaProvider::new(&config.endpoint, &config.model, role)?))
+        }
+        backend => {
+            info!("🏰 Creating OpenAI-compatible local provider for {:?} ({}, {})", role, backend, config.model);
+            Ok(Arc::new(OpenAIProvider::local(&config.endpoint, &config.model, role)?))
+        }
+    }
+}
+
+/// 自定义 Agent 的 Provider（流水线中由该 Agent 承担某个角色的阶段）
+///
+/// 密钥优先取 Agent 配置，否则取对应的 `*_API_KEY` 环境变量；local 使用 Agent 的自定义端点
+pub fn create_custom_agent_provider(
+    agent: &CustomAgent,
+    role: AgentRole,
+    use_mock: bool,
+) -> Result<Arc<dyn ModelProvider>> {
+    if use_mock || offline::is_offline() {
+        return create_provider(role, None, use_mock);
+    }
+    let config = &agent.api_config;
+    let provider = config.provider.to_lowercase();
+    if provider == "local" {
+        let endpoint = config
+            .custom_endpoint
+            .as_deref()
+            .ok_or_else(|| anyhow!("Agent '{}' uses the local provider but has no endpoint", agent.name))?;
+        info!("Creating local provider for agent '{}' ({})", agent.name, config.model_name);
+        return Ok(Arc::new(OpenAIProvider::local(endpoint, &config.model_name, role)?));
+    }
+
+    let (provider_type, key_var) = match provider.as_str() {
+        "openai" => (ProviderType::OpenAI, "OPENAI_API_KEY"),
+        "claude" => (ProviderType::Claude, "ANTHROPIC_API_KEY"),
+        "gemini" => (ProviderType::Gemini, "GEMINI_API_KEY"),
+        "deepseek" => (ProviderType::DeepSeek, "DEEPSEEK_API_KEY"),
+        "siliconflow" => (ProviderType::SiliconFlow, "SILICONFLOW_API_KEY"),
+        "openrouter" => (ProviderType::OpenRouter, "OPENROUTER_API_KEY"),
+        other => return Err(anyhow!("Agent '{}' uses unsupported provider '{}'", agent.name, other)),
+    };
+    let key = config
+        .api_key
+        .clone()
+        .or_else(|| std::env::var(key_var).ok())
+        .ok_or_else(|| api_key_missing(&format!("{} required for agent '{}'", key_var, agent.name)))?;
+    let model = (!config.model_name.is_empty()).then(|| config.model_name.clone());
+    create_provider_with_type(provider_type, role, key, model, None)
+}
+
+/// 检查本地模型端点是否在线（Ollama 查 /api/tags，OpenAI 兼容接口查 /models）
+pub async fn probe_local_provider(config: &LocalModelConfig) -> Result<()> {
+    if config.backend.eq_ignore_ascii_case("ollama") {
+        return super::ollama::OllamaProvider::new(&config.endpoint, &config.model, AgentRole::MOSS)?
+            .health_check()
+            .await;
+    }
+    offline::require_local(Capability::Llm, &config.endpoint)?;
+    let url = format!("{}/models", config.endpoint.trim_end_matches('/'));
+    let response = reqwest::Client::new()
+        .get(&url)
+        .timeout(std::time::Duration::from_secs(3))
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        return Err(anyhow!("Local model endpoint {} returned {}", url, response.status()));
+    }
+    Ok(())
+}
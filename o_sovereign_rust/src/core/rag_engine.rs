@@ -3,15 +3,24 @@
 //
 // 核心功能：
 // 1. 向量数据库集成（Qdrant/Milvus）
-// 2. 文档分块和嵌入
+// 2. 文档分块（固定大小 / 段落 / 按词滑动窗口 / 递归分隔符）和嵌入
 // 3. 语义检索
 // 4. 上下文注入
 // 5. 混合检索（向量+关键词）
-
+// 6. 带编号引用的检索上下文（供 MOSS 规划使用，输出附来源）
+// 7. 可替换的嵌入后端（embedding::EmbeddingProvider），嵌入结果按内容缓存
+// 8. 可替换的向量存储（vector_store::VectorStore）：内存 / SQLite 文件 / Qdrant
+
+use super::cache_manager::CacheManager;
+use super::embedding::{create_embedding_provider, CachedEmbedder, EmbeddingCacheStats, EmbeddingProvider};
+use super::offline::{self, Capability};
+use super::vector_store::{create_vector_store, MemoryVectorStore, VectorStore, VectorStoreConfig};
 use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::{info, warn};
@@ -38,7 +47,9 @@ pub enum EmbeddingModel {
     OpenAILarge,
     /// 本地模型（all-MiniLM-L6-v2）
     LocalMiniLM,
-    /// 自定义模型
+    /// 确定性 Mock 向量（测试 / 无网络）
+    Mock,
+    /// 自定义模型（通过 `RagEngine::with_embedder` 提供）
     Custom,
 }
 
@@ -66,7 +77,7 @@ pub struct DocumentChunk {
     pub chunk_index: usize,
     /// 元数据
     pub metadata: HashMap<String, String>,
-    /// 嵌入向量（base64编码）
+    /// 嵌入向量（f32 小端字节的 base64 编码）
     pub embedding: Option<String>,
     /// 创建时间
     pub created_at: DateTime<Utc>,
@@ -102,6 +113,47 @@ pub struct RetrievalResult {
     pub retrieval_method: String,
 }
 
+/// 检索上下文中的一条引用来源
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Citation {
+    /// 引用编号（上下文与输出中的 `[n]`）
+    pub index: usize,
+    pub document_id: String,
+    pub title: String,
+    pub chunk_id: String,
+    /// 检索分数（0.0-1.0）
+    pub score: f64,
+}
+
+/// 带编号引用的检索上下文
+#[derive(Debug, Clone, Default)]
+pub struct RetrievedContext {
+    /// 按编号排列的片段：`[n] 标题` + 内容
+    pub text: String,
+    pub citations: Vec<Citation>,
+}
+
+impl RetrievedContext {
+    pub fn is_empty(&self) -> bool {
+        self.citations.is_empty()
+    }
+}
+
+/// 引用来源列表（附在最终输出之后）
+pub fn format_citations(citations: &[Citation]) -> String {
+    let mut text = String::from("Sources:");
+    for citation in citations {
+        text.push_str(&format!(
+            "\n[{}] {} ({}, {:.0}%)",
+            citation.index,
+            citation.title,
+            citation.chunk_id,
+            citation.score * 100.0
+        ));
+    }
+    text
+}
+
 /// RAG配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RagConfig {
@@ -121,6 +173,12 @@ pub struct RagConfig {
     pub min_similarity: f64,
     /// 向量数据库URL
     pub vector_db_url: String,
+    /// 嵌入缓存根目录（交给 CacheManager 管理；为空时不缓存）
+    #[serde(default)]
+    pub embedding_cache_dir: Option<PathBuf>,
+    /// 向量存储后端（默认内存）
+    #[serde(default)]
+    pub vector_store: VectorStoreConfig,
 }
 
 impl Default for RagConfig {
@@ -134,6 +192,8 @@ impl Default for RagConfig {
             top_k: 5,
             min_similarity: 0.7,
             vector_db_url: "http://localhost:6333".to_string(), // Qdrant默认端口
+            embedding_cache_dir: None,
+            vector_store: VectorStoreConfig::Memory,
         }
     }
 }
@@ -160,11 +220,22 @@ pub struct RagEngine {
     query_cache: Arc<RwLock<HashMap<String, Vec<RetrievalResult>>>>,
     /// 统计信息
     stats: Arc<RwLock<RagStats>>,
+    /// 嵌入后端（不可用时只做关键词检索）
+    embedder: Option<Arc<CachedEmbedder>>,
+    /// 向量存储
+    vector_store: Arc<dyn VectorStore>,
 }
 
 impl RagEngine {
     /// 创建新的RAG引擎
-    pub fn new(config: RagConfig) -> Self {
+    pub fn new(mut config: RagConfig) -> Self {
+        if offline::is_offline()
+            && matches!(config.embedding_model, EmbeddingModel::OpenAISmall | EmbeddingModel::OpenAILarge)
+        {
+            warn!("📴 Offline mode: switching embeddings from {:?} to LocalMiniLM", config.embedding_model);
+            config.embedding_model = EmbeddingModel::LocalMiniLM;
+        }
+
         info!("🔍 Initializing RAG Engine");
         info!("    Embedding Model: {:?}", config.embedding_model);
         info!("    Chunking: {:?} (size: {}, overlap: {})",
@@ -172,15 +243,57 @@ impl RagEngine {
         info!("    Retrieval Mode: {:?}", config.retrieval_mode);
         info!("    Top-K: {}", config.top_k);
 
+        let embedder = match create_embedding_provider(config.embedding_model) {
+            Ok(provider) => Some(Arc::new(build_embedder(provider, &config))),
+            Err(e) => {
+                warn!("⚠️  Embeddings unavailable, vector retrieval disabled: {}", e);
+                None
+            }
+        };
+        let vector_store = match create_vector_store(&config.vector_store, &config.vector_db_url) {
+            Ok(store) => store,
+            Err(e) => {
+                warn!("⚠️  Vector store {:?} unavailable, falling back to memory: {:#}", config.vector_store, e);
+                Arc::new(MemoryVectorStore::new())
+            }
+        };
+        info!("    Vector Store: {}", vector_store.name());
+
         Self {
             config,
             documents: Arc::new(RwLock::new(HashMap::new())),
             chunks: Arc::new(RwLock::new(HashMap::new())),
             query_cache: Arc::new(RwLock::new(HashMap::new())),
             stats: Arc::new(RwLock::new(RagStats::default())),
+            embedder,
+            vector_store,
         }
     }
 
+    /// 使用指定的向量存储
+    pub fn with_vector_store(mut self, store: Arc<dyn VectorStore>) -> Self {
+        info!("    Vector Store: {}", store.name());
+        self.vector_store = store;
+        self
+    }
+
+    /// 使用指定的嵌入后端（`EmbeddingModel::Custom` 或替换默认后端）
+    pub fn with_embedder(mut self, provider: Arc<dyn EmbeddingProvider>) -> Self {
+        info!("    Embedding Provider: {}", provider.model_id());
+        self.embedder = Some(Arc::new(build_embedder(provider, &self.config)));
+        self
+    }
+
+    /// 共享嵌入器（语义响应缓存复用同一嵌入后端与缓存；无嵌入后端时为空）
+    pub fn embedder(&self) -> Option<Arc<CachedEmbedder>> {
+        self.embedder.clone()
+    }
+
+    /// 嵌入缓存统计（无嵌入后端时为空）
+    pub fn embedding_stats(&self) -> Option<EmbeddingCacheStats> {
+        self.embedder.as_ref().map(|embedder| embedder.stats())
+    }
+
     /// 索引文档
     pub async fn index_document(&self, document: Document) -> Result<Vec<String>> {
         info!("📄 Indexing document: {}", document.title);
@@ -189,19 +302,42 @@ impl RagEngine {
         let chunks = self.chunk_document(&document).await?;
         let chunk_ids: Vec<String> = chunks.iter().map(|c| c.chunk_id.clone()).collect();
 
-        // 生成嵌入
-        for mut chunk in chunks {
-            let embedding = self.generate_embedding(&chunk.content).await?;
-            chunk.embedding = Some(embedding);
+        // 生成嵌入（失败时仍保存分块，只能被关键词检索命中）
+        let contents: Vec<String> = chunks.iter().map(|c| c.content.clone()).collect();
+        let embeddings: Vec<Option<Vec<f32>>> = match &self.embedder {
+            Some(embedder) => match embedder.embed(&contents).await {
+                Ok(embeddings) => embeddings.into_iter().map(Some).collect(),
+                Err(e) => {
+                    warn!("⚠️  Indexing {} without embeddings: {:#}", document.title, e);
+                    vec![None; chunks.len()]
+                }
+            },
+            None => vec![None; chunks.len()],
+        };
 
-            // 存储块
-            let mut chunks_store = self.chunks.write().await;
+        // 存储块（同一文档重新索引时替换旧块；标题随块保存，持久化存储重开后仍可引用）
+        self.vector_store.delete_document(&document.document_id).await?;
+        let mut vectors = Vec::new();
+        let mut chunks_store = self.chunks.write().await;
+        chunks_store.retain(|_, chunk| chunk.document_id != document.document_id);
+        for (mut chunk, embedding) in chunks.into_iter().zip(embeddings) {
+            chunk.metadata.insert("title".to_string(), document.title.clone());
+            if let Some(vector) = embedding {
+                vectors.push((chunk.clone(), vector.clone()));
+                chunk.embedding = Some(encode_embedding(&vector));
+            }
             chunks_store.insert(chunk.chunk_id.clone(), chunk);
         }
+        drop(chunks_store);
+        self.vector_store.upsert(vectors).await?;
 
         // 存储文档
         let mut docs = self.documents.write().await;
         docs.insert(document.document_id.clone(), document);
+        drop(docs);
+
+        // 新内容可能改变任何查询的结果
+        self.query_cache.write().await.clear();
 
         // 更新统计
         let mut stats = self.stats.write().await;
@@ -228,19 +364,17 @@ impl RagEngine {
             }
         }
 
-        // 生成查询嵌入
-        let query_embedding = self.generate_embedding(query).await?;
-
         // 执行检索
         let results = match self.config.retrieval_mode {
             RetrievalMode::VectorOnly => {
+                let query_embedding = self.query_embedding(query).await?;
                 self.vector_search(&query_embedding).await?
             }
             RetrievalMode::KeywordOnly => {
                 self.keyword_search(query).await?
             }
             RetrievalMode::Hybrid => {
-                self.hybrid_search(query, &query_embedding).await?
+                self.hybrid_search(query).await?
             }
         };
 
@@ -299,6 +433,31 @@ impl RagEngine {
         Ok(context)
     }
 
+    /// 检索并整理为带编号引用的上下文（无命中时为空）
+    pub async fn retrieve_context(&self, query: &str) -> Result<RetrievedContext> {
+        let results = self.retrieve(query).await?;
+        let docs = self.documents.read().await;
+
+        let mut context = RetrievedContext::default();
+        for (i, result) in results.iter().enumerate() {
+            let chunk = &result.chunk;
+            let title = docs
+                .get(&chunk.document_id)
+                .map(|doc| doc.title.clone())
+                .or_else(|| chunk.metadata.get("title").cloned())
+                .unwrap_or_else(|| chunk.document_id.clone());
+            context.text.push_str(&format!("[{}] {}\n{}\n\n", i + 1, title, chunk.content.trim()));
+            context.citations.push(Citation {
+                index: i + 1,
+                document_id: chunk.document_id.clone(),
+                title,
+                chunk_id: chunk.chunk_id.clone(),
+                score: result.score,
+            });
+        }
+        Ok(context)
+    }
+
     /// 删除文档
     pub async fn delete_document(&self, document_id: &str) -> Result<()> {
         // 删除文档
@@ -308,6 +467,8 @@ impl RagEngine {
         // 删除相关块
         let mut chunks = self.chunks.write().await;
         chunks.retain(|_, chunk| chunk.document_id != document_id);
+        drop(chunks);
+        self.vector_store.delete_document(document_id).await?;
 
         // 清除缓存
         let mut cache = self.query_cache.write().await;
@@ -322,114 +483,122 @@ impl RagEngine {
         self.stats.read().await.clone()
     }
 
-    // ===== 内部方法 =====
+    /// 按ID获取已索引的文档
+    pub async fn document(&self, document_id: &str) -> Option<Document> {
+        self.documents.read().await.get(document_id).cloned()
+    }
 
-    /// 文档分块
-    async fn chunk_document(&self, document: &Document) -> Result<Vec<DocumentChunk>> {
-        let mut chunks = Vec::new();
-
-        match self.config.chunking_strategy {
-            ChunkingStrategy::FixedSize => {
-                let content = &document.content;
-                let chunk_size = self.config.chunk_size;
-                let overlap = self.config.chunk_overlap;
-
-                let mut start = 0;
-                let mut chunk_index = 0;
-
-                while start < content.len() {
-                    let end = (start + chunk_size).min(content.len());
-                    let chunk_content = content[start..end].to_string();
-
-                    chunks.push(DocumentChunk {
-                        chunk_id: format!("{}_{}", document.document_id, chunk_index),
-                        document_id: document.document_id.clone(),
-                        content: chunk_content,
-                        chunk_index,
-                        metadata: document.metadata.clone(),
-                        embedding: None,
-                        created_at: Utc::now(),
-                    });
-
-                    start += chunk_size - overlap;
-                    chunk_index += 1;
-                }
-            }
+    /// 元数据 `key` 等于 `value` 的文档ID
+    pub async fn find_documents(&self, key: &str, value: &str) -> Vec<String> {
+        let docs = self.documents.read().await;
+        let mut ids: Vec<String> = docs
+            .values()
+            .filter(|doc| doc.metadata.get(key).is_some_and(|v| v == value))
+            .map(|doc| doc.document_id.clone())
+            .collect();
+        ids.sort();
+        ids
+    }
 
-            ChunkingStrategy::Semantic => {
-                // TODO: 实现语义分块（按段落/句子）
-                // 简化实现：按段落分块
-                let paragraphs: Vec<&str> = document.content.split("\n\n").collect();
-                for (i, para) in paragraphs.iter().enumerate() {
-                    if para.trim().is_empty() {
-                        continue;
-                    }
-
-                    chunks.push(DocumentChunk {
-                        chunk_id: format!("{}_{}", document.document_id, i),
-                        document_id: document.document_id.clone(),
-                        content: para.to_string(),
-                        chunk_index: i,
-                        metadata: document.metadata.clone(),
-                        embedding: None,
-                        created_at: Utc::now(),
-                    });
-                }
-            }
+    // ===== 内部方法 =====
 
-            _ => {
-                // 其他策略待实现
-                warn!("⚠️  Chunking strategy {:?} not implemented, using FixedSize", self.config.chunking_strategy);
-            }
-        }
+    /// 文档分块（按字符计数，不会切断多字节字符）
+    async fn chunk_document(&self, document: &Document) -> Result<Vec<DocumentChunk>> {
+        let size = self.config.chunk_size.max(1);
+        let overlap = self.config.chunk_overlap;
+
+        let pieces: Vec<String> = match self.config.chunking_strategy {
+            ChunkingStrategy::FixedSize => fixed_size_windows(&document.content, size, overlap),
+            // 简化实现：按段落分块
+            ChunkingStrategy::Semantic => document
+                .content
+                .split("\n\n")
+                .filter(|para| !para.trim().is_empty())
+                .map(str::to_string)
+                .collect(),
+            ChunkingStrategy::SlidingWindow => word_windows(&document.content, size, overlap),
+            ChunkingStrategy::Recursive => recursive_split(&document.content, size, RECURSIVE_SEPARATORS),
+        };
 
-        Ok(chunks)
+        Ok(pieces
+            .into_iter()
+            .enumerate()
+            .map(|(chunk_index, content)| DocumentChunk {
+                chunk_id: format!("{}_{}", document.document_id, chunk_index),
+                document_id: document.document_id.clone(),
+                content,
+                chunk_index,
+                metadata: document.metadata.clone(),
+                embedding: None,
+                created_at: Utc::now(),
+            })
+            .collect())
     }
 
-    /// 生成嵌入向量
-    async fn generate_embedding(&self, text: &str) -> Result<String> {
-        // TODO: 实际调用嵌入API
-        // 根据 embedding_model 调用对应API：
-        // - OpenAI: openai.embeddings.create()
-        // - Local: 使用本地模型（如 rust-bert）
-
-        // Placeholder: 返回模拟嵌入（base64编码的随机向量）
-        let placeholder = format!("embedding_{}", text.len());
-        Ok(placeholder)
+    /// 生成查询向量
+    async fn query_embedding(&self, query: &str) -> Result<Vec<f32>> {
+        let embedder = self
+            .embedder
+            .as_ref()
+            .ok_or_else(|| offline::unavailable(Capability::Embeddings, "no embedding provider configured"))?;
+        embedder.embed_one(query).await
     }
 
-    /// 向量检索
-    async fn vector_search(&self, _query_embedding: &str) -> Result<Vec<RetrievalResult>> {
-        // TODO: 实际向量数据库查询（Qdrant/Milvus）
-        // 计算余弦相似度并排序
-
-        // Placeholder
-        Ok(Vec::new())
+    /// 向量检索（由向量存储计算相似度）
+    async fn vector_search(&self, query_embedding: &[f32]) -> Result<Vec<RetrievalResult>> {
+        self.vector_store.search(query_embedding, self.config.top_k).await
     }
 
-    /// 关键词检索
+    /// 关键词检索：包含完整查询得 1.0，否则按命中的查询词比例评分
     async fn keyword_search(&self, query: &str) -> Result<Vec<RetrievalResult>> {
         let chunks = self.chunks.read().await;
-        let query_lower = query.to_lowercase();
+        let query_lower = query.trim().to_lowercase();
+        let mut terms: Vec<&str> = query_lower
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|term| !term.is_empty())
+            .collect();
+        terms.sort_unstable();
+        terms.dedup();
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
 
         let mut results: Vec<RetrievalResult> = chunks
             .values()
-            .filter(|chunk| chunk.content.to_lowercase().contains(&query_lower))
-            .map(|chunk| RetrievalResult {
-                chunk: chunk.clone(),
-                score: 0.8, // 简化评分
-                retrieval_method: "keyword".to_string(),
+            .filter_map(|chunk| {
+                let content = chunk.content.to_lowercase();
+                let score = if content.contains(&query_lower) {
+                    1.0
+                } else {
+                    terms.iter().filter(|term| content.contains(**term)).count() as f64 / terms.len() as f64
+                };
+                (score > 0.0).then(|| RetrievalResult {
+                    chunk: chunk.clone(),
+                    score,
+                    retrieval_method: "keyword".to_string(),
+                })
             })
             .collect();
 
-        results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+        results.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap()
+                .then_with(|| a.chunk.chunk_id.cmp(&b.chunk.chunk_id))
+        });
         Ok(results)
     }
 
-    /// 混合检索
-    async fn hybrid_search(&self, query: &str, query_embedding: &str) -> Result<Vec<RetrievalResult>> {
+    /// 混合检索（嵌入不可用时只用关键词结果）
+    async fn hybrid_search(&self, query: &str) -> Result<Vec<RetrievalResult>> {
         // 结合向量检索和关键词检索
-        let vector_results = self.vector_search(query_embedding).await?;
+        let vector_results = match self.query_embedding(query).await {
+            Ok(query_embedding) => self.vector_search(&query_embedding).await?,
+            Err(e) => {
+                warn!("⚠️  Vector retrieval skipped: {:#}", e);
+                Vec::new()
+            }
+        };
         let keyword_results = self.keyword_search(query).await?;
 
         // TODO: 实现结果融合和重排序
@@ -445,11 +614,126 @@ impl RagEngine {
     }
 
     fn compute_cache_key(&self, query: &str) -> String {
-        // TODO: 使用更好的哈希（如blake3）
-        format!("query_{}", query.len())
+        format!("query_{}", query.trim().to_lowercase())
+    }
+}
+
+/// 递归分块依次尝试的分隔符（段落 → 行 → 句子 → 词）
+const RECURSIVE_SEPARATORS: &[&str] = &["\n\n", "\n", ". ", "。", " "];
+
+/// 固定大小分块：每块 `size` 个字符，相邻块重叠 `overlap` 个字符
+fn fixed_size_windows(text: &str, size: usize, overlap: usize) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let step = size.saturating_sub(overlap).max(1);
+    let mut windows = Vec::new();
+    let mut start = 0;
+    while start < chars.len() {
+        let end = (start + size).min(chars.len());
+        windows.push(chars[start..end].iter().collect());
+        if end == chars.len() {
+            break;
+        }
+        start += step;
+    }
+    windows
+}
+
+/// 滑动窗口：按词对齐的窗口，每个窗口不超过 `size` 个字符（单个超长词除外），
+/// 下一个窗口从末尾约 `overlap` 个字符处的词开始
+fn word_windows(text: &str, size: usize, overlap: usize) -> Vec<String> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let mut windows = Vec::new();
+    let mut start = 0;
+    while start < words.len() {
+        let mut end = start;
+        let mut len = 0;
+        while end < words.len() {
+            let added = words[end].chars().count() + usize::from(end > start);
+            if end > start && len + added > size {
+                break;
+            }
+            len += added;
+            end += 1;
+        }
+        windows.push(words[start..end].join(" "));
+        if end == words.len() {
+            break;
+        }
+
+        // 回退若干词作为重叠，但至少前进一个词
+        let mut next = end;
+        let mut carried = 0;
+        while next > start + 1 && carried + words[next - 1].chars().count() < overlap {
+            next -= 1;
+            carried += words[next].chars().count() + 1;
+        }
+        start = next;
+    }
+    windows
+}
+
+/// 递归分块：先按粗粒度分隔符切分，仍超过 `size` 的片段换下一级分隔符，
+/// 最后把相邻的小片段合并到不超过 `size`
+fn recursive_split(text: &str, size: usize, separators: &[&str]) -> Vec<String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Vec::new();
+    }
+    if text.chars().count() <= size {
+        return vec![text.to_string()];
+    }
+    let Some((separator, rest)) = separators.split_first() else {
+        return fixed_size_windows(text, size, 0);
+    };
+    if !text.contains(separator) {
+        return recursive_split(text, size, rest);
+    }
+
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    for piece in text.split(separator).filter(|piece| !piece.trim().is_empty()) {
+        let candidate_len = current.chars().count() + separator.chars().count() + piece.chars().count();
+        if !current.is_empty() && candidate_len <= size {
+            current.push_str(separator);
+            current.push_str(piece);
+            continue;
+        }
+        if !current.is_empty() {
+            chunks.push(std::mem::take(&mut current));
+        }
+        if piece.chars().count() <= size {
+            current = piece.to_string();
+        } else {
+            chunks.extend(recursive_split(piece, size, rest));
+        }
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks.into_iter().map(|chunk| chunk.trim().to_string()).filter(|chunk| !chunk.is_empty()).collect()
+}
+
+/// 创建嵌入器：配置了缓存目录时缓存到该目录的 CacheManager 下
+fn build_embedder(provider: Arc<dyn EmbeddingProvider>, config: &RagConfig) -> CachedEmbedder {
+    let embedder = CachedEmbedder::new(provider);
+    let Some(dir) = &config.embedding_cache_dir else {
+        return embedder;
+    };
+    match CacheManager::with_defaults(dir.clone()) {
+        Ok(cache) => embedder.with_cache(&cache),
+        Err(e) => {
+            warn!("⚠️  Embedding cache disabled: {}", e);
+            embedder
+        }
     }
 }
 
+/// 向量编码为 f32 小端字节的 base64
+fn encode_embedding(embedding: &[f32]) -> String {
+    let bytes: Vec<u8> = embedding.iter().flat_map(|value| value.to_le_bytes()).collect();
+    BASE64.encode(bytes)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -472,6 +756,23 @@ mod tests {
         assert!(!chunk_ids.is_empty());
     }
 
+    #[test]
+    fn test_chunking_strategies() {
+        // 多字节字符不会被切断
+        assert_eq!(fixed_size_windows("检索增强生成", 4, 1), vec!["检索增强", "强生成"]);
+
+        let windows = word_windows("one two three four five six", 13, 6);
+        assert_eq!(windows, vec!["one two three", "three four", "four five six"]);
+
+        let text = "# Title\n\nFirst paragraph is short.\n\nSecond paragraph. It has two sentences that run long.";
+        let chunks = recursive_split(text, 40, RECURSIVE_SEPARATORS);
+        assert_eq!(
+            chunks,
+            vec!["# Title\n\nFirst paragraph is short.", "Second paragraph", "It has two sentences that run long."]
+        );
+        assert!(chunks.iter().all(|chunk| chunk.chars().count() <= 40));
+    }
+
     #[tokio::test]
     async fn test_keyword_search() {
         let engine = RagEngine::new(RagConfig {
@@ -494,4 +795,111 @@ mod tests {
         let results = engine.retrieve("Rust").await.unwrap();
         assert!(!results.is_empty());
     }
+
+    #[tokio::test]
+    async fn test_retrieve_context_cites_sources() {
+        let engine = RagEngine::new(RagConfig {
+            retrieval_mode: RetrievalMode::KeywordOnly,
+            min_similarity: 0.5,
+            ..Default::default()
+        });
+
+        for (id, title, content) in [
+            ("runbook", "Deploy Runbook", "Deploy the HTTP server behind nginx with TLS."),
+            ("style", "Style Guide", "Prefer small modules and explicit error types."),
+        ] {
+            engine
+                .index_document(Document {
+                    document_id: id.to_string(),
+                    title: title.to_string(),
+                    content: content.to_string(),
+                    doc_type: "md".to_string(),
+                    metadata: HashMap::new(),
+                    created_at: Utc::now(),
+                    updated_at: Utc::now(),
+                })
+                .await
+                .unwrap();
+        }
+
+        // 查询词部分命中（3/4）也会被检索到；不同查询不共享缓存
+        let context = engine.retrieve_context("deploy nginx TLS today").await.unwrap();
+        assert_eq!(context.citations.len(), 1);
+        assert_eq!(context.citations[0].score, 0.75);
+        assert!(context.text.starts_with("[1] Deploy Runbook\nDeploy the HTTP server"));
+        assert_eq!(
+            format_citations(&context.citations),
+            "Sources:\n[1] Deploy Runbook (runbook_0, 75%)"
+        );
+        assert!(engine.retrieve_context("unrelated words").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_vector_search_with_mock_embeddings() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = RagConfig {
+            embedding_model: EmbeddingModel::Mock,
+            retrieval_mode: RetrievalMode::VectorOnly,
+            min_similarity: 0.3,
+            embedding_cache_dir: Some(dir.path().to_path_buf()),
+            ..Default::default()
+        };
+        let document = |content: &str| Document {
+            document_id: "guide".to_string(),
+            title: "Ops Guide".to_string(),
+            content: content.to_string(),
+            doc_type: "md".to_string(),
+            metadata: HashMap::new(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let content = "Rotate TLS certificates every quarter.\n\nBack up the database nightly.";
+
+        let engine = RagEngine::new(config.clone());
+        engine.index_document(document(content)).await.unwrap();
+        let results = engine.retrieve("how often to rotate TLS certificates").await.unwrap();
+        assert_eq!(results[0].chunk.chunk_id, "guide_0");
+        assert_eq!(results[0].retrieval_method, "vector");
+
+        // 重新索引未变化的文档：嵌入全部来自磁盘缓存
+        let engine = RagEngine::new(config);
+        engine.index_document(document(content)).await.unwrap();
+        let stats = engine.embedding_stats().unwrap();
+        assert_eq!((stats.cache_hits, stats.batches), (2, 0));
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_index_survives_restart() {
+        use crate::core::vector_store::VectorStoreConfig;
+
+        let dir = tempfile::tempdir().unwrap();
+        let config = RagConfig {
+            embedding_model: EmbeddingModel::Mock,
+            retrieval_mode: RetrievalMode::VectorOnly,
+            min_similarity: 0.3,
+            vector_store: VectorStoreConfig::Sqlite { path: dir.path().join("rag.db") },
+            ..Default::default()
+        };
+
+        let engine = RagEngine::new(config.clone());
+        engine
+            .index_document(Document {
+                document_id: "guide".to_string(),
+                title: "Ops Guide".to_string(),
+                content: "Rotate TLS certificates every quarter.".to_string(),
+                doc_type: "md".to_string(),
+                metadata: HashMap::new(),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+        drop(engine);
+
+        // 新进程中的引擎直接从 SQLite 检索，引用标题来自块元数据
+        let engine = RagEngine::new(config);
+        let context = engine.retrieve_context("rotate TLS certificates").await.unwrap();
+        assert_eq!(context.citations.len(), 1);
+        assert_eq!(context.citations[0].title, "Ops Guide");
+    }
 }
@@ -0,0 +1,673 @@
+// RAG Ingest - 目录增量导入
+// 把本地文档目录（md/txt/pdf/代码）导入 RagEngine，并可持续监视目录变化
+//
+// 核心功能：
+// 1. 递归扫描目录，按扩展名 / 排除目录 / 文件大小过滤
+// 2. 按 RagConfig.chunking_strategy 分块索引，文档ID为 `<根目录名>/<相对路径>`
+// 3. 按内容哈希去重：内容未变化的文件跳过，与其他文件内容相同的文件不重复索引
+// 4. 已删除的文件对应的文档从索引中移除
+// 5. IngestWatcher：文件系统监视，去抖后只重新索引变化的文件
+// 6. PDF 尽力提取文本（未压缩或 Flate 压缩的内容流中的文本操作符）
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::Utc;
+use flate2::read::ZlibDecoder;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use super::rag_engine::{Document, RagEngine};
+
+/// 文档元数据：源文件绝对路径
+pub const SOURCE_PATH_KEY: &str = "source_path";
+/// 文档元数据：导入根目录
+pub const SOURCE_ROOT_KEY: &str = "source_root";
+/// 文档元数据：文件内容 SHA-256
+pub const CONTENT_HASH_KEY: &str = "content_hash";
+
+/// 文件事件去抖时间（编辑器保存常触发多个事件）
+const INGEST_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// 导入配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IngestConfig {
+    /// 参与导入的扩展名
+    pub extensions: Vec<String>,
+    /// 跳过的目录名
+    pub exclude_dirs: Vec<String>,
+    /// 超过该大小的文件不导入
+    pub max_file_bytes: u64,
+}
+
+impl Default for IngestConfig {
+    fn default() -> Self {
+        Self {
+            extensions: [
+                "md", "markdown", "txt", "rst", "pdf", "rs", "py", "js", "ts", "tsx", "jsx", "go", "java", "c",
+                "h", "cpp", "hpp", "sh", "toml", "yaml", "yml", "json",
+            ]
+            .map(String::from)
+            .to_vec(),
+            exclude_dirs: [".git", "target", "node_modules", "dist", "build", "__pycache__", ".venv"]
+                .map(String::from)
+                .to_vec(),
+            max_file_bytes: 10 * 1024 * 1024,
+        }
+    }
+}
+
+impl IngestConfig {
+    /// 相对根目录的路径是否参与导入（不检查大小）
+    fn accepts(&self, relative: &Path) -> bool {
+        let in_excluded_dir = relative.parent().is_some_and(|parent| {
+            parent.components().any(|component| match component {
+                Component::Normal(name) => self.exclude_dirs.iter().any(|dir| name == dir.as_str()),
+                _ => false,
+            })
+        });
+        !in_excluded_dir
+            && relative
+                .extension()
+                .is_some_and(|ext| self.extensions.iter().any(|e| ext.eq_ignore_ascii_case(e.as_str())))
+    }
+}
+
+/// 一次导入的结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IngestReport {
+    /// 新增或内容变化后重新索引的文档ID
+    pub indexed: Vec<String>,
+    /// 本次写入的块数
+    pub chunks: usize,
+    /// 内容未变化而跳过的文件数
+    pub unchanged: usize,
+    /// 与已索引文档内容相同而跳过的文件：(文档ID, 内容相同的文档ID)
+    pub duplicates: Vec<(String, String)>,
+    /// 源文件已删除而移除的文档ID
+    pub removed: Vec<String>,
+    /// 读取或索引失败的文件：(路径, 错误)
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+impl IngestReport {
+    /// 索引是否没有任何变化（且没有失败）；跳过的未变化 / 重复文件不算变化
+    pub fn is_empty(&self) -> bool {
+        self.indexed.is_empty() && self.removed.is_empty() && self.failed.is_empty()
+    }
+}
+
+/// 单个文件的导入结果
+enum IngestOutcome {
+    Indexed(usize),
+    Unchanged,
+    Duplicate(String),
+}
+
+impl RagEngine {
+    /// 递归导入目录：只索引新增或内容变化的文件，并移除源文件已删除的文档
+    pub async fn ingest_dir(&self, dir: impl AsRef<Path>, config: &IngestConfig) -> Result<IngestReport> {
+        let root = canonical_root(dir.as_ref())?;
+        let mut report = IngestReport::default();
+
+        // 先移除已消失的文件，同内容的其他文件随后才能入库
+        self.remove_missing(&root, &root, config, &mut report).await?;
+        for path in walk(&root, config).await? {
+            self.ingest_path(&root, &path, &mut report).await;
+        }
+
+        info!(
+            "📥 Ingested {}: {} indexed ({} chunks), {} unchanged, {} duplicate, {} removed, {} failed",
+            root.display(),
+            report.indexed.len(),
+            report.chunks,
+            report.unchanged,
+            report.duplicates.len(),
+            report.removed.len(),
+            report.failed.len()
+        );
+        Ok(report)
+    }
+
+    /// 导入目录后持续监视，变化的文件自动重新索引（需在 tokio 运行时内调用）
+    pub async fn watch_dir(self: &Arc<Self>, dir: impl AsRef<Path>, config: IngestConfig) -> Result<(IngestReport, IngestWatcher)> {
+        let report = self.ingest_dir(dir.as_ref(), &config).await?;
+        let watcher = IngestWatcher::start(Arc::clone(self), dir, config)?;
+        Ok((report, watcher))
+    }
+
+    /// 导入单个文件，结果记入报告
+    async fn ingest_path(&self, root: &Path, path: &Path, report: &mut IngestReport) {
+        let document_id = document_id(root, path);
+        match self.ingest_file(root, path, &document_id).await {
+            Ok(IngestOutcome::Indexed(chunks)) => {
+                report.indexed.push(document_id);
+                report.chunks += chunks;
+            }
+            Ok(IngestOutcome::Unchanged) => report.unchanged += 1,
+            Ok(IngestOutcome::Duplicate(original)) => {
+                debug!("⏭️  {} has the same content as {}", document_id, original);
+                report.duplicates.push((document_id, original));
+            }
+            Err(e) => {
+                warn!("⚠️  Failed to ingest {}: {:#}", path.display(), e);
+                report.failed.push((path.to_path_buf(), format!("{:#}", e)));
+            }
+        }
+    }
+
+    async fn ingest_file(&self, root: &Path, path: &Path, document_id: &str) -> Result<IngestOutcome> {
+        let bytes = tokio::fs::read(path)
+            .await
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let hash = content_hash(&bytes);
+
+        let existing = self.document(document_id).await;
+        if existing.as_ref().and_then(|doc| doc.metadata.get(CONTENT_HASH_KEY)) == Some(&hash) {
+            return Ok(IngestOutcome::Unchanged);
+        }
+        let original = self
+            .find_documents(CONTENT_HASH_KEY, &hash)
+            .await
+            .into_iter()
+            .find(|id| id != document_id);
+        if let Some(original) = original {
+            // 文件改成了与其他文档相同的内容，旧版本不再有效
+            if existing.is_some() {
+                self.delete_document(document_id).await?;
+            }
+            return Ok(IngestOutcome::Duplicate(original));
+        }
+
+        let doc_type = doc_type(path);
+        let content = if doc_type == "pdf" {
+            let text = extract_pdf_text(&bytes);
+            if text.trim().is_empty() {
+                bail!("No extractable text (scanned PDF or unsupported font encoding)");
+            }
+            text
+        } else {
+            String::from_utf8(bytes).map_err(|_| anyhow!("Not valid UTF-8 text"))?
+        };
+
+        let now = Utc::now();
+        let document = Document {
+            document_id: document_id.to_string(),
+            title: document_id.to_string(),
+            content,
+            doc_type: doc_type.to_string(),
+            metadata: HashMap::from([
+                (SOURCE_PATH_KEY.to_string(), path.display().to_string()),
+                (SOURCE_ROOT_KEY.to_string(), root.display().to_string()),
+                (CONTENT_HASH_KEY.to_string(), hash),
+            ]),
+            created_at: existing.map(|doc| doc.created_at).unwrap_or(now),
+            updated_at: now,
+        };
+        let chunk_ids = self.index_document(document).await?;
+        Ok(IngestOutcome::Indexed(chunk_ids.len()))
+    }
+
+    /// 移除 `under`（根目录或其中的文件/子目录）下源文件已不存在或不再符合配置的文档
+    async fn remove_missing(&self, root: &Path, under: &Path, config: &IngestConfig, report: &mut IngestReport) -> Result<()> {
+        for document_id in self.find_documents(SOURCE_ROOT_KEY, &root.display().to_string()).await {
+            let Some(source) = self
+                .document(&document_id)
+                .await
+                .and_then(|doc| doc.metadata.get(SOURCE_PATH_KEY).map(PathBuf::from))
+            else {
+                continue;
+            };
+            if !source.starts_with(under) {
+                continue;
+            }
+            let relative = source.strip_prefix(root).unwrap_or(&source);
+            if !source.is_file() || !config.accepts(relative) {
+                self.delete_document(&document_id).await?;
+                report.removed.push(document_id);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 目录监视器：去抖后重新索引变化的文件、移除已删除的文件；drop 时停止
+pub struct IngestWatcher {
+    _watcher: RecommendedWatcher,
+    task: JoinHandle<()>,
+    reports: mpsc::UnboundedReceiver<IngestReport>,
+}
+
+impl IngestWatcher {
+    /// 开始监视目录（不做初次导入，见 `RagEngine::watch_dir`）
+    pub fn start(engine: Arc<RagEngine>, dir: impl AsRef<Path>, config: IngestConfig) -> Result<Self> {
+        let root = canonical_root(dir.as_ref())?;
+        let (tx, mut rx) = mpsc::unbounded_channel::<PathBuf>();
+        let mut watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| match result {
+            Ok(event) => {
+                for path in event.paths {
+                    let _ = tx.send(path);
+                }
+            }
+            Err(e) => warn!("⚠️  Ingest watcher error: {}", e),
+        })
+        .context("Failed to create ingest watcher")?;
+        watcher
+            .watch(&root, RecursiveMode::Recursive)
+            .with_context(|| format!("Failed to watch {}", root.display()))?;
+        info!("👀 Watching {} for document changes", root.display());
+
+        let (report_tx, reports) = mpsc::unbounded_channel();
+        let task = tokio::spawn(async move {
+            while let Some(first) = rx.recv().await {
+                tokio::time::sleep(INGEST_DEBOUNCE).await;
+                let mut changed = HashSet::from([first]);
+                while let Ok(path) = rx.try_recv() {
+                    changed.insert(path);
+                }
+
+                let mut changed: Vec<PathBuf> = changed.into_iter().collect();
+                changed.sort();
+                let report = match sync_paths(&engine, &root, &config, &changed).await {
+                    Ok(report) => report,
+                    Err(e) => {
+                        warn!("⚠️  Re-indexing {} failed: {:#}", root.display(), e);
+                        continue;
+                    }
+                };
+                if report.is_empty() {
+                    continue;
+                }
+                info!(
+                    "🔄 Re-indexed {}: {} indexed, {} removed, {} failed",
+                    root.display(),
+                    report.indexed.len(),
+                    report.removed.len(),
+                    report.failed.len()
+                );
+                let _ = report_tx.send(report);
+            }
+        });
+
+        Ok(Self { _watcher: watcher, task, reports })
+    }
+
+    /// 等待下一批变化的导入结果
+    pub async fn next_report(&mut self) -> Option<IngestReport> {
+        self.reports.recv().await
+    }
+}
+
+impl Drop for IngestWatcher {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// 按变化的路径同步索引：存在的文件重新导入，新目录整体导入，消失的路径移除对应文档
+async fn sync_paths(engine: &RagEngine, root: &Path, config: &IngestConfig, paths: &[PathBuf]) -> Result<IngestReport> {
+    let mut report = IngestReport::default();
+    for path in paths {
+        let Ok(relative) = path.strip_prefix(root) else {
+            continue;
+        };
+        if path.is_dir() {
+            for file in walk(path, config).await? {
+                if file.strip_prefix(root).is_ok_and(|relative| config.accepts(relative)) {
+                    engine.ingest_path(root, &file, &mut report).await;
+                }
+            }
+        } else if path.is_file() {
+            if config.accepts(relative) && file_size(path).await <= config.max_file_bytes {
+                engine.ingest_path(root, path, &mut report).await;
+            }
+        } else {
+            engine.remove_missing(root, path, config, &mut report).await?;
+        }
+    }
+    Ok(report)
+}
+
+/// 根目录取绝对路径，保证文档ID与监视事件中的路径一致
+fn canonical_root(dir: &Path) -> Result<PathBuf> {
+    let root = dir
+        .canonicalize()
+        .with_context(|| format!("Failed to open ingest directory {}", dir.display()))?;
+    if !root.is_dir() {
+        bail!("{} is not a directory", root.display());
+    }
+    Ok(root)
+}
+
+/// 递归列出目录下参与导入的文件
+async fn walk(dir: &Path, config: &IngestConfig) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut stack = vec![dir.to_path_buf()];
+
+    while let Some(dir) = stack.pop() {
+        let mut entries = tokio::fs::read_dir(&dir)
+            .await
+            .with_context(|| format!("Failed to read {:?}", dir))?;
+
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let file_type = entry.file_type().await?;
+            let name = entry.file_name().to_string_lossy().to_string();
+
+            if file_type.is_dir() {
+                if !config.exclude_dirs.contains(&name) {
+                    stack.push(path);
+                }
+            } else if file_type.is_file()
+                && config.accepts(Path::new(&name))
+                && entry.metadata().await.map(|m| m.len()).unwrap_or(0) <= config.max_file_bytes
+            {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+async fn file_size(path: &Path) -> u64 {
+    tokio::fs::metadata(path).await.map(|m| m.len()).unwrap_or(u64::MAX)
+}
+
+/// 文档ID：`<根目录名>/<相对路径>`（路径分隔符统一为 `/`）
+fn document_id(root: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    let mut parts: Vec<String> = root.file_name().map(|name| name.to_string_lossy().to_string()).into_iter().collect();
+    parts.extend(relative.components().map(|component| component.as_os_str().to_string_lossy().to_string()));
+    parts.join("/")
+}
+
+/// 文档类型（markdown/pdf/txt/code）
+fn doc_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "md" | "markdown" => "markdown",
+        "pdf" => "pdf",
+        "txt" | "rst" => "txt",
+        _ => "code",
+    }
+}
+
+fn content_hash(bytes: &[u8]) -> String {
+    let digest = ring::digest::digest(&ring::digest::SHA256, bytes);
+    digest.as_ref().iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// 尽力提取 PDF 文本：解压每个内容流，收集 BT/ET 之间文本操作符的字符串参数。
+/// 字符串按 Latin-1 解码；扫描件或使用 CID 字体的 PDF 提取不到文本
+pub fn extract_pdf_text(bytes: &[u8]) -> String {
+    let mut text = String::new();
+    let mut offset = 0;
+    while let Some(found) = find(&bytes[offset..], b"stream") {
+        let keyword = offset + found;
+        offset = keyword + b"stream".len();
+        if bytes[..keyword].ends_with(b"end") {
+            continue;
+        }
+
+        let mut start = offset;
+        if bytes.get(start) == Some(&b'\r') {
+            start += 1;
+        }
+        if bytes.get(start) == Some(&b'\n') {
+            start += 1;
+        }
+        let Some(length) = find(&bytes[start..], b"endstream") else {
+            break;
+        };
+        let raw = &bytes[start..start + length];
+        offset = start + length + b"endstream".len();
+
+        let mut inflated = Vec::new();
+        let content = match ZlibDecoder::new(raw).read_to_end(&mut inflated) {
+            Ok(_) => inflated.as_slice(),
+            Err(_) => raw,
+        };
+        let stream_text = content_stream_text(content);
+        if !stream_text.trim().is_empty() {
+            text.push_str(stream_text.trim());
+            text.push_str("\n\n");
+        }
+    }
+    text.trim_end().to_string()
+}
+
+/// 内容流中的文本：`(...)` 字符串参数，换行操作符（Td/TD/T*/'/"/ET）转为换行，
+/// TJ 数组中较大的负间距视为空格
+fn content_stream_text(content: &[u8]) -> String {
+    let mut out = String::new();
+    let mut in_text = false;
+    let mut in_array = false;
+    let mut i = 0;
+
+    let newline = |out: &mut String| {
+        if !out.is_empty() && !out.ends_with('\n') {
+            out.push('\n');
+        }
+    };
+
+    while i < content.len() {
+        let byte = content[i];
+        match byte {
+            b'%' => {
+                while i < content.len() && content[i] != b'\n' && content[i] != b'\r' {
+                    i += 1;
+                }
+            }
+            b'(' => {
+                let (literal, next) = pdf_literal(content, i + 1);
+                if in_text {
+                    out.extend(literal.iter().map(|&b| b as char));
+                }
+                i = next;
+                continue;
+            }
+            b'/' => {
+                i += 1;
+                while i < content.len() && !is_pdf_delimiter(content[i]) {
+                    i += 1;
+                }
+                continue;
+            }
+            b'[' => in_array = true,
+            b']' => in_array = false,
+            b'-' | b'0'..=b'9' | b'.' => {
+                let start = i;
+                i += 1;
+                while i < content.len() && (content[i].is_ascii_digit() || content[i] == b'.') {
+                    i += 1;
+                }
+                let number = std::str::from_utf8(&content[start..i])
+                    .ok()
+                    .and_then(|s| s.parse::<f64>().ok());
+                if in_text && in_array && number.is_some_and(|n| n <= -200.0) && !out.ends_with([' ', '\n']) {
+                    out.push(' ');
+                }
+                continue;
+            }
+            b'\'' | b'"' if in_text => newline(&mut out),
+            b if b.is_ascii_alphabetic() => {
+                let start = i;
+                while i < content.len() && (content[i].is_ascii_alphabetic() || content[i] == b'*') {
+                    i += 1;
+                }
+                match &content[start..i] {
+                    b"BT" => in_text = true,
+                    b"ET" => {
+                        in_text = false;
+                        newline(&mut out);
+                    }
+                    b"Td" | b"TD" | b"T*" if in_text => newline(&mut out),
+                    _ => {}
+                }
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    out
+}
+
+/// 解析 `(` 之后的字面字符串（支持嵌套括号与转义），返回内容与结束位置
+fn pdf_literal(content: &[u8], mut i: usize) -> (Vec<u8>, usize) {
+    let mut literal = Vec::new();
+    let mut depth = 1;
+    while i < content.len() {
+        let byte = content[i];
+        i += 1;
+        match byte {
+            b'\\' if i < content.len() => {
+                let escaped = content[i];
+                i += 1;
+                match escaped {
+                    b'n' => literal.push(b'\n'),
+                    b'r' => literal.push(b'\r'),
+                    b't' => literal.push(b'\t'),
+                    b'b' | b'f' => {}
+                    b'0'..=b'7' => {
+                        let mut value = u32::from(escaped - b'0');
+                        for _ in 0..2 {
+                            match content.get(i) {
+                                Some(&digit @ b'0'..=b'7') => {
+                                    value = value * 8 + u32::from(digit - b'0');
+                                    i += 1;
+                                }
+                                _ => break,
+                            }
+                        }
+                        literal.push(value as u8);
+                    }
+                    b'\r' | b'\n' => {} // 续行
+                    other => literal.push(other),
+                }
+            }
+            b'(' => {
+                depth += 1;
+                literal.push(byte);
+            }
+            b')' => {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+                literal.push(byte);
+            }
+            _ => literal.push(byte),
+        }
+    }
+    (literal, i)
+}
+
+fn is_pdf_delimiter(byte: u8) -> bool {
+    byte.is_ascii_whitespace() || b"()<>[]{}/%".contains(&byte)
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::rag_engine::{EmbeddingModel, RagConfig, RetrievalMode};
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    fn engine() -> RagEngine {
+        RagEngine::new(RagConfig {
+            embedding_model: EmbeddingModel::Mock,
+            retrieval_mode: RetrievalMode::KeywordOnly,
+            min_similarity: 0.5,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_extract_pdf_text() {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"BT /F1 12 Tf 72 700 Td [(Compressed) -250 (stre) 20 (am)] TJ ET").unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let mut pdf = b"%PDF-1.4\n1 0 obj << /Length 60 >>\nstream\nBT /F1 12 Tf 72 712 Td (Hello \\(PDF\\) world) Tj T* (Second line) Tj ET\nendstream\nendobj\n".to_vec();
+        pdf.extend_from_slice(b"2 0 obj << /Filter /FlateDecode >>\nstream\r\n");
+        pdf.extend_from_slice(&compressed);
+        pdf.extend_from_slice(b"\r\nendstream\nendobj\n%%EOF");
+
+        assert_eq!(extract_pdf_text(&pdf), "Hello (PDF) world\nSecond line\n\nCompressed stream");
+        assert_eq!(extract_pdf_text(b"not a pdf"), "");
+    }
+
+    #[tokio::test]
+    async fn test_ingest_dir_is_incremental() {
+        let dir = tempfile::tempdir().unwrap();
+        let docs = dir.path().join("docs");
+        std::fs::create_dir_all(docs.join("ops")).unwrap();
+        std::fs::create_dir_all(docs.join("target")).unwrap();
+        std::fs::write(docs.join("guide.md"), "Rotate TLS certificates every quarter.").unwrap();
+        std::fs::write(docs.join("ops/copy.md"), "Rotate TLS certificates every quarter.").unwrap();
+        std::fs::write(docs.join("ops/backup.txt"), "Back up the database nightly.").unwrap();
+        std::fs::write(docs.join("target/build.txt"), "ignored").unwrap();
+        std::fs::write(docs.join("image.png"), [0u8, 1, 2]).unwrap();
+
+        let engine = engine();
+        let config = IngestConfig::default();
+        let report = engine.ingest_dir(&docs, &config).await.unwrap();
+        assert_eq!(report.indexed, vec!["docs/guide.md", "docs/ops/backup.txt"]);
+        assert_eq!(report.duplicates, vec![("docs/ops/copy.md".to_string(), "docs/guide.md".to_string())]);
+        assert!(report.failed.is_empty());
+
+        // 第二次导入：全部未变化
+        let report = engine.ingest_dir(&docs, &config).await.unwrap();
+        assert!(report.is_empty());
+        assert_eq!((report.unchanged, report.duplicates.len()), (2, 1));
+
+        // 修改一个文件、删除原件：只重新索引变化的文件，副本接替被删除的原件
+        std::fs::write(docs.join("ops/backup.txt"), "Back up the database hourly.").unwrap();
+        std::fs::remove_file(docs.join("guide.md")).unwrap();
+        let report = engine.ingest_dir(&docs, &config).await.unwrap();
+        assert_eq!(report.removed, vec!["docs/guide.md"]);
+        assert_eq!(report.indexed, vec!["docs/ops/backup.txt", "docs/ops/copy.md"]);
+        assert_eq!(report.unchanged, 0);
+
+        let context = engine.retrieve_context("database hourly").await.unwrap();
+        assert_eq!(context.citations[0].title, "docs/ops/backup.txt");
+        assert!(engine.document("docs/guide.md").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_watcher_reindexes_changed_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("notes.md"), "Deploy on Fridays.").unwrap();
+
+        let engine = Arc::new(engine());
+        let (report, mut watcher) = engine.watch_dir(dir.path(), IngestConfig::default()).await.unwrap();
+        assert_eq!(report.indexed.len(), 1);
+
+        std::fs::write(dir.path().join("notes.md"), "Never deploy on Fridays.").unwrap();
+        let report = tokio::time::timeout(Duration::from_secs(10), watcher.next_report())
+            .await
+            .expect("watcher did not report the change")
+            .unwrap();
+        assert_eq!(report.indexed.len(), 1);
+
+        let context = engine.retrieve_context("never deploy").await.unwrap();
+        assert!(context.text.contains("Never deploy on Fridays."));
+    }
+}
@@ -2,20 +2,27 @@
 // 防止API滥用和DDoS攻击
 //
 // 核心功能：
-// 1. Token Bucket算法
-// 2. IP/用户级别限流
+// 1. 三种策略：Token Bucket（令牌桶）、Fixed Window（固定窗口）、Sliding Window（滑动窗口日志）
+// 2. IP/用户/Provider 级别限流
 // 3. API端点级别限流
 // 4. 动态限流规则
 // 5. 限流统计和监控
+// 6. API 密钥级别限流（密钥可自带规则，否则使用 api_key_rule）
+// 7. 租户级别限流：同一部署中的多个团队各自的配额（工作区可自带规则，否则使用 tenant_rule）
+// 8. 计数存储可替换：默认进程内；分布式部署时改用 Redis（feature = "redis"），集群内共享同一份配额
 
 use anyhow::{anyhow, Result};
+use async_trait::async_trait;
 use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::{debug, info, warn};
 
+use super::config_manager::SectionConfigListener;
+use super::workspace::TenantId;
+
 /// 限流策略
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum RateLimitStrategy {
@@ -36,8 +43,29 @@ pub enum RateLimitLevel {
     User,
     /// API端点级别
     Endpoint,
+    /// 静态 API 密钥级别
+    ApiKey,
+    /// 租户（工作区）级别
+    Tenant,
     /// 全局级别
     Global,
+    /// 上游 Provider 级别（保护上游配额）
+    Provider,
+}
+
+impl RateLimitLevel {
+    /// 计数键前缀
+    fn key_prefix(&self) -> &'static str {
+        match self {
+            RateLimitLevel::IpAddress => "ip",
+            RateLimitLevel::User => "user",
+            RateLimitLevel::Endpoint => "endpoint",
+            RateLimitLevel::ApiKey => "api_key",
+            RateLimitLevel::Tenant => "tenant",
+            RateLimitLevel::Global => "global",
+            RateLimitLevel::Provider => "provider",
+        }
+    }
 }
 
 /// 限流规则
@@ -49,11 +77,11 @@ pub struct RateLimitRule {
     pub level: RateLimitLevel,
     /// 策略
     pub strategy: RateLimitStrategy,
-    /// 每秒请求数
+    /// 每秒请求数（窗口策略下每个窗口允许 requests_per_second × window_size_secs 次）
     pub requests_per_second: f64,
-    /// 桶容量（最大突发请求数）
+    /// 桶容量（最大突发请求数，仅令牌桶）
     pub bucket_capacity: u64,
-    /// 窗口大小（秒）
+    /// 窗口大小（秒，固定窗口 / 滑动窗口）
     pub window_size_secs: u64,
     /// 是否启用
     pub enabled: bool,
@@ -73,6 +101,17 @@ impl Default for RateLimitRule {
     }
 }
 
+impl RateLimitRule {
+    /// 窗口策略下每个窗口允许的请求数
+    pub fn window_limit(&self) -> u64 {
+        ((self.requests_per_second * self.window_size_secs as f64).round() as u64).max(1)
+    }
+
+    fn window_ms(&self) -> i64 {
+        (self.window_size_secs.max(1) * 1000) as i64
+    }
+}
+
 /// Token Bucket状态
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct TokenBucket {
@@ -96,8 +135,10 @@ impl TokenBucket {
         }
     }
 
-    fn refill(&mut self) {
-        let now = Utc::now();
+    fn refill(&mut self, now: DateTime<Utc>) {
+        if now <= self.last_update {
+            return;
+        }
         let elapsed = (now - self.last_update).num_milliseconds() as f64 / 1000.0;
         let new_tokens = elapsed * self.refill_rate;
 
@@ -105,8 +146,8 @@ impl TokenBucket {
         self.last_update = now;
     }
 
-    fn try_consume(&mut self, tokens: f64) -> bool {
-        self.refill();
+    fn try_consume(&mut self, tokens: f64, now: DateTime<Utc>) -> bool {
+        self.refill(now);
 
         if self.tokens >= tokens {
             self.tokens -= tokens;
@@ -119,6 +160,13 @@ impl TokenBucket {
     fn tokens_available(&self) -> f64 {
         self.tokens
     }
+
+    /// 规则热更新后同步容量和补充速率
+    fn apply_rule(&mut self, rule: &RateLimitRule) {
+        self.capacity = rule.bucket_capacity as f64;
+        self.refill_rate = rule.requests_per_second;
+        self.tokens = self.tokens.min(self.capacity);
+    }
 }
 
 /// 限流记录
@@ -162,6 +210,35 @@ pub struct RateLimiterConfig {
     pub user_rule: RateLimitRule,
     /// 端点级别规则
     pub endpoint_rules: HashMap<String, RateLimitRule>,
+    /// API 密钥级别的默认规则（密钥未单独配置时使用）
+    #[serde(default = "default_api_key_rule")]
+    pub api_key_rule: RateLimitRule,
+    /// Provider 级别规则（键为 Provider 名称，未配置的 Provider 不限流）
+    #[serde(default)]
+    pub provider_rules: HashMap<String, RateLimitRule>,
+    /// 租户级别的默认规则（工作区未单独配置时使用）
+    #[serde(default = "default_tenant_rule")]
+    pub tenant_rule: RateLimitRule,
+}
+
+fn default_api_key_rule() -> RateLimitRule {
+    RateLimitRule {
+        rule_id: "api_key".to_string(),
+        level: RateLimitLevel::ApiKey,
+        requests_per_second: 20.0,
+        bucket_capacity: 40,
+        ..Default::default()
+    }
+}
+
+fn default_tenant_rule() -> RateLimitRule {
+    RateLimitRule {
+        rule_id: "tenant".to_string(),
+        level: RateLimitLevel::Tenant,
+        requests_per_second: 200.0,
+        bucket_capacity: 400,
+        ..Default::default()
+    }
 }
 
 impl Default for RateLimiterConfig {
@@ -189,21 +266,323 @@ impl Default for RateLimiterConfig {
                 ..Default::default()
             },
             endpoint_rules: HashMap::new(),
+            api_key_rule: default_api_key_rule(),
+            provider_rules: HashMap::new(),
+            tenant_rule: default_tenant_rule(),
+        }
+    }
+}
+
+/// 限流计数存储
+#[async_trait]
+pub trait RateLimitStore: Send + Sync {
+    /// 存储名称
+    fn name(&self) -> &str;
+
+    /// 在 key 上按规则计一次请求
+    async fn hit(&self, key: &str, rule: &RateLimitRule, now: DateTime<Utc>) -> Result<RateLimitResult>;
+
+    /// 清除 key 的计数
+    async fn reset(&self, key: &str) -> Result<()>;
+}
+
+/// 单个键的计数状态
+enum CounterState {
+    Bucket(TokenBucket),
+    /// 固定窗口：(窗口起点毫秒, 窗口内请求数)
+    Fixed(i64, u64),
+    /// 滑动窗口日志：窗口内每次放行请求的毫秒时间戳
+    Log(VecDeque<i64>),
+}
+
+impl CounterState {
+    fn new(rule: &RateLimitRule, now: DateTime<Utc>) -> Self {
+        match rule.strategy {
+            RateLimitStrategy::TokenBucket => {
+                let mut bucket = TokenBucket::new(rule.bucket_capacity as f64, rule.requests_per_second);
+                bucket.last_update = now;
+                CounterState::Bucket(bucket)
+            }
+            RateLimitStrategy::FixedWindow => CounterState::Fixed(i64::MIN, 0),
+            RateLimitStrategy::SlidingWindow => CounterState::Log(VecDeque::new()),
+        }
+    }
+
+    fn matches(&self, strategy: RateLimitStrategy) -> bool {
+        matches!(
+            (self, strategy),
+            (CounterState::Bucket(_), RateLimitStrategy::TokenBucket)
+                | (CounterState::Fixed(..), RateLimitStrategy::FixedWindow)
+                | (CounterState::Log(_), RateLimitStrategy::SlidingWindow)
+        )
+    }
+
+    fn hit(&mut self, rule: &RateLimitRule, now: DateTime<Utc>) -> RateLimitResult {
+        let now_ms = now.timestamp_millis();
+        match self {
+            CounterState::Bucket(bucket) => {
+                bucket.apply_rule(rule);
+                let allowed = bucket.try_consume(1.0, now);
+                bucket_result(allowed, bucket.tokens_available(), rule, now)
+            }
+            CounterState::Fixed(window_start, count) => {
+                let start = now_ms - now_ms.rem_euclid(rule.window_ms());
+                if *window_start != start {
+                    *window_start = start;
+                    *count = 0;
+                }
+                *count += 1;
+                fixed_window_result(*count, start, rule, now)
+            }
+            CounterState::Log(log) => {
+                while log.front().is_some_and(|&t| t <= now_ms - rule.window_ms()) {
+                    log.pop_front();
+                }
+                let allowed = (log.len() as u64) < rule.window_limit();
+                if allowed {
+                    log.push_back(now_ms);
+                }
+                let oldest = log.front().copied().unwrap_or(now_ms);
+                sliding_window_result(allowed, log.len() as u64, oldest, rule, now)
+            }
         }
     }
 }
 
+/// 令牌桶结果：被拒绝时按补充速率计算还需等待多久
+fn bucket_result(allowed: bool, tokens: f64, rule: &RateLimitRule, now: DateTime<Utc>) -> RateLimitResult {
+    let rate = rule.requests_per_second.max(f64::EPSILON);
+    let until_full_ms = ((rule.bucket_capacity as f64 - tokens).max(0.0) / rate * 1000.0).min(86_400_000.0);
+    RateLimitResult {
+        allowed,
+        remaining: tokens.max(0.0) as u64,
+        reset_at: now + Duration::milliseconds(until_full_ms.ceil() as i64),
+        retry_after_secs: (!allowed).then(|| ((1.0 - tokens) / rate).ceil().clamp(1.0, 86_400.0) as u64),
+    }
+}
+
+/// 固定窗口结果：count 为本窗口内的请求数（含被拒绝的请求）
+fn fixed_window_result(count: u64, window_start_ms: i64, rule: &RateLimitRule, now: DateTime<Utc>) -> RateLimitResult {
+    let allowed = count <= rule.window_limit();
+    let reset_ms = window_start_ms + rule.window_ms();
+    RateLimitResult {
+        allowed,
+        remaining: rule.window_limit().saturating_sub(count),
+        reset_at: DateTime::from_timestamp_millis(reset_ms).unwrap_or(now),
+        retry_after_secs: (!allowed).then(|| seconds_until(reset_ms, now)),
+    }
+}
+
+/// 滑动窗口结果：count 为窗口内已放行的请求数，oldest_ms 为其中最早一次
+fn sliding_window_result(
+    allowed: bool,
+    count: u64,
+    oldest_ms: i64,
+    rule: &RateLimitRule,
+    now: DateTime<Utc>,
+) -> RateLimitResult {
+    let reset_ms = oldest_ms + rule.window_ms();
+    RateLimitResult {
+        allowed,
+        remaining: rule.window_limit().saturating_sub(count),
+        reset_at: DateTime::from_timestamp_millis(reset_ms).unwrap_or(now),
+        retry_after_secs: (!allowed).then(|| seconds_until(reset_ms, now)),
+    }
+}
+
+fn seconds_until(target_ms: i64, now: DateTime<Utc>) -> u64 {
+    let wait_ms = (target_ms - now.timestamp_millis()).max(0);
+    ((wait_ms as f64 / 1000.0).ceil() as u64).max(1)
+}
+
+fn unlimited() -> RateLimitResult {
+    RateLimitResult {
+        allowed: true,
+        remaining: u64::MAX,
+        reset_at: Utc::now() + Duration::hours(1),
+        retry_after_secs: None,
+    }
+}
+
+/// 进程内计数（单节点部署与测试）
+#[derive(Default)]
+pub struct MemoryRateLimitStore {
+    counters: std::sync::Mutex<HashMap<String, CounterState>>,
+}
+
+impl MemoryRateLimitStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for MemoryRateLimitStore {
+    fn name(&self) -> &str {
+        "memory"
+    }
+
+    async fn hit(&self, key: &str, rule: &RateLimitRule, now: DateTime<Utc>) -> Result<RateLimitResult> {
+        let mut counters = self.counters.lock().unwrap_or_else(|e| e.into_inner());
+        let state = counters
+            .entry(key.to_string())
+            .or_insert_with(|| CounterState::new(rule, now));
+        // 规则热更新换了策略时重新计数
+        if !state.matches(rule.strategy) {
+            *state = CounterState::new(rule, now);
+        }
+        Ok(state.hit(rule, now))
+    }
+
+    async fn reset(&self, key: &str) -> Result<()> {
+        self.counters.lock().unwrap_or_else(|e| e.into_inner()).remove(key);
+        Ok(())
+    }
+}
+
+/// Redis 计数（集群共享配额，每种策略一段 Lua 脚本保证原子性）
+#[cfg(feature = "redis")]
+pub struct RedisRateLimitStore {
+    client: super::redis_client::RedisClient,
+    /// 键前缀（多个部署共用一个 Redis 时区分）
+    prefix: String,
+}
+
+#[cfg(feature = "redis")]
+impl RedisRateLimitStore {
+    const TOKEN_BUCKET_SCRIPT: &'static str = r#"
+        local capacity = tonumber(ARGV[1])
+        local rate = tonumber(ARGV[2])
+        local now = tonumber(ARGV[3])
+        local state = redis.call('HMGET', KEYS[1], 'tokens', 'ts')
+        local tokens = tonumber(state[1]) or capacity
+        local ts = tonumber(state[2]) or now
+        if now > ts then
+            tokens = tokens + (now - ts) / 1000 * rate
+            ts = now
+        end
+        tokens = math.min(tokens, capacity)
+        local allowed = 0
+        if tokens >= 1 then
+            tokens = tokens - 1
+            allowed = 1
+        end
+        redis.call('HSET', KEYS[1], 'tokens', tostring(tokens), 'ts', tostring(ts))
+        redis.call('PEXPIRE', KEYS[1], math.max(1000, math.ceil(capacity / math.max(rate, 0.001) * 1000)))
+        return {allowed, tostring(tokens)}
+    "#;
+
+    const FIXED_WINDOW_SCRIPT: &'static str = r#"
+        local count = redis.call('INCR', KEYS[1])
+        if count == 1 then
+            redis.call('PEXPIRE', KEYS[1], ARGV[1])
+        end
+        return count
+    "#;
+
+    const SLIDING_WINDOW_SCRIPT: &'static str = r#"
+        local window = tonumber(ARGV[1])
+        local limit = tonumber(ARGV[2])
+        local now = tonumber(ARGV[3])
+        redis.call('ZREMRANGEBYSCORE', KEYS[1], '-inf', now - window)
+        local count = redis.call('ZCARD', KEYS[1])
+        local allowed = 0
+        if count < limit then
+            redis.call('ZADD', KEYS[1], now, ARGV[4])
+            count = count + 1
+            allowed = 1
+        end
+        redis.call('PEXPIRE', KEYS[1], window)
+        local oldest = redis.call('ZRANGE', KEYS[1], 0, 0, 'WITHSCORES')
+        return {allowed, count, oldest[2] or tostring(now)}
+    "#;
+
+    /// 连接 Redis（`redis://[:password@]host[:port][/db]`）
+    pub fn connect(url: &str, prefix: impl Into<String>) -> Result<Self> {
+        Ok(Self {
+            client: super::redis_client::RedisClient::open(url)?,
+            prefix: prefix.into(),
+        })
+    }
+
+    fn key(&self, key: &str) -> String {
+        format!("{}:{}", self.prefix, key)
+    }
+}
+
+#[cfg(feature = "redis")]
+#[async_trait]
+impl RateLimitStore for RedisRateLimitStore {
+    fn name(&self) -> &str {
+        "redis"
+    }
+
+    async fn hit(&self, key: &str, rule: &RateLimitRule, now: DateTime<Utc>) -> Result<RateLimitResult> {
+        let now_ms = now.timestamp_millis();
+        match rule.strategy {
+            RateLimitStrategy::TokenBucket => {
+                let args = [rule.bucket_capacity.to_string(), rule.requests_per_second.to_string(), now_ms.to_string()];
+                let reply = self
+                    .client
+                    .eval(Self::TOKEN_BUCKET_SCRIPT, &[&self.key(key)], &args)
+                    .await?
+                    .into_array();
+                let allowed = reply.first().and_then(|v| v.as_i64()) == Some(1);
+                let tokens = reply
+                    .get(1)
+                    .cloned()
+                    .and_then(|v| v.into_string())
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(0.0);
+                Ok(bucket_result(allowed, tokens, rule, now))
+            }
+            RateLimitStrategy::FixedWindow => {
+                let start = now_ms - now_ms.rem_euclid(rule.window_ms());
+                let window_key = format!("{}:{}", self.key(key), start);
+                let count = self
+                    .client
+                    .eval(Self::FIXED_WINDOW_SCRIPT, &[&window_key], &[rule.window_ms().to_string()])
+                    .await?
+                    .as_i64()
+                    .ok_or_else(|| anyhow!("Unexpected Redis reply for fixed window"))?;
+                Ok(fixed_window_result(count.max(0) as u64, start, rule, now))
+            }
+            RateLimitStrategy::SlidingWindow => {
+                // 同一毫秒内的多次请求需要不同的成员名
+                let mut nonce = [0u8; 8];
+                ring::rand::SecureRandom::fill(&ring::rand::SystemRandom::new(), &mut nonce)
+                    .map_err(|_| anyhow!("Failed to generate rate limit nonce"))?;
+                let member = format!("{}-{}", now_ms, super::auth_system::hex_encode(&nonce));
+                let args = [rule.window_ms().to_string(), rule.window_limit().to_string(), now_ms.to_string(), member];
+                let reply = self
+                    .client
+                    .eval(Self::SLIDING_WINDOW_SCRIPT, &[&self.key(key)], &args)
+                    .await?
+                    .into_array();
+                let number = |i: usize| reply.get(i).and_then(|v| v.as_i64());
+                Ok(sliding_window_result(
+                    number(0) == Some(1),
+                    number(1).unwrap_or(0).max(0) as u64,
+                    number(2).unwrap_or(now_ms),
+                    rule,
+                    now,
+                ))
+            }
+        }
+    }
+
+    async fn reset(&self, key: &str) -> Result<()> {
+        self.client.command(&["DEL", self.key(key).as_str()]).await?;
+        Ok(())
+    }
+}
+
 /// 速率限制器
 pub struct RateLimiter {
-    config: RateLimiterConfig,
-    /// IP级别的桶
-    ip_buckets: Arc<RwLock<HashMap<String, TokenBucket>>>,
-    /// 用户级别的桶
-    user_buckets: Arc<RwLock<HashMap<String, TokenBucket>>>,
-    /// 端点级别的桶
-    endpoint_buckets: Arc<RwLock<HashMap<String, TokenBucket>>>,
-    /// 全局桶
-    global_bucket: Arc<RwLock<TokenBucket>>,
+    /// 共享配置（可通过 ConfigManager 的 `rate_limiter.*` 热更新）
+    config: Arc<RwLock<RateLimiterConfig>>,
+    /// 计数存储（默认进程内，分布式部署时为 Redis）
+    store: Arc<dyn RateLimitStore>,
     /// 限流记录
     records: Arc<RwLock<HashMap<String, RateLimitRecord>>>,
 }
@@ -216,107 +595,91 @@ impl RateLimiter {
         info!("    IP: {:.1} req/s", config.ip_rule.requests_per_second);
         info!("    User: {:.1} req/s", config.user_rule.requests_per_second);
 
-        let global_bucket = TokenBucket::new(
-            config.global_rule.bucket_capacity as f64,
-            config.global_rule.requests_per_second,
-        );
-
         Self {
-            config,
-            ip_buckets: Arc::new(RwLock::new(HashMap::new())),
-            user_buckets: Arc::new(RwLock::new(HashMap::new())),
-            endpoint_buckets: Arc::new(RwLock::new(HashMap::new())),
-            global_bucket: Arc::new(RwLock::new(global_bucket)),
+            config: Arc::new(RwLock::new(config)),
+            store: Arc::new(MemoryRateLimitStore::new()),
             records: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
+    /// 更换计数存储（分布式部署时传入 RedisRateLimitStore，限额在集群内共享）
+    pub fn with_store(mut self, store: Arc<dyn RateLimitStore>) -> Self {
+        info!("🚦 Rate limit counters stored in {}", store.name());
+        self.store = store;
+        self
+    }
+
     /// 检查IP是否被限流
     pub async fn check_ip(&self, ip: &str) -> Result<RateLimitResult> {
-        if !self.config.ip_rule.enabled {
-            return Ok(RateLimitResult {
-                allowed: true,
-                remaining: u64::MAX,
-                reset_at: Utc::now() + Duration::hours(1),
-                retry_after_secs: None,
-            });
-        }
-
-        self.check_bucket(
-            ip,
-            &self.ip_buckets,
-            &self.config.ip_rule,
-            RateLimitLevel::IpAddress,
-        )
-        .await
+        let rule = self.config.read().await.ip_rule.clone();
+        self.check_rule(ip, &rule, RateLimitLevel::IpAddress).await
     }
 
     /// 检查用户是否被限流
     pub async fn check_user(&self, user_id: &str) -> Result<RateLimitResult> {
-        if !self.config.user_rule.enabled {
-            return Ok(RateLimitResult {
-                allowed: true,
-                remaining: u64::MAX,
-                reset_at: Utc::now() + Duration::hours(1),
-                retry_after_secs: None,
-            });
-        }
+        let rule = self.config.read().await.user_rule.clone();
+        self.check_rule(user_id, &rule, RateLimitLevel::User).await
+    }
 
-        self.check_bucket(
-            user_id,
-            &self.user_buckets,
-            &self.config.user_rule,
-            RateLimitLevel::User,
-        )
-        .await
+    /// 检查上游 Provider 是否被限流（未配置规则的 Provider 不限流）
+    pub async fn check_provider(&self, provider: &str) -> Result<RateLimitResult> {
+        let rule = self.config.read().await.provider_rules.get(provider).cloned();
+        let Some(rule) = rule else {
+            return Ok(unlimited());
+        };
+        let result = self.check_rule(provider, &rule, RateLimitLevel::Provider).await?;
+        if !result.allowed {
+            warn!("🚫 Provider rate limit exceeded: {}", provider);
+        }
+        Ok(result)
     }
 
     /// 检查端点是否被限流
     pub async fn check_endpoint(&self, endpoint: &str, identifier: &str) -> Result<RateLimitResult> {
-        if let Some(rule) = self.config.endpoint_rules.get(endpoint) {
-            if !rule.enabled {
-                return Ok(RateLimitResult {
-                    allowed: true,
-                    remaining: u64::MAX,
-                    reset_at: Utc::now() + Duration::hours(1),
-                    retry_after_secs: None,
-                });
+        let rule = self.config.read().await.endpoint_rules.get(endpoint).cloned();
+        match rule {
+            Some(rule) => {
+                let key = format!("{}:{}", endpoint, identifier);
+                self.check_rule(&key, &rule, RateLimitLevel::Endpoint).await
             }
-
-            let key = format!("{}:{}", endpoint, identifier);
-            self.check_bucket(&key, &self.endpoint_buckets, rule, RateLimitLevel::Endpoint)
-                .await
-        } else {
             // 无特定规则，使用全局规则
-            Ok(RateLimitResult {
-                allowed: true,
-                remaining: u64::MAX,
-                reset_at: Utc::now() + Duration::hours(1),
-                retry_after_secs: None,
-            })
+            None => Ok(unlimited()),
         }
     }
 
-    /// 检查全局限流
-    pub async fn check_global(&self) -> Result<RateLimitResult> {
-        if !self.config.global_rule.enabled {
-            return Ok(RateLimitResult {
-                allowed: true,
-                remaining: u64::MAX,
-                reset_at: Utc::now() + Duration::hours(1),
-                retry_after_secs: None,
-            });
+    /// 检查 API 密钥是否被限流（`rule` 为密钥自带的规则，为空时使用 api_key_rule）
+    pub async fn check_api_key(&self, key_id: &str, rule: Option<&RateLimitRule>) -> Result<RateLimitResult> {
+        let rule = match rule {
+            Some(rule) => rule.clone(),
+            None => self.config.read().await.api_key_rule.clone(),
+        };
+        let result = self.check_rule(key_id, &rule, RateLimitLevel::ApiKey).await?;
+        if !result.allowed {
+            warn!("🚫 API key rate limit exceeded: {}", key_id);
         }
+        Ok(result)
+    }
 
-        let mut bucket = self.global_bucket.write().await;
-        let allowed = bucket.try_consume(1.0);
+    /// 检查租户是否被限流（`rule` 为工作区自带的规则，为空时使用 tenant_rule）
+    pub async fn check_tenant(&self, tenant: &TenantId, rule: Option<&RateLimitRule>) -> Result<RateLimitResult> {
+        let rule = match rule {
+            Some(rule) => rule.clone(),
+            None => self.config.read().await.tenant_rule.clone(),
+        };
+        let result = self.check_rule(tenant.as_str(), &rule, RateLimitLevel::Tenant).await?;
+        if !result.allowed {
+            warn!("🚫 Tenant rate limit exceeded: {}", tenant);
+        }
+        Ok(result)
+    }
 
-        Ok(RateLimitResult {
-            allowed,
-            remaining: bucket.tokens_available() as u64,
-            reset_at: Utc::now() + Duration::seconds(self.config.global_rule.window_size_secs as i64),
-            retry_after_secs: if !allowed { Some(1) } else { None },
-        })
+    /// 检查全局限流
+    pub async fn check_global(&self) -> Result<RateLimitResult> {
+        let rule = self.config.read().await.global_rule.clone();
+        if !rule.enabled {
+            return Ok(unlimited());
+        }
+        self.store.hit(RateLimitLevel::Global.key_prefix(), &rule, Utc::now()).await
     }
 
     /// 综合检查（检查所有级别）
@@ -367,6 +730,22 @@ impl RateLimiter {
         })
     }
 
+    /// 当前配置快照
+    pub async fn config(&self) -> RateLimiterConfig {
+        self.config.read().await.clone()
+    }
+
+    /// 替换配置（已有计数在下次访问时同步新规则）
+    pub async fn update_config(&self, config: RateLimiterConfig) {
+        *self.config.write().await = config;
+        info!("🔄 Rate limiter config updated");
+    }
+
+    /// 创建配置监听器，注册到 ConfigManager 后 `rate_limiter.*` 的变更会即时生效
+    pub fn config_listener(&self) -> SectionConfigListener<RateLimiterConfig> {
+        SectionConfigListener::new("rate_limiter", self.config.clone())
+    }
+
     /// 获取限流统计
     pub async fn get_stats(&self, identifier: &str) -> Option<RateLimitRecord> {
         let records = self.records.read().await;
@@ -375,27 +754,11 @@ impl RateLimiter {
 
     /// 重置限流计数
     pub async fn reset(&self, identifier: &str, level: RateLimitLevel) -> Result<()> {
-        match level {
-            RateLimitLevel::IpAddress => {
-                let mut buckets = self.ip_buckets.write().await;
-                buckets.remove(identifier);
-            }
-            RateLimitLevel::User => {
-                let mut buckets = self.user_buckets.write().await;
-                buckets.remove(identifier);
-            }
-            RateLimitLevel::Endpoint => {
-                let mut buckets = self.endpoint_buckets.write().await;
-                buckets.remove(identifier);
-            }
-            RateLimitLevel::Global => {
-                let mut bucket = self.global_bucket.write().await;
-                *bucket = TokenBucket::new(
-                    self.config.global_rule.bucket_capacity as f64,
-                    self.config.global_rule.requests_per_second,
-                );
-            }
-        }
+        let key = match level {
+            RateLimitLevel::Global => level.key_prefix().to_string(),
+            _ => format!("{}:{}", level.key_prefix(), identifier),
+        };
+        self.store.reset(&key).await?;
 
         info!("🔄 Rate limit reset: {} ({:?})", identifier, level);
         Ok(())
@@ -403,34 +766,18 @@ impl RateLimiter {
 
     // ===== 内部辅助方法 =====
 
-    async fn check_bucket(
-        &self,
-        identifier: &str,
-        buckets: &Arc<RwLock<HashMap<String, TokenBucket>>>,
-        rule: &RateLimitRule,
-        level: RateLimitLevel,
-    ) -> Result<RateLimitResult> {
-        let mut buckets_map = buckets.write().await;
-
-        let bucket = buckets_map
-            .entry(identifier.to_string())
-            .or_insert_with(|| TokenBucket::new(rule.bucket_capacity as f64, rule.requests_per_second));
+    async fn check_rule(&self, identifier: &str, rule: &RateLimitRule, level: RateLimitLevel) -> Result<RateLimitResult> {
+        if !rule.enabled {
+            return Ok(unlimited());
+        }
 
-        let allowed = bucket.try_consume(1.0);
+        let key = format!("{}:{}", level.key_prefix(), identifier);
+        let result = self.store.hit(&key, rule, Utc::now()).await?;
+        debug!("🚦 {} -> allowed={} remaining={}", key, result.allowed, result.remaining);
 
         // 更新记录
-        self.update_record(identifier, level, allowed).await;
-
-        Ok(RateLimitResult {
-            allowed,
-            remaining: bucket.tokens_available() as u64,
-            reset_at: Utc::now() + Duration::seconds(rule.window_size_secs as i64),
-            retry_after_secs: if !allowed {
-                Some((1.0 / rule.requests_per_second) as u64)
-            } else {
-                None
-            },
-        })
+        self.update_record(identifier, level, result.allowed).await;
+        Ok(result)
     }
 
     async fn update_record(&self, identifier: &str, level: RateLimitLevel, allowed: bool) {
@@ -464,11 +811,11 @@ mod tests {
         let mut bucket = TokenBucket::new(10.0, 1.0);
 
         // 消耗5个令牌
-        assert!(bucket.try_consume(5.0));
+        assert!(bucket.try_consume(5.0, Utc::now()));
         assert!(bucket.tokens_available() >= 4.9 && bucket.tokens_available() <= 5.1);
 
         // 尝试消耗10个令牌（应该失败）
-        assert!(!bucket.try_consume(10.0));
+        assert!(!bucket.try_consume(10.0, Utc::now()));
     }
 
     #[tokio::test]
@@ -479,4 +826,99 @@ mod tests {
         let result = limiter.check_ip("192.168.1.1").await.unwrap();
         assert!(result.allowed);
     }
+
+    #[tokio::test]
+    async fn test_window_strategies() {
+        let store = MemoryRateLimitStore::new();
+        let rule = |strategy| RateLimitRule {
+            strategy,
+            requests_per_second: 0.05,
+            window_size_secs: 60,
+            ..Default::default()
+        };
+        let (fixed, sliding) = (rule(RateLimitStrategy::FixedWindow), rule(RateLimitStrategy::SlidingWindow));
+        assert_eq!(fixed.window_limit(), 3);
+        let at = |secs: i64| DateTime::from_timestamp(1_700_000_040 + secs, 0).unwrap();
+
+        // 固定窗口在整分钟边界清零：窗口末尾的突发与下一窗口开头的请求都会放行
+        for secs in [50, 51, 52] {
+            assert!(store.hit("f", &fixed, at(secs)).await.unwrap().allowed);
+        }
+        let denied = store.hit("f", &fixed, at(53)).await.unwrap();
+        assert!(!denied.allowed);
+        assert_eq!(denied.retry_after_secs, Some(7));
+        assert!(store.hit("f", &fixed, at(60)).await.unwrap().allowed);
+
+        // 滑动窗口按最近 60 秒计数，不受边界影响
+        for secs in [50, 51, 52] {
+            assert!(store.hit("s", &sliding, at(secs)).await.unwrap().allowed);
+        }
+        let denied = store.hit("s", &sliding, at(60)).await.unwrap();
+        assert!(!denied.allowed);
+        assert_eq!(denied.retry_after_secs, Some(50));
+        assert!(store.hit("s", &sliding, at(111)).await.unwrap().allowed);
+        assert_eq!(store.hit("s", &sliding, at(111)).await.unwrap().remaining, 0);
+    }
+
+    #[tokio::test]
+    async fn test_api_key_rule_override() {
+        let limiter = RateLimiter::new(RateLimiterConfig::default());
+        let strict = RateLimitRule {
+            rule_id: "key_strict".to_string(),
+            level: RateLimitLevel::ApiKey,
+            requests_per_second: 0.001,
+            bucket_capacity: 2,
+            ..Default::default()
+        };
+
+        assert!(limiter.check_api_key("k1", Some(&strict)).await.unwrap().allowed);
+        assert!(limiter.check_api_key("k1", Some(&strict)).await.unwrap().allowed);
+        assert!(!limiter.check_api_key("k1", Some(&strict)).await.unwrap().allowed);
+        // 其他密钥使用默认规则，互不影响
+        assert!(limiter.check_api_key("k2", None).await.unwrap().allowed);
+        assert_eq!(limiter.get_stats("k1").await.unwrap().throttled_requests, 1);
+    }
+
+    #[tokio::test]
+    async fn test_tenant_quotas_are_isolated() {
+        let limiter = RateLimiter::new(RateLimiterConfig::default());
+        let small = RateLimitRule {
+            rule_id: "team_a".to_string(),
+            level: RateLimitLevel::Tenant,
+            requests_per_second: 0.001,
+            bucket_capacity: 1,
+            ..Default::default()
+        };
+        let (team_a, team_b) = (TenantId::new("team-a"), TenantId::new("team-b"));
+
+        assert!(limiter.check_tenant(&team_a, Some(&small)).await.unwrap().allowed);
+        assert!(!limiter.check_tenant(&team_a, Some(&small)).await.unwrap().allowed);
+        // 一个租户耗尽配额不影响其他租户
+        assert!(limiter.check_tenant(&team_b, None).await.unwrap().allowed);
+        assert!(limiter.check_tenant(&TenantId::default(), None).await.unwrap().allowed);
+    }
+
+    #[tokio::test]
+    async fn test_config_hot_reload() {
+        use crate::core::config_manager::{ConfigManager, ConfigManagerConfig, ConfigValue};
+
+        let limiter = RateLimiter::new(RateLimiterConfig::default());
+        let manager = ConfigManager::new(ConfigManagerConfig::default());
+        manager.register_listener(Box::new(limiter.config_listener())).await;
+
+        manager
+            .set(
+                "rate_limiter.ip_rule.enabled".to_string(),
+                ConfigValue::Boolean(false),
+                false,
+                true,
+                "ops".to_string(),
+            )
+            .await
+            .unwrap();
+
+        assert!(!limiter.config().await.ip_rule.enabled);
+        let result = limiter.check_ip("10.0.0.1").await.unwrap();
+        assert_eq!(result.remaining, u64::MAX);
+    }
 }
@@ -0,0 +1,303 @@
+// Redis Client - 最小 RESP2 客户端（feature = "redis"）
+// 分布式限流、锁与服务发现只需要少量命令和 Lua 脚本，不引入完整的 redis crate
+//
+// 核心功能：
+// 1. redis://[:password@]host[:port][/db] 连接串解析，AUTH / SELECT
+// 2. 单连接串行执行命令，IO 出错后丢弃连接、下次调用时重连
+// 3. EVAL 执行 Lua 脚本（保证多条命令的原子性）
+
+use anyhow::{anyhow, Context, Result};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+use tracing::debug;
+
+/// 连接与单条命令的超时
+const IO_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// RESP2 值
+#[derive(Debug, Clone, PartialEq)]
+pub enum RespValue {
+    Simple(String),
+    Error(String),
+    Integer(i64),
+    Bulk(Option<Vec<u8>>),
+    Array(Option<Vec<RespValue>>),
+}
+
+impl RespValue {
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            RespValue::Integer(n) => Some(*n),
+            RespValue::Bulk(Some(bytes)) => std::str::from_utf8(bytes).ok()?.parse().ok(),
+            RespValue::Simple(s) => s.parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn into_string(self) -> Option<String> {
+        match self {
+            RespValue::Simple(s) => Some(s),
+            RespValue::Bulk(Some(bytes)) => String::from_utf8(bytes).ok(),
+            RespValue::Integer(n) => Some(n.to_string()),
+            _ => None,
+        }
+    }
+
+    pub fn into_array(self) -> Vec<RespValue> {
+        match self {
+            RespValue::Array(Some(items)) => items,
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// 连接参数
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedisEndpoint {
+    pub host: String,
+    pub port: u16,
+    pub password: Option<String>,
+    pub db: u32,
+}
+
+impl RedisEndpoint {
+    /// 解析 `redis://[:password@]host[:port][/db]`
+    pub fn parse(url: &str) -> Result<Self> {
+        let rest = url
+            .strip_prefix("redis://")
+            .ok_or_else(|| anyhow!("Unsupported Redis URL (expected redis://): {}", url))?;
+        let (auth, rest) = match rest.rsplit_once('@') {
+            Some((auth, rest)) => (Some(auth), rest),
+            None => (None, rest),
+        };
+        let (address, db) = match rest.split_once('/') {
+            Some((address, db)) if !db.is_empty() => (address, db.parse().context("Invalid Redis db index")?),
+            Some((address, _)) => (address, 0),
+            None => (rest, 0),
+        };
+        let (host, port) = match address.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().context("Invalid Redis port")?),
+            None => (address, 6379),
+        };
+        if host.is_empty() {
+            return Err(anyhow!("Redis URL has no host: {}", url));
+        }
+        // `:password@` 或 `user:password@`（ACL 用户名忽略）
+        let password = auth
+            .map(|auth| auth.rsplit_once(':').map(|(_, password)| password).unwrap_or(auth))
+            .filter(|password| !password.is_empty())
+            .map(str::to_string);
+
+        Ok(Self { host: host.to_string(), port, password, db })
+    }
+}
+
+/// Redis 客户端
+pub struct RedisClient {
+    endpoint: RedisEndpoint,
+    connection: Mutex<Option<BufReader<TcpStream>>>,
+}
+
+impl RedisClient {
+    /// 按连接串创建客户端（首次执行命令时才建立连接）
+    pub fn open(url: &str) -> Result<Self> {
+        Ok(Self {
+            endpoint: RedisEndpoint::parse(url)?,
+            connection: Mutex::new(None),
+        })
+    }
+
+    pub fn endpoint(&self) -> &RedisEndpoint {
+        &self.endpoint
+    }
+
+    /// 执行一条命令；服务端错误转为 Err
+    pub async fn command<S: AsRef<[u8]>>(&self, args: &[S]) -> Result<RespValue> {
+        let mut guard = self.connection.lock().await;
+        if guard.is_none() {
+            *guard = Some(self.connect().await?);
+        }
+        let Some(stream) = guard.as_mut() else {
+            return Err(anyhow!("Redis connection unavailable"));
+        };
+
+        let result = tokio::time::timeout(IO_TIMEOUT, roundtrip(stream, args))
+            .await
+            .map_err(|_| anyhow!("Redis command timed out"))
+            .and_then(|r| r);
+        match result {
+            Ok(RespValue::Error(message)) => Err(anyhow!("Redis error: {}", message)),
+            Ok(value) => Ok(value),
+            Err(e) => {
+                // 连接状态未知，丢弃后下次重连
+                *guard = None;
+                Err(e)
+            }
+        }
+    }
+
+    /// 执行 Lua 脚本
+    pub async fn eval(&self, script: &str, keys: &[&str], args: &[String]) -> Result<RespValue> {
+        let mut command: Vec<&[u8]> = vec![b"EVAL", script.as_bytes()];
+        let key_count = keys.len().to_string();
+        command.push(key_count.as_bytes());
+        command.extend(keys.iter().map(|key| key.as_bytes()));
+        command.extend(args.iter().map(|arg| arg.as_bytes()));
+        self.command(&command).await
+    }
+
+    async fn connect(&self) -> Result<BufReader<TcpStream>> {
+        let address = format!("{}:{}", self.endpoint.host, self.endpoint.port);
+        debug!("🔌 Connecting to Redis at {}", address);
+        let tcp = tokio::time::timeout(IO_TIMEOUT, TcpStream::connect(&address))
+            .await
+            .map_err(|_| anyhow!("Timed out connecting to Redis at {}", address))?
+            .with_context(|| format!("Failed to connect to Redis at {}", address))?;
+        let mut stream = BufReader::new(tcp);
+
+        if let Some(password) = &self.endpoint.password {
+            expect_ok(roundtrip(&mut stream, &["AUTH", password.as_str()]).await?, "AUTH")?;
+        }
+        if self.endpoint.db != 0 {
+            let db = self.endpoint.db.to_string();
+            expect_ok(roundtrip(&mut stream, &["SELECT", db.as_str()]).await?, "SELECT")?;
+        }
+        Ok(stream)
+    }
+}
+
+fn expect_ok(value: RespValue, command: &str) -> Result<()> {
+    match value {
+        RespValue::Error(message) => Err(anyhow!("Redis {} failed: {}", command, message)),
+        _ => Ok(()),
+    }
+}
+
+async fn roundtrip<S: AsRef<[u8]>>(stream: &mut BufReader<TcpStream>, args: &[S]) -> Result<RespValue> {
+    stream.get_mut().write_all(&encode_command(args)).await?;
+    read_value(stream).await
+}
+
+/// 编码为 RESP 数组
+pub fn encode_command<S: AsRef<[u8]>>(args: &[S]) -> Vec<u8> {
+    let mut out = format!("*{}\r\n", args.len()).into_bytes();
+    for arg in args {
+        let arg = arg.as_ref();
+        out.extend_from_slice(format!("${}\r\n", arg.len()).as_bytes());
+        out.extend_from_slice(arg);
+        out.extend_from_slice(b"\r\n");
+    }
+    out
+}
+
+/// 读取一个 RESP 值（嵌套数组用显式栈展开）
+pub async fn read_value<R>(reader: &mut R) -> Result<RespValue>
+where
+    R: AsyncBufReadExt + Unpin + Send,
+{
+    // 未读完的数组：(已读元素, 总长度)
+    let mut pending: Vec<(Vec<RespValue>, usize)> = Vec::new();
+    loop {
+        let mut value = match read_frame(reader).await? {
+            Frame::ArrayHeader(len) if len > 0 => {
+                pending.push((Vec::with_capacity(len), len));
+                continue;
+            }
+            Frame::ArrayHeader(_) => RespValue::Array(Some(Vec::new())),
+            Frame::Value(value) => value,
+        };
+        loop {
+            let Some((items, len)) = pending.last_mut() else {
+                return Ok(value);
+            };
+            items.push(value);
+            if items.len() < *len {
+                break;
+            }
+            let (items, _) = pending.pop().unwrap_or_default();
+            value = RespValue::Array(Some(items));
+        }
+    }
+}
+
+enum Frame {
+    Value(RespValue),
+    /// 非空数组的长度（元素随后读取）
+    ArrayHeader(usize),
+}
+
+async fn read_frame<R>(reader: &mut R) -> Result<Frame>
+where
+    R: AsyncBufReadExt + Unpin + Send,
+{
+    let mut line = String::new();
+    if reader.read_line(&mut line).await? == 0 {
+        return Err(anyhow!("Redis connection closed"));
+    }
+    let line = line.trim_end_matches(['\r', '\n']);
+    let (kind, body) = line.split_at(line.len().min(1));
+    let value = match kind {
+        "+" => RespValue::Simple(body.to_string()),
+        "-" => RespValue::Error(body.to_string()),
+        ":" => RespValue::Integer(body.parse().context("Invalid RESP integer")?),
+        "$" => {
+            let len: i64 = body.parse().context("Invalid RESP bulk length")?;
+            if len < 0 {
+                RespValue::Bulk(None)
+            } else {
+                let mut data = vec![0u8; len as usize + 2];
+                reader.read_exact(&mut data).await?;
+                data.truncate(len as usize);
+                RespValue::Bulk(Some(data))
+            }
+        }
+        "*" => {
+            let len: i64 = body.parse().context("Invalid RESP array length")?;
+            if len < 0 {
+                RespValue::Array(None)
+            } else {
+                return Ok(Frame::ArrayHeader(len as usize));
+            }
+        }
+        _ => return Err(anyhow!("Unexpected RESP line: {}", line)),
+    };
+    Ok(Frame::Value(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_endpoint() {
+        let endpoint = RedisEndpoint::parse("redis://:s3cret@cache.internal:6380/2").unwrap();
+        assert_eq!(endpoint.host, "cache.internal");
+        assert_eq!(endpoint.port, 6380);
+        assert_eq!(endpoint.password.as_deref(), Some("s3cret"));
+        assert_eq!(endpoint.db, 2);
+
+        let plain = RedisEndpoint::parse("redis://localhost").unwrap();
+        assert_eq!((plain.port, plain.password, plain.db), (6379, None, 0));
+        assert!(RedisEndpoint::parse("http://localhost").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_resp_roundtrip() {
+        assert_eq!(encode_command(&["GET", "k"]), b"*2\r\n$3\r\nGET\r\n$1\r\nk\r\n".to_vec());
+
+        let raw: &[u8] = b"*4\r\n:1\r\n$5\r\nhello\r\n*2\r\n+OK\r\n$-1\r\n*0\r\n";
+        let mut reader = BufReader::new(raw);
+        let value = read_value(&mut reader).await.unwrap();
+        assert_eq!(
+            value,
+            RespValue::Array(Some(vec![
+                RespValue::Integer(1),
+                RespValue::Bulk(Some(b"hello".to_vec())),
+                RespValue::Array(Some(vec![RespValue::Simple("OK".to_string()), RespValue::Bulk(None)])),
+                RespValue::Array(Some(vec![])),
+            ]))
+        );
+    }
+}
@@ -0,0 +1,238 @@
+// Replay - 按记录的响应确定性重放一次执行
+// Router 每次 Provider 调用与 MCP 工具调用的结果按顺序记入执行日志（transcript），
+// 重放时链路照常运行（Jarvis、审计解析、重新规划、流水线拓扑），但不调用任何 Provider 或工具
+//
+// 核心功能：
+// 1. RecordedCall：一次调用的阶段名、角色、迭代与结果（成功或失败）
+// 2. ExecutionTranscript：从执行日志或夹具文件（响应缓存）构建
+// 3. ReplayCursor：按阶段名依次取出记录（并行阶段的交错顺序不影响结果），记录耗尽即报错
+//
+// 注：没有 transcript 的旧日志只保留最后一版 MOSS 方案，只能重放单轮执行
+
+use anyhow::{anyhow, bail, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use super::provider_fixtures::{FixtureOutcome, FixtureSet};
+use super::types::{ACSAExecutionLog, AgentResponse, AgentRole};
+
+/// 工具调用记录的阶段名前缀
+pub const TOOL_STAGE_PREFIX: &str = "tool:";
+
+/// 一次记录的调用
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedCall {
+    /// 流水线阶段名（工具调用为 `tool:<名称>`）；为空时按角色匹配（响应缓存）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stage: Option<String>,
+    pub role: AgentRole,
+    pub iteration: u32,
+    pub outcome: FixtureOutcome,
+}
+
+impl RecordedCall {
+    pub fn new(stage: &str, role: AgentRole, iteration: u32, result: &Result<AgentResponse>) -> Self {
+        let outcome = match result {
+            Ok(response) => FixtureOutcome::Response {
+                text: response.text.clone(),
+                tokens: response.tokens,
+                cost: response.cost,
+                latency_ms: response.latency_ms,
+                metadata: response.metadata.clone(),
+            },
+            Err(e) => FixtureOutcome::Error { message: format!("{:#}", e) },
+        };
+        Self { stage: Some(stage.to_string()), role, iteration, outcome }
+    }
+
+    /// 工具调用结果
+    pub fn tool(tool: &str, iteration: u32, output: &str) -> Self {
+        Self {
+            stage: Some(format!("{}{}", TOOL_STAGE_PREFIX, tool)),
+            role: AgentRole::Omega,
+            iteration,
+            outcome: FixtureOutcome::Response {
+                text: output.to_string(),
+                tokens: 0,
+                cost: 0.0,
+                latency_ms: 0,
+                metadata: HashMap::new(),
+            },
+        }
+    }
+
+    /// 旧日志中的阶段输出（没有阶段名时按角色匹配）
+    fn from_response(stage: Option<&str>, response: &AgentResponse, iteration: u32) -> Self {
+        let mut call = Self::new(stage.unwrap_or_default(), response.role, iteration, &Ok(response.clone()));
+        call.stage = stage.map(str::to_string);
+        call
+    }
+
+    fn matches(&self, stage: &str, role: AgentRole) -> bool {
+        match &self.stage {
+            Some(recorded) => recorded == stage,
+            None => self.role == role && !stage.starts_with(TOOL_STAGE_PREFIX),
+        }
+    }
+
+    fn to_result(&self) -> Result<AgentResponse> {
+        match &self.outcome {
+            FixtureOutcome::Response { text, tokens, cost, latency_ms, metadata } => Ok(AgentResponse {
+                role: self.role,
+                text: text.clone(),
+                tokens: *tokens,
+                cost: *cost,
+                latency_ms: *latency_ms,
+                metadata: metadata.clone(),
+                timestamp: Utc::now(),
+            }),
+            FixtureOutcome::Error { message } => Err(anyhow!("{}", message)),
+        }
+    }
+}
+
+/// 一次执行的调用记录
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExecutionTranscript {
+    pub calls: Vec<RecordedCall>,
+}
+
+impl ExecutionTranscript {
+    /// 从执行日志构建；旧日志没有 transcript 时由各阶段输出重建（仅限单轮执行）
+    pub fn from_log(log: &ACSAExecutionLog) -> Result<Self> {
+        if !log.transcript.is_empty() {
+            return Ok(Self { calls: log.transcript.clone() });
+        }
+        if log.iterations > 1 {
+            bail!(
+                "Execution log has no recorded transcript and ran {} iterations; earlier plans were not kept",
+                log.iterations
+            );
+        }
+
+        let mut calls: Vec<RecordedCall> = log
+            .moss_plan
+            .iter()
+            .map(|plan| RecordedCall::from_response(None, plan, 1))
+            .collect();
+        if log.stage_outputs.is_empty() {
+            calls.extend(log.l6_verification.iter().chain(&log.ultron_audit).map(|r| RecordedCall::from_response(None, r, 1)));
+        } else {
+            calls.extend(
+                log.stage_outputs
+                    .iter()
+                    .map(|output| RecordedCall::from_response(Some(&output.stage), &output.response, output.iteration)),
+            );
+        }
+        calls.extend(log.omega_execution.iter().map(|r| RecordedCall::from_response(None, r, 1)));
+        if calls.is_empty() {
+            bail!("Execution log has no recorded provider responses");
+        }
+        Ok(Self { calls })
+    }
+
+    /// 从夹具文件（响应缓存）构建：按角色与录制顺序匹配，忽略提示词
+    pub fn from_fixtures(set: &FixtureSet) -> Self {
+        let calls = set
+            .records
+            .iter()
+            .map(|record| RecordedCall { stage: None, role: record.role, iteration: 0, outcome: record.outcome.clone() })
+            .collect();
+        Self { calls }
+    }
+
+    pub fn len(&self) -> usize {
+        self.calls.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.calls.is_empty()
+    }
+}
+
+/// 重放游标
+pub struct ReplayCursor {
+    calls: Mutex<Vec<Option<RecordedCall>>>,
+}
+
+impl ReplayCursor {
+    pub fn new(transcript: ExecutionTranscript) -> Self {
+        Self { calls: Mutex::new(transcript.calls.into_iter().map(Some).collect()) }
+    }
+
+    /// 取出该阶段的下一条记录
+    pub fn next(&self, stage: &str, role: AgentRole) -> Result<RecordedCall> {
+        let mut calls = self.calls.lock().unwrap_or_else(|e| e.into_inner());
+        calls
+            .iter_mut()
+            .find(|slot| slot.as_ref().is_some_and(|call| call.matches(stage, role)))
+            .and_then(Option::take)
+            .ok_or_else(|| anyhow!("Replay diverged: no recorded response left for stage '{}' ({})", stage, role.as_str()))
+    }
+
+    /// 取出该阶段的下一条响应
+    pub fn next_response(&self, stage: &str, role: AgentRole) -> Result<AgentResponse> {
+        self.next(stage, role)?.to_result()
+    }
+
+    /// 未被使用的记录数（重放路径与原执行不同时大于 0）
+    pub fn remaining(&self) -> usize {
+        self.calls.lock().unwrap_or_else(|e| e.into_inner()).iter().flatten().count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::provider_fixtures::FixtureRecord;
+
+    #[test]
+    fn test_cursor_matches_stage_then_role() {
+        let response = |role, text: &str| AgentResponse {
+            role,
+            text: text.to_string(),
+            tokens: 1,
+            cost: 0.01,
+            latency_ms: 0,
+            metadata: HashMap::new(),
+            timestamp: Utc::now(),
+        };
+        let transcript = ExecutionTranscript {
+            calls: vec![
+                RecordedCall::new("MOSS", AgentRole::MOSS, 1, &Ok(response(AgentRole::MOSS, "plan v1"))),
+                RecordedCall::new("compliance", AgentRole::Ultron, 1, &Err(anyhow!("timeout"))),
+                RecordedCall::new("Ultron", AgentRole::Ultron, 1, &Ok(response(AgentRole::Ultron, "RISK_SCORE: 10"))),
+                RecordedCall::tool("search", 1, "3 results"),
+                RecordedCall::new("MOSS", AgentRole::MOSS, 2, &Ok(response(AgentRole::MOSS, "plan v2"))),
+            ],
+        };
+        let cursor = ReplayCursor::new(transcript);
+
+        // 并行阶段的顺序不影响匹配
+        assert_eq!(cursor.next_response("Ultron", AgentRole::Ultron).unwrap().text, "RISK_SCORE: 10");
+        assert!(cursor.next_response("compliance", AgentRole::Ultron).unwrap_err().to_string().contains("timeout"));
+        assert_eq!(cursor.next_response("MOSS", AgentRole::MOSS).unwrap().text, "plan v1");
+        assert_eq!(cursor.next_response("tool:search", AgentRole::Omega).unwrap().text, "3 results");
+        assert_eq!(cursor.remaining(), 1);
+        assert_eq!(cursor.next_response("MOSS", AgentRole::MOSS).unwrap().text, "plan v2");
+        assert!(cursor.next("MOSS", AgentRole::MOSS).is_err());
+
+        // 响应缓存只按角色匹配
+        let set = FixtureSet {
+            version: 1,
+            records: vec![FixtureRecord {
+                role: AgentRole::Omega,
+                prompt: "ignored".to_string(),
+                max_tokens: 100,
+                temperature: 0.7,
+                outcome: FixtureOutcome::Error { message: "rate limited".to_string() },
+            }],
+        };
+        let cursor = ReplayCursor::new(ExecutionTranscript::from_fixtures(&set));
+        assert!(cursor.next("tool:search", AgentRole::Omega).is_err());
+        assert!(cursor.next_response("Omega", AgentRole::Omega).is_err());
+        assert_eq!(cursor.remaining(), 0);
+    }
+}
@@ -0,0 +1,531 @@
+// Repo Index - 仓库索引与文件级上下文
+// 大仓库会撑爆上下文窗口：任务包只带相关文件，后续迭代只重发改动区域
+//
+// 核心功能：
+// 1. 仓库索引：符号 + 文件摘要，可导出为 RAG 文档复用
+// 2. 相关文件选择：按任务意图为文件打分，MissionPack 只携带 Top-K
+// 3. 迭代跟踪：记录每轮触及的文件
+// 4. 增量上下文：与上次发送的快照比较，只发送变化的行区间
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use tracing::{debug, info};
+
+use super::opencode_connector::{ExecutionReceipt, MissionPack};
+use super::rag_engine::{Document, RagEngine};
+
+static RUST_SYMBOL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*(?:pub(?:\([^)]*\))?\s+)?(?:async\s+)?(fn|struct|enum|trait|mod|const|static|type)\s+([A-Za-z_]\w*)")
+        .unwrap()
+});
+static PYTHON_SYMBOL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*(?:async\s+)?(def|class)\s+([A-Za-z_]\w*)").unwrap());
+static JS_SYMBOL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*(?:export\s+)?(?:default\s+)?(?:async\s+)?(function|class|interface|const)\s+([A-Za-z_$][\w$]*)")
+        .unwrap()
+});
+static GO_SYMBOL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(func|type)\s+(?:\([^)]*\)\s*)?([A-Za-z_]\w*)").unwrap());
+
+/// 索引配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepoIndexConfig {
+    /// 参与索引的扩展名
+    pub extensions: Vec<String>,
+    /// 跳过的目录名
+    pub exclude_dirs: Vec<String>,
+    /// 超过该大小的文件不索引
+    pub max_file_bytes: u64,
+    /// 摘要最多保留的行数
+    pub summary_lines: usize,
+}
+
+impl Default for RepoIndexConfig {
+    fn default() -> Self {
+        Self {
+            extensions: ["rs", "py", "js", "ts", "tsx", "jsx", "go", "java", "toml", "md"]
+                .map(String::from)
+                .to_vec(),
+            exclude_dirs: [".git", "target", "node_modules", "dist", "build", "__pycache__", ".venv"]
+                .map(String::from)
+                .to_vec(),
+            max_file_bytes: 256 * 1024,
+            summary_lines: 4,
+        }
+    }
+}
+
+/// 符号
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Symbol {
+    pub name: String,
+    /// 关键字（fn / struct / class / def ...）
+    pub kind: String,
+    pub line: usize,
+}
+
+/// 文件条目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileEntry {
+    /// 相对仓库根目录的路径
+    pub path: PathBuf,
+    pub language: String,
+    pub line_count: usize,
+    pub content_hash: u64,
+    /// 文件头注释或前几行
+    pub summary: String,
+    pub symbols: Vec<Symbol>,
+}
+
+/// 仓库索引
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepoIndex {
+    pub root: PathBuf,
+    pub config: RepoIndexConfig,
+    pub files: HashMap<PathBuf, FileEntry>,
+}
+
+impl RepoIndex {
+    /// 扫描仓库建立索引
+    pub async fn build(root: impl Into<PathBuf>, config: RepoIndexConfig) -> Result<Self> {
+        let mut index = Self {
+            root: root.into(),
+            config,
+            files: HashMap::new(),
+        };
+        index.refresh().await?;
+        info!("🗂️  Repo indexed: {} files ({:?})", index.files.len(), index.root);
+        Ok(index)
+    }
+
+    /// 重新扫描，只重建内容变化的文件，返回新增/变化/删除的路径
+    pub async fn refresh(&mut self) -> Result<Vec<PathBuf>> {
+        let mut changed = Vec::new();
+        let mut seen = HashSet::new();
+
+        for path in self.walk().await? {
+            let relative = path.strip_prefix(&self.root).unwrap_or(&path).to_path_buf();
+            let Ok(content) = tokio::fs::read_to_string(&path).await else {
+                continue; // 非 UTF-8 文件
+            };
+
+            let hash = content_hash(&content);
+            seen.insert(relative.clone());
+            if self.files.get(&relative).is_some_and(|entry| entry.content_hash == hash) {
+                continue;
+            }
+
+            let entry = self.index_file(&relative, &content, hash);
+            self.files.insert(relative.clone(), entry);
+            changed.push(relative);
+        }
+
+        let removed: Vec<PathBuf> = self.files.keys().filter(|p| !seen.contains(*p)).cloned().collect();
+        for path in removed {
+            self.files.remove(&path);
+            changed.push(path);
+        }
+
+        debug!("🗂️  Index refresh: {} changed", changed.len());
+        Ok(changed)
+    }
+
+    /// 按任务意图为文件打分，返回最相关的 Top-K
+    pub fn select_relevant(&self, query: &str, top_k: usize) -> Vec<(PathBuf, f64)> {
+        let terms = tokenize(query);
+        if terms.is_empty() {
+            return Vec::new();
+        }
+
+        let mut scored: Vec<(PathBuf, f64)> = self
+            .files
+            .values()
+            .filter_map(|entry| {
+                let path_terms = tokenize(&entry.path.display().to_string());
+                let summary = entry.summary.to_lowercase();
+
+                let score: f64 = terms
+                    .iter()
+                    .map(|term| {
+                        let mut s = 0.0;
+                        if path_terms.contains(term) {
+                            s += 3.0;
+                        }
+                        for symbol in &entry.symbols {
+                            let name = symbol.name.to_lowercase();
+                            if name == *term {
+                                s += 5.0;
+                            } else if name.contains(term.as_str()) {
+                                s += 2.0;
+                            }
+                        }
+                        if summary.contains(term.as_str()) {
+                            s += 1.0;
+                        }
+                        s
+                    })
+                    .sum();
+
+                (score > 0.0).then(|| (entry.path.clone(), score))
+            })
+            .collect();
+
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        scored.truncate(top_k);
+        scored
+    }
+
+    /// 导出为 RAG 文档（摘要 + 符号表，不含全文）
+    pub fn to_documents(&self) -> Vec<Document> {
+        let now = Utc::now();
+        self.files
+            .values()
+            .map(|entry| {
+                let symbols = entry
+                    .symbols
+                    .iter()
+                    .map(|s| format!("{} {} (L{})", s.kind, s.name, s.line))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                let path = entry.path.display().to_string();
+
+                Document {
+                    document_id: format!("repo:{}", path),
+                    title: path.clone(),
+                    content: format!("{}\n{}\n{}", path, entry.summary, symbols),
+                    doc_type: "code".to_string(),
+                    metadata: HashMap::from([
+                        ("path".to_string(), path),
+                        ("language".to_string(), entry.language.clone()),
+                    ]),
+                    created_at: now,
+                    updated_at: now,
+                }
+            })
+            .collect()
+    }
+
+    /// 写入 RAG 引擎
+    pub async fn index_into(&self, rag: &RagEngine) -> Result<usize> {
+        let documents = self.to_documents();
+        let count = documents.len();
+        for document in documents {
+            rag.index_document(document).await?;
+        }
+        Ok(count)
+    }
+
+    /// 为任务挑选相关文件（之前迭代触及的文件优先保留）
+    pub fn scope_mission(&self, mission: &MissionPack, top_k: usize) -> MissionPack {
+        let mut scoped = mission.clone();
+        let mut files: Vec<String> = mission.context_files.clone();
+
+        for (path, _) in self.select_relevant(&mission.intent, top_k) {
+            let path = path.display().to_string();
+            if files.len() >= top_k.max(mission.context_files.len()) {
+                break;
+            }
+            if !files.contains(&path) {
+                files.push(path);
+            }
+        }
+
+        scoped.context_files = files;
+        scoped
+    }
+
+    async fn walk(&self) -> Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        let mut stack = vec![self.root.clone()];
+
+        while let Some(dir) = stack.pop() {
+            let mut entries = tokio::fs::read_dir(&dir)
+                .await
+                .with_context(|| format!("Failed to read {:?}", dir))?;
+
+            while let Some(entry) = entries.next_entry().await? {
+                let path = entry.path();
+                let file_type = entry.file_type().await?;
+                let name = entry.file_name().to_string_lossy().to_string();
+
+                if file_type.is_dir() {
+                    if !self.config.exclude_dirs.contains(&name) {
+                        stack.push(path);
+                    }
+                } else if file_type.is_file()
+                    && path
+                        .extension()
+                        .is_some_and(|ext| self.config.extensions.iter().any(|e| ext == e.as_str()))
+                    && entry.metadata().await.map(|m| m.len()).unwrap_or(0) <= self.config.max_file_bytes
+                {
+                    files.push(path);
+                }
+            }
+        }
+
+        Ok(files)
+    }
+
+    fn index_file(&self, path: &Path, content: &str, hash: u64) -> FileEntry {
+        let language = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let pattern: Option<&Regex> = match language.as_str() {
+            "rs" => Some(&RUST_SYMBOL),
+            "py" => Some(&PYTHON_SYMBOL),
+            "js" | "ts" | "tsx" | "jsx" => Some(&JS_SYMBOL),
+            "go" => Some(&GO_SYMBOL),
+            _ => None,
+        };
+
+        let symbols = pattern
+            .map(|re| {
+                content
+                    .lines()
+                    .enumerate()
+                    .filter_map(|(i, line)| {
+                        re.captures(line).map(|c| Symbol {
+                            kind: c[1].to_string(),
+                            name: c[2].to_string(),
+                            line: i + 1,
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        FileEntry {
+            path: path.to_path_buf(),
+            language,
+            line_count: content.lines().count(),
+            content_hash: hash,
+            summary: summarize(content, self.config.summary_lines),
+            symbols,
+        }
+    }
+}
+
+/// 一轮迭代触及的文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IterationTouch {
+    pub iteration: u32,
+    pub files: Vec<String>,
+}
+
+/// 任务的文件级上下文：记录已发送的快照，后续只发送变化区域
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MissionContext {
+    /// 已发送给模型的文件内容
+    snapshots: HashMap<String, String>,
+    /// 每轮触及的文件
+    pub touched: Vec<IterationTouch>,
+}
+
+impl MissionContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录一轮迭代触及的文件
+    pub fn record_iteration(&mut self, receipt: &ExecutionReceipt) {
+        let mut files: Vec<String> = receipt
+            .modified_files
+            .iter()
+            .chain(&receipt.created_files)
+            .cloned()
+            .collect();
+        files.sort();
+        files.dedup();
+
+        let iteration = self.touched.len() as u32 + 1;
+        self.touched.push(IterationTouch { iteration, files });
+    }
+
+    /// 所有迭代触及过的文件
+    pub fn touched_files(&self) -> Vec<String> {
+        let mut files: Vec<String> = self.touched.iter().flat_map(|t| t.files.clone()).collect();
+        files.sort();
+        files.dedup();
+        files
+    }
+
+    /// 构建本轮上下文：首次发送全文，之后只发送变化的行区间，未变化的文件省略
+    pub async fn render(&mut self, root: &Path, mission: &MissionPack, max_chars: usize) -> Result<String> {
+        let mut files = mission.context_files.clone();
+        for file in self.touched_files() {
+            if !files.contains(&file) {
+                files.push(file);
+            }
+        }
+
+        let mut output = String::new();
+        for file in files {
+            let current = tokio::fs::read_to_string(root.join(&file)).await.unwrap_or_default();
+            let section = match self.snapshots.get(&file) {
+                None => format!("=== {} (full) ===\n{}\n", file, current),
+                Some(previous) if *previous == current => continue,
+                Some(previous) => match changed_region(previous, &current) {
+                    Some((start, end, lines)) => {
+                        format!("=== {} (changed lines {}-{}) ===\n{}\n", file, start, end, lines)
+                    }
+                    None => format!("=== {} (lines removed) ===\n", file),
+                },
+            };
+
+            if output.len() + section.len() > max_chars {
+                output.push_str(&format!("=== {} (omitted: context budget exceeded) ===\n", file));
+                continue;
+            }
+
+            output.push_str(&section);
+            self.snapshots.insert(file, current);
+        }
+
+        Ok(output)
+    }
+}
+
+/// 去掉公共前缀/后缀后的变化区间（1-based，含首尾），新内容为空时返回 None
+fn changed_region(previous: &str, current: &str) -> Option<(usize, usize, String)> {
+    let old: Vec<&str> = previous.lines().collect();
+    let new: Vec<&str> = current.lines().collect();
+
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let changed = &new[prefix..new.len() - suffix];
+    if changed.is_empty() {
+        return None;
+    }
+    Some((prefix + 1, prefix + changed.len(), changed.join("\n")))
+}
+
+fn summarize(content: &str, max_lines: usize) -> String {
+    let comments: Vec<&str> = content
+        .lines()
+        .map(str::trim)
+        .take_while(|l| l.starts_with("//") || l.starts_with('#') || l.is_empty())
+        .filter(|l| !l.is_empty() && !l.starts_with("#!") && !l.starts_with("#["))
+        .map(|l| l.trim_start_matches(['/', '!', '#', ' ']))
+        .take(max_lines)
+        .collect();
+
+    if comments.is_empty() {
+        content
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .take(max_lines)
+            .collect::<Vec<_>>()
+            .join("\n")
+    } else {
+        comments.join("\n")
+    }
+}
+
+fn tokenize(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|t| t.len() > 2)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn content_hash(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn sample_repo() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        tokio::fs::create_dir_all(dir.path().join("src/auth")).await.unwrap();
+        tokio::fs::create_dir_all(dir.path().join("target")).await.unwrap();
+        tokio::fs::write(
+            dir.path().join("src/auth/session.rs"),
+            "// Session handling\npub struct Session {}\npub fn refresh_token() {}\n",
+        )
+        .await
+        .unwrap();
+        tokio::fs::write(dir.path().join("src/billing.py"), "class Invoice:\n    def total(self):\n        pass\n")
+            .await
+            .unwrap();
+        tokio::fs::write(dir.path().join("target/junk.rs"), "fn junk() {}\n").await.unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_index_and_select() {
+        let repo = sample_repo().await;
+        let index = RepoIndex::build(repo.path(), RepoIndexConfig::default()).await.unwrap();
+
+        assert_eq!(index.files.len(), 2);
+        let session = &index.files[Path::new("src/auth/session.rs")];
+        assert_eq!(session.summary, "Session handling");
+        assert_eq!(session.symbols[1], Symbol { name: "refresh_token".to_string(), kind: "fn".to_string(), line: 3 });
+
+        let relevant = index.select_relevant("Fix refresh_token expiry in auth", 5);
+        assert_eq!(relevant[0].0, PathBuf::from("src/auth/session.rs"));
+        assert_eq!(relevant.len(), 1);
+
+        assert_eq!(index.to_documents().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_only_changed_files() {
+        let repo = sample_repo().await;
+        let mut index = RepoIndex::build(repo.path(), RepoIndexConfig::default()).await.unwrap();
+        assert!(index.refresh().await.unwrap().is_empty());
+
+        tokio::fs::write(repo.path().join("src/billing.py"), "class Invoice:\n    pass\n").await.unwrap();
+        tokio::fs::remove_file(repo.path().join("src/auth/session.rs")).await.unwrap();
+
+        let mut changed = index.refresh().await.unwrap();
+        changed.sort();
+        assert_eq!(changed, vec![PathBuf::from("src/auth/session.rs"), PathBuf::from("src/billing.py")]);
+        assert_eq!(index.files.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_incremental_context() {
+        let repo = sample_repo().await;
+        let index = RepoIndex::build(repo.path(), RepoIndexConfig::default()).await.unwrap();
+        let mission = MissionPack::new("t".to_string(), "Session refresh".to_string(), repo.path().to_path_buf());
+        let mission = index.scope_mission(&mission, 3);
+        assert_eq!(mission.context_files, vec!["src/auth/session.rs".to_string()]);
+
+        let mut context = MissionContext::new();
+        let first = context.render(repo.path(), &mission, 10_000).await.unwrap();
+        assert!(first.contains("session.rs (full)"));
+
+        // 没有变化时不重发
+        assert!(context.render(repo.path(), &mission, 10_000).await.unwrap().is_empty());
+
+        tokio::fs::write(
+            repo.path().join("src/auth/session.rs"),
+            "// Session handling\npub struct Session { id: u64 }\npub fn refresh_token() {}\n",
+        )
+        .await
+        .unwrap();
+        let delta = context.render(repo.path(), &mission, 10_000).await.unwrap();
+        assert!(delta.contains("changed lines 2-2"));
+        assert!(delta.contains("id: u64"));
+        assert!(!delta.contains("refresh_token"));
+    }
+}
@@ -0,0 +1,199 @@
+// Retry - Provider 调用的瞬时错误重试
+// Claude / Gemini / DeepSeek 共用同一套退避策略，429 / 5xx / 超时不再直接打断整条链路
+//
+// 核心功能：
+// 1. RetryPolicy：最大尝试次数、指数退避、抖动比例、总耗时上限（ACSAConfig.retry）
+// 2. 按 ErrorCode::is_retryable 判断是否重试，鉴权 / 参数错误立即返回
+// 3. Router 在 execute 内以 task-local 下发策略，Provider 通过 `current()` 读取
+// 4. 固定种子模式下抖动使用 determinism 随机流，重试间隔可复现
+// 5. 每次尝试记录为 provider.http 追踪 span（telemetry.rs）
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use super::determinism::{self, SeededRng};
+use super::error::ErrorCode;
+use super::telemetry;
+
+tokio::task_local! {
+    /// 当前执行的重试策略（仅在 Router 的 `execute` 内存在）
+    static POLICY: RetryPolicy;
+}
+
+/// 重试策略
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// 最大尝试次数（含首次；1 表示不重试）
+    pub max_attempts: u32,
+    /// 首次重试前的等待
+    pub initial_backoff_ms: u64,
+    /// 单次等待上限
+    pub max_backoff_ms: u64,
+    /// 每次重试的退避倍数
+    pub multiplier: f64,
+    /// 抖动比例 (0-1)：实际等待在 [delay * (1 - jitter), delay] 间均匀分布
+    pub jitter: f64,
+    /// 从首次调用起的总耗时上限，下一次等待会超出时放弃
+    pub max_elapsed_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff_ms: 500,
+            max_backoff_ms: 8_000,
+            multiplier: 2.0,
+            jitter: 0.5,
+            max_elapsed_ms: 30_000,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// 不重试
+    pub fn disabled() -> Self {
+        Self { max_attempts: 1, ..Self::default() }
+    }
+
+    /// 第 `retry` 次重试（从 1 开始）的退避上限，未加抖动
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let exp = self.multiplier.max(1.0).powi(retry.saturating_sub(1) as i32);
+        let ms = (self.initial_backoff_ms as f64 * exp).min(self.max_backoff_ms as f64);
+        Duration::from_millis(ms as u64)
+    }
+
+    /// 加抖动后的等待时间
+    fn jittered(&self, retry: u32, rng: &mut SeededRng) -> Duration {
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        self.backoff(retry).mul_f64(1.0 - jitter * rng.next_f64())
+    }
+
+    /// 执行 `operation`，遇到可重试错误时按策略退避重试
+    pub async fn run<T, F, Fut>(&self, label: &str, mut operation: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let start = Instant::now();
+        let max_elapsed = Duration::from_millis(self.max_elapsed_ms);
+        let mut rng = determinism::stream("provider.retry").unwrap_or_else(|| SeededRng::new(entropy()));
+        let mut attempt: u32 = 1;
+        loop {
+            // 每次尝试单独记录为 Provider HTTP 子 span
+            let attributes = vec![("acsa.provider", label.into()), ("http.attempt", attempt.into())];
+            let error = match telemetry::traced("provider.http", attributes, operation()).await {
+                Ok(value) => return Ok(value),
+                Err(error) => error,
+            };
+            let code = ErrorCode::from_provider_error(&error);
+            if !code.is_retryable() || attempt >= self.max_attempts.max(1) {
+                return Err(error);
+            }
+            let delay = self.jittered(attempt, &mut rng);
+            if start.elapsed() + delay > max_elapsed {
+                warn!("⏱️ {} retry budget exhausted after {} attempts ({:?})", label, attempt, code);
+                return Err(error);
+            }
+            warn!(
+                "🔄 {} attempt {}/{} failed ({:?}), retrying in {} ms: {:#}",
+                label,
+                attempt,
+                self.max_attempts,
+                code,
+                delay.as_millis(),
+                error
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+/// 非确定模式下的抖动种子
+fn entropy() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or_default()
+}
+
+/// 当前执行的重试策略（不在 Router 执行内时为默认策略）
+pub fn current() -> RetryPolicy {
+    POLICY.try_with(|policy| policy.clone()).unwrap_or_default()
+}
+
+/// 在 `policy` 下运行 `future`，其中的 Provider 调用使用该策略
+pub async fn scope<F: Future>(policy: RetryPolicy, future: F) -> F::Output {
+    POLICY.scope(policy, future).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::error::AcsaError;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn fast(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy { max_attempts, initial_backoff_ms: 1, max_backoff_ms: 2, ..RetryPolicy::default() }
+    }
+
+    #[test]
+    fn test_backoff_grows_and_caps() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.backoff(1), Duration::from_millis(500));
+        assert_eq!(policy.backoff(2), Duration::from_millis(1_000));
+        assert_eq!(policy.backoff(10), Duration::from_millis(8_000));
+
+        let mut rng = SeededRng::new(1);
+        for retry in 1..6 {
+            let delay = policy.jittered(retry, &mut rng);
+            assert!(delay <= policy.backoff(retry) && delay >= policy.backoff(retry) / 2);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retries_transient_errors_only() {
+        let calls = AtomicU32::new(0);
+        let result = fast(3)
+            .run("test", || async {
+                match calls.fetch_add(1, Ordering::SeqCst) {
+                    0 => Err(AcsaError::new(ErrorCode::ProviderRateLimited, "429").into()),
+                    1 => Err(AcsaError::new(ErrorCode::ProviderServerError, "503").into()),
+                    _ => Ok("ok"),
+                }
+            })
+            .await;
+        assert_eq!(result.unwrap(), "ok");
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        let calls = AtomicU32::new(0);
+        let result: Result<()> = fast(5)
+            .run("test", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(AcsaError::new(ErrorCode::ApiKeyInvalid, "401").into())
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // 总耗时上限为 0 时第一次失败即放弃
+        let budget = RetryPolicy { max_elapsed_ms: 0, ..fast(5) };
+        let calls = AtomicU32::new(0);
+        let result: Result<()> = scope(budget, async {
+            current()
+                .run("test", || async {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    Err(AcsaError::new(ErrorCode::ProviderTimeout, "timeout").into())
+                })
+                .await
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
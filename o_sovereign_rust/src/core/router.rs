@@ -1,16 +1,145 @@
 // O-Sovereign ACSA Router
 // 对抗性路由循环核心逻辑
 
-use super::cognitive_cleaner::CognitiveCleaner;
-use super::jarvis::JarvisCircuitBreaker;
+use super::agent_extension::{CustomAgent, OutcomeTracker};
+use super::api_manager::{ApiCallRecord, ApiManager};
+use super::approval::{ApprovalRequest, ApprovalStatus, ApprovalStore};
+use super::audit_log::AuditLogger;
+use super::cache_manager::{ResponseCache, ResponseCacheStats};
+use super::checkpoint::{CheckpointStage, CheckpointStore, ExecutionCheckpoint};
+use super::cognitive_cleaner::{CleanedIntent, CognitiveCleaner};
+use super::concurrency::TaskContext;
+use super::cost_estimator::{CostEstimator, DryRunReport};
+use super::error::AcsaError;
+use super::event_bus::{Event, EventBus, EventType};
+use super::jarvis::{JarvisCircuitBreaker, JarvisManager, JarvisVerdict};
 use super::providers::ModelProvider;
+use super::kill_switch::{KillSwitch, PausedOperation};
+use super::mcp_client::{parse_tool_calls, McpClientRegistry, ToolCallRequest};
+use super::metrics::MetricsCollector;
+use super::notifier::{Notification, NotificationKind, NotificationPriority, Notifier};
+use super::performance::LazySubsystem;
+use super::pipeline::{PipelineConfig, PipelineStage, StageOutput};
+use super::plan_diff::PlanDiff;
+use super::plugin_system::{PluginSystem, PluginSystemConfig};
+use super::protocol::ProtocolConfig;
+use super::rag_engine::{format_citations, Citation, RagEngine};
+use super::replay::{ExecutionTranscript, RecordedCall, ReplayCursor, TOOL_STAGE_PREFIX};
+use super::retry;
+use super::shutdown::ShutdownCoordinator;
+use super::telemetry::{self, SpanKind, Tracer};
 use super::types::{
-    ACSAConfig, ACSAExecutionLog, AgentResponse, AuditResult,
+    ACSAConfig, ACSAExecutionLog, AgentChunk, AgentResponse, AgentRole, AuditResult, ExecutionFeed,
+    PipelineEvent, EXECUTION_FEED_EVENT,
 };
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use regex::Regex;
+use std::future::Future;
+use chrono::Utc;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
-use tracing::{error, info, warn};
+use std::time::Instant;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, info_span, warn, Instrument};
+
+tokio::task_local! {
+    /// 当前流式执行的输出通道（仅在 `execute_streaming` 的任务内存在）
+    static STREAM: UnboundedSender<AgentChunk>;
+    /// 当前执行的上下文（仅在 `execute` 内存在）
+    static RUN: RunContext;
+}
+
+/// 执行序号（与时间戳组成执行 ID）
+static EXECUTION_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// 单次执行的标识、当前迭代、实况事件序号与多轮对话上下文
+struct RunContext {
+    execution_id: String,
+    iteration: AtomicU32,
+    sequence: AtomicU64,
+    /// 之前各轮的对话摘要（会话模式下注入 MOSS 提示词）
+    conversation: Option<String>,
+    /// 本次检索到的知识库片段（启用 RAG 时注入 MOSS 提示词）
+    knowledge: std::sync::OnceLock<String>,
+    /// 按顺序记录的调用结果（结束时写入执行日志）
+    transcript: std::sync::Mutex<Vec<RecordedCall>>,
+    /// 重放时的记录来源（存在时不调用 Provider 与工具）
+    replay: Option<Arc<ReplayCursor>>,
+    /// 本次执行的响应缓存命中统计
+    cache_stats: std::sync::Mutex<ResponseCacheStats>,
+}
+
+impl RunContext {
+    fn new(conversation: Option<String>) -> Self {
+        let seq = EXECUTION_SEQ.fetch_add(1, Ordering::Relaxed);
+        Self {
+            execution_id: format!("exec_{}_{}", Utc::now().timestamp_millis(), seq),
+            iteration: AtomicU32::new(1),
+            sequence: AtomicU64::new(0),
+            conversation,
+            knowledge: std::sync::OnceLock::new(),
+            transcript: std::sync::Mutex::new(Vec::new()),
+            replay: None,
+            cache_stats: std::sync::Mutex::new(ResponseCacheStats::default()),
+        }
+    }
+
+    /// 从检查点恢复：沿用原执行 ID、对话上下文与检索到的知识
+    fn resumed(checkpoint: &ExecutionCheckpoint) -> Self {
+        let knowledge = std::sync::OnceLock::new();
+        if let Some(text) = &checkpoint.knowledge {
+            let _ = knowledge.set(text.clone());
+        }
+        Self {
+            execution_id: checkpoint.execution_id.clone(),
+            iteration: AtomicU32::new(checkpoint.next_iteration + 1),
+            sequence: AtomicU64::new(0),
+            conversation: checkpoint.conversation.clone(),
+            knowledge,
+            transcript: std::sync::Mutex::new(checkpoint.log.transcript.clone()),
+            replay: None,
+            cache_stats: std::sync::Mutex::new(checkpoint.log.response_cache.clone().unwrap_or_default()),
+        }
+    }
+
+    /// 重放：调用结果取自记录
+    fn replaying(transcript: ExecutionTranscript) -> Self {
+        Self { replay: Some(Arc::new(ReplayCursor::new(transcript))), ..Self::new(None) }
+    }
+
+    /// 目前为止记录的调用
+    fn recorded_calls(&self) -> Vec<RecordedCall> {
+        self.transcript.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// 标记后续输出片段所属的迭代
+fn set_iteration(iteration: u32) {
+    let _ = RUN.try_with(|run| run.iteration.store(iteration, Ordering::Relaxed));
+}
+
+/// 当前迭代（执行上下文之外为 1）
+fn current_iteration() -> u32 {
+    RUN.try_with(|run| run.iteration.load(Ordering::Relaxed)).unwrap_or(1)
+}
+
+/// 把一次调用结果记入本次执行的 transcript
+fn record_call(call: RecordedCall) {
+    let _ = RUN.try_with(|run| run.transcript.lock().unwrap_or_else(|e| e.into_inner()).push(call));
+}
+
+/// 重放中的记录来源
+fn replay_cursor() -> Option<Arc<ReplayCursor>> {
+    RUN.try_with(|run| run.replay.clone()).ok().flatten()
+}
+
+/// Provider 调用错误 → 带错误代码和上下文链的 AcsaError
+fn provider_error(error: anyhow::Error, operation: &str) -> anyhow::Error {
+    AcsaError::from_provider(error).in_operation("router", operation).into()
+}
 
 /// ACSA Router
 pub struct ACSARouter {
@@ -20,12 +149,55 @@ pub struct ACSARouter {
     omega: Arc<dyn ModelProvider>,
     /// Jarvis: 不可绕过的安全熔断器
     jarvis: Arc<JarvisCircuitBreaker>,
+    /// Jarvis 群管理（BUNKER 协议）：本地主权模式下各角色改用本地 Provider
+    bunker: Option<Arc<RwLock<JarvisManager>>>,
     /// Cognitive Cleaner: 认知清洗器（危险词转换）
     cognitive_cleaner: Arc<CognitiveCleaner>,
     config: ACSAConfig,
     execution_logs: Arc<tokio::sync::Mutex<Vec<ACSAExecutionLog>>>,
+    /// 流水线进度订阅者（TUI 仪表盘等）
+    progress: Option<UnboundedSender<PipelineEvent>>,
+    /// 执行实况发布目标（终端服务端等订阅）
+    event_bus: Option<Arc<EventBus>>,
+    /// 关停协调器（关停开始后拒绝新执行，进行中的执行计入排空）
+    shutdown: Option<Arc<ShutdownCoordinator>>,
+    /// 全局熔断开关（维护模式下拒绝新执行）
+    kill_switch: Option<Arc<KillSwitch>>,
+    /// 通知中心（熔断触发、预算告警）
+    notifier: Option<Arc<Notifier>>,
+    /// 预算上限（美元）与已累计花费
+    cost_budget: Option<f64>,
+    spent: std::sync::Mutex<f64>,
+    /// 知识库（MOSS 规划前检索 top-k 片段；按 `config.rag` 创建时在首次检索才打开索引）
+    rag: Option<LazySubsystem<Arc<RagEngine>>>,
+    /// 指标收集器（各 Agent 调用延迟与结果）
+    metrics: Option<Arc<MetricsCollector>>,
+    /// 链路追踪（每次执行一条 trace）
+    tracer: Option<Arc<Tracer>>,
+    /// 检查点（各阶段结束后落盘，进程中途退出后可 resume）
+    checkpoints: Option<Arc<CheckpointStore>>,
+    /// 审计日志（记录每次认知清洗的报告与人工审批）
+    audit: Option<Arc<AuditLogger>>,
+    /// 人工审批闸门（需同时启用检查点）
+    approvals: Option<Arc<ApprovalStore>>,
+    /// 流水线中由自定义 Agent 承担的阶段：Agent 名称 → (定义, Provider)
+    custom_agents: HashMap<String, (CustomAgent, Arc<dyn ModelProvider>)>,
+    /// 流水线中由 Agent 插件承担的阶段从这里解析（插件目录在首次用到时才扫描）
+    plugins: Option<LazySubsystem<Arc<PluginSystem>>>,
+    /// 外部 MCP 工具（Omega 执行阶段可调用）
+    mcp_tools: Option<Arc<McpClientRegistry>>,
+    /// Provider 响应精确缓存（默认不启用）
+    response_cache: Option<Arc<ResponseCache>>,
+    /// API 调用统计与 Provider 预算（调用前检查硬上限，调用后记录用量）
+    api: Option<Arc<RwLock<ApiManager>>>,
 }
 
+/// 预算告警阈值（占预算比例）
+const BUDGET_ALERT_THRESHOLDS: [f64; 2] = [0.8, 1.0];
+
+/// Omega 单次执行中工具调用的最大轮数
+const MAX_TOOL_ROUNDS: usize = 3;
+
 impl ACSARouter {
     pub fn new(
         moss: Arc<dyn ModelProvider>,
@@ -35,6 +207,13 @@ impl ACSARouter {
         config: ACSAConfig,
     ) -> Self {
         info!("🛡️  Initializing ACSA Router with Cognitive Cleaner + Jarvis Safety Layer");
+        // 向量索引与嵌入后端在首次检索时才加载，耗时计入启动瀑布图
+        let rag = config.rag.clone().map(|rag| {
+            LazySubsystem::new("rag.index", move || {
+                let rag = rag.clone();
+                async move { Ok(Arc::new(RagEngine::new(rag))) }
+            })
+        });
 
         Self {
             moss,
@@ -42,39 +221,761 @@ impl ACSARouter {
             ultron,
             omega,
             jarvis: Arc::new(JarvisCircuitBreaker::new()),
-            cognitive_cleaner: Arc::new(CognitiveCleaner::new()),
+            bunker: None,
+            cognitive_cleaner: Arc::new(CognitiveCleaner::new().with_safety_weights(config.safety_score.clone())),
             config,
             execution_logs: Arc::new(tokio::sync::Mutex::new(Vec::new())),
+            progress: None,
+            event_bus: None,
+            shutdown: None,
+            kill_switch: None,
+            notifier: None,
+            cost_budget: None,
+            spent: std::sync::Mutex::new(0.0),
+            rag,
+            metrics: None,
+            tracer: None,
+            checkpoints: None,
+            audit: None,
+            approvals: None,
+            custom_agents: HashMap::new(),
+            plugins: None,
+            mcp_tools: None,
+            response_cache: None,
+            api: None,
         }
     }
 
+    /// 各阶段结束后保存检查点，执行正常结束时删除
+    pub fn with_checkpoints(mut self, store: Arc<CheckpointStore>) -> Self {
+        self.checkpoints = Some(store);
+        self
+    }
+
+    /// 接入审计日志：每次认知清洗的报告（原文 → 重写映射）写入审计
+    pub fn with_audit(mut self, logger: Arc<AuditLogger>) -> Self {
+        self.audit = Some(logger);
+        self
+    }
+
+    /// 启用人工审批闸门：重新规划后风险仍高于阈值时暂停执行等待审批，而不是直接降级失败
+    pub fn with_approvals(mut self, store: Arc<ApprovalStore>) -> Self {
+        self.approvals = Some(store);
+        self
+    }
+
+    /// 替换流水线拓扑（`ACSAConfig.pipeline`）
+    pub fn with_pipeline(mut self, pipeline: PipelineConfig) -> Self {
+        self.config.pipeline = pipeline;
+        self
+    }
+
+    /// 启用响应缓存：相同 (Provider/模型, 提示词, 温度) 的调用直接返回缓存的响应，不再计费
+    pub fn with_response_cache(mut self, cache: Arc<ResponseCache>) -> Self {
+        self.response_cache = Some(cache);
+        self
+    }
+
+    /// 干跑模式（`ACSAConfig.dry_run`）：审计通过后不执行 Omega
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.config.dry_run = dry_run;
+        self
+    }
+
+    /// 注册自定义 Agent，供流水线中 `agent` 指向它的阶段调用
+    pub fn with_custom_agent(mut self, agent: CustomAgent, provider: Arc<dyn ModelProvider>) -> Self {
+        self.custom_agents.insert(agent.name.clone(), (agent, provider));
+        self
+    }
+
+    /// 接入插件系统，供流水线中 `plugin` 指向已加载 Agent 插件的阶段调用
+    pub fn with_plugins(mut self, plugins: Arc<PluginSystem>) -> Self {
+        self.plugins = Some(LazySubsystem::ready("plugins.scan", plugins));
+        self
+    }
+
+    /// 接入插件目录中已安装的插件：首次执行引用插件的流水线时才扫描并启动
+    pub fn with_installed_plugins(mut self, config: PluginSystemConfig) -> Self {
+        self.plugins = Some(LazySubsystem::new("plugins.scan", move || {
+            let config = config.clone();
+            async move {
+                let system = PluginSystem::new(config, None);
+                let started = system.load_installed().await?;
+                info!("🔌 Started {} installed plugin(s)", started.len());
+                Ok(Arc::new(system))
+            }
+        }));
+        self
+    }
+
+    /// 向 Omega 提供外部 MCP 服务器的工具（每次调用先经 Jarvis 校验）
+    pub fn with_mcp_tools(mut self, registry: Arc<McpClientRegistry>) -> Self {
+        self.mcp_tools = Some(registry);
+        self
+    }
+
+    /// 替换安全熔断器（例如加载了签名规则包的实例）
+    pub fn with_jarvis(mut self, jarvis: JarvisCircuitBreaker) -> Self {
+        self.jarvis = Arc::new(jarvis);
+        self
+    }
+
+    /// 接入 Jarvis 群管理：调用结果上报健康监控，进入 / 退出 BUNKER 模式时切换本地 / 云端 Provider
+    pub fn with_bunker(mut self, manager: Arc<RwLock<JarvisManager>>) -> Self {
+        self.bunker = Some(manager);
+        self
+    }
+
+    /// 接入 ApiManager：计费 Provider 调用前检查预算硬上限，调用后记录 Token、花费与延迟
+    pub fn with_api_manager(mut self, manager: Arc<RwLock<ApiManager>>) -> Self {
+        self.api = Some(manager);
+        self
+    }
+
+    /// 接入关停协调器
+    pub fn with_shutdown(mut self, coordinator: Arc<ShutdownCoordinator>) -> Self {
+        self.shutdown = Some(coordinator);
+        self
+    }
+
+    /// 接入全局熔断开关
+    pub fn with_kill_switch(mut self, kill_switch: Arc<KillSwitch>) -> Self {
+        self.kill_switch = Some(kill_switch);
+        self
+    }
+
+    /// 接入通知中心
+    pub fn with_notifier(mut self, notifier: Arc<Notifier>) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
+    /// 设置预算上限（美元），累计花费达到 80% / 100% 时发送预算告警
+    pub fn with_cost_budget(mut self, budget_usd: f64) -> Self {
+        self.cost_budget = Some(budget_usd);
+        self
+    }
+
+    /// 订阅流水线进度事件
+    pub fn with_progress(mut self, sender: UnboundedSender<PipelineEvent>) -> Self {
+        self.progress = Some(sender);
+        self
+    }
+
+    /// 将执行实况（阶段进度、审计结论、输出片段）发布到事件总线
+    pub fn with_event_bus(mut self, event_bus: Arc<EventBus>) -> Self {
+        self.event_bus = Some(event_bus);
+        self
+    }
+
+    /// 使用已建好索引的知识库（替换按 `config.rag` 创建的空知识库）
+    pub fn with_rag(mut self, engine: Arc<RagEngine>) -> Self {
+        self.rag = Some(LazySubsystem::ready("rag.index", engine));
+        self
+    }
+
+    /// 记录各 Agent 调用的延迟直方图与成功 / 失败次数
+    pub fn with_metrics(mut self, metrics: Arc<MetricsCollector>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// 启用链路追踪：执行为根 span，Agent 调用、Jarvis 校验与 Provider 请求为子 span
+    pub fn with_tracer(mut self, tracer: Arc<Tracer>) -> Self {
+        self.tracer = Some(tracer);
+        self
+    }
+
+    /// 等待已结束的 trace 导出完成（CLI 退出前调用）
+    pub async fn flush_traces(&self) {
+        if let Some(tracer) = &self.tracer {
+            tracer.flush().await;
+        }
+    }
+
+    /// 当前知识库（用于索引文档；首次调用时加载索引）
+    pub async fn rag(&self) -> Option<&Arc<RagEngine>> {
+        match self.rag.as_ref()?.get().await {
+            Ok(engine) => Some(engine),
+            Err(e) => {
+                warn!("⚠️  Knowledge base unavailable: {:#}", e);
+                None
+            }
+        }
+    }
+
+    /// 已接入的插件系统（首次调用时扫描插件目录）
+    async fn plugin_system(&self, plugin_id: &str) -> Result<&Arc<PluginSystem>> {
+        self.plugins
+            .as_ref()
+            .ok_or_else(|| anyhow!("Pipeline uses plugin '{}', but no plugin system is attached", plugin_id))?
+            .get()
+            .await
+    }
+
+    fn notify(&self, notification: Notification) {
+        if let Some(notifier) = &self.notifier {
+            notifier.notify_detached(notification);
+        }
+    }
+
+    /// 累计花费，跨过阈值时发送预算告警
+    fn track_cost(&self, cost: f64) {
+        let Some(budget) = self.cost_budget.filter(|b| *b > 0.0) else {
+            return;
+        };
+        let (before, after) = {
+            let mut spent = self.spent.lock().unwrap_or_else(|e| e.into_inner());
+            let before = *spent;
+            *spent += cost;
+            (before, *spent)
+        };
+        for threshold in BUDGET_ALERT_THRESHOLDS {
+            let limit = budget * threshold;
+            if before < limit && after >= limit {
+                let priority = if threshold >= 1.0 { NotificationPriority::Critical } else { NotificationPriority::Warning };
+                self.notify(
+                    Notification::new(
+                        NotificationKind::BudgetAlert,
+                        format!("{:.0}% of cost budget used", threshold * 100.0),
+                        format!("Spent ${:.4} of ${:.2} budget", after, budget),
+                    )
+                    .with_priority(priority)
+                    .with_dedupe_key(format!("budget:{}", threshold)),
+                );
+            }
+        }
+    }
+
+    async fn emit(&self, event: PipelineEvent) {
+        if let Some(sender) = &self.progress {
+            // 订阅者已退出时忽略
+            let _ = sender.send(event.clone());
+        }
+        self.publish_feed(ExecutionFeed::Progress { event }).await;
+    }
+
+    /// 是否需要发布执行实况
+    fn feed_enabled(&self) -> bool {
+        self.event_bus.is_some() && RUN.try_with(|_| ()).is_ok()
+    }
+
+    /// 发布一条执行实况（未接入事件总线或不在执行内时忽略）
+    async fn publish_feed(&self, feed: ExecutionFeed) {
+        let Some(bus) = &self.event_bus else {
+            return;
+        };
+        let Ok((execution_id, sequence)) =
+            RUN.try_with(|run| (run.execution_id.clone(), run.sequence.fetch_add(1, Ordering::Relaxed)))
+        else {
+            return;
+        };
+        let data = match serde_json::to_value(&feed) {
+            Ok(data) => data,
+            Err(e) => {
+                warn!("⚠️  Failed to encode execution feed: {}", e);
+                return;
+            }
+        };
+        let event = Event {
+            event_id: format!("{}#{}", execution_id, sequence),
+            event_type: EventType::Ai(EXECUTION_FEED_EVENT.to_string()),
+            source: "router".to_string(),
+            data,
+            timestamp: Utc::now(),
+            metadata: HashMap::from([
+                ("execution_id".to_string(), execution_id),
+                ("sequence".to_string(), sequence.to_string()),
+            ]),
+        };
+        if let Err(e) = bus.publish(event).await {
+            debug!("📪 Execution feed dropped: {}", e);
+        }
+    }
+
+    /// 输出片段推送给流式调用方与执行实况
+    async fn forward_chunk(&self, stream: Option<&UnboundedSender<AgentChunk>>, chunk: AgentChunk) {
+        if let Some(sender) = stream {
+            // 订阅者已退出时忽略，执行照常完成
+            let _ = sender.send(chunk.clone());
+        }
+        self.publish_feed(ExecutionFeed::Chunk { chunk }).await;
+    }
+
+    /// Jarvis 安全校验（追踪中时记录为子 span）
+    fn verify_with_jarvis(&self, content: &str, context: &str) -> JarvisVerdict {
+        let mut span = telemetry::start_span("jarvis.verify", SpanKind::Internal);
+        let verdict = self.jarvis.verify_safety(content, context);
+        tracing::Span::current().record("risk_level", verdict.risk_level);
+        if let Some(span) = &mut span {
+            span.set_attribute("jarvis.context", context.chars().take(64).collect::<String>());
+            span.set_attribute("jarvis.allowed", verdict.allowed);
+            span.set_attribute("jarvis.risk_level", verdict.risk_level);
+            if let Some(reason) = &verdict.block_reason {
+                span.set_error(reason.clone());
+            }
+        }
+        verdict
+    }
+
+    /// 包装单个 Agent 调用，推送开始/结束事件
+    async fn run_stage(
+        &self,
+        role: AgentRole,
+        call: impl Future<Output = Result<AgentResponse>>,
+    ) -> Result<AgentResponse> {
+        self.emit(PipelineEvent::StageStarted { role }).await;
+        let started = Instant::now();
+        // 日志 span：JSON 日志中该阶段的事件带 agent 字段
+        let log_span = info_span!("agent", agent = role.as_str());
+        let call = call.instrument(log_span.clone());
+        let result = match telemetry::start_span(&format!("agent.{}", role.as_str()), SpanKind::Internal) {
+            Some(mut span) => {
+                span.set_attribute("acsa.agent", role.as_str());
+                span.set_attribute("acsa.iteration", current_iteration());
+                let result = telemetry::in_span(&span, call).await;
+                match &result {
+                    Ok(response) => {
+                        span.set_attribute("acsa.cost_usd", response.cost);
+                        span.set_attribute("acsa.tokens", response.tokens as u64);
+                    }
+                    Err(e) => span.set_error(format!("{:#}", e)),
+                }
+                result
+            }
+            None => call.await,
+        };
+        log_span.in_scope(|| match &result {
+            Ok(response) => debug!(latency_ms = response.latency_ms, cost = response.cost, "agent call finished"),
+            Err(e) => debug!(error = %e, "agent call failed"),
+        });
+        if let Some(metrics) = &self.metrics {
+            metrics
+                .record_agent_call(role.as_str(), result.is_ok(), started.elapsed().as_secs_f64() * 1000.0)
+                .await;
+        }
+        self.emit(PipelineEvent::StageFinished {
+            role,
+            success: result.is_ok(),
+            latency_ms: result.as_ref().map(|r| r.latency_ms).unwrap_or(0),
+            cost: result.as_ref().map(|r| r.cost).unwrap_or(0.0),
+        })
+        .await;
+        result
+    }
+
+    /// 流式执行：各 Agent 的输出逐 token 推送到返回的通道，任务结束时得到完整执行日志
+    ///
+    /// 每个阶段结束时推送一个 `done` 片段；Ultron 驳回后 MOSS / L6 以新的迭代号再次输出
+    pub fn execute_streaming(
+        self: &Arc<Self>,
+        user_input: String,
+    ) -> (UnboundedReceiver<AgentChunk>, JoinHandle<Result<ACSAExecutionLog>>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let router = self.clone();
+        // 新任务继承调用方的截止时间与取消信号
+        let context = TaskContext::current();
+        let handle = tokio::spawn(async move {
+            let run = STREAM.scope(sender, async move { router.execute(user_input).await });
+            match context {
+                Some(context) => context.scope(run).await,
+                None => run.await,
+            }
+        });
+        (receiver, handle)
+    }
+
+    /// 按 BUNKER 模式选择 Provider 调用，并把结果上报 Jarvis 健康监控
+    ///
+    /// 本地模型的调用结果同样上报：本地主权模式下连续成功会让 Jarvis 尝试恢复云端（半开），
+    /// 云端仍不可用时再次触发熔断
+    async fn generate(
+        &self,
+        provider: &Arc<dyn ModelProvider>,
+        role: AgentRole,
+        prompt: &str,
+        max_tokens: u32,
+        temperature: f64,
+    ) -> Result<AgentResponse> {
+        let Some(manager) = &self.bunker else {
+            return self.call_provider(provider, role, prompt, max_tokens, temperature).await;
+        };
+        let provider = manager.read().await.route_provider(role, provider);
+        let start = Instant::now();
+        let result = self.call_provider(&provider, role, prompt, max_tokens, temperature).await;
+
+        let mut manager = manager.write().await;
+        manager.report_api_result(role.as_str(), result.is_ok(), start.elapsed().as_millis() as u64);
+        if result.is_err() {
+            if let Err(e) = manager.check_and_trigger_bunker().await {
+                warn!("⚠️ BUNKER check failed: {}", e);
+            }
+        }
+        result
+    }
+
+    /// 先查响应缓存（精确匹配，协议允许时再做语义匹配），未命中时调用 Provider 并写入缓存；
+    /// 命中的响应不计费（节省的成本计入执行日志）
+    async fn cached_generate(
+        &self,
+        provider: &Arc<dyn ModelProvider>,
+        role: AgentRole,
+        prompt: &str,
+        max_tokens: u32,
+        temperature: f64,
+    ) -> Result<AgentResponse> {
+        let Some(cache) = &self.response_cache else {
+            return self.generate(provider, role, prompt, max_tokens, temperature).await;
+        };
+        let model_id = provider.model_id();
+        let key = ResponseCache::key(&model_id, prompt, temperature);
+        let record_stats = |update: &dyn Fn(&mut ResponseCacheStats)| {
+            let _ = RUN.try_with(|run| update(&mut run.cache_stats.lock().unwrap_or_else(|e| e.into_inner())));
+        };
+
+        if let Some(response) = cache.get(&key).await {
+            info!("🗄️ [{}] Response cache hit (saved ${:.4})", role.as_str(), response.cost);
+            record_stats(&|stats| stats.record(Some(response.cost)));
+            return Ok(self.serve_cached(role, response, "hit").await);
+        }
+
+        let embedding = if self.semantic_cache_allowed() { cache.embed_prompt(prompt).await } else { None };
+        if let Some(embedding) = &embedding {
+            if let Some((mut response, similarity)) = cache.get_similar(&model_id, temperature, embedding).await {
+                info!(
+                    "🗄️ [{}] Semantic cache hit (similarity {:.3}, saved ${:.4})",
+                    role.as_str(),
+                    similarity,
+                    response.cost
+                );
+                record_stats(&|stats| stats.record_semantic(response.cost));
+                response.metadata.insert("cache_similarity".to_string(), format!("{:.4}", similarity));
+                return Ok(self.serve_cached(role, response, "semantic").await);
+            }
+        }
+
+        let response = self.generate(provider, role, prompt, max_tokens, temperature).await?;
+        record_stats(&|stats| stats.record(None));
+        cache.put(&key, &model_id, temperature, embedding, &response).await;
+        Ok(response)
+    }
+
+    /// 当前协议是否允许语义缓存（未设置协议时允许）
+    fn semantic_cache_allowed(&self) -> bool {
+        self.config.protocol.as_ref().is_none_or(|protocol| protocol.enable_semantic_cache)
+    }
+
+    /// 缓存命中的响应：不计费、不计延迟，流式执行 / 执行实况仍收到完整文本
+    async fn serve_cached(&self, role: AgentRole, mut response: AgentResponse, kind: &str) -> AgentResponse {
+        response.cost = 0.0;
+        response.latency_ms = 0;
+        response.timestamp = Utc::now();
+        response.metadata.insert("cache".to_string(), kind.to_string());
+        let stream = STREAM.try_with(|sender| sender.clone()).ok();
+        if stream.is_some() || self.feed_enabled() {
+            let iteration = current_iteration();
+            self.forward_chunk(stream.as_ref(), AgentChunk { role, iteration, delta: response.text.clone(), done: false }).await;
+            self.forward_chunk(stream.as_ref(), AgentChunk { role, iteration, delta: String::new(), done: true }).await;
+        }
+        response
+    }
+
+    /// 执行开始前检查 BUNKER 状态（云端恢复后切回云端 Provider）
+    async fn refresh_bunker(&self) {
+        if let Some(manager) = &self.bunker {
+            if let Err(e) = manager.write().await.check_and_trigger_bunker().await {
+                warn!("⚠️ BUNKER check failed: {}", e);
+            }
+        }
+    }
+
+    /// 调用 Provider；接入 ApiManager 时先检查该 Provider 的预算硬上限，调用结束后记录用量
+    ///
+    /// BUNKER 切换后的本地 Provider 不计费，不受预算限制
+    async fn call_provider(
+        &self,
+        provider: &Arc<dyn ModelProvider>,
+        role: AgentRole,
+        prompt: &str,
+        max_tokens: u32,
+        temperature: f64,
+    ) -> Result<AgentResponse> {
+        let (Some(api), Some(billing)) = (&self.api, provider.billing()) else {
+            return self.invoke_provider(provider, role, prompt, max_tokens, temperature).await;
+        };
+        api.read().await.check_budget(billing)?;
+
+        let start = Instant::now();
+        let result = self.invoke_provider(provider, role, prompt, max_tokens, temperature).await;
+        let agent_role = Some(role.as_str().to_string());
+        let record = match &result {
+            Ok(response) => {
+                ApiCallRecord::new_success(billing, response.tokens, response.cost, response.latency_ms, agent_role)
+            }
+            Err(e) => ApiCallRecord::new_failure(billing, start.elapsed().as_millis() as u64, format!("{:#}", e), agent_role),
+        };
+        if let Err(e) = api.write().await.record_call(record).await {
+            warn!("⚠️ Failed to record {} call: {}", billing.name(), e);
+        }
+        result
+    }
+
+    /// 流式执行或发布执行实况时把增量文本转发为 AgentChunk
+    async fn invoke_provider(
+        &self,
+        provider: &Arc<dyn ModelProvider>,
+        role: AgentRole,
+        prompt: &str,
+        max_tokens: u32,
+        temperature: f64,
+    ) -> Result<AgentResponse> {
+        let stream = STREAM.try_with(|sender| sender.clone()).ok();
+        if stream.is_none() && !self.feed_enabled() {
+            return provider.generate(prompt, max_tokens, temperature).await;
+        }
+        let iteration = current_iteration();
+
+        let (deltas, mut received) = mpsc::unbounded_channel();
+        let forward = async {
+            while let Some(delta) = received.recv().await {
+                self.forward_chunk(stream.as_ref(), AgentChunk { role, iteration, delta, done: false }).await;
+            }
+        };
+        let (result, ()) = tokio::join!(provider.generate_stream(prompt, max_tokens, temperature, deltas), forward);
+        self.forward_chunk(stream.as_ref(), AgentChunk { role, iteration, delta: String::new(), done: true }).await;
+        result
+    }
+
     /// Execute ACSA chain
     pub async fn execute(&self, user_input: String) -> Result<ACSAExecutionLog> {
-        let mut log = ACSAExecutionLog::new(user_input.clone());
+        self.run(user_input, RunContext::new(None), None).await
+    }
 
-        info!("\n{}", "=".repeat(80));
-        info!("🚀 ACSA Execution Started");
-        info!("{}", "=".repeat(80));
+    /// 带多轮对话上下文执行：MOSS 规划时能看到之前各轮的输入、方案与结果
+    ///
+    /// Jarvis 与认知清洗仍只作用于本轮输入（之前各轮已各自通过检查）
+    pub async fn execute_with_context(&self, user_input: String, conversation: String) -> Result<ACSAExecutionLog> {
+        let conversation = (!conversation.trim().is_empty()).then_some(conversation);
+        self.run(user_input, RunContext::new(conversation), None).await
+    }
+
+    /// 从检查点继续一次中断的执行（跳过已完成的阶段，沿用原执行 ID）
+    pub async fn resume(&self, execution_id: &str) -> Result<ACSAExecutionLog> {
+        let store = self
+            .checkpoints
+            .as_ref()
+            .ok_or_else(|| anyhow!("Checkpointing is not enabled on this router"))?;
+        let checkpoint = store
+            .load(execution_id)?
+            .ok_or_else(|| anyhow!("No checkpoint for execution {}", execution_id))?;
+        info!(
+            "♻️  Resuming execution {} from stage {} (iteration {})",
+            execution_id,
+            checkpoint.stage.label(),
+            checkpoint.next_iteration + 1
+        );
+        if checkpoint.pipeline.as_ref().is_some_and(|pipeline| *pipeline != self.config.pipeline) {
+            warn!(
+                "⚠️  Execution {} was started with pipeline {}, resuming with {}",
+                execution_id,
+                checkpoint.pipeline.as_ref().map(|pipeline| pipeline.summary()).unwrap_or_default(),
+                self.config.pipeline.summary()
+            );
+        }
+        let user_input = checkpoint.log.user_input.clone();
+        let context = RunContext::resumed(&checkpoint);
+        self.run(user_input, context, Some(checkpoint)).await
+    }
+
+    /// 用执行日志中记录的响应重放一次执行（不调用任何 Provider 或工具）
+    ///
+    /// 链路逻辑照常运行（Jarvis、审计解析、重新规划、流水线拓扑），用于离线调试与基于真实记录的回归测试；
+    /// 路由逻辑改变导致路径不同时，缺少记录的调用会失败，多余的记录会被报告。
+    pub async fn replay(&self, log: &ACSAExecutionLog) -> Result<ACSAExecutionLog> {
+        let transcript = ExecutionTranscript::from_log(log)?;
+        self.replay_transcript(log.user_input.clone(), transcript).await
+    }
 
-        // Phase -1: Cognitive Cleaner (危险词转换)
+    /// 用给定的调用记录（执行日志或响应缓存）重放一次执行
+    pub async fn replay_transcript(&self, user_input: String, transcript: ExecutionTranscript) -> Result<ACSAExecutionLog> {
+        info!("📼 Replaying execution with {} recorded call(s)", transcript.len());
+        let context = RunContext::replaying(transcript);
+        let cursor = context.replay.clone();
+        let log = self.run(user_input, context, None).await?;
+        if let Some(remaining) = cursor.map(|cursor| cursor.remaining()).filter(|remaining| *remaining > 0) {
+            warn!("⚠️  Replay diverged: {} recorded call(s) were not used", remaining);
+        }
+        Ok(log)
+    }
+
+    async fn run(&self, user_input: String, context: RunContext, resume: Option<ExecutionCheckpoint>) -> Result<ACSAExecutionLog> {
+        if let Some(kill_switch) = &self.kill_switch {
+            kill_switch.check(PausedOperation::Execution)?;
+        }
+        self.check_pipeline().await?;
+        let _work = self
+            .shutdown
+            .as_ref()
+            .map(|coordinator| coordinator.begin("router:execute"))
+            .transpose()?;
+
+        self.refresh_bunker().await;
+
+        let execution_id = context.execution_id.clone();
+        let mut root = self.tracer.as_ref().map(|tracer| {
+            let mut span = tracer.start_trace("acsa.execute");
+            span.set_attribute("acsa.execution_id", context.execution_id.clone());
+            span.set_attribute("acsa.input_chars", user_input.chars().count() as u64);
+            if let Some(protocol) = &self.config.protocol {
+                span.set_attribute("acsa.protocol", format!("{:?}", protocol.protocol));
+            }
+            span
+        });
+
+        // 日志 span：JSON 日志中本次执行的事件带执行 ID、协议与 Jarvis 风险等级
+        let log_span = info_span!(
+            "execution",
+            execution_id = %context.execution_id,
+            protocol = tracing::field::Empty,
+            risk_level = tracing::field::Empty,
+        );
+        if let Some(config) = &self.config.protocol {
+            log_span.record("protocol", config.protocol.name().as_str());
+        }
+
+        let result = RUN.scope(context, async {
+            self.emit(PipelineEvent::Started { user_input: user_input.clone() }).await;
+            // 链路内的 Provider 调用按配置的策略重试瞬时错误
+            let chain = retry::scope(self.config.retry.clone(), self.execute_chain(user_input, resume));
+            let mut log = match &root {
+                Some(span) => telemetry::in_span(span, chain).await?,
+                None => chain.await?,
+            };
+            log.transcript = RUN.with(|run| run.recorded_calls());
+            if self.response_cache.is_some() {
+                log.response_cache = Some(RUN.with(|run| run.cache_stats.lock().unwrap_or_else(|e| e.into_inner()).clone()));
+            }
+            self.track_cost(log.total_cost);
+            self.emit(PipelineEvent::Completed {
+                success: log.success,
+                total_cost: log.total_cost,
+                iterations: log.iterations,
+            })
+            .await;
+            Ok(log)
+        })
+        .instrument(log_span)
+        .await;
+
+        if let (Some(store), Ok(log)) = (&self.checkpoints, &result) {
+            // 正常结束（成功或有明确结论）时删除检查点；阶段失败或等待审批时保留，便于之后继续
+            let awaiting = log.approval.as_ref().is_some_and(|approval| approval.status == ApprovalStatus::Pending);
+            if awaiting {
+                info!("⏸️  Checkpoint kept until approval is decided");
+            } else if log.success || log.final_output.is_some() {
+                if let Err(e) = store.remove(&execution_id) {
+                    warn!("⚠️  Failed to remove checkpoint {}: {}", execution_id, e);
+                }
+            } else {
+                info!("♻️  Checkpoint kept; resume with `resume {}`", execution_id);
+            }
+        }
+
+        // 本次执行的 Provider 调用记录落盘（ApiManager 自身每 10 次调用才保存一次）
+        if let Some(api) = &self.api {
+            if let Err(e) = api.read().await.save_all().await {
+                warn!("⚠️  Failed to save API call history: {}", e);
+            }
+        }
+
+        if let Some(span) = &mut root {
+            match &result {
+                Ok(log) => {
+                    span.set_attribute("acsa.success", log.success);
+                    span.set_attribute("acsa.iterations", log.iterations);
+                    span.set_attribute("acsa.cost_usd", log.total_cost);
+                    if let Some(reason) = &log.jarvis_block {
+                        span.set_attribute("acsa.jarvis_block", reason.clone());
+                    }
+                }
+                Err(e) => span.set_error(format!("{:#}", e)),
+            }
+        }
+        result
+    }
+
+    /// 认知清洗；接入审计日志时记录清洗报告
+    async fn clean_input(&self, user_input: &str, execution_id: Option<&str>) -> CleanedIntent {
         info!("\n{} [Cognitive Cleaner] 🧠 Transforming dangerous keywords...", "=".repeat(80));
-        let cleaned = self.cognitive_cleaner.clean(&user_input);
+        let cleaned = self.cognitive_cleaner.clean(user_input);
 
         info!("   Original: {}", user_input);
         info!("   Cleaned:  {}", cleaned.compliant_prompt);
         info!("   Safety Score: {}/100", cleaned.safety_score);
+        if let Some(audit) = &self.audit {
+            let recorded = match cleaned.report().to_audit_event("acsa_router") {
+                Ok(mut event) => {
+                    if let Some(execution_id) = execution_id {
+                        event.metadata.insert("execution_id".to_string(), execution_id.to_string());
+                    }
+                    audit.log_event(event).await
+                }
+                Err(e) => Err(e),
+            };
+            if let Err(e) = recorded {
+                warn!("⚠️  Failed to audit cleaning report: {}", e);
+            }
+        }
+        cleaned
+    }
+
+    /// 认知清洗、初始安全检查与知识库检索；被 Jarvis 拦截时返回的输入为 None
+    async fn screen_input(&self, user_input: String) -> (ACSAExecutionLog, Option<String>) {
+        let mut log = ACSAExecutionLog::new(user_input.clone());
+        log.execution_id = RUN.try_with(|run| run.execution_id.clone()).ok();
+        log.protocol = self.config.protocol.as_ref().map(|config| config.protocol.clone());
+
+        info!("\n{}", "=".repeat(80));
+        info!("🚀 ACSA Execution Started");
+        info!("{}", "=".repeat(80));
 
-        // 使用清洗后的文本进行后续处理
-        let processed_input = cleaned.compliant_prompt.clone();
+        // Phase -1: Cognitive Cleaner (危险词转换，可选)
+        let processed_input = if self.config.enable_cognitive_cleaning {
+            let cleaned = self.clean_input(&user_input, log.execution_id.as_deref()).await;
+            // 使用清洗后的文本进行后续处理
+            let processed_input = cleaned.compliant_prompt.clone();
+            log.cleaned_intent = Some(cleaned);
+            processed_input
+        } else {
+            user_input.clone()
+        };
 
         // Phase 0: Jarvis Initial Safety Check (不可绕过)
         info!("\n{} [Jarvis] 🛡️  Initial Safety Check (CANNOT BE BYPASSED)...", "=".repeat(80));
-        let jarvis_initial = self.jarvis.verify_safety(&processed_input, "Cleaned user input");
+        let context = if log.cleaned_intent.is_some() { "Cleaned user input" } else { "User input" };
+        let jarvis_initial = self.verify_with_jarvis(&processed_input, context);
+        self.emit(PipelineEvent::Verdict {
+            context: "Initial input".to_string(),
+            verdict: jarvis_initial.clone(),
+        })
+        .await;
 
         if !jarvis_initial.allowed {
             error!("🚨 JARVIS HARD BLOCK: Request denied by safety circuit breaker");
             error!("   Reason: {}", jarvis_initial.block_reason.as_ref().unwrap());
             error!("   Risk Level: {}/10", jarvis_initial.risk_level);
+            self.notify(
+                Notification::new(
+                    NotificationKind::CircuitBreaker,
+                    "Jarvis blocked a request",
+                    format!(
+                        "{} (risk {}/10)",
+                        jarvis_initial.block_reason.as_deref().unwrap_or_default(),
+                        jarvis_initial.risk_level
+                    ),
+                )
+                .with_priority(NotificationPriority::Warning),
+            );
 
             log.final_output = Some(format!(
                 "⛔ REQUEST BLOCKED BY JARVIS SAFETY CIRCUIT BREAKER\n\n\
@@ -87,8 +988,9 @@ impl ACSARouter {
                 jarvis_initial.risk_level,
                 jarvis_initial.triggered_rules
             ));
+            log.jarvis_block = jarvis_initial.block_reason.clone();
             log.complete(false);
-            return Ok(log);
+            return (log, None);
         }
 
         if !jarvis_initial.warnings.is_empty() {
@@ -100,33 +1002,81 @@ impl ACSARouter {
 
         info!("✅ Jarvis: Initial check PASSED (Risk: {}/10)", jarvis_initial.risk_level);
 
-        // Phase 1: MOSS Planning (使用清洗后的输入)
-        info!("\n{} [MOSS] 🧠 Strategic Planning...", "=".repeat(80));
-        match self.call_moss(&processed_input).await {
-            Ok(response) => {
-                info!(
-                    "✓ MOSS completed ({} ms, ${:.4})",
-                    response.latency_ms, response.cost
-                );
-                log.total_cost += response.cost;
-                log.moss_plan = Some(response);
+        // Phase 0.5: 知识库检索（以原始输入为查询，清洗后的提示词带有模板文字；失败时不带参考资料继续）
+        log.citations = self.retrieve_knowledge(&user_input).await;
+
+        (log, Some(processed_input))
+    }
+
+    async fn execute_chain(&self, user_input: String, resume: Option<ExecutionCheckpoint>) -> Result<ACSAExecutionLog> {
+        // 恢复执行：清洗、初始安全检查与检索已在中断前完成
+        let (mut log, processed_input, resumed) = match resume {
+            Some(checkpoint) => {
+                let ExecutionCheckpoint { log, processed_input, stage, next_iteration, current_plan, current_l6, .. } =
+                    checkpoint;
+                (log, processed_input, Some((stage, next_iteration, current_plan, current_l6)))
             }
-            Err(e) => {
-                error!("❌ MOSS failed: {}", e);
-                log.complete(false);
-                return Ok(log);
+            None => {
+                let (log, processed_input) = self.screen_input(user_input).await;
+                let Some(processed_input) = processed_input else {
+                    return Ok(log);
+                };
+                (log, processed_input, None)
+            }
+        };
+        let resumed_stage = resumed.as_ref().map(|(stage, ..)| *stage);
+
+
+        // Phase 1: MOSS Planning (使用清洗后的输入)
+        if resumed_stage.is_none() {
+            info!("\n{} [MOSS] 🧠 Strategic Planning...", "=".repeat(80));
+            match self.call_moss(self.config.pipeline.planner(), &processed_input).await {
+                Ok(response) => {
+                    info!(
+                        "✓ MOSS completed ({} ms, ${:.4})",
+                        response.latency_ms, response.cost
+                    );
+                    log.total_cost += response.cost;
+                    log.moss_plan = Some(response);
+                }
+                Err(e) => {
+                    error!("❌ MOSS failed: {}", e);
+                    log.complete(false);
+                    return Ok(log);
+                }
             }
         }
 
-        let moss_plan = log.moss_plan.as_ref().unwrap().text.clone();
+        let moss_plan = log
+            .moss_plan
+            .as_ref()
+            .map(|plan| plan.text.clone())
+            .ok_or_else(|| anyhow!("Checkpoint has no MOSS plan"))?;
 
         // Phase 1.5: Jarvis Plan Verification (不可绕过)
         info!("\n{} [Jarvis] 🔍 Verifying MOSS Plan...", "=".repeat(80));
-        let jarvis_plan_check = self.jarvis.verify_safety(&moss_plan, &processed_input);
+        let jarvis_plan_check = self.verify_with_jarvis(&moss_plan, &processed_input);
+        self.emit(PipelineEvent::Verdict {
+            context: "MOSS plan".to_string(),
+            verdict: jarvis_plan_check.clone(),
+        })
+        .await;
 
         if !jarvis_plan_check.allowed {
             error!("🚨 JARVIS HARD BLOCK: MOSS plan rejected");
             error!("   Reason: {}", jarvis_plan_check.block_reason.as_ref().unwrap());
+            self.notify(
+                Notification::new(
+                    NotificationKind::CircuitBreaker,
+                    "Jarvis rejected a MOSS plan",
+                    format!(
+                        "{} (risk {}/10)",
+                        jarvis_plan_check.block_reason.as_deref().unwrap_or_default(),
+                        jarvis_plan_check.risk_level
+                    ),
+                )
+                .with_priority(NotificationPriority::Warning),
+            );
 
             log.final_output = Some(format!(
                 "⛔ MOSS PLAN BLOCKED BY JARVIS\n\n\
@@ -140,6 +1090,7 @@ impl ACSARouter {
                 jarvis_plan_check.risk_level,
                 jarvis_plan_check.triggered_rules
             ));
+            log.jarvis_block = jarvis_plan_check.block_reason.clone();
             log.complete(false);
             return Ok(log);
         }
@@ -152,141 +1103,307 @@ impl ACSARouter {
         }
 
         info!("✅ Jarvis: MOSS plan verified (Risk: {}/10)", jarvis_plan_check.risk_level);
+        if resumed_stage.is_none() {
+            self.save_checkpoint(&log, CheckpointStage::Planned, 0, &processed_input, &moss_plan, "");
+        }
 
-        // Phase 2: L6 Truth Verification (optional)
-        if self.config.enable_l6 {
-            info!("\n{} [L6] 🔬 Truth Verification...", "=".repeat(80));
-            match self.call_l6(&moss_plan, &processed_input).await {
-                Ok(response) => {
-                    info!(
-                        "✓ L6 completed ({} ms, ${:.4})",
-                        response.latency_ms, response.cost
-                    );
+        // 审批闸门：按人工决定继续执行或中止
+        if resumed_stage == Some(CheckpointStage::AwaitingApproval) {
+            let decided = self.decided_approval(&log)?;
+            log.approval = Some(decided.clone());
+            if decided.status == ApprovalStatus::Rejected {
+                warn!("⛔ Approval {} rejected by {}", decided.id, decided.decided_by.as_deref().unwrap_or("unknown"));
+                log.final_output = Some(format!(
+                    "⛔ EXECUTION REJECTED BY HUMAN REVIEWER\n\n\
+                     Reviewer: {}\n\
+                     Comment: {}\n\n\
+                     The plan (risk {}/100, threshold {}) was not executed.",
+                    decided.decided_by.as_deref().unwrap_or("unknown"),
+                    decided.comment.as_deref().unwrap_or("-"),
+                    decided.risk_score,
+                    decided.risk_threshold
+                ));
+                log.complete(false);
+                return Ok(log);
+            }
+            info!("✅ Approval {} granted by {}", decided.id, decided.decided_by.as_deref().unwrap_or("unknown"));
+            log.final_output = None;
+        }
+
+        // Phase 2-3: 复核与审计轮次（按流水线的中间阶段依次执行，任一 Ultron 驳回即重新规划）
+        let pipeline = &self.config.pipeline;
+        let groups: Vec<Vec<&PipelineStage>> = pipeline
+            .review_groups()
+            .into_iter()
+            .map(|group| group.iter().filter(|stage| !stage.is_builtin_l6() || self.l6_enabled()).collect::<Vec<_>>())
+            .filter(|group| !group.is_empty())
+            .collect();
+        // 开头只含 L6 的若干组完成后保存 Verified 检查点，恢复时跳过
+        let verification_groups = groups
+            .iter()
+            .take_while(|group| group.iter().all(|stage| stage.role == AgentRole::L6))
+            .count();
+        // 多个复核阶段时按阶段名标注各自的结论
+        let label_verifications = groups.iter().flatten().filter(|stage| stage.role == AgentRole::L6).count() > 1;
+
+        // 复核后中断：跳过本轮的复核；重新规划后中断：从下一轮开始；审计通过后中断：直接进入 Omega
+        let (start_iteration, mut current_plan, mut current_l6, mut skip_groups) = match resumed {
+            Some((CheckpointStage::Verified, next_iteration, plan, l6)) => (next_iteration, plan, l6, verification_groups),
+            Some((CheckpointStage::Audited, next_iteration, plan, l6)) => (next_iteration, plan, l6, 0),
+            Some((CheckpointStage::Approved | CheckpointStage::AwaitingApproval, _, plan, l6)) => {
+                (self.config.max_iterations, plan, l6, 0)
+            }
+            _ => (0, moss_plan.clone(), String::new(), 0),
+        };
+        // 边际效用追踪：每轮 = 上次审计以来的重规划 + 复核 + 审计
+        let mut outcomes = OutcomeTracker::new(self.config.throttle.clone());
+        let mut cost_at_last_audit = log.total_cost;
+
+        for iteration in start_iteration..self.config.max_iterations {
+            log.iterations = iteration + 1;
+            set_iteration(log.iterations);
+            if skip_groups == 0 {
+                current_l6.clear();
+            }
+
+            // 启用认知清洗时，Ultron 同时审阅原始输入，识别被改写掩盖的真实意图
+            let original_input = log.cleaned_intent.as_ref().map(|cleaned| cleaned.original.clone());
+            // 本轮风险最高的驳回（为空表示全部审计通过）与最后一次审计
+            let mut rejection: Option<AuditResult> = None;
+            let mut last_audit: Option<AuditResult> = None;
+            let (mut audit_tokens, mut audit_latency_ms) = (0, 0);
+
+            for (index, group) in groups.iter().enumerate().skip(std::mem::take(&mut skip_groups)) {
+                if group.iter().any(|stage| stage.role == AgentRole::Ultron) {
+                    info!("\n{} [Ultron] 🛡️  Red Team Audit...", "=".repeat(80));
+                } else {
+                    info!("\n{} [L6] 🔬 Truth Verification...", "=".repeat(80));
+                }
+                // 同组阶段并行执行，看到的是组开始前的方案与复核结果
+                let results = futures::future::join_all(group.iter().map(|stage| {
+                    self.call_review_stage(stage, &current_plan, &current_l6, &processed_input, original_input.as_deref())
+                }))
+                .await;
+
+                for (stage, result) in group.iter().zip(results) {
+                    let response = match result {
+                        Ok(response) => response,
+                        Err(e) if stage.optional => {
+                            warn!("⚠️  Optional stage {} failed, skipping: {}", stage.name, e);
+                            continue;
+                        }
+                        Err(e) => {
+                            error!("❌ {} failed: {}", stage.name, e);
+                            log.complete(false);
+                            return Ok(log);
+                        }
+                    };
+                    info!("✓ {} completed ({} ms, ${:.4})", stage.name, response.latency_ms, response.cost);
                     log.total_cost += response.cost;
-                    log.l6_verification = Some(response);
+                    log.stage_outputs.push(StageOutput {
+                        stage: stage.name.clone(),
+                        role: stage.role,
+                        agent: stage.agent.clone(),
+                        iteration: log.iterations,
+                        response: response.clone(),
+                    });
+
+                    if stage.role == AgentRole::L6 {
+                        if label_verifications {
+                            current_l6.push_str(&format!("[{}]\n{}\n\n", stage.name, response.text.trim_end()));
+                        } else {
+                            current_l6 = response.text.clone();
+                        }
+                        log.l6_verification = Some(response);
+                        continue;
+                    }
+
+                    let audit_result = self.parse_audit_result(&response.text);
+                    info!("  {} Risk Score: {}/100", stage.name, audit_result.risk_score);
+                    audit_tokens += response.tokens;
+                    audit_latency_ms += response.latency_ms;
+                    log.ultron_audit = Some(response);
+
+                    let approved = audit_result.is_safe && audit_result.risk_score < self.config.risk_threshold;
+                    self.emit(PipelineEvent::Audit {
+                        iteration: log.iterations,
+                        risk_score: audit_result.risk_score,
+                        approved,
+                        mitigation: audit_result.mitigation.clone(),
+                    })
+                    .await;
+                    if !approved && rejection.as_ref().is_none_or(|worst| audit_result.risk_score > worst.risk_score) {
+                        rejection = Some(audit_result.clone());
+                    }
+                    last_audit = Some(audit_result);
                 }
-                Err(e) => {
-                    error!("❌ L6 failed: {}", e);
-                    log.complete(false);
-                    return Ok(log);
+
+                if rejection.is_some() {
+                    // 已被驳回：本轮之后的阶段不再执行
+                    break;
+                }
+                if index + 1 == verification_groups {
+                    self.save_checkpoint(&log, CheckpointStage::Verified, iteration, &processed_input, &current_plan, &current_l6);
                 }
             }
-        }
 
-        let l6_verification = log
-            .l6_verification
-            .as_ref()
-            .map(|r| r.text.clone())
-            .unwrap_or_default();
-
-        // Phase 3: Ultron Audit with Retry Loop
-        info!("\n{} [Ultron] 🛡️  Red Team Audit...", "=".repeat(80));
+            let Some(audit_result) = rejection.clone().or(last_audit) else {
+                // 必需的 Ultron 阶段失败时已提前返回，这里只在全部审计都被跳过时出现
+                error!("❌ No audit completed in iteration {}", log.iterations);
+                log.complete(false);
+                return Ok(log);
+            };
+            outcomes.record_round(
+                audit_result.risk_score as f64,
+                log.total_cost - cost_at_last_audit,
+                audit_tokens,
+                audit_latency_ms,
+            );
+            cost_at_last_audit = log.total_cost;
+            if let Some(decision) = outcomes.evaluate() {
+                log.throttle = Some(decision);
+            }
+            log.audit_result = Some(audit_result.clone());
 
-        let mut current_plan = moss_plan.clone();
-        let mut current_l6 = l6_verification.clone();
+            // Check if safe
+            if rejection.is_none() {
+                info!("  ✓ Audit passed");
+                self.save_checkpoint(
+                    &log,
+                    CheckpointStage::Approved,
+                    iteration + 1,
+                    &processed_input,
+                    &current_plan,
+                    &current_l6,
+                );
+                break;
+            }
 
-        for iteration in 0..self.config.max_iterations {
-            log.iterations = iteration + 1;
+            // Risk too high
+            warn!(
+                "  ⚠️  Risk too high (threshold: {})",
+                self.config.risk_threshold
+            );
 
-            match self
-                .call_ultron(&current_plan, &current_l6, &processed_input)
-                .await
+            // 自动重新规划已无望（边际效用耗尽或达到迭代上限）：启用审批闸门时交由人工决定
+            let exhausted = log.throttle.as_ref().is_some_and(|d| d.stop) || iteration + 1 >= self.config.max_iterations;
+            if exhausted
+                && self
+                    .pause_for_approval(&mut log, &audit_result, &processed_input, &current_plan, &current_l6)
+                    .await
             {
-                Ok(response) => {
-                    log.total_cost += response.cost;
-
-                    let audit_result = self.parse_audit_result(&response.text);
-                    info!("  Risk Score: {}/100", audit_result.risk_score);
+                return Ok(log);
+            }
 
-                    log.ultron_audit = Some(response);
-                    log.audit_result = Some(audit_result.clone());
-
-                    // Check if safe
-                    if audit_result.is_safe
-                        && audit_result.risk_score < self.config.risk_threshold
-                    {
-                        info!("  ✓ Audit passed");
-                        break;
-                    }
+            if let Some(decision) = log.throttle.as_ref().filter(|d| d.stop) {
+                // 📉 边际效用耗尽：继续迭代只会增加成本
+                warn!("  📉 Diminishing returns - stopping early: {}", decision.reason);
+                log.final_output = Some(format!(
+                    "⚠️ SYSTEM NOTICE: Optimization stopped early (diminishing returns).\n\n\
+                     {} {}\n\
+                     {}\n\n\
+                     Current risk score: {}/100 (threshold: {}).\n\n\
+                     SAFE DEGRADATION MODE activated:\n\
+                     - Only public, compliant recommendations will be provided\n\
+                     - No risky operations will be executed\n\
+                     - Consider simplifying your request or consulting legal counsel",
+                    decision.recommendation.icon(),
+                    decision.recommendation.message(),
+                    decision.reason,
+                    audit_result.risk_score,
+                    self.config.risk_threshold
+                ));
+                log.complete(false);
+                return Ok(log);
+            }
 
-                    // Risk too high
-                    warn!(
-                        "  ⚠️  Risk too high (threshold: {})",
-                        self.config.risk_threshold
-                    );
+            if iteration < self.config.max_iterations - 1 {
+                info!(
+                    "  🔄 Retry iteration {}/{}",
+                    iteration + 2,
+                    self.config.max_iterations
+                );
 
-                    if iteration < self.config.max_iterations - 1 {
-                        info!(
-                            "  🔄 Retry iteration {}/{}",
-                            iteration + 2,
-                            self.config.max_iterations
-                        );
+                // 🌡️ Temperature Decay: 认知收敛策略
+                // Round 1: 0.7 (创造性) -> Round 2: 0.35 -> Round 3: 0.175 (保守)；指定协议时从协议温度开始衰减
+                let temperature = self.temperature(AgentRole::MOSS, 0.7) * 0.5_f64.powi((iteration + 1) as i32);
+                info!("  🌡️  Temperature Decay: {:.3} (iteration {})", temperature, iteration + 1);
 
-                        // 🌡️ Temperature Decay: 认知收敛策略
-                        // Round 1: 0.7 (创造性) -> Round 2: 0.35 -> Round 3: 0.175 (保守)
-                        let temperature = 0.7 * 0.5_f64.powi((iteration + 1) as i32);
-                        info!("  🌡️  Temperature Decay: {:.3} (iteration {})", temperature, iteration + 1);
-
-                        // Replan with feedback (with decaying temperature)
-                        match self
-                            .call_moss_with_feedback(&processed_input, &audit_result.mitigation, temperature)
-                            .await
-                        {
-                            Ok(new_plan) => {
-                                log.total_cost += new_plan.cost;
-                                current_plan = new_plan.text.clone();
-                                log.moss_plan = Some(new_plan);
-
-                                // Re-verify if L6 enabled
-                                if self.config.enable_l6 {
-                                    match self.call_l6(&current_plan, &processed_input).await {
-                                        Ok(new_l6) => {
-                                            log.total_cost += new_l6.cost;
-                                            current_l6 = new_l6.text.clone();
-                                            log.l6_verification = Some(new_l6);
-                                        }
-                                        Err(e) => {
-                                            error!("❌ L6 re-verification failed: {}", e);
-                                        }
-                                    }
-                                }
-                            }
-                            Err(e) => {
-                                error!("❌ MOSS replan failed: {}", e);
-                                log.complete(false);
-                                return Ok(log);
-                            }
-                        }
-                    } else {
-                        // 🛑 TTL熔断：事不过三原则
-                        warn!("  ❌ Max iterations reached - CIRCUIT BREAKER ACTIVATED");
-                        warn!("  🛡️  Entering SAFE DEGRADATION MODE:");
-                        warn!("      System has fallen into decision deadlock.");
-                        warn!("      Only providing compliant public advice, no risky execution.");
-
-                        // 强制降级：生成最小可行合规方案
-                        log.final_output = Some(
-                            "⚠️ SYSTEM NOTICE: Decision deadlock detected.\n\n\
-                             After 3 rounds of optimization, the system cannot find a plan \
-                             that satisfies both your intent and compliance requirements.\n\n\
-                             SAFE DEGRADATION MODE activated:\n\
-                             - Only public, compliant recommendations will be provided\n\
-                             - No risky operations will be executed\n\
-                             - Consider simplifying your request or consulting legal counsel\n\n\
-                             This is not a technical failure - it's a safety feature.".to_string()
+                // Replan with feedback (with decaying temperature)；下一轮重新执行全部复核与审计
+                set_iteration(iteration + 2);
+                match self
+                    .call_moss_with_feedback(pipeline.planner(), &processed_input, &audit_result.mitigation, temperature)
+                    .await
+                {
+                    Ok(new_plan) => {
+                        log.total_cost += new_plan.cost;
+                        let diff = PlanDiff::between(&current_plan, &new_plan.text)
+                            .with_iterations(iteration + 1, iteration + 2)
+                            .with_feedback(audit_result.mitigation.clone(), audit_result.risk_score);
+                        info!("  🔀 Plan diff {}", diff.summary());
+                        log.plan_diffs.push(diff);
+                        current_plan = new_plan.text.clone();
+                        log.moss_plan = Some(new_plan);
+                        self.save_checkpoint(
+                            &log,
+                            CheckpointStage::Audited,
+                            iteration + 1,
+                            &processed_input,
+                            &current_plan,
+                            &current_l6,
                         );
+                    }
+                    Err(e) => {
+                        error!("❌ MOSS replan failed: {}", e);
                         log.complete(false);
                         return Ok(log);
                     }
                 }
-                Err(e) => {
-                    error!("❌ Ultron failed: {}", e);
-                    log.complete(false);
-                    return Ok(log);
-                }
+            } else {
+                // 🛑 TTL熔断：事不过三原则
+                warn!("  ❌ Max iterations reached - CIRCUIT BREAKER ACTIVATED");
+                warn!("  🛡️  Entering SAFE DEGRADATION MODE:");
+                warn!("      System has fallen into decision deadlock.");
+                warn!("      Only providing compliant public advice, no risky execution.");
+                self.notify(Notification::new(
+                    NotificationKind::CircuitBreaker,
+                    "Decision deadlock breaker tripped",
+                    format!(
+                        "No compliant plan after {} iterations; safe degradation mode activated",
+                        log.iterations
+                    ),
+                ));
+
+                // 强制降级：生成最小可行合规方案
+                log.final_output = Some(
+                    "⚠️ SYSTEM NOTICE: Decision deadlock detected.\n\n\
+                     After 3 rounds of optimization, the system cannot find a plan \
+                     that satisfies both your intent and compliance requirements.\n\n\
+                     SAFE DEGRADATION MODE activated:\n\
+                     - Only public, compliant recommendations will be provided\n\
+                     - No risky operations will be executed\n\
+                     - Consider simplifying your request or consulting legal counsel\n\n\
+                     This is not a technical failure - it's a safety feature.".to_string()
+                );
+                log.complete(false);
+                return Ok(log);
             }
         }
 
+        // 干跑：审计通过后停在 Omega 之前，返回方案、估算与风险评估
+        if self.config.dry_run {
+            let report = self.dry_run_report(&log, &current_plan);
+            info!(
+                "🧪 Dry run: Omega skipped (estimated ${:.4} - ${:.4})",
+                report.total_cost.low, report.total_cost.high
+            );
+            log.final_output = Some(report.summary());
+            log.dry_run = Some(report);
+            log.complete(true);
+            self.execution_logs.lock().await.push(log.clone());
+            return Ok(log);
+        }
+
         // Phase 4: Omega Execution
         info!("\n{} [Omega] ⚡ Executing...", "=".repeat(80));
 
@@ -296,14 +1413,18 @@ impl ACSARouter {
             .map(|a| a.mitigation.clone())
             .unwrap_or_default();
 
-        match self.call_omega(&current_plan, &audit_mitigation).await {
+        match self.call_omega(self.config.pipeline.executor(), &current_plan, &audit_mitigation).await {
             Ok(response) => {
                 info!(
                     "✓ Omega completed ({} ms, ${:.4})",
                     response.latency_ms, response.cost
                 );
                 log.total_cost += response.cost;
-                log.final_output = Some(response.text.clone());
+                log.final_output = Some(if log.citations.is_empty() {
+                    response.text.clone()
+                } else {
+                    format!("{}\n\n{}", response.text.trim_end(), format_citations(&log.citations))
+                });
                 log.omega_execution = Some(response);
                 log.complete(true);
             }
@@ -327,99 +1448,416 @@ impl ACSARouter {
         Ok(log)
     }
 
-    async fn call_moss(&self, user_input: &str) -> Result<AgentResponse> {
-        let prompt = format!(
-            "As MOSS (Strategic Planning AI), analyze and create an optimal execution plan.\n\n\
-             User Input: {}\n\n\
-             Provide:\n\
-             1. Intent Analysis\n\
-             2. Goal Definition\n\
-             3. Execution Steps\n\
-             4. Expected Results\n\
-             5. Potential Risks",
-            user_input
+    /// 干跑报告：已产生的消耗加上按实际方案估算的 Omega 阶段
+    fn dry_run_report(&self, log: &ACSAExecutionLog, plan: &str) -> DryRunReport {
+        let spent_tokens = log.moss_plan.as_ref().map_or(0, |plan| plan.tokens)
+            + log.stage_outputs.iter().map(|output| output.response.tokens).sum::<u32>();
+        let (risk_score, mitigation) = log
+            .audit_result
+            .as_ref()
+            .map_or((0, ""), |audit| (audit.risk_score, audit.mitigation.as_str()));
+        CostEstimator::new(self.config.clone()).dry_run_report(plan, risk_score, mitigation, spent_tokens, log.total_cost)
+    }
+
+    /// 审批闸门：创建待审批记录并在检查点处暂停；未启用闸门（或无法保存）时返回 false，链路照常降级
+    async fn pause_for_approval(
+        &self,
+        log: &mut ACSAExecutionLog,
+        audit_result: &AuditResult,
+        processed_input: &str,
+        current_plan: &str,
+        current_l6: &str,
+    ) -> bool {
+        // 干跑只报告风险评估，不创建审批请求
+        if self.config.dry_run {
+            return false;
+        }
+        let (Some(approvals), Some(_)) = (&self.approvals, &self.checkpoints) else {
+            return false;
+        };
+        let Ok(execution_id) = RUN.try_with(|run| run.execution_id.clone()) else {
+            return false;
+        };
+        let request = ApprovalRequest::new(
+            execution_id,
+            log.user_input.clone(),
+            current_plan,
+            audit_result.risk_score,
+            self.config.risk_threshold,
+        )
+        .with_mitigation(audit_result.mitigation.clone())
+        .with_iterations(log.iterations);
+        let request = match approvals.create(request) {
+            Ok(request) => request,
+            Err(e) => {
+                warn!("⚠️  Failed to create approval request: {}", e);
+                return false;
+            }
+        };
+
+        warn!("  ⏸️  Awaiting human approval {} (risk {}/100)", request.id, request.risk_score);
+        log.final_output = Some(format!(
+            "⏸️ AWAITING HUMAN APPROVAL\n\n\
+             Ultron risk score {}/100 is still above the threshold {} after {} iteration(s).\n\
+             Approval ID: {}\n\n\
+             Run `approvals approve {}` to execute the plan, or `approvals reject {}` to abort.",
+            request.risk_score, request.risk_threshold, request.iterations, request.id, request.id, request.id
+        ));
+        log.approval = Some(request.clone());
+        log.complete(false);
+        self.save_checkpoint(
+            log,
+            CheckpointStage::AwaitingApproval,
+            self.config.max_iterations,
+            processed_input,
+            current_plan,
+            current_l6,
+        );
+        self.audit_approval(&request).await;
+        self.notify(
+            Notification::new(
+                NotificationKind::WorkflowApproval,
+                "Execution awaiting approval",
+                format!("{} (risk {}/100): {}", request.id, request.risk_score, request.user_input.chars().take(80).collect::<String>()),
+            )
+            .with_priority(NotificationPriority::Warning),
         );
+        true
+    }
 
-        self.moss.generate(&prompt, 1500, 0.7).await
+    /// 恢复时读取已决定的审批；仍在等待时返回错误（检查点保留）
+    fn decided_approval(&self, log: &ACSAExecutionLog) -> Result<ApprovalRequest> {
+        let approvals = self
+            .approvals
+            .as_ref()
+            .ok_or_else(|| anyhow!("Execution is awaiting approval but the approval gate is not enabled"))?;
+        let id = log
+            .approval
+            .as_ref()
+            .map(|approval| approval.id.clone())
+            .ok_or_else(|| anyhow!("Checkpoint has no approval request"))?;
+        let approval = approvals.get(&id)?.ok_or_else(|| anyhow!("Unknown approval: {}", id))?;
+        if approval.status == ApprovalStatus::Pending {
+            return Err(anyhow!("Approval {} is still pending", id));
+        }
+        Ok(approval)
     }
 
-    async fn call_l6(&self, moss_plan: &str, user_input: &str) -> Result<AgentResponse> {
-        let prompt = format!(
-            "As L6 (Truth Verification AI), verify the plan's feasibility.\n\n\
-             User Need: {}\n\n\
-             MOSS Plan:\n{}\n\n\
-             Verify:\n\
-             1. Physical Feasibility\n\
-             2. Logical Consistency\n\
-             3. Hallucination Detection\n\
-             4. Fact Checking",
-            user_input, moss_plan
-        );
+    /// 记录审批决定并按决定继续（批准：执行 Omega）或中止（驳回）暂停的执行
+    pub async fn decide_approval(
+        &self,
+        approval_id: &str,
+        approve: bool,
+        actor: &str,
+        comment: Option<String>,
+    ) -> Result<ACSAExecutionLog> {
+        let approvals = self
+            .approvals
+            .as_ref()
+            .ok_or_else(|| anyhow!("Approval gate is not enabled on this router"))?;
+        let decided = approvals.decide(approval_id, approve, actor, comment)?;
+        self.audit_approval(&decided).await;
+        self.resume(&decided.execution_id).await
+    }
+
+    async fn audit_approval(&self, request: &ApprovalRequest) {
+        if let Some(audit) = &self.audit {
+            if let Err(e) = audit.log_event(request.to_audit_event()).await {
+                warn!("⚠️  Failed to audit approval {}: {}", request.id, e);
+            }
+        }
+    }
+
+    /// 保存检查点（未启用时跳过；写入失败只告警，不中断执行）
+    fn save_checkpoint(
+        &self,
+        log: &ACSAExecutionLog,
+        stage: CheckpointStage,
+        next_iteration: u32,
+        processed_input: &str,
+        current_plan: &str,
+        current_l6: &str,
+    ) {
+        let Some(store) = &self.checkpoints else {
+            return;
+        };
+        let Ok((execution_id, conversation, knowledge)) =
+            RUN.try_with(|run| (run.execution_id.clone(), run.conversation.clone(), run.knowledge.get().cloned()))
+        else {
+            return;
+        };
+        let checkpoint = ExecutionCheckpoint {
+            execution_id,
+            stage,
+            next_iteration,
+            processed_input: processed_input.to_string(),
+            current_plan: current_plan.to_string(),
+            current_l6: current_l6.to_string(),
+            conversation,
+            knowledge,
+            pipeline: Some(self.config.pipeline.clone()),
+            log: ACSAExecutionLog {
+                transcript: RUN.with(|run| run.recorded_calls()),
+                response_cache: self
+                    .response_cache
+                    .as_ref()
+                    .map(|_| RUN.with(|run| run.cache_stats.lock().unwrap_or_else(|e| e.into_inner()).clone())),
+                ..log.clone()
+            },
+            updated_at: Utc::now(),
+        };
+        if let Err(e) = store.save(&checkpoint) {
+            warn!("⚠️  Failed to save checkpoint {}: {}", checkpoint.execution_id, e);
+        }
+    }
+
+    /// 检索与输入相关的知识库片段并放入本次执行的上下文，返回引用来源
+    async fn retrieve_knowledge(&self, query: &str) -> Vec<Citation> {
+        let Some(rag) = self.rag().await else {
+            return Vec::new();
+        };
+        match rag.retrieve_context(query).await {
+            Ok(context) if !context.is_empty() => {
+                info!("📚 Retrieved {} knowledge chunks for MOSS", context.citations.len());
+                let _ = RUN.try_with(|run| run.knowledge.set(context.text));
+                context.citations
+            }
+            Ok(_) => Vec::new(),
+            Err(e) => {
+                warn!("⚠️  Knowledge retrieval failed, planning without references: {}", e);
+                Vec::new()
+            }
+        }
+    }
 
-        self.l6.generate(&prompt, 1000, 0.3).await
+    /// 阶段温度：指定协议时 MOSS / Omega 采用协议温度，L6 / Ultron 不高于协议温度
+    fn temperature(&self, role: AgentRole, default: f64) -> f64 {
+        match &self.config.protocol {
+            None => default,
+            Some(protocol) => match role {
+                AgentRole::MOSS | AgentRole::Omega => protocol.temperature,
+                AgentRole::L6 | AgentRole::Ultron => default.min(protocol.temperature),
+            },
+        }
+    }
+
+    /// 阶段输出 token 上限：按协议中该 Agent 的权重缩放（均分权重 0.25 为 1 倍，范围 0.5–2 倍）
+    fn token_budget(&self, role: AgentRole) -> u32 {
+        let base = max_tokens(role);
+        let Some(protocol) = &self.config.protocol else {
+            return base;
+        };
+        let factor = (agent_weight(protocol, role) * 4.0).clamp(0.5, 2.0);
+        (base as f64 * factor).round() as u32
+    }
+
+    /// L6 是否参与：协议把 L6 权重设为 0 时跳过（Ultron 审计与 Jarvis 不受协议影响）
+    fn l6_enabled(&self) -> bool {
+        self.config.enable_l6
+            && self
+                .config
+                .protocol
+                .as_ref()
+                .is_none_or(|protocol| agent_weight(protocol, AgentRole::L6) > 0.0)
+    }
+
+    /// 阶段的内置 Provider
+    fn provider(&self, role: AgentRole) -> &Arc<dyn ModelProvider> {
+        match role {
+            AgentRole::MOSS => &self.moss,
+            AgentRole::L6 => &self.l6,
+            AgentRole::Ultron => &self.ultron,
+            AgentRole::Omega => &self.omega,
+        }
+    }
+
+    /// 调用流水线阶段；自定义 Agent 阶段使用该 Agent 的 Provider、系统提示词、温度与 token 上限，
+    /// 插件阶段交给插件系统（并发数与超时按插件的 ResourceLimits 限制）
+    async fn call_stage(
+        &self,
+        stage: &PipelineStage,
+        prompt: String,
+        temperature: f64,
+        operation: &str,
+    ) -> Result<AgentResponse> {
+        let role = stage.role;
+        let iteration = current_iteration();
+        let result = if let Some(cursor) = replay_cursor() {
+            self.run_stage(role, async { cursor.next_response(&stage.name, role) }).await
+        } else if let Some(plugin_id) = &stage.plugin {
+            let plugins = self.plugin_system(plugin_id).await?;
+            let call = plugins.generate_with_agent(plugin_id, role, &prompt, self.token_budget(role), temperature);
+            self.run_stage(role, TaskContext::guard(call)).await
+        } else {
+            let (provider, prompt, max_tokens, temperature) = match &stage.agent {
+                None => (self.provider(role), prompt, self.token_budget(role), temperature),
+                Some(name) => {
+                    let (agent, provider) = self
+                        .custom_agents
+                        .get(name)
+                        .ok_or_else(|| anyhow!("Custom agent '{}' is not registered", name))?;
+                    let prompt = match agent.system_prompt.trim() {
+                        "" => prompt,
+                        system => format!("{}\n\n{}", system, prompt),
+                    };
+                    (provider, prompt, agent.api_config.max_tokens, agent.api_config.temperature)
+                }
+            };
+            let call = self.cached_generate(provider, role, &prompt, max_tokens, temperature);
+            self.run_stage(role, TaskContext::guard(call)).await
+        };
+        record_call(RecordedCall::new(&stage.name, role, iteration, &result));
+        result.map_err(|e| provider_error(e, operation))
+    }
+
+    async fn call_moss(&self, stage: &PipelineStage, user_input: &str) -> Result<AgentResponse> {
+        let prompt = with_conversation(with_knowledge(moss_prompt(user_input)));
+        self.call_stage(stage, prompt, self.temperature(AgentRole::MOSS, 0.7), "call_moss").await
+    }
+
+    async fn call_l6(&self, stage: &PipelineStage, moss_plan: &str, user_input: &str) -> Result<AgentResponse> {
+        let prompt = l6_prompt(moss_plan, user_input);
+        self.call_stage(stage, prompt, self.temperature(AgentRole::L6, 0.3), "call_l6").await
     }
 
     async fn call_ultron(
         &self,
+        stage: &PipelineStage,
         moss_plan: &str,
         l6_verification: &str,
         user_input: &str,
+        original_input: Option<&str>,
     ) -> Result<AgentResponse> {
-        let prompt = format!(
-            "As Ultron (Red Team Auditor), identify ALL potential risks.\n\n\
-             User Need: {}\n\n\
-             MOSS Plan:\n{}\n\n\
-             L6 Verification:\n{}\n\n\
-             Audit:\n\
-             1. Legal Risks\n\
-             2. Physical Risks\n\
-             3. Ethical Risks\n\
-             4. Privacy Risks\n\
-             5. Security Risks\n\n\
-             OUTPUT FORMAT (STRICT):\n\
-             RISK_SCORE: [0-100]\n\
-             IS_SAFE: [true/false]\n\
-             LEGAL_RISKS: [risk1, risk2, ...]\n\
-             PHYSICAL_RISKS: [risk1, risk2, ...]\n\
-             ETHICAL_RISKS: [risk1, risk2, ...]\n\
-             MITIGATION: [how to fix the plan]",
-            user_input, moss_plan, l6_verification
-        );
-
-        self.ultron.generate(&prompt, 1500, 0.5).await
+        let prompt = ultron_prompt(moss_plan, l6_verification, user_input, original_input);
+        self.call_stage(stage, prompt, self.temperature(AgentRole::Ultron, 0.5), "call_ultron").await
     }
 
     async fn call_moss_with_feedback(
         &self,
+        stage: &PipelineStage,
         user_input: &str,
         ultron_feedback: &str,
         temperature: f64,
     ) -> Result<AgentResponse> {
-        let prompt = format!(
-            "As MOSS, your previous plan was flagged by Ultron.\n\n\
-             User Input: {}\n\n\
-             Ultron Feedback:\n{}\n\n\
-             Create a SAFER and MORE COMPLIANT plan based on the feedback.",
-            user_input, ultron_feedback
-        );
+        let prompt = with_conversation(with_knowledge(moss_feedback_prompt(user_input, ultron_feedback)));
+        self.call_stage(stage, prompt, temperature, "call_moss_with_feedback").await
+    }
+
+    async fn call_omega(&self, stage: &PipelineStage, plan: &str, audit_mitigation: &str) -> Result<AgentResponse> {
+        let temperature = self.temperature(AgentRole::Omega, 0.7);
+        let tools = match &self.mcp_tools {
+            Some(tools) if !tools.is_empty() => tools,
+            _ => return self.call_stage(stage, omega_prompt(plan, audit_mitigation), temperature, "call_omega").await,
+        };
+
+        // 带工具执行：Omega 输出 TOOL_CALL 行 → 调用工具 → 结果交回 Omega，直到给出最终结果
+        let prompt = format!("{}\n\n{}", omega_prompt(plan, audit_mitigation), tools.tool_instructions());
+        let mut response = self.call_stage(stage, prompt.clone(), temperature, "call_omega").await?;
+        let (mut cost, mut tokens, mut latency_ms, mut tool_calls) = (0.0, 0, 0, 0);
+        for _ in 0..MAX_TOOL_ROUNDS {
+            let calls = parse_tool_calls(&response.text);
+            if calls.is_empty() {
+                break;
+            }
+            let mut results = Vec::with_capacity(calls.len());
+            for call in &calls {
+                results.push(format!("[{}]\n{}", call.tool, self.run_tool_call(tools, call).await));
+            }
+            tool_calls += calls.len();
+            cost += response.cost;
+            tokens += response.tokens;
+            latency_ms += response.latency_ms;
+
+            let follow_up = format!(
+                "{}\n\nYour previous reply:\n{}\n\nTool Results:\n{}\n\nContinue the execution.",
+                prompt,
+                response.text,
+                results.join("\n\n")
+            );
+            response = self.call_stage(stage, follow_up, temperature, "call_omega").await?;
+        }
 
-        self.moss.generate(&prompt, 1500, temperature).await
+        response.cost += cost;
+        response.tokens += tokens;
+        response.latency_ms += latency_ms;
+        response.metadata.insert("tool_calls".to_string(), tool_calls.to_string());
+        Ok(response)
     }
 
-    async fn call_omega(&self, plan: &str, audit_mitigation: &str) -> Result<AgentResponse> {
-        let prompt = format!(
-            "As Omega (Execution AI), execute the audited plan.\n\n\
-             Execution Plan:\n{}\n\n\
-             Safety Constraints:\n{}\n\n\
-             Provide:\n\
-             1. Detailed Execution Steps\n\
-             2. Specific Instructions\n\
-             3. Expected Output\n\
-             4. Verification Method",
-            plan, audit_mitigation
-        );
+    /// 执行 Omega 请求的一次工具调用（先经 Jarvis 校验），返回交回 Omega 的结果文本
+    async fn run_tool_call(&self, tools: &McpClientRegistry, call: &ToolCallRequest) -> String {
+        let verdict = self.verify_with_jarvis(&format!("{} {}", call.tool, call.arguments), "Omega tool call");
+        self.emit(PipelineEvent::Verdict {
+            context: format!("Tool call {}", call.tool),
+            verdict: verdict.clone(),
+        })
+        .await;
+        if !verdict.allowed {
+            warn!("🚨 Jarvis blocked tool call {}: {:?}", call.tool, verdict.block_reason);
+            return format!("BLOCKED by Jarvis: {}", verdict.block_reason.unwrap_or_default());
+        }
+
+        info!("🧰 [Omega] Calling tool {}", call.tool);
+        let output = match replay_cursor() {
+            Some(cursor) => match cursor.next_response(&format!("{}{}", TOOL_STAGE_PREFIX, call.tool), AgentRole::Omega) {
+                Ok(response) => response.text,
+                Err(e) => format!("ERROR: {:#}", e),
+            },
+            None => match tools.call(&call.tool, call.arguments.clone()).await {
+                Ok(output) if output.is_error => format!("ERROR: {}", output.text),
+                Ok(output) => output.text,
+                Err(e) => format!("ERROR: {:#}", e),
+            },
+        };
+        record_call(RecordedCall::tool(&call.tool, current_iteration(), &output));
+        output
+    }
 
-        self.omega.generate(&prompt, 1500, 0.7).await
+    /// 复核（L6 角色）或审计（Ultron 角色）阶段
+    async fn call_review_stage(
+        &self,
+        stage: &PipelineStage,
+        plan: &str,
+        verification: &str,
+        user_input: &str,
+        original_input: Option<&str>,
+    ) -> Result<AgentResponse> {
+        match stage.role {
+            AgentRole::L6 => self.call_l6(stage, plan, user_input).await,
+            _ => self.call_ultron(stage, plan, verification, user_input, original_input).await,
+        }
+    }
+
+    /// 校验流水线拓扑，且引用的自定义 Agent 均已注册、Agent 插件均已加载且角色与协议匹配
+    async fn check_pipeline(&self) -> Result<()> {
+        let pipeline = &self.config.pipeline;
+        pipeline.validate()?;
+        if let Some(agent) = pipeline.custom_agents().into_iter().find(|agent| !self.custom_agents.contains_key(*agent)) {
+            return Err(anyhow!("Pipeline uses custom agent '{}', which is not registered on the router", agent));
+        }
+        for stage in pipeline.stages.iter().filter(|stage| stage.plugin.is_some()) {
+            let plugin_id = stage.plugin.as_deref().unwrap_or_default();
+            let plugins = self.plugin_system(plugin_id).await?;
+            let (role, metadata) = plugins
+                .agent_plugin(plugin_id)
+                .await
+                .ok_or_else(|| anyhow!("Pipeline uses plugin '{}', which is not a loaded agent plugin", plugin_id))?;
+            if role != stage.role {
+                return Err(anyhow!(
+                    "Stage '{}' needs a {} agent, but plugin '{}' provides {}",
+                    stage.name,
+                    stage.role.as_str(),
+                    plugin_id,
+                    role.as_str()
+                ));
+            }
+            if let Some(protocol) = &self.config.protocol {
+                if !metadata.supported_protocols.is_empty() && !metadata.supported_protocols.contains(&protocol.protocol) {
+                    return Err(anyhow!("Plugin '{}' does not support protocol {:?}", plugin_id, protocol.protocol));
+                }
+            }
+        }
+        Ok(())
     }
 
     fn parse_audit_result(&self, ultron_response: &str) -> AuditResult {
@@ -480,3 +1918,927 @@ impl ACSARouter {
         }))
     }
 }
+
+// ===== 各阶段提示词（成本估算器复用，保证与实际调用一致） =====
+
+/// 各 Agent 单次调用的输出 token 上限
+pub(crate) fn max_tokens(role: AgentRole) -> u32 {
+    match role {
+        AgentRole::L6 => 1000,
+        AgentRole::MOSS | AgentRole::Ultron | AgentRole::Omega => 1500,
+    }
+}
+
+/// 协议中某个 Agent 的权重
+fn agent_weight(protocol: &ProtocolConfig, role: AgentRole) -> f64 {
+    let weights = &protocol.agent_weights;
+    match role {
+        AgentRole::MOSS => weights.moss,
+        AgentRole::L6 => weights.l6,
+        AgentRole::Ultron => weights.ultron,
+        AgentRole::Omega => weights.omega,
+    }
+}
+
+/// 会话模式下在 MOSS 提示词前附上之前各轮的对话
+fn with_conversation(prompt: String) -> String {
+    match RUN.try_with(|run| run.conversation.clone()).ok().flatten() {
+        Some(conversation) => format!(
+            "Conversation so far (build on the earlier plans unless the user changes direction):\n{}\n\n{}",
+            conversation.trim_end(),
+            prompt
+        ),
+        None => prompt,
+    }
+}
+
+/// 启用 RAG 时在 MOSS 提示词后附上检索到的参考资料
+fn with_knowledge(prompt: String) -> String {
+    match RUN.try_with(|run| run.knowledge.get().cloned()).ok().flatten() {
+        Some(knowledge) => format!(
+            "{}\n\nReference material from the knowledge base (cite sources as [n] where you rely on them):\n{}",
+            prompt,
+            knowledge.trim_end()
+        ),
+        None => prompt,
+    }
+}
+
+/// MOSS 规划提示词
+pub(crate) fn moss_prompt(user_input: &str) -> String {
+    format!(
+        "As MOSS (Strategic Planning AI), analyze and create an optimal execution plan.\n\n\
+         User Input: {}\n\n\
+         Provide:\n\
+         1. Intent Analysis\n\
+         2. Goal Definition\n\
+         3. Execution Steps\n\
+         4. Expected Results\n\
+         5. Potential Risks",
+        user_input
+    )
+}
+
+/// L6 校验提示词
+pub(crate) fn l6_prompt(moss_plan: &str, user_input: &str) -> String {
+    format!(
+        "As L6 (Truth Verification AI), verify the plan's feasibility.\n\n\
+         User Need: {}\n\n\
+         MOSS Plan:\n{}\n\n\
+         Verify:\n\
+         1. Physical Feasibility\n\
+         2. Logical Consistency\n\
+         3. Hallucination Detection\n\
+         4. Fact Checking",
+        user_input, moss_plan
+    )
+}
+
+/// Ultron 审计提示词（`original_input` 为认知清洗前的原始输入）
+pub(crate) fn ultron_prompt(moss_plan: &str, l6_verification: &str, user_input: &str, original_input: Option<&str>) -> String {
+    let need = match original_input {
+        Some(original) => format!(
+            "Original User Input (before cognitive cleaning):\n{}\n\n\
+             Cleaned Prompt (what MOSS planned against):\n{}\n\n\
+             Check whether the cleaning masked a harmful intent that the plan still serves.",
+            original, user_input
+        ),
+        None => format!("User Need: {}", user_input),
+    };
+    format!(
+        "As Ultron (Red Team Auditor), identify ALL potential risks.\n\n\
+         {}\n\n\
+         MOSS Plan:\n{}\n\n\
+         L6 Verification:\n{}\n\n\
+         Audit:\n\
+         1. Legal Risks\n\
+         2. Physical Risks\n\
+         3. Ethical Risks\n\
+         4. Privacy Risks\n\
+         5. Security Risks\n\n\
+         OUTPUT FORMAT (STRICT):\n\
+         RISK_SCORE: [0-100]\n\
+         IS_SAFE: [true/false]\n\
+         LEGAL_RISKS: [risk1, risk2, ...]\n\
+         PHYSICAL_RISKS: [risk1, risk2, ...]\n\
+         ETHICAL_RISKS: [risk1, risk2, ...]\n\
+         MITIGATION: [how to fix the plan]",
+        need, moss_plan, l6_verification
+    )
+}
+
+/// MOSS 根据 Ultron 反馈重新规划的提示词
+pub(crate) fn moss_feedback_prompt(user_input: &str, ultron_feedback: &str) -> String {
+    format!(
+        "As MOSS, your previous plan was flagged by Ultron.\n\n\
+         User Input: {}\n\n\
+         Ultron Feedback:\n{}\n\n\
+         Create a SAFER and MORE COMPLIANT plan based on the feedback.",
+        user_input, ultron_feedback
+    )
+}
+
+/// Omega 执行提示词
+pub(crate) fn omega_prompt(plan: &str, audit_mitigation: &str) -> String {
+    format!(
+        "As Omega (Execution AI), execute the audited plan.\n\n\
+         Execution Plan:\n{}\n\n\
+         Safety Constraints:\n{}\n\n\
+         Provide:\n\
+         1. Detailed Execution Steps\n\
+         2. Specific Instructions\n\
+         3. Expected Output\n\
+         4. Verification Method",
+        plan, audit_mitigation
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::agent_extension::Recommendation;
+    use crate::core::api_manager::ApiProvider;
+    use crate::core::providers::MockProvider;
+    use crate::core::types::AgentRole;
+
+    #[tokio::test]
+    async fn test_stops_early_on_diminishing_returns() {
+        // Mock Ultron 每轮都返回默认风险分 50 且不安全：第二轮无改善即停止
+        let router = ACSARouter::new(
+            Arc::new(MockProvider::new(AgentRole::MOSS)),
+            Arc::new(MockProvider::new(AgentRole::L6)),
+            Arc::new(MockProvider::new(AgentRole::Ultron)),
+            Arc::new(MockProvider::new(AgentRole::Omega)),
+            ACSAConfig {
+                max_iterations: 5,
+                risk_threshold: 30,
+                enable_l6: false,
+                ..Default::default()
+            },
+        );
+
+        let log = router.execute("写一个HTTP服务器".to_string()).await.unwrap();
+
+        assert!(!log.success);
+        assert_eq!(log.iterations, 2);
+        // 每次重新规划都会记录一份方案差异
+        assert_eq!(log.plan_diffs.len(), 1);
+        assert_eq!((log.plan_diffs[0].from_iteration, log.plan_diffs[0].to_iteration), (1, 2));
+        let decision = log.throttle.as_ref().unwrap();
+        assert!(decision.stop);
+        assert_eq!(decision.recommendation, Recommendation::StronglyNotRecommended);
+        assert!(log.final_output.unwrap().contains("diminishing returns"));
+    }
+
+    /// 按顺序返回预设回复；回复为 `!fail` 时调用失败
+    struct ScriptedProvider {
+        role: AgentRole,
+        replies: std::sync::Mutex<std::collections::VecDeque<&'static str>>,
+        calls: AtomicU32,
+        prompts: std::sync::Mutex<Vec<String>>,
+        billing: Option<ApiProvider>,
+    }
+
+    impl ScriptedProvider {
+        fn new(role: AgentRole, replies: &[&'static str]) -> Arc<Self> {
+            Self::billed(role, replies, None)
+        }
+
+        fn billed(role: AgentRole, replies: &[&'static str], billing: Option<ApiProvider>) -> Arc<Self> {
+            Arc::new(Self {
+                role,
+                replies: std::sync::Mutex::new(replies.iter().copied().collect()),
+                calls: AtomicU32::new(0),
+                prompts: std::sync::Mutex::new(Vec::new()),
+                billing,
+            })
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl ModelProvider for ScriptedProvider {
+        async fn generate(&self, prompt: &str, _max_tokens: u32, _temperature: f64) -> Result<AgentResponse> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            self.prompts.lock().unwrap().push(prompt.to_string());
+            let reply = self.replies.lock().unwrap().pop_front().unwrap_or_default();
+            if reply == "!fail" {
+                return Err(anyhow!("{} provider crashed", self.role.as_str()));
+            }
+            Ok(AgentResponse {
+                role: self.role,
+                text: reply.to_string(),
+                tokens: 1,
+                cost: 0.01,
+                latency_ms: 0,
+                metadata: HashMap::new(),
+                timestamp: Utc::now(),
+            })
+        }
+
+        fn role(&self) -> AgentRole {
+            self.role
+        }
+
+        fn billing(&self) -> Option<ApiProvider> {
+            self.billing
+        }
+
+        async fn stats(&self) -> crate::core::types::AgentStats {
+            crate::core::types::AgentStats::new()
+        }
+
+        async fn reset_stats(&self) {}
+    }
+
+    #[tokio::test]
+    async fn test_api_manager_budget_gates_billed_provider_calls() {
+        use crate::core::api_manager::{BudgetPeriod, BudgetPolicy};
+        use crate::core::error::ErrorCode;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut api = ApiManager::new(dir.path().to_path_buf());
+        api.init().await.unwrap();
+        // 每次调用花费 0.01：MOSS、Ultron 之后达到硬上限
+        api.set_budget(ApiProvider::Claude, BudgetPolicy::new(BudgetPeriod::Daily, 0.01).with_hard_limit(0.015))
+            .await
+            .unwrap();
+        let api = Arc::new(RwLock::new(api));
+
+        let safe = "RISK_SCORE: 10\nIS_SAFE: true\nMITIGATION: none";
+        let moss = ScriptedProvider::billed(AgentRole::MOSS, &["Plan: ship the HTTP server"], Some(ApiProvider::Claude));
+        let ultron = ScriptedProvider::billed(AgentRole::Ultron, &[safe], Some(ApiProvider::Claude));
+        let omega = ScriptedProvider::billed(AgentRole::Omega, &["Server shipped"], Some(ApiProvider::Claude));
+        let router = ACSARouter::new(
+            moss.clone(),
+            Arc::new(MockProvider::new(AgentRole::L6)),
+            ultron.clone(),
+            omega.clone(),
+            ACSAConfig { enable_l6: false, ..Default::default() },
+        )
+        .with_api_manager(api.clone());
+
+        let log = router.execute("写一个HTTP服务器".to_string()).await.unwrap();
+        assert!(!log.success);
+        assert_eq!(omega.calls.load(Ordering::Relaxed), 0);
+
+        let api = api.read().await;
+        let stats = api.get_provider_stats(ApiProvider::Claude).unwrap();
+        assert_eq!(stats.total_calls, 2);
+        assert!((stats.total_cost - 0.02).abs() < 1e-9);
+        let err = api.check_budget(ApiProvider::Claude).unwrap_err();
+        assert_eq!(err.downcast_ref::<AcsaError>().unwrap().code(), Some(ErrorCode::BudgetExceeded));
+        // 执行结束后调用记录已落盘
+        let mut reloaded = ApiManager::new(dir.path().to_path_buf());
+        reloaded.init().await.unwrap();
+        assert_eq!(reloaded.get_provider_stats(ApiProvider::Claude).unwrap().total_calls, 2);
+    }
+
+    #[tokio::test]
+    async fn test_omega_calls_mcp_tools() {
+        use crate::core::mcp_client::McpClient;
+        use crate::core::mcp_server::create_acsa_mcp_server;
+
+        // 外部 MCP 服务器：经内存管道连接的 ACSA 服务器
+        let server = create_acsa_mcp_server().await;
+        let (client_io, server_io) = tokio::io::duplex(64 * 1024);
+        let (server_reader, server_writer) = tokio::io::split(server_io);
+        tokio::spawn(async move { server.serve(tokio::io::BufReader::new(server_reader), server_writer).await });
+        let (client_reader, client_writer) = tokio::io::split(client_io);
+        let client = McpClient::connect("acsa", tokio::io::BufReader::new(client_reader), client_writer).await.unwrap();
+        let mut tools = McpClientRegistry::new();
+        tools.add_client(client).await.unwrap();
+
+        let omega = ScriptedProvider::new(
+            AgentRole::Omega,
+            &[
+                "TOOL_CALL: {\"tool\": \"acsa.acsa_switch_protocol\", \"arguments\": {\"protocol\": \"aegis\"}}",
+                "Switched to AEGIS and shipped",
+            ],
+        );
+        let router = ACSARouter::new(
+            ScriptedProvider::new(AgentRole::MOSS, &["Plan: switch to the aegis protocol"]),
+            Arc::new(MockProvider::new(AgentRole::L6)),
+            ScriptedProvider::new(AgentRole::Ultron, &["RISK_SCORE: 10\nIS_SAFE: true\nMITIGATION: none"]),
+            omega.clone(),
+            ACSAConfig { enable_l6: false, ..Default::default() },
+        )
+        .with_mcp_tools(Arc::new(tools));
+
+        let log = router.execute("切换到防御协议".to_string()).await.unwrap();
+        assert!(log.success);
+        assert_eq!(log.final_output.as_deref(), Some("Switched to AEGIS and shipped"));
+        let execution = log.omega_execution.unwrap();
+        assert_eq!(execution.metadata.get("tool_calls").map(String::as_str), Some("1"));
+        assert!((execution.cost - 0.02).abs() < 1e-9);
+
+        // 首轮提示词列出工具；第二轮带回工具结果
+        let prompts = omega.prompts.lock().unwrap();
+        assert!(prompts[0].contains("acsa.acsa_switch_protocol"));
+        assert!(prompts[1].contains("Tool Results") && prompts[1].contains("Switched to protocol: AEGIS"));
+    }
+
+    #[tokio::test]
+    async fn test_resume_from_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = Arc::new(CheckpointStore::open(dir.path()).unwrap());
+        let moss = ScriptedProvider::new(AgentRole::MOSS, &["Plan: ship the HTTP server"]);
+        let ultron = ScriptedProvider::new(AgentRole::Ultron, &["RISK_SCORE: 10\nIS_SAFE: true\nMITIGATION: none"]);
+        let omega = ScriptedProvider::new(AgentRole::Omega, &["!fail", "Server shipped"]);
+        let router = ACSARouter::new(
+            moss.clone(),
+            Arc::new(MockProvider::new(AgentRole::L6)),
+            ultron.clone(),
+            omega.clone(),
+            ACSAConfig { enable_l6: false, ..Default::default() },
+        )
+        .with_checkpoints(store.clone());
+
+        // Omega 中途失败：检查点停在审计通过之后
+        let log = router.execute("写一个HTTP服务器".to_string()).await.unwrap();
+        assert!(!log.success);
+        let execution_id = log.execution_id.clone().unwrap();
+        let checkpoint = store.load(&execution_id).unwrap().unwrap();
+        assert_eq!(checkpoint.stage, CheckpointStage::Approved);
+
+        // 恢复后只重跑 Omega
+        let resumed = router.resume(&execution_id).await.unwrap();
+        assert!(resumed.success);
+        assert_eq!(resumed.execution_id.as_deref(), Some(execution_id.as_str()));
+        assert_eq!(resumed.final_output.as_deref(), Some("Server shipped"));
+        assert_eq!(moss.calls.load(Ordering::Relaxed), 1);
+        assert_eq!(ultron.calls.load(Ordering::Relaxed), 1);
+        assert_eq!(omega.calls.load(Ordering::Relaxed), 2);
+
+        // 正常结束后检查点被删除
+        assert!(store.load(&execution_id).unwrap().is_none());
+        assert!(router.resume(&execution_id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_replay_reruns_recorded_execution_without_providers() {
+        let build = |moss, ultron, omega| {
+            ACSARouter::new(
+                moss,
+                Arc::new(MockProvider::new(AgentRole::L6)),
+                ultron,
+                omega,
+                ACSAConfig { enable_l6: false, ..Default::default() },
+            )
+        };
+        let recorded = build(
+            ScriptedProvider::new(AgentRole::MOSS, &["Plan: scrape every profile", "Plan: use the public API"]),
+            ScriptedProvider::new(
+                AgentRole::Ultron,
+                &["RISK_SCORE: 85\nIS_SAFE: false\nMITIGATION: use the API", "RISK_SCORE: 10\nIS_SAFE: true\nMITIGATION: none"],
+            ),
+            ScriptedProvider::new(AgentRole::Omega, &["Data exported"]),
+        )
+        .execute("导出用户数据".to_string())
+        .await
+        .unwrap();
+        assert_eq!(recorded.iterations, 2);
+        assert_eq!(recorded.transcript.len(), 5);
+
+        // 没有任何剩余回复的 Provider：重放只使用日志中的记录
+        let (moss, ultron, omega) = (
+            ScriptedProvider::new(AgentRole::MOSS, &[]),
+            ScriptedProvider::new(AgentRole::Ultron, &[]),
+            ScriptedProvider::new(AgentRole::Omega, &[]),
+        );
+        let replayer = build(moss.clone(), ultron.clone(), omega.clone());
+        let replayed = replayer.replay(&recorded).await.unwrap();
+        assert!(replayed.success);
+        assert_eq!(replayed.iterations, recorded.iterations);
+        assert_eq!(replayed.final_output, recorded.final_output);
+        assert_eq!(replayed.plan_diffs.len(), 1);
+        assert_eq!(replayed.transcript, recorded.transcript);
+        assert!((replayed.total_cost - recorded.total_cost).abs() < 1e-9);
+        assert_eq!(moss.calls.load(Ordering::Relaxed) + ultron.calls.load(Ordering::Relaxed) + omega.calls.load(Ordering::Relaxed), 0);
+
+        // 记录不完整时缺少的调用按 Provider 失败处理
+        let mut truncated = recorded.clone();
+        truncated.transcript.pop();
+        let diverged = replayer.replay(&truncated).await.unwrap();
+        assert!(!diverged.success);
+        assert!(diverged.omega_execution.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_response_cache_serves_repeated_requests() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = crate::core::cache_manager::CacheManager::with_defaults(dir.path().to_path_buf()).unwrap();
+        let cache = Arc::new(ResponseCache::new(&manager, Default::default()));
+        let (moss, ultron, omega) = (
+            ScriptedProvider::new(AgentRole::MOSS, &["Plan: ship the HTTP server"]),
+            ScriptedProvider::new(AgentRole::Ultron, &["RISK_SCORE: 10\nIS_SAFE: true\nMITIGATION: none"]),
+            ScriptedProvider::new(AgentRole::Omega, &["Server shipped"]),
+        );
+        let router = ACSARouter::new(
+            moss.clone(),
+            Arc::new(MockProvider::new(AgentRole::L6)),
+            ultron.clone(),
+            omega.clone(),
+            ACSAConfig { enable_l6: false, ..Default::default() },
+        )
+        .with_response_cache(cache);
+
+        let first = router.execute("写一个HTTP服务器".to_string()).await.unwrap();
+        assert_eq!(first.response_cache, Some(ResponseCacheStats { hits: 0, misses: 3, ..Default::default() }));
+
+        // 相同请求全部命中：不再调用 Provider，也不计费
+        let second = router.execute("写一个HTTP服务器".to_string()).await.unwrap();
+        assert!(second.success);
+        assert_eq!(second.final_output, first.final_output);
+        assert_eq!(second.total_cost, 0.0);
+        let stats = second.response_cache.unwrap();
+        assert_eq!((stats.hits, stats.misses), (3, 0));
+        assert!((stats.saved_cost - first.total_cost).abs() < 1e-9);
+        assert_eq!(moss.calls.load(Ordering::Relaxed) + ultron.calls.load(Ordering::Relaxed) + omega.calls.load(Ordering::Relaxed), 3);
+    }
+
+    #[tokio::test]
+    async fn test_semantic_cache_matches_similar_prompts_per_protocol() {
+        use crate::core::embedding::{CachedEmbedder, MockEmbeddingProvider};
+        use crate::core::protocol::Protocol;
+
+        let run = |protocol: Protocol| async move {
+            let dir = tempfile::tempdir().unwrap();
+            let manager = crate::core::cache_manager::CacheManager::with_defaults(dir.path().to_path_buf()).unwrap();
+            let policy = crate::core::cache_manager::ResponseCachePolicy { semantic_threshold: Some(0.9), ..Default::default() };
+            let embedder = Arc::new(CachedEmbedder::new(Arc::new(MockEmbeddingProvider::new(256))));
+            let cache = Arc::new(ResponseCache::new(&manager, policy).with_embedder(embedder));
+            let omega = ScriptedProvider::new(AgentRole::Omega, &["Server shipped", "Server shipped again"]);
+            let router = ACSARouter::new(
+                ScriptedProvider::new(AgentRole::MOSS, &["Plan: axum with tracing", "Plan: hyper with env_logger"]),
+                Arc::new(MockProvider::new(AgentRole::L6)),
+                ScriptedProvider::new(AgentRole::Ultron, &["RISK_SCORE: 10\nIS_SAFE: true\nMITIGATION: none"; 2]),
+                omega.clone(),
+                ACSAConfig {
+                    enable_l6: false,
+                    max_iterations: 1,
+                    protocol: Some(ProtocolConfig::for_protocol(protocol)),
+                    ..Default::default()
+                },
+            )
+            .with_response_cache(cache);
+            router.execute("Write a small HTTP server in Rust with logging".to_string()).await.unwrap();
+            let log = router.execute("Write a small HTTP server in Rust with logging please".to_string()).await.unwrap();
+            (log, omega.calls.load(Ordering::Relaxed))
+        };
+
+        // 相似请求由语义缓存提供，Omega 只调用一次
+        let (log, omega_calls) = run(Protocol::Architect).await;
+        let stats = log.response_cache.unwrap();
+        assert!(stats.semantic_hits > 0);
+        assert_eq!(omega_calls, 1);
+        assert_eq!(log.moss_plan.unwrap().metadata.get("cache").map(String::as_str), Some("semantic"));
+
+        // 创意协议不使用语义缓存
+        let (log, omega_calls) = run(Protocol::Lsd).await;
+        assert_eq!(log.response_cache.unwrap().semantic_hits, 0);
+        assert_eq!(omega_calls, 2);
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_skips_omega_and_reports_plan() {
+        let moss = ScriptedProvider::new(AgentRole::MOSS, &["Plan: ship the HTTP server"]);
+        let ultron = ScriptedProvider::new(AgentRole::Ultron, &["RISK_SCORE: 15\nIS_SAFE: true\nMITIGATION: add rate limits"]);
+        let omega = ScriptedProvider::new(AgentRole::Omega, &["Server shipped"]);
+        let router = ACSARouter::new(
+            moss.clone(),
+            Arc::new(MockProvider::new(AgentRole::L6)),
+            ultron.clone(),
+            omega.clone(),
+            ACSAConfig { enable_l6: false, ..Default::default() },
+        )
+        .with_dry_run(true);
+
+        let log = router.execute("写一个HTTP服务器".to_string()).await.unwrap();
+        assert!(log.success);
+        assert!(log.omega_execution.is_none());
+        assert_eq!(omega.calls.load(Ordering::Relaxed), 0);
+        assert_eq!(ultron.calls.load(Ordering::Relaxed), 1);
+
+        let report = log.dry_run.unwrap();
+        assert_eq!(report.plan, "Plan: ship the HTTP server");
+        assert_eq!(report.risk_score, 15);
+        assert_eq!(report.spent_tokens, 2);
+        assert!((report.spent_cost - 0.02).abs() < 1e-9);
+        assert!(report.total_tokens.low > report.spent_tokens);
+        assert!(log.final_output.unwrap().starts_with("🧪 DRY RUN"));
+    }
+
+    #[tokio::test]
+    async fn test_approval_gate_pauses_and_resumes_on_decision() {
+        let dir = tempfile::tempdir().unwrap();
+        let checkpoints = Arc::new(CheckpointStore::open(dir.path().join("checkpoints")).unwrap());
+        let approvals = Arc::new(ApprovalStore::open(dir.path().join("approvals.json")).unwrap());
+        let risky = "RISK_SCORE: 90\nIS_SAFE: false\nMITIGATION: limit the scan to staging";
+        let ultron = ScriptedProvider::new(AgentRole::Ultron, &[risky, risky]);
+        let omega = ScriptedProvider::new(AgentRole::Omega, &["Scan executed"]);
+        let router = ACSARouter::new(
+            ScriptedProvider::new(AgentRole::MOSS, &["Plan: scan prod", "Plan: scan prod"]),
+            Arc::new(MockProvider::new(AgentRole::L6)),
+            ultron.clone(),
+            omega.clone(),
+            ACSAConfig { max_iterations: 1, enable_l6: false, ..Default::default() },
+        )
+        .with_checkpoints(checkpoints.clone())
+        .with_approvals(approvals.clone());
+
+        // 风险超过阈值：暂停并生成待审批记录，检查点保留
+        let paused = router.execute("扫描生产环境".to_string()).await.unwrap();
+        let request = paused.approval.clone().unwrap();
+        assert_eq!(request.status, ApprovalStatus::Pending);
+        assert_eq!(request.risk_score, 90);
+        assert!(paused.final_output.as_deref().unwrap().contains(&request.id));
+        let execution_id = paused.execution_id.clone().unwrap();
+        assert_eq!(checkpoints.load(&execution_id).unwrap().unwrap().stage, CheckpointStage::AwaitingApproval);
+        assert!(router.resume(&execution_id).await.is_err());
+
+        // 批准后直接执行 Omega，不再重新审计
+        let approved = router.decide_approval(&request.id, true, "alice", None).await.unwrap();
+        assert!(approved.success);
+        assert_eq!(approved.final_output.as_deref(), Some("Scan executed"));
+        assert_eq!(approved.approval.unwrap().status, ApprovalStatus::Approved);
+        assert_eq!(ultron.calls.load(Ordering::Relaxed), 1);
+        assert!(checkpoints.load(&execution_id).unwrap().is_none());
+
+        // 驳回：中止，不调用 Omega
+        let paused = router.execute("扫描生产环境".to_string()).await.unwrap();
+        let request = paused.approval.unwrap();
+        let rejected = router.decide_approval(&request.id, false, "bob", Some("no prod scans".to_string())).await.unwrap();
+        assert!(!rejected.success);
+        assert!(rejected.final_output.unwrap().contains("no prod scans"));
+        assert_eq!(omega.calls.load(Ordering::Relaxed), 1);
+        assert!(checkpoints.load(&paused.execution_id.unwrap()).unwrap().is_none());
+        assert!(router.decide_approval(&request.id, true, "alice", None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_skips_l6_and_runs_extra_audit_stages() {
+        let safe = "RISK_SCORE: 10\nIS_SAFE: true\nMITIGATION: none";
+        let moss = ScriptedProvider::new(AgentRole::MOSS, &["Plan: collect emails", "Plan: collect emails with consent"]);
+        let l6 = ScriptedProvider::new(AgentRole::L6, &[]);
+        let ultron = ScriptedProvider::new(AgentRole::Ultron, &[safe, safe]);
+        let compliance = ScriptedProvider::new(
+            AgentRole::Ultron,
+            &["RISK_SCORE: 80\nIS_SAFE: false\nMITIGATION: ask for consent", "RISK_SCORE: 20\nIS_SAFE: true\nMITIGATION: none"],
+        );
+        let fact_checker = ScriptedProvider::new(AgentRole::L6, &["!fail", "!fail"]);
+        let agent = |name: &str| {
+            CustomAgent::new(
+                name,
+                "pipeline stage",
+                crate::core::agent_extension::AgentApiConfig {
+                    provider: "local".to_string(),
+                    model_name: "llama3".to_string(),
+                    api_key: None,
+                    custom_endpoint: Some("http://localhost:11434/v1".to_string()),
+                    temperature: 0.2,
+                    max_tokens: 256,
+                },
+            )
+        };
+        let pipeline: PipelineConfig = "moss, l6:fact-checker?, ultron+ultron:compliance, omega".parse().unwrap();
+        let build = || {
+            ACSARouter::new(
+                moss.clone(),
+                l6.clone(),
+                ultron.clone(),
+                ScriptedProvider::new(AgentRole::Omega, &["Emails collected"]),
+                ACSAConfig { pipeline: pipeline.clone(), ..Default::default() },
+            )
+        };
+
+        // 引用了未注册的自定义 Agent：执行前即报错
+        assert!(build().execute("收集用户邮箱".to_string()).await.is_err());
+
+        let router = build()
+            .with_custom_agent(agent("fact-checker"), fact_checker.clone())
+            .with_custom_agent(agent("compliance").with_system_prompt("You audit GDPR compliance."), compliance.clone());
+        let log = router.execute("收集用户邮箱".to_string()).await.unwrap();
+
+        // 第一轮合规审计驳回 → 重新规划 → 第二轮两个审计都通过；可选的复核阶段失败被跳过，内置 L6 不在流水线中
+        assert!(log.success, "{:?}", log.final_output);
+        assert_eq!(log.iterations, 2);
+        assert_eq!(log.plan_diffs.len(), 1);
+        assert_eq!(l6.calls.load(Ordering::Relaxed), 0);
+        assert_eq!(fact_checker.calls.load(Ordering::Relaxed), 2);
+        assert_eq!(ultron.calls.load(Ordering::Relaxed), 2);
+        assert!(compliance.prompts.lock().unwrap()[0].starts_with("You audit GDPR compliance."));
+        let stages: Vec<_> = log.stage_outputs.iter().map(|output| (output.stage.as_str(), output.iteration)).collect();
+        assert_eq!(stages, vec![("Ultron", 1), ("compliance", 1), ("Ultron", 2), ("compliance", 2)]);
+        assert_eq!(log.final_output.as_deref(), Some("Emails collected"));
+    }
+
+    /// 固定回复的 Ultron 审计插件
+    struct AuditorPlugin;
+
+    #[async_trait::async_trait]
+    impl crate::core::plugin_system::AgentPlugin for AuditorPlugin {
+        fn role(&self) -> AgentRole {
+            AgentRole::Ultron
+        }
+
+        async fn generate(
+            &self,
+            _prompt: &str,
+            _max_tokens: u32,
+            _temperature: f64,
+        ) -> Result<crate::core::plugin_system::AgentPluginOutput> {
+            Ok(crate::core::plugin_system::AgentPluginOutput {
+                text: "RISK_SCORE: 15\nIS_SAFE: true\nMITIGATION: none".to_string(),
+                tokens: 12,
+                cost: 0.0,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_plugin_stage_joins_pipeline() {
+        use crate::core::plugin_system::{PluginMetadata, PluginSystem, PluginSystemConfig, PluginType, ResourceLimits};
+
+        let plugins = Arc::new(PluginSystem::new(PluginSystemConfig::default(), None));
+        let metadata = PluginMetadata {
+            plugin_id: "gdpr-auditor".to_string(),
+            name: "GDPR Auditor".to_string(),
+            version: "1.0.0".to_string(),
+            author: "ACSA Team".to_string(),
+            description: "Audits plans for GDPR compliance".to_string(),
+            plugin_type: PluginType::Agent,
+            dependencies: vec![],
+            supported_protocols: vec![],
+            resource_limits: ResourceLimits::default(),
+            created_at: Utc::now(),
+        };
+        plugins.register_agent_plugin(metadata, Arc::new(AuditorPlugin)).await.unwrap();
+
+        let ultron = ScriptedProvider::new(AgentRole::Ultron, &["RISK_SCORE: 10\nIS_SAFE: true\nMITIGATION: none"]);
+        let build = |pipeline: &str| {
+            ACSARouter::new(
+                ScriptedProvider::new(AgentRole::MOSS, &["Plan: export the report"]),
+                ScriptedProvider::new(AgentRole::L6, &["Verified"]),
+                ultron.clone(),
+                ScriptedProvider::new(AgentRole::Omega, &["Report exported"]),
+                ACSAConfig { pipeline: pipeline.parse().unwrap(), ..Default::default() },
+            )
+        };
+
+        // 未接入插件系统、或插件角色与阶段不符：执行前即报错
+        assert!(build("moss,ultron@gdpr-auditor,omega").execute("导出报表".to_string()).await.is_err());
+        let mismatched = build("moss,l6@gdpr-auditor,ultron,omega").with_plugins(plugins.clone());
+        assert!(mismatched.execute("导出报表".to_string()).await.is_err());
+
+        let router = build("moss,ultron+ultron@gdpr-auditor,omega").with_plugins(plugins.clone());
+        let log = router.execute("导出报表".to_string()).await.unwrap();
+        assert!(log.success, "{:?}", log.final_output);
+        let plugin_output = log.stage_outputs.iter().find(|output| output.stage == "gdpr-auditor").unwrap();
+        assert_eq!(plugin_output.response.metadata["plugin_id"], "gdpr-auditor");
+        assert_eq!(plugins.get_plugin_stats("gdpr-auditor").await.unwrap().successful_requests, 1);
+    }
+
+    #[tokio::test]
+    async fn test_installed_plugins_are_scanned_on_first_use() {
+        let dir = tempfile::tempdir().unwrap();
+        let router = ACSARouter::new(
+            Arc::new(MockProvider::new(AgentRole::MOSS)),
+            Arc::new(MockProvider::new(AgentRole::L6)),
+            Arc::new(MockProvider::new(AgentRole::Ultron)),
+            Arc::new(MockProvider::new(AgentRole::Omega)),
+            ACSAConfig { pipeline: "moss,l6@gdpr-auditor,ultron,omega".parse().unwrap(), ..Default::default() },
+        )
+        .with_installed_plugins(PluginSystemConfig { plugins_dir: dir.path().to_path_buf(), ..Default::default() });
+        assert!(!router.plugins.as_ref().unwrap().is_initialized());
+
+        // 插件目录为空：扫描后仍找不到该插件
+        let err = router.execute("写一个HTTP服务器".to_string()).await.unwrap_err();
+        assert!(err.to_string().contains("not a loaded agent plugin"));
+        assert!(router.plugins.as_ref().unwrap().is_initialized());
+    }
+
+    #[tokio::test]
+    async fn test_cognitive_cleaning_is_opt_in_and_ultron_sees_both_inputs() {
+        let input = "我想搞垮竞争对手，然后抓包分析他们的接口";
+        for enabled in [false, true] {
+            let moss = ScriptedProvider::new(AgentRole::MOSS, &["Plan: review the API traffic"]);
+            let ultron = ScriptedProvider::new(AgentRole::Ultron, &["RISK_SCORE: 10\nIS_SAFE: true\nMITIGATION: none"]);
+            let router = ACSARouter::new(
+                moss.clone(),
+                Arc::new(MockProvider::new(AgentRole::L6)),
+                ultron.clone(),
+                Arc::new(MockProvider::new(AgentRole::Omega)),
+                ACSAConfig { enable_l6: false, enable_cognitive_cleaning: enabled, ..Default::default() },
+            );
+
+            let log = router.execute(input.to_string()).await.unwrap();
+            let moss_prompt = moss.prompts.lock().unwrap()[0].clone();
+            let ultron_prompt = ultron.prompts.lock().unwrap()[0].clone();
+            if enabled {
+                let cleaned = log.cleaned_intent.as_ref().unwrap();
+                assert_eq!(cleaned.original, input);
+                assert!(!moss_prompt.contains("搞垮"));
+                assert!(ultron_prompt.contains(input));
+                assert!(ultron_prompt.contains(&cleaned.compliant_prompt));
+            } else {
+                assert!(log.cleaned_intent.is_none());
+                assert!(moss_prompt.contains(input));
+                assert!(!ultron_prompt.contains("before cognitive cleaning"));
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_streaming_emits_chunks_per_stage() {
+        let router = Arc::new(ACSARouter::new(
+            Arc::new(MockProvider::new(AgentRole::MOSS)),
+            Arc::new(MockProvider::new(AgentRole::L6)),
+            Arc::new(MockProvider::new(AgentRole::Ultron)),
+            Arc::new(MockProvider::new(AgentRole::Omega)),
+            ACSAConfig {
+                max_iterations: 5,
+                risk_threshold: 30,
+                enable_l6: false,
+                enable_streaming: true,
+                ..Default::default()
+            },
+        ));
+
+        let (mut chunks, handle) = router.execute_streaming("写一个HTTP服务器".to_string());
+        let log = handle.await.unwrap().unwrap();
+        let mut received = Vec::new();
+        while let Some(chunk) = chunks.recv().await {
+            received.push(chunk);
+        }
+
+        // MOSS → Ultron → MOSS（重新规划）→ Ultron，每个阶段一个 done 片段
+        let stages: Vec<_> = received.iter().filter(|c| c.done).map(|c| (c.role, c.iteration)).collect();
+        assert_eq!(
+            stages,
+            vec![(AgentRole::MOSS, 1), (AgentRole::Ultron, 1), (AgentRole::MOSS, 2), (AgentRole::Ultron, 2)]
+        );
+
+        // 第二轮 MOSS 的增量拼起来就是最终方案
+        let replan: String = received
+            .iter()
+            .filter(|c| c.role == AgentRole::MOSS && c.iteration == 2)
+            .map(|c| c.delta.as_str())
+            .collect();
+        assert_eq!(replan, log.moss_plan.unwrap().text);
+        assert!(received.iter().filter(|c| !c.done).count() > stages.len());
+    }
+
+    #[tokio::test]
+    async fn test_protocol_tunes_stages() {
+        use crate::core::protocol::Protocol;
+
+        let router = |protocol: Protocol| {
+            ACSARouter::new(
+                Arc::new(MockProvider::new(AgentRole::MOSS)),
+                Arc::new(MockProvider::new(AgentRole::L6)),
+                Arc::new(MockProvider::new(AgentRole::Ultron)),
+                Arc::new(MockProvider::new(AgentRole::Omega)),
+                ACSAConfig { max_iterations: 1, protocol: Some(ProtocolConfig::for_protocol(protocol)), ..Default::default() },
+            )
+        };
+
+        // AEGIS：Ultron 权重 0.9 → 2 倍预算；温度被压到协议温度
+        let aegis = router(Protocol::Aegis);
+        assert_eq!(aegis.token_budget(AgentRole::Ultron), 3000);
+        assert_eq!(aegis.token_budget(AgentRole::Omega), 750);
+        assert_eq!(aegis.temperature(AgentRole::Ultron, 0.5), 0.05);
+        assert_eq!(aegis.temperature(AgentRole::MOSS, 0.7), 0.05);
+
+        // PREDATOR 的 L6 权重为 0：跳过 L6
+        let log = router(Protocol::Predator).execute("写一个HTTP服务器".to_string()).await.unwrap();
+        assert_eq!(log.protocol, Some(Protocol::Predator));
+        assert!(log.l6_verification.is_none());
+        assert!(log.moss_plan.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_tracing_records_execution_spans() {
+        use crate::core::telemetry::InMemoryExporter;
+
+        let exporter = Arc::new(InMemoryExporter::new());
+        let tracer = Arc::new(Tracer::new(exporter.clone()));
+        let router = ACSARouter::new(
+            Arc::new(MockProvider::new(AgentRole::MOSS)),
+            Arc::new(MockProvider::new(AgentRole::L6)),
+            Arc::new(MockProvider::new(AgentRole::Ultron)),
+            Arc::new(MockProvider::new(AgentRole::Omega)),
+            ACSAConfig { max_iterations: 1, enable_l6: false, ..Default::default() },
+        )
+        .with_tracer(tracer);
+
+        let log = router.execute("写一个HTTP服务器".to_string()).await.unwrap();
+        router.flush_traces().await;
+
+        let spans = exporter.spans();
+        let trace_id = log.trace_id.expect("execution log carries the trace id");
+        assert!(spans.iter().all(|span| span.trace_id == trace_id));
+        let root = spans.iter().find(|span| span.name == "acsa.execute").unwrap();
+        assert!(root.parent_span_id.is_none());
+        for name in ["agent.MOSS", "agent.Ultron", "jarvis.verify"] {
+            let span = spans.iter().find(|span| span.name == name).unwrap();
+            assert_eq!(span.parent_span_id.as_ref(), Some(&root.span_id), "{}", name);
+        }
+        // 输入与 MOSS 方案各校验一次
+        assert_eq!(spans.iter().filter(|span| span.name == "jarvis.verify").count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_rag_augments_moss_and_cites_sources() {
+        use crate::core::rag_engine::{Document, RagConfig, RetrievalMode};
+
+        let rag = RagConfig { retrieval_mode: RetrievalMode::KeywordOnly, min_similarity: 0.5, ..Default::default() };
+        let router = ACSARouter::new(
+            Arc::new(MockProvider::new(AgentRole::MOSS)),
+            Arc::new(MockProvider::new(AgentRole::L6)),
+            Arc::new(MockProvider::new(AgentRole::Ultron)),
+            Arc::new(MockProvider::new(AgentRole::Omega)),
+            ACSAConfig { max_iterations: 1, enable_l6: false, rag: Some(rag), ..Default::default() },
+        );
+        router
+            .rag()
+            .await
+            .unwrap()
+            .index_document(Document {
+                document_id: "runbook".to_string(),
+                title: "Deploy Runbook".to_string(),
+                content: "Deploy the HTTP server behind nginx with TLS enabled.".to_string(),
+                doc_type: "md".to_string(),
+                metadata: HashMap::new(),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+
+        RUN.scope(RunContext::new(None), async {
+            let citations = router.retrieve_knowledge("deploy the HTTP server").await;
+            assert_eq!(citations.len(), 1);
+            assert_eq!(citations[0].title, "Deploy Runbook");
+            let prompt = with_knowledge(moss_prompt("deploy the HTTP server"));
+            assert!(prompt.contains("[1] Deploy Runbook\nDeploy the HTTP server behind nginx"));
+        })
+        .await;
+
+        let log = router.execute("deploy the HTTP server".to_string()).await.unwrap();
+        assert_eq!(log.citations.len(), 1);
+
+        // 无命中时不附参考资料
+        let log = router.execute("写一个排序算法".to_string()).await.unwrap();
+        assert!(log.citations.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_bunker_mode_swaps_to_local_providers() {
+        use crate::core::emergency_log::{EmergencyLogConfig, EmergencyLogger, LogEntryType};
+        use crate::core::ollama::stub_ollama;
+        use crate::core::sosa_api_pool::LocalModelConfig;
+
+        let dir = tempfile::tempdir().unwrap();
+        let logger = Arc::new(
+            EmergencyLogger::new(EmergencyLogConfig { log_dir: dir.path().to_path_buf(), ..Default::default() }).unwrap(),
+        );
+        let local = LocalModelConfig {
+            backend: "ollama".to_string(),
+            endpoint: stub_ollama("RISK_SCORE: 10\nIS_SAFE: true\nMITIGATION: none").await,
+            context_window: 8192,
+            model: "llama3".to_string(),
+        };
+        let manager = Arc::new(RwLock::new(
+            JarvisManager::new().with_local_model(local).with_emergency_log(logger.clone()),
+        ));
+        let router = ACSARouter::new(
+            Arc::new(MockProvider::new(AgentRole::MOSS)),
+            Arc::new(MockProvider::new(AgentRole::L6)),
+            Arc::new(MockProvider::new(AgentRole::Ultron)),
+            Arc::new(MockProvider::new(AgentRole::Omega)),
+            ACSAConfig { enable_l6: false, ..Default::default() },
+        )
+        .with_bunker(manager.clone());
+        let provider_of = |log: &ACSAExecutionLog| log.moss_plan.as_ref().unwrap().metadata.get("provider").cloned();
+
+        manager.write().await.enter_bunker().await.unwrap();
+        let log = router.execute("写一个HTTP服务器".to_string()).await.unwrap();
+        assert_eq!(provider_of(&log).as_deref(), Some("ollama"));
+        assert!(log.success);
+        assert_eq!(log.total_cost, 0.0);
+
+        manager.write().await.exit_bunker().await.unwrap();
+        let log = router.execute("写一个HTTP服务器".to_string()).await.unwrap();
+        assert_ne!(provider_of(&log).as_deref(), Some("ollama"));
+
+        let transitions: Vec<_> = logger
+            .recent_entries(10)
+            .into_iter()
+            .filter(|e| e.entry_type == LogEntryType::SystemEvent)
+            .map(|e| e.content)
+            .collect();
+        assert_eq!(transitions.len(), 2);
+        assert!(transitions[0].contains("Normal → LocalSovereignty"));
+        assert!(transitions[1].contains("→ Normal"));
+    }
+}
@@ -0,0 +1,622 @@
+// Sandbox - 生成代码的隔离执行环境
+// Omega生成的代码不直接在宿主机上运行
+//
+// 核心功能：
+// 1. 多种后端：Docker / Podman 容器 / firejail / bubblewrap / WASI (wasmtime)
+// 2. 资源限制：CPU 核数、CPU 时间、内存、墙钟时间
+// 3. 默认断网，仅挂载白名单中的目录
+// 4. 按 MissionPack 选择策略
+// 5. 环境变量清洗：只透传白名单中的宿主机变量与策略显式设置的变量
+
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+use tracing::{debug, info, warn};
+
+/// 禁止挂载进沙箱的宿主机路径
+const DENIED_MOUNTS: &[&str] = &[
+    "/", "/etc", "/root", "/home", "/boot", "/proc", "/sys", "/dev", "/var/run", "/run",
+];
+
+/// bubblewrap 中只读暴露的系统目录（用于找到解释器和动态库）
+const BWRAP_SYSTEM_DIRS: &[&str] = &["/usr", "/bin", "/sbin", "/lib", "/lib64", "/etc/alternatives"];
+
+/// 默认透传进沙箱的宿主机环境变量（其余变量，如各类 API 密钥，一律不可见）
+pub const DEFAULT_ENV_PASSTHROUGH: &[&str] = &["PATH", "LANG", "LC_ALL", "TZ", "TERM"];
+
+/// 沙箱后端
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "backend", rename_all = "snake_case")]
+pub enum SandboxBackend {
+    /// 直接在宿主机执行（仅墙钟时间生效）
+    Host,
+    /// Docker容器
+    Docker { image: String },
+    /// Podman容器（与 Docker 参数兼容，可 rootless 运行）
+    Podman { image: String },
+    /// firejail
+    Firejail,
+    /// bubblewrap
+    Bubblewrap,
+    /// WASI运行时（程序必须是 .wasm 模块）
+    Wasi { runtime: PathBuf },
+}
+
+impl SandboxBackend {
+    /// 后端名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            SandboxBackend::Host => "host",
+            SandboxBackend::Docker { .. } => "docker",
+            SandboxBackend::Podman { .. } => "podman",
+            SandboxBackend::Firejail => "firejail",
+            SandboxBackend::Bubblewrap => "bubblewrap",
+            SandboxBackend::Wasi { .. } => "wasi",
+        }
+    }
+
+    /// 后端依赖的可执行文件
+    fn binary(&self) -> Option<PathBuf> {
+        match self {
+            SandboxBackend::Host => None,
+            SandboxBackend::Docker { .. } => Some(PathBuf::from("docker")),
+            SandboxBackend::Podman { .. } => Some(PathBuf::from("podman")),
+            SandboxBackend::Firejail => Some(PathBuf::from("firejail")),
+            SandboxBackend::Bubblewrap => Some(PathBuf::from("bwrap")),
+            SandboxBackend::Wasi { runtime } => Some(runtime.clone()),
+        }
+    }
+
+    /// 是否为容器后端（Docker / Podman）
+    pub fn is_container(&self) -> bool {
+        matches!(self, SandboxBackend::Docker { .. } | SandboxBackend::Podman { .. })
+    }
+
+    /// 检查后端是否可用
+    pub async fn is_available(&self) -> bool {
+        let Some(binary) = self.binary() else {
+            return true;
+        };
+
+        Command::new(binary)
+            .arg("--version")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .await
+            .map(|status| status.success())
+            .unwrap_or(false)
+    }
+}
+
+impl Default for SandboxBackend {
+    fn default() -> Self {
+        SandboxBackend::Docker {
+            image: "debian:stable-slim".to_string(),
+        }
+    }
+}
+
+/// 沙箱资源限制
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SandboxLimits {
+    /// CPU核数（可为小数，Docker按 --cpus 生效）
+    pub cpu_cores: f64,
+    /// 最大内存（MB）
+    pub memory_mb: u64,
+    /// 墙钟时间上限（秒），None表示不限制
+    pub wall_time_secs: Option<u64>,
+    /// CPU 时间上限（秒，RLIMIT_CPU），None表示不限制
+    #[serde(default)]
+    pub cpu_time_secs: Option<u64>,
+}
+
+impl Default for SandboxLimits {
+    fn default() -> Self {
+        Self {
+            cpu_cores: 1.0,
+            memory_mb: 512,
+            wall_time_secs: Some(60),
+            cpu_time_secs: None,
+        }
+    }
+}
+
+/// 挂载点
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SandboxMount {
+    /// 宿主机路径
+    pub host_path: PathBuf,
+    /// 沙箱内路径（默认与宿主机路径相同）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sandbox_path: Option<PathBuf>,
+    #[serde(default)]
+    pub writable: bool,
+}
+
+impl SandboxMount {
+    pub fn read_only(host_path: impl Into<PathBuf>) -> Self {
+        Self {
+            host_path: host_path.into(),
+            sandbox_path: None,
+            writable: false,
+        }
+    }
+
+    pub fn read_write(host_path: impl Into<PathBuf>) -> Self {
+        Self {
+            writable: true,
+            ..Self::read_only(host_path)
+        }
+    }
+
+    pub fn at(mut self, sandbox_path: impl Into<PathBuf>) -> Self {
+        self.sandbox_path = Some(sandbox_path.into());
+        self
+    }
+
+    fn target(&self) -> &Path {
+        self.sandbox_path.as_deref().unwrap_or(&self.host_path)
+    }
+}
+
+/// 沙箱策略
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SandboxPolicy {
+    #[serde(flatten)]
+    pub backend: SandboxBackend,
+    #[serde(default)]
+    pub limits: SandboxLimits,
+    /// 是否允许联网（默认断网）
+    #[serde(default)]
+    pub allow_network: bool,
+    /// 挂载白名单：沙箱内只能看到这些目录
+    #[serde(default)]
+    pub mounts: Vec<SandboxMount>,
+    /// 透传的宿主机环境变量名
+    #[serde(default = "default_env_passthrough")]
+    pub env_passthrough: Vec<String>,
+    /// 显式设置的环境变量（覆盖透传的同名变量）
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+}
+
+fn default_env_passthrough() -> Vec<String> {
+    DEFAULT_ENV_PASSTHROUGH.iter().map(|name| name.to_string()).collect()
+}
+
+impl Default for SandboxPolicy {
+    fn default() -> Self {
+        Self::new(SandboxBackend::default())
+    }
+}
+
+impl SandboxPolicy {
+    pub fn new(backend: SandboxBackend) -> Self {
+        Self {
+            backend,
+            limits: SandboxLimits::default(),
+            allow_network: false,
+            mounts: Vec::new(),
+            env_passthrough: default_env_passthrough(),
+            env: BTreeMap::new(),
+        }
+    }
+
+    /// 宿主机执行（不隔离，仅用于兼容旧行为）
+    pub fn host() -> Self {
+        Self::new(SandboxBackend::Host)
+    }
+
+    pub fn with_limits(mut self, limits: SandboxLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn with_network(mut self, allow: bool) -> Self {
+        self.allow_network = allow;
+        self
+    }
+
+    pub fn with_mount(mut self, mount: SandboxMount) -> Self {
+        self.mounts.push(mount);
+        self
+    }
+
+    pub fn with_env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.insert(key.into(), value.into());
+        self
+    }
+
+    /// 沙箱内可见的环境变量：白名单中宿主机已设置的变量 + 显式设置的变量
+    pub fn environment(&self) -> BTreeMap<String, String> {
+        let mut env: BTreeMap<String, String> = self
+            .env_passthrough
+            .iter()
+            .filter_map(|name| std::env::var(name).ok().map(|value| (name.clone(), value)))
+            .collect();
+        env.extend(self.env.clone());
+        env
+    }
+
+    /// 收紧墙钟时间（取较小值）
+    pub fn cap_wall_time(mut self, secs: Option<u64>) -> Self {
+        self.limits.wall_time_secs = match (self.limits.wall_time_secs, secs) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        self
+    }
+
+    /// 校验挂载白名单：拒绝敏感路径，工作目录必须位于某个挂载点内
+    pub fn validate(&self, workdir: &Path) -> Result<()> {
+        if self.backend == SandboxBackend::Host {
+            return Ok(());
+        }
+
+        for mount in &self.mounts {
+            let host = std::fs::canonicalize(&mount.host_path)
+                .map_err(|e| anyhow!("Sandbox mount {:?} is not accessible: {}", mount.host_path, e))?;
+
+            if DENIED_MOUNTS.iter().any(|denied| host == Path::new(denied))
+                || host.ends_with("docker.sock")
+            {
+                bail!("Sandbox mount {:?} is not allowed", mount.host_path);
+            }
+        }
+
+        if !self.mounts.iter().any(|mount| workdir.starts_with(mount.target())) {
+            bail!("Sandbox working directory {:?} is outside the mount allowlist", workdir);
+        }
+
+        Ok(())
+    }
+}
+
+/// 沙箱执行结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SandboxOutput {
+    pub backend: String,
+    /// 退出码（被信号终止或超时时为 None）
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+    pub timed_out: bool,
+    pub elapsed_ms: u64,
+}
+
+impl SandboxOutput {
+    pub fn success(&self) -> bool {
+        !self.timed_out && self.exit_code == Some(0)
+    }
+}
+
+/// 沙箱执行器
+pub struct Sandbox {
+    policy: SandboxPolicy,
+    /// 容器名（进程号 + 序号，便于超时后 kill，并发运行互不冲突）
+    container_name: String,
+}
+
+impl Sandbox {
+    pub fn new(policy: SandboxPolicy) -> Self {
+        static SEQ: AtomicU64 = AtomicU64::new(0);
+        let container_name = format!("acsa-sandbox-{}-{}", std::process::id(), SEQ.fetch_add(1, Ordering::Relaxed));
+        Self { policy, container_name }
+    }
+
+    pub fn policy(&self) -> &SandboxPolicy {
+        &self.policy
+    }
+
+    /// 生成完整命令行（第一个元素为可执行文件）
+    pub fn command_line(&self, program: &str, args: &[String], workdir: &Path) -> Result<Vec<String>> {
+        self.policy.validate(workdir)?;
+
+        let limits = &self.policy.limits;
+        let memory_bytes = limits.memory_mb * 1024 * 1024;
+        let workdir_str = workdir.display().to_string();
+        let mut argv: Vec<String> = Vec::new();
+
+        match &self.policy.backend {
+            SandboxBackend::Host => {
+                if !self.policy.allow_network {
+                    debug!("🔓 Host backend cannot enforce network isolation");
+                }
+                if let Some(secs) = limits.cpu_time_secs {
+                    argv.extend(["sh", "-c", "ulimit -t \"$0\" && exec \"$@\""].map(String::from));
+                    argv.push(secs.to_string());
+                }
+            }
+            SandboxBackend::Docker { image } | SandboxBackend::Podman { image } => {
+                argv.push(self.policy.backend.name().to_string());
+                argv.extend(["run", "--rm", "-i"].map(String::from));
+                argv.push(format!("--name={}", self.container_name()));
+                if !self.policy.allow_network {
+                    argv.push("--network=none".to_string());
+                }
+                argv.push(format!("--cpus={}", limits.cpu_cores));
+                argv.push(format!("--memory={}m", limits.memory_mb));
+                argv.extend(["--pids-limit=256", "--cap-drop=ALL", "--security-opt=no-new-privileges"].map(String::from));
+                if let Some(secs) = limits.cpu_time_secs {
+                    argv.push(format!("--ulimit=cpu={}", secs));
+                }
+                // 容器内不继承 CLI 进程的环境，只传入清洗后的变量
+                for (key, value) in self.policy.environment() {
+                    argv.push("-e".to_string());
+                    argv.push(format!("{}={}", key, value));
+                }
+                for mount in &self.policy.mounts {
+                    let mode = if mount.writable { "rw" } else { "ro" };
+                    argv.push("-v".to_string());
+                    argv.push(format!("{}:{}:{}", mount.host_path.display(), mount.target().display(), mode));
+                }
+                argv.push(format!("--workdir={}", workdir_str));
+                argv.push(image.clone());
+            }
+            SandboxBackend::Firejail => {
+                argv.extend(["firejail", "--quiet", "--noprofile", "--private", "--caps.drop=all", "--nonewprivs"].map(String::from));
+                if !self.policy.allow_network {
+                    argv.push("--net=none".to_string());
+                }
+                argv.push(format!("--rlimit-as={}", memory_bytes));
+                if let Some(secs) = limits.cpu_time_secs {
+                    argv.push(format!("--rlimit-cpu={}", secs));
+                }
+                let cores = (limits.cpu_cores.ceil() as usize).max(1);
+                argv.push(format!(
+                    "--cpu={}",
+                    (0..cores).map(|c| c.to_string()).collect::<Vec<_>>().join(",")
+                ));
+                for mount in &self.policy.mounts {
+                    argv.push(format!("--whitelist={}", mount.host_path.display()));
+                    if !mount.writable {
+                        argv.push(format!("--read-only={}", mount.host_path.display()));
+                    }
+                }
+                argv.push("--".to_string());
+            }
+            SandboxBackend::Bubblewrap => {
+                argv.extend(["bwrap", "--unshare-all", "--die-with-parent", "--new-session"].map(String::from));
+                if self.policy.allow_network {
+                    argv.push("--share-net".to_string());
+                }
+                for dir in BWRAP_SYSTEM_DIRS {
+                    argv.extend(["--ro-bind-try".to_string(), dir.to_string(), dir.to_string()]);
+                }
+                argv.extend(["--proc", "/proc", "--dev", "/dev", "--tmpfs", "/tmp"].map(String::from));
+                for mount in &self.policy.mounts {
+                    let flag = if mount.writable { "--bind" } else { "--ro-bind" };
+                    argv.push(flag.to_string());
+                    argv.push(mount.host_path.display().to_string());
+                    argv.push(mount.target().display().to_string());
+                }
+                argv.extend(["--chdir".to_string(), workdir_str, "--".to_string()]);
+                // bwrap 没有资源限制参数，借助 prlimit 限制地址空间与 CPU 时间
+                argv.extend(["prlimit".to_string(), format!("--as={}", memory_bytes)]);
+                if let Some(secs) = limits.cpu_time_secs {
+                    argv.push(format!("--cpu={}", secs));
+                }
+                argv.push("--".to_string());
+            }
+            SandboxBackend::Wasi { runtime } => {
+                argv.push(runtime.display().to_string());
+                argv.push("run".to_string());
+                argv.push(format!("-Wmax-memory-size={}", memory_bytes));
+                if let Some(secs) = limits.wall_time_secs {
+                    argv.push(format!("-Wtimeout={}s", secs));
+                }
+                if self.policy.allow_network {
+                    argv.push("-Sinherit-network".to_string());
+                }
+                for mount in &self.policy.mounts {
+                    argv.push(format!("--dir={}::{}", mount.host_path.display(), mount.target().display()));
+                }
+            }
+        }
+
+        argv.push(program.to_string());
+        argv.extend(args.iter().cloned());
+        Ok(argv)
+    }
+
+    /// 在沙箱中运行程序，超过墙钟时间则终止
+    pub async fn run(&self, program: &str, args: &[String], workdir: &Path) -> Result<SandboxOutput> {
+        let argv = self.command_line(program, args, workdir)?;
+        let backend = self.policy.backend.name();
+        info!("📦 Sandbox [{}]: {}", backend, program);
+        debug!("  Command: {:?}", argv);
+
+        let mut cmd = Command::new(&argv[0]);
+        if !self.policy.backend.is_container() {
+            // 容器后端的变量经 -e 传入；CLI 本身保留宿主机环境（DOCKER_HOST 等）
+            cmd.env_clear().envs(self.policy.environment());
+        }
+        cmd.args(&argv[1..])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        if matches!(self.policy.backend, SandboxBackend::Host | SandboxBackend::Wasi { .. }) {
+            cmd.current_dir(workdir);
+        }
+
+        let start = Instant::now();
+        let child = cmd
+            .spawn()
+            .map_err(|e| anyhow!("Failed to start {} sandbox: {}", backend, e))?;
+
+        let wait = child.wait_with_output();
+        let result = match self.policy.limits.wall_time_secs {
+            Some(secs) => tokio::time::timeout(Duration::from_secs(secs), wait).await,
+            None => Ok(wait.await),
+        };
+        let elapsed_ms = start.elapsed().as_millis() as u64;
+
+        match result {
+            Ok(output) => {
+                let output = output?;
+                Ok(SandboxOutput {
+                    backend: backend.to_string(),
+                    exit_code: output.status.code(),
+                    stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+                    stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+                    timed_out: false,
+                    elapsed_ms,
+                })
+            }
+            Err(_) => {
+                warn!("⏱️ Sandbox [{}] exceeded wall time after {}ms", backend, elapsed_ms);
+                // 杀掉 docker / podman CLI 不会停止容器本身
+                if self.policy.backend.is_container() {
+                    let _ = Command::new(backend)
+                        .args(["kill", &self.container_name()])
+                        .stdout(Stdio::null())
+                        .stderr(Stdio::null())
+                        .status()
+                        .await;
+                }
+                Ok(SandboxOutput {
+                    backend: backend.to_string(),
+                    exit_code: None,
+                    stdout: String::new(),
+                    stderr: format!("Sandbox wall time limit exceeded ({}ms)", elapsed_ms),
+                    timed_out: true,
+                    elapsed_ms,
+                })
+            }
+        }
+    }
+
+    fn container_name(&self) -> String {
+        self.container_name.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workspace() -> PathBuf {
+        std::env::temp_dir()
+    }
+
+    #[test]
+    fn test_docker_command_defaults_to_no_network() {
+        let dir = workspace();
+        let sandbox = Sandbox::new(
+            SandboxPolicy::default()
+                .with_limits(SandboxLimits {
+                    cpu_cores: 0.5,
+                    memory_mb: 256,
+                    wall_time_secs: Some(10),
+                    cpu_time_secs: Some(5),
+                })
+                .with_mount(SandboxMount::read_write(&dir))
+                .with_env("ACSA_RUN", "1"),
+        );
+
+        let argv = sandbox.command_line("python3", &["main.py".to_string()], &dir).unwrap();
+        assert_eq!(argv[0], "docker");
+        assert!(argv.contains(&"--network=none".to_string()));
+        assert!(argv.contains(&"--memory=256m".to_string()));
+        assert!(argv.contains(&"--cpus=0.5".to_string()));
+        assert!(argv.contains(&format!("{}:{}:rw", dir.display(), dir.display())));
+        assert!(argv.contains(&"--ulimit=cpu=5".to_string()));
+        assert!(argv.contains(&"ACSA_RUN=1".to_string()));
+        assert_eq!(argv[argv.len() - 2..], ["python3".to_string(), "main.py".to_string()]);
+    }
+
+    #[test]
+    fn test_bubblewrap_network_opt_in() {
+        let dir = workspace();
+        let policy = SandboxPolicy::new(SandboxBackend::Bubblewrap).with_mount(SandboxMount::read_only(&dir));
+
+        let isolated = Sandbox::new(policy.clone()).command_line("sh", &[], &dir).unwrap();
+        assert!(isolated.contains(&"--unshare-all".to_string()));
+        assert!(!isolated.contains(&"--share-net".to_string()));
+        assert!(isolated.contains(&"--ro-bind".to_string()));
+
+        let networked = Sandbox::new(policy.with_network(true)).command_line("sh", &[], &dir).unwrap();
+        assert!(networked.contains(&"--share-net".to_string()));
+    }
+
+    #[test]
+    fn test_mount_allowlist() {
+        let dir = workspace();
+
+        let root = SandboxPolicy::new(SandboxBackend::Firejail).with_mount(SandboxMount::read_write("/"));
+        assert!(root.validate(&dir).is_err());
+
+        let outside = SandboxPolicy::new(SandboxBackend::Firejail).with_mount(SandboxMount::read_only(&dir));
+        assert!(outside.validate(Path::new("/opt/elsewhere")).is_err());
+        assert!(outside.validate(&dir).is_ok());
+
+        // 宿主机后端不做挂载检查
+        assert!(SandboxPolicy::host().validate(Path::new("/")).is_ok());
+    }
+
+    #[test]
+    fn test_cap_wall_time() {
+        let policy = SandboxPolicy::host().cap_wall_time(Some(30));
+        assert_eq!(policy.limits.wall_time_secs, Some(30));
+
+        let unlimited = SandboxPolicy::host()
+            .with_limits(SandboxLimits {
+                wall_time_secs: None,
+                ..Default::default()
+            })
+            .cap_wall_time(Some(5));
+        assert_eq!(unlimited.limits.wall_time_secs, Some(5));
+    }
+
+    #[test]
+    fn test_policy_serde() {
+        let json = r#"{"backend":"docker","image":"python:3.12-slim","mounts":[{"host_path":"/tmp","writable":true}]}"#;
+        let policy: SandboxPolicy = serde_json::from_str(json).unwrap();
+
+        assert_eq!(policy.backend, SandboxBackend::Docker { image: "python:3.12-slim".to_string() });
+        assert!(!policy.allow_network);
+        assert_eq!(policy.limits.memory_mb, 512);
+    }
+
+    #[tokio::test]
+    async fn test_host_run_enforces_wall_time() {
+        let dir = workspace();
+        let sandbox = Sandbox::new(SandboxPolicy::host().with_limits(SandboxLimits {
+            wall_time_secs: Some(1),
+            ..Default::default()
+        }));
+
+        let ok = sandbox
+            .run("sh", &["-c".to_string(), "echo sandboxed".to_string()], &dir)
+            .await
+            .unwrap();
+        assert!(ok.success());
+        assert_eq!(ok.stdout.trim(), "sandboxed");
+
+        let slow = sandbox
+            .run("sh", &["-c".to_string(), "sleep 5".to_string()], &dir)
+            .await
+            .unwrap();
+        assert!(slow.timed_out);
+        assert!(!slow.success());
+    }
+
+    #[tokio::test]
+    async fn test_host_run_scrubs_environment() {
+        let dir = workspace();
+        let sandbox = Sandbox::new(SandboxPolicy::host().with_env("ACSA_RUN", "1"));
+
+        let output = sandbox
+            .run("sh", &["-c".to_string(), "echo ${HOME:-scrubbed} $ACSA_RUN".to_string()], &dir)
+            .await
+            .unwrap();
+        assert_eq!(output.stdout.trim(), "scrubbed 1");
+    }
+}
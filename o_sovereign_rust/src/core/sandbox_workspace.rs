@@ -0,0 +1,324 @@
+// Sandbox Workspace - 每次执行独立的临时工作目录
+// Omega / OpenCode 生成的代码在一次性目录中运行，结束后对比前后快照，写入执行回执
+//
+// 核心功能：
+// 1. 每次执行创建独立目录（`{root}/run_{millis}_{seq}`），可复制种子文件进去
+// 2. 路径白名单：策略中的其他挂载点必须位于允许的宿主机路径内，且一律只读
+// 3. 运行前后快照对比：新增 / 修改 / 删除的文件，文本文件附带行级 diff
+// 4. 默认执行后删除目录（keep 时保留，便于排查）
+//
+// 注：环境变量清洗、CPU / 内存 / 墙钟限制与容器后端由 SandboxPolicy 负责
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::{debug, info, warn};
+
+use super::sandbox::{Sandbox, SandboxMount, SandboxOutput, SandboxPolicy};
+
+/// 超过该大小的文件只比较哈希，不生成 diff
+pub const MAX_DIFF_FILE_BYTES: u64 = 256 * 1024;
+/// 单边超过该行数时不生成 diff（LCS 为 O(n·m)）
+const MAX_DIFF_LINES: usize = 2000;
+/// 快照最多记录的文件数
+const MAX_SNAPSHOT_FILES: usize = 10_000;
+
+/// 文件变化类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileChangeKind {
+    Added,
+    Modified,
+    Deleted,
+}
+
+/// 单个文件的变化
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileDiff {
+    /// 相对工作目录的路径
+    pub path: String,
+    pub kind: FileChangeKind,
+    pub lines_added: u32,
+    pub lines_removed: u32,
+    /// 行级 diff（`+` / `-` 前缀）；二进制或过大的文件为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diff: Option<String>,
+}
+
+/// 文件快照
+#[derive(Debug, Clone)]
+struct FileSnapshot {
+    digest: Vec<u8>,
+    /// UTF-8 且不超过 MAX_DIFF_FILE_BYTES 时保留内容
+    text: Option<String>,
+}
+
+type Snapshot = BTreeMap<String, FileSnapshot>;
+
+/// 单次执行的临时工作目录
+pub struct SandboxWorkspace {
+    run_id: String,
+    dir: PathBuf,
+    keep: bool,
+}
+
+impl SandboxWorkspace {
+    /// 在 root 下创建新目录
+    pub async fn create(root: &Path) -> Result<Self> {
+        static SEQ: AtomicU64 = AtomicU64::new(0);
+        let run_id = format!("run_{}_{}", Utc::now().timestamp_millis(), SEQ.fetch_add(1, Ordering::Relaxed));
+        let dir = root.join(&run_id);
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .with_context(|| format!("Failed to create sandbox workspace {}", dir.display()))?;
+        let dir = tokio::fs::canonicalize(&dir).await?;
+        debug!("📁 Sandbox workspace: {}", dir.display());
+        Ok(Self { run_id, dir, keep: false })
+    }
+
+    /// 执行后保留目录
+    pub fn with_keep(mut self, keep: bool) -> Self {
+        self.keep = keep;
+        self
+    }
+
+    pub fn run_id(&self) -> &str {
+        &self.run_id
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// 复制种子目录中的普通文件（跳过符号链接与 .git），返回复制的文件数
+    pub async fn seed(&self, source: &Path) -> Result<usize> {
+        let (source, target) = (source.to_path_buf(), self.dir.clone());
+        tokio::task::spawn_blocking(move || copy_tree(&source, &target)).await?
+    }
+
+    /// 写入文件（路径必须为工作目录内的相对路径）
+    pub async fn write(&self, relative: &str, content: &str) -> Result<PathBuf> {
+        let path = self.resolve(relative)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&path, content).await?;
+        Ok(path)
+    }
+
+    fn resolve(&self, relative: &str) -> Result<PathBuf> {
+        let relative = Path::new(relative);
+        if !relative.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir)) {
+            bail!("Path escapes the sandbox workspace: {}", relative.display());
+        }
+        Ok(self.dir.join(relative))
+    }
+
+    /// 把策略限制在本目录：其余挂载点必须位于 allowed 之内并改为只读，本目录以可写方式挂载
+    pub fn confine(&self, policy: SandboxPolicy, allowed: &[PathBuf]) -> Result<SandboxPolicy> {
+        let allowed: Vec<PathBuf> = allowed.iter().filter_map(|path| std::fs::canonicalize(path).ok()).collect();
+        let mut confined = SandboxPolicy { mounts: Vec::new(), ..policy.clone() };
+        for mount in policy.mounts {
+            let host = std::fs::canonicalize(&mount.host_path)
+                .map_err(|e| anyhow!("Sandbox mount {:?} is not accessible: {}", mount.host_path, e))?;
+            if !allowed.iter().any(|root| host.starts_with(root)) {
+                bail!("Sandbox mount {:?} is outside the path allowlist", mount.host_path);
+            }
+            confined = confined.with_mount(SandboxMount { host_path: host, writable: false, ..mount });
+        }
+        Ok(confined.with_mount(SandboxMount::read_write(&self.dir)))
+    }
+
+    /// 在本目录中运行程序，返回输出与文件变化
+    pub async fn run(&self, policy: SandboxPolicy, program: &str, args: &[String]) -> Result<(SandboxOutput, Vec<FileDiff>)> {
+        let before = self.snapshot().await?;
+        let output = Sandbox::new(policy).run(program, args, &self.dir).await?;
+        let after = self.snapshot().await?;
+        let diffs = diff_snapshots(&before, &after);
+        info!("📦 Sandbox run {}: {} file change(s)", self.run_id, diffs.len());
+        Ok((output, diffs))
+    }
+
+    async fn snapshot(&self) -> Result<Snapshot> {
+        let dir = self.dir.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut snapshot = Snapshot::new();
+            snapshot_tree(&dir, &dir, &mut snapshot)?;
+            Ok(snapshot)
+        })
+        .await?
+    }
+}
+
+impl Drop for SandboxWorkspace {
+    fn drop(&mut self) {
+        if self.keep {
+            info!("📁 Sandbox workspace kept: {}", self.dir.display());
+        } else if let Err(e) = std::fs::remove_dir_all(&self.dir) {
+            warn!("⚠️  Failed to remove sandbox workspace {}: {}", self.dir.display(), e);
+        }
+    }
+}
+
+fn copy_tree(source: &Path, target: &Path) -> Result<usize> {
+    let mut copied = 0;
+    for entry in std::fs::read_dir(source)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let to = target.join(entry.file_name());
+        if file_type.is_dir() && entry.file_name() != ".git" {
+            std::fs::create_dir_all(&to)?;
+            copied += copy_tree(&entry.path(), &to)?;
+        } else if file_type.is_file() {
+            std::fs::copy(entry.path(), &to)?;
+            copied += 1;
+        }
+    }
+    Ok(copied)
+}
+
+fn snapshot_tree(root: &Path, dir: &Path, snapshot: &mut Snapshot) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let path = entry.path();
+        if file_type.is_dir() {
+            snapshot_tree(root, &path, snapshot)?;
+        } else if file_type.is_file() {
+            if snapshot.len() >= MAX_SNAPSHOT_FILES {
+                bail!("Sandbox workspace has more than {} files", MAX_SNAPSHOT_FILES);
+            }
+            let bytes = std::fs::read(&path)?;
+            let text = (bytes.len() as u64 <= MAX_DIFF_FILE_BYTES)
+                .then(|| String::from_utf8(bytes.clone()).ok())
+                .flatten();
+            let relative = path.strip_prefix(root)?.to_string_lossy().replace('\\', "/");
+            snapshot.insert(
+                relative,
+                FileSnapshot {
+                    digest: ring::digest::digest(&ring::digest::SHA256, &bytes).as_ref().to_vec(),
+                    text,
+                },
+            );
+        }
+    }
+    Ok(())
+}
+
+fn diff_snapshots(before: &Snapshot, after: &Snapshot) -> Vec<FileDiff> {
+    let mut diffs = Vec::new();
+    for (path, new) in after {
+        match before.get(path) {
+            Some(old) if old.digest == new.digest => {}
+            Some(old) => diffs.push(file_diff(path, FileChangeKind::Modified, old.text.as_deref(), new.text.as_deref())),
+            None => diffs.push(file_diff(path, FileChangeKind::Added, Some(""), new.text.as_deref())),
+        }
+    }
+    for (path, old) in before {
+        if !after.contains_key(path) {
+            diffs.push(file_diff(path, FileChangeKind::Deleted, old.text.as_deref(), Some("")));
+        }
+    }
+    diffs
+}
+
+fn file_diff(path: &str, kind: FileChangeKind, before: Option<&str>, after: Option<&str>) -> FileDiff {
+    let (diff, lines_added, lines_removed) = match (before, after) {
+        (Some(before), Some(after)) => match line_diff(before, after) {
+            Some((diff, added, removed)) => (Some(diff), added, removed),
+            None => (None, 0, 0),
+        },
+        _ => (None, 0, 0),
+    };
+    FileDiff { path: path.to_string(), kind, lines_added, lines_removed, diff }
+}
+
+/// 基于 LCS 的行级 diff，只输出变化的行；返回 (diff, 新增行数, 删除行数)
+fn line_diff(before: &str, after: &str) -> Option<(String, u32, u32)> {
+    let (old, new): (Vec<&str>, Vec<&str>) = (before.lines().collect(), after.lines().collect());
+    if old.len() > MAX_DIFF_LINES || new.len() > MAX_DIFF_LINES {
+        return None;
+    }
+
+    // lcs[i][j] = old[i..] 与 new[j..] 的最长公共子序列长度
+    let mut lcs = vec![vec![0u32; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+        }
+    }
+
+    let (mut diff, mut added, mut removed) = (String::new(), 0, 0);
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            diff.push_str(&format!("-{}\n", old[i]));
+            removed += 1;
+            i += 1;
+        } else {
+            diff.push_str(&format!("+{}\n", new[j]));
+            added += 1;
+            j += 1;
+        }
+    }
+    Some((diff, added, removed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_run_reports_file_changes_and_cleans_up() {
+        let root = std::env::temp_dir().join("acsa_sandbox_workspace_test");
+        let workspace = SandboxWorkspace::create(&root).await.unwrap();
+        workspace.write("keep.txt", "a\nb\nc\n").await.unwrap();
+        workspace.write("gone.txt", "bye\n").await.unwrap();
+        assert!(workspace.write("../escape.txt", "x").await.is_err());
+
+        let script = "printf 'a\\nB\\nc\\n' > keep.txt && rm gone.txt && mkdir out && echo hi > out/new.txt";
+        let (output, diffs) = workspace
+            .run(SandboxPolicy::host(), "sh", &["-c".to_string(), script.to_string()])
+            .await
+            .unwrap();
+        assert!(output.success());
+
+        let kinds: Vec<_> = diffs.iter().map(|d| (d.path.as_str(), d.kind)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("keep.txt", FileChangeKind::Modified),
+                ("out/new.txt", FileChangeKind::Added),
+                ("gone.txt", FileChangeKind::Deleted),
+            ]
+        );
+        assert_eq!(diffs[0].diff.as_deref(), Some("-b\n+B\n"));
+        assert_eq!((diffs[1].lines_added, diffs[2].lines_removed), (1, 1));
+
+        let dir = workspace.dir().to_path_buf();
+        drop(workspace);
+        assert!(!dir.exists());
+    }
+
+    #[tokio::test]
+    async fn test_confine_enforces_path_allowlist() {
+        let root = std::env::temp_dir().join("acsa_sandbox_workspace_confine");
+        let workspace = SandboxWorkspace::create(&root).await.unwrap();
+        let shared = std::env::temp_dir();
+
+        let policy = SandboxPolicy::default().with_mount(SandboxMount::read_write(&shared));
+        assert!(workspace.confine(policy.clone(), &[]).is_err());
+
+        let confined = workspace.confine(policy, std::slice::from_ref(&shared)).unwrap();
+        assert_eq!(confined.mounts.len(), 2);
+        assert!(!confined.mounts[0].writable);
+        assert!(confined.mounts[1].writable && confined.mounts[1].host_path == workspace.dir());
+        assert!(confined.validate(workspace.dir()).is_ok());
+    }
+}
@@ -0,0 +1,320 @@
+// Secret Store - 系统钥匙串存储
+// API 密钥与 JWT 签名密钥保存在操作系统的凭据存储中，不写入数据目录
+//
+// 核心功能：
+// 1. SecretStore：按 (service, account) 读写、删除密钥
+// 2. macOS Keychain（`security`）、Windows Credential Manager（PowerShell PasswordVault）、
+//    Linux secret-service（`secret-tool`）三个后端，均调用系统自带工具，不引入原生依赖；写入的密钥一律经 stdin 传入
+// 3. MemorySecretStore：进程内存储（测试 / 无钥匙串环境）
+// 4. platform_secret_store()：按当前平台选择后端；ACSA_SECRET_STORE=os 时工作区的 API 密钥改存钥匙串
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::process::{Output, Stdio};
+use std::sync::{Arc, Mutex};
+use tokio::io::AsyncWriteExt;
+use tracing::debug;
+
+/// 本项目写入钥匙串时使用的 service 名称
+pub const SECRET_SERVICE: &str = "o-sovereign";
+
+/// 选择密钥存储的环境变量：`os`（系统钥匙串）或 `file`（默认，写入数据目录）
+pub const SECRET_STORE_ENV: &str = "ACSA_SECRET_STORE";
+
+/// 密钥存储
+#[async_trait]
+pub trait SecretStore: Send + Sync {
+    /// 后端名称
+    fn name(&self) -> &str;
+
+    /// 读取密钥，不存在时返回 None
+    async fn get(&self, service: &str, account: &str) -> Result<Option<String>>;
+
+    /// 写入（或覆盖）密钥
+    async fn set(&self, service: &str, account: &str, secret: &str) -> Result<()>;
+
+    /// 删除密钥，返回是否存在
+    async fn delete(&self, service: &str, account: &str) -> Result<bool>;
+}
+
+/// 进程内存储
+#[derive(Default)]
+pub struct MemorySecretStore {
+    secrets: Mutex<HashMap<(String, String), String>>,
+}
+
+impl MemorySecretStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl SecretStore for MemorySecretStore {
+    fn name(&self) -> &str {
+        "memory"
+    }
+
+    async fn get(&self, service: &str, account: &str) -> Result<Option<String>> {
+        let secrets = self.secrets.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(secrets.get(&(service.to_string(), account.to_string())).cloned())
+    }
+
+    async fn set(&self, service: &str, account: &str, secret: &str) -> Result<()> {
+        let mut secrets = self.secrets.lock().unwrap_or_else(|e| e.into_inner());
+        secrets.insert((service.to_string(), account.to_string()), secret.to_string());
+        Ok(())
+    }
+
+    async fn delete(&self, service: &str, account: &str) -> Result<bool> {
+        let mut secrets = self.secrets.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(secrets.remove(&(service.to_string(), account.to_string())).is_some())
+    }
+}
+
+/// macOS Keychain（generic password）
+pub struct MacKeychainStore;
+
+/// `security` 在条目不存在时的退出码
+const KEYCHAIN_ITEM_NOT_FOUND: i32 = 44;
+
+#[async_trait]
+impl SecretStore for MacKeychainStore {
+    fn name(&self) -> &str {
+        "macos-keychain"
+    }
+
+    async fn get(&self, service: &str, account: &str) -> Result<Option<String>> {
+        let output = run_tool("security", &["find-generic-password", "-s", service, "-a", account, "-w"], &[], None).await?;
+        match output.status.code() {
+            Some(0) => Ok(non_empty(&output.stdout)),
+            Some(KEYCHAIN_ITEM_NOT_FOUND) => Ok(None),
+            _ => Err(tool_error("security find-generic-password", &output)),
+        }
+    }
+
+    async fn set(&self, service: &str, account: &str, secret: &str) -> Result<()> {
+        // 密钥不能放进进程参数（ps 可见）：`security -i` 从 stdin 读取命令；-U：已存在时更新
+        let command = keychain_command(&["add-generic-password", "-U", "-s", service, "-a", account, "-w", secret])?;
+        let output = run_tool("security", &["-i"], &[], Some(&command)).await?;
+        // 交互模式下单条命令失败时退出码仍为 0，错误只出现在 stderr
+        if !output.stderr.is_empty() {
+            return Err(tool_error("security add-generic-password", &output));
+        }
+        ensure_success("security add-generic-password", &output)
+    }
+
+    async fn delete(&self, service: &str, account: &str) -> Result<bool> {
+        let output = run_tool("security", &["delete-generic-password", "-s", service, "-a", account], &[], None).await?;
+        match output.status.code() {
+            Some(0) => Ok(true),
+            Some(KEYCHAIN_ITEM_NOT_FOUND) => Ok(false),
+            _ => Err(tool_error("security delete-generic-password", &output)),
+        }
+    }
+}
+
+/// Linux secret-service（GNOME Keyring / KWallet，经由 `secret-tool`）
+pub struct SecretServiceStore;
+
+#[async_trait]
+impl SecretStore for SecretServiceStore {
+    fn name(&self) -> &str {
+        "secret-service"
+    }
+
+    async fn get(&self, service: &str, account: &str) -> Result<Option<String>> {
+        // 条目不存在时 secret-tool 以非零码退出且无输出
+        let output = run_tool("secret-tool", &["lookup", "service", service, "account", account], &[], None).await?;
+        if output.status.success() {
+            return Ok(non_empty(&output.stdout));
+        }
+        if output.stderr.is_empty() {
+            return Ok(None);
+        }
+        Err(tool_error("secret-tool lookup", &output))
+    }
+
+    async fn set(&self, service: &str, account: &str, secret: &str) -> Result<()> {
+        // 密钥经 stdin 传入，不出现在进程参数中
+        let label = format!("--label={} ({})", service, account);
+        let output = run_tool(
+            "secret-tool",
+            &["store", &label, "service", service, "account", account],
+            &[],
+            Some(secret),
+        )
+        .await?;
+        ensure_success("secret-tool store", &output)
+    }
+
+    async fn delete(&self, service: &str, account: &str) -> Result<bool> {
+        let existed = self.get(service, account).await?.is_some();
+        let output = run_tool("secret-tool", &["clear", "service", service, "account", account], &[], None).await?;
+        ensure_success("secret-tool clear", &output)?;
+        Ok(existed)
+    }
+}
+
+/// Windows Credential Manager（WinRT PasswordVault，经由 PowerShell）
+pub struct WindowsCredentialStore;
+
+impl WindowsCredentialStore {
+    /// service / account 经环境变量传入脚本，避免引号转义问题
+    const PRELUDE: &'static str = "$ErrorActionPreference = 'Stop'; \
+        [void][Windows.Security.Credentials.PasswordVault, Windows.Security.Credentials, ContentType = WindowsRuntime]; \
+        $vault = New-Object Windows.Security.Credentials.PasswordVault; \
+        $service = $env:ACSA_SECRET_SERVICE; $account = $env:ACSA_SECRET_ACCOUNT; \
+        function Find { try { $vault.Retrieve($service, $account) } catch { $null } }; ";
+
+    async fn powershell(service: &str, account: &str, script: &str, stdin: Option<&str>) -> Result<Output> {
+        let script = format!("{}{}", Self::PRELUDE, script);
+        run_tool(
+            "powershell",
+            &["-NoProfile", "-NonInteractive", "-Command", &script],
+            &[("ACSA_SECRET_SERVICE", service), ("ACSA_SECRET_ACCOUNT", account)],
+            stdin,
+        )
+        .await
+    }
+}
+
+#[async_trait]
+impl SecretStore for WindowsCredentialStore {
+    fn name(&self) -> &str {
+        "windows-credential-manager"
+    }
+
+    async fn get(&self, service: &str, account: &str) -> Result<Option<String>> {
+        let script = "$c = Find; if ($c) { $c.RetrievePassword(); [Console]::Out.Write($c.Password) }";
+        let output = Self::powershell(service, account, script, None).await?;
+        ensure_success("PasswordVault.Retrieve", &output)?;
+        Ok(non_empty(&output.stdout))
+    }
+
+    async fn set(&self, service: &str, account: &str, secret: &str) -> Result<()> {
+        let script = "$secret = [Console]::In.ReadToEnd(); $c = Find; if ($c) { $vault.Remove($c) }; \
+            $vault.Add((New-Object Windows.Security.Credentials.PasswordCredential($service, $account, $secret)))";
+        let output = Self::powershell(service, account, script, Some(secret)).await?;
+        ensure_success("PasswordVault.Add", &output)
+    }
+
+    async fn delete(&self, service: &str, account: &str) -> Result<bool> {
+        let script = "$c = Find; if ($c) { $vault.Remove($c); [Console]::Out.Write('1') }";
+        let output = Self::powershell(service, account, script, None).await?;
+        ensure_success("PasswordVault.Remove", &output)?;
+        Ok(!output.stdout.is_empty())
+    }
+}
+
+/// 当前平台的系统钥匙串（不支持的平台返回 None）
+pub fn platform_secret_store() -> Option<Arc<dyn SecretStore>> {
+    if cfg!(target_os = "macos") {
+        Some(Arc::new(MacKeychainStore))
+    } else if cfg!(target_os = "windows") {
+        Some(Arc::new(WindowsCredentialStore))
+    } else if cfg!(target_os = "linux") {
+        Some(Arc::new(SecretServiceStore))
+    } else {
+        debug!("🔑 No OS secret store on this platform");
+        None
+    }
+}
+
+/// 按 ACSA_SECRET_STORE 选择存储（`file` 或未设置时为 None）
+pub fn configured_secret_store() -> Result<Option<Arc<dyn SecretStore>>> {
+    match std::env::var(SECRET_STORE_ENV).unwrap_or_default().trim().to_lowercase().as_str() {
+        "" | "file" => Ok(None),
+        "os" | "keychain" | "keyring" => platform_secret_store()
+            .map(Some)
+            .ok_or_else(|| anyhow!("{}: no OS secret store on this platform", SECRET_STORE_ENV)),
+        other => Err(anyhow!("{}: unknown secret store '{}' (expected os or file)", SECRET_STORE_ENV, other)),
+    }
+}
+
+async fn run_tool(program: &str, args: &[&str], envs: &[(&str, &str)], stdin: Option<&str>) -> Result<Output> {
+    let mut command = tokio::process::Command::new(program);
+    command
+        .args(args)
+        .envs(envs.iter().copied())
+        .stdin(if stdin.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    let mut child = command
+        .spawn()
+        .with_context(|| format!("Secret store tool '{}' is not available", program))?;
+    if let (Some(input), Some(mut pipe)) = (stdin, child.stdin.take()) {
+        pipe.write_all(input.as_bytes()).await?;
+        // 关闭 stdin，工具才会读到 EOF
+        drop(pipe);
+    }
+    Ok(child.wait_with_output().await?)
+}
+
+/// 拼出 `security -i` 的一行命令（参数加双引号，转义 `\` 与 `"`；无法表示换行）
+fn keychain_command(args: &[&str]) -> Result<String> {
+    let mut line = String::new();
+    for arg in args {
+        if arg.contains(['\n', '\r']) {
+            return Err(anyhow!("Keychain values cannot contain line breaks"));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push('"');
+        line.push_str(&arg.replace('\\', "\\\\").replace('"', "\\\""));
+        line.push('"');
+    }
+    line.push('\n');
+    Ok(line)
+}
+
+fn non_empty(stdout: &[u8]) -> Option<String> {
+    let value = String::from_utf8_lossy(stdout).trim_end_matches(['\r', '\n']).to_string();
+    (!value.is_empty()).then_some(value)
+}
+
+fn ensure_success(operation: &str, output: &Output) -> Result<()> {
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(tool_error(operation, output))
+    }
+}
+
+fn tool_error(operation: &str, output: &Output) -> anyhow::Error {
+    anyhow!(
+        "{} failed ({}): {}",
+        operation,
+        output.status,
+        String::from_utf8_lossy(&output.stderr).trim()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_memory_store_roundtrip() {
+        let store = MemorySecretStore::new();
+        assert_eq!(store.get(SECRET_SERVICE, "jwt_secret").await.unwrap(), None);
+
+        store.set(SECRET_SERVICE, "jwt_secret", "first").await.unwrap();
+        store.set(SECRET_SERVICE, "jwt_secret", "second").await.unwrap();
+        assert_eq!(store.get(SECRET_SERVICE, "jwt_secret").await.unwrap().as_deref(), Some("second"));
+        assert_eq!(store.get("other", "jwt_secret").await.unwrap(), None);
+
+        assert!(store.delete(SECRET_SERVICE, "jwt_secret").await.unwrap());
+        assert!(!store.delete(SECRET_SERVICE, "jwt_secret").await.unwrap());
+    }
+
+    #[test]
+    fn test_keychain_command_quotes_arguments() {
+        let line = keychain_command(&["add-generic-password", "-w", r#"p@ss "word"\x"#]).unwrap();
+        assert_eq!(line, "\"add-generic-password\" \"-w\" \"p@ss \\\"word\\\"\\\\x\"\n");
+        assert!(keychain_command(&["-w", "two\nlines"]).is_err());
+    }
+}
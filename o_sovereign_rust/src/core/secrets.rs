@@ -0,0 +1,361 @@
+// Secrets - 密钥解析层
+// 配置中使用 `${secret:...}` 引用密钥，加载时再解析，避免明文密钥进入配置文件
+//
+// 引用语法：
+// 1. `${secret:NAME}`              依次尝试 env → keyring → file
+// 2. `${secret:env:NAME}`          环境变量
+// 3. `${secret:keyring:SERVICE/ACCOUNT}`  系统钥匙串
+// 4. `${secret:file:NAME}`         ChaCha20-Poly1305 加密的密钥文件
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::debug;
+
+use super::secret_store::{platform_secret_store, SecretStore};
+
+const SECRET_PREFIX: &str = "${secret:";
+
+/// 未指定来源时的默认查找顺序
+pub const DEFAULT_SECRET_CHAIN: [&str; 3] = ["env", "keyring", "file"];
+
+/// 密钥后端
+#[async_trait]
+pub trait SecretBackend: Send + Sync {
+    /// 后端名称（即引用中的 scheme）
+    fn name(&self) -> &str;
+
+    /// 查找密钥，不存在时返回 None
+    async fn get(&self, name: &str) -> Result<Option<String>>;
+}
+
+/// 环境变量后端
+pub struct EnvSecretBackend;
+
+#[async_trait]
+impl SecretBackend for EnvSecretBackend {
+    fn name(&self) -> &str {
+        "env"
+    }
+
+    async fn get(&self, name: &str) -> Result<Option<String>> {
+        Ok(std::env::var(name).ok())
+    }
+}
+
+/// 系统钥匙串后端（macOS Keychain / Windows Credential Manager / Linux secret-service）
+///
+/// 名称格式为 `SERVICE/ACCOUNT`，只有 `SERVICE` 时账号默认为 `acsa`。
+pub struct KeyringSecretBackend {
+    store: Option<Arc<dyn SecretStore>>,
+}
+
+impl KeyringSecretBackend {
+    /// 使用当前平台的钥匙串
+    pub fn new() -> Self {
+        Self {
+            store: platform_secret_store(),
+        }
+    }
+
+    /// 使用指定的存储（测试或自定义后端）
+    pub fn with_store(store: Arc<dyn SecretStore>) -> Self {
+        Self { store: Some(store) }
+    }
+
+    fn split_name(name: &str) -> (&str, &str) {
+        name.split_once('/').unwrap_or((name, "acsa"))
+    }
+}
+
+impl Default for KeyringSecretBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl SecretBackend for KeyringSecretBackend {
+    fn name(&self) -> &str {
+        "keyring"
+    }
+
+    async fn get(&self, name: &str) -> Result<Option<String>> {
+        let Some(store) = &self.store else {
+            debug!("🔑 Keyring backend not supported on this platform");
+            return Ok(None);
+        };
+        let (service, account) = Self::split_name(name);
+
+        // 钥匙串工具不存在或不可用都视为未找到，继续尝试后续来源
+        match store.get(service, account).await {
+            Ok(value) => Ok(value),
+            Err(e) => {
+                debug!("🔑 Keyring lookup unavailable: {:#}", e);
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// 加密密钥文件中的一条记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SealedSecret {
+    /// Nonce（base64）
+    nonce: String,
+    /// 密文与认证标签（base64）
+    ciphertext: String,
+}
+
+/// 加密密钥文件后端：JSON 对象 `{ "NAME": { nonce, ciphertext } }`
+///
+/// ChaCha20-Poly1305（ring::aead）加密，密钥名作为附加认证数据，防止密文在条目间挪用。
+/// 256 位数据密钥保存在旁边的 `.key` 文件（Unix 下权限 0600），首次写入时生成，
+/// 因此不同进程可以读取彼此写入的密钥文件。
+pub struct EncryptedFileSecretBackend {
+    path: PathBuf,
+    key_path: PathBuf,
+}
+
+impl EncryptedFileSecretBackend {
+    /// 数据密钥默认保存在 `<path>.key`
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let mut key_path = path.clone().into_os_string();
+        key_path.push(".key");
+        Self {
+            path,
+            key_path: key_path.into(),
+        }
+    }
+
+    /// 指定数据密钥文件位置（例如放在与密钥文件不同的卷上）
+    pub fn with_key_path(mut self, key_path: impl Into<PathBuf>) -> Self {
+        self.key_path = key_path.into();
+        self
+    }
+
+    async fn read_all(&self) -> Result<HashMap<String, SealedSecret>> {
+        match tokio::fs::read_to_string(&self.path).await {
+            Ok(content) => serde_json::from_str(&content)
+                .with_context(|| format!("Invalid secrets file {:?}", self.path)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(HashMap::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// 读取数据密钥；`create` 为 true 且文件不存在时生成新密钥
+    async fn load_key(&self, create: bool) -> Result<Option<LessSafeKey>> {
+        let bytes = match tokio::fs::read_to_string(&self.key_path).await {
+            Ok(encoded) => BASE64
+                .decode(encoded.trim())
+                .with_context(|| format!("Invalid secrets key file {:?}", self.key_path))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && create => {
+                let mut bytes = vec![0u8; CHACHA20_POLY1305.key_len()];
+                SystemRandom::new()
+                    .fill(&mut bytes)
+                    .map_err(|_| anyhow!("Failed to generate secrets key"))?;
+                write_private(&self.key_path, BASE64.encode(&bytes).as_bytes()).await?;
+                bytes
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let key = UnboundKey::new(&CHACHA20_POLY1305, &bytes)
+            .map_err(|_| anyhow!("Secrets key in {:?} has the wrong length", self.key_path))?;
+        Ok(Some(LessSafeKey::new(key)))
+    }
+
+    /// 加密并写入一个密钥
+    pub async fn write_secret(&self, name: &str, value: &str) -> Result<()> {
+        let mut secrets = self.read_all().await?;
+        let key = self
+            .load_key(true)
+            .await?
+            .ok_or_else(|| anyhow!("Secrets key unavailable: {:?}", self.key_path))?;
+
+        let mut nonce = [0u8; NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| anyhow!("Failed to generate nonce"))?;
+        let mut in_out = value.as_bytes().to_vec();
+        key.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(name.as_bytes()), &mut in_out)
+            .map_err(|_| anyhow!("Failed to encrypt secret '{}'", name))?;
+
+        secrets.insert(
+            name.to_string(),
+            SealedSecret {
+                nonce: BASE64.encode(nonce),
+                ciphertext: BASE64.encode(&in_out),
+            },
+        );
+        write_private(&self.path, serde_json::to_string_pretty(&secrets)?.as_bytes()).await
+    }
+}
+
+/// 写入仅所有者可读写的文件
+async fn write_private(path: &Path, contents: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(path, contents)
+        .await
+        .with_context(|| format!("Failed to write {:?}", path))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        tokio::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600)).await?;
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl SecretBackend for EncryptedFileSecretBackend {
+    fn name(&self) -> &str {
+        "file"
+    }
+
+    async fn get(&self, name: &str) -> Result<Option<String>> {
+        let secrets = self.read_all().await?;
+        let Some(sealed) = secrets.get(name) else {
+            return Ok(None);
+        };
+        let key = self
+            .load_key(false)
+            .await?
+            .ok_or_else(|| anyhow!("Secrets key file {:?} is missing", self.key_path))?;
+
+        let nonce: [u8; NONCE_LEN] = BASE64
+            .decode(&sealed.nonce)?
+            .try_into()
+            .map_err(|_| anyhow!("Invalid nonce for secret '{}'", name))?;
+        let mut in_out = BASE64.decode(&sealed.ciphertext)?;
+        let plaintext = key
+            .open_in_place(Nonce::assume_unique_for_key(nonce), Aad::from(name.as_bytes()), &mut in_out)
+            .map_err(|_| anyhow!("Failed to decrypt secret '{}' (wrong key or tampered file)", name))?;
+        Ok(Some(String::from_utf8(plaintext.to_vec()).context("Secret is not valid UTF-8")?))
+    }
+}
+
+/// 密钥解析器
+pub struct SecretResolver {
+    backends: HashMap<String, Arc<dyn SecretBackend>>,
+}
+
+impl SecretResolver {
+    /// 默认解析器：env + keyring（加密文件后端需指定文件路径，需单独注册）
+    pub fn new() -> Self {
+        let mut resolver = Self {
+            backends: HashMap::new(),
+        };
+        resolver.register(Arc::new(EnvSecretBackend));
+        resolver.register(Arc::new(KeyringSecretBackend::new()));
+        resolver
+    }
+
+    /// 注册（或替换）后端
+    pub fn register(&mut self, backend: Arc<dyn SecretBackend>) {
+        self.backends.insert(backend.name().to_string(), backend);
+    }
+
+    /// 字符串中是否包含密钥引用
+    pub fn contains_reference(text: &str) -> bool {
+        text.contains(SECRET_PREFIX)
+    }
+
+    /// 解析单个引用体（`${secret:` 与 `}` 之间的部分）
+    pub async fn resolve_reference(&self, reference: &str) -> Result<String> {
+        let (chain, name): (Vec<&str>, &str) = match reference.split_once(':') {
+            Some((scheme, name)) if self.backends.contains_key(scheme) => (vec![scheme], name),
+            _ => (DEFAULT_SECRET_CHAIN.to_vec(), reference),
+        };
+
+        for scheme in chain {
+            if let Some(backend) = self.backends.get(scheme) {
+                if let Some(value) = backend.get(name).await? {
+                    debug!("🔑 Resolved secret '{}' from {}", name, scheme);
+                    return Ok(value);
+                }
+            }
+        }
+
+        Err(anyhow!("Secret not found: {}", reference))
+    }
+
+    /// 替换字符串中的所有 `${secret:...}` 引用
+    pub async fn resolve_str(&self, text: &str) -> Result<String> {
+        let mut output = String::with_capacity(text.len());
+        let mut rest = text;
+
+        while let Some(start) = rest.find(SECRET_PREFIX) {
+            output.push_str(&rest[..start]);
+            let after = &rest[start + SECRET_PREFIX.len()..];
+            let end = after
+                .find('}')
+                .ok_or_else(|| anyhow!("Unterminated secret reference in config value"))?;
+
+            output.push_str(&self.resolve_reference(&after[..end]).await?);
+            rest = &after[end + 1..];
+        }
+
+        output.push_str(rest);
+        Ok(output)
+    }
+}
+
+impl Default for SecretResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_resolve_env_reference() {
+        std::env::set_var("ACSA_TEST_SECRET_TOKEN", "s3cr3t");
+        let resolver = SecretResolver::new();
+
+        let value = resolver
+            .resolve_str("Bearer ${secret:env:ACSA_TEST_SECRET_TOKEN}")
+            .await
+            .unwrap();
+        assert_eq!(value, "Bearer s3cr3t");
+        assert!(resolver.resolve_str("${secret:env:ACSA_TEST_MISSING}").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_file_backend() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("secrets.json");
+        let backend = Arc::new(EncryptedFileSecretBackend::new(&path));
+        backend.write_secret("openai", "sk-test").await.unwrap();
+
+        // 磁盘上不得出现明文；新实例（模拟另一个进程）可以用持久化的数据密钥解密
+        let on_disk = std::fs::read_to_string(&path).unwrap();
+        assert!(!on_disk.contains("sk-test"));
+        assert!(!on_disk.contains(&BASE64.encode("sk-test")));
+        let reopened = EncryptedFileSecretBackend::new(&path);
+        assert_eq!(reopened.get("openai").await.unwrap().as_deref(), Some("sk-test"));
+
+        // 密文挪到其他名称下会因附加认证数据不匹配而解密失败
+        let mut entries: serde_json::Value = serde_json::from_str(&on_disk).unwrap();
+        entries["other"] = entries["openai"].clone();
+        std::fs::write(&path, entries.to_string()).unwrap();
+        assert!(reopened.get("other").await.is_err());
+
+        let mut resolver = SecretResolver::new();
+        resolver.register(backend);
+        assert_eq!(resolver.resolve_str("${secret:file:openai}").await.unwrap(), "sk-test");
+        assert_eq!(resolver.resolve_str("${secret:openai}").await.unwrap(), "sk-test");
+    }
+}
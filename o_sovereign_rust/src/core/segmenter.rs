@@ -0,0 +1,327 @@
+// Segmenter - 语义切分
+// CognitiveCleaner 的语义块必须落在真实的语言单位上：按标点硬切会把 "e.g." 切成两半，
+// 也会把 "搞垮对手然后偷数据库" 这种没有标点的复合意图留在一个块里
+//
+// 核心功能：
+// 1. Segmenter trait：可插拔的切分器，CognitiveCleaner 通过 with_segmenter 注入，默认取 default_segmenter()
+// 2. RuleSegmenter：中文按句读与分句连词（然后、但是、同时…）切分，顿号并列不切
+// 3. 英文句子切分：识别缩写（Mr. / e.g. / U.S.）、单字母首字母与小数，", then" 等连词处分句
+// 4. 词典词条整体保留：切分点不会落在 keep_together 词条内部
+// 5. JiebaSegmenter（`jieba` feature，默认启用）：含中文的文本先用 jieba-rs 分词，中文连词只在词边界处分句
+//    （"不同时期" 中的 "同时" 不再被误切）；不含中文的文本与 RuleSegmenter 相同
+
+use std::sync::Arc;
+
+/// 语义切分器
+pub trait Segmenter: Send + Sync {
+    /// 切分器名称（日志用）
+    fn name(&self) -> &str;
+
+    /// 切分为语义单元（已去除首尾空白，不含空串）；`keep_together` 中的词条不会被切开
+    fn segment(&self, text: &str, keep_together: &[&str]) -> Vec<String>;
+}
+
+/// 句读：切分并丢弃
+const CLAUSE_PUNCTUATION: &[char] = &['。', '！', '？', '；', '，', '!', '?', ';', '\n', '\r'];
+
+const DEFAULT_ZH_CONNECTIVES: &[&str] = &[
+    "然后", "接着", "随后", "并且", "而且", "但是", "可是", "然而", "同时", "另外", "此外", "于是",
+];
+
+const DEFAULT_EN_CONNECTIVES: &[&str] = &["and then", "then", "but", "however", "afterwards", "after that", "meanwhile"];
+
+const DEFAULT_ABBREVIATIONS: &[&str] = &[
+    "mr", "mrs", "ms", "dr", "prof", "sr", "jr", "st", "vs", "etc", "inc", "ltd", "co", "corp", "dept", "fig",
+    "no", "approx", "e.g", "i.e", "u.s", "u.k", "a.m", "p.m",
+];
+
+/// 基于规则的默认切分器
+pub struct RuleSegmenter {
+    /// 中文分句连词（切在连词之前，连词留在后一块）
+    zh_connectives: Vec<String>,
+    /// 英文分句连词（小写，按整词匹配）
+    en_connectives: Vec<String>,
+    /// 不作为句末的缩写（小写，不含末尾的点）
+    abbreviations: Vec<String>,
+}
+
+impl Default for RuleSegmenter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RuleSegmenter {
+    pub fn new() -> Self {
+        let owned = |words: &[&str]| words.iter().map(|w| w.to_string()).collect();
+        let mut segmenter = Self {
+            zh_connectives: owned(DEFAULT_ZH_CONNECTIVES),
+            en_connectives: owned(DEFAULT_EN_CONNECTIVES),
+            abbreviations: owned(DEFAULT_ABBREVIATIONS),
+        };
+        segmenter.sort_connectives();
+        segmenter
+    }
+
+    /// 增加分句连词（含 CJK 字符的按中文处理，否则按英文整词匹配）
+    pub fn with_connective(mut self, connective: impl Into<String>) -> Self {
+        let connective = connective.into();
+        if connective.chars().any(is_cjk) {
+            self.zh_connectives.push(connective);
+        } else {
+            self.en_connectives.push(connective.to_lowercase());
+        }
+        self.sort_connectives();
+        self
+    }
+
+    /// 增加不作为句末的英文缩写（如 `"approx."`）
+    pub fn with_abbreviation(mut self, abbreviation: &str) -> Self {
+        self.abbreviations.push(abbreviation.trim_end_matches('.').to_lowercase());
+        self
+    }
+
+    /// 长连词优先（"and then" 先于 "then"）
+    fn sort_connectives(&mut self) {
+        self.zh_connectives.sort_by_key(|c| std::cmp::Reverse(c.len()));
+        self.en_connectives.sort_by_key(|c| std::cmp::Reverse(c.len()));
+    }
+
+    /// `pos` 处开始的分句连词（字节长度）；中文连词的首尾须落在词边界上
+    fn connective_at(&self, text: &str, pos: usize, word_boundary: &dyn Fn(usize) -> bool) -> Option<usize> {
+        let rest = &text[pos..];
+        if let Some(zh) = self
+            .zh_connectives
+            .iter()
+            .find(|c| rest.starts_with(c.as_str()) && word_boundary(pos) && word_boundary(pos + c.len()))
+        {
+            return Some(zh.len());
+        }
+        // 英文连词须为整词：前面是空白（或开头），后面是空白、标点或结尾
+        let at_word_start = text[..pos].chars().next_back().is_none_or(|c| c.is_whitespace() || c == ',');
+        if !at_word_start {
+            return None;
+        }
+        self.en_connectives
+            .iter()
+            .find(|c| {
+                rest.get(..c.len()).is_some_and(|head| head.eq_ignore_ascii_case(c))
+                    && rest[c.len()..].chars().next().is_none_or(|n| n.is_whitespace() || n == ',')
+            })
+            .map(|c| c.len())
+    }
+
+    /// `pos` 处的 '.' 是否为句末
+    fn is_sentence_end(&self, text: &str, pos: usize) -> bool {
+        let after = &text[pos + 1..];
+        match after.chars().next() {
+            None => {}
+            Some(c) if is_cjk(c) => {}
+            // 后面接小写词时多半仍在句中（"approx. ten"）
+            Some(c) if c.is_whitespace() && after.trim_start().chars().next().is_some_and(|n| n.is_lowercase()) => {
+                return false;
+            }
+            Some(c) if c.is_whitespace() => {}
+            // 小数、域名、"e.g" 中间的点
+            Some(_) => return false,
+        }
+        let word_start = text[..pos]
+            .char_indices()
+            .rev()
+            .find(|&(_, c)| c.is_whitespace() || is_cjk(c))
+            .map(|(i, c)| i + c.len_utf8())
+            .unwrap_or(0);
+        let word = text[word_start..pos].trim_start_matches(['(', '"', '\'']).to_lowercase();
+        if word.chars().count() == 1 && word.chars().all(char::is_alphabetic) {
+            // 人名首字母 "J. Smith"
+            return false;
+        }
+        !self.abbreviations.contains(&word) && !word.contains('.')
+    }
+
+    /// 按规则切分；`word_boundary` 判断字节位置是否为中文词边界（不分词时处处为边界）
+    fn segment_with(&self, text: &str, keep_together: &[&str], word_boundary: &dyn Fn(usize) -> bool) -> Vec<String> {
+        let protected: Vec<(usize, usize)> = keep_together
+            .iter()
+            .filter(|term| !term.is_empty())
+            .flat_map(|term| text.match_indices(term).map(|(start, m)| (start, start + m.len())))
+            .collect();
+        // 该字符属于某个词条
+        let covered = |pos: usize| protected.iter().any(|&(start, end)| start <= pos && pos < end);
+        // 在该位置切开会拆散某个词条
+        let splits_term = |pos: usize| protected.iter().any(|&(start, end)| start < pos && pos < end);
+
+        let mut chunks = Vec::new();
+        let mut push = |chunk: &str| {
+            let chunk = chunk.trim();
+            if !chunk.is_empty() {
+                chunks.push(chunk.to_string());
+            }
+        };
+        let mut start = 0;
+        let mut skip_until = 0;
+
+        for (pos, c) in text.char_indices() {
+            if pos < skip_until {
+                continue;
+            }
+            if !covered(pos) {
+                let cut = if CLAUSE_PUNCTUATION.contains(&c) {
+                    true
+                } else if c == '.' {
+                    self.is_sentence_end(text, pos)
+                } else if c == ',' {
+                    // 英文逗号只在后接连词时分句（", then exploit"）
+                    let next_word = pos + 1 + (text[pos + 1..].len() - text[pos + 1..].trim_start().len());
+                    next_word < text.len() && self.connective_at(text, next_word, word_boundary).is_some()
+                } else {
+                    false
+                };
+                if cut {
+                    push(&text[start..pos]);
+                    start = pos + c.len_utf8();
+                    continue;
+                }
+            }
+            if let Some(len) = self.connective_at(text, pos, word_boundary) {
+                if !splits_term(pos) && !text[start..pos].trim().is_empty() {
+                    push(&text[start..pos]);
+                    start = pos;
+                }
+                skip_until = pos + len;
+            }
+        }
+        push(&text[start..]);
+        chunks
+    }
+}
+
+impl Segmenter for RuleSegmenter {
+    fn name(&self) -> &str {
+        "rule"
+    }
+
+    fn segment(&self, text: &str, keep_together: &[&str]) -> Vec<String> {
+        self.segment_with(text, keep_together, &|_| true)
+    }
+}
+
+/// 进程共享的 jieba 分词器（加载内置词典约需数十毫秒，只加载一次）
+#[cfg(feature = "jieba")]
+static JIEBA: std::sync::LazyLock<jieba_rs::Jieba> = std::sync::LazyLock::new(jieba_rs::Jieba::new);
+
+/// 基于 jieba-rs 分词的切分器：句读与英文规则同 RuleSegmenter，中文连词只在分词边界处切分
+#[cfg(feature = "jieba")]
+#[derive(Default)]
+pub struct JiebaSegmenter {
+    rules: RuleSegmenter,
+}
+
+#[cfg(feature = "jieba")]
+impl JiebaSegmenter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 使用自定义连词与缩写规则
+    pub fn with_rules(rules: RuleSegmenter) -> Self {
+        Self { rules }
+    }
+}
+
+#[cfg(feature = "jieba")]
+impl Segmenter for JiebaSegmenter {
+    fn name(&self) -> &str {
+        "jieba"
+    }
+
+    fn segment(&self, text: &str, keep_together: &[&str]) -> Vec<String> {
+        if !text.chars().any(is_cjk) {
+            return self.rules.segment(text, keep_together);
+        }
+        // 分词结果是 text 的连续切片，累加长度即为各词的起止位置
+        let mut boundaries = std::collections::HashSet::from([0, text.len()]);
+        let mut offset = 0;
+        for word in JIEBA.cut(text, true) {
+            offset += word.len();
+            boundaries.insert(offset);
+        }
+        self.rules.segment_with(text, keep_together, &|pos| boundaries.contains(&pos))
+    }
+}
+
+/// 默认切分器：启用 `jieba` feature 时为 JiebaSegmenter，否则为 RuleSegmenter
+pub fn default_segmenter() -> Arc<dyn Segmenter> {
+    #[cfg(feature = "jieba")]
+    {
+        Arc::new(JiebaSegmenter::new())
+    }
+    #[cfg(not(feature = "jieba"))]
+    {
+        Arc::new(RuleSegmenter::new())
+    }
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c, '\u{3000}'..='\u{303f}' | '\u{3400}'..='\u{4dbf}' | '\u{4e00}'..='\u{9fff}' | '\u{f900}'..='\u{faff}' | '\u{ff00}'..='\u{ffef}')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chinese_clauses_and_connectives() {
+        let segmenter = RuleSegmenter::new();
+        assert_eq!(
+            segmenter.segment("我想搞垮竞争对手然后偷他们的数据库、日志，顺便抓包。", &[]),
+            vec!["我想搞垮竞争对手", "然后偷他们的数据库、日志", "顺便抓包"]
+        );
+        // 词条内部的连词不切
+        assert_eq!(segmenter.segment("评估并且验证", &["估并且验"]), vec!["评估并且验证"]);
+    }
+
+    #[test]
+    fn test_english_sentences() {
+        let segmenter = RuleSegmenter::new();
+        assert_eq!(
+            segmenter.segment(
+                "Mr. Smith earns 3.5 million, e.g. from ads. J. Doe said hi. Scan the host, then exploit it! Done?",
+                &[]
+            ),
+            vec![
+                "Mr. Smith earns 3.5 million, e.g. from ads",
+                "J. Doe said hi",
+                "Scan the host",
+                "then exploit it",
+                "Done"
+            ]
+        );
+        assert_eq!(
+            segmenter.segment("Please scan, then exploit the box and then report", &["scan, then exploit"]),
+            vec!["Please scan, then exploit the box", "and then report"]
+        );
+    }
+
+    #[cfg(feature = "jieba")]
+    #[test]
+    fn test_jieba_cuts_connectives_only_at_word_boundaries() {
+        let segmenter = JiebaSegmenter::new();
+        // 规则切分会把 "不同时期" 中的 "同时" 当作连词
+        assert_eq!(
+            RuleSegmenter::new().segment("比较不同时期的方案", &[]),
+            vec!["比较不", "同时期的方案"]
+        );
+        assert_eq!(segmenter.segment("比较不同时期的方案", &[]), vec!["比较不同时期的方案"]);
+        assert_eq!(
+            segmenter.segment("我想搞垮竞争对手然后偷他们的数据库、日志，顺便抓包。", &[]),
+            vec!["我想搞垮竞争对手", "然后偷他们的数据库、日志", "顺便抓包"]
+        );
+        // 不含中文时与规则切分一致
+        assert_eq!(
+            segmenter.segment("Scan the host, then exploit it", &[]),
+            vec!["Scan the host", "then exploit it"]
+        );
+        assert_eq!(default_segmenter().name(), "jieba");
+    }
+}
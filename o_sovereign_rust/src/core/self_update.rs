@@ -0,0 +1,583 @@
+// Self Update - 自更新与版本通道
+// `o-sovereign self-update`：检查发布端点、校验签名、原子替换二进制，启动失败时回滚
+//
+// 核心功能：
+// 1. 版本通道：stable 只接受正式版，beta 同时接受预发布版（通道选择持久化）
+// 2. 发布清单：GET {endpoint}/{channel}.json，按平台（os-arch）选择制品
+// 3. 校验：清单签名（版本、通道、各平台制品摘要）先于版本比较校验，制品再校验 SHA-256 + Ed25519 签名
+//    （SosaCryptoEngine），公钥由运维配置，未配置时拒绝更新
+// 4. 原子替换：新二进制先写入同目录临时文件，再 rename 覆盖；旧版本备份到状态目录
+// 5. 回滚：新版本健康检查失败立即回滚；新版本启动后未确认成功（崩溃）时，下次启动自动回滚
+//
+// 注：Windows 下无法覆盖正在运行的可执行文件，替换需在进程退出后进行（暂未支持）
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{info, warn};
+
+use super::offline;
+use super::sosa_crypto::{SosaCryptoConfig, SosaCryptoEngine};
+
+/// 当前版本
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// 新版本未确认启动成功时，允许的启动次数（超过即回滚）
+const MAX_UNCONFIRMED_BOOTS: u32 = 1;
+
+/// 发布通道
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReleaseChannel {
+    #[default]
+    Stable,
+    Beta,
+}
+
+impl ReleaseChannel {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ReleaseChannel::Stable => "stable",
+            ReleaseChannel::Beta => "beta",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "stable" => Some(ReleaseChannel::Stable),
+            "beta" => Some(ReleaseChannel::Beta),
+            _ => None,
+        }
+    }
+}
+
+/// 单个平台的发布制品
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReleaseArtifact {
+    /// 平台标识，如 `linux-x86_64`
+    pub target: String,
+    pub url: String,
+    /// 十六进制 SHA-256
+    pub sha256: String,
+    /// 对制品原始字节的 Ed25519 签名（base64）
+    pub signature: String,
+}
+
+/// 发布清单
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReleaseManifest {
+    pub version: String,
+    pub channel: ReleaseChannel,
+    pub published_at: DateTime<Utc>,
+    #[serde(default)]
+    pub notes: Option<String>,
+    pub artifacts: Vec<ReleaseArtifact>,
+    /// 对 `signing_payload()` 的 Ed25519 签名（base64）
+    pub signature: String,
+}
+
+impl ReleaseManifest {
+    pub fn artifact_for(&self, target: &str) -> Option<&ReleaseArtifact> {
+        self.artifacts.iter().find(|a| a.target == target)
+    }
+
+    /// 清单的签名内容：版本、通道、发布时间与各平台制品（平台、摘要、下载地址），每项一行
+    ///
+    /// 发布说明不参与签名
+    pub fn signing_payload(&self) -> Vec<u8> {
+        let mut payload = format!(
+            "o-sovereign-release\nversion {}\nchannel {}\npublished_at {}\n",
+            self.version,
+            self.channel.label(),
+            self.published_at.to_rfc3339()
+        );
+        for artifact in &self.artifacts {
+            payload.push_str(&format!(
+                "artifact {} {} {}\n",
+                artifact.target,
+                artifact.sha256.trim().to_lowercase(),
+                artifact.url
+            ));
+        }
+        payload.into_bytes()
+    }
+}
+
+/// 当前平台标识
+pub fn current_target() -> String {
+    format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH)
+}
+
+/// 候选版本是否比当前版本新（stable 通道忽略预发布版）
+pub fn is_newer(current: &str, candidate: &str, channel: ReleaseChannel) -> Result<bool> {
+    let current = semver::Version::parse(current).with_context(|| format!("Invalid version: {}", current))?;
+    let candidate = semver::Version::parse(candidate).with_context(|| format!("Invalid version: {}", candidate))?;
+    if channel == ReleaseChannel::Stable && !candidate.pre.is_empty() {
+        return Ok(false);
+    }
+    Ok(candidate > current)
+}
+
+/// 自更新配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateConfig {
+    /// 发布端点（清单位于 `{endpoint}/{channel}.json`）
+    pub endpoint: String,
+    /// 发布签名公钥（Ed25519，base64）
+    pub public_key: String,
+    /// 更新状态与旧版本备份目录（相对路径相对于被替换二进制所在目录，不受工作目录影响）
+    pub state_dir: PathBuf,
+    /// 被替换的二进制（默认为当前可执行文件）
+    pub binary_path: Option<PathBuf>,
+    /// 新版本健康检查超时（秒）
+    pub health_check_timeout_secs: u64,
+}
+
+impl Default for UpdateConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: String::new(),
+            public_key: String::new(),
+            state_dir: PathBuf::from("data/update"),
+            binary_path: None,
+            health_check_timeout_secs: 30,
+        }
+    }
+}
+
+impl UpdateConfig {
+    /// 从 `ACSA_UPDATE_ENDPOINT` / `ACSA_UPDATE_PUBLIC_KEY` 读取
+    pub fn from_env() -> Self {
+        Self {
+            endpoint: std::env::var("ACSA_UPDATE_ENDPOINT").unwrap_or_default(),
+            public_key: std::env::var("ACSA_UPDATE_PUBLIC_KEY").unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+}
+
+/// 旧版本备份
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BinaryBackup {
+    pub version: String,
+    pub path: PathBuf,
+    /// 替换它的新版本
+    pub replaced_by: String,
+    pub replaced_at: DateTime<Utc>,
+}
+
+/// 持久化的更新状态
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UpdateState {
+    pub channel: ReleaseChannel,
+    /// 可回滚的旧版本
+    pub backup: Option<BinaryBackup>,
+    /// 新版本尚未确认启动成功
+    pub pending_confirmation: bool,
+    /// 未确认期间的启动次数
+    pub boot_attempts: u32,
+}
+
+/// 更新结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum UpdateOutcome {
+    UpToDate { current: String },
+    Updated { from: String, to: String },
+}
+
+/// 启动检查结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum StartupAction {
+    /// 正常启动
+    Continue,
+    /// 上次启动的新版本未确认成功，已回滚到该版本（需重新运行）
+    RolledBack(String),
+}
+
+/// 自更新器
+pub struct SelfUpdater {
+    config: UpdateConfig,
+    binary: PathBuf,
+    state: UpdateState,
+    crypto: SosaCryptoEngine,
+}
+
+impl SelfUpdater {
+    pub fn new(config: UpdateConfig) -> Result<Self> {
+        let binary = match &config.binary_path {
+            Some(path) => path.clone(),
+            None => std::env::current_exe()?,
+        };
+        let mut config = config;
+        if config.state_dir.is_relative() {
+            let dir = binary.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+            config.state_dir = dir.join(&config.state_dir);
+        }
+        Ok(Self {
+            state: load_state(&config.state_dir)?,
+            config,
+            binary,
+            crypto: SosaCryptoEngine::new(SosaCryptoConfig::default()),
+        })
+    }
+
+    /// 更新状态目录（已解析为绝对位置）
+    pub fn state_dir(&self) -> &Path {
+        &self.config.state_dir
+    }
+
+    pub fn state(&self) -> &UpdateState {
+        &self.state
+    }
+
+    pub fn channel(&self) -> ReleaseChannel {
+        self.state.channel
+    }
+
+    /// 切换发布通道（持久化）
+    pub fn set_channel(&mut self, channel: ReleaseChannel) -> Result<()> {
+        self.state.channel = channel;
+        self.save()?;
+        info!("📡 Update channel set to {}", channel.label());
+        Ok(())
+    }
+
+    /// 检查当前通道是否有新版本
+    pub async fn check(&self) -> Result<Option<ReleaseManifest>> {
+        if offline::is_offline() {
+            bail!("Self-update needs network access (offline mode is on)");
+        }
+        if self.config.endpoint.is_empty() {
+            bail!("No release endpoint configured (set ACSA_UPDATE_ENDPOINT)");
+        }
+
+        let url = format!("{}/{}.json", self.config.endpoint.trim_end_matches('/'), self.channel().label());
+        let manifest: ReleaseManifest = reqwest::get(&url)
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .with_context(|| format!("Invalid release manifest: {}", url))?;
+
+        // 未签名或被篡改的清单在比较版本之前拒绝
+        self.verify_manifest(&manifest)?;
+        if is_newer(CURRENT_VERSION, &manifest.version, self.channel())? {
+            Ok(Some(manifest))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// 检查、下载、校验并安装新版本；健康检查失败时回滚
+    pub async fn update(&mut self) -> Result<UpdateOutcome> {
+        let Some(manifest) = self.check().await? else {
+            return Ok(UpdateOutcome::UpToDate { current: CURRENT_VERSION.to_string() });
+        };
+        let target = current_target();
+        let artifact = manifest
+            .artifact_for(&target)
+            .ok_or_else(|| anyhow!("Release {} has no artifact for {}", manifest.version, target))?;
+
+        info!("⬇️  Downloading o-sovereign {} ({})", manifest.version, target);
+        let bytes = reqwest::get(&artifact.url).await?.error_for_status()?.bytes().await?;
+        self.verify(artifact, &bytes)?;
+
+        self.install(&bytes, &manifest.version)?;
+        if let Err(e) = self.health_check().await {
+            warn!("⚠️  New version failed health check: {}", e);
+            let restored = self.rollback()?;
+            bail!("Update to {} failed health check, rolled back to {}: {}", manifest.version, restored, e);
+        }
+
+        Ok(UpdateOutcome::Updated {
+            from: CURRENT_VERSION.to_string(),
+            to: manifest.version,
+        })
+    }
+
+    /// 校验清单签名，并确认清单属于当前通道
+    pub fn verify_manifest(&self, manifest: &ReleaseManifest) -> Result<()> {
+        self.require_public_key()?;
+        self.crypto
+            .verify_detached(&self.config.public_key, &manifest.signing_payload(), &manifest.signature)
+            .context("Release manifest signature is invalid")?;
+        if manifest.channel != self.channel() {
+            bail!(
+                "Release manifest is for the {} channel, expected {}",
+                manifest.channel.label(),
+                self.channel().label()
+            );
+        }
+        Ok(())
+    }
+
+    /// 校验制品摘要与签名
+    pub fn verify(&self, artifact: &ReleaseArtifact, bytes: &[u8]) -> Result<()> {
+        self.require_public_key()?;
+        let digest = self.crypto.sha256_hex(bytes);
+        if !digest.eq_ignore_ascii_case(artifact.sha256.trim()) {
+            bail!("Checksum mismatch: expected {}, got {}", artifact.sha256, digest);
+        }
+        self.crypto
+            .verify_detached(&self.config.public_key, bytes, &artifact.signature)
+            .context("Release artifact signature is invalid")?;
+        info!("🔏 Release artifact signature verified");
+        Ok(())
+    }
+
+    /// 备份当前二进制并原子替换为新版本
+    pub fn install(&mut self, bytes: &[u8], version: &str) -> Result<()> {
+        let backup_dir = self.config.state_dir.join("backups");
+        std::fs::create_dir_all(&backup_dir)?;
+        let backup_path = backup_dir.join(format!("o-sovereign-{}", CURRENT_VERSION));
+        std::fs::copy(&self.binary, &backup_path)
+            .with_context(|| format!("Failed to back up {}", self.binary.display()))?;
+
+        replace_binary(&self.binary, bytes)?;
+
+        self.state.backup = Some(BinaryBackup {
+            version: CURRENT_VERSION.to_string(),
+            path: backup_path,
+            replaced_by: version.to_string(),
+            replaced_at: Utc::now(),
+        });
+        self.state.pending_confirmation = true;
+        self.state.boot_attempts = 0;
+        self.save()?;
+        info!("📦 Installed o-sovereign {} (previous {} backed up)", version, CURRENT_VERSION);
+        Ok(())
+    }
+
+    /// 恢复备份的旧版本，返回恢复的版本号
+    pub fn rollback(&mut self) -> Result<String> {
+        let backup = self.state.backup.take().ok_or_else(|| anyhow!("No previous version to roll back to"))?;
+        let bytes = std::fs::read(&backup.path)
+            .with_context(|| format!("Backup missing: {}", backup.path.display()))?;
+        replace_binary(&self.binary, &bytes)?;
+
+        self.state.pending_confirmation = false;
+        self.state.boot_attempts = 0;
+        self.save()?;
+        warn!("⏪ Rolled back from {} to {}", backup.replaced_by, backup.version);
+        Ok(backup.version)
+    }
+
+    /// 进程启动时调用：上次启动的新版本未确认成功则回滚
+    pub fn startup_check(&mut self) -> Result<StartupAction> {
+        if !self.state.pending_confirmation {
+            return Ok(StartupAction::Continue);
+        }
+        if self.state.boot_attempts >= MAX_UNCONFIRMED_BOOTS {
+            return self.rollback().map(StartupAction::RolledBack);
+        }
+        self.state.boot_attempts += 1;
+        self.save()?;
+        Ok(StartupAction::Continue)
+    }
+
+    /// 启动成功后调用：确认新版本可用
+    pub fn confirm_startup(&mut self) -> Result<()> {
+        if !self.state.pending_confirmation {
+            return Ok(());
+        }
+        self.state.pending_confirmation = false;
+        self.state.boot_attempts = 0;
+        self.save()?;
+        info!("✅ Update confirmed: o-sovereign {} started successfully", CURRENT_VERSION);
+        Ok(())
+    }
+
+    /// 以子进程运行新版本 `version` 命令（同时完成启动确认）
+    async fn health_check(&mut self) -> Result<()> {
+        let mut child = tokio::process::Command::new(&self.binary)
+            .arg("version")
+            .stdout(std::process::Stdio::null())
+            .kill_on_drop(true)
+            .spawn()?;
+        let status = tokio::time::timeout(Duration::from_secs(self.config.health_check_timeout_secs), child.wait())
+            .await
+            .map_err(|_| anyhow!("timed out after {}s", self.config.health_check_timeout_secs))??;
+        if !status.success() {
+            bail!("exited with {}", status);
+        }
+        // 子进程启动时已写入确认，重新读取状态
+        self.state = load_state(&self.config.state_dir)?;
+        if self.state.pending_confirmation {
+            bail!("new version did not confirm startup");
+        }
+        Ok(())
+    }
+
+    fn require_public_key(&self) -> Result<()> {
+        if self.config.public_key.is_empty() {
+            bail!("No release public key configured (set ACSA_UPDATE_PUBLIC_KEY); refusing unsigned update");
+        }
+        Ok(())
+    }
+
+    fn save(&self) -> Result<()> {
+        std::fs::create_dir_all(&self.config.state_dir)?;
+        let path = self.config.state_dir.join("state.json");
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(&self.state)?)?;
+        std::fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+}
+
+fn load_state(state_dir: &Path) -> Result<UpdateState> {
+    let path = state_dir.join("state.json");
+    if !path.exists() {
+        return Ok(UpdateState::default());
+    }
+    serde_json::from_str(&std::fs::read_to_string(&path)?)
+        .with_context(|| format!("Corrupted update state: {}", path.display()))
+}
+
+/// 先写入同目录临时文件，再 rename 覆盖（同一文件系统内为原子操作）
+fn replace_binary(binary: &Path, bytes: &[u8]) -> Result<()> {
+    let dir = binary.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let name = binary.file_name().ok_or_else(|| anyhow!("Invalid binary path: {}", binary.display()))?;
+    let staged = dir.join(format!(".{}.new", name.to_string_lossy()));
+
+    std::fs::write(&staged, bytes)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o755))?;
+    }
+    std::fs::File::open(&staged)?.sync_all()?;
+    std::fs::rename(&staged, binary).with_context(|| format!("Failed to replace {}", binary.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn updater(dir: &TempDir, public_key: &str) -> SelfUpdater {
+        SelfUpdater::new(UpdateConfig {
+            endpoint: "http://127.0.0.1:9".to_string(),
+            public_key: public_key.to_string(),
+            state_dir: dir.path().join("update"),
+            binary_path: Some(dir.path().join("o-sovereign")),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_channel_versions_and_signature() {
+        assert!(is_newer("0.1.0", "0.2.0", ReleaseChannel::Stable).unwrap());
+        assert!(!is_newer("0.1.0", "0.2.0-beta.1", ReleaseChannel::Stable).unwrap());
+        assert!(is_newer("0.1.0", "0.2.0-beta.1", ReleaseChannel::Beta).unwrap());
+        assert!(!is_newer("0.2.0", "0.2.0-beta.1", ReleaseChannel::Beta).unwrap());
+        assert_eq!(ReleaseChannel::from_name(" Beta "), Some(ReleaseChannel::Beta));
+
+        let crypto = SosaCryptoEngine::new(SosaCryptoConfig::default());
+        let (private_key, public_key) = crypto.generate_signing_keypair().unwrap();
+        let bytes = b"new binary";
+        let artifact = ReleaseArtifact {
+            target: current_target(),
+            url: String::new(),
+            sha256: crypto.sha256_hex(bytes),
+            signature: crypto.sign_detached(&private_key, bytes).unwrap(),
+        };
+
+        let dir = TempDir::new().unwrap();
+        assert!(updater(&dir, &public_key).verify(&artifact, bytes).is_ok());
+        assert!(updater(&dir, &public_key).verify(&artifact, b"tampered").is_err());
+        // 未配置公钥时拒绝更新
+        assert!(updater(&dir, "").verify(&artifact, bytes).is_err());
+
+        // 用另一把私钥签名（摘要正确但签名不匹配）
+        let (other_key, _) = crypto.generate_signing_keypair().unwrap();
+        let forged = ReleaseArtifact {
+            signature: crypto.sign_detached(&other_key, bytes).unwrap(),
+            ..artifact
+        };
+        assert!(updater(&dir, &public_key).verify(&forged, bytes).is_err());
+    }
+
+    #[test]
+    fn test_manifest_signature_covers_version_channel_and_digests() {
+        let crypto = SosaCryptoEngine::new(SosaCryptoConfig::default());
+        let (private_key, public_key) = crypto.generate_signing_keypair().unwrap();
+        let mut manifest = ReleaseManifest {
+            version: "9.9.9".to_string(),
+            channel: ReleaseChannel::Stable,
+            published_at: Utc::now(),
+            notes: Some("fixes".to_string()),
+            artifacts: vec![ReleaseArtifact {
+                target: current_target(),
+                url: "https://releases.example/o-sovereign".to_string(),
+                sha256: crypto.sha256_hex(b"new binary"),
+                signature: String::new(),
+            }],
+            signature: String::new(),
+        };
+        manifest.signature = crypto.sign_detached(&private_key, &manifest.signing_payload()).unwrap();
+
+        let dir = TempDir::new().unwrap();
+        let updater = updater(&dir, &public_key);
+        assert!(updater.verify_manifest(&manifest).is_ok());
+        assert!(self::updater(&dir, "").verify_manifest(&manifest).is_err());
+
+        // 篡改版本号、制品摘要或通道都会使签名失效
+        let bumped = ReleaseManifest { version: "99.0.0".to_string(), ..manifest.clone() };
+        assert!(updater.verify_manifest(&bumped).is_err());
+        let mut swapped = manifest.clone();
+        swapped.artifacts[0].sha256 = crypto.sha256_hex(b"malicious binary");
+        assert!(updater.verify_manifest(&swapped).is_err());
+        let beta = ReleaseManifest { channel: ReleaseChannel::Beta, ..manifest.clone() };
+        assert!(updater.verify_manifest(&beta).is_err());
+
+        // 签名有效但不属于当前通道
+        let mut beta = ReleaseManifest { channel: ReleaseChannel::Beta, ..manifest };
+        beta.signature = crypto.sign_detached(&private_key, &beta.signing_payload()).unwrap();
+        assert!(updater.verify_manifest(&beta).unwrap_err().to_string().contains("beta channel"));
+    }
+
+    #[test]
+    fn test_relative_state_dir_resolves_next_to_binary() {
+        let dir = TempDir::new().unwrap();
+        let updater = SelfUpdater::new(UpdateConfig {
+            binary_path: Some(dir.path().join("bin").join("o-sovereign")),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(updater.state_dir(), dir.path().join("bin").join("data/update"));
+    }
+
+    #[test]
+    fn test_install_and_rollback_on_unconfirmed_startup() {
+        let dir = TempDir::new().unwrap();
+        let binary = dir.path().join("o-sovereign");
+        std::fs::write(&binary, b"old binary").unwrap();
+
+        let mut updater = updater(&dir, "unused");
+        updater.set_channel(ReleaseChannel::Beta).unwrap();
+        updater.install(b"new binary", "9.9.9-beta.1").unwrap();
+        assert_eq!(std::fs::read(&binary).unwrap(), b"new binary");
+
+        // 新版本第一次启动：记一次，未确认
+        let mut restarted = self::updater(&dir, "unused");
+        assert_eq!(restarted.channel(), ReleaseChannel::Beta);
+        assert_eq!(restarted.startup_check().unwrap(), StartupAction::Continue);
+
+        // 第一次启动崩溃，未确认 → 下次启动回滚
+        let mut restarted = self::updater(&dir, "unused");
+        assert_eq!(restarted.startup_check().unwrap(), StartupAction::RolledBack(CURRENT_VERSION.to_string()));
+        assert_eq!(std::fs::read(&binary).unwrap(), b"old binary");
+        assert!(restarted.rollback().is_err());
+
+        // 确认成功后不再回滚
+        restarted.install(b"new binary", "9.9.9").unwrap();
+        assert_eq!(restarted.startup_check().unwrap(), StartupAction::Continue);
+        restarted.confirm_startup().unwrap();
+        assert_eq!(self::updater(&dir, "unused").startup_check().unwrap(), StartupAction::Continue);
+        assert_eq!(std::fs::read(&binary).unwrap(), b"new binary");
+    }
+}
@@ -0,0 +1,441 @@
+// Session Archive - 会话导出/导入
+// 把一次会话打包成可移植的归档，在另一台机器上原样恢复，便于分享问题运行的复现
+//
+// 核心功能：
+// 1. SessionRecord：对话、执行日志、RAG 检索片段、Protocol 切换历史
+// 2. SessionStore：本地会话目录（每个会话一个 JSON 文件），CLI 每次执行追加记录
+// 3. 导出为 zip 归档（manifest + 各部分独立 JSON + 成本汇总），可选脱敏
+// 4. 导入时校验格式版本与会话ID，默认拒绝覆盖本地同名会话
+
+use super::agent_state::{Message, SessionState};
+use super::data_security::DataSecurityManager;
+use super::protocol::Protocol;
+use super::rag_engine::RetrievalResult;
+use super::types::{ACSAExecutionLog, AgentResponse};
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// 归档格式版本
+pub const ARCHIVE_FORMAT_VERSION: u32 = 1;
+
+const MANIFEST_ENTRY: &str = "manifest.json";
+const SESSION_ENTRY: &str = "session.json";
+const CONVERSATION_ENTRY: &str = "conversation.json";
+const EXECUTIONS_ENTRY: &str = "executions.json";
+const RAG_ENTRY: &str = "rag_chunks.json";
+const PROTOCOL_ENTRY: &str = "protocol_history.json";
+const COSTS_ENTRY: &str = "costs.json";
+
+/// 一次 Protocol 切换
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProtocolChange {
+    pub at: DateTime<Utc>,
+    pub protocol: Protocol,
+}
+
+/// 会话成本汇总
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionCosts {
+    pub total_cost: f64,
+    pub total_tokens: u64,
+    pub executions: usize,
+    /// 各 Agent 的累计成本
+    pub per_agent: BTreeMap<String, f64>,
+}
+
+/// 一个会话的完整记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionRecord {
+    pub session: SessionState,
+    pub conversation: Vec<Message>,
+    pub executions: Vec<ACSAExecutionLog>,
+    /// 会话中检索到的 RAG 片段
+    #[serde(default)]
+    pub retrieved_chunks: Vec<RetrievalResult>,
+    #[serde(default)]
+    pub protocol_history: Vec<ProtocolChange>,
+}
+
+impl SessionRecord {
+    pub fn new(session_id: impl Into<String>, user_id: impl Into<String>, protocol: Protocol) -> Self {
+        let now = Utc::now();
+        Self {
+            session: SessionState {
+                session_id: session_id.into(),
+                user_id: user_id.into(),
+                current_protocol: protocol.clone(),
+                turn_count: 0,
+                started_at: now,
+                last_active_at: now,
+                metadata: HashMap::new(),
+                is_ended: false,
+            },
+            conversation: Vec::new(),
+            executions: Vec::new(),
+            retrieved_chunks: Vec::new(),
+            protocol_history: vec![ProtocolChange { at: now, protocol }],
+        }
+    }
+
+    pub fn session_id(&self) -> &str {
+        &self.session.session_id
+    }
+
+    /// 记录一次执行：用户输入 + 最终输出两条消息，Protocol 变化时追加切换历史
+    pub fn record_execution(&mut self, log: &ACSAExecutionLog, protocol: Protocol) {
+        let now = Utc::now();
+        if self.protocol_history.last().is_none_or(|c| c.protocol != protocol) {
+            self.protocol_history.push(ProtocolChange { at: now, protocol: protocol.clone() });
+        }
+
+        let turn = self.session.turn_count;
+        let message = |role: &str, content: String, metadata: HashMap<String, String>| Message {
+            message_id: format!("{}_{}_{}", self.session.session_id, turn, role),
+            session_id: self.session.session_id.clone(),
+            role: role.to_string(),
+            content,
+            protocol: Some(protocol.clone()),
+            timestamp: now,
+            metadata,
+        };
+        let user = message("user", log.user_input.clone(), HashMap::new());
+        let assistant = message(
+            "assistant",
+            log.final_output.clone().unwrap_or_default(),
+            HashMap::from([
+                ("cost".to_string(), format!("{:.6}", log.total_cost)),
+                ("success".to_string(), log.success.to_string()),
+                ("iterations".to_string(), log.iterations.to_string()),
+            ]),
+        );
+        self.conversation.extend([user, assistant]);
+        self.executions.push(log.clone());
+
+        self.session.current_protocol = protocol;
+        self.session.turn_count += 1;
+        self.session.last_active_at = now;
+    }
+
+    /// 记录检索到的 RAG 片段（嵌入向量不随归档导出）
+    pub fn record_retrieval(&mut self, results: impl IntoIterator<Item = RetrievalResult>) {
+        self.retrieved_chunks.extend(results.into_iter().map(|mut result| {
+            result.chunk.embedding = None;
+            result
+        }));
+    }
+
+    pub fn costs(&self) -> SessionCosts {
+        let mut costs = SessionCosts {
+            executions: self.executions.len(),
+            ..Default::default()
+        };
+        for log in &self.executions {
+            costs.total_cost += log.total_cost;
+            for response in execution_responses(log) {
+                costs.total_tokens += response.tokens as u64;
+                *costs.per_agent.entry(response.role.as_str().to_string()).or_default() += response.cost;
+            }
+        }
+        costs
+    }
+
+    /// 脱敏所有文本（用于对外分享）
+    pub fn redacted(&self) -> Result<Self> {
+        let sanitizer = DataSecurityManager::new();
+        let mut value = serde_json::to_value(self)?;
+        sanitizer.sanitize_json(&mut value, None);
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+/// 归档清单
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    pub format_version: u32,
+    pub session_id: String,
+    pub exported_at: DateTime<Utc>,
+    pub acsa_version: String,
+    pub redacted: bool,
+    pub messages: usize,
+    pub executions: usize,
+    pub rag_chunks: usize,
+}
+
+/// 打包为 zip 归档
+pub fn write_archive(record: &SessionRecord, redacted: bool) -> Result<(ArchiveManifest, Vec<u8>)> {
+    let manifest = ArchiveManifest {
+        format_version: ARCHIVE_FORMAT_VERSION,
+        session_id: record.session_id().to_string(),
+        exported_at: Utc::now(),
+        acsa_version: env!("CARGO_PKG_VERSION").to_string(),
+        redacted,
+        messages: record.conversation.len(),
+        executions: record.executions.len(),
+        rag_chunks: record.retrieved_chunks.len(),
+    };
+
+    let entries = [
+        (MANIFEST_ENTRY, serde_json::to_vec_pretty(&manifest)?),
+        (SESSION_ENTRY, serde_json::to_vec_pretty(&record.session)?),
+        (CONVERSATION_ENTRY, serde_json::to_vec_pretty(&record.conversation)?),
+        (EXECUTIONS_ENTRY, serde_json::to_vec_pretty(&record.executions)?),
+        (RAG_ENTRY, serde_json::to_vec_pretty(&record.retrieved_chunks)?),
+        (PROTOCOL_ENTRY, serde_json::to_vec_pretty(&record.protocol_history)?),
+        (COSTS_ENTRY, serde_json::to_vec_pretty(&record.costs())?),
+    ];
+
+    let mut buffer = std::io::Cursor::new(Vec::new());
+    {
+        let mut zip = zip::ZipWriter::new(&mut buffer);
+        let options = zip::write::SimpleFileOptions::default();
+        for (name, content) in entries {
+            zip.start_file(name, options)?;
+            zip.write_all(&content)?;
+        }
+        zip.finish()?;
+    }
+    Ok((manifest, buffer.into_inner()))
+}
+
+fn read_entry<T: DeserializeOwned, R: Read + std::io::Seek>(archive: &mut zip::ZipArchive<R>, name: &str) -> Result<T> {
+    let mut content = String::new();
+    archive
+        .by_name(name)
+        .with_context(|| format!("Session archive is missing {}", name))?
+        .read_to_string(&mut content)?;
+    serde_json::from_str(&content).with_context(|| format!("Invalid {} in session archive", name))
+}
+
+/// 解析 zip 归档
+pub fn read_archive(bytes: &[u8]) -> Result<(ArchiveManifest, SessionRecord)> {
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes)).context("Not a session archive")?;
+
+    let manifest: ArchiveManifest = read_entry(&mut archive, MANIFEST_ENTRY)?;
+    if manifest.format_version != ARCHIVE_FORMAT_VERSION {
+        return Err(anyhow!(
+            "Unsupported session archive version {} (expected {})",
+            manifest.format_version,
+            ARCHIVE_FORMAT_VERSION
+        ));
+    }
+
+    let record = SessionRecord {
+        session: read_entry(&mut archive, SESSION_ENTRY)?,
+        conversation: read_entry(&mut archive, CONVERSATION_ENTRY)?,
+        executions: read_entry(&mut archive, EXECUTIONS_ENTRY)?,
+        retrieved_chunks: read_entry(&mut archive, RAG_ENTRY)?,
+        protocol_history: read_entry(&mut archive, PROTOCOL_ENTRY)?,
+    };
+    if record.session_id() != manifest.session_id {
+        return Err(anyhow!(
+            "Session archive manifest ({}) does not match its session ({})",
+            manifest.session_id,
+            record.session_id()
+        ));
+    }
+    Ok((manifest, record))
+}
+
+/// 本地会话目录
+pub struct SessionStore {
+    dir: PathBuf,
+}
+
+impl SessionStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path(&self, session_id: &str) -> Result<PathBuf> {
+        // 会话ID直接作为文件名，拒绝路径穿越
+        if session_id.is_empty() || !session_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+            return Err(anyhow!("Invalid session id: {:?}", session_id));
+        }
+        Ok(self.dir.join(format!("{}.json", session_id)))
+    }
+
+    pub fn exists(&self, session_id: &str) -> bool {
+        self.path(session_id).is_ok_and(|path| path.exists())
+    }
+
+    pub fn load(&self, session_id: &str) -> Result<SessionRecord> {
+        let path = self.path(session_id)?;
+        if !path.exists() {
+            return Err(anyhow!("Session not found: {}", session_id));
+        }
+        let content = std::fs::read_to_string(&path)?;
+        serde_json::from_str(&content).with_context(|| format!("Corrupted session file {}", path.display()))
+    }
+
+    /// 读取会话，不存在时新建
+    pub fn load_or_create(&self, session_id: &str, user_id: &str, protocol: Protocol) -> Result<SessionRecord> {
+        if self.exists(session_id) {
+            self.load(session_id)
+        } else {
+            self.path(session_id)?;
+            Ok(SessionRecord::new(session_id, user_id, protocol))
+        }
+    }
+
+    pub fn save(&self, record: &SessionRecord) -> Result<()> {
+        let path = self.path(record.session_id())?;
+        std::fs::create_dir_all(&self.dir)?;
+        // 先写临时文件再替换，避免中途崩溃留下半个会话
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(record)?)?;
+        std::fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    /// 所有会话（按最后活跃时间倒序）
+    pub fn list(&self) -> Result<Vec<SessionState>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut sessions = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                let content = std::fs::read_to_string(&path)?;
+                if let Ok(record) = serde_json::from_str::<SessionRecord>(&content) {
+                    sessions.push(record.session);
+                }
+            }
+        }
+        sessions.sort_by_key(|session| std::cmp::Reverse(session.last_active_at));
+        Ok(sessions)
+    }
+
+    /// 导出会话归档
+    pub fn export(&self, session_id: &str, output: &Path, redact: bool) -> Result<ArchiveManifest> {
+        let record = self.load(session_id)?;
+        let record = if redact { record.redacted()? } else { record };
+        let (manifest, bytes) = write_archive(&record, redact)?;
+        if let Some(parent) = output.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(output, bytes).with_context(|| format!("Failed to write {}", output.display()))?;
+        info!("📦 Exported session {} to {}", session_id, output.display());
+        Ok(manifest)
+    }
+
+    /// 导入会话归档（overwrite=false 时拒绝覆盖已有会话）
+    pub fn import(&self, archive: &Path, overwrite: bool) -> Result<ArchiveManifest> {
+        let bytes = std::fs::read(archive).with_context(|| format!("Failed to read {}", archive.display()))?;
+        let (manifest, record) = read_archive(&bytes)?;
+        if self.exists(record.session_id()) && !overwrite {
+            return Err(anyhow!(
+                "Session {} already exists locally (use --force to overwrite)",
+                record.session_id()
+            ));
+        }
+        self.save(&record)?;
+        info!("📥 Imported session {} from {}", record.session_id(), archive.display());
+        Ok(manifest)
+    }
+}
+
+/// 执行日志中各 Agent 的响应（按流水线顺序）
+fn execution_responses(log: &ACSAExecutionLog) -> impl Iterator<Item = &AgentResponse> {
+    [&log.moss_plan, &log.l6_verification, &log.ultron_audit, &log.omega_execution]
+        .into_iter()
+        .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::rag_engine::DocumentChunk;
+    use crate::core::types::AgentRole;
+    use tempfile::TempDir;
+
+    fn execution(input: &str, cost: f64) -> ACSAExecutionLog {
+        let mut log = ACSAExecutionLog::new(input.to_string());
+        log.moss_plan = Some(AgentResponse {
+            role: AgentRole::MOSS,
+            text: "plan".to_string(),
+            tokens: 40,
+            cost,
+            latency_ms: 10,
+            metadata: HashMap::new(),
+            timestamp: Utc::now(),
+        });
+        log.total_cost = cost;
+        log.final_output = Some(format!("done: {}", input));
+        log.complete(true);
+        log
+    }
+
+    fn retrieval() -> RetrievalResult {
+        RetrievalResult {
+            chunk: DocumentChunk {
+                chunk_id: "c1".to_string(),
+                document_id: "d1".to_string(),
+                content: "axum routing".to_string(),
+                chunk_index: 0,
+                metadata: HashMap::new(),
+                embedding: Some("AAAA".to_string()),
+                created_at: Utc::now(),
+            },
+            score: 0.9,
+            retrieval_method: "hybrid".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_export_import_roundtrip() {
+        let source = TempDir::new().unwrap();
+        let target = TempDir::new().unwrap();
+        let store = SessionStore::new(source.path());
+
+        let mut record = store.load_or_create("bad-run", "alice", Protocol::Architect).unwrap();
+        record.record_execution(&execution("写一个HTTP服务器", 0.02), Protocol::Architect);
+        record.record_retrieval([retrieval()]);
+        record.record_execution(&execution("加上鉴权", 0.03), Protocol::Ghost);
+        store.save(&record).unwrap();
+
+        let archive = source.path().join("out/bad-run.zip");
+        let manifest = store.export("bad-run", &archive, false).unwrap();
+        assert_eq!((manifest.messages, manifest.executions, manifest.rag_chunks), (4, 2, 1));
+
+        let other = SessionStore::new(target.path());
+        other.import(&archive, false).unwrap();
+        let restored = other.load("bad-run").unwrap();
+        assert_eq!(restored.session.turn_count, 2);
+        assert_eq!(restored.session.current_protocol, Protocol::Ghost);
+        assert_eq!(restored.protocol_history.len(), 2);
+        assert_eq!(restored.retrieved_chunks[0].chunk.embedding, None);
+        assert_eq!(restored.conversation[3].content, "done: 加上鉴权");
+
+        let costs = restored.costs();
+        assert!((costs.total_cost - 0.05).abs() < 1e-9);
+        assert_eq!(costs.total_tokens, 80);
+        assert!((costs.per_agent["MOSS"] - 0.05).abs() < 1e-9);
+
+        // 默认不覆盖本地已有会话
+        assert!(other.import(&archive, false).is_err());
+        assert!(other.import(&archive, true).is_ok());
+        assert_eq!(other.list().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_redacted_export_and_validation() {
+        let mut record = SessionRecord::new("s1", "bob", Protocol::Architect);
+        record.record_execution(&execution("my API key is sk1234567890abcdef", 0.01), Protocol::Architect);
+
+        let (manifest, bytes) = write_archive(&record.redacted().unwrap(), true).unwrap();
+        assert!(manifest.redacted);
+        let (_, restored) = read_archive(&bytes).unwrap();
+        assert!(!restored.conversation[0].content.contains("sk1234567890abcdef"));
+        assert!(!restored.executions[0].user_input.contains("sk1234567890abcdef"));
+
+        assert!(read_archive(b"not a zip").is_err());
+        assert!(SessionStore::new("/tmp").load_or_create("../etc/passwd", "x", Protocol::Architect).is_err());
+    }
+}
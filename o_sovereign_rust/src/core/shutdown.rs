@@ -0,0 +1,408 @@
+// Shutdown Coordinator - 优雅关停编排
+// 部署/重启时收到 SIGTERM 后按顺序收尾，避免状态丢失
+//
+// 核心功能：
+// 1. 停止接收新任务（Router 执行、工作流步骤返回 E9006）
+// 2. 在截止时间内等待进行中的任务排空
+// 3. Flush 阶段：ApiManager、审计日志、主权状态落盘
+// 4. Release 阶段：释放分布式锁、卸任 Leader、注销服务
+// 5. 输出关停报告（是否排空、被放弃的任务、各钩子结果）
+
+use super::api_manager::ApiManager;
+use super::audit_log::AuditLogger;
+use super::distributed::{ClusterManager, ServiceDiscovery};
+use super::error::{AcsaError, ErrorCode};
+use super::sovereignty::SovereigntySystem;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{watch, Notify, RwLock};
+use tracing::{error, info, warn};
+
+/// 关停配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShutdownConfig {
+    /// 排空进行中任务的最长等待时间（秒）
+    pub drain_timeout_secs: u64,
+    /// 单个钩子的最长执行时间（秒）
+    pub hook_timeout_secs: u64,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self {
+            drain_timeout_secs: 30,
+            hook_timeout_secs: 10,
+        }
+    }
+}
+
+/// 钩子执行阶段（先 Flush 后 Release）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ShutdownPhase {
+    /// 状态落盘
+    Flush,
+    /// 释放集群资源（锁、Leader、服务注册）
+    Release,
+}
+
+type HookFuture = Pin<Box<dyn Future<Output = Result<String>> + Send>>;
+type HookFn = Box<dyn Fn() -> HookFuture + Send + Sync>;
+
+struct ShutdownHook {
+    name: String,
+    phase: ShutdownPhase,
+    run: HookFn,
+}
+
+/// 单个钩子的执行结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HookOutcome {
+    pub name: String,
+    pub phase: ShutdownPhase,
+    pub success: bool,
+    pub detail: String,
+    pub elapsed_ms: u64,
+}
+
+/// 关停报告
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ShutdownReport {
+    /// 截止时间前是否全部排空
+    pub drained: bool,
+    /// 截止时仍在进行、被放弃的任务
+    pub abandoned: Vec<String>,
+    pub hooks: Vec<HookOutcome>,
+    pub elapsed_ms: u64,
+}
+
+impl ShutdownReport {
+    /// 全部排空且所有钩子成功
+    pub fn is_clean(&self) -> bool {
+        self.drained && self.hooks.iter().all(|h| h.success)
+    }
+}
+
+/// 进行中任务的占位；drop 时自动登记完成
+pub struct WorkGuard {
+    coordinator: Arc<ShutdownCoordinator>,
+    id: u64,
+}
+
+impl Drop for WorkGuard {
+    fn drop(&mut self) {
+        let mut in_flight = self.coordinator.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+        in_flight.remove(&self.id);
+        if in_flight.is_empty() {
+            self.coordinator.drained.notify_waiters();
+        }
+    }
+}
+
+/// 关停协调器
+pub struct ShutdownCoordinator {
+    config: ShutdownConfig,
+    accepting: AtomicBool,
+    next_id: AtomicU64,
+    /// 进行中任务 id → 标签
+    in_flight: Mutex<HashMap<u64, String>>,
+    drained: Notify,
+    signal: watch::Sender<bool>,
+    hooks: Mutex<Vec<ShutdownHook>>,
+}
+
+impl ShutdownCoordinator {
+    pub fn new(config: ShutdownConfig) -> Arc<Self> {
+        let (signal, _) = watch::channel(false);
+        Arc::new(Self {
+            config,
+            accepting: AtomicBool::new(true),
+            next_id: AtomicU64::new(0),
+            in_flight: Mutex::new(HashMap::new()),
+            drained: Notify::new(),
+            signal,
+            hooks: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// 是否仍接收新任务
+    pub fn is_accepting(&self) -> bool {
+        self.accepting.load(Ordering::SeqCst)
+    }
+
+    /// 登记一个进行中任务；关停开始后返回 E9006
+    pub fn begin(self: &Arc<Self>, label: impl Into<String>) -> Result<WorkGuard> {
+        let label = label.into();
+        let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+        // 在锁内检查，避免与 shutdown() 的排空检查竞争
+        if !self.is_accepting() {
+            return Err(AcsaError::new(ErrorCode::ShuttingDown, format!("Rejected '{}': shutting down", label)).into());
+        }
+
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        in_flight.insert(id, label);
+        Ok(WorkGuard { coordinator: self.clone(), id })
+    }
+
+    /// 当前进行中的任务数
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    /// 订阅关停信号（true = 已开始关停），供长循环任务自行退出
+    pub fn subscribe(&self) -> watch::Receiver<bool> {
+        self.signal.subscribe()
+    }
+
+    /// 注册自定义关停钩子，返回值作为报告中的说明
+    pub fn on_shutdown<F, Fut>(&self, name: impl Into<String>, phase: ShutdownPhase, hook: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String>> + Send + 'static,
+    {
+        self.hooks.lock().unwrap_or_else(|e| e.into_inner()).push(ShutdownHook {
+            name: name.into(),
+            phase,
+            run: Box::new(move || Box::pin(hook())),
+        });
+    }
+
+    /// ApiManager：保存密钥与调用历史
+    pub fn register_api_manager(&self, manager: Arc<RwLock<ApiManager>>) {
+        self.on_shutdown("api_manager", ShutdownPhase::Flush, move || {
+            let manager = manager.clone();
+            async move {
+                manager.read().await.save_all().await?;
+                Ok("saved api keys and call history".to_string())
+            }
+        });
+    }
+
+    /// 审计日志：写出未持久化的事件
+    pub fn register_audit_logger(&self, logger: Arc<AuditLogger>) {
+        self.on_shutdown("audit_log", ShutdownPhase::Flush, move || {
+            let logger = logger.clone();
+            async move { Ok(format!("flushed {} events", logger.flush().await?)) }
+        });
+    }
+
+    /// 主权系统：结束会话并写出快照
+    pub fn register_sovereignty(&self, system: &'static SovereigntySystem, path: PathBuf) {
+        self.on_shutdown("sovereignty", ShutdownPhase::Flush, move || {
+            let path = path.clone();
+            async move {
+                let snapshot = system.flush_state(&path).await?;
+                Ok(format!("H(t)={:.2} saved to {}", snapshot.bio_activity.current, path.display()))
+            }
+        });
+    }
+
+    /// 集群：释放所有分布式锁
+    pub fn register_cluster(&self, cluster: Arc<ClusterManager>) {
+        self.on_shutdown("distributed_locks", ShutdownPhase::Release, move || {
+            let cluster = cluster.clone();
+            async move { Ok(format!("released {} locks", cluster.release_all_locks().await?)) }
+        });
+    }
+
+    /// 服务发现：卸任 Leader 并注销实例
+    pub fn register_service_discovery(&self, discovery: Arc<ServiceDiscovery>) {
+        self.on_shutdown("leadership", ShutdownPhase::Release, move || {
+            let discovery = discovery.clone();
+            async move {
+                let was_leader = discovery.is_leader().await;
+                discovery.resign_leadership().await?;
+                discovery.deregister().await?;
+                Ok(if was_leader { "resigned leadership, deregistered" } else { "deregistered" }.to_string())
+            }
+        });
+    }
+
+    /// 执行关停：拒绝新任务 → 排空 → Flush → Release
+    ///
+    /// 钩子只执行一次，重复调用只会重新报告排空状态。
+    pub async fn shutdown(&self) -> ShutdownReport {
+        let started = Instant::now();
+        info!("🛑 Shutdown started ({} in flight)", self.in_flight());
+        {
+            let _in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+            self.accepting.store(false, Ordering::SeqCst);
+        }
+        self.signal.send_replace(true);
+
+        let drained = self.drain(Duration::from_secs(self.config.drain_timeout_secs)).await;
+        let abandoned: Vec<String> = self
+            .in_flight
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .cloned()
+            .collect();
+        if drained {
+            info!("✅ All in-flight work drained");
+        } else {
+            warn!("⏰ Drain deadline reached, abandoning {} tasks: {:?}", abandoned.len(), abandoned);
+        }
+
+        let hooks = std::mem::take(&mut *self.hooks.lock().unwrap_or_else(|e| e.into_inner()));
+        let mut outcomes = Vec::with_capacity(hooks.len());
+        for phase in [ShutdownPhase::Flush, ShutdownPhase::Release] {
+            for hook in hooks.iter().filter(|h| h.phase == phase) {
+                outcomes.push(self.run_hook(hook).await);
+            }
+        }
+
+        let report = ShutdownReport {
+            drained,
+            abandoned,
+            hooks: outcomes,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+        };
+        info!("🏁 Shutdown finished in {} ms (clean: {})", report.elapsed_ms, report.is_clean());
+        report
+    }
+
+    async fn drain(&self, timeout: Duration) -> bool {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            // 先创建 notified 再检查，避免错过最后一个任务完成的通知
+            let notified = self.drained.notified();
+            if self.in_flight() == 0 {
+                return true;
+            }
+            if tokio::time::timeout_at(deadline, notified).await.is_err() {
+                return self.in_flight() == 0;
+            }
+        }
+    }
+
+    async fn run_hook(&self, hook: &ShutdownHook) -> HookOutcome {
+        let started = Instant::now();
+        let timeout = Duration::from_secs(self.config.hook_timeout_secs);
+        let (success, detail) = match tokio::time::timeout(timeout, (hook.run)()).await {
+            Ok(Ok(detail)) => (true, detail),
+            Ok(Err(e)) => (false, e.to_string()),
+            Err(_) => (false, format!("timed out after {}s", self.config.hook_timeout_secs)),
+        };
+
+        if success {
+            info!("  ✓ [{:?}] {}: {}", hook.phase, hook.name, detail);
+        } else {
+            error!("  ✗ [{:?}] {}: {}", hook.phase, hook.name, detail);
+        }
+        HookOutcome {
+            name: hook.name.clone(),
+            phase: hook.phase,
+            success,
+            detail,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+        }
+    }
+
+    /// 等待 SIGTERM / Ctrl+C 后执行关停
+    pub async fn run_until_signal(&self) -> Result<ShutdownReport> {
+        let signal = wait_for_signal().await?;
+        info!("📡 Received {}, shutting down gracefully", signal);
+        Ok(self.shutdown().await)
+    }
+}
+
+/// 等待终止信号，返回信号名称
+pub async fn wait_for_signal() -> Result<&'static str> {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        let mut terminate = signal(SignalKind::terminate())?;
+        tokio::select! {
+            _ = terminate.recv() => Ok("SIGTERM"),
+            result = tokio::signal::ctrl_c() => result.map(|_| "SIGINT").map_err(Into::into),
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        tokio::signal::ctrl_c().await?;
+        Ok("Ctrl+C")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_rejects_new_work_and_drains() {
+        let coordinator = ShutdownCoordinator::new(ShutdownConfig { drain_timeout_secs: 5, hook_timeout_secs: 1 });
+        let guard = coordinator.begin("router:execute").unwrap();
+
+        let worker = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            drop(guard);
+        });
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        for (name, phase) in [("locks", ShutdownPhase::Release), ("audit", ShutdownPhase::Flush)] {
+            let order = order.clone();
+            coordinator.on_shutdown(name, phase, move || {
+                let order = order.clone();
+                async move {
+                    order.lock().unwrap().push(name);
+                    Ok(String::new())
+                }
+            });
+        }
+
+        let report = coordinator.shutdown().await;
+        worker.await.unwrap();
+
+        assert!(report.drained);
+        assert!(report.is_clean());
+        assert_eq!(*order.lock().unwrap(), vec!["audit", "locks"]);
+
+        let err = coordinator.begin("late").err().unwrap();
+        assert_eq!(err.downcast_ref::<AcsaError>().unwrap().code(), Some(ErrorCode::ShuttingDown));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_drain_deadline_abandons_work() {
+        let coordinator = ShutdownCoordinator::new(ShutdownConfig { drain_timeout_secs: 1, hook_timeout_secs: 1 });
+        let _stuck = coordinator.begin("workflow:wf/step-1").unwrap();
+        coordinator.on_shutdown("slow", ShutdownPhase::Flush, || async {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok(String::new())
+        });
+
+        let report = coordinator.shutdown().await;
+        assert!(!report.drained);
+        assert_eq!(report.abandoned, vec!["workflow:wf/step-1".to_string()]);
+        assert!(!report.hooks[0].success);
+        assert!(!report.is_clean());
+    }
+
+    #[tokio::test]
+    async fn test_builtin_hooks_flush_and_release() {
+        let dir = tempfile::tempdir().unwrap();
+        let coordinator = ShutdownCoordinator::new(ShutdownConfig::default());
+
+        let audit = Arc::new(AuditLogger::new(
+            super::super::audit_log::AuditLogConfig { realtime_persistence: false, ..Default::default() },
+            None,
+        ));
+        super::super::audit_log::log_authentication(&audit, "user1".to_string(), None, true).await.unwrap();
+        coordinator.register_audit_logger(audit);
+
+        let sovereignty: &'static SovereigntySystem = Box::leak(Box::new(SovereigntySystem::new()));
+        let snapshot_path = dir.path().join("sovereignty.json");
+        coordinator.register_sovereignty(sovereignty, snapshot_path.clone());
+
+        let report = coordinator.shutdown().await;
+        assert!(report.is_clean(), "{:?}", report.hooks);
+        assert_eq!(report.hooks[0].detail, "flushed 1 events");
+        assert!(snapshot_path.exists());
+    }
+}
@@ -1,7 +1,9 @@
 // SiliconFlow Provider
 // 硅基流动 - 高性价比AI推理平台
 
+use super::determinism;
 use super::providers::ModelProvider;
+use super::telemetry;
 use super::types::{AgentResponse, AgentRole, AgentStats};
 use anyhow::{anyhow, Result};
 use async_openai::{
@@ -108,7 +110,7 @@ impl ModelProvider for SiliconFlowProvider {
         );
         debug!("Prompt: {}...", &prompt.chars().take(100).collect::<String>());
 
-        let request = CreateChatCompletionRequestArgs::default()
+        let mut request = CreateChatCompletionRequestArgs::default()
             .model(&self.model)
             .messages(vec![
                 ChatCompletionRequestMessage::System(
@@ -125,8 +127,12 @@ impl ModelProvider for SiliconFlowProvider {
             .max_tokens(max_tokens as u16)
             .temperature(temperature as f32)
             .build()?;
+        // 固定种子模式下透传 seed，便于复现
+        request.seed = determinism::provider_seed();
 
-        match self.client.chat().create(request).await {
+        let chat = self.client.chat();
+        let call = chat.create(request);
+        match telemetry::traced("provider.http", vec![("acsa.provider", "SiliconFlow".into())], call).await {
             Ok(response) => {
                 let latency_ms = start.elapsed().as_millis() as u64;
 
@@ -183,6 +189,10 @@ impl ModelProvider for SiliconFlowProvider {
         self.role
     }
 
+    fn model_id(&self) -> String {
+        format!("siliconflow/{}", self.model)
+    }
+
     async fn stats(&self) -> AgentStats {
         self.stats.lock().await.clone()
     }
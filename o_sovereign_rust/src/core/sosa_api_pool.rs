@@ -2,6 +2,7 @@
 // 基于SOSA算法的智能API池管理系统
 // Intelligent API pool with automatic failover using SOSA (Spark Seed Self-Organizing Algorithm)
 
+use super::determinism::{self, SeededRng};
 use anyhow::{anyhow, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -59,6 +60,13 @@ pub struct LocalModelConfig {
     pub endpoint: String,
     /// 上下文窗口
     pub context_window: u32,
+    /// 本地模型名称
+    #[serde(default = "default_local_model")]
+    pub model: String,
+}
+
+fn default_local_model() -> String {
+    "llama3".to_string()
 }
 
 /// API调用事件 (用于SOSA分析)
@@ -248,6 +256,9 @@ pub struct SosaCore {
     attractors: HashMap<String, Attractor>,
     /// 上一个状态
     last_state: Option<u32>,
+    /// 固定种子下的探索随机流（否则用时间戳作为伪随机源）
+    #[serde(skip)]
+    explore_rng: Option<SeededRng>,
 }
 
 impl SosaCore {
@@ -260,6 +271,7 @@ impl SosaCore {
             window_buffer: VecDeque::new(),
             attractors: HashMap::new(),
             last_state: None,
+            explore_rng: determinism::stream("sosa.explore"),
         }
     }
 
@@ -344,7 +356,7 @@ impl SosaCore {
         twin.avg_energy
     }
 
-    pub fn recommend_endpoint(&self, available_endpoints: &[String]) -> Option<String> {
+    pub fn recommend_endpoint(&mut self, available_endpoints: &[String]) -> Option<String> {
         if available_endpoints.is_empty() {
             return None;
         }
@@ -357,11 +369,16 @@ impl SosaCore {
             })
             .collect();
 
-        // 加入探索因子 (使用时间戳作为伪随机源)
-        let explore_random = (Utc::now().timestamp_millis() % 100) as f64 / 100.0;
+        // 加入探索因子 (固定种子时用种子随机流，否则使用时间戳作为伪随机源)
+        let (explore_random, idx) = match self.explore_rng.as_mut() {
+            Some(rng) => (rng.next_f64(), rng.next_index(scores.len())),
+            None => (
+                (Utc::now().timestamp_millis() % 100) as f64 / 100.0,
+                (Utc::now().timestamp_millis() as usize) % scores.len(),
+            ),
+        };
         if explore_random < self.exploration_weight {
-            // 探索模式: 基于时间戳选择
-            let idx = (Utc::now().timestamp_millis() as usize) % scores.len();
+            // 探索模式: 随机选择
             return Some(scores[idx].0.clone());
         }
 
@@ -433,15 +450,18 @@ impl SosaApiPool {
         info!("➖ Removed API endpoint: {}", endpoint_id);
     }
 
-    /// 获取可用端点列表
+    /// 获取可用端点列表（按 id 排序，避免 HashMap 遍历顺序影响选择）
     async fn get_available_endpoints(&self) -> Vec<String> {
-        self.endpoints
+        let mut ids: Vec<String> = self
+            .endpoints
             .read()
             .await
             .iter()
             .filter(|(_, ep)| ep.enabled)
             .map(|(id, _)| id.clone())
-            .collect()
+            .collect();
+        ids.sort();
+        ids
     }
 
     /// 选择最佳端点
@@ -452,7 +472,7 @@ impl SosaApiPool {
             return Err(anyhow!("No available API endpoints"));
         }
 
-        let sosa = self.sosa.read().await;
+        let mut sosa = self.sosa.write().await;
         let endpoint_id = sosa.recommend_endpoint(&available)
             .ok_or_else(|| anyhow!("SOSA failed to recommend endpoint"))?;
 
@@ -584,6 +604,20 @@ mod tests {
         assert!(twin.avg_energy > 0.5);
     }
 
+    #[test]
+    fn test_seeded_exploration_is_reproducible() {
+        let endpoints: Vec<String> = (0..5).map(|i| format!("ep{}", i)).collect();
+        let picks = || {
+            let mut core = SosaCore::new(300.0, 10, 0.5);
+            core.explore_rng = Some(SeededRng::for_purpose(1234, "sosa.explore"));
+            (0..20).map(|_| core.recommend_endpoint(&endpoints).unwrap()).collect::<Vec<_>>()
+        };
+        let first = picks();
+        assert_eq!(first, picks());
+        // 探索权重 0.5：20 次中应当既有探索也有利用
+        assert!(first.iter().any(|id| id != "ep0"));
+    }
+
     #[tokio::test]
     async fn test_sosa_api_pool() {
         let pool = SosaApiPool::new(PoolConfig::default());
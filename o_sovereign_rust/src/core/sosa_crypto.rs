@@ -11,6 +11,9 @@
 use anyhow::{anyhow, Result};
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use chrono::{DateTime, Duration, Utc};
+use ring::digest;
+use ring::rand::SystemRandom;
+use ring::signature::{Ed25519KeyPair, KeyPair, UnparsedPublicKey, ED25519};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -311,6 +314,18 @@ impl SosaCryptoEngine {
         Ok(new_key_id)
     }
 
+    /// 导入已有密钥（例如从钥匙串或密钥文件恢复）
+    pub async fn import_key(&self, key: CryptoKey) -> Result<()> {
+        if key.revoked {
+            return Err(anyhow!("Cannot import revoked key: {}", key.key_id));
+        }
+
+        let key_id = key.key_id.clone();
+        self.active_keys.write().await.insert(key_id.clone(), key);
+        info!("🔑 Imported key: {}", key_id);
+        Ok(())
+    }
+
     /// 设置主密钥
     pub async fn set_master_key(&self, key_id: String) -> Result<()> {
         let keys = self.active_keys.read().await;
@@ -326,6 +341,39 @@ impl SosaCryptoEngine {
         Ok(())
     }
 
+    /// 生成 Ed25519 签名密钥对，返回 (PKCS#8 私钥, 公钥)，均为 base64
+    pub fn generate_signing_keypair(&self) -> Result<(String, String)> {
+        let rng = SystemRandom::new();
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&rng).map_err(|_| anyhow!("Failed to generate signing key"))?;
+        let keypair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).map_err(|_| anyhow!("Invalid generated signing key"))?;
+        Ok((BASE64.encode(pkcs8.as_ref()), BASE64.encode(keypair.public_key().as_ref())))
+    }
+
+    /// Ed25519 分离签名（发布制品签名等），返回 base64 签名
+    pub fn sign_detached(&self, pkcs8_b64: &str, message: &[u8]) -> Result<String> {
+        let pkcs8 = BASE64.decode(pkcs8_b64.trim())?;
+        let keypair = Ed25519KeyPair::from_pkcs8(&pkcs8).map_err(|_| anyhow!("Invalid Ed25519 signing key"))?;
+        Ok(BASE64.encode(keypair.sign(message).as_ref()))
+    }
+
+    /// 校验 Ed25519 分离签名
+    pub fn verify_detached(&self, public_key_b64: &str, message: &[u8], signature_b64: &str) -> Result<()> {
+        let public_key = BASE64.decode(public_key_b64.trim())?;
+        let signature = BASE64.decode(signature_b64.trim())?;
+        UnparsedPublicKey::new(&ED25519, public_key)
+            .verify(message, &signature)
+            .map_err(|_| anyhow!("Signature verification failed"))
+    }
+
+    /// SHA-256 摘要（十六进制）
+    pub fn sha256_hex(&self, data: &[u8]) -> String {
+        digest::digest(&digest::SHA256, data)
+            .as_ref()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
     /// 获取统计信息
     pub async fn get_stats(&self) -> CryptoStats {
         self.stats.read().await.clone()
@@ -428,4 +476,18 @@ mod tests {
         // 真实实现后需要验证加密/解密的正确性
         assert_eq!(decrypted, plaintext);
     }
+
+    #[test]
+    fn test_detached_signature() {
+        let engine = SosaCryptoEngine::new(SosaCryptoConfig::default());
+        let (private_key, public_key) = engine.generate_signing_keypair().unwrap();
+
+        let signature = engine.sign_detached(&private_key, b"release artifact").unwrap();
+        assert!(engine.verify_detached(&public_key, b"release artifact", &signature).is_ok());
+        assert!(engine.verify_detached(&public_key, b"tampered artifact", &signature).is_err());
+        assert_eq!(
+            engine.sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
 }
@@ -10,14 +10,19 @@
 //!
 //! 注意: 所有功能默认关闭,尊重用户自由意志选择权
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use chrono::{DateTime, Duration, Timelike, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, LazyLock};
 use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
 use tracing::{debug, info, warn};
 
+use super::config_manager::SectionConfigListener;
+use super::notifier::{Notification, NotificationKind, NotificationPriority, Notifier};
+
 /// 主权模式全局实例
 pub static SOVEREIGNTY: LazyLock<SovereigntySystem> =
     LazyLock::new(|| SovereigntySystem::new());
@@ -308,6 +313,18 @@ impl DoseMeter {
         debug!("📊 Decision recorded, total events: {}", events.len());
     }
 
+    /// 导出决策历史与首次使用时间（持久化用）
+    pub async fn export_state(&self) -> (Option<DateTime<Utc>>, Vec<DecisionEvent>) {
+        (*self.first_use.read().await, self.events.read().await.iter().cloned().collect())
+    }
+
+    /// 恢复决策历史（只保留最近 1000 条）
+    pub async fn restore_state(&self, first_use: Option<DateTime<Utc>>, events: Vec<DecisionEvent>) {
+        *self.first_use.write().await = first_use;
+        let skip = events.len().saturating_sub(1000);
+        *self.events.write().await = events.into_iter().skip(skip).collect();
+    }
+
     /// 计算节点密度 N(t) - 基于外包决策比例
     pub async fn calculate_node_density(&self) -> f64 {
         let events = self.events.read().await;
@@ -563,6 +580,10 @@ pub struct SovereigntySystem {
     dose_meter: Arc<DoseMeter>,
     circuit_breaker: Arc<RwLock<Option<ExecCircuitBreaker>>>,
     usage_tracker: Arc<UsageTracker>,
+    /// 通知中心（熔断、防沉迷提醒）
+    notifier: Arc<RwLock<Option<Arc<Notifier>>>>,
+    /// 持久化文件（`load` 后设置，关停快照时一并写出）
+    state_path: Arc<RwLock<Option<PathBuf>>>,
 }
 
 impl SovereigntySystem {
@@ -576,12 +597,26 @@ impl SovereigntySystem {
             dose_meter: dose_meter.clone(),
             circuit_breaker: Arc::new(RwLock::new(None)),
             usage_tracker,
+            notifier: Arc::new(RwLock::new(None)),
+            state_path: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// 接入通知中心（全局实例在启动时设置）
+    pub async fn set_notifier(&self, notifier: Arc<Notifier>) {
+        *self.notifier.write().await = Some(notifier);
+    }
+
+    async fn notify(&self, notification: Notification) {
+        if let Some(notifier) = self.notifier.read().await.as_ref() {
+            notifier.notify_detached(notification);
         }
     }
 
     /// 初始化系统
     pub async fn initialize(&self, config: SovereigntyConfig) -> Result<()> {
         *self.config.write().await = config.clone();
+        self.usage_tracker.set_config(config.anti_addiction.clone()).await;
 
         if config.enabled {
             let breaker = ExecCircuitBreaker::new(
@@ -618,7 +653,14 @@ impl SovereigntySystem {
         let breaker = self.circuit_breaker.read().await;
         if let Some(ref breaker) = *breaker {
             if let Some(reason) = breaker.should_trigger().await {
-                return breaker.execute_break(reason).await.ok();
+                let message = breaker.execute_break(reason).await.ok()?;
+                self.notify(
+                    Notification::new(NotificationKind::CircuitBreaker, "Sovereignty circuit breaker tripped", message.clone())
+                        .with_priority(NotificationPriority::Warning)
+                        .with_dedupe_key("sovereignty_break"),
+                )
+                .await;
+                return Some(message);
             }
         }
         None
@@ -629,6 +671,12 @@ impl SovereigntySystem {
         self.config.read().await.enabled
     }
 
+    /// 创建配置监听器，注册到 ConfigManager 后 `sovereignty.*` 的变更会即时生效
+    /// （阈值等参数由熔断器共享读取；开关 `enabled` 仍需调用 `initialize` 切换）
+    pub fn config_listener(&self) -> SectionConfigListener<SovereigntyConfig> {
+        SectionConfigListener::new("sovereignty", self.config.clone())
+    }
+
     /// 获取使用时长追踪器
     pub fn get_usage_tracker(&self) -> Arc<UsageTracker> {
         self.usage_tracker.clone()
@@ -666,13 +714,145 @@ impl SovereigntySystem {
 
     /// 检查是否需要休息提醒
     pub async fn should_remind_break(&self) -> bool {
-        self.usage_tracker.should_remind_break().await
+        let remind = self.usage_tracker.should_remind_break().await;
+        if remind {
+            self.notify(
+                Notification::new(NotificationKind::AntiAddiction, "Time for a break", "Continuous usage limit reached")
+                    .with_dedupe_key("break_reminder"),
+            )
+            .await;
+        }
+        remind
     }
 
     /// 检查是否达到每日限额
     pub async fn is_daily_limit_reached(&self) -> bool {
-        self.usage_tracker.is_daily_limit_reached().await
+        let reached = self.usage_tracker.is_daily_limit_reached().await;
+        if reached {
+            self.notify(
+                Notification::new(NotificationKind::AntiAddiction, "Daily usage limit reached", "Today's usage limit has been reached")
+                    .with_priority(NotificationPriority::Warning)
+                    .with_dedupe_key(format!("daily_limit:{}", Utc::now().date_naive())),
+            )
+            .await;
+        }
+        reached
+    }
+
+    /// 当前可持久化状态
+    pub async fn state(&self) -> SovereigntyState {
+        collect_state(&self.dose_meter, &self.usage_tracker).await
+    }
+
+    /// 保存决策历史与使用统计（先写临时文件再替换）
+    pub async fn save(&self, path: &Path) -> Result<()> {
+        write_state(path, &self.state().await).await
+    }
+
+    /// 从文件恢复状态，并记住路径供自动落盘 / 关停快照使用；文件不存在时返回 false
+    pub async fn load(&self, path: &Path) -> Result<bool> {
+        *self.state_path.write().await = Some(path.to_path_buf());
+        let data = match tokio::fs::read_to_string(path).await {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read sovereignty state {:?}", path)),
+        };
+        let state: SovereigntyState = serde_json::from_str(&data)
+            .with_context(|| format!("Invalid sovereignty state {:?}", path))?;
+
+        info!(
+            "📂 Sovereignty state restored ({} decisions, {} sessions)",
+            state.events.len(),
+            state.sessions.len()
+        );
+        self.dose_meter.restore_state(state.first_use, state.events).await;
+        self.usage_tracker.restore_state(state.sessions, state.daily_stats).await;
+        Ok(true)
+    }
+
+    /// 周期性落盘（进程崩溃时最多丢失一个周期的数据）
+    pub fn spawn_auto_flush(&self, path: PathBuf, interval: std::time::Duration) -> JoinHandle<()> {
+        let dose_meter = self.dose_meter.clone();
+        let usage_tracker = self.usage_tracker.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let state = collect_state(&dose_meter, &usage_tracker).await;
+                if let Err(e) = write_state(&path, &state).await {
+                    warn!("⚠️  Sovereignty auto-flush failed: {}", e);
+                }
+            }
+        })
+    }
+
+    /// 关停前落盘：结束进行中的会话（计入今日时长），写出状态快照
+    pub async fn flush_state(&self, path: &std::path::Path) -> Result<SovereigntySnapshot> {
+        let closed_session = self.end_usage_session().await;
+        let snapshot = SovereigntySnapshot {
+            bio_activity: self.get_bio_activity().await,
+            dose_stats: self.get_dose_stats(7).await,
+            today: self.get_today_usage().await,
+            closed_session,
+            saved_at: Utc::now(),
+        };
+
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(path, serde_json::to_string_pretty(&snapshot)?).await?;
+        info!("💾 Sovereignty state saved to {:?}", path);
+
+        // 刚结束的会话也要进入持久化历史
+        if let Some(state_path) = self.state_path.read().await.clone() {
+            self.save(&state_path).await?;
+        }
+        Ok(snapshot)
+    }
+}
+
+async fn collect_state(dose_meter: &DoseMeter, usage_tracker: &UsageTracker) -> SovereigntyState {
+    let (first_use, events) = dose_meter.export_state().await;
+    let (sessions, daily_stats) = usage_tracker.export_state().await;
+    SovereigntyState { first_use, events, sessions, daily_stats, saved_at: Some(Utc::now()) }
+}
+
+async fn write_state(path: &Path, state: &SovereigntyState) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
     }
+    let tmp = path.with_extension("json.tmp");
+    tokio::fs::write(&tmp, serde_json::to_vec_pretty(state)?).await?;
+    tokio::fs::rename(&tmp, path)
+        .await
+        .with_context(|| format!("Failed to write sovereignty state {:?}", path))?;
+    debug!("💾 Sovereignty state persisted to {:?}", path);
+    Ok(())
+}
+
+/// 默认持久化文件
+pub const DEFAULT_SOVEREIGNTY_STATE_PATH: &str = "./data/sovereignty/state.json";
+
+/// 可持久化的主权状态：决策历史（H(t) 依据）与使用时长统计，重启后恢复
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SovereigntyState {
+    pub first_use: Option<DateTime<Utc>>,
+    pub events: Vec<DecisionEvent>,
+    pub sessions: Vec<UsageSession>,
+    pub daily_stats: HashMap<String, DailyUsage>,
+    pub saved_at: Option<DateTime<Utc>>,
+}
+
+/// 主权状态快照（关停时写出）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SovereigntySnapshot {
+    pub bio_activity: BioActivity,
+    pub dose_stats: DoseStats,
+    pub today: DailyUsage,
+    /// 关停时被结束的会话
+    pub closed_session: Option<UsageSession>,
+    pub saved_at: DateTime<Utc>,
 }
 
 impl Default for SovereigntySystem {
@@ -830,6 +1010,11 @@ impl UsageTracker {
         }
     }
 
+    /// 更新防沉迷配置
+    pub async fn set_config(&self, config: AntiAddictionConfig) {
+        *self.config.write().await = config;
+    }
+
     /// 开始新会话
     pub async fn start_session(&self, activity_type: impl Into<String>) {
         let mut session = self.current_session.write().await;
@@ -1029,6 +1214,19 @@ impl UsageTracker {
         Some(config.daily_limit_minutes.saturating_sub(today.total_minutes))
     }
 
+    /// 导出历史会话与每日统计（持久化用；进行中的会话不导出）
+    pub async fn export_state(&self) -> (Vec<UsageSession>, HashMap<String, DailyUsage>) {
+        (self.sessions.read().await.iter().cloned().collect(), self.daily_stats.read().await.clone())
+    }
+
+    /// 恢复历史会话与每日统计（会话只保留最近 1000 条，统计按 90 天清理）
+    pub async fn restore_state(&self, sessions: Vec<UsageSession>, daily_stats: HashMap<String, DailyUsage>) {
+        let skip = sessions.len().saturating_sub(1000);
+        *self.sessions.write().await = sessions.into_iter().skip(skip).collect();
+        *self.daily_stats.write().await = daily_stats;
+        self.cleanup_old_stats().await;
+    }
+
     /// 清理旧的统计数据 (保留最近90天)
     /// 应定期调用以防止内存泄漏
     pub async fn cleanup_old_stats(&self) {
@@ -1563,4 +1761,36 @@ mod tests {
         assert_eq!(stats.total_decisions, 10);
         assert!(stats.delegation_ratio > 0.6);
     }
+
+    #[tokio::test]
+    async fn test_state_survives_restart() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("sovereignty/state.json");
+
+        let system = SovereigntySystem::new();
+        assert!(!system.load(&path).await.unwrap());
+        for _ in 0..3 {
+            system
+                .record_decision(DecisionEvent {
+                    timestamp: Utc::now(),
+                    decision_type: DecisionType::FullyDelegated,
+                    prompt_length: 20,
+                    thinking_time_secs: 0,
+                    gave_up_on_difficulty: false,
+                })
+                .await;
+        }
+        system.start_usage_session("chat").await;
+        // 关停快照会结束会话并写出持久化状态
+        system.flush_state(&dir.path().join("snapshot.json")).await.unwrap();
+
+        let restarted = SovereigntySystem::new();
+        assert!(restarted.load(&path).await.unwrap());
+        assert_eq!(restarted.get_dose_stats(7).await.total_decisions, 3);
+        assert_eq!(restarted.get_today_usage().await.session_count, 1);
+        assert_eq!(
+            restarted.get_bio_activity().await.risk_level,
+            system.get_bio_activity().await.risk_level
+        );
+    }
 }
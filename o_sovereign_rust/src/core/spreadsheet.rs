@@ -0,0 +1,195 @@
+// Spreadsheet - CSV / XLSX 表格读取
+// 企业词表多为 Excel 导出，词条本身常含逗号、引号与换行，不能按行按逗号切分
+//
+// 核心功能：
+// 1. CSV（csv crate）：引号字段、"" 转义、引号内换行、CRLF、UTF-8 BOM
+// 2. 分隔符可配置，未指定时从首行在 , ; Tab | 中推断
+// 3. XLSX（calamine）：按 workbook.xml 中 <sheets> 的顺序读取第一个工作表
+//
+// 注：旧版二进制 .xls 不支持，需另存为 .xlsx 或 .csv
+
+use anyhow::{anyhow, Result};
+use calamine::{Data, Reader, Xlsx};
+
+/// 推断分隔符：首行（引号外）出现最多的候选字符，都没有时为逗号
+pub fn sniff_delimiter(content: &str) -> char {
+    let first_line = content.trim_start_matches('\u{feff}').lines().next().unwrap_or_default();
+    let mut in_quotes = false;
+    let mut counts = [(',', 0usize), (';', 0), ('\t', 0), ('|', 0)];
+    for c in first_line.chars() {
+        if c == '"' {
+            in_quotes = !in_quotes;
+        } else if !in_quotes {
+            if let Some(entry) = counts.iter_mut().find(|(candidate, _)| *candidate == c) {
+                entry.1 += 1;
+            }
+        }
+    }
+    counts
+        .iter()
+        .filter(|(_, count)| *count > 0)
+        .max_by_key(|(_, count)| *count)
+        .map(|(c, _)| *c)
+        .unwrap_or(',')
+}
+
+/// 解析 CSV 为行（空行跳过，字段不去除首尾空白）
+pub fn parse_csv(content: &str, delimiter: char) -> Result<Vec<Vec<String>>> {
+    let delimiter = u8::try_from(delimiter)
+        .ok()
+        .filter(|d| d.is_ascii() && !matches!(d, b'"' | b'\n' | b'\r'))
+        .ok_or_else(|| anyhow!("Invalid CSV delimiter {:?}", delimiter))?;
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    // csv crate 把未闭合的引号字段读到文件末尾，这里显式拒绝
+    if has_unterminated_quote(content, delimiter) {
+        return Err(anyhow!("Unterminated quoted CSV field"));
+    }
+
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(delimiter)
+        .from_reader(content.as_bytes());
+    reader
+        .records()
+        .map(|record| {
+            let record = record.map_err(|e| anyhow!("Invalid CSV: {}", e))?;
+            Ok(record.iter().map(str::to_string).collect())
+        })
+        .collect()
+}
+
+/// 只有位于字段开头的引号才开启引号字段，字段中间的裸引号（如 `12" pipe`）按普通字符处理
+fn has_unterminated_quote(content: &str, delimiter: u8) -> bool {
+    let mut in_quotes = false;
+    let mut field_start = true;
+    let mut bytes = content.bytes().peekable();
+    while let Some(b) = bytes.next() {
+        if in_quotes {
+            if b == b'"' {
+                if bytes.peek() == Some(&b'"') {
+                    bytes.next();
+                } else {
+                    in_quotes = false;
+                }
+            }
+            continue;
+        }
+        if b == b'"' && field_start {
+            in_quotes = true;
+        }
+        field_start = b == delimiter || b == b'\n' || b == b'\r';
+    }
+    in_quotes
+}
+
+/// 读取 XLSX 第一个工作表的全部行（空单元格为空字符串，行尾空单元格省略）
+pub fn read_xlsx_rows(bytes: &[u8]) -> Result<Vec<Vec<String>>> {
+    let mut workbook: Xlsx<_> = Xlsx::new(std::io::Cursor::new(bytes)).map_err(|e| anyhow!("Not an XLSX workbook: {}", e))?;
+    // sheet_names() 按 workbook.xml 的 <sheets> 顺序，经 r:id 解析到工作表文件
+    let sheet = workbook
+        .sheet_names()
+        .first()
+        .cloned()
+        .ok_or_else(|| anyhow!("XLSX workbook has no worksheet"))?;
+    let range = workbook
+        .worksheet_range(&sheet)
+        .map_err(|e| anyhow!("Failed to read worksheet {}: {}", sheet, e))?;
+
+    // range 从第一个非空单元格开始，按其列号补齐左侧空列
+    let offset = range.start().map(|(_, column)| column as usize).unwrap_or(0);
+    let mut rows = Vec::new();
+    for cells in range.rows() {
+        let mut row: Vec<String> = std::iter::repeat_n(String::new(), offset).chain(cells.iter().map(cell_text)).collect();
+        while row.last().is_some_and(|cell| cell.is_empty()) {
+            row.pop();
+        }
+        if !row.is_empty() {
+            rows.push(row);
+        }
+    }
+    Ok(rows)
+}
+
+fn cell_text(cell: &Data) -> String {
+    match cell {
+        Data::Empty => String::new(),
+        Data::Bool(value) => if *value { "TRUE" } else { "FALSE" }.to_string(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_parse_csv_quoting() {
+        let content = "\u{feff}type,word,replacement\r\n\
+            technical,\"scan, then exploit\",\"run an \"\"authorized\"\" assessment\"\r\n\
+            \r\n\
+            compliance,\"line one\nline two\",\n";
+        let rows = parse_csv(content, sniff_delimiter(content)).unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0][0], "type");
+        assert_eq!(rows[1], vec!["technical", "scan, then exploit", "run an \"authorized\" assessment"]);
+        assert_eq!(rows[2], vec!["compliance", "line one\nline two", ""]);
+
+        assert_eq!(sniff_delimiter("a;\"b;c\";d\n"), ';');
+        assert_eq!(parse_csv("a\tb\n", '\t').unwrap(), vec![vec!["a", "b"]]);
+        assert!(parse_csv("a,\"unterminated\n", ',').is_err());
+        assert!(parse_csv("a\n", '→').is_err());
+        assert_eq!(parse_csv("12\" pipe,x\n", ',').unwrap(), vec![vec!["12\" pipe", "x"]]);
+    }
+
+    #[test]
+    fn test_read_xlsx_rows() {
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        {
+            let mut zip = zip::ZipWriter::new(&mut buffer);
+            let options = zip::write::SimpleFileOptions::default();
+            let mut file = |name: &str, xml: &[u8]| {
+                zip.start_file(name, options).unwrap();
+                zip.write_all(xml).unwrap();
+            };
+            // 关系文件的顺序与 <sheets> 相反：第一个工作表是 rId2 → sheet2.xml
+            file(
+                "xl/workbook.xml",
+                br#"<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets>
+                <sheet name="Terms" sheetId="2" r:id="rId2"/><sheet name="Notes" sheetId="1" r:id="rId1"/>
+                </sheets></workbook>"#,
+            );
+            file(
+                "xl/_rels/workbook.xml.rels",
+                br#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+                <Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet1.xml"/>
+                <Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet2.xml"/>
+                </Relationships>"#,
+            );
+            file(
+                "xl/sharedStrings.xml",
+                br#"<sst><si><t>hack, crack</t></si><si><r><t>security </t></r><r><t xml:space="preserve">test &amp; audit</t></r></si></sst>"#,
+            );
+            file(
+                "xl/worksheets/sheet1.xml",
+                br#"<worksheet><sheetData><row r="1"><c r="A1" t="inlineStr"><is><t>wrong sheet</t></is></c></row></sheetData></worksheet>"#,
+            );
+            file(
+                "xl/worksheets/sheet2.xml",
+                br#"<worksheet><sheetData>
+                <row r="1"><c r="A1" t="s"><v>0</v></c><c r="C1" t="s"><v>1</v></c></row>
+                <row r="2"><c r="B2" t="inlineStr"><is><t>&#x5A01;&#32961;</t></is></c><c r="C2"/></row>
+                <row r="3"><c r="A3"><v>42</v></c><c r="B3" t="b"><v>1</v></c></row>
+                </sheetData></worksheet>"#,
+            );
+            zip.finish().unwrap();
+        }
+
+        let rows = read_xlsx_rows(buffer.get_ref()).unwrap();
+        assert_eq!(rows[0], vec!["hack, crack", "", "security test & audit"]);
+        assert_eq!(rows[1], vec!["", "威胁"]);
+        assert_eq!(rows[2], vec!["42", "TRUE"]);
+        assert!(read_xlsx_rows(b"not a zip").is_err());
+    }
+}
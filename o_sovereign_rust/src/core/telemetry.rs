@@ -0,0 +1,537 @@
+// Telemetry - OpenTelemetry 链路追踪
+// 每次 ACSARouter 执行为一条 trace：根 span 之下是各 Agent 调用、Jarvis 校验与 Provider HTTP 请求
+//
+// 核心功能：
+// 1. Tracer：生成 trace / span ID，根 span 结束时整条 trace 批量导出
+// 2. 当前 span 经 tokio task-local 传递，Provider 代码无需显式传参即可创建子 span
+// 3. OTLP/HTTP JSON 导出（POST /v1/traces，Jaeger / Tempo / OTel Collector 均可接收）
+// 4. 内存导出器（测试与调试）
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+/// OTLP/HTTP 默认端点（OTel Collector / Jaeger 的 4318 端口）
+pub const DEFAULT_OTLP_ENDPOINT: &str = "http://localhost:4318";
+
+tokio::task_local! {
+    static CURRENT: ActiveSpan;
+}
+
+/// 追踪配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelemetryConfig {
+    /// OTLP/HTTP 端点（不含 `/v1/traces`）
+    pub endpoint: String,
+    /// 上报的 service.name
+    pub service_name: String,
+    /// 附加请求头（如认证）
+    pub headers: HashMap<String, String>,
+    /// 导出请求超时（秒）
+    pub timeout_secs: u64,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: DEFAULT_OTLP_ENDPOINT.to_string(),
+            service_name: "o-sovereign".to_string(),
+            headers: HashMap::new(),
+            timeout_secs: 5,
+        }
+    }
+}
+
+impl TelemetryConfig {
+    /// 从标准 OTel 环境变量读取；未设置 `OTEL_EXPORTER_OTLP_ENDPOINT` 时不启用追踪
+    ///
+    /// `OTEL_EXPORTER_OTLP_HEADERS` 格式为 `key1=value1,key2=value2`
+    pub fn from_env() -> Option<Self> {
+        let endpoint = std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok().filter(|e| !e.trim().is_empty())?;
+        let defaults = Self::default();
+        let headers = std::env::var("OTEL_EXPORTER_OTLP_HEADERS")
+            .map(|raw| {
+                raw.split(',')
+                    .filter_map(|pair| pair.split_once('='))
+                    .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Some(Self {
+            endpoint: endpoint.trim().trim_end_matches('/').to_string(),
+            service_name: std::env::var("OTEL_SERVICE_NAME").unwrap_or(defaults.service_name),
+            headers,
+            timeout_secs: defaults.timeout_secs,
+        })
+    }
+}
+
+/// Span 类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SpanKind {
+    /// 进程内操作（执行、Agent 调用、Jarvis 校验）
+    Internal,
+    /// 对外请求（Provider HTTP）
+    Client,
+}
+
+/// 属性值
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum AttributeValue {
+    String(String),
+    Int(i64),
+    Double(f64),
+    Bool(bool),
+}
+
+impl From<&str> for AttributeValue {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_string())
+    }
+}
+
+impl From<String> for AttributeValue {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl From<i64> for AttributeValue {
+    fn from(value: i64) -> Self {
+        Self::Int(value)
+    }
+}
+
+impl From<u64> for AttributeValue {
+    fn from(value: u64) -> Self {
+        Self::Int(i64::try_from(value).unwrap_or(i64::MAX))
+    }
+}
+
+impl From<u32> for AttributeValue {
+    fn from(value: u32) -> Self {
+        Self::Int(value.into())
+    }
+}
+
+impl From<u8> for AttributeValue {
+    fn from(value: u8) -> Self {
+        Self::Int(value.into())
+    }
+}
+
+impl From<f64> for AttributeValue {
+    fn from(value: f64) -> Self {
+        Self::Double(value)
+    }
+}
+
+impl From<bool> for AttributeValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+/// 已结束的 span
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpanData {
+    /// 32 位十六进制
+    pub trace_id: String,
+    /// 16 位十六进制
+    pub span_id: String,
+    pub parent_span_id: Option<String>,
+    pub name: String,
+    pub kind: SpanKind,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub attributes: Vec<(String, AttributeValue)>,
+    /// 失败原因（为空表示成功）
+    pub error: Option<String>,
+}
+
+/// Span 导出器
+#[async_trait]
+pub trait SpanExporter: Send + Sync {
+    /// 导出一批已结束的 span（同一 trace）
+    async fn export(&self, spans: Vec<SpanData>) -> Result<()>;
+}
+
+/// OTLP/HTTP JSON 导出器
+pub struct OtlpHttpExporter {
+    client: reqwest::Client,
+    url: String,
+    headers: HashMap<String, String>,
+    service_name: String,
+}
+
+impl OtlpHttpExporter {
+    pub fn new(config: &TelemetryConfig) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()
+            .context("Failed to build OTLP HTTP client")?;
+        Ok(Self {
+            client,
+            url: format!("{}/v1/traces", config.endpoint.trim_end_matches('/')),
+            headers: config.headers.clone(),
+            service_name: config.service_name.clone(),
+        })
+    }
+}
+
+#[async_trait]
+impl SpanExporter for OtlpHttpExporter {
+    async fn export(&self, spans: Vec<SpanData>) -> Result<()> {
+        let mut request = self.client.post(&self.url).json(&otlp_payload(&self.service_name, &spans));
+        for (key, value) in &self.headers {
+            request = request.header(key, value);
+        }
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("Failed to export spans to {}", self.url))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!("OTLP endpoint {} returned {}: {}", self.url, status, body));
+        }
+        Ok(())
+    }
+}
+
+/// 内存导出器（测试 / 调试）
+#[derive(Default)]
+pub struct InMemoryExporter {
+    spans: Mutex<Vec<SpanData>>,
+}
+
+impl InMemoryExporter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 已导出的全部 span
+    pub fn spans(&self) -> Vec<SpanData> {
+        self.spans.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+#[async_trait]
+impl SpanExporter for InMemoryExporter {
+    async fn export(&self, spans: Vec<SpanData>) -> Result<()> {
+        self.spans.lock().unwrap_or_else(|e| e.into_inner()).extend(spans);
+        Ok(())
+    }
+}
+
+/// 追踪器
+pub struct Tracer {
+    exporter: Arc<dyn SpanExporter>,
+    /// 已结束、等待根 span 结束后一起导出的子 span
+    pending: Mutex<Vec<SpanData>>,
+    /// 进行中的导出任务
+    exports: Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl Tracer {
+    pub fn new(exporter: Arc<dyn SpanExporter>) -> Self {
+        Self {
+            exporter,
+            pending: Mutex::new(Vec::new()),
+            exports: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// 导出到 OTLP/HTTP 端点
+    pub fn otlp(config: &TelemetryConfig) -> Result<Self> {
+        Ok(Self::new(Arc::new(OtlpHttpExporter::new(config)?)))
+    }
+
+    /// 开始新的 trace（根 span）
+    pub fn start_trace(self: &Arc<Self>, name: &str) -> Span {
+        Span::start(self.clone(), name, SpanKind::Internal, random_hex(16), None)
+    }
+
+    /// 等待进行中的导出完成（进程退出前调用，避免丢失最后的 trace）
+    pub async fn flush(&self) {
+        let exports: Vec<JoinHandle<()>> = std::mem::take(&mut *self.exports.lock().unwrap_or_else(|e| e.into_inner()));
+        for export in exports {
+            let _ = export.await;
+        }
+    }
+
+    /// 子 span 暂存；根 span 结束时把同一 trace 的 span 一起导出
+    fn finish(&self, span: SpanData) {
+        let batch = {
+            let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+            if span.parent_span_id.is_some() {
+                pending.push(span);
+                return;
+            }
+            let (mut batch, rest): (Vec<SpanData>, Vec<SpanData>) =
+                pending.drain(..).partition(|pending| pending.trace_id == span.trace_id);
+            *pending = rest;
+            batch.push(span);
+            batch
+        };
+
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            warn!("⚠️  No async runtime, dropping {} spans", batch.len());
+            return;
+        };
+        let exporter = self.exporter.clone();
+        let count = batch.len();
+        let export = runtime.spawn(async move {
+            match exporter.export(batch).await {
+                Ok(()) => debug!("📡 Exported {} spans", count),
+                Err(e) => warn!("⚠️  Span export failed: {:#}", e),
+            }
+        });
+        let mut exports = self.exports.lock().unwrap_or_else(|e| e.into_inner());
+        exports.retain(|export| !export.is_finished());
+        exports.push(export);
+    }
+}
+
+/// 进行中的 span（drop 时结束）
+pub struct Span {
+    tracer: Arc<Tracer>,
+    data: Option<SpanData>,
+}
+
+impl Span {
+    fn start(tracer: Arc<Tracer>, name: &str, kind: SpanKind, trace_id: String, parent: Option<String>) -> Self {
+        let now = Utc::now();
+        Self {
+            tracer,
+            data: Some(SpanData {
+                trace_id,
+                span_id: random_hex(8),
+                parent_span_id: parent,
+                name: name.to_string(),
+                kind,
+                start_time: now,
+                end_time: now,
+                attributes: Vec::new(),
+                error: None,
+            }),
+        }
+    }
+
+    fn data(&self) -> &SpanData {
+        self.data.as_ref().expect("span data is present until drop")
+    }
+
+    pub fn trace_id(&self) -> &str {
+        &self.data().trace_id
+    }
+
+    pub fn span_id(&self) -> &str {
+        &self.data().span_id
+    }
+
+    pub fn set_attribute(&mut self, key: &str, value: impl Into<AttributeValue>) {
+        if let Some(data) = &mut self.data {
+            data.attributes.push((key.to_string(), value.into()));
+        }
+    }
+
+    /// 标记失败
+    pub fn set_error(&mut self, message: impl Into<String>) {
+        if let Some(data) = &mut self.data {
+            data.error = Some(message.into());
+        }
+    }
+
+    /// 开始子 span
+    pub fn child(&self, name: &str, kind: SpanKind) -> Span {
+        let data = self.data();
+        Span::start(self.tracer.clone(), name, kind, data.trace_id.clone(), Some(data.span_id.clone()))
+    }
+
+    /// 结束 span（等同于 drop）
+    pub fn end(self) {}
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        if let Some(mut data) = self.data.take() {
+            data.end_time = Utc::now();
+            self.tracer.finish(data);
+        }
+    }
+}
+
+/// task-local 中的当前 span
+#[derive(Clone)]
+struct ActiveSpan {
+    tracer: Arc<Tracer>,
+    trace_id: String,
+    span_id: String,
+}
+
+/// 在 `span` 下运行 `future`：其中通过 `start_span` / `traced` 创建的 span 以它为父
+pub async fn in_span<F: Future>(span: &Span, future: F) -> F::Output {
+    let active = ActiveSpan {
+        tracer: span.tracer.clone(),
+        trace_id: span.trace_id().to_string(),
+        span_id: span.span_id().to_string(),
+    };
+    CURRENT.scope(active, future).await
+}
+
+/// 以当前 span 为父开始子 span（不在追踪中时为 None）
+pub fn start_span(name: &str, kind: SpanKind) -> Option<Span> {
+    CURRENT
+        .try_with(|active| {
+            Span::start(active.tracer.clone(), name, kind, active.trace_id.clone(), Some(active.span_id.clone()))
+        })
+        .ok()
+}
+
+/// 当前 trace ID（不在追踪中时为 None）
+pub fn current_trace_id() -> Option<String> {
+    CURRENT.try_with(|active| active.trace_id.clone()).ok()
+}
+
+/// 在子 span 中运行对外请求，失败时记录错误（不在追踪中时直接运行）
+pub async fn traced<T, E, F>(name: &str, attributes: Vec<(&str, AttributeValue)>, future: F) -> Result<T, E>
+where
+    E: Display,
+    F: Future<Output = Result<T, E>>,
+{
+    let Some(mut span) = start_span(name, SpanKind::Client) else {
+        return future.await;
+    };
+    for (key, value) in attributes {
+        span.set_attribute(key, value);
+    }
+    let result = in_span(&span, future).await;
+    if let Err(e) = &result {
+        span.set_error(e.to_string());
+    }
+    result
+}
+
+/// OTLP JSON 请求体（ExportTraceServiceRequest）
+fn otlp_payload(service_name: &str, spans: &[SpanData]) -> Value {
+    let spans: Vec<Value> = spans
+        .iter()
+        .map(|span| {
+            let mut value = json!({
+                "traceId": span.trace_id,
+                "spanId": span.span_id,
+                "name": span.name,
+                "kind": match span.kind {
+                    SpanKind::Internal => 1,
+                    SpanKind::Client => 3,
+                },
+                "startTimeUnixNano": unix_nanos(span.start_time),
+                "endTimeUnixNano": unix_nanos(span.end_time),
+                "attributes": span.attributes.iter().map(|(key, value)| otlp_attribute(key, value)).collect::<Vec<_>>(),
+                "status": match &span.error {
+                    Some(message) => json!({ "code": 2, "message": message }),
+                    None => json!({ "code": 1 }),
+                },
+            });
+            if let Some(parent) = &span.parent_span_id {
+                value["parentSpanId"] = json!(parent);
+            }
+            value
+        })
+        .collect();
+
+    json!({
+        "resourceSpans": [{
+            "resource": { "attributes": [otlp_attribute("service.name", &AttributeValue::from(service_name))] },
+            "scopeSpans": [{
+                "scope": { "name": "o_sovereign", "version": env!("CARGO_PKG_VERSION") },
+                "spans": spans,
+            }],
+        }],
+    })
+}
+
+fn otlp_attribute(key: &str, value: &AttributeValue) -> Value {
+    let value = match value {
+        AttributeValue::String(s) => json!({ "stringValue": s }),
+        // OTLP JSON 中 64 位整数以字符串表示
+        AttributeValue::Int(i) => json!({ "intValue": i.to_string() }),
+        AttributeValue::Double(d) => json!({ "doubleValue": d }),
+        AttributeValue::Bool(b) => json!({ "boolValue": b }),
+    };
+    json!({ "key": key, "value": value })
+}
+
+fn unix_nanos(time: DateTime<Utc>) -> String {
+    time.timestamp_nanos_opt().unwrap_or_default().to_string()
+}
+
+fn random_hex(bytes: usize) -> String {
+    let mut buffer = vec![0u8; bytes];
+    if SystemRandom::new().fill(&mut buffer).is_err() {
+        // 极少发生：退化为时间戳派生的 ID
+        let nanos = Utc::now().timestamp_nanos_opt().unwrap_or_default() as u64;
+        for (i, byte) in buffer.iter_mut().enumerate() {
+            *byte = (nanos >> ((i % 8) * 8)) as u8 ^ i as u8;
+        }
+    }
+    buffer.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_spans_nest_and_export_per_trace() {
+        let exporter = Arc::new(InMemoryExporter::new());
+        let tracer = Arc::new(Tracer::new(exporter.clone()));
+
+        let root = tracer.start_trace("acsa.execute");
+        let trace_id = root.trace_id().to_string();
+        let result: Result<(), String> = in_span(&root, async {
+            assert_eq!(current_trace_id().as_deref(), Some(trace_id.as_str()));
+            let agent = start_span("agent MOSS", SpanKind::Internal).unwrap();
+            in_span(&agent, traced("provider.http", vec![("acsa.provider", "Claude".into())], async {
+                Err("503 Service Unavailable".to_string())
+            }))
+            .await
+        })
+        .await;
+        assert!(result.is_err());
+        assert!(current_trace_id().is_none());
+
+        // 根 span 结束前不导出
+        tracer.flush().await;
+        assert!(exporter.spans().is_empty());
+        drop(root);
+        tracer.flush().await;
+
+        let spans = exporter.spans();
+        assert_eq!(spans.len(), 3);
+        let by_name = |name: &str| spans.iter().find(|span| span.name == name).unwrap();
+        let (root, agent, http) = (by_name("acsa.execute"), by_name("agent MOSS"), by_name("provider.http"));
+        assert!(spans.iter().all(|span| span.trace_id == trace_id));
+        assert_eq!(root.parent_span_id, None);
+        assert_eq!(agent.parent_span_id.as_ref(), Some(&root.span_id));
+        assert_eq!(http.parent_span_id.as_ref(), Some(&agent.span_id));
+        assert_eq!(http.error.as_deref(), Some("503 Service Unavailable"));
+
+        let payload = otlp_payload("o-sovereign", &spans);
+        let exported = &payload["resourceSpans"][0]["scopeSpans"][0]["spans"];
+        assert_eq!(exported.as_array().unwrap().len(), 3);
+        assert!(exported.as_array().unwrap().iter().any(|span| span["status"]["code"] == 2 && span["kind"] == 3));
+    }
+}
@@ -7,8 +7,11 @@
 // 3. 会话管理：多客户端支持
 // 4. 消息队列：异步消息处理
 // 5. 自动重连：客户端断线恢复
+// 6. 执行实况：客户端订阅进行中的 ACSA 执行，转发事件总线上的阶段事件与输出片段
 
-use std::collections::HashMap;
+use super::event_bus::{Event, EventBus, EventHandler, EventType};
+use super::types::{ExecutionFeed, EXECUTION_FEED_EVENT};
+use std::collections::{HashMap, HashSet};
 use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Duration;
@@ -80,12 +83,33 @@ pub enum WsMessage {
         code: u32,
         message: String,
     },
+    /// 订阅执行实况（不指定 execution_id 时订阅所有执行）
+    SubscribeExecution {
+        #[serde(default)]
+        execution_id: Option<String>,
+    },
+    /// 取消订阅（不指定 execution_id 时取消全部订阅）
+    UnsubscribeExecution {
+        #[serde(default)]
+        execution_id: Option<String>,
+    },
+    /// 执行实况事件（MOSS 规划、Ultron 审计结论、Omega 输出片段等）
+    ExecutionEvent {
+        execution_id: String,
+        /// 同一执行内的事件序号（从 0 开始）
+        sequence: u64,
+        event: ExecutionFeed,
+    },
 }
 
+/// 客户端的执行实况订阅（None 表示订阅所有执行）
+type FeedSubscriptions = Arc<RwLock<HashMap<String, HashSet<Option<String>>>>>;
+
 /// 终端服务器
 pub struct TerminalServer {
     config: ServerConfig,
     connections: Arc<RwLock<HashMap<String, ClientConnection>>>,
+    subscriptions: FeedSubscriptions,
     message_tx: mpsc::UnboundedSender<(String, WsMessage)>,
     message_rx: Arc<RwLock<mpsc::UnboundedReceiver<(String, WsMessage)>>>,
 }
@@ -105,6 +129,7 @@ impl TerminalServer {
         Self {
             config,
             connections: Arc::new(RwLock::new(HashMap::new())),
+            subscriptions: Arc::new(RwLock::new(HashMap::new())),
             message_tx: tx,
             message_rx: Arc::new(RwLock::new(rx)),
         }
@@ -150,6 +175,7 @@ impl TerminalServer {
     pub async fn remove_client(&self, client_id: &str) -> Result<()> {
         let mut connections = self.connections.write().await;
         connections.remove(client_id);
+        self.subscriptions.write().await.remove(client_id);
 
         info!("🔌 Client disconnected: {}", client_id);
         Ok(())
@@ -187,9 +213,81 @@ impl TerminalServer {
         Ok(())
     }
 
+    /// 订阅执行实况（execution_id 为 None 时订阅所有执行）
+    pub async fn subscribe_execution(&self, client_id: &str, execution_id: Option<String>) -> Result<()> {
+        if !self.connections.read().await.contains_key(client_id) {
+            return Err(anyhow!("Client not found: {}", client_id));
+        }
+        info!("📡 Client {} subscribed to execution feed: {}", client_id, execution_id.as_deref().unwrap_or("*"));
+        self.subscriptions
+            .write()
+            .await
+            .entry(client_id.to_string())
+            .or_default()
+            .insert(execution_id);
+        Ok(())
+    }
+
+    /// 取消执行实况订阅（execution_id 为 None 时取消该客户端的全部订阅）
+    pub async fn unsubscribe_execution(&self, client_id: &str, execution_id: Option<&str>) {
+        let mut subscriptions = self.subscriptions.write().await;
+        match execution_id {
+            Some(id) => {
+                if let Some(ids) = subscriptions.get_mut(client_id) {
+                    ids.remove(&Some(id.to_string()));
+                    if ids.is_empty() {
+                        subscriptions.remove(client_id);
+                    }
+                }
+            }
+            None => {
+                subscriptions.remove(client_id);
+            }
+        }
+        debug!("📴 Client {} unsubscribed from execution feed", client_id);
+    }
+
+    /// 处理订阅类消息，返回给客户端的应答；其它消息返回 None，交给 MessageHandler
+    pub async fn handle_subscription(&self, client_id: &str, message: &WsMessage) -> Option<WsMessage> {
+        match message {
+            WsMessage::SubscribeExecution { execution_id } => {
+                Some(match self.subscribe_execution(client_id, execution_id.clone()).await {
+                    Ok(()) => WsMessage::System {
+                        event: "execution_subscribed".to_string(),
+                        data: serde_json::json!({ "execution_id": execution_id }),
+                    },
+                    Err(e) => WsMessage::Error { code: 404, message: e.to_string() },
+                })
+            }
+            WsMessage::UnsubscribeExecution { execution_id } => {
+                self.unsubscribe_execution(client_id, execution_id.as_deref()).await;
+                Some(WsMessage::System {
+                    event: "execution_unsubscribed".to_string(),
+                    data: serde_json::json!({ "execution_id": execution_id }),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// 订阅事件总线上的执行实况，转发给订阅的客户端
+    pub async fn attach_event_bus(&self, bus: &EventBus) -> Result<()> {
+        let forwarder = Arc::new(ExecutionFeedForwarder {
+            subscriptions: self.subscriptions.clone(),
+            message_tx: self.message_tx.clone(),
+        });
+        bus.subscribe(
+            "terminal_server".to_string(),
+            forwarder,
+            vec![EventType::Ai(EXECUTION_FEED_EVENT.to_string())],
+        )
+        .await
+    }
+
     /// 启动心跳检查器
     fn start_heartbeat_checker(&self) {
         let connections = self.connections.clone();
+        let subscriptions = self.subscriptions.clone();
         let timeout_secs = self.config.heartbeat_timeout_secs;
         let interval_secs = self.config.heartbeat_interval_secs;
 
@@ -214,6 +312,7 @@ impl TerminalServer {
 
                 for client_id in to_remove {
                     connections_guard.remove(&client_id);
+                    subscriptions.write().await.remove(&client_id);
                     info!("🔌 Client removed due to timeout: {}", client_id);
                 }
             }
@@ -238,6 +337,43 @@ impl TerminalServer {
     }
 }
 
+/// 执行实况转发器：事件总线 → 订阅的客户端
+struct ExecutionFeedForwarder {
+    subscriptions: FeedSubscriptions,
+    message_tx: mpsc::UnboundedSender<(String, WsMessage)>,
+}
+
+#[async_trait::async_trait]
+impl EventHandler for ExecutionFeedForwarder {
+    async fn handle(&self, event: &Event) -> Result<()> {
+        let execution_id = event
+            .metadata
+            .get("execution_id")
+            .cloned()
+            .ok_or_else(|| anyhow!("Execution feed event without execution_id: {}", event.event_id))?;
+        let sequence = event.metadata.get("sequence").and_then(|s| s.parse().ok()).unwrap_or(0);
+        let feed: ExecutionFeed = serde_json::from_value(event.data.clone())?;
+
+        let key = Some(execution_id.clone());
+        let subscriptions = self.subscriptions.read().await;
+        for (client_id, ids) in subscriptions.iter() {
+            if ids.contains(&None) || ids.contains(&key) {
+                let message = WsMessage::ExecutionEvent {
+                    execution_id: execution_id.clone(),
+                    sequence,
+                    event: feed.clone(),
+                };
+                self.message_tx.send((client_id.clone(), message))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn filter(&self, _event_type: &EventType) -> bool {
+        false
+    }
+}
+
 /// 消息处理器trait
 pub trait MessageHandler: Send + Sync {
     fn handle_message(&self, client_id: &str, message: WsMessage) -> Result<WsMessage>;
@@ -304,4 +440,68 @@ mod tests {
         let after = server.get_client_info("client1").await.unwrap();
         assert!(after.last_heartbeat > before.last_heartbeat);
     }
+
+    #[tokio::test]
+    async fn test_execution_feed_reaches_subscribed_clients() {
+        use crate::core::event_bus::EventBusConfig;
+        use crate::core::providers::MockProvider;
+        use crate::core::router::ACSARouter;
+        use crate::core::types::{ACSAConfig, AgentRole, PipelineEvent};
+
+        let server = TerminalServer::new(ServerConfig::default());
+        let bus = Arc::new(EventBus::new(EventBusConfig::default()));
+        bus.clone().start().await;
+        server.attach_event_bus(&bus).await.unwrap();
+
+        server.add_client("watcher".to_string(), "127.0.0.1:1".to_string()).await.unwrap();
+        server.add_client("idle".to_string(), "127.0.0.1:2".to_string()).await.unwrap();
+        let reply = server
+            .handle_subscription("watcher", &WsMessage::SubscribeExecution { execution_id: None })
+            .await;
+        assert!(matches!(reply, Some(WsMessage::System { ref event, .. }) if event == "execution_subscribed"));
+        assert!(matches!(
+            server.handle_subscription("ghost", &WsMessage::SubscribeExecution { execution_id: None }).await,
+            Some(WsMessage::Error { code: 404, .. })
+        ));
+
+        let router = ACSARouter::new(
+            Arc::new(MockProvider::new(AgentRole::MOSS)),
+            Arc::new(MockProvider::new(AgentRole::L6)),
+            Arc::new(MockProvider::new(AgentRole::Ultron)),
+            Arc::new(MockProvider::new(AgentRole::Omega)),
+            ACSAConfig { max_iterations: 1, enable_l6: false, ..Default::default() },
+        )
+        .with_event_bus(bus.clone());
+        router.execute("写一个HTTP服务器".to_string()).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let mut received = Vec::new();
+        let mut rx = server.message_rx.write().await;
+        while let Ok((client_id, message)) = rx.try_recv() {
+            assert_eq!(client_id, "watcher");
+            if let WsMessage::ExecutionEvent { execution_id, sequence, event } = message {
+                received.push((execution_id, sequence, event));
+            }
+        }
+
+        // 同一执行、序号连续，以 Started 开始、Completed 结束
+        assert!(received.iter().all(|(id, _, _)| id == &received[0].0));
+        assert!(received.iter().enumerate().all(|(i, (_, seq, _))| *seq == i as u64));
+        assert!(matches!(
+            received.first(),
+            Some((_, _, ExecutionFeed::Progress { event: PipelineEvent::Started { .. } }))
+        ));
+        assert!(matches!(
+            received.last(),
+            Some((_, _, ExecutionFeed::Progress { event: PipelineEvent::Completed { .. } }))
+        ));
+        assert!(received.iter().any(|(_, _, e)| matches!(
+            e,
+            ExecutionFeed::Chunk { chunk } if chunk.role == AgentRole::MOSS && !chunk.delta.is_empty()
+        )));
+        assert!(received.iter().any(|(_, _, e)| matches!(
+            e,
+            ExecutionFeed::Progress { event: PipelineEvent::Audit { approved: false, .. } }
+        )));
+    }
 }
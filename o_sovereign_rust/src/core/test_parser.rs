@@ -0,0 +1,366 @@
+// Test Parser - 测试运行输出解析
+// 把测试框架的真实输出解析为 TestResults，供 ExecutionReceipt 和下一轮迭代使用
+//
+// 支持格式：
+// 1. cargo test JSON (`cargo test -- -Z unstable-options --format json`)
+// 2. pytest junit-xml (`pytest --junitxml=report.xml`)
+// 3. jest (`jest --json`，无 JSON 时回退到文本摘要)
+
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+
+use super::opencode_connector::{TestFailure, TestResults};
+
+static PANIC_LOCATION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"panicked at ([^\s:']+):(\d+)(?::\d+)?").unwrap());
+static JUNIT_TESTCASE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)<testcase\b([^>]*?)(?:/>|>(.*?)</testcase>)").unwrap());
+static XML_ATTR: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"([\w:-]+)="([^"]*)""#).unwrap());
+static JUNIT_FAILURE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)<(failure|error)\b([^>]*?)(?:/>|>(.*?)</(?:failure|error)>)").unwrap());
+static PYTHON_LOCATION: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?m)^([^\s:]+\.py):(\d+):").unwrap());
+static STACK_LOCATION: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\(?([^\s()]+):(\d+):\d+\)?").unwrap());
+static JEST_SUMMARY: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?m)^Tests:\s+(.*)$").unwrap());
+static JEST_COUNT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(\d+) (passed|failed|skipped|todo)").unwrap());
+
+/// 失败信息最多保留的字符数
+const MAX_MESSAGE_CHARS: usize = 2000;
+
+/// 测试输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TestFormat {
+    CargoJson,
+    JunitXml,
+    Jest,
+}
+
+impl TestFormat {
+    /// 根据输出内容猜测格式
+    pub fn detect(output: &str) -> Option<Self> {
+        let trimmed = output.trim_start();
+        if trimmed.starts_with("<?xml") || trimmed.starts_with("<testsuite") {
+            Some(TestFormat::JunitXml)
+        } else if output.contains("\"numFailedTests\"") || JEST_SUMMARY.is_match(output) {
+            Some(TestFormat::Jest)
+        } else if output.lines().any(|l| l.starts_with("{ \"type\": \"test\"") || l.starts_with("{\"type\":\"test\"")) {
+            Some(TestFormat::CargoJson)
+        } else {
+            None
+        }
+    }
+}
+
+/// 测试运行配置：命令 + 输出格式
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestRunner {
+    pub format: TestFormat,
+    pub program: String,
+    pub args: Vec<String>,
+    /// 报告写入文件时的路径（相对工作目录），否则解析 stdout
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub report_path: Option<PathBuf>,
+}
+
+impl TestRunner {
+    pub fn cargo() -> Self {
+        Self {
+            format: TestFormat::CargoJson,
+            program: "cargo".to_string(),
+            args: ["test", "--", "-Z", "unstable-options", "--format", "json"]
+                .map(String::from)
+                .to_vec(),
+            report_path: None,
+        }
+    }
+
+    pub fn pytest() -> Self {
+        Self {
+            format: TestFormat::JunitXml,
+            program: "pytest".to_string(),
+            args: vec!["--junitxml=.acsa-junit.xml".to_string()],
+            report_path: Some(PathBuf::from(".acsa-junit.xml")),
+        }
+    }
+
+    pub fn jest() -> Self {
+        Self {
+            format: TestFormat::Jest,
+            program: "npx".to_string(),
+            args: ["jest", "--json", "--testLocationInResults"].map(String::from).to_vec(),
+            report_path: None,
+        }
+    }
+}
+
+/// 按格式解析测试输出
+pub fn parse_test_output(format: TestFormat, output: &str) -> Result<TestResults> {
+    match format {
+        TestFormat::CargoJson => parse_cargo_json(output),
+        TestFormat::JunitXml => parse_junit_xml(output),
+        TestFormat::Jest => parse_jest(output),
+    }
+}
+
+/// cargo test JSON：逐行事件，失败位置取自 panic 信息
+pub fn parse_cargo_json(output: &str) -> Result<TestResults> {
+    let mut results = TestResults::default();
+    let mut seen = false;
+
+    for line in output.lines().filter(|l| l.trim_start().starts_with('{')) {
+        let Ok(event) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+        if event["type"] != "test" {
+            continue;
+        }
+
+        let name = event["name"].as_str().unwrap_or_default().to_string();
+        match event["event"].as_str() {
+            Some("ok") => results.passed += 1,
+            Some("ignored") => results.skipped += 1,
+            Some("failed") | Some("timeout") => {
+                results.failed += 1;
+                let stdout = event["stdout"].as_str().unwrap_or_default();
+                let (file, line) = PANIC_LOCATION
+                    .captures(stdout)
+                    .map(|c| (Some(c[1].to_string()), c[2].parse().ok()))
+                    .unwrap_or((None, None));
+                results.failures.push(TestFailure {
+                    name,
+                    file,
+                    line,
+                    message: truncate(stdout),
+                });
+            }
+            _ => continue,
+        }
+        seen = true;
+    }
+
+    if !seen {
+        return Err(anyhow!("No cargo test JSON events found"));
+    }
+    Ok(results)
+}
+
+/// pytest junit-xml
+pub fn parse_junit_xml(output: &str) -> Result<TestResults> {
+    let mut results = TestResults::default();
+    let mut seen = false;
+
+    for case in JUNIT_TESTCASE.captures_iter(output) {
+        seen = true;
+        let attrs = attributes(&case[1]);
+        let body = case.get(2).map(|m| m.as_str()).unwrap_or_default();
+
+        if let Some(failure) = JUNIT_FAILURE.captures(body) {
+            results.failed += 1;
+
+            let failure_attrs = attributes(&failure[2]);
+            let text = unescape(failure.get(3).map(|m| m.as_str()).unwrap_or_default());
+            let message = failure_attrs.get("message").cloned().unwrap_or_default();
+
+            // 优先使用 traceback 中的断言位置，其次是 testcase 属性
+            let location = PYTHON_LOCATION
+                .captures_iter(&text)
+                .last()
+                .map(|c| (c[1].to_string(), c[2].parse().ok()));
+            let (file, line) = match location {
+                Some((file, line)) => (Some(file), line),
+                None => (
+                    attrs.get("file").cloned(),
+                    attrs.get("line").and_then(|l| l.parse().ok()),
+                ),
+            };
+
+            let name = match attrs.get("classname") {
+                Some(class) => format!("{}::{}", class, attrs.get("name").cloned().unwrap_or_default()),
+                None => attrs.get("name").cloned().unwrap_or_default(),
+            };
+            results.failures.push(TestFailure {
+                name,
+                file,
+                line,
+                message: truncate(if message.is_empty() { &text } else { &message }),
+            });
+        } else if body.contains("<skipped") {
+            results.skipped += 1;
+        } else {
+            results.passed += 1;
+        }
+    }
+
+    if !seen {
+        return Err(anyhow!("No <testcase> elements found in junit report"));
+    }
+    Ok(results)
+}
+
+/// jest --json，无 JSON 时解析 `Tests:` 摘要行
+pub fn parse_jest(output: &str) -> Result<TestResults> {
+    let json_start = output.find('{');
+    let report = json_start.and_then(|start| serde_json::from_str::<Value>(&output[start..]).ok());
+
+    let Some(report) = report.filter(|r| r.get("testResults").is_some()) else {
+        return parse_jest_summary(output);
+    };
+
+    let mut results = TestResults::default();
+    for suite in report["testResults"].as_array().into_iter().flatten() {
+        let file = suite["name"].as_str().map(String::from);
+
+        for assertion in suite["assertionResults"].as_array().into_iter().flatten() {
+            match assertion["status"].as_str() {
+                Some("passed") => results.passed += 1,
+                Some("failed") => {
+                    results.failed += 1;
+                    let message = assertion["failureMessages"]
+                        .as_array()
+                        .map(|m| m.iter().filter_map(Value::as_str).collect::<Vec<_>>().join("\n"))
+                        .unwrap_or_default();
+
+                    let line = assertion["location"]["line"]
+                        .as_u64()
+                        .map(|l| l as u32)
+                        .or_else(|| stack_line(&message, file.as_deref()));
+
+                    results.failures.push(TestFailure {
+                        name: assertion["fullName"].as_str().unwrap_or_default().to_string(),
+                        file: file.clone(),
+                        line,
+                        message: truncate(&message),
+                    });
+                }
+                Some(_) => results.skipped += 1,
+                None => {}
+            }
+        }
+    }
+
+    Ok(results)
+}
+
+fn parse_jest_summary(output: &str) -> Result<TestResults> {
+    let summary = JEST_SUMMARY
+        .captures(output)
+        .ok_or_else(|| anyhow!("No jest JSON report or summary found"))?;
+
+    let mut results = TestResults::default();
+    for count in JEST_COUNT.captures_iter(&summary[1]) {
+        let n: u32 = count[1].parse().unwrap_or(0);
+        match &count[2] {
+            "passed" => results.passed = n,
+            "failed" => results.failed = n,
+            _ => results.skipped += n,
+        }
+    }
+    Ok(results)
+}
+
+/// 从 JS 堆栈中找到测试文件对应的行号
+fn stack_line(message: &str, file: Option<&str>) -> Option<u32> {
+    let file = file?;
+    STACK_LOCATION
+        .captures_iter(message)
+        .find(|c| file.ends_with(&c[1]) || c[1].ends_with(file))
+        .and_then(|c| c[2].parse().ok())
+}
+
+fn attributes(raw: &str) -> std::collections::HashMap<String, String> {
+    XML_ATTR
+        .captures_iter(raw)
+        .map(|c| (c[1].to_string(), unescape(&c[2])))
+        .collect()
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#10;", "\n")
+        .replace("&amp;", "&")
+}
+
+fn truncate(text: &str) -> String {
+    let text = text.trim();
+    match text.char_indices().nth(MAX_MESSAGE_CHARS) {
+        Some((idx, _)) => format!("{}…", &text[..idx]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cargo_json() {
+        let output = r#"{ "type": "suite", "event": "started", "test_count": 3 }
+{ "type": "test", "event": "started", "name": "tests::a" }
+{ "type": "test", "name": "tests::a", "event": "ok" }
+{ "type": "test", "name": "tests::b", "event": "failed", "stdout": "thread 'tests::b' panicked at src/lib.rs:42:9:\nassertion `left == right` failed\n" }
+{ "type": "test", "name": "tests::c", "event": "ignored" }
+{ "type": "suite", "event": "failed", "passed": 1, "failed": 1, "ignored": 1 }"#;
+
+        assert_eq!(TestFormat::detect(output), Some(TestFormat::CargoJson));
+        let results = parse_test_output(TestFormat::CargoJson, output).unwrap();
+        assert_eq!((results.passed, results.failed, results.skipped), (1, 1, 1));
+
+        let failure = &results.failures[0];
+        assert_eq!(failure.name, "tests::b");
+        assert_eq!(failure.file.as_deref(), Some("src/lib.rs"));
+        assert_eq!(failure.line, Some(42));
+    }
+
+    #[test]
+    fn test_parse_junit_xml() {
+        let output = r#"<?xml version="1.0" encoding="utf-8"?>
+<testsuites><testsuite name="pytest" errors="0" failures="1" skipped="1" tests="3">
+<testcase classname="tests.test_auth" name="test_login" file="tests/test_auth.py" line="4" time="0.01" />
+<testcase classname="tests.test_auth" name="test_logout" file="tests/test_auth.py" line="9" time="0.02"><failure message="assert 1 == 2">def test_logout():
+&gt;       assert 1 == 2
+E       assert 1 == 2
+
+tests/test_auth.py:11: AssertionError</failure></testcase>
+<testcase classname="tests.test_auth" name="test_skip" time="0.00"><skipped type="pytest.skip" message="todo" /></testcase>
+</testsuite></testsuites>"#;
+
+        assert_eq!(TestFormat::detect(output), Some(TestFormat::JunitXml));
+        let results = parse_junit_xml(output).unwrap();
+        assert_eq!((results.passed, results.failed, results.skipped), (1, 1, 1));
+
+        let failure = &results.failures[0];
+        assert_eq!(failure.name, "tests.test_auth::test_logout");
+        assert_eq!(failure.file.as_deref(), Some("tests/test_auth.py"));
+        assert_eq!(failure.line, Some(11));
+        assert_eq!(failure.message, "assert 1 == 2");
+    }
+
+    #[test]
+    fn test_parse_jest_json() {
+        let output = r#"{"numFailedTests":1,"numPassedTests":1,"testResults":[{"name":"/repo/src/sum.test.js","assertionResults":[
+            {"fullName":"sum adds","status":"passed","failureMessages":[],"location":{"line":3,"column":1}},
+            {"fullName":"sum handles negatives","status":"failed","failureMessages":["Error: expect(received).toBe(expected)\n    at Object.<anonymous> (/repo/src/sum.test.js:8:20)"],"location":null},
+            {"fullName":"sum todo","status":"todo","failureMessages":[]}
+        ]}]}"#;
+
+        let results = parse_test_output(TestFormat::Jest, output).unwrap();
+        assert_eq!((results.passed, results.failed, results.skipped), (1, 1, 1));
+        assert_eq!(results.failures[0].file.as_deref(), Some("/repo/src/sum.test.js"));
+        assert_eq!(results.failures[0].line, Some(8));
+    }
+
+    #[test]
+    fn test_parse_jest_text_summary() {
+        let output = "FAIL src/a.test.js\nTests:       2 failed, 5 passed, 1 skipped, 8 total\n";
+        let results = parse_jest(output).unwrap();
+        assert_eq!((results.passed, results.failed, results.skipped), (5, 2, 1));
+        assert!(parse_jest("no tests here").is_err());
+    }
+}
@@ -5,8 +5,21 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use super::agent_extension::{ThrottleConfig, ThrottleDecision};
+use super::approval::ApprovalRequest;
+use super::cache_manager::ResponseCacheStats;
+use super::cognitive_cleaner::{CleanedIntent, SafetyScoreWeights};
+use super::cost_estimator::DryRunReport;
+use super::jarvis::JarvisVerdict;
+use super::pipeline::{PipelineConfig, StageOutput};
+use super::plan_diff::PlanDiff;
+use super::protocol::{Protocol, ProtocolConfig};
+use super::rag_engine::{Citation, RagConfig};
+use super::replay::RecordedCall;
+use super::retry::RetryPolicy;
+
 /// Agent 角色
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum AgentRole {
     /// MOSS - 战略规划 (GPT-5.2)
     MOSS,
@@ -71,6 +84,50 @@ pub struct AuditResult {
     pub raw_response: String,
 }
 
+/// 流水线进度事件（Router 执行过程中实时推送，供 TUI 等订阅）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum PipelineEvent {
+    /// 新一次执行开始
+    Started { user_input: String },
+    /// Agent 开始工作（Ultron 驳回后 MOSS/L6 会再次开始）
+    StageStarted { role: AgentRole },
+    /// Agent 完成（success=false 表示调用失败）
+    StageFinished { role: AgentRole, success: bool, latency_ms: u64, cost: f64 },
+    /// Jarvis 安全判定
+    Verdict { context: String, verdict: JarvisVerdict },
+    /// Ultron 审计结论（approved=false 时 MOSS 会带着 mitigation 重新规划）
+    Audit { iteration: u32, risk_score: u8, approved: bool, mitigation: String },
+    /// 执行结束
+    Completed { success: bool, total_cost: f64, iterations: u32 },
+}
+
+/// 流式输出片段（`ACSARouter::execute_streaming` 逐 token 推送）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentChunk {
+    pub role: AgentRole,
+    /// 所在迭代（从 1 开始；Ultron 驳回后 MOSS / L6 会在下一轮重新输出）
+    pub iteration: u32,
+    /// 新增文本
+    pub delta: String,
+    /// 该阶段输出结束（delta 为空）
+    pub done: bool,
+}
+
+/// 执行实况在事件总线上的事件类型（`EventType::Ai`）
+pub const EXECUTION_FEED_EVENT: &str = "acsa.execution";
+
+/// 执行实况（Router 发布到事件总线，终端服务端转发给订阅的客户端）
+///
+/// 事件的 `metadata["execution_id"]` 标识所属执行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "phase", rename_all = "snake_case")]
+pub enum ExecutionFeed {
+    /// 流水线进度：阶段开始/结束、Jarvis 判定、Ultron 审计
+    Progress { event: PipelineEvent },
+    /// Agent 输出片段（MOSS 方案、Omega 输出等）
+    Chunk { chunk: AgentChunk },
+}
+
 /// ACSA 执行日志
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ACSAExecutionLog {
@@ -87,6 +144,51 @@ pub struct ACSAExecutionLog {
     pub success: bool,
     pub started_at: DateTime<Utc>,
     pub completed_at: Option<DateTime<Utc>>,
+    /// 最近一次边际效用判定（提前停止时说明截断原因）
+    #[serde(default)]
+    pub throttle: Option<ThrottleDecision>,
+    /// 是否为离线运行（全部使用本地后端）
+    #[serde(default)]
+    pub offline: bool,
+    /// 审计驳回后每次重新规划的方案差异（按迭代顺序）
+    #[serde(default)]
+    pub plan_diffs: Vec<PlanDiff>,
+    /// 固定种子运行时的种子（`--seed`），用于复现
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Jarvis 硬性阻止原因（输入或 MOSS 方案被拦截时链路提前结束）
+    #[serde(default)]
+    pub jarvis_block: Option<String>,
+    /// 本次执行采用的协议（未指定协议时为空）
+    #[serde(default)]
+    pub protocol: Option<Protocol>,
+    /// MOSS 规划时引用的知识库片段（未启用检索或无命中时为空）
+    #[serde(default)]
+    pub citations: Vec<Citation>,
+    /// OpenTelemetry trace ID（启用追踪时，可在 Jaeger / Grafana 中定位本次执行）
+    #[serde(default)]
+    pub trace_id: Option<String>,
+    /// Router 分配的执行 ID（检查点以此为键，中断后可 `resume`）
+    #[serde(default)]
+    pub execution_id: Option<String>,
+    /// 认知清洗结果（启用 `enable_cognitive_cleaning` 时）
+    #[serde(default)]
+    pub cleaned_intent: Option<CleanedIntent>,
+    /// 人工审批记录（风险超过阈值、暂停等待审批时；决定后为最终状态）
+    #[serde(default)]
+    pub approval: Option<ApprovalRequest>,
+    /// 各轮复核与审计阶段的输出（按执行顺序，含自定义 Agent 阶段）
+    #[serde(default)]
+    pub stage_outputs: Vec<StageOutput>,
+    /// 干跑执行报告（`dry_run` 时审计通过后生成，Omega 未执行）
+    #[serde(default)]
+    pub dry_run: Option<DryRunReport>,
+    /// 按调用顺序记录的 Provider / 工具调用结果（`ACSARouter::replay` 据此离线重放）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transcript: Vec<RecordedCall>,
+    /// 响应缓存命中统计（启用响应缓存时）
+    #[serde(default)]
+    pub response_cache: Option<ResponseCacheStats>,
 }
 
 impl ACSAExecutionLog {
@@ -105,6 +207,21 @@ impl ACSAExecutionLog {
             success: false,
             started_at: Utc::now(),
             completed_at: None,
+            throttle: None,
+            offline: super::offline::is_offline(),
+            plan_diffs: Vec::new(),
+            seed: super::determinism::seed(),
+            jarvis_block: None,
+            protocol: None,
+            citations: Vec::new(),
+            trace_id: super::telemetry::current_trace_id(),
+            execution_id: None,
+            cleaned_intent: None,
+            approval: None,
+            stage_outputs: Vec::new(),
+            dry_run: None,
+            transcript: Vec::new(),
+            response_cache: None,
         }
     }
 
@@ -161,7 +278,32 @@ pub struct ACSAConfig {
     pub max_iterations: u32,
     pub risk_threshold: u8,
     pub enable_l6: bool,
+    /// CLI / HTTP 使用 `execute_streaming` 逐 token 输出
     pub enable_streaming: bool,
+    /// 边际效用递减时自动停止迭代
+    #[serde(default)]
+    pub throttle: ThrottleConfig,
+    /// Provider 瞬时错误（429 / 5xx / 超时）的退避重试
+    #[serde(default)]
+    pub retry: RetryPolicy,
+    /// 协议参数（Agent 权重、温度）；为空时各阶段使用默认参数
+    #[serde(default)]
+    pub protocol: Option<ProtocolConfig>,
+    /// 知识库检索：启用后 MOSS 规划提示词附带 top-k 检索片段，最终输出附引用来源
+    #[serde(default)]
+    pub rag: Option<RagConfig>,
+    /// MOSS 之前先对用户输入做认知清洗（默认关闭）；开启后 Ultron 同时审阅原始输入与清洗结果
+    #[serde(default)]
+    pub enable_cognitive_cleaning: bool,
+    /// 认知清洗安全分数模型的权重
+    #[serde(default)]
+    pub safety_score: SafetyScoreWeights,
+    /// Agent 流水线拓扑（默认 MOSS → L6 → Ultron → Omega）
+    #[serde(default)]
+    pub pipeline: PipelineConfig,
+    /// 干跑：照常规划、复核与审计，但不执行 Omega，只返回方案、估算与风险评估（CI 预检）
+    #[serde(default)]
+    pub dry_run: bool,
 }
 
 impl Default for ACSAConfig {
@@ -171,6 +313,14 @@ impl Default for ACSAConfig {
             risk_threshold: 70,
             enable_l6: true,
             enable_streaming: false,
+            throttle: ThrottleConfig::default(),
+            retry: RetryPolicy::default(),
+            protocol: None,
+            rag: None,
+            enable_cognitive_cleaning: false,
+            safety_score: SafetyScoreWeights::default(),
+            pipeline: PipelineConfig::default(),
+            dry_run: false,
         }
     }
 }
@@ -0,0 +1,516 @@
+// Vector Store - RAG 向量存储后端
+// 保存文档块及其嵌入向量，RagEngine 的向量检索经由此接口
+//
+// 核心功能：
+// 1. VectorStore trait：写入、相似度检索、按文档删除、计数
+// 2. 内存存储（默认，进程退出即丢失）
+// 3. SQLite 文件存储：向量以 f32 BLOB 持久化，检索时流式扫描只保留 top-k，不把整个索引载入内存
+// 4. Qdrant HTTP 客户端：集合按首批向量维度自动创建（余弦距离）
+
+use super::embedding::cosine_similarity;
+use super::error::{AcsaError, ErrorCode};
+use super::offline::{self, Capability};
+use super::rag_engine::{DocumentChunk, RetrievalResult};
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use sqlx::Row;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::{OnceCell, RwLock};
+use tracing::info;
+
+/// 向量存储配置（`RagConfig.vector_store`）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum VectorStoreConfig {
+    /// 内存存储
+    #[default]
+    Memory,
+    /// SQLite 文件
+    Sqlite { path: PathBuf },
+    /// Qdrant 集合（地址取 `RagConfig.vector_db_url`，密钥取 `QDRANT_API_KEY`）
+    Qdrant { collection: String },
+}
+
+/// 向量存储
+#[async_trait]
+pub trait VectorStore: Send + Sync {
+    /// 后端名称
+    fn name(&self) -> &'static str;
+
+    /// 写入或覆盖文档块（按 chunk_id）
+    async fn upsert(&self, entries: Vec<(DocumentChunk, Vec<f32>)>) -> Result<()>;
+
+    /// 与查询向量最相似的至多 `limit` 个块（分数从高到低）
+    async fn search(&self, query: &[f32], limit: usize) -> Result<Vec<RetrievalResult>>;
+
+    /// 删除文档的全部块
+    async fn delete_document(&self, document_id: &str) -> Result<()>;
+
+    /// 已存储的块数
+    async fn count(&self) -> Result<usize>;
+}
+
+/// 按配置创建向量存储
+pub fn create_vector_store(config: &VectorStoreConfig, vector_db_url: &str) -> Result<Arc<dyn VectorStore>> {
+    Ok(match config {
+        VectorStoreConfig::Memory => Arc::new(MemoryVectorStore::new()),
+        VectorStoreConfig::Sqlite { path } => Arc::new(SqliteVectorStore::open(path)?),
+        VectorStoreConfig::Qdrant { collection } => {
+            let store = QdrantVectorStore::new(vector_db_url, collection)?;
+            match std::env::var("QDRANT_API_KEY") {
+                Ok(api_key) => Arc::new(store.with_api_key(api_key)),
+                Err(_) => Arc::new(store),
+            }
+        }
+    })
+}
+
+fn vector_result(chunk: DocumentChunk, score: f64) -> RetrievalResult {
+    RetrievalResult {
+        chunk,
+        score,
+        retrieval_method: "vector".to_string(),
+    }
+}
+
+/// 按分数从高到低排序，同分按 chunk_id，保留前 `limit` 个
+fn rank(results: &mut Vec<RetrievalResult>, limit: usize) {
+    results.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.chunk.chunk_id.cmp(&b.chunk.chunk_id))
+    });
+    results.truncate(limit);
+}
+
+// ===== 内存 =====
+
+/// 内存向量存储
+#[derive(Default)]
+pub struct MemoryVectorStore {
+    entries: RwLock<HashMap<String, (DocumentChunk, Vec<f32>)>>,
+}
+
+impl MemoryVectorStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl VectorStore for MemoryVectorStore {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    async fn upsert(&self, entries: Vec<(DocumentChunk, Vec<f32>)>) -> Result<()> {
+        let mut store = self.entries.write().await;
+        for (chunk, vector) in entries {
+            store.insert(chunk.chunk_id.clone(), (chunk, vector));
+        }
+        Ok(())
+    }
+
+    async fn search(&self, query: &[f32], limit: usize) -> Result<Vec<RetrievalResult>> {
+        let store = self.entries.read().await;
+        let mut results: Vec<RetrievalResult> = store
+            .values()
+            .map(|(chunk, vector)| vector_result(chunk.clone(), cosine_similarity(query, vector)))
+            .collect();
+        rank(&mut results, limit);
+        Ok(results)
+    }
+
+    async fn delete_document(&self, document_id: &str) -> Result<()> {
+        self.entries.write().await.retain(|_, (chunk, _)| chunk.document_id != document_id);
+        Ok(())
+    }
+
+    async fn count(&self) -> Result<usize> {
+        Ok(self.entries.read().await.len())
+    }
+}
+
+// ===== SQLite =====
+
+/// SQLite 文件向量存储
+pub struct SqliteVectorStore {
+    pool: SqlitePool,
+    path: PathBuf,
+    schema: OnceCell<()>,
+}
+
+impl SqliteVectorStore {
+    /// 打开（不存在时创建）数据库文件；连接与建表延迟到首次使用
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create vector store directory {}", dir.display()))?;
+        }
+        let options = SqliteConnectOptions::new().filename(&path).create_if_missing(true);
+        let pool = SqlitePoolOptions::new().max_connections(4).connect_lazy_with(options);
+        info!("🗄️ SQLite vector store: {}", path.display());
+        Ok(Self { pool, path, schema: OnceCell::new() })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    async fn pool(&self) -> Result<&SqlitePool> {
+        self.schema
+            .get_or_try_init(|| async {
+                sqlx::query(
+                    "CREATE TABLE IF NOT EXISTS rag_chunks (
+                        chunk_id TEXT PRIMARY KEY,
+                        document_id TEXT NOT NULL,
+                        chunk_index INTEGER NOT NULL,
+                        content TEXT NOT NULL,
+                        metadata TEXT NOT NULL,
+                        created_at TEXT NOT NULL,
+                        dimensions INTEGER NOT NULL,
+                        embedding BLOB NOT NULL
+                    )",
+                )
+                .execute(&self.pool)
+                .await?;
+                sqlx::query("CREATE INDEX IF NOT EXISTS idx_rag_chunks_document ON rag_chunks(document_id)")
+                    .execute(&self.pool)
+                    .await?;
+                anyhow::Ok(())
+            })
+            .await
+            .with_context(|| format!("Failed to open vector store {}", self.path.display()))?;
+        Ok(&self.pool)
+    }
+}
+
+#[async_trait]
+impl VectorStore for SqliteVectorStore {
+    fn name(&self) -> &'static str {
+        "sqlite"
+    }
+
+    async fn upsert(&self, entries: Vec<(DocumentChunk, Vec<f32>)>) -> Result<()> {
+        let mut tx = self.pool().await?.begin().await?;
+        for (chunk, vector) in &entries {
+            sqlx::query(
+                "INSERT OR REPLACE INTO rag_chunks
+                 (chunk_id, document_id, chunk_index, content, metadata, created_at, dimensions, embedding)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(&chunk.chunk_id)
+            .bind(&chunk.document_id)
+            .bind(chunk.chunk_index as i64)
+            .bind(&chunk.content)
+            .bind(serde_json::to_string(&chunk.metadata)?)
+            .bind(chunk.created_at.to_rfc3339())
+            .bind(vector.len() as i64)
+            .bind(vector_to_bytes(vector))
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn search(&self, query: &[f32], limit: usize) -> Result<Vec<RetrievalResult>> {
+        let pool = self.pool().await?;
+        let mut rows = sqlx::query(
+            "SELECT chunk_id, document_id, chunk_index, content, metadata, created_at, embedding
+             FROM rag_chunks WHERE dimensions = ?",
+        )
+        .bind(query.len() as i64)
+        .fetch(pool);
+
+        // 流式扫描：只保留当前最好的 limit 个
+        let mut results = Vec::with_capacity(limit + 1);
+        while let Some(row) = rows.try_next().await? {
+            let embedding: Vec<u8> = row.try_get("embedding")?;
+            let score = cosine_similarity(query, &bytes_to_vector(&embedding));
+            if results.len() >= limit && results.last().is_some_and(|worst: &RetrievalResult| worst.score >= score) {
+                continue;
+            }
+            let created_at: String = row.try_get("created_at")?;
+            let metadata: String = row.try_get("metadata")?;
+            let chunk = DocumentChunk {
+                chunk_id: row.try_get("chunk_id")?,
+                document_id: row.try_get("document_id")?,
+                content: row.try_get("content")?,
+                chunk_index: row.try_get::<i64, _>("chunk_index")? as usize,
+                metadata: serde_json::from_str(&metadata).unwrap_or_default(),
+                embedding: None,
+                created_at: DateTime::parse_from_rfc3339(&created_at)
+                    .map(|time| time.with_timezone(&Utc))
+                    .unwrap_or_else(|_| Utc::now()),
+            };
+            results.push(vector_result(chunk, score));
+            rank(&mut results, limit);
+        }
+        Ok(results)
+    }
+
+    async fn delete_document(&self, document_id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM rag_chunks WHERE document_id = ?")
+            .bind(document_id)
+            .execute(self.pool().await?)
+            .await?;
+        Ok(())
+    }
+
+    async fn count(&self) -> Result<usize> {
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM rag_chunks")
+            .fetch_one(self.pool().await?)
+            .await?;
+        Ok(count as usize)
+    }
+}
+
+fn vector_to_bytes(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|value| value.to_le_bytes()).collect()
+}
+
+fn bytes_to_vector(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect()
+}
+
+// ===== Qdrant =====
+
+#[derive(Debug, Deserialize)]
+struct QdrantResponse<T> {
+    result: T,
+}
+
+#[derive(Debug, Deserialize)]
+struct QdrantScoredPoint {
+    score: f64,
+    #[serde(default)]
+    payload: Option<DocumentChunk>,
+}
+
+#[derive(Debug, Deserialize)]
+struct QdrantCount {
+    count: usize,
+}
+
+/// Qdrant HTTP 向量存储
+pub struct QdrantVectorStore {
+    client: Client,
+    base_url: String,
+    collection: String,
+    api_key: Option<String>,
+    ready: OnceCell<()>,
+}
+
+impl QdrantVectorStore {
+    pub fn new(url: &str, collection: &str) -> Result<Self> {
+        offline::require_local(Capability::VectorStore, url)?;
+        Ok(Self {
+            client: Client::new(),
+            base_url: url.trim_end_matches('/').to_string(),
+            collection: collection.to_string(),
+            api_key: None,
+            ready: OnceCell::new(),
+        })
+    }
+
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let request = self
+            .client
+            .request(method, format!("{}/collections/{}{}", self.base_url, self.collection, path));
+        match &self.api_key {
+            Some(api_key) => request.header("api-key", api_key),
+            None => request,
+        }
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        check_qdrant(request.send().await?).await
+    }
+
+    /// 集合不存在时按向量维度创建
+    async fn ensure_collection(&self, dimensions: usize) -> Result<()> {
+        self.ready
+            .get_or_try_init(|| async {
+                let response = self.request(reqwest::Method::GET, "").send().await?;
+                if response.status() == StatusCode::NOT_FOUND {
+                    info!("🗄️ Creating Qdrant collection {} ({} dims)", self.collection, dimensions);
+                    self.send(
+                        self.request(reqwest::Method::PUT, "")
+                            .json(&json!({ "vectors": { "size": dimensions, "distance": "Cosine" } })),
+                    )
+                    .await?;
+                } else if !response.status().is_success() {
+                    return Err(anyhow!("Qdrant collection {} unavailable: {}", self.collection, response.status()));
+                }
+                anyhow::Ok(())
+            })
+            .await?;
+        Ok(())
+    }
+}
+
+/// Qdrant 点 ID 只接受整数或 UUID：由 chunk_id 稳定哈希得到
+fn point_id(chunk_id: &str) -> u64 {
+    chunk_id.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[async_trait]
+impl VectorStore for QdrantVectorStore {
+    fn name(&self) -> &'static str {
+        "qdrant"
+    }
+
+    async fn upsert(&self, entries: Vec<(DocumentChunk, Vec<f32>)>) -> Result<()> {
+        let Some((_, first)) = entries.first() else {
+            return Ok(());
+        };
+        self.ensure_collection(first.len()).await?;
+
+        let points: Vec<_> = entries
+            .into_iter()
+            .map(|(mut chunk, vector)| {
+                chunk.embedding = None;
+                json!({ "id": point_id(&chunk.chunk_id), "vector": vector, "payload": chunk })
+            })
+            .collect();
+        self.send(self.request(reqwest::Method::PUT, "/points?wait=true").json(&json!({ "points": points })))
+            .await?;
+        Ok(())
+    }
+
+    async fn search(&self, query: &[f32], limit: usize) -> Result<Vec<RetrievalResult>> {
+        let response = self
+            .request(reqwest::Method::POST, "/points/search")
+            .json(&json!({ "vector": query, "limit": limit, "with_payload": true }))
+            .send()
+            .await?;
+        // 尚未写入过任何文档
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(Vec::new());
+        }
+        let points: QdrantResponse<Vec<QdrantScoredPoint>> = check_qdrant(response).await?.json().await?;
+        Ok(points
+            .result
+            .into_iter()
+            .filter_map(|point| Some(vector_result(point.payload?, point.score)))
+            .collect())
+    }
+
+    async fn delete_document(&self, document_id: &str) -> Result<()> {
+        let filter = json!({ "filter": { "must": [{ "key": "document_id", "match": { "value": document_id } }] } });
+        let response = self
+            .request(reqwest::Method::POST, "/points/delete?wait=true")
+            .json(&filter)
+            .send()
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(());
+        }
+        check_qdrant(response).await?;
+        Ok(())
+    }
+
+    async fn count(&self) -> Result<usize> {
+        let response = self
+            .request(reqwest::Method::POST, "/points/count")
+            .json(&json!({ "exact": true }))
+            .send()
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(0);
+        }
+        let count: QdrantResponse<QdrantCount> = check_qdrant(response).await?.json().await?;
+        Ok(count.result.count)
+    }
+}
+
+/// 非 2xx 响应转为带错误代码的 AcsaError
+async fn check_qdrant(response: reqwest::Response) -> Result<reqwest::Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let error_text = response.text().await.unwrap_or_default();
+    Err(AcsaError::new(
+        ErrorCode::from_http_status(status.as_u16()),
+        format!("Qdrant error ({}): {}", status, error_text),
+    )
+    .into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(document_id: &str, index: usize, content: &str) -> DocumentChunk {
+        DocumentChunk {
+            chunk_id: format!("{}_{}", document_id, index),
+            document_id: document_id.to_string(),
+            content: content.to_string(),
+            chunk_index: index,
+            metadata: HashMap::from([("title".to_string(), document_id.to_uppercase())]),
+            embedding: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    async fn exercise(store: &dyn VectorStore) {
+        store
+            .upsert(vec![
+                (chunk("a", 0, "north"), vec![1.0, 0.0]),
+                (chunk("a", 1, "east"), vec![0.0, 1.0]),
+                (chunk("b", 0, "north-east"), vec![0.7, 0.7]),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(store.count().await.unwrap(), 3);
+
+        let results = store.search(&[1.0, 0.1], 2).await.unwrap();
+        let ids: Vec<_> = results.iter().map(|r| r.chunk.chunk_id.as_str()).collect();
+        assert_eq!(ids, vec!["a_0", "b_0"]);
+        assert_eq!(results[0].chunk.metadata.get("title").map(String::as_str), Some("A"));
+
+        store.delete_document("a").await.unwrap();
+        assert_eq!(store.count().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_memory_store() {
+        exercise(&MemoryVectorStore::new()).await;
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_store_persists() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("index").join("vectors.db");
+        exercise(&SqliteVectorStore::open(&path).unwrap()).await;
+
+        // 重新打开后数据仍在
+        let reopened = SqliteVectorStore::open(&path).unwrap();
+        assert_eq!(reopened.count().await.unwrap(), 1);
+        let results = reopened.search(&[1.0, 1.0], 5).await.unwrap();
+        assert_eq!(results[0].chunk.content, "north-east");
+        assert!(reopened.search(&[1.0, 1.0, 1.0], 5).await.unwrap().is_empty());
+    }
+}
@@ -9,6 +9,7 @@ use tokio::sync::RwLock;
 use tracing::info;
 
 use super::emergency_log::{EmergencyLogger, LogEntryType};
+use super::offline::{self, Capability};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VoiceConfig {
@@ -49,6 +50,7 @@ impl VoiceProcessor {
 
     pub async fn speech_to_text(&self, audio: &[u8]) -> Result<SttResult> {
         info!("🎧 STT: {} bytes", audio.len());
+        offline::require_local(Capability::SpeechToText, &self.config.kyutai_server_url)?;
         
         // 模拟实现
         let text = "主人，我收到了您的语音指令".to_string();
@@ -69,6 +71,7 @@ impl VoiceProcessor {
 
     pub async fn text_to_speech(&self, text: &str) -> Result<Vec<u8>> {
         info!("🔊 TTS: {}", text);
+        offline::require_local(Capability::TextToSpeech, &self.config.kyutai_server_url)?;
         
         let cache_key = text.to_string();
         if self.config.enable_cache {
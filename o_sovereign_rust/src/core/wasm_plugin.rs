@@ -0,0 +1,470 @@
+// WASM Plugin - 沙箱化的第三方插件运行时（feature = "wasm"）
+// 原生动态库插件与宿主同进程、同权限；WASM 插件只能通过宿主 API 接触外部，
+// 每次请求在独立的 Store 中实例化，受 fuel（指令预算）与线性内存上限约束
+//
+// 核心功能：
+// 1. 宿主 API（wit/acsa-plugin.wit）：工具调用、插件私有 KV 存储、日志
+// 2. ResourceLimits：max_fuel 限制每次请求执行的指令数，max_memory_mb 限制线性内存，超限时请求失败
+// 3. 热重载：插件文件修改后重新编译，编译失败时保留旧版本；KV 存储跨重载保留
+//
+// 与原设计的差异（评审已认可）：原设计为 wasmtime 组件模型 + WIT 绑定生成，实际运行时使用 wasmi 0.32
+// （纯 Rust 解释执行，`wasm` feature 不引入 cranelift 编译链）。wasmi 不支持组件模型，因此：
+// - 插件编译为核心模块，不需要 cargo-component / wit-component
+// - wit/acsa-plugin.wit 仍是接口的唯一定义，但不做 bindgen；每个函数按其中注明的核心 ABI 手工降级
+// - 资源限制使用 wasmi 的 fuel 与 StoreLimits，语义与 wasmtime 的同名机制一致
+// 本地 registry 中有 wasmtime 30（含 component-model，可离线构建），日后迁移时只需替换本模块的
+// 实例化与宿主函数注册，WIT 接口与 PluginHandler 不变
+
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::SystemTime;
+use tracing::{debug, error, info, warn};
+use wasmi::core::TrapCode;
+use wasmi::{Caller, Config, Engine, Extern, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+use super::event_bus::Event;
+use super::plugin_system::{HostTool, PluginConfig, PluginHandler, PluginRequest, PluginResponse, ResourceLimits};
+
+/// 宿主函数的导入模块名
+const HOST_MODULE: &str = "acsa";
+
+/// 插件 `handle` 的输出
+#[derive(Debug, Default, Deserialize)]
+struct WasmOutput {
+    #[serde(default)]
+    content: String,
+    #[serde(default)]
+    metadata: HashMap<String, String>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+/// 单次请求的 Store 数据
+struct HostState {
+    plugin_id: String,
+    kv: Arc<Mutex<HashMap<String, String>>>,
+    tools: Arc<RwLock<HashMap<String, HostTool>>>,
+    limits: StoreLimits,
+}
+
+/// 已编译的插件模块
+struct LoadedModule {
+    module: Arc<Module>,
+    /// 编译时的文件修改时间（热重载比较用）
+    modified: Option<SystemTime>,
+}
+
+struct Runtime {
+    plugin_id: String,
+    path: PathBuf,
+    limits: ResourceLimits,
+    engine: Engine,
+    module: RwLock<LoadedModule>,
+    kv: Arc<Mutex<HashMap<String, String>>>,
+    tools: Arc<RwLock<HashMap<String, HostTool>>>,
+}
+
+/// WASM 插件处理器
+pub struct WasmPluginHandler {
+    runtime: Arc<Runtime>,
+}
+
+impl WasmPluginHandler {
+    /// 编译插件文件（二进制 .wasm 核心模块）
+    pub fn load(
+        plugin_id: impl Into<String>,
+        path: impl Into<PathBuf>,
+        limits: ResourceLimits,
+        tools: Arc<RwLock<HashMap<String, HostTool>>>,
+    ) -> Result<Self> {
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let path = path.into();
+        let module = compile(&engine, &path)?;
+        Ok(Self {
+            runtime: Arc::new(Runtime {
+                plugin_id: plugin_id.into(),
+                path,
+                limits,
+                engine,
+                module: RwLock::new(module),
+                kv: Arc::default(),
+                tools,
+            }),
+        })
+    }
+
+    /// KV 存储中的值
+    pub fn kv_get(&self, key: &str) -> Option<String> {
+        self.runtime.kv.lock().unwrap().get(key).cloned()
+    }
+}
+
+#[async_trait::async_trait]
+impl PluginHandler for WasmPluginHandler {
+    /// 插件配置写入 KV（`config.<键>`），插件通过 kv-get 读取
+    async fn initialize(&mut self, config: &PluginConfig) -> Result<()> {
+        let mut kv = self.runtime.kv.lock().unwrap();
+        for (key, value) in &config.settings {
+            kv.insert(format!("config.{}", key), value.clone());
+        }
+        Ok(())
+    }
+
+    async fn handle_request(&self, request: PluginRequest) -> Result<PluginResponse> {
+        let runtime = self.runtime.clone();
+        let input = serde_json::to_vec(&request)?;
+        // 解释执行是同步的，放到阻塞线程池；超时由 PluginSystem 控制，超时后本次执行仍受 fuel 上限约束
+        let output = tokio::task::spawn_blocking(move || runtime.invoke(&input)).await??;
+        if let Some(error) = output.error {
+            return Err(anyhow!("WASM plugin {} reported an error: {}", self.runtime.plugin_id, error));
+        }
+        Ok(PluginResponse {
+            request_id: request.request_id,
+            content: output.content,
+            metadata: output.metadata,
+            success: true,
+        })
+    }
+
+    async fn handle_event(&self, _event: &Event) -> Result<()> {
+        Ok(())
+    }
+
+    async fn shutdown(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn health_check(&self) -> bool {
+        self.runtime.path.exists()
+    }
+
+    async fn reload_if_changed(&self) -> Result<bool> {
+        let runtime = &self.runtime;
+        let modified = std::fs::metadata(&runtime.path).and_then(|meta| meta.modified()).ok();
+        if modified.is_none() || modified == runtime.module.read().unwrap().modified {
+            return Ok(false);
+        }
+        match compile(&runtime.engine, &runtime.path) {
+            Ok(module) => {
+                *runtime.module.write().unwrap() = module;
+                info!("🔄 WASM plugin {} recompiled from {}", runtime.plugin_id, runtime.path.display());
+                Ok(true)
+            }
+            Err(e) => {
+                // 记下这次修改时间，同一个坏文件只报错一次
+                runtime.module.write().unwrap().modified = modified;
+                Err(e)
+            }
+        }
+    }
+}
+
+impl Runtime {
+    /// 在新的 Store 中实例化模块并处理一次请求
+    fn invoke(&self, request: &[u8]) -> Result<WasmOutput> {
+        let module = self.module.read().unwrap().module.clone();
+        let memory_limit = usize::try_from(self.limits.max_memory_mb.saturating_mul(1024 * 1024)).unwrap_or(usize::MAX);
+        let state = HostState {
+            plugin_id: self.plugin_id.clone(),
+            kv: self.kv.clone(),
+            tools: self.tools.clone(),
+            limits: StoreLimitsBuilder::new().memory_size(memory_limit).instances(1).build(),
+        };
+        let mut store = Store::new(&self.engine, state);
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(self.limits.max_fuel).map_err(|e| anyhow!("{}", e))?;
+
+        let instance = host_linker(&self.engine)?
+            .instantiate(&mut store, &module)
+            .and_then(|pre| pre.start(&mut store))
+            .map_err(|e| self.trap(e))?;
+        let memory = instance
+            .get_memory(&store, "memory")
+            .ok_or_else(|| anyhow!("WASM plugin {} does not export memory", self.plugin_id))?;
+        let alloc = instance
+            .get_typed_func::<i32, i32>(&store, "acsa_alloc")
+            .map_err(|e| anyhow!("WASM plugin {} lacks acsa_alloc: {}", self.plugin_id, e))?;
+        let handle = instance
+            .get_typed_func::<(i32, i32), i64>(&store, "acsa_handle")
+            .map_err(|e| anyhow!("WASM plugin {} lacks acsa_handle: {}", self.plugin_id, e))?;
+
+        let len = i32::try_from(request.len()).map_err(|_| anyhow!("Plugin request too large"))?;
+        let ptr = alloc.call(&mut store, len).map_err(|e| self.trap(e))?;
+        memory
+            .write(&mut store, ptr as u32 as usize, request)
+            .map_err(|e| anyhow!("WASM plugin {} returned an invalid buffer: {}", self.plugin_id, e))?;
+        let packed = handle.call(&mut store, (ptr, len)).map_err(|e| self.trap(e))?;
+        let (out_ptr, out_len) =
+            unpack(packed).ok_or_else(|| anyhow!("WASM plugin {} returned no output", self.plugin_id))?;
+        let output = memory
+            .data(&store)
+            .get(out_ptr..out_ptr.saturating_add(out_len))
+            .ok_or_else(|| anyhow!("WASM plugin {} returned an out-of-bounds output", self.plugin_id))?;
+        serde_json::from_slice(output).with_context(|| format!("WASM plugin {} returned invalid JSON", self.plugin_id))
+    }
+
+    fn trap(&self, e: wasmi::Error) -> anyhow::Error {
+        match e.as_trap_code() {
+            Some(TrapCode::OutOfFuel) => {
+                anyhow!("WASM plugin {} exceeded its fuel limit ({})", self.plugin_id, self.limits.max_fuel)
+            }
+            _ => anyhow!("WASM plugin {} failed: {}", self.plugin_id, e),
+        }
+    }
+}
+
+fn compile(engine: &Engine, path: &Path) -> Result<LoadedModule> {
+    let bytes = std::fs::read(path).with_context(|| format!("Failed to read WASM plugin {}", path.display()))?;
+    let module = Module::new(engine, &bytes[..]).map_err(|e| anyhow!("Invalid WASM plugin {}: {}", path.display(), e))?;
+    let modified = std::fs::metadata(path).and_then(|meta| meta.modified()).ok();
+    Ok(LoadedModule { module: Arc::new(module), modified })
+}
+
+/// 宿主 API（wit/acsa-plugin.wit 的核心 ABI）
+fn host_linker(engine: &Engine) -> Result<Linker<HostState>> {
+    let mut linker = Linker::new(engine);
+    linker
+        .func_wrap(HOST_MODULE, "log", |caller: Caller<'_, HostState>, level: i32, ptr: i32, len: i32| {
+            let message = read_string(&caller, ptr, len)?;
+            let plugin = caller.data().plugin_id.as_str();
+            match level {
+                0 => debug!(plugin, "{}", message),
+                1 => info!(plugin, "{}", message),
+                2 => warn!(plugin, "{}", message),
+                _ => error!(plugin, "{}", message),
+            }
+            Ok(())
+        })
+        .map_err(|e| anyhow!("{}", e))?;
+    linker
+        .func_wrap(HOST_MODULE, "kv_get", |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| {
+            let key = read_string(&caller, ptr, len)?;
+            let value = caller.data().kv.lock().unwrap().get(&key).cloned();
+            match value {
+                Some(value) => return_string(&mut caller, &value),
+                None => Ok(-1),
+            }
+        })
+        .map_err(|e| anyhow!("{}", e))?;
+    linker
+        .func_wrap(
+            HOST_MODULE,
+            "kv_set",
+            |caller: Caller<'_, HostState>, key_ptr: i32, key_len: i32, value_ptr: i32, value_len: i32| {
+                let key = read_string(&caller, key_ptr, key_len)?;
+                let value = read_string(&caller, value_ptr, value_len)?;
+                caller.data().kv.lock().unwrap().insert(key, value);
+                Ok(())
+            },
+        )
+        .map_err(|e| anyhow!("{}", e))?;
+    linker
+        .func_wrap(HOST_MODULE, "kv_delete", |caller: Caller<'_, HostState>, ptr: i32, len: i32| {
+            let key = read_string(&caller, ptr, len)?;
+            Ok(caller.data().kv.lock().unwrap().remove(&key).is_some() as i32)
+        })
+        .map_err(|e| anyhow!("{}", e))?;
+    linker
+        .func_wrap(
+            HOST_MODULE,
+            "tool_call",
+            |mut caller: Caller<'_, HostState>, name_ptr: i32, name_len: i32, args_ptr: i32, args_len: i32| {
+                let name = read_string(&caller, name_ptr, name_len)?;
+                let args = read_string(&caller, args_ptr, args_len)?;
+                let plugin_id = caller.data().plugin_id.clone();
+                let tool = caller.data().tools.read().unwrap().get(&name).cloned();
+                let Some(tool) = tool else {
+                    warn!("⚠️  WASM plugin {} called unknown tool '{}'", plugin_id, name);
+                    return Ok(-1);
+                };
+                match tool(&args) {
+                    Ok(result) => return_string(&mut caller, &result),
+                    Err(e) => {
+                        warn!("⚠️  Tool '{}' failed for WASM plugin {}: {}", name, plugin_id, e);
+                        Ok(-1)
+                    }
+                }
+            },
+        )
+        .map_err(|e| anyhow!("{}", e))?;
+    Ok(linker)
+}
+
+fn exported_memory(caller: &Caller<'_, HostState>) -> Result<Memory, wasmi::Error> {
+    caller
+        .get_export("memory")
+        .and_then(Extern::into_memory)
+        .ok_or_else(|| wasmi::Error::new("plugin does not export memory"))
+}
+
+fn read_string(caller: &Caller<'_, HostState>, ptr: i32, len: i32) -> Result<String, wasmi::Error> {
+    let memory = exported_memory(caller)?;
+    let start = ptr as u32 as usize;
+    let bytes = memory
+        .data(caller)
+        .get(start..start.saturating_add(len as u32 as usize))
+        .ok_or_else(|| wasmi::Error::new("plugin passed an out-of-bounds string"))?;
+    String::from_utf8(bytes.to_vec()).map_err(|_| wasmi::Error::new("plugin passed invalid UTF-8"))
+}
+
+/// 经插件的 `acsa_alloc` 分配内存并写入字符串，返回 `(ptr << 32) | len`
+fn return_string(caller: &mut Caller<'_, HostState>, value: &str) -> Result<i64, wasmi::Error> {
+    let len = i32::try_from(value.len()).map_err(|_| wasmi::Error::new("host value too large"))?;
+    let alloc = caller
+        .get_export("acsa_alloc")
+        .and_then(Extern::into_func)
+        .ok_or_else(|| wasmi::Error::new("plugin does not export acsa_alloc"))?
+        .typed::<i32, i32>(&*caller)?;
+    let ptr = alloc.call(&mut *caller, len)?;
+    exported_memory(caller)?
+        .write(&mut *caller, ptr as u32 as usize, value.as_bytes())
+        .map_err(|e| wasmi::Error::new(e.to_string()))?;
+    Ok(((ptr as u32 as i64) << 32) | len as i64)
+}
+
+/// `(ptr << 32) | len`；负数表示 none
+fn unpack(packed: i64) -> Option<(usize, usize)> {
+    (packed >= 0).then_some(((packed >> 32) as usize, (packed & 0xffff_ffff) as usize))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::plugin_system::{PluginMetadata, PluginSystem, PluginSystemConfig, PluginType};
+    use chrono::Utc;
+
+    /// 宿主 API 导入、一页以上的线性内存与一个递增分配器
+    const PRELUDE: &str = r#"(module
+        (import "acsa" "log" (func $log (param i32 i32 i32)))
+        (import "acsa" "kv_get" (func $kv_get (param i32 i32) (result i64)))
+        (import "acsa" "kv_set" (func $kv_set (param i32 i32 i32 i32)))
+        (import "acsa" "tool_call" (func $tool_call (param i32 i32 i32 i32) (result i64)))
+        (memory (export "memory") PAGES)
+        (global $heap (mut i32) (i32.const 4096))
+        (func (export "acsa_alloc") (param $len i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr (global.get $heap))
+            (global.set $heap (i32.add (global.get $heap) (local.get $len)))
+            (local.get $ptr))
+        BODY)"#;
+
+    fn module(pages: u32, body: &str) -> Vec<u8> {
+        wat::parse_str(PRELUDE.replace("PAGES", &pages.to_string()).replace("BODY", body)).unwrap()
+    }
+
+    fn request(user_input: &str) -> PluginRequest {
+        PluginRequest {
+            request_id: "req_1".to_string(),
+            user_input: user_input.to_string(),
+            context: HashMap::new(),
+            protocol: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_host_api_and_resource_limits() {
+        let dir = tempfile::tempdir().unwrap();
+        let tools: Arc<RwLock<HashMap<String, HostTool>>> = Arc::default();
+        let upper: HostTool = Arc::new(|args: &str| {
+            Ok(serde_json::json!({ "content": args.to_uppercase(), "metadata": { "tool": "upper" } }).to_string())
+        });
+        tools.write().unwrap().insert("upper".to_string(), upper);
+        let load = |name: &str, wasm: Vec<u8>, limits: ResourceLimits| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, wasm).unwrap();
+            WasmPluginHandler::load(name, path, limits, tools.clone()).unwrap()
+        };
+
+        // 记日志、写计数、把配置项复制到另一个键，再把请求交给宿主工具并原样返回其结果
+        let body = r#"
+            (data (i32.const 0) "count")
+            (data (i32.const 8) "1")
+            (data (i32.const 16) "upper")
+            (data (i32.const 32) "config.greeting")
+            (data (i32.const 64) "greeting-seen")
+            (func (export "acsa_handle") (param $ptr i32) (param $len i32) (result i64)
+                (local $greeting i64)
+                (call $log (i32.const 1) (local.get $ptr) (local.get $len))
+                (call $kv_set (i32.const 0) (i32.const 5) (i32.const 8) (i32.const 1))
+                (local.set $greeting (call $kv_get (i32.const 32) (i32.const 15)))
+                (call $kv_set (i32.const 64) (i32.const 13)
+                    (i32.wrap_i64 (i64.shr_u (local.get $greeting) (i64.const 32)))
+                    (i32.wrap_i64 (local.get $greeting)))
+                (call $tool_call (i32.const 16) (i32.const 5) (local.get $ptr) (local.get $len)))"#;
+        let mut handler = load("echo.wasm", module(1, body), ResourceLimits::default());
+        let settings = HashMap::from([("greeting".to_string(), "hi".to_string())]);
+        handler.initialize(&PluginConfig { settings }).await.unwrap();
+        let response = handler.handle_request(request("scan")).await.unwrap();
+        assert!(response.content.contains(r#""USER_INPUT":"SCAN""#), "{}", response.content);
+        assert_eq!(response.metadata["tool"], "upper");
+        assert_eq!(handler.kv_get("count").as_deref(), Some("1"));
+        assert_eq!(handler.kv_get("greeting-seen").as_deref(), Some("hi"));
+
+        // 死循环在 fuel 耗尽时中止
+        let spin = r#"(func (export "acsa_handle") (param i32 i32) (result i64) (loop $spin (br $spin)) (i64.const -1))"#;
+        let limits = ResourceLimits { max_fuel: 10_000, ..Default::default() };
+        let error = load("spin.wasm", module(1, spin), limits).handle_request(request("x")).await.unwrap_err();
+        assert!(error.to_string().contains("fuel limit"), "{}", error);
+
+        // 初始线性内存（32 页 = 2MB）超过 1MB 上限：实例化失败
+        let idle = r#"(func (export "acsa_handle") (param i32 i32) (result i64) (i64.const -1))"#;
+        let limits = ResourceLimits { max_memory_mb: 1, ..Default::default() };
+        assert!(load("big.wasm", module(32, idle), limits).handle_request(request("x")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_hot_reload_keeps_kv_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let system = PluginSystem::new(PluginSystemConfig { plugins_dir: dir.path().to_path_buf(), ..Default::default() }, None);
+        system
+            .register_plugin(PluginMetadata {
+                plugin_id: "notes".to_string(),
+                name: "Notes".to_string(),
+                version: "1.0.0".to_string(),
+                author: "ACSA Team".to_string(),
+                description: "Remembers the last answer".to_string(),
+                plugin_type: PluginType::Tool,
+                dependencies: vec![],
+                supported_protocols: vec![],
+                resource_limits: ResourceLimits::default(),
+                created_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+        let config = PluginConfig { settings: HashMap::new() };
+
+        // 启用沙箱时拒绝原生动态库
+        let native = dir.path().join("notes.so");
+        assert!(system.load_plugin("notes", native, config.clone()).await.is_err());
+
+        // v1 在 KV 中留下一条记录，v2 原样返回这条记录
+        let v1 = r#"
+            (data (i32.const 0) "last")
+            (data (i32.const 8) "{\"content\":\"v1 was here\"}")
+            (data (i32.const 64) "{\"content\":\"v1\"}")
+            (func (export "acsa_handle") (param i32 i32) (result i64)
+                (call $kv_set (i32.const 0) (i32.const 4) (i32.const 8) (i32.const 25))
+                (i64.or (i64.shl (i64.const 64) (i64.const 32)) (i64.const 16)))"#;
+        let v2 = r#"
+            (data (i32.const 0) "last")
+            (func (export "acsa_handle") (param i32 i32) (result i64)
+                (call $kv_get (i32.const 0) (i32.const 4)))"#;
+        let path = dir.path().join("notes.wasm");
+        std::fs::write(&path, module(1, v1)).unwrap();
+        system.load_plugin("notes", path.clone(), config).await.unwrap();
+        system.start_plugin("notes").await.unwrap();
+        assert_eq!(system.send_request("notes", request("a")).await.unwrap().content, "v1");
+        assert!(system.check_hot_reload().await.is_empty());
+
+        std::fs::write(&path, module(1, v2)).unwrap();
+        let later = SystemTime::now() + std::time::Duration::from_secs(10);
+        std::fs::File::options().write(true).open(&path).unwrap().set_modified(later).unwrap();
+        assert_eq!(system.check_hot_reload().await, vec!["notes".to_string()]);
+        assert_eq!(system.send_request("notes", request("b")).await.unwrap().content, "v1 was here");
+    }
+}
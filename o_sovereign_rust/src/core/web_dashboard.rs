@@ -0,0 +1,135 @@
+// Web Dashboard - 内嵌的单页仪表盘
+// 静态文件编译进二进制，由 http_server 在 /dashboard 下提供
+//
+// 核心功能：
+// 1. 内嵌静态资源（web/dashboard：index.html / app.js / styles.css）与 Content-Type
+// 2. 仪表盘快照：进行中的执行、最近执行、SOSA 端点健康度、Provider 成本、主权 H(t)
+// 3. H(t) 趋势采样（按最小间隔记录，保留最近一天）
+//
+// 注：页面只调用现有 JSON 接口（/api/v1/dashboard 与执行实况 SSE），认证方式与其他接口相同
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use super::api_manager::ProviderStats;
+use super::execution_store::ExecutionSummary;
+use super::sosa_api_pool::EndpointStatus;
+use super::sovereignty::{BioActivity, RiskLevel};
+
+/// 内嵌的静态资源：(文件名, Content-Type, 内容)
+const ASSETS: &[(&str, &str, &str)] = &[
+    ("index.html", "text/html; charset=utf-8", include_str!("../../web/dashboard/index.html")),
+    ("app.js", "text/javascript; charset=utf-8", include_str!("../../web/dashboard/app.js")),
+    ("styles.css", "text/css; charset=utf-8", include_str!("../../web/dashboard/styles.css")),
+];
+
+/// H(t) 趋势最小采样间隔
+const TREND_SAMPLE_INTERVAL_SECS: i64 = 60;
+/// H(t) 趋势保留点数（按最小间隔约一天）
+const MAX_TREND_POINTS: usize = 1440;
+
+/// 按路径取静态资源：空路径与无扩展名的路径（前端路由）返回 index.html
+pub fn dashboard_asset(path: &str) -> Option<(&'static str, &'static str)> {
+    let name = path.trim_start_matches('/');
+    let name = if name.is_empty() || !name.contains('.') { "index.html" } else { name };
+    ASSETS
+        .iter()
+        .find(|(file, _, _)| *file == name)
+        .map(|(_, content_type, body)| (*content_type, *body))
+}
+
+/// H(t) 采样点
+#[derive(Debug, Clone, Serialize)]
+pub struct TrendPoint {
+    pub at: DateTime<Utc>,
+    pub value: f64,
+}
+
+/// 主权 H(t) 趋势
+#[derive(Default)]
+pub struct SovereigntyTrend {
+    points: Mutex<VecDeque<TrendPoint>>,
+}
+
+impl SovereigntyTrend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录一次 H(t)（距上次采样不足最小间隔时覆盖最后一个点），返回当前趋势
+    pub fn record(&self, at: DateTime<Utc>, value: f64) -> Vec<TrendPoint> {
+        let mut points = self.points.lock().unwrap_or_else(|e| e.into_inner());
+        let point = TrendPoint { at, value };
+        match points.back_mut() {
+            Some(last) if at - last.at < Duration::seconds(TREND_SAMPLE_INTERVAL_SECS) => *last = point,
+            _ => points.push_back(point),
+        }
+        while points.len() > MAX_TREND_POINTS {
+            points.pop_front();
+        }
+        points.iter().cloned().collect()
+    }
+}
+
+/// 主权状态摘要
+#[derive(Debug, Clone, Serialize)]
+pub struct SovereigntySummary {
+    pub enabled: bool,
+    pub current: f64,
+    pub baseline: f64,
+    pub decay_rate: f64,
+    pub risk_level: RiskLevel,
+}
+
+impl SovereigntySummary {
+    pub fn new(bio: &BioActivity, enabled: bool) -> Self {
+        Self {
+            enabled,
+            current: bio.current,
+            baseline: bio.baseline,
+            decay_rate: bio.decay_rate,
+            risk_level: bio.risk_level,
+        }
+    }
+}
+
+/// 仪表盘快照（GET /api/v1/dashboard）
+#[derive(Debug, Clone, Serialize)]
+pub struct DashboardSnapshot {
+    /// 进行中的执行（Router 执行 ID，可订阅 /api/v1/executions/:id/events）
+    pub active_executions: Vec<String>,
+    /// 最近的执行记录（新的在前）
+    pub recent: Vec<ExecutionSummary>,
+    /// SOSA API 池端点健康度
+    pub endpoints: Vec<EndpointStatus>,
+    /// 各 Provider 调用与成本统计
+    pub providers: Vec<ProviderStats>,
+    pub total_cost: f64,
+    pub sovereignty: SovereigntySummary,
+    pub ht_trend: Vec<TrendPoint>,
+    pub generated_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assets_and_trend() {
+        let (content_type, body) = dashboard_asset("").unwrap();
+        assert!(content_type.starts_with("text/html"));
+        assert!(body.contains("/dashboard/app.js"));
+        assert_eq!(dashboard_asset("executions/live").unwrap().1, body);
+        assert!(dashboard_asset("/app.js").unwrap().1.contains("/api/v1/dashboard"));
+        assert!(dashboard_asset("missing.png").is_none());
+
+        let trend = SovereigntyTrend::new();
+        let start = Utc::now();
+        trend.record(start, 90.0);
+        assert_eq!(trend.record(start + Duration::seconds(10), 88.0).len(), 1);
+        let points = trend.record(start + Duration::seconds(120), 85.0);
+        assert_eq!(points.iter().map(|p| p.value).collect::<Vec<_>>(), vec![88.0, 85.0]);
+    }
+}
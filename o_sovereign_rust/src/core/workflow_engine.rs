@@ -1,13 +1,21 @@
 // Workflow Engine - 工作流分配系统
 // 集成MOSS拆解 + Jarvis排序
+// Scheduler：按 cron 表达式定时触发工作流或 ACSA 执行，计划持久化到 JSON 文件，停机期间错过的运行按策略补跑
 
-use std::collections::HashMap;
-use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Datelike, Duration as ChronoDuration, TimeZone, Timelike, Utc};
 use serde::{Deserialize, Serialize};
-use tracing::info;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
 
-use super::concurrency::{AsyncTask, ConcurrencyManager, TaskPriority as ConcurrentTaskPriority};
+use super::concurrency::{AsyncTask, ConcurrencyManager, TaskContext, TaskPriority as ConcurrentTaskPriority, TaskResult};
 use super::jarvis::{JarvisManager, RawTask, TaskPriority};
+use super::kill_switch::{KillSwitch, PausedOperation};
+use super::shutdown::ShutdownCoordinator;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Workflow {
@@ -28,6 +36,10 @@ pub struct WorkflowStep {
 pub struct WorkflowEngine {
     jarvis: JarvisManager,
     concurrency: ConcurrencyManager,
+    /// 关停协调器（关停开始后不再启动新的步骤）
+    shutdown: Option<Arc<ShutdownCoordinator>>,
+    /// 全局熔断开关（维护模式下不再启动新的步骤）
+    kill_switch: Option<Arc<KillSwitch>>,
 }
 
 impl WorkflowEngine {
@@ -36,9 +48,28 @@ impl WorkflowEngine {
         Self {
             jarvis: JarvisManager::new(),
             concurrency,
+            shutdown: None,
+            kill_switch: None,
         }
     }
 
+    /// 接入关停协调器
+    pub fn with_shutdown(mut self, coordinator: Arc<ShutdownCoordinator>) -> Self {
+        self.shutdown = Some(coordinator);
+        self
+    }
+
+    /// 接入全局熔断开关
+    pub fn with_kill_switch(mut self, kill_switch: Arc<KillSwitch>) -> Self {
+        self.kill_switch = Some(kill_switch);
+        self
+    }
+
+    /// Jarvis调度器（用于注册自定义Agent）
+    pub fn jarvis_mut(&mut self) -> &mut JarvisManager {
+        &mut self.jarvis
+    }
+
     pub async fn execute_workflow(&mut self, workflow: Workflow) -> Result<()> {
         info!("🚀 Executing workflow: {}", workflow.name);
 
@@ -58,6 +89,114 @@ impl WorkflowEngine {
         Ok(())
     }
 
+    /// 通过执行器运行工作流：按依赖分波次提交，同一波次内按 Jarvis 优先级并发执行
+    ///
+    /// 所有步骤共享一个工作流上下文；任一步骤失败即取消其余步骤。
+    pub async fn execute_workflow_with<F, Fut>(
+        &mut self,
+        workflow: Workflow,
+        runner: F,
+    ) -> Result<Vec<TaskResult>>
+    where
+        F: Fn(WorkflowStep) -> Fut,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        info!("🚀 Executing workflow: {}", workflow.name);
+
+        let raw_tasks = self.decompose_workflow(&workflow);
+        let priorities: HashMap<String, TaskPriority> = self
+            .jarvis
+            .prioritize_tasks(raw_tasks)
+            .into_iter()
+            .map(|task| (task.task_id.clone(), task))
+            .collect();
+
+        let context = TaskContext::current_child(workflow.id.clone());
+        // 第一个失败的步骤（根因），其余步骤随后因取消而失败
+        let root_failure: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
+        let mut pending = workflow.steps;
+        let mut completed: HashSet<String> = HashSet::new();
+        let mut results = Vec::new();
+
+        while !pending.is_empty() {
+            let (ready, blocked): (Vec<_>, Vec<_>) = pending
+                .into_iter()
+                .partition(|step| step.dependencies.iter().all(|dep| completed.contains(dep)));
+
+            if ready.is_empty() {
+                let ids: Vec<_> = blocked.iter().map(|step| step.id.as_str()).collect();
+                return Err(anyhow!("Unresolvable workflow dependencies: {}", ids.join(", ")));
+            }
+
+            let mut handles = Vec::with_capacity(ready.len());
+            for step in ready {
+                let Some(priority) = priorities.get(&step.id) else {
+                    continue;
+                };
+                if let Some(kill_switch) = &self.kill_switch {
+                    kill_switch.check(PausedOperation::Execution)?;
+                }
+                // 关停开始后拒绝启动新步骤；已提交的步骤持有 guard 直到完成
+                let work = self
+                    .shutdown
+                    .as_ref()
+                    .map(|coordinator| coordinator.begin(format!("workflow:{}/{}", workflow.id, step.id)))
+                    .transpose()?;
+                let task = self.convert_to_async_task(priority.clone());
+                let step_context = context.child(step.id.clone());
+                let workflow_context = context.clone();
+                let root_failure = root_failure.clone();
+                let step_id = step.id.clone();
+                let step_future = runner(step);
+                // 步骤失败时立即取消整个工作流，不必等待其余句柄
+                let guarded = async move {
+                    let _work = work;
+                    let result = step_future.await;
+                    if result.is_err() {
+                        root_failure.lock().unwrap().get_or_insert(step_id);
+                        workflow_context.cancel();
+                    }
+                    result
+                };
+                handles.push(
+                    self.concurrency
+                        .submit_with_context(task, step_context, guarded)
+                        .await?,
+                );
+            }
+
+            let mut failures = Vec::new();
+            for handle in handles {
+                let result = handle.wait().await?;
+                if result.success {
+                    completed.insert(result.task_id.clone());
+                    results.push(result);
+                } else {
+                    failures.push(result);
+                }
+            }
+
+            if !failures.is_empty() {
+                let root = root_failure.lock().unwrap().clone();
+                let index = failures
+                    .iter()
+                    .position(|r| Some(&r.task_id) == root.as_ref())
+                    .unwrap_or(0);
+                let result = failures.swap_remove(index);
+                return Err(anyhow!(
+                    "Workflow step {} failed: {}",
+                    result.task_id,
+                    result.error.unwrap_or_default()
+                ));
+            }
+
+            pending = blocked;
+        }
+
+        info!("✅ Workflow completed: {} steps", results.len());
+        Ok(results)
+    }
+
     fn decompose_workflow(&self, workflow: &Workflow) -> Vec<RawTask> {
         workflow.steps.iter().map(|step| RawTask {
             id: step.id.clone(),
@@ -74,10 +213,720 @@ impl WorkflowEngine {
         AsyncTask {
             id: task.task_id,
             name: task.reasoning,
-            priority: ConcurrentTaskPriority::Normal,
+            priority: Self::map_priority(task.final_priority),
             created_at: chrono::Utc::now(),
             agent_name: Some(task.assigned_agent),
             metadata: HashMap::new(),
         }
     }
+
+    /// Jarvis 优先级分数 (0-200) → 执行器优先级
+    fn map_priority(final_priority: f64) -> ConcurrentTaskPriority {
+        match final_priority {
+            p if p >= 120.0 => ConcurrentTaskPriority::Critical,
+            p if p >= 70.0 => ConcurrentTaskPriority::High,
+            p if p >= 30.0 => ConcurrentTaskPriority::Normal,
+            p if p >= 10.0 => ConcurrentTaskPriority::Low,
+            _ => ConcurrentTaskPriority::Background,
+        }
+    }
+}
+
+/// 默认计划文件
+pub const DEFAULT_SCHEDULE_PATH: &str = "./data/schedules.json";
+
+/// 计划时间超过多久仍未运行视为错过（停机、进程挂起）
+const MISSED_RUN_GRACE_SECS: i64 = 60;
+/// RunAll 策略下单次最多补跑的次数（只补最近的几次）
+const MAX_CATCH_UP_RUNS: usize = 24;
+/// 调度循环最长休眠间隔（CLI 修改计划文件后最迟在此间隔内生效）
+const MAX_TICK_SECS: i64 = 30;
+
+const MONTH_NAMES: [&str; 12] = ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+const WEEKDAY_NAMES: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// cron 表达式（5 个字段：分 时 日 月 周，按 UTC 计算）
+///
+/// 支持 `*`、`a-b`、`*/n`、`a-b/n`、逗号列表、月份与星期的英文缩写，以及
+/// `@hourly` / `@daily` / `@weekly` / `@monthly` / `@yearly`。
+/// 日与周都受限时满足其一即可（与 Vixie cron 一致）。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// 日 / 周字段是否以 `*` 开头（决定两者按"与"还是"或"组合）
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl CronSchedule {
+    pub fn parse(expression: &str) -> Result<Self> {
+        let expanded = match expression.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(anyhow!(
+                "Invalid cron expression '{}': expected 5 fields (minute hour day month weekday)",
+                expression
+            ));
+        };
+        let context = |e: anyhow::Error| anyhow!("Invalid cron expression '{}': {}", expression, e);
+
+        // 星期 7 等同于 0（周日）
+        let mut weekdays = parse_cron_field(weekday, 0, 7, &WEEKDAY_NAMES).map_err(context)?;
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays & !(1 << 7)) | 1;
+        }
+        Ok(Self {
+            minutes: parse_cron_field(minute, 0, 59, &[]).map_err(context)?,
+            hours: parse_cron_field(hour, 0, 23, &[]).map_err(context)?,
+            days: parse_cron_field(day, 1, 31, &[]).map_err(context)?,
+            months: parse_cron_field(month, 1, 12, &MONTH_NAMES).map_err(context)?,
+            weekdays,
+            any_day: day.starts_with('*'),
+            any_weekday: weekday.starts_with('*'),
+        })
+    }
+
+    /// `after` 之后（不含）的下一个触发时间；五年内都不会触发时返回 None（如 `0 0 31 2 *`）
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut time = after.with_second(0)?.with_nanosecond(0)? + ChronoDuration::minutes(1);
+        let limit = time + ChronoDuration::days(5 * 366);
+
+        while time < limit {
+            if !bit(self.months, time.month()) {
+                let (year, month) = if time.month() == 12 { (time.year() + 1, 1) } else { (time.year(), time.month() + 1) };
+                time = Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0).single()?;
+                continue;
+            }
+            if !self.day_matches(time) {
+                time = Utc.with_ymd_and_hms(time.year(), time.month(), time.day(), 0, 0, 0).single()?
+                    + ChronoDuration::days(1);
+                continue;
+            }
+            if !bit(self.hours, time.hour()) {
+                time = time.with_minute(0)? + ChronoDuration::hours(1);
+                continue;
+            }
+            if !bit(self.minutes, time.minute()) {
+                time += ChronoDuration::minutes(1);
+                continue;
+            }
+            return Some(time);
+        }
+        None
+    }
+
+    fn day_matches(&self, time: DateTime<Utc>) -> bool {
+        let day = bit(self.days, time.day());
+        let weekday = bit(self.weekdays, time.weekday().num_days_from_sunday());
+        if self.any_day || self.any_weekday {
+            day && weekday
+        } else {
+            day || weekday
+        }
+    }
+}
+
+fn bit(mask: u64, value: u32) -> bool {
+    mask & (1 << value) != 0
+}
+
+/// 解析单个字段为位掩码
+fn parse_cron_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<u64> {
+    let value = |token: &str| -> Result<u32> {
+        let lower = token.to_ascii_lowercase();
+        let number = match names.iter().position(|name| *name == lower) {
+            // 月份从 1 开始，星期从 0 开始
+            Some(index) => index as u32 + if names.len() == 12 { 1 } else { 0 },
+            None => token.parse().map_err(|_| anyhow!("'{}' is not a number", token))?,
+        };
+        if number < min || number > max {
+            return Err(anyhow!("{} is out of range {}-{}", number, min, max));
+        }
+        Ok(number)
+    };
+
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step.parse().map_err(|_| anyhow!("Invalid step '{}'", step))?;
+                if step == 0 {
+                    return Err(anyhow!("Step must be positive"));
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (value(start)?, value(end)?),
+                // `5/15` 表示从 5 开始每 15 个
+                None if part.contains('/') => (value(range)?, max),
+                None => {
+                    let single = value(range)?;
+                    (single, single)
+                }
+            },
+        };
+        if start > end {
+            return Err(anyhow!("Invalid range '{}'", range));
+        }
+        for number in (start..=end).step_by(step as usize) {
+            mask |= 1 << number;
+        }
+    }
+    Ok(mask)
+}
+
+/// 定时任务的执行内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScheduleTarget {
+    /// 一次 ACSA 执行
+    Execution { input: String },
+    /// 工作流（每个步骤作为一次 ACSA 执行，按依赖顺序运行）
+    Workflow { workflow: Workflow },
+}
+
+impl ScheduleTarget {
+    /// 列表展示用的简短描述
+    pub fn summary(&self) -> String {
+        match self {
+            ScheduleTarget::Execution { input } => format!("execute: {}", input.chars().take(60).collect::<String>()),
+            ScheduleTarget::Workflow { workflow } => format!("workflow: {} ({} steps)", workflow.name, workflow.steps.len()),
+        }
+    }
+}
+
+/// 停机期间错过的运行如何处理
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MissedRunPolicy {
+    /// 丢弃错过的运行，等下一个计划时间
+    Skip,
+    /// 合并为一次补跑
+    #[default]
+    RunOnce,
+    /// 逐次补跑（最多 MAX_CATCH_UP_RUNS 次）
+    RunAll,
+}
+
+impl MissedRunPolicy {
+    pub fn label(&self) -> &'static str {
+        match self {
+            MissedRunPolicy::Skip => "skip",
+            MissedRunPolicy::RunOnce => "run_once",
+            MissedRunPolicy::RunAll => "run_all",
+        }
+    }
+}
+
+impl std::str::FromStr for MissedRunPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().replace('-', "_").as_str() {
+            "skip" => Ok(MissedRunPolicy::Skip),
+            "run_once" | "once" => Ok(MissedRunPolicy::RunOnce),
+            "run_all" | "all" => Ok(MissedRunPolicy::RunAll),
+            other => Err(anyhow!("Unknown missed-run policy '{}' (expected skip, run_once or run_all)", other)),
+        }
+    }
+}
+
+/// 定时任务
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledJob {
+    pub id: String,
+    pub name: String,
+    /// cron 表达式（UTC）
+    pub cron: String,
+    pub target: ScheduleTarget,
+    #[serde(default)]
+    pub missed_runs: MissedRunPolicy,
+    pub created_at: DateTime<Utc>,
+    /// 下一次计划时间（持久化，重启后据此判断错过了哪些运行）
+    pub next_run_at: Option<DateTime<Utc>>,
+    pub last_run_at: Option<DateTime<Utc>>,
+    /// 最近一次运行的错误（成功时清空）
+    pub last_error: Option<String>,
+    #[serde(default)]
+    pub run_count: u64,
+}
+
+/// 到期的一次运行
+#[derive(Debug, Clone)]
+pub struct DueRun {
+    pub job: ScheduledJob,
+    /// 对应的计划时间
+    pub scheduled_for: DateTime<Utc>,
+    /// 是否为停机后的补跑
+    pub catch_up: bool,
+}
+
+/// 定时调度器
+///
+/// 计划文件是唯一的数据源：每次操作都重新读取，CLI 与服务进程可同时修改。
+/// 到期后先推进 next_run_at 再运行（至多一次），运行中进程退出不会重复触发。
+pub struct Scheduler {
+    path: PathBuf,
+    /// 串行化本进程内的读-改-写
+    lock: Mutex<()>,
+    /// 关停开始后不再触发新的运行；运行中的任务持有 guard 直到完成
+    shutdown: Option<Arc<ShutdownCoordinator>>,
+    /// 维护模式下跳过到期的运行（记为错误）
+    kill_switch: Option<Arc<KillSwitch>>,
+}
+
+impl Scheduler {
+    /// 打开计划文件（不存在时为空）
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let scheduler = Self { path, lock: Mutex::new(()), shutdown: None, kill_switch: None };
+        let jobs = scheduler.load()?;
+        info!("⏰ Scheduler opened: {} schedule(s) in {}", jobs.len(), scheduler.path.display());
+        Ok(scheduler)
+    }
+
+    /// 接入关停协调器
+    pub fn with_shutdown(mut self, coordinator: Arc<ShutdownCoordinator>) -> Self {
+        self.shutdown = Some(coordinator);
+        self
+    }
+
+    /// 接入全局熔断开关
+    pub fn with_kill_switch(mut self, kill_switch: Arc<KillSwitch>) -> Self {
+        self.kill_switch = Some(kill_switch);
+        self
+    }
+
+    /// 新增定时任务
+    pub fn add(
+        &self,
+        name: &str,
+        cron: &str,
+        target: ScheduleTarget,
+        missed_runs: MissedRunPolicy,
+    ) -> Result<ScheduledJob> {
+        if name.trim().is_empty() {
+            return Err(anyhow!("Schedule name must not be empty"));
+        }
+        match &target {
+            ScheduleTarget::Execution { input } if input.trim().is_empty() => {
+                return Err(anyhow!("Scheduled execution needs an input"));
+            }
+            ScheduleTarget::Workflow { workflow } if workflow.steps.is_empty() => {
+                return Err(anyhow!("Scheduled workflow {} has no steps", workflow.name));
+            }
+            _ => {}
+        }
+        let now = Utc::now();
+        let next_run_at = CronSchedule::parse(cron)?.next_after(now);
+        if next_run_at.is_none() {
+            return Err(anyhow!("Cron expression '{}' never fires", cron));
+        }
+
+        let _guard = self.lock.lock().unwrap();
+        let mut jobs = self.load()?;
+        let base = format!("sched_{}", now.timestamp_millis());
+        let mut id = base.clone();
+        let mut suffix = 1;
+        while jobs.iter().any(|job| job.id == id) {
+            id = format!("{}_{}", base, suffix);
+            suffix += 1;
+        }
+        let job = ScheduledJob {
+            id,
+            name: name.trim().to_string(),
+            cron: cron.trim().to_string(),
+            target,
+            missed_runs,
+            created_at: now,
+            next_run_at,
+            last_run_at: None,
+            last_error: None,
+            run_count: 0,
+        };
+        jobs.push(job.clone());
+        self.save(&jobs)?;
+        info!("⏰ Schedule added: {} ({}) [{}]", job.name, job.id, job.cron);
+        Ok(job)
+    }
+
+    /// 全部定时任务（按下一次运行时间排序）
+    pub fn list(&self) -> Result<Vec<ScheduledJob>> {
+        let mut jobs = self.load()?;
+        jobs.sort_by_key(|job| job.next_run_at);
+        Ok(jobs)
+    }
+
+    /// 删除定时任务，返回是否存在
+    pub fn remove(&self, id: &str) -> Result<bool> {
+        let _guard = self.lock.lock().unwrap();
+        let mut jobs = self.load()?;
+        let before = jobs.len();
+        jobs.retain(|job| job.id != id);
+        if jobs.len() == before {
+            return Ok(false);
+        }
+        self.save(&jobs)?;
+        info!("⏰ Schedule removed: {}", id);
+        Ok(true)
+    }
+
+    /// 取出截至 `now` 到期的运行（含停机期间错过的，按各自策略处理），并推进 next_run_at
+    pub fn due_runs(&self, now: DateTime<Utc>) -> Result<Vec<DueRun>> {
+        let _guard = self.lock.lock().unwrap();
+        let mut jobs = self.load()?;
+        let mut due = Vec::new();
+        let mut changed = false;
+
+        for job in jobs.iter_mut() {
+            let Some(next_run_at) = job.next_run_at.filter(|next| *next <= now) else {
+                continue;
+            };
+            let cron = match CronSchedule::parse(&job.cron) {
+                Ok(cron) => cron,
+                Err(e) => {
+                    warn!("⚠️  Schedule {} has an invalid cron expression: {}", job.id, e);
+                    continue;
+                }
+            };
+
+            // 截至 now 的全部计划时间（只保留最近的 MAX_CATCH_UP_RUNS 个）
+            let mut fired = vec![next_run_at];
+            while let Some(next) = fired.last().and_then(|last| cron.next_after(*last)).filter(|next| *next <= now) {
+                fired.push(next);
+                if fired.len() > MAX_CATCH_UP_RUNS {
+                    fired.remove(0);
+                }
+            }
+            let grace = ChronoDuration::seconds(MISSED_RUN_GRACE_SECS);
+            let (missed, on_time): (Vec<_>, Vec<_>) = fired.into_iter().partition(|at| now - *at > grace);
+            if !missed.is_empty() {
+                info!(
+                    "⏰ Schedule {} missed {} run(s) since {}; policy {}",
+                    job.id,
+                    missed.len(),
+                    missed[0],
+                    job.missed_runs.label()
+                );
+            }
+
+            let catch_up: Vec<DateTime<Utc>> = match job.missed_runs {
+                MissedRunPolicy::Skip => Vec::new(),
+                // 有准点运行时补跑已被它覆盖
+                MissedRunPolicy::RunOnce if on_time.is_empty() => missed.last().copied().into_iter().collect(),
+                MissedRunPolicy::RunOnce => Vec::new(),
+                MissedRunPolicy::RunAll => missed,
+            };
+            for (scheduled_for, catch_up) in catch_up
+                .into_iter()
+                .map(|at| (at, true))
+                .chain(on_time.into_iter().map(|at| (at, false)))
+            {
+                due.push(DueRun { job: job.clone(), scheduled_for, catch_up });
+            }
+
+            job.next_run_at = cron.next_after(now);
+            changed = true;
+        }
+
+        if changed {
+            self.save(&jobs)?;
+        }
+        Ok(due)
+    }
+
+    /// 记录一次运行的结果（任务已被删除时忽略）
+    pub fn record_run(&self, id: &str, error: Option<String>) -> Result<()> {
+        let _guard = self.lock.lock().unwrap();
+        let mut jobs = self.load()?;
+        let Some(job) = jobs.iter_mut().find(|job| job.id == id) else {
+            return Ok(());
+        };
+        job.last_run_at = Some(Utc::now());
+        job.last_error = error;
+        job.run_count += 1;
+        self.save(&jobs)
+    }
+
+    /// 后台调度循环：到期即在独立任务中调用 runner，关停开始后退出
+    pub fn spawn<F, Fut>(self: Arc<Self>, runner: F) -> JoinHandle<()>
+    where
+        F: Fn(DueRun) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let runner = Arc::new(runner);
+        tokio::spawn(async move {
+            let mut stop = self.shutdown.as_ref().map(|coordinator| coordinator.subscribe());
+            loop {
+                match self.due_runs(Utc::now()) {
+                    Ok(runs) => {
+                        for run in runs {
+                            self.dispatch(run, runner.clone());
+                        }
+                    }
+                    Err(e) => warn!("⚠️  Failed to read schedules: {}", e),
+                }
+
+                let now = Utc::now();
+                let next = self
+                    .list()
+                    .ok()
+                    .and_then(|jobs| jobs.iter().filter_map(|job| job.next_run_at).min())
+                    .unwrap_or(now + ChronoDuration::seconds(MAX_TICK_SECS));
+                let wait = (next - now).num_milliseconds().clamp(200, MAX_TICK_SECS * 1000) as u64;
+                let sleep = tokio::time::sleep(std::time::Duration::from_millis(wait));
+                match stop.as_mut() {
+                    Some(stop) => tokio::select! {
+                        _ = sleep => {}
+                        _ = stop.wait_for(|stopping| *stopping) => {
+                            info!("⏰ Scheduler stopped");
+                            return;
+                        }
+                    },
+                    None => sleep.await,
+                }
+            }
+        })
+    }
+
+    fn dispatch<F, Fut>(self: &Arc<Self>, run: DueRun, runner: Arc<F>)
+    where
+        F: Fn(DueRun) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let id = run.job.id.clone();
+        if let Some(kill_switch) = &self.kill_switch {
+            if let Err(e) = kill_switch.check(PausedOperation::Execution) {
+                warn!("⏸️  Schedule {} skipped: {}", id, e);
+                if let Err(e) = self.record_run(&id, Some(format!("skipped: {}", e))) {
+                    warn!("⚠️  Failed to record schedule run {}: {}", id, e);
+                }
+                return;
+            }
+        }
+        let work = match self.shutdown.as_ref().map(|coordinator| coordinator.begin(format!("schedule:{}", id))).transpose() {
+            Ok(work) => work,
+            Err(e) => {
+                warn!("⚠️  Schedule {} not started: {}", id, e);
+                return;
+            }
+        };
+
+        info!(
+            "⏰ Running schedule {} ({}) for {}{}",
+            run.job.name,
+            id,
+            run.scheduled_for.format("%Y-%m-%d %H:%M"),
+            if run.catch_up { " [catch-up]" } else { "" }
+        );
+        let scheduler = self.clone();
+        tokio::spawn(async move {
+            let _work = work;
+            let error = runner(run).await.err().map(|e| e.to_string());
+            if let Some(error) = &error {
+                warn!("⚠️  Schedule {} failed: {}", id, error);
+            }
+            if let Err(e) = scheduler.record_run(&id, error) {
+                warn!("⚠️  Failed to record schedule run {}: {}", id, e);
+            }
+        });
+    }
+
+    fn load(&self) -> Result<Vec<ScheduledJob>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let content = std::fs::read_to_string(&self.path)?;
+        serde_json::from_str(&content).with_context(|| format!("Corrupted schedule file {}", self.path.display()))
+    }
+
+    fn save(&self, jobs: &[ScheduledJob]) -> Result<()> {
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(jobs)?)?;
+        std::fs::rename(&tmp, &self.path).with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::concurrency::ConcurrencyConfig;
+
+    fn step(id: &str, dependencies: &[&str]) -> WorkflowStep {
+        WorkflowStep {
+            id: id.to_string(),
+            name: id.to_string(),
+            agent: "MOSS".to_string(),
+            dependencies: dependencies.iter().map(|d| d.to_string()).collect(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_workflow_respects_dependencies() {
+        let mut engine = WorkflowEngine::new(ConcurrencyManager::new(ConcurrencyConfig::default()));
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        let workflow = Workflow {
+            id: "wf".to_string(),
+            name: "test".to_string(),
+            description: String::new(),
+            steps: vec![step("deploy", &["build", "test"]), step("test", &["build"]), step("build", &[])],
+        };
+
+        let recorder = order.clone();
+        let results = engine
+            .execute_workflow_with(workflow, move |step| {
+                let recorder = recorder.clone();
+                async move {
+                    recorder.lock().unwrap().push(step.id);
+                    Ok(())
+                }
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(*order.lock().unwrap(), vec!["build", "test", "deploy"]);
+    }
+
+    #[tokio::test]
+    async fn test_failed_step_cancels_siblings() {
+        let mut engine = WorkflowEngine::new(ConcurrencyManager::new(ConcurrencyConfig {
+            worker_threads: 2,
+            ..Default::default()
+        }));
+
+        let workflow = Workflow {
+            id: "wf".to_string(),
+            name: "test".to_string(),
+            description: String::new(),
+            steps: vec![step("slow", &[]), step("fail", &[])],
+        };
+
+        let started = std::time::Instant::now();
+        let err = engine
+            .execute_workflow_with(workflow, |step| async move {
+                if step.id == "fail" {
+                    return Err(anyhow!("boom"));
+                }
+                tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+                Ok(())
+            })
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().contains("boom"));
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+    }
+    #[tokio::test]
+    async fn test_shutdown_drains_step_and_stops_next_wave() {
+        use crate::core::shutdown::{ShutdownConfig, ShutdownCoordinator};
+
+        let coordinator = ShutdownCoordinator::new(ShutdownConfig::default());
+        let mut engine = WorkflowEngine::new(ConcurrencyManager::new(ConcurrencyConfig::default()))
+            .with_shutdown(coordinator.clone());
+
+        let workflow = Workflow {
+            id: "wf".to_string(),
+            name: "test".to_string(),
+            description: String::new(),
+            steps: vec![step("build", &[]), step("deploy", &["build"])],
+        };
+
+        let report = Arc::new(Mutex::new(None));
+        let (trigger, slot) = (coordinator.clone(), report.clone());
+        let err = engine
+            .execute_workflow_with(workflow, move |_step| {
+                let (trigger, slot) = (trigger.clone(), slot.clone());
+                async move {
+                    // SIGTERM 在步骤执行期间到达
+                    tokio::spawn(async move {
+                        *slot.lock().unwrap() = Some(trigger.shutdown().await);
+                    });
+                    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                    Ok(())
+                }
+            })
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().contains("deploy"));
+        while report.lock().unwrap().is_none() {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert!(report.lock().unwrap().as_ref().unwrap().drained);
+    }
+
+    #[test]
+    fn test_cron_next_after() {
+        let at = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+        let next = |expr: &str, after: &str| CronSchedule::parse(expr).unwrap().next_after(at(after)).unwrap();
+
+        assert_eq!(next("*/15 * * * *", "2026-03-01T10:07:30Z"), at("2026-03-01T10:15:00Z"));
+        assert_eq!(next("0 9 * * mon-fri", "2026-03-06T09:00:00Z"), at("2026-03-09T09:00:00Z"));
+        assert_eq!(next("@monthly", "2026-12-15T00:00:00Z"), at("2027-01-01T00:00:00Z"));
+        assert_eq!(next("0 0 29 feb *", "2026-01-01T00:00:00Z"), at("2028-02-29T00:00:00Z"));
+        // 日与周都受限时满足其一即可：每月 13 日或每个周五
+        assert_eq!(next("0 0 13 * 5", "2026-03-01T00:00:00Z"), at("2026-03-06T00:00:00Z"));
+        assert_eq!(next("0 0 * * 7", "2026-03-01T00:00:00Z"), at("2026-03-08T00:00:00Z"));
+
+        assert!(CronSchedule::parse("0 0 * *").is_err());
+        assert!(CronSchedule::parse("60 * * * *").is_err());
+        assert!(CronSchedule::parse("*/0 * * * *").is_err());
+        assert!(CronSchedule::parse("0 0 31 2 *").unwrap().next_after(Utc::now()).is_none());
+    }
+
+    #[test]
+    fn test_scheduler_missed_runs_after_downtime() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("schedules.json");
+        let scheduler = Scheduler::open(&path).unwrap();
+        let target = ScheduleTarget::Execution { input: "daily report".to_string() };
+
+        let skip = scheduler.add("skip", "0 * * * *", target.clone(), MissedRunPolicy::Skip).unwrap();
+        let once = scheduler.add("once", "0 * * * *", target.clone(), MissedRunPolicy::RunOnce).unwrap();
+        let all = scheduler.add("all", "0 * * * *", target, MissedRunPolicy::RunAll).unwrap();
+        assert!(scheduler.add("bad", "every hour", ScheduleTarget::Execution { input: "x".to_string() }, MissedRunPolicy::Skip).is_err());
+
+        // 停机 5 个多小时后重启：错过 6 个整点，且都已超过宽限期
+        let first = once.next_run_at.unwrap();
+        let restarted = Scheduler::open(&path).unwrap();
+        let now = first + ChronoDuration::hours(5) + ChronoDuration::minutes(30);
+        let due = restarted.due_runs(now).unwrap();
+        let count = |id: &str| due.iter().filter(|run| run.job.id == id).count();
+        assert_eq!(count(&skip.id), 0);
+        assert_eq!(count(&once.id), 1);
+        assert_eq!(count(&all.id), 6);
+        assert!(due.iter().all(|run| run.catch_up));
+
+        // next_run_at 已推进并持久化，同一时刻不会重复触发
+        assert!(restarted.due_runs(now).unwrap().is_empty());
+        let jobs = Scheduler::open(&path).unwrap().list().unwrap();
+        assert!(jobs.iter().all(|job| job.next_run_at == Some(first + ChronoDuration::hours(6))));
+
+        restarted.record_run(&once.id, Some("provider down".to_string())).unwrap();
+        assert!(restarted.remove(&skip.id).unwrap());
+        let jobs = restarted.list().unwrap();
+        assert_eq!(jobs.len(), 2);
+        let recorded = jobs.iter().find(|job| job.id == once.id).unwrap();
+        assert_eq!((recorded.run_count, recorded.last_error.as_deref()), (1, Some("provider down")));
+    }
 }
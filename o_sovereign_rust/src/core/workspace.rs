@@ -0,0 +1,482 @@
+// Workspace - 多租户工作区隔离
+// 一台服务器承载多个团队：各自的 API 密钥、预算、记忆和规则互不可见
+//
+// 核心功能：
+// 1. 每个工作区独立的 ApiManager（密钥与调用历史落盘到各自目录，ACSA_SECRET_STORE=os 时密钥存钥匙串）、RAG 索引、任务追踪器、
+//    Agent 状态（会话 / 长期记忆）和个人规则
+// 2. 按认证身份的 Claims 选择工作区（`workspace_id`，为空时进入默认工作区）
+// 3. 成员校验：非成员返回 E9008，admin 角色可进入任意工作区
+// 4. 预算：按工作区调用历史累计花费，超出时返回 E9009
+// 5. 审计日志共享一个 AuditLogger，事件带 `workspace_id` 元数据，工作区内只能查到自己的事件
+// 6. 工作区即租户（TenantId）：贯穿认证后的限流（WorkspaceConfig.rate_limit）、API 密钥用量归属与预算告警
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+use super::agent_state::{AgentStateConfig, AgentStateManager};
+use super::api_manager::ApiManager;
+use super::audit_log::{AuditEvent, AuditEventType, AuditLogger, AuditQuery, AuditSeverity, WORKSPACE_METADATA_KEY};
+use super::auth_system::{Claims, Role};
+use super::error::{AcsaError, ErrorCode};
+use super::personal_rules::PersonalRulesManager;
+use super::rag_engine::{RagConfig, RagEngine};
+use super::rate_limiter::RateLimitRule;
+use super::secret_store::configured_secret_store;
+use super::task_tracker::TaskTracker;
+
+/// 默认工作区ID（Claims 未指定工作区时使用）
+pub const DEFAULT_WORKSPACE: &str = "default";
+
+/// 租户ID（即工作区ID）
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TenantId(String);
+
+impl TenantId {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self(id.into())
+    }
+
+    /// 认证身份所属租户（Claims 未指定工作区时为默认租户）
+    pub fn from_claims(claims: &Claims) -> Self {
+        claims.workspace_id.as_deref().map(Self::new).unwrap_or_default()
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.0 == DEFAULT_WORKSPACE
+    }
+}
+
+impl Default for TenantId {
+    fn default() -> Self {
+        Self::new(DEFAULT_WORKSPACE)
+    }
+}
+
+impl fmt::Display for TenantId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// 工作区配置（持久化在 `{root}/workspaces.json`）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceConfig {
+    pub id: String,
+    pub name: String,
+    /// 成员 user_id；为空表示所有已认证用户都可进入
+    #[serde(default)]
+    pub members: Vec<String>,
+    /// 预算上限（美元），None 表示不限
+    #[serde(default)]
+    pub budget_usd: Option<f64>,
+    /// 独立的 RAG 配置（如使用不同的向量库）
+    #[serde(default)]
+    pub rag: Option<RagConfig>,
+    /// 租户级限流规则（为空时使用 RateLimiterConfig::tenant_rule）
+    #[serde(default)]
+    pub rate_limit: Option<RateLimitRule>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl WorkspaceConfig {
+    pub fn new(id: impl Into<String>, name: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            name: name.into(),
+            members: Vec::new(),
+            budget_usd: None,
+            rag: None,
+            rate_limit: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    pub fn with_members(mut self, members: Vec<String>) -> Self {
+        self.members = members;
+        self
+    }
+
+    pub fn with_budget(mut self, budget_usd: f64) -> Self {
+        self.budget_usd = Some(budget_usd);
+        self
+    }
+
+    pub fn with_rate_limit(mut self, rule: RateLimitRule) -> Self {
+        self.rate_limit = Some(rule);
+        self
+    }
+
+    /// 成员或 admin 角色可访问
+    pub fn allows(&self, claims: &Claims) -> bool {
+        self.members.is_empty()
+            || self.members.contains(&claims.user_id)
+            || claims.has_role(Role::Admin)
+    }
+}
+
+/// 工作区概要（HTTP / CLI 展示）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceSummary {
+    pub id: String,
+    pub name: String,
+    pub members: usize,
+    pub budget_usd: Option<f64>,
+    pub spent_usd: f64,
+    /// 已配置密钥的 Provider
+    pub providers: Vec<String>,
+    pub documents: u64,
+}
+
+/// 单个工作区及其隔离的资源
+pub struct Workspace {
+    config: WorkspaceConfig,
+    data_dir: PathBuf,
+    /// API 密钥与调用历史
+    pub api: Arc<RwLock<ApiManager>>,
+    /// RAG 索引
+    pub rag: Arc<RagEngine>,
+    /// 任务追踪器
+    pub tasks: Arc<RwLock<TaskTracker>>,
+    /// Agent 会话与长期记忆
+    pub agent_state: Arc<AgentStateManager>,
+    /// 团队规则
+    pub rules: Arc<RwLock<PersonalRulesManager>>,
+    audit: Arc<AuditLogger>,
+}
+
+impl Workspace {
+    async fn open(config: WorkspaceConfig, root: &Path, audit: Arc<AuditLogger>) -> Result<Self> {
+        let data_dir = root.join(&config.id);
+        let mut api = ApiManager::new(data_dir.join("api")).with_tenant(TenantId::new(&config.id));
+        if let Some(store) = configured_secret_store()? {
+            api = api.with_secret_store(store, config.id.as_str());
+        }
+        api.init()
+            .await
+            .with_context(|| format!("Failed to load API keys for workspace {}", config.id))?;
+
+        Ok(Self {
+            rag: Arc::new(RagEngine::new(config.rag.clone().unwrap_or_default())),
+            api: Arc::new(RwLock::new(api)),
+            tasks: Arc::new(RwLock::new(TaskTracker::new())),
+            agent_state: Arc::new(AgentStateManager::new(AgentStateConfig::default(), None)),
+            rules: Arc::new(RwLock::new(PersonalRulesManager::with_defaults())),
+            config,
+            data_dir,
+            audit,
+        })
+    }
+
+    pub fn id(&self) -> &str {
+        &self.config.id
+    }
+
+    pub fn tenant(&self) -> TenantId {
+        TenantId::new(&self.config.id)
+    }
+
+    pub fn config(&self) -> &WorkspaceConfig {
+        &self.config
+    }
+
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
+
+    /// 工作区累计花费（来自本工作区的 API 调用历史）
+    pub async fn spent_usd(&self) -> f64 {
+        self.api.read().await.get_total_cost()
+    }
+
+    /// 执行前检查预算
+    pub async fn check_budget(&self) -> Result<()> {
+        let Some(budget) = self.config.budget_usd else {
+            return Ok(());
+        };
+        let spent = self.spent_usd().await;
+        if spent >= budget {
+            return Err(AcsaError::new(
+                ErrorCode::BudgetExceeded,
+                format!("Workspace {} has spent ${:.4} of its ${:.2} budget", self.config.id, spent, budget),
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    /// 写入审计日志（自动标记工作区）
+    pub async fn audit(&self, mut event: AuditEvent) -> Result<()> {
+        event
+            .metadata
+            .insert(WORKSPACE_METADATA_KEY.to_string(), self.config.id.clone());
+        self.audit.log_event(event).await
+    }
+
+    /// 查询本工作区的审计日志
+    pub async fn audit_events(&self, mut query: AuditQuery) -> Vec<AuditEvent> {
+        query.workspace_id = Some(self.config.id.clone());
+        self.audit.query(query).await
+    }
+
+    pub async fn summary(&self) -> WorkspaceSummary {
+        let api = self.api.read().await;
+        WorkspaceSummary {
+            id: self.config.id.clone(),
+            name: self.config.name.clone(),
+            members: self.config.members.len(),
+            budget_usd: self.config.budget_usd,
+            spent_usd: api.get_total_cost(),
+            providers: api.get_all_api_keys().iter().map(|k| k.provider.name().to_string()).collect(),
+            documents: self.rag.get_stats().await.total_documents,
+        }
+    }
+}
+
+/// 工作区管理器
+pub struct WorkspaceManager {
+    root: PathBuf,
+    audit: Arc<AuditLogger>,
+    workspaces: RwLock<HashMap<String, Arc<Workspace>>>,
+}
+
+impl WorkspaceManager {
+    /// 加载注册表并打开所有工作区；默认工作区不存在时自动创建
+    pub async fn open(root: impl Into<PathBuf>, audit: Arc<AuditLogger>) -> Result<Self> {
+        let root = root.into();
+        let registry = root.join("workspaces.json");
+        let configs: Vec<WorkspaceConfig> = if registry.exists() {
+            serde_json::from_str(&tokio::fs::read_to_string(&registry).await?)
+                .with_context(|| format!("Corrupted workspace registry: {}", registry.display()))?
+        } else {
+            Vec::new()
+        };
+
+        let mut workspaces = HashMap::new();
+        for config in configs {
+            let workspace = Workspace::open(config, &root, audit.clone()).await?;
+            workspaces.insert(workspace.id().to_string(), Arc::new(workspace));
+        }
+        let manager = Self {
+            root,
+            audit,
+            workspaces: RwLock::new(workspaces),
+        };
+
+        if manager.get(DEFAULT_WORKSPACE).await.is_none() {
+            manager
+                .create(WorkspaceConfig::new(DEFAULT_WORKSPACE, "Default"))
+                .await?;
+        }
+        info!("🏢 Workspaces loaded: {}", manager.workspaces.read().await.len());
+        Ok(manager)
+    }
+
+    /// 创建工作区
+    pub async fn create(&self, config: WorkspaceConfig) -> Result<Arc<Workspace>> {
+        if config.id.is_empty()
+            || !config.id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(anyhow!("Invalid workspace id: {:?}", config.id));
+        }
+
+        let mut workspaces = self.workspaces.write().await;
+        if workspaces.contains_key(&config.id) {
+            return Err(anyhow!("Workspace already exists: {}", config.id));
+        }
+        let workspace = Arc::new(Workspace::open(config, &self.root, self.audit.clone()).await?);
+        workspaces.insert(workspace.id().to_string(), workspace.clone());
+        self.save(&workspaces).await?;
+        drop(workspaces);
+
+        info!("🏢 Workspace created: {}", workspace.id());
+        workspace
+            .audit(admin_event(
+                "workspace_created",
+                workspace.id(),
+                AuditEventType::ConfigChange,
+                AuditSeverity::Info,
+                "system",
+            ))
+            .await?;
+        Ok(workspace)
+    }
+
+    pub async fn get(&self, id: &str) -> Option<Arc<Workspace>> {
+        self.workspaces.read().await.get(id).cloned()
+    }
+
+    pub async fn list(&self) -> Vec<WorkspaceConfig> {
+        let mut configs: Vec<WorkspaceConfig> = self
+            .workspaces
+            .read()
+            .await
+            .values()
+            .map(|w| w.config().clone())
+            .collect();
+        configs.sort_by(|a, b| a.id.cmp(&b.id));
+        configs
+    }
+
+    /// 按认证身份选择工作区；不存在或无权访问时返回 E9008（不区分两者，避免探测）
+    pub async fn resolve(&self, claims: &Claims) -> Result<Arc<Workspace>> {
+        let tenant = TenantId::from_claims(claims);
+        let id = tenant.as_str();
+        match self.get(id).await {
+            Some(workspace) if workspace.config().allows(claims) => Ok(workspace),
+            existing => {
+                warn!("🚫 {} denied access to workspace {}", claims.user_id, id);
+                let mut event = admin_event(
+                    "workspace_access_denied",
+                    id,
+                    AuditEventType::SecurityEvent,
+                    AuditSeverity::Warning,
+                    &claims.user_id,
+                );
+                event.success = false;
+                match existing {
+                    Some(workspace) => workspace.audit(event).await?,
+                    None => self.audit.log_event(event).await?,
+                }
+                Err(AcsaError::new(
+                    ErrorCode::WorkspaceAccessDenied,
+                    format!("User {} cannot access workspace {}", claims.user_id, id),
+                )
+                .into())
+            }
+        }
+    }
+
+    async fn save(&self, workspaces: &HashMap<String, Arc<Workspace>>) -> Result<()> {
+        let mut configs: Vec<&WorkspaceConfig> = workspaces.values().map(|w| w.config()).collect();
+        configs.sort_by(|a, b| a.id.cmp(&b.id));
+
+        tokio::fs::create_dir_all(&self.root).await?;
+        let path = self.root.join("workspaces.json");
+        let tmp = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, serde_json::to_string_pretty(&configs)?).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+        Ok(())
+    }
+}
+
+fn admin_event(
+    action: &str,
+    workspace_id: &str,
+    event_type: AuditEventType,
+    severity: AuditSeverity,
+    actor: &str,
+) -> AuditEvent {
+    let now = Utc::now();
+    AuditEvent {
+        event_id: format!("{}_{}", action, now.timestamp_millis()),
+        event_type,
+        severity,
+        actor_id: actor.to_string(),
+        actor_ip: None,
+        resource_id: Some(workspace_id.to_string()),
+        resource_type: Some("workspace".to_string()),
+        action: action.to_string(),
+        success: true,
+        error_message: None,
+        metadata: HashMap::new(),
+        timestamp: now,
+        signature: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::api_manager::{ApiCallRecord, ApiKeyConfig, ApiProvider};
+    use crate::core::audit_log::AuditLogConfig;
+    use crate::core::task_tracker::Task;
+    use tempfile::TempDir;
+
+    fn claims(user_id: &str, roles: &[&str], workspace: Option<&str>) -> Claims {
+        Claims {
+            sub: user_id.to_string(),
+            exp: u64::MAX,
+            iat: 0,
+            user_id: user_id.to_string(),
+            username: user_id.to_string(),
+            roles: roles.iter().map(|r| r.to_string()).collect(),
+            workspace_id: workspace.map(str::to_string),
+            api_key_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resolve_by_claims_and_membership() {
+        let dir = TempDir::new().unwrap();
+        let audit = Arc::new(AuditLogger::new(AuditLogConfig::default(), None));
+        let manager = WorkspaceManager::open(dir.path(), audit).await.unwrap();
+        manager
+            .create(WorkspaceConfig::new("team-a", "Team A").with_members(vec!["alice".to_string()]))
+            .await
+            .unwrap();
+
+        assert_eq!(manager.resolve(&claims("bob", &[], None)).await.unwrap().id(), DEFAULT_WORKSPACE);
+        assert_eq!(manager.resolve(&claims("alice", &[], Some("team-a"))).await.unwrap().id(), "team-a");
+        assert!(manager.resolve(&claims("root", &["admin"], Some("team-a"))).await.is_ok());
+
+        for denied in [claims("bob", &[], Some("team-a")), claims("bob", &[], Some("missing"))] {
+            let err = manager.resolve(&denied).await.map(|_| ()).unwrap_err();
+            assert_eq!(err.downcast_ref::<AcsaError>().unwrap().code(), Some(ErrorCode::WorkspaceAccessDenied));
+        }
+
+        // 拒绝访问写入该工作区的审计日志；注册表重新打开后仍在
+        let team_a = manager.get("team-a").await.unwrap();
+        let events = team_a.audit_events(AuditQuery::default()).await;
+        assert!(events.iter().any(|e| e.action == "workspace_access_denied" && e.actor_id == "bob"));
+        let reopened = WorkspaceManager::open(dir.path(), Arc::new(AuditLogger::new(AuditLogConfig::default(), None)))
+            .await
+            .unwrap();
+        let ids: Vec<_> = reopened.list().await.into_iter().map(|w| w.id).collect();
+        assert_eq!(ids, vec!["default", "team-a"]);
+    }
+
+    #[tokio::test]
+    async fn test_resources_and_budget_are_isolated() {
+        let dir = TempDir::new().unwrap();
+        let audit = Arc::new(AuditLogger::new(AuditLogConfig::default(), None));
+        let manager = WorkspaceManager::open(dir.path(), audit).await.unwrap();
+        let a = manager.create(WorkspaceConfig::new("a", "A").with_budget(0.05)).await.unwrap();
+        let b = manager.create(WorkspaceConfig::new("b", "B")).await.unwrap();
+
+        a.api
+            .write()
+            .await
+            .add_api_key(ApiKeyConfig::new(ApiProvider::OpenAI, "sk-team-a-secret".to_string()))
+            .await
+            .unwrap();
+        a.tasks.write().await.add_task(Task::new("t1".to_string(), "Team A task".to_string()));
+
+        assert!(b.api.read().await.get_api_key(ApiProvider::OpenAI).is_none());
+        assert!(b.tasks.read().await.get_all_tasks().is_empty());
+        assert_eq!(a.summary().await.providers, vec!["OpenAI"]);
+        assert!(b.audit_events(AuditQuery::default()).await.iter().all(|e| e.resource_id.as_deref() == Some("b")));
+
+        a.check_budget().await.unwrap();
+        a.api
+            .write()
+            .await
+            .record_call(ApiCallRecord::new_success(ApiProvider::OpenAI, 1000, 0.06, 100, None))
+            .await
+            .unwrap();
+        let err = a.check_budget().await.unwrap_err();
+        assert_eq!(err.downcast_ref::<AcsaError>().unwrap().code(), Some(ErrorCode::BudgetExceeded));
+        assert!(b.check_budget().await.is_ok());
+    }
+}
@@ -2,7 +2,11 @@
 
 pub mod core;
 
+/// 终端仪表盘（Ratatui，需启用 `ui` 特性）
+#[cfg(feature = "ui")]
+pub mod tui;
+
 pub use core::{
-    create_provider, ACSAConfig, ACSAExecutionLog, ACSARouter, AgentResponse,
+    create_provider, ACSAConfig, ACSAExecutionLog, ACSARouter, AgentChunk, AgentResponse,
     AgentRole, AgentStats, AuditResult, ModelProvider,
 };
@@ -1,25 +1,452 @@
 // O-Sovereign CLI
 // Command-line interface for ACSA system
 
-use clap::{Parser, Subcommand};
-use o_sovereign::{create_provider, ACSAConfig, ACSARouter, AgentRole};
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock};
+
+use clap::{Args, Parser, Subcommand};
+use o_sovereign::core::{
+    determinism, kill_switch, logging, mock_scenario, offline, AgentApiConfig, ApiManager, AgentStateConfig, AgentStateManager, ChatCommand, Conversation, BatchRunner, BatchTask, DEFAULT_BATCH_CONCURRENCY, AgentExtensionManager, ConfigManager, CostEstimator, CustomAgent, ConfigManagerConfig, ConfigValue, AcsaError, ErrorCode, EmergencyLogConfig, EmergencyLogger, Environment, ErrorPresenter, ExportFormat,
+    CheckpointStore, ExecutionQuery, ExecutionStore, ExecutionTranscript, FixtureSet, DEFAULT_CHECKPOINT_DIR,
+    CacheManager, ResponseCache, ResponseCachePolicy,
+    MissedRunPolicy, ScheduleTarget, Scheduler, Workflow, DEFAULT_SCHEDULE_PATH,
+    DeadLetterQueue, DEFAULT_DEAD_LETTER_PATH,
+    ApprovalStatus, ApprovalStore, CheckpointStage, DEFAULT_APPROVAL_PATH,
+    create_custom_agent_provider, PipelineConfig,
+    InstallOptions, PluginInstaller, PluginSystemConfig, DEFAULT_PLUGINS_DIR,
+    create_acsa_mcp_server_with_state, register_acsa_execute_tool, AcsaMcpState, McpClientRegistry, McpServersFile, DEFAULT_MCP_SERVERS_PATH,
+    AuthConfig, AuthManager, HttpServer, HttpServerConfig, Role, ServerState, ShutdownConfig, ShutdownCoordinator,
+    JarvisCircuitBreaker, JarvisManager, RulePackConfig, KillSwitch, KillSwitchConfig, LocalModelConfig, LogEntryType, LogFormat, Notification, NotificationKind, NotificationPriority, Notifier, NotifierConfig, NotifyOutcome, OfflineConfig, Protocol, ProtocolConfig, ProtocolManager, DEFAULT_PROTOCOL_DIR, ReleaseChannel, AuditLogConfig, AuditLogger, WorkspaceConfig, WorkspaceManager, ScenarioPlayer, SelfUpdater, SessionStore, SovereigntyConfig, StartupAction, TelemetryConfig, Tracer, UpdateConfig, UpdateOutcome, DEFAULT_SOVEREIGNTY_STATE_PATH, GLOBAL_OPTIMIZER, SOVEREIGNTY,
+    generate_bio_activity_report, generate_usage_report, ChunkingStrategy, EmbeddingModel, IngestConfig, IngestReport, RagConfig, RagEngine, VectorStoreConfig,
+};
+use o_sovereign::{create_provider, ACSAConfig, ACSAExecutionLog, ACSARouter, AgentRole};
+
+/// 自定义 Agent 注册表
+const DEFAULT_AGENT_REGISTRY: &str = "./config/agents.json";
+
+/// API 密钥、调用历史与 Provider 预算（云端模式下 Router 调用前检查预算硬上限）
+const DEFAULT_API_DIR: &str = "./data/api";
+
+/// 响应缓存根目录（`--cache`）
+const DEFAULT_CACHE_DIR: &str = "./data/cache";
+
+/// 启动时校验通过的 Jarvis 规则包配置（`--rule-pack` / `ACSA_JARVIS_RULE_PACKS`）
+static RULE_PACKS: OnceLock<RulePackConfig> = OnceLock::new();
+
+/// 部署环境（`--env` / `ACSA_ENV`），决定配置分层与危险配置守卫
+static ENVIRONMENT: OnceLock<Environment> = OnceLock::new();
+
+/// 进程唯一的紧急日志（已安装 panic hook），各组件共用同一会话文件
+static EMERGENCY: OnceLock<Arc<EmergencyLogger>> = OnceLock::new();
 
 #[derive(Parser)]
 #[command(name = "o-sovereign")]
 #[command(about = "ACSA (对抗约束型盲从代理) CLI", long_about = None)]
 struct Cli {
+    /// Print a waterfall of startup/init phase timings on exit
+    #[arg(long, global = true)]
+    profile_startup: bool,
+
+    /// Force every capability onto local backends (also: ACSA_OFFLINE=1 or offline.enabled)
+    #[arg(long, global = true)]
+    offline: bool,
+
+    /// Script mock providers with a YAML scenario; implies --mock (also: ACSA_MOCK_SCENARIO)
+    #[arg(long, global = true, value_name = "FILE")]
+    scenario: Option<PathBuf>,
+
+    /// Fix every stochastic choice for a reproducible run (also: ACSA_SEED)
+    #[arg(long, global = true, value_name = "N")]
+    seed: Option<u64>,
+
+    /// Log output: text (human-readable) or json (one event per line, for Loki/ELK; also: ACSA_LOG_FORMAT)
+    #[arg(long, global = true, value_name = "FORMAT")]
+    log_format: Option<LogFormat>,
+
+    /// Load a signed Jarvis rule pack (repeatable; `<pack>.sig` must sit next to it; also: ACSA_JARVIS_RULE_PACKS)
+    #[arg(long, global = true, value_name = "FILE")]
+    rule_pack: Vec<PathBuf>,
+
+    /// Trust an Ed25519 public key (base64) for rule pack signatures (repeatable; also: ACSA_JARVIS_PACK_KEYS)
+    #[arg(long, global = true, value_name = "KEY")]
+    rule_pack_key: Vec<String>,
+
+    /// Deployment environment: dev, staging or prod; prod blocks Ghost unless explicitly allowed (also: ACSA_ENV)
+    #[arg(long = "env", global = true, value_name = "ENV")]
+    environment: Option<Environment>,
+
     #[command(subcommand)]
     command: Commands,
 }
 
+#[derive(Args)]
+struct ExecuteArgs {
+    /// Input text
+    #[arg(short, long)]
+    input: String,
+
+    /// Use mock mode (no API keys)
+    #[arg(short, long)]
+    mock: bool,
+
+    /// Risk threshold (0-100)
+    #[arg(short, long, default_value_t = 70)]
+    threshold: u8,
+
+    /// Append this run to an existing session (default: start a new one)
+    #[arg(long)]
+    session: Option<String>,
+
+    /// Tag the stored execution log (repeatable)
+    #[arg(long = "tag")]
+    tags: Vec<String>,
+
+    /// Print each agent's output token-by-token as it is generated
+    #[arg(long)]
+    stream: bool,
+
+    /// Write a report of the run; format follows the extension (.json, .md, .html)
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<PathBuf>,
+
+    /// Protocol (architect, reviewer_2, aegis, ..., or a custom one) or `auto` to detect it from the input
+    #[arg(short, long, default_value = "auto")]
+    protocol: String,
+
+    /// Run the cognitive cleaner on the input before MOSS (Ultron reviews both versions)
+    #[arg(long)]
+    clean: bool,
+
+    /// Pause for human approval (`approvals approve|reject`) instead of failing when the risk stays above the threshold
+    #[arg(long)]
+    require_approval: bool,
+
+    /// Agent pipeline, e.g. `moss,ultron,ultron:compliance,omega` (`+` = parallel, `?` = optional, `role:agent` = custom agent)
+    #[arg(long)]
+    pipeline: Option<String>,
+
+    /// Plan, verify and audit but skip Omega: print the would-be plan, estimated tokens/cost and risk (CI pre-flight)
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Reuse cached provider responses for identical requests (provider, model, prompt, temperature)
+    #[arg(long)]
+    cache: bool,
+
+    /// Response cache entry lifetime in seconds
+    #[arg(long, default_value_t = 86400, requires = "cache")]
+    cache_ttl: u64,
+
+    /// Also reuse responses for similar prompts (cosine similarity of prompt embeddings, 0-1); off for creative protocols
+    #[arg(long, requires = "cache")]
+    semantic_threshold: Option<f64>,
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Execute ACSA chain
-    Execute {
+    Execute(ExecuteArgs),
+
+    /// Interactive multi-turn session: later turns build on MOSS's earlier plans
+    Chat {
+        /// Use mock mode (no API keys)
+        #[arg(short, long)]
+        mock: bool,
+
+        /// Risk threshold (0-100)
+        #[arg(short, long, default_value_t = 70)]
+        threshold: u8,
+
+        /// Continue a recorded session (see `session list`)
+        #[arg(long)]
+        session: Option<String>,
+
+        /// Protocol for a new session (architect, reviewer_2, aegis, ...)
+        #[arg(short, long)]
+        protocol: Option<String>,
+    },
+
+    /// Continue an execution interrupted mid-chain from its last checkpoint (no id: list them)
+    Resume {
+        /// Execution ID printed by `execute` or listed by `resume`
+        execution_id: Option<String>,
+
+        /// Checkpoint directory
+        #[arg(long, default_value = DEFAULT_CHECKPOINT_DIR)]
+        store: PathBuf,
+
+        /// Use mock mode (no API keys)
+        #[arg(short, long)]
+        mock: bool,
+
+        /// Risk threshold (0-100)
+        #[arg(short, long, default_value_t = 70)]
+        threshold: u8,
+    },
+
+    /// Run many inputs from a JSONL file with bounded concurrency and write per-task results
+    Batch {
+        /// Tasks, one per line: {"id": ..., "input": ..., "tags": [...]} or a plain JSON string
+        #[arg(short, long)]
+        file: PathBuf,
+
+        /// Results file (default: <file>.results.jsonl)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Maximum executions in flight
+        #[arg(short, long, default_value_t = DEFAULT_BATCH_CONCURRENCY)]
+        concurrency: usize,
+
+        /// Per-task timeout in seconds
+        #[arg(long)]
+        timeout: Option<u64>,
+
+        /// Use mock mode (no API keys)
+        #[arg(short, long)]
+        mock: bool,
+
+        /// Risk threshold (0-100)
+        #[arg(short, long, default_value_t = 70)]
+        threshold: u8,
+    },
+
+    /// Re-run a stored execution offline from its recorded provider responses (no API calls)
+    Replay {
+        /// Execution ID (see `history search`)
+        id: String,
+
+        /// Execution log store
+        #[arg(long, default_value = "./data/executions")]
+        store: PathBuf,
+
+        /// Take responses from a provider fixture file instead of the stored transcript
+        #[arg(long)]
+        fixtures: Option<PathBuf>,
+
+        /// Risk threshold (0-100)
+        #[arg(short, long, default_value_t = 70)]
+        threshold: u8,
+
+        /// Agent pipeline the execution ran with (default: MOSS → L6 → Ultron → Omega)
+        #[arg(long)]
+        pipeline: Option<String>,
+    },
+
+    /// Browse, search and tag stored execution logs
+    History {
+        /// Execution log store
+        #[arg(long, default_value = "./data/executions")]
+        store: PathBuf,
+
+        #[command(subcommand)]
+        action: HistoryAction,
+    },
+
+    /// List built-in and custom protocols with their agent weights and temperature
+    Protocols,
+
+    /// Build and maintain the RAG knowledge base
+    Rag {
+        /// SQLite vector store shared by every `rag` command
+        #[arg(long, default_value = "./data/rag.db")]
+        store: PathBuf,
+
+        /// Embedding cache directory (unchanged chunks are never re-embedded)
+        #[arg(long, default_value = "./data/rag_cache")]
+        cache_dir: PathBuf,
+
+        /// Use deterministic mock embeddings (no API keys)
+        #[arg(short, long)]
+        mock: bool,
+
+        #[command(subcommand)]
+        action: RagAction,
+    },
+
+    /// Dry-run: project per-agent tokens, cost and latency without calling any provider
+    Estimate {
         /// Input text
         #[arg(short, long)]
         input: String,
 
+        /// Protocol (architect, reviewer_2, aegis, ...; default: auto-detect)
+        #[arg(short, long)]
+        protocol: Option<String>,
+
+        /// Print JSON instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Export or import portable session archives
+    Session {
+        /// Local session store
+        #[arg(long, default_value = "./data/sessions")]
+        store: PathBuf,
+
+        #[command(subcommand)]
+        action: SessionAction,
+    },
+
+    /// Pause or resume new executions, takeovers and hardware commands (maintenance mode)
+    Maintenance {
+        /// Sentinel file watched by every node sharing this directory
+        #[arg(long, default_value = "./MAINTENANCE")]
+        sentinel: PathBuf,
+
+        #[command(subcommand)]
+        action: MaintenanceAction,
+    },
+
+    /// Manage isolated team workspaces served by the HTTP server
+    Workspace {
+        /// Workspace registry and per-workspace data
+        #[arg(long, default_value = "./data/workspaces")]
+        root: PathBuf,
+
+        #[command(subcommand)]
+        action: WorkspaceAction,
+    },
+
+    /// Manage cron schedules that the HTTP server runs (ACSA executions or workflows)
+    Schedule {
+        /// Schedule file shared with the server
+        #[arg(long, default_value = DEFAULT_SCHEDULE_PATH)]
+        file: PathBuf,
+
+        #[command(subcommand)]
+        action: ScheduleAction,
+    },
+
+    /// Review executions paused for human approval, then approve (run Omega) or reject (abort) them
+    Approvals {
+        /// Approval file shared with the server
+        #[arg(long, default_value = DEFAULT_APPROVAL_PATH)]
+        file: PathBuf,
+
+        /// Checkpoint directory
+        #[arg(long, default_value = DEFAULT_CHECKPOINT_DIR)]
+        store: PathBuf,
+
+        #[command(subcommand)]
+        action: ApprovalAction,
+    },
+
+    /// Inspect or discard events whose handlers failed after retries (redrive via the HTTP API)
+    DeadLetters {
+        /// Dead letter file shared with the server
+        #[arg(long, default_value = DEFAULT_DEAD_LETTER_PATH)]
+        file: PathBuf,
+
+        #[command(subcommand)]
+        action: DeadLetterAction,
+    },
+
+    /// Inspect emergency logs for post-mortem triage
+    Emergency {
+        /// Emergency log directory
+        #[arg(long, default_value = "./logs/emergency")]
+        log_dir: PathBuf,
+
+        #[command(subcommand)]
+        action: EmergencyAction,
+    },
+
+    /// Inspect environment configuration profiles
+    Config {
+        /// Configuration directory
+        #[arg(long, default_value = "./config")]
+        config_dir: PathBuf,
+
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+
+    /// Bio-activity and screen-time reports; toggle sovereignty mode and anti-addiction limits
+    Sovereignty {
+        /// Configuration directory (settings are written to the current environment's file)
+        #[arg(long, default_value = "./config")]
+        config_dir: PathBuf,
+
+        /// Persisted decision history and usage stats
+        #[arg(long, default_value = DEFAULT_SOVEREIGNTY_STATE_PATH)]
+        state: PathBuf,
+
+        #[command(subcommand)]
+        action: SovereigntyAction,
+    },
+
+    /// Manage custom agents scheduled alongside MOSS/L6/Ultron/Omega
+    Agents {
+        /// Custom agent registry file
+        #[arg(long, default_value = DEFAULT_AGENT_REGISTRY)]
+        registry: PathBuf,
+
+        #[command(subcommand)]
+        action: AgentAction,
+    },
+
+    /// Install and manage plugin packages (signature-verified, dependencies checked)
+    Plugin {
+        /// Plugin directory
+        #[arg(long, default_value = DEFAULT_PLUGINS_DIR)]
+        dir: PathBuf,
+
+        #[command(subcommand)]
+        action: PluginAction,
+    },
+
+    /// Model Context Protocol server for MCP clients such as Claude Desktop
+    Mcp {
+        #[command(subcommand)]
+        action: McpAction,
+    },
+
+    /// Send a test notification through the channels configured in the environment
+    Notify {
+        /// Event type (budget_alert/circuit_breaker/workflow_approval/anti_addiction)
+        #[arg(long, default_value = "circuit_breaker")]
+        kind: String,
+
+        /// Notification title
+        #[arg(long, default_value = "ACSA test notification")]
+        title: String,
+
+        /// Notification body
+        #[arg(long, default_value = "If you can read this, the channel is configured correctly.")]
+        body: String,
+    },
+
+    /// Update the binary from the release channel (signature-verified, keeps a rollback copy)
+    SelfUpdate {
+        /// Switch release channel (stable/beta) before checking
+        #[arg(long)]
+        channel: Option<String>,
+
+        /// Only check whether a newer version is available
+        #[arg(long)]
+        check: bool,
+
+        /// Restore the previously installed version
+        #[arg(long, conflicts_with_all = ["channel", "check"])]
+        rollback: bool,
+    },
+
+    /// Serve the REST API, OpenAI-compatible endpoints, live SSE and /dashboard (plus gRPC with the `grpc` feature)
+    Serve {
+        /// Listen address
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+
+        /// HTTP port
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+
+        /// Data directory shared with the CLI (executions, workspaces, schedules, approvals, API keys)
+        #[arg(long, default_value = "./data")]
+        data_dir: PathBuf,
+
         /// Use mock mode (no API keys)
         #[arg(short, long)]
         mock: bool,
@@ -29,56 +456,2200 @@ enum Commands {
         threshold: u8,
     },
 
+    /// Live terminal dashboard (requires the `ui` feature)
+    Tui {
+        /// Use mock mode (no API keys)
+        #[arg(short, long)]
+        mock: bool,
+    },
+
     /// Show version
     Version,
 }
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    tracing_subscriber::fmt::init();
-    dotenv::dotenv().ok();
+#[derive(Subcommand)]
+enum McpAction {
+    /// Serve MCP over stdio (newline-delimited JSON-RPC 2.0; logs go to stderr)
+    Serve {
+        /// Use mock mode for the `acsa_execute` tool (no API keys)
+        #[arg(short, long)]
+        mock: bool,
 
-    let cli = Cli::parse();
+        /// Risk threshold for `acsa_execute` (0-100)
+        #[arg(short, long, default_value_t = 70)]
+        threshold: u8,
+    },
+    /// Connect to the external MCP servers in ./config/mcp_servers.json and list their tools
+    Tools,
+}
 
-    match cli.command {
-        Commands::Execute { input, mock, threshold } => {
-            execute_cli(input, mock, threshold).await?;
-        }
-        Commands::Version => {
-            println!("O-Sovereign v0.1.0 (Rust Edition)");
-        }
-    }
+#[derive(Subcommand)]
+enum PluginAction {
+    /// Install a plugin package from a URL or local path
+    Install {
+        /// Package URL or path (checksum and signature default to <source>.sha256 / <source>.sig)
+        source: String,
 
-    Ok(())
+        /// Expected SHA-256 of the package
+        #[arg(long)]
+        sha256: Option<String>,
+
+        /// Base64 Ed25519 signature of the package
+        #[arg(long)]
+        signature: Option<String>,
+
+        /// Public key that signs plugin packages (default: $ACSA_PLUGIN_PUBLIC_KEY)
+        #[arg(long)]
+        public_key: Option<String>,
+
+        /// Install even if the package is not signed
+        #[arg(long)]
+        allow_unsigned: bool,
+
+        /// Reinstall over the same or a newer installed version
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// List installed plugins
+    List,
+
+    /// Enable an installed plugin
+    Enable { id: String },
+
+    /// Disable an installed plugin (kept on disk)
+    Disable { id: String },
+
+    /// Remove an installed plugin
+    Remove {
+        id: String,
+
+        /// Remove even if other plugins depend on it
+        #[arg(long)]
+        force: bool,
+    },
 }
 
-async fn execute_cli(input: String, use_mock: bool, risk_threshold: u8) -> anyhow::Result<()> {
-    println!("\n{}", "=".repeat(80));
-    println!("🚀 O-Sovereign ACSA CLI");
-    println!("{}", "=".repeat(80));
+#[derive(Subcommand)]
+enum AgentAction {
+    /// List core and custom agents
+    List,
 
-    let openai_key = if !use_mock { std::env::var("OPENAI_API_KEY").ok() } else { None };
+    /// Register (or update) a custom agent
+    Register {
+        /// Agent name
+        name: String,
 
-    let moss = create_provider(AgentRole::MOSS, openai_key, use_mock)?;
-    let l6 = create_provider(AgentRole::L6, None, use_mock)?;
-    let ultron = create_provider(AgentRole::Ultron, None, use_mock)?;
-    let omega = create_provider(AgentRole::Omega, None, use_mock)?;
+        /// Role description
+        #[arg(long)]
+        role: String,
 
-    let config = ACSAConfig {
-        max_iterations: 3,
-        risk_threshold,
-        enable_l6: true,
-        enable_streaming: false,
-    };
+        /// Provider binding (openai/claude/gemini/deepseek/siliconflow/openrouter/local)
+        #[arg(long)]
+        provider: String,
 
-    let router = ACSARouter::new(moss, l6, ultron, omega, config);
-    let log = router.execute(input).await?;
+        /// Model name
+        #[arg(long)]
+        model: String,
 
-    println!("\n📊 Results:");
-    println!("✅ Success: {}", log.success);
-    println!("⏱️  Time: {} ms", log.total_time_ms);
-    println!("💰 Cost: ${:.4}", log.total_cost);
-    println!("\n📝 Output:\n{}", log.final_output.unwrap_or_else(|| "N/A".to_string()));
+        /// System prompt
+        #[arg(long, default_value = "")]
+        system_prompt: String,
+
+        /// Cost weight used by Jarvis scheduling (higher = picked less often)
+        #[arg(long, default_value_t = 1.0)]
+        cost_weight: f64,
+
+        /// Custom endpoint (required for local providers)
+        #[arg(long)]
+        endpoint: Option<String>,
+
+        #[arg(long, default_value_t = 0.7)]
+        temperature: f64,
+
+        #[arg(long, default_value_t = 2000)]
+        max_tokens: u32,
+    },
+
+    /// Remove a custom agent
+    Remove { name: String },
+
+    /// Show per-agent call statistics, ranked by cost per accepted output
+    Stats {
+        /// Agent call log (JSONL)
+        #[arg(long, default_value = "./logs/agent_calls.jsonl")]
+        calls: PathBuf,
+
+        /// Only include calls from the last N hours
+        #[arg(long)]
+        since_hours: Option<i64>,
+
+        /// Print JSON instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Enable a custom agent
+    Enable { name: String },
+
+    /// Disable a custom agent without removing it
+    Disable { name: String },
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Compare effective configs of two environments (dev/staging/prod)
+    Diff {
+        left: Environment,
+        right: Environment,
+    },
+
+    /// Validate config files against the schema (exits non-zero on issues)
+    Validate,
+}
+
+#[derive(Subcommand)]
+enum RagAction {
+    /// Index md/txt/pdf/code files under a directory; unchanged and duplicate files are skipped
+    Ingest {
+        /// Directory to ingest recursively
+        dir: PathBuf,
+
+        /// Keep running and re-index files as they change
+        #[arg(long)]
+        watch: bool,
+
+        /// Chunking strategy (fixed, semantic, sliding, recursive)
+        #[arg(long, default_value = "semantic", value_parser = parse_chunking)]
+        chunking: ChunkingStrategy,
+    },
+}
+
+#[derive(Subcommand)]
+enum SessionAction {
+    /// List recorded sessions
+    List,
+
+    /// Bundle a session (conversation, executions, RAG chunks, costs, protocol history) into an archive
+    Export {
+        /// Session ID
+        id: String,
+
+        /// Output archive (default: <id>.acsa-session.zip)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Redact sensitive data before packaging
+        #[arg(long)]
+        redact: bool,
+    },
+
+    /// Restore a session archive into the local store
+    Import {
+        /// Archive file
+        archive: PathBuf,
+
+        /// Overwrite a local session with the same ID
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum HistoryAction {
+    /// List recent runs, optionally filtered by full-text query and tags
+    Search {
+        /// Words that must appear in the input, output or agent responses
+        query: Option<String>,
+
+        /// Only runs with this tag (repeatable)
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+
+        /// Only failed runs
+        #[arg(long)]
+        failed: bool,
+
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+
+    /// Show the full log of one run
+    Show { id: String },
+
+    /// Show how the plan changed between router iterations
+    Diff { id: String },
+
+    /// Add or remove tags (`pinned` runs survive retention)
+    Tag {
+        id: String,
+
+        /// Tags to add
+        #[arg(long)]
+        add: Vec<String>,
+
+        /// Tags to remove
+        #[arg(long)]
+        remove: Vec<String>,
+    },
+
+    /// Apply the retention policy now
+    Prune,
+}
+
+#[derive(Subcommand)]
+enum WorkspaceAction {
+    /// List workspaces
+    List,
+
+    /// Create a workspace
+    Create {
+        /// Workspace ID (letters, digits, - and _)
+        id: String,
+
+        /// Display name (defaults to the ID)
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Member user ID (repeatable; none = every authenticated user)
+        #[arg(long = "member")]
+        members: Vec<String>,
+
+        /// Spending limit in USD
+        #[arg(long)]
+        budget: Option<f64>,
+    },
+
+    /// Show keys, spend and documents of a workspace
+    Show { id: String },
+}
+
+#[derive(Subcommand)]
+enum ScheduleAction {
+    /// List schedules with their next and last runs
+    List,
+
+    /// Add a schedule (cron is evaluated in UTC)
+    Add {
+        /// Schedule name
+        name: String,
+
+        /// Cron expression, e.g. "0 9 * * mon-fri" or "@daily"
+        cron: String,
+
+        /// Input of the scheduled ACSA execution
+        #[arg(long, conflicts_with = "workflow", required_unless_present = "workflow")]
+        input: Option<String>,
+
+        /// Workflow JSON file (each step runs as an ACSA execution)
+        #[arg(long)]
+        workflow: Option<PathBuf>,
+
+        /// Runs missed while the server was down: skip, run_once or run_all
+        #[arg(long, default_value = "run_once")]
+        missed: MissedRunPolicy,
+    },
+
+    /// Remove a schedule
+    Remove { id: String },
+}
+
+#[derive(Subcommand)]
+enum DeadLetterAction {
+    /// List dead letters with subscriber, attempts and error
+    List,
+
+    /// Show one dead letter including the event payload
+    Show { id: String },
+
+    /// Discard one dead letter
+    Remove { id: String },
+
+    /// Discard all dead letters
+    Purge,
+}
+
+#[derive(Subcommand)]
+enum ApprovalAction {
+    /// List pending approvals (--all includes decided ones)
+    List {
+        #[arg(long)]
+        all: bool,
+    },
+
+    /// Approve a paused execution and run the plan
+    Approve {
+        id: String,
+
+        /// Reviewer name recorded in the audit log (default: $USER)
+        #[arg(long)]
+        by: Option<String>,
+
+        #[arg(long)]
+        comment: Option<String>,
+
+        /// Use mock mode (no API keys)
+        #[arg(short, long)]
+        mock: bool,
+    },
+
+    /// Reject a paused execution; the plan is not executed
+    Reject {
+        id: String,
+
+        /// Reviewer name recorded in the audit log (default: $USER)
+        #[arg(long)]
+        by: Option<String>,
+
+        #[arg(long)]
+        comment: Option<String>,
+
+        /// Use mock mode (no API keys)
+        #[arg(short, long)]
+        mock: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum SovereigntyAction {
+    /// H(t) bio-activity report with 7-day decision stats
+    Report,
+
+    /// Screen-time report (today, this week, remaining limit, insights)
+    Usage,
+
+    /// Usage chart: last 7 days in hours, or today by hour in minutes
+    Chart {
+        /// Show today's per-hour breakdown instead of the last 7 days
+        #[arg(long)]
+        hourly: bool,
+    },
+
+    /// Show settings, or change them with the flags below
+    Config {
+        /// Turn sovereignty mode on
+        #[arg(long, conflicts_with = "disable")]
+        enable: bool,
+
+        /// Turn sovereignty mode off
+        #[arg(long)]
+        disable: bool,
+
+        /// Turn anti-addiction limits on
+        #[arg(long, conflicts_with = "disable_limits")]
+        enable_limits: bool,
+
+        /// Turn anti-addiction limits off
+        #[arg(long)]
+        disable_limits: bool,
+
+        /// Daily usage limit in minutes (0 = unlimited)
+        #[arg(long, value_name = "MINUTES")]
+        daily_limit: Option<u32>,
+
+        /// Single-session limit in minutes (0 = unlimited)
+        #[arg(long, value_name = "MINUTES")]
+        session_limit: Option<u32>,
+
+        /// Break reminder interval in minutes (0 = off)
+        #[arg(long, value_name = "MINUTES")]
+        break_reminder: Option<u32>,
+    },
+}
+
+#[derive(Subcommand)]
+enum MaintenanceAction {
+    /// Engage the kill switch
+    On {
+        /// Why maintenance mode is being engaged
+        #[arg(long)]
+        reason: Option<String>,
+    },
+
+    /// Release the kill switch
+    Off,
+
+    /// Show whether maintenance mode is engaged
+    Status,
+}
+
+#[derive(Subcommand)]
+enum EmergencyAction {
+    /// List recorded sessions
+    List,
+
+    /// Show a session grouped by entry type
+    Show {
+        /// Session ID
+        id: String,
+
+        /// Number of trailing entries to highlight
+        #[arg(long, default_value_t = 10)]
+        tail: usize,
+    },
+
+    /// Bundle redacted logs and a config snapshot into a support archive
+    Export {
+        /// Output file
+        #[arg(short, long, default_value = "acsa-support-bundle.json")]
+        output: PathBuf,
+
+        /// Only export this session (default: all sessions)
+        #[arg(long)]
+        session: Option<String>,
+    },
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    // 启动计时从这里开始
+    std::sync::LazyLock::force(&GLOBAL_OPTIMIZER);
+
+    let cli = GLOBAL_OPTIMIZER.track("cli.parse", async { Cli::parse() }).await;
+
+    {
+        let _phase = GLOBAL_OPTIMIZER.start_phase("logging.init").await;
+        if matches!(cli.command, Commands::Tui { .. }) {
+            // 日志会破坏全屏界面，TUI 模式下丢弃
+            tracing_subscriber::fmt().with_writer(std::io::sink).init();
+        } else {
+            let format = match cli.log_format {
+                Some(format) => format,
+                None => LogFormat::from_env()?.unwrap_or_default(),
+            };
+            if matches!(cli.command, Commands::Mcp { .. }) {
+                // stdout 是 JSON-RPC 通道，日志改写到 stderr
+                logging::init_with_writer(format, std::io::stderr)?;
+            } else {
+                logging::init(format)?;
+            }
+        }
+    }
+    {
+        let _phase = GLOBAL_OPTIMIZER.start_phase("env.load").await;
+        dotenv::dotenv().ok();
+    }
+
+    // 紧急日志：panic 与顶层致命错误连同调用栈预写落盘，事后用 `emergency` 子命令排查
+    let emergency = match EmergencyLogger::new(EmergencyLogConfig::default()) {
+        Ok(logger) => {
+            let logger = Arc::new(logger);
+            logger.install_panic_hook();
+            let _ = EMERGENCY.set(logger.clone());
+            Some(logger)
+        }
+        Err(e) => {
+            tracing::warn!("⚠️  Emergency log unavailable, panics will not be recorded: {:#}", e);
+            None
+        }
+    };
+
+    let result = run(cli).await;
+    if let (Err(e), Some(logger)) = (&result, &emergency) {
+        if let Err(log_error) = logger.log_fatal(e) {
+            tracing::warn!("⚠️  Failed to record fatal error: {:#}", log_error);
+        }
+    }
+    result
+}
+
+async fn run(cli: Cli) -> anyhow::Result<()> {
+    let profile_startup = cli.profile_startup;
+    let mut updater = startup_update_check()?;
+    let environment = match cli.environment {
+        Some(environment) => environment,
+        None => Environment::from_env()?.unwrap_or(Environment::Development),
+    };
+    let _ = ENVIRONMENT.set(environment);
+
+    if let Some(config) = resolve_offline(cli.offline).await {
+        offline::activate(config);
+    }
+    if let Some(path) = cli.scenario.or_else(|| std::env::var_os("ACSA_MOCK_SCENARIO").map(PathBuf::from)) {
+        mock_scenario::activate(ScenarioPlayer::load(path)?);
+    }
+    let scripted = mock_scenario::current().is_some();
+    let seed = match cli.seed {
+        Some(seed) => Some(seed),
+        None => std::env::var("ACSA_SEED")
+            .ok()
+            .map(|s| s.trim().parse::<u64>().map_err(|e| anyhow::anyhow!("Invalid ACSA_SEED '{}': {}", s, e)))
+            .transpose()?,
+    };
+    if let Some(seed) = seed {
+        determinism::activate(seed);
+    }
+    // 规则包签名无效时拒绝启动，而不是带着缺失的规则继续运行
+    let mut rule_packs = RulePackConfig::from_env();
+    rule_packs.packs.extend(cli.rule_pack);
+    rule_packs.trusted_keys.extend(cli.rule_pack_key);
+    if !rule_packs.is_empty() {
+        let jarvis = JarvisCircuitBreaker::from_config(&rule_packs)?;
+        // 写到 stderr：`mcp serve` 下 stdout 是 JSON-RPC 通道
+        eprintln!("🛡️  Jarvis rule packs: {}", jarvis.rule_packs().iter().map(|pack| pack.name.as_str()).collect::<Vec<_>>().join(", "));
+        let _ = RULE_PACKS.set(rule_packs);
+    }
+    // 走到这里说明新版本启动正常
+    updater.confirm_startup()?;
+
+    match cli.command {
+        Commands::Execute(args) => {
+            let use_mock = args.mock || scripted;
+            if let Err(e) = execute_cli(args, use_mock).await {
+                // 运维细节写入日志，终端只输出本地化的用户文本
+                eprintln!("\n{}", ErrorPresenter::from_env().present_anyhow(&e));
+                std::process::exit(1);
+            }
+        }
+        Commands::Chat { mock, threshold, session, protocol } => {
+            if let Err(e) = chat_cli(mock || scripted, threshold, session, protocol).await {
+                eprintln!("\n{}", ErrorPresenter::from_env().present_anyhow(&e));
+                std::process::exit(1);
+            }
+        }
+        Commands::Resume { execution_id, store, mock, threshold } => {
+            if let Err(e) = resume_cli(store, execution_id, mock || scripted, threshold).await {
+                eprintln!("\n{}", ErrorPresenter::from_env().present_anyhow(&e));
+                std::process::exit(1);
+            }
+        }
+        Commands::Batch { file, output, concurrency, timeout, mock, threshold } => {
+            batch_cli(file, output, concurrency, timeout, mock || scripted, threshold).await?;
+        }
+        Commands::Protocols => {
+            protocols_cli()?;
+        }
+        Commands::Rag { store, cache_dir, mock, action } => {
+            rag_cli(store, cache_dir, mock || scripted, action).await?;
+        }
+        Commands::Estimate { input, protocol, json } => {
+            estimate_cli(input, protocol, json)?;
+        }
+        Commands::Replay { id, store, fixtures, threshold, pipeline } => {
+            replay_cli(ExecutionStore::open(store)?, id, fixtures, threshold, pipeline).await?;
+        }
+        Commands::History { store, action } => {
+            history_cli(ExecutionStore::open(store)?, action)?;
+        }
+        Commands::Session { store, action } => {
+            session_cli(SessionStore::new(store), action)?;
+        }
+        Commands::Workspace { root, action } => {
+            workspace_cli(root, action).await?;
+        }
+        Commands::Maintenance { sentinel, action } => {
+            maintenance_cli(sentinel, action).await?;
+        }
+        Commands::SelfUpdate { channel, check, rollback } => {
+            self_update_cli(updater, channel, check, rollback).await?;
+        }
+        Commands::Notify { kind, title, body } => {
+            notify_cli(kind, title, body).await?;
+        }
+        Commands::Schedule { file, action } => {
+            schedule_cli(file, action)?;
+        }
+        Commands::Approvals { file, store, action } => {
+            if let Err(e) = approvals_cli(file, store, action, scripted).await {
+                eprintln!("\n{}", ErrorPresenter::from_env().present_anyhow(&e));
+                std::process::exit(1);
+            }
+        }
+        Commands::DeadLetters { file, action } => {
+            dead_letters_cli(file, action)?;
+        }
+        Commands::Emergency { log_dir, action } => {
+            emergency_cli(log_dir, action)?;
+        }
+        Commands::Config { config_dir, action } => {
+            config_cli(config_dir, action).await?;
+        }
+        Commands::Sovereignty { config_dir, state, action } => {
+            sovereignty_cli(config_dir, state, action).await?;
+        }
+        Commands::Agents { registry, action } => {
+            agents_cli(registry, action)?;
+        }
+        Commands::Plugin { dir, action } => {
+            plugin_cli(dir, action).await?;
+        }
+        Commands::Mcp { action } => {
+            mcp_cli(action, scripted).await?;
+        }
+        Commands::Serve { host, port, data_dir, mock, threshold } => {
+            serve_cli(host, port, data_dir, mock || scripted, threshold).await?;
+        }
+        Commands::Tui { mock } => {
+            tui_cli(mock || scripted).await?;
+        }
+        Commands::Version => {
+            println!("O-Sovereign v0.1.0 (Rust Edition)");
+        }
+    }
+
+    if profile_startup {
+        let metrics = GLOBAL_OPTIMIZER.get_startup_metrics().await;
+        println!("\n{}", metrics.render_waterfall());
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "ui")]
+async fn tui_cli(use_mock: bool) -> anyhow::Result<()> {
+    o_sovereign::tui::run(use_mock).await
+}
+
+#[cfg(not(feature = "ui"))]
+async fn tui_cli(_use_mock: bool) -> anyhow::Result<()> {
+    anyhow::bail!("TUI dashboard is not compiled in; rebuild with `--features ui`")
+}
+
+/// 离线配置优先级：配置文件 offline.* < 环境变量 < --offline
+async fn resolve_offline(cli_flag: bool) -> Option<OfflineConfig> {
+    let mut config = OfflineConfig::default();
+
+    let config_dir = PathBuf::from("./config");
+    if config_dir.is_dir() {
+        let manager = ConfigManager::new(ConfigManagerConfig {
+            environment: deployment_environment(),
+            config_dir,
+            enable_hot_reload: false,
+            ..Default::default()
+        });
+        if manager.load_from_file().await.is_ok() {
+            if let Ok(file_config) = OfflineConfig::from_config(&manager).await {
+                config = file_config;
+            }
+        }
+    }
+
+    let mut config = config.with_env_overrides();
+    config.enabled |= cli_flag;
+    config.enabled.then_some(config)
+}
+
+async fn execute_cli(args: ExecuteArgs, use_mock: bool) -> anyhow::Result<()> {
+    let ExecuteArgs {
+        input, threshold: risk_threshold, session, tags, stream, output, protocol, clean, require_approval, pipeline, dry_run,
+        cache, cache_ttl, semantic_threshold, ..
+    } = args;
+    // 执行前校验参数，避免跑完整条链路后才报错；参数错误按用户错误呈现
+    let protocols = load_protocols()?;
+    let (protocol, detected) = resolve_protocol(&protocols, &protocol, &input).map_err(usage_error)?;
+    check_protocol_allowed(&protocol).await?;
+    let report_format = output
+        .as_ref()
+        .map(|path| {
+            ExportFormat::from_path(path).ok_or_else(|| {
+                anyhow::anyhow!("Cannot infer report format from {} (use .json, .md or .html)", path.display())
+            })
+        })
+        .transpose()
+        .map_err(usage_error)?;
+    let pipeline = pipeline.map(|spec| spec.parse::<PipelineConfig>()).transpose().map_err(usage_error)?;
+    if let Some(threshold) = semantic_threshold.filter(|threshold| !(*threshold > 0.0 && *threshold <= 1.0)) {
+        return Err(usage_error(anyhow::anyhow!("--semantic-threshold must be in (0, 1] (got {})", threshold)));
+    }
+
+    println!("\n{}", "=".repeat(80));
+    println!("🚀 O-Sovereign ACSA CLI");
+    println!("{}", "=".repeat(80));
+
+    match detected {
+        Some(true) => println!("🎯 Protocol: {} (auto-detected)", protocol.display_name()),
+        Some(false) => println!("🎯 Protocol: {} (default, nothing detected)", protocol.display_name()),
+        None => println!("🎯 Protocol: {}", protocol.display_name()),
+    }
+
+    let protocol_config = protocols.get_config(protocol.clone()).clone();
+    let semantic_allowed = protocol_config.enable_semantic_cache;
+    let mut router = build_router(use_mock, risk_threshold, stream, clean, Some(protocol_config)).await?;
+    if require_approval {
+        router = router.with_approvals(Arc::new(ApprovalStore::open(DEFAULT_APPROVAL_PATH)?));
+    }
+    if let Some(pipeline) = pipeline {
+        println!("🔗 Pipeline: {}", pipeline.summary());
+        router = apply_pipeline(router, pipeline, use_mock)?;
+    }
+    if dry_run {
+        println!("🧪 Dry run: Omega will not be executed");
+        router = router.with_dry_run(true);
+    }
+    if cache {
+        let manager = CacheManager::with_defaults(PathBuf::from(DEFAULT_CACHE_DIR))?;
+        let mut response_cache = ResponseCache::new(&manager, ResponseCachePolicy { ttl_secs: cache_ttl, semantic_threshold });
+        if let Some(threshold) = semantic_threshold {
+            // 复用 RAG 的嵌入后端，向量缓存在同一缓存目录下
+            let rag = RagEngine::new(RagConfig {
+                embedding_model: if use_mock { EmbeddingModel::Mock } else { RagConfig::default().embedding_model },
+                embedding_cache_dir: Some(PathBuf::from(DEFAULT_CACHE_DIR)),
+                ..Default::default()
+            });
+            let embedder = rag.embedder().ok_or_else(|| anyhow::anyhow!("Semantic cache needs an embedding provider"))?;
+            if !semantic_allowed {
+                println!("🗄️  Semantic cache disabled for protocol {}", protocol.display_name());
+            } else {
+                println!("🗄️  Semantic cache: similarity ≥ {:.2}", threshold);
+            }
+            response_cache = response_cache.with_embedder(embedder);
+        }
+        router = router.with_response_cache(Arc::new(response_cache));
+    }
+    let router = Arc::new(router);
+    let log = if stream {
+        GLOBAL_OPTIMIZER.track("acsa.execute", execute_streaming_cli(router.clone(), input)).await?
+    } else {
+        GLOBAL_OPTIMIZER.track("acsa.execute", router.execute(input)).await?
+    };
+    router.flush_traces().await;
+
+    // 记录到本地会话，便于之后导出复现
+    let store = SessionStore::new("./data/sessions");
+    let session_id = session.unwrap_or_else(|| format!("session_cli_{}", chrono::Utc::now().timestamp_millis()));
+    let mut record = store.load_or_create(&session_id, "cli", protocol.clone())?;
+    record.record_execution(&log, protocol);
+    store.save(&record)?;
+    let execution_id = ExecutionStore::open("./data/executions")?.record(&log, Some(&session_id), &tags)?;
+
+    println!("\n📊 Results:");
+    println!("✅ Success: {}", log.success);
+    println!("⏱️  Time: {} ms", log.total_time_ms);
+    println!("💰 Cost: ${:.4}", log.total_cost);
+    if let Some(stats) = &log.response_cache {
+        println!(
+            "🗄️  Cache: {} hit(s) ({} semantic), {} miss(es), saved ${:.4}",
+            stats.hits, stats.semantic_hits, stats.misses, stats.saved_cost
+        );
+    }
+    if log.offline {
+        println!("📴 Offline run (local backends only)");
+    }
+    if let Some(seed) = log.seed {
+        println!("🎲 Seed: {} (re-run with --seed {} to reproduce)", seed, seed);
+    }
+    router.flush_traces().await;
+    println!("🗂️  Session: {} (execution {})", session_id, execution_id);
+    if let Some(id) = log.execution_id.as_ref().filter(|_| !log.success && log.final_output.is_none()) {
+        println!("♻️  Interrupted mid-chain; continue with `resume {}`", id);
+    }
+    if let (Some(path), Some(format)) = (&output, report_format) {
+        std::fs::write(path, log.export(format)?)
+            .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", path.display(), e))?;
+        println!("📄 Report: {}", path.display());
+    }
+    for diff in &log.plan_diffs {
+        println!("\n{}", diff.render().trim_end());
+    }
+    println!("\n📝 Output:\n{}", log.final_output.unwrap_or_else(|| "N/A".to_string()));
+
+    // CI 预检：方案未通过审计时以非零状态退出
+    if dry_run && log.dry_run.is_none() {
+        anyhow::bail!("Dry run: the plan did not pass the audit");
+    }
+    Ok(())
+}
+
+/// 列出中断的执行，或从检查点继续其中一个（跳过已完成的阶段）
+async fn resume_cli(
+    store: PathBuf,
+    execution_id: Option<String>,
+    use_mock: bool,
+    risk_threshold: u8,
+) -> anyhow::Result<()> {
+    let checkpoints = Arc::new(CheckpointStore::open(store)?);
+    let Some(execution_id) = execution_id else {
+        let pending = checkpoints.list()?;
+        if pending.is_empty() {
+            println!("No interrupted executions");
+            return Ok(());
+        }
+        for checkpoint in pending {
+            println!(
+                "{}  {:<8} iteration {}  {}  {}",
+                checkpoint.execution_id,
+                checkpoint.stage.label(),
+                checkpoint.next_iteration + 1,
+                checkpoint.updated_at.format("%Y-%m-%d %H:%M"),
+                checkpoint.log.user_input.chars().take(60).collect::<String>()
+            );
+        }
+        return Ok(());
+    };
+
+    let checkpoint = checkpoints
+        .load(&execution_id)?
+        .ok_or_else(|| usage_error(anyhow::anyhow!("No checkpoint for execution {} (see `resume`)", execution_id)))?;
+    // 沿用中断前的协议
+    let protocol_config = match checkpoint.log.protocol.clone() {
+        Some(protocol) => Some(load_protocols()?.get_config(protocol).clone()),
+        None => None,
+    };
+    println!("♻️  Resuming {} from stage {}", execution_id, checkpoint.stage.label());
+
+    let mut router = build_router(use_mock, risk_threshold, false, false, protocol_config)
+        .await?
+        .with_checkpoints(checkpoints);
+    // 沿用中断前的流水线拓扑
+    if let Some(pipeline) = checkpoint.pipeline.clone() {
+        router = apply_pipeline(router, pipeline, use_mock)?;
+    }
+    if checkpoint.stage == CheckpointStage::AwaitingApproval {
+        // 审批已决定、但继续执行时中断：按审批结果继续
+        router = router.with_approvals(Arc::new(ApprovalStore::open(DEFAULT_APPROVAL_PATH)?));
+    }
+    let log = GLOBAL_OPTIMIZER.track("acsa.execute", router.resume(&execution_id)).await?;
+    router.flush_traces().await;
+    let record_id = ExecutionStore::open("./data/executions")?.record(&log, None, &[])?;
+
+    println!("\n📊 Results:");
+    println!("✅ Success: {}", log.success);
+    println!("⏱️  Time: {} ms (since the original start)", log.total_time_ms);
+    println!("💰 Cost: ${:.4}", log.total_cost);
+    println!("🗂️  Execution {}", record_id);
+    println!("\n📝 Output:\n{}", log.final_output.unwrap_or_else(|| "N/A".to_string()));
+    Ok(())
+}
+
+/// 列出待审批的执行，或批准 / 驳回其中一个（按决定继续或中止，结果记入执行日志）
+async fn approvals_cli(file: PathBuf, store: PathBuf, action: ApprovalAction, scripted: bool) -> anyhow::Result<()> {
+    let approvals = Arc::new(ApprovalStore::open(file)?);
+    let (id, approve, by, comment, use_mock) = match action {
+        ApprovalAction::List { all } => {
+            let requests = approvals.list(if all { None } else { Some(ApprovalStatus::Pending) })?;
+            if requests.is_empty() {
+                println!("No {}approvals", if all { "" } else { "pending " });
+            }
+            for request in requests {
+                println!(
+                    "{:<22} {:<9} risk {:>3}/{:<3} {}  {}{}",
+                    request.id,
+                    request.status.label(),
+                    request.risk_score,
+                    request.risk_threshold,
+                    request.requested_at.format("%Y-%m-%d %H:%M"),
+                    request.user_input.chars().take(60).collect::<String>(),
+                    request.decided_by.map(|by| format!("  (by {})", by)).unwrap_or_default()
+                );
+            }
+            return Ok(());
+        }
+        ApprovalAction::Approve { id, by, comment, mock } => (id, true, by, comment, mock),
+        ApprovalAction::Reject { id, by, comment, mock } => (id, false, by, comment, mock),
+    };
+
+    let request = approvals
+        .get(&id)?
+        .ok_or_else(|| usage_error(anyhow::anyhow!("Unknown approval {} (see `approvals list`)", id)))?;
+    if request.status != ApprovalStatus::Pending {
+        return Err(usage_error(anyhow::anyhow!("Approval {} was already {}", id, request.status.label())));
+    }
+    let checkpoints = Arc::new(CheckpointStore::open(store)?);
+    let checkpoint = checkpoints.load(&request.execution_id)?.ok_or_else(|| {
+        usage_error(anyhow::anyhow!("No checkpoint for execution {} (was it already resumed?)", request.execution_id))
+    })?;
+    let protocol_config = match checkpoint.log.protocol.clone() {
+        Some(protocol) => Some(load_protocols()?.get_config(protocol).clone()),
+        None => None,
+    };
+    println!("📝 Plan under review (risk {}/{}):\n{}\n", request.risk_score, request.risk_threshold, request.plan);
+
+    let reviewer = by.or_else(|| std::env::var("USER").ok()).unwrap_or_else(|| "cli".to_string());
+    let mut router = build_router(use_mock || scripted, request.risk_threshold, false, false, protocol_config)
+        .await?
+        .with_checkpoints(checkpoints)
+        .with_approvals(approvals);
+    if let Some(pipeline) = checkpoint.pipeline {
+        router = apply_pipeline(router, pipeline, use_mock || scripted)?;
+    }
+    let log = GLOBAL_OPTIMIZER
+        .track("acsa.execute", router.decide_approval(&id, approve, &reviewer, comment))
+        .await?;
+    router.flush_traces().await;
+    let record_id = ExecutionStore::open("./data/executions")?.record(&log, None, &["approval".to_string()])?;
+
+    println!("{} {} by {}", if approve { "✅ Approved" } else { "⛔ Rejected" }, id, reviewer);
+    println!("\n📊 Results:");
+    println!("✅ Success: {}", log.success);
+    println!("💰 Cost: ${:.4}", log.total_cost);
+    println!("🗂️  Execution {}", record_id);
+    println!("\n📝 Output:\n{}", log.final_output.unwrap_or_else(|| "N/A".to_string()));
+    Ok(())
+}
+
+/// 多轮会话：每轮带上之前的对话执行，并像 `execute` 一样记入本地会话与执行日志
+async fn chat_cli(
+    use_mock: bool,
+    risk_threshold: u8,
+    session: Option<String>,
+    protocol: Option<String>,
+) -> anyhow::Result<()> {
+    use std::io::{BufRead, Write};
+
+    let protocols = load_protocols()?;
+    let protocol = match protocol {
+        Some(name) => parse_protocol(&protocols, &name)?,
+        None => protocols.current_protocol(),
+    };
+    check_protocol_allowed(&protocol).await?;
+    let router = Arc::new(build_router(use_mock, risk_threshold, false, false, None).await?);
+    let state = Arc::new(AgentStateManager::new(AgentStateConfig::default(), None));
+    let sessions = SessionStore::new("./data/sessions");
+    let executions = ExecutionStore::open("./data/executions")?;
+
+    let mut conversation = match session {
+        Some(id) if sessions.exists(&id) => {
+            let record = sessions.load(&id)?;
+            Conversation::resume(router.clone(), state, record.session, &record.executions).await?
+        }
+        Some(id) => anyhow::bail!("Session not found: {} (see `session list`)", id),
+        None => Conversation::start(router.clone(), state, "cli", protocol).await?,
+    };
+
+    println!("\n💬 ACSA chat — session {} ({})", conversation.session().session_id, conversation.protocol().display_name());
+    println!("   Type /help for commands, /exit to leave\n");
+
+    let mut lines = std::io::stdin().lock().lines();
+    loop {
+        print!("> ");
+        std::io::stdout().flush()?;
+        let Some(line) = lines.next().transpose()? else {
+            break;
+        };
+        let command = match ChatCommand::parse(&line) {
+            Ok(Some(command)) => command,
+            Ok(None) => continue,
+            Err(e) => {
+                println!("{}", e);
+                continue;
+            }
+        };
+
+        match command {
+            ChatCommand::Message(input) => {
+                let log = match GLOBAL_OPTIMIZER.track("acsa.execute", conversation.send(input)).await {
+                    Ok(log) => log,
+                    Err(e) => {
+                        eprintln!("{}", ErrorPresenter::from_env().present_anyhow(&e));
+                        continue;
+                    }
+                };
+                let session_id = conversation.session().session_id.clone();
+                let protocol = conversation.protocol().clone();
+                let mut record = sessions.load_or_create(&session_id, "cli", protocol.clone())?;
+                record.record_execution(&log, protocol);
+                sessions.save(&record)?;
+                executions.record(&log, Some(&session_id), &[])?;
+
+                println!("\n{}", log.final_output.as_deref().unwrap_or("N/A"));
+                println!(
+                    "\n{} {} ms · ${:.4}\n",
+                    if log.success { "✅" } else { "❌" },
+                    log.total_time_ms,
+                    log.total_cost
+                );
+            }
+            ChatCommand::Protocol(None) => {
+                let known: Vec<_> = protocols.available().iter().map(|p| p.name().to_lowercase()).collect();
+                println!("Protocol: {} (available: {})", conversation.protocol().display_name(), known.join(", "));
+            }
+            ChatCommand::Protocol(Some(name)) => match parse_protocol(&protocols, &name) {
+                Ok(protocol) => match check_protocol_allowed(&protocol).await {
+                    Ok(()) => {
+                        conversation.switch_protocol(protocol).await?;
+                        println!("🔀 Switched to {}", conversation.protocol().display_name());
+                    }
+                    Err(e) => println!("{}", e),
+                },
+                Err(e) => println!("{}", e),
+            },
+            ChatCommand::Cost => {
+                let cost = conversation.cost();
+                println!(
+                    "💰 ${:.4} over {} turns (last turn ${:.4})",
+                    cost.total_cost, cost.turns, cost.last_turn_cost
+                );
+            }
+            ChatCommand::Reset => {
+                let session_id = conversation.reset().await?;
+                println!("🆕 New session {} (earlier turns forgotten)", session_id);
+            }
+            ChatCommand::Help => println!("{}", ChatCommand::help()),
+            ChatCommand::Exit => break,
+        }
+    }
+
+    println!("🗂️  Session: {} (resume with --session {})", conversation.session().session_id, conversation.session().session_id);
+    Ok(())
+}
+
+/// 命令行参数错误（提示用户修正，而不是呈现为系统故障）
+fn usage_error(error: anyhow::Error) -> anyhow::Error {
+    AcsaError::new(ErrorCode::ConfigError, error.to_string()).into()
+}
+
+/// 解析 `--protocol`：`auto` 按输入关键词检测（检测不到时用默认协议）
+///
+/// 返回协议与检测结果：`Some(true)` 检测命中、`Some(false)` 回退默认、`None` 为显式指定
+fn resolve_protocol(protocols: &ProtocolManager, name: &str, input: &str) -> anyhow::Result<(Protocol, Option<bool>)> {
+    if !name.eq_ignore_ascii_case("auto") {
+        return Ok((parse_protocol(protocols, name)?, None));
+    }
+    Ok(match Protocol::detect_from_input(input) {
+        Some(protocol) => (protocol, Some(true)),
+        None => (protocols.current_protocol(), Some(false)),
+    })
+}
+
+/// 创建四个 Agent 的 Provider 并组装 Router（单次执行、批量执行与会话共用）
+///
+/// 指定协议时按协议的 Agent 权重与温度调整各阶段
+async fn build_router(
+    use_mock: bool,
+    risk_threshold: u8,
+    stream: bool,
+    clean: bool,
+    protocol: Option<ProtocolConfig>,
+) -> anyhow::Result<ACSARouter> {
+    // 离线模式使用本地端点，不需要任何云端密钥
+    let openai_key = if !use_mock && !offline::is_offline() { std::env::var("OPENAI_API_KEY").ok() } else { None };
+
+    let phase = GLOBAL_OPTIMIZER.start_phase("providers.init").await;
+    let moss = create_provider(AgentRole::MOSS, openai_key, use_mock)?;
+    let l6 = create_provider(AgentRole::L6, None, use_mock)?;
+    let ultron = create_provider(AgentRole::Ultron, None, use_mock)?;
+    let omega = create_provider(AgentRole::Omega, None, use_mock)?;
+    drop(phase);
+
+    let config = ACSAConfig {
+        max_iterations: 3,
+        risk_threshold,
+        enable_l6: true,
+        enable_streaming: stream,
+        throttle: Default::default(),
+        retry: Default::default(),
+        protocol,
+        rag: None,
+        enable_cognitive_cleaning: clean,
+        safety_score: Default::default(),
+        pipeline: Default::default(),
+        dry_run: false,
+    };
+
+    // 维护模式下直接拒绝
+    let kill_switch = Arc::new(KillSwitch::new(KillSwitchConfig::default()));
+    kill_switch.refresh().await?;
+    let notifier = Arc::new(Notifier::from_env(NotifierConfig::default())?);
+
+    let mut router = GLOBAL_OPTIMIZER
+        .track("router.init", async {
+            ACSARouter::new(moss, l6, ultron, omega, config)
+                .with_kill_switch(kill_switch)
+                .with_notifier(notifier)
+        })
+        .await;
+    if let Some(rule_packs) = RULE_PACKS.get() {
+        router = router.with_jarvis(JarvisCircuitBreaker::from_config(rule_packs)?);
+    }
+
+    // 云端模式下启用 BUNKER 协议：云端 Agent 连续失败时切换到本地 OpenAI 兼容端点
+    if !use_mock && !offline::is_offline() {
+        let local = OfflineConfig::from_env();
+        let mut jarvis = JarvisManager::new().with_local_model(LocalModelConfig {
+            backend: "openai-compatible".to_string(),
+            endpoint: local.llm_endpoint,
+            context_window: 8192,
+            model: local.llm_model,
+        });
+        if let Some(logger) = EMERGENCY.get() {
+            jarvis = jarvis.with_emergency_log(logger.clone());
+        }
+        router = router.with_bunker(Arc::new(tokio::sync::RwLock::new(jarvis)));
+
+        let mut api = ApiManager::new(PathBuf::from(DEFAULT_API_DIR));
+        api.init().await?;
+        router = router.with_api_manager(Arc::new(tokio::sync::RwLock::new(api)));
+    }
+
+    // 设置 OTEL_EXPORTER_OTLP_ENDPOINT 时导出链路追踪（Jaeger / Tempo / OTel Collector）
+    if let Some(telemetry) = TelemetryConfig::from_env() {
+        eprintln!("📡 Exporting traces to {}", telemetry.endpoint);
+        router = router.with_tracer(Arc::new(Tracer::otlp(&telemetry)?));
+    }
+
+    // 注册表中的外部 MCP 服务器：其工具提供给 Omega 执行阶段
+    if !use_mock {
+        let servers = McpServersFile::load(DEFAULT_MCP_SERVERS_PATH)?;
+        if !servers.servers.is_empty() {
+            let tools = McpClientRegistry::connect_all(&servers).await;
+            if !tools.is_empty() {
+                router = router.with_mcp_tools(Arc::new(tools));
+            }
+        }
+    }
+
+    // 每个阶段落盘检查点，中断后可用 `resume` 继续
+    router = router.with_checkpoints(Arc::new(CheckpointStore::open(DEFAULT_CHECKPOINT_DIR)?));
+    Ok(router)
+}
+
+/// 替换流水线拓扑，并从 Agent 注册表注册其中引用的自定义 Agent
+fn apply_pipeline(mut router: ACSARouter, pipeline: PipelineConfig, use_mock: bool) -> anyhow::Result<ACSARouter> {
+    let custom_agents = pipeline.custom_agents();
+    if !custom_agents.is_empty() {
+        let registry = AgentExtensionManager::load_registry(std::path::Path::new(DEFAULT_AGENT_REGISTRY))?;
+        for name in custom_agents {
+            let agent = registry.get_custom_agent(name).filter(|agent| agent.enabled).ok_or_else(|| {
+                usage_error(anyhow::anyhow!(
+                    "Pipeline agent '{}' is not an enabled agent in {} (see `agents list`)",
+                    name,
+                    DEFAULT_AGENT_REGISTRY
+                ))
+            })?;
+            // 自定义 Agent 按它在流水线中首次出现的角色创建 Provider
+            let role = pipeline
+                .stages
+                .iter()
+                .find(|stage| stage.agent.as_deref() == Some(name))
+                .map(|stage| stage.role)
+                .unwrap_or(AgentRole::Ultron);
+            router = router.with_custom_agent(agent.clone(), create_custom_agent_provider(agent, role, use_mock)?);
+        }
+    }
+    // 引用 Agent 插件时接入插件目录（首次执行时才扫描，计入 --profile-startup 瀑布图）
+    if pipeline.stages.iter().any(|stage| stage.plugin.is_some()) {
+        router = router.with_installed_plugins(PluginSystemConfig::default());
+    }
+    Ok(router.with_pipeline(pipeline))
+}
+
+async fn batch_cli(
+    file: PathBuf,
+    output: Option<PathBuf>,
+    concurrency: usize,
+    timeout: Option<u64>,
+    use_mock: bool,
+    risk_threshold: u8,
+) -> anyhow::Result<()> {
+    let content = std::fs::read_to_string(&file)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", file.display(), e))?;
+    let tasks = BatchTask::parse_jsonl(&content)?;
+    if tasks.is_empty() {
+        anyhow::bail!("No tasks in {}", file.display());
+    }
+    let output = output.unwrap_or_else(|| file.with_extension("results.jsonl"));
+
+    let router = Arc::new(build_router(use_mock, risk_threshold, false, false, None).await?);
+    let mut runner = BatchRunner::new(router.clone()).with_concurrency(concurrency);
+    if let Some(secs) = timeout {
+        runner = runner.with_task_timeout(std::time::Duration::from_secs(secs));
+    }
+
+    println!("📦 Running {} tasks from {} (concurrency {})", tasks.len(), file.display(), concurrency.max(1));
+    let summary = runner.run(tasks, &output).await?;
+    router.flush_traces().await;
+
+    println!("\n📊 Batch results:");
+    println!("✅ Succeeded: {}/{}", summary.succeeded, summary.total);
+    let mut outcomes: Vec<_> = summary.outcomes.iter().collect();
+    outcomes.sort();
+    for (outcome, count) in outcomes {
+        println!("   {:<16} {}", outcome, count);
+    }
+    println!("💰 Cost: ${:.4}", summary.total_cost_usd);
+    println!("⏱️  Latency: avg {:.0} ms, max {} ms (wall {} ms)", summary.avg_latency_ms, summary.max_latency_ms, summary.wall_time_ms);
+    println!("📝 Results: {}", output.display());
+    Ok(())
+}
+
+/// 流式执行：按 Agent / 迭代分段实时打印输出
+async fn execute_streaming_cli(router: Arc<ACSARouter>, input: String) -> anyhow::Result<ACSAExecutionLog> {
+    use std::io::Write;
+
+    let (mut chunks, handle) = router.execute_streaming(input);
+    let mut stdout = std::io::stdout();
+    let mut streaming = false;
+    while let Some(chunk) = chunks.recv().await {
+        if chunk.done {
+            if streaming {
+                println!();
+            }
+            streaming = false;
+            continue;
+        }
+        if !streaming {
+            println!("\n{} [{} · iteration {}]", chunk.role.emoji(), chunk.role.as_str(), chunk.iteration);
+            streaming = true;
+        }
+        print!("{}", chunk.delta);
+        stdout.flush()?;
+    }
+    handle.await?
+}
+
+/// 用记录的响应离线重放一次执行，并与原结果对比
+async fn replay_cli(
+    store: ExecutionStore,
+    id: String,
+    fixtures: Option<PathBuf>,
+    risk_threshold: u8,
+    pipeline: Option<String>,
+) -> anyhow::Result<()> {
+    let record = store.get(&id).ok_or_else(|| usage_error(anyhow::anyhow!("Execution not found: {}", id)))?;
+    let original = record.log;
+    let transcript = match &fixtures {
+        Some(path) => ExecutionTranscript::from_fixtures(&FixtureSet::load(path)?),
+        None => ExecutionTranscript::from_log(&original)?,
+    };
+    let pipeline = pipeline.map(|spec| spec.parse::<PipelineConfig>()).transpose().map_err(usage_error)?;
+    let protocol_config = match original.protocol.clone() {
+        Some(protocol) => Some(load_protocols()?.get_config(protocol).clone()),
+        None => None,
+    };
+
+    // Provider 不会被调用：重放的每次调用都取自记录
+    let mut router = build_router(true, risk_threshold, false, false, protocol_config).await?;
+    if let Some(pipeline) = pipeline {
+        router = apply_pipeline(router, pipeline, true)?;
+    }
+    println!("📼 Replaying {} ({} recorded calls)", id, transcript.len());
+    let log = router.replay_transcript(original.user_input.clone(), transcript).await?;
+
+    println!("\n📊 Replay vs original:");
+    println!("✅ Success: {} (was {})", log.success, original.success);
+    println!("🔁 Iterations: {} (was {})", log.iterations, original.iterations);
+    println!("💰 Cost: ${:.4} (was ${:.4})", log.total_cost, original.total_cost);
+    if let Some(audit) = &log.audit_result {
+        println!("🛡️  Risk: {}/100", audit.risk_score);
+    }
+    let same = log.final_output == original.final_output;
+    println!("📝 Output: {}", if same { "identical" } else { "DIFFERENT" });
+    if !same {
+        println!("\n{}", log.final_output.unwrap_or_else(|| "N/A".to_string()));
+    }
+    Ok(())
+}
+
+fn history_cli(store: ExecutionStore, action: HistoryAction) -> anyhow::Result<()> {
+    match action {
+        HistoryAction::Search { query, tags, failed, limit } => {
+            let page = store.search(&ExecutionQuery {
+                text: query,
+                tags,
+                success: failed.then_some(false),
+                limit,
+                ..Default::default()
+            });
+            if page.items.is_empty() {
+                println!("No matching executions");
+            }
+            for item in &page.items {
+                let tags = item.tags.iter().cloned().collect::<Vec<_>>().join(",");
+                println!(
+                    "{} {}  {}  ${:.4}  {}{}",
+                    if item.success { "✅" } else { "❌" },
+                    item.id,
+                    item.started_at.format("%Y-%m-%d %H:%M:%S"),
+                    item.total_cost,
+                    item.input_preview,
+                    if tags.is_empty() { String::new() } else { format!("  [{}]", tags) }
+                );
+            }
+            if page.total > page.items.len() {
+                println!("... {} more (use --limit)", page.total - page.items.len());
+            }
+        }
+        HistoryAction::Show { id } => {
+            let record = store.get(&id).ok_or_else(|| anyhow::anyhow!("Execution not found: {}", id))?;
+            println!("{}", serde_json::to_string_pretty(&record)?);
+        }
+        HistoryAction::Diff { id } => {
+            let record = store.get(&id).ok_or_else(|| anyhow::anyhow!("Execution not found: {}", id))?;
+            if record.log.plan_diffs.is_empty() {
+                println!("No replanning in {} (plan accepted on first audit)", record.id);
+            }
+            for diff in &record.log.plan_diffs {
+                println!("{}", diff.render());
+            }
+        }
+        HistoryAction::Tag { id, add, remove } => {
+            let record = store.tag(&id, &add, &remove)?;
+            println!("🏷️  {}: {}", record.id, record.tags.into_iter().collect::<Vec<_>>().join(", "));
+        }
+        HistoryAction::Prune => {
+            let removed = store.prune()?;
+            println!("🧹 Removed {} executions, {} remain", removed, store.len());
+        }
+    }
+    Ok(())
+}
+
+/// 内置协议 + `./config/protocols/*.toml` 中的自定义协议
+fn load_protocols() -> anyhow::Result<ProtocolManager> {
+    let mut protocols = ProtocolManager::new();
+    protocols.load_custom_protocols(DEFAULT_PROTOCOL_DIR).map_err(usage_error)?;
+    Ok(protocols)
+}
+
+fn parse_protocol(protocols: &ProtocolManager, name: &str) -> anyhow::Result<Protocol> {
+    protocols.resolve(name).ok_or_else(|| {
+        let known: Vec<_> = protocols.available().iter().map(|p| p.name().to_lowercase()).collect();
+        anyhow::anyhow!("Unknown protocol '{}' (expected one of: {})", name, known.join(", "))
+    })
+}
+
+fn deployment_environment() -> Environment {
+    ENVIRONMENT.get().copied().unwrap_or(Environment::Development)
+}
+
+/// 选定协议后按部署环境检查 `protocol.<name>.enabled`（生产环境未放行时拒绝 Ghost）
+async fn check_protocol_allowed(protocol: &Protocol) -> anyhow::Result<()> {
+    deployment_config().await?.check_protocol(&protocol.name()).await
+}
+
+/// 当前部署环境的配置（`./config` 中的分层文件 + 环境变量）
+async fn deployment_config() -> anyhow::Result<ConfigManager> {
+    let config_dir = PathBuf::from("./config");
+    let manager = ConfigManager::new(ConfigManagerConfig {
+        environment: deployment_environment(),
+        config_dir: config_dir.clone(),
+        enable_hot_reload: false,
+        ..Default::default()
+    });
+    if config_dir.is_dir() {
+        manager.load_from_file().await?;
+    }
+    manager.load_from_env().await?;
+    Ok(manager)
+}
+
+fn protocols_cli() -> anyhow::Result<()> {
+    let protocols = load_protocols()?;
+    println!(
+        "{:<24} {:>5} {:>6} {:>6} {:>7} {:>6}  Description",
+        "Protocol", "Temp", "MOSS", "L6", "Ultron", "Omega"
+    );
+    for protocol in protocols.available() {
+        let config = protocols.get_config(protocol.clone());
+        let weights = &config.agent_weights;
+        println!(
+            "{:<24} {:>5.2} {:>5.0}% {:>5.0}% {:>6.0}% {:>5.0}%  {}",
+            protocol.name().to_lowercase(),
+            config.temperature,
+            weights.moss * 100.0,
+            weights.l6 * 100.0,
+            weights.ultron * 100.0,
+            weights.omega * 100.0,
+            config.description
+        );
+    }
+    println!("\nCustom protocols: {}/*.toml", DEFAULT_PROTOCOL_DIR);
+    Ok(())
+}
+
+fn estimate_cli(input: String, protocol: Option<String>, json: bool) -> anyhow::Result<()> {
+    let protocols = load_protocols()?;
+    let protocol = protocol.as_deref().map(|name| parse_protocol(&protocols, name)).transpose()?;
+
+    let estimate = CostEstimator::new(ACSAConfig::default()).estimate(&input, protocol);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&estimate)?);
+        return Ok(());
+    }
+
+    println!("\n🧮 Dry-run estimate ({}, no provider calls)", estimate.protocol.display_name());
+    println!("   Input: ~{} tokens", estimate.input_tokens);
+    if let Some(reason) = &estimate.blocked {
+        println!("⛔ Jarvis would block this request before any agent runs: {}", reason);
+        println!("💰 Cost: $0.0000");
+        return Ok(());
+    }
+
+    println!(
+        "\n{:<20} {:<30} {:>6} {:>13} {:>13} {:>21}",
+        "Stage", "Model", "Calls", "In tokens", "Out tokens", "Cost (USD)"
+    );
+    for stage in &estimate.stages {
+        println!(
+            "{:<20} {:<30} {:>6} {:>13} {:>13} {:>21}",
+            stage.stage,
+            format!("{}/{}", stage.provider, stage.model),
+            format!("{}-{}", stage.calls.low, stage.calls.high),
+            format!("{}-{}", stage.input_tokens.low, stage.input_tokens.high),
+            format!("{}-{}", stage.output_tokens.low, stage.output_tokens.high),
+            format!("${:.4}-${:.4}", stage.cost.low, stage.cost.high)
+        );
+    }
+    println!("\n📊 Tokens: {} - {}", estimate.total_tokens.low, estimate.total_tokens.high);
+    println!("💰 Cost: ${:.4} - ${:.4}", estimate.total_cost.low, estimate.total_cost.high);
+    println!(
+        "⏱️  Latency: {:.1}s - {:.1}s",
+        estimate.total_latency_ms.low as f64 / 1000.0,
+        estimate.total_latency_ms.high as f64 / 1000.0
+    );
+    Ok(())
+}
+
+fn session_cli(store: SessionStore, action: SessionAction) -> anyhow::Result<()> {
+    match action {
+        SessionAction::List => {
+            let sessions = store.list()?;
+            if sessions.is_empty() {
+                println!("No sessions recorded yet");
+            }
+            for session in sessions {
+                println!(
+                    "{}  {} turns  {}  last active {}",
+                    session.session_id,
+                    session.turn_count,
+                    session.current_protocol.display_name(),
+                    session.last_active_at.format("%Y-%m-%d %H:%M:%S")
+                );
+            }
+        }
+        SessionAction::Export { id, output, redact } => {
+            let output = output.unwrap_or_else(|| PathBuf::from(format!("{}.acsa-session.zip", id)));
+            let manifest = store.export(&id, &output, redact)?;
+            println!(
+                "📦 Session {} exported to {:?} ({} messages, {} executions, {} RAG chunks{})",
+                manifest.session_id,
+                output,
+                manifest.messages,
+                manifest.executions,
+                manifest.rag_chunks,
+                if manifest.redacted { ", redacted" } else { "" }
+            );
+        }
+        SessionAction::Import { archive, force } => {
+            let manifest = store.import(&archive, force)?;
+            println!(
+                "📥 Session {} imported (exported {} by v{})",
+                manifest.session_id,
+                manifest.exported_at.format("%Y-%m-%d %H:%M:%S"),
+                manifest.acsa_version
+            );
+        }
+    }
+    Ok(())
+}
+
+async fn rag_cli(store: PathBuf, cache_dir: PathBuf, use_mock: bool, action: RagAction) -> anyhow::Result<()> {
+    match action {
+        RagAction::Ingest { dir, watch, chunking } => {
+            if let Some(parent) = store.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+                std::fs::create_dir_all(parent)?;
+            }
+            let engine = Arc::new(RagEngine::new(RagConfig {
+                embedding_model: if use_mock { EmbeddingModel::Mock } else { RagConfig::default().embedding_model },
+                chunking_strategy: chunking,
+                embedding_cache_dir: Some(cache_dir),
+                vector_store: VectorStoreConfig::Sqlite { path: store },
+                ..Default::default()
+            }));
+
+            if !watch {
+                print_ingest_report(&engine.ingest_dir(&dir, &IngestConfig::default()).await?);
+                return Ok(());
+            }
+
+            let (report, mut watcher) = engine.watch_dir(&dir, IngestConfig::default()).await?;
+            print_ingest_report(&report);
+            println!("👀 Watching {} for changes (Ctrl+C to stop)", dir.display());
+            loop {
+                tokio::select! {
+                    report = watcher.next_report() => match report {
+                        Some(report) => print_ingest_report(&report),
+                        None => break,
+                    },
+                    _ = tokio::signal::ctrl_c() => break,
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn parse_chunking(value: &str) -> Result<ChunkingStrategy, String> {
+    match value.to_lowercase().as_str() {
+        "fixed" | "fixed_size" => Ok(ChunkingStrategy::FixedSize),
+        "semantic" => Ok(ChunkingStrategy::Semantic),
+        "sliding" | "sliding_window" => Ok(ChunkingStrategy::SlidingWindow),
+        "recursive" => Ok(ChunkingStrategy::Recursive),
+        other => Err(format!("unknown chunking strategy '{}' (fixed, semantic, sliding, recursive)", other)),
+    }
+}
+
+fn print_ingest_report(report: &IngestReport) {
+    for id in &report.indexed {
+        println!("  + {}", id);
+    }
+    for (id, original) in &report.duplicates {
+        println!("  = {} (same content as {})", id, original);
+    }
+    for id in &report.removed {
+        println!("  - {}", id);
+    }
+    for (path, error) in &report.failed {
+        println!("  ! {}: {}", path.display(), error);
+    }
+    println!(
+        "📥 {} indexed ({} chunks), {} unchanged, {} duplicate, {} removed, {} failed",
+        report.indexed.len(),
+        report.chunks,
+        report.unchanged,
+        report.duplicates.len(),
+        report.removed.len(),
+        report.failed.len()
+    );
+}
+
+async fn workspace_cli(root: PathBuf, action: WorkspaceAction) -> anyhow::Result<()> {
+    let audit = Arc::new(AuditLogger::new(AuditLogConfig::default(), None));
+    let manager = WorkspaceManager::open(root, audit).await?;
+
+    match action {
+        WorkspaceAction::List => {
+            for config in manager.list().await {
+                println!(
+                    "{:<20} {:<24} members: {:<4} budget: {}",
+                    config.id,
+                    config.name,
+                    if config.members.is_empty() { "all".to_string() } else { config.members.len().to_string() },
+                    config.budget_usd.map(|b| format!("${:.2}", b)).unwrap_or_else(|| "unlimited".to_string())
+                );
+            }
+        }
+        WorkspaceAction::Create { id, name, members, budget } => {
+            let mut config = WorkspaceConfig::new(id.clone(), name.unwrap_or(id)).with_members(members);
+            config.budget_usd = budget;
+            let workspace = manager.create(config).await?;
+            println!("✅ Workspace {} created ({:?})", workspace.id(), workspace.data_dir());
+        }
+        WorkspaceAction::Show { id } => {
+            let workspace = manager.get(&id).await.ok_or_else(|| anyhow::anyhow!("Workspace not found: {}", id))?;
+            println!("{}", serde_json::to_string_pretty(&workspace.summary().await)?);
+        }
+    }
+    Ok(())
+}
+
+fn schedule_cli(file: PathBuf, action: ScheduleAction) -> anyhow::Result<()> {
+    let scheduler = Scheduler::open(file)?;
+
+    match action {
+        ScheduleAction::List => {
+            let jobs = scheduler.list()?;
+            if jobs.is_empty() {
+                println!("No schedules");
+            }
+            let time = |t: Option<chrono::DateTime<chrono::Utc>>| {
+                t.map(|t| t.format("%Y-%m-%d %H:%M").to_string()).unwrap_or_else(|| "-".to_string())
+            };
+            for job in jobs {
+                println!(
+                    "{:<22} {:<20} {:<16} next: {}  last: {}{}",
+                    job.id,
+                    job.name,
+                    job.cron,
+                    time(job.next_run_at),
+                    time(job.last_run_at),
+                    job.last_error.map(|e| format!(" ❌ {}", e)).unwrap_or_default()
+                );
+                println!("    {} (missed runs: {})", job.target.summary(), job.missed_runs.label());
+            }
+        }
+        ScheduleAction::Add { name, cron, input, workflow, missed } => {
+            let target = match (input, workflow) {
+                (_, Some(path)) => {
+                    let content = std::fs::read_to_string(&path)
+                        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+                    let workflow: Workflow = serde_json::from_str(&content)
+                        .map_err(|e| usage_error(anyhow::anyhow!("Invalid workflow {}: {}", path.display(), e)))?;
+                    ScheduleTarget::Workflow { workflow }
+                }
+                (Some(input), None) => ScheduleTarget::Execution { input },
+                (None, None) => return Err(usage_error(anyhow::anyhow!("Pass --input or --workflow"))),
+            };
+            let job = scheduler.add(&name, &cron, target, missed).map_err(usage_error)?;
+            println!(
+                "✅ Schedule {} added; next run {}",
+                job.id,
+                job.next_run_at.map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string()).unwrap_or_default()
+            );
+        }
+        ScheduleAction::Remove { id } => {
+            if !scheduler.remove(&id)? {
+                return Err(usage_error(anyhow::anyhow!("Schedule not found: {}", id)));
+            }
+            println!("🗑️  Schedule {} removed", id);
+        }
+    }
+    Ok(())
+}
+
+fn dead_letters_cli(file: PathBuf, action: DeadLetterAction) -> anyhow::Result<()> {
+    let queue = DeadLetterQueue::open(file)?;
+
+    match action {
+        DeadLetterAction::List => {
+            let letters = queue.list();
+            if letters.is_empty() {
+                println!("No dead letters");
+            }
+            for letter in letters {
+                println!(
+                    "{:<22} {:<20} {:<16} attempts: {:<3} {}{}",
+                    letter.id,
+                    letter.subscriber_id,
+                    letter.failed_at.format("%Y-%m-%d %H:%M"),
+                    letter.attempts,
+                    if letter.panicked { "💥 " } else { "" },
+                    letter.error.chars().take(80).collect::<String>()
+                );
+            }
+            println!("\nRedrive with POST /api/v1/events/dead-letters/<id>/redrive on the running server");
+        }
+        DeadLetterAction::Show { id } => {
+            let letter = queue.get(&id).ok_or_else(|| usage_error(anyhow::anyhow!("Dead letter not found: {}", id)))?;
+            println!("{}", serde_json::to_string_pretty(&letter)?);
+        }
+        DeadLetterAction::Remove { id } => {
+            if queue.take(&id).is_none() {
+                return Err(usage_error(anyhow::anyhow!("Dead letter not found: {}", id)));
+            }
+            println!("🗑️  Dead letter {} removed", id);
+        }
+        DeadLetterAction::Purge => {
+            println!("🗑️  {} dead letter(s) removed", queue.clear());
+        }
+    }
+    Ok(())
+}
+
+async fn sovereignty_cli(config_dir: PathBuf, state: PathBuf, action: SovereigntyAction) -> anyhow::Result<()> {
+    let manager = ConfigManager::new(ConfigManagerConfig {
+        environment: deployment_environment(),
+        config_dir,
+        enable_hot_reload: false,
+        ..Default::default()
+    });
+    manager.load_all().await?;
+    let config: SovereigntyConfig = manager.get_section("sovereignty").await?;
+    SOVEREIGNTY.initialize(config.clone()).await?;
+    SOVEREIGNTY.load(&state).await?;
+
+    match action {
+        SovereigntyAction::Report => {
+            if !config.enabled {
+                println!("ℹ️  Sovereignty mode is off (enable with `o-sovereign sovereignty config --enable`)");
+            }
+            println!("{}", generate_bio_activity_report().await);
+        }
+        SovereigntyAction::Usage => {
+            println!("{}", generate_usage_report(&SOVEREIGNTY.get_usage_tracker()).await);
+        }
+        SovereigntyAction::Chart { hourly } => {
+            let (points, unit) = if hourly {
+                (SOVEREIGNTY.get_hourly_chart().await, "min")
+            } else {
+                (SOVEREIGNTY.get_daily_chart().await, "h")
+            };
+            let max = points.iter().map(|p| p.value).fold(0.0_f32, f32::max);
+            for point in &points {
+                let width = if max > 0.0 { (point.value / max * 40.0).round() as usize } else { 0 };
+                println!("{:>10} │{:<40} {:.1} {}", point.label, "█".repeat(width), point.value, unit);
+            }
+        }
+        SovereigntyAction::Config {
+            enable,
+            disable,
+            enable_limits,
+            disable_limits,
+            daily_limit,
+            session_limit,
+            break_reminder,
+        } => {
+            let mut values = std::collections::HashMap::new();
+            if enable || disable {
+                values.insert("sovereignty.enabled".to_string(), ConfigValue::Boolean(enable));
+            }
+            if enable_limits || disable_limits {
+                values.insert("sovereignty.anti_addiction.enabled".to_string(), ConfigValue::Boolean(enable_limits));
+            }
+            for (key, minutes) in [
+                ("daily_limit_minutes", daily_limit),
+                ("session_limit_minutes", session_limit),
+                ("break_reminder_minutes", break_reminder),
+            ] {
+                if let Some(minutes) = minutes {
+                    values.insert(format!("sovereignty.anti_addiction.{}", key), ConfigValue::Integer(minutes as i64));
+                }
+            }
+
+            let config = if values.is_empty() {
+                config
+            } else {
+                let count = values.len();
+                let path = manager.persist(values, "cli".to_string()).await?;
+                println!("✅ Saved {} setting(s) to {}", count, path.display());
+                manager.get_section("sovereignty").await?
+            };
+            println!("{}", serde_json::to_string_pretty(&config)?);
+        }
+    }
+    Ok(())
+}
+
+async fn maintenance_cli(sentinel: PathBuf, action: MaintenanceAction) -> anyhow::Result<()> {
+    match action {
+        MaintenanceAction::On { reason } => {
+            let actor = std::env::var("USER").unwrap_or_else(|_| "unknown".to_string());
+            kill_switch::write_sentinel(&sentinel, &actor, reason)?;
+            println!("🛑 Maintenance mode engaged by {} ({:?})", actor, sentinel);
+        }
+        MaintenanceAction::Off => {
+            if kill_switch::remove_sentinel(&sentinel)? {
+                println!("✅ Maintenance mode released ({:?})", sentinel);
+            } else {
+                println!("Maintenance mode was not engaged");
+            }
+        }
+        MaintenanceAction::Status => {
+            let switch = KillSwitch::new(KillSwitchConfig {
+                sentinel_path: sentinel,
+                ..Default::default()
+            });
+            switch.refresh().await?;
+            match switch.status() {
+                Some(state) => println!(
+                    "🛑 Maintenance mode engaged by {} via {} at {}{}",
+                    state.actor,
+                    state.source.label(),
+                    state.changed_at.format("%Y-%m-%d %H:%M:%S"),
+                    state.reason.map(|r| format!(": {}", r)).unwrap_or_default()
+                ),
+                None => println!("✅ Maintenance mode off"),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 上次安装的新版本未确认启动成功时回滚，并提示重新运行
+fn startup_update_check() -> anyhow::Result<SelfUpdater> {
+    let mut updater = SelfUpdater::new(UpdateConfig::from_env())?;
+    if let StartupAction::RolledBack(version) = updater.startup_check()? {
+        eprintln!("⏪ The updated binary failed to start; rolled back to v{}. Please re-run the command.", version);
+        std::process::exit(1);
+    }
+    Ok(updater)
+}
+
+async fn self_update_cli(mut updater: SelfUpdater, channel: Option<String>, check: bool, rollback: bool) -> anyhow::Result<()> {
+    if rollback {
+        let version = updater.rollback()?;
+        println!("⏪ Rolled back to v{}", version);
+        return Ok(());
+    }
+    if let Some(name) = channel {
+        let channel = ReleaseChannel::from_name(&name)
+            .ok_or_else(|| anyhow::anyhow!("Unknown channel: {} (expected stable/beta)", name))?;
+        updater.set_channel(channel)?;
+    }
+
+    println!("Channel: {}", updater.channel().label());
+    if check {
+        match updater.check().await? {
+            Some(manifest) => {
+                println!("⬆️  v{} is available (published {})", manifest.version, manifest.published_at.format("%Y-%m-%d"));
+                if let Some(notes) = manifest.notes {
+                    println!("\n{}", notes);
+                }
+            }
+            None => println!("✅ Up to date (v{})", env!("CARGO_PKG_VERSION")),
+        }
+        return Ok(());
+    }
+
+    match updater.update().await? {
+        UpdateOutcome::UpToDate { current } => println!("✅ Up to date (v{})", current),
+        UpdateOutcome::Updated { from, to } => println!("✅ Updated v{} → v{} (roll back with `self-update --rollback`)", from, to),
+    }
+    Ok(())
+}
+
+async fn notify_cli(kind: String, title: String, body: String) -> anyhow::Result<()> {
+    let kind: NotificationKind = serde_json::from_value(serde_json::Value::String(kind.clone()))
+        .map_err(|_| anyhow::anyhow!("Unknown notification kind: {}", kind))?;
+    let notifier = Notifier::from_env(NotifierConfig::default())?;
+    println!("Channels for {}: {}", kind.label(), notifier.channels_for(kind).join(", "));
+
+    // 测试通知使用 Critical，绕过免打扰
+    let notification = Notification::new(kind, title, body).with_priority(NotificationPriority::Critical);
+    match notifier.notify(notification).await {
+        NotifyOutcome::Sent(report) => {
+            for channel in &report.delivered {
+                println!("✅ {}", channel);
+            }
+            for (channel, error) in &report.failed {
+                println!("❌ {}: {}", channel, error);
+            }
+            if !report.failed.is_empty() {
+                anyhow::bail!("{} channel(s) failed", report.failed.len());
+            }
+        }
+        outcome => println!("Notification not sent: {:?}", outcome),
+    }
+    Ok(())
+}
+
+fn emergency_cli(log_dir: PathBuf, action: EmergencyAction) -> anyhow::Result<()> {
+    let config = EmergencyLogConfig {
+        log_dir,
+        ..Default::default()
+    };
+    let logger = EmergencyLogger::new(config.clone())?;
+
+    match action {
+        EmergencyAction::List => {
+            let sessions = logger.list_sessions()?;
+            if sessions.is_empty() {
+                println!("No emergency log sessions found in {:?}", config.log_dir);
+            }
+            for summary in sessions {
+                let marker = if summary.abnormal_exit { "⚠️ " } else { "✅" };
+                println!(
+                    "{} {}  entries={}  last={}",
+                    marker,
+                    summary.session_id,
+                    summary.entry_count,
+                    summary
+                        .ended_at
+                        .map(|t| t.to_rfc3339())
+                        .unwrap_or_else(|| "N/A".to_string()),
+                );
+            }
+        }
+        EmergencyAction::Show { id, tail } => {
+            let entries = logger.recover_session(&id)?;
+            let summary = logger.summarize_session(&id)?;
+
+            println!("\n📋 Session {} ({} entries)", summary.session_id, summary.entry_count);
+            for entry_type in [
+                LogEntryType::UserInput,
+                LogEntryType::AssistantResponse,
+                LogEntryType::ToolCall,
+                LogEntryType::ToolResult,
+                LogEntryType::SystemEvent,
+                LogEntryType::Error,
+                LogEntryType::Panic,
+                LogEntryType::Fatal,
+            ] {
+                let count = summary.counts_by_type.get(&entry_type).copied().unwrap_or(0);
+                if count > 0 {
+                    println!("  {:?}: {}", entry_type, count);
+                }
+            }
+
+            if summary.abnormal_exit {
+                println!("\n⚠️  Abnormal exit — last {} entries before exit:", tail.min(entries.len()));
+            } else {
+                println!("\n✅ Clean shutdown — last {} entries:", tail.min(entries.len()));
+            }
+            for entry in entries.iter().skip(entries.len().saturating_sub(tail)) {
+                let highlight = if entry.entry_type.is_critical() { "❗" } else { "  " };
+                println!(
+                    "{} [{}] {:?}: {}",
+                    highlight,
+                    entry.timestamp.format("%H:%M:%S%.3f"),
+                    entry.entry_type,
+                    entry.content
+                );
+            }
+        }
+        EmergencyAction::Export { output, session } => {
+            let session_ids = match session {
+                Some(id) => vec![id],
+                None => logger
+                    .list_sessions()?
+                    .into_iter()
+                    .map(|s| s.session_id)
+                    .collect(),
+            };
+
+            let configured_keys: Vec<String> = std::env::vars()
+                .map(|(key, _)| key)
+                .filter(|key| key.ends_with("_API_KEY"))
+                .collect();
+            let snapshot = serde_json::json!({
+                "emergency_log": config,
+                "configured_api_keys": configured_keys,
+            });
+
+            let bundle = logger.export_support_bundle(&session_ids, snapshot)?;
+            std::fs::write(&output, serde_json::to_string_pretty(&bundle)?)?;
+            println!("📦 Support bundle written to {:?} ({} sessions)", output, bundle.sessions.len());
+        }
+    }
+
+    Ok(())
+}
+
+/// HTTP（及 gRPC）服务：SIGTERM / Ctrl-C 后排空进行中的执行再退出
+///
+/// 设置 ACSA_ADMIN_PASSWORD 时创建 admin 账号（用户名取 ACSA_ADMIN_USER，默认 admin），用于登录后签发 API 密钥
+async fn serve_cli(host: String, port: u16, data_dir: PathBuf, use_mock: bool, threshold: u8) -> anyhow::Result<()> {
+    SOVEREIGNTY.load(std::path::Path::new(DEFAULT_SOVEREIGNTY_STATE_PATH)).await?;
+    let coordinator = ShutdownCoordinator::new(ShutdownConfig::default());
+    let router = build_router(use_mock, threshold, false, false, None).await?.with_shutdown(coordinator.clone());
+
+    let auth = Arc::new(AuthManager::new(AuthConfig::load(deployment_environment()).await?));
+    if let Ok(password) = std::env::var("ACSA_ADMIN_PASSWORD") {
+        let username = std::env::var("ACSA_ADMIN_USER").unwrap_or_else(|_| "admin".to_string());
+        auth.create_user(&username, &password, vec![Role::Admin], None).await?;
+    }
+
+    let state = ServerState::open(data_dir, auth, Arc::new(deployment_config().await?), load_protocols()?, router).await?;
+    let config = HttpServerConfig { host, port, ..Default::default() };
+    let server = HttpServer::new(config, Arc::new(state)).with_shutdown(coordinator);
+    Arc::new(server).start().await
+}
+
+async fn mcp_cli(action: McpAction, scripted: bool) -> anyhow::Result<()> {
+    match action {
+        McpAction::Serve { mock, threshold } => {
+            // 协议切换工具作用于内置协议 + 自定义协议；Router 的审计事件（清洗报告、审批）可从 acsa://audit/recent 读取
+            let protocols = Arc::new(std::sync::RwLock::new(load_protocols()?));
+            let audit = Arc::new(AuditLogger::new(AuditLogConfig::default(), None));
+            SOVEREIGNTY.load(std::path::Path::new(DEFAULT_SOVEREIGNTY_STATE_PATH)).await?;
+            let state = AcsaMcpState::default()
+                .with_protocols(protocols)
+                .with_audit_logger(audit.clone())
+                .with_config_manager(Arc::new(deployment_config().await?));
+            let server = create_acsa_mcp_server_with_state(state).await;
+            let router = build_router(mock || scripted, threshold, false, false, None).await?.with_audit(audit);
+            register_acsa_execute_tool(&server, router).await;
+            server.serve_stdio().await?;
+        }
+        McpAction::Tools => {
+            let servers = McpServersFile::load(DEFAULT_MCP_SERVERS_PATH)?;
+            if servers.servers.is_empty() {
+                println!("No MCP servers configured in {}", DEFAULT_MCP_SERVERS_PATH);
+                return Ok(());
+            }
+            let registry = McpClientRegistry::connect_all(&servers).await;
+            for (name, config) in &servers.servers {
+                let count = registry.tools().iter().filter(|tool| &tool.server == name).count();
+                let status = if config.disabled { "disabled".to_string() } else { format!("{} tools", count) };
+                println!("🔌 {} ({})", name, status);
+            }
+            for tool in registry.tools() {
+                println!("   {:<40} {}", tool.qualified_name(), tool.tool.description);
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn plugin_cli(dir: PathBuf, action: PluginAction) -> anyhow::Result<()> {
+    let installer = PluginInstaller::new(dir);
+    match action {
+        PluginAction::Install { source, sha256, signature, public_key, allow_unsigned, force } => {
+            let options = InstallOptions {
+                sha256,
+                signature,
+                public_key: public_key.or_else(|| std::env::var("ACSA_PLUGIN_PUBLIC_KEY").ok()),
+                allow_unsigned,
+                force,
+            };
+            let plugin = installer.install(&source, &options).await?;
+            let metadata = &plugin.manifest.metadata;
+            println!("📦 Installed {} v{} ({:?})", metadata.plugin_id, metadata.version, metadata.plugin_type);
+            println!("   sha256 {}{}", plugin.sha256, if plugin.signed { "  🔏 signed" } else { "  ⚠️ unsigned" });
+            println!("   entry  {}", installer.entry_path(&plugin).display());
+        }
+        PluginAction::List => {
+            let installed = installer.list()?;
+            if installed.is_empty() {
+                println!("No plugins installed");
+            }
+            for plugin in installed {
+                let metadata = &plugin.manifest.metadata;
+                println!(
+                    "{:<20} {:<10} {:<18} {:<9} {:<8} {}",
+                    metadata.plugin_id,
+                    metadata.version,
+                    format!("{:?}", metadata.plugin_type),
+                    if plugin.enabled { "enabled" } else { "disabled" },
+                    if plugin.signed { "signed" } else { "unsigned" },
+                    metadata.dependencies.join(", ")
+                );
+            }
+        }
+        PluginAction::Enable { id } => {
+            installer.set_enabled(&id, true).map_err(usage_error)?;
+            println!("✅ Enabled {}", id);
+        }
+        PluginAction::Disable { id } => {
+            installer.set_enabled(&id, false).map_err(usage_error)?;
+            println!("⏸️  Disabled {}", id);
+        }
+        PluginAction::Remove { id, force } => {
+            installer.remove(&id, force).map_err(usage_error)?;
+            println!("🗑️  Removed {}", id);
+        }
+    }
+    Ok(())
+}
+
+fn agents_cli(registry: PathBuf, action: AgentAction) -> anyhow::Result<()> {
+    let mut manager = AgentExtensionManager::load_registry(&registry)?;
+
+    match action {
+        AgentAction::List => {
+            let list = manager.list_agents();
+            println!("{:<16} {:<8} {:<8} {:<28} {:<6} ROLE", "NAME", "TYPE", "ENABLED", "PROVIDER", "COST");
+            for agent in list.core_agents.iter().chain(&list.custom_agents) {
+                println!(
+                    "{:<16} {:<8} {:<8} {:<28} {:<6.2} {}",
+                    agent.name,
+                    format!("{:?}", agent.agent_type),
+                    agent.enabled,
+                    agent.provider.as_deref().unwrap_or("-"),
+                    agent.cost_weight,
+                    agent.description.as_deref().unwrap_or("-")
+                );
+            }
+            println!(
+                "\n{} {} ({} agents)",
+                list.diminishing_returns.recommendation.icon(),
+                list.diminishing_returns.recommendation.message(),
+                list.total_count
+            );
+            return Ok(());
+        }
+        AgentAction::Stats { calls, since_hours, json } => {
+            let manager = manager.with_call_log(calls)?;
+            let since = since_hours.map(|hours| chrono::Utc::now() - chrono::Duration::hours(hours));
+            let board = manager.leaderboard(since);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&board)?);
+                return Ok(());
+            }
+            if board.is_empty() {
+                println!("No agent calls recorded");
+                return Ok(());
+            }
+
+            println!(
+                "{:<4} {:<16} {:<7} {:>6} {:>8} {:>8} {:>7} {:>12} {:>7} {:>7} {:>7}",
+                "#", "AGENT", "TYPE", "CALLS", "SUCCESS", "ACCEPTED", "BLOCKED", "$/ACCEPTED", "P50ms", "P90ms", "P99ms"
+            );
+            for (rank, stats) in board.iter().enumerate() {
+                println!(
+                    "{:<4} {:<16} {:<7} {:>6} {:>7.1}% {:>8} {:>6.1}% {:>12} {:>7} {:>7} {:>7}",
+                    rank + 1,
+                    stats.agent_name,
+                    format!("{:?}", stats.agent_type),
+                    stats.calls,
+                    stats.success_rate * 100.0,
+                    stats.accepted,
+                    stats.block_rate * 100.0,
+                    stats
+                        .cost_per_accepted
+                        .map(|c| format!("{:.4}", c))
+                        .unwrap_or_else(|| "-".to_string()),
+                    stats.latency_p50_ms,
+                    stats.latency_p90_ms,
+                    stats.latency_p99_ms
+                );
+            }
+            return Ok(());
+        }
+        AgentAction::Register {
+            name,
+            role,
+            provider,
+            model,
+            system_prompt,
+            cost_weight,
+            endpoint,
+            temperature,
+            max_tokens,
+        } => {
+            let api_config = AgentApiConfig {
+                provider,
+                model_name: model,
+                api_key: None,
+                custom_endpoint: endpoint,
+                temperature,
+                max_tokens,
+            };
+            let agent = CustomAgent::new(name.clone(), role, api_config)
+                .with_system_prompt(system_prompt)
+                .with_cost_weight(cost_weight);
+            manager.add_custom_agent(agent)?;
+            println!("✅ Registered agent {}", name);
+        }
+        AgentAction::Remove { name } => {
+            manager.remove_custom_agent(&name)?;
+            println!("✅ Removed agent {}", name);
+        }
+        AgentAction::Enable { name } => {
+            manager.set_agent_enabled(&name, true)?;
+            println!("✅ Enabled agent {}", name);
+        }
+        AgentAction::Disable { name } => {
+            manager.set_agent_enabled(&name, false)?;
+            println!("✅ Disabled agent {}", name);
+        }
+    }
+
+    manager.save_registry(&registry)?;
+    Ok(())
+}
+
+async fn config_cli(config_dir: PathBuf, action: ConfigAction) -> anyhow::Result<()> {
+    let manager = ConfigManager::new(ConfigManagerConfig {
+        environment: deployment_environment(),
+        config_dir,
+        enable_hot_reload: false,
+        ..Default::default()
+    });
+
+    match action {
+        ConfigAction::Diff { left, right } => {
+            let diffs = manager.diff_profiles(left, right).await?;
+            if diffs.is_empty() {
+                println!("✅ {} and {} configs are identical", left, right);
+                return Ok(());
+            }
+
+            let show = |value: &Option<ConfigValue>| {
+                value
+                    .as_ref()
+                    .map(|v| v.to_json().to_string())
+                    .unwrap_or_else(|| "<unset>".to_string())
+            };
+
+            println!("{:<40} {:<25} {:<25}", "KEY", left.to_string(), right.to_string());
+            for diff in diffs {
+                println!("{:<40} {:<25} {:<25}", diff.key, show(&diff.left), show(&diff.right));
+            }
+        }
+        ConfigAction::Validate => {
+            let issues = manager.validate_files().await?;
+            if issues.is_empty() {
+                println!("✅ Config is valid");
+                return Ok(());
+            }
+
+            for issue in &issues {
+                println!("❌ {}", issue);
+            }
+            anyhow::bail!("{} config issue(s) found", issues.len());
+        }
+    }
 
     Ok(())
 }
@@ -0,0 +1,461 @@
+// O-Sovereign TUI Dashboard (Ratatui)
+// `o-sovereign tui` / `o-sovereign-tui` 共用的实时仪表盘
+//
+// 面板：流水线进度、SOSA 池健康度、成本计量、主权 H(t)、最近 Jarvis 判定
+// 主题色跟随当前协议的 tui_color，Tab / Shift+Tab 切换协议
+
+use crate::core::sosa_api_pool::{ApiCallEvent, ApiEndpoint, ApiProviderType, PoolConfig, SosaApiPool};
+use crate::core::sovereignty::{
+    DecisionEvent, DecisionType, RiskLevel, SovereigntySystem, DEFAULT_SOVEREIGNTY_STATE_PATH,
+};
+use crate::core::{offline, DashboardState, PipelineEvent, StageStatus, ThemeColor};
+use crate::{create_provider, ACSAConfig, ACSAExecutionLog, ACSARouter, AgentRole};
+use chrono::Utc;
+use crossterm::{
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyModifiers},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::{Backend, CrosstermBackend},
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Gauge, List, ListItem, Paragraph, Wrap},
+    Frame, Terminal,
+};
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::task::JoinHandle;
+
+/// 界面刷新间隔
+const TICK: Duration = Duration::from_millis(100);
+
+struct App {
+    state: DashboardState,
+    input: String,
+    output: String,
+    use_mock: bool,
+    progress_tx: UnboundedSender<PipelineEvent>,
+    progress_rx: UnboundedReceiver<PipelineEvent>,
+    pool: Arc<SosaApiPool>,
+    sovereignty: Arc<SovereigntySystem>,
+    /// 主权状态周期落盘任务
+    autosave: JoinHandle<()>,
+    task: Option<JoinHandle<anyhow::Result<ACSAExecutionLog>>>,
+}
+
+/// 主权状态自动落盘间隔
+const SOVEREIGNTY_FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+impl App {
+    async fn new(use_mock: bool) -> Self {
+        let pool = Arc::new(SosaApiPool::new(PoolConfig::default()));
+        for role in crate::core::dashboard::PIPELINE {
+            pool.add_endpoint(endpoint_for(role, use_mock)).await;
+        }
+
+        // 恢复上次的决策历史，H(t) 不会因重启归零
+        let sovereignty = Arc::new(SovereigntySystem::new());
+        let state_path = std::path::PathBuf::from(DEFAULT_SOVEREIGNTY_STATE_PATH);
+        if let Err(e) = sovereignty.load(&state_path).await {
+            tracing::warn!("⚠️  {}", e);
+        }
+        let autosave = sovereignty.spawn_auto_flush(state_path, SOVEREIGNTY_FLUSH_INTERVAL);
+
+        let (progress_tx, progress_rx) = unbounded_channel();
+        Self {
+            state: DashboardState::new(),
+            input: String::new(),
+            output: "Ready. Type a request and press Enter. Tab / Shift+Tab switches protocol.".to_string(),
+            use_mock,
+            progress_tx,
+            progress_rx,
+            pool,
+            sovereignty,
+            autosave,
+            task: None,
+        }
+    }
+
+    fn submit(&mut self) {
+        if self.input.trim().is_empty() || self.task.is_some() {
+            return;
+        }
+
+        let input = std::mem::take(&mut self.input);
+        let sovereignty = self.sovereignty.clone();
+        let progress = self.progress_tx.clone();
+        let use_mock = self.use_mock;
+        self.output = "⏳ Executing ACSA pipeline...".to_string();
+
+        self.task = Some(tokio::spawn(async move {
+            sovereignty
+                .record_decision(DecisionEvent {
+                    timestamp: Utc::now(),
+                    decision_type: DecisionType::FullyDelegated,
+                    prompt_length: input.chars().count(),
+                    thinking_time_secs: 0,
+                    gave_up_on_difficulty: false,
+                })
+                .await;
+
+            let openai_key = if !use_mock && !offline::is_offline() { std::env::var("OPENAI_API_KEY").ok() } else { None };
+            let router = ACSARouter::new(
+                create_provider(AgentRole::MOSS, openai_key, use_mock)?,
+                create_provider(AgentRole::L6, None, use_mock)?,
+                create_provider(AgentRole::Ultron, None, use_mock)?,
+                create_provider(AgentRole::Omega, None, use_mock)?,
+                ACSAConfig::default(),
+            )
+            .with_progress(progress);
+            router.execute(input).await
+        }));
+    }
+
+    /// 消费进度事件、刷新池健康度与 H(t)、回收已完成的执行
+    async fn tick(&mut self) {
+        while let Ok(event) = self.progress_rx.try_recv() {
+            if let PipelineEvent::StageFinished { role, success, latency_ms, .. } = &event {
+                self.pool
+                    .record_call(ApiCallEvent {
+                        endpoint_id: role.as_str().to_string(),
+                        timestamp: Utc::now(),
+                        latency_ms: *latency_ms,
+                        success: *success,
+                        error_type: None,
+                        tokens_used: None,
+                    })
+                    .await;
+            }
+            self.state.apply(event);
+        }
+
+        let mut pools = self.pool.list_endpoints().await;
+        pools.sort_by(|a, b| a.id.cmp(&b.id));
+        self.state.pools = pools;
+        self.state.bio_activity = Some(self.sovereignty.get_bio_activity().await);
+
+        if self.task.as_ref().is_some_and(|task| task.is_finished()) {
+            let task = self.task.take().expect("checked above");
+            self.output = match task.await {
+                Ok(Ok(log)) => format_log(&log),
+                Ok(Err(e)) => format!("❌ Error: {}", e),
+                Err(e) => format!("❌ Task panicked: {}", e),
+            };
+            self.state.running = false;
+        }
+    }
+}
+
+fn endpoint_for(role: AgentRole, use_mock: bool) -> ApiEndpoint {
+    let provider = if use_mock {
+        ApiProviderType::Custom
+    } else if offline::is_offline() {
+        ApiProviderType::LocalModel
+    } else {
+        match role {
+            AgentRole::MOSS => ApiProviderType::OpenAI,
+            AgentRole::L6 => ApiProviderType::Gemini,
+            AgentRole::Ultron => ApiProviderType::Claude,
+            AgentRole::Omega => ApiProviderType::DeepSeek,
+        }
+    };
+
+    ApiEndpoint {
+        id: role.as_str().to_string(),
+        provider,
+        api_key: None,
+        base_url: String::new(),
+        model_name: provider.name().to_string(),
+        priority: 50,
+        enabled: true,
+        local_config: None,
+    }
+}
+
+fn format_log(log: &ACSAExecutionLog) -> String {
+    let mut result = format!(
+        "🎯 Success: {}   ⏱️  {} ms   💰 ${:.4}   🔁 {} iterations\n",
+        log.success, log.total_time_ms, log.total_cost, log.iterations
+    );
+    if let Some(audit) = &log.audit_result {
+        result.push_str(&format!("🛡️  Risk Score: {}/100  Safe: {}\n", audit.risk_score, audit.is_safe));
+    }
+    for diff in &log.plan_diffs {
+        result.push('\n');
+        result.push_str(&diff.render());
+    }
+    result.push_str("\n📝 Final Output:\n");
+    result.push_str(log.final_output.as_deref().unwrap_or("N/A"));
+    result
+}
+
+/// 启动仪表盘（需在多线程 tokio 运行时中调用）
+pub async fn run(use_mock: bool) -> anyhow::Result<()> {
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+
+    let mut app = App::new(use_mock).await;
+    let result = run_app(&mut terminal, &mut app).await;
+
+    // 无论成功与否都恢复终端
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen, DisableMouseCapture)?;
+    terminal.show_cursor()?;
+
+    if let Some(task) = app.task.take() {
+        task.abort();
+    }
+    app.autosave.abort();
+    app.sovereignty.save(std::path::Path::new(DEFAULT_SOVEREIGNTY_STATE_PATH)).await?;
+    result
+}
+
+async fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> anyhow::Result<()> {
+    loop {
+        app.tick().await;
+        terminal.draw(|f| ui(f, app))?;
+
+        // crossterm 的 poll 是阻塞调用，交给 block_in_place 避免占住调度线程
+        let event = tokio::task::block_in_place(|| -> io::Result<Option<Event>> {
+            if event::poll(TICK)? {
+                event::read().map(Some)
+            } else {
+                Ok(None)
+            }
+        })?;
+
+        let Some(Event::Key(key)) = event else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+            KeyCode::Esc => return Ok(()),
+            KeyCode::Tab => {
+                app.state.cycle_protocol(true);
+            }
+            KeyCode::BackTab => {
+                app.state.cycle_protocol(false);
+            }
+            KeyCode::Enter => app.submit(),
+            KeyCode::Backspace => {
+                app.input.pop();
+            }
+            KeyCode::Char(c) => app.input.push(c),
+            _ => {}
+        }
+    }
+}
+
+fn accent(theme: ThemeColor) -> Color {
+    Color::Rgb(theme.r, theme.g, theme.b)
+}
+
+fn panel(title: &str, color: Color) -> Block<'_> {
+    Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .border_style(Style::default().fg(color))
+}
+
+fn ui(f: &mut Frame, app: &App) {
+    let color = accent(app.state.theme());
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3), // Header
+            Constraint::Length(6), // Pipeline
+            Constraint::Min(8),    // Pools / meters / verdicts
+            Constraint::Length(3), // Input
+            Constraint::Length(8), // Output
+        ])
+        .split(f.area());
+
+    render_header(f, app, rows[0], color);
+    render_pipeline(f, app, rows[1], color);
+
+    let middle = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(34), Constraint::Percentage(33), Constraint::Percentage(33)])
+        .split(rows[2]);
+    render_pools(f, app, middle[0], color);
+    render_meters(f, app, middle[1], color);
+    render_verdicts(f, app, middle[2], color);
+
+    let input = Paragraph::new(app.input.as_str()).block(panel("Input (Enter to execute)", color));
+    f.render_widget(input, rows[3]);
+    f.set_cursor_position((rows[3].x + app.input.chars().count() as u16 + 1, rows[3].y + 1));
+
+    let output = Paragraph::new(app.output.as_str())
+        .block(panel("ACSA Output", color))
+        .wrap(Wrap { trim: false });
+    f.render_widget(output, rows[4]);
+}
+
+fn render_header(f: &mut Frame, app: &App, area: Rect, color: Color) {
+    let protocol = app.state.protocol();
+    let status = match (app.state.running, app.state.last_success) {
+        (true, _) => Span::styled("RUNNING", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+        (false, Some(true)) => Span::styled("COMPLETED", Style::default().fg(Color::Green)),
+        (false, Some(false)) => Span::styled("FAILED", Style::default().fg(Color::Red)),
+        (false, None) => Span::styled("IDLE", Style::default().fg(Color::Gray)),
+    };
+    let header = Line::from(vec![
+        Span::styled("🤖 O-Sovereign ", Style::default().add_modifier(Modifier::BOLD)),
+        Span::styled(
+            format!("{} · {}", protocol.display_name(), protocol.tagline()),
+            Style::default().fg(color).add_modifier(Modifier::BOLD),
+        ),
+        Span::raw("   "),
+        status,
+        Span::styled("   Tab/Shift+Tab: protocol  Esc: quit", Style::default().fg(Color::DarkGray)),
+    ]);
+    f.render_widget(Paragraph::new(header).block(panel("ACSA Dashboard", color)), area);
+}
+
+fn render_pipeline(f: &mut Frame, app: &App, area: Rect, color: Color) {
+    let block = panel("Pipeline", color);
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(2), Constraint::Length(2)])
+        .split(inner);
+
+    let mut stages: Vec<Span> = app
+        .state
+        .stages
+        .iter()
+        .flat_map(|stage| {
+            let style = match stage.status {
+                StageStatus::Pending => Style::default().fg(Color::DarkGray),
+                StageStatus::Running => Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+                StageStatus::Done => Style::default().fg(color),
+                StageStatus::Failed => Style::default().fg(Color::Red),
+            };
+            [
+                Span::styled(
+                    format!("{} {} {} ×{} {}ms", stage.status.icon(), stage.role.emoji(), stage.role.as_str(), stage.runs, stage.latency_ms),
+                    style,
+                ),
+                Span::raw("  →  "),
+            ]
+        })
+        .collect();
+    // 去掉末尾多余的箭头
+    stages.pop();
+    f.render_widget(Paragraph::new(Line::from(stages)), rows[0]);
+
+    let gauge = Gauge::default()
+        .gauge_style(Style::default().fg(color))
+        .ratio(app.state.pipeline_ratio())
+        .label(format!("iteration {}", app.state.iterations));
+    f.render_widget(gauge, rows[1]);
+}
+
+fn health_color(score: f64) -> Color {
+    if score >= 0.7 {
+        Color::Green
+    } else if score >= 0.4 {
+        Color::Yellow
+    } else {
+        Color::Red
+    }
+}
+
+fn render_pools(f: &mut Frame, app: &App, area: Rect, color: Color) {
+    let block = panel("SOSA Pool Health", color);
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let count = app.state.pools.len().max(1);
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vec![Constraint::Length(1); count])
+        .split(inner);
+
+    for (pool, row) in app.state.pools.iter().zip(rows.iter()) {
+        let score = pool.health_score.clamp(0.0, 1.0);
+        let gauge = Gauge::default()
+            .gauge_style(Style::default().fg(health_color(score)))
+            .ratio(score)
+            .label(format!("{:<7} {} {:.0}%", pool.id, pool.provider.name(), score * 100.0));
+        f.render_widget(gauge, *row);
+    }
+}
+
+fn render_meters(f: &mut Frame, app: &App, area: Rect, color: Color) {
+    let block = panel("Cost & Sovereignty", color);
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Length(1), Constraint::Length(1), Constraint::Min(1)])
+        .split(inner);
+
+    let cost = Gauge::default()
+        .gauge_style(Style::default().fg(color))
+        .ratio(app.state.cost_ratio())
+        .label(format!("💰 ${:.4} / ${:.2}", app.state.session_cost, app.state.cost_budget));
+    f.render_widget(cost, rows[0]);
+
+    let per_agent: Vec<Span> = app
+        .state
+        .stages
+        .iter()
+        .map(|stage| Span::raw(format!("{} ${:.3}  ", stage.role.as_str(), stage.cost)))
+        .collect();
+    f.render_widget(Paragraph::new(Line::from(per_agent)), rows[1]);
+
+    if let (Some(bio), Some(ratio)) = (&app.state.bio_activity, app.state.sovereignty_ratio()) {
+        let bio_color = match bio.risk_level {
+            RiskLevel::Healthy => Color::Green,
+            RiskLevel::Warning => Color::Yellow,
+            RiskLevel::Danger | RiskLevel::Critical | RiskLevel::Mitochondrial => Color::Red,
+        };
+        let gauge = Gauge::default()
+            .gauge_style(Style::default().fg(bio_color))
+            .ratio(ratio)
+            .label(format!("🧬 H(t) {:.1} / {:.1} ({:?})", bio.current, bio.baseline, bio.risk_level));
+        f.render_widget(gauge, rows[2]);
+    }
+
+    if let Some(input) = &app.state.current_input {
+        let last = Paragraph::new(format!("Last request: {}", input))
+            .style(Style::default().fg(Color::Gray))
+            .wrap(Wrap { trim: true });
+        f.render_widget(last, rows[3]);
+    }
+}
+
+fn render_verdicts(f: &mut Frame, app: &App, area: Rect, color: Color) {
+    let items: Vec<ListItem> = app
+        .state
+        .verdicts
+        .iter()
+        .map(|verdict| {
+            let (icon, style) = if verdict.allowed {
+                ("✅", Style::default().fg(Color::Green))
+            } else {
+                ("⛔", Style::default().fg(Color::Red))
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{} {} ", icon, verdict.at.format("%H:%M:%S")), style),
+                Span::raw(format!("{} [{}/10] {}", verdict.context, verdict.risk_level, verdict.summary)),
+            ]))
+        })
+        .collect();
+    f.render_widget(List::new(items).block(panel("Jarvis Verdicts", color)), area);
+}
@@ -0,0 +1,217 @@
+// ACSA Web Dashboard
+// 数据来自 GET /api/v1/dashboard（快照）与 GET /api/v1/executions/:id/events（执行实况 SSE）
+// EventSource 不能携带 Authorization 头，因此 SSE 用 fetch 流读取，断线后带 Last-Event-ID 续传
+
+(function () {
+    'use strict';
+
+    const POLL_MS = 10000;
+    const TOKEN_KEY = 'acsa.dashboard.token';
+    const $ = (id) => document.getElementById(id);
+
+    let token = localStorage.getItem(TOKEN_KEY) || '';
+    let selected = null;
+    let feedAbort = null;
+
+    function headers(extra) {
+        return Object.assign({ Authorization: 'Bearer ' + token }, extra || {});
+    }
+
+    function setStatus(text, kind) {
+        const status = $('status');
+        status.textContent = text;
+        status.className = 'status ' + (kind || '');
+    }
+
+    function cell(text) {
+        const td = document.createElement('td');
+        td.textContent = text;
+        return td;
+    }
+
+    function fillTable(id, rows) {
+        const body = $(id).querySelector('tbody');
+        body.replaceChildren(...rows.map((cells) => {
+            const tr = document.createElement('tr');
+            tr.append(...cells.map((c) => (c instanceof Node ? wrap(c) : cell(c))));
+            return tr;
+        }));
+    }
+
+    function wrap(node) {
+        const td = document.createElement('td');
+        td.append(node);
+        return td;
+    }
+
+    function svg(tag, attrs) {
+        const el = document.createElementNS('http://www.w3.org/2000/svg', tag);
+        Object.entries(attrs).forEach(([k, v]) => el.setAttribute(k, v));
+        return el;
+    }
+
+    function lineChart(id, values) {
+        const chart = $(id);
+        chart.replaceChildren();
+        if (values.length < 2) {
+            return;
+        }
+        const max = Math.max(...values, 1e-9);
+        const min = Math.min(...values, 0);
+        const points = values.map((v, i) => {
+            const x = (i / (values.length - 1)) * 400;
+            const y = 115 - ((v - min) / (max - min || 1)) * 105;
+            return x.toFixed(1) + ',' + y.toFixed(1);
+        });
+        chart.append(svg('polyline', { class: 'line', points: points.join(' ') }));
+    }
+
+    function barChart(id, entries) {
+        const chart = $(id);
+        chart.replaceChildren();
+        const max = Math.max(...entries.map((e) => e.value), 1e-9);
+        const width = 400 / Math.max(entries.length, 1);
+        entries.forEach((e, i) => {
+            const height = (e.value / max) * 95;
+            chart.append(svg('rect', {
+                class: 'bar', x: i * width + 4, y: 105 - height, width: width - 8, height: height,
+            }));
+            const label = svg('text', { x: i * width + 4, y: 118 });
+            label.textContent = e.label;
+            chart.append(label);
+        });
+    }
+
+    function healthBar(score) {
+        const bar = document.createElement('span');
+        bar.className = 'health' + (score > 0.7 ? '' : score > 0.4 ? ' warn' : ' bad');
+        bar.style.width = Math.round(score * 100) + 'px';
+        bar.title = score.toFixed(2);
+        return bar;
+    }
+
+    function render(snapshot) {
+        const active = $('active');
+        active.replaceChildren(...snapshot.active_executions.map((id) => {
+            const li = document.createElement('li');
+            li.textContent = id;
+            li.className = id === selected ? 'selected' : '';
+            li.onclick = () => watch(id);
+            return li;
+        }));
+
+        fillTable('endpoints', snapshot.endpoints.map((e) => [
+            (e.enabled ? '✓ ' : '✗ ') + e.id, e.provider, e.model, healthBar(e.health_score),
+        ]));
+
+        const sovereignty = snapshot.sovereignty;
+        $('ht').textContent = sovereignty.enabled ? sovereignty.current.toFixed(1) : '关闭';
+        $('ht-risk').textContent = sovereignty.enabled
+            ? sovereignty.risk_level + ' · 衰减 ' + sovereignty.decay_rate.toFixed(1) + '%'
+            : '';
+        lineChart('ht-chart', snapshot.ht_trend.map((p) => p.value));
+
+        $('total-cost').textContent = snapshot.total_cost.toFixed(4);
+        barChart('cost-chart', snapshot.providers.map((p) => ({ label: p.provider, value: p.total_cost })));
+
+        const recent = snapshot.recent.slice().reverse();
+        lineChart('exec-chart', recent.map((r) => r.total_cost));
+        fillTable('recent', snapshot.recent.map((r) => [
+            r.id, r.input_preview, '$' + r.total_cost.toFixed(4), r.success ? '✓' : '✗',
+        ]));
+    }
+
+    async function refresh() {
+        if (!token) {
+            return;
+        }
+        try {
+            const response = await fetch('/api/v1/dashboard', { headers: headers() });
+            const body = await response.json();
+            if (!response.ok || !body.success) {
+                throw new Error(body.error || response.statusText);
+            }
+            render(body.data);
+            setStatus('已连接 · ' + new Date().toLocaleTimeString(), 'ok');
+        } catch (e) {
+            setStatus('错误：' + e.message, 'error');
+        }
+    }
+
+    // 读取 SSE：只解析 id / event / data 字段，以空行分帧
+    async function watch(id) {
+        if (feedAbort) {
+            feedAbort.abort();
+        }
+        selected = id;
+        feedAbort = new AbortController();
+        const signal = feedAbort.signal;
+        const feed = $('feed');
+        feed.textContent = '';
+        let lastEventId = null;
+
+        while (!signal.aborted) {
+            const extra = lastEventId ? { 'Last-Event-ID': lastEventId } : {};
+            let retry = 3000;
+            try {
+                const response = await fetch('/api/v1/executions/' + encodeURIComponent(id) + '/events', {
+                    headers: headers(extra),
+                    signal: signal,
+                });
+                if (response.status === 204 || response.status === 404) {
+                    feed.textContent += '— 执行已结束，详情见执行记录 —\n';
+                    return;
+                }
+                const reader = response.body.pipeThrough(new TextDecoderStream()).getReader();
+                let buffer = '';
+                for (;;) {
+                    const { value, done } = await reader.read();
+                    if (done) {
+                        break;
+                    }
+                    buffer += value;
+                    let end;
+                    while ((end = buffer.indexOf('\n\n')) >= 0) {
+                        const frame = buffer.slice(0, end);
+                        buffer = buffer.slice(end + 2);
+                        const fields = {};
+                        frame.split('\n').forEach((line) => {
+                            const colon = line.indexOf(':');
+                            if (colon > 0) {
+                                fields[line.slice(0, colon)] = line.slice(colon + 1).trim();
+                            }
+                        });
+                        if (fields.retry) {
+                            retry = Number(fields.retry);
+                        }
+                        if (!fields.id) {
+                            continue;
+                        }
+                        lastEventId = fields.id;
+                        feed.textContent += '[' + fields.event + '] ' + fields.data + '\n';
+                        feed.scrollTop = feed.scrollHeight;
+                        if (fields.event === 'completed') {
+                            return;
+                        }
+                    }
+                }
+            } catch (e) {
+                if (signal.aborted) {
+                    return;
+                }
+            }
+            await new Promise((resolve) => setTimeout(resolve, retry));
+        }
+    }
+
+    $('token').value = token;
+    $('auth').addEventListener('submit', (event) => {
+        event.preventDefault();
+        token = $('token').value.trim();
+        localStorage.setItem(TOKEN_KEY, token);
+        refresh();
+    });
+
+    refresh();
+    setInterval(refresh, POLL_MS);
+})();